@@ -0,0 +1,14 @@
+-- Zwroty środków za pozycje usunięte z zamówienia (np. produkt uszkodzony przed wysyłką).
+-- Pozycja znika z order_items, ale ślad po niej zostaje tutaj razem z kwotą do zwrotu.
+CREATE TABLE order_refunds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    amount BIGINT NOT NULL CHECK (amount >= 0),
+    reason TEXT NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    refunded_at TIMESTAMPTZ, -- NULL = zwrot jeszcze nie zrealizowany
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_refunds_order_id ON order_refunds (order_id);
@@ -0,0 +1,13 @@
+-- Flagi klientów problematycznych (nieopłacone zamówienia, odmowy odbioru itp.)
+CREATE TYPE customer_flag_type AS ENUM ('email', 'phone', 'address');
+
+CREATE TABLE customer_flags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    flag_type customer_flag_type NOT NULL,
+    -- Wartość znormalizowana (e-mail małymi literami, telefon same cyfry, adres bez interpunkcji)
+    value TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT unique_customer_flag UNIQUE (flag_type, value)
+);
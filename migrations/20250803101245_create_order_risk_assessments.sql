@@ -0,0 +1,11 @@
+-- Ocena ryzyka liczona przy składaniu zamówienia (prefiks telefonu vs kraj,
+-- jednorazowy e-mail, anulowane zamówienia z tego samego IP, flagi klienta).
+CREATE TABLE order_risk_assessments (
+    order_id UUID PRIMARY KEY REFERENCES orders(id) ON DELETE CASCADE,
+    score SMALLINT NOT NULL CHECK (score BETWEEN 0 AND 100),
+    signals TEXT[] NOT NULL DEFAULT '{}',
+    client_ip TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_risk_assessments_client_ip ON order_risk_assessments (client_ip);
@@ -0,0 +1,11 @@
+-- Wyniki audytu jakości zdjęć produktów (raport "do poprawy" w panelu admina).
+-- Tabela jest czyszczona i wypełniana od nowa przy każdym przebiegu audytu.
+CREATE TABLE product_image_issues (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    image_url TEXT, -- NULL dla problemów dotyczących całego produktu (np. brak drugiego zdjęcia)
+    issue TEXT NOT NULL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_image_issues_product_id ON product_image_issues (product_id);
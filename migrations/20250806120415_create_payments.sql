@@ -0,0 +1,20 @@
+-- Płatności online przez Przelewy24.
+-- ALTER TYPE ... ADD VALUE nie może być użyte w tej samej transakcji co nowa wartość,
+-- dlatego tabela płatności nie odwołuje się do enuma.
+ALTER TYPE payment_method_enum ADD VALUE IF NOT EXISTS 'przelewy24';
+
+CREATE TABLE payments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL DEFAULT 'przelewy24',
+    -- sessionId przekazany do bramki (unikalny per próba płatności)
+    session_id TEXT NOT NULL UNIQUE,
+    amount BIGINT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'paid', 'failed')),
+    -- orderId nadany przez Przelewy24 (znany dopiero z powiadomienia)
+    provider_order_id BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_payments_order_id ON payments (order_id);
@@ -0,0 +1,10 @@
+-- Wybrany Paczkomat dla zamówień z dostawą "Paczkomat InPost 24/7"
+ALTER TABLE orders ADD COLUMN inpost_locker_code VARCHAR(20) NULL;
+
+-- Przesyłki utworzone w InPost ShipX (jedna na zamówienie)
+CREATE TABLE inpost_shipments (
+    order_id UUID PRIMARY KEY REFERENCES orders(id) ON DELETE CASCADE,
+    shipment_id BIGINT NOT NULL,
+    tracking_number TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
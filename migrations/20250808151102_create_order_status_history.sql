@@ -0,0 +1,17 @@
+-- Historia zmian statusu zamówień (oś czasu w panelu admina i na koncie klienta)
+CREATE TABLE order_status_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    from_status order_status_enum,
+    to_status order_status_enum NOT NULL,
+    changed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    note TEXT,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_status_history_order_id ON order_status_history (order_id, changed_at);
+
+-- Istniejące zamówienia dostają jeden wpis z aktualnym statusem
+INSERT INTO order_status_history (order_id, from_status, to_status, note, changed_at)
+SELECT id, NULL, status, 'Stan w chwili wprowadzenia historii statusów', updated_at
+FROM orders;
@@ -0,0 +1,7 @@
+-- Sprzedane produkty, które admin zdecydował się pokazać w publicznym archiwum
+CREATE TABLE sold_archive_products (
+    product_id UUID PRIMARY KEY REFERENCES products(id) ON DELETE CASCADE,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sold_archive_products_added_at ON sold_archive_products (added_at DESC);
@@ -0,0 +1,34 @@
+-- Zdarzenia domenowe do raportu lejka konwersji (tabela tylko do dopisywania)
+CREATE TYPE event_type_enum AS ENUM (
+    'product_viewed',
+    'added_to_cart',
+    'removed_from_cart',
+    'checkout_started',
+    'order_placed'
+);
+
+CREATE TABLE events (
+    id BIGSERIAL PRIMARY KEY,
+    event_type event_type_enum NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    guest_session_id UUID,
+    product_id UUID REFERENCES products(id) ON DELETE SET NULL,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    metadata JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_type_created_at ON events (event_type, created_at);
+CREATE INDEX IF NOT EXISTS idx_events_product_id ON events (product_id) WHERE product_id IS NOT NULL;
+
+-- Zdarzenia się nie zmieniają - blokujemy UPDATE, żeby historia lejka była wiarygodna
+CREATE OR REPLACE FUNCTION prevent_events_update()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'Tabela events jest tylko do dopisywania';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER events_no_update
+BEFORE UPDATE ON events
+FOR EACH ROW EXECUTE FUNCTION prevent_events_update();
@@ -0,0 +1,37 @@
+-- Kody rabatowe (procentowe i kwotowe) wraz z historią użyć
+CREATE TYPE coupon_discount_type AS ENUM ('percentage', 'fixed');
+
+CREATE TABLE coupons (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Kod zapisany wielkimi literami, porównywany po normalizacji
+    code TEXT NOT NULL UNIQUE,
+    discount_type coupon_discount_type NOT NULL,
+    -- Procent (1-100) albo kwota w groszach, zależnie od discount_type
+    value BIGINT NOT NULL CHECK (value > 0),
+    -- Minimalna wartość produktów w koszyku (w groszach)
+    min_order_value BIGINT NOT NULL DEFAULT 0 CHECK (min_order_value >= 0),
+    expires_at TIMESTAMPTZ,
+    single_use_per_user BOOLEAN NOT NULL DEFAULT FALSE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    usage_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT percentage_coupon_max_100 CHECK (discount_type <> 'percentage' OR value <= 100)
+);
+
+CREATE TABLE coupon_redemptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    coupon_id UUID NOT NULL REFERENCES coupons(id) ON DELETE CASCADE,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Dla gości jednorazowość sprawdzamy po adresie e-mail (małymi literami)
+    guest_email TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_coupon_redemptions_coupon_user ON coupon_redemptions (coupon_id, user_id);
+CREATE INDEX idx_coupon_redemptions_coupon_email ON coupon_redemptions (coupon_id, guest_email);
+
+ALTER TABLE orders
+    ADD COLUMN coupon_id UUID REFERENCES coupons(id) ON DELETE SET NULL,
+    ADD COLUMN discount_amount BIGINT NOT NULL DEFAULT 0;
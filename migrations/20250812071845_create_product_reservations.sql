@@ -0,0 +1,11 @@
+-- Rezerwacje produktów w koszykach (każdy produkt jest unikatowy, więc trzyma go tylko jeden koszyk)
+CREATE TABLE product_reservations (
+    product_id UUID PRIMARY KEY REFERENCES products(id) ON DELETE CASCADE,
+    -- Bez klucza obcego: rezerwacja usuniętego koszyka po prostu wygaśnie
+    cart_id UUID NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_reservations_cart_id ON product_reservations (cart_id);
+CREATE INDEX idx_product_reservations_expires_at ON product_reservations (expires_at);
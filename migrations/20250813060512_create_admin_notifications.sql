@@ -0,0 +1,13 @@
+-- Centrum powiadomień admina (błędy zadań w tle, kopie zapasowe itp.)
+CREATE TYPE admin_notification_level AS ENUM ('info', 'warning', 'error');
+
+CREATE TABLE admin_notifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    level admin_notification_level NOT NULL,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    read_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_admin_notifications_unread ON admin_notifications (created_at DESC) WHERE read_at IS NULL;
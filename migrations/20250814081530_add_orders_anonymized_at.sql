@@ -0,0 +1,5 @@
+-- Znacznik anonimizacji danych osobowych zamówienia gościa (polityka retencji danych)
+ALTER TABLE orders ADD COLUMN anonymized_at TIMESTAMPTZ NULL;
+
+CREATE INDEX IF NOT EXISTS idx_orders_guest_retention ON orders (order_date)
+    WHERE user_id IS NULL AND anonymized_at IS NULL;
@@ -0,0 +1,33 @@
+-- Wyszukiwanie pełnotekstowe produktów.
+-- Konfiguracja `shop_search` używa polskiego słownika ispell (odmiana: "sukienki" -> "sukienka"),
+-- jeśli pliki polish.dict/polish.affix/polish.stop są w katalogu tsearch_data serwera.
+-- Bez nich działa jak 'simple' - wyszukiwanie nadal działa, ale bez sprowadzania do formy podstawowej.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TEXT SEARCH CONFIGURATION shop_search (COPY = simple);
+
+DO $$
+BEGIN
+    CREATE TEXT SEARCH DICTIONARY polish_ispell (
+        TEMPLATE = ispell,
+        DictFile = polish,
+        AffFile = polish,
+        StopWords = polish
+    );
+    ALTER TEXT SEARCH CONFIGURATION shop_search
+        ALTER MAPPING FOR asciiword, asciihword, hword_asciipart, word, hword, hword_part
+        WITH polish_ispell, simple;
+EXCEPTION WHEN OTHERS THEN
+    RAISE NOTICE 'Brak polskiego słownika ispell - shop_search działa bez odmiany wyrazów (%).', SQLERRM;
+END
+$$;
+
+ALTER TABLE products ADD COLUMN search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('shop_search', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('shop_search', coalesce(description, '')), 'B')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_products_search_vector ON products USING GIN (search_vector);
+-- Dopasowanie nazw z literówkami (word_similarity)
+CREATE INDEX IF NOT EXISTS idx_products_name_trgm ON products USING GIN (name gin_trgm_ops);
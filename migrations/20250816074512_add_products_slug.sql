@@ -0,0 +1,34 @@
+-- Przyjazne adresy produktów: /produkty/{slug} zamiast /produkty/{uuid}.
+-- Slug powstaje z nazwy przy dodaniu produktu i później się nie zmienia (stabilne linki dla SEO).
+ALTER TABLE products ADD COLUMN slug TEXT;
+
+-- Istniejące produkty: nazwa bez polskich znaków, tylko [a-z0-9-]. Powtórzone slugi
+-- dostają końcówkę z początku UUID, tak samo jak nowe produkty w aplikacji.
+WITH base AS (
+    SELECT
+        id,
+        created_at,
+        COALESCE(
+            NULLIF(
+                trim(BOTH '-' FROM regexp_replace(
+                    lower(translate(name, 'ąćęłńóśźżĄĆĘŁŃÓŚŹŻ', 'acelnoszzACELNOSZZ')),
+                    '[^a-z0-9]+', '-', 'g'
+                )),
+                ''
+            ),
+            'produkt'
+        ) AS base_slug
+    FROM products
+),
+numbered AS (
+    SELECT id, base_slug, row_number() OVER (PARTITION BY base_slug ORDER BY created_at, id) AS n
+    FROM base
+)
+UPDATE products p
+SET slug = CASE WHEN numbered.n = 1 THEN numbered.base_slug
+                ELSE numbered.base_slug || '-' || left(p.id::text, 8) END
+FROM numbered
+WHERE p.id = numbered.id;
+
+ALTER TABLE products ALTER COLUMN slug SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_slug ON products (slug);
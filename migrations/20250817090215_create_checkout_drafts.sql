@@ -0,0 +1,24 @@
+-- Szkic zamówienia: dane z kolejnych kroków kasy (dane -> dostawa -> płatność -> podsumowanie)
+-- zapisywane po stronie serwera. Jeden szkic na koszyk; znika razem z koszykiem
+-- albo po złożeniu zamówienia.
+CREATE TABLE checkout_drafts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    cart_id UUID NOT NULL UNIQUE REFERENCES shopping_carts(id) ON DELETE CASCADE,
+    -- Ile pierwszych kroków klient już zatwierdził (0-3); można wracać tylko do nich
+    completed_steps SMALLINT NOT NULL DEFAULT 0,
+    guest_email TEXT,
+    shipping_first_name TEXT,
+    shipping_last_name TEXT,
+    shipping_phone TEXT,
+    shipping_address_line1 TEXT,
+    shipping_address_line2 TEXT,
+    shipping_city TEXT,
+    shipping_postal_code TEXT,
+    shipping_country TEXT,
+    shipping_method_key TEXT,
+    inpost_locker_code TEXT,
+    payment_method TEXT,
+    coupon_code TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
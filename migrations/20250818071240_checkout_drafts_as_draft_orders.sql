@@ -0,0 +1,21 @@
+-- Szkic zamówienia staje się trwałym bytem: po złożeniu zamówienia nie znika, tylko dostaje
+-- powiązanie z zamówieniem. Rezerwacje produktów w kasie wskazują szkic, do którego należą.
+
+ALTER TABLE checkout_drafts DROP CONSTRAINT checkout_drafts_cart_id_key;
+ALTER TABLE checkout_drafts DROP CONSTRAINT checkout_drafts_cart_id_fkey;
+
+-- Koszyk gościa jest usuwany po złożeniu zamówienia - szkic musi go przeżyć
+ALTER TABLE checkout_drafts
+    ALTER COLUMN cart_id DROP NOT NULL,
+    ADD CONSTRAINT checkout_drafts_cart_id_fkey
+        FOREIGN KEY (cart_id) REFERENCES shopping_carts(id) ON DELETE SET NULL,
+    ADD COLUMN order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    ADD COLUMN submitted_at TIMESTAMPTZ;
+
+-- Jeden otwarty szkic na koszyk; złożone szkice zostają w historii
+CREATE UNIQUE INDEX idx_checkout_drafts_open_cart
+    ON checkout_drafts (cart_id) WHERE submitted_at IS NULL;
+CREATE INDEX idx_checkout_drafts_order_id ON checkout_drafts (order_id);
+
+ALTER TABLE product_reservations
+    ADD COLUMN checkout_draft_id UUID REFERENCES checkout_drafts(id) ON DELETE SET NULL;
@@ -0,0 +1,17 @@
+-- Potwierdzanie adresu e-mail klienta. Konto działa od razu po rejestracji,
+-- ale do czasu potwierdzenia kasa i "Moje konto" pokazują przypomnienie.
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+-- Konta administratorów zakładane są ręcznie - traktujemy je jako potwierdzone
+UPDATE users SET email_verified_at = NOW() WHERE role = 'admin';
+
+CREATE TABLE verification_tokens (
+    token UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Adres, który potwierdza link - gdyby e-mail konta się zmienił, stary link przestaje działać
+    email VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_verification_tokens_user_id ON verification_tokens (user_id);
@@ -0,0 +1,3 @@
+-- Adres wybranego Paczkomatu zapisujemy razem z jego kodem, żeby nie odpytywać InPost przy każdym wyświetleniu
+ALTER TABLE orders ADD COLUMN inpost_locker_address TEXT;
+ALTER TABLE checkout_drafts ADD COLUMN inpost_locker_address TEXT;
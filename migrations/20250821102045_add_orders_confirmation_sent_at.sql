@@ -0,0 +1,2 @@
+-- Kiedy ostatnio wysłano klientowi potwierdzenie zamówienia (limit ponownych wysyłek ze strony podziękowania)
+ALTER TABLE orders ADD COLUMN confirmation_sent_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+-- Atrybuty odzieży: rozmiar, marka, kolor i materiał. Wszystkie opcjonalne -
+-- starsze produkty i dodatki (np. biżuteria) mogą ich nie mieć.
+ALTER TABLE products
+    ADD COLUMN size TEXT,
+    ADD COLUMN brand TEXT,
+    ADD COLUMN color TEXT,
+    ADD COLUMN material TEXT;
+
+-- Filtry listingu porównują wartości bez rozróżniania wielkości liter
+CREATE INDEX idx_products_size_lower ON products (lower(size)) WHERE size IS NOT NULL;
+CREATE INDEX idx_products_brand_lower ON products (lower(brand)) WHERE brand IS NOT NULL;
+CREATE INDEX idx_products_color_lower ON products (lower(color)) WHERE color IS NOT NULL;
+CREATE INDEX idx_products_material_lower ON products (lower(material)) WHERE material IS NOT NULL;
@@ -0,0 +1,15 @@
+-- Faktury VAT marża do zamówień. Numeracja ciągła w obrębie roku: FM/2025/0001, FM/2025/0002...
+CREATE TABLE invoice_counters (
+    year INTEGER PRIMARY KEY,
+    last_sequence INTEGER NOT NULL
+);
+
+CREATE TABLE invoices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL UNIQUE REFERENCES orders(id) ON DELETE RESTRICT,
+    year INTEGER NOT NULL,
+    sequence INTEGER NOT NULL,
+    number TEXT NOT NULL UNIQUE,
+    issued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (year, sequence)
+);
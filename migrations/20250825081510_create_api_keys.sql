@@ -0,0 +1,12 @@
+-- Klucze publicznego API katalogu (/api/v1). Przechowujemy tylko skrót SHA-256 klucza;
+-- pełny klucz admin widzi raz, przy utworzeniu.
+CREATE TABLE api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    -- Początek klucza do rozpoznania go na liście, np. "mess_3f9a1c"
+    key_prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,30 @@
+-- Zwroty w ramach odstąpienia od umowy (14 dni od doręczenia). Klient zgłasza zwrot wybranych
+-- pozycji z konta, admin go przyjmuje albo odrzuca. Przyjęcie rejestruje zwrot środków
+-- w order_refunds i przywraca produkty do sprzedaży.
+CREATE TYPE return_status AS ENUM ('requested', 'approved', 'rejected');
+
+CREATE TABLE returns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    status return_status NOT NULL DEFAULT 'requested',
+    reason TEXT NOT NULL,
+    bank_account TEXT NOT NULL,
+    admin_note TEXT,
+    decided_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    decided_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_returns_order_id ON returns (order_id);
+CREATE INDEX idx_returns_status ON returns (status, created_at DESC);
+
+CREATE TABLE return_items (
+    return_id UUID NOT NULL REFERENCES returns(id) ON DELETE CASCADE,
+    order_item_id UUID NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    amount BIGINT NOT NULL CHECK (amount >= 0),
+    PRIMARY KEY (return_id, order_item_id)
+);
+
+CREATE INDEX idx_return_items_order_item_id ON return_items (order_item_id);
@@ -0,0 +1,23 @@
+-- Reklamacje (niezgodność towaru z umową). Klient zgłasza reklamację konkretnej pozycji
+-- doręczonego zamówienia, opcjonalnie ze zdjęciami (Cloudinary, folder complaints/{id}).
+-- Sklep ma 14 dni na odpowiedź - termin liczymy od created_at.
+CREATE TYPE complaint_status AS ENUM ('open', 'in_review', 'accepted', 'rejected');
+
+CREATE TABLE complaints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    order_item_id UUID NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE RESTRICT,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    status complaint_status NOT NULL DEFAULT 'open',
+    description TEXT NOT NULL,
+    photos TEXT[] NOT NULL DEFAULT '{}',
+    resolution TEXT,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_complaints_order_id ON complaints (order_id);
+CREATE INDEX idx_complaints_status ON complaints (status, created_at);
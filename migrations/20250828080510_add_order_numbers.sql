@@ -0,0 +1,32 @@
+-- Czytelne numery zamówień (np. MESS-2025-00123) zamiast prefiksu UUID.
+-- Liczniki są osobne dla każdego roku; year = 0 to wspólny licznik dla formatów bez roku.
+-- Istniejące zamówienia dostają numery w domyślnym formacie, według daty złożenia.
+CREATE TABLE order_number_counters (
+    year INTEGER PRIMARY KEY,
+    last_value INTEGER NOT NULL
+);
+
+ALTER TABLE orders ADD COLUMN order_number TEXT;
+
+WITH numbered AS (
+    SELECT
+        id,
+        EXTRACT(YEAR FROM order_date AT TIME ZONE 'Europe/Warsaw')::INTEGER AS year,
+        ROW_NUMBER() OVER (
+            PARTITION BY EXTRACT(YEAR FROM order_date AT TIME ZONE 'Europe/Warsaw')
+            ORDER BY order_date, id
+        ) AS seq
+    FROM orders
+)
+UPDATE orders o
+SET order_number = 'MESS-' || n.year || '-' || LPAD(n.seq::TEXT, 5, '0')
+FROM numbered n
+WHERE o.id = n.id;
+
+INSERT INTO order_number_counters (year, last_value)
+SELECT EXTRACT(YEAR FROM order_date AT TIME ZONE 'Europe/Warsaw')::INTEGER, COUNT(*)
+FROM orders
+GROUP BY 1;
+
+ALTER TABLE orders ALTER COLUMN order_number SET NOT NULL;
+CREATE UNIQUE INDEX idx_orders_order_number ON orders (order_number);
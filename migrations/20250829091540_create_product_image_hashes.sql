@@ -0,0 +1,10 @@
+-- Hashe percepcyjne (pHash, 64 bity) zdjęć produktów - do wykrywania podwójnie wystawionych rzeczy.
+-- Wiersz dotyczy konkretnego adresu zdjęcia; po usunięciu zdjęcia z produktu wpis jest ignorowany
+-- (zapytania sprawdzają `image_url = ANY(products.images)`).
+CREATE TABLE product_image_hashes (
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    image_url TEXT NOT NULL,
+    phash BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (product_id, image_url)
+);
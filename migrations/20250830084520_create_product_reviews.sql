@@ -0,0 +1,24 @@
+-- Opinie o produktach. Opinię może wystawić tylko klient, którego zamówienie z tym produktem
+-- zostało doręczone (verified_purchase). Na stronie produktu widać dopiero opinie
+-- zatwierdzone przez admina.
+CREATE TYPE review_status AS ENUM ('pending', 'approved', 'rejected');
+
+CREATE TABLE product_reviews (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    order_id UUID REFERENCES orders(id) ON DELETE SET NULL,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    content TEXT NOT NULL,
+    -- Podpis pod opinią, np. "Anna K." - z danych zamówienia w chwili wystawienia
+    author_name TEXT NOT NULL,
+    verified_purchase BOOLEAN NOT NULL DEFAULT FALSE,
+    status review_status NOT NULL DEFAULT 'pending',
+    moderated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    moderated_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (product_id, user_id)
+);
+
+CREATE INDEX idx_product_reviews_product ON product_reviews (product_id, status);
+CREATE INDEX idx_product_reviews_status ON product_reviews (status, created_at);
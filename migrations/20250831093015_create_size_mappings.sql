@@ -0,0 +1,61 @@
+-- Przeliczanie rozmiarów z metek (EU/FR/IT/UK/US, stare rozmiarówki vintage) na współczesne
+-- S/M/L. Filtr rozmiaru w sklepie dopasowuje produkt po rozmiarze z metki albo po rozmiarze
+-- przeliczonym, więc francuska sukienka "38" pojawia się też przy filtrze "M".
+-- Puste category/gender oznacza mapowanie dla wszystkich kategorii/płci; przy kilku pasujących
+-- wygrywa najbardziej szczegółowe.
+CREATE TABLE size_mappings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    category category_type,
+    gender product_gender,
+    -- Rozmiarówka metki, np. EU, FR, IT, UK, US, vintage - tylko do opisu w panelu
+    size_system TEXT NOT NULL,
+    label_size TEXT NOT NULL,
+    modern_size TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- NULLS NOT DISTINCT: dwa mapowania "dla wszystkich kategorii" z tym samym rozmiarem to duplikat
+CREATE UNIQUE INDEX idx_size_mappings_unique
+    ON size_mappings (category, gender, lower(label_size)) NULLS NOT DISTINCT;
+
+-- Damska odzież: rozmiary EU/FR, włoskie (EU + 4), brytyjskie i amerykańskie.
+-- Bez obuwia, bielizny i akcesoriów - tam "38" znaczy co innego.
+INSERT INTO size_mappings (category, gender, size_system, label_size, modern_size)
+SELECT c.category::category_type, 'Damskie'::product_gender, s.size_system, s.label_size, s.modern_size
+FROM UNNEST(ARRAY[
+    'Koszule', 'Spodnie', 'Sukienki', 'Spodnice', 'Swetry', 'Bluzy',
+    'KurtkiPlaszcze', 'MarynarkiZakiety'
+]) AS c(category)
+CROSS JOIN (VALUES
+    ('EU', '32', 'XXS'), ('EU', '34', 'XS'), ('EU', '36', 'S'), ('EU', '38', 'M'),
+    ('EU', '40', 'L'), ('EU', '42', 'XL'), ('EU', '44', 'XXL'),
+    ('EU', 'EU 34', 'XS'), ('EU', 'EU 36', 'S'), ('EU', 'EU 38', 'M'),
+    ('EU', 'EU 40', 'L'), ('EU', 'EU 42', 'XL'), ('EU', 'EU 44', 'XXL'),
+    ('FR', 'FR 34', 'XS'), ('FR', 'FR 36', 'S'), ('FR', 'FR 38', 'M'),
+    ('FR', 'FR 40', 'L'), ('FR', 'FR 42', 'XL'), ('FR', 'FR 44', 'XXL'),
+    ('IT', 'IT 38', 'XS'), ('IT', 'IT 40', 'S'), ('IT', 'IT 42', 'M'),
+    ('IT', 'IT 44', 'L'), ('IT', 'IT 46', 'XL'), ('IT', 'IT 48', 'XXL'),
+    ('UK', 'UK 6', 'XS'), ('UK', 'UK 8', 'S'), ('UK', 'UK 10', 'M'),
+    ('UK', 'UK 12', 'L'), ('UK', 'UK 14', 'XL'), ('UK', 'UK 16', 'XXL'),
+    ('US', 'US 2', 'XS'), ('US', 'US 4', 'S'), ('US', 'US 6', 'M'),
+    ('US', 'US 8', 'L'), ('US', 'US 10', 'XL'), ('US', 'US 12', 'XXL'),
+    -- Brytyjskie metki z lat 60.-80. są o około dwa numery mniejsze niż dzisiejsze
+    ('vintage', 'vintage UK 10', 'XS'), ('vintage', 'vintage UK 12', 'S'),
+    ('vintage', 'vintage UK 14', 'M'), ('vintage', 'vintage UK 16', 'L'),
+    ('vintage', 'vintage UK 18', 'XL')
+) AS s(size_system, label_size, modern_size);
+
+-- Męskie marynarki, kurtki i płaszcze: EU (obwód klatki / 2) i UK/US (cale)
+INSERT INTO size_mappings (category, gender, size_system, label_size, modern_size)
+SELECT c.category::category_type, 'Meskie'::product_gender, s.size_system, s.label_size, s.modern_size
+FROM UNNEST(ARRAY['KurtkiPlaszcze', 'MarynarkiZakiety', 'Swetry']) AS c(category)
+CROSS JOIN (VALUES
+    ('EU', '46', 'S'), ('EU', '48', 'M'), ('EU', '50', 'L'),
+    ('EU', '52', 'XL'), ('EU', '54', 'XXL'),
+    ('UK', '36', 'S'), ('UK', '38', 'M'), ('UK', '40', 'L'), ('UK', '42', 'XL'),
+    ('UK', '44', 'XXL'),
+    ('UK', 'UK 36', 'S'), ('UK', 'UK 38', 'M'), ('UK', 'UK 40', 'L'),
+    ('UK', 'UK 42', 'XL'), ('UK', 'UK 44', 'XXL'),
+    ('US', 'US 36', 'S'), ('US', 'US 38', 'M'), ('US', 'US 40', 'L'),
+    ('US', 'US 42', 'XL'), ('US', 'US 44', 'XXL')
+) AS s(size_system, label_size, modern_size);
@@ -0,0 +1,18 @@
+-- Ręczne rezerwacje produktów dla konkretnego klienta (np. po negocjacjach w wiadomościach
+-- na Instagramie). Produkt ma status 'Reserved', a klient dostaje osobisty link, przez który
+-- może dodać go do koszyka mimo rezerwacji. Po expires_at rezerwacja wygasa, a produkt wraca
+-- do sprzedaży.
+CREATE TABLE product_holds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    product_id UUID NOT NULL UNIQUE REFERENCES products(id) ON DELETE CASCADE,
+    -- Losowy token osobistego linku /rezerwacja/{token}
+    token UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+    -- Dla kogo trzymamy produkt, np. "@anna.vintage" - widoczne w panelu i na stronie linku
+    customer_label TEXT NOT NULL,
+    note TEXT,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_holds_expires_at ON product_holds (expires_at);
@@ -0,0 +1,20 @@
+-- Tagi klientów (VIP, hurt, problematyczny) nadawane ręcznie w panelu oraz kryteria
+-- segmentu na kodach rabatowych - kod z segmentem działa tylko dla pasujących klientów.
+CREATE TYPE customer_tag AS ENUM ('vip', 'wholesale', 'problematic');
+
+CREATE TABLE customer_tags (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    tag customer_tag NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, tag)
+);
+
+CREATE INDEX idx_customer_tags_tag ON customer_tags (tag);
+
+-- Puste kryterium = bez ograniczenia; wszystkie ustawione muszą być spełnione
+ALTER TABLE coupons
+    ADD COLUMN segment_tag customer_tag,
+    -- Minimalna suma opłaconych zamówień klienta (w groszach)
+    ADD COLUMN segment_min_spent BIGINT CHECK (segment_min_spent > 0),
+    ADD COLUMN segment_min_orders INTEGER CHECK (segment_min_orders > 0);
@@ -0,0 +1,21 @@
+-- Kampanie kodów rabatowych: seria unikalnych, jednorazowych kodów generowanych naraz
+-- (np. do wydrukowania na kartkach z podziękowaniem dołączanych do paczek).
+CREATE TABLE coupon_campaigns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    -- Wspólny początek kodów kampanii, np. "DZIEKI" -> "DZIEKI-7KQ4XM"
+    code_prefix TEXT NOT NULL,
+    discount_type coupon_discount_type NOT NULL,
+    value BIGINT NOT NULL CHECK (value > 0),
+    min_order_value BIGINT NOT NULL DEFAULT 0 CHECK (min_order_value >= 0),
+    expires_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE coupons
+    ADD COLUMN campaign_id UUID REFERENCES coupon_campaigns(id) ON DELETE CASCADE,
+    -- Łączny limit użyć kodu (wszyscy klienci razem); NULL = bez limitu
+    ADD COLUMN max_uses INTEGER CHECK (max_uses > 0);
+
+CREATE INDEX idx_coupons_campaign_id ON coupons (campaign_id);
@@ -0,0 +1,13 @@
+-- Kartki z podziękowaniem dołączane do paczek: każda ma osobisty, jednorazowy kod na kolejne
+-- zakupy. Kody pochodzą z kampanii systemowej, więc ich wykorzystanie widać w statystykach
+-- kampanii; rabat kartek zmienia się w tym wierszu kampanii.
+ALTER TABLE coupon_campaigns ADD COLUMN system_key TEXT UNIQUE;
+
+INSERT INTO coupon_campaigns (name, code_prefix, discount_type, value, system_key)
+VALUES ('Kartki z podziękowaniem', 'DZIEKI', 'percentage', 10, 'thank_you_cards');
+
+CREATE TABLE thank_you_cards (
+    order_id UUID PRIMARY KEY REFERENCES orders(id) ON DELETE CASCADE,
+    coupon_id UUID NOT NULL REFERENCES coupons(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
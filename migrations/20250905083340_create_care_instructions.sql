@@ -0,0 +1,34 @@
+-- Wskazówki pielęgnacji według materiału. Materiał produktu to wolny tekst ("100% wełna",
+-- "wełniany"), więc dopasowanie odbywa się w aplikacji po rdzeniu nazwy materiału.
+-- Pokazywane na stronie produktu, w e-mailu o wysyłce i na kartce z podziękowaniem.
+CREATE TABLE care_instructions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    material TEXT NOT NULL,
+    instructions TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_care_instructions_material ON care_instructions (lower(material));
+
+CREATE TRIGGER update_care_instructions_updated_at
+BEFORE UPDATE ON care_instructions
+FOR EACH ROW
+EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO care_instructions (material, instructions) VALUES
+    ('Wełna', 'Wełnę pierz ręcznie w letniej wodzie i susz na płasko - na wieszaku się rozciągnie.'),
+    ('Kaszmir', 'Kaszmir pierz ręcznie w letniej wodzie z odrobiną szamponu, susz na płasko.'),
+    ('Jedwab', 'Jedwab pierz ręcznie w chłodnej wodzie, bez wykręcania; prasuj na lewej stronie.'),
+    ('Satyna', 'Satynę pierz ręcznie w chłodnej wodzie i prasuj na lewej stronie.'),
+    ('Bawełna', 'Bawełnę pierz w 30-40°C, ciemne kolory na lewej stronie - dłużej zachowają kolor.'),
+    ('Len', 'Len pierz w 30-40°C i prasuj jeszcze lekko wilgotny.'),
+    ('Denim', 'Denim pierz rzadko, na lewej stronie w 30°C - kolor i fason zostaną na dłużej.'),
+    ('Skóra', 'Skóry nie pierz - czyść wilgotną ściereczką, impregnuj i trzymaj z dala od kaloryfera.'),
+    ('Zamsz', 'Zamszu nie pierz - czyść szczotką do zamszu i chroń impregnatem przed wilgocią.'),
+    ('Wiskoza', 'Wiskozę pierz ręcznie w chłodnej wodzie - łatwo się kurczy.'),
+    ('Poliester', 'Poliester pierz w 30°C i nie susz w suszarce.'),
+    ('Aksamit', 'Aksamit pierz na lewej stronie i odświeżaj parą zamiast żelazkiem.'),
+    ('Sztruks', 'Sztruks pierz na lewej stronie i prasuj przez ściereczkę, żeby nie zgnieść prążków.'),
+    ('Koronka', 'Koronkę pierz ręcznie albo w woreczku do prania.'),
+    ('Futro', 'Futro oddaj do specjalistycznego czyszczenia, przechowuj w przewiewnym pokrowcu.');
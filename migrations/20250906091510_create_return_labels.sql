@@ -0,0 +1,11 @@
+-- Etykiety zwrotne InPost kupowane przez klienta przy zgłoszeniu zwrotu. Klient nadaje paczkę
+-- w dowolnym Paczkomacie, a cena etykiety jest potrącana z kwoty zwrotu przy jego przyjęciu.
+CREATE TABLE return_labels (
+    return_id UUID PRIMARY KEY REFERENCES returns(id) ON DELETE CASCADE,
+    shipment_id BIGINT NOT NULL,
+    tracking_number TEXT,
+    price BIGINT NOT NULL CHECK (price >= 0),
+    -- NULL = etykieta jeszcze nie wysłana klientowi (InPost generuje ją z opóźnieniem)
+    emailed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
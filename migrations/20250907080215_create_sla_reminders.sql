@@ -0,0 +1,21 @@
+-- Terminy ustawowe w obsłudze klienta: odpowiedź na reklamację i zwrot pieniędzy po odstąpieniu
+-- od umowy (po 14 dni). Przypomnienia eskalują w miarę zbliżania się terminu; każdy etap
+-- trafia do centrum powiadomień admina tylko raz.
+CREATE TABLE sla_reminders (
+    subject_kind TEXT NOT NULL CHECK (subject_kind IN ('complaint', 'return')),
+    subject_id UUID NOT NULL,
+    stage TEXT NOT NULL CHECK (stage IN ('due_soon', 'due_today', 'overdue')),
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (subject_kind, subject_id, stage)
+);
+
+-- Zwroty środków wynikające z przyjętego zwrotu - po nich poznajemy, czy pieniądze już wyszły
+ALTER TABLE order_refunds ADD COLUMN return_id UUID REFERENCES returns(id) ON DELETE SET NULL;
+
+UPDATE order_refunds o
+SET return_id = r.id
+FROM returns r
+WHERE o.order_id = r.order_id
+  AND o.reason = 'Odstąpienie od umowy (ZW-' || upper(left(replace(r.id::text, '-', ''), 8)) || ')';
+
+CREATE INDEX idx_order_refunds_return_id ON order_refunds (return_id) WHERE return_id IS NOT NULL;
@@ -0,0 +1,61 @@
+-- Rozbicie VAT zapisywane w chwili zakupu: stawka, netto i VAT każdej pozycji, dostawa i sumy
+-- zamówienia oraz netto/VAT każdego zwrotu środków. Faktury, zwroty i eksport dla księgowej
+-- czytają te wartości zamiast liczyć je od nowa według bieżących ustawień podatkowych.
+-- Kwoty w groszach; rabat zamówienia jest rozdzielany na pozycje proporcjonalnie do cen.
+CREATE TYPE vat_scheme AS ENUM ('margin', 'standard');
+
+ALTER TABLE orders
+    ADD COLUMN vat_scheme vat_scheme NOT NULL DEFAULT 'margin',
+    ADD COLUMN vat_rate_bp INTEGER NOT NULL DEFAULT 2300,
+    ADD COLUMN shipping_cost BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN net_total BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN vat_total BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE order_items
+    ADD COLUMN vat_rate_bp INTEGER NOT NULL DEFAULT 2300,
+    ADD COLUMN discount_share BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN net_amount BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN vat_amount BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE order_refunds
+    ADD COLUMN net_amount BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN vat_amount BIGINT NOT NULL DEFAULT 0;
+
+-- Dotychczasowe zamówienia: procedura marży, więc VAT nie jest wykazywany (netto = brutto).
+-- Rabat dzielimy proporcjonalnie, a resztę z zaokrągleń dostaje najdroższa pozycja.
+WITH shares AS (
+    SELECT oi.id, oi.order_id, o.discount_amount,
+           COALESCE(o.discount_amount * oi.price_at_purchase
+               / NULLIF(SUM(oi.price_at_purchase) OVER (PARTITION BY oi.order_id), 0), 0) AS share,
+           ROW_NUMBER() OVER (PARTITION BY oi.order_id ORDER BY oi.price_at_purchase DESC, oi.id) AS rn
+    FROM order_items oi
+    JOIN orders o ON o.id = oi.order_id
+),
+allocated AS (
+    SELECT id,
+           share + CASE WHEN rn = 1
+                        THEN discount_amount - SUM(share) OVER (PARTITION BY order_id)
+                        ELSE 0 END AS discount_share
+    FROM shares
+)
+UPDATE order_items oi
+SET discount_share = a.discount_share,
+    net_amount = oi.price_at_purchase - a.discount_share
+FROM allocated a
+WHERE a.id = oi.id;
+
+UPDATE orders o
+SET shipping_cost = GREATEST(o.total_price + o.discount_amount - COALESCE(
+        (SELECT SUM(oi.price_at_purchase) FROM order_items oi WHERE oi.order_id = o.id), 0), 0),
+    net_total = o.total_price;
+
+UPDATE order_refunds SET net_amount = amount;
+
+-- Nowe wiersze muszą podać rozbicie jawnie
+ALTER TABLE orders ALTER COLUMN vat_scheme DROP DEFAULT, ALTER COLUMN vat_rate_bp DROP DEFAULT,
+    ALTER COLUMN shipping_cost DROP DEFAULT, ALTER COLUMN net_total DROP DEFAULT,
+    ALTER COLUMN vat_total DROP DEFAULT;
+ALTER TABLE order_items ALTER COLUMN vat_rate_bp DROP DEFAULT,
+    ALTER COLUMN net_amount DROP DEFAULT, ALTER COLUMN vat_amount DROP DEFAULT;
+ALTER TABLE order_refunds ALTER COLUMN net_amount DROP DEFAULT,
+    ALTER COLUMN vat_amount DROP DEFAULT;
@@ -0,0 +1,13 @@
+-- Ostatni przebieg każdego zadania cyklicznego (zob. src/jobs.rs) - do podglądu w panelu admina.
+-- Jeden wiersz na zadanie, nadpisywany przy każdym uruchomieniu; pełna historia jest w logach.
+CREATE TABLE jobs_log (
+    job_name TEXT PRIMARY KEY,
+    last_started_at TIMESTAMPTZ NOT NULL,
+    last_finished_at TIMESTAMPTZ,
+    last_duration_ms BIGINT,
+    last_outcome TEXT NOT NULL CHECK (last_outcome IN ('running', 'success', 'failure')),
+    last_message TEXT,
+    last_success_at TIMESTAMPTZ,
+    run_count BIGINT NOT NULL DEFAULT 0,
+    failure_count BIGINT NOT NULL DEFAULT 0
+);
@@ -0,0 +1,19 @@
+-- Pomiary Core Web Vitals od prawdziwych użytkowników (beacon z /api/rum, zob. src/rum.rs).
+-- Jeden wiersz na wejście na stronę; trasa jest już zgrupowana (np. /produkty/{produkt}).
+CREATE TABLE rum_samples (
+    id BIGSERIAL PRIMARY KEY,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    route TEXT NOT NULL,
+    device TEXT NOT NULL CHECK (device IN ('mobile', 'desktop')),
+    lcp_ms INTEGER,
+    cls REAL,
+    inp_ms INTEGER,
+    -- Element LCP był zdjęciem: czy miał preload, czy był ładowany leniwie i kiedy się pobrał
+    lcp_is_image BOOLEAN,
+    lcp_preloaded BOOLEAN,
+    lcp_lazy BOOLEAN,
+    lcp_load_ms INTEGER
+);
+
+CREATE INDEX idx_rum_samples_recorded_at ON rum_samples (recorded_at);
+CREATE INDEX idx_rum_samples_route_recorded_at ON rum_samples (route, recorded_at);
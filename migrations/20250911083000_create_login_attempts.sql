@@ -0,0 +1,27 @@
+-- Próby logowania i czasowe blokady kont (zob. src/login_lockout.rs).
+-- Adres e-mail jest znormalizowany (małe litery) - blokujemy po adresie, więc nieistniejące
+-- konta zachowują się tak samo jak istniejące i nie da się w ten sposób sprawdzić, kto ma konto.
+CREATE TABLE login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    email TEXT NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    succeeded BOOLEAN NOT NULL,
+    ip_address TEXT,
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_login_attempts_email_attempted_at ON login_attempts (email, attempted_at DESC);
+
+CREATE TABLE account_lockouts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    email TEXT NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    failed_attempts INTEGER NOT NULL,
+    locked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ NOT NULL,
+    -- Ręczne odblokowanie przez admina
+    unlocked_at TIMESTAMPTZ,
+    unlocked_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_account_lockouts_email_locked_at ON account_lockouts (email, locked_at DESC);
@@ -0,0 +1,18 @@
+-- Weryfikacja dwuetapowa (TOTP) - wymagana dla kont administratorów.
+-- totp_secret jest zapisywany już przy rozpoczęciu konfiguracji, a totp_enabled_at dopiero
+-- po potwierdzeniu pierwszym kodem z aplikacji. totp_last_step blokuje ponowne użycie kodu.
+ALTER TABLE users
+    ADD COLUMN totp_secret TEXT,
+    ADD COLUMN totp_enabled_at TIMESTAMPTZ,
+    ADD COLUMN totp_last_step BIGINT;
+
+-- Jednorazowe kody zapasowe (na wypadek utraty telefonu); przechowujemy tylko skróty SHA-256
+CREATE TABLE totp_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_totp_recovery_codes_user_id ON totp_recovery_codes (user_id);
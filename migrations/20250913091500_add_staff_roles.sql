@@ -0,0 +1,9 @@
+-- Role personelu (zob. src/permissions.rs) i blokowanie kont przez admina (zob. src/user_management.rs).
+-- ALTER TYPE ... ADD VALUE nie może działać w jednej transakcji z użyciem nowych wartości,
+-- dlatego w tej migracji ich nie używamy.
+ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'editor';
+ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'fulfilment';
+
+ALTER TABLE users
+    ADD COLUMN disabled_at TIMESTAMPTZ,
+    ADD COLUMN disabled_by UUID REFERENCES users(id) ON DELETE SET NULL;
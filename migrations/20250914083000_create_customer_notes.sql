@@ -0,0 +1,10 @@
+-- Wewnętrzne notatki personelu o kliencie (zob. src/customer_profiles.rs), widoczne tylko w panelu
+CREATE TABLE customer_notes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_customer_notes_user_id_created_at ON customer_notes (user_id, created_at DESC);
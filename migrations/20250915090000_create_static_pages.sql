@@ -0,0 +1,12 @@
+-- Treści stron informacyjnych edytowane w panelu (zob. src/cms.rs). Brak wiersza albo
+-- nieopublikowany wiersz oznacza wbudowaną treść z src/views/static_pages.rs.
+CREATE TABLE static_pages (
+    slug TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    -- Markdown; wstawki HTML przechodzą bez zmian
+    body TEXT NOT NULL,
+    published BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
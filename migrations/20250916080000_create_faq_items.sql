@@ -0,0 +1,44 @@
+-- Pytania FAQ edytowane w panelu (zob. src/faq.rs). Z tej tabeli powstaje akordeon na /faq
+-- i dane strukturalne FAQPage. `{prog_darmowej_dostawy}` w odpowiedzi zamienia się
+-- przy renderowaniu na aktualny próg darmowej dostawy.
+CREATE TABLE faq_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    question TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    visible BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_faq_items_position ON faq_items (position);
+
+-- Dotychczasowe, wbudowane pytania
+INSERT INTO faq_items (question, answer, position) VALUES
+('Jakie są dostępne metody płatności?',
+ 'W naszym sklepie mess - all that vintage akceptujemy następujące metody płatności: szybkie przelewy online BLIK oraz przelew tradycyjny. Wszystkie transakcje są bezpieczne i szyfrowane.',
+ 1),
+('Jaki jest czas realizacji zamówienia?',
+ 'Standardowo, zamówienia przygotowujemy do wysyłki w ciągu 1-2 dni roboczych od momentu zaksięgowania wpłaty. Czas dostawy przez przewoźnika to zazwyczaj dodatkowe 1-2 dni robocze.',
+ 2),
+('Jakie są koszty i opcje dostawy?',
+ 'Oferujemy dostawę za pośrednictwem Paczkomatów InPost oraz Poczta Polska. Koszt dostawy jest widoczny podczas składania zamówienia i zależy od wybranej opcji. Dla zamówień powyżej {prog_darmowej_dostawy} dostawa jest darmowa!',
+ 3),
+('Czy wysyłacie za granicę?',
+ 'Obecnie realizujemy wysyłki wyłącznie na terenie Polski. Pracujemy nad rozszerzeniem naszej oferty o wysyłki międzynarodowe.',
+ 4),
+('W jakim stanie są oferowane ubrania?',
+ 'W mess - all that vintage specjalizujemy się w odzieży vintage i używanej w doskonałym lub bardzo dobrym stanie. Każdy produkt jest starannie sprawdzany, a jego stan (wraz z ewentualnymi minimalnymi śladami użytkowania, które dodają charakteru) jest dokładnie opisany na karcie produktu. Stawiamy na jakość i unikatowość.',
+ 5),
+('Jak dbać o odzież vintage?',
+ 'Pielęgnacja odzieży vintage zależy od materiału. Zawsze sprawdzaj metki, jeśli są dostępne. Generalnie zalecamy delikatne pranie ręczne lub w niskich temperaturach, a dla szczególnie cennych materiałów (jak jedwab czy wełna) czyszczenie chemiczne. Unikaj suszenia w suszarce bębnowej.',
+ 6),
+('Czy produkty są unikatowe?',
+ 'Tak, większość naszej oferty to pojedyncze, unikatowe egzemplarze. To właśnie czyni zakupy w mess - all that vintage wyjątkowym doświadczeniem - masz szansę zdobyć coś, czego nie będzie miał nikt inny!',
+ 7),
+('Czy mogę zwrócić zakupiony produkt?',
+ 'Oczywiście. Masz 14 dni na zwrot towaru bez podania przyczyny od momentu otrzymania przesyłki. Produkt musi być w stanie nienaruszonym, z oryginalnymi metkami (jeśli były). Szczegóły procedury zwrotu znajdziesz w naszym Regulaminie Sklepu.',
+ 8),
+('Jak złożyć reklamację?',
+ 'Jeśli otrzymany produkt posiada wadę, która nie była opisana, skontaktuj się z nami mailowo, dołączając zdjęcia i opis problemu. Każdą reklamację rozpatrujemy indywidualnie. Więcej informacji znajdziesz w Regulaminie Sklepu.',
+ 9);
@@ -0,0 +1,28 @@
+-- Konfiguracja strony głównej edytowana w panelu (zob. src/homepage.rs): sekcje nad listą
+-- nowości w ustalonej kolejności - baner (hero) i karuzela wyróżnionych produktów.
+CREATE TYPE homepage_section AS ENUM ('hero', 'featured');
+
+CREATE TABLE homepage_sections (
+    section homepage_section PRIMARY KEY,
+    position INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    title TEXT,
+    subtitle TEXT,
+    -- Tylko baner: zdjęcie z Cloudinary i opcjonalny link po kliknięciu
+    image_url TEXT,
+    link_url TEXT,
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE homepage_featured_products (
+    product_id UUID PRIMARY KEY REFERENCES products(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    added_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Dotychczasowy baner; karuzela startuje pusta (bez produktów nie jest pokazywana)
+INSERT INTO homepage_sections (section, position, enabled, title, image_url) VALUES
+('hero', 1, TRUE, NULL,
+ 'https://res.cloudinary.com/dvndapjpc/image/upload/v1754091645/dpf1xb0grl4gtl56gepn.avif'),
+('featured', 2, TRUE, 'Wyróżnione', NULL);
@@ -0,0 +1,6 @@
+-- Własne metadane SEO produktu. Puste pola oznaczają wartości domyślne: nazwę produktu
+-- w tytule, początek opisu w meta description i pierwsze zdjęcie w podglądzie linku.
+ALTER TABLE products
+    ADD COLUMN meta_title TEXT,
+    ADD COLUMN meta_description TEXT,
+    ADD COLUMN og_image TEXT;
@@ -0,0 +1,16 @@
+-- Przekierowania starych adresów (zob. src/redirects.rs): zmieniony slug produktu, usunięte
+-- strony, stare linki z zewnątrz. Sprawdzane dopiero, gdy żądanie kończy się 404.
+-- `source_path` trzymamy bez parametrów i końcowego ukośnika.
+CREATE TABLE redirects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source_path TEXT NOT NULL UNIQUE,
+    target_path TEXT NOT NULL,
+    status_code SMALLINT NOT NULL DEFAULT 301 CHECK (status_code IN (301, 302)),
+    hits BIGINT NOT NULL DEFAULT 0,
+    last_hit_at TIMESTAMPTZ,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (source_path <> target_path)
+);
+
+CREATE INDEX idx_redirects_target_path ON redirects (target_path);
@@ -0,0 +1,15 @@
+-- Licznik wyświetleń produktów (zob. src/product_views.rs): jeden wiersz na odwiedzającego,
+-- produkt i dzień, więc odświeżanie strony nie nabija wyświetleń. Z ostatnich 30 dni liczymy
+-- sortowanie `sort_by=popularity` i sekcję "Najpopularniejsze" na stronie głównej.
+CREATE TABLE product_views (
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    -- "u:{user_id}", "g:{guest_session_id}" albo "ip:{adres}" dla wejść bez sesji
+    visitor TEXT NOT NULL,
+    viewed_on DATE NOT NULL DEFAULT CURRENT_DATE,
+    PRIMARY KEY (product_id, visitor, viewed_on)
+);
+
+CREATE INDEX idx_product_views_viewed_on ON product_views (viewed_on, product_id);
+
+-- Nowej wartości enuma nie można użyć w tej samej transakcji - sekcję dodaje następna migracja
+ALTER TYPE homepage_section ADD VALUE 'popular';
@@ -0,0 +1,4 @@
+-- Sekcja "Najpopularniejsze" na końcu strony głównej (produkty wg wyświetleń z 30 dni)
+INSERT INTO homepage_sections (section, position, enabled, title)
+SELECT 'popular', COALESCE(MAX(position), 0) + 1, TRUE, 'Najpopularniejsze'
+FROM homepage_sections;
@@ -0,0 +1,15 @@
+-- Historia zmian ceny i flagi "okazja" (zob. src/price_history.rs). Zapisywana z panelu
+-- (edycja produktu, akcje masowe); z ostatniej obniżki bierzemy "cena obniżona z X"
+-- na stronie produktu.
+CREATE TABLE product_price_history (
+    id BIGSERIAL PRIMARY KEY,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    old_price BIGINT NOT NULL,
+    new_price BIGINT NOT NULL,
+    old_on_sale BOOLEAN NOT NULL,
+    new_on_sale BOOLEAN NOT NULL,
+    changed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_price_history_product ON product_price_history (product_id, changed_at DESC);
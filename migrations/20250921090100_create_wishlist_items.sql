@@ -0,0 +1,10 @@
+-- Ulubione produkty klientów (zob. src/wishlist.rs). Przy obniżce ceny albo oznaczeniu
+-- produktu jako okazja wysyłamy e-mail wszystkim, którzy mają go w ulubionych.
+CREATE TABLE wishlist_items (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, product_id)
+);
+
+CREATE INDEX idx_wishlist_items_product ON wishlist_items (product_id);
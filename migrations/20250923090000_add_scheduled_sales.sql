@@ -0,0 +1,13 @@
+-- Zaplanowane promocje (zob. src/sales.rs): cena `sale_price` obowiązuje od `sale_starts_at`
+-- do `sale_ends_at` (puste = od razu / do odwołania). Cenę efektywną liczymy przy każdym
+-- zapytaniu, więc promocja startuje i kończy się bez ręcznego przełączania `on_sale`.
+ALTER TABLE products
+    ADD COLUMN sale_price BIGINT,
+    ADD COLUMN sale_starts_at TIMESTAMPTZ,
+    ADD COLUMN sale_ends_at TIMESTAMPTZ,
+    ADD CONSTRAINT products_sale_price_check CHECK (sale_price IS NULL OR (sale_price > 0 AND sale_price < price)),
+    ADD CONSTRAINT products_sale_period_check CHECK (sale_starts_at IS NULL OR sale_ends_at IS NULL OR sale_ends_at > sale_starts_at);
+
+-- Zadanie pilnujące granic promocji szuka startów i końców z ostatnich minut
+CREATE INDEX idx_products_sale_starts_at ON products (sale_starts_at) WHERE sale_price IS NOT NULL;
+CREATE INDEX idx_products_sale_ends_at ON products (sale_ends_at) WHERE sale_price IS NOT NULL;
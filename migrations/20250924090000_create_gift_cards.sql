@@ -0,0 +1,47 @@
+-- Karty podarunkowe: kupowane jak produkt wirtualny, kod trafia e-mailem do obdarowanego.
+-- W kasie kod pokrywa część albo całość zamówienia, niewykorzystane saldo zostaje na karcie.
+CREATE TABLE gift_cards (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Kod zapisany wielkimi literami, porównywany po normalizacji
+    code TEXT NOT NULL UNIQUE,
+    initial_amount BIGINT NOT NULL CHECK (initial_amount > 0),
+    balance BIGINT NOT NULL CHECK (balance >= 0),
+    purchaser_email TEXT NOT NULL,
+    purchaser_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    recipient_email TEXT NOT NULL,
+    recipient_name TEXT,
+    message TEXT,
+    -- Nieopłacona karta nie działa w kasie, a jej kod nie jest nikomu wysyłany
+    paid_at TIMESTAMPTZ,
+    sent_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT gift_card_balance_within_amount CHECK (balance <= initial_amount)
+);
+
+CREATE INDEX idx_gift_cards_created_at ON gift_cards (created_at DESC);
+
+CREATE TABLE gift_card_redemptions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    gift_card_id UUID NOT NULL REFERENCES gift_cards(id) ON DELETE CASCADE,
+    order_id UUID NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    -- Anulowanie zamówienia zwraca kwotę na kartę
+    restored_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_gift_card_redemptions_card ON gift_card_redemptions (gift_card_id);
+CREATE INDEX idx_gift_card_redemptions_order ON gift_card_redemptions (order_id);
+
+-- Część zamówienia opłacona kartą; `total_price` to kwota pozostała do zapłaty
+ALTER TABLE orders ADD COLUMN gift_card_amount BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE checkout_drafts ADD COLUMN gift_card_code TEXT;
+
+-- Płatność Przelewy24 dotyczy zamówienia albo zakupu karty podarunkowej
+ALTER TABLE payments
+    ALTER COLUMN order_id DROP NOT NULL,
+    ADD COLUMN gift_card_id UUID REFERENCES gift_cards(id) ON DELETE CASCADE,
+    ADD CONSTRAINT payments_single_target CHECK ((order_id IS NULL) <> (gift_card_id IS NULL));
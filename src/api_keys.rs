@@ -0,0 +1,114 @@
+// src/api_keys.rs
+
+// Klucze publicznego API katalogu (`/api/v1`). Klient przesyła klucz w nagłówku `X-Api-Key`;
+// w bazie trzymamy tylko skrót SHA-256, więc wyciek bazy nie ujawnia działających kluczy.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::ApiKey;
+use crate::state::AppState;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+const API_KEY_PREFIX: &str = "mess_";
+/// Ile znaków klucza (razem z prefiksem) pokazujemy na liście w panelu
+const DISPLAYED_PREFIX_LEN: usize = 11;
+
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Tworzy nowy klucz. Zwraca zapisany wiersz i pełny klucz - do pokazania adminowi tylko raz.
+pub async fn create_api_key(pool: &PgPool, name: &str) -> Result<(ApiKey, String), AppError> {
+    let key = format!(
+        "{}{}{}",
+        API_KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    );
+    let api_key = sqlx::query_as::<_, ApiKey>(
+        r#"
+        INSERT INTO api_keys (name, key_prefix, key_hash)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(name)
+    .bind(&key[..DISPLAYED_PREFIX_LEN])
+    .bind(hash_api_key(&key))
+    .fetch_one(pool)
+    .await?;
+    Ok((api_key, key))
+}
+
+/// Unieważnia klucz. Zwraca `false`, gdy klucza nie ma albo był już unieważniony.
+pub async fn revoke_api_key(pool: &PgPool, key_id: Uuid) -> Result<bool, AppError> {
+    let result =
+        sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+            .bind(key_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn list_api_keys(pool: &PgPool) -> Result<Vec<ApiKey>, AppError> {
+    Ok(sqlx::query_as::<_, ApiKey>(
+        "SELECT * FROM api_keys ORDER BY revoked_at IS NOT NULL, created_at DESC",
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Klient publicznego API uwierzytelniony ważnym kluczem z nagłówka `X-Api-Key`.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    pub name: String,
+}
+
+impl<S> FromRequestParts<S> for ApiClient
+where
+    Arc<AppState>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let app_state = Arc::<AppState>::from_ref(state);
+        let key = parts
+            .headers
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| AppError::MissingToken("Brak nagłówka X-Api-Key.".to_string()))?;
+
+        let (key_id, name): (Uuid, String) = sqlx::query_as(
+            "SELECT id, name FROM api_keys WHERE key_hash = $1 AND revoked_at IS NULL",
+        )
+        .bind(hash_api_key(key))
+        .fetch_optional(&app_state.db_pool)
+        .await?
+        .ok_or_else(|| AppError::InvalidToken("Nieprawidłowy klucz API.".to_string()))?;
+
+        // Znacznik użycia najwyżej raz na minutę - bez zapisu do bazy przy każdym żądaniu
+        sqlx::query(
+            r#"
+            UPDATE api_keys SET last_used_at = NOW()
+            WHERE id = $1 AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#,
+        )
+        .bind(key_id)
+        .execute(&app_state.db_pool)
+        .await?;
+
+        Ok(ApiClient { name })
+    }
+}
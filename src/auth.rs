@@ -1,4 +1,6 @@
 // src/auth.rs
+pub mod oauth;
+
 pub use crate::auth_models::{Role, TokenClaims};
 use crate::errors::AppError;
 use argon2::Argon2;
@@ -32,9 +34,11 @@ pub fn verify_password(hashed_password: &str, password: &str) -> Result<bool, Ap
 }
 
 //Funkcja do generowania JWT
+/// `two_factor` - logowanie zakończone kodem TOTP (zob. `two_factor`)
 pub fn create_jwt(
     user_id: Uuid,
     role: Role,
+    two_factor: bool,
     secret: &str,
     expiration_hours: i64,
 ) -> Result<String, AppError> {
@@ -46,6 +50,8 @@ pub fn create_jwt(
         role,
         exp: expiration_time.timestamp(),
         iat: now.timestamp(),
+        impersonated_by: None,
+        two_factor,
     };
 
     encode(
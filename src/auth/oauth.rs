@@ -0,0 +1,349 @@
+// src/auth/oauth.rs
+
+// Logowanie przez Google (OAuth2, authorization code flow). `/api/auth/google` przekierowuje
+// do Google z losowym `state` zapisanym w krótkotrwałym ciasteczku, a `/api/auth/google/callback`
+// wymienia kod na dane konta. Konto łączymy po potwierdzonym przez Google adresie e-mail
+// (albo zakładamy nowe) i wydajemy ten sam JWT i ciasteczko `token` co logowanie hasłem,
+// więc koszyk gościa, `jwtToken` w localStorage i reszta sesji działają bez zmian.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use reqwest::Client;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::auth::{create_jwt, hash_password};
+use crate::client_ip::ClientIp;
+use crate::errors::AppError;
+use crate::login_lockout::{normalize_login_email, record_login_attempt};
+use crate::models::{Role, User};
+use crate::repo;
+use crate::security_headers::CspNonce;
+use crate::state::{AppState, GoogleOAuthConfig};
+use crate::two_factor::{challenge_cookie, create_challenge, requires_second_step};
+use crate::user_management::is_account_disabled;
+
+const AUTHORIZATION_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const USERINFO_URL: &str = "https://openidconnect.googleapis.com/v1/userinfo";
+
+const STATE_COOKIE: &str = "oauth_state";
+/// Tyle czasu klient ma na wybór konta w Google
+const STATE_COOKIE_MINUTES: i64 = 10;
+
+/// Dokąd wracamy po nieudanym logowaniu - strona logowania pokazuje wtedy komunikat
+const LOGIN_FAILED_PATH: &str = "/logowanie?google=blad";
+
+#[derive(Debug, Deserialize)]
+pub struct GoogleCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Np. `access_denied`, gdy klient anulował wybór konta
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleTokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleUserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+/// Ciasteczko z `state`; `None` tworzy ciasteczko usuwające.
+/// `SameSite=Lax` wystarcza - powrót z Google to nawigacja GET najwyższego poziomu.
+fn state_cookie(state: Option<String>) -> Cookie<'static> {
+    let max_age = if state.is_some() {
+        time::Duration::minutes(STATE_COOKIE_MINUTES)
+    } else {
+        time::Duration::ZERO
+    };
+    Cookie::build((STATE_COOKIE, state.unwrap_or_default()))
+        .path("/api/auth/google")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(max_age)
+        .build()
+}
+
+fn google_config(app_state: &AppState) -> Result<&GoogleOAuthConfig, AppError> {
+    app_state.google_oauth.as_ref().ok_or(AppError::NotFound)
+}
+
+// GET /api/auth/google
+pub async fn google_login_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let config = google_config(&app_state)?;
+    let state = hex::encode(rand::random::<[u8; 32]>());
+
+    let authorization_url = format!(
+        "{}?response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&prompt=select_account",
+        AUTHORIZATION_URL,
+        urlencoding::encode(&config.client_id),
+        urlencoding::encode(&config.redirect_url),
+        urlencoding::encode("openid email"),
+        state
+    );
+
+    let mut response = Redirect::to(&authorization_url).into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&state_cookie(Some(state)).to_string()) {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+    Ok(response)
+}
+
+// GET /api/auth/google/callback
+pub async fn google_callback_handler(
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+    csp_nonce: CspNonce,
+    ClientIp(client_ip): ClientIp,
+    Query(query): Query<GoogleCallbackQuery>,
+) -> Result<Response, AppError> {
+    let config = google_config(&app_state)?;
+
+    let expected_state = CookieJar::from_headers(&request_headers)
+        .get(STATE_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .filter(|state| !state.is_empty());
+    let clear_state_cookie = HeaderValue::from_str(&state_cookie(None).to_string()).ok();
+
+    let result = match (&query.error, &query.code, &query.state, &expected_state) {
+        (Some(error), _, _, _) => {
+            tracing::info!("Logowanie przez Google przerwane: {}", error);
+            None
+        }
+        (None, Some(code), Some(state), Some(expected)) if state == expected => {
+            match complete_google_login(&app_state, config, code, client_ip.as_deref()).await {
+                Ok(login) => Some(login),
+                Err(e) => {
+                    tracing::error!("Logowanie przez Google nie powiodło się: {:?}", e);
+                    None
+                }
+            }
+        }
+        _ => {
+            tracing::warn!("Powrót z Google bez kodu albo z niezgodnym parametrem state");
+            None
+        }
+    };
+
+    let mut response = match result {
+        Some(GoogleLogin::Token(token, redirect)) => {
+            login_bridge_response(&token, redirect, &csp_nonce)
+        }
+        Some(GoogleLogin::SecondStep(user_id)) => {
+            let mut response = Redirect::to("/logowanie/weryfikacja").into_response();
+            let cookie = challenge_cookie(Some(create_challenge(&app_state.jwt_secret, user_id)));
+            if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+                response.headers_mut().append(header::SET_COOKIE, value);
+            }
+            response
+        }
+        None => Redirect::to(LOGIN_FAILED_PATH).into_response(),
+    };
+    if let Some(cookie) = clear_state_cookie {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+    Ok(response)
+}
+
+enum GoogleLogin {
+    /// JWT gotowy do wydania i strona, na którą przechodzimy po zalogowaniu
+    Token(String, &'static str),
+    /// Konto personelu z weryfikacją dwuetapową - JWT dopiero po kodzie (zob. `two_factor`)
+    SecondStep(Uuid),
+}
+
+/// Wymienia kod na dane konta Google i łączy je z kontem sklepu
+async fn complete_google_login(
+    app_state: &Arc<AppState>,
+    config: &GoogleOAuthConfig,
+    code: &str,
+    client_ip: Option<&str>,
+) -> Result<GoogleLogin, AppError> {
+    let client = Client::new();
+
+    let token_resp = client
+        .post(TOKEN_URL)
+        .form(&[
+            ("code", code),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("grant_type", "authorization_code"),
+        ])
+        .send()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Błąd połączenia z Google: {}", e)))?;
+    if !token_resp.status().is_success() {
+        let status = token_resp.status();
+        let error_text = token_resp.text().await.unwrap_or_default();
+        return Err(AppError::InternalServerError(format!(
+            "Google odrzuciło wymianę kodu (status: {}): {}",
+            status, error_text
+        )));
+    }
+    let tokens: GoogleTokenResponse = token_resp.json().await.map_err(|e| {
+        AppError::InternalServerError(format!("Nieprawidłowa odpowiedź Google (token): {}", e))
+    })?;
+
+    let user_info: GoogleUserInfo = client
+        .get(USERINFO_URL)
+        .bearer_auth(&tokens.access_token)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Błąd pobierania danych konta Google: {}", e))
+        })?
+        .json()
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!(
+                "Nieprawidłowa odpowiedź Google (userinfo): {}",
+                e
+            ))
+        })?;
+
+    // Bez potwierdzonego adresu nie wolno nam łączyć kont - ktoś mógłby przejąć cudze konto
+    let email = match user_info.email {
+        Some(email) if user_info.email_verified => email,
+        _ => {
+            return Err(AppError::UnauthorizedAccess(format!(
+                "Konto Google {} nie ma potwierdzonego adresu e-mail",
+                user_info.sub
+            )));
+        }
+    };
+
+    let user = find_or_create_user(app_state, &email).await?;
+    if is_account_disabled(&app_state.db_pool, user.id).await? {
+        return Err(AppError::UnauthorizedAccess(format!(
+            "Konto {} jest zablokowane",
+            user.email
+        )));
+    }
+    mark_email_verified(app_state, user.id).await?;
+
+    // Personel z weryfikacją dwuetapową podaje jeszcze kod, tak jak po haśle
+    if requires_second_step(&app_state.db_pool, &user).await? {
+        tracing::info!(
+            "Pracownik {} zalogowany przez Google - oczekiwanie na kod weryfikacji dwuetapowej",
+            user.id
+        );
+        return Ok(GoogleLogin::SecondStep(user.id));
+    }
+
+    record_login_attempt(
+        &app_state.db_pool,
+        &normalize_login_email(&email),
+        Some(user.id),
+        true,
+        client_ip,
+    )
+    .await?;
+
+    tracing::info!(
+        "Użytkownik {} ({}) zalogowany przez Google.",
+        user.email,
+        user.id
+    );
+    // Jak po haśle: personel bez weryfikacji dwuetapowej trafia od razu do jej konfiguracji
+    let redirect = if user.role.is_staff() {
+        "/moje-konto/bezpieczenstwo"
+    } else {
+        "/"
+    };
+    create_jwt(
+        user.id,
+        user.role,
+        false,
+        &app_state.jwt_secret,
+        app_state.jwt_expiration_hours,
+    )
+    .map(|token| GoogleLogin::Token(token, redirect))
+}
+
+/// Istniejące konto o tym adresie albo nowe konto klienta. Nowe konto dostaje losowe hasło,
+/// którego nikt nie zna - klient może ustawić własne przez "Zapomniałeś hasła?".
+async fn find_or_create_user(app_state: &AppState, email: &str) -> Result<User, AppError> {
+    if let Some(user) = repo::users::find_by_email_ignore_case(&app_state.db_pool, email).await? {
+        return Ok(user);
+    }
+
+    let unusable_password = hex::encode(rand::random::<[u8; 32]>());
+    let password_hash = hash_password(&unusable_password)?;
+    let user = repo::users::insert(
+        &app_state.db_pool,
+        &normalize_login_email(email),
+        &password_hash,
+        Role::Customer,
+    )
+    .await?;
+    tracing::info!(
+        "Założono konto {} ({}) przy logowaniu przez Google",
+        user.email,
+        user.id
+    );
+    Ok(user)
+}
+
+/// Google potwierdziło adres, więc nie wysyłamy już własnego linku weryfikacyjnego
+async fn mark_email_verified(app_state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&app_state.db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Strona pośrednia po udanym logowaniu: ustawia ciasteczko `token` (jak `login_handler`),
+/// zapisuje JWT w localStorage (jak obsługa `loginSuccessDetails` w index.html) i przechodzi na `redirect`
+fn login_bridge_response(token: &str, redirect: &str, csp_nonce: &CspNonce) -> Response {
+    let token_json = serde_json::to_string(token).unwrap_or_else(|_| "\"\"".to_string());
+    let redirect_json = serde_json::to_string(redirect).unwrap_or_else(|_| "\"/\"".to_string());
+    let page = format!(
+        r#"<!DOCTYPE html>
+<html lang="pl">
+<head><meta charset="utf-8"><title>Logowanie - mess - all that vintage</title></head>
+<body>
+<p>Logowanie...</p>
+<script nonce="{}">localStorage.setItem('jwtToken', {});window.location.replace({});</script>
+</body>
+</html>
+"#,
+        csp_nonce.as_str(),
+        token_json,
+        redirect_json
+    );
+
+    let cookie = Cookie::build(("token", token.to_string()))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::days(365))
+        .build();
+
+    let mut response = Html(page).into_response();
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+        headers.append(header::SET_COOKIE, value);
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    response
+}
@@ -29,4 +29,10 @@ pub struct TokenClaims {
     pub role: Role,
     pub exp: i64,
     pub iat: i64,
+    /// Admin oglądający sklep jako ten klient (tryb podglądu, tylko do odczytu)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonated_by: Option<Uuid>,
+    /// Token wydany po drugim kroku logowania (kod TOTP) - wymagany dla uprawnień admina
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub two_factor: bool,
 }
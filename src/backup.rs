@@ -0,0 +1,169 @@
+// src/backup.rs
+
+use chrono::{Duration, Utc};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use url::Url;
+
+use crate::errors::AppError;
+use crate::models::AdminNotificationLevel;
+use crate::notifications::notify_admin;
+use crate::object_storage::{delete_object, list_objects, put_object};
+use crate::state::{AppState, BackupConfig};
+
+/// Podsumowanie wykonanej kopii
+#[derive(Debug)]
+pub struct BackupReport {
+    pub key: String,
+    pub size_bytes: usize,
+    pub removed_old: usize,
+}
+
+/// Uruchamia proces i zwraca błąd z treścią stderr, jeśli zakończył się niepowodzeniem.
+async fn run_command(mut command: Command, name: &str) -> Result<(), AppError> {
+    let output = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Nie udało się uruchomić {}: {}", name, e))
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::InternalServerError(format!(
+            "{} zakończył się błędem ({}): {}",
+            name,
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Zrzut bazy w formacie custom (`pg_restore`). Hasło przekazujemy przez PGPASSWORD,
+/// żeby nie było widoczne w liście procesów.
+async fn dump_database(database_url: &str, target: &Path) -> Result<(), AppError> {
+    let mut url = Url::parse(database_url)
+        .map_err(|e| AppError::InternalServerError(format!("Nieprawidłowy DATABASE_URL: {}", e)))?;
+    let password = url.password().map(|p| {
+        urlencoding::decode(p)
+            .map(|p| p.into_owned())
+            .unwrap_or_else(|_| p.to_string())
+    });
+    let _ = url.set_password(None);
+
+    let mut command = Command::new("pg_dump");
+    command
+        .arg("--format=custom")
+        .arg("--no-owner")
+        .arg("--file")
+        .arg(target)
+        .arg("--dbname")
+        .arg(url.as_str());
+    if let Some(password) = password {
+        command.env("PGPASSWORD", password);
+    }
+    run_command(command, "pg_dump").await
+}
+
+/// Szyfruje zrzut. Odszyfrowanie:
+/// `openssl enc -d -aes-256-cbc -pbkdf2 -iter 200000 -in plik.dump.enc -out plik.dump`
+async fn encrypt_file(source: &Path, target: &Path, passphrase: &str) -> Result<(), AppError> {
+    let mut command = Command::new("openssl");
+    command
+        .args(["enc", "-aes-256-cbc", "-salt", "-pbkdf2", "-iter", "200000"])
+        .arg("-in")
+        .arg(source)
+        .arg("-out")
+        .arg(target)
+        .args(["-pass", "env:BACKUP_ENCRYPTION_PASSPHRASE"])
+        .env("BACKUP_ENCRYPTION_PASSPHRASE", passphrase);
+    run_command(command, "openssl").await
+}
+
+/// Usuwa z magazynu kopie starsze niż `retention_days`. Świeżo wysłanej kopii nigdy nie rusza.
+async fn rotate_old_backups(config: &BackupConfig, current_key: &str) -> Result<usize, AppError> {
+    let cutoff = Utc::now() - Duration::days(config.retention_days);
+    let objects = list_objects(&config.storage, &config.prefix).await?;
+
+    let mut removed = 0;
+    for object in objects {
+        if object.key == current_key || object.last_modified >= cutoff {
+            continue;
+        }
+        delete_object(&config.storage, &object.key).await?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+/// Wykonuje pełną kopię: pg_dump, szyfrowanie, wysyłka do magazynu i rotacja starych kopii.
+pub async fn run_database_backup(config: &BackupConfig) -> Result<BackupReport, AppError> {
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+    let temp_dir = std::env::temp_dir();
+    let dump_path: PathBuf = temp_dir.join(format!("shop-{}.dump", timestamp));
+    let encrypted_path: PathBuf = temp_dir.join(format!("shop-{}.dump.enc", timestamp));
+    let key = format!("{}shop-{}.dump.enc", config.prefix, timestamp);
+
+    let result = async {
+        dump_database(&config.database_url, &dump_path).await?;
+        encrypt_file(&dump_path, &encrypted_path, &config.encryption_passphrase).await?;
+
+        let body = tokio::fs::read(&encrypted_path).await.map_err(|e| {
+            AppError::InternalServerError(format!(
+                "Nie udało się odczytać zaszyfrowanej kopii: {}",
+                e
+            ))
+        })?;
+        let size_bytes = body.len();
+        put_object(&config.storage, &key, body).await?;
+
+        let removed_old = rotate_old_backups(config, &key).await?;
+        Ok(BackupReport {
+            key: key.clone(),
+            size_bytes,
+            removed_old,
+        })
+    }
+    .await;
+
+    // Niezaszyfrowany zrzut nie może zostać na dysku, niezależnie od wyniku
+    let _ = tokio::fs::remove_file(&dump_path).await;
+    let _ = tokio::fs::remove_file(&encrypted_path).await;
+
+    result
+}
+
+/// Zadanie kopii zapasowej (raz na dobę i na żądanie z panelu); błąd trafia też do centrum powiadomień
+pub async fn backup_job(state: &AppState) -> Result<String, AppError> {
+    let Some(config) = &state.backup_config else {
+        return Err(AppError::InternalServerError(
+            "Brak konfiguracji BACKUP_*.".to_string(),
+        ));
+    };
+
+    match run_database_backup(config).await {
+        Ok(report) => Ok(format!(
+            "Zapisano {} ({} B), usunięto {} starych kopii.",
+            report.key, report.size_bytes, report.removed_old
+        )),
+        Err(e) => {
+            let details = match &e {
+                AppError::InternalServerError(message) => message.clone(),
+                other => other.to_string(),
+            };
+            notify_admin(
+                &state.db_pool,
+                AdminNotificationLevel::Error,
+                "Kopia zapasowa bazy nie powiodła się",
+                &details,
+            )
+            .await;
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,331 @@
+// src/cache_stats.rs
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use strum::IntoEnumIterator;
+use strum_macros::{AsRefStr, EnumIter, EnumString};
+use uuid::Uuid;
+
+use crate::models::ProductGender;
+use crate::state::AppState;
+
+/// Ile kluczy jednego cache'u pokazujemy w panelu admina (cache produktów ma do 1000 wpisów)
+pub const MAX_LISTED_KEYS: usize = 200;
+
+/// Cache'e moka w `AppState`, którymi admin może zarządzać z panelu "Cache".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, AsRefStr, EnumString, EnumIter)]
+#[strum(serialize_all = "kebab-case")]
+pub enum CacheName {
+    Products,
+    StaticHtml,
+    ListingFragments,
+    Categories,
+}
+
+impl CacheName {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CacheName::Products => "Produkty",
+            CacheName::StaticHtml => "Strony statyczne",
+            CacheName::ListingFragments => "Fragmenty listingów",
+            CacheName::Categories => "Listy kategorii",
+        }
+    }
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Liczniki trafień i momenty wstawienia kluczy. moka 0.12 nie zbiera statystyk sama,
+/// a TTL liczy od wstawienia wpisu, więc czas do wygaśnięcia wyliczamy z zapamiętanej chwili.
+/// Liczniki są liczone od startu serwera.
+pub struct CacheStats {
+    counters: HashMap<CacheName, CacheCounters>,
+    inserted_at: Mutex<HashMap<(CacheName, String), Instant>>,
+}
+
+impl Default for CacheStats {
+    fn default() -> Self {
+        CacheStats {
+            counters: CacheName::iter()
+                .map(|name| (name, CacheCounters::default()))
+                .collect(),
+            inserted_at: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl CacheStats {
+    pub fn record_lookup(&self, cache: CacheName, hit: bool) {
+        if let Some(counters) = self.counters.get(&cache) {
+            let counter = if hit {
+                &counters.hits
+            } else {
+                &counters.misses
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_insert(&self, cache: CacheName, key: impl ToString) {
+        if let Ok(mut inserted_at) = self.inserted_at.lock() {
+            inserted_at.insert((cache, key.to_string()), Instant::now());
+        }
+    }
+
+    /// (trafienia, chybienia)
+    pub fn lookups(&self, cache: CacheName) -> (u64, u64) {
+        self.counters
+            .get(&cache)
+            .map(|c| {
+                (
+                    c.hits.load(Ordering::Relaxed),
+                    c.misses.load(Ordering::Relaxed),
+                )
+            })
+            .unwrap_or((0, 0))
+    }
+
+    fn inserted_at(&self, cache: CacheName, key: &str) -> Option<Instant> {
+        self.inserted_at
+            .lock()
+            .ok()
+            .and_then(|map| map.get(&(cache, key.to_string())).copied())
+    }
+
+    /// Usuwa znaczniki kluczy, których już nie ma w cache'u (wygasłe, wyparte, unieważnione)
+    fn retain_keys(&self, cache: CacheName, live_keys: &[String]) {
+        if let Ok(mut map) = self.inserted_at.lock() {
+            map.retain(|(name, key), _| *name != cache || live_keys.contains(key));
+        }
+    }
+}
+
+/// Jeden wpis cache'u w panelu: klucz, opis wartości i czas do wygaśnięcia (jeśli znany).
+pub struct CacheEntryInfo {
+    pub key: String,
+    pub description: String,
+    pub expires_in: Option<Duration>,
+}
+
+pub struct CacheOverview {
+    pub name: CacheName,
+    pub entry_count: u64,
+    pub max_capacity: Option<u64>,
+    pub time_to_live: Option<Duration>,
+    pub hits: u64,
+    pub misses: u64,
+    /// Wpisy posortowane od najszybciej wygasających, najwyżej `MAX_LISTED_KEYS`
+    pub entries: Vec<CacheEntryInfo>,
+}
+
+impl CacheOverview {
+    /// Odsetek trafień w procentach; `None`, gdy nie było jeszcze żadnego odczytu
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 * 100.0 / total as f64)
+    }
+}
+
+fn build_overview(
+    app_state: &AppState,
+    name: CacheName,
+    entry_count: u64,
+    max_capacity: Option<u64>,
+    time_to_live: Option<Duration>,
+    mut entries: Vec<(String, String)>,
+) -> CacheOverview {
+    let stats = &app_state.cache_stats;
+    let live_keys: Vec<String> = entries.iter().map(|(key, _)| key.clone()).collect();
+    stats.retain_keys(name, &live_keys);
+
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut entries: Vec<CacheEntryInfo> = entries
+        .into_iter()
+        .map(|(key, description)| {
+            let expires_in = match (time_to_live, stats.inserted_at(name, &key)) {
+                (Some(ttl), Some(inserted_at)) => Some(ttl.saturating_sub(inserted_at.elapsed())),
+                _ => None,
+            };
+            CacheEntryInfo {
+                key,
+                description,
+                expires_in,
+            }
+        })
+        .collect();
+    entries.sort_by_key(|entry| entry.expires_in.unwrap_or(Duration::MAX));
+    entries.truncate(MAX_LISTED_KEYS);
+
+    let (hits, misses) = stats.lookups(name);
+    CacheOverview {
+        name,
+        entry_count,
+        max_capacity,
+        time_to_live,
+        hits,
+        misses,
+        entries,
+    }
+}
+
+fn html_size_description(html: &str) -> String {
+    format!("{:.1} KB", html.len() as f64 / 1024.0).replace('.', ",")
+}
+
+/// Stan wszystkich cache'y do panelu admina. Najpierw wykonuje zaległe zadania moka,
+/// żeby liczba wpisów nie zawierała już wygasłych.
+pub async fn cache_overview(app_state: &AppState) -> Vec<CacheOverview> {
+    let product_cache = &app_state.product_cache;
+    let static_html_cache = &app_state.static_html_cache;
+    let listing_fragment_cache = &app_state.listing_fragment_cache;
+    let category_list_cache = &app_state.category_list_cache;
+    product_cache.run_pending_tasks().await;
+    static_html_cache.run_pending_tasks().await;
+    listing_fragment_cache.run_pending_tasks().await;
+    category_list_cache.run_pending_tasks().await;
+
+    vec![
+        build_overview(
+            app_state,
+            CacheName::Products,
+            product_cache.entry_count(),
+            product_cache.policy().max_capacity(),
+            product_cache.policy().time_to_live(),
+            product_cache
+                .iter()
+                .map(|(id, product)| (id.to_string(), product.name.clone()))
+                .collect(),
+        ),
+        build_overview(
+            app_state,
+            CacheName::StaticHtml,
+            static_html_cache.entry_count(),
+            static_html_cache.policy().max_capacity(),
+            static_html_cache.policy().time_to_live(),
+            static_html_cache
+                .iter()
+                .map(|(key, html)| (key.to_string(), html_size_description(&html)))
+                .collect(),
+        ),
+        build_overview(
+            app_state,
+            CacheName::ListingFragments,
+            listing_fragment_cache.entry_count(),
+            listing_fragment_cache.policy().max_capacity(),
+            listing_fragment_cache.policy().time_to_live(),
+            listing_fragment_cache
+                .iter()
+                .map(|(key, html)| (key.to_string(), html_size_description(&html)))
+                .collect(),
+        ),
+        build_overview(
+            app_state,
+            CacheName::Categories,
+            category_list_cache.entry_count(),
+            category_list_cache.policy().max_capacity(),
+            category_list_cache.policy().time_to_live(),
+            category_list_cache
+                .iter()
+                .map(|(gender, categories)| {
+                    let names: Vec<String> = categories.iter().map(|c| c.to_string()).collect();
+                    (gender.as_ref().to_string(), names.join(", "))
+                })
+                .collect(),
+        ),
+    ]
+}
+
+/// Usuwa jeden klucz. Zwraca `false`, gdy klucza nie było (albo ma zły format dla tego cache'u).
+pub async fn purge_key(app_state: &AppState, cache: CacheName, key: &str) -> bool {
+    match cache {
+        CacheName::Products => match Uuid::parse_str(key) {
+            Ok(id) => app_state.product_cache.remove(&id).await.is_some(),
+            Err(_) => false,
+        },
+        CacheName::StaticHtml => app_state.static_html_cache.remove(key).await.is_some(),
+        CacheName::ListingFragments => app_state.listing_fragment_cache.remove(key).await.is_some(),
+        CacheName::Categories => match ProductGender::from_str(key) {
+            Ok(gender) => app_state
+                .category_list_cache
+                .remove(&gender)
+                .await
+                .is_some(),
+            Err(_) => false,
+        },
+    }
+}
+
+/// Dopasowanie klucza do wzorca, w którym `*` oznacza dowolny ciąg znaków (także pusty).
+pub fn key_matches_pattern(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // Wzorzec bez `*` - dokładne dopasowanie
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Usuwa z cache'u wszystkie klucze pasujące do wzorca. Zwraca liczbę usuniętych wpisów.
+pub async fn purge_matching(app_state: &AppState, cache: CacheName, pattern: &str) -> usize {
+    let keys: Vec<String> = match cache {
+        CacheName::Products => app_state
+            .product_cache
+            .iter()
+            .map(|(id, _)| id.to_string())
+            .collect(),
+        CacheName::StaticHtml => app_state
+            .static_html_cache
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect(),
+        CacheName::ListingFragments => app_state
+            .listing_fragment_cache
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect(),
+        CacheName::Categories => app_state
+            .category_list_cache
+            .iter()
+            .map(|(gender, _)| gender.as_ref().to_string())
+            .collect(),
+    };
+
+    let mut purged = 0;
+    for key in keys.iter().filter(|key| key_matches_pattern(pattern, key)) {
+        if purge_key(app_state, cache, key).await {
+            purged += 1;
+        }
+    }
+    purged
+}
+
+/// Czyści cały wskazany cache albo - przy `None` - wszystkie.
+pub fn purge_all(app_state: &AppState, cache: Option<CacheName>) {
+    for name in CacheName::iter().filter(|name| cache.is_none_or(|c| c == *name)) {
+        match name {
+            CacheName::Products => app_state.product_cache.invalidate_all(),
+            CacheName::StaticHtml => app_state.static_html_cache.invalidate_all(),
+            CacheName::ListingFragments => app_state.listing_fragment_cache.invalidate_all(),
+            CacheName::Categories => app_state.category_list_cache.invalidate_all(),
+        }
+    }
+}
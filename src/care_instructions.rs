@@ -0,0 +1,121 @@
+// src/care_instructions.rs
+
+// Wskazówki pielęgnacji według materiału (tabela `care_instructions`, edytowana w panelu).
+// Materiał produktu to wolny tekst ("100% wełna", "wełniany sweter"), więc wskazówkę
+// dopasowujemy po rdzeniu nazwy materiału: "Wełna" -> "wełn" pasuje do obu przykładów.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{CareInstruction, OrderDetailsResponse};
+
+/// Rdzeń nie krótszy niż 3 znaki - "len" nie może się skurczyć do "l"
+const MIN_STEM_LEN: usize = 3;
+
+/// Rdzeń słowa bez końcowych samogłosek (odmiana: wełna/wełniany, skóra/skórzany)
+fn stem(word: &str) -> String {
+    let mut chars: Vec<char> = word.to_lowercase().chars().collect();
+    while chars.len() > MIN_STEM_LEN && chars.last().is_some_and(|c| "aeiouyąęó".contains(*c)) {
+        chars.pop();
+    }
+    chars.into_iter().collect()
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Czy materiał produktu zawiera materiał ze wskazówki (każde jego słowo, po rdzeniu)
+pub fn material_matches(instruction_material: &str, product_material: &str) -> bool {
+    let product_words = words(product_material);
+    let stems: Vec<String> = words(instruction_material)
+        .iter()
+        .map(|word| stem(word))
+        .collect();
+    !stems.is_empty()
+        && stems.iter().all(|stem| {
+            product_words
+                .iter()
+                .any(|word| word.starts_with(stem.as_str()))
+        })
+}
+
+pub async fn list_care_instructions(pool: &PgPool) -> Result<Vec<CareInstruction>, AppError> {
+    Ok(sqlx::query_as::<_, CareInstruction>(
+        "SELECT * FROM care_instructions ORDER BY lower(material)",
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Wskazówki pasujące do materiałów produktów, bez powtórzeń. Tabela ma kilkanaście
+/// wierszy, więc dopasowanie po stronie aplikacji jest tańsze niż wyrażenia w SQL.
+pub async fn care_instructions_for_materials<'a>(
+    pool: &PgPool,
+    materials: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<CareInstruction>, AppError> {
+    let materials: Vec<&str> = materials.into_iter().collect();
+    if materials.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(list_care_instructions(pool)
+        .await?
+        .into_iter()
+        .filter(|instruction| {
+            materials
+                .iter()
+                .any(|material| material_matches(&instruction.material, material))
+        })
+        .collect())
+}
+
+/// Dodaje wskazówkę albo podmienia treść istniejącej dla tego samego materiału
+pub async fn save_care_instruction(
+    pool: &PgPool,
+    material: &str,
+    instructions: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO care_instructions (material, instructions)
+        VALUES ($1, $2)
+        ON CONFLICT (lower(material)) DO UPDATE SET instructions = EXCLUDED.instructions
+        "#,
+    )
+    .bind(material)
+    .bind(instructions)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_care_instruction(pool: &PgPool, instruction_id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM care_instructions WHERE id = $1")
+        .bind(instruction_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Wskazówki dla materiałów wszystkich produktów zamówienia
+pub async fn care_instructions_for_order(
+    pool: &PgPool,
+    order_details: &OrderDetailsResponse,
+) -> Result<Vec<CareInstruction>, AppError> {
+    care_instructions_for_materials(
+        pool,
+        order_details
+            .items
+            .iter()
+            .filter_map(|item| item.product.material.as_deref()),
+    )
+    .await
+}
@@ -1,16 +1,14 @@
 // src/cart_utils.rs
 
-use chrono::{DateTime, Utc};
 use sqlx::PgConnection;
 use uuid::Uuid;
 
 use crate::{
     auth::TokenClaims,
     errors::AppError,
-    models::{
-        CartDetailsResponse, CartItemPublic, CartItemWithProduct, Product, ProductStatus,
-        ShoppingCart,
-    },
+    models::{CartDetailsResponse, CartItemPublic, Product, ProductStatus, ShoppingCart},
+    repo::{self, carts::CartOwner},
+    reservations::reserved_product_ids_for_cart,
 };
 
 // NOWA, DOCELOWA WERSJA FUNKCJI POMOCNICZEJ
@@ -28,22 +26,14 @@ pub async fn get_cart_details(
             "get_cart_details_v2: Szukanie koszyka dla zalogowanego użytkownika ID: {}",
             claims.sub
         );
-        sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE user_id = $1")
-            .bind(claims.sub)
-            .fetch_optional(&mut *conn)
-            .await?
+        repo::carts::find_by_owner(&mut *conn, CartOwner::User(claims.sub)).await?
     } else if let Some(guest_id) = guest_cart_id_opt {
         // Scenariusz 2: Użytkownik jest gościem. Szukamy koszyka po jego ID sesji.
         tracing::debug!(
             "get_cart_details_v2: Szukanie koszyka dla gościa o ID sesji: {}",
             guest_id
         );
-        sqlx::query_as::<_, ShoppingCart>(
-            "SELECT * FROM shopping_carts WHERE guest_session_id = $1",
-        )
-        .bind(guest_id)
-        .fetch_optional(&mut *conn)
-        .await?
+        repo::carts::find_by_owner(&mut *conn, CartOwner::Guest(guest_id)).await?
     } else {
         // Scenariusz 3: Nie ma żadnej tożsamości (ani tokenu, ani ciasteczka). Użytkownik nie ma koszyka.
         tracing::debug!(
@@ -72,83 +62,71 @@ pub async fn build_cart_details_response(
     cart: &ShoppingCart,
     conn: &mut PgConnection,
 ) -> Result<CartDetailsResponse, AppError> {
-    // KROK 1: Zmodyfikowane zapytanie, które JAWNIE wybiera wszystkie pola.
-    let items_with_products = sqlx::query_as::<_, CartItemWithProduct>(
-        r#"
-            SELECT
-                ci.id AS cart_item_id,
-                ci.cart_id,
-                ci.product_id,
-                ci.added_at,
-                p.id,
-                p.name,
-                p.description,
-                p.price,
-                p.gender,
-                p.condition,
-                p.category,
-                p.status,
-                p.on_sale,
-                p.images,
-                p.created_at, 
-                p.updated_at  
-            FROM cart_items ci
-            JOIN products p ON ci.product_id = p.id
-            WHERE ci.cart_id = $1
-            ORDER BY ci.added_at ASC
-        "#,
-    )
-    .bind(cart.id)
-    .fetch_all(&mut *conn)
-    .await?;
+    // KROK 1: Pozycje koszyka razem z produktami
+    let items_with_products = repo::carts::items_with_products(&mut *conn, cart.id).await?;
 
     let mut cart_items_public: Vec<CartItemPublic> = Vec::with_capacity(items_with_products.len());
     let mut current_total_price: i64 = 0;
 
+    // Produkty zarezerwowane dla tego koszyka zostają - usuwamy tylko te, które trzyma ktoś inny lub sprzedano
+    let product_ids: Vec<Uuid> = items_with_products
+        .iter()
+        .map(|row| row.product_id)
+        .collect();
+    let reserved_for_cart = reserved_product_ids_for_cart(conn, cart.id, &product_ids).await?;
+
     for row in items_with_products {
-        if row.status != ProductStatus::Available {
+        let held_by_this_cart =
+            row.status == ProductStatus::Reserved && reserved_for_cart.contains(&row.product_id);
+        if row.status != ProductStatus::Available && !held_by_this_cart {
             tracing::warn!(
-                "Produkt '{}' w koszyku ma status inny niż 'Available': {:?}. Usuwam.",
+                "Produkt '{}' w koszyku nie jest dostępny dla tego koszyka: {:?}. Usuwam.",
                 row.name,
                 row.status
             );
-            sqlx::query("DELETE FROM cart_items WHERE id = $1")
-                .bind(row.cart_item_id)
-                .execute(&mut *conn)
-                .await?;
+            repo::carts::delete_item(&mut *conn, row.cart_item_id).await?;
             continue;
         }
 
-        current_total_price += row.price;
+        let product = Product {
+            // Teraz wszystkie pola w `row` pasują do pól w `Product`
+            id: row.product_id,
+            name: row.name,
+            slug: row.slug,
+            description: row.description,
+            price: row.price,
+            gender: row.gender,
+            condition: row.condition,
+            category: row.category,
+            status: row.status,
+            images: row.images,
+            on_sale: row.on_sale,
+            size: row.size,
+            brand: row.brand,
+            color: row.color,
+            material: row.material,
+            meta_title: row.meta_title,
+            meta_description: row.meta_description,
+            og_image: row.og_image,
+            sale_price: row.sale_price,
+            sale_starts_at: row.sale_starts_at,
+            sale_ends_at: row.sale_ends_at,
+            created_at: row.created_at, // Teraz to pole istnieje
+            updated_at: row.updated_at, // I to również
+        };
+        // Promocja mogła ruszyć albo się skończyć od dodania do koszyka - liczymy cenę z teraz
+        current_total_price += product.effective_price();
         cart_items_public.push(CartItemPublic {
             cart_item_id: row.cart_item_id,
-            product: Product {
-                // Teraz wszystkie pola w `row` pasują do pól w `Product`
-                id: row.product_id,
-                name: row.name,
-                description: row.description,
-                price: row.price,
-                gender: row.gender,
-                condition: row.condition,
-                category: row.category,
-                status: row.status,
-                images: row.images,
-                on_sale: row.on_sale,
-                created_at: row.created_at, // Teraz to pole istnieje
-                updated_at: row.updated_at, // I to również
-            },
+            product,
             added_at: row.added_at,
         });
     }
 
     // Reszta funkcji pozostaje bez zmian (aktualizacja updated_at i zwrócenie odpowiedzi)
-    let updated_cart_timestamp = sqlx::query_scalar::<_, DateTime<Utc>>(
-        "UPDATE shopping_carts SET updated_at = CURRENT_TIMESTAMP WHERE id = $1 RETURNING updated_at",
-    )
-    .bind(cart.id)
-    .fetch_one(conn)
-    .await
-    .unwrap_or_else(|_| cart.updated_at);
+    let updated_cart_timestamp = repo::carts::touch(conn, cart.id)
+        .await
+        .unwrap_or(cart.updated_at);
 
     Ok(CartDetailsResponse {
         cart_id: cart.id,
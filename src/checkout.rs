@@ -0,0 +1,574 @@
+// src/checkout.rs
+
+// Wieloetapowa kasa: kroki, opcje dostawy i płatności oraz szkic zamówienia (`checkout_drafts`).
+// Każdy krok jest walidowany i zapisywany osobno; ostatni krok składa zamówienie
+// przez `create_order_handler` z danymi odczytanymi ze szkicu.
+
+use std::sync::OnceLock;
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+use validator::{Validate, ValidateEmail};
+
+use crate::errors::{AppError, ValidationErrors};
+use crate::models::{CheckoutDraft, CheckoutFormPayload, CheckoutStepPayload, UserShippingDetails};
+
+/// Domyślny próg darmowej dostawy (w groszach), gdy `FREE_SHIPPING_THRESHOLD` nie jest ustawione
+pub const DEFAULT_FREE_SHIPPING_THRESHOLD: i64 = 20000;
+
+static FREE_SHIPPING_THRESHOLD: OnceLock<i64> = OnceLock::new();
+
+/// Ustawia próg darmowej dostawy z konfiguracji; wywoływane raz przy starcie.
+pub fn set_free_shipping_threshold(threshold: i64) {
+    if FREE_SHIPPING_THRESHOLD.set(threshold).is_err() {
+        tracing::warn!("Próg darmowej dostawy był już ustawiony - pomijam ponowne ustawienie");
+    }
+}
+
+/// Próg wartości produktów (w groszach), od którego dostawa jest darmowa
+pub fn free_shipping_threshold() -> i64 {
+    *FREE_SHIPPING_THRESHOLD
+        .get()
+        .unwrap_or(&DEFAULT_FREE_SHIPPING_THRESHOLD)
+}
+
+/// Metoda dostawy. `name` trafia do zamówienia (`shipping_method_name`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShippingOption {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub cost: i64,
+}
+
+// TODO: Przenieść metody dostawy do konfiguracji lub bazy danych.
+pub const SHIPPING_OPTIONS: [ShippingOption; 3] = [
+    ShippingOption {
+        key: "inpost",
+        name: "Paczkomat InPost 24/7",
+        cost: 1199,
+    },
+    ShippingOption {
+        key: "poczta",
+        name: "Poczta Polska S.A.",
+        cost: 1799,
+    },
+    ShippingOption {
+        key: "darmowa",
+        name: "Darmowa dostawa",
+        cost: 0,
+    },
+];
+
+/// Metody dostawy dostępne dla koszyka o danej wartości produktów.
+pub fn available_shipping_options(items_total: i64) -> Vec<&'static ShippingOption> {
+    SHIPPING_OPTIONS
+        .iter()
+        .filter(|option| option.key != "darmowa" || items_total >= free_shipping_threshold())
+        .collect()
+}
+
+/// Szuka metody dostawy po kluczu; darmowa dostawa tylko od progu.
+pub fn find_shipping_option(key: &str, items_total: i64) -> Option<&'static ShippingOption> {
+    available_shipping_options(items_total)
+        .into_iter()
+        .find(|option| option.key == key)
+}
+
+/// Metody płatności do wyboru w kasie: (wartość formularza, etykieta, opis)
+pub fn payment_method_options(
+    przelewy24_enabled: bool,
+) -> Vec<(&'static str, &'static str, Option<&'static str>)> {
+    let mut options = vec![
+        ("blik", "BLIK", Some("(Zalecane)")),
+        ("transfer", "Przelew tradycyjny", None),
+    ];
+    if przelewy24_enabled {
+        options.push((
+            "przelewy24",
+            "Przelewy24",
+            Some("(szybki przelew, BLIK, karta - płatność online)"),
+        ));
+    }
+    options
+}
+
+/// Krok kasy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckoutStep {
+    Dane,
+    Dostawa,
+    Platnosc,
+    Podsumowanie,
+}
+
+impl CheckoutStep {
+    pub const ALL: [CheckoutStep; 4] = [
+        CheckoutStep::Dane,
+        CheckoutStep::Dostawa,
+        CheckoutStep::Platnosc,
+        CheckoutStep::Podsumowanie,
+    ];
+
+    pub fn index(self) -> i16 {
+        match self {
+            CheckoutStep::Dane => 0,
+            CheckoutStep::Dostawa => 1,
+            CheckoutStep::Platnosc => 2,
+            CheckoutStep::Podsumowanie => 3,
+        }
+    }
+
+    /// Fragment adresu kroku, np. `/htmx/checkout/krok/dostawa`
+    pub fn slug(self) -> &'static str {
+        match self {
+            CheckoutStep::Dane => "dane",
+            CheckoutStep::Dostawa => "dostawa",
+            CheckoutStep::Platnosc => "platnosc",
+            CheckoutStep::Podsumowanie => "podsumowanie",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CheckoutStep::Dane => "Dane",
+            CheckoutStep::Dostawa => "Dostawa",
+            CheckoutStep::Platnosc => "Płatność",
+            CheckoutStep::Podsumowanie => "Podsumowanie",
+        }
+    }
+
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.slug() == slug)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL
+            .get(self.index() as usize + 1)
+            .copied()
+            .unwrap_or(CheckoutStep::Podsumowanie)
+    }
+
+    /// Pierwszy niezatwierdzony krok - tam wraca klient, który przerwał kasę.
+    pub fn first_incomplete(completed_steps: i16) -> Self {
+        Self::ALL
+            .get(completed_steps.max(0) as usize)
+            .copied()
+            .unwrap_or(CheckoutStep::Podsumowanie)
+    }
+
+    /// Do kroku można przejść, jeśli wszystkie wcześniejsze są zatwierdzone.
+    pub fn is_reachable(self, completed_steps: i16) -> bool {
+        self.index() <= completed_steps
+    }
+}
+
+fn trimmed(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn require(
+    errors: &mut ValidationErrors,
+    field: &'static str,
+    value: &Option<String>,
+    message: &str,
+) {
+    if trimmed(value).is_none() {
+        errors.insert(field, message.to_string());
+    }
+}
+
+/// Waliduje pola jednego kroku. E-mail gościa jest wymagany tylko, gdy `is_guest`.
+pub fn validate_step(
+    step: CheckoutStep,
+    payload: &CheckoutStepPayload,
+    is_guest: bool,
+    items_total: i64,
+    przelewy24_enabled: bool,
+) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    match step {
+        CheckoutStep::Dane => {
+            if is_guest {
+                match trimmed(&payload.guest_email) {
+                    None => {
+                        errors.insert("guest_email", "Adres e-mail jest wymagany.".to_string());
+                    }
+                    Some(email) if !email.validate_email() => {
+                        errors.insert(
+                            "guest_email",
+                            "Nieprawidłowy format adresu e-mail.".to_string(),
+                        );
+                    }
+                    Some(_) => {}
+                }
+            }
+            require(
+                &mut errors,
+                "shipping_first_name",
+                &payload.shipping_first_name,
+                "Imię jest wymagane.",
+            );
+            require(
+                &mut errors,
+                "shipping_last_name",
+                &payload.shipping_last_name,
+                "Nazwisko jest wymagane.",
+            );
+            require(
+                &mut errors,
+                "shipping_phone",
+                &payload.shipping_phone,
+                "Telefon jest wymagany.",
+            );
+        }
+        CheckoutStep::Dostawa => {
+            let method = trimmed(&payload.shipping_method_key);
+            match method.as_deref() {
+                None => {
+                    errors.insert("shipping_method_key", "Wybierz metodę dostawy.".to_string());
+                }
+                Some(key) if find_shipping_option(key, items_total).is_none() => {
+                    errors.insert(
+                        "shipping_method_key",
+                        "Ta metoda dostawy nie jest dostępna dla Twojego koszyka.".to_string(),
+                    );
+                }
+                Some("inpost") if trimmed(&payload.inpost_locker_code).is_none() => {
+                    errors.insert(
+                        "inpost_locker_code",
+                        "Wybierz Paczkomat, do którego mamy wysłać paczkę.".to_string(),
+                    );
+                }
+                Some(_) => {}
+            }
+            require(
+                &mut errors,
+                "shipping_address_line1",
+                &payload.shipping_address_line1,
+                "Adres jest wymagany.",
+            );
+            require(
+                &mut errors,
+                "shipping_city",
+                &payload.shipping_city,
+                "Miasto jest wymagane.",
+            );
+            require(
+                &mut errors,
+                "shipping_postal_code",
+                &payload.shipping_postal_code,
+                "Kod pocztowy jest wymagany.",
+            );
+            require(
+                &mut errors,
+                "shipping_country",
+                &payload.shipping_country,
+                "Wybierz kraj.",
+            );
+        }
+        CheckoutStep::Platnosc => {
+            let method = trimmed(&payload.payment_method);
+            let valid = method.as_deref().is_some_and(|m| {
+                payment_method_options(przelewy24_enabled)
+                    .iter()
+                    .any(|(value, _, _)| *value == m)
+            });
+            if !valid {
+                errors.insert("payment_method", "Wybierz metodę płatności.".to_string());
+            }
+        }
+        CheckoutStep::Podsumowanie => {}
+    }
+    errors
+}
+
+/// Pola formularza kasy z krokiem, w którym klient je wypełnia.
+const STEP_FIELDS: [(&str, CheckoutStep); 12] = [
+    ("guest_email", CheckoutStep::Dane),
+    ("shipping_first_name", CheckoutStep::Dane),
+    ("shipping_last_name", CheckoutStep::Dane),
+    ("shipping_phone", CheckoutStep::Dane),
+    ("shipping_method_key", CheckoutStep::Dostawa),
+    ("inpost_locker_code", CheckoutStep::Dostawa),
+    ("shipping_address_line1", CheckoutStep::Dostawa),
+    ("shipping_address_line2", CheckoutStep::Dostawa),
+    ("shipping_city", CheckoutStep::Dostawa),
+    ("shipping_postal_code", CheckoutStep::Dostawa),
+    ("shipping_country", CheckoutStep::Dostawa),
+    ("payment_method", CheckoutStep::Platnosc),
+];
+
+/// Krok kasy, w którym znajduje się pole formularza (`guest_checkout_email` to `guest_email`).
+pub fn step_for_field(field: &str) -> Option<(&'static str, CheckoutStep)> {
+    let field = if field == "guest_checkout_email" {
+        "guest_email"
+    } else {
+        field
+    };
+    STEP_FIELDS.iter().copied().find(|(name, _)| *name == field)
+}
+
+impl From<&CheckoutFormPayload> for CheckoutStepPayload {
+    fn from(payload: &CheckoutFormPayload) -> Self {
+        CheckoutStepPayload {
+            guest_email: payload.guest_checkout_email.clone(),
+            shipping_first_name: Some(payload.shipping_first_name.clone()),
+            shipping_last_name: Some(payload.shipping_last_name.clone()),
+            shipping_phone: Some(payload.shipping_phone.clone()),
+            shipping_address_line1: Some(payload.shipping_address_line1.clone()),
+            shipping_address_line2: payload.shipping_address_line2.clone(),
+            shipping_city: Some(payload.shipping_city.clone()),
+            shipping_postal_code: Some(payload.shipping_postal_code.clone()),
+            shipping_country: Some(payload.shipping_country.clone()),
+            shipping_method_key: Some(payload.shipping_method_key.clone()),
+            inpost_locker_code: payload.inpost_locker_code.clone(),
+            inpost_locker_address: payload.inpost_locker_address.clone(),
+            payment_method: Some(payload.payment_method.clone()),
+        }
+    }
+}
+
+/// Walidacja całego formularza zamówienia przed złożeniem: reguły kroków kasy oraz atrybuty
+/// `validator` z `CheckoutFormPayload`. Zwraca pierwszy krok z błędami i komunikaty przy jego polach.
+pub fn validate_checkout_form(
+    payload: &CheckoutFormPayload,
+    is_guest: bool,
+    items_total: i64,
+    przelewy24_enabled: bool,
+) -> Option<(CheckoutStep, ValidationErrors)> {
+    let step_payload = CheckoutStepPayload::from(payload);
+    let mut errors_by_step: Vec<(CheckoutStep, ValidationErrors)> = [
+        CheckoutStep::Dane,
+        CheckoutStep::Dostawa,
+        CheckoutStep::Platnosc,
+    ]
+    .into_iter()
+    .map(|step| {
+        let errors = validate_step(
+            step,
+            &step_payload,
+            is_guest,
+            items_total,
+            przelewy24_enabled,
+        );
+        (step, errors)
+    })
+    .collect();
+
+    if let Err(validation_errors) = payload.validate() {
+        for (field, field_errors) in validation_errors.field_errors() {
+            let Some((name, step)) = step_for_field(&field) else {
+                continue;
+            };
+            let message = field_errors
+                .first()
+                .and_then(|e| e.message.as_ref())
+                .map_or_else(|| "Nieprawidłowa wartość.".to_string(), |m| m.to_string());
+            if let Some((_, errors)) = errors_by_step.iter_mut().find(|(s, _)| *s == step) {
+                errors.insert(name, message);
+            }
+        }
+    }
+
+    errors_by_step
+        .into_iter()
+        .find(|(_, errors)| !errors.is_empty())
+}
+
+/// Zwraca otwarty (niezłożony) szkic zamówienia koszyka, tworząc go przy pierwszym wejściu do kasy.
+/// Zalogowanemu klientowi nowy szkic wypełniamy zapisanymi danymi do wysyłki.
+pub async fn get_or_create_checkout_draft(
+    conn: &mut PgConnection,
+    cart_id: Uuid,
+    user_id: Option<Uuid>,
+) -> Result<CheckoutDraft, AppError> {
+    if let Some(draft) = sqlx::query_as::<_, CheckoutDraft>(
+        "SELECT * FROM checkout_drafts WHERE cart_id = $1 AND submitted_at IS NULL",
+    )
+    .bind(cart_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    {
+        return Ok(draft);
+    }
+
+    let saved_details = match user_id {
+        Some(user_id) => {
+            sqlx::query_as::<_, UserShippingDetails>(
+                "SELECT * FROM user_shipping_details WHERE user_id = $1",
+            )
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        None => None,
+    };
+    let details = saved_details.as_ref();
+
+    // ON CONFLICT: dwa równoległe żądania kasy nie utworzą dwóch szkiców
+    let draft = sqlx::query_as::<_, CheckoutDraft>(
+        r#"
+            INSERT INTO checkout_drafts (
+                cart_id, shipping_first_name, shipping_last_name, shipping_phone,
+                shipping_address_line1, shipping_address_line2, shipping_city,
+                shipping_postal_code, shipping_country
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (cart_id) WHERE submitted_at IS NULL
+            DO UPDATE SET updated_at = checkout_drafts.updated_at
+            RETURNING *
+        "#,
+    )
+    .bind(cart_id)
+    .bind(details.and_then(|d| d.shipping_first_name.clone()))
+    .bind(details.and_then(|d| d.shipping_last_name.clone()))
+    .bind(details.and_then(|d| d.shipping_phone.clone()))
+    .bind(details.and_then(|d| d.shipping_address_line1.clone()))
+    .bind(details.and_then(|d| d.shipping_address_line2.clone()))
+    .bind(details.and_then(|d| d.shipping_city.clone()))
+    .bind(details.and_then(|d| d.shipping_postal_code.clone()))
+    .bind(details.and_then(|d| d.shipping_country.clone()))
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(draft)
+}
+
+/// Ile kroków kasy klient zatwierdził w otwartym szkicu koszyka (`None`, gdy szkicu nie ma).
+pub async fn open_draft_progress(
+    conn: &mut PgConnection,
+    cart_id: Uuid,
+) -> Result<Option<i16>, AppError> {
+    let progress = sqlx::query_scalar(
+        "SELECT completed_steps FROM checkout_drafts WHERE cart_id = $1 AND submitted_at IS NULL",
+    )
+    .bind(cart_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(progress)
+}
+
+/// Zamyka otwarty szkic koszyka i wiąże go ze złożonym zamówieniem.
+/// Wywoływane w transakcji składania zamówienia, przed usunięciem koszyka gościa.
+pub async fn mark_draft_submitted(
+    conn: &mut PgConnection,
+    cart_id: Uuid,
+    order_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            UPDATE checkout_drafts
+            SET order_id = $1, submitted_at = NOW(), updated_at = NOW()
+            WHERE cart_id = $2 AND submitted_at IS NULL
+        "#,
+    )
+    .bind(order_id)
+    .bind(cart_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Zapisuje pola zatwierdzonego kroku w szkicu i oznacza krok jako ukończony.
+pub async fn save_checkout_step(
+    conn: &mut PgConnection,
+    draft_id: Uuid,
+    step: CheckoutStep,
+    payload: &CheckoutStepPayload,
+) -> Result<CheckoutDraft, AppError> {
+    let completed_steps = step.index() + 1;
+    let query = match step {
+        CheckoutStep::Dane => sqlx::query_as::<_, CheckoutDraft>(
+            r#"
+                UPDATE checkout_drafts SET
+                    guest_email = $2, shipping_first_name = $3, shipping_last_name = $4,
+                    shipping_phone = $5, completed_steps = GREATEST(completed_steps, $6),
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#,
+        )
+        .bind(draft_id)
+        .bind(trimmed(&payload.guest_email).map(|e| e.to_lowercase()))
+        .bind(trimmed(&payload.shipping_first_name))
+        .bind(trimmed(&payload.shipping_last_name))
+        .bind(trimmed(&payload.shipping_phone)),
+        CheckoutStep::Dostawa => {
+            let method = trimmed(&payload.shipping_method_key);
+            // Kod i adres Paczkomatu zapisujemy tylko przy dostawie InPost
+            let (locker, locker_address) = match method.as_deref() {
+                Some("inpost") => match trimmed(&payload.inpost_locker_code) {
+                    Some(code) => (
+                        Some(code.to_uppercase()),
+                        trimmed(&payload.inpost_locker_address),
+                    ),
+                    None => (None, None),
+                },
+                _ => (None, None),
+            };
+            sqlx::query_as::<_, CheckoutDraft>(
+                r#"
+                    UPDATE checkout_drafts SET
+                        shipping_method_key = $2, inpost_locker_code = $3,
+                        inpost_locker_address = $4,
+                        shipping_address_line1 = $5, shipping_address_line2 = $6,
+                        shipping_city = $7, shipping_postal_code = $8, shipping_country = $9,
+                        completed_steps = GREATEST(completed_steps, $10), updated_at = NOW()
+                    WHERE id = $1
+                    RETURNING *
+                "#,
+            )
+            .bind(draft_id)
+            .bind(method)
+            .bind(locker)
+            .bind(locker_address)
+            .bind(trimmed(&payload.shipping_address_line1))
+            .bind(trimmed(&payload.shipping_address_line2))
+            .bind(trimmed(&payload.shipping_city))
+            .bind(trimmed(&payload.shipping_postal_code))
+            .bind(trimmed(&payload.shipping_country))
+        }
+        CheckoutStep::Platnosc => sqlx::query_as::<_, CheckoutDraft>(
+            r#"
+                UPDATE checkout_drafts SET
+                    payment_method = $2, completed_steps = GREATEST(completed_steps, $3),
+                    updated_at = NOW()
+                WHERE id = $1
+                RETURNING *
+            "#,
+        )
+        .bind(draft_id)
+        .bind(trimmed(&payload.payment_method)),
+        CheckoutStep::Podsumowanie => {
+            return Err(AppError::BadRequest(
+                "Podsumowanie zatwierdza się złożeniem zamówienia.".to_string(),
+            ));
+        }
+    };
+
+    let draft = query.bind(completed_steps).fetch_one(&mut *conn).await?;
+    Ok(draft)
+}
+
+/// Wypełnia formularz kroku danymi ze szkicu (np. przy powrocie do wcześniejszego kroku).
+pub fn payload_from_draft(draft: &CheckoutDraft) -> CheckoutStepPayload {
+    CheckoutStepPayload {
+        guest_email: draft.guest_email.clone(),
+        shipping_first_name: draft.shipping_first_name.clone(),
+        shipping_last_name: draft.shipping_last_name.clone(),
+        shipping_phone: draft.shipping_phone.clone(),
+        shipping_address_line1: draft.shipping_address_line1.clone(),
+        shipping_address_line2: draft.shipping_address_line2.clone(),
+        shipping_city: draft.shipping_city.clone(),
+        shipping_postal_code: draft.shipping_postal_code.clone(),
+        shipping_country: draft.shipping_country.clone(),
+        shipping_method_key: draft.shipping_method_key.clone(),
+        inpost_locker_code: draft.inpost_locker_code.clone(),
+        inpost_locker_address: draft.inpost_locker_address.clone(),
+        payment_method: draft.payment_method.clone(),
+    }
+}
@@ -0,0 +1,84 @@
+// src/client_ip.rs
+
+// Adres IP klienta dla limitów żądań, oceny ryzyka zamówień, logu prób logowania i licznika
+// wyświetleń. Bazą jest adres połączenia - `X-Forwarded-For` może ustawić każdy, więc czytamy
+// go tylko, gdy połączenie przyszło od naszego proxy (`TRUSTED_PROXIES`).
+
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::{Extensions, HeaderMap};
+
+use crate::state::AppState;
+
+/// Lista adresów IP z `TRUSTED_PROXIES` ("10.0.0.1, 10.0.0.2"). Błąd zwraca błędny wpis.
+pub fn parse_trusted_proxies(value: &str) -> Result<Vec<IpAddr>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.parse::<IpAddr>().map_err(|_| entry.to_string()))
+        .collect()
+}
+
+/// Adres klienta: adres połączenia, a gdy połączenie przyszło od naszego proxy - pierwszy
+/// obcy adres w `X-Forwarded-For`, licząc od prawej (wpisy dopisane przez nasze proxy).
+pub fn resolve_client_ip(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let forwarded_for = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    Some(
+        forwarded_for
+            .iter()
+            .rev()
+            .filter_map(|hop| hop.parse::<IpAddr>().ok())
+            .find(|hop| !trusted_proxies.contains(hop))
+            .unwrap_or(peer),
+    )
+}
+
+/// Adres połączenia odłożony przez `into_make_service_with_connect_info`
+pub fn peer_ip(extensions: &Extensions) -> Option<IpAddr> {
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
+/// Ekstraktor adresu klienta dla handlerów (zob. `resolve_client_ip`)
+pub struct ClientIp(pub Option<String>);
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    Arc<AppState>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = Arc::<AppState>::from_ref(state);
+        Ok(ClientIp(
+            resolve_client_ip(
+                peer_ip(&parts.extensions),
+                &parts.headers,
+                &state.trusted_proxies,
+            )
+            .map(|ip| ip.to_string()),
+        ))
+    }
+}
@@ -1,11 +1,13 @@
 // src.cloudinary
 
 use crate::{errors::AppError, state::CloudinaryConfig};
+use chrono::{DateTime, Utc};
 use reqwest::{Client, multipart};
 use serde::Deserialize;
 use sha1::{Digest, Sha1};
 use std::collections::BTreeMap;
 use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 struct CloudinaryUploadResponse {
@@ -18,6 +20,21 @@ struct CloudinaryDeleteResponse {
     result: String,
 }
 
+/// Folder, w którym trzymamy wszystkie zdjęcia danego produktu (`products/{id}`).
+/// Dzięki temu po public_id od razu wiadomo, do którego produktu należy zasób.
+pub fn product_asset_folder(product_id: Uuid) -> String {
+    format!("products/{}", product_id)
+}
+
+/// Folder zdjęć dołączonych do reklamacji (`complaints/{id}`), poza `products/`,
+/// więc sprzątanie osieroconych zdjęć produktów ich nie dotyka.
+pub fn complaint_asset_folder(complaint_id: Uuid) -> String {
+    format!("complaints/{}", complaint_id)
+}
+
+/// Folder banerów strony głównej, poza `products/` - sprzątanie zdjęć produktów go nie dotyka
+pub const HOMEPAGE_ASSET_FOLDER: &str = "homepage";
+
 // Funkcja do ekstrakcji public_id z URL-a Cloudinary
 pub fn extract_public_id_from_url(url: &str, cloud_name: &str) -> Option<String> {
     let base = format!("https://res.cloudinary.com/{}/image/upload/", cloud_name);
@@ -162,6 +179,7 @@ pub async fn delete_image_from_cloudinary(
 pub async fn upload_image_to_cloudinary(
     image_bytes: Vec<u8>,
     filename: String,
+    folder: &str,
     config: &CloudinaryConfig,
 ) -> Result<String, AppError> {
     // Generowanie timestampu
@@ -170,8 +188,8 @@ pub async fn upload_image_to_cloudinary(
         .map_err(|_| AppError::InternalServerError("Błąd czasu systemowego".to_string()))?
         .as_secs();
 
-    // Przygotowanie parametrów do podpisu
-    let params_to_sign = format!("timestamp={}", timestamp);
+    // Przygotowanie parametrów do podpisu (w kolejności alfabetycznej)
+    let params_to_sign = format!("folder={}&timestamp={}", folder, timestamp);
 
     // Dodanie sekretu API do stringu do podpisania
     let string_to_sign = format!("{}{}", params_to_sign, config.api_secret);
@@ -196,6 +214,7 @@ pub async fn upload_image_to_cloudinary(
     let form = reqwest::multipart::Form::new()
         .part("file", part)
         .text("api_key", config.api_key.clone())
+        .text("folder", folder.to_string())
         .text("timestamp", timestamp.to_string())
         .text("signature", signature);
 
@@ -247,3 +266,414 @@ pub async fn upload_image_to_cloudinary(
         }
     }
 }
+
+/// Metadane obrazu z Admin API Cloudinary (rozmiar i opcjonalna analiza jakości).
+#[derive(Debug, Deserialize)]
+pub struct CloudinaryImageMetadata {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: u64,
+    pub quality_analysis: Option<CloudinaryQualityAnalysis>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CloudinaryQualityAnalysis {
+    /// Ostrość w skali 0-1 (im niżej, tym bardziej rozmyte zdjęcie).
+    pub focus: Option<f64>,
+}
+
+pub async fn fetch_image_metadata(
+    public_id: &str,
+    config: &CloudinaryConfig,
+) -> Result<CloudinaryImageMetadata, AppError> {
+    let url = format!(
+        "https://api.cloudinary.com/v1_1/{}/resources/image/upload/{}",
+        config.cloud_name, public_id
+    );
+
+    let client = Client::new();
+    let resp = client
+        .get(&url)
+        .basic_auth(&config.api_key, Some(&config.api_secret))
+        .query(&[("quality_analysis", "true")])
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Błąd sieci przy pobieraniu metadanych obrazu '{}': {}",
+                public_id,
+                e
+            );
+            AppError::InternalServerError("Błąd połączenia z serwerem obrazów".to_string())
+        })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        tracing::warn!(
+            "Cloudinary zwróciło status {} dla metadanych obrazu '{}'",
+            status,
+            public_id
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Nie można pobrać metadanych obrazu (status: {})",
+            status
+        )));
+    }
+
+    resp.json::<CloudinaryImageMetadata>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji metadanych obrazu z Cloudinary: {}", e);
+        AppError::InternalServerError(
+            "Nie można przetworzyć odpowiedzi z serwera obrazów".to_string(),
+        )
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct CloudinaryTaggingUploadResponse {
+    public_id: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Wysyła zdjęcie do tymczasowego folderu z włączonym auto-tagowaniem (dodatek Cloudinary),
+/// odczytuje rozpoznane tagi i od razu usuwa tymczasowy zasób.
+pub async fn fetch_image_tags(
+    image_bytes: Vec<u8>,
+    filename: String,
+    config: &CloudinaryConfig,
+    tagging_addon: &str,
+) -> Result<Vec<String>, AppError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| AppError::InternalServerError("Błąd czasu systemowego".to_string()))?
+        .as_secs();
+
+    let mut params_to_sign = BTreeMap::new();
+    params_to_sign.insert("auto_tagging".to_string(), "0.5".to_string());
+    params_to_sign.insert("categorization".to_string(), tagging_addon.to_string());
+    params_to_sign.insert("folder".to_string(), "tagging_tmp".to_string());
+    params_to_sign.insert("timestamp".to_string(), timestamp.to_string());
+
+    let mut signature_string = params_to_sign
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&");
+    signature_string.push_str(&config.api_secret);
+
+    let mut hasher = Sha1::new();
+    hasher.update(signature_string.as_bytes());
+    let signature = hex::encode(hasher.finalize());
+
+    let part = multipart::Part::bytes(image_bytes)
+        .file_name(filename)
+        .mime_str("image/*")
+        .map_err(|e| {
+            tracing::error!("Błąd ustawiania typu MIME: {}", e);
+            AppError::InternalServerError("Wewnętrzny błąd podczas przygotowania pliku".to_string())
+        })?;
+
+    let mut form = multipart::Form::new()
+        .part("file", part)
+        .text("api_key", config.api_key.clone())
+        .text("signature", signature);
+    for (key, value) in params_to_sign {
+        form = form.text(key, value);
+    }
+
+    let url = format!(
+        "https://api.cloudinary.com/v1_1/{}/image/upload",
+        config.cloud_name
+    );
+    let resp = Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!("Błąd sieci podczas tagowania zdjęcia w Cloudinary: {}", e);
+            AppError::InternalServerError("Błąd połączenia z serwerem obrazów".to_string())
+        })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "Błąd tagowania zdjęcia w Cloudinary: Status={}, Treść={}",
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Błąd podczas analizy zdjęcia (status: {})",
+            status
+        )));
+    }
+
+    let tagging_result = resp
+        .json::<CloudinaryTaggingUploadResponse>()
+        .await
+        .map_err(|e| {
+            tracing::error!("Błąd deserializacji odpowiedzi tagowania Cloudinary: {}", e);
+            AppError::InternalServerError(
+                "Nie można przetworzyć odpowiedzi z serwera obrazów".to_string(),
+            )
+        })?;
+
+    // Zasób był potrzebny tylko do analizy - właściwe zdjęcia są wysyłane przy zapisie produktu
+    if let Err(e) = delete_image_from_cloudinary(&tagging_result.public_id, config).await {
+        tracing::warn!(
+            "Nie udało się usunąć tymczasowego zdjęcia '{}': {:?}",
+            tagging_result.public_id,
+            e
+        );
+    }
+
+    Ok(tagging_result.tags)
+}
+
+/// Ujednolicony kadr zdjęć produktu: białe tło, proporcje 4:5 jak w siatce sklepu.
+const UNIFORM_FRAMING_TRANSFORMATION: &str = "b_white,c_pad,ar_4:5,w_1200";
+
+/// Tworzy nową wersję istniejącego zdjęcia z usuniętym tłem (dodatek Cloudinary AI Background Removal)
+/// i ujednoliconym kadrem. Oryginał pozostaje nietknięty - zwracany jest adres nowego zasobu.
+///
+/// Usuwanie tła działa po stronie Cloudinary asynchronicznie - przez chwilę po zapisie
+/// zdjęcie może jeszcze wyświetlać się z tłem.
+pub async fn create_background_removed_copy(
+    source_url: &str,
+    folder: &str,
+    config: &CloudinaryConfig,
+) -> Result<String, AppError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| AppError::InternalServerError("Błąd czasu systemowego".to_string()))?
+        .as_secs();
+
+    let mut params_to_sign = BTreeMap::new();
+    params_to_sign.insert(
+        "background_removal".to_string(),
+        "cloudinary_ai".to_string(),
+    );
+    params_to_sign.insert("folder".to_string(), folder.to_string());
+    params_to_sign.insert("timestamp".to_string(), timestamp.to_string());
+    params_to_sign.insert(
+        "transformation".to_string(),
+        UNIFORM_FRAMING_TRANSFORMATION.to_string(),
+    );
+
+    let mut signature_string = params_to_sign
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&");
+    signature_string.push_str(&config.api_secret);
+
+    let mut hasher = Sha1::new();
+    hasher.update(signature_string.as_bytes());
+    let signature = hex::encode(hasher.finalize());
+
+    let mut form_params = params_to_sign;
+    form_params.insert("file".to_string(), source_url.to_string());
+    form_params.insert("api_key".to_string(), config.api_key.clone());
+    form_params.insert("signature".to_string(), signature);
+
+    let url = format!(
+        "https://api.cloudinary.com/v1_1/{}/image/upload",
+        config.cloud_name
+    );
+    let resp = Client::new()
+        .post(&url)
+        .form(&form_params)
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!("Błąd sieci podczas usuwania tła w Cloudinary: {}", e);
+            AppError::InternalServerError("Błąd połączenia z serwerem obrazów".to_string())
+        })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "Błąd usuwania tła dla '{}': Status={}, Treść={}",
+            source_url,
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Nie udało się usunąć tła (status: {})",
+            status
+        )));
+    }
+
+    let upload_result = resp.json::<CloudinaryUploadResponse>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji odpowiedzi Cloudinary: {}", e);
+        AppError::InternalServerError(
+            "Nie można przetworzyć odpowiedzi z serwera obrazów".to_string(),
+        )
+    })?;
+    Ok(upload_result.secure_url)
+}
+
+/// Przenosi zasób pod nowy public_id (Upload API `rename`) i zwraca jego nowy adres.
+pub async fn rename_image(
+    from_public_id: &str,
+    to_public_id: &str,
+    config: &CloudinaryConfig,
+) -> Result<String, AppError> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| AppError::InternalServerError("Błąd czasu systemowego".to_string()))?
+        .as_secs();
+
+    let mut params_to_sign = BTreeMap::new();
+    params_to_sign.insert("from_public_id".to_string(), from_public_id.to_string());
+    params_to_sign.insert("timestamp".to_string(), timestamp.to_string());
+    params_to_sign.insert("to_public_id".to_string(), to_public_id.to_string());
+
+    let mut signature_string = params_to_sign
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<String>>()
+        .join("&");
+    signature_string.push_str(&config.api_secret);
+
+    let mut hasher = Sha1::new();
+    hasher.update(signature_string.as_bytes());
+    let signature = hex::encode(hasher.finalize());
+
+    let mut form_params = params_to_sign;
+    form_params.insert("api_key".to_string(), config.api_key.clone());
+    form_params.insert("signature".to_string(), signature);
+
+    let url = format!(
+        "https://api.cloudinary.com/v1_1/{}/image/rename",
+        config.cloud_name
+    );
+    let resp = Client::new()
+        .post(&url)
+        .form(&form_params)
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Błąd sieci przy przenoszeniu zasobu '{}': {}",
+                from_public_id,
+                e
+            );
+            AppError::InternalServerError("Błąd połączenia z serwerem obrazów".to_string())
+        })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "Błąd przenoszenia '{}' -> '{}': Status={}, Treść={}",
+            from_public_id,
+            to_public_id,
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Nie udało się przenieść zdjęcia (status: {})",
+            status
+        )));
+    }
+
+    let rename_result = resp.json::<CloudinaryUploadResponse>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji odpowiedzi Cloudinary: {}", e);
+        AppError::InternalServerError(
+            "Nie można przetworzyć odpowiedzi z serwera obrazów".to_string(),
+        )
+    })?;
+    Ok(rename_result.secure_url)
+}
+
+/// Zasób z listy Admin API.
+#[derive(Debug, Deserialize)]
+pub struct CloudinaryResource {
+    pub public_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CloudinaryResourceListResponse {
+    #[serde(default)]
+    resources: Vec<CloudinaryResource>,
+    next_cursor: Option<String>,
+}
+
+/// Zwraca wszystkie zdjęcia, których public_id zaczyna się od `prefix` (Admin API, stronicowane).
+pub async fn list_images_with_prefix(
+    prefix: &str,
+    config: &CloudinaryConfig,
+) -> Result<Vec<CloudinaryResource>, AppError> {
+    let url = format!(
+        "https://api.cloudinary.com/v1_1/{}/resources/image/upload",
+        config.cloud_name
+    );
+    let client = Client::new();
+    let mut resources = Vec::new();
+    let mut next_cursor: Option<String> = None;
+
+    loop {
+        let mut query = vec![
+            ("prefix", prefix.to_string()),
+            ("max_results", "500".to_string()),
+        ];
+        if let Some(cursor) = &next_cursor {
+            query.push(("next_cursor", cursor.clone()));
+        }
+
+        let resp = client
+            .get(&url)
+            .basic_auth(&config.api_key, Some(&config.api_secret))
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Błąd sieci przy listowaniu zasobów '{}': {}", prefix, e);
+                AppError::InternalServerError("Błąd połączenia z serwerem obrazów".to_string())
+            })?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            tracing::error!(
+                "Cloudinary zwróciło status {} przy listowaniu zasobów '{}'",
+                status,
+                prefix
+            );
+            return Err(AppError::InternalServerError(format!(
+                "Nie można pobrać listy zdjęć (status: {})",
+                status
+            )));
+        }
+
+        let page = resp
+            .json::<CloudinaryResourceListResponse>()
+            .await
+            .map_err(|e| {
+                tracing::error!("Błąd deserializacji listy zasobów Cloudinary: {}", e);
+                AppError::InternalServerError(
+                    "Nie można przetworzyć odpowiedzi z serwera obrazów".to_string(),
+                )
+            })?;
+        resources.extend(page.resources);
+
+        match page.next_cursor {
+            Some(cursor) => next_cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    Ok(resources)
+}
@@ -0,0 +1,128 @@
+// src/cloudinary_maintenance.rs
+
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+
+use crate::cloudinary::{
+    delete_image_from_cloudinary, extract_public_id_from_url, list_images_with_prefix,
+    product_asset_folder, rename_image,
+};
+use crate::errors::AppError;
+use crate::models::Product;
+use crate::state::AppState;
+
+/// Zasoby młodsze niż ten próg pomijamy przy sprzątaniu - zdjęcia są wysyłane do Cloudinary
+/// przed zapisem produktu w bazie, więc świeży plik może jeszcze nie mieć wpisu w `products`.
+const ORPHAN_GRACE_PERIOD_HOURS: i64 = 24;
+
+/// Przenosi zdjęcia produktów z płaskiej przestrzeni nazw do folderów `products/{id}/`
+/// i podmienia adresy w `products.images`.
+///
+/// Zadanie jest idempotentne - zdjęcia, które już leżą w folderze produktu, są pomijane,
+/// więc można je bezpiecznie uruchomić ponownie po przerwaniu.
+///
+/// Zwraca liczbę przeniesionych zdjęć.
+pub async fn migrate_product_images_to_folders(app_state: &AppState) -> Result<usize, AppError> {
+    let products = sqlx::query_as::<_, Product>("SELECT * FROM products")
+        .fetch_all(&app_state.db_pool)
+        .await?;
+    let cloud_name = &app_state.cloudinary_config.cloud_name;
+
+    let mut moved = 0;
+    for product in &products {
+        let folder = product_asset_folder(product.id);
+        let mut product_changed = false;
+
+        for image_url in &product.images {
+            let Some(public_id) = extract_public_id_from_url(image_url, cloud_name) else {
+                continue;
+            };
+            if public_id.starts_with(&format!("{}/", folder)) {
+                continue;
+            }
+
+            let file_name = public_id.rsplit('/').next().unwrap_or(&public_id);
+            let target_public_id = format!("{}/{}", folder, file_name);
+            let new_url =
+                match rename_image(&public_id, &target_public_id, &app_state.cloudinary_config)
+                    .await
+                {
+                    Ok(url) => url,
+                    Err(e) => {
+                        tracing::warn!(
+                            "[Foldery Cloudinary] Pominięto zdjęcie '{}' produktu {}: {:?}",
+                            public_id,
+                            product.id,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+            // array_replace zamiast nadpisywania całej tablicy - admin mógł w międzyczasie edytować produkt
+            sqlx::query(
+                "UPDATE products SET images = array_replace(images, $1, $2), updated_at = NOW() WHERE id = $3",
+            )
+            .bind(image_url)
+            .bind(&new_url)
+            .bind(product.id)
+            .execute(&app_state.db_pool)
+            .await?;
+
+            moved += 1;
+            product_changed = true;
+        }
+
+        if product_changed {
+            app_state.product_cache.invalidate(&product.id).await;
+        }
+    }
+
+    tracing::info!(
+        "[Foldery Cloudinary] Przeniesiono {} zdjęć do folderów produktów.",
+        moved
+    );
+    Ok(moved)
+}
+
+/// Usuwa z Cloudinary zdjęcia w folderach `products/`, do których nie odwołuje się żaden produkt
+/// (np. po przerwanym zapisie formularza albo ręcznej edycji bazy).
+///
+/// Zwraca liczbę usuniętych zasobów.
+pub async fn cleanup_orphaned_product_images(app_state: &AppState) -> Result<usize, AppError> {
+    let cloud_name = &app_state.cloudinary_config.cloud_name;
+    let referenced_images: Vec<String> = sqlx::query_scalar("SELECT unnest(images) FROM products")
+        .fetch_all(&app_state.db_pool)
+        .await?;
+    let referenced_public_ids: HashSet<String> = referenced_images
+        .iter()
+        .filter_map(|url| extract_public_id_from_url(url, cloud_name))
+        .collect();
+
+    let resources = list_images_with_prefix("products/", &app_state.cloudinary_config).await?;
+    let grace_cutoff = Utc::now() - Duration::hours(ORPHAN_GRACE_PERIOD_HOURS);
+
+    let mut deleted = 0;
+    for resource in resources {
+        if resource.created_at > grace_cutoff || referenced_public_ids.contains(&resource.public_id)
+        {
+            continue;
+        }
+
+        match delete_image_from_cloudinary(&resource.public_id, &app_state.cloudinary_config).await
+        {
+            Ok(()) => deleted += 1,
+            Err(e) => tracing::warn!(
+                "[Sprzątanie Cloudinary] Nie udało się usunąć '{}': {:?}",
+                resource.public_id,
+                e
+            ),
+        }
+    }
+
+    tracing::info!(
+        "[Sprzątanie Cloudinary] Usunięto {} osieroconych zdjęć.",
+        deleted
+    );
+    Ok(deleted)
+}
@@ -0,0 +1,158 @@
+// src/cms.rs
+
+// Strony informacyjne edytowane w panelu (o nas, regulamin, polityka prywatności, FAQ...).
+// Treść to Markdown zapisany w `static_pages`; opublikowana wersja zastępuje wbudowaną treść
+// z `views::static_pages`, a renderowany HTML trafia do tego samego cache'u stron statycznych.
+
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{Event, Options, Parser, Tag, html};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+/// Maksymalna długość treści strony (Markdown)
+pub const MAX_STATIC_PAGE_BODY_LEN: usize = 100_000;
+pub const MAX_STATIC_PAGE_TITLE_LEN: usize = 200;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct StaticPage {
+    pub slug: String,
+    pub title: String,
+    pub body: String,
+    pub published: bool,
+    pub updated_by_email: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Klucz strony w cache'u stron statycznych - wspólny dla handlera, rozgrzewania i panelu
+pub fn static_page_cache_key(slug: &str) -> String {
+    format!("static_page:{}", slug)
+}
+
+/// Markdown -> HTML (tabele, przekreślenia, przypisy). Surowy HTML z treści pokazujemy
+/// jako tekst, a linki `javascript:`/`data:` zamieniamy na `#` - strona trafia do cache'u
+/// i jest serwowana wszystkim klientom, więc nie może wstrzyknąć znaczników ani skryptów.
+pub fn render_markdown(body: &str) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_SMART_PUNCTUATION;
+    let events = Parser::new_ext(body, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if is_unsafe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: "#".into(),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if is_unsafe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: "#".into(),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut output = String::with_capacity(body.len() * 3 / 2);
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Schematy adresów, które wykonują kod zamiast prowadzić do strony. Przeglądarki pomijają
+/// białe znaki i znaki sterujące w schemacie ("java\tscript:"), więc my też.
+fn is_unsafe_url(url: &str) -> bool {
+    let scheme: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    ["javascript:", "vbscript:", "data:"]
+        .iter()
+        .any(|unsafe_scheme| scheme.starts_with(unsafe_scheme))
+}
+
+pub async fn find_static_page(pool: &PgPool, slug: &str) -> Result<Option<StaticPage>, AppError> {
+    let page = sqlx::query_as::<_, StaticPage>(
+        r#"
+            SELECT p.slug, p.title, p.body, p.published, u.email AS updated_by_email, p.updated_at
+            FROM static_pages p
+            LEFT JOIN users u ON u.id = p.updated_by
+            WHERE p.slug = $1
+        "#,
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await?;
+    Ok(page)
+}
+
+/// Opublikowana wersja strony; `None` = wbudowana treść
+pub async fn published_static_page(
+    pool: &PgPool,
+    slug: &str,
+) -> Result<Option<StaticPage>, AppError> {
+    Ok(find_static_page(pool, slug)
+        .await?
+        .filter(|page| page.published))
+}
+
+pub async fn list_static_pages(pool: &PgPool) -> Result<Vec<StaticPage>, AppError> {
+    let pages = sqlx::query_as::<_, StaticPage>(
+        r#"
+            SELECT p.slug, p.title, p.body, p.published, u.email AS updated_by_email, p.updated_at
+            FROM static_pages p
+            LEFT JOIN users u ON u.id = p.updated_by
+            ORDER BY p.slug
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(pages)
+}
+
+pub async fn save_static_page(
+    pool: &PgPool,
+    slug: &str,
+    title: &str,
+    body: &str,
+    published: bool,
+    admin_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            INSERT INTO static_pages (slug, title, body, published, updated_by)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (slug) DO UPDATE
+            SET title = EXCLUDED.title, body = EXCLUDED.body, published = EXCLUDED.published,
+                updated_by = EXCLUDED.updated_by, updated_at = NOW()
+        "#,
+    )
+    .bind(slug)
+    .bind(title)
+    .bind(body)
+    .bind(published)
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Usuwa wersję z panelu - strona wraca do wbudowanej treści
+pub async fn delete_static_page(pool: &PgPool, slug: &str) -> Result<bool, AppError> {
+    let result = sqlx::query("DELETE FROM static_pages WHERE slug = $1")
+        .bind(slug)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,257 @@
+// src/complaints.rs
+
+// Reklamacje (niezgodność towaru z umową). Klient zgłasza reklamację jednej pozycji doręczonego
+// zamówienia, opcjonalnie ze zdjęciami, a sklep ma 14 dni na odpowiedź - po tym terminie
+// reklamację uważa się za uznaną, dlatego kolejka w panelu pokazuje odliczanie.
+
+use chrono::{DateTime, Duration, Utc};
+use futures::future::try_join_all;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::cloudinary::{complaint_asset_folder, upload_image_to_cloudinary};
+use crate::errors::AppError;
+use crate::models::{Complaint, ComplaintStatus, Order, OrderItem};
+use crate::returns::delivered_at;
+use crate::state::AppState;
+
+/// Ustawowy termin na odpowiedź na reklamację
+pub const COMPLAINT_RESPONSE_DAYS: i64 = 14;
+/// Odpowiedzialność za niezgodność towaru z umową - 2 lata od doręczenia
+pub const COMPLAINT_WINDOW_DAYS: i64 = 2 * 365;
+pub const MAX_COMPLAINT_PHOTOS: usize = 5;
+pub const MAX_COMPLAINT_DESCRIPTION_LEN: usize = 2000;
+
+const COMPLAINT_SELECT: &str = r#"
+    SELECT c.*, p.name AS product_name
+    FROM complaints c
+    JOIN products p ON p.id = c.product_id
+"#;
+
+/// Krótki numer reklamacji do e-maili i panelu, np. "RK-1A2B3C4D"
+pub fn complaint_reference(complaint: &Complaint) -> String {
+    format!(
+        "RK-{}",
+        complaint.id.simple().to_string()[..8].to_uppercase()
+    )
+}
+
+/// Do kiedy sklep musi odpowiedzieć na reklamację
+pub fn response_deadline(complaint: &Complaint) -> DateTime<Utc> {
+    complaint.created_at + Duration::days(COMPLAINT_RESPONSE_DAYS)
+}
+
+/// Czy do zamówienia można jeszcze złożyć reklamację (doręczone, w ciągu 2 lat)
+pub async fn complaint_window_open(pool: &PgPool, order: &Order) -> Result<bool, AppError> {
+    Ok(delivered_at(pool, order)
+        .await?
+        .is_some_and(|delivered_at| {
+            delivered_at + Duration::days(COMPLAINT_WINDOW_DAYS) > Utc::now()
+        }))
+}
+
+/// Pozycje zamówienia bez trwającej ani uznanej reklamacji. Po odrzuceniu można złożyć kolejną.
+pub async fn complaintable_order_item_ids(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Vec<Uuid>, AppError> {
+    Ok(sqlx::query_scalar(
+        r#"
+        SELECT oi.id FROM order_items oi
+        WHERE oi.order_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM complaints c
+              WHERE c.order_item_id = oi.id AND c.status <> $2
+          )
+        "#,
+    )
+    .bind(order_id)
+    .bind(ComplaintStatus::Rejected)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn complaints_for_order(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Vec<Complaint>, AppError> {
+    Ok(sqlx::query_as::<_, Complaint>(&format!(
+        "{} WHERE c.order_id = $1 ORDER BY c.created_at DESC",
+        COMPLAINT_SELECT
+    ))
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Kolejka reklamacji: najpierw nierozpatrzone, od najbliższego terminu odpowiedzi
+pub async fn list_complaints(
+    pool: &PgPool,
+    status: Option<ComplaintStatus>,
+) -> Result<Vec<Complaint>, AppError> {
+    Ok(sqlx::query_as::<_, Complaint>(&format!(
+        r#"
+        {}
+        WHERE ($1::complaint_status IS NULL OR c.status = $1)
+        ORDER BY c.resolved_at IS NOT NULL, c.resolved_at DESC, c.created_at ASC
+        "#,
+        COMPLAINT_SELECT
+    ))
+    .bind(status)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Reklamacje czekające na odpowiedź sklepu (dla przypomnień o terminie)
+pub async fn unresolved_complaints(pool: &PgPool) -> Result<Vec<Complaint>, AppError> {
+    Ok(sqlx::query_as::<_, Complaint>(&format!(
+        "{} WHERE c.status IN ($1, $2) ORDER BY c.created_at ASC",
+        COMPLAINT_SELECT
+    ))
+    .bind(ComplaintStatus::Open)
+    .bind(ComplaintStatus::InReview)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn find_complaint(pool: &PgPool, complaint_id: Uuid) -> Result<Complaint, AppError> {
+    sqlx::query_as::<_, Complaint>(&format!("{} WHERE c.id = $1", COMPLAINT_SELECT))
+        .bind(complaint_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// Zapisuje reklamację pozycji zamówienia. Zdjęcia (nazwa pliku, treść) trafiają do folderu
+/// reklamacji w Cloudinary. Sprawdzenie właściciela i terminu należy do wywołującego.
+pub async fn create_complaint(
+    app_state: &AppState,
+    order_id: Uuid,
+    order_item_id: Uuid,
+    user_id: Uuid,
+    description: &str,
+    photos: Vec<(String, Vec<u8>)>,
+) -> Result<Complaint, AppError> {
+    let pool = &app_state.db_pool;
+    if !complaintable_order_item_ids(pool, order_id)
+        .await?
+        .contains(&order_item_id)
+    {
+        return Err(AppError::Conflict(
+            "Ten produkt ma już zgłoszoną reklamację.".to_string(),
+        ));
+    }
+
+    // ID nadajemy przed uploadem, żeby zdjęcia od razu trafiły do folderu reklamacji
+    let complaint_id = Uuid::new_v4();
+    let folder = complaint_asset_folder(complaint_id);
+    let photo_urls: Vec<String> = try_join_all(photos.into_iter().map(|(filename, bytes)| {
+        let folder = folder.clone();
+        async move {
+            upload_image_to_cloudinary(bytes, filename, &folder, &app_state.cloudinary_config).await
+        }
+    }))
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    // Blokada zamówienia, żeby dwa równoległe zgłoszenia nie objęły tej samej pozycji
+    sqlx::query("SELECT id FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let item = sqlx::query_as::<_, OrderItem>(
+        r#"
+        SELECT oi.* FROM order_items oi
+        WHERE oi.id = $1 AND oi.order_id = $2
+          AND NOT EXISTS (
+              SELECT 1 FROM complaints c
+              WHERE c.order_item_id = oi.id AND c.status <> $3
+          )
+        "#,
+    )
+    .bind(order_item_id)
+    .bind(order_id)
+    .bind(ComplaintStatus::Rejected)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Ten produkt ma już zgłoszoną reklamację.".to_string()))?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO complaints (id, order_id, order_item_id, product_id, user_id, description, photos)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(complaint_id)
+    .bind(order_id)
+    .bind(item.id)
+    .bind(item.product_id)
+    .bind(user_id)
+    .bind(description)
+    .bind(&photo_urls)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Złożono reklamację {} do zamówienia {} ({} zdjęć)",
+        complaint_id,
+        order_id,
+        photo_urls.len()
+    );
+    find_complaint(pool, complaint_id).await
+}
+
+/// Zmienia status reklamacji. Uznanie albo odrzucenie zamyka reklamację - później
+/// nie można już zmienić decyzji.
+pub async fn update_complaint_status(
+    pool: &PgPool,
+    complaint_id: Uuid,
+    admin_id: Uuid,
+    status: ComplaintStatus,
+    resolution: Option<&str>,
+) -> Result<Complaint, AppError> {
+    let mut tx = pool.begin().await?;
+    let current: ComplaintStatus =
+        sqlx::query_scalar("SELECT status FROM complaints WHERE id = $1 FOR UPDATE")
+            .bind(complaint_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::NotFound)?;
+    if current.is_resolved() {
+        return Err(AppError::Conflict(format!(
+            "Reklamacja ma już status \"{}\".",
+            current
+        )));
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE complaints
+        SET status = $1,
+            resolution = COALESCE($2, resolution),
+            resolved_by = CASE WHEN $3 THEN $4 END,
+            resolved_at = CASE WHEN $3 THEN NOW() END,
+            updated_at = NOW()
+        WHERE id = $5
+        "#,
+    )
+    .bind(status)
+    .bind(resolution)
+    .bind(status.is_resolved())
+    .bind(admin_id)
+    .bind(complaint_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Admin {} zmienił status reklamacji {} z '{}' na '{}'",
+        admin_id,
+        complaint_id,
+        current,
+        status
+    );
+    find_complaint(pool, complaint_id).await
+}
@@ -0,0 +1,389 @@
+// src/config.rs
+
+// Konfiguracja aplikacji ze zmiennych środowiskowych (`.env` w dev), wczytywana raz przy starcie.
+// Błędy nie przerywają odczytu na pierwszej zmiennej - zbieramy wszystkie brakujące
+// i nieprawidłowe wartości i wypisujemy je razem, żeby deploy dało się poprawić za jednym razem.
+
+use std::env;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use chrono_tz::Tz;
+
+use crate::checkout;
+use crate::client_ip;
+use crate::date_format;
+use crate::load_shed::LoadShedConfig;
+use crate::order_numbers;
+use crate::state::{
+    BackupConfig, CloudinaryConfig, DescriptionAssistantConfig, GoogleOAuthConfig, InpostConfig,
+    InpostReturnLabelConfig, InvoiceConfig, ObjectStorageConfig, OrderNumberConfig,
+    PaymentDetailsConfig, Przelewy24Config, RetentionConfig,
+};
+use crate::vat::{VatConfig, VatScheme};
+
+const MEGABYTE: usize = 1024 * 1024;
+
+/// Lista wszystkich problemów z konfiguracją, po jednym na linię
+#[derive(Debug)]
+pub struct ConfigError(Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.0 {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Czyta zmienne i zapamiętuje problemy zamiast panikować
+#[derive(Default)]
+struct EnvReader {
+    problems: Vec<String>,
+}
+
+impl EnvReader {
+    /// Wartość zmiennej; pusta traktowana jak brak
+    fn optional(&self, name: &str) -> Option<String> {
+        env::var(name).ok().filter(|value| !value.is_empty())
+    }
+
+    fn required(&mut self, name: &str) -> String {
+        self.optional(name).unwrap_or_else(|| {
+            self.problems
+                .push(format!("{}: brak wymaganej zmiennej", name));
+            String::new()
+        })
+    }
+
+    fn or(&self, name: &str, default: &str) -> String {
+        self.optional(name).unwrap_or_else(|| default.to_string())
+    }
+
+    fn parse_optional<T: FromStr>(&mut self, name: &str) -> Option<T> {
+        let value = self.optional(name)?;
+        match value.trim().parse::<T>() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => {
+                self.problems
+                    .push(format!("{}: nieprawidłowa wartość '{}'", name, value));
+                None
+            }
+        }
+    }
+
+    fn parse_or<T: FromStr>(&mut self, name: &str, default: T) -> T {
+        self.parse_optional(name).unwrap_or(default)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.optional(name)
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+    }
+
+    fn seconds_or(&mut self, name: &str, default_secs: u64) -> Duration {
+        Duration::from_secs(self.parse_or(name, default_secs))
+    }
+}
+
+/// Kompresja odpowiedzi (gzip/brotli) - zob. `CompressionLayer` w main.rs
+#[derive(Debug, Clone)]
+pub struct CompressionConfig {
+    pub gzip: bool,
+    pub brotli: bool,
+    /// Mniejszych odpowiedzi nie opłaca się kompresować
+    pub min_size_bytes: u16,
+}
+
+/// Czasy życia wpisów w cache'ach moka
+#[derive(Debug, Clone)]
+pub struct CacheTtlConfig {
+    pub products: Duration,
+    pub static_html: Duration,
+    pub listing_fragments: Duration,
+    pub category_list: Duration,
+}
+
+pub struct AppConfig {
+    /// Port HTTPS, na którym nasłuchuje serwer
+    pub port: u16,
+    pub tls_cert_path: PathBuf,
+    pub tls_key_path: PathBuf,
+    pub database_url: String,
+    pub db_max_connections: u32,
+    /// Globalny limit rozmiaru żądania (upload zdjęć produktów)
+    pub max_body_bytes: usize,
+    /// Limit dla publicznego wyszukiwania po zdjęciu
+    pub photo_search_max_body_bytes: usize,
+    pub cache_ttl: CacheTtlConfig,
+    /// Dozwolone źródła CORS; pusta lista = dowolne źródło
+    pub cors_allowed_origins: Vec<HeaderValue>,
+    /// Próg wartości produktów (w groszach), od którego dostawa jest darmowa
+    pub free_shipping_threshold: i64,
+    /// Strefa czasowa sklepu (IANA), w której pokazujemy daty i liczymy dni
+    pub shop_timezone: Tz,
+    /// Adresy proxy, którym ufamy w nagłówku `X-Forwarded-For`
+    pub trusted_proxies: Vec<IpAddr>,
+
+    pub jwt_secret: String,
+    pub jwt_expiration_hours: i64,
+    pub resend_api_key: String,
+    /// Adres, z którego sklep wysyła e-maile
+    pub admin_email: String,
+    /// Publiczny adres sklepu bez końcowego `/`
+    pub public_base_url: String,
+    pub cloudinary: CloudinaryConfig,
+    pub disposable_email_domains: String,
+    pub disposable_email_list_url: Option<String>,
+    pub description_assistant: Option<DescriptionAssistantConfig>,
+    pub przelewy24: Option<Przelewy24Config>,
+    pub google_oauth: Option<GoogleOAuthConfig>,
+    pub inpost: InpostConfig,
+    pub payment_details: PaymentDetailsConfig,
+    pub invoice: InvoiceConfig,
+    pub backup: Option<BackupConfig>,
+    pub retention: RetentionConfig,
+    pub order_number: OrderNumberConfig,
+    pub vat: VatConfig,
+    pub indexnow_key: Option<String>,
+    pub staging: bool,
+    pub metrics_token: Option<String>,
+    pub load_shed: LoadShedConfig,
+    pub compression: CompressionConfig,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let mut env = EnvReader::default();
+
+        let database_url = env.required("DATABASE_URL");
+        let public_base_url = env
+            .or("PUBLIC_BASE_URL", "https://messvintage.com")
+            .trim_end_matches('/')
+            .to_string();
+
+        let cors_allowed_origins = env
+            .optional("CORS_ALLOWED_ORIGINS")
+            .map(|origins| {
+                origins
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|origin| !origin.is_empty())
+                    .filter_map(|origin| match HeaderValue::from_str(origin) {
+                        Ok(value) => Some(value),
+                        Err(_) => {
+                            env.problems.push(format!(
+                                "CORS_ALLOWED_ORIGINS: nieprawidłowe źródło '{}'",
+                                origin
+                            ));
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let cloudinary = CloudinaryConfig {
+            cloud_name: env.required("CLOUDINARY_CLOUD_NAME"),
+            api_key: env.required("CLOUDINARY_API_KEY"),
+            api_secret: env.required("CLOUDINARY_API_SECRET"),
+            tagging_addon: env.optional("CLOUDINARY_TAGGING_ADDON"),
+        };
+
+        // --- Asystent opisów produktów (opcjonalny) ---
+        let description_assistant =
+            env.optional("DESCRIPTION_LLM_ENDPOINT")
+                .map(|endpoint| DescriptionAssistantConfig {
+                    endpoint,
+                    api_key: env.optional("DESCRIPTION_LLM_API_KEY"),
+                    model: env.or("DESCRIPTION_LLM_MODEL", "gpt-4o-mini"),
+                });
+
+        // --- Przelewy24 (opcjonalne; bez konfiguracji dostępne są tylko BLIK i przelew) ---
+        let przelewy24 = env
+            .parse_optional::<i64>("P24_MERCHANT_ID")
+            .map(|merchant_id| Przelewy24Config {
+                merchant_id,
+                pos_id: env.parse_or("P24_POS_ID", merchant_id),
+                crc: env.required("P24_CRC"),
+                api_key: env.required("P24_API_KEY"),
+                base_url: env.or("P24_BASE_URL", "https://sandbox.przelewy24.pl"),
+                public_base_url: public_base_url.clone(),
+            });
+
+        // --- Logowanie przez Google (opcjonalne) ---
+        let google_oauth = env
+            .optional("GOOGLE_CLIENT_ID")
+            .map(|client_id| GoogleOAuthConfig {
+                client_id,
+                client_secret: env.required("GOOGLE_CLIENT_SECRET"),
+                redirect_url: env.or(
+                    "GOOGLE_REDIRECT_URL",
+                    &format!("{}/api/auth/google/callback", public_base_url),
+                ),
+            });
+
+        // --- InPost ShipX ---
+        let inpost = InpostConfig {
+            api_base_url: env.or("INPOST_API_URL", "https://api-shipx-pl.easypack24.net"),
+            api_token: env.optional("INPOST_API_TOKEN"),
+            organization_id: env.parse_optional("INPOST_ORGANIZATION_ID"),
+            return_label: env.optional("INPOST_RETURN_POINT").map(|target_point| {
+                InpostReturnLabelConfig {
+                    target_point,
+                    price: env.parse_or("INPOST_RETURN_LABEL_PRICE", 999),
+                    receiver_name: env.or("INPOST_RETURN_RECEIVER_NAME", "mess - all that vintage"),
+                    receiver_email: env.required("INPOST_RETURN_RECEIVER_EMAIL"),
+                    receiver_phone: env.required("INPOST_RETURN_RECEIVER_PHONE"),
+                }
+            }),
+        };
+
+        // --- Dane do przelewu tradycyjnego i BLIK ---
+        let payment_details = PaymentDetailsConfig {
+            account_holder: env.or("BANK_ACCOUNT_HOLDER", "mess - all that vintage"),
+            account_number: env.optional("BANK_ACCOUNT_NUMBER").unwrap_or_else(|| {
+                tracing::warn!(
+                    "Brak BANK_ACCOUNT_NUMBER - klienci zobaczą przykładowy numer konta."
+                );
+                "PL XX XXXX XXXX XXXX XXXX XXXX XXXX".to_string()
+            }),
+            blik_phone: env.or("BLIK_PHONE", "603 117 793"),
+        };
+
+        let invoice = InvoiceConfig {
+            seller_name: env.or("INVOICE_SELLER_NAME", &payment_details.account_holder),
+            seller_address: env.optional("INVOICE_SELLER_ADDRESS").unwrap_or_else(|| {
+                tracing::warn!(
+                    "Brak INVOICE_SELLER_ADDRESS - faktury nie będą miały adresu sprzedawcy."
+                );
+                String::new()
+            }),
+            seller_tax_id: env.optional("INVOICE_SELLER_NIP"),
+            pdf_command: env.or("WKHTMLTOPDF_PATH", "wkhtmltopdf"),
+        };
+
+        // --- Kopie zapasowe bazy (opcjonalne; wymagają pg_dump i openssl na serwerze) ---
+        let backup = env.optional("BACKUP_S3_BUCKET").map(|bucket| BackupConfig {
+            database_url: database_url.clone(),
+            storage: ObjectStorageConfig {
+                endpoint: env.required("BACKUP_S3_ENDPOINT"),
+                region: env.or("BACKUP_S3_REGION", "us-east-1"),
+                bucket,
+                access_key_id: env.required("BACKUP_S3_ACCESS_KEY_ID"),
+                secret_access_key: env.required("BACKUP_S3_SECRET_ACCESS_KEY"),
+            },
+            encryption_passphrase: env.required("BACKUP_ENCRYPTION_PASSPHRASE"),
+            prefix: env.or("BACKUP_S3_PREFIX", "backups/"),
+            retention_days: env.parse_or("BACKUP_RETENTION_DAYS", 14),
+        });
+
+        // --- Okresy przechowywania danych (polityka prywatności) ---
+        let retention = RetentionConfig {
+            guest_order_pii_days: env.parse_or("GUEST_ORDER_RETENTION_DAYS", 1825),
+            guest_cart_days: env.parse_or("GUEST_CART_RETENTION_DAYS", 30),
+        };
+
+        // --- Numery zamówień ---
+        let order_number_format = env.or(
+            "ORDER_NUMBER_FORMAT",
+            order_numbers::DEFAULT_ORDER_NUMBER_FORMAT,
+        );
+        let order_number_digits = env.parse_or(
+            "ORDER_NUMBER_DIGITS",
+            order_numbers::DEFAULT_ORDER_NUMBER_DIGITS,
+        );
+        let order_number = OrderNumberConfig::new(order_number_format, order_number_digits)
+            .unwrap_or_else(|problem| {
+                env.problems.push(problem);
+                // Nieużywane - przy jakimkolwiek problemie konfiguracja nie jest zwracana
+                OrderNumberConfig {
+                    format: order_numbers::DEFAULT_ORDER_NUMBER_FORMAT.to_string(),
+                    digits: order_numbers::DEFAULT_ORDER_NUMBER_DIGITS,
+                }
+            });
+
+        let trusted_proxies = client_ip::parse_trusted_proxies(&env.or("TRUSTED_PROXIES", ""))
+            .unwrap_or_else(|invalid| {
+                env.problems.push(format!(
+                    "TRUSTED_PROXIES: nieprawidłowy adres IP '{}'",
+                    invalid
+                ));
+                Vec::new()
+            });
+
+        let config = AppConfig {
+            port: env.parse_or("PORT", 3000),
+            tls_cert_path: PathBuf::from(env.or("TLS_CERT_PATH", "localhost+2.pem")),
+            tls_key_path: PathBuf::from(env.or("TLS_KEY_PATH", "localhost+2-key.pem")),
+            database_url,
+            db_max_connections: env.parse_or("DB_MAX_CONNECTIONS", 10),
+            max_body_bytes: env.parse_or("MAX_BODY_SIZE_MB", 100) * MEGABYTE,
+            photo_search_max_body_bytes: env.parse_or("PHOTO_SEARCH_MAX_BODY_SIZE_MB", 12)
+                * MEGABYTE,
+            cache_ttl: CacheTtlConfig {
+                products: env.seconds_or("PRODUCT_CACHE_TTL_SECS", 3600),
+                static_html: env.seconds_or("STATIC_HTML_CACHE_TTL_SECS", 3600 * 24),
+                listing_fragments: env.seconds_or("LISTING_CACHE_TTL_SECS", 600),
+                category_list: env.seconds_or("CATEGORY_CACHE_TTL_SECS", 3600),
+            },
+            cors_allowed_origins,
+            free_shipping_threshold: env.parse_or(
+                "FREE_SHIPPING_THRESHOLD",
+                checkout::DEFAULT_FREE_SHIPPING_THRESHOLD,
+            ),
+            shop_timezone: env.parse_or("SHOP_TIMEZONE", date_format::DEFAULT_SHOP_TIMEZONE),
+            trusted_proxies,
+            jwt_secret: env.required("JWT_SECRET"),
+            jwt_expiration_hours: env.parse_or("JWT_EXPIRATION_HOURS", 1),
+            resend_api_key: env.required("RESEND_API_KEY"),
+            admin_email: env.required("ADMIN_EMAIL"),
+            public_base_url,
+            cloudinary,
+            disposable_email_domains: env.or("DISPOSABLE_EMAIL_DOMAINS", ""),
+            disposable_email_list_url: env.optional("DISPOSABLE_EMAIL_LIST_URL"),
+            description_assistant,
+            przelewy24,
+            google_oauth,
+            inpost,
+            payment_details,
+            invoice,
+            backup,
+            retention,
+            order_number,
+            // Procedura marży (towary używane) to domyślny tryb sklepu
+            vat: VatConfig {
+                scheme: env.parse_or("VAT_SCHEME", VatScheme::Margin),
+                rate_bp: env.parse_or::<i32>("VAT_RATE_PERCENT", 23) * 100,
+            },
+            // --- Zgłaszanie zmian produktów do wyszukiwarek (opcjonalne) ---
+            indexnow_key: env.optional("INDEXNOW_KEY"),
+            staging: env.flag("STAGING"),
+            metrics_token: env.optional("METRICS_TOKEN"),
+            // --- Ochrona przed przeciążeniem (limity jednoczesnych żądań) ---
+            load_shed: LoadShedConfig {
+                public_limit: env.parse_or("MAX_CONCURRENT_PUBLIC_REQUESTS", 256),
+                checkout_limit: env.parse_or("MAX_CONCURRENT_CHECKOUT_REQUESTS", 64),
+                admin_limit: env.parse_or("MAX_CONCURRENT_ADMIN_REQUESTS", 32),
+                queue_timeout: Duration::from_millis(env.parse_or("LOAD_SHED_QUEUE_MS", 250)),
+            },
+            // --- Kompresja odpowiedzi (np. za proxy, które kompresuje samo: oba na false) ---
+            compression: CompressionConfig {
+                gzip: env.parse_or("COMPRESSION_GZIP", true),
+                brotli: env.parse_or("COMPRESSION_BROTLI", true),
+                min_size_bytes: env.parse_or("COMPRESSION_MIN_SIZE_BYTES", 1024),
+            },
+        };
+
+        if env.problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(env.problems))
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// src/coupon_campaigns.rs
+
+// Kampanie kodów rabatowych: N unikalnych kodów jednorazowych (np. na kartki z podziękowaniem
+// dołączane do paczek). Każdy kod to zwykły wiersz `coupons` z `max_uses = 1`, więc kasa
+// obsługuje je tak samo jak pozostałe kody.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::date_format::to_shop_time;
+use crate::errors::AppError;
+use crate::models::{Coupon, CouponCampaignSummary, CouponDiscountType};
+
+/// Znaki części losowej - bez łatwych do pomylenia na wydruku (0/O, 1/I/L)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_RANDOM_LEN: usize = 6;
+/// Kolizje z istniejącymi kodami są rzadkie - kilka prób wystarcza z zapasem
+const MAX_GENERATION_ROUNDS: usize = 5;
+
+/// Parametry rabatu wspólne dla wszystkich kodów kampanii
+pub struct NewCampaign<'a> {
+    pub name: &'a str,
+    pub code_prefix: &'a str,
+    pub discount_type: CouponDiscountType,
+    pub value: i64,
+    pub min_order_value: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+fn random_codes(prefix: &str, count: usize) -> HashSet<String> {
+    let mut rng = rand::rng();
+    let mut codes = HashSet::with_capacity(count);
+    while codes.len() < count {
+        let random_part: String = (0..CODE_RANDOM_LEN)
+            .map(|_| CODE_ALPHABET[rng.random_range(0..CODE_ALPHABET.len())] as char)
+            .collect();
+        codes.insert(format!("{}-{}", prefix, random_part));
+    }
+    codes
+}
+
+/// Tworzy kampanię i `count` jednorazowych kodów. Zwraca ID kampanii.
+pub async fn create_campaign(
+    pool: &PgPool,
+    campaign: &NewCampaign<'_>,
+    count: usize,
+    admin_id: Uuid,
+) -> Result<Uuid, AppError> {
+    let mut tx = pool.begin().await?;
+    let campaign_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO coupon_campaigns (name, code_prefix, discount_type, value, min_order_value, expires_at, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        "#,
+    )
+    .bind(campaign.name)
+    .bind(campaign.code_prefix)
+    .bind(campaign.discount_type)
+    .bind(campaign.value)
+    .bind(campaign.min_order_value)
+    .bind(campaign.expires_at)
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut created = 0;
+    for _ in 0..MAX_GENERATION_ROUNDS {
+        if created == count {
+            break;
+        }
+        let codes: Vec<String> = random_codes(campaign.code_prefix, count - created)
+            .into_iter()
+            .collect();
+        // Kody zajęte przez inne kampanie lub ręcznie dodane są pomijane i losowane ponownie
+        let inserted = sqlx::query(
+            r#"
+            INSERT INTO coupons (code, discount_type, value, min_order_value, expires_at,
+                                 single_use_per_user, max_uses, campaign_id)
+            SELECT code, $2, $3, $4, $5, TRUE, 1, $6 FROM UNNEST($1::text[]) AS t(code)
+            ON CONFLICT (code) DO NOTHING
+            "#,
+        )
+        .bind(&codes)
+        .bind(campaign.discount_type)
+        .bind(campaign.value)
+        .bind(campaign.min_order_value)
+        .bind(campaign.expires_at)
+        .bind(campaign_id)
+        .execute(&mut *tx)
+        .await?;
+        created += inserted.rows_affected() as usize;
+    }
+    if created < count {
+        return Err(AppError::InternalServerError(format!(
+            "Nie udało się wygenerować unikalnych kodów kampanii ({} z {}).",
+            created, count
+        )));
+    }
+    tx.commit().await?;
+
+    tracing::info!(
+        "Admin {} utworzył kampanię '{}' z {} kodami rabatowymi",
+        admin_id,
+        campaign.name,
+        count
+    );
+    Ok(campaign_id)
+}
+
+/// Wydaje jeden nowy kod z istniejącej kampanii, z rabatem kampanii i własną datą ważności
+/// (np. osobisty kod na kartce z podziękowaniem).
+pub async fn issue_campaign_code(
+    conn: &mut PgConnection,
+    campaign_id: Uuid,
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<Coupon, AppError> {
+    let code_prefix: String =
+        sqlx::query_scalar("SELECT code_prefix FROM coupon_campaigns WHERE id = $1")
+            .bind(campaign_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+    for _ in 0..MAX_GENERATION_ROUNDS {
+        let Some(code) = random_codes(&code_prefix, 1).into_iter().next() else {
+            continue;
+        };
+        let coupon = sqlx::query_as::<_, Coupon>(
+            r#"
+            INSERT INTO coupons (code, discount_type, value, min_order_value, expires_at,
+                                 single_use_per_user, max_uses, campaign_id)
+            SELECT $1, discount_type, value, min_order_value, $2, TRUE, 1, id
+            FROM coupon_campaigns WHERE id = $3
+            ON CONFLICT (code) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(&code)
+        .bind(expires_at)
+        .bind(campaign_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some(coupon) = coupon {
+            return Ok(coupon);
+        }
+    }
+    Err(AppError::InternalServerError(
+        "Nie udało się wygenerować unikalnego kodu kampanii.".to_string(),
+    ))
+}
+
+/// Kampanie od najnowszej z liczbą wykorzystanych kodów i wartością zamówień z tymi kodami
+pub async fn list_campaigns(pool: &PgPool) -> Result<Vec<CouponCampaignSummary>, AppError> {
+    Ok(sqlx::query_as::<_, CouponCampaignSummary>(
+        r#"
+        SELECT cc.id, cc.name, cc.code_prefix, cc.discount_type, cc.value, cc.min_order_value,
+               cc.expires_at, cc.created_at,
+               (SELECT COUNT(*) FROM coupons c WHERE c.campaign_id = cc.id) AS codes_count,
+               (SELECT COUNT(*) FROM coupons c WHERE c.campaign_id = cc.id AND c.usage_count > 0) AS used_count,
+               COALESCE(o.orders_total, 0) AS orders_total,
+               COALESCE(o.discount_total, 0) AS discount_total
+        FROM coupon_campaigns cc
+        LEFT JOIN (
+            SELECT c.campaign_id, SUM(o.total_price)::BIGINT AS orders_total,
+                   SUM(o.discount_amount)::BIGINT AS discount_total
+            FROM orders o
+            JOIN coupons c ON c.id = o.coupon_id
+            WHERE c.campaign_id IS NOT NULL AND o.status <> 'cancelled'
+            GROUP BY c.campaign_id
+        ) o ON o.campaign_id = cc.id
+        ORDER BY cc.created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Kody kampanii w CSV: kod, czy użyty, data ważności - do druku lub korespondencji seryjnej
+pub async fn campaign_codes_csv(pool: &PgPool, campaign_id: Uuid) -> Result<String, AppError> {
+    let codes: Vec<(String, i32, Option<DateTime<Utc>>)> = sqlx::query_as(
+        "SELECT code, usage_count, expires_at FROM coupons WHERE campaign_id = $1 ORDER BY code",
+    )
+    .bind(campaign_id)
+    .fetch_all(pool)
+    .await?;
+    if codes.is_empty() {
+        return Err(AppError::NotFound);
+    }
+
+    let mut csv_output = String::from("kod,wykorzystany,wazny_do\n");
+    for (code, usage_count, expires_at) in codes {
+        csv_output.push_str(&format!(
+            "{},{},{}\n",
+            code,
+            if usage_count > 0 { "tak" } else { "nie" },
+            expires_at
+                .map(|dt| to_shop_time(&dt).format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        ));
+    }
+    Ok(csv_output)
+}
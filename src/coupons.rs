@@ -0,0 +1,151 @@
+// src/coupons.rs
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::customer_segments::{CustomerSegment, customer_in_segment};
+use crate::errors::AppError;
+use crate::models::Coupon;
+
+/// Powód, dla którego kodu rabatowego nie można użyć w danym koszyku
+#[derive(Debug, Clone, PartialEq)]
+pub enum CouponRejection {
+    NotFound,
+    Inactive,
+    Expired,
+    BelowMinimum(i64),
+    AlreadyUsed,
+    /// Wyczerpany łączny limit użyć (np. jednorazowy kod z kampanii)
+    UsedUp,
+    /// Kod ograniczony do segmentu klientów, a zamówienie składa gość
+    LoginRequired,
+    NotInSegment,
+}
+
+impl CouponRejection {
+    /// Komunikat dla klienta wyświetlany pod polem kodu w podsumowaniu zamówienia.
+    pub fn message(&self) -> String {
+        match self {
+            CouponRejection::NotFound => "Nie znaleziono takiego kodu rabatowego.".to_string(),
+            CouponRejection::Inactive => "Ten kod rabatowy nie jest już aktywny.".to_string(),
+            CouponRejection::Expired => "Ten kod rabatowy wygasł.".to_string(),
+            CouponRejection::BelowMinimum(min) => format!(
+                "Kod działa od wartości produktów {},{:02} zł.",
+                min / 100,
+                min % 100
+            ),
+            CouponRejection::AlreadyUsed => {
+                "Ten kod rabatowy został już przez Ciebie wykorzystany.".to_string()
+            }
+            CouponRejection::UsedUp => "Ten kod rabatowy został już wykorzystany.".to_string(),
+            CouponRejection::LoginRequired => {
+                "Zaloguj się, aby użyć tego kodu rabatowego.".to_string()
+            }
+            CouponRejection::NotInSegment => {
+                "Ten kod rabatowy jest dostępny tylko dla wybranych klientów.".to_string()
+            }
+        }
+    }
+}
+
+/// Wyszukuje kod rabatowy i sprawdza, czy można go użyć dla koszyka o wartości `items_total`.
+///
+/// Wiersz kodu jest blokowany (`FOR UPDATE`), więc wywołane w transakcji składania zamówienia
+/// sprawdzenie jednorazowości nie przepuści dwóch równoległych zamówień tego samego klienta.
+/// Zewnętrzny `Result` niesie błędy bazy, wewnętrzny - powód odrzucenia kodu.
+pub async fn find_applicable_coupon(
+    conn: &mut PgConnection,
+    raw_code: &str,
+    items_total: i64,
+    user_id: Option<Uuid>,
+    guest_email: Option<&str>,
+) -> Result<Result<Coupon, CouponRejection>, AppError> {
+    let code = Coupon::normalize_code(raw_code);
+    let Some(coupon) =
+        sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE code = $1 FOR UPDATE")
+            .bind(&code)
+            .fetch_optional(&mut *conn)
+            .await?
+    else {
+        return Ok(Err(CouponRejection::NotFound));
+    };
+
+    if !coupon.is_active {
+        return Ok(Err(CouponRejection::Inactive));
+    }
+    if coupon.is_expired() {
+        return Ok(Err(CouponRejection::Expired));
+    }
+    if coupon
+        .max_uses
+        .is_some_and(|max_uses| coupon.usage_count >= max_uses)
+    {
+        return Ok(Err(CouponRejection::UsedUp));
+    }
+    if items_total < coupon.min_order_value {
+        return Ok(Err(CouponRejection::BelowMinimum(coupon.min_order_value)));
+    }
+
+    let segment = CustomerSegment::for_coupon(&coupon);
+    if !segment.is_empty() {
+        let Some(user_id) = user_id else {
+            return Ok(Err(CouponRejection::LoginRequired));
+        };
+        if !customer_in_segment(&mut *conn, user_id, &segment).await? {
+            return Ok(Err(CouponRejection::NotInSegment));
+        }
+    }
+
+    if coupon.single_use_per_user {
+        let guest_email = guest_email.map(|e| e.trim().to_lowercase());
+        let already_used: bool = sqlx::query_scalar(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM coupon_redemptions
+                    WHERE coupon_id = $1
+                      AND ((user_id IS NOT NULL AND user_id = $2)
+                           OR (guest_email IS NOT NULL AND guest_email = $3))
+                )
+            "#,
+        )
+        .bind(coupon.id)
+        .bind(user_id)
+        .bind(&guest_email)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if already_used {
+            return Ok(Err(CouponRejection::AlreadyUsed));
+        }
+    }
+
+    Ok(Ok(coupon))
+}
+
+/// Zapisuje użycie kodu przy zamówieniu i zwiększa licznik użyć.
+pub async fn record_coupon_redemption(
+    conn: &mut PgConnection,
+    coupon_id: Uuid,
+    order_id: Uuid,
+    user_id: Option<Uuid>,
+    guest_email: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO coupon_redemptions (coupon_id, order_id, user_id, guest_email) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(coupon_id)
+    .bind(order_id)
+    .bind(user_id)
+    .bind(guest_email.map(|e| e.trim().to_lowercase()))
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "UPDATE coupons SET usage_count = usage_count + 1, updated_at = NOW() WHERE id = $1",
+    )
+    .bind(coupon_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+// src/customer_profiles.rs
+
+// Karta klienta w panelu: podsumowanie zakupów (wartość klienta), dane konta i wewnętrzne
+// notatki personelu. Lista klientów i tagi są w `customer_segments`, blokowanie kont
+// w `user_management`.
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::customer_segments::COUNTED_ORDER_STATUSES;
+use crate::errors::AppError;
+
+/// Maksymalna długość notatki o kliencie
+pub const MAX_CUSTOMER_NOTE_LEN: usize = 2000;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CustomerProfile {
+    pub id: Uuid,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    /// Opłacone, nieanulowane zamówienia (jak w segmentach)
+    pub orders_count: i64,
+    /// Wartość klienta w groszach - suma opłaconych, nieanulowanych zamówień
+    pub total_spent: i64,
+    /// Wszystkie zamówienia, także nieopłacone i anulowane
+    pub all_orders_count: i64,
+    pub first_order_at: Option<DateTime<Utc>>,
+    pub last_order_at: Option<DateTime<Utc>>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+impl CustomerProfile {
+    /// Średnia wartość zamówienia w groszach
+    pub fn average_order_value(&self) -> Option<i64> {
+        (self.orders_count > 0).then(|| self.total_spent / self.orders_count)
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CustomerNote {
+    pub id: Uuid,
+    pub content: String,
+    pub created_by_email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Karta klienta; `None` także dla kont personelu - te obsługuje ekran użytkowników
+pub async fn fetch_customer_profile(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<CustomerProfile>, AppError> {
+    let profile = sqlx::query_as::<_, CustomerProfile>(
+        r#"
+            SELECT u.id, u.email, u.created_at, u.email_verified_at, u.disabled_at,
+                   COUNT(o.id) FILTER (WHERE o.status = ANY($2)) AS orders_count,
+                   COALESCE(SUM(o.total_price) FILTER (WHERE o.status = ANY($2)), 0)::BIGINT AS total_spent,
+                   COUNT(o.id) AS all_orders_count,
+                   MIN(o.order_date) AS first_order_at,
+                   MAX(o.order_date) AS last_order_at,
+                   (SELECT MAX(a.attempted_at) FROM login_attempts a
+                    WHERE a.user_id = u.id AND a.succeeded) AS last_login_at
+            FROM users u
+            LEFT JOIN orders o ON o.user_id = u.id
+            WHERE u.id = $1 AND u.role = 'customer'
+            GROUP BY u.id
+        "#,
+    )
+    .bind(user_id)
+    .bind(COUNTED_ORDER_STATUSES.to_vec())
+    .fetch_optional(pool)
+    .await?;
+    Ok(profile)
+}
+
+pub async fn list_customer_notes(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<CustomerNote>, AppError> {
+    let notes = sqlx::query_as::<_, CustomerNote>(
+        r#"
+            SELECT n.id, n.content, a.email AS created_by_email, n.created_at
+            FROM customer_notes n
+            LEFT JOIN users a ON a.id = n.created_by
+            WHERE n.user_id = $1
+            ORDER BY n.created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(notes)
+}
+
+pub async fn add_customer_note(
+    pool: &PgPool,
+    user_id: Uuid,
+    content: &str,
+    admin_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO customer_notes (user_id, content, created_by) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(content)
+        .bind(admin_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_customer_note(
+    pool: &PgPool,
+    user_id: Uuid,
+    note_id: Uuid,
+) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM customer_notes WHERE id = $1 AND user_id = $2")
+        .bind(note_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
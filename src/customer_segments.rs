@@ -0,0 +1,271 @@
+// src/customer_segments.rs
+
+// Tagi klientów (VIP, hurt, problematyczny) i proste segmenty: tag, wydana kwota, liczba
+// zamówień. Segment filtruje listę klientów w panelu, eksport adresów do newslettera
+// i ogranicza kody rabatowe do wybranej grupy.
+
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Coupon, CustomerSegmentParams, CustomerSummary, CustomerTag, OrderStatus};
+
+/// Zamówienia liczone do segmentu - opłacone i nieanulowane
+pub const COUNTED_ORDER_STATUSES: [OrderStatus; 3] = [
+    OrderStatus::Processing,
+    OrderStatus::Shipped,
+    OrderStatus::Delivered,
+];
+
+/// Kryteria segmentu; wszystkie ustawione muszą być spełnione
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomerSegment {
+    pub tag: Option<CustomerTag>,
+    /// W groszach
+    pub min_spent: Option<i64>,
+    pub min_orders: Option<i32>,
+}
+
+impl CustomerSegment {
+    /// Segment z formularza listy klientów (kwota w pełnych złotych). Nieprawidłowe
+    /// wartości pomijamy - filtr ma się nie wywracać od literówki.
+    pub fn from_params(params: &CustomerSegmentParams) -> Self {
+        let field = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        CustomerSegment {
+            tag: field(&params.tag).and_then(|tag| tag.parse().ok()),
+            min_spent: field(&params.min_spent)
+                .and_then(|zl| zl.parse::<i64>().ok())
+                .filter(|zl| *zl > 0)
+                .map(|zl| zl * 100),
+            min_orders: field(&params.min_orders)
+                .and_then(|count| count.parse::<i32>().ok())
+                .filter(|count| *count > 0),
+        }
+    }
+
+    pub fn for_coupon(coupon: &Coupon) -> Self {
+        CustomerSegment {
+            tag: coupon.segment_tag,
+            min_spent: coupon.segment_min_spent,
+            min_orders: coupon.segment_min_orders,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none() && self.min_spent.is_none() && self.min_orders.is_none()
+    }
+
+    /// Opis do panelu, np. "VIP, wydane min. 1000 zł, zamówienia: min. 3"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(tag) = self.tag {
+            parts.push(tag.to_string());
+        }
+        if let Some(min_spent) = self.min_spent {
+            parts.push(format!("wydane min. {} zł", min_spent / 100));
+        }
+        if let Some(min_orders) = self.min_orders {
+            parts.push(format!("zamówienia: min. {}", min_orders));
+        }
+        parts.join(", ")
+    }
+
+    /// Parametry zapytania listy klientów odpowiadające segmentowi (do linku eksportu)
+    pub fn query_string(&self) -> String {
+        format!(
+            "tag={}&min_spent={}&min_orders={}",
+            self.tag.map_or("", |tag| tag.to_form_value()),
+            self.min_spent
+                .map(|gr| (gr / 100).to_string())
+                .unwrap_or_default(),
+            self.min_orders.map(|c| c.to_string()).unwrap_or_default()
+        )
+    }
+}
+
+/// `FROM ... WHERE ...` klientów z ich statystykami, zawężone do segmentu.
+/// Kolumny: `u` (users) oraz `s.orders_count`, `s.total_spent`, `s.last_order_at`.
+fn push_segment_from(builder: &mut QueryBuilder<'_, Postgres>, segment: &CustomerSegment) {
+    builder
+        .push(
+            r#"
+            FROM users u
+            LEFT JOIN (
+                SELECT user_id, COUNT(*) AS orders_count, SUM(total_price)::BIGINT AS total_spent,
+                       MAX(order_date) AS last_order_at
+                FROM orders
+                WHERE user_id IS NOT NULL AND status = ANY("#,
+        )
+        .push_bind(COUNTED_ORDER_STATUSES.to_vec())
+        .push(
+            r#")
+                GROUP BY user_id
+            ) s ON s.user_id = u.id
+            WHERE u.role = 'customer'"#,
+        );
+    if let Some(tag) = segment.tag {
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM customer_tags t WHERE t.user_id = u.id AND t.tag = ")
+            .push_bind(tag)
+            .push(")");
+    }
+    if let Some(min_spent) = segment.min_spent {
+        builder
+            .push(" AND COALESCE(s.total_spent, 0) >= ")
+            .push_bind(min_spent);
+    }
+    if let Some(min_orders) = segment.min_orders {
+        builder
+            .push(" AND COALESCE(s.orders_count, 0) >= ")
+            .push_bind(i64::from(min_orders));
+    }
+}
+
+/// Fraza z pola wyszukiwania listy klientów; pusta = bez wyszukiwania
+pub fn customer_search(params: &CustomerSegmentParams) -> Option<String> {
+    params
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(str::to_string)
+}
+
+/// Wyszukiwanie po fragmencie e-maila albo numeru telefonu (z danych wysyłki konta
+/// lub z zamówień). W telefonach porównujemy same cyfry, więc "600 100 200" znajdzie "+48600100200".
+fn push_customer_search(builder: &mut QueryBuilder<'_, Postgres>, search: &str) {
+    builder
+        .push(" AND (u.email ILIKE ")
+        .push_bind(format!("%{}%", search));
+    let digits: String = search.chars().filter(char::is_ascii_digit).collect();
+    if digits.len() >= 3 {
+        let phone_pattern = format!("%{}%", digits);
+        builder
+            .push(
+                " OR EXISTS (SELECT 1 FROM user_shipping_details d WHERE d.user_id = u.id \
+                 AND regexp_replace(d.shipping_phone, '\\D', '', 'g') LIKE ",
+            )
+            .push_bind(phone_pattern.clone())
+            .push(
+                ") OR EXISTS (SELECT 1 FROM orders o WHERE o.user_id = u.id \
+                 AND regexp_replace(o.shipping_phone, '\\D', '', 'g') LIKE ",
+            )
+            .push_bind(phone_pattern)
+            .push(")");
+    }
+    builder.push(")");
+}
+
+/// Klienci w segmencie (opcjonalnie zawężeni wyszukiwaniem), od najwięcej wydających
+pub async fn list_customers(
+    pool: &PgPool,
+    segment: &CustomerSegment,
+    search: Option<&str>,
+) -> Result<Vec<CustomerSummary>, AppError> {
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT u.id, u.email,
+               COALESCE(s.orders_count, 0) AS orders_count,
+               COALESCE(s.total_spent, 0) AS total_spent,
+               s.last_order_at,
+               COALESCE(
+                   (SELECT array_agg(t.tag ORDER BY t.tag) FROM customer_tags t WHERE t.user_id = u.id),
+                   '{}'
+               ) AS tags
+        "#,
+    );
+    push_segment_from(&mut builder, segment);
+    if let Some(search) = search {
+        push_customer_search(&mut builder, search);
+    }
+    builder.push(" ORDER BY total_spent DESC, u.created_at DESC");
+
+    Ok(builder
+        .build_query_as::<CustomerSummary>()
+        .fetch_all(pool)
+        .await?)
+}
+
+/// Czy klient należy do segmentu - sprawdzane przy kodach rabatowych ograniczonych do segmentu
+pub async fn customer_in_segment(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    segment: &CustomerSegment,
+) -> Result<bool, AppError> {
+    let mut builder = QueryBuilder::new("SELECT EXISTS (SELECT 1 ");
+    push_segment_from(&mut builder, segment);
+    builder.push(" AND u.id = ").push_bind(user_id).push(")");
+
+    Ok(builder
+        .build_query_scalar::<bool>()
+        .fetch_one(&mut *conn)
+        .await?)
+}
+
+/// Nadaje tag; ponowne nadanie tego samego tagu nic nie zmienia
+pub async fn add_customer_tag(
+    pool: &PgPool,
+    user_id: Uuid,
+    tag: CustomerTag,
+    admin_id: Uuid,
+) -> Result<(), AppError> {
+    let user_exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    if !user_exists {
+        return Err(AppError::NotFound);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO customer_tags (user_id, tag, created_by)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, tag) DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(tag)
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_customer_tag(
+    pool: &PgPool,
+    user_id: Uuid,
+    tag: CustomerTag,
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM customer_tags WHERE user_id = $1 AND tag = $2")
+        .bind(user_id)
+        .bind(tag)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Tagi jednego klienta (karta klienta w panelu)
+pub async fn customer_tags(pool: &PgPool, user_id: Uuid) -> Result<Vec<CustomerTag>, AppError> {
+    let tags = sqlx::query_scalar("SELECT tag FROM customer_tags WHERE user_id = $1 ORDER BY tag")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(tags)
+}
+
+/// Adresy e-mail segmentu w CSV (jedna kolumna), do zaimportowania w narzędziu newslettera
+pub fn emails_csv(customers: &[CustomerSummary]) -> String {
+    let mut csv_output = String::from("email\n");
+    for customer in customers {
+        csv_output.push_str(&customer.email);
+        csv_output.push('\n');
+    }
+    csv_output
+}
@@ -0,0 +1,116 @@
+// src/date_format.rs
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::sync::OnceLock;
+
+/// Strefa czasowa sklepu, ustawiana raz przy starcie (`set_shop_timezone` w main.rs)
+static SHOP_TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+pub const DEFAULT_SHOP_TIMEZONE: Tz = chrono_tz::Europe::Warsaw;
+
+pub fn set_shop_timezone(tz: Tz) {
+    if SHOP_TIMEZONE.set(tz).is_err() {
+        tracing::warn!("Strefa czasowa sklepu była już ustawiona - pomijam {}", tz);
+    }
+}
+
+fn shop_timezone() -> Tz {
+    SHOP_TIMEZONE
+        .get()
+        .copied()
+        .unwrap_or(DEFAULT_SHOP_TIMEZONE)
+}
+
+/// Nazwy miesięcy w dopełniaczu ("5 sierpnia"), tak jak zapisuje się daty po polsku.
+const MONTHS_GENITIVE: [&str; 12] = [
+    "stycznia",
+    "lutego",
+    "marca",
+    "kwietnia",
+    "maja",
+    "czerwca",
+    "lipca",
+    "sierpnia",
+    "września",
+    "października",
+    "listopada",
+    "grudnia",
+];
+
+/// Nazwa strefy sklepu (IANA) - do grupowania po dniach w SQL (`AT TIME ZONE`).
+pub fn shop_timezone_name() -> &'static str {
+    shop_timezone().name()
+}
+
+pub fn to_shop_time(dt: &DateTime<Utc>) -> DateTime<Tz> {
+    dt.with_timezone(&shop_timezone())
+}
+
+/// "05-08-2025 14:30" - czas lokalny sklepu.
+pub fn format_datetime(dt: &DateTime<Utc>) -> String {
+    to_shop_time(dt).format("%d-%m-%Y %H:%M").to_string()
+}
+
+/// "2025-08-05 14:30" - format dla tabel w panelu admina (sortuje się alfabetycznie).
+pub fn format_datetime_admin(dt: &DateTime<Utc>) -> String {
+    to_shop_time(dt).format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// "05-08-2025"
+pub fn format_date(dt: &DateTime<Utc>) -> String {
+    to_shop_time(dt).format("%d-%m-%Y").to_string()
+}
+
+/// "05-08-2025" dla dnia, który jest już w czasie sklepu (np. zgrupowanego w SQL przez `AT TIME ZONE`).
+pub fn format_local_date(date: &NaiveDate) -> String {
+    date.format("%d-%m-%Y").to_string()
+}
+
+/// "05-08" - krótka etykieta dnia, np. na osi wykresu.
+pub fn format_local_day_month(date: &NaiveDate) -> String {
+    date.format("%d-%m").to_string()
+}
+
+/// "5 sierpnia 2025"
+pub fn format_date_long(dt: &DateTime<Utc>) -> String {
+    let local = to_shop_time(dt);
+    format!(
+        "{} {} {}",
+        local.day(),
+        MONTHS_GENITIVE[local.month0() as usize],
+        local.year()
+    )
+}
+
+/// "5 sierpnia 2025, 14:30"
+pub fn format_datetime_long(dt: &DateTime<Utc>) -> String {
+    format!(
+        "{}, {}",
+        format_date_long(dt),
+        to_shop_time(dt).format("%H:%M")
+    )
+}
+
+/// Interpretuje datę/godzinę wpisaną przez użytkownika (np. filtr "data od") jako czas lokalny sklepu.
+pub fn shop_local_to_utc(naive: NaiveDateTime) -> DateTime<Utc> {
+    shop_timezone()
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc))
+        .unwrap_or_else(|| DateTime::from_naive_utc_and_offset(naive, Utc))
+}
+
+/// "2025-08-05T14:30" - wartość pola `datetime-local` w czasie lokalnym sklepu.
+pub fn format_datetime_input(dt: &DateTime<Utc>) -> String {
+    to_shop_time(dt).format("%Y-%m-%dT%H:%M").to_string()
+}
+
+/// Odczytuje pole `datetime-local` (z sekundami albo bez) jako czas lokalny sklepu.
+pub fn parse_datetime_input(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .map(shop_local_to_utc)
+}
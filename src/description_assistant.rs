@@ -0,0 +1,146 @@
+// src/description_assistant.rs
+
+use axum::body::Bytes;
+use futures::StreamExt;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+use crate::errors::AppError;
+use crate::models::ProductDescriptionDraftPayload;
+use crate::state::DescriptionAssistantConfig;
+
+const SYSTEM_PROMPT: &str = "Jesteś copywriterem sklepu z odzieżą vintage i second hand \"mess - all that vintage\". \
+Piszesz krótkie (3-5 zdań), rzeczowe opisy produktów po polsku, bez przesadnych superlatywów i bez emoji. \
+Nie wymyślaj faktów (marki, składu, wymiarów), których nie podano. \
+Jeśli podano wymiary, zakończ opis listą wymiarów.";
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    choices: Vec<ChatCompletionChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunkChoice {
+    delta: ChatCompletionDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionDelta {
+    content: Option<String>,
+}
+
+fn build_user_prompt(payload: &ProductDescriptionDraftPayload) -> String {
+    let mut prompt = format!(
+        "Napisz opis produktu.\nNazwa: {}\nKategoria: {}\nDla: {}\nStan: {}",
+        payload.name.trim(),
+        payload.category.trim(),
+        payload.gender.trim(),
+        payload.condition.trim()
+    );
+    if let Some(brand) = payload
+        .brand
+        .as_deref()
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+    {
+        prompt.push_str(&format!("\nMarka: {}", brand));
+    }
+    if let Some(measurements) = payload
+        .measurements
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+    {
+        prompt.push_str(&format!("\nWymiary: {}", measurements));
+    }
+    prompt
+}
+
+/// Wysyła dane produktu do skonfigurowanego endpointu LLM i zwraca kanał, którym
+/// napływają kolejne fragmenty szkicu opisu (odpowiedź strumieniowana w formacie SSE).
+///
+/// Szkic trafia wyłącznie do przeglądarki admina - nic nie jest zapisywane w bazie.
+pub async fn stream_description_draft(
+    config: &DescriptionAssistantConfig,
+    payload: &ProductDescriptionDraftPayload,
+) -> Result<mpsc::Receiver<Bytes>, AppError> {
+    let request_body = json!({
+        "model": config.model,
+        "stream": true,
+        "messages": [
+            { "role": "system", "content": SYSTEM_PROMPT },
+            { "role": "user", "content": build_user_prompt(payload) }
+        ]
+    });
+
+    let client = Client::new();
+    let mut request = client.post(&config.endpoint).json(&request_body);
+    if let Some(api_key) = &config.api_key {
+        request = request.bearer_auth(api_key);
+    }
+
+    let resp = request.send().await.map_err(|e| {
+        tracing::error!("Błąd połączenia z asystentem opisów: {}", e);
+        AppError::InternalServerError("Błąd połączenia z asystentem opisów".to_string())
+    })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "Asystent opisów zwrócił błąd: Status={}, Treść={}",
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Asystent opisów zwrócił błąd (status: {})",
+            status
+        )));
+    }
+
+    let (tx, rx) = mpsc::channel::<Bytes>(32);
+    tokio::spawn(async move {
+        let mut byte_stream = resp.bytes_stream();
+        // Fragmenty sieciowe nie pokrywają się z liniami SSE - buforujemy niepełne linie
+        let mut buffer = String::new();
+
+        while let Some(chunk) = byte_stream.next().await {
+            let chunk = match chunk {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::warn!("Przerwany strumień z asystenta opisów: {}", e);
+                    break;
+                }
+            };
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(newline_idx) = buffer.find('\n') {
+                let line: String = buffer.drain(..=newline_idx).collect();
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    return;
+                }
+                let Ok(parsed) = serde_json::from_str::<ChatCompletionChunk>(data) else {
+                    continue;
+                };
+                for choice in parsed.choices {
+                    if let Some(content) = choice.delta.content.filter(|c| !c.is_empty())
+                        && tx.send(Bytes::from(content)).await.is_err()
+                    {
+                        // Admin zamknął połączenie - nie ma sensu czytać dalej
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}
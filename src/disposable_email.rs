@@ -0,0 +1,119 @@
+// src/disposable_email.rs
+
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use crate::errors::AppError;
+use crate::state::AppState;
+
+/// Domeny jednorazowe znane zawsze, nawet gdy zdalna lista jest niedostępna.
+const BUILTIN_DOMAINS: &[&str] = &[
+    "10minutemail.com",
+    "guerrillamail.com",
+    "mailinator.com",
+    "temp-mail.org",
+    "tempmail.com",
+    "throwawaymail.com",
+    "yopmail.com",
+    "trashmail.com",
+    "getnada.com",
+    "dispostable.com",
+];
+
+/// Lista zablokowanych domen e-mail (skrzynki jednorazowe).
+///
+/// Źródła domen:
+/// 1. Lista wbudowana (`BUILTIN_DOMAINS`).
+/// 2. Zmienna `DISPOSABLE_EMAIL_DOMAINS` - dodatkowe domeny oddzielone przecinkami.
+/// 3. Opcjonalna zdalna lista (`DISPOSABLE_EMAIL_LIST_URL`, jedna domena na linię),
+///    odświeżana okresowo przez zadanie `refresh_job` (zob. `jobs`).
+pub struct DisposableEmailBlocklist {
+    domains: RwLock<HashSet<String>>,
+    local_domains: HashSet<String>,
+    remote_list_url: Option<String>,
+}
+
+impl DisposableEmailBlocklist {
+    pub fn new(extra_domains: &str, remote_list_url: Option<String>) -> Self {
+        let local_domains: HashSet<String> = BUILTIN_DOMAINS
+            .iter()
+            .map(|d| d.to_string())
+            .chain(
+                extra_domains
+                    .split(',')
+                    .map(|d| d.trim().to_lowercase())
+                    .filter(|d| !d.is_empty()),
+            )
+            .collect();
+
+        Self {
+            domains: RwLock::new(local_domains.clone()),
+            local_domains,
+            remote_list_url,
+        }
+    }
+
+    /// Sprawdza, czy domena adresu e-mail jest na liście skrzynek jednorazowych.
+    pub fn is_disposable(&self, email: &str) -> bool {
+        let Some((_, domain)) = email.rsplit_once('@') else {
+            return false;
+        };
+        let domain = domain.trim().to_lowercase();
+        let domains = self.domains.read().unwrap_or_else(|e| e.into_inner());
+        domains.contains(&domain)
+    }
+
+    /// Liczba domen na liście
+    pub fn len(&self) -> usize {
+        self.domains.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adres zdalnej listy domen, jeśli skonfigurowano odświeżanie
+    pub fn remote_list_url(&self) -> Option<&str> {
+        self.remote_list_url.as_deref()
+    }
+
+    /// Pobiera zdalną listę i podmienia zestaw domen (lokalne domeny zawsze zostają).
+    /// Zwraca liczbę domen po odświeżeniu.
+    pub async fn refresh(&self) -> Result<usize, AppError> {
+        let Some(url) = &self.remote_list_url else {
+            return Ok(self.len());
+        };
+
+        let body = reqwest::get(url)
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Błąd pobierania listy domen: {}", e))
+            })?
+            .text()
+            .await
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Błąd odczytu listy domen: {}", e))
+            })?;
+
+        let mut new_domains = self.local_domains.clone();
+        new_domains.extend(
+            body.lines()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|line| !line.is_empty() && !line.starts_with('#')),
+        );
+        let count = new_domains.len();
+
+        *self.domains.write().unwrap_or_else(|e| e.into_inner()) = new_domains;
+        Ok(count)
+    }
+}
+
+/// Zadanie cykliczne (raz na dobę): odświeżenie zdalnej listy domen
+pub async fn refresh_job(state: &AppState) -> Result<String, AppError> {
+    let count = state.disposable_email_blocklist.refresh().await?;
+    Ok(format!(
+        "Odświeżono listę jednorazowych domen e-mail: {} domen",
+        count
+    ))
+}
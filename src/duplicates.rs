@@ -0,0 +1,137 @@
+// src/duplicates.rs
+
+// Wykrywanie podwójnie wystawionych produktów. Przy fotografowaniu partiami łatwo dodać tę samą
+// rzecz dwa razy, więc przed zapisem nowego produktu porównujemy jego nazwę i hashe zdjęć
+// z dostępnymi produktami. To tylko ostrzeżenie - admin może zapisać produkt mimo to.
+
+use std::collections::{HashMap, HashSet};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::image_hash::hamming_distance;
+use crate::models::ProductStatus;
+
+/// Zdjęcia różniące się na najwyżej tylu bitach pHash uznajemy za to samo ujęcie
+pub const MAX_IMAGE_HASH_DISTANCE: u32 = 8;
+/// Minimalne podobieństwo nazw (wspólne słowa / wszystkie słowa)
+pub const MIN_NAME_SIMILARITY: f64 = 0.8;
+const MAX_DUPLICATE_CANDIDATES: usize = 5;
+
+/// Dostępny produkt podobny do dodawanego
+#[derive(Debug, Clone)]
+pub struct DuplicateCandidate {
+    pub product_id: Uuid,
+    pub name: String,
+    pub image: Option<String>,
+    /// Odległość najbardziej podobnej pary zdjęć, jeśli jest poniżej progu
+    pub image_distance: Option<u32>,
+    /// Podobieństwo nazw 0-1, jeśli jest powyżej progu
+    pub name_similarity: Option<f64>,
+}
+
+/// Słowa nazwy bez wielkości liter, polskich znaków i interpunkcji,
+/// żeby "Sukienka ZARA, midi" i "sukienka zara midi" były tym samym.
+fn name_tokens(name: &str) -> HashSet<String> {
+    name.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ó' => 'o',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Współczynnik Jaccarda zbiorów słów obu nazw
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (name_tokens(a), name_tokens(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// Szuka wśród dostępnych produktów prawdopodobnych duplikatów nowego produktu:
+/// z bardzo podobną nazwą albo z prawie identycznym zdjęciem. Najpierw dopasowania po zdjęciach.
+pub async fn find_probable_duplicates(
+    pool: &PgPool,
+    name: &str,
+    image_hashes: &[i64],
+) -> Result<Vec<DuplicateCandidate>, AppError> {
+    let products: Vec<(Uuid, String, Vec<String>)> =
+        sqlx::query_as("SELECT id, name, images FROM products WHERE status = $1")
+            .bind(ProductStatus::Available)
+            .fetch_all(pool)
+            .await?;
+
+    let mut closest_images: HashMap<Uuid, u32> = HashMap::new();
+    if !image_hashes.is_empty() {
+        let stored: Vec<(Uuid, i64)> = sqlx::query_as(
+            r#"
+            SELECT h.product_id, h.phash
+            FROM product_image_hashes h
+            JOIN products p ON p.id = h.product_id
+            WHERE p.status = $1 AND h.image_url = ANY(p.images)
+            "#,
+        )
+        .bind(ProductStatus::Available)
+        .fetch_all(pool)
+        .await?;
+        for (product_id, stored_hash) in stored {
+            let distance = image_hashes
+                .iter()
+                .map(|hash| hamming_distance(*hash, stored_hash))
+                .min()
+                .unwrap_or(u32::MAX);
+            if distance <= MAX_IMAGE_HASH_DISTANCE {
+                closest_images
+                    .entry(product_id)
+                    .and_modify(|current| *current = (*current).min(distance))
+                    .or_insert(distance);
+            }
+        }
+    }
+
+    let mut candidates: Vec<DuplicateCandidate> = products
+        .into_iter()
+        .filter_map(|(product_id, product_name, images)| {
+            let image_distance = closest_images.get(&product_id).copied();
+            let similarity = name_similarity(name, &product_name);
+            let name_similarity = (similarity >= MIN_NAME_SIMILARITY).then_some(similarity);
+            if image_distance.is_none() && name_similarity.is_none() {
+                return None;
+            }
+            Some(DuplicateCandidate {
+                product_id,
+                name: product_name,
+                image: images.into_iter().next(),
+                image_distance,
+                name_similarity,
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| {
+        a.image_distance
+            .unwrap_or(u32::MAX)
+            .cmp(&b.image_distance.unwrap_or(u32::MAX))
+            .then(
+                b.name_similarity
+                    .unwrap_or(0.0)
+                    .total_cmp(&a.name_similarity.unwrap_or(0.0)),
+            )
+    });
+    candidates.truncate(MAX_DUPLICATE_CANDIDATES);
+    Ok(candidates)
+}
@@ -1,35 +1,46 @@
 // src/email_service.rs
 
-use std::env;
-
 use crate::{
+    care_instructions::care_instructions_for_order,
+    complaints::{complaint_reference, response_deadline},
+    date_format::{format_date_long, format_datetime_long},
     errors::AppError,
-    models::{OrderDetailsResponse, PaymentMethod, User},
-    state::AppState,
+    gift_cards::GiftCard,
+    invoices::{invoice_filename, invoice_pdf_for_order},
+    models::{
+        Complaint, ComplaintStatus, Order, OrderDetailsResponse, OrderStatus, PaymentMethod,
+        Product, ReturnStatus, User,
+    },
+    plural::products_count,
+    returns::{ReturnDetails, return_reference},
+    state::{AppState, PaymentDetailsConfig},
 };
 use maud::{Markup, PreEscaped, html};
-use resend_rs::{Resend, types::CreateEmailBaseOptions};
+use resend_rs::{
+    Resend,
+    types::{Attachment, CreateEmailBaseOptions},
+};
 
-// Pomocnicza funkcja do formatowania ceny, tak jak w htmx_handlers
-#[allow(dead_code)]
+/// Nadawca e-maili sklepu; adres (`ADMIN_EMAIL`) jest sprawdzany przy starcie w `AppConfig`
+fn sender_formatted(app_state: &AppState) -> String {
+    format!("mess - all that vintage <{}>", app_state.admin_email)
+}
+
+// Pomocnicza funkcja do formatowania ceny, tak jak w views::common
 fn format_price_maud(price: i64) -> String {
     format!("{:.2}", (price as f64) / 100.0).replace('.', ",") + " zł"
 }
 
-// Funkcja, którą będziemy wywoływać z handlera
-#[allow(dead_code)]
-pub async fn send_order_confirmation_email(
+/// Ustala adres e-mail klienta dla zamówienia: e-mail gościa albo e-mail z tabeli `users`.
+pub async fn resolve_order_recipient_email(
     app_state: &AppState,
-    order_details: &OrderDetailsResponse,
-) -> Result<(), AppError> {
-    let recipient_email: String;
-
-    // === NOWA, POPRAWNA LOGIKA POBIERANIA E-MAILA ===
-    if let Some(guest_email_val) = &order_details.order.guest_email {
+    order: &Order,
+) -> Result<String, AppError> {
+    if let Some(guest_email_val) = &order.guest_email {
         // Przypadek 1: Zamówienie gościa, e-mail jest w zamówieniu.
-        recipient_email = guest_email_val.clone();
-        tracing::info!("Wysyłka e-maila do gościa na adres: {}", recipient_email);
-    } else if let Some(user_id_val) = order_details.order.user_id {
+        tracing::info!("Wysyłka e-maila do gościa na adres: {}", guest_email_val);
+        Ok(guest_email_val.clone())
+    } else if let Some(user_id_val) = order.user_id {
         // Przypadek 2: Zamówienie zalogowanego użytkownika, pobierz e-mail z tabeli `users`.
         let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
             .bind(user_id_val)
@@ -37,37 +48,42 @@ pub async fn send_order_confirmation_email(
             .await?
             .ok_or_else(|| AppError::NotFound)?; // Zwróć błąd, jeśli użytkownik nie istnieje
 
-        recipient_email = user.email;
         tracing::info!(
             "Wysyłka e-maila do zalogowanego użytkownika na adres: {}",
-            recipient_email
+            user.email
         );
+        Ok(user.email)
     } else {
         // Przypadek 3: Błąd - zamówienie nie ma ani e-maila gościa, ani ID użytkownika.
         tracing::error!(
             "Nie można ustalić adresu e-mail odbiorcy dla zamówienia ID: {}",
-            order_details.order.id
+            order.id
         );
-        return Err(AppError::InternalServerError(
+        Err(AppError::InternalServerError(
             "Brak adresu e-mail do wysyłki potwierdzenia.".to_string(),
-        ));
+        ))
     }
+}
+
+// Funkcja, którą będziemy wywoływać z handlera
+pub async fn send_order_confirmation_email(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+) -> Result<(), AppError> {
+    let recipient_email = resolve_order_recipient_email(app_state, &order_details.order).await?;
 
     // Inicjalizacja klienta Resend
     let resend = Resend::new(&app_state.resend_api_key);
 
     // Wyrenderuj treść HTML e-maila
-    let email_html_content = render_order_confirmation_email_html(order_details);
+    let email_html_content =
+        render_order_confirmation_email_html(order_details, &app_state.payment_details);
 
-    // Pobierz e-mail administratora/nadawcy ze zmiennej środowiskowej
-    let sender_display_name = "mess - all that vintage";
-    let sender_email_address =
-        env::var("ADMIN_EMAIL").unwrap_or_else(|_| "noreply@mess.com".to_string());
-    let sender_formatted = format!("{} <{}>", sender_display_name, sender_email_address);
+    let sender_formatted = sender_formatted(app_state);
 
     let subject = format!(
-        "Potwierdzenie zamówienia nr #{}",
-        &order_details.order.id.to_string()[..8]
+        "Potwierdzenie zamówienia nr {}",
+        &order_details.order.order_number
     );
 
     // Używamy .builder() do stworzenia zapytania
@@ -78,6 +94,23 @@ pub async fn send_order_confirmation_email(
     )
     .with_html(&email_html_content.into_string());
 
+    // Faktura w załączniku, jeśli zamówienie jest już opłacone (np. kartą podarunkową);
+    // błąd generowania PDF nie blokuje potwierdzenia (faktura jest też na koncie)
+    let params = match invoice_pdf_for_order(app_state, order_details).await {
+        Ok(Some((invoice, pdf))) => params.with_attachment(
+            Attachment::from_content(pdf).with_filename(&invoice_filename(&invoice)),
+        ),
+        Ok(None) => params,
+        Err(e) => {
+            tracing::error!(
+                "Nie udało się przygotować faktury do zamówienia {}: {:?}",
+                order_details.order.id,
+                e
+            );
+            params
+        }
+    };
+
     tracing::info!(
         "Wysyłanie e-maila z potwierdzeniem zamówienia do: {}",
         recipient_email
@@ -90,22 +123,23 @@ pub async fn send_order_confirmation_email(
     })?;
 
     tracing::info!("E-mail z potwierdzeniem zamówienia został wysłany pomyślnie.");
+
+    // Znacznik wysyłki ogranicza ponowne wysyłki ze strony podziękowania
+    sqlx::query("UPDATE orders SET confirmation_sent_at = NOW() WHERE id = $1")
+        .bind(order_details.order.id)
+        .execute(&app_state.db_pool)
+        .await?;
     Ok(())
 }
 
 // Funkcja renderująca szablon HTML e-maila
-fn render_order_confirmation_email_html(order_details: &OrderDetailsResponse) -> Markup {
+fn render_order_confirmation_email_html(
+    order_details: &OrderDetailsResponse,
+    payment_details: &PaymentDetailsConfig,
+) -> Markup {
     let order = &order_details.order;
-    let order_id_short = &order.id.to_string()[..8];
-    let payment_method_details = match order.payment_method.as_ref() {
-        Some(PaymentMethod::Blik) => {
-            "Płatność BLIK na numer telefonu: <strong>603 117 793</strong>. W tytule przelewu prosimy podać numer zamówienia."
-        }
-        Some(PaymentMethod::Transfer) => {
-            "Prosimy o dokonanie przelewu na numer konta: <strong>XX XXXX XXXX XXXX XXXX XXXX XXXX</strong>. W tytule przelewu prosimy podać numer zamówienia."
-        }
-        None => "Metoda płatności nie została określona. Skontaktuj się z nami.",
-    };
+    let order_number = &order.order_number;
+    let payment_reference = order.payment_reference();
 
     html! {
         (PreEscaped("<!DOCTYPE html>"))
@@ -135,9 +169,11 @@ fn render_order_confirmation_email_html(order_details: &OrderDetailsResponse) ->
                         h2 { "Dziękujemy za Twoje zamówienie!" }
                     }
                     h3 { "Hej, " (order.shipping_first_name) "!" }
-                    p { "Twoje zamówienie nr #" (order_id_short) " zostało pomyślnie złożone. Poniżej znajdziesz jego podsumowanie." }
+                    p { "Twoje zamówienie nr " (order_number) " zostało pomyślnie złożone. Poniżej znajdziesz jego podsumowanie." }
+                    p { "Data złożenia: " (format_datetime_long(&order.order_date)) }
 
                     h4 style="border-bottom: 2px solid #eee; padding-bottom: 5px;" { "Szczegóły zamówienia" }
+                    p { "Zamówienie zawiera " (products_count(order_details.items.len() as i64)) "." }
 
                     @for item in &order_details.items {
                         div class="item" {
@@ -158,7 +194,30 @@ fn render_order_confirmation_email_html(order_details: &OrderDetailsResponse) ->
 
                     div class="payment-info" {
                         h4 { "Dane do płatności" }
-                        p { (PreEscaped(payment_method_details)) }
+                        @match order.payment_method.as_ref() {
+                            Some(PaymentMethod::Blik) => {
+                                p { "Płatność BLIK na numer telefonu: " strong { (payment_details.blik_phone) } }
+                                p { "Kwota: " strong { (format_price_maud(order.total_price)) } }
+                                p { "Tytuł płatności: " strong { (payment_reference) } }
+                            }
+                            Some(PaymentMethod::Transfer) => {
+                                p { "Odbiorca: " strong { (payment_details.account_holder) } }
+                                p { "Numer konta: " strong { (payment_details.account_number) } }
+                                p { "Kwota: " strong { (format_price_maud(order.total_price)) } }
+                                p { "Tytuł przelewu: " strong { (payment_reference) } }
+                                p { "Zamówienie wyślemy po zaksięgowaniu wpłaty." }
+                            }
+                            Some(PaymentMethod::Przelewy24) => {
+                                p { "Płatność online Przelewy24. Jeśli płatność nie została dokończona, możesz ją ponowić ze strony podsumowania zamówienia." }
+                            }
+                            None => {
+                                p { "Metoda płatności nie została określona. Skontaktuj się z nami." }
+                            }
+                        }
+                    }
+
+                    @if order.guest_email.is_some() {
+                        p { "Zamówienie zostało złożone bez zakładania konta - zachowaj tę wiadomość, to Twoje potwierdzenie zakupu." }
                     }
 
                     div {
@@ -179,6 +238,468 @@ fn render_order_confirmation_email_html(order_details: &OrderDetailsResponse) ->
     }
 }
 
+/// Informuje klienta, że pozycja została usunięta z zamówienia (np. uszkodzenie przed wysyłką)
+/// i że kwota za nią zostanie zwrócona. Pozostałe produkty wysyłamy normalnie.
+pub async fn send_order_item_removed_email(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+    removed_product: &Product,
+    refund_amount: i64,
+    gift_card_refund: i64,
+    reason: &str,
+) -> Result<(), AppError> {
+    let recipient_email = resolve_order_recipient_email(app_state, &order_details.order).await?;
+    let order = &order_details.order;
+    let order_number = &order.order_number;
+
+    let email_html_content = html! {
+        h1 { "mess - all that vintage" }
+        h3 { "Hej, " (order.shipping_first_name) "!" }
+        p {
+            "Niestety, podczas przygotowywania Twojego zamówienia nr " (order_number)
+            " okazało się, że produkt " strong { (removed_product.name) } " nie nadaje się do wysyłki."
+        }
+        p { "Powód: " (reason) }
+        p { "Usunęliśmy go z zamówienia." }
+        @if refund_amount > 0 {
+            p {
+                "Kwota " strong { (format_price_maud(refund_amount)) }
+                " zostanie zwrócona na Twoje konto."
+            }
+        }
+        @if gift_card_refund > 0 {
+            p {
+                "Kwota " strong { (format_price_maud(gift_card_refund)) }
+                " wróciła na saldo Twojej karty podarunkowej."
+            }
+        }
+        @if !order_details.items.is_empty() {
+            h4 { "Pozostałe produkty zostaną wysłane:" }
+            ul {
+                @for item in &order_details.items {
+                    li { (item.product.name) " - " (format_price_maud(item.price_at_purchase)) }
+                }
+            }
+            p { "Nowa suma zamówienia: " strong { (format_price_maud(order.total_price)) } }
+        }
+        p { "Bardzo przepraszamy za utrudnienia." }
+        p { "Zespół mess - all that vintage" }
+    };
+
+    let resend = Resend::new(&app_state.resend_api_key);
+    let sender_formatted = sender_formatted(app_state);
+    let subject = format!("Zmiana w zamówieniu nr {}", order_number);
+
+    let params =
+        CreateEmailBaseOptions::new(&sender_formatted, vec![recipient_email.clone()], &subject)
+            .with_html(&email_html_content.into_string());
+
+    resend.emails.send(params).await.map_err(|e| {
+        tracing::error!("Błąd API Resend przy e-mailu o usunięciu pozycji: {:?}", e);
+        AppError::InternalServerError("Błąd wysyłki e-maila".to_string())
+    })?;
+
+    tracing::info!(
+        "Wysłano e-mail o usunięciu pozycji z zamówienia {} do: {}",
+        order.id,
+        recipient_email
+    );
+    Ok(())
+}
+
+/// Wspólna oprawa e-maili o zmianie statusu zamówienia (nagłówek sklepu, stopka).
+fn render_order_email_layout(title: &str, order: &Order, content: Markup) -> Markup {
+    html! {
+        (PreEscaped("<!DOCTYPE html>"))
+        html lang="pl" {
+            head {
+                meta charset="UTF-8";
+                meta name="viewport" content="width=device-width, initial-scale=1.0";
+                title { (title) }
+                style {
+                    (PreEscaped(r#"
+                        body { font-family: Arial, sans-serif; color: #333; }
+                        .container { max-width: 600px; margin: auto; padding: 20px; border: 1px solid #ddd; }
+                        .header { background-color: #fce4ec; padding: 10px; text-align: center; }
+                        .header h1 { color: #e91e63; }
+                        .button { display: inline-block; background-color: #e91e63; color: #fff; padding: 10px 20px; text-decoration: none; border-radius: 4px; }
+                    "#))
+                }
+            }
+            body {
+                div class="container" {
+                    div class="header" {
+                        h1 { "mess - all that vintage" }
+                        h2 { (title) }
+                    }
+                    h3 { "Hej, " (order.shipping_first_name) "!" }
+                    (content)
+                    p { "Zespół mess - all that vintage" }
+                }
+            }
+        }
+    }
+}
+
+/// Wysyła e-mail przez Resend i loguje wynik. `email_kind` trafia tylko do logów.
+async fn send_order_email(
+    app_state: &AppState,
+    order: &Order,
+    subject: &str,
+    email_kind: &str,
+    content: Markup,
+) -> Result<(), AppError> {
+    send_order_email_with_attachment(app_state, order, subject, email_kind, content, None).await
+}
+
+/// Jak `send_order_email`, z opcjonalnym załącznikiem (nazwa pliku, zawartość)
+async fn send_order_email_with_attachment(
+    app_state: &AppState,
+    order: &Order,
+    subject: &str,
+    email_kind: &str,
+    content: Markup,
+    attachment: Option<(&str, Vec<u8>)>,
+) -> Result<(), AppError> {
+    let recipient_email = resolve_order_recipient_email(app_state, order).await?;
+
+    let resend = Resend::new(&app_state.resend_api_key);
+    let sender_formatted = sender_formatted(app_state);
+
+    let mut params =
+        CreateEmailBaseOptions::new(&sender_formatted, vec![recipient_email.clone()], subject)
+            .with_html(&content.into_string());
+    if let Some((filename, file_content)) = attachment {
+        params =
+            params.with_attachment(Attachment::from_content(file_content).with_filename(filename));
+    }
+
+    match resend.emails.send(params).await {
+        Ok(_) => {
+            tracing::info!(
+                "Wysłano e-mail '{}' dla zamówienia {} do: {}",
+                email_kind,
+                order.id,
+                recipient_email
+            );
+            Ok(())
+        }
+        Err(e) => {
+            tracing::error!(
+                "Błąd API Resend przy e-mailu '{}' dla zamówienia {}: {:?}",
+                email_kind,
+                order.id,
+                e
+            );
+            Err(AppError::InternalServerError(
+                "Błąd wysyłki e-maila".to_string(),
+            ))
+        }
+    }
+}
+
+/// Potwierdzenie otrzymania płatności - zamówienie przechodzi do realizacji.
+pub async fn send_payment_received_email(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+) -> Result<(), AppError> {
+    let order = &order_details.order;
+    let order_number = &order.order_number;
+    let title = "Otrzymaliśmy płatność";
+
+    let content = html! {
+        p {
+            "Otrzymaliśmy płatność " strong { (format_price_maud(order.total_price)) }
+            " za zamówienie nr " (order_number) "."
+        }
+        p { "Zamówienie jest już w realizacji. Damy znać, gdy paczka zostanie nadana." }
+    };
+    // Faktura jest wystawiana razem z zaksięgowaniem płatności - dołączamy ją do tej wiadomości
+    let invoice = match invoice_pdf_for_order(app_state, order_details).await {
+        Ok(invoice) => invoice,
+        Err(e) => {
+            tracing::error!(
+                "Nie udało się przygotować faktury do zamówienia {}: {:?}",
+                order.id,
+                e
+            );
+            None
+        }
+    };
+    let filename = invoice
+        .as_ref()
+        .map(|(invoice, _)| invoice_filename(invoice));
+    let attachment = filename.as_deref().zip(invoice.map(|(_, pdf)| pdf));
+
+    send_order_email_with_attachment(
+        app_state,
+        order,
+        &format!("Płatność za zamówienie nr {} zaksięgowana", order_number),
+        "płatność otrzymana",
+        render_order_email_layout(title, order, content),
+        attachment,
+    )
+    .await
+}
+
+/// Informacja o wysyłce. Dla przesyłek InPost dołączamy link do śledzenia.
+pub async fn send_order_shipped_email(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+    tracking_number: Option<&str>,
+) -> Result<(), AppError> {
+    let order = &order_details.order;
+    let order_number = &order.order_number;
+    let title = "Twoja paczka jest w drodze";
+    // Bez wskazówek pielęgnacji e-mail nadal ma sens - błąd tylko logujemy
+    let care_instructions = care_instructions_for_order(&app_state.db_pool, order_details)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(
+                "Nie udało się pobrać wskazówek pielęgnacji do zamówienia {}: {:?}",
+                order.id,
+                e
+            );
+            Vec::new()
+        });
+
+    let content = html! {
+        p { "Zamówienie nr " (order_number) " zostało wysłane." }
+        @if let Some(shipping_name) = &order.shipping_method_name {
+            p { "Metoda dostawy: " strong { (shipping_name) } }
+        }
+        @if let Some(locker_code) = &order.inpost_locker_code {
+            p { "Paczkomat: " strong { (locker_code) } }
+            @if let Some(locker_address) = &order.inpost_locker_address {
+                p { (locker_address) }
+            }
+        }
+        @if let Some(tracking_number) = tracking_number {
+            p { "Numer przesyłki: " strong { (tracking_number) } }
+            p {
+                a class="button" href=(format!("https://inpost.pl/sledzenie-przesylek?number={}", tracking_number)) {
+                    "Śledź przesyłkę"
+                }
+            }
+        }
+        h4 { "Wysłane produkty" }
+        ul {
+            @for item in &order_details.items {
+                li { (item.product.name) }
+            }
+        }
+        @if !care_instructions.is_empty() {
+            h4 { "Pielęgnacja" }
+            ul {
+                @for instruction in &care_instructions {
+                    li { strong { (instruction.material) ": " } (instruction.instructions) }
+                }
+            }
+        }
+        p { "Dziękujemy za zakupy i zapraszamy ponownie!" }
+    };
+
+    send_order_email(
+        app_state,
+        order,
+        &format!("Zamówienie nr {} zostało wysłane", order_number),
+        "zamówienie wysłane",
+        render_order_email_layout(title, order, content),
+    )
+    .await
+}
+
+/// Informacja o anulowaniu zamówienia.
+pub async fn send_order_cancelled_email(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+) -> Result<(), AppError> {
+    let order = &order_details.order;
+    let order_number = &order.order_number;
+    let title = "Zamówienie anulowane";
+
+    let content = html! {
+        p { "Zamówienie nr " (order_number) " zostało anulowane." }
+        p {
+            "Jeśli zamówienie było już opłacone, kwota " strong { (format_price_maud(order.total_price)) }
+            " zostanie zwrócona tą samą metodą płatności."
+        }
+        p { "Masz pytania? Po prostu odpowiedz na tę wiadomość." }
+    };
+
+    send_order_email(
+        app_state,
+        order,
+        &format!("Zamówienie nr {} zostało anulowane", order_number),
+        "zamówienie anulowane",
+        render_order_email_layout(title, order, content),
+    )
+    .await
+}
+
+/// E-mail o zwrocie: potwierdzenie zgłoszenia albo decyzja admina (przyjęcie / odrzucenie).
+pub async fn send_return_status_email(
+    app_state: &AppState,
+    order: &Order,
+    details: &ReturnDetails,
+) -> Result<(), AppError> {
+    let request = &details.request;
+    let reference = return_reference(request);
+    let order_number = &order.order_number;
+    let (title, email_kind) = match request.status {
+        ReturnStatus::Requested => ("Otrzymaliśmy zgłoszenie zwrotu", "zwrot zgłoszony"),
+        ReturnStatus::Approved => ("Zwrot przyjęty", "zwrot przyjęty"),
+        ReturnStatus::Rejected => ("Zwrot odrzucony", "zwrot odrzucony"),
+    };
+
+    let content = html! {
+        p { "Zwrot " strong { (reference) } " do zamówienia nr " (order_number) " obejmuje:" }
+        ul {
+            @for item in &details.items {
+                li { (item.product_name) " - " (format_price_maud(item.amount)) }
+            }
+        }
+        @match request.status {
+            ReturnStatus::Requested => {
+                @if details.label.is_some() {
+                    p { "Etykietę zwrotną InPost wyślemy osobnym e-mailem, gdy tylko InPost ją wygeneruje. Po otrzymaniu paczki i sprawdzeniu produktów damy znać o decyzji." }
+                } @else {
+                    p { "Odeślij produkty na adres sklepu w ciągu 14 dni. Po ich otrzymaniu i sprawdzeniu damy znać o decyzji." }
+                }
+            }
+            ReturnStatus::Approved => {
+                p {
+                    "Przyjęliśmy zwrot. Kwota " strong { (format_price_maud(details.refund_amount())) }
+                    " zostanie przelana na konto " (request.bank_account) "."
+                }
+                @if let Some(label) = &details.label {
+                    p { "Kwota uwzględnia potrącenie za etykietę zwrotną InPost (" (format_price_maud(label.price)) ")." }
+                }
+            }
+            ReturnStatus::Rejected => {
+                p { "Niestety, nie możemy przyjąć tego zwrotu." }
+            }
+        }
+        @if let Some(note) = &request.admin_note {
+            p { "Uwagi: " (note) }
+        }
+    };
+
+    send_order_email(
+        app_state,
+        order,
+        &format!("{} {} - zamówienie nr {}", title, reference, order_number),
+        email_kind,
+        render_order_email_layout(title, order, content),
+    )
+    .await
+}
+
+/// Etykieta zwrotna InPost w załączniku PDF - klient drukuje ją i nadaje paczkę w Paczkomacie.
+pub async fn send_return_label_email(
+    app_state: &AppState,
+    order: &Order,
+    details: &ReturnDetails,
+    label_pdf: Vec<u8>,
+    tracking_number: Option<&str>,
+) -> Result<(), AppError> {
+    let reference = return_reference(&details.request);
+    let title = "Etykieta zwrotna";
+    let label_price = details.label.as_ref().map_or(0, |label| label.price);
+
+    let content = html! {
+        p { "W załączniku jest etykieta InPost do zwrotu " strong { (reference) } " z zamówienia nr " (order.order_number) "." }
+        p { "Wydrukuj ją, przyklej na paczkę i nadaj w dowolnym Paczkomacie w ciągu 14 dni." }
+        @if let Some(tracking_number) = tracking_number {
+            p { "Numer przesyłki: " strong { (tracking_number) } }
+        }
+        p { "Koszt etykiety (" (format_price_maud(label_price)) ") potrącimy z kwoty zwrotu." }
+    };
+
+    send_order_email_with_attachment(
+        app_state,
+        order,
+        &format!(
+            "{} {} - zamówienie nr {}",
+            title, reference, order.order_number
+        ),
+        "etykieta zwrotna",
+        render_order_email_layout(title, order, content),
+        Some((&format!("etykieta-zwrotna-{}.pdf", reference), label_pdf)),
+    )
+    .await
+}
+
+/// E-mail o reklamacji: potwierdzenie złożenia albo zmiana statusu przez admina.
+pub async fn send_complaint_status_email(
+    app_state: &AppState,
+    order: &Order,
+    complaint: &Complaint,
+) -> Result<(), AppError> {
+    let reference = complaint_reference(complaint);
+    let order_number = &order.order_number;
+    let (title, email_kind) = match complaint.status {
+        ComplaintStatus::Open => ("Otrzymaliśmy reklamację", "reklamacja złożona"),
+        ComplaintStatus::InReview => ("Rozpatrujemy reklamację", "reklamacja rozpatrywana"),
+        ComplaintStatus::Accepted => ("Reklamacja uznana", "reklamacja uznana"),
+        ComplaintStatus::Rejected => ("Reklamacja odrzucona", "reklamacja odrzucona"),
+    };
+
+    let content = html! {
+        p {
+            "Reklamacja " strong { (reference) } " dotyczy produktu "
+            strong { (complaint.product_name) } " z zamówienia nr " (order_number) "."
+        }
+        @match complaint.status {
+            ComplaintStatus::Open => {
+                p {
+                    "Odpowiemy najpóźniej do " strong { (format_date_long(&response_deadline(complaint))) }
+                    ". Jeśli będziemy potrzebować dodatkowych informacji, napiszemy do Ciebie."
+                }
+            }
+            ComplaintStatus::InReview => {
+                p { "Sprawdzamy zgłoszenie - damy znać o decyzji." }
+            }
+            ComplaintStatus::Accepted => {
+                p { "Uznaliśmy Twoją reklamację." }
+            }
+            ComplaintStatus::Rejected => {
+                p { "Niestety, nie możemy uznać tej reklamacji." }
+            }
+        }
+        @if let Some(resolution) = &complaint.resolution {
+            p { "Odpowiedź sklepu: " (resolution) }
+        }
+        p { "Masz pytania? Po prostu odpowiedz na tę wiadomość." }
+    };
+
+    send_order_email(
+        app_state,
+        order,
+        &format!("{} {} - zamówienie nr {}", title, reference, order_number),
+        email_kind,
+        render_order_email_layout(title, order, content),
+    )
+    .await
+}
+
+/// Wysyła e-mail odpowiadający nowemu statusowi zamówienia (o ile taki istnieje).
+/// Przejście do `Processing` traktujemy jako zaksięgowanie płatności.
+pub async fn send_order_status_email(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+    tracking_number: Option<&str>,
+) -> Result<(), AppError> {
+    match order_details.order.status {
+        OrderStatus::Processing => send_payment_received_email(app_state, order_details).await,
+        OrderStatus::Shipped => {
+            send_order_shipped_email(app_state, order_details, tracking_number).await
+        }
+        OrderStatus::Cancelled => send_order_cancelled_email(app_state, order_details).await,
+        OrderStatus::Pending | OrderStatus::Delivered => Ok(()),
+    }
+}
+
 pub async fn send_password_reset_email(
     app_state: &AppState,
     recipient_email: &str,
@@ -196,10 +717,7 @@ pub async fn send_password_reset_email(
     };
 
     let resend = Resend::new(&app_state.resend_api_key);
-    let sender_formatted = format!(
-        "mess - all that vintage <{}>",
-        env::var("ADMIN_EMAIL").unwrap()
-    );
+    let sender_formatted = sender_formatted(app_state);
     let params = CreateEmailBaseOptions::new(
         &sender_formatted,
         vec![recipient_email.to_string()],
@@ -214,3 +732,168 @@ pub async fn send_password_reset_email(
 
     Ok(())
 }
+
+pub async fn send_email_verification_email(
+    app_state: &AppState,
+    recipient_email: &str,
+    verification_link: &str,
+) -> Result<(), AppError> {
+    let email_html_content = html! {
+        h1 { "Potwierdź swój adres e-mail" }
+        p { "Dziękujemy za założenie konta w mess - all that vintage!" }
+        p { "Aby potwierdzić, że ten adres należy do Ciebie, kliknij w poniższy link. Link jest ważny przez 48 godzin:" }
+        a href=(verification_link) { "Potwierdzam adres e-mail" }
+        p { "Jeśli nie zakładano konta na ten adres, zignoruj tę wiadomość." }
+    };
+
+    let resend = Resend::new(&app_state.resend_api_key);
+    let sender_formatted = sender_formatted(app_state);
+    let params = CreateEmailBaseOptions::new(
+        &sender_formatted,
+        vec![recipient_email.to_string()],
+        "Potwierdź adres e-mail - mess - all that vintage",
+    )
+    .with_html(&email_html_content.into_string());
+
+    resend.emails.send(params).await.map_err(|e| {
+        tracing::error!("Błąd API Resend przy potwierdzaniu adresu e-mail: {:?}", e);
+        AppError::InternalServerError("Błąd wysyłki e-maila".to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Powiadomienie o czasowej blokadzie logowania po serii nieudanych prób (zob. `login_lockout`)
+pub async fn send_account_locked_email(
+    app_state: &AppState,
+    recipient_email: &str,
+    locked_until: &chrono::DateTime<chrono::Utc>,
+) -> Result<(), AppError> {
+    let reset_link = format!("{}/zapomnialem-hasla", app_state.public_base_url);
+
+    let email_html_content = html! {
+        h1 { "Logowanie do Twojego konta zostało wstrzymane" }
+        p { "Zanotowaliśmy kilka nieudanych prób logowania do Twojego konta w mess - all that vintage." }
+        p { "Dla bezpieczeństwa logowanie jest zablokowane do " strong { (format_datetime_long(locked_until)) } "." }
+        p { "Jeśli to Ty i nie pamiętasz hasła, możesz je zresetować:" }
+        a href=(reset_link) { "Ustaw nowe hasło" }
+        p { "Jeśli to nie Ty, ktoś mógł próbować odgadnąć Twoje hasło - warto ustawić nowe, silne hasło." }
+    };
+
+    let resend = Resend::new(&app_state.resend_api_key);
+    let params = CreateEmailBaseOptions::new(
+        sender_formatted(app_state),
+        vec![recipient_email.to_string()],
+        "Logowanie wstrzymane - mess - all that vintage",
+    )
+    .with_html(&email_html_content.into_string());
+
+    resend.emails.send(params).await.map_err(|e| {
+        tracing::error!(
+            "Błąd API Resend przy powiadomieniu o blokadzie konta: {:?}",
+            e
+        );
+        AppError::InternalServerError("Błąd wysyłki e-maila".to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Powiadomienie o obniżce produktu z ulubionych (zob. `wishlist::notify_price_drop`).
+pub async fn send_price_drop_email(
+    app_state: &AppState,
+    recipient_email: &str,
+    product: &Product,
+    old_price: i64,
+) -> Result<(), AppError> {
+    let product_link = format!("{}{}", app_state.public_base_url, product.public_path());
+
+    let email_html_content = html! {
+        h1 { "Produkt z Twoich ulubionych jest tańszy" }
+        @if let Some(image) = product.images.first() {
+            img src=(image) alt=(product.name) width="200";
+        }
+        p { strong { (product.name) } }
+        @if product.effective_price() < old_price {
+            p {
+                "Cena obniżona z " s { (format_price_maud(old_price)) }
+                " na " strong { (format_price_maud(product.effective_price())) } "."
+            }
+        } @else {
+            p { "Produkt trafił do okazji - cena: " strong { (format_price_maud(product.effective_price())) } "." }
+        }
+        p { "Każdy produkt w naszym sklepie jest jedyny w swoim rodzaju, więc nie zwlekaj:" }
+        a href=(product_link) { "Zobacz produkt" }
+    };
+
+    let resend = Resend::new(&app_state.resend_api_key);
+    let params = CreateEmailBaseOptions::new(
+        sender_formatted(app_state),
+        vec![recipient_email.to_string()],
+        format!("Obniżka: {} - mess - all that vintage", product.name),
+    )
+    .with_html(&email_html_content.into_string());
+
+    resend.emails.send(params).await.map_err(|e| {
+        tracing::error!("Błąd API Resend przy powiadomieniu o obniżce: {:?}", e);
+        AppError::InternalServerError("Błąd wysyłki e-maila".to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Kod karty podarunkowej dla obdarowanego - wysyłany po zaksięgowaniu płatności
+pub async fn send_gift_card_email(
+    app_state: &AppState,
+    gift_card: &GiftCard,
+) -> Result<(), AppError> {
+    let shop_link = format!("{}/", app_state.public_base_url);
+
+    let email_html_content = html! {
+        h1 { "Masz kartę podarunkową do mess - all that vintage!" }
+        @if let Some(name) = &gift_card.recipient_name {
+            p { "Cześć " (name) "," }
+        }
+        p {
+            "Ktoś pomyślał o Tobie i podarował Ci kartę o wartości "
+            strong { (format_price_maud(gift_card.initial_amount)) } "."
+        }
+        @if let Some(message) = &gift_card.message {
+            p { "Dołączona wiadomość:" }
+            blockquote style="margin: 0 0 16px; padding: 8px 16px; border-left: 3px solid #db2777; color: #4b5563;" {
+                @for line in message.lines() {
+                    (line) br;
+                }
+            }
+        }
+        p { "Twój kod:" }
+        p style="font-size: 22px; font-family: monospace; font-weight: bold; letter-spacing: 2px;" { (gift_card.code) }
+        p {
+            "Wpisz go w polu „Karta podarunkowa” w podsumowaniu zamówienia. "
+            "Kartą możesz płacić w częściach - niewykorzystane saldo zostaje na kolejne zakupy."
+        }
+        @if let Some(expires_at) = &gift_card.expires_at {
+            p { "Karta jest ważna do " strong { (format_date_long(expires_at)) } "." }
+        }
+        a href=(shop_link) { "Przejdź do sklepu" }
+    };
+
+    let resend = Resend::new(&app_state.resend_api_key);
+    let params = CreateEmailBaseOptions::new(
+        sender_formatted(app_state),
+        vec![gift_card.recipient_email.clone()],
+        "Karta podarunkowa - mess - all that vintage",
+    )
+    .with_html(&email_html_content.into_string());
+
+    resend.emails.send(params).await.map_err(|e| {
+        tracing::error!(
+            "Błąd API Resend przy wysyłce karty podarunkowej {}: {:?}",
+            gift_card.id,
+            e
+        );
+        AppError::InternalServerError("Błąd wysyłki e-maila".to_string())
+    })?;
+
+    Ok(())
+}
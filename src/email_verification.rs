@@ -0,0 +1,198 @@
+// src/email_verification.rs
+
+// Potwierdzanie adresu e-mail konta. Link w wiadomości zawiera losowy token zapisany w bazie
+// oraz podpis HMAC (token + konto + adres), więc nie da się go podrobić ani przenieść na inne konto.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::email_service::send_email_verification_email;
+use crate::errors::AppError;
+use crate::state::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Jak długo link potwierdzający jest ważny.
+const VERIFICATION_TOKEN_TTL_HOURS: i64 = 48;
+/// Minimalny odstęp między kolejnymi wiadomościami z linkiem dla jednego konta.
+pub const RESEND_COOLDOWN_MINUTES: i64 = 2;
+
+/// Wynik kliknięcia w link potwierdzający
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerificationOutcome {
+    Verified,
+    Expired,
+    Invalid,
+}
+
+impl VerificationOutcome {
+    /// Wartość parametru `status` strony z wynikiem potwierdzenia.
+    pub fn as_query_value(self) -> &'static str {
+        match self {
+            VerificationOutcome::Verified => "potwierdzony",
+            VerificationOutcome::Expired => "wygasl",
+            VerificationOutcome::Invalid => "nieprawidlowy",
+        }
+    }
+}
+
+fn signing_mac(secret: &str, token: Uuid, user_id: Uuid, email: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .expect("HMAC przyjmuje klucz dowolnej długości");
+    mac.update(format!("{}:{}:{}", token, user_id, email.to_lowercase()).as_bytes());
+    mac
+}
+
+fn link_signature(secret: &str, token: Uuid, user_id: Uuid, email: &str) -> String {
+    hex::encode(
+        signing_mac(secret, token, user_id, email)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+/// Porównanie podpisu w stałym czasie
+fn signature_matches(secret: &str, token: Uuid, user_id: Uuid, email: &str, sig: &str) -> bool {
+    hex::decode(sig).is_ok_and(|sig_bytes| {
+        signing_mac(secret, token, user_id, email)
+            .verify_slice(&sig_bytes)
+            .is_ok()
+    })
+}
+
+/// Czy konto ma potwierdzony adres e-mail.
+pub async fn is_email_verified(conn: &mut PgConnection, user_id: Uuid) -> Result<bool, AppError> {
+    let verified: Option<bool> =
+        sqlx::query_scalar("SELECT email_verified_at IS NOT NULL FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    Ok(verified.unwrap_or(true))
+}
+
+/// Tworzy nowy token (poprzednie linki konta przestają działać) i zwraca pełny link do wiadomości.
+async fn issue_verification_link(
+    conn: &mut PgConnection,
+    app_state: &AppState,
+    user_id: Uuid,
+    email: &str,
+) -> Result<String, AppError> {
+    sqlx::query("DELETE FROM verification_tokens WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let token = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO verification_tokens (token, user_id, email, expires_at) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(token)
+    .bind(user_id)
+    .bind(email)
+    .bind(Utc::now() + Duration::hours(VERIFICATION_TOKEN_TTL_HOURS))
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(format!(
+        "{}/api/auth/verify-email?token={}&sig={}",
+        app_state.public_base_url,
+        token,
+        link_signature(&app_state.jwt_secret, token, user_id, email)
+    ))
+}
+
+/// Wysyła wiadomość z linkiem potwierdzającym. Zwraca `false`, gdy poprzednia wiadomość
+/// poszła mniej niż `RESEND_COOLDOWN_MINUTES` temu (ochrona przed zasypaniem skrzynki).
+pub async fn send_verification_link(
+    app_state: &AppState,
+    user_id: Uuid,
+    email: &str,
+) -> Result<bool, AppError> {
+    let mut conn = app_state.db_pool.acquire().await?;
+
+    let recently_sent: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM verification_tokens WHERE user_id = $1 AND created_at > $2)",
+    )
+    .bind(user_id)
+    .bind(Utc::now() - Duration::minutes(RESEND_COOLDOWN_MINUTES))
+    .fetch_one(&mut *conn)
+    .await?;
+    if recently_sent {
+        return Ok(false);
+    }
+
+    let link = issue_verification_link(&mut conn, app_state, user_id, email).await?;
+    send_email_verification_email(app_state, email, &link).await?;
+    Ok(true)
+}
+
+/// Wysyła link w tle - rejestracja nie czeka na odpowiedź Resend.
+pub fn spawn_verification_email(app_state: Arc<AppState>, user_id: Uuid, email: String) {
+    let background_jobs = app_state.background_jobs.clone();
+    background_jobs.spawn(async move {
+        if let Err(e) = send_verification_link(&app_state, user_id, &email).await {
+            tracing::error!(
+                "Nie udało się wysłać linku potwierdzającego e-mail do {}: {:?}",
+                email,
+                e
+            );
+        }
+    });
+}
+
+/// Sprawdza token i podpis z linku i oznacza adres konta jako potwierdzony.
+pub async fn verify_email_token(
+    app_state: &AppState,
+    token: &str,
+    sig: &str,
+) -> Result<VerificationOutcome, AppError> {
+    let Ok(token) = Uuid::parse_str(token) else {
+        return Ok(VerificationOutcome::Invalid);
+    };
+
+    let mut tx = app_state.db_pool.begin().await?;
+    let row: Option<(Uuid, String, chrono::DateTime<Utc>, String)> = sqlx::query_as(
+        r#"
+            SELECT t.user_id, t.email, t.expires_at, u.email
+            FROM verification_tokens t
+            JOIN users u ON u.id = t.user_id
+            WHERE t.token = $1
+            FOR UPDATE OF t
+        "#,
+    )
+    .bind(token)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((user_id, token_email, expires_at, current_email)) = row else {
+        return Ok(VerificationOutcome::Invalid);
+    };
+    if !signature_matches(&app_state.jwt_secret, token, user_id, &token_email, sig)
+        || !token_email.eq_ignore_ascii_case(&current_email)
+    {
+        return Ok(VerificationOutcome::Invalid);
+    }
+    if expires_at <= Utc::now() {
+        return Ok(VerificationOutcome::Expired);
+    }
+
+    sqlx::query(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM verification_tokens WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    tracing::info!("Użytkownik {} potwierdził adres e-mail", user_id);
+    Ok(VerificationOutcome::Verified)
+}
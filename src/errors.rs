@@ -1,13 +1,14 @@
 use axum::{
     extract::multipart::MultipartError,
-    http::{HeaderMap, HeaderValue, StatusCode},
+    http::{HeaderMap, HeaderValue, StatusCode, header::RETRY_AFTER},
     response::{IntoResponse, Json, Response},
 };
 
-use maud::Markup;
+use maud::{Markup, html};
+use serde::Serialize;
 use serde_json::json;
+use std::collections::BTreeMap;
 use thiserror::Error;
-use validator::ValidationErrors;
 
 #[allow(dead_code)]
 #[derive(Debug, Error)]
@@ -19,7 +20,11 @@ pub enum AppError {
     NotFound,
 
     #[error("Błędy walidacji")]
-    ValidationError(#[from] ValidationErrors),
+    ValidationError(#[from] validator::ValidationErrors),
+
+    /// Błędy przypisane do konkretnych pól formularza; dla `/api/*` zwracane jako JSON
+    #[error("Błędy walidacji pól formularza")]
+    FieldValidation(ValidationErrors),
 
     #[error("Nieprawidłowe dane wejściowe: {0}")]
     UnprocessableEntity(String),
@@ -60,11 +65,141 @@ pub enum AppError {
     #[error("Wystąpił błąd z niedostępnm produktem")]
     UnprocessableEntityWithHtml(Markup),
 
+    /// Fragment formularza z błędami przy polach; nagłówki (np. `HX-Retarget`) wskazują, co podmienić
+    #[error("Błędy walidacji pól formularza")]
+    UnprocessableEntityWithFragment(Markup, HeaderMap),
+
     #[error("Konflikt: {0}")]
     ConflictWithHeaders(String, HeaderMap),
 
     #[error("Użytkownik nie jest zalogowany, przekierowanie")]
     RedirectToLogin,
+
+    /// Przekroczony limit żądań z jednego adresu IP; liczba sekund do ponownej próby
+    #[error("Zbyt wiele żądań")]
+    TooManyRequests(u64),
+}
+
+/// Błędy walidacji przypisane do pól formularza: nazwa pola -> komunikat.
+/// Dla każdego pola zapamiętujemy tylko pierwszy komunikat.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct ValidationErrors(BTreeMap<String, String>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dodaje błąd pola; jeśli pole ma już komunikat, zostaje poprzedni.
+    pub fn insert(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.entry(field.into()).or_insert_with(|| message.into());
+    }
+
+    pub fn get(&self, field: &str) -> Option<&String> {
+        self.0.get(field)
+    }
+
+    pub fn contains(&self, field: &str) -> bool {
+        self.0.contains_key(field)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Klasa obramowania pola - czerwona, gdy pole ma błąd.
+    pub fn border_class(&self, field: &str) -> &'static str {
+        if self.contains(field) {
+            "border-red-500"
+        } else {
+            "border-gray-300"
+        }
+    }
+
+    /// Miejsce na komunikat pod polem. Renderowane zawsze (także puste),
+    /// żeby odpowiedź z błędami mogła je podmienić przez `hx-swap-oob`.
+    pub fn field_error_maud(&self, field: &str) -> Markup {
+        field_error_slot_maud(field, self.get(field).map(String::as_str), false)
+    }
+
+    /// Odpowiedź 422 z przerenderowanym formularzem, który HTMX podmienia w `target`.
+    pub fn into_fragment(self, markup: Markup, target: &str) -> AppError {
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(target) {
+            headers.insert("HX-Retarget", value);
+        }
+        headers.insert("HX-Reswap", HeaderValue::from_static("outerHTML"));
+        AppError::UnprocessableEntityWithFragment(markup, headers)
+    }
+
+    /// Błąd dla formularza, którego nie przerenderowujemy w całości: żądania HTMX dostają
+    /// podsumowanie w `summary_target` i komunikaty przy polach (`hx-swap-oob`), pozostałe - JSON.
+    pub fn into_error(self, request_headers: &HeaderMap, summary_target: &str) -> AppError {
+        if !request_headers.contains_key("HX-Request") {
+            return AppError::FieldValidation(self);
+        }
+        let markup = html! {
+            div role="alert" class="p-3 bg-red-50 border border-red-200 rounded-md text-sm text-red-700" {
+                p ."font-semibold" { "Popraw zaznaczone pola:" }
+                ul class="list-disc list-inside mt-1" {
+                    @for (_, message) in self.iter() {
+                        li { (message) }
+                    }
+                }
+            }
+            @for (field, message) in self.iter() {
+                (field_error_slot_maud(field, Some(message), true))
+            }
+        };
+        let mut headers = HeaderMap::new();
+        if let Ok(value) = HeaderValue::from_str(summary_target) {
+            headers.insert("HX-Retarget", value);
+        }
+        headers.insert("HX-Reswap", HeaderValue::from_static("innerHTML"));
+        AppError::UnprocessableEntityWithFragment(markup, headers)
+    }
+}
+
+impl From<&validator::ValidationErrors> for ValidationErrors {
+    fn from(errors: &validator::ValidationErrors) -> Self {
+        let mut result = ValidationErrors::new();
+        for (field, field_errors) in errors.field_errors() {
+            let message = field_errors
+                .first()
+                .and_then(|e| e.message.as_ref())
+                .map_or_else(|| "Nieprawidłowa wartość.".to_string(), |m| m.to_string());
+            result.insert(field.to_string(), message);
+        }
+        result
+    }
+}
+
+fn field_error_slot_maud(field: &str, message: Option<&str>, oob: bool) -> Markup {
+    html! {
+        p id=(format!("{}-error", field)) class="field-error mt-1 text-xs text-red-600"
+          hx-swap-oob=[oob.then_some("true")] {
+            @if let Some(message) = message { (message) }
+        }
+    }
+}
+
+/// Fragment HTML dla odpowiedzi 429 - nadaje się do podmiany przez HTMX i czytelny bez JS.
+fn render_too_many_requests_maud(retry_after_secs: u64) -> Markup {
+    let minutes = retry_after_secs.div_ceil(60);
+    html! {
+        div role="alert" class="p-4 bg-red-50 border border-red-200 rounded-md text-sm text-red-700" {
+            p ."font-semibold" { "Zbyt wiele prób" }
+            p {
+                "Wykonano zbyt wiele żądań w krótkim czasie. Spróbuj ponownie za "
+                @if retry_after_secs < 60 { (retry_after_secs) " s." } @else { (minutes) " min." }
+            }
+        }
+    }
 }
 
 impl IntoResponse for AppError {
@@ -89,7 +224,19 @@ impl IntoResponse for AppError {
                         messages.push(msg);
                     }
                 }
-                (StatusCode::UNPROCESSABLE_ENTITY, messages.join("; "))
+                let fields = ValidationErrors::from(&errors);
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(json!({ "error": messages.join("; "), "fields": fields })),
+                )
+                    .into_response();
+            }
+            AppError::FieldValidation(fields) => {
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(json!({ "error": "Błędy walidacji", "fields": fields })),
+                )
+                    .into_response();
             }
             AppError::UnprocessableEntity(message) => (StatusCode::UNPROCESSABLE_ENTITY, message),
             AppError::EmailAlreadyExists(message) => (StatusCode::CONFLICT, message),
@@ -112,6 +259,14 @@ impl IntoResponse for AppError {
             AppError::UnprocessableEntityWithHtml(markup) => {
                 return (StatusCode::UNPROCESSABLE_ENTITY, markup.into_string()).into_response();
             }
+            AppError::UnprocessableEntityWithFragment(markup, headers) => {
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    headers,
+                    markup.into_string(),
+                )
+                    .into_response();
+            }
             AppError::ConflictWithHeaders(message, headers) => {
                 let mut response =
                     (StatusCode::CONFLICT, Json(json!({ "error": message}))).into_response();
@@ -123,6 +278,23 @@ impl IntoResponse for AppError {
                 headers.insert("Location", HeaderValue::from_static("/"));
                 return (StatusCode::SEE_OTHER, headers).into_response();
             }
+            AppError::TooManyRequests(retry_after_secs) => {
+                let mut headers = HeaderMap::new();
+                headers.insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+                // HTMX nie podmienia odpowiedzi 4xx - komunikat pokazujemy też jako toast
+                headers.insert(
+                    "HX-Trigger",
+                    HeaderValue::from_static(
+                        r#"{"showMessage": {"message": "Zbyt wiele prob. Odczekaj chwile i sprobuj ponownie.", "type": "error"}}"#,
+                    ),
+                );
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    headers,
+                    render_too_many_requests_maud(retry_after_secs),
+                )
+                    .into_response();
+            }
         };
 
         let body = Json(json!({ "error": error_message }));
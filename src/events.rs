@@ -0,0 +1,55 @@
+// src/events.rs
+
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{EventType, ShoppingCart};
+
+/// Dane nowego zdarzenia. Wypełniamy tylko te pola, które mają sens dla danego typu.
+#[derive(Debug, Default)]
+pub struct NewEvent {
+    pub user_id: Option<Uuid>,
+    pub guest_session_id: Option<Uuid>,
+    pub product_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub metadata: Option<Value>,
+}
+
+impl NewEvent {
+    /// Zdarzenie koszyka - klient identyfikowany po właścicielu koszyka (użytkownik lub gość).
+    pub fn for_cart(cart: &ShoppingCart, product_id: Uuid) -> Self {
+        NewEvent {
+            user_id: cart.user_id,
+            guest_session_id: cart.guest_session_id,
+            product_id: Some(product_id),
+            ..Default::default()
+        }
+    }
+}
+
+/// Zapisuje zdarzenie w tle. Analityka nie może spowolnić ani wywrócić żądania klienta,
+/// więc błąd zapisu jest tylko logowany.
+pub fn record_event(pool: &PgPool, event_type: EventType, event: NewEvent) {
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let result = sqlx::query(
+            r#"
+                INSERT INTO events (event_type, user_id, guest_session_id, product_id, order_id, metadata)
+                VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(event_type)
+        .bind(event.user_id)
+        .bind(event.guest_session_id)
+        .bind(event.product_id)
+        .bind(event.order_id)
+        .bind(event.metadata)
+        .execute(&pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Nie udało się zapisać zdarzenia {:?}: {:?}", event_type, e);
+        }
+    });
+}
@@ -0,0 +1,162 @@
+// src/faq.rs
+
+// Pytania FAQ z tabeli `faq_items`: kolejność i widoczność ustawiane w panelu. Z widocznych
+// pytań powstaje akordeon na /faq i dane strukturalne FAQPage (zob. `views::static_pages`),
+// więc każda zmiana musi unieważnić stronę "faq" w cache'u stron statycznych.
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::checkout::free_shipping_threshold;
+use crate::errors::AppError;
+use crate::models::MoveDirection;
+use crate::views::common::format_price_maud;
+
+pub const MAX_FAQ_QUESTION_LEN: usize = 300;
+pub const MAX_FAQ_ANSWER_LEN: usize = 5000;
+
+/// Znacznik w odpowiedzi zamieniany na aktualny próg darmowej dostawy
+pub const FREE_SHIPPING_PLACEHOLDER: &str = "{prog_darmowej_dostawy}";
+
+#[derive(Debug, Clone, FromRow)]
+pub struct FaqItem {
+    pub id: Uuid,
+    pub question: String,
+    pub answer: String,
+    pub position: i32,
+    pub visible: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FaqItem {
+    /// Odpowiedź do wyświetlenia klientom (z podstawionymi znacznikami)
+    pub fn rendered_answer(&self) -> String {
+        self.answer.replace(
+            FREE_SHIPPING_PLACEHOLDER,
+            &format_price_maud(free_shipping_threshold()),
+        )
+    }
+}
+
+/// Pytania w kolejności wyświetlania; `only_visible` dla strony sklepu
+pub async fn list_faq_items(pool: &PgPool, only_visible: bool) -> Result<Vec<FaqItem>, AppError> {
+    let items = sqlx::query_as::<_, FaqItem>(
+        r#"
+            SELECT id, question, answer, position, visible, updated_at
+            FROM faq_items
+            WHERE visible OR NOT $1
+            ORDER BY position, created_at
+        "#,
+    )
+    .bind(only_visible)
+    .fetch_all(pool)
+    .await?;
+    Ok(items)
+}
+
+/// Nowe pytanie trafia na koniec listy
+pub async fn create_faq_item(
+    pool: &PgPool,
+    question: &str,
+    answer: &str,
+    visible: bool,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            INSERT INTO faq_items (question, answer, visible, position)
+            VALUES ($1, $2, $3, (SELECT COALESCE(MAX(position), 0) + 1 FROM faq_items))
+        "#,
+    )
+    .bind(question)
+    .bind(answer)
+    .bind(visible)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn update_faq_item(
+    pool: &PgPool,
+    id: Uuid,
+    question: &str,
+    answer: &str,
+    visible: bool,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        r#"
+            UPDATE faq_items SET question = $2, answer = $3, visible = $4, updated_at = NOW()
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(question)
+    .bind(answer)
+    .bind(visible)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+pub async fn delete_faq_item(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM faq_items WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Zamienia pytanie miejscami z sąsiadem. Zwraca `false`, gdy pytanie jest już pierwsze/ostatnie.
+pub async fn move_faq_item(
+    pool: &PgPool,
+    id: Uuid,
+    direction: MoveDirection,
+) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let position: Option<i32> =
+        sqlx::query_scalar("SELECT position FROM faq_items WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let position = position.ok_or(AppError::NotFound)?;
+
+    let neighbour_sql = match direction {
+        MoveDirection::Up => {
+            "SELECT id, position FROM faq_items WHERE position < $1 ORDER BY position DESC LIMIT 1 FOR UPDATE"
+        }
+        MoveDirection::Down => {
+            "SELECT id, position FROM faq_items WHERE position > $1 ORDER BY position LIMIT 1 FOR UPDATE"
+        }
+    };
+    let neighbour: Option<(Uuid, i32)> = sqlx::query_as(neighbour_sql)
+        .bind(position)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some((neighbour_id, neighbour_position)) = neighbour else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"
+            UPDATE faq_items
+            SET position = CASE WHEN id = $1 THEN $4 ELSE $3 END, updated_at = NOW()
+            WHERE id IN ($1, $2)
+        "#,
+    )
+    .bind(id)
+    .bind(neighbour_id)
+    .bind(position)
+    .bind(neighbour_position)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
@@ -0,0 +1,213 @@
+// src/feeds.rs
+
+// Kanał Atom z nowościami (`/feed.xml`) - najnowsze dostępne produkty ze zdjęciem i ceną,
+// żeby klienci i agregatory mogli śledzić "Nowości" w czytniku RSS zamiast odświeżać stronę.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use maud::html;
+use quick_xml::se::to_string;
+use serde::Serialize;
+
+use crate::cache_stats::CacheName;
+use crate::errors::AppError;
+use crate::models::{Product, ProductStatus};
+use crate::state::AppState;
+use crate::views::common::{format_price_maud, transform_cloudinary_url};
+
+/// Kanał trzymamy w cache'u listingów - jest czyszczony przy każdej zmianie produktów,
+/// a TTL (10 min) ogranicza częstotliwość generowania przy częstym odpytywaniu przez czytniki.
+pub const ATOM_FEED_CACHE_KEY: &str = "atom_feed_nowosci";
+const FEED_ENTRIES_LIMIT: i64 = 50;
+const FEED_IMAGE_TRANSFORMATION: &str = "w_600,c_limit,f_jpg,q_auto";
+const FEED_TITLE: &str = "Nowości - mess - all that vintage";
+
+#[derive(Serialize)]
+#[serde(rename = "feed")]
+struct AtomFeed {
+    #[serde(rename = "@xmlns")]
+    xmlns: &'static str,
+    title: &'static str,
+    subtitle: &'static str,
+    id: String,
+    updated: String,
+    #[serde(rename = "link")]
+    links: Vec<AtomLink>,
+    author: AtomAuthor,
+    #[serde(rename = "entry")]
+    entries: Vec<AtomEntry>,
+}
+
+#[derive(Serialize)]
+struct AtomLink {
+    #[serde(rename = "@rel")]
+    rel: &'static str,
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    link_type: Option<&'static str>,
+    #[serde(rename = "@href")]
+    href: String,
+}
+
+#[derive(Serialize)]
+struct AtomAuthor {
+    name: &'static str,
+}
+
+#[derive(Serialize)]
+struct AtomCategory {
+    #[serde(rename = "@term")]
+    term: String,
+}
+
+#[derive(Serialize)]
+struct AtomSummary {
+    #[serde(rename = "@type")]
+    content_type: &'static str,
+    #[serde(rename = "$text")]
+    html: String,
+}
+
+#[derive(Serialize)]
+struct AtomEntry {
+    id: String,
+    title: String,
+    link: AtomLink,
+    published: String,
+    updated: String,
+    category: AtomCategory,
+    summary: AtomSummary,
+}
+
+/// Opis wpisu w HTML (zdjęcie, cena, rozmiar) - czytniki pokazują go jako podgląd produktu
+fn entry_summary_html(product: &Product) -> String {
+    let image = product
+        .images
+        .first()
+        .map(|image| transform_cloudinary_url(image, FEED_IMAGE_TRANSFORMATION));
+    html! {
+        @if let Some(image) = image {
+            p { img src=(image) alt=(product.name); }
+        }
+        p {
+            strong { (format_price_maud(product.effective_price())) }
+            @if let Some(size) = &product.size { " · rozmiar " (size) }
+            @if let Some(brand) = &product.brand { " · " (brand) }
+        }
+        p { (product.description) }
+    }
+    .into_string()
+}
+
+fn feed_entry(product: &Product, base_url: &str) -> AtomEntry {
+    AtomEntry {
+        id: format!("urn:uuid:{}", product.id),
+        title: product.name.clone(),
+        link: AtomLink {
+            rel: "alternate",
+            link_type: Some("text/html"),
+            href: format!("{}{}", base_url, product.public_path()),
+        },
+        published: product.created_at.to_rfc3339(),
+        updated: product.updated_at.to_rfc3339(),
+        category: AtomCategory {
+            term: product.category.to_string(),
+        },
+        summary: AtomSummary {
+            content_type: "html",
+            html: entry_summary_html(product),
+        },
+    }
+}
+
+/// Generuje kanał z `FEED_ENTRIES_LIMIT` najnowszych dostępnych produktów
+pub async fn generate_atom_feed(app_state: &AppState) -> Result<String, AppError> {
+    let base_url = &app_state.public_base_url;
+    let products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE status = $1 ORDER BY created_at DESC LIMIT $2",
+    )
+    .bind(ProductStatus::Available)
+    .bind(FEED_ENTRIES_LIMIT)
+    .fetch_all(&app_state.db_pool)
+    .await?;
+
+    // Kanał zmienia się, gdy dochodzi nowy produkt albo zmienia się któryś z widocznych
+    let updated = products
+        .iter()
+        .map(|product| product.updated_at)
+        .max()
+        .unwrap_or_else(chrono::Utc::now);
+    let feed = AtomFeed {
+        xmlns: "http://www.w3.org/2005/Atom",
+        title: FEED_TITLE,
+        subtitle: "Najnowsze perełki vintage i second hand",
+        id: format!("{}/nowosci", base_url),
+        updated: updated.to_rfc3339(),
+        links: vec![
+            AtomLink {
+                rel: "self",
+                link_type: Some("application/atom+xml"),
+                href: format!("{}/feed.xml", base_url),
+            },
+            AtomLink {
+                rel: "alternate",
+                link_type: Some("text/html"),
+                href: format!("{}/nowosci", base_url),
+            },
+        ],
+        author: AtomAuthor {
+            name: "mess - all that vintage",
+        },
+        entries: products
+            .iter()
+            .map(|product| feed_entry(product, base_url))
+            .collect(),
+    };
+
+    let mut xml_output = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string();
+    xml_output.push_str(&to_string(&feed).map_err(|e| {
+        AppError::InternalServerError(format!("Błąd podczas generowania kanału Atom: {}", e))
+    })?);
+    Ok(xml_output)
+}
+
+/// GET /feed.xml
+pub async fn atom_feed_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let cached_feed = app_state
+        .listing_fragment_cache
+        .get(ATOM_FEED_CACHE_KEY)
+        .await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::ListingFragments, cached_feed.is_some());
+
+    let feed = match cached_feed {
+        Some(feed) => feed,
+        None => {
+            let feed = generate_atom_feed(&app_state).await?;
+            app_state
+                .listing_fragment_cache
+                .insert(ATOM_FEED_CACHE_KEY.to_string(), feed.clone())
+                .await;
+            app_state
+                .cache_stats
+                .record_insert(CacheName::ListingFragments, ATOM_FEED_CACHE_KEY);
+            feed
+        }
+    };
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/atom+xml; charset=utf-8"),
+        )],
+        feed,
+    )
+        .into_response())
+}
@@ -1,7 +1,12 @@
 // src/filters.rs
-use crate::models::{Category, OrderStatus, ProductCondition, ProductGender};
-use chrono::{DateTime, Utc};
+use crate::date_format::{shop_local_to_utc, to_shop_time};
+use crate::models::{Category, OrderStatus, ProductCondition, ProductGender, ProductStatus};
+use crate::sales::{DISCOUNTED_SQL, EFFECTIVE_PRICE_SQL};
+use crate::search::push_search_condition;
+use crate::sizes::push_size_filter;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Deserializer, Serialize, de};
+use sqlx::{Postgres, QueryBuilder};
 use std::str::FromStr;
 
 const DEFAULT_PAGE_LIMIT: i64 = 8;
@@ -14,6 +19,9 @@ const MAX_ORDER_PAGE_LIMIT: i64 = 50;
 const DEFAULT_ORDER_SORT_BY: &str = "order_date";
 const DEFAULT_ORDER_SORT_ORDER: &str = "desc";
 
+const DEFAULT_SALES_RANGE_DAYS: i64 = 30;
+const MAX_SALES_RANGE_DAYS: i64 = 366;
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
@@ -48,6 +56,14 @@ pub struct ListingParams {
     pub price_max: Option<i64>,
     #[serde(default)]
     pub on_sale: Option<bool>,
+    #[serde(default)]
+    pub size: Option<String>,
+    #[serde(default)]
+    pub brand: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub material: Option<String>,
 
     //Sortowanie
     #[serde(default)]
@@ -104,6 +120,23 @@ impl ListingParams {
         self.on_sale.clone()
     }
 
+    /// Filtry atrybutów: pusta wartość z formularza oznacza brak filtra
+    pub fn size(&self) -> Option<&str> {
+        non_empty(&self.size)
+    }
+
+    pub fn brand(&self) -> Option<&str> {
+        non_empty(&self.brand)
+    }
+
+    pub fn color(&self) -> Option<&str> {
+        non_empty(&self.color)
+    }
+
+    pub fn material(&self) -> Option<&str> {
+        non_empty(&self.material)
+    }
+
     pub fn sort_by(&self) -> &str {
         self.sort_by.as_deref().unwrap_or(DEFAULT_SORT_BY)
     }
@@ -156,6 +189,10 @@ impl ListingParams {
         push_if_some!("price-min", &self.price_min);
         push_if_some!("price-max", &self.price_max);
         push_if_some!("on-sale", &self.on_sale);
+        push_if_some!("size", &self.size());
+        push_if_some!("brand", &self.brand());
+        push_if_some!("color", &self.color());
+        push_if_some!("material", &self.material());
         push_if_some!("sort-by", &self.sort_by.as_deref());
         push_if_some!("order", &self.order.as_deref());
         push_if_some!("search", &self.search.as_deref());
@@ -180,6 +217,19 @@ impl ListingParams {
         }
     }
 
+    /// Ustawione filtry atrybutów jako pary (parametr, wartość)
+    pub fn attribute_filters(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("size", self.size()),
+            ("brand", self.brand()),
+            ("color", self.color()),
+            ("material", self.material()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| (key, v)))
+        .collect()
+    }
+
     pub fn to_query_string_with_skips(&self, skip_params: &[&str]) -> String {
         let mut query_parts = Vec::new();
         if !skip_params.contains(&"limit") {
@@ -227,6 +277,11 @@ impl ListingParams {
                 query_parts.push(format!("on-sale={}", val));
             }
         }
+        for (key, value) in self.attribute_filters() {
+            if !skip_params.contains(&key) {
+                query_parts.push(format!("{}={}", key, urlencoding::encode(value)));
+            }
+        }
         if !skip_params.contains(&"sort_by") {
             if let Some(val) = &self.sort_by {
                 query_parts.push(format!("sort-by={}", val));
@@ -261,6 +316,10 @@ impl ListingParams {
             price_min: self.price_min,
             price_max: self.price_max,
             on_sale: self.on_sale.clone(),
+            size: self.size.clone(),
+            brand: self.brand.clone(),
+            color: self.color.clone(),
+            material: self.material.clone(),
             sort_by: self.sort_by.clone(),
             order: self.order.clone(),
             search: self.search.clone(),
@@ -300,6 +359,9 @@ impl ListingParams {
         if let Some(on_sale_val) = self.on_sale {
             query_parts.push(format!("on-sale={}", on_sale_val));
         }
+        for (key, value) in self.attribute_filters() {
+            query_parts.push(format!("{}={}", key, urlencoding::encode(value)));
+        }
         if let Some(source) = &self.source {
             query_parts.push(format!("source={}", source));
         }
@@ -317,6 +379,155 @@ impl ListingParams {
     }
 }
 
+/// Wymiar filtra listingu, dla którego panel filtrów pokazuje liczby produktów
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facet {
+    Category,
+    Condition,
+    Size,
+    Price,
+}
+
+/// Przedział cenowy w panelu filtrów (w groszach). `max` jest wyłączne, `None` - bez górnej granicy.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceBucket {
+    pub label: &'static str,
+    pub min: i64,
+    pub max: Option<i64>,
+}
+
+impl PriceBucket {
+    /// Granice w postaci parametrów `price-min`/`price-max` listingu (górna granica włącznie)
+    pub fn price_range(&self) -> (Option<i64>, Option<i64>) {
+        (Some(self.min), self.max.map(|max| max - 1))
+    }
+}
+
+pub const PRICE_BUCKETS: [PriceBucket; 4] = [
+    PriceBucket {
+        label: "do 50 zł",
+        min: 0,
+        max: Some(5000),
+    },
+    PriceBucket {
+        label: "50–100 zł",
+        min: 5000,
+        max: Some(10000),
+    },
+    PriceBucket {
+        label: "100–200 zł",
+        min: 10000,
+        max: Some(20000),
+    },
+    PriceBucket {
+        label: "od 200 zł",
+        min: 20000,
+        max: None,
+    },
+];
+
+/// Liczby produktów dla wartości filtrów przy bieżących parametrach listingu.
+/// Każdy wymiar liczony jest bez własnego filtra, więc liczba mówi, ile produktów
+/// będzie po wybraniu tej wartości.
+#[derive(Debug, Clone, Default)]
+pub struct FacetCounts {
+    pub categories: Vec<(Category, i64)>,
+    pub conditions: Vec<(ProductCondition, i64)>,
+    pub sizes: Vec<(String, i64)>,
+    /// Liczby w kolejności `PRICE_BUCKETS`
+    pub price_buckets: Vec<i64>,
+}
+
+impl FacetCounts {
+    pub fn category_count(&self, category: &Category) -> i64 {
+        self.categories
+            .iter()
+            .find(|(c, _)| c == category)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    pub fn condition_count(&self, condition: &ProductCondition) -> i64 {
+        self.conditions
+            .iter()
+            .find(|(c, _)| c == condition)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+/// Dokleja do zapytania o produkty klauzulę WHERE z filtrów listingu.
+/// `skip` pomija filtr jednego wymiaru - do liczenia wartości w panelu filtrów.
+pub fn push_product_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    params: &ListingParams,
+    skip: Option<Facet>,
+) {
+    builder.push(" WHERE TRUE");
+    if let Some(gender) = params.gender() {
+        builder.push(" AND gender = ").push_bind(gender);
+    }
+    if let Some(category) = params.category().filter(|_| skip != Some(Facet::Category)) {
+        builder.push(" AND category = ").push_bind(category);
+    }
+    if let Some(condition) = params
+        .condition()
+        .filter(|_| skip != Some(Facet::Condition))
+    {
+        builder.push(" AND condition = ").push_bind(condition);
+    }
+    match params.status.as_deref() {
+        Some("all") => {}
+        Some(status_str) => {
+            if let Ok(status_enum) = ProductStatus::from_str(status_str) {
+                builder.push(" AND status = ").push_bind(status_enum);
+            }
+        }
+        None => {
+            builder.push(" AND status IN ('Available', 'Reserved')");
+        }
+    }
+    if skip != Some(Facet::Price) {
+        // Filtr ceny działa na cenie po promocji - tej, którą klient widzi na karcie
+        if let Some(price_min) = params.price_min() {
+            builder
+                .push(format!(" AND {} >= ", EFFECTIVE_PRICE_SQL))
+                .push_bind(price_min);
+        }
+        if let Some(price_max) = params.price_max() {
+            builder
+                .push(format!(" AND {} <= ", EFFECTIVE_PRICE_SQL))
+                .push_bind(price_max);
+        }
+    }
+    // "Okazje" to produkty oznaczone ręcznie i te z trwającą zaplanowaną promocją
+    if let Some(on_sale_filter) = params.on_sale() {
+        builder
+            .push(format!(" AND {} = ", DISCOUNTED_SQL))
+            .push_bind(on_sale_filter);
+    }
+    // Atrybuty porównujemy bez rozróżniania wielkości liter ("M" = "m", "Levi's" = "levi's")
+    for (column, value) in params.attribute_filters() {
+        // Rozmiar dopasowujemy też po przeliczeniu z metki (FR "38" pasuje do filtra "M")
+        if column == "size" {
+            if skip != Some(Facet::Size) {
+                push_size_filter(builder, value);
+            }
+            continue;
+        }
+        builder
+            .push(format!(" AND lower({}) = lower(", column))
+            .push_bind(value.to_string())
+            .push(")");
+    }
+    if let Some(search_term) = params.search().filter(|s| !s.trim().is_empty()) {
+        builder.push(" AND ");
+        push_search_condition(builder, search_term.trim());
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
 fn deserialize_optional_enum_from_empty_string<'de, D, T>(
     deserializer: D,
 ) -> Result<Option<T>, D::Error>
@@ -384,12 +595,7 @@ impl OrderListingParams {
         self.date_from.as_ref().and_then(|s| {
             chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
                 .ok()
-                .map(|naive_date| {
-                    DateTime::from_naive_utc_and_offset(
-                        naive_date.and_hms_opt(0, 0, 0).unwrap(),
-                        Utc,
-                    )
-                })
+                .map(|naive_date| shop_local_to_utc(naive_date.and_hms_opt(0, 0, 0).unwrap()))
         })
     }
 
@@ -397,12 +603,7 @@ impl OrderListingParams {
         self.date_to.as_ref().and_then(|s| {
             chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
                 .ok()
-                .map(|naive_date| {
-                    DateTime::from_naive_utc_and_offset(
-                        naive_date.and_hms_opt(23, 59, 59).unwrap(),
-                        Utc,
-                    )
-                }) // Koniec dnia
+                .map(|naive_date| shop_local_to_utc(naive_date.and_hms_opt(23, 59, 59).unwrap())) // Koniec dnia
         })
     }
 
@@ -454,3 +655,55 @@ impl OrderListingParams {
         query_parts.join("&")
     }
 }
+
+/// Zakres dat panelu sprzedaży admina (`?date-from=YYYY-MM-DD&date-to=YYYY-MM-DD`).
+/// Domyślnie ostatnie 30 dni, łącznie z dzisiejszym. Daty są w czasie lokalnym sklepu.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct SalesDashboardParams {
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+}
+
+impl SalesDashboardParams {
+    fn parse_date(value: Option<&String>) -> Option<NaiveDate> {
+        value.and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+    }
+
+    /// Ostatni dzień zakresu (włącznie). Nie może wypadać w przyszłości.
+    pub fn date_to(&self) -> NaiveDate {
+        let today = to_shop_time(&Utc::now()).date_naive();
+        Self::parse_date(self.date_to.as_ref()).map_or(today, |date| date.min(today))
+    }
+
+    /// Pierwszy dzień zakresu. Zakres jest przycinany do roku, żeby wykresy dzienne pozostały czytelne.
+    pub fn date_from(&self) -> NaiveDate {
+        let date_to = self.date_to();
+        let earliest = date_to - chrono::Duration::days(MAX_SALES_RANGE_DAYS - 1);
+        Self::parse_date(self.date_from.as_ref())
+            .map_or(
+                date_to - chrono::Duration::days(DEFAULT_SALES_RANGE_DAYS - 1),
+                |date| date.min(date_to),
+            )
+            .max(earliest)
+    }
+
+    /// Zakres w UTC jako przedział `[od, do)` - koniec to północ po ostatnim dniu.
+    pub fn range_utc(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let from = shop_local_to_utc(self.date_from().and_hms_opt(0, 0, 0).unwrap());
+        let to = shop_local_to_utc(
+            (self.date_to() + chrono::Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+        );
+        (from, to)
+    }
+
+    pub fn to_query_string(&self) -> String {
+        format!(
+            "date-from={}&date-to={}",
+            self.date_from().format("%Y-%m-%d"),
+            self.date_to().format("%Y-%m-%d")
+        )
+    }
+}
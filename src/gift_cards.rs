@@ -0,0 +1,336 @@
+// src/gift_cards.rs
+
+// Karty podarunkowe: klient wybiera kwotę i adres obdarowanego, płaci (Przelewy24 albo
+// przelewem potwierdzanym w panelu), a po zaksięgowaniu wpłaty kod idzie e-mailem do
+// obdarowanego. W kasie karta jest formą płatności: pokrywa też dostawę, nie zmienia podstawy
+// VAT zamówienia, a niewykorzystane saldo zostaje na kolejne zakupy.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::email_service::send_gift_card_email;
+use crate::errors::AppError;
+use crate::state::AppState;
+
+/// Kwoty do wyboru w formularzu zakupu (w groszach)
+pub const GIFT_CARD_AMOUNTS: [i64; 5] = [5000, 10000, 20000, 30000, 50000];
+/// Karta jest ważna rok od opłacenia
+pub const GIFT_CARD_VALIDITY_MONTHS: i32 = 12;
+pub const MAX_GIFT_CARD_MESSAGE_LEN: usize = 500;
+
+const CODE_PREFIX: &str = "PREZENT";
+/// Jak w kampaniach kodów rabatowych - bez znaków łatwych do pomylenia (0/O, 1/I/L)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_GROUP_LEN: usize = 5;
+/// Kolizje z istniejącymi kodami są rzadkie - kilka prób wystarcza z zapasem
+const MAX_GENERATION_ROUNDS: usize = 5;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct GiftCard {
+    pub id: Uuid,
+    pub code: String,
+    pub initial_amount: i64,
+    pub balance: i64,
+    pub purchaser_email: String,
+    pub purchaser_user_id: Option<Uuid>,
+    pub recipient_email: String,
+    pub recipient_name: Option<String>,
+    pub message: Option<String>,
+    pub paid_at: Option<DateTime<Utc>>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl GiftCard {
+    pub fn normalize_code(raw: &str) -> String {
+        raw.trim().to_uppercase()
+    }
+
+    /// Tytuł przelewu za kartę - bez kodu, który ma poznać dopiero obdarowany
+    pub fn payment_reference(&self) -> String {
+        format!("KARTA-{}", self.id.simple().to_string()[..8].to_uppercase())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+}
+
+/// Powód, dla którego karty nie można użyć w kasie
+#[derive(Debug, Clone, PartialEq)]
+pub enum GiftCardRejection {
+    NotFound,
+    NotPaid,
+    Expired,
+    Empty,
+}
+
+impl GiftCardRejection {
+    /// Komunikat dla klienta wyświetlany pod polem karty w podsumowaniu zamówienia
+    pub fn message(&self) -> &'static str {
+        match self {
+            GiftCardRejection::NotFound => "Nie znaleziono karty podarunkowej o tym kodzie.",
+            GiftCardRejection::NotPaid => "Ta karta podarunkowa nie została jeszcze opłacona.",
+            GiftCardRejection::Expired => "Ta karta podarunkowa straciła ważność.",
+            GiftCardRejection::Empty => "Saldo tej karty podarunkowej zostało już wykorzystane.",
+        }
+    }
+}
+
+/// Dane z formularza zakupu karty
+pub struct NewGiftCard<'a> {
+    pub amount: i64,
+    pub purchaser_email: &'a str,
+    pub purchaser_user_id: Option<Uuid>,
+    pub recipient_email: &'a str,
+    pub recipient_name: Option<&'a str>,
+    pub message: Option<&'a str>,
+}
+
+fn random_code() -> String {
+    let mut rng = rand::rng();
+    let mut group = || -> String {
+        (0..CODE_GROUP_LEN)
+            .map(|_| CODE_ALPHABET[rng.random_range(0..CODE_ALPHABET.len())] as char)
+            .collect()
+    };
+    let first = group();
+    let second = group();
+    format!("{}-{}-{}", CODE_PREFIX, first, second)
+}
+
+/// Zakłada nieopłaconą kartę z unikalnym kodem. Karta zaczyna działać po `mark_gift_card_paid`.
+pub async fn create_gift_card(pool: &PgPool, card: &NewGiftCard<'_>) -> Result<GiftCard, AppError> {
+    for _ in 0..MAX_GENERATION_ROUNDS {
+        let created = sqlx::query_as::<_, GiftCard>(
+            r#"
+                INSERT INTO gift_cards (code, initial_amount, balance, purchaser_email,
+                                        purchaser_user_id, recipient_email, recipient_name, message)
+                VALUES ($1, $2, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT (code) DO NOTHING
+                RETURNING *
+            "#,
+        )
+        .bind(random_code())
+        .bind(card.amount)
+        .bind(card.purchaser_email.trim().to_lowercase())
+        .bind(card.purchaser_user_id)
+        .bind(card.recipient_email.trim().to_lowercase())
+        .bind(card.recipient_name)
+        .bind(card.message)
+        .fetch_optional(pool)
+        .await?;
+        if let Some(created) = created {
+            return Ok(created);
+        }
+    }
+    Err(AppError::InternalServerError(
+        "Nie udało się wygenerować unikalnego kodu karty podarunkowej.".to_string(),
+    ))
+}
+
+pub async fn find_gift_card(pool: &PgPool, id: Uuid) -> Result<Option<GiftCard>, AppError> {
+    let gift_card = sqlx::query_as::<_, GiftCard>("SELECT * FROM gift_cards WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(gift_card)
+}
+
+/// Karty od najnowszych - do panelu
+pub async fn list_gift_cards(pool: &PgPool) -> Result<Vec<GiftCard>, AppError> {
+    let gift_cards =
+        sqlx::query_as::<_, GiftCard>("SELECT * FROM gift_cards ORDER BY created_at DESC")
+            .fetch_all(pool)
+            .await?;
+    Ok(gift_cards)
+}
+
+/// Wyszukuje kartę po kodzie i sprawdza, czy można nią zapłacić.
+///
+/// Wiersz karty jest blokowany (`FOR UPDATE`), więc dwa równoległe zamówienia nie wydadzą
+/// tego samego salda dwa razy. Zewnętrzny `Result` niesie błędy bazy, wewnętrzny - powód odrzucenia.
+pub async fn find_usable_gift_card(
+    conn: &mut PgConnection,
+    raw_code: &str,
+) -> Result<Result<GiftCard, GiftCardRejection>, AppError> {
+    let code = GiftCard::normalize_code(raw_code);
+    let Some(gift_card) =
+        sqlx::query_as::<_, GiftCard>("SELECT * FROM gift_cards WHERE code = $1 FOR UPDATE")
+            .bind(&code)
+            .fetch_optional(&mut *conn)
+            .await?
+    else {
+        return Ok(Err(GiftCardRejection::NotFound));
+    };
+
+    if gift_card.paid_at.is_none() {
+        return Ok(Err(GiftCardRejection::NotPaid));
+    }
+    if gift_card.is_expired() {
+        return Ok(Err(GiftCardRejection::Expired));
+    }
+    if gift_card.balance <= 0 {
+        return Ok(Err(GiftCardRejection::Empty));
+    }
+    Ok(Ok(gift_card))
+}
+
+/// Zdejmuje `amount` z salda karty i zapisuje użycie przy zamówieniu
+pub async fn redeem_gift_card(
+    conn: &mut PgConnection,
+    gift_card_id: Uuid,
+    order_id: Uuid,
+    amount: i64,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "UPDATE gift_cards SET balance = balance - $2, updated_at = NOW() WHERE id = $1 AND balance >= $2",
+    )
+    .bind(gift_card_id)
+    .bind(amount)
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::Conflict(
+            "Saldo karty podarunkowej jest niewystarczające.".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        "INSERT INTO gift_card_redemptions (gift_card_id, order_id, amount) VALUES ($1, $2, $3)",
+    )
+    .bind(gift_card_id)
+    .bind(order_id)
+    .bind(amount)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Anulowane zamówienie: kwoty zapłacone kartami wracają na ich saldo (tylko raz)
+pub async fn restore_gift_card_redemptions(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            WITH restored AS (
+                UPDATE gift_card_redemptions SET restored_at = NOW()
+                WHERE order_id = $1 AND restored_at IS NULL
+                RETURNING gift_card_id, amount
+            )
+            UPDATE gift_cards g
+            SET balance = g.balance + r.amount, updated_at = NOW()
+            FROM (
+                SELECT gift_card_id, SUM(amount)::BIGINT AS amount FROM restored GROUP BY gift_card_id
+            ) r
+            WHERE g.id = r.gift_card_id
+        "#,
+    )
+    .bind(order_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Część zamówienia zwolniona z karty (np. po usunięciu pozycji) wraca na jej saldo.
+/// Zmniejszamy zapisane użycie karty, żeby późniejsze anulowanie nie zwróciło jej drugi raz.
+pub async fn restore_gift_card_amount(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+    amount: i64,
+) -> Result<(), AppError> {
+    if amount <= 0 {
+        return Ok(());
+    }
+    let redemption: Option<(Uuid, Uuid, i64)> = sqlx::query_as(
+        r#"
+            SELECT id, gift_card_id, amount FROM gift_card_redemptions
+            WHERE order_id = $1 AND restored_at IS NULL
+            ORDER BY created_at
+            LIMIT 1
+            FOR UPDATE
+        "#,
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((redemption_id, gift_card_id, redeemed)) = redemption else {
+        return Ok(());
+    };
+    let amount = amount.min(redeemed);
+
+    if amount == redeemed {
+        sqlx::query("UPDATE gift_card_redemptions SET restored_at = NOW() WHERE id = $1")
+            .bind(redemption_id)
+            .execute(&mut *conn)
+            .await?;
+    } else {
+        sqlx::query("UPDATE gift_card_redemptions SET amount = amount - $2 WHERE id = $1")
+            .bind(redemption_id)
+            .bind(amount)
+            .execute(&mut *conn)
+            .await?;
+    }
+    sqlx::query("UPDATE gift_cards SET balance = balance + $2, updated_at = NOW() WHERE id = $1")
+        .bind(gift_card_id)
+        .bind(amount)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Oznacza kartę jako opłaconą i ustawia datę ważności. Zwraca `None`, gdy karta była już
+/// opłacona (np. powtórzone powiadomienie z bramki) - wtedy nie wysyłamy kodu drugi raz.
+pub async fn mark_gift_card_paid(
+    conn: &mut PgConnection,
+    id: Uuid,
+) -> Result<Option<GiftCard>, AppError> {
+    let gift_card = sqlx::query_as::<_, GiftCard>(
+        r#"
+            UPDATE gift_cards
+            SET paid_at = NOW(), expires_at = NOW() + make_interval(months => $2), updated_at = NOW()
+            WHERE id = $1 AND paid_at IS NULL
+            RETURNING *
+        "#,
+    )
+    .bind(id)
+    .bind(GIFT_CARD_VALIDITY_MONTHS)
+    .fetch_optional(&mut *conn)
+    .await?;
+    Ok(gift_card)
+}
+
+/// Wysyła kod obdarowanemu w tle i zapisuje datę wysyłki
+pub fn deliver_gift_card(app_state: Arc<AppState>, gift_card: GiftCard) {
+    let background_jobs = app_state.background_jobs.clone();
+    background_jobs.spawn(async move {
+        if let Err(e) = send_gift_card_email(&app_state, &gift_card).await {
+            tracing::error!(
+                "Nie udało się wysłać karty podarunkowej {}: {:?}",
+                gift_card.id,
+                e
+            );
+            return;
+        }
+        let result =
+            sqlx::query("UPDATE gift_cards SET sent_at = NOW(), updated_at = NOW() WHERE id = $1")
+                .bind(gift_card.id)
+                .execute(&app_state.db_pool)
+                .await;
+        if let Err(e) = result {
+            tracing::warn!(
+                "Nie udało się zapisać wysyłki karty podarunkowej {}: {:?}",
+                gift_card.id,
+                e
+            );
+        }
+    });
+}
@@ -0,0 +1,384 @@
+// src/graphql.rs
+
+// Opcjonalne API GraphQL katalogu (`/api/graphql`, feature `graphql`) dla stron partnerów,
+// które chcą osadzić wybrane produkty bez osobnych endpointów REST. Filtry, sortowanie
+// i paginacja działają jak w `ListingParams`; uwierzytelnienie tym samym kluczem co `/api/v1`.
+// Sklep nie ma jeszcze kolekcji - wyselekcjonowany zestaw pobiera się przez `productsByIds`.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, InputObject, Object, Schema, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::extract::{Query, State};
+use axum::response::Html;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+
+use crate::api_keys::ApiClient;
+use crate::errors::AppError;
+use crate::filters::ListingParams;
+use crate::models::{Category, Product, ProductCondition, ProductGender, ProductStatus};
+use crate::public_api::{ImageDto, absolute_url, category_tree};
+use crate::state::AppState;
+use crate::views::common::format_price_maud;
+
+/// Limity chroniące bazę przed zbyt kosztownymi zapytaniami
+const MAX_QUERY_DEPTH: usize = 6;
+const MAX_QUERY_COMPLEXITY: usize = 500;
+/// Najwięcej produktów w jednym `productsByIds`
+const MAX_PRODUCTS_BY_IDS: usize = 50;
+
+pub type CatalogSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: Lazy<CatalogSchema> = Lazy::new(|| {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+});
+
+/// Błędy bazy i serwera nie trafiają do klienta - tylko do logów, jak w `AppError::into_response`.
+fn graphql_error(error: AppError) -> async_graphql::Error {
+    match error {
+        AppError::SqlxError(_) | AppError::InternalServerError(_) => {
+            tracing::error!("GraphQL: {}", error);
+            async_graphql::Error::new("Wystąpił wewnętrzny błąd serwera")
+        }
+        other => async_graphql::Error::new(other.to_string()),
+    }
+}
+
+/// Wartość z filtra (np. `"Damskie"`, `"Koszule"`) parsowana jak w parametrach URL listingu
+fn parse_filter_value<T: FromStr>(
+    field: &str,
+    value: Option<String>,
+) -> async_graphql::Result<Option<T>> {
+    match value.filter(|v| !v.trim().is_empty()) {
+        None => Ok(None),
+        Some(v) => T::from_str(v.trim()).map(Some).map_err(|_| {
+            async_graphql::Error::new(format!("Nieprawidłowa wartość pola {}: {}", field, v))
+        }),
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "LabeledValue")]
+pub struct LabeledValueObject {
+    /// Stabilny identyfikator, ten sam co w filtrach
+    pub value: String,
+    pub label: String,
+}
+
+impl LabeledValueObject {
+    fn new<T: AsRef<str> + std::fmt::Display>(value: T) -> Self {
+        LabeledValueObject {
+            value: value.as_ref().to_string(),
+            label: value.to_string(),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Price")]
+pub struct PriceObject {
+    /// Kwota w groszach
+    pub amount: i64,
+    pub currency: String,
+    pub formatted: String,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Image")]
+pub struct ImageObject {
+    pub thumbnail: String,
+    pub card: String,
+    pub mobile: String,
+    pub large: String,
+    pub original: String,
+}
+
+impl From<ImageDto> for ImageObject {
+    fn from(dto: ImageDto) -> Self {
+        ImageObject {
+            thumbnail: dto.thumbnail,
+            card: dto.card,
+            mobile: dto.mobile,
+            large: dto.large,
+            original: dto.original,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Product")]
+pub struct ProductObject {
+    pub id: Uuid,
+    pub slug: String,
+    pub url: String,
+    pub name: String,
+    pub description: String,
+    pub price: PriceObject,
+    pub on_sale: bool,
+    /// `false`, gdy produkt jest zarezerwowany w czyimś koszyku
+    pub available: bool,
+    pub gender: LabeledValueObject,
+    pub category: LabeledValueObject,
+    pub condition: LabeledValueObject,
+    pub size: Option<String>,
+    pub brand: Option<String>,
+    pub color: Option<String>,
+    pub material: Option<String>,
+    pub images: Vec<ImageObject>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ProductObject {
+    fn new(app_state: &AppState, product: Product) -> Self {
+        ProductObject {
+            url: absolute_url(app_state, &product.public_path()),
+            images: product
+                .images
+                .iter()
+                .map(|url| ImageDto::from_url(url).into())
+                .collect(),
+            id: product.id,
+            slug: product.slug,
+            name: product.name,
+            description: product.description,
+            price: PriceObject {
+                amount: product.effective_price(),
+                currency: "PLN".to_string(),
+                formatted: format_price_maud(product.effective_price()),
+            },
+            on_sale: product.on_sale,
+            available: product.status == ProductStatus::Available,
+            gender: LabeledValueObject::new(product.gender),
+            category: LabeledValueObject::new(product.category),
+            condition: LabeledValueObject::new(product.condition),
+            size: product.size,
+            brand: product.brand,
+            color: product.color,
+            material: product.material,
+            created_at: product.created_at,
+            updated_at: product.updated_at,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ProductPage")]
+pub struct ProductPageObject {
+    pub total_items: i64,
+    pub total_pages: i64,
+    pub current_page: i64,
+    pub per_page: i64,
+    pub items: Vec<ProductObject>,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "CategoryNode")]
+pub struct CategoryNodeObject {
+    pub category: LabeledValueObject,
+    pub url: String,
+    pub product_count: i64,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "GenderNode")]
+pub struct GenderNodeObject {
+    pub gender: LabeledValueObject,
+    pub url: String,
+    pub product_count: i64,
+    pub categories: Vec<CategoryNodeObject>,
+}
+
+/// Filtry listingu - te same nazwy i wartości co parametry `/api/v1/products`
+#[derive(InputObject, Default)]
+pub struct ProductFilter {
+    pub gender: Option<String>,
+    pub category: Option<String>,
+    pub condition: Option<String>,
+    /// W groszach
+    pub price_min: Option<i64>,
+    pub price_max: Option<i64>,
+    pub on_sale: Option<bool>,
+    pub size: Option<String>,
+    pub brand: Option<String>,
+    pub color: Option<String>,
+    pub material: Option<String>,
+    pub search: Option<String>,
+}
+
+impl ProductFilter {
+    fn into_listing_params(
+        self,
+        sort_by: Option<String>,
+        order: Option<String>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<ListingParams> {
+        Ok(ListingParams {
+            gender: parse_filter_value::<ProductGender>("gender", self.gender)?,
+            category: parse_filter_value::<Category>("category", self.category)?,
+            condition: parse_filter_value::<ProductCondition>("condition", self.condition)?,
+            price_min: self.price_min,
+            price_max: self.price_max,
+            on_sale: self.on_sale,
+            size: self.size,
+            brand: self.brand,
+            color: self.color,
+            material: self.material,
+            search: self.search,
+            sort_by,
+            order,
+            limit,
+            offset,
+            // Zawsze tylko produkty widoczne w sklepie
+            status: None,
+            ..ListingParams::default()
+        })
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Lista produktów z filtrami, sortowaniem (`name`, `price`, `created_at`, `popularity`) i paginacją
+    async fn products(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<ProductFilter>,
+        sort_by: Option<String>,
+        order: Option<String>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<ProductPageObject> {
+        let app_state = ctx.data::<Arc<AppState>>()?;
+        let params = filter
+            .unwrap_or_default()
+            .into_listing_params(sort_by, order, limit, offset)?;
+        let page = crate::handlers::list_products(State(app_state.clone()), Query(params))
+            .await
+            .map_err(graphql_error)?
+            .0;
+
+        Ok(ProductPageObject {
+            total_items: page.total_items,
+            total_pages: page.total_pages,
+            current_page: page.current_page,
+            per_page: page.per_page,
+            items: page
+                .data
+                .into_iter()
+                .map(|product| ProductObject::new(app_state, product))
+                .collect(),
+        })
+    }
+
+    /// Produkt po ID albo slugu; `null`, gdy nie istnieje lub nie jest już w sprzedaży
+    async fn product(
+        &self,
+        ctx: &Context<'_>,
+        id: Option<Uuid>,
+        slug: Option<String>,
+    ) -> async_graphql::Result<Option<ProductObject>> {
+        let app_state = ctx.data::<Arc<AppState>>()?;
+        let product = match (id, slug) {
+            (Some(id), _) => sqlx::query_as::<_, Product>(
+                "SELECT * FROM products WHERE id = $1 AND status IN ('Available', 'Reserved')",
+            )
+            .bind(id)
+            .fetch_optional(&app_state.db_pool)
+            .await
+            .map_err(|e| graphql_error(e.into()))?,
+            (None, Some(slug)) => sqlx::query_as::<_, Product>(
+                "SELECT * FROM products WHERE slug = $1 AND status IN ('Available', 'Reserved')",
+            )
+            .bind(slug)
+            .fetch_optional(&app_state.db_pool)
+            .await
+            .map_err(|e| graphql_error(e.into()))?,
+            (None, None) => return Err(async_graphql::Error::new("Podaj id albo slug produktu.")),
+        };
+        Ok(product.map(|product| ProductObject::new(app_state, product)))
+    }
+
+    /// Wyselekcjonowany zestaw produktów w podanej kolejności; pomija produkty niedostępne
+    async fn products_by_ids(
+        &self,
+        ctx: &Context<'_>,
+        ids: Vec<Uuid>,
+    ) -> async_graphql::Result<Vec<ProductObject>> {
+        if ids.len() > MAX_PRODUCTS_BY_IDS {
+            return Err(async_graphql::Error::new(format!(
+                "Można pobrać najwyżej {} produktów naraz.",
+                MAX_PRODUCTS_BY_IDS
+            )));
+        }
+        let app_state = ctx.data::<Arc<AppState>>()?;
+        let products = sqlx::query_as::<_, Product>(
+            r#"
+            SELECT p.* FROM products p
+            JOIN UNNEST($1::uuid[]) WITH ORDINALITY AS selected(id, position) ON selected.id = p.id
+            WHERE p.status IN ('Available', 'Reserved')
+            ORDER BY selected.position
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&app_state.db_pool)
+        .await
+        .map_err(|e| graphql_error(e.into()))?;
+        Ok(products
+            .into_iter()
+            .map(|product| ProductObject::new(app_state, product))
+            .collect())
+    }
+
+    /// Drzewo płeć → kategorie z liczbą dostępnych produktów
+    async fn categories(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<GenderNodeObject>> {
+        let app_state = ctx.data::<Arc<AppState>>()?;
+        let tree = category_tree(app_state).await.map_err(graphql_error)?;
+        Ok(tree
+            .into_iter()
+            .map(|node| GenderNodeObject {
+                gender: LabeledValueObject::new(node.gender.value),
+                url: node.url,
+                product_count: node.product_count,
+                categories: node
+                    .categories
+                    .into_iter()
+                    .map(|category| CategoryNodeObject {
+                        category: LabeledValueObject::new(category.category.value),
+                        url: category.url,
+                        product_count: category.product_count,
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+/// POST /api/graphql
+pub async fn graphql_handler(
+    State(app_state): State<Arc<AppState>>,
+    client: ApiClient,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    tracing::debug!("GraphQL ({}): zapytanie", client.name);
+    SCHEMA
+        .execute(request.into_inner().data(app_state))
+        .await
+        .into()
+}
+
+/// GET /api/graphql - GraphiQL do przeglądania schematu (zapytania i tak wymagają klucza)
+pub async fn graphiql_handler() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/api/graphql").finish())
+}
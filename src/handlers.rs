@@ -1,32 +1,169 @@
 use axum::http::HeaderValue;
 // src/handlers.rs
-use axum::response::IntoResponse;
+use axum::body::Body;
+use axum::http::header;
+use axum::response::{IntoResponse, Redirect};
 use axum::{Form, Json};
 use axum::{
     extract::{Multipart, Path, Query, State},
     http::{HeaderMap, StatusCode},
 };
 use axum_extra::TypedHeader;
-use axum_extra::extract::cookie::{Cookie, SameSite};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
 use chrono::{Duration, Utc};
 use maud::{Markup, html};
+use serde::Deserialize;
 use serde_json::{Value, json};
-use sqlx::{Postgres, QueryBuilder};
+use sqlx::{PgConnection, Postgres, QueryBuilder};
 use time;
 
-use crate::cart_utils::build_cart_details_response;
-use crate::cloudinary::{delete_image_from_cloudinary, extract_public_id_from_url};
+use crate::api_keys::{create_api_key, list_api_keys, revoke_api_key};
+use crate::cache_stats::{CacheName, purge_all, purge_key, purge_matching};
+use crate::care_instructions::{
+    care_instructions_for_order, delete_care_instruction, save_care_instruction,
+};
+use crate::cart_utils::{build_cart_details_response, get_cart_details};
+use crate::checkout::{
+    CheckoutStep, find_shipping_option, mark_draft_submitted, step_for_field,
+    validate_checkout_form,
+};
+use crate::client_ip::ClientIp;
+use crate::cloudinary::{
+    HOMEPAGE_ASSET_FOLDER, create_background_removed_copy, delete_image_from_cloudinary,
+    extract_public_id_from_url, fetch_image_tags, product_asset_folder,
+};
+use crate::cloudinary_maintenance::{
+    cleanup_orphaned_product_images, migrate_product_images_to_folders,
+};
+use crate::cms::{
+    MAX_STATIC_PAGE_BODY_LEN, MAX_STATIC_PAGE_TITLE_LEN, delete_static_page, save_static_page,
+    static_page_cache_key,
+};
+use crate::complaints::{
+    MAX_COMPLAINT_DESCRIPTION_LEN, MAX_COMPLAINT_PHOTOS, complaint_window_open, create_complaint,
+    update_complaint_status,
+};
+use crate::coupon_campaigns::{NewCampaign, campaign_codes_csv, create_campaign};
+use crate::coupons::{find_applicable_coupon, record_coupon_redemption};
+use crate::customer_profiles::{
+    MAX_CUSTOMER_NOTE_LEN, add_customer_note, delete_customer_note, fetch_customer_profile,
+};
+use crate::customer_segments::{
+    CustomerSegment, add_customer_tag, customer_search, emails_csv, list_customers,
+    remove_customer_tag,
+};
+use crate::date_format::{parse_datetime_input, shop_local_to_utc};
+use crate::description_assistant::stream_description_draft;
+use crate::duplicates::find_probable_duplicates;
 #[allow(unused_imports)]
-use crate::email_service::{send_order_confirmation_email, send_password_reset_email};
-use crate::errors::AppError;
-use crate::filters::{ListingParams, OrderListingParams};
-use crate::htmx_handlers::{
-    render_admin_product_list_row_maud, render_checkout_error_page_maud, render_thank_you_page_maud,
+use crate::email_service::{
+    resolve_order_recipient_email, send_complaint_status_email, send_order_confirmation_email,
+    send_order_item_removed_email, send_order_status_email, send_password_reset_email,
+    send_return_status_email,
 };
+use crate::email_verification::{
+    is_email_verified, send_verification_link, spawn_verification_email, verify_email_token,
+};
+use crate::errors::{AppError, ValidationErrors};
+use crate::events::{NewEvent, record_event};
+use crate::faq::{
+    MAX_FAQ_ANSWER_LEN, MAX_FAQ_QUESTION_LEN, create_faq_item, delete_faq_item, move_faq_item,
+    update_faq_item,
+};
+use crate::filters::{ListingParams, OrderListingParams, push_product_filters};
+use crate::gift_cards::{
+    GIFT_CARD_AMOUNTS, MAX_GIFT_CARD_MESSAGE_LEN, NewGiftCard, create_gift_card, deliver_gift_card,
+    find_gift_card, find_usable_gift_card, mark_gift_card_paid, redeem_gift_card,
+    restore_gift_card_amount,
+};
+use crate::holds::{cancel_hold, create_hold};
+use crate::homepage::{
+    HeroUpdate, MAX_HERO_IMAGE_BYTES, MAX_HERO_TEXT_LEN, add_featured_product,
+    find_product_by_reference, invalidate_homepage_sections, is_valid_hero_link,
+    move_featured_product, move_homepage_section, remove_featured_product, save_carousel_section,
+    save_hero,
+};
+use crate::image_audit::run_image_quality_audit;
+use crate::image_hash::{perceptual_hashes, store_image_hashes};
+use crate::image_tagging::{AttributeSuggestions, suggestions_from_tags};
+use crate::impersonation::{
+    IMPERSONATION_MINUTES, create_impersonation_token, impersonation_cookie,
+    impersonation_from_headers,
+};
+use crate::inpost::{
+    InpostPoint, create_locker_shipment, fetch_label_pdf, fetch_point, fetch_tracking_number,
+    search_points,
+};
+use crate::invoices::{find_invoice_for_order, invoice_filename, invoice_pdf_for_order};
+use crate::jobs::{find_job, run_job};
+use crate::login_lockout::{
+    AccountLockout, active_lockout, normalize_login_email, record_login_attempt,
+    register_failed_login, spawn_lockout_email, unlock_account,
+};
+use crate::merchant_feed::invalidate_merchant_feed;
 use crate::middleware::OptionalTokenClaims;
 use crate::models::Product;
 use crate::models::*;
-use crate::pagination::{PaginatedOrdersResponse, PaginatedProductsResponse};
+use crate::order_numbers::next_order_number;
+use crate::pagination::{PaginatedOrdersResponse, PaginatedProductsResponse, total_pages};
+use crate::payments::{
+    start_gift_card_payment, start_przelewy24_payment, verify_notification_sign,
+    verify_przelewy24_transaction,
+};
+use crate::permissions::Permission;
+use crate::plural::pluralize;
+use crate::price_history::record_price_change;
+use crate::product_views::push_popularity_score;
+use crate::redirects::{
+    MAX_REDIRECT_PATH_LEN, REDIRECT_STATUS_CODES, create_redirect, delete_redirect,
+    is_valid_source_path, is_valid_target, normalize_redirect_path, record_moved_path,
+};
+use crate::repo::{self, carts::CartOwner};
+use crate::reservations::{
+    ReservationOutcome, clear_product_reservations, release_product_reservation,
+    reserve_product_for_cart, reserved_product_ids_for_cart, transfer_cart_reservations,
+};
+use crate::return_labels::purchase_return_label;
+use crate::returns::{
+    MAX_RETURN_REASON_LEN, ReturnDetails, create_return_request, decide_return,
+    normalize_bank_account, return_deadline,
+};
+use crate::reviews::{MAX_REVIEW_CONTENT_LEN, create_review, moderate_review};
+use crate::risk::assess_and_store_order_risk;
+use crate::sales::EFFECTIVE_PRICE_SQL;
+use crate::sales_register::{
+    fetch_sales_register, parse_register_month, sales_register_csv, sales_register_filename,
+};
+use crate::search::push_search_rank;
+use crate::security_headers::CspNonce;
+use crate::services::{record_order_status_change, transition_order_status};
+use crate::sitemap_generator::notify_search_engines;
+use crate::sizes::{delete_size_mapping, save_size_mapping};
+use crate::slugs::{is_slug_taken, slugify, unique_product_slug};
+use crate::thank_you_cards::{get_or_issue_thank_you_code, render_thank_you_card};
+use crate::two_factor::{
+    CHALLENGE_COOKIE, begin_enrolment, challenge_cookie, confirm_enrolment, create_challenge,
+    disable_two_factor, otpauth_uri, regenerate_recovery_codes, requires_second_step,
+    two_factor_status, verify_challenge, verify_second_factor,
+};
+use crate::user_management::{
+    find_managed_user, is_account_disabled, set_account_disabled, set_user_role,
+};
+use crate::vat::{OrderVat, refund_vat_breakdown, removed_item_gross, split_removed_item_gross};
+use crate::views::{
+    account::{
+        render_customer_complaint_maud, render_customer_return_maud, render_customer_review_maud,
+        render_recovery_codes_maud, render_two_factor_section_maud, render_two_factor_setup_maud,
+    },
+    admin::{
+        render_admin_complaint_card_maud, render_admin_product_list_row_maud,
+        render_admin_return_card_maud, render_admin_review_card_maud, render_api_keys_panel_maud,
+        render_duplicate_warning_maud, render_product_bulk_result_maud,
+    },
+    cart::{checkout_step_errors, render_checkout_error_page_maud, render_thank_you_page_maud},
+    static_pages::built_in_page,
+};
+use crate::wishlist::notify_price_drop;
 use crate::{
     auth::{create_jwt, hash_password, verify_password},
     cloudinary::upload_image_to_cloudinary,
@@ -34,12 +171,13 @@ use crate::{
 };
 use crate::{
     auth_models::{LoginPayload, RegistrationPayload, TokenClaims},
-    models::{Order, OrderStatus, ProductGender, ProductStatus, Role, User},
+    models::{Order, OrderStatus, ProductGender, ProductStatus, Role},
 };
 use futures::future::try_join_all;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use strum::IntoEnumIterator;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -48,7 +186,11 @@ pub async fn get_product_details(
     Path(product_id): Path<Uuid>,
 ) -> Result<Json<Product>, AppError> {
     // KROK 1: Sprawdź cache
-    if let Some(product) = app_state.product_cache.get(&product_id).await {
+    let cached_product = app_state.product_cache.get(&product_id).await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::Products, cached_product.is_some());
+    if let Some(product) = cached_product {
         tracing::info!("Cache HIT dla produktu o ID: {}", product_id);
         return Ok(Json(product));
     }
@@ -59,21 +201,19 @@ pub async fn get_product_details(
         product_id
     );
 
-    let product_result = sqlx::query_as::<_, Product>(r#"SELECT * FROM products WHERE id = $1"#)
-        .bind(product_id)
-        .fetch_one(&app_state.db_pool)
-        .await;
-
-    match product_result {
-        Ok(product) => {
+    match repo::products::find_by_id(&app_state.db_pool, product_id).await {
+        Ok(Some(product)) => {
             // KROK 3: Zapisz pobrany produkt w cache'u na przyszłość
             app_state
                 .product_cache
                 .insert(product.id, product.clone())
                 .await;
+            app_state
+                .cache_stats
+                .record_insert(CacheName::Products, product.id);
             Ok(Json(product))
         }
-        Err(sqlx::Error::RowNotFound) => {
+        Ok(None) => {
             tracing::warn!("Nie znaleziono produktu o ID: {}", product_id);
             Err(AppError::NotFound)
         }
@@ -83,7 +223,7 @@ pub async fn get_product_details(
                 product_id,
                 e
             );
-            Err(AppError::from(e))
+            Err(e)
         }
     }
 }
@@ -103,76 +243,43 @@ pub async fn list_products(
     let mut query_builder: QueryBuilder<Postgres> =
         QueryBuilder::new("SELECT *, COUNT(*) OVER() as total_count FROM products");
 
-    let mut conditions_added = false;
-    let mut append_where_or_and = |builder: &mut QueryBuilder<Postgres>| {
-        if !conditions_added {
-            builder.push(" WHERE ");
-            conditions_added = true;
-        } else {
-            builder.push(" AND ");
-        }
-    };
-
     // --- KROK 2: Dodajemy wszystkie filtry (klauzule WHERE) ---
-    if let Some(gender) = params.gender() {
-        append_where_or_and(&mut query_builder);
-        query_builder.push("gender = ").push_bind(gender);
-    }
-    if let Some(category) = params.category() {
-        append_where_or_and(&mut query_builder);
-        query_builder.push("category = ").push_bind(category);
-    }
-    if let Some(condition) = params.condition() {
-        append_where_or_and(&mut query_builder);
-        query_builder.push("condition = ").push_bind(condition);
-    }
-    match params.status.as_deref() {
-        Some("all") => {}
-        Some(status_str) => {
-            if let Ok(status_enum) = ProductStatus::from_str(status_str) {
-                append_where_or_and(&mut query_builder);
-                query_builder.push("status = ").push_bind(status_enum);
-            }
+    push_product_filters(&mut query_builder, &params, None);
+    let search_term = params.search().filter(|s| !s.trim().is_empty());
+
+    // --- KROK 3: Dodajemy sortowanie i paginację ---
+    // Przy wyszukiwaniu bez jawnie wybranego sortowania najtrafniejsze wyniki są pierwsze
+    match (&search_term, params.sort_by.is_none()) {
+        (Some(search_term), true) => {
+            query_builder.push(" ORDER BY ");
+            push_search_rank(&mut query_builder, search_term.trim());
+            query_builder.push(" DESC, id ASC");
         }
-        None => {
-            append_where_or_and(&mut query_builder);
-            query_builder.push("status IN ('Available', 'Reserved')");
+        // Najpopularniejsze domyślnie od góry - rosnąco tylko przy jawnym `order=asc`
+        _ if params.sort_by() == "popularity" => {
+            let order = if params.order.is_none() {
+                "desc"
+            } else {
+                params.order()
+            };
+            query_builder.push(" ORDER BY ");
+            push_popularity_score(&mut query_builder);
+            query_builder.push(format!(" {}, created_at DESC, id ASC", order));
+        }
+        _ => {
+            let sort_by_column = match params.sort_by() {
+                "price" => EFFECTIVE_PRICE_SQL,
+                "created_at" => "created_at",
+                // "name" i nieznane wartości
+                _ => "name",
+            };
+            query_builder.push(format!(
+                " ORDER BY {} {}, id ASC",
+                sort_by_column,
+                params.order()
+            ));
         }
     }
-    if let Some(price_min) = params.price_min() {
-        append_where_or_and(&mut query_builder);
-        query_builder.push("price >= ").push_bind(price_min);
-    }
-    if let Some(price_max) = params.price_max() {
-        append_where_or_and(&mut query_builder);
-        query_builder.push("price <= ").push_bind(price_max);
-    }
-    if let Some(on_sale_filter) = params.on_sale() {
-        append_where_or_and(&mut query_builder);
-        query_builder.push("on_sale = ").push_bind(on_sale_filter);
-    }
-    if let Some(search_term) = params.search() {
-        append_where_or_and(&mut query_builder);
-        let like_pattern = format!("%{}%", search_term);
-        query_builder
-            .push("(name ILIKE ")
-            .push_bind(like_pattern.clone())
-            .push(" OR description ILIKE ")
-            .push_bind(like_pattern)
-            .push(")");
-    }
-
-    // --- KROK 3: Dodajemy sortowanie i paginację ---
-    let sort_by_column = match params.sort_by() {
-        "price" => "price",
-        "created_at" => "created_at",
-        "name" | _ => "name",
-    };
-    query_builder.push(format!(
-        " ORDER BY {} {}, id ASC",
-        sort_by_column,
-        params.order()
-    ));
     query_builder.push(" LIMIT ").push_bind(limit);
     query_builder.push(" OFFSET ").push_bind(offset);
 
@@ -191,6 +298,7 @@ pub async fn list_products(
         .map(|p_wc| Product {
             id: p_wc.id,
             name: p_wc.name,
+            slug: p_wc.slug,
             description: p_wc.description,
             price: p_wc.price,
             gender: p_wc.gender,
@@ -199,17 +307,23 @@ pub async fn list_products(
             status: p_wc.status,
             images: p_wc.images,
             on_sale: p_wc.on_sale,
+            size: p_wc.size,
+            brand: p_wc.brand,
+            color: p_wc.color,
+            material: p_wc.material,
+            meta_title: p_wc.meta_title,
+            meta_description: p_wc.meta_description,
+            og_image: p_wc.og_image,
+            sale_price: p_wc.sale_price,
+            sale_starts_at: p_wc.sale_starts_at,
+            sale_ends_at: p_wc.sale_ends_at,
             created_at: p_wc.created_at,
             updated_at: p_wc.updated_at,
         })
         .collect();
 
     // --- KROK 5: Obliczamy paginację i zwracamy odpowiedź ---
-    let total_pages = if total_items == 0 {
-        0
-    } else {
-        (total_items as f64 / limit as f64).ceil() as i64
-    };
+    let total_pages = total_pages(total_items, limit);
     let current_page = (offset as f64 / limit as f64).floor() as i64 + 1;
 
     let response = PaginatedProductsResponse {
@@ -223,16 +337,191 @@ pub async fn list_products(
     Ok(Json(response))
 }
 
+/// Liczba produktów spełniających filtry listingu, bez pobierania wierszy - dla siatki
+/// wziętej z `listing_fragment_cache`, gdy potrzebna jest liczba stron
+pub async fn count_products(app_state: &AppState, params: &ListingParams) -> Result<i64, AppError> {
+    let mut query_builder: QueryBuilder<Postgres> =
+        QueryBuilder::new("SELECT COUNT(*) FROM products");
+    push_product_filters(&mut query_builder, params, None);
+    let count: i64 = query_builder
+        .build_query_scalar()
+        .fetch_one(&app_state.db_pool)
+        .await?;
+    Ok(count)
+}
+
+/// Kontener na komunikaty formularza produktu w panelu admina
+const PRODUCT_FORM_MESSAGES_TARGET: &str = "#product-form-messages";
+
+/// Sprawdza pola tekstowe formularza produktu. Przy tworzeniu (`require_all`) brak pola
+/// jest błędem; przy edycji sprawdzamy tylko przesłane pola.
+fn validate_product_fields(
+    text_fields: &HashMap<String, String>,
+    require_all: bool,
+) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    let required = [
+        ("name", "Podaj nazwę produktu."),
+        ("description", "Podaj opis produktu."),
+        ("price", "Podaj cenę."),
+        ("gender", "Wybierz płeć."),
+        ("condition", "Wybierz stan."),
+        ("category", "Wybierz kategorię."),
+    ];
+    if require_all {
+        for (field, message) in required {
+            if !text_fields.contains_key(field) {
+                errors.insert(field, message);
+            }
+        }
+    }
+
+    if let Some(name) = text_fields.get("name")
+        && (name.trim().is_empty() || name.len() > 255)
+    {
+        errors.insert("name", "Nazwa musi mieć od 1 do 255 znaków.");
+    }
+    if let Some(description) = text_fields.get("description")
+        && description.len() > 5000
+    {
+        errors.insert("description", "Opis może mieć najwyżej 5000 znaków.");
+    }
+    if let Some(price) = text_fields.get("price") {
+        match price.trim().parse::<i64>() {
+            Ok(value) if value < 0 => errors.insert("price", "Cena nie może być ujemna."),
+            Ok(_) => {}
+            Err(_) => errors.insert("price", "Cena musi być liczbą całkowitą (w groszach)."),
+        }
+    }
+    if let Some(gender) = text_fields.get("gender")
+        && ProductGender::from_str(gender).is_err()
+    {
+        errors.insert("gender", "Nieprawidłowa płeć.");
+    }
+    if let Some(condition) = text_fields.get("condition")
+        && ProductCondition::from_str(condition).is_err()
+    {
+        errors.insert("condition", "Nieprawidłowy stan.");
+    }
+    if let Some(category) = text_fields.get("category")
+        && Category::from_str(category).is_err()
+    {
+        errors.insert("category", "Nieprawidłowa kategoria.");
+    }
+    if let Some(status) = text_fields.get("status")
+        && ProductStatus::from_str(status).is_err()
+    {
+        errors.insert("status", "Nieprawidłowy status.");
+    }
+    for field in PRODUCT_ATTRIBUTE_FIELDS {
+        if let Some(value) = text_fields.get(field)
+            && value.trim().chars().count() > PRODUCT_ATTRIBUTE_MAX_LEN
+        {
+            errors.insert(
+                field,
+                format!("Maksymalnie {} znaków.", PRODUCT_ATTRIBUTE_MAX_LEN),
+            );
+        }
+    }
+    for (field, max_len) in PRODUCT_SEO_FIELDS {
+        if let Some(value) = text_fields.get(field)
+            && value.trim().chars().count() > max_len
+        {
+            errors.insert(field, format!("Maksymalnie {} znaków.", max_len));
+        }
+    }
+    if let Some(og_image) = text_fields.get("og_image") {
+        let og_image = og_image.trim();
+        if !og_image.is_empty() && !og_image.starts_with("https://") {
+            errors.insert("og_image", "Adres zdjęcia musi zaczynać się od https://.");
+        }
+    }
+    if let Some(sale_price) = product_attribute(text_fields, "sale_price") {
+        match sale_price.parse::<i64>() {
+            Ok(value) if value <= 0 => {
+                errors.insert("sale_price", "Cena promocyjna musi być większa od zera.")
+            }
+            Ok(value) => {
+                let price = text_fields
+                    .get("price")
+                    .and_then(|price| price.trim().parse::<i64>().ok());
+                if price.is_some_and(|price| value >= price) {
+                    errors.insert("sale_price", "Cena promocyjna musi być niższa od ceny.");
+                }
+            }
+            Err(_) => errors.insert(
+                "sale_price",
+                "Cena promocyjna musi być liczbą całkowitą (w groszach).",
+            ),
+        }
+    }
+    let mut sale_period = [None, None];
+    for (index, field) in ["sale_starts_at", "sale_ends_at"].into_iter().enumerate() {
+        if let Some(value) = product_attribute(text_fields, field) {
+            match parse_datetime_input(&value) {
+                Some(datetime) => sale_period[index] = Some(datetime),
+                None => errors.insert(field, "Nieprawidłowa data."),
+            }
+        }
+    }
+    if let [Some(starts_at), Some(ends_at)] = sale_period
+        && ends_at <= starts_at
+    {
+        errors.insert("sale_ends_at", "Koniec promocji musi być po jej początku.");
+    }
+    errors
+}
+
+/// Zaplanowana promocja z formularza produktu (pola sprawdzone w `validate_product_fields`);
+/// bez ceny promocyjnej daty nie mają znaczenia i są czyszczone
+fn product_scheduled_sale(
+    text_fields: &HashMap<String, String>,
+) -> (
+    Option<i64>,
+    Option<chrono::DateTime<Utc>>,
+    Option<chrono::DateTime<Utc>>,
+) {
+    let Some(sale_price) = product_attribute(text_fields, "sale_price")
+        .and_then(|sale_price| sale_price.parse::<i64>().ok())
+    else {
+        return (None, None, None);
+    };
+    let datetime = |field: &str| {
+        product_attribute(text_fields, field).and_then(|value| parse_datetime_input(&value))
+    };
+    (
+        Some(sale_price),
+        datetime("sale_starts_at"),
+        datetime("sale_ends_at"),
+    )
+}
+
+/// Opcjonalne atrybuty odzieży z formularza produktu
+const PRODUCT_ATTRIBUTE_FIELDS: [&str; 4] = ["size", "brand", "color", "material"];
+const PRODUCT_ATTRIBUTE_MAX_LEN: usize = 100;
+/// Pola SEO formularza produktu z maksymalną długością; puste = wartości domyślne
+const PRODUCT_SEO_FIELDS: [(&str, usize); 3] = [
+    ("meta_title", 120),
+    ("meta_description", 320),
+    ("og_image", 500),
+];
+
+/// Wartość atrybutu z formularza: puste pole oznacza brak atrybutu.
+fn product_attribute(text_fields: &HashMap<String, String>, field: &str) -> Option<String> {
+    text_fields
+        .get(field)
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
 pub async fn create_product_handler(
     State(app_state): State<Arc<AppState>>,
     claims: TokenClaims,
+    request_headers: HeaderMap,
     mut multipart: Multipart,
 ) -> Result<(StatusCode, HeaderMap, String), AppError> {
-    if claims.role != Role::Admin {
-        return Err(AppError::UnauthorizedAccess(
-            "Tylko administrator może dodawać produkty".to_string(),
-        ));
-    }
+    claims.require_permission(Permission::ManageProducts)?;
     tracing::info!("Obsłużono zapytanie POST /api/products - tworzenie produktu");
 
     let mut text_fields: HashMap<String, String> = HashMap::new();
@@ -293,6 +582,15 @@ pub async fn create_product_handler(
         }
     }
 
+    let mut errors = validate_product_fields(&text_fields, true);
+    if image_uploads.is_empty() {
+        errors.insert("images", "Dodaj co najmniej jedno zdjęcie.");
+    }
+    if !errors.is_empty() {
+        tracing::warn!("Błędy walidacji formularza produktu: {:?}", errors);
+        return Err(errors.into_error(&request_headers, PRODUCT_FORM_MESSAGES_TARGET));
+    }
+
     let name = text_fields
         .get("name")
         .ok_or_else(|| AppError::UnprocessableEntity("Brak pola 'name'.".to_string()))?
@@ -319,6 +617,14 @@ pub async fn create_product_handler(
         .clone();
     let on_sale_str = text_fields.get("on_sale").map_or("false", |s| s.as_str());
     let on_sale = on_sale_str.eq_ignore_ascii_case("true") || on_sale_str == "on";
+    let size = product_attribute(&text_fields, "size");
+    let brand = product_attribute(&text_fields, "brand");
+    let color = product_attribute(&text_fields, "color");
+    let material = product_attribute(&text_fields, "material");
+    let meta_title = product_attribute(&text_fields, "meta_title");
+    let meta_description = product_attribute(&text_fields, "meta_description");
+    let og_image = product_attribute(&text_fields, "og_image");
+    let (sale_price, sale_starts_at, sale_ends_at) = product_scheduled_sale(&text_fields);
     if image_uploads.is_empty() {
         return Err(AppError::UnprocessableEntity(
             "Należy przesłac conajmniej jeden plik obrazu ('image_file)".to_string(),
@@ -363,11 +669,51 @@ pub async fn create_product_handler(
         ));
     }
 
+    // Ostrzeżenie o prawdopodobnym duplikacie - przed uploadem, żeby nie wgrywać zdjęć na próżno.
+    // Admin, który już je widział, wysyła formularz ponownie z `confirm_duplicate=true`.
+    let (image_uploads, image_hashes) = perceptual_hashes(image_uploads).await?;
+    let duplicate_confirmed = text_fields
+        .get("confirm_duplicate")
+        .is_some_and(|v| v == "true");
+    if !duplicate_confirmed {
+        let known_hashes: Vec<i64> = image_hashes.iter().flatten().copied().collect();
+        let duplicates = find_probable_duplicates(&app_state.db_pool, &name, &known_hashes).await?;
+        if !duplicates.is_empty() {
+            tracing::info!(
+                "Nowy produkt '{}' przypomina {} dostępnych produktów - ostrzegam admina",
+                name,
+                duplicates.len()
+            );
+            if !request_headers.contains_key("HX-Request") {
+                let names: Vec<&str> = duplicates.iter().map(|d| d.name.as_str()).collect();
+                return Err(AppError::Conflict(format!(
+                    "Prawdopodobny duplikat produktów: {}. Wyślij ponownie z confirm_duplicate=true, aby zapisać mimo to.",
+                    names.join(", ")
+                )));
+            }
+            let mut headers = HeaderMap::new();
+            if let Ok(value) = HeaderValue::from_str(PRODUCT_FORM_MESSAGES_TARGET) {
+                headers.insert("HX-Retarget", value);
+            }
+            headers.insert("HX-Reswap", HeaderValue::from_static("innerHTML"));
+            return Err(AppError::UnprocessableEntityWithFragment(
+                render_duplicate_warning_maud(&duplicates),
+                headers,
+            ));
+        }
+    }
+
+    // ID nadajemy przed uploadem, żeby zdjęcia od razu trafiły do folderu produktu
+    let new_product_id = Uuid::new_v4();
+    let asset_folder = product_asset_folder(new_product_id);
+
     let mut image_upload_futures = Vec::new();
     for (filename, bytes) in image_uploads {
         let config_clone = app_state.cloudinary_config.clone();
-        image_upload_futures
-            .push(async move { upload_image_to_cloudinary(bytes, filename, &config_clone).await });
+        let folder = asset_folder.clone();
+        image_upload_futures.push(async move {
+            upload_image_to_cloudinary(bytes, filename, &folder, &config_clone).await
+        });
     }
 
     let cloudinary_urls: Vec<String> = try_join_all(image_upload_futures).await?;
@@ -376,28 +722,50 @@ pub async fn create_product_handler(
         cloudinary_urls
     );
 
-    let new_product_id = Uuid::new_v4();
     let product_status = ProductStatus::Available;
-    sqlx::query_as::<_, Product>(
-        r#"
-            INSERT INTO products (id, name, description, price, gender, condition, category, status, images, on_sale)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-            RETURNING id, name, description, price, gender, condition , category, status, images, on_sale, created_at, updated_at
-        "#,
+    let mut conn = app_state.db_pool.acquire().await?;
+    let slug = unique_product_slug(&mut conn, &name, new_product_id).await?;
+    repo::products::insert(
+        &mut *conn,
+        &repo::products::NewProduct {
+            id: new_product_id,
+            name: &name,
+            slug: &slug,
+            description: &description,
+            price,
+            gender,
+            condition,
+            category,
+            status: product_status,
+            images: &cloudinary_urls,
+            on_sale,
+            size: size.as_deref(),
+            brand: brand.as_deref(),
+            color: color.as_deref(),
+            material: material.as_deref(),
+            meta_title: meta_title.as_deref(),
+            meta_description: meta_description.as_deref(),
+            og_image: og_image.as_deref(),
+            sale_price,
+            sale_starts_at,
+            sale_ends_at,
+        },
     )
-    .bind(new_product_id)
-    .bind(&name)
-    .bind(&description)
-    .bind(price)
-    .bind(gender)
-    .bind(condition)
-    .bind(category)
-    .bind(product_status)
-    .bind(&cloudinary_urls)
-    .bind(on_sale)
-    .fetch_one(&app_state.db_pool)
     .await?;
     tracing::info!("Utworzono produkt o ID: {}", new_product_id);
+    let url_hashes: Vec<(String, Option<i64>)> =
+        cloudinary_urls.iter().cloned().zip(image_hashes).collect();
+    if let Err(e) = store_image_hashes(&app_state.db_pool, new_product_id, &url_hashes).await {
+        tracing::warn!(
+            "Nie zapisano hashy zdjęć produktu {}: {:?}",
+            new_product_id,
+            e
+        );
+    }
+    // Nowy produkt ma być od razu widoczny na listingach
+    app_state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(&app_state).await;
+    notify_search_engines(app_state.clone(), vec![new_product_id]);
 
     let mut headers = HeaderMap::new();
     let toast_payload = json!({
@@ -424,13 +792,10 @@ pub async fn update_product_partial_handler(
     State(app_state): State<Arc<AppState>>,
     Path(product_id): Path<Uuid>,
     claims: TokenClaims,
+    request_headers: HeaderMap,
     mut multipart: Multipart,
 ) -> Result<Json<Product>, AppError> {
-    if claims.role != Role::Admin {
-        return Err(AppError::UnauthorizedAccess(
-            "Tylko administrator może aktualizować produkty".to_string(),
-        ));
-    }
+    claims.require_permission(Permission::ManageProducts)?;
     tracing::info!(
         "Obsłużono zapytanie PATCH /api/products/{} - aktualizacja (multipart)",
         product_id
@@ -462,6 +827,17 @@ pub async fn update_product_partial_handler(
         }
     }
 
+    // Błędy pól zgłaszamy przed jakąkolwiek operacją na Cloudinary
+    let errors = validate_product_fields(&text_fields, false);
+    if !errors.is_empty() {
+        tracing::warn!(
+            "Błędy walidacji edycji produktu {}: {:?}",
+            product_id,
+            errors
+        );
+        return Err(errors.into_error(&request_headers, PRODUCT_FORM_MESSAGES_TARGET));
+    }
+
     // KROK 2: Wykonujemy operacje na Cloudinary (usuwanie) - nadal BEZ transakcji.
     let urls_to_delete: Vec<String> = if let Some(json_str) = urls_to_delete_json_opt {
         if !json_str.is_empty() && json_str != "[]" {
@@ -496,13 +872,17 @@ pub async fn update_product_partial_handler(
     }
 
     // KROK 3: Wykonujemy operacje na Cloudinary (upload) - nadal BEZ transakcji.
+    // Hashe nowych zdjęć zapisujemy po aktualizacji - do wykrywania duplikatów.
+    let (new_image_uploads, new_image_hashes) = perceptual_hashes(new_image_uploads).await?;
     let mut uploaded_urls: Vec<String> = Vec::new();
     if !new_image_uploads.is_empty() {
+        let asset_folder = product_asset_folder(product_id);
         let mut upload_futures = Vec::new();
         for (filename, bytes) in new_image_uploads {
             let config_clone = app_state.cloudinary_config.clone();
+            let folder = asset_folder.clone();
             upload_futures.push(async move {
-                upload_image_to_cloudinary(bytes, filename, &config_clone).await
+                upload_image_to_cloudinary(bytes, filename, &folder, &config_clone).await
             });
         }
         uploaded_urls = try_join_all(upload_futures).await?;
@@ -511,12 +891,10 @@ pub async fn update_product_partial_handler(
     // KROK 4: DOPIERO TERAZ, gdy wszystkie operacje zewnętrzne się powiodły, otwieramy krótką transakcję.
     let mut tx = app_state.db_pool.begin().await?;
 
-    let mut existing_product =
-        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
-            .bind(product_id)
-            .fetch_one(&mut *tx)
-            .await
-            .map_err(|_| AppError::NotFound)?;
+    let mut existing_product = repo::products::find_by_id_for_update(&mut *tx, product_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let product_before_update = existing_product.clone();
 
     // Aktualizujemy pola produktu w pamięci
     if let Some(name) = text_fields.get("name") {
@@ -549,139 +927,617 @@ pub async fn update_product_partial_handler(
     existing_product.on_sale = text_fields
         .get("on_sale")
         .map_or(false, |s| s.eq_ignore_ascii_case("true") || s == "on");
+    // Atrybuty zmieniamy tylko, gdy pole przyszło w formularzu; puste pole czyści wartość
+    if text_fields.contains_key("size") {
+        existing_product.size = product_attribute(&text_fields, "size");
+    }
+    if text_fields.contains_key("brand") {
+        existing_product.brand = product_attribute(&text_fields, "brand");
+    }
+    if text_fields.contains_key("color") {
+        existing_product.color = product_attribute(&text_fields, "color");
+    }
+    if text_fields.contains_key("material") {
+        existing_product.material = product_attribute(&text_fields, "material");
+    }
+    if text_fields.contains_key("meta_title") {
+        existing_product.meta_title = product_attribute(&text_fields, "meta_title");
+    }
+    if text_fields.contains_key("meta_description") {
+        existing_product.meta_description = product_attribute(&text_fields, "meta_description");
+    }
+    if text_fields.contains_key("og_image") {
+        existing_product.og_image = product_attribute(&text_fields, "og_image");
+    }
+    if text_fields.contains_key("sale_price") {
+        (
+            existing_product.sale_price,
+            existing_product.sale_starts_at,
+            existing_product.sale_ends_at,
+        ) = product_scheduled_sale(&text_fields);
+    }
+    // Cena mogła się zmienić bez pola promocji - sprawdzamy parę po scaleniu
+    if existing_product
+        .sale_price
+        .is_some_and(|sale_price| sale_price >= existing_product.price)
+    {
+        let mut errors = ValidationErrors::new();
+        errors.insert("sale_price", "Cena promocyjna musi być niższa od ceny.");
+        return Err(errors.into_error(&request_headers, PRODUCT_FORM_MESSAGES_TARGET));
+    }
+    // Zmiana sluga zmienia adres strony produktu - stary adres dostaje przekierowanie 301
+    let old_path = existing_product.public_path();
+    if let Some(slug) = text_fields
+        .get("slug")
+        .filter(|slug| !slug.trim().is_empty())
+    {
+        let slug = slugify(slug);
+        if slug != existing_product.slug {
+            if is_slug_taken(&mut tx, &slug, product_id).await? {
+                let mut errors = ValidationErrors::new();
+                errors.insert("slug", "Ten adres ma już inny produkt.");
+                return Err(errors.into_error(&request_headers, PRODUCT_FORM_MESSAGES_TARGET));
+            }
+            existing_product.slug = slug;
+        }
+    }
 
     // Aktualizujemy listę obrazków
     existing_product
         .images
         .retain(|url| !urls_to_delete.contains(url));
-    existing_product.images.extend(uploaded_urls);
+    existing_product
+        .images
+        .extend(uploaded_urls.iter().cloned());
 
     if existing_product.images.is_empty() {
-        return Err(AppError::UnprocessableEntity(
-            "Produkt musi mieć co najmniej jeden obrazek.".to_string(),
-        ));
+        let mut errors = ValidationErrors::new();
+        errors.insert("images", "Produkt musi mieć co najmniej jedno zdjęcie.");
+        return Err(errors.into_error(&request_headers, PRODUCT_FORM_MESSAGES_TARGET));
     }
 
     // KROK 5: Wykonujemy JEDNO zapytanie UPDATE w naszej krótkiej transakcji.
-    let updated_product_db = sqlx::query_as::<_, Product>(
-        r#"
-            UPDATE products
-            SET name = $1, description = $2, price = $3, gender = $4, condition = $5, category = $6, status = $7, images = $8, on_sale = $9, updated_at = NOW()
-            WHERE id = $10
-            RETURNING *
-        "#,
+    let updated_product_db = repo::products::update(&mut *tx, &existing_product).await?;
+    record_price_change(
+        &mut tx,
+        &product_before_update,
+        &updated_product_db,
+        Some(claims.sub),
     )
-    .bind(&existing_product.name)
-    .bind(&existing_product.description)
-    .bind(existing_product.price)
-    .bind(existing_product.gender)
-    .bind(existing_product.condition)
-    .bind(existing_product.category)
-    .bind(existing_product.status)
-    .bind(&existing_product.images)
-    .bind(existing_product.on_sale)
-    .bind(product_id)
-    .fetch_one(&mut *tx)
     .await?;
+    let new_path = updated_product_db.public_path();
+    if new_path != old_path {
+        record_moved_path(&mut tx, &old_path, &new_path, claims.sub).await?;
+        tracing::info!(
+            "Zmieniono adres produktu {}: {} -> {} (dodano przekierowanie)",
+            product_id,
+            old_path,
+            new_path
+        );
+    }
 
     // KROK 6: Zamykamy transakcję. Całość trwała ułamki sekund.
     tx.commit().await?;
     app_state.product_cache.invalidate(&product_id).await;
+    app_state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(&app_state).await;
+    notify_price_drop(&app_state, &product_before_update, &updated_product_db);
+
+    let url_hashes: Vec<(String, Option<i64>)> =
+        uploaded_urls.into_iter().zip(new_image_hashes).collect();
+    if let Err(e) = store_image_hashes(&app_state.db_pool, product_id, &url_hashes).await {
+        tracing::warn!("Nie zapisano hashy zdjęć produktu {}: {:?}", product_id, e);
+    }
 
     tracing::info!("Pomyślnie zaktualizowano produkt o ID: {}", product_id);
     Ok(Json(updated_product_db))
 }
 
-pub async fn archivize_product_handler(
+/// "Wyczyść tło": zastępuje zdjęcie produktu wersją z usuniętym tłem i jednolitym kadrem.
+/// Kolejność zdjęć zostaje zachowana, stary zasób jest usuwany z Cloudinary.
+pub async fn clean_product_image_background_handler(
     State(app_state): State<Arc<AppState>>,
     Path(product_id): Path<Uuid>,
     claims: TokenClaims,
-    Query(params): Query<ListingParams>,
-) -> Result<Markup, AppError> {
-    tracing::info!(
-        "Obsłużono żądanie SOFT DELETE / ARCHIVIZE /api/products/{}",
-        product_id
-    );
+    Form(payload): Form<ProductImageActionPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
 
-    if claims.role != Role::Admin {
-        return Err(AppError::UnauthorizedAccess(
-            "Tylko administrator może usuwać produkty".to_string(),
+    let product = repo::products::find_by_id(&app_state.db_pool, product_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if !product.images.contains(&payload.image_url) {
+        return Err(AppError::BadRequest(
+            "To zdjęcie nie należy do produktu.".to_string(),
         ));
     }
 
-    // Aktualizujemy status na "Archived"
-    let update_result =
-        sqlx::query("UPDATE products SET status = $1, updated_at = NOW() WHERE id = $2")
-            .bind(ProductStatus::Archived)
-            .bind(product_id)
-            .execute(&app_state.db_pool)
-            .await?;
+    let new_url = create_background_removed_copy(
+        &payload.image_url,
+        &product_asset_folder(product_id),
+        &app_state.cloudinary_config,
+    )
+    .await?;
 
-    if update_result.rows_affected() == 0 {
+    repo::products::replace_image(&app_state.db_pool, product_id, &payload.image_url, &new_url)
+        .await?;
+    app_state.product_cache.invalidate(&product_id).await;
+
+    if let Some(public_id) =
+        extract_public_id_from_url(&payload.image_url, &app_state.cloudinary_config.cloud_name)
+        && let Err(e) = delete_image_from_cloudinary(&public_id, &app_state.cloudinary_config).await
+    {
         tracing::warn!(
-            "ARCHIVIZE: Nie znaleziono produktu o ID {} do zarchiwizowania",
-            product_id
+            "Nie udało się usunąć oryginału zdjęcia '{}' po usunięciu tła: {:?}",
+            public_id,
+            e
         );
-        return Err(AppError::NotFound); // Zwracamy błąd, jeśli produkt nie istnieje
     }
 
-    // Pobieramy zaktualizowany produkt z bazy, aby mieć świeże dane
-    let updated_product = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1")
-        .bind(product_id)
-        .fetch_one(&app_state.db_pool)
-        .await?;
-
-    tracing::info!("Zarchiwizowano produkt o ID: {}", product_id);
+    tracing::info!(
+        "Admin {} usunął tło zdjęcia produktu {}: {} -> {}",
+        claims.sub,
+        product_id,
+        payload.image_url,
+        new_url
+    );
 
-    // Renderujemy i zwracamy HTML dla zaktualizowanego wiersza
-    Ok(render_admin_product_list_row_maud(
-        &updated_product,
-        &params,
-    ))
+    let mut headers = HeaderMap::new();
+    let toast_payload = json!({
+        "showMessage": {
+            "message": "Tlo zostalo usuniete. Nowa wersja zdjecia pojawi sie za chwile.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&toast_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    let location_payload = json!({
+        "path": format!("/htmx/admin/products/{}/edit", product_id),
+        "target": "#admin-content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+    Ok((StatusCode::OK, headers))
 }
 
-// ZMIANA: Nowa funkcja do trwałego usuwania produktów
-pub async fn permanent_delete_product_handler(
+/// Dodaje sprzedany produkt do publicznego archiwum albo go z niego usuwa.
+/// W archiwum pokazujemy wyłącznie wersje zdjęć ze znakiem wodnym "SPRZEDANE".
+pub async fn toggle_sold_archive_handler(
     State(app_state): State<Arc<AppState>>,
     Path(product_id): Path<Uuid>,
     claims: TokenClaims,
 ) -> Result<(StatusCode, HeaderMap), AppError> {
-    tracing::info!(
-        "Obsłużono żądanie PERMANENT DELETE /api/products/{}",
-        product_id
-    );
+    claims.require_permission(Permission::ManageProducts)?;
 
-    if claims.role != Role::Admin {
-        return Err(AppError::UnauthorizedAccess(
-            "Tylko administrator może trwale usuwać produkty".to_string(),
+    let status = repo::products::status(&app_state.db_pool, product_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let mut headers = HeaderMap::new();
+    if status != ProductStatus::Sold {
+        let toast_payload = json!({
+            "showMessage": {
+                "message": "Do archiwum mozna dodac tylko sprzedany produkt.",
+                "type": "error"
+            }
+        });
+        if let Ok(val) = HeaderValue::from_str(&toast_payload.to_string()) {
+            headers.insert("HX-Trigger", val);
+        }
+        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+        return Err(AppError::ConflictWithHeaders(
+            "Produkt nie jest sprzedany.".to_string(),
+            headers,
         ));
     }
 
-    let mut tx = app_state.db_pool.begin().await?;
-
-    // KROK 1: Sprawdź, czy produkt nie jest powiązany z żadnym zamówieniem.
-    let is_in_order = sqlx::query("SELECT 1 FROM order_items WHERE product_id = $1 LIMIT 1")
+    let removed = sqlx::query("DELETE FROM sold_archive_products WHERE product_id = $1")
         .bind(product_id)
-        .fetch_optional(&mut *tx)
+        .execute(&app_state.db_pool)
         .await?
-        .is_some();
-
-    if is_in_order {
-        tx.rollback().await?; // Zakończ transakcję
-        tracing::warn!(
-            "Próba trwałego usunięcia produktu (ID: {}), który jest częścią zamówienia.",
-            product_id
-        );
-        return Err(AppError::Conflict("Nie można usunąć produktu, który jest częścią istniejących zamówień. Zamiast tego zarchiwizuj go.".to_string()));
+        .rows_affected()
+        > 0;
+    if !removed {
+        sqlx::query("INSERT INTO sold_archive_products (product_id) VALUES ($1)")
+            .bind(product_id)
+            .execute(&app_state.db_pool)
+            .await?;
     }
 
-    // KROK 2: Pobierz produkt, aby uzyskać listę obrazów do usunięcia z Cloudinary
-    let product_to_delete =
-        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
-            .bind(product_id)
-            .fetch_optional(&mut *tx)
+    tracing::info!(
+        "Admin {} {} produkt {} w archiwum sprzedanych",
+        claims.sub,
+        if removed { "ukrył" } else { "opublikował" },
+        product_id
+    );
+
+    let toast_message = if removed {
+        "Produkt usuniety z archiwum."
+    } else {
+        "Produkt dodany do archiwum."
+    };
+    let toast_payload = json!({
+        "showMessage": {
+            "message": toast_message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&toast_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    let location_payload = json!({
+        "path": format!("/htmx/admin/products/{}/edit", product_id),
+        "target": "#admin-content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+/// Odświeża formularz edycji produktu w panelu po zmianie wykonanej poza formularzem
+fn reload_product_edit_form(headers: &mut HeaderMap, product_id: Uuid) {
+    let location_payload = json!({
+        "path": format!("/htmx/admin/products/{}/edit", product_id),
+        "target": "#admin-content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+}
+
+/// POST /api/admin/products/{product_id}/hold - rezerwacja produktu dla konkretnego klienta
+pub async fn create_product_hold_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(product_id): Path<Uuid>,
+    Form(payload): Form<CreateProductHoldPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    payload.validate()?;
+
+    let note = payload
+        .note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+    if let Err(e) = create_hold(
+        &app_state.db_pool,
+        product_id,
+        claims.sub,
+        payload.customer_label.trim(),
+        payload.days,
+        note,
+    )
+    .await
+    {
+        return match e {
+            AppError::Conflict(_) => Err(toast_form_error(
+                "Produkt jest niedostepny albo lezy w koszyku innego klienta.",
+            )),
+            other => Err(other),
+        };
+    }
+    app_state.product_cache.invalidate(&product_id).await;
+    app_state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(&app_state).await;
+
+    let mut headers = HeaderMap::new();
+    let toast_payload = json!({
+        "showMessage": {
+            "message": "Produkt zarezerwowany dla klienta. Skopiuj link zakupu.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&toast_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    reload_product_edit_form(&mut headers, product_id);
+    Ok((StatusCode::OK, headers))
+}
+
+/// DELETE /api/admin/products/{product_id}/hold - anulowanie rezerwacji, produkt wraca do sprzedaży
+pub async fn cancel_product_hold_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(product_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    cancel_hold(&app_state.db_pool, product_id).await?;
+    tracing::info!(
+        "Admin {} anulował rezerwację ręczną produktu {}",
+        claims.sub,
+        product_id
+    );
+    app_state.product_cache.invalidate(&product_id).await;
+    app_state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(&app_state).await;
+
+    let mut headers = HeaderMap::new();
+    let toast_payload = json!({
+        "showMessage": {
+            "message": "Rezerwacja anulowana, produkt wrocil do sprzedazy.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&toast_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    reload_product_edit_form(&mut headers, product_id);
+    Ok((StatusCode::OK, headers))
+}
+
+pub async fn archivize_product_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    claims: TokenClaims,
+    Query(params): Query<ListingParams>,
+) -> Result<Markup, AppError> {
+    tracing::info!(
+        "Obsłużono żądanie SOFT DELETE / ARCHIVIZE /api/products/{}",
+        product_id
+    );
+
+    claims.require_permission(Permission::ManageProducts)?;
+
+    // Aktualizujemy status na "Archived"
+    let updated =
+        repo::products::set_status(&app_state.db_pool, product_id, ProductStatus::Archived).await?;
+
+    if !updated {
+        tracing::warn!(
+            "ARCHIVIZE: Nie znaleziono produktu o ID {} do zarchiwizowania",
+            product_id
+        );
+        return Err(AppError::NotFound); // Zwracamy błąd, jeśli produkt nie istnieje
+    }
+
+    // Pobieramy zaktualizowany produkt z bazy, aby mieć świeże dane
+    let updated_product = repo::products::find_by_id(&app_state.db_pool, product_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    tracing::info!("Zarchiwizowano produkt o ID: {}", product_id);
+    app_state.product_cache.invalidate(&product_id).await;
+    app_state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(&app_state).await;
+
+    // Renderujemy i zwracamy HTML dla zaktualizowanego wiersza
+    Ok(render_admin_product_list_row_maud(
+        &updated_product,
+        &params,
+    ))
+}
+
+/// Dopuszczalna zmiana ceny w akcji masowej, w procentach
+const BULK_PRICE_CHANGE_MIN_PERCENT: i64 = -90;
+const BULK_PRICE_CHANGE_MAX_PERCENT: i64 = 200;
+/// Najniższa cena po zmianie masowej (1 zł)
+const BULK_MIN_PRICE: i64 = 100;
+
+/// Formularz akcji masowej. Zaznaczone produkty przychodzą jako powtórzone pole `product_ids`,
+/// czego `Form` (serde_urlencoded) nie obsługuje, więc treść parsujemy ręcznie.
+fn parse_product_bulk_form(
+    body: &str,
+) -> Result<(ProductBulkAction, Vec<Uuid>, Option<i64>), AppError> {
+    let mut action = None;
+    let mut product_ids = Vec::new();
+    let mut percent = None;
+    for (key, value) in url::form_urlencoded::parse(body.as_bytes()) {
+        match key.as_ref() {
+            "action" => action = ProductBulkAction::from_str(&value).ok(),
+            "product_ids" => {
+                if let Ok(id) = Uuid::parse_str(&value)
+                    && !product_ids.contains(&id)
+                {
+                    product_ids.push(id);
+                }
+            }
+            "percent" => percent = value.trim().parse::<i64>().ok(),
+            _ => {}
+        }
+    }
+
+    let action = action.ok_or_else(|| toast_form_error("Wybierz akcje do wykonania."))?;
+    if product_ids.is_empty() {
+        return Err(toast_form_error("Nie zaznaczono zadnych produktow."));
+    }
+    if action == ProductBulkAction::ChangePrice {
+        match percent {
+            Some(p)
+                if p != 0
+                    && (BULK_PRICE_CHANGE_MIN_PERCENT..=BULK_PRICE_CHANGE_MAX_PERCENT)
+                        .contains(&p) => {}
+            _ => {
+                return Err(toast_form_error(
+                    "Podaj zmiane ceny od -90% do +200% (rozna od zera).",
+                ));
+            }
+        }
+    }
+    Ok((action, product_ids, percent))
+}
+
+/// Wykonuje akcję masową na jednym (zablokowanym) produkcie. Zwraca `Some(powód)`,
+/// gdy produkt trzeba pominąć - pozostałe produkty są przetwarzane dalej.
+async fn apply_product_bulk_action(
+    conn: &mut PgConnection,
+    product: &Product,
+    action: ProductBulkAction,
+    percent: Option<i64>,
+    admin_id: Uuid,
+) -> Result<Option<String>, AppError> {
+    match action {
+        ProductBulkAction::Archive => {
+            if product.status == ProductStatus::Archived {
+                return Ok(Some("Produkt jest już zarchiwizowany.".to_string()));
+            }
+            repo::products::set_status(&mut *conn, product.id, ProductStatus::Archived).await?;
+        }
+        ProductBulkAction::MarkSold => {
+            if matches!(
+                product.status,
+                ProductStatus::Sold | ProductStatus::Archived
+            ) {
+                return Ok(Some(format!(
+                    "Produkt ma już status \"{}\".",
+                    product.status
+                )));
+            }
+            repo::products::set_status(&mut *conn, product.id, ProductStatus::Sold).await?;
+            clear_product_reservations(&mut *conn, &[product.id]).await?;
+        }
+        ProductBulkAction::ToggleOnSale => {
+            repo::products::toggle_on_sale(&mut *conn, product.id).await?;
+            let after = Product {
+                on_sale: !product.on_sale,
+                ..product.clone()
+            };
+            record_price_change(&mut *conn, product, &after, Some(admin_id)).await?;
+        }
+        ProductBulkAction::ChangePrice => {
+            if matches!(
+                product.status,
+                ProductStatus::Sold | ProductStatus::Archived
+            ) {
+                return Ok(Some(
+                    "Nie zmieniamy ceny sprzedanych ani zarchiwizowanych produktów.".to_string(),
+                ));
+            }
+            let percent = percent.unwrap_or(0);
+            // Zaokrąglenie do pełnego grosza
+            let new_price = (product.price * (100 + percent) + 50).div_euclid(100);
+            if new_price < BULK_MIN_PRICE {
+                return Ok(Some("Cena po zmianie byłaby niższa niż 1 zł.".to_string()));
+            }
+            if product
+                .sale_price
+                .is_some_and(|sale_price| new_price <= sale_price)
+            {
+                return Ok(Some(
+                    "Cena po zmianie nie byłaby wyższa od ceny promocyjnej.".to_string(),
+                ));
+            }
+            repo::products::set_price(&mut *conn, product.id, new_price).await?;
+            let after = Product {
+                price: new_price,
+                ..product.clone()
+            };
+            record_price_change(&mut *conn, product, &after, Some(admin_id)).await?;
+        }
+    }
+    Ok(None)
+}
+
+/// POST /api/admin/products/bulk - akcja masowa na zaznaczonych produktach w jednej transakcji.
+/// Zwraca podsumowanie (udane/pominięte) i podmienia (OOB) wiersze zmienionych produktów.
+pub async fn bulk_products_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<ListingParams>,
+    body: String,
+) -> Result<Markup, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    let (action, product_ids, percent) = parse_product_bulk_form(&body)?;
+
+    let mut tx = app_state.db_pool.begin().await?;
+    let products: HashMap<Uuid, Product> =
+        repo::products::find_by_ids_for_update(&mut *tx, &product_ids)
             .await?
-            .ok_or(AppError::NotFound)?;
+            .into_iter()
+            .map(|product| (product.id, product))
+            .collect();
+
+    let mut outcomes = Vec::with_capacity(product_ids.len());
+    for product_id in &product_ids {
+        let (product_name, error) = match products.get(product_id) {
+            Some(product) => (
+                Some(product.name.clone()),
+                apply_product_bulk_action(&mut tx, product, action, percent, claims.sub).await?,
+            ),
+            None => (None, Some("Produkt nie istnieje.".to_string())),
+        };
+        outcomes.push(ProductBulkOutcome {
+            product_id: *product_id,
+            product_name,
+            error,
+        });
+    }
+    tx.commit().await?;
+
+    let updated_ids: Vec<Uuid> = outcomes
+        .iter()
+        .filter(|outcome| outcome.error.is_none())
+        .map(|outcome| outcome.product_id)
+        .collect();
+    for product_id in &updated_ids {
+        app_state.product_cache.invalidate(product_id).await;
+    }
+    if !updated_ids.is_empty() {
+        app_state.listing_fragment_cache.invalidate_all();
+        invalidate_merchant_feed(&app_state).await;
+    }
+    tracing::info!(
+        "Admin {} wykonał akcję masową {} ({:?}%): zmieniono {} z {} produktów",
+        claims.sub,
+        action.as_ref(),
+        percent,
+        updated_ids.len(),
+        outcomes.len()
+    );
+
+    let updated_products = repo::products::find_by_ids(&app_state.db_pool, &updated_ids).await?;
+    for updated_product in &updated_products {
+        if let Some(before) = products.get(&updated_product.id) {
+            notify_price_drop(&app_state, before, updated_product);
+        }
+    }
+    Ok(render_product_bulk_result_maud(
+        action,
+        &outcomes,
+        &updated_products,
+        &params,
+    ))
+}
+
+// ZMIANA: Nowa funkcja do trwałego usuwania produktów
+pub async fn permanent_delete_product_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    tracing::info!(
+        "Obsłużono żądanie PERMANENT DELETE /api/products/{}",
+        product_id
+    );
+
+    claims.require_permission(Permission::ManageProducts)?;
+
+    let mut tx = app_state.db_pool.begin().await?;
+
+    // KROK 1: Sprawdź, czy produkt nie jest powiązany z żadnym zamówieniem.
+    let is_in_order = repo::products::is_in_any_order(&mut *tx, product_id).await?;
+
+    if is_in_order {
+        tx.rollback().await?; // Zakończ transakcję
+        tracing::warn!(
+            "Próba trwałego usunięcia produktu (ID: {}), który jest częścią zamówienia.",
+            product_id
+        );
+        return Err(AppError::Conflict("Nie można usunąć produktu, który jest częścią istniejących zamówień. Zamiast tego zarchiwizuj go.".to_string()));
+    }
+
+    // KROK 2: Pobierz produkt, aby uzyskać listę obrazów do usunięcia z Cloudinary
+    let product_to_delete = repo::products::find_by_id_for_update(&mut *tx, product_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
     // KROK 3: Usuń obrazy z Cloudinary
     if !product_to_delete.images.is_empty() {
@@ -708,16 +1564,16 @@ pub async fn permanent_delete_product_handler(
     }
 
     // KROK 4: Trwale usuń produkt z bazy danych
-    let delete_result = sqlx::query("DELETE FROM products WHERE id = $1")
-        .bind(product_id)
-        .execute(&mut *tx)
-        .await?;
+    let deleted = repo::products::delete(&mut *tx, product_id).await?;
 
     tx.commit().await?;
 
-    if delete_result.rows_affected() > 0 {
+    if deleted {
         tracing::info!("Trwale usunięto produkt o ID: {}", product_id);
     }
+    app_state.product_cache.invalidate(&product_id).await;
+    app_state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(&app_state).await;
 
     // KROK 5: Wyślij odpowiedź do HTMX
     let mut headers = HeaderMap::new();
@@ -737,77 +1593,54 @@ pub async fn permanent_delete_product_handler(
     Ok((StatusCode::OK, headers))
 }
 
+/// Kontener na komunikaty formularza rejestracji (`registration_page_htmx_handler`)
+const REGISTRATION_MESSAGES_TARGET: &str = "#registration-messages";
+
 pub async fn register_handler(
     State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
     Form(payload): Form<RegistrationPayload>,
 ) -> Result<impl IntoResponse, AppError> {
-    // 1. Walidacja danych wejściowych
+    // 1. Walidacja danych wejściowych - komunikaty trafiają pod pola formularza
     if let Err(validation_errors) = payload.validate() {
         tracing::warn!("Błąd walidacji danych rejestracji: {:?}", validation_errors);
-        let mut headers = HeaderMap::new();
-        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
-
-        let mut error_message = "Niepoprawne dane w formularzu.".to_string();
-        if let Some(field_errors) = validation_errors.field_errors().values().next() {
-            if let Some(first_error) = field_errors.get(0) {
-                if let Some(msg) = &first_error.message {
-                    error_message = msg.to_string();
-                } else {
-                    error_message = first_error.code.to_string();
-                }
-            }
-        }
+        return Err(ValidationErrors::from(&validation_errors)
+            .into_error(&request_headers, REGISTRATION_MESSAGES_TARGET));
+    }
 
-        let trigger_payload = json!({
-            "showMessage": {"message": error_message, "type": "error"}
-        });
-        if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
-            headers.insert("HX-Trigger", trigger_value);
-        }
-        return Ok((
-            StatusCode::UNPROCESSABLE_ENTITY,
-            headers,
-            Json(
-                json!({ "error": "Validation failed", "details_str": validation_errors.to_string() }),
-            ), // Zmieniono "details" na "details_str" lub serializuj inaczej
-        ));
+    // 1a. Blokada jednorazowych skrzynek e-mail
+    if app_state
+        .disposable_email_blocklist
+        .is_disposable(&payload.email)
+    {
+        tracing::warn!(
+            "Próba rejestracji z jednorazowym adresem e-mail: {}",
+            payload.email
+        );
+        let mut errors = ValidationErrors::new();
+        errors.insert(
+            "email",
+            "Nie przyjmujemy adresów z tymczasowych skrzynek e-mail. Podaj swój stały adres.",
+        );
+        return Err(errors.into_error(&request_headers, REGISTRATION_MESSAGES_TARGET));
     }
 
     // 2. Sprawdzanie czy użytkownik istnieje
-    let existing_user: Option<User> = sqlx::query_as(
-        r#"
-            SELECT id, email, password_hash, role, created_at, updated_at
-            FROM users
-            WHERE email = $1
-        "#,
-    )
-    .bind(&payload.email)
-    .fetch_optional(&app_state.db_pool)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            "Błąd bazy danych podczas sprawdzania emaila {}: {:?}",
-            payload.email,
-            e
-        );
-        AppError::SqlxError(e)
-    })?;
+    let existing_user = repo::users::find_by_email(&app_state.db_pool, &payload.email)
+        .await
+        .inspect_err(|e| {
+            tracing::error!(
+                "Błąd bazy danych podczas sprawdzania emaila {}: {:?}",
+                payload.email,
+                e
+            );
+        })?;
 
     if existing_user.is_some() {
         tracing::warn!("Próba rejestracji z istniejącym emailem: {}", payload.email);
-        let mut headers = HeaderMap::new();
-        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
-        let trigger_payload = json!({
-            "showMessage": {"message": "Podany adres email jest juz zarejestrowany.", "type": "error"}
-        });
-        if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
-            headers.insert("HX-Trigger", trigger_value);
-        }
-        return Ok((
-            StatusCode::CONFLICT,
-            headers,
-            Json(json!({"message": "Email już istnieje"})),
-        ));
+        let mut errors = ValidationErrors::new();
+        errors.insert("email", "Podany adres e-mail jest już zarejestrowany.");
+        return Err(errors.into_error(&request_headers, REGISTRATION_MESSAGES_TARGET));
     }
 
     // 3. Hash hasła
@@ -832,15 +1665,12 @@ pub async fn register_handler(
     };
 
     // 4. Wstawianie nowego użytkownika
-    let new_user = match sqlx::query_as::<_, User>(
-        r#"INSERT INTO users (email, password_hash, role) 
-           VALUES ($1, $2, $3)
-           RETURNING id, email, password_hash, role, created_at, updated_at"#,
+    let new_user = match repo::users::insert(
+        &app_state.db_pool,
+        &payload.email,
+        &password_hash,
+        Role::Customer,
     )
-    .bind(&payload.email)
-    .bind(&password_hash)
-    .bind(Role::Customer)
-    .fetch_one(&app_state.db_pool)
     .await
     {
         Ok(user) => user,
@@ -867,6 +1697,7 @@ pub async fn register_handler(
         new_user.email,
         new_user.id
     );
+    spawn_verification_email(app_state.clone(), new_user.id, new_user.email.clone());
 
     // 5. Sukces - przygotowanie odpowiedzi z nagłówkami HTMX
     let mut headers = HeaderMap::new();
@@ -874,7 +1705,7 @@ pub async fn register_handler(
 
     let trigger_payload = json!({
         "registrationComplete": { "userId": new_user.id.to_string() },
-        "showMessage": {"message": "Rejestracja pomyslna! Mozesz sie teraz zalogowac.", "type": "success"}
+        "showMessage": {"message": "Rejestracja pomyslna! Mozesz sie teraz zalogowac. Na Twoj adres wyslalismy link potwierdzajacy.", "type": "success"}
     });
     if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
         headers.insert("HX-Trigger", trigger_value);
@@ -886,14 +1717,75 @@ pub async fn register_handler(
     Ok((StatusCode::CREATED, headers, Json(json!(user_public_data))))
 }
 
-pub async fn login_handler(
-    State(app_state): State<Arc<AppState>>,
-    Form(payload): Form<LoginPayload>,
-) -> Result<impl IntoResponse, AppError> {
-    // 1. Walidacja danych wejściowych
-    if let Err(validation_errors) = payload.validate() {
-        // Możesz chcieć przekształcić validation_errors na bardziej przyjazny komunikat
-        // lub zwrócić szczegóły błędów walidacji.
+/// Odpowiedź dla zablokowanego adresu: komunikat i przejście na stronę logowania,
+/// która pokazuje, ile zostało do końca blokady
+fn login_lockout_response(lockout: &AccountLockout) -> (StatusCode, HeaderMap, Json<Value>) {
+    let remaining_minutes = lockout.remaining_minutes();
+    let message = format!(
+        "Zbyt wiele nieudanych prób logowania. Spróbuj ponownie za {}.",
+        pluralize(remaining_minutes, "minutę", "minuty", "minut")
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::RETRY_AFTER,
+        HeaderValue::from(remaining_minutes * 60),
+    );
+    // Nagłówki HTMX bez polskich znaków, jak w pozostałych komunikatach logowania
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": format!("Zbyt wiele nieudanych prob logowania. Sprobuj ponownie za {} min.", remaining_minutes),
+            "type": "error"
+        }
+    });
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    }
+    let location_payload = json!({
+        "path": format!("/logowanie?email={}", urlencoding::encode(&lockout.email)),
+        "target": "#content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        headers,
+        Json(json!({"message": message})),
+    )
+}
+
+/// Konto zablokowane przez admina - komunikat dopiero po poprawnym haśle,
+/// żeby nie dało się w ten sposób sprawdzać, czy adres ma konto
+fn disabled_account_response() -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut headers = HeaderMap::new();
+    headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "To konto zostalo zablokowane. Skontaktuj sie ze sklepem.",
+            "type": "error"
+        }
+    });
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    }
+    (
+        StatusCode::FORBIDDEN,
+        headers,
+        Json(json!({"message": "To konto zostało zablokowane."})),
+    )
+}
+
+pub async fn login_handler(
+    State(app_state): State<Arc<AppState>>,
+    ClientIp(client_ip): ClientIp,
+    Form(payload): Form<LoginPayload>,
+) -> Result<impl IntoResponse, AppError> {
+    // 1. Walidacja danych wejściowych
+    if let Err(validation_errors) = payload.validate() {
+        // Możesz chcieć przekształcić validation_errors na bardziej przyjazny komunikat
+        // lub zwrócić szczegóły błędów walidacji.
         // Na razie zwracamy generyczny AppError::Validation.
         tracing::warn!("Błąd walidacji danych logowania: {:?}", validation_errors);
         let mut headers = HeaderMap::new();
@@ -909,25 +1801,27 @@ pub async fn login_handler(
         return Err(AppError::Validation("Błąd walidacji danych".to_string()));
     }
 
-    // 2. Znajdowanie użytkownika po emailu
-    let user_optional = sqlx::query_as::<_, User>(
-        r#"
-            SELECT id, email, password_hash, role, created_at, updated_at
-            FROM users
-            WHERE email = $1
-        "#,
-    )
-    .bind(&payload.email)
-    .fetch_optional(&app_state.db_pool)
-    .await
-    .map_err(|e| {
-        tracing::error!(
-            "Błąd bazy danych podczas wyszukiwania użytkownika {}: {:?}",
-            payload.email,
-            e
+    // Zablokowany adres (seria nieudanych prób) - nie sprawdzamy nawet hasła
+    let login_email = normalize_login_email(&payload.email);
+    if let Some(lockout) = active_lockout(&app_state.db_pool, &login_email).await? {
+        tracing::warn!(
+            "Odrzucono logowanie na zablokowany adres {} (blokada do {})",
+            login_email,
+            lockout.locked_until
         );
-        AppError::SqlxError(e) // Lub bardziej generyczny błąd serwera
-    })?;
+        return Ok(login_lockout_response(&lockout));
+    }
+
+    // 2. Znajdowanie użytkownika po emailu
+    let user_optional = repo::users::find_by_email(&app_state.db_pool, &payload.email)
+        .await
+        .inspect_err(|e| {
+            tracing::error!(
+                "Błąd bazy danych podczas wyszukiwania użytkownika {}: {:?}",
+                payload.email,
+                e
+            );
+        })?;
 
     let user = match user_optional {
         Some(u) => u,
@@ -937,6 +1831,12 @@ pub async fn login_handler(
                 "Nieudana próba logowania: użytkownik {} nie znaleziony.",
                 payload.email
             );
+            if let Some(lockout) =
+                register_failed_login(&app_state.db_pool, &login_email, None, client_ip.as_deref())
+                    .await?
+            {
+                return Ok(login_lockout_response(&lockout));
+            }
             let mut headers = HeaderMap::new();
             headers.insert("HX-Reswap", HeaderValue::from_static("none"));
             let trigger_payload = json!({
@@ -961,6 +1861,18 @@ pub async fn login_handler(
                     "Nieudana próba logowania dla {}: nieprawidłowe hasło.",
                     payload.email
                 );
+                if let Some(lockout) = register_failed_login(
+                    &app_state.db_pool,
+                    &login_email,
+                    Some(user.id),
+                    client_ip.as_deref(),
+                )
+                .await?
+                {
+                    let response = login_lockout_response(&lockout);
+                    spawn_lockout_email(app_state.clone(), lockout);
+                    return Ok(response);
+                }
                 let mut headers = HeaderMap::new();
                 headers.insert("HX-Reswap", HeaderValue::from_static("none"));
                 let trigger_payload = json!({
@@ -999,15 +1911,56 @@ pub async fn login_handler(
         }
     }
 
-    // 4. Logowanie pomyślne - generowanie tokenu JWT
+    if is_account_disabled(&app_state.db_pool, user.id).await? {
+        tracing::warn!("Odrzucono logowanie na zablokowane konto {}", user.email);
+        return Ok(disabled_account_response());
+    }
+
+    // 4. Personel z włączoną weryfikacją dwuetapową podaje jeszcze kod z aplikacji.
+    // Udaną próbę zapisujemy dopiero po kodzie - inaczej samo hasło zerowałoby licznik
+    // nieudanych prób i kody dałoby się zgadywać bez końca.
+    if requires_second_step(&app_state.db_pool, &user).await? {
+        tracing::info!(
+            "Hasło pracownika {} poprawne - oczekiwanie na kod weryfikacji dwuetapowej",
+            user.id
+        );
+        return Ok(two_factor_challenge_response(
+            &app_state.jwt_secret,
+            user.id,
+        ));
+    }
+
+    // 5. Logowanie pomyślne - zapis próby (zeruje licznik nieudanych) i generowanie tokenu JWT
+    record_login_attempt(
+        &app_state.db_pool,
+        &login_email,
+        Some(user.id),
+        true,
+        client_ip.as_deref(),
+    )
+    .await?;
+    // Personel bez weryfikacji dwuetapowej dostaje token z uprawnieniami klienta
+    // i trafia od razu do jej konfiguracji
+    let redirect = user.role.is_staff().then_some("/moje-konto/bezpieczenstwo");
+    Ok(login_success_response(&app_state, &user, false, redirect))
+}
+
+/// Wydaje JWT po udanym logowaniu: ciasteczko `token` (F5) i `loginSuccessDetails`,
+/// z którego index.html zapisuje token w localStorage (HTMX) i przechodzi na `redirect`
+fn login_success_response(
+    app_state: &AppState,
+    user: &User,
+    two_factor: bool,
+    redirect: Option<&str>,
+) -> (StatusCode, HeaderMap, Json<Value>) {
     match create_jwt(
         user.id, // Używamy ID i roli użytkownika pobranego z bazy
         user.role,
+        two_factor,
         &app_state.jwt_secret,
         app_state.jwt_expiration_hours,
     ) {
         Ok(token_str) => {
-            // --- POCZĄTEK NOWEGO KODU ---
             // Tworzymy bezpieczne ciasteczko z naszym tokenem JWT.
             let cookie = Cookie::build(("token", token_str.clone())) // Klonujemy token, bo użyjemy go też w triggerze
                 .path("/") // Ciasteczko będzie dostępne na całej stronie
@@ -1023,14 +1976,12 @@ pub async fn login_handler(
                 axum::http::header::SET_COOKIE,
                 cookie.to_string().parse().unwrap(),
             );
-            // --- KONIEC NOWEGO KODU ---
 
-            // Istniejąca logika nagłówków HTMX pozostaje bez zmian
             headers.insert("HX-Reswap", HeaderValue::from_static("none"));
 
             let trigger_payload = json!({
                 // Przekazujemy token do JS, aby mógł go zapisać w localStorage (dla HTMX)
-                "loginSuccessDetails": {"token": token_str},
+                "loginSuccessDetails": {"token": token_str, "redirect": redirect.unwrap_or("/")},
                 "showMessage": {"message": "Zalogowano pomyslnie!", "type": "success"}
             });
             if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
@@ -1043,7 +1994,7 @@ pub async fn login_handler(
                 user.id
             );
 
-            Ok((StatusCode::OK, headers, Json(json!({"status": "success"}))))
+            (StatusCode::OK, headers, Json(json!({"status": "success"})))
         }
         Err(e) => {
             tracing::error!("Błąd generowania tokenu JWT dla {}: {:?}", user.email, e);
@@ -1055,13 +2006,262 @@ pub async fn login_handler(
             if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
                 headers.insert("HX-Trigger", trigger_value);
             }
-            Ok((
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 headers,
                 Json(json!({"message": "Błąd serwera"})),
-            ))
+            )
+        }
+    }
+}
+
+/// Hasło admina poprawne - ciasteczko drugiego kroku i przejście do formularza kodu
+fn two_factor_challenge_response(
+    jwt_secret: &str,
+    user_id: Uuid,
+) -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut headers = HeaderMap::new();
+    let cookie = challenge_cookie(Some(create_challenge(jwt_secret, user_id)));
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+        headers.insert(header::SET_COOKIE, value);
+    }
+    headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+    let location_payload = json!({
+        "path": "/logowanie/weryfikacja",
+        "target": "#content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+    (
+        StatusCode::OK,
+        headers,
+        Json(json!({"status": "two_factor_required"})),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodePayload {
+    pub code: String,
+}
+
+/// Komunikat dla złego kodu weryfikacji dwuetapowej (bez polskich znaków - nagłówek HX-Trigger)
+fn invalid_two_factor_code_response() -> (StatusCode, HeaderMap, Json<Value>) {
+    let mut headers = HeaderMap::new();
+    headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+    let trigger_payload = json!({
+        "showMessage": {"message": "Nieprawidlowy kod weryfikacyjny.", "type": "error"}
+    });
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        headers,
+        Json(json!({"message": "Nieprawidłowy kod weryfikacyjny."})),
+    )
+}
+
+// POST /api/auth/2fa - drugi krok logowania admina (kod z aplikacji albo kod zapasowy)
+pub async fn verify_two_factor_login_handler(
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+    ClientIp(client_ip): ClientIp,
+    Form(payload): Form<TwoFactorCodePayload>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = CookieJar::from_headers(&request_headers)
+        .get(CHALLENGE_COOKIE)
+        .and_then(|cookie| verify_challenge(&app_state.jwt_secret, cookie.value()));
+    let Some(user_id) = user_id else {
+        // Znacznik wygasł albo go nie ma - od nowa, od hasła
+        let mut headers = HeaderMap::new();
+        let trigger_payload = json!({
+            "showMessage": {"message": "Czas na podanie kodu minal. Zaloguj sie ponownie.", "type": "error"}
+        });
+        if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+            headers.insert("HX-Trigger", trigger_value);
+        }
+        let location_payload =
+            json!({"path": "/logowanie", "target": "#content", "swap": "innerHTML"});
+        if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+            headers.insert("HX-Location", val);
+        }
+        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+        return Ok((
+            StatusCode::UNAUTHORIZED,
+            headers,
+            Json(json!({"message": "Czas na podanie kodu minął."})),
+        ));
+    };
+
+    let user = repo::users::find_by_id(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let login_email = normalize_login_email(&user.email);
+
+    // Zgadywanie kodów liczy się do tej samej blokady co zgadywanie haseł
+    if let Some(lockout) = active_lockout(&app_state.db_pool, &login_email).await? {
+        return Ok(login_lockout_response(&lockout));
+    }
+    // Konto mogło zostać zablokowane między hasłem a kodem
+    if is_account_disabled(&app_state.db_pool, user.id).await? {
+        return Ok(disabled_account_response());
+    }
+    if !verify_second_factor(&app_state.db_pool, user.id, &payload.code).await? {
+        tracing::warn!(
+            "Nieudana próba logowania dla {}: nieprawidłowy kod weryfikacji dwuetapowej.",
+            user.email
+        );
+        if let Some(lockout) = register_failed_login(
+            &app_state.db_pool,
+            &login_email,
+            Some(user.id),
+            client_ip.as_deref(),
+        )
+        .await?
+        {
+            let response = login_lockout_response(&lockout);
+            spawn_lockout_email(app_state.clone(), lockout);
+            return Ok(response);
         }
+        return Ok(invalid_two_factor_code_response());
+    }
+
+    record_login_attempt(
+        &app_state.db_pool,
+        &login_email,
+        Some(user.id),
+        true,
+        client_ip.as_deref(),
+    )
+    .await?;
+    let (status, mut headers, body) =
+        login_success_response(&app_state, &user, true, Some("/admin"));
+    if let Ok(value) = HeaderValue::from_str(&challenge_cookie(None).to_string()) {
+        headers.append(header::SET_COOKIE, value);
+    }
+    Ok((status, headers, body))
+}
+
+/// Konto z "Moje konto -> Bezpieczeństwo" - weryfikację dwuetapową konfiguruje tylko personel.
+/// Rolę sprawdzamy w bazie: token personelu bez drugiego kroku ma uprawnienia klienta.
+async fn two_factor_settings_user(
+    app_state: &AppState,
+    claims: &TokenClaims,
+) -> Result<User, AppError> {
+    let user = repo::users::find_by_id(&app_state.db_pool, claims.sub)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if !user.role.is_staff() {
+        return Err(AppError::UnauthorizedAccess(
+            "Weryfikacja dwuetapowa jest dostępna dla kont personelu sklepu.".to_string(),
+        ));
+    }
+    Ok(user)
+}
+
+/// Nowy JWT po zmianie weryfikacji dwuetapowej: ciasteczko `token` i `authTokenRefreshed`
+/// (index.html podmienia token w localStorage bez przeładowania strony)
+fn refreshed_token_headers(
+    app_state: &AppState,
+    user: &User,
+    two_factor: bool,
+    message: &str,
+) -> Result<HeaderMap, AppError> {
+    let token = create_jwt(
+        user.id,
+        user.role,
+        two_factor,
+        &app_state.jwt_secret,
+        app_state.jwt_expiration_hours,
+    )?;
+    let cookie = Cookie::build(("token", token.clone()))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::days(365))
+        .build();
+
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+        headers.insert(header::SET_COOKIE, value);
+    }
+    let trigger_payload = json!({
+        "authTokenRefreshed": {"token": token},
+        "showMessage": {"message": message, "type": "success"}
+    });
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    }
+    Ok(headers)
+}
+
+// POST /api/moje-konto/2fa/start
+pub async fn start_two_factor_setup_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Markup, AppError> {
+    let user = two_factor_settings_user(&app_state, &claims).await?;
+    let secret = begin_enrolment(&app_state.db_pool, user.id).await?;
+    Ok(render_two_factor_setup_maud(
+        &secret,
+        &otpauth_uri(&secret, &user.email),
+    ))
+}
+
+// POST /api/moje-konto/2fa/potwierdz
+pub async fn confirm_two_factor_setup_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<TwoFactorCodePayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let user = two_factor_settings_user(&app_state, &claims).await?;
+    let Some(codes) = confirm_enrolment(&app_state.db_pool, user.id, &payload.code).await? else {
+        return Err(toast_form_error(
+            "Nieprawidlowy kod. Sprawdz zegar telefonu i sprobuj ponownie.",
+        ));
+    };
+    // Konfiguracja potwierdzona kodem to pełnoprawny drugi krok - od razu token z uprawnieniami admina
+    let headers =
+        refreshed_token_headers(&app_state, &user, true, "Weryfikacja dwuetapowa wlaczona.")?;
+    Ok((headers, render_recovery_codes_maud(&codes)))
+}
+
+// POST /api/moje-konto/2fa/kody-zapasowe
+pub async fn regenerate_recovery_codes_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<TwoFactorCodePayload>,
+) -> Result<Markup, AppError> {
+    let user = two_factor_settings_user(&app_state, &claims).await?;
+    if !verify_second_factor(&app_state.db_pool, user.id, &payload.code).await? {
+        return Err(toast_form_error("Nieprawidlowy kod weryfikacyjny."));
+    }
+    let codes = regenerate_recovery_codes(&app_state.db_pool, user.id).await?;
+    Ok(render_recovery_codes_maud(&codes))
+}
+
+// POST /api/moje-konto/2fa/wylacz
+pub async fn disable_two_factor_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<TwoFactorCodePayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let user = two_factor_settings_user(&app_state, &claims).await?;
+    if !verify_second_factor(&app_state.db_pool, user.id, &payload.code).await? {
+        return Err(toast_form_error("Nieprawidlowy kod weryfikacyjny."));
     }
+    disable_two_factor(&app_state.db_pool, user.id).await?;
+    let headers = refreshed_token_headers(
+        &app_state,
+        &user,
+        false,
+        "Weryfikacja dwuetapowa wylaczona.",
+    )?;
+    let status = two_factor_status(&app_state.db_pool, user.id).await?;
+    Ok((headers, render_two_factor_section_maud(true, &status, 0)))
 }
 
 pub async fn protected_route_handler(claims: TokenClaims) -> Result<Json<Value>, AppError> {
@@ -1078,86 +2278,91 @@ pub async fn create_order_handler(
     State(app_state): State<Arc<AppState>>,
     OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
     guest_cart_id_header: Option<TypedHeader<XGuestCartId>>,
+    ClientIp(client_ip): ClientIp,
     Form(payload): Form<CheckoutFormPayload>,
 ) -> Result<(HeaderMap, Markup), AppError> {
-    if let Err(validation_errors) = payload.validate() {
-        tracing::warn!("Błąd walidacji danych checkout: {:?}", validation_errors);
-        let mut headers = HeaderMap::new();
-        let error_message_str = validation_errors.to_string();
-        headers.insert(
-            "HX-Trigger",
-            HeaderValue::from_str(&format!(
-                r#"{{"showMessage": {{"message": "Bledy w formularzu: {}", "type": "error"}}}}"#,
-                error_message_str.replace('"', "\\\"")
-            ))
-            .map_err(|_| {
-                AppError::InternalServerError("Failed to create HX-Trigger header".to_string())
-            })?,
+    let guest_cart_id_opt = guest_cart_id_header
+        .as_ref()
+        .map(|TypedHeader(XGuestCartId(guest_id))| *guest_id);
+    let items_total = {
+        let mut conn = app_state.db_pool.acquire().await?;
+        get_cart_details(&mut conn, user_claims_opt.clone(), guest_cart_id_opt)
+            .await?
+            .map_or(0, |details| details.total_price)
+    };
+
+    // Błędy pokazujemy przy polach właściwego kroku kasy, z wartościami wpisanymi przez klienta
+    if let Some((step, errors)) = validate_checkout_form(
+        &payload,
+        user_claims_opt.is_none(),
+        items_total,
+        app_state.przelewy24_config.is_some(),
+    ) {
+        tracing::warn!(
+            "Błąd walidacji danych checkout (krok {:?}): {:?}",
+            step,
+            errors
         );
-        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
-        return Ok((headers, html! {}));
+        return Err(checkout_step_errors(
+            &app_state,
+            user_claims_opt,
+            guest_cart_id_opt,
+            step,
+            &CheckoutStepPayload::from(&payload),
+            &errors,
+        )
+        .await);
     }
+    // Błąd przy jednym polu formularza (np. e-mail gościa zajęty przez konto)
+    let field_error = |field: &'static str, message: &str| {
+        let (name, step) = step_for_field(field).unwrap_or((field, CheckoutStep::Dane));
+        let mut errors = ValidationErrors::new();
+        errors.insert(name, message.to_string());
+        (step, errors)
+    };
 
     let mut order_user_id: Option<Uuid> = None;
     let mut order_guest_email: Option<String> = None;
     let mut order_guest_session_id: Option<Uuid> = None;
-    let cart_query_id: Uuid;
-    let cart_selector_sql: String;
+    let cart_owner: CartOwner;
 
+    let user_claims_for_errors = user_claims_opt.clone();
     if let Some(claims) = user_claims_opt {
         let user_id = claims.sub;
         order_user_id = Some(user_id);
-        cart_query_id = user_id;
-        cart_selector_sql =
-            "SELECT * FROM shopping_carts WHERE user_id = $1 FOR UPDATE".to_string();
+        cart_owner = CartOwner::User(user_id);
         tracing::info!("Zalogowany użytkownik {} składa zamówienie.", user_id);
     } else if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
         // Sprawdzamy, czy gość podał e-mail i czy ten e-mail istnieje już w bazie użytkowników.
         if let Some(email_to_check) = payload.guest_checkout_email.as_deref() {
             if !email_to_check.trim().is_empty() {
                 // Wykonujemy zapytanie do bazy PRZED rozpoczęciem transakcji.
-                let user_exists: Option<i32> =
-                    sqlx::query_scalar("SELECT 1 FROM users WHERE email = $1")
-                        .bind(email_to_check)
-                        .fetch_optional(&app_state.db_pool)
-                        .await?;
-
-                if user_exists.is_some() {
-                    // E-mail istnieje! Blokujemy zamówienie i wysyłamy błąd.
+                if repo::users::email_registered(&app_state.db_pool, email_to_check).await? {
+                    // E-mail istnieje! Blokujemy zamówienie i pokazujemy błąd przy polu.
                     tracing::warn!(
                         "Gość (sesja: {}) próbował złożyć zamówienie na zarejestrowany adres e-mail: {}",
                         guest_id,
                         email_to_check
                     );
-
-                    let mut headers = HeaderMap::new();
-                    let trigger_payload = json!({
-                        "showMessage": {
-                            "message": "Ten adres e-mail jest juz zarejestrowany. Zaloguj sie, aby kontynuowac.",
-                            "type": "error"
-                        }
-                    });
-
-                    // Używamy .unwrap(), ponieważ wiemy, że nasz JSON jest poprawny
-                    headers.insert(
-                        "HX-Trigger",
-                        HeaderValue::from_str(&trigger_payload.to_string()).unwrap(),
+                    let (step, errors) = field_error(
+                        "guest_email",
+                        "Ten adres e-mail jest już zarejestrowany. Zaloguj się, aby kontynuować.",
                     );
-                    headers.insert("HX-Reswap", HeaderValue::from_static("none")); // Nie podmieniaj widoku!
-
-                    // Zwracamy nasz nowy, niestandardowy błąd z nagłówkami.
-                    return Err(AppError::ConflictWithHeaders(
-                        "Email jest już zarejestrowany.".to_string(),
-                        headers,
-                    ));
+                    return Err(checkout_step_errors(
+                        &app_state,
+                        None,
+                        guest_cart_id_opt,
+                        step,
+                        &CheckoutStepPayload::from(&payload),
+                        &errors,
+                    )
+                    .await);
                 }
             }
         }
 
         order_guest_session_id = Some(guest_id);
-        cart_query_id = guest_id;
-        cart_selector_sql =
-            "SELECT * FROM shopping_carts WHERE guest_session_id = $1 FOR UPDATE".to_string();
+        cart_owner = CartOwner::Guest(guest_id);
         if payload.guest_checkout_email.is_none()
             || payload
                 .guest_checkout_email
@@ -1172,6 +2377,29 @@ pub async fn create_order_handler(
             headers.insert("HX-Reswap", HeaderValue::from_static("none"));
             return Ok((headers, html! {}));
         }
+        if payload
+            .guest_checkout_email
+            .as_deref()
+            .is_some_and(|email| app_state.disposable_email_blocklist.is_disposable(email))
+        {
+            tracing::warn!(
+                "Gość (sesja: {}) próbował złożyć zamówienie z jednorazowym adresem e-mail.",
+                guest_id
+            );
+            let (step, errors) = field_error(
+                "guest_email",
+                "Nie przyjmujemy adresów z tymczasowych skrzynek e-mail. Podaj swój stały adres.",
+            );
+            return Err(checkout_step_errors(
+                &app_state,
+                None,
+                guest_cart_id_opt,
+                step,
+                &CheckoutStepPayload::from(&payload),
+                &errors,
+            )
+            .await);
+        }
         order_guest_email = payload
             .guest_checkout_email
             .clone()
@@ -1190,28 +2418,17 @@ pub async fn create_order_handler(
 
     let mut tx = app_state.db_pool.begin().await?;
 
-    let cart = match sqlx::query_as::<_, ShoppingCart>(&cart_selector_sql)
-        .bind(cart_query_id)
-        .fetch_optional(&mut *tx)
-        .await?
-    {
+    let cart = match repo::carts::find_by_owner_for_update(&mut *tx, cart_owner).await? {
         Some(c) => c,
         None => {
-            tracing::warn!(
-                "Nie znaleziono koszyka dla identyfikatora: {}",
-                cart_query_id
-            );
+            tracing::warn!("Nie znaleziono koszyka dla właściciela: {:?}", cart_owner);
             return Err(AppError::UnprocessableEntity(
                 "Twój koszyk nie został znaleziony lub jest pusty.".to_string(),
             ));
         }
     };
 
-    let cart_items_db =
-        sqlx::query_as::<_, CartItem>("SELECT * FROM cart_items WHERE cart_id = $1 FOR UPDATE")
-            .bind(cart.id)
-            .fetch_all(&mut *tx)
-            .await?;
+    let cart_items_db = repo::carts::items_for_update(&mut *tx, cart.id).await?;
 
     if cart_items_db.is_empty() {
         tracing::warn!("Koszyk (ID: {}) jest pusty.", cart.id);
@@ -1222,15 +2439,13 @@ pub async fn create_order_handler(
 
     // ZMIANA: Optymalizacja N+1 - pobieranie wszystkich produktów jednym zapytaniem.
     let product_ids: Vec<Uuid> = cart_items_db.iter().map(|item| item.product_id).collect();
-    let products_in_cart =
-        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ANY($1) FOR UPDATE")
-            .bind(&product_ids)
-            .fetch_all(&mut *tx)
-            .await?;
+    let products_in_cart = repo::products::find_by_ids_for_update(&mut *tx, &product_ids).await?;
 
     let products_map: HashMap<Uuid, Product> =
         products_in_cart.into_iter().map(|p| (p.id, p)).collect();
 
+    let reserved_for_cart = reserved_product_ids_for_cart(&mut tx, cart.id, &product_ids).await?;
+
     let mut order_items_to_create: Vec<(Uuid, i64)> = Vec::with_capacity(cart_items_db.len());
     let mut total_price_items: i64 = 0;
     let mut product_ids_to_mark_sold: Vec<Uuid> = Vec::new();
@@ -1238,7 +2453,9 @@ pub async fn create_order_handler(
     for cart_item in &cart_items_db {
         match products_map.get(&cart_item.product_id) {
             Some(p) => {
-                if p.status != ProductStatus::Available {
+                let held_by_this_cart =
+                    p.status == ProductStatus::Reserved && reserved_for_cart.contains(&p.id);
+                if p.status != ProductStatus::Available && !held_by_this_cart {
                     tracing::warn!(
                         "Produkt {} (ID: {}) w koszyku jest niedostępny (status: {:?}).",
                         p.name,
@@ -1248,8 +2465,10 @@ pub async fn create_order_handler(
                     let error_html = render_checkout_error_page_maud(&p.name);
                     return Err(AppError::UnprocessableEntityWithHtml(error_html));
                 }
-                order_items_to_create.push((p.id, p.price));
-                total_price_items += p.price;
+                // Cena z trwającej promocji liczy się w chwili złożenia zamówienia
+                let price = p.effective_price();
+                order_items_to_create.push((p.id, price));
+                total_price_items += price;
                 product_ids_to_mark_sold.push(p.id);
             }
             None => {
@@ -1264,108 +2483,267 @@ pub async fn create_order_handler(
         }
     }
 
-    // REFAKTORYZACJA: Przeniesienie stałych do bardziej elastycznej konfiguracji.
-    // Na razie zostawiamy je tutaj, ale z komentarzem.
-    // TODO: Przenieść mapowanie kluczy metod dostawy na koszty i nazwy do konfiguracji lub bazy danych.
-    const SHIPPING_INPOST_COST: i64 = 1199;
-    const SHIPPING_INPOST_NAME: &str = "Paczkomat InPost 24/7";
-    const SHIPPING_POCZTA_COST: i64 = 1799;
-    const SHIPPING_POCZTA_NAME: &str = "Poczta Polska S.A.";
-    const SHIPPING_FREE_NAME: &str = "Darmowa dostawa";
-    const FREE_SHIPPING_THRESHOLD: i64 = 20000;
-
-    let (derived_shipping_cost, shipping_method_name_to_store): (i64, String) = match payload
-        .shipping_method_key
-        .as_str()
-    {
-        "inpost" => (SHIPPING_INPOST_COST, SHIPPING_INPOST_NAME.to_string()),
-        "poczta" => (SHIPPING_POCZTA_COST, SHIPPING_POCZTA_NAME.to_string()),
-        "darmowa" => {
-            // WAŻNA WALIDACJA: Sprawdź, czy serwer zgadza się, że dostawa jest darmowa.
-            if total_price_items >= FREE_SHIPPING_THRESHOLD {
-                (0, SHIPPING_FREE_NAME.to_string())
-            } else {
+    let (derived_shipping_cost, shipping_method_name_to_store): (i64, String) =
+        match find_shipping_option(&payload.shipping_method_key, total_price_items) {
+            Some(option) => (option.cost, option.name.to_string()),
+            None if payload.shipping_method_key == "darmowa" => {
                 // Jeśli ktoś spróbuje oszukać i wysłać "darmowa" przy zbyt małym zamówieniu
                 tracing::warn!(
                     "Próba użycia darmowej dostawy dla zamówienia poniżej progu: {}",
                     total_price_items
                 );
-                // Zwracamy błąd lub przypisujemy domyślną, płatną metodę
                 return Err(AppError::BadRequest(
                     "Nie kwalifikujesz się do darmowej dostawy.".to_string(),
                 ));
             }
-        }
-        _ => {
-            tracing::warn!(
-                "Nieprawidłowy lub brakujący klucz metody dostawy: '{}'",
-                payload.shipping_method_key
-            );
+            None => {
+                tracing::warn!(
+                    "Nieprawidłowy lub brakujący klucz metody dostawy: '{}'",
+                    payload.shipping_method_key
+                );
+                let mut headers = HeaderMap::new();
+                headers.insert("HX-Trigger", HeaderValue::from_static(r#"{"showMessage": {"message": "Prosze wybrac prawidlowa metode dostawy.", "type": "error"}}"#));
+                headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+                return Ok((headers, html! {}));
+            }
+        };
+
+    // Dostawa do Paczkomatu wymaga wybranego, istniejącego punktu
+    let (inpost_locker_code, inpost_locker_address) = if payload.shipping_method_key == "inpost" {
+        let code = payload
+            .inpost_locker_code
+            .as_deref()
+            .map(|c| c.trim().to_uppercase())
+            .filter(|c| !c.is_empty());
+        let Some(code) = code else {
             let mut headers = HeaderMap::new();
-            headers.insert("HX-Trigger", HeaderValue::from_str(r#"{{"showMessage": {{"message": "Proszę wybrać prawidłową metodę dostawy.", "type": "error"}}}}"#)
-                .map_err(|_| AppError::InternalServerError("Failed to create HX-Trigger for shipping method".to_string()))?);
+            headers.insert("HX-Trigger", HeaderValue::from_static(r#"{"showMessage": {"message": "Wybierz Paczkomat, do ktorego mamy wyslac paczke.", "type": "error"}}"#));
             headers.insert("HX-Reswap", HeaderValue::from_static("none"));
             return Ok((headers, html! {}));
-        }
+        };
+        // Adres bierzemy z ShipX; z formularza tylko, gdy API jest niedostępne
+        let locker_address = match fetch_point(&app_state.inpost_config, &code).await {
+            Ok(Some(point)) => Some(point.full_address()),
+            Ok(None) => {
+                drop(tx);
+                let (step, errors) = field_error(
+                    "inpost_locker_code",
+                    "Wybrany Paczkomat nie istnieje. Wybierz inny.",
+                );
+                return Err(checkout_step_errors(
+                    &app_state,
+                    user_claims_for_errors,
+                    guest_cart_id_opt,
+                    step,
+                    &CheckoutStepPayload::from(&payload),
+                    &errors,
+                )
+                .await);
+            }
+            Err(e) => {
+                // Awaria API InPost nie może blokować zamówień - kod sprawdzimy przy nadaniu
+                tracing::warn!("Nie udało się zweryfikować Paczkomatu {}: {:?}", code, e);
+                payload
+                    .inpost_locker_address
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string)
+            }
+        };
+        (Some(code), locker_address)
+    } else {
+        (None, None)
     };
 
     let payment_method_enum = PaymentMethod::from_str(&payload.payment_method)
         .map_err(|_| AppError::Validation("Nieprawidłowa metoda płatności.".to_string()))?;
+    if payment_method_enum == PaymentMethod::Przelewy24 && app_state.przelewy24_config.is_none() {
+        return Err(AppError::Validation(
+            "Płatność Przelewy24 jest obecnie niedostępna.".to_string(),
+        ));
+    }
 
-    let final_total_price = total_price_items + derived_shipping_cost;
-    let initial_status = OrderStatus::Pending;
-    let order_id = Uuid::new_v4();
-
-    sqlx::query(
-        r#"
-            INSERT INTO orders (
-                id, user_id, guest_email, guest_session_id, status, total_price,
-                shipping_first_name, shipping_last_name, shipping_address_line1, shipping_address_line2,
-                shipping_city, shipping_postal_code, shipping_country, shipping_phone, 
-                payment_method, shipping_method_name
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
-        "#,
-    )
-    .bind(order_id)
-    .bind(order_user_id)
-    .bind(option_string_empty_as_none(order_guest_email))
-    .bind(order_guest_session_id)
-    .bind(initial_status)
-    .bind(final_total_price)
-    .bind(&payload.shipping_first_name)
-    .bind(&payload.shipping_last_name)
-    .bind(&payload.shipping_address_line1)
-    .bind(option_string_empty_as_none(payload.shipping_address_line2.clone()))
-    .bind(&payload.shipping_city)
-    .bind(&payload.shipping_postal_code)
-    .bind(&payload.shipping_country)
-    .bind(&payload.shipping_phone)
-    .bind(payment_method_enum)
-    .bind(Some(shipping_method_name_to_store.clone()))
-    .execute(&mut *tx)
+    // Kod rabatowy sprawdzamy ponownie po stronie serwera, w tej samej transakcji co zamówienie
+    let applied_coupon = match payload
+        .coupon_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        Some(code) => match find_applicable_coupon(
+            &mut tx,
+            code,
+            total_price_items,
+            order_user_id,
+            order_guest_email.as_deref(),
+        )
+        .await?
+        {
+            Ok(coupon) => Some(coupon),
+            Err(rejection) => {
+                tracing::warn!(
+                    "Odrzucono kod rabatowy '{}' przy składaniu zamówienia: {:?}",
+                    code,
+                    rejection
+                );
+                let mut headers = HeaderMap::new();
+                headers.insert("HX-Trigger", HeaderValue::from_static(r#"{"showMessage": {"message": "Kod rabatowy nie moze zostac uzyty. Sprawdz go ponownie w podsumowaniu zamowienia.", "type": "error"}}"#));
+                headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+                return Ok((headers, html! {}));
+            }
+        },
+        None => None,
+    };
+    // Rabat dotyczy tylko produktów - koszt dostawy doliczamy w pełnej wysokości
+    let discount_amount = applied_coupon
+        .as_ref()
+        .map_or(0, |coupon| coupon.discount_for(total_price_items));
+    let order_total = total_price_items - discount_amount + derived_shipping_cost;
+
+    // Karta podarunkowa to forma płatności: pokrywa też dostawę i nie zmienia podstawy VAT
+    let applied_gift_card = match payload
+        .gift_card_code
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+    {
+        Some(code) => match find_usable_gift_card(&mut tx, code).await? {
+            Ok(gift_card) => Some(gift_card),
+            Err(rejection) => {
+                tracing::warn!(
+                    "Odrzucono kartę podarunkową przy składaniu zamówienia: {:?}",
+                    rejection
+                );
+                let mut headers = HeaderMap::new();
+                headers.insert("HX-Trigger", HeaderValue::from_static(r#"{"showMessage": {"message": "Karta podarunkowa nie moze zostac uzyta. Sprawdz ja ponownie w podsumowaniu zamowienia.", "type": "error"}}"#));
+                headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+                return Ok((headers, html! {}));
+            }
+        },
+        None => None,
+    };
+    let gift_card_amount = applied_gift_card
+        .as_ref()
+        .map_or(0, |gift_card| gift_card.balance.min(order_total));
+
+    let final_total_price = order_total - gift_card_amount;
+    let item_prices: Vec<i64> = order_items_to_create
+        .iter()
+        .map(|(_, price_at_purchase)| *price_at_purchase)
+        .collect();
+    let order_vat = OrderVat::compute(
+        app_state.vat_config,
+        &item_prices,
+        discount_amount,
+        derived_shipping_cost,
+    );
+    let initial_status = OrderStatus::Pending;
+    let order_id = Uuid::new_v4();
+    let order_number = next_order_number(&mut tx, &app_state.order_number_config).await?;
+
+    let shipping_address_line2 =
+        option_string_empty_as_none(payload.shipping_address_line2.clone());
+    repo::orders::insert(
+        &mut *tx,
+        &repo::orders::NewOrder {
+            id: order_id,
+            order_number: &order_number,
+            user_id: order_user_id,
+            guest_email: order_guest_email
+                .as_deref()
+                .filter(|email| !email.is_empty()),
+            guest_session_id: order_guest_session_id,
+            status: initial_status,
+            total_price: final_total_price,
+            shipping_first_name: &payload.shipping_first_name,
+            shipping_last_name: &payload.shipping_last_name,
+            shipping_address_line1: &payload.shipping_address_line1,
+            shipping_address_line2: shipping_address_line2.as_deref(),
+            shipping_city: &payload.shipping_city,
+            shipping_postal_code: &payload.shipping_postal_code,
+            shipping_country: &payload.shipping_country,
+            shipping_phone: &payload.shipping_phone,
+            payment_method: payment_method_enum,
+            shipping_method_name: Some(&shipping_method_name_to_store),
+            inpost_locker_code: inpost_locker_code.as_deref(),
+            inpost_locker_address: inpost_locker_address.as_deref(),
+            coupon_id: applied_coupon.as_ref().map(|coupon| coupon.id),
+            discount_amount,
+            gift_card_amount,
+            vat: &order_vat,
+        },
+    )
     .await?;
 
-    for (product_id, price_at_purchase) in order_items_to_create {
-        sqlx::query(
-            "INSERT INTO order_items (order_id, product_id, price_at_purchase) VALUES ($1, $2, $3)",
+    if let Some(coupon) = &applied_coupon {
+        record_coupon_redemption(
+            &mut tx,
+            coupon.id,
+            order_id,
+            order_user_id,
+            order_guest_email.as_deref(),
         )
-        .bind(order_id)
-        .bind(product_id)
-        .bind(price_at_purchase)
-        .execute(&mut *tx)
         .await?;
+        tracing::info!(
+            "Zamówienie {}: zastosowano kod rabatowy '{}', rabat {} gr",
+            order_id,
+            coupon.code,
+            discount_amount
+        );
     }
 
-    sqlx::query("DELETE FROM cart_items WHERE cart_id = $1")
-        .bind(cart.id)
-        .execute(&mut *tx)
+    record_order_status_change(
+        &mut tx,
+        order_id,
+        None,
+        OrderStatus::Pending,
+        None,
+        Some("Zamówienie złożone"),
+    )
+    .await?;
+
+    if let Some(gift_card) = &applied_gift_card {
+        if gift_card_amount > 0 {
+            redeem_gift_card(&mut tx, gift_card.id, order_id, gift_card_amount).await?;
+            tracing::info!(
+                "Zamówienie {}: zapłacono kartą podarunkową {} gr",
+                order_id,
+                gift_card_amount
+            );
+        }
+        // Karta pokryła całość - nie ma na co czekać z realizacją
+        if final_total_price == 0 {
+            transition_order_status(
+                &mut tx,
+                order_id,
+                OrderStatus::Processing,
+                None,
+                Some("Zamówienie opłacone kartą podarunkową"),
+            )
+            .await?;
+        }
+    }
+
+    for ((product_id, price_at_purchase), item_vat) in
+        order_items_to_create.into_iter().zip(&order_vat.items)
+    {
+        repo::orders::insert_item(
+            &mut *tx,
+            order_id,
+            product_id,
+            price_at_purchase,
+            order_vat.rate_bp,
+            item_vat,
+        )
         .await?;
+    }
+
+    repo::carts::clear(&mut *tx, cart.id).await?;
+    mark_draft_submitted(&mut tx, cart.id, order_id).await?;
 
     if order_user_id.is_none() && cart.guest_session_id.is_some() {
-        sqlx::query("DELETE FROM shopping_carts WHERE id = $1")
-            .bind(cart.id)
-            .execute(&mut *tx)
-            .await?;
+        repo::carts::delete(&mut *tx, cart.id).await?;
         tracing::info!(
             "Usunięto koszyk gościa (ID: {}) po złożeniu zamówienia.",
             cart.id
@@ -1374,34 +2752,52 @@ pub async fn create_order_handler(
 
     // ZMIANA: Status produktu zmieniony na 'Sold', nie 'Reserved'
     if !product_ids_to_mark_sold.is_empty() {
-        sqlx::query(r#"UPDATE products SET status = $1 WHERE id = ANY($2)"#)
-            .bind(ProductStatus::Sold)
-            .bind(&product_ids_to_mark_sold)
-            .execute(&mut *tx)
+        repo::products::set_status_many(&mut *tx, &product_ids_to_mark_sold, ProductStatus::Sold)
             .await?;
+        clear_product_reservations(&mut tx, &product_ids_to_mark_sold).await?;
     }
 
     tx.commit().await?;
+    app_state.metrics.record_order_created();
+    // Sprzedane produkty znikają z feedu Zakupów Google
+    invalidate_merchant_feed(&app_state).await;
+    notify_search_engines(app_state.clone(), product_ids_to_mark_sold);
+
+    record_event(
+        &app_state.db_pool,
+        EventType::OrderPlaced,
+        NewEvent {
+            user_id: order_user_id,
+            guest_session_id: cart.guest_session_id,
+            order_id: Some(order_id),
+            metadata: Some(json!({ "total_price": final_total_price })),
+            ..Default::default()
+        },
+    );
 
     // === WYSYŁANIE E-MAIL ===
-    // match fetch_order_details_service(&app_state.db_pool, order_id).await {
-    //     Ok(details) => {
-    //         if let Err(e) = send_order_confirmation_email(&app_state, &details).await {
-    //             tracing::error!(
-    //                 "Nie udało się wysłać e-maila z potwierdzeniem dla zamówienia {}: {:?}",
-    //                 order_id,
-    //                 e
-    //             );
-    //         }
-    //     }
-    //     Err(e) => {
-    //         tracing::error!(
-    //             "Nie udało się pobrać szczegółów zamówienia {} do wysłania e-maila: {:?}",
-    //             order_id,
-    //             e
-    //         );
-    //     }
-    // }
+    // W tle, żeby opóźnienie Resend nie wydłużało składania zamówienia
+    let email_app_state = app_state.clone();
+    app_state.background_jobs.spawn(async move {
+        match fetch_order_details_service(&email_app_state.db_pool, order_id).await {
+            Ok(details) => {
+                if let Err(e) = send_order_confirmation_email(&email_app_state, &details).await {
+                    tracing::error!(
+                        "Nie udało się wysłać e-maila z potwierdzeniem dla zamówienia {}: {:?}",
+                        order_id,
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Nie udało się pobrać szczegółów zamówienia {} do wysłania e-maila: {:?}",
+                    order_id,
+                    e
+                );
+            }
+        }
+    });
 
     tracing::info!(
         "Utworzono nowe zamówienie ID: {} z metodą dostawy: '{}', koszt dostawy: {} gr, suma końcowa: {} gr",
@@ -1415,9 +2811,51 @@ pub async fn create_order_handler(
     // Używamy `fetch_order_details_service`, który już mamy!
     let order_details = fetch_order_details_service(&app_state.db_pool, order_id).await?;
 
+    // Ocena ryzyka nie blokuje zamówienia - w razie błędu tylko logujemy.
+    if let Err(e) =
+        assess_and_store_order_risk(&app_state, &order_details.order, client_ip.as_deref()).await
+    {
+        tracing::error!(
+            "Nie udało się ocenić ryzyka zamówienia {}: {:?}",
+            order_id,
+            e
+        );
+    }
+
+    // Płatność online: rejestrujemy transakcję i przekierowujemy klienta do Przelewy24.
+    // Jeśli bramka zawiedzie, pokazujemy zwykłą stronę podziękowania z linkiem do ponowienia płatności.
+    if order_details.order.payment_method == Some(PaymentMethod::Przelewy24)
+        && order_details.order.total_price > 0
+        && let Some(p24_config) = &app_state.przelewy24_config
+    {
+        match start_przelewy24_payment(&app_state, p24_config, &order_details.order).await {
+            Ok(payment_url) => {
+                let mut headers = HeaderMap::new();
+                if let Ok(val) = HeaderValue::from_str(&payment_url) {
+                    headers.insert("HX-Redirect", val);
+                    headers.insert(
+                        "HX-Trigger",
+                        HeaderValue::from_static(r#"{"clearCartDisplay": {}}"#),
+                    );
+                    return Ok((headers, html! {}));
+                }
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Nie udało się rozpocząć płatności Przelewy24 dla zamówienia {}: {:?}",
+                    order_id,
+                    e
+                );
+            }
+        }
+    }
+
     // 2. Wyrenderuj widok strony z podziękowaniem, używając naszej nowej funkcji
-    let final_response_html =
-        render_thank_you_page_maud(&order_details.order, &order_details.items);
+    let final_response_html = render_thank_you_page_maud(
+        &order_details.order,
+        &order_details.items,
+        &app_state.payment_details,
+    );
 
     // 5. Przygotuj nagłówki dla HTMX
     let mut headers = HeaderMap::new();
@@ -1445,6 +2883,376 @@ pub async fn create_order_handler(
     Ok((headers, final_response_html))
 }
 
+/// Ponawia płatność Przelewy24 dla nieopłaconego zamówienia (np. po przerwanej płatności).
+pub async fn retry_przelewy24_payment_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Redirect, AppError> {
+    let p24_config = app_state
+        .przelewy24_config
+        .as_ref()
+        .ok_or(AppError::NotFound)?;
+
+    let order = repo::orders::find_by_id(&app_state.db_pool, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if order.payment_method != Some(PaymentMethod::Przelewy24)
+        || order.status != OrderStatus::Pending
+    {
+        return Ok(Redirect::to(&format!(
+            "/zamowienie/dziekujemy/{}",
+            order_id
+        )));
+    }
+
+    let payment_url = start_przelewy24_payment(&app_state, p24_config, &order).await?;
+    Ok(Redirect::to(&payment_url))
+}
+
+/// Kontener na komunikaty formularza zakupu karty (`gift_card_purchase_page_handler`)
+const GIFT_CARD_MESSAGES_TARGET: &str = "#gift-card-messages";
+
+/// Zakup karty podarunkowej: zakłada nieopłaconą kartę i przekierowuje do Przelewy24,
+/// a bez płatności online - na stronę karty z danymi do przelewu.
+pub async fn purchase_gift_card_handler(
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    request_headers: HeaderMap,
+    Form(payload): Form<GiftCardPurchasePayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    let mut errors = match payload.validate() {
+        Ok(()) => ValidationErrors::new(),
+        Err(validation_errors) => ValidationErrors::from(&validation_errors),
+    };
+    if !GIFT_CARD_AMOUNTS.contains(&payload.amount) {
+        errors.insert("amount", "Wybierz wartość karty z listy.");
+    }
+    let recipient_name = payload
+        .recipient_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    let message = payload
+        .message
+        .as_deref()
+        .map(str::trim)
+        .filter(|message| !message.is_empty());
+    if message.is_some_and(|message| message.chars().count() > MAX_GIFT_CARD_MESSAGE_LEN) {
+        errors.insert(
+            "message",
+            format!(
+                "Wiadomość może mieć najwyżej {} znaków.",
+                MAX_GIFT_CARD_MESSAGE_LEN
+            ),
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors.into_error(&request_headers, GIFT_CARD_MESSAGES_TARGET));
+    }
+
+    let gift_card = create_gift_card(
+        &app_state.db_pool,
+        &NewGiftCard {
+            amount: payload.amount,
+            purchaser_email: &payload.purchaser_email,
+            purchaser_user_id: user_claims_opt.as_ref().map(|claims| claims.sub),
+            recipient_email: &payload.recipient_email,
+            recipient_name,
+            message,
+        },
+    )
+    .await?;
+    tracing::info!(
+        "Złożono zamówienie na kartę podarunkową {} ({} gr)",
+        gift_card.id,
+        gift_card.initial_amount
+    );
+
+    let mut redirect_url = format!("/karta-podarunkowa/{}", gift_card.id);
+    if let Some(p24_config) = &app_state.przelewy24_config {
+        match start_gift_card_payment(&app_state, p24_config, &gift_card).await {
+            Ok(payment_url) => redirect_url = payment_url,
+            // Strona karty pozwala ponowić płatność
+            Err(e) => tracing::error!(
+                "Nie udało się rozpocząć płatności za kartę podarunkową {}: {:?}",
+                gift_card.id,
+                e
+            ),
+        }
+    }
+
+    let mut headers = HeaderMap::new();
+    if let Ok(val) = HeaderValue::from_str(&redirect_url) {
+        headers.insert("HX-Redirect", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+/// Ponawia płatność Przelewy24 za nieopłaconą kartę podarunkową
+pub async fn retry_gift_card_payment_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(gift_card_id): Path<Uuid>,
+) -> Result<Redirect, AppError> {
+    let p24_config = app_state
+        .przelewy24_config
+        .as_ref()
+        .ok_or(AppError::NotFound)?;
+    let gift_card = find_gift_card(&app_state.db_pool, gift_card_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if gift_card.paid_at.is_some() {
+        return Ok(Redirect::to(&format!(
+            "/karta-podarunkowa/{}",
+            gift_card_id
+        )));
+    }
+
+    let payment_url = start_gift_card_payment(&app_state, p24_config, &gift_card).await?;
+    Ok(Redirect::to(&payment_url))
+}
+
+/// Powiadomienie o płatności z Przelewy24 (`urlStatus`).
+///
+/// 1. Sprawdza podpis i zgodność kwoty z zarejestrowaną płatnością.
+/// 2. Potwierdza transakcję w Przelewy24 (`transaction/verify`).
+/// 3. Oznacza płatność jako opłaconą i przenosi zamówienie do statusu `Processing`
+///    albo - przy zakupie karty podarunkowej - aktywuje kartę i wysyła kod obdarowanemu.
+pub async fn przelewy24_webhook_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(notification): Json<Przelewy24Notification>,
+) -> Result<StatusCode, AppError> {
+    let p24_config = app_state
+        .przelewy24_config
+        .as_ref()
+        .ok_or(AppError::NotFound)?;
+
+    if notification.merchant_id != p24_config.merchant_id
+        || notification.pos_id != p24_config.pos_id
+        || !verify_notification_sign(p24_config, &notification)?
+    {
+        tracing::warn!(
+            "Odrzucono powiadomienie Przelewy24 z nieprawidłowym podpisem (sesja: {})",
+            notification.session_id
+        );
+        return Err(AppError::BadRequest("Nieprawidłowy podpis.".to_string()));
+    }
+
+    let payment = sqlx::query_as::<_, Payment>("SELECT * FROM payments WHERE session_id = $1")
+        .bind(&notification.session_id)
+        .fetch_optional(&app_state.db_pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if payment.status == "paid" {
+        // Przelewy24 ponawia powiadomienia - drugie i kolejne ignorujemy
+        return Ok(StatusCode::OK);
+    }
+    if payment.amount != notification.amount {
+        tracing::error!(
+            "Kwota w powiadomieniu Przelewy24 ({}) różni się od płatności {} ({})",
+            notification.amount,
+            payment.id,
+            payment.amount
+        );
+        return Err(AppError::BadRequest("Nieprawidłowa kwota.".to_string()));
+    }
+
+    verify_przelewy24_transaction(p24_config, &notification).await?;
+
+    let mut tx = app_state.db_pool.begin().await?;
+    sqlx::query(
+        "UPDATE payments SET status = 'paid', provider_order_id = $1, updated_at = NOW() WHERE id = $2",
+    )
+    .bind(notification.order_id)
+    .bind(payment.id)
+    .execute(&mut *tx)
+    .await?;
+
+    if let Some(gift_card_id) = payment.gift_card_id {
+        let activated = mark_gift_card_paid(&mut tx, gift_card_id).await?;
+        tx.commit().await?;
+        if let Some(gift_card) = activated {
+            deliver_gift_card(app_state.clone(), gift_card);
+        }
+        tracing::info!(
+            "Płatność Przelewy24 za kartę podarunkową {} potwierdzona (P24 orderId: {})",
+            gift_card_id,
+            notification.order_id
+        );
+        return Ok(StatusCode::OK);
+    }
+    let order_id = payment.order_id.ok_or(AppError::NotFound)?;
+
+    let current_status = repo::orders::status_for_update(&mut *tx, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    // Admin mógł w międzyczasie zmienić status ręcznie - wtedy nie ruszamy zamówienia
+    let status_changed = if current_status == OrderStatus::Pending {
+        let (_, changed) = transition_order_status(
+            &mut tx,
+            order_id,
+            OrderStatus::Processing,
+            None,
+            Some("Płatność Przelewy24 potwierdzona"),
+        )
+        .await?;
+        changed
+    } else {
+        false
+    };
+    tx.commit().await?;
+    if status_changed {
+        spawn_order_status_email(app_state.clone(), order_id);
+    }
+
+    tracing::info!(
+        "Płatność Przelewy24 dla zamówienia {} potwierdzona (P24 orderId: {})",
+        order_id,
+        notification.order_id
+    );
+    Ok(StatusCode::OK)
+}
+
+/// Proxy do wyszukiwarki Paczkomatów InPost ShipX (kasa, wybór punktu odbioru).
+pub async fn inpost_points_handler(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<InpostPointsQuery>,
+) -> Result<Json<Vec<InpostPoint>>, AppError> {
+    if query.q.trim().chars().count() < 3 {
+        return Ok(Json(Vec::new()));
+    }
+    let points = search_points(&app_state.inpost_config, &query.q).await?;
+    Ok(Json(points))
+}
+
+/// Faktura VAT marża zamówienia w PDF (`/moje-konto/zamowienia/{id}/faktura`). Dostępna dla
+/// właściciela zamówienia i admina. Faktura jest wystawiana po opłaceniu zamówienia -
+/// wcześniej zwracamy 409.
+pub async fn download_invoice_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let order_details = fetch_order_details_service(&app_state.db_pool, order_id).await?;
+    if !claims.role.has_permission(Permission::ManageOrders)
+        && order_details.order.user_id != Some(claims.sub)
+    {
+        return Err(AppError::UnauthorizedAccess(
+            "Nie masz uprawnień do tego zamówienia".to_string(),
+        ));
+    }
+
+    let (invoice, pdf) = invoice_pdf_for_order(&app_state, &order_details)
+        .await?
+        .ok_or_else(|| {
+            AppError::Conflict("Faktura zostanie wystawiona po opłaceniu zamówienia.".to_string())
+        })?;
+    let disposition = format!("inline; filename=\"{}\"", invoice_filename(&invoice));
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        pdf,
+    ))
+}
+
+/// Etykieta InPost (PDF) dla zamówienia z dostawą do Paczkomatu.
+/// Przy pierwszym wywołaniu tworzy przesyłkę w ShipX; etykieta bywa dostępna dopiero po chwili.
+pub async fn inpost_label_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+
+    let order = repo::orders::find_by_id(&app_state.db_pool, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let existing_shipment: Option<(i64, Option<String>)> = sqlx::query_as(
+        "SELECT shipment_id, tracking_number FROM inpost_shipments WHERE order_id = $1",
+    )
+    .bind(order_id)
+    .fetch_optional(&app_state.db_pool)
+    .await?;
+
+    let (shipment_id, tracking_number) = match existing_shipment {
+        Some(shipment) => shipment,
+        None => {
+            let recipient_email = resolve_order_recipient_email(&app_state, &order).await?;
+            let shipment_id =
+                create_locker_shipment(&app_state.inpost_config, &order, &recipient_email).await?;
+            sqlx::query("INSERT INTO inpost_shipments (order_id, shipment_id) VALUES ($1, $2)")
+                .bind(order_id)
+                .bind(shipment_id)
+                .execute(&app_state.db_pool)
+                .await?;
+            tracing::info!(
+                "Admin {} utworzył przesyłkę InPost {} dla zamówienia {}",
+                claims.sub,
+                shipment_id,
+                order_id
+            );
+            (shipment_id, None)
+        }
+    };
+
+    if tracking_number.is_none()
+        && let Some(tracking) = fetch_tracking_number(&app_state.inpost_config, shipment_id).await?
+    {
+        sqlx::query("UPDATE inpost_shipments SET tracking_number = $1 WHERE order_id = $2")
+            .bind(&tracking)
+            .bind(order_id)
+            .execute(&app_state.db_pool)
+            .await?;
+    }
+
+    let label = fetch_label_pdf(&app_state.inpost_config, shipment_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::Conflict(
+                "Przesyłka została utworzona, ale InPost jeszcze przygotowuje etykietę. Spróbuj ponownie za chwilę."
+                    .to_string(),
+            )
+        })?;
+
+    let disposition = format!(
+        "inline; filename=\"etykieta-inpost-{}.pdf\"",
+        &order_id.to_string()[..8]
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        label,
+    ))
+}
+
+/// Kartka z podziękowaniem do paczki - przy pierwszym otwarciu wydaje osobisty kod rabatowy
+pub async fn thank_you_card_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    csp_nonce: CspNonce,
+    Path(order_id): Path<Uuid>,
+) -> Result<Markup, AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+
+    let order_details = fetch_order_details_service(&app_state.db_pool, order_id).await?;
+    let coupon = get_or_issue_thank_you_code(&app_state.db_pool, order_id).await?;
+    let care_instructions = care_instructions_for_order(&app_state.db_pool, &order_details).await?;
+    // Osobny dokument do druku (bez `build_response`) - nonce CSP żądania oznacza skrypt przycisku
+    Ok(render_thank_you_card(
+        &order_details,
+        &coupon,
+        &care_instructions,
+        &app_state.public_base_url,
+        csp_nonce.as_str(),
+    ))
+}
+
 pub async fn list_orders_handler(
     State(app_state): State<Arc<AppState>>,
     claims: TokenClaims, // Potrzebne do rozróżnienia admin/klient
@@ -1474,7 +3282,7 @@ pub async fn list_orders_handler(
     let mut data_query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
         r#"
             SELECT
-                o.id, o.user_id,
+                o.id, o.order_number, o.user_id,
                 o.order_date,
                 o.status,
                 o.total_price,
@@ -1487,6 +3295,11 @@ pub async fn list_orders_handler(
                 o.shipping_country,
                 o.shipping_phone,
                 o.shipping_method_name,
+                o.inpost_locker_code,
+                o.inpost_locker_address,
+                o.coupon_id,
+                o.discount_amount,
+                o.gift_card_amount,
                 o.payment_method,
                 o.guest_email,
                 o.guest_session_id,
@@ -1506,7 +3319,7 @@ pub async fn list_orders_handler(
         }
     };
 
-    if user_role != Role::Admin {
+    if !user_role.has_permission(Permission::ManageOrders) {
         // Klient widzi tylko swoje zamówienia
         append_where_or_and_count(&mut count_query_builder);
         count_query_builder.push(" o.user_id = ").push_bind(user_id);
@@ -1560,6 +3373,8 @@ pub async fn list_orders_handler(
             count_query_builder
                 .push(" (CAST(o.id AS TEXT) ILIKE ")
                 .push_bind(like_pattern.clone())
+                .push(" OR o.order_number ILIKE ")
+                .push_bind(like_pattern.clone())
                 .push(" OR o.shipping_last_name ILIKE ")
                 .push_bind(like_pattern.clone())
                 .push(" OR o.guest_email ILIKE ")
@@ -1571,6 +3386,8 @@ pub async fn list_orders_handler(
             data_query_builder
                 .push(" (CAST(o.id AS TEXT) ILIKE ")
                 .push_bind(like_pattern.clone())
+                .push(" OR o.order_number ILIKE ")
+                .push_bind(like_pattern.clone())
                 .push(" OR o.shipping_last_name ILIKE ")
                 .push_bind(like_pattern.clone())
                 .push(" OR o.guest_email ILIKE ")
@@ -1632,7 +3449,9 @@ pub async fn get_order_details_handler(
     let order_details = fetch_order_details_service(&app_state.db_pool, order_id).await?;
 
     // Krok 2: Sprawdź uprawnienia na pobranych danych
-    if user_role != Role::Admin && order_details.order.user_id != Some(user_id) {
+    if !user_role.has_permission(Permission::ManageOrders)
+        && order_details.order.user_id != Some(user_id)
+    {
         tracing::warn!(
             "Nieautoryzowany dostęp do zamówienia: order_id={}, user_id={}, user_role={:?}",
             order_id,
@@ -1659,59 +3478,2711 @@ pub async fn update_order_status_handler(
     Form(payload): Form<UpdateOrderStatusPayload>,
 ) -> Result<(StatusCode, HeaderMap, Json<Order>), AppError> {
     // Zwracamy też zaktualizowany Order
-    if claims.role != Role::Admin {
-        return Err(AppError::UnauthorizedAccess(
-            "Tylko administrator może zmieniać status zamówienia".to_string(),
-        ));
-    }
+    claims.require_permission(Permission::ManageOrders)?;
 
-    let updated_order_opt = sqlx::query_as::<_, Order>(
-        r#"
-            UPDATE orders
-            SET status = $1, updated_at = CURRENT_TIMESTAMP
-            WHERE id = $2
-            RETURNING *
-        "#,
+    let mut tx = app_state.db_pool.begin().await?;
+    let (order, status_changed) = match transition_order_status(
+        &mut tx,
+        order_id,
+        payload.status.clone(),
+        Some(claims.sub),
+        None,
     )
-    .bind(&payload.status)
-    .bind(order_id)
-    .fetch_optional(&app_state.db_pool)
-    .await?;
-
-    match updated_order_opt {
-        Some(order) => {
-            tracing::info!(
-                "Zaktualizowano status zamówienia: order_id={}, nowy_status={:?}, admin_id={}",
+    .await
+    {
+        Ok(result) => result,
+        Err(AppError::Conflict(message)) => {
+            tracing::warn!(
+                "Odrzucono zmianę statusu zamówienia {} przez admina {}: {}",
                 order_id,
-                payload.status,
-                claims.sub
+                claims.sub,
+                message
             );
-
+            // Przeładowanie widoku przywraca w selekcie faktyczny status
             let mut headers = HeaderMap::new();
-
-            // Jeden HX-Trigger z obiektem JSON zawierającym wiele zdarzeń
-            let trigger_payload = serde_json::json!({
-                "reloadAdminOrderList": true, // Zdarzenie do przeładowania listy
-                "showMessage": {              // Zdarzenie do wyświetlenia toasta
-                    "message": "Status zamowienia zostal pomyslnie zaktualizowany.",
-                    "type": "success"
+            let trigger_payload = json!({
+                "reloadAdminOrderList": true,
+                "showMessage": {
+                    "message": "Niedozwolona zmiana statusu zamowienia.",
+                    "type": "error"
                 }
             });
+            if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+                headers.insert("HX-Trigger", val);
+            }
+            return Err(AppError::ConflictWithHeaders(message, headers));
+        }
+        Err(e) => return Err(e),
+    };
+    tx.commit().await?;
+
+    tracing::info!(
+        "Zaktualizowano status zamówienia: order_id={}, nowy_status={:?}, admin_id={}",
+        order_id,
+        payload.status,
+        claims.sub
+    );
+
+    if status_changed {
+        spawn_order_status_email(app_state.clone(), order_id);
+    }
+
+    let mut headers = HeaderMap::new();
+
+    // Jeden HX-Trigger z obiektem JSON zawierającym wiele zdarzeń
+    let trigger_payload = serde_json::json!({
+        "reloadAdminOrderList": true, // Zdarzenie do przeładowania listy
+        "showMessage": {              // Zdarzenie do wyświetlenia toasta
+            "message": "Status zamowienia zostal pomyslnie zaktualizowany.",
+            "type": "success"
+        }
+    });
+
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers, Json(order))) // Zwracamy OK, nagłówki i zaktualizowany obiekt Order
+}
+
+/// Wysyła w tle e-mail o nowym statusie zamówienia. Błąd wysyłki jest tylko logowany.
+fn spawn_order_status_email(app_state: Arc<AppState>, order_id: Uuid) {
+    let background_jobs = app_state.background_jobs.clone();
+    background_jobs.spawn(async move {
+        let details = match fetch_order_details_service(&app_state.db_pool, order_id).await {
+            Ok(details) => details,
+            Err(e) => {
+                tracing::error!(
+                    "Nie udało się pobrać szczegółów zamówienia {} do wysłania e-maila: {:?}",
+                    order_id,
+                    e
+                );
+                return;
+            }
+        };
+        let tracking_number: Option<String> =
+            sqlx::query_scalar("SELECT tracking_number FROM inpost_shipments WHERE order_id = $1")
+                .bind(order_id)
+                .fetch_optional(&app_state.db_pool)
+                .await
+                .ok()
+                .flatten()
+                .flatten();
+
+        if let Err(e) =
+            send_order_status_email(&app_state, &details, tracking_number.as_deref()).await
+        {
+            tracing::error!(
+                "Nie udało się wysłać e-maila o statusie '{}' zamówienia {}: {:?}",
+                details.order.status,
+                order_id,
+                e
+            );
+        }
+    });
+}
+
+/// Usuwa pojedynczą pozycję z zamówienia (np. produkt okazał się uszkodzony przed wysyłką),
+/// rejestruje zwrot kwoty za tę pozycję i powiadamia klienta. Reszta zamówienia jest realizowana dalej.
+pub async fn remove_order_item_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((order_id, order_item_id)): Path<(Uuid, Uuid)>,
+    Form(payload): Form<RemoveOrderItemPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+    payload.validate()?;
+
+    let mut tx = app_state.db_pool.begin().await?;
+
+    // Krok 1: Zablokuj zamówienie i sprawdź, czy można je jeszcze edytować.
+    let order = repo::orders::find_by_id_for_update(&mut *tx, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if !matches!(order.status, OrderStatus::Pending | OrderStatus::Processing) {
+        return Err(AppError::Conflict(format!(
+            "Nie można edytować zamówienia o statusie '{}'.",
+            order.status
+        )));
+    }
+
+    // Krok 2: Pobierz pozycję i upewnij się, że nie jest ostatnią w zamówieniu.
+    let item = repo::orders::find_item_for_update(&mut *tx, order_id, order_item_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let items_count = repo::orders::item_count(&mut *tx, order_id).await?;
+    if items_count <= 1 {
+        return Err(AppError::Conflict(
+            "To ostatnia pozycja w zamówieniu - anuluj całe zamówienie zamiast ją usuwać."
+                .to_string(),
+        ));
+    }
+
+    // Krok 3: Usuń pozycję, zmniejsz sumę zamówienia i zarejestruj zwrot.
+    // Klient zapłacił za pozycję cenę pomniejszoną o jej udział w rabacie. Część opłacona
+    // kartą podarunkową wraca na kartę, zwrot na konto dotyczy tylko reszty.
+    // Rozbicie VAT według stawki z chwili zakupu - przed usunięciem pozycji
+    let discount_share = repo::orders::item_discount_share(&mut *tx, item.id).await?;
+    let item_gross = removed_item_gross(item.price_at_purchase, discount_share);
+    let (gift_card_share, refund_amount) =
+        split_removed_item_gross(item_gross, order.gift_card_amount, order.total_price);
+    let item_vat = refund_vat_breakdown(&mut tx, item.id, item_gross).await?;
+    let refund_vat = refund_vat_breakdown(&mut tx, item.id, refund_amount).await?;
+    repo::orders::delete_item(&mut *tx, item.id).await?;
+    repo::orders::reduce_totals(
+        &mut *tx,
+        order_id,
+        refund_amount,
+        gift_card_share,
+        discount_share,
+        item_vat,
+    )
+    .await?;
+    restore_gift_card_amount(&mut tx, order_id, gift_card_share).await?;
+    repo::orders::insert_refund(
+        &mut *tx,
+        &item,
+        refund_amount,
+        refund_vat,
+        payload.reason.trim(),
+        claims.sub,
+    )
+    .await?;
+
+    // Krok 4: Uszkodzony produkt nie wraca do sprzedaży - archiwizujemy go.
+    let removed_product =
+        repo::products::set_status_returning(&mut *tx, item.product_id, ProductStatus::Archived)
+            .await?;
+
+    tx.commit().await?;
+    app_state.product_cache.invalidate(&item.product_id).await;
+
+    tracing::info!(
+        "Admin {} usunął pozycję {} (produkt {}) z zamówienia {}, zwrot: {} gr, na kartę podarunkową: {} gr",
+        claims.sub,
+        order_item_id,
+        item.product_id,
+        order_id,
+        refund_amount,
+        gift_card_share
+    );
+
+    // Krok 5: Powiadom klienta. Błąd wysyłki nie cofa zmian w zamówieniu.
+    match fetch_order_details_service(&app_state.db_pool, order_id).await {
+        Ok(details) => {
+            if let Err(e) = send_order_item_removed_email(
+                &app_state,
+                &details,
+                &removed_product,
+                refund_amount,
+                gift_card_share,
+                payload.reason.trim(),
+            )
+            .await
+            {
+                tracing::error!(
+                    "Nie udało się wysłać e-maila o usunięciu pozycji z zamówienia {}: {:?}",
+                    order_id,
+                    e
+                );
+            }
+        }
+        Err(e) => tracing::error!(
+            "Nie udało się pobrać szczegółów zamówienia {} do wysłania e-maila: {:?}",
+            order_id,
+            e
+        ),
+    }
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadAdminOrderList": true,
+        "showMessage": {
+            "message": "Pozycja zostala usunieta, zwrot zarejestrowany.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// Oznacza zarejestrowany zwrot jako zrealizowany (pieniądze zostały odesłane klientowi).
+pub async fn complete_order_refund_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((order_id, refund_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+
+    let result = sqlx::query(
+        r#"
+            UPDATE order_refunds SET refunded_at = NOW()
+            WHERE id = $1 AND order_id = $2 AND refunded_at IS NULL
+        "#,
+    )
+    .bind(refund_id)
+    .bind(order_id)
+    .execute(&app_state.db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!(
+        "Admin {} oznaczył zwrot {} (zamówienie {}) jako zrealizowany",
+        claims.sub,
+        refund_id,
+        order_id
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadAdminOrderList": true,
+        "showMessage": {
+            "message": "Zwrot oznaczony jako zrealizowany.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// Wysyła klientowi e-mail o zwrocie. Błąd wysyłki tylko logujemy - zwrot jest już zapisany.
+async fn notify_customer_about_return(app_state: &AppState, details: &ReturnDetails) {
+    let order = match repo::orders::find_by_id(&app_state.db_pool, details.request.order_id)
+        .await
+        .and_then(|order| order.ok_or(AppError::NotFound))
+    {
+        Ok(order) => order,
+        Err(e) => {
+            tracing::error!(
+                "Nie udało się pobrać zamówienia {} do e-maila o zwrocie: {:?}",
+                details.request.order_id,
+                e
+            );
+            return;
+        }
+    };
+    if let Err(e) = send_return_status_email(app_state, &order, details).await {
+        tracing::error!(
+            "Nie udało się wysłać e-maila o zwrocie {} (status '{}'): {:?}",
+            details.request.id,
+            details.request.status,
+            e
+        );
+    }
+}
+
+/// Zgłoszenie zwrotu przez klienta. Zaznaczone pozycje przychodzą jako powtórzone pole
+/// `order_item_ids`, więc treść formularza parsujemy ręcznie.
+pub async fn create_return_request_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+    body: String,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let order = repo::orders::find_by_id(&app_state.db_pool, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if order.user_id != Some(claims.sub) {
+        return Err(AppError::UnauthorizedAccess(
+            "Nie masz uprawnień do tego zamówienia".to_string(),
+        ));
+    }
+    match return_deadline(&app_state.db_pool, &order).await? {
+        Some(deadline) if deadline > Utc::now() => {}
+        _ => {
+            return Err(toast_form_error(
+                "Termin na odstapienie od umowy minal albo zamowienie nie zostalo jeszcze doreczone.",
+            ));
+        }
+    }
+
+    let mut order_item_ids: Vec<Uuid> = Vec::new();
+    let mut reason = String::new();
+    let mut bank_account = String::new();
+    let mut wants_return_label = false;
+    for (key, value) in url::form_urlencoded::parse(body.as_bytes()) {
+        match key.as_ref() {
+            "return_label" => wants_return_label = value == "on",
+            "order_item_ids" => {
+                if let Ok(id) = Uuid::parse_str(&value)
+                    && !order_item_ids.contains(&id)
+                {
+                    order_item_ids.push(id);
+                }
+            }
+            "reason" => reason = value.trim().to_string(),
+            "bank_account" => bank_account = value.into_owned(),
+            _ => {}
+        }
+    }
+
+    if order_item_ids.is_empty() {
+        return Err(toast_form_error("Zaznacz produkty, ktore chcesz zwrocic."));
+    }
+    if reason.chars().count() > MAX_RETURN_REASON_LEN {
+        return Err(toast_form_error("Opis powodu zwrotu jest zbyt dlugi."));
+    }
+    let bank_account = normalize_bank_account(&bank_account)
+        .ok_or_else(|| toast_form_error("Podaj poprawny numer konta (IBAN)."))?;
+
+    let mut details = create_return_request(
+        &app_state.db_pool,
+        order_id,
+        claims.sub,
+        &order_item_ids,
+        &reason,
+        &bank_account,
+    )
+    .await?;
+    // Zwrot jest już zgłoszony - nieudany zakup etykiety nie może go cofnąć
+    let mut message = "Zgloszenie zwrotu zostalo przyjete. Potwierdzenie wyslalismy e-mailem.";
+    let mut message_type = "success";
+    if wants_return_label {
+        match purchase_return_label(&app_state, &order, &details).await {
+            Ok(label) => details.label = Some(label),
+            Err(e) => {
+                tracing::error!(
+                    "Nie udało się kupić etykiety zwrotnej dla zwrotu {}: {:?}",
+                    details.request.id,
+                    e
+                );
+                message = "Zgloszenie zwrotu zostalo przyjete, ale nie udalo sie przygotowac etykiety InPost. Odeslij paczke samodzielnie.";
+                message_type = "warning";
+            }
+        }
+    }
+    if let Err(e) = send_return_status_email(&app_state, &order, &details).await {
+        tracing::error!(
+            "Nie udało się wysłać potwierdzenia zgłoszenia zwrotu {}: {:?}",
+            details.request.id,
+            e
+        );
+    }
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": message,
+            "type": message_type
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_customer_return_maud(&details)))
+}
+
+async fn decide_return_handler(
+    app_state: &AppState,
+    claims: &TokenClaims,
+    return_id: Uuid,
+    approve: bool,
+    payload: ReturnDecisionPayload,
+) -> Result<(HeaderMap, Markup), AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+    let admin_note = payload
+        .admin_note
+        .as_deref()
+        .map(str::trim)
+        .filter(|note| !note.is_empty());
+
+    let details = decide_return(app_state, return_id, claims.sub, approve, admin_note).await?;
+    tracing::info!(
+        "Admin {} zmienił status zwrotu {} na '{}'",
+        claims.sub,
+        return_id,
+        details.request.status
+    );
+    notify_customer_about_return(app_state, &details).await;
+
+    let message = if approve {
+        "Zwrot przyjety, produkty wrocily do sprzedazy."
+    } else {
+        "Zwrot odrzucony."
+    };
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_admin_return_card_maud(&details)))
+}
+
+/// Przyjęcie zwrotu: rejestruje zwrot środków w zamówieniu i przywraca produkty do sprzedaży.
+pub async fn approve_return_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(return_id): Path<Uuid>,
+    Form(payload): Form<ReturnDecisionPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    decide_return_handler(&app_state, &claims, return_id, true, payload).await
+}
+
+pub async fn reject_return_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(return_id): Path<Uuid>,
+    Form(payload): Form<ReturnDecisionPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    decide_return_handler(&app_state, &claims, return_id, false, payload).await
+}
+
+/// Największy dopuszczalny rozmiar jednego zdjęcia do reklamacji
+const MAX_COMPLAINT_PHOTO_BYTES: usize = 10 * 1024 * 1024;
+
+/// Reklamacja składana przez klienta z konta (multipart - opis i opcjonalne zdjęcia).
+pub async fn create_complaint_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let order = repo::orders::find_by_id(&app_state.db_pool, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if order.user_id != Some(claims.sub) {
+        return Err(AppError::UnauthorizedAccess(
+            "Nie masz uprawnień do tego zamówienia".to_string(),
+        ));
+    }
+    if !complaint_window_open(&app_state.db_pool, &order).await? {
+        return Err(toast_form_error(
+            "Do tego zamowienia nie mozna juz zlozyc reklamacji.",
+        ));
+    }
+
+    let mut order_item_id: Option<Uuid> = None;
+    let mut description = String::new();
+    let mut photos: Vec<(String, Vec<u8>)> = Vec::new();
+    while let Some(field) = multipart.next_field().await? {
+        match field.name().unwrap_or_default() {
+            "order_item_id" => order_item_id = Uuid::parse_str(field.text().await?.trim()).ok(),
+            "description" => description = field.text().await?.trim().to_string(),
+            "photos" => {
+                let filename = field
+                    .file_name()
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| "reklamacja.jpg".to_string());
+                let is_image = field
+                    .content_type()
+                    .is_some_and(|content_type| content_type.starts_with("image/"));
+                let bytes = field.bytes().await?;
+                // Pusty input pliku przychodzi jako pole bez treści
+                if bytes.is_empty() {
+                    continue;
+                }
+                if !is_image {
+                    return Err(toast_form_error("Mozna dolaczyc tylko zdjecia."));
+                }
+                if bytes.len() > MAX_COMPLAINT_PHOTO_BYTES {
+                    return Err(toast_form_error(
+                        "Zdjecie jest za duze (maksymalnie 10 MB).",
+                    ));
+                }
+                photos.push((filename, bytes.to_vec()));
+            }
+            _ => {}
+        }
+    }
+
+    let order_item_id = order_item_id
+        .ok_or_else(|| toast_form_error("Wybierz produkt, ktorego dotyczy reklamacja."))?;
+    if description.is_empty() {
+        return Err(toast_form_error("Opisz wade produktu."));
+    }
+    if description.chars().count() > MAX_COMPLAINT_DESCRIPTION_LEN {
+        return Err(toast_form_error("Opis reklamacji jest zbyt dlugi."));
+    }
+    if photos.len() > MAX_COMPLAINT_PHOTOS {
+        return Err(toast_form_error("Mozna dolaczyc maksymalnie 5 zdjec."));
+    }
+
+    let complaint = create_complaint(
+        &app_state,
+        order_id,
+        order_item_id,
+        claims.sub,
+        &description,
+        photos,
+    )
+    .await?;
+    if let Err(e) = send_complaint_status_email(&app_state, &order, &complaint).await {
+        tracing::error!(
+            "Nie udało się wysłać potwierdzenia reklamacji {}: {:?}",
+            complaint.id,
+            e
+        );
+    }
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Reklamacja zostala zlozona. Potwierdzenie wyslalismy e-mailem.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_customer_complaint_maud(&complaint)))
+}
+
+/// Zmiana statusu reklamacji przez admina. Każda zmiana wysyła klientowi e-mail;
+/// uznanie i odrzucenie wymagają odpowiedzi dla klienta.
+pub async fn update_complaint_status_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(complaint_id): Path<Uuid>,
+    Form(payload): Form<ComplaintStatusPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+    let resolution = payload
+        .resolution
+        .as_deref()
+        .map(str::trim)
+        .filter(|resolution| !resolution.is_empty());
+    if payload.status == ComplaintStatus::Open {
+        return Err(toast_form_error("Wybierz nowy status reklamacji."));
+    }
+    if payload.status.is_resolved() && resolution.is_none() {
+        return Err(toast_form_error(
+            "Przy uznaniu lub odrzuceniu reklamacji wpisz odpowiedz dla klienta.",
+        ));
+    }
+
+    let complaint = update_complaint_status(
+        &app_state.db_pool,
+        complaint_id,
+        claims.sub,
+        payload.status,
+        resolution,
+    )
+    .await?;
+
+    match repo::orders::find_by_id(&app_state.db_pool, complaint.order_id)
+        .await
+        .and_then(|order| order.ok_or(AppError::NotFound))
+    {
+        Ok(order) => {
+            if let Err(e) = send_complaint_status_email(&app_state, &order, &complaint).await {
+                tracing::error!(
+                    "Nie udało się wysłać e-maila o reklamacji {} (status '{}'): {:?}",
+                    complaint.id,
+                    complaint.status,
+                    e
+                );
+            }
+        }
+        Err(e) => tracing::error!(
+            "Nie udało się pobrać zamówienia {} do e-maila o reklamacji: {:?}",
+            complaint.order_id,
+            e
+        ),
+    }
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Status reklamacji zapisany, klient dostal e-mail.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_admin_complaint_card_maud(&complaint)))
+}
+
+/// Opinia klienta o produkcie z doręczonego zamówienia. Trafia do moderacji.
+pub async fn create_review_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(product_id): Path<Uuid>,
+    Form(payload): Form<ProductReviewPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let content = payload.content.trim();
+    if !(1..=5).contains(&payload.rating) {
+        return Err(toast_form_error("Wybierz ocene od 1 do 5 gwiazdek."));
+    }
+    if content.is_empty() {
+        return Err(toast_form_error("Napisz kilka slow o produkcie."));
+    }
+    if content.chars().count() > MAX_REVIEW_CONTENT_LEN {
+        return Err(toast_form_error("Opinia jest za dluga."));
+    }
+
+    let review = create_review(
+        &app_state.db_pool,
+        claims.sub,
+        product_id,
+        payload.rating,
+        content,
+    )
+    .await?;
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Dziekujemy! Opinia pojawi sie po zatwierdzeniu.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_customer_review_maud(&review)))
+}
+
+async fn moderate_review_handler(
+    app_state: &AppState,
+    claims: &TokenClaims,
+    review_id: Uuid,
+    status: ReviewStatus,
+) -> Result<(HeaderMap, Markup), AppError> {
+    claims.require_permission(Permission::ModerateReviews)?;
+    let review = moderate_review(&app_state.db_pool, review_id, claims.sub, status).await?;
+
+    let message = if status == ReviewStatus::Approved {
+        "Opinia opublikowana."
+    } else {
+        "Opinia ukryta."
+    };
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_admin_review_card_maud(&review)))
+}
+
+pub async fn approve_review_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(review_id): Path<Uuid>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    moderate_review_handler(&app_state, &claims, review_id, ReviewStatus::Approved).await
+}
+
+pub async fn reject_review_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(review_id): Path<Uuid>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    moderate_review_handler(&app_state, &claims, review_id, ReviewStatus::Rejected).await
+}
+
+/// Dodaje flagę ostrzegawczą dla e-maila, telefonu lub adresu klienta.
+/// Ponowne oflagowanie tej samej wartości aktualizuje jedynie powód.
+pub async fn create_customer_flag_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<CreateCustomerFlagPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+    payload.validate()?;
+
+    let normalized_value = payload.flag_type.normalize(&payload.value);
+    if normalized_value.is_empty() {
+        return Err(AppError::UnprocessableEntity(
+            "Podana wartość jest pusta po normalizacji.".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+            INSERT INTO customer_flags (flag_type, value, reason, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (flag_type, value) DO UPDATE SET reason = EXCLUDED.reason
+        "#,
+    )
+    .bind(payload.flag_type)
+    .bind(&normalized_value)
+    .bind(payload.reason.trim())
+    .bind(claims.sub)
+    .execute(&app_state.db_pool)
+    .await?;
+
+    tracing::info!(
+        "Admin {} oflagował klienta: {:?} = '{}'",
+        claims.sub,
+        payload.flag_type,
+        normalized_value
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCustomerFlags": true,
+        "showMessage": {
+            "message": "Flaga klienta zostala zapisana.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// Analizuje wybrane w formularzu zdjęcie i zwraca podpowiedzi kategorii, koloru i materiału.
+/// Nic nie jest zapisywane - formularz pokazuje podpowiedzi do ręcznego zatwierdzenia.
+pub async fn suggest_product_attributes_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    mut multipart: Multipart,
+) -> Result<Json<AttributeSuggestions>, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    let tagging_addon = app_state
+        .cloudinary_config
+        .tagging_addon
+        .as_deref()
+        .ok_or_else(|| {
+            AppError::BadRequest("Tagowanie zdjęć nie jest skonfigurowane.".to_string())
+        })?;
+
+    let mut image: Option<(String, Vec<u8>)> = None;
+    while let Some(field) = multipart.next_field().await? {
+        if field.name() == Some("image") {
+            let filename = field.file_name().unwrap_or("image.jpg").to_string();
+            image = Some((filename, field.bytes().await?.to_vec()));
+            break;
+        }
+    }
+    let (filename, bytes) = image
+        .filter(|(_, bytes)| !bytes.is_empty())
+        .ok_or_else(|| AppError::UnprocessableEntity("Brak zdjęcia do analizy.".to_string()))?;
+
+    let tags =
+        fetch_image_tags(bytes, filename, &app_state.cloudinary_config, tagging_addon).await?;
+    let suggestions = suggestions_from_tags(&tags);
+    tracing::info!(
+        "Podpowiedzi atrybutów ze zdjęcia (tagi: {:?}): {:?}",
+        tags,
+        suggestions
+    );
+
+    Ok(Json(suggestions))
+}
+
+/// Strumieniuje szkic opisu produktu z asystenta LLM jako zwykły tekst.
+/// Szkic jest tylko wstawiany do formularza - zapis następuje dopiero po kliknięciu "Zapisz".
+pub async fn draft_product_description_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Json(payload): Json<ProductDescriptionDraftPayload>,
+) -> Result<impl IntoResponse, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    payload.validate()?;
+
+    let config = app_state.description_assistant.as_ref().ok_or_else(|| {
+        AppError::BadRequest("Asystent opisów nie jest skonfigurowany.".to_string())
+    })?;
+
+    tracing::info!(
+        "Admin {} prosi o szkic opisu dla produktu '{}'",
+        claims.sub,
+        payload.name
+    );
+    let receiver = stream_description_draft(config, &payload).await?;
+    let body_stream = futures::stream::unfold(receiver, |mut rx| async move {
+        rx.recv()
+            .await
+            .map(|chunk| (Ok::<_, std::convert::Infallible>(chunk), rx))
+    });
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        Body::from_stream(body_stream),
+    ))
+}
+
+/// Uruchamia audyt jakości zdjęć na żądanie. Audyt działa w tle (odpytuje Cloudinary
+/// dla każdego zdjęcia), więc odpowiadamy od razu.
+pub async fn run_image_audit_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    tracing::info!("Admin {} uruchomił audyt zdjęć.", claims.sub);
+    tokio::spawn(async move {
+        if let Err(e) = run_image_quality_audit(&app_state).await {
+            tracing::error!("[Audyt zdjęć] Audyt zakończył się błędem: {:?}", e);
+        }
+    });
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Audyt zdjec zostal uruchomiony. Odswiez raport za kilka minut.",
+            "type": "info"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::ACCEPTED, headers))
+}
+
+/// Tworzy klucz publicznego API i zwraca panel kluczy z jednorazowo widocznym pełnym kluczem.
+pub async fn create_api_key_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<CreateApiKeyPayload>,
+) -> Result<Markup, AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+    let payload = CreateApiKeyPayload {
+        name: payload.name.trim().to_string(),
+    };
+    payload.validate()?;
+
+    let (api_key, full_key) = create_api_key(&app_state.db_pool, &payload.name).await?;
+    tracing::info!(
+        "Admin {} utworzył klucz API '{}' ({})",
+        claims.sub,
+        api_key.name,
+        api_key.id
+    );
+    let keys = list_api_keys(&app_state.db_pool).await?;
+    Ok(render_api_keys_panel_maud(
+        &keys,
+        Some((&api_key, &full_key)),
+    ))
+}
+
+pub async fn revoke_api_key_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(key_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    if !revoke_api_key(&app_state.db_pool, key_id).await? {
+        return Err(AppError::NotFound);
+    }
+    tracing::info!("Admin {} unieważnił klucz API {}", claims.sub, key_id);
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadApiKeys": true,
+        "showMessage": { "message": "Klucz API zostal uniewazniony.", "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+/// Zdejmuje blokadę logowania przed czasem (np. klient dzwoni, że to on mylił hasło).
+pub async fn unlock_account_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(lockout_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    unlock_account(&app_state.db_pool, lockout_id, claims.sub).await?;
+    tracing::info!(
+        "Admin {} zdjął blokadę logowania {}",
+        claims.sub,
+        lockout_id
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadLoginLockouts": true,
+        "showMessage": { "message": "Blokada logowania zostala zdjeta.", "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+fn admin_users_saved_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadAdminUsers": true,
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// POST /api/admin/users/{user_id}/rola
+pub async fn set_user_role_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+    Form(payload): Form<UserRolePayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageUsers)?;
+
+    let role = Role::from_str(&payload.role)
+        .map_err(|_| toast_form_error("Nieznana rola uzytkownika."))?;
+    // Własnej roli nie zmieniamy - admin nie odbierze sobie przypadkiem dostępu do tego ekranu
+    if user_id == claims.sub {
+        return Err(toast_form_error("Nie mozesz zmienic wlasnej roli."));
+    }
+    let user = find_managed_user(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if user.role == role {
+        return Ok((StatusCode::OK, HeaderMap::new()));
+    }
+    if !set_user_role(&app_state.db_pool, user_id, role).await? {
+        return Err(toast_form_error(
+            "Sklep musi miec co najmniej jednego aktywnego administratora.",
+        ));
+    }
+    tracing::info!(
+        "Admin {} zmienił rolę użytkownika {} z {} na {}",
+        claims.sub,
+        user.email,
+        user.role.label(),
+        role.label()
+    );
+
+    Ok((
+        StatusCode::OK,
+        admin_users_saved_headers("Rola uzytkownika zostala zmieniona."),
+    ))
+}
+
+/// POST /api/admin/users/{user_id}/blokada
+pub async fn set_user_disabled_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+    Form(payload): Form<UserDisabledPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageUsers)?;
+
+    if user_id == claims.sub {
+        return Err(toast_form_error("Nie mozesz zablokowac wlasnego konta."));
+    }
+    let user = find_managed_user(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if user.disabled_at.is_some() == payload.disabled {
+        return Ok((StatusCode::OK, HeaderMap::new()));
+    }
+    if !set_account_disabled(&app_state.db_pool, user_id, payload.disabled, claims.sub).await? {
+        return Err(toast_form_error(
+            "Sklep musi miec co najmniej jednego aktywnego administratora.",
+        ));
+    }
+    tracing::info!(
+        "Admin {} {} konto {}",
+        claims.sub,
+        if payload.disabled {
+            "zablokował"
+        } else {
+            "odblokował"
+        },
+        user.email
+    );
+
+    let message = if payload.disabled {
+        "Konto zostalo zablokowane."
+    } else {
+        "Konto zostalo odblokowane."
+    };
+    Ok((StatusCode::OK, admin_users_saved_headers(message)))
+}
+
+/// POST /api/admin/strony/{slug} - zapis strony informacyjnej; zmiana widoczna od razu
+pub async fn save_static_page_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(slug): Path<String>,
+    Form(payload): Form<StaticPagePayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let page = built_in_page(&slug).ok_or(AppError::NotFound)?;
+    let title = payload.title.trim();
+    let body = payload.body.trim();
+    if title.is_empty() || body.is_empty() {
+        return Err(toast_form_error("Tytul i tresc strony sa wymagane."));
+    }
+    if title.chars().count() > MAX_STATIC_PAGE_TITLE_LEN
+        || body.chars().count() > MAX_STATIC_PAGE_BODY_LEN
+    {
+        return Err(toast_form_error("Tytul lub tresc strony sa za dlugie."));
+    }
+    let published = payload.published.is_some();
+    save_static_page(
+        &app_state.db_pool,
+        page.slug,
+        title,
+        body,
+        published,
+        claims.sub,
+    )
+    .await?;
+    app_state
+        .static_html_cache
+        .remove(&static_page_cache_key(page.slug))
+        .await;
+    tracing::info!(
+        "Admin {} zapisał stronę '{}' (opublikowana: {})",
+        claims.sub,
+        page.slug,
+        published
+    );
+
+    let message = if published {
+        "Strona zostala zapisana i opublikowana."
+    } else {
+        "Szkic strony zostal zapisany."
+    };
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": { "message": message, "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+/// DELETE /api/admin/strony/{slug} - powrót do treści wbudowanej
+pub async fn delete_static_page_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(slug): Path<String>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let page = built_in_page(&slug).ok_or(AppError::NotFound)?;
+    if !delete_static_page(&app_state.db_pool, page.slug).await? {
+        return Err(AppError::NotFound);
+    }
+    app_state
+        .static_html_cache
+        .remove(&static_page_cache_key(page.slug))
+        .await;
+    tracing::info!(
+        "Admin {} przywrócił wbudowaną treść strony '{}'",
+        claims.sub,
+        page.slug
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": { "message": "Przywrocono wbudowana tresc strony.", "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    let location_payload = json!({
+        "path": format!("/htmx/admin/strony/{}", page.slug),
+        "target": "#admin-content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+/// Po każdej zmianie FAQ: przeładowanie listy w panelu i unieważnienie strony /faq w cache'u
+fn redirects_changed_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadRedirects": true,
+        "showMessage": { "message": message, "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// POST /api/admin/przekierowania
+pub async fn create_redirect_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<RedirectPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    // Stary adres może być wklejony razem z domeną sklepu
+    let source = payload.source_path.trim();
+    let source = source
+        .strip_prefix(app_state.public_base_url.as_str())
+        .unwrap_or(source);
+    let source_path = normalize_redirect_path(source);
+    let target_path = payload.target_path.trim();
+    if !is_valid_source_path(&source_path) {
+        return Err(toast_form_error(
+            "Stary adres musi byc sciezka w sklepie, np. /produkty/stara-nazwa.",
+        ));
+    }
+    if !is_valid_target(target_path) {
+        return Err(toast_form_error(
+            "Nowy adres musi zaczynac sie od / albo https://.",
+        ));
+    }
+    if source_path.len() > MAX_REDIRECT_PATH_LEN || target_path.len() > MAX_REDIRECT_PATH_LEN {
+        return Err(toast_form_error("Adres jest za dlugi."));
+    }
+    if normalize_redirect_path(target_path) == source_path {
+        return Err(toast_form_error("Stary i nowy adres sa takie same."));
+    }
+    if !REDIRECT_STATUS_CODES.contains(&payload.status_code) {
+        return Err(toast_form_error("Nieprawidlowy rodzaj przekierowania."));
+    }
+
+    let created = create_redirect(
+        &app_state.db_pool,
+        &source_path,
+        target_path,
+        payload.status_code,
+        claims.sub,
+    )
+    .await?;
+    if !created {
+        return Err(toast_form_error("Dla tego adresu jest juz przekierowanie."));
+    }
+    tracing::info!(
+        "Admin {} dodał przekierowanie {} '{}' -> '{}'",
+        claims.sub,
+        payload.status_code,
+        source_path,
+        target_path
+    );
+
+    Ok((
+        StatusCode::OK,
+        redirects_changed_headers("Przekierowanie zostalo dodane."),
+    ))
+}
+
+/// DELETE /api/admin/przekierowania/{redirect_id}
+pub async fn delete_redirect_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(redirect_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    delete_redirect(&app_state.db_pool, redirect_id).await?;
+    tracing::info!("Admin {} usunął przekierowanie {}", claims.sub, redirect_id);
+
+    Ok((
+        StatusCode::OK,
+        redirects_changed_headers("Przekierowanie zostalo usuniete."),
+    ))
+}
+
+async fn faq_changed_headers(app_state: &AppState, message: &str) -> HeaderMap {
+    app_state
+        .static_html_cache
+        .remove(&static_page_cache_key("faq"))
+        .await;
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadFaqItems": true,
+        "showMessage": { "message": message, "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// Pytanie i odpowiedź z formularza FAQ po walidacji
+fn validated_faq_item(payload: &FaqItemPayload) -> Result<(&str, &str), AppError> {
+    let question = payload.question.trim();
+    let answer = payload.answer.trim();
+    if question.is_empty() || answer.is_empty() {
+        return Err(toast_form_error("Pytanie i odpowiedz sa wymagane."));
+    }
+    if question.chars().count() > MAX_FAQ_QUESTION_LEN
+        || answer.chars().count() > MAX_FAQ_ANSWER_LEN
+    {
+        return Err(toast_form_error("Pytanie lub odpowiedz sa za dlugie."));
+    }
+    Ok((question, answer))
+}
+
+/// POST /api/admin/faq - nowe pytanie na końcu listy
+pub async fn create_faq_item_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<FaqItemPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let (question, answer) = validated_faq_item(&payload)?;
+    create_faq_item(
+        &app_state.db_pool,
+        question,
+        answer,
+        payload.visible.is_some(),
+    )
+    .await?;
+    tracing::info!("Admin {} dodał pytanie FAQ '{}'", claims.sub, question);
+
+    Ok((
+        StatusCode::OK,
+        faq_changed_headers(&app_state, "Pytanie zostalo dodane.").await,
+    ))
+}
+
+/// POST /api/admin/faq/{item_id}
+pub async fn update_faq_item_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(item_id): Path<Uuid>,
+    Form(payload): Form<FaqItemPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let (question, answer) = validated_faq_item(&payload)?;
+    update_faq_item(
+        &app_state.db_pool,
+        item_id,
+        question,
+        answer,
+        payload.visible.is_some(),
+    )
+    .await?;
+    tracing::info!("Admin {} zmienił pytanie FAQ {}", claims.sub, item_id);
+
+    Ok((
+        StatusCode::OK,
+        faq_changed_headers(&app_state, "Pytanie zostalo zapisane.").await,
+    ))
+}
+
+/// DELETE /api/admin/faq/{item_id}
+pub async fn delete_faq_item_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(item_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    delete_faq_item(&app_state.db_pool, item_id).await?;
+    tracing::info!("Admin {} usunął pytanie FAQ {}", claims.sub, item_id);
+
+    Ok((
+        StatusCode::OK,
+        faq_changed_headers(&app_state, "Pytanie zostalo usuniete.").await,
+    ))
+}
+
+/// POST /api/admin/faq/{item_id}/przesun/{direction} - `gora` albo `dol`
+pub async fn move_faq_item_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((item_id, direction)): Path<(Uuid, MoveDirection)>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    if !move_faq_item(&app_state.db_pool, item_id, direction).await? {
+        return Err(toast_form_error("Pytanie jest juz na skraju listy."));
+    }
+
+    Ok((
+        StatusCode::OK,
+        faq_changed_headers(&app_state, "Zmieniono kolejnosc pytan.").await,
+    ))
+}
+
+/// Po każdej zmianie strony głównej: przeładowanie ekranu w panelu i nowe sekcje w sklepie
+async fn homepage_changed_headers(app_state: &AppState, message: &str) -> HeaderMap {
+    invalidate_homepage_sections(app_state).await;
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadHomepageSettings": true,
+        "showMessage": { "message": message, "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// POST /api/admin/strona-glowna/baner - tekst, link i (opcjonalnie) nowe zdjęcie banera
+pub async fn save_homepage_hero_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let mut update = HeroUpdate {
+        title: None,
+        subtitle: None,
+        link_url: None,
+        image_url: None,
+        enabled: false,
+    };
+    let mut image: Option<(String, Vec<u8>)> = None;
+    while let Some(field) = multipart.next_field().await? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "title" | "subtitle" | "link_url" => {
+                let value = field.text().await?.trim().to_string();
+                if value.chars().count() > MAX_HERO_TEXT_LEN {
+                    return Err(toast_form_error("Tekst banera jest za dlugi."));
+                }
+                let value = (!value.is_empty()).then_some(value);
+                match name.as_str() {
+                    "title" => update.title = value,
+                    "subtitle" => update.subtitle = value,
+                    _ => update.link_url = value,
+                }
+            }
+            "enabled" => update.enabled = true,
+            "image" => {
+                let filename = field
+                    .file_name()
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| "baner.jpg".to_string());
+                let is_image = field
+                    .content_type()
+                    .is_some_and(|content_type| content_type.starts_with("image/"));
+                let bytes = field.bytes().await?;
+                // Pusty input pliku - zostaje obecne zdjęcie
+                if bytes.is_empty() {
+                    continue;
+                }
+                if !is_image {
+                    return Err(toast_form_error("Baner musi byc zdjeciem."));
+                }
+                if bytes.len() > MAX_HERO_IMAGE_BYTES {
+                    return Err(toast_form_error(
+                        "Zdjecie jest za duze (maksymalnie 10 MB).",
+                    ));
+                }
+                image = Some((filename, bytes.to_vec()));
+            }
+            _ => {}
+        }
+    }
+    if update
+        .link_url
+        .as_deref()
+        .is_some_and(|link| !is_valid_hero_link(link))
+    {
+        return Err(toast_form_error(
+            "Link banera musi zaczynac sie od / albo https://.",
+        ));
+    }
+    if let Some((filename, bytes)) = image {
+        update.image_url = Some(
+            upload_image_to_cloudinary(
+                bytes,
+                filename,
+                HOMEPAGE_ASSET_FOLDER,
+                &app_state.cloudinary_config,
+            )
+            .await?,
+        );
+    }
+
+    save_hero(&app_state.db_pool, &update, claims.sub).await?;
+    tracing::info!("Admin {} zapisał baner strony głównej", claims.sub);
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Baner zostal zapisany.").await,
+    ))
+}
+
+/// POST /api/admin/strona-glowna/wyroznione - tytuł i widoczność karuzeli
+pub async fn save_homepage_featured_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<HomepageFeaturedPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let title = payload.title.trim();
+    if title.chars().count() > MAX_HERO_TEXT_LEN {
+        return Err(toast_form_error("Tytul karuzeli jest za dlugi."));
+    }
+    save_carousel_section(
+        &app_state.db_pool,
+        HomepageSection::Featured,
+        title,
+        payload.enabled.is_some(),
+        claims.sub,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Karuzela zostala zapisana.").await,
+    ))
+}
+
+/// POST /api/admin/strona-glowna/popularne - tytuł i widoczność karuzeli najpopularniejszych
+pub async fn save_homepage_popular_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<HomepageFeaturedPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let title = payload.title.trim();
+    if title.chars().count() > MAX_HERO_TEXT_LEN {
+        return Err(toast_form_error("Tytul karuzeli jest za dlugi."));
+    }
+    save_carousel_section(
+        &app_state.db_pool,
+        HomepageSection::Popular,
+        title,
+        payload.enabled.is_some(),
+        claims.sub,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Karuzela zostala zapisana.").await,
+    ))
+}
+
+/// POST /api/admin/strona-glowna/sekcje/{section}/przesun/{direction}
+pub async fn move_homepage_section_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((section, direction)): Path<(HomepageSection, MoveDirection)>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    if !move_homepage_section(&app_state.db_pool, section, direction).await? {
+        return Err(toast_form_error("Sekcja jest juz na skraju strony."));
+    }
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Zmieniono kolejnosc sekcji.").await,
+    ))
+}
+
+/// POST /api/admin/strona-glowna/wyroznione/produkty
+pub async fn add_featured_product_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<HomepageFeaturedProductPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let product = find_product_by_reference(&app_state.db_pool, &payload.product)
+        .await?
+        .ok_or_else(|| toast_form_error("Nie znaleziono produktu."))?;
+    if !add_featured_product(&app_state.db_pool, product.id).await? {
+        return Err(toast_form_error(
+            "Produkt jest juz w karuzeli albo karuzela jest pelna.",
+        ));
+    }
+    tracing::info!(
+        "Admin {} dodał produkt {} do wyróżnionych",
+        claims.sub,
+        product.id
+    );
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Produkt dodany do karuzeli.").await,
+    ))
+}
+
+/// DELETE /api/admin/strona-glowna/wyroznione/produkty/{product_id}
+pub async fn remove_featured_product_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(product_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    remove_featured_product(&app_state.db_pool, product_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Produkt usuniety z karuzeli.").await,
+    ))
+}
+
+/// POST /api/admin/strona-glowna/wyroznione/produkty/{product_id}/przesun/{direction}
+pub async fn move_featured_product_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((product_id, direction)): Path<(Uuid, MoveDirection)>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    if !move_featured_product(&app_state.db_pool, product_id, direction).await? {
+        return Err(toast_form_error("Produkt jest juz na skraju karuzeli."));
+    }
+
+    Ok((
+        StatusCode::OK,
+        homepage_changed_headers(&app_state, "Zmieniono kolejnosc produktow.").await,
+    ))
+}
+
+/// Czyści wszystkie cache'e (produkty, strony statyczne, listingi, kategorie).
+pub async fn purge_all_caches_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    purge_all(&app_state, None);
+    tracing::info!("Admin {} wyczyścił wszystkie cache'e.", claims.sub);
+    Ok((
+        StatusCode::OK,
+        cache_purged_headers("Wszystkie cache zostaly wyczyszczone."),
+    ))
+}
+
+/// Czyści jeden klucz wskazanego cache'u albo - bez `key` w formularzu - cały ten cache.
+pub async fn purge_cache_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(cache_name): Path<String>,
+    Form(payload): Form<PurgeCachePayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+    let cache = CacheName::from_str(&cache_name).map_err(|_| AppError::NotFound)?;
+
+    let message = match payload
+        .key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+    {
+        Some(key) => {
+            if !purge_key(&app_state, cache, key).await {
+                return Err(AppError::NotFound);
+            }
+            tracing::info!(
+                "Admin {} usunął klucz '{}' z cache'u {}.",
+                claims.sub,
+                key,
+                cache.as_ref()
+            );
+            "Klucz zostal usuniety z cache."
+        }
+        None => {
+            purge_all(&app_state, Some(cache));
+            tracing::info!("Admin {} wyczyścił cache {}.", claims.sub, cache.as_ref());
+            "Cache zostal wyczyszczony."
+        }
+    };
+    Ok((StatusCode::OK, cache_purged_headers(message)))
+}
+
+/// Najwięcej kluczy i wzorców w jednym żądaniu unieważnienia
+const MAX_CACHE_INVALIDATION_ITEMS: usize = 500;
+
+/// POST /api/admin/cache/invalidate - unieważnienie kluczy i wzorców kluczy dla zewnętrznych
+/// narzędzi, które zmieniają dane poza panelem (token admina w nagłówku `Authorization`).
+pub async fn invalidate_cache_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Json(payload): Json<CacheInvalidationPayload>,
+) -> Result<Json<CacheInvalidationResponse>, AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+    if payload.keys.is_empty() && payload.patterns.is_empty() {
+        return Err(AppError::BadRequest(
+            "Podaj co najmniej jeden klucz (keys) albo wzorzec (patterns).".to_string(),
+        ));
+    }
+    if payload.keys.len() + payload.patterns.len() > MAX_CACHE_INVALIDATION_ITEMS {
+        return Err(AppError::BadRequest(format!(
+            "Najwyżej {} kluczy i wzorców w jednym żądaniu.",
+            MAX_CACHE_INVALIDATION_ITEMS
+        )));
+    }
+    let caches: Vec<CacheName> = if payload.caches.is_empty() {
+        CacheName::iter().collect()
+    } else {
+        payload
+            .caches
+            .iter()
+            .map(|name| {
+                CacheName::from_str(name.trim())
+                    .map_err(|_| AppError::BadRequest(format!("Nieznany cache: {}", name)))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut invalidated = 0;
+    for cache in &caches {
+        for key in payload
+            .keys
+            .iter()
+            .map(|k| k.trim())
+            .filter(|k| !k.is_empty())
+        {
+            if purge_key(&app_state, *cache, key).await {
+                invalidated += 1;
+            }
+        }
+        for pattern in payload
+            .patterns
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+        {
+            invalidated += purge_matching(&app_state, *cache, pattern).await;
+        }
+    }
+    tracing::info!(
+        "Admin {} unieważnił {} wpisów cache ({} kluczy, {} wzorców)",
+        claims.sub,
+        invalidated,
+        payload.keys.len(),
+        payload.patterns.len()
+    );
+
+    Ok(Json(CacheInvalidationResponse {
+        invalidated,
+        caches: caches.iter().map(|c| c.as_ref().to_string()).collect(),
+    }))
+}
+
+fn cache_purged_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadAdminCache": true,
+        "showMessage": { "message": message, "type": "success" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// Uruchamia w tle przenoszenie zdjęć produktów do folderów `products/{id}/`.
+pub async fn run_cloudinary_folder_migration_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    tracing::info!(
+        "Admin {} uruchomił przenoszenie zdjęć do folderów.",
+        claims.sub
+    );
+    tokio::spawn(async move {
+        if let Err(e) = migrate_product_images_to_folders(&app_state).await {
+            tracing::error!(
+                "[Foldery Cloudinary] Migracja zakończyła się błędem: {:?}",
+                e
+            );
+        }
+    });
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Przenoszenie zdjec do folderow produktow zostalo uruchomione w tle.",
+            "type": "info"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::ACCEPTED, headers))
+}
+
+/// Uruchamia w tle usuwanie zdjęć z folderów produktów, do których nie odwołuje się żaden produkt.
+pub async fn run_cloudinary_orphan_cleanup_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    tracing::info!(
+        "Admin {} uruchomił sprzątanie osieroconych zdjęć.",
+        claims.sub
+    );
+    tokio::spawn(async move {
+        if let Err(e) = cleanup_orphaned_product_images(&app_state).await {
+            tracing::error!("[Sprzątanie Cloudinary] Zakończone błędem: {:?}", e);
+        }
+    });
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Sprzatanie osieroconych zdjec zostalo uruchomione w tle.",
+            "type": "info"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::ACCEPTED, headers))
+}
+
+/// Uruchamia kopię zapasową bazy na żądanie admina (poza codziennym harmonogramem).
+pub async fn run_database_backup_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    let mut headers = HeaderMap::new();
+    if app_state.backup_config.is_none() {
+        let trigger_payload = json!({
+            "showMessage": {
+                "message": "Kopie zapasowe nie sa skonfigurowane (zmienne BACKUP_*).",
+                "type": "error"
+            }
+        });
+        if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+            headers.insert("HX-Trigger", val);
+        }
+        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+        return Err(AppError::ConflictWithHeaders(
+            "Kopie zapasowe nie są skonfigurowane.".to_string(),
+            headers,
+        ));
+    }
+
+    tracing::info!("Admin {} uruchomił kopię zapasową bazy.", claims.sub);
+    if let Some(job) = find_job("database_backup") {
+        let background_jobs = app_state.background_jobs.clone();
+        background_jobs.spawn(async move {
+            let _ = run_job(app_state, job).await;
+        });
+    }
+
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": "Kopia zapasowa zostala uruchomiona w tle. Bledy pojawia sie w powiadomieniach.",
+            "type": "info"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::ACCEPTED, headers))
+}
+
+/// Oznacza wszystkie powiadomienia admina jako przeczytane.
+pub async fn mark_admin_notifications_read_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::AccessPanel)?;
+
+    sqlx::query("UPDATE admin_notifications SET read_at = NOW() WHERE read_at IS NULL")
+        .execute(&app_state.db_pool)
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({ "reloadAdminNotifications": true });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+pub async fn delete_customer_flag_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(flag_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let result = sqlx::query("DELETE FROM customer_flags WHERE id = $1")
+        .bind(flag_id)
+        .execute(&app_state.db_pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!("Admin {} usunął flagę klienta {}", claims.sub, flag_id);
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCustomerFlags": true,
+        "showMessage": {
+            "message": "Flaga klienta zostala usunieta.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+fn customer_tags_saved_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCustomers": true,
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// POST /api/admin/klienci/{user_id}/tagi
+pub async fn add_customer_tag_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+    Form(payload): Form<CustomerTagPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    add_customer_tag(&app_state.db_pool, user_id, payload.tag, claims.sub).await?;
+    tracing::info!(
+        "Admin {} nadał klientowi {} tag {}",
+        claims.sub,
+        user_id,
+        payload.tag
+    );
+
+    Ok((
+        StatusCode::OK,
+        customer_tags_saved_headers("Tag klienta zostal dodany."),
+    ))
+}
+
+/// DELETE /api/admin/klienci/{user_id}/tagi/{tag}
+pub async fn remove_customer_tag_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((user_id, tag)): Path<(Uuid, CustomerTag)>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    remove_customer_tag(&app_state.db_pool, user_id, tag).await?;
+    tracing::info!(
+        "Admin {} usunął klientowi {} tag {}",
+        claims.sub,
+        user_id,
+        tag
+    );
+
+    Ok((
+        StatusCode::OK,
+        customer_tags_saved_headers("Tag klienta zostal usuniety."),
+    ))
+}
+
+fn customer_profile_saved_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCustomerProfile": true,
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// POST /api/admin/klienci/{user_id}/reset-hasla - link do ustawienia nowego hasła na e-mail klienta
+pub async fn send_customer_password_reset_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let profile = fetch_customer_profile(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    issue_password_reset(&app_state, profile.id, &profile.email)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Nie udało się wysłać e-maila z resetem hasła do {}: {:?}",
+                profile.email,
+                e
+            );
+            toast_form_error("Nie udalo sie wyslac e-maila. Sprobuj ponownie.")
+        })?;
+    tracing::info!(
+        "Admin {} wysłał klientowi {} link do resetu hasła",
+        claims.sub,
+        profile.email
+    );
+
+    Ok((
+        StatusCode::OK,
+        customer_profile_saved_headers("Wyslano e-mail z linkiem do zmiany hasla."),
+    ))
+}
+
+/// POST /api/admin/klienci/{user_id}/blokada - konta personelu blokuje się na ekranie użytkowników
+pub async fn set_customer_disabled_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+    Form(payload): Form<UserDisabledPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let profile = fetch_customer_profile(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    set_account_disabled(&app_state.db_pool, user_id, payload.disabled, claims.sub).await?;
+    tracing::info!(
+        "Admin {} {} konto klienta {}",
+        claims.sub,
+        if payload.disabled {
+            "zablokował"
+        } else {
+            "odblokował"
+        },
+        profile.email
+    );
+
+    let message = if payload.disabled {
+        "Konto klienta zostalo zablokowane."
+    } else {
+        "Konto klienta zostalo odblokowane."
+    };
+    Ok((StatusCode::OK, customer_profile_saved_headers(message)))
+}
+
+/// POST /api/admin/klienci/{user_id}/notatki
+pub async fn add_customer_note_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+    Form(payload): Form<CustomerNotePayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let content = payload.content.trim();
+    if content.is_empty() {
+        return Err(toast_form_error("Notatka nie moze byc pusta."));
+    }
+    if content.chars().count() > MAX_CUSTOMER_NOTE_LEN {
+        return Err(toast_form_error("Notatka jest za dluga."));
+    }
+    fetch_customer_profile(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    add_customer_note(&app_state.db_pool, user_id, content, claims.sub).await?;
+    tracing::info!("Admin {} dodał notatkę do klienta {}", claims.sub, user_id);
+
+    Ok((
+        StatusCode::OK,
+        customer_profile_saved_headers("Notatka zostala dodana."),
+    ))
+}
+
+/// DELETE /api/admin/klienci/{user_id}/notatki/{note_id}
+pub async fn delete_customer_note_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path((user_id, note_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    delete_customer_note(&app_state.db_pool, user_id, note_id).await?;
+    tracing::info!(
+        "Admin {} usunął notatkę {} klienta {}",
+        claims.sub,
+        note_id,
+        user_id
+    );
+
+    Ok((
+        StatusCode::OK,
+        customer_profile_saved_headers("Notatka zostala usunieta."),
+    ))
+}
+
+/// GET /api/admin/klienci/eksport - adresy e-mail klientów z segmentu (CSV) do newslettera
+pub async fn export_customer_segment_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<CustomerSegmentParams>,
+) -> Result<impl IntoResponse, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let segment = CustomerSegment::from_params(&params);
+    let search = customer_search(&params);
+    let customers = list_customers(&app_state.db_pool, &segment, search.as_deref()).await?;
+    tracing::info!(
+        "Admin {} wyeksportował {} adresów klientów (segment: '{}')",
+        claims.sub,
+        customers.len(),
+        segment.describe()
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"klienci-segment.csv\"".to_string(),
+            ),
+        ],
+        emails_csv(&customers),
+    ))
+}
+
+/// Dodaje mapowanie rozmiaru z metki na współczesny (albo zmienia istniejące)
+pub async fn create_size_mapping_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<CreateSizeMappingPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    payload.validate()?;
+
+    let category = match payload.category.trim() {
+        "" => None,
+        value => {
+            Some(Category::from_str(value).map_err(|_| toast_form_error("Nieznana kategoria."))?)
+        }
+    };
+    let gender = match payload.gender.trim() {
+        "" => None,
+        value => {
+            Some(ProductGender::from_str(value).map_err(|_| toast_form_error("Nieznana plec."))?)
+        }
+    };
+    let label_size = payload.label_size.trim();
+    let modern_size = payload.modern_size.trim().to_uppercase();
+
+    save_size_mapping(
+        &app_state.db_pool,
+        category,
+        gender,
+        payload.size_system.trim(),
+        label_size,
+        &modern_size,
+    )
+    .await?;
+
+    tracing::info!(
+        "Admin {} zapisał mapowanie rozmiaru '{}' -> '{}' ({:?}, {:?})",
+        claims.sub,
+        label_size,
+        modern_size,
+        category,
+        gender
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadSizeMappings": true,
+        "showMessage": {
+            "message": "Mapowanie rozmiaru zostalo zapisane.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+pub async fn delete_size_mapping_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(mapping_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    delete_size_mapping(&app_state.db_pool, mapping_id).await?;
+    tracing::info!(
+        "Admin {} usunął mapowanie rozmiaru {}",
+        claims.sub,
+        mapping_id
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadSizeMappings": true,
+        "showMessage": {
+            "message": "Mapowanie rozmiaru zostalo usuniete.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// Dodaje wskazówkę pielęgnacji dla materiału (albo podmienia treść istniejącej)
+pub async fn save_care_instruction_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<CareInstructionPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    payload.validate()?;
+
+    let material = payload.material.trim();
+    save_care_instruction(&app_state.db_pool, material, payload.instructions.trim()).await?;
+    tracing::info!(
+        "Admin {} zapisał wskazówkę pielęgnacji dla materiału '{}'",
+        claims.sub,
+        material
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCareInstructions": true,
+        "showMessage": {
+            "message": "Wskazowka pielegnacji zostala zapisana.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+pub async fn delete_care_instruction_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(instruction_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    delete_care_instruction(&app_state.db_pool, instruction_id).await?;
+    tracing::info!(
+        "Admin {} usunął wskazówkę pielęgnacji {}",
+        claims.sub,
+        instruction_id
+    );
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCareInstructions": true,
+        "showMessage": {
+            "message": "Wskazowka pielegnacji zostala usunieta.",
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// Błąd formularza w panelu admina pokazywany jako komunikat, bez podmiany widoku.
+/// `message` trafia do nagłówka HX-Trigger, więc musi być bez polskich znaków.
+fn toast_form_error(message: &str) -> AppError {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": {
+            "message": message,
+            "type": "error"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+    AppError::ConflictWithHeaders(message.to_string(), headers)
+}
+
+/// Data ważności z pola `input type="date"` - ostatni dzień ważności, kod działa do końca
+/// tego dnia czasu sklepu. Puste pole = bezterminowo.
+fn parse_coupon_expiry(raw: Option<&str>) -> Result<Option<chrono::DateTime<Utc>>, AppError> {
+    match raw.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(raw) => {
+            let date = chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .map_err(|_| toast_form_error("Nieprawidlowa data waznosci kodu."))?;
+            let end_of_day = date
+                .and_hms_opt(23, 59, 59)
+                .ok_or_else(|| toast_form_error("Nieprawidlowa data waznosci kodu."))?;
+            Ok(Some(shop_local_to_utc(end_of_day)))
+        }
+    }
+}
+
+/// Sprawdza formularz kodu rabatowego i zwraca znormalizowany kod, datę ważności i segment klientów.
+fn parse_coupon_payload(
+    payload: &CouponPayload,
+) -> Result<(String, Option<chrono::DateTime<Utc>>, CustomerSegment), AppError> {
+    payload.validate()?;
+
+    let code = Coupon::normalize_code(&payload.code);
+    if !code
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(toast_form_error(
+            "Kod moze zawierac tylko litery, cyfry, myslniki i podkreslenia.",
+        ));
+    }
+    if payload.discount_type == CouponDiscountType::Percentage && payload.value > 100 {
+        return Err(toast_form_error(
+            "Rabat procentowy nie moze przekraczac 100%.",
+        ));
+    }
+
+    let expires_at = parse_coupon_expiry(payload.expires_at.as_deref())?;
+
+    let optional_field = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+    let segment = CustomerSegment {
+        tag: optional_field(&payload.segment_tag)
+            .map(|tag| CustomerTag::from_str(&tag))
+            .transpose()
+            .map_err(|_| toast_form_error("Nieznany tag klienta."))?,
+        min_spent: optional_field(&payload.segment_min_spent)
+            .map(|gr| gr.parse::<i64>().ok().filter(|gr| *gr > 0))
+            .map(|gr| gr.ok_or_else(|| toast_form_error("Nieprawidlowa minimalna kwota zakupow.")))
+            .transpose()?,
+        min_orders: optional_field(&payload.segment_min_orders)
+            .map(|count| count.parse::<i32>().ok().filter(|count| *count > 0))
+            .map(|count| {
+                count.ok_or_else(|| toast_form_error("Nieprawidlowa minimalna liczba zamowien."))
+            })
+            .transpose()?,
+    };
+
+    Ok((code, expires_at, segment))
+}
+
+fn coupon_saved_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCoupons": true,
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// Tworzy nowy kod rabatowy (procentowy lub kwotowy).
+pub async fn create_coupon_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<CouponPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+    let (code, expires_at, segment) = parse_coupon_payload(&payload)?;
+
+    let code_taken: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM coupons WHERE code = $1)")
+            .bind(&code)
+            .fetch_one(&app_state.db_pool)
+            .await?;
+    if code_taken {
+        return Err(toast_form_error("Kod o tej nazwie juz istnieje."));
+    }
+
+    sqlx::query(
+        r#"
+            INSERT INTO coupons (code, discount_type, value, min_order_value, expires_at, single_use_per_user,
+                                 segment_tag, segment_min_spent, segment_min_orders)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(&code)
+    .bind(payload.discount_type)
+    .bind(payload.value)
+    .bind(payload.min_order_value)
+    .bind(expires_at)
+    .bind(payload.single_use_per_user.is_some())
+    .bind(segment.tag)
+    .bind(segment.min_spent)
+    .bind(segment.min_orders)
+    .execute(&app_state.db_pool)
+    .await?;
+
+    tracing::info!("Admin {} utworzył kod rabatowy '{}'", claims.sub, code);
+
+    Ok((
+        StatusCode::OK,
+        coupon_saved_headers("Kod rabatowy zostal utworzony."),
+    ))
+}
+
+/// Zapisuje zmiany istniejącego kodu rabatowego. Licznik użyć pozostaje bez zmian.
+pub async fn update_coupon_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(coupon_id): Path<Uuid>,
+    Form(payload): Form<CouponPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+    let (code, expires_at, segment) = parse_coupon_payload(&payload)?;
+
+    let code_taken: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM coupons WHERE code = $1 AND id <> $2)")
+            .bind(&code)
+            .bind(coupon_id)
+            .fetch_one(&app_state.db_pool)
+            .await?;
+    if code_taken {
+        return Err(toast_form_error("Kod o tej nazwie juz istnieje."));
+    }
+
+    let result = sqlx::query(
+        r#"
+            UPDATE coupons
+            SET code = $1, discount_type = $2, value = $3, min_order_value = $4,
+                expires_at = $5, single_use_per_user = $6, segment_tag = $7,
+                segment_min_spent = $8, segment_min_orders = $9, updated_at = NOW()
+            WHERE id = $10
+        "#,
+    )
+    .bind(&code)
+    .bind(payload.discount_type)
+    .bind(payload.value)
+    .bind(payload.min_order_value)
+    .bind(expires_at)
+    .bind(payload.single_use_per_user.is_some())
+    .bind(segment.tag)
+    .bind(segment.min_spent)
+    .bind(segment.min_orders)
+    .bind(coupon_id)
+    .execute(&app_state.db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!("Admin {} zaktualizował kod rabatowy '{}'", claims.sub, code);
+
+    let mut headers = coupon_saved_headers("Kod rabatowy zostal zapisany.");
+    // Po edycji wracamy do czystego formularza dodawania
+    let location_payload = json!({
+        "path": "/htmx/admin/coupons",
+        "target": "#admin-content",
+        "swap": "innerHTML"
+    });
+    if let Ok(val) = HeaderValue::from_str(&location_payload.to_string()) {
+        headers.insert("HX-Location", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// Włącza lub wyłącza kod rabatowy bez jego usuwania.
+pub async fn toggle_coupon_active_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(coupon_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    let is_active: Option<bool> = sqlx::query_scalar(
+        "UPDATE coupons SET is_active = NOT is_active, updated_at = NOW() WHERE id = $1 RETURNING is_active",
+    )
+    .bind(coupon_id)
+    .fetch_optional(&app_state.db_pool)
+    .await?;
+    let Some(is_active) = is_active else {
+        return Err(AppError::NotFound);
+    };
+
+    tracing::info!(
+        "Admin {} zmienił aktywność kodu rabatowego {} na {}",
+        claims.sub,
+        coupon_id,
+        is_active
+    );
+
+    let message = if is_active {
+        "Kod rabatowy zostal wlaczony."
+    } else {
+        "Kod rabatowy zostal wylaczony."
+    };
+    Ok((StatusCode::OK, coupon_saved_headers(message)))
+}
+
+/// Usuwa kod rabatowy. Kodów użytych w zamówieniach nie usuwamy - można je tylko wyłączyć,
+/// żeby historia zamówień zachowała informację o rabacie.
+pub async fn delete_coupon_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(coupon_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    let result = sqlx::query(
+        r#"
+            DELETE FROM coupons
+            WHERE id = $1
+              AND NOT EXISTS (SELECT 1 FROM coupon_redemptions WHERE coupon_id = $1)
+        "#,
+    )
+    .bind(coupon_id)
+    .execute(&app_state.db_pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM coupons WHERE id = $1)")
+                .bind(coupon_id)
+                .fetch_one(&app_state.db_pool)
+                .await?;
+        if !exists {
+            return Err(AppError::NotFound);
+        }
+        return Err(toast_form_error(
+            "Kod byl juz uzyty w zamowieniach - zamiast usuwac, wylacz go.",
+        ));
+    }
+
+    tracing::info!("Admin {} usunął kod rabatowy {}", claims.sub, coupon_id);
+
+    Ok((
+        StatusCode::OK,
+        coupon_saved_headers("Kod rabatowy zostal usuniety."),
+    ))
+}
+
+fn gift_card_saved_headers(message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadGiftCards": true,
+        "showMessage": {
+            "message": message,
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    headers
+}
+
+/// POST /api/admin/karty-podarunkowe/{id}/oplacona - wpłata za kartę przyszła przelewem:
+/// karta zaczyna działać, a kod idzie do obdarowanego
+pub async fn mark_gift_card_paid_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(gift_card_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    let mut conn = app_state.db_pool.acquire().await?;
+    let Some(gift_card) = mark_gift_card_paid(&mut conn, gift_card_id).await? else {
+        find_gift_card(&app_state.db_pool, gift_card_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        return Err(toast_form_error("Karta jest juz oplacona."));
+    };
+    deliver_gift_card(app_state.clone(), gift_card);
+
+    tracing::info!(
+        "Admin {} potwierdził wpłatę za kartę podarunkową {}",
+        claims.sub,
+        gift_card_id
+    );
+    Ok((
+        StatusCode::OK,
+        gift_card_saved_headers("Karta oplacona - kod zostal wyslany do obdarowanego."),
+    ))
+}
+
+/// POST /api/admin/karty-podarunkowe/{id}/wyslij - ponowna wysyłka kodu (np. wiadomość
+/// trafiła do spamu)
+pub async fn resend_gift_card_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(gift_card_id): Path<Uuid>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    let gift_card = find_gift_card(&app_state.db_pool, gift_card_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if gift_card.paid_at.is_none() {
+        return Err(toast_form_error(
+            "Karta nie jest oplacona - kodu nie mozna jeszcze wyslac.",
+        ));
+    }
+    deliver_gift_card(app_state.clone(), gift_card);
+
+    tracing::info!(
+        "Admin {} ponownie wysłał kartę podarunkową {}",
+        claims.sub,
+        gift_card_id
+    );
+    Ok((
+        StatusCode::OK,
+        gift_card_saved_headers("Kod karty zostal wyslany ponownie."),
+    ))
+}
+
+/// POST /api/admin/coupons/kampanie - generuje kampanię jednorazowych kodów
+pub async fn create_coupon_campaign_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<CreateCouponCampaignPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+    payload.validate()?;
+
+    let code_prefix = Coupon::normalize_code(&payload.code_prefix);
+    if !code_prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(toast_form_error(
+            "Prefiks moze zawierac tylko litery i cyfry.",
+        ));
+    }
+    if payload.discount_type == CouponDiscountType::Percentage && payload.value > 100 {
+        return Err(toast_form_error(
+            "Rabat procentowy nie moze przekraczac 100%.",
+        ));
+    }
+    let expires_at = parse_coupon_expiry(payload.expires_at.as_deref())?;
+
+    let campaign = NewCampaign {
+        name: payload.name.trim(),
+        code_prefix: &code_prefix,
+        discount_type: payload.discount_type,
+        value: payload.value,
+        min_order_value: payload.min_order_value,
+        expires_at,
+    };
+    create_campaign(
+        &app_state.db_pool,
+        &campaign,
+        payload.count as usize,
+        claims.sub,
+    )
+    .await?;
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "reloadCouponCampaigns": true,
+        "showMessage": {
+            "message": format!("Wygenerowano {} kodow rabatowych.", payload.count),
+            "type": "success"
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((StatusCode::OK, headers))
+}
+
+/// GET /api/admin/raporty/rejestr-sprzedazy?month=RRRR-MM - rejestr sprzedaży dla księgowej w CSV
+pub async fn export_sales_register_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(query): Query<SalesRegisterQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    claims.require_permission(Permission::ViewReports)?;
+
+    let month_start = parse_register_month(query.month.as_deref())?;
+    let entries = fetch_sales_register(&app_state.db_pool, month_start).await?;
+    tracing::info!(
+        "Admin {} pobrał rejestr sprzedaży za {} ({} pozycji)",
+        claims.sub,
+        month_start.format("%Y-%m"),
+        entries.len()
+    );
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        sales_register_filename(month_start)
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        sales_register_csv(&entries),
+    ))
+}
 
-            if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
-                headers.insert("HX-Trigger", val);
-            }
+/// GET /api/admin/coupons/kampanie/{campaign_id}/eksport - kody kampanii w CSV
+pub async fn export_coupon_campaign_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(campaign_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
 
-            Ok((StatusCode::OK, headers, Json(order))) // Zwracamy OK, nagłówki i zaktualizowany obiekt Order
-        }
-        None => {
-            tracing::warn!(
-                "Nie znaleziono zamówienia do aktualizacji statusu: order_id={}",
-                order_id
-            );
-            Err(AppError::NotFound)
-        }
-    }
+    let csv_output = campaign_codes_csv(&app_state.db_pool, campaign_id).await?;
+    let disposition = format!(
+        "attachment; filename=\"kampania-{}.csv\"",
+        &campaign_id.to_string()[..8]
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        csv_output,
+    ))
 }
 
 pub async fn add_item_to_cart_handler(
@@ -1722,49 +6193,31 @@ pub async fn add_item_to_cart_handler(
     let user_id = claims.sub;
     let mut tx = app_state.db_pool.begin().await?;
 
-    let cart = match sqlx::query_as::<_, ShoppingCart>(
-        "SELECT * FROM shopping_carts WHERE user_id = $1 FOR UPDATE",
-    )
-    .bind(user_id)
-    .fetch_optional(&mut *tx)
-    .await?
-    {
-        Some(existing_cart) => existing_cart,
-        None => {
-            sqlx::query_as::<_, ShoppingCart>(
-                "INSERT INTO shopping_carts (user_id) VALUES ($1) RETURNING *",
-            )
-            .bind(user_id)
-            .fetch_one(&mut *tx)
-            .await?
-        }
-    };
+    let cart = repo::carts::cart_for_owner_for_update(&mut tx, CartOwner::User(user_id)).await?;
 
     let product_to_add_opt =
-        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
-            .bind(payload.product_id)
-            .fetch_optional(&mut *tx)
-            .await?;
+        repo::products::find_by_id_for_update(&mut *tx, payload.product_id).await?;
 
     match product_to_add_opt {
         Some(product) => {
-            if product.status != ProductStatus::Available {
+            let reservation = reserve_product_for_cart(&mut tx, product.id, cart.id).await?;
+            if reservation != ReservationOutcome::Reserved {
                 tracing::warn!(
-                    "Użytkownik {} próbował dodać niedostępny produkt {} (status: {:?}) do koszyka {}",
+                    "Użytkownik {} próbował dodać niedostępny produkt {} (status: {:?}, rezerwacja: {:?}) do koszyka {}",
                     user_id,
                     payload.product_id,
                     product.status,
+                    reservation,
                     cart.id
                 );
-                return Err(AppError::UnprocessableEntity(
-                    "Produkt jest niedostępny.".to_string(),
-                ));
+                let message = if reservation == ReservationOutcome::HeldByAnotherCart {
+                    "Produkt jest zarezerwowany przez innego klienta."
+                } else {
+                    "Produkt jest niedostępny."
+                };
+                return Err(AppError::UnprocessableEntity(message.to_string()));
             }
-            sqlx::query("INSERT INTO cart_items (cart_id, product_id) VALUES ($1, $2) ON CONFLICT (cart_id, product_id) DO NOTHING")
-                .bind(cart.id)
-                .bind(payload.product_id)
-                .execute(&mut *tx)
-                .await?;
+            repo::carts::add_item(&mut *tx, cart.id, payload.product_id).await?;
             tracing::info!(
                 "Produkt {} dodany (lub już był) w koszyku {} dla użytkownika {}",
                 payload.product_id,
@@ -1786,15 +6239,18 @@ pub async fn add_item_to_cart_handler(
     // ZMIANA: Zamiast budować odpowiedź ręcznie, używamy build_cart_details_response po zatwierdzeniu
     // Najpierw zatwierdzamy zmiany...
     tx.commit().await?;
+    record_event(
+        &app_state.db_pool,
+        EventType::AddedToCart,
+        NewEvent::for_cart(&cart, payload.product_id),
+    );
 
     // ...a potem pobieramy świeże dane i budujemy odpowiedź.
     // To oddziela logikę zapisu od logiki odczytu.
     let mut conn = app_state.db_pool.acquire().await?;
-    let final_cart =
-        sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE id = $1")
-            .bind(cart.id)
-            .fetch_one(&mut *conn)
-            .await?;
+    let final_cart = repo::carts::find_by_id(&mut *conn, cart.id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
     let response_cart = build_cart_details_response(&final_cart, &mut conn).await?;
 
@@ -1815,16 +6271,7 @@ pub async fn get_cart_handler(
     })?;
 
     // Znajdź koszyk użytkownika
-    let cart_optional = sqlx::query_as::<_, ShoppingCart>(
-        r#"
-            SELECT *
-            FROM shopping_carts
-            WHERE user_id = $1
-        "#,
-    )
-    .bind(user_id)
-    .fetch_optional(&mut *conn)
-    .await?;
+    let cart_optional = repo::carts::find_by_owner(&mut *conn, CartOwner::User(user_id)).await?;
 
     match cart_optional {
         Some(cart) => {
@@ -1853,31 +6300,21 @@ pub async fn remove_item_from_cart_handler(
 
     let mut tx = app_state.db_pool.begin().await?;
 
-    let cart = match sqlx::query_as::<_, ShoppingCart>(
-        "SELECT * FROM shopping_carts WHERE user_id = $1 FOR UPDATE",
-    )
-    .bind(user_id)
-    .fetch_optional(&mut *tx)
-    .await?
-    {
-        Some(existing_cart) => existing_cart,
-        None => {
-            tracing::warn!(
-                "Użytkownik {} próbował usunąć produkt, ale nie ma koszyka.",
-                user_id
-            );
-            return Err(AppError::NotFound);
-        }
-    };
-
-    let delete_result =
-        sqlx::query("DELETE FROM cart_items WHERE cart_id = $1 AND product_id = $2")
-            .bind(cart.id)
-            .bind(product_id_to_remove)
-            .execute(&mut *tx)
-            .await?;
+    let cart =
+        match repo::carts::find_by_owner_for_update(&mut *tx, CartOwner::User(user_id)).await? {
+            Some(existing_cart) => existing_cart,
+            None => {
+                tracing::warn!(
+                    "Użytkownik {} próbował usunąć produkt, ale nie ma koszyka.",
+                    user_id
+                );
+                return Err(AppError::NotFound);
+            }
+        };
 
-    if delete_result.rows_affected() > 0 {
+    let item_removed = repo::carts::remove_item(&mut *tx, cart.id, product_id_to_remove).await?;
+    if item_removed {
+        release_product_reservation(&mut tx, product_id_to_remove, cart.id).await?;
         tracing::info!(
             "Produkt {} usunięty z koszyka {} dla użytkownika {}",
             product_id_to_remove,
@@ -1894,13 +6331,18 @@ pub async fn remove_item_from_cart_handler(
 
     // ZMIANA: Użycie build_cart_details_response po zatwierdzeniu transakcji
     tx.commit().await?;
+    if item_removed {
+        record_event(
+            &app_state.db_pool,
+            EventType::RemovedFromCart,
+            NewEvent::for_cart(&cart, product_id_to_remove),
+        );
+    }
 
     let mut conn = app_state.db_pool.acquire().await?;
-    let final_cart =
-        sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE id = $1")
-            .bind(cart.id)
-            .fetch_one(&mut *conn)
-            .await?;
+    let final_cart = repo::carts::find_by_id(&mut *conn, cart.id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
     let cart_details = build_cart_details_response(&final_cart, &mut conn).await?;
 
@@ -1967,52 +6409,19 @@ pub async fn add_item_to_cart_htmx_handler(
     if let Ok(claims) = user_claims_result {
         // --- SCENARIUSZ 1: Użytkownik zalogowany ---
         let user_id = claims.sub;
-        cart = match sqlx::query_as("SELECT * FROM shopping_carts WHERE user_id = $1 FOR UPDATE")
-            .bind(user_id)
-            .fetch_optional(&mut *tx)
-            .await?
-        {
-            Some(existing_cart) => existing_cart,
-            None => {
-                sqlx::query_as("INSERT INTO shopping_carts (user_id) VALUES ($1) RETURNING *")
-                    .bind(user_id)
-                    .fetch_one(&mut *tx)
-                    .await?
-            }
-        };
+        cart = repo::carts::cart_for_owner_for_update(&mut tx, CartOwner::User(user_id)).await?;
     } else {
         // --- SCENARIUSZ 2: Użytkownik jest gościem ---
         if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
-            // Gość ma już ID sesji, szukamy jego koszyka
-            if let Some(existing_cart) = sqlx::query_as(
-                "SELECT * FROM shopping_carts WHERE guest_session_id = $1 FOR UPDATE",
-            )
-            .bind(guest_id)
-            .fetch_optional(&mut *tx)
-            .await?
-            {
-                cart = existing_cart;
-                new_guest_cart_id_to_set = Some(guest_id);
-            } else {
-                // ID sesji było, ale koszyk zniknął (rzadkie) - tworzymy nowy z tym samym ID
-                cart = sqlx::query_as(
-                    "INSERT INTO shopping_carts (guest_session_id) VALUES ($1) RETURNING *",
-                )
-                .bind(guest_id)
-                .fetch_one(&mut *tx)
-                .await?;
-                new_guest_cart_id_to_set = Some(guest_id);
-            }
+            // Gość ma już ID sesji - jeśli koszyk zniknął (rzadkie), powstaje nowy z tym samym ID
+            cart =
+                repo::carts::cart_for_owner_for_update(&mut tx, CartOwner::Guest(guest_id)).await?;
+            new_guest_cart_id_to_set = Some(guest_id);
         } else {
             // Nowy gość, tworzymy mu ID sesji i koszyk
             let new_id = Uuid::new_v4();
             new_guest_cart_id_to_set = Some(new_id);
-            cart = sqlx::query_as(
-                "INSERT INTO shopping_carts (guest_session_id) VALUES ($1) RETURNING *",
-            )
-            .bind(new_id)
-            .fetch_one(&mut *tx)
-            .await?;
+            cart = repo::carts::create(&mut *tx, CartOwner::Guest(new_id)).await?;
 
             // <<< KLUCZOWA POPRAWKA: Ustawiamy ciasteczko dla nowego gościa >>>
             let guest_cookie = Cookie::build(("guest_cart_id", new_id.to_string()))
@@ -2030,18 +6439,23 @@ pub async fn add_item_to_cart_htmx_handler(
         }
     }
 
-    let product_opt =
-        sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = $1 FOR UPDATE")
-            .bind(product_id)
-            .fetch_optional(&mut *tx)
-            .await?;
+    let product_opt = repo::products::find_by_id_for_update(&mut *tx, product_id).await?;
 
     match product_opt {
         Some(product) => {
-            if product.status != ProductStatus::Available {
+            let reservation = reserve_product_for_cart(&mut tx, product_id, cart.id).await?;
+            if reservation != ReservationOutcome::Reserved {
                 tx.rollback().await?;
+                let message = if reservation == ReservationOutcome::HeldByAnotherCart {
+                    format!(
+                        "Produkt '{}' jest zarezerwowany przez innego klienta.",
+                        product.name
+                    )
+                } else {
+                    format!("Produkt '{}' jest obecnie niedostepny.", product.name)
+                };
                 let trigger_payload = serde_json::json!({
-                    "showMessage": { "type": "warning", "message": format!("Produkt '{}' jest obecnie niedostepny.", product.name) }
+                    "showMessage": { "type": "warning", "message": message }
                 });
                 if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
                     headers.insert("HX-Trigger", val);
@@ -2049,11 +6463,7 @@ pub async fn add_item_to_cart_htmx_handler(
                 return Ok((headers, html!()));
             }
 
-            sqlx::query("INSERT INTO cart_items (cart_id, product_id) VALUES ($1, $2) ON CONFLICT (cart_id, product_id) DO NOTHING")
-                .bind(cart.id)
-                .bind(product_id)
-                .execute(&mut *tx)
-                .await?;
+            repo::carts::add_item(&mut *tx, cart.id, product_id).await?;
         }
         None => {
             tx.rollback().await?;
@@ -2143,12 +6553,8 @@ pub async fn get_guest_cart(
 ) -> Result<impl IntoResponse, AppError> {
     if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
         let mut conn = app_state.db_pool.acquire().await?;
-        if let Some(cart) = sqlx::query_as::<_, ShoppingCart>(
-            "SELECT * FROM shopping_carts WHERE guest_session_id = $1",
-        )
-        .bind(guest_id)
-        .fetch_optional(&mut *conn)
-        .await?
+        if let Some(cart) =
+            repo::carts::find_by_owner(&mut *conn, CartOwner::Guest(guest_id)).await?
         {
             let response = build_cart_details_response(&cart, &mut conn).await?;
             return Ok((StatusCode::OK, Json(response)));
@@ -2167,28 +6573,28 @@ pub async fn remove_item_from_guest_cart(
 
     let mut tx = app_state.db_pool.begin().await?;
 
-    let cart = sqlx::query_as::<_, ShoppingCart>(
-        "SELECT * FROM shopping_carts WHERE guest_session_id = $1",
-    )
-    .bind(guest_id)
-    .fetch_optional(&mut *tx)
-    .await?
-    .ok_or(AppError::NotFound)?;
+    let cart = repo::carts::find_by_owner(&mut *tx, CartOwner::Guest(guest_id))
+        .await?
+        .ok_or(AppError::NotFound)?;
 
-    sqlx::query("DELETE FROM cart_items WHERE cart_id = $1 AND product_id = $2")
-        .bind(cart.id)
-        .bind(product_id_to_remove)
-        .execute(&mut *tx)
-        .await?;
+    let item_removed = repo::carts::remove_item(&mut *tx, cart.id, product_id_to_remove).await?;
+    if item_removed {
+        release_product_reservation(&mut tx, product_id_to_remove, cart.id).await?;
+    }
 
     tx.commit().await?;
+    if item_removed {
+        record_event(
+            &app_state.db_pool,
+            EventType::RemovedFromCart,
+            NewEvent::for_cart(&cart, product_id_to_remove),
+        );
+    }
 
     let mut conn = app_state.db_pool.acquire().await?;
-    let final_cart =
-        sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE id = $1")
-            .bind(cart.id)
-            .fetch_one(&mut *conn)
-            .await?;
+    let final_cart = repo::carts::find_by_id(&mut *conn, cart.id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
     let response_details = build_cart_details_response(&final_cart, &mut conn).await?;
     let response = GuestCartOperationResponse {
@@ -2209,70 +6615,33 @@ pub async fn merge_cart_handler(
     let guest_cart_id_to_merge = payload.guest_cart_id;
     let mut tx = app_state.db_pool.begin().await?;
 
-    let user_cart =
-        match sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE user_id = $1")
-            .bind(user_id)
-            .fetch_optional(&mut *tx)
-            .await?
-        {
-            Some(cart) => cart,
-            None => {
-                sqlx::query_as::<_, ShoppingCart>(
-                    "INSERT INTO shopping_carts (user_id) VALUES ($1) RETURNING *",
-                )
-                .bind(user_id)
-                .fetch_one(&mut *tx)
-                .await?
-            }
-        };
+    let user_owner = CartOwner::User(user_id);
+    let user_cart = match repo::carts::find_by_owner(&mut *tx, user_owner).await? {
+        Some(cart) => cart,
+        None => repo::carts::create(&mut *tx, user_owner).await?,
+    };
 
-    if let Some(guest_cart) = sqlx::query_as::<_, ShoppingCart>(
-        "SELECT * FROM shopping_carts WHERE guest_session_id = $1",
-    )
-    .bind(guest_cart_id_to_merge)
-    .fetch_optional(&mut *tx)
-    .await?
+    if let Some(guest_cart) =
+        repo::carts::find_by_owner(&mut *tx, CartOwner::Guest(guest_cart_id_to_merge)).await?
     {
         if guest_cart.id != user_cart.id {
-            // Przeniesienie itemów z koszyka gościa do koszyka użytkownika za pomocą jednego zapytania UPDATE
-            sqlx::query(
-                r#"
-                    UPDATE cart_items
-                    SET cart_id = $1
-                    WHERE cart_id = $2 AND product_id NOT IN (
-                        SELECT product_id FROM cart_items WHERE cart_id = $1
-                    )
-                "#,
-            )
-            .bind(user_cart.id)
-            .bind(guest_cart.id)
-            .execute(&mut *tx)
-            .await?;
+            // Przeniesienie itemów z koszyka gościa do koszyka użytkownika jednym zapytaniem UPDATE
+            repo::carts::move_items(&mut *tx, guest_cart.id, user_cart.id).await?;
+            transfer_cart_reservations(&mut tx, guest_cart.id, user_cart.id).await?;
 
             // Usunięcie koszyka gościa (itemy, które nie zostały przeniesione, zostaną usunięte kaskadowo)
-            sqlx::query("DELETE FROM shopping_carts WHERE id = $1")
-                .bind(guest_cart.id)
-                .execute(&mut *tx)
-                .await?;
+            repo::carts::delete(&mut *tx, guest_cart.id).await?;
         } else {
-            sqlx::query(
-                "UPDATE shopping_carts SET guest_session_id = NULL WHERE id = $1 AND user_id = $2",
-            )
-            .bind(user_cart.id)
-            .bind(user_id)
-            .execute(&mut *tx)
-            .await?;
+            repo::carts::detach_guest_session(&mut *tx, user_cart.id, user_id).await?;
         }
     }
 
     tx.commit().await?;
 
     let mut conn = app_state.db_pool.acquire().await?;
-    let final_cart =
-        sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE id = $1")
-            .bind(user_cart.id)
-            .fetch_one(&mut *conn)
-            .await?;
+    let final_cart = repo::carts::find_by_id(&mut *conn, user_cart.id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
     let response = build_cart_details_response(&final_cart, &mut *conn).await?;
 
@@ -2342,38 +6711,18 @@ pub async fn upsert_user_shipping_details_handler(
     let country = option_string_empty_as_none(payload.shipping_country);
     let phone = option_string_empty_as_none(payload.shipping_phone);
 
-    // Logika UPSERT (INSERT OR UPDATE)
-    // ON CONFLICT (user_id) DO UPDATE ...
-    let query_result = sqlx::query_as::<_, UserShippingDetails>(
-        r#"
-            INSERT INTO user_shipping_details (
-                user_id, shipping_first_name, shipping_last_name, shipping_address_line1,
-                shipping_address_line2, shipping_city, shipping_postal_code, shipping_country, shipping_phone
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-            ON CONFLICT (user_id) DO UPDATE SET
-                shipping_first_name = EXCLUDED.shipping_first_name,
-                shipping_last_name = EXCLUDED.shipping_last_name,
-                shipping_address_line1 = EXCLUDED.shipping_address_line1,
-                shipping_address_line2 = EXCLUDED.shipping_address_line2,
-                shipping_city = EXCLUDED.shipping_city,
-                shipping_postal_code = EXCLUDED.shipping_postal_code,
-                shipping_country = EXCLUDED.shipping_country,
-                shipping_phone = EXCLUDED.shipping_phone,
-                updated_at = NOW()
-            RETURNING *
-        "#,
-    )
-    .bind(user_id)
-    .bind(first_name)
-    .bind(last_name)
-    .bind(address1)
-    .bind(address2)
-    .bind(city)
-    .bind(postal_code)
-    .bind(country)
-    .bind(phone)
-    .fetch_one(&app_state.db_pool)
-    .await;
+    let details = repo::users::ShippingDetailsUpdate {
+        first_name: first_name.as_deref(),
+        last_name: last_name.as_deref(),
+        address_line1: address1.as_deref(),
+        address_line2: address2.as_deref(),
+        city: city.as_deref(),
+        postal_code: postal_code.as_deref(),
+        country: country.as_deref(),
+        phone: phone.as_deref(),
+    };
+    let query_result =
+        repo::users::upsert_shipping_details(&app_state.db_pool, user_id, &details).await;
 
     match query_result {
         Ok(_) => {
@@ -2409,7 +6758,7 @@ pub async fn upsert_user_shipping_details_handler(
                 user_id,
                 e
             );
-            Err(AppError::from(e)) // Lub bardziej szczegółowy błąd
+            Err(e)
         }
     }
 }
@@ -2421,11 +6770,7 @@ pub async fn permanent_delete_order_handler(
     Path(order_id): Path<Uuid>,
 ) -> Result<(StatusCode, HeaderMap), AppError> {
     // Krok 1: Sprawdzenie uprawnień. Tylko admin może usuwać zamówienia.
-    if claims.role != Role::Admin {
-        return Err(AppError::UnauthorizedAccess(
-            "Brak uprawnień administratora.".to_string(),
-        ));
-    }
+    claims.require_permission(Permission::ManageOrders)?;
 
     tracing::info!(
         "Admin ID: {} zażądał trwałego usunięcia zamówienia ID: {}",
@@ -2433,6 +6778,14 @@ pub async fn permanent_delete_order_handler(
         order_id
     );
 
+    // Wystawionej faktury nie wolno usunąć razem z zamówieniem - takie zamówienie można tylko anulować
+    if let Some(invoice) = find_invoice_for_order(&app_state.db_pool, order_id).await? {
+        return Err(AppError::Conflict(format!(
+            "Do zamówienia wystawiono fakturę {} - nie można go trwale usunąć.",
+            invoice.number
+        )));
+    }
+
     // Krok 2: Rozpoczęcie transakcji bazodanowej. To kluczowe dla bezpieczeństwa!
     let mut tx = app_state.db_pool.begin().await?;
     tracing::debug!(
@@ -2441,11 +6794,7 @@ pub async fn permanent_delete_order_handler(
     );
 
     // Krok 3: Znajdź wszystkie ID produktów w tym zamówieniu.
-    let product_ids: Vec<Uuid> =
-        sqlx::query_scalar("SELECT product_id FROM order_items WHERE order_id = $1")
-            .bind(order_id)
-            .fetch_all(&mut *tx)
-            .await?;
+    let product_ids = repo::orders::product_ids(&mut *tx, order_id).await?;
 
     // Krok 4: Jeśli znaleziono produkty, zmień ich status z powrotem na "Available".
     if !product_ids.is_empty() {
@@ -2453,38 +6802,24 @@ pub async fn permanent_delete_order_handler(
             "Znaleziono produkty {:?} do przywrócenia statusu na 'Available'.",
             product_ids
         );
-        sqlx::query("UPDATE products SET status = $1 WHERE id = ANY($2)")
-            .bind(ProductStatus::Available) // Używamy enuma dla bezpieczeństwa
-            .bind(&product_ids)
-            .execute(&mut *tx)
-            .await?;
+        repo::products::set_status_many(&mut *tx, &product_ids, ProductStatus::Available).await?;
     }
 
-    // Krok 5: Usuń pozycje z tabeli pośredniczącej `order_items`.
-    // To zerwie powiązania i pozwoli bezpiecznie usunąć zamówienie.
-    sqlx::query("DELETE FROM order_items WHERE order_id = $1")
-        .bind(order_id)
-        .execute(&mut *tx)
-        .await?;
-    tracing::debug!("Usunięto pozycje z order_items dla zamówienia {}", order_id);
-
-    // Krok 6: Usuń właściwe zamówienie z tabeli `orders`.
-    let delete_result = sqlx::query("DELETE FROM orders WHERE id = $1")
-        .bind(order_id)
-        .execute(&mut *tx)
-        .await?;
+    // Krok 5: Usuń zamówienie razem z pozycjami z `order_items`.
+    let deleted = repo::orders::delete(&mut *tx, order_id).await?;
 
-    // Krok 7: Zatwierdź transakcję. Dopiero teraz wszystkie zmiany zostaną trwale zapisane w bazie.
+    // Krok 6: Zatwierdź transakcję. Dopiero teraz wszystkie zmiany zostaną trwale zapisane w bazie.
     tx.commit().await?;
     tracing::info!(
         "Transakcja zakończona pomyślnie. Zamówienie {} zostało usunięte.",
         order_id
     );
+    invalidate_merchant_feed(&app_state).await;
 
-    // Krok 8: Przygotuj odpowiedź dla HTMX.
+    // Krok 7: Przygotuj odpowiedź dla HTMX.
     let mut headers = HeaderMap::new();
 
-    if delete_result.rows_affected() == 0 {
+    if !deleted {
         tracing::warn!(
             "Próbowano usunąć zamówienie {}, ale nie znaleziono go w bazie.",
             order_id
@@ -2515,27 +6850,18 @@ pub async fn fetch_order_details_service(
     pool: &sqlx::PgPool,
     order_id: Uuid,
 ) -> Result<OrderDetailsResponse, AppError> {
-    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
-        .bind(order_id)
-        .fetch_optional(pool)
+    let order = repo::orders::find_by_id(pool, order_id)
         .await?
         .ok_or(AppError::NotFound)?; // Jeśli nie ma zamówienia, zwracamy błąd
 
-    let order_items_db =
-        sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1")
-            .bind(order_id)
-            .fetch_all(pool)
-            .await?;
+    let order_items_db = repo::orders::items(pool, order_id).await?;
 
     let mut items_details_public: Vec<OrderItemDetailsPublic> =
         Vec::with_capacity(order_items_db.len());
 
     if !order_items_db.is_empty() {
         let product_ids: Vec<Uuid> = order_items_db.iter().map(|item| item.product_id).collect();
-        let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ANY($1)")
-            .bind(&product_ids)
-            .fetch_all(pool)
-            .await?;
+        let products = repo::products::find_by_ids(pool, &product_ids).await?;
 
         let products_map: HashMap<Uuid, Product> =
             products.into_iter().map(|p| (p.id, p)).collect();
@@ -2564,6 +6890,37 @@ pub async fn fetch_order_details_service(
     })
 }
 
+/// Nowy token resetu hasła (poprzednie przestają działać) i e-mail z linkiem.
+/// Z formularza "Nie pamiętam hasła" i z karty klienta w panelu.
+async fn issue_password_reset(
+    app_state: &AppState,
+    user_id: Uuid,
+    email: &str,
+) -> Result<(), AppError> {
+    let mut tx = app_state.db_pool.begin().await?;
+
+    // Usuń stare tokeny tego użytkownika, aby uniknąć bałaganu
+    sqlx::query("DELETE FROM password_resets WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    // Wygeneruj nowy token
+    let token = Uuid::new_v4();
+    let expires_at = Utc::now() + Duration::minutes(30);
+
+    sqlx::query("INSERT INTO password_resets (token, user_id, expires_at) VALUES ($1, $2, $3)")
+        .bind(token)
+        .bind(user_id)
+        .bind(expires_at)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    send_password_reset_email(app_state, email, &token.to_string()).await
+}
+
 pub async fn forgot_password_handler(
     State(app_state): State<Arc<AppState>>,
     Form(payload): Form<ForgotPasswordPayload>,
@@ -2571,36 +6928,9 @@ pub async fn forgot_password_handler(
     // Zawsze zwracamy ten sam komunikat, aby nie ujawniać, czy e-mail istnieje w bazie.
     let success_message = "Jeśli konto powiązane z tym adresem e-mail istnieje, wysłaliśmy na nie link do zresetowania hasła.";
 
-    if let Some(user) = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1")
-        .bind(&payload.email)
-        .fetch_optional(&app_state.db_pool)
-        .await?
-    {
-        // Użytkownik istnieje, kontynuujemy logikę
-        let mut tx = app_state.db_pool.begin().await?;
-
-        // Usuń stare tokeny tego użytkownika, aby uniknąć bałaganu
-        sqlx::query("DELETE FROM password_resets WHERE user_id = $1")
-            .bind(user.id)
-            .execute(&mut *tx)
-            .await?;
-
-        // Wygeneruj nowy token
-        let token = Uuid::new_v4();
-        let expires_at = Utc::now() + Duration::minutes(30);
-
-        sqlx::query("INSERT INTO password_resets (token, user_id, expires_at) VALUES ($1, $2, $3)")
-            .bind(token)
-            .bind(user.id)
-            .bind(expires_at)
-            .execute(&mut *tx)
-            .await?;
-
-        tx.commit().await?;
-
-        // Wyślij e-mail
-        if let Err(e) = send_password_reset_email(&app_state, &user.email, &token.to_string()).await
-        {
+    if let Some(user) = repo::users::find_by_email(&app_state.db_pool, &payload.email).await? {
+        // Użytkownik istnieje - błąd wysyłki tylko logujemy, odpowiedź ma być taka sama
+        if let Err(e) = issue_password_reset(&app_state, user.id, &user.email).await {
             tracing::error!(
                 "Nie udało się wysłać e-maila z resetem hasła do {}: {:?}",
                 user.email,
@@ -2641,11 +6971,7 @@ pub async fn reset_password_handler(
 
     // Zmień hasło
     let new_password_hash = hash_password(&payload.new_password)?;
-    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
-        .bind(new_password_hash)
-        .bind(token_data.user_id)
-        .execute(&mut *tx)
-        .await?;
+    repo::users::set_password_hash(&mut *tx, token_data.user_id, &new_password_hash).await?;
 
     // Usuń zużyty token
     sqlx::query("DELETE FROM password_resets WHERE token = $1")
@@ -2672,6 +6998,155 @@ pub async fn reset_password_handler(
     Ok((headers, html! {}))
 }
 
+#[derive(Deserialize)]
+pub struct VerifyEmailQuery {
+    pub token: String,
+    pub sig: String,
+}
+
+/// Link z wiadomości potwierdzającej adres e-mail. Przekierowuje na stronę z wynikiem.
+pub async fn verify_email_handler(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<VerifyEmailQuery>,
+) -> Result<Redirect, AppError> {
+    let outcome = verify_email_token(&app_state, &query.token, &query.sig).await?;
+    Ok(Redirect::to(&format!(
+        "/weryfikacja-email?status={}",
+        outcome.as_query_value()
+    )))
+}
+
+/// Ponownie wysyła link potwierdzający adres e-mail zalogowanego klienta.
+pub async fn resend_verification_email_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    let mut conn = app_state.db_pool.acquire().await?;
+    let message = if is_email_verified(&mut conn, claims.sub).await? {
+        "Twoj adres e-mail jest juz potwierdzony."
+    } else {
+        let email = repo::users::email(&mut *conn, claims.sub)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        drop(conn);
+        if send_verification_link(&app_state, claims.sub, &email).await? {
+            "Wyslalismy nowy link potwierdzajacy. Sprawdz skrzynke (rowniez folder spam)."
+        } else {
+            "Link zostal wyslany przed chwila. Sprobuj ponownie za kilka minut."
+        }
+    };
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = json!({
+        "showMessage": { "message": message, "type": "info" }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((StatusCode::OK, headers))
+}
+
+/// Rozpoczyna podgląd sklepu jako klient (tylko do odczytu). Token admina zostaje bez zmian,
+/// podgląd trzyma osobne ciasteczko, a sesja trafia do dziennika `impersonation_sessions`.
+pub async fn start_impersonation_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Form(payload): Form<StartImpersonationPayload>,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let customer_query = payload.customer.trim();
+    let customer = match Uuid::parse_str(customer_query) {
+        Ok(customer_id) => repo::users::find_by_id(&app_state.db_pool, customer_id).await?,
+        Err(_) => {
+            repo::users::find_by_email_ignore_case(&app_state.db_pool, customer_query).await?
+        }
+    };
+    let customer = match customer {
+        Some(user) if user.role == Role::Customer => user,
+        found => {
+            // Podgląd tylko dla istniejących kont klientów - nie dla innych adminów
+            let message = if found.is_none() {
+                r#"{"showMessage": {"message": "Nie znaleziono klienta o podanym e-mailu lub ID.", "type": "error"}}"#
+            } else {
+                r#"{"showMessage": {"message": "Podglad jest dostepny tylko dla kont klientow.", "type": "error"}}"#
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert("HX-Trigger", HeaderValue::from_static(message));
+            return Ok((StatusCode::OK, headers));
+        }
+    };
+
+    let reason = payload
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    let session_id: Uuid = sqlx::query_scalar(
+        r#"
+            INSERT INTO impersonation_sessions (admin_id, customer_id, reason, expires_at)
+            VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))
+            RETURNING id
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(customer.id)
+    .bind(reason)
+    .bind(IMPERSONATION_MINUTES as i32)
+    .fetch_one(&app_state.db_pool)
+    .await?;
+
+    let token =
+        create_impersonation_token(session_id, claims.sub, customer.id, &app_state.jwt_secret)?;
+    tracing::warn!(
+        "Admin {} rozpoczął podgląd konta klienta {} (sesja {}, powód: {:?})",
+        claims.sub,
+        customer.id,
+        session_id,
+        reason
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::SET_COOKIE,
+        impersonation_cookie(Some(token))
+            .to_string()
+            .parse()
+            .unwrap(),
+    );
+    headers.insert("HX-Redirect", HeaderValue::from_static("/moje-konto"));
+    Ok((StatusCode::OK, headers))
+}
+
+/// Kończy podgląd konta klienta i wraca do panelu admina.
+pub async fn stop_impersonation_handler(
+    State(app_state): State<Arc<AppState>>,
+    request_headers: HeaderMap,
+) -> Result<(StatusCode, HeaderMap), AppError> {
+    if let Some(session) = impersonation_from_headers(&request_headers, &app_state.jwt_secret) {
+        sqlx::query(
+            "UPDATE impersonation_sessions SET ended_at = NOW() WHERE id = $1 AND ended_at IS NULL",
+        )
+        .bind(session.sid)
+        .execute(&app_state.db_pool)
+        .await?;
+        tracing::info!(
+            "Admin {} zakończył podgląd konta klienta {} (sesja {})",
+            session.admin_id,
+            session.customer_id,
+            session.sid
+        );
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        axum::http::header::SET_COOKIE,
+        impersonation_cookie(None).to_string().parse().unwrap(),
+    );
+    headers.insert("HX-Redirect", HeaderValue::from_static("/admin"));
+    Ok((StatusCode::OK, headers))
+}
+
 /// Obsługuje wylogowanie użytkownika po stronie serwera.
 /// Głównym zadaniem jest wyczyszczenie ciasteczka 'token'.
 #[allow(deprecated)]
@@ -2692,6 +7167,11 @@ pub async fn logout_handler() -> Result<(StatusCode, HeaderMap), AppError> {
         axum::http::header::SET_COOKIE,
         cookie.to_string().parse().unwrap(),
     );
+    // Wylogowanie kończy też ewentualny podgląd konta klienta
+    headers.append(
+        axum::http::header::SET_COOKIE,
+        impersonation_cookie(None).to_string().parse().unwrap(),
+    );
 
     // Dodatkowo wysyłamy trigger, który poinformuje klienta, że ma dokończyć wylogowanie
     // (np. wyczyścić localStorage i przekierować).
@@ -2713,12 +7193,7 @@ pub async fn init_guest_session_handler(
     let new_guest_id = Uuid::new_v4();
 
     // Utwórz nowy koszyk dla gościa w bazie danych
-    let cart = sqlx::query_as::<_, ShoppingCart>(
-        "INSERT INTO shopping_carts (guest_session_id) VALUES ($1) RETURNING *",
-    )
-    .bind(new_guest_id)
-    .fetch_one(&app_state.db_pool)
-    .await?;
+    let cart = repo::carts::create(&app_state.db_pool, CartOwner::Guest(new_guest_id)).await?;
 
     tracing::info!(
         "Utworzono nowy koszyk ID: {} dla gościa z sesją ID: {}",
@@ -2762,31 +7237,16 @@ pub async fn add_item_to_guest_cart(
     // Logika jest teraz identyczna jak w htmx_handler
     let (cart, guest_cart_uuid) = if let Some(TypedHeader(XGuestCartId(id))) = guest_cart_id_header
     {
-        if let Some(existing_cart) = sqlx::query_as::<_, ShoppingCart>(
-            "SELECT * FROM shopping_carts WHERE guest_session_id = $1",
-        )
-        .bind(id)
-        .fetch_optional(&mut *tx)
-        .await?
-        {
+        let owner = CartOwner::Guest(id);
+        if let Some(existing_cart) = repo::carts::find_by_owner(&mut *tx, owner).await? {
             (existing_cart, id)
         } else {
-            let new_cart = sqlx::query_as::<_, ShoppingCart>(
-                "INSERT INTO shopping_carts (guest_session_id) VALUES ($1) RETURNING *",
-            )
-            .bind(id)
-            .fetch_one(&mut *tx)
-            .await?;
+            let new_cart = repo::carts::create(&mut *tx, owner).await?;
             (new_cart, id)
         }
     } else {
         let new_generated_id = Uuid::new_v4();
-        let new_cart = sqlx::query_as::<_, ShoppingCart>(
-            "INSERT INTO shopping_carts (guest_session_id) VALUES ($1) RETURNING *",
-        )
-        .bind(new_generated_id)
-        .fetch_one(&mut *tx)
-        .await?;
+        let new_cart = repo::carts::create(&mut *tx, CartOwner::Guest(new_generated_id)).await?;
 
         // <<< KLUCZOWA POPRAWKA: Ustawiamy ciasteczko także tutaj >>>
         let guest_cookie = Cookie::build(("guest_cart_id", new_generated_id.to_string()))
@@ -2804,20 +7264,33 @@ pub async fn add_item_to_guest_cart(
         (new_cart, new_generated_id)
     };
 
-    sqlx::query("INSERT INTO cart_items (cart_id, product_id) VALUES ($1, $2) ON CONFLICT (cart_id, product_id) DO NOTHING")
-        .bind(cart.id)
-        .bind(product_id)
-        .execute(&mut *tx)
-        .await?;
+    match reserve_product_for_cart(&mut tx, product_id, cart.id).await? {
+        ReservationOutcome::Reserved => {}
+        ReservationOutcome::HeldByAnotherCart => {
+            return Err(AppError::UnprocessableEntity(
+                "Produkt jest zarezerwowany przez innego klienta.".to_string(),
+            ));
+        }
+        ReservationOutcome::Unavailable => {
+            return Err(AppError::UnprocessableEntity(
+                "Produkt jest niedostępny.".to_string(),
+            ));
+        }
+    }
+
+    repo::carts::add_item(&mut *tx, cart.id, product_id).await?;
 
     tx.commit().await?;
+    record_event(
+        &app_state.db_pool,
+        EventType::AddedToCart,
+        NewEvent::for_cart(&cart, product_id),
+    );
 
     let mut conn = app_state.db_pool.acquire().await?;
-    let final_cart =
-        sqlx::query_as::<_, ShoppingCart>("SELECT * FROM shopping_carts WHERE id = $1")
-            .bind(cart.id)
-            .fetch_one(&mut *conn)
-            .await?;
+    let final_cart = repo::carts::find_by_id(&mut *conn, cart.id)
+        .await?
+        .ok_or(AppError::NotFound)?;
 
     let cart_details_response = build_cart_details_response(&final_cart, &mut conn).await?;
 
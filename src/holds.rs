@@ -0,0 +1,214 @@
+// src/holds.rs
+
+// Ręczne rezerwacje dla konkretnego klienta, np. po ustaleniu ceny w wiadomościach na Instagramie.
+// Produkt dostaje status "Zarezerwowany" bez wpisu w `product_reservations`, więc nikt inny nie
+// doda go do koszyka. Klient dostaje osobisty link - dodanie produktu z linku zamienia rezerwację
+// ręczną w rezerwację jego koszyka, ważną do końca terminu rezerwacji ręcznej.
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{ProductHold, ProductStatus};
+use crate::reservations::{ReservationOutcome, reserve_product_for_cart};
+
+/// Osobisty link zakupu dla klienta
+pub fn hold_link(base_url: &str, hold: &ProductHold) -> String {
+    format!("{}/rezerwacja/{}", base_url, hold.token)
+}
+
+pub async fn find_hold_for_product(
+    pool: &PgPool,
+    product_id: Uuid,
+) -> Result<Option<ProductHold>, AppError> {
+    Ok(
+        sqlx::query_as::<_, ProductHold>("SELECT * FROM product_holds WHERE product_id = $1")
+            .bind(product_id)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Aktywna rezerwacja po tokenie z linku; wygasłe traktujemy jak nieistniejące
+pub async fn find_active_hold_by_token(
+    pool: &PgPool,
+    token: Uuid,
+) -> Result<Option<ProductHold>, AppError> {
+    Ok(sqlx::query_as::<_, ProductHold>(
+        "SELECT * FROM product_holds WHERE token = $1 AND expires_at > NOW()",
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Rezerwuje produkt dla klienta na `days` dni. Produkt musi być dostępny (albo mieć już
+/// rezerwację ręczną - wtedy jest ona zastępowana nową, z nowym linkiem).
+pub async fn create_hold(
+    pool: &PgPool,
+    product_id: Uuid,
+    admin_id: Uuid,
+    customer_label: &str,
+    days: i64,
+    note: Option<&str>,
+) -> Result<ProductHold, AppError> {
+    let mut tx = pool.begin().await?;
+    let status: ProductStatus =
+        sqlx::query_scalar("SELECT status FROM products WHERE id = $1 FOR UPDATE")
+            .bind(product_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::NotFound)?;
+    let held_by_cart: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM product_reservations WHERE product_id = $1 AND expires_at > NOW())",
+    )
+    .bind(product_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let can_hold = matches!(status, ProductStatus::Available | ProductStatus::Reserved);
+    if !can_hold || held_by_cart {
+        return Err(AppError::Conflict(
+            "Produkt jest niedostępny albo leży w koszyku innego klienta.".to_string(),
+        ));
+    }
+
+    let hold = sqlx::query_as::<_, ProductHold>(
+        r#"
+        INSERT INTO product_holds (product_id, customer_label, note, expires_at, created_by)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (product_id) DO UPDATE
+        SET token = gen_random_uuid(), customer_label = EXCLUDED.customer_label,
+            note = EXCLUDED.note, expires_at = EXCLUDED.expires_at,
+            created_by = EXCLUDED.created_by, created_at = NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(product_id)
+    .bind(customer_label)
+    .bind(note)
+    .bind(Utc::now() + Duration::days(days))
+    .bind(admin_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    // Wygasłe wpisy koszyków nie mogą przejąć produktu przed zadaniem czyszczącym
+    sqlx::query("DELETE FROM product_reservations WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE products SET status = $1, updated_at = NOW() WHERE id = $2")
+        .bind(ProductStatus::Reserved)
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Admin {} zarezerwował produkt {} dla '{}' do {}",
+        admin_id,
+        product_id,
+        customer_label,
+        hold.expires_at
+    );
+    Ok(hold)
+}
+
+/// Anuluje rezerwację ręczną razem z rezerwacją koszyka klienta, jeśli użył już linku,
+/// i przywraca produkt do sprzedaży.
+pub async fn cancel_hold(pool: &PgPool, product_id: Uuid) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    let deleted = sqlx::query("DELETE FROM product_holds WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    sqlx::query("DELETE FROM product_reservations WHERE product_id = $1")
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "UPDATE products SET status = $1, updated_at = NOW() WHERE id = $2 AND status = $3",
+    )
+    .bind(ProductStatus::Available)
+    .bind(product_id)
+    .bind(ProductStatus::Reserved)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Rezerwuje produkt dla koszyka klienta z osobistego linku. Ważny token omija rezerwację
+/// ręczną; rezerwacja koszyka trwa do końca rezerwacji ręcznej. Bez ważnego tokenu - zwykła
+/// rezerwacja koszyka.
+pub async fn reserve_held_product_for_cart(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    cart_id: Uuid,
+    token: Uuid,
+) -> Result<ReservationOutcome, AppError> {
+    let hold: Option<(ProductStatus, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT p.status, h.expires_at
+        FROM products p
+        JOIN product_holds h ON h.product_id = p.id
+        WHERE p.id = $1 AND h.token = $2 AND h.expires_at > NOW()
+        FOR UPDATE OF p
+        "#,
+    )
+    .bind(product_id)
+    .bind(token)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match hold {
+        Some((ProductStatus::Reserved, expires_at)) => {
+            // Ten sam link na innym urządzeniu przenosi rezerwację do nowego koszyka
+            sqlx::query(
+                r#"
+                INSERT INTO product_reservations (product_id, cart_id, expires_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (product_id) DO UPDATE
+                SET cart_id = EXCLUDED.cart_id, expires_at = EXCLUDED.expires_at,
+                    created_at = NOW(), checkout_draft_id = NULL
+                "#,
+            )
+            .bind(product_id)
+            .bind(cart_id)
+            .bind(expires_at)
+            .execute(&mut *conn)
+            .await?;
+            Ok(ReservationOutcome::Reserved)
+        }
+        Some(_) => Ok(ReservationOutcome::Unavailable),
+        None => reserve_product_for_cart(conn, product_id, cart_id).await,
+    }
+}
+
+/// Usuwa wygasłe rezerwacje ręczne i przywraca produkty do sprzedaży, o ile klient nie trzyma
+/// ich jeszcze w koszyku. Zwraca ID zwolnionych produktów (do unieważnienia cache).
+pub async fn release_expired_holds(pool: &PgPool) -> Result<Vec<Uuid>, AppError> {
+    let released: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        WITH expired AS (
+            DELETE FROM product_holds
+            WHERE expires_at <= NOW()
+            RETURNING product_id
+        )
+        UPDATE products SET status = $1
+        WHERE id IN (SELECT product_id FROM expired) AND status = $2
+          AND NOT EXISTS (
+              SELECT 1 FROM product_reservations r
+              WHERE r.product_id = products.id AND r.expires_at > NOW()
+          )
+        RETURNING id
+        "#,
+    )
+    .bind(ProductStatus::Available)
+    .bind(ProductStatus::Reserved)
+    .fetch_all(pool)
+    .await?;
+    Ok(released)
+}
@@ -0,0 +1,290 @@
+// src/homepage.rs
+
+// Strona główna edytowana w panelu: sekcje nad listą nowości (baner, karuzele wyróżnionych
+// i najpopularniejszych produktów), ich kolejność i widoczność. Wyrenderowane sekcje trzymamy w
+// `listing_fragment_cache` pod `HOMEPAGE_SECTIONS_CACHE_KEY` - zmiana produktów czyści ten
+// cache w całości, więc sprzedany produkt znika z karuzeli razem z listą nowości.
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{HomepageSection, MoveDirection, Product, ProductStatus};
+use crate::repo;
+use crate::state::AppState;
+
+pub const HOMEPAGE_SECTIONS_CACHE_KEY: &str = "listing:home:sections";
+
+pub const MAX_HERO_TEXT_LEN: usize = 200;
+pub const MAX_HERO_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Więcej produktów w karuzeli i tak nikt nie przewinie
+pub const MAX_FEATURED_PRODUCTS: i64 = 12;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct HomepageSectionConfig {
+    pub section: HomepageSection,
+    pub position: i32,
+    pub enabled: bool,
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub image_url: Option<String>,
+    pub link_url: Option<String>,
+    pub updated_by_email: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Zmiany banera z formularza panelu; `image_url: None` zostawia obecne zdjęcie
+#[derive(Debug, Clone)]
+pub struct HeroUpdate {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub link_url: Option<String>,
+    pub image_url: Option<String>,
+    pub enabled: bool,
+}
+
+/// Link banera: ścieżka w sklepie albo adres https
+pub fn is_valid_hero_link(link: &str) -> bool {
+    (link.starts_with('/') && !link.starts_with("//")) || link.starts_with("https://")
+}
+
+/// Sekcje w kolejności wyświetlania (także wyłączone - dla panelu)
+pub async fn list_homepage_sections(pool: &PgPool) -> Result<Vec<HomepageSectionConfig>, AppError> {
+    let sections = sqlx::query_as::<_, HomepageSectionConfig>(
+        r#"
+            SELECT s.section, s.position, s.enabled, s.title, s.subtitle, s.image_url, s.link_url,
+                   u.email AS updated_by_email, s.updated_at
+            FROM homepage_sections s
+            LEFT JOIN users u ON u.id = s.updated_by
+            ORDER BY s.position
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(sections)
+}
+
+pub async fn save_hero(pool: &PgPool, update: &HeroUpdate, admin_id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            UPDATE homepage_sections
+            SET title = $1, subtitle = $2, link_url = $3, image_url = COALESCE($4, image_url),
+                enabled = $5, updated_by = $6, updated_at = NOW()
+            WHERE section = $7
+        "#,
+    )
+    .bind(&update.title)
+    .bind(&update.subtitle)
+    .bind(&update.link_url)
+    .bind(&update.image_url)
+    .bind(update.enabled)
+    .bind(admin_id)
+    .bind(HomepageSection::Hero)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Tytuł i widoczność karuzeli (wyróżnione albo najpopularniejsze)
+pub async fn save_carousel_section(
+    pool: &PgPool,
+    section: HomepageSection,
+    title: &str,
+    enabled: bool,
+    admin_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            UPDATE homepage_sections
+            SET title = $1, enabled = $2, updated_by = $3, updated_at = NOW()
+            WHERE section = $4
+        "#,
+    )
+    .bind(title)
+    .bind(enabled)
+    .bind(admin_id)
+    .bind(section)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Zamienia sekcję miejscami z sąsiednią. Zwraca `false`, gdy sekcja jest już pierwsza/ostatnia.
+pub async fn move_homepage_section(
+    pool: &PgPool,
+    section: HomepageSection,
+    direction: MoveDirection,
+) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let position: Option<i32> =
+        sqlx::query_scalar("SELECT position FROM homepage_sections WHERE section = $1 FOR UPDATE")
+            .bind(section)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let position = position.ok_or(AppError::NotFound)?;
+
+    let neighbour_sql = match direction {
+        MoveDirection::Up => {
+            "SELECT section, position FROM homepage_sections WHERE position < $1 ORDER BY position DESC LIMIT 1 FOR UPDATE"
+        }
+        MoveDirection::Down => {
+            "SELECT section, position FROM homepage_sections WHERE position > $1 ORDER BY position LIMIT 1 FOR UPDATE"
+        }
+    };
+    let neighbour: Option<(HomepageSection, i32)> = sqlx::query_as(neighbour_sql)
+        .bind(position)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some((neighbour_section, neighbour_position)) = neighbour else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"
+            UPDATE homepage_sections
+            SET position = CASE WHEN section = $1 THEN $4 ELSE $3 END, updated_at = NOW()
+            WHERE section IN ($1, $2)
+        "#,
+    )
+    .bind(section)
+    .bind(neighbour_section)
+    .bind(position)
+    .bind(neighbour_position)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Produkty karuzeli w ustalonej kolejności; `only_available` dla strony sklepu
+pub async fn featured_products(
+    pool: &PgPool,
+    only_available: bool,
+) -> Result<Vec<Product>, AppError> {
+    let products = sqlx::query_as::<_, Product>(
+        r#"
+            SELECT p.*
+            FROM homepage_featured_products f
+            JOIN products p ON p.id = f.product_id
+            WHERE NOT $1 OR p.status = $2
+            ORDER BY f.position
+        "#,
+    )
+    .bind(only_available)
+    .bind(ProductStatus::Available)
+    .fetch_all(pool)
+    .await?;
+    Ok(products)
+}
+
+/// Produkt z pola panelu: adres strony produktu (`.../produkty/{slug}`), sam slug albo ID
+pub async fn find_product_by_reference(
+    pool: &PgPool,
+    reference: &str,
+) -> Result<Option<Product>, AppError> {
+    let reference = reference.trim().trim_end_matches('/');
+    let reference = reference
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default();
+    if reference.is_empty() {
+        return Ok(None);
+    }
+    match Uuid::parse_str(reference) {
+        Ok(id) => repo::products::find_by_id(pool, id).await,
+        Err(_) => repo::products::find_by_slug(pool, reference).await,
+    }
+}
+
+/// Dodaje produkt na koniec karuzeli. Zwraca `false`, gdy produkt już w niej jest
+/// albo karuzela jest pełna.
+pub async fn add_featured_product(pool: &PgPool, product_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+            INSERT INTO homepage_featured_products (product_id, position)
+            SELECT $1, COALESCE(MAX(position), 0) + 1 FROM homepage_featured_products
+            HAVING COUNT(*) < $2
+            ON CONFLICT (product_id) DO NOTHING
+        "#,
+    )
+    .bind(product_id)
+    .bind(MAX_FEATURED_PRODUCTS)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn remove_featured_product(pool: &PgPool, product_id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM homepage_featured_products WHERE product_id = $1")
+        .bind(product_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Zamienia produkt miejscami z sąsiadem w karuzeli. Zwraca `false` na skraju listy.
+pub async fn move_featured_product(
+    pool: &PgPool,
+    product_id: Uuid,
+    direction: MoveDirection,
+) -> Result<bool, AppError> {
+    let mut tx = pool.begin().await?;
+
+    let position: Option<i32> = sqlx::query_scalar(
+        "SELECT position FROM homepage_featured_products WHERE product_id = $1 FOR UPDATE",
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let position = position.ok_or(AppError::NotFound)?;
+
+    let neighbour_sql = match direction {
+        MoveDirection::Up => {
+            "SELECT product_id, position FROM homepage_featured_products WHERE position < $1 ORDER BY position DESC LIMIT 1 FOR UPDATE"
+        }
+        MoveDirection::Down => {
+            "SELECT product_id, position FROM homepage_featured_products WHERE position > $1 ORDER BY position LIMIT 1 FOR UPDATE"
+        }
+    };
+    let neighbour: Option<(Uuid, i32)> = sqlx::query_as(neighbour_sql)
+        .bind(position)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some((neighbour_id, neighbour_position)) = neighbour else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"
+            UPDATE homepage_featured_products
+            SET position = CASE WHEN product_id = $1 THEN $4 ELSE $3 END
+            WHERE product_id IN ($1, $2)
+        "#,
+    )
+    .bind(product_id)
+    .bind(neighbour_id)
+    .bind(position)
+    .bind(neighbour_position)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Po każdej zmianie w panelu - następne wejście na stronę główną wyrenderuje sekcje od nowa
+pub async fn invalidate_homepage_sections(app_state: &AppState) {
+    app_state
+        .listing_fragment_cache
+        .remove(HOMEPAGE_SECTIONS_CACHE_KEY)
+        .await;
+}
@@ -0,0 +1,127 @@
+// src/image_audit.rs
+
+use uuid::Uuid;
+
+use crate::cloudinary::{extract_public_id_from_url, fetch_image_metadata};
+use crate::errors::AppError;
+use crate::models::{Product, ProductStatus};
+use crate::state::AppState;
+
+/// Krótszy bok zdjęcia poniżej tej wartości (px) uznajemy za zbyt mały.
+const MIN_IMAGE_SIDE_PX: u32 = 800;
+/// Pliki mniejsze niż ~30 KB to zwykle mocno skompresowane lub przycięte miniatury.
+const MIN_IMAGE_BYTES: u64 = 30 * 1024;
+/// Ostrość (focus) z analizy jakości Cloudinary poniżej tego progu oznacza rozmyte zdjęcie.
+const MIN_FOCUS_SCORE: f64 = 0.5;
+
+/// Przechodzi przez zdjęcia wszystkich dostępnych produktów i zapisuje wykryte problemy
+/// w `product_image_issues` (poprzednie wyniki są zastępowane).
+///
+/// Sprawdzane są:
+/// 1. Brak drugiego zdjęcia produktu.
+/// 2. Rozdzielczość i rozmiar pliku (metadane z Cloudinary).
+/// 3. Ostrość zdjęcia (analiza jakości Cloudinary, jeśli dostępna).
+///
+/// Zwraca liczbę wykrytych problemów.
+pub async fn run_image_quality_audit(app_state: &AppState) -> Result<usize, AppError> {
+    let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE status = $1")
+        .bind(ProductStatus::Available)
+        .fetch_all(&app_state.db_pool)
+        .await?;
+
+    let mut issues: Vec<(Uuid, Option<String>, String)> = Vec::new();
+
+    for product in &products {
+        if product.images.len() < 2 {
+            issues.push((
+                product.id,
+                None,
+                "Brak drugiego zdjęcia produktu".to_string(),
+            ));
+        }
+
+        for image_url in &product.images {
+            let Some(public_id) =
+                extract_public_id_from_url(image_url, &app_state.cloudinary_config.cloud_name)
+            else {
+                issues.push((
+                    product.id,
+                    Some(image_url.clone()),
+                    "Zdjęcie spoza Cloudinary - nie można sprawdzić jakości".to_string(),
+                ));
+                continue;
+            };
+
+            let metadata =
+                match fetch_image_metadata(&public_id, &app_state.cloudinary_config).await {
+                    Ok(m) => m,
+                    Err(_) => {
+                        issues.push((
+                            product.id,
+                            Some(image_url.clone()),
+                            "Nie udało się pobrać metadanych zdjęcia (może zostało usunięte?)"
+                                .to_string(),
+                        ));
+                        continue;
+                    }
+                };
+
+            let shorter_side = metadata.width.min(metadata.height);
+            if shorter_side < MIN_IMAGE_SIDE_PX {
+                issues.push((
+                    product.id,
+                    Some(image_url.clone()),
+                    format!(
+                        "Za mała rozdzielczość: {}x{} px (min. {} px)",
+                        metadata.width, metadata.height, MIN_IMAGE_SIDE_PX
+                    ),
+                ));
+            }
+            if metadata.bytes < MIN_IMAGE_BYTES {
+                issues.push((
+                    product.id,
+                    Some(image_url.clone()),
+                    format!("Podejrzanie mały plik: {} KB", metadata.bytes / 1024),
+                ));
+            }
+            if let Some(focus) = metadata.quality_analysis.and_then(|q| q.focus)
+                && focus < MIN_FOCUS_SCORE
+            {
+                issues.push((
+                    product.id,
+                    Some(image_url.clone()),
+                    format!("Zdjęcie prawdopodobnie rozmyte (ostrość: {:.2})", focus),
+                ));
+            }
+        }
+    }
+
+    let mut tx = app_state.db_pool.begin().await?;
+    sqlx::query("DELETE FROM product_image_issues")
+        .execute(&mut *tx)
+        .await?;
+    for (product_id, image_url, issue) in &issues {
+        sqlx::query(
+            "INSERT INTO product_image_issues (product_id, image_url, issue) VALUES ($1, $2, $3)",
+        )
+        .bind(product_id)
+        .bind(image_url)
+        .bind(issue)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!(
+        "[Audyt zdjęć] Sprawdzono {} produktów, wykryto {} problemów.",
+        products.len(),
+        issues.len()
+    );
+    Ok(issues.len())
+}
+
+/// Zadanie cykliczne (raz na dobę): audyt zdjęć dostępnych produktów
+pub async fn image_audit_job(state: &AppState) -> Result<String, AppError> {
+    let issues = run_image_quality_audit(state).await?;
+    Ok(format!("Wykryto {} problemów.", issues))
+}
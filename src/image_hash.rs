@@ -0,0 +1,275 @@
+// src/image_hash.rs
+
+// Hash percepcyjny zdjęć (pHash). Podobne zdjęcia - ten sam ciuch sfotografowany drugi raz,
+// przeskalowany czy lekko przycięty - dają hashe różniące się na kilku bitach, więc
+// podobieństwo mierzymy odległością Hamminga.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use image::imageops::FilterType;
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Product, ProductStatus};
+use crate::state::AppState;
+use crate::views::common::transform_cloudinary_url;
+
+/// Zdjęcie zmniejszamy do 32x32 w skali szarości...
+const SAMPLE_SIZE: usize = 32;
+/// ...i z DCT bierzemy 8x8 najniższych częstotliwości, czyli 64 bity hasha
+const HASH_BLOCK_SIZE: usize = 8;
+
+/// Wyszukiwanie po zdjęciu pokazuje też mniej oczywiste dopasowania (inne ujęcie, tło)
+pub const MAX_SIMILAR_IMAGE_DISTANCE: u32 = 14;
+/// Hash liczymy z małej kopii z Cloudinary - nie trzeba pobierać oryginału
+const HASH_SOURCE_TRANSFORMATION: &str = "w_256,c_limit,f_jpg,q_auto";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+const CONCURRENT_DOWNLOADS: usize = 4;
+
+/// Współczynniki cos((2x + 1) * u * PI / 64) dla DCT-II - liczone raz
+static DCT_COSINES: Lazy<Vec<[f64; SAMPLE_SIZE]>> = Lazy::new(|| {
+    (0..HASH_BLOCK_SIZE)
+        .map(|u| {
+            let mut row = [0.0; SAMPLE_SIZE];
+            for (x, value) in row.iter_mut().enumerate() {
+                *value = ((2 * x + 1) as f64 * u as f64 * PI / (2 * SAMPLE_SIZE) as f64).cos();
+            }
+            row
+        })
+        .collect()
+});
+
+/// pHash zdjęcia jako i64 (tak trzymamy go w kolumnie BIGINT). `None`, gdy nie da się
+/// zdekodować pliku (np. HEIC) - brak hasha nie może blokować zapisu produktu.
+/// Dekodowanie dużych zdjęć z telefonu trwa - wołać przez `spawn_blocking`.
+pub fn perceptual_hash(bytes: &[u8]) -> Option<i64> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| tracing::warn!("Nie udało się zdekodować zdjęcia do pHash: {}", e))
+        .ok()?;
+    let pixels: Vec<f64> = image
+        .resize_exact(SAMPLE_SIZE as u32, SAMPLE_SIZE as u32, FilterType::Triangle)
+        .to_luma8()
+        .pixels()
+        .map(|pixel| f64::from(pixel.0[0]))
+        .collect();
+
+    // Współczynniki DCT w kolejności wierszami: (v = 0, u = 0..8), (v = 1, u = 0..8)...
+    let mut coefficients = Vec::with_capacity(HASH_BLOCK_SIZE * HASH_BLOCK_SIZE);
+    for cosines_y in DCT_COSINES.iter() {
+        for cosines_x in DCT_COSINES.iter() {
+            let sum: f64 = pixels
+                .chunks(SAMPLE_SIZE)
+                .zip(cosines_y)
+                .map(|(line, cos_y)| {
+                    line.iter()
+                        .zip(cosines_x)
+                        .map(|(pixel, cos_x)| pixel * cos_x)
+                        .sum::<f64>()
+                        * cos_y
+                })
+                .sum();
+            coefficients.push(sum);
+        }
+    }
+
+    // Składowa stała (średnia jasność) zaburzyłaby medianę - pomijamy ją przy liczeniu progu
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    let hash = coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, coefficient)| **coefficient > median)
+        .fold(0u64, |hash, (bit, _)| hash | (1 << bit));
+    Some(hash as i64)
+}
+
+/// Liczba różniących się bitów: 0 - praktycznie to samo zdjęcie, powyżej ~12 - inne zdjęcia
+pub fn hamming_distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Liczy hashe przesłanych zdjęć (nazwa pliku, treść) poza wątkami obsługi żądań.
+/// Zdjęcia wracają do wywołującego razem z hashami, w tej samej kolejności.
+pub async fn perceptual_hashes(
+    images: Vec<(String, Vec<u8>)>,
+) -> Result<(Vec<(String, Vec<u8>)>, Vec<Option<i64>>), AppError> {
+    tokio::task::spawn_blocking(move || {
+        let hashes = images
+            .iter()
+            .map(|(_, bytes)| perceptual_hash(bytes))
+            .collect();
+        (images, hashes)
+    })
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Błąd liczenia hashy zdjęć: {}", e)))
+}
+
+/// Zapisuje hashe zdjęć produktu: pary (adres zdjęcia, hash); zdjęcia bez hasha są pomijane.
+pub async fn store_image_hashes(
+    pool: &PgPool,
+    product_id: Uuid,
+    hashes: &[(String, Option<i64>)],
+) -> Result<(), AppError> {
+    let (urls, phashes): (Vec<String>, Vec<i64>) = hashes
+        .iter()
+        .filter_map(|(url, hash)| Some((url.clone(), (*hash)?)))
+        .unzip();
+    if urls.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO product_image_hashes (product_id, image_url, phash)
+        SELECT $1, image_url, phash FROM UNNEST($2::text[], $3::bigint[]) AS t(image_url, phash)
+        ON CONFLICT (product_id, image_url) DO UPDATE SET phash = EXCLUDED.phash
+        "#,
+    )
+    .bind(product_id)
+    .bind(&urls)
+    .bind(&phashes)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Produkty ze zdjęciem podobnym do podanego hasha, od najbardziej podobnych.
+/// Odległość produktu to odległość jego najbardziej podobnego zdjęcia.
+pub async fn find_similar_products(
+    pool: &PgPool,
+    hash: i64,
+    statuses: &[ProductStatus],
+    limit: usize,
+) -> Result<Vec<(Product, u32)>, AppError> {
+    let stored: Vec<(Uuid, i64)> = sqlx::query_as(
+        r#"
+        SELECT h.product_id, h.phash
+        FROM product_image_hashes h
+        JOIN products p ON p.id = h.product_id
+        WHERE p.status = ANY($1) AND h.image_url = ANY(p.images)
+        "#,
+    )
+    .bind(statuses)
+    .fetch_all(pool)
+    .await?;
+
+    let mut closest: HashMap<Uuid, u32> = HashMap::new();
+    for (product_id, stored_hash) in stored {
+        let distance = hamming_distance(hash, stored_hash);
+        if distance <= MAX_SIMILAR_IMAGE_DISTANCE {
+            closest
+                .entry(product_id)
+                .and_modify(|current| *current = (*current).min(distance))
+                .or_insert(distance);
+        }
+    }
+    let mut ranked: Vec<(Uuid, u32)> = closest.into_iter().collect();
+    ranked.sort_by_key(|&(_, distance)| distance);
+    ranked.truncate(limit);
+    if ranked.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids: Vec<Uuid> = ranked.iter().map(|&(product_id, _)| product_id).collect();
+    let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ANY($1)")
+        .bind(&ids)
+        .fetch_all(pool)
+        .await?;
+    Ok(ranked
+        .into_iter()
+        .filter_map(|(product_id, distance)| {
+            products
+                .iter()
+                .find(|product| product.id == product_id)
+                .map(|product| (product.clone(), distance))
+        })
+        .collect())
+}
+
+/// Pobiera zmniejszoną kopię zdjęcia z Cloudinary i liczy jej hash
+async fn hash_remote_image(client: &Client, image_url: &str) -> Result<Option<i64>, AppError> {
+    let url = transform_cloudinary_url(image_url, HASH_SOURCE_TRANSFORMATION);
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| AppError::InternalServerError(format!("Błąd pobierania zdjęcia: {}", e)))?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Błąd pobierania zdjęcia: {}", e)))?;
+    tokio::task::spawn_blocking(move || perceptual_hash(&bytes))
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Błąd liczenia hasha zdjęcia: {}", e)))
+}
+
+/// Uzupełnia hashe zdjęć niezarchiwizowanych produktów, które ich jeszcze nie mają
+/// (produkty sprzed wprowadzenia hashy, zdjęcia po "Wyczyść tło"). Zwraca liczbę nowych hashy.
+pub async fn backfill_image_hashes(app_state: &AppState) -> Result<usize, AppError> {
+    let missing: Vec<(Uuid, String)> = sqlx::query_as(
+        r#"
+        SELECT p.id, img.url
+        FROM products p, UNNEST(p.images) AS img(url)
+        WHERE p.status <> $1
+          AND NOT EXISTS (
+              SELECT 1 FROM product_image_hashes h
+              WHERE h.product_id = p.id AND h.image_url = img.url
+          )
+        "#,
+    )
+    .bind(ProductStatus::Archived)
+    .fetch_all(&app_state.db_pool)
+    .await?;
+    if missing.is_empty() {
+        return Ok(0);
+    }
+
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::InternalServerError(format!("Błąd klienta HTTP: {}", e)))?;
+    let hashed: Vec<(Uuid, String, i64)> = stream::iter(missing)
+        .map(|(product_id, image_url)| {
+            let client = &client;
+            async move {
+                match hash_remote_image(client, &image_url).await {
+                    Ok(hash) => hash.map(|hash| (product_id, image_url, hash)),
+                    Err(e) => {
+                        tracing::warn!("[pHash] Pominięto zdjęcie {}: {:?}", image_url, e);
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(CONCURRENT_DOWNLOADS)
+        .filter_map(|hashed| async move { hashed })
+        .collect()
+        .await;
+
+    for (product_id, image_url, hash) in &hashed {
+        store_image_hashes(
+            &app_state.db_pool,
+            *product_id,
+            &[(image_url.clone(), Some(*hash))],
+        )
+        .await?;
+    }
+    Ok(hashed.len())
+}
+
+/// Zadanie cykliczne: uzupełnianie hashy zaraz po starcie (produkty sprzed wprowadzenia hashy),
+/// potem raz na dobę.
+pub async fn backfill_job(state: &AppState) -> Result<String, AppError> {
+    Ok(match backfill_image_hashes(state).await? {
+        0 => String::new(),
+        count => format!("Uzupełniono hashe {} zdjęć.", count),
+    })
+}
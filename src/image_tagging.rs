@@ -0,0 +1,131 @@
+// src/image_tagging.rs
+
+use serde::Serialize;
+
+use crate::models::Category;
+
+/// Podpowiedzi atrybutów produktu wyciągnięte z tagów zdjęcia.
+/// To tylko sugestie - formularz pokazuje je osobno i admin sam decyduje, czy je zastosować.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AttributeSuggestions {
+    /// Wartość `<option>` z selecta kategorii (np. "Sukienki")
+    pub category: Option<String>,
+    pub colours: Vec<String>,
+    pub materials: Vec<String>,
+}
+
+// Tagi z dodatków Cloudinary są po angielsku - mapujemy je na nasze kategorie i polskie nazwy.
+const CATEGORY_KEYWORDS: &[(&str, Category)] = &[
+    ("dress", Category::Sukienki),
+    ("gown", Category::Sukienki),
+    ("skirt", Category::Spodnice),
+    ("jeans", Category::Spodnie),
+    ("trousers", Category::Spodnie),
+    ("pants", Category::Spodnie),
+    ("shorts", Category::Spodnie),
+    ("shirt", Category::Koszule),
+    ("blouse", Category::Koszule),
+    ("sweater", Category::Swetry),
+    ("cardigan", Category::Swetry),
+    ("hoodie", Category::Bluzy),
+    ("sweatshirt", Category::Bluzy),
+    ("coat", Category::KurtkiPlaszcze),
+    ("jacket", Category::KurtkiPlaszcze),
+    ("parka", Category::KurtkiPlaszcze),
+    ("blazer", Category::MarynarkiZakiety),
+    ("suit", Category::MarynarkiZakiety),
+    ("shoe", Category::Obuwie),
+    ("boot", Category::Obuwie),
+    ("sneaker", Category::Obuwie),
+    ("sandal", Category::Obuwie),
+    ("handbag", Category::Torebki),
+    ("bag", Category::Torebki),
+    ("purse", Category::Torebki),
+    ("swimwear", Category::StrojeKapielowe),
+    ("bikini", Category::StrojeKapielowe),
+    ("swimsuit", Category::StrojeKapielowe),
+    ("lingerie", Category::Bielizna),
+    ("underwear", Category::Bielizna),
+    ("bra", Category::Bielizna),
+    ("scarf", Category::Akcesoria),
+    ("belt", Category::Akcesoria),
+    ("hat", Category::Akcesoria),
+    ("jewelry", Category::Akcesoria),
+    ("sunglasses", Category::Akcesoria),
+];
+
+const COLOUR_KEYWORDS: &[(&str, &str)] = &[
+    ("black", "czarny"),
+    ("white", "biały"),
+    ("grey", "szary"),
+    ("gray", "szary"),
+    ("red", "czerwony"),
+    ("pink", "różowy"),
+    ("blue", "niebieski"),
+    ("navy", "granatowy"),
+    ("green", "zielony"),
+    ("yellow", "żółty"),
+    ("orange", "pomarańczowy"),
+    ("purple", "fioletowy"),
+    ("brown", "brązowy"),
+    ("beige", "beżowy"),
+    ("cream", "kremowy"),
+    ("gold", "złoty"),
+    ("silver", "srebrny"),
+];
+
+const MATERIAL_KEYWORDS: &[(&str, &str)] = &[
+    ("denim", "denim"),
+    ("leather", "skóra"),
+    ("suede", "zamsz"),
+    ("wool", "wełna"),
+    ("knit", "dzianina"),
+    ("cotton", "bawełna"),
+    ("linen", "len"),
+    ("silk", "jedwab"),
+    ("satin", "satyna"),
+    ("velvet", "aksamit"),
+    ("corduroy", "sztruks"),
+    ("lace", "koronka"),
+    ("fur", "futro"),
+    ("tweed", "tweed"),
+];
+
+/// Sprawdza, czy tag zawiera słowo kluczowe jako osobne słowo ("red" nie pasuje do "shredded").
+fn tag_contains_word(tag: &str, keyword: &str) -> bool {
+    tag.split(|c: char| !c.is_alphanumeric())
+        .any(|word| word == keyword || word.strip_suffix('s') == Some(keyword))
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|v| v == value) {
+        list.push(value.to_string());
+    }
+}
+
+/// Zamienia tagi zwrócone przez dodatek do tagowania na podpowiedzi atrybutów.
+/// Tagi przychodzą posortowane wg pewności, więc kategoria to pierwsze dopasowanie.
+pub fn suggestions_from_tags(tags: &[String]) -> AttributeSuggestions {
+    let mut suggestions = AttributeSuggestions::default();
+
+    for tag in tags.iter().map(|t| t.to_lowercase()) {
+        if suggestions.category.is_none() {
+            suggestions.category = CATEGORY_KEYWORDS
+                .iter()
+                .find(|(keyword, _)| tag_contains_word(&tag, keyword))
+                .map(|(_, category)| category.as_ref().to_string());
+        }
+        for (keyword, colour) in COLOUR_KEYWORDS {
+            if tag_contains_word(&tag, keyword) {
+                push_unique(&mut suggestions.colours, colour);
+            }
+        }
+        for (keyword, material) in MATERIAL_KEYWORDS {
+            if tag_contains_word(&tag, keyword) {
+                push_unique(&mut suggestions.materials, material);
+            }
+        }
+    }
+
+    suggestions
+}
@@ -0,0 +1,226 @@
+// src/impersonation.rs
+
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    auth_models::{Role, TokenClaims},
+    errors::AppError,
+    permissions::Permission,
+    state::AppState,
+};
+
+/// Ciasteczko z podpisanym tokenem podglądu. Token logowania admina zostaje bez zmian.
+pub const IMPERSONATION_COOKIE: &str = "impersonation";
+/// Jak długo trwa jedna sesja podglądu
+pub const IMPERSONATION_MINUTES: i64 = 30;
+/// Zakończenie podglądu - jedyne żądanie modyfikujące dozwolone w trakcie podglądu
+pub const STOP_IMPERSONATION_PATH: &str = "/api/impersonation/stop";
+
+/// Trasy, które mimo metody GET zmieniają stan konta klienta (szkic zamówienia, rezerwacje,
+/// weryfikacja e-maila, płatność) - w podglądzie są zablokowane.
+const BLOCKED_PATH_PREFIXES: &[&str] = &[
+    "/checkout",
+    "/htmx/checkout",
+    "/api/auth/verify-email",
+    "/zamowienie/",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpersonationClaims {
+    /// ID sesji w `impersonation_sessions`
+    pub sid: Uuid,
+    pub admin_id: Uuid,
+    pub customer_id: Uuid,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+/// Aktywna sesja podglądu, wstawiana do rozszerzeń żądania przez `impersonation_guard`.
+#[derive(Debug, Clone)]
+pub struct ActiveImpersonation(pub ImpersonationClaims);
+
+pub fn create_impersonation_token(
+    session_id: Uuid,
+    admin_id: Uuid,
+    customer_id: Uuid,
+    secret: &str,
+) -> Result<String, AppError> {
+    let now = Utc::now();
+    let claims = ImpersonationClaims {
+        sid: session_id,
+        admin_id,
+        customer_id,
+        exp: (now + Duration::minutes(IMPERSONATION_MINUTES)).timestamp(),
+        iat: now.timestamp(),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .map_err(|e| {
+        AppError::InternalServerError(format!("Błąd podczas tworzenia tokenu podglądu: {}", e))
+    })
+}
+
+/// Odczytuje token podglądu z ciasteczka. `None`, jeśli go nie ma, wygasł lub podpis się nie zgadza.
+pub fn impersonation_from_headers(
+    headers: &HeaderMap,
+    secret: &str,
+) -> Option<ImpersonationClaims> {
+    let jar = CookieJar::from_headers(headers);
+    let token = jar.get(IMPERSONATION_COOKIE)?.value().to_string();
+    decode::<ImpersonationClaims>(
+        &token,
+        &DecodingKey::from_secret(secret.as_ref()),
+        &Validation::default(),
+    )
+    .ok()
+    .map(|data| data.claims)
+}
+
+/// Ciasteczko podglądu; `None` jako token tworzy ciasteczko usuwające.
+pub fn impersonation_cookie(token: Option<String>) -> Cookie<'static> {
+    let max_age = if token.is_some() {
+        time::Duration::minutes(IMPERSONATION_MINUTES)
+    } else {
+        time::Duration::ZERO
+    };
+    Cookie::build((IMPERSONATION_COOKIE, token.unwrap_or_default()))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(max_age)
+        .build()
+}
+
+/// Podmienia dane zalogowanego admina na klienta, jeśli to ten admin rozpoczął aktywny podgląd.
+/// Wywoływane przez ekstraktory `TokenClaims`/`OptionalTokenClaims`.
+pub fn apply_impersonation(claims: TokenClaims, parts: &Parts) -> TokenClaims {
+    match parts.extensions.get::<ActiveImpersonation>() {
+        Some(ActiveImpersonation(session))
+            if claims.role.has_permission(Permission::ManageCustomers)
+                && session.admin_id == claims.sub =>
+        {
+            TokenClaims {
+                sub: session.customer_id,
+                role: Role::Customer,
+                exp: session.exp,
+                iat: session.iat,
+                impersonated_by: Some(claims.sub),
+                two_factor: false,
+            }
+        }
+        _ => claims,
+    }
+}
+
+fn is_read_only_request(method: &Method, path: &str) -> bool {
+    (method == Method::GET || method == Method::HEAD)
+        && !BLOCKED_PATH_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+}
+
+/// Middleware całej aplikacji: rozpoznaje aktywny podgląd, zapisuje każde żądanie w dzienniku
+/// i blokuje wszystko poza odczytem (oraz zakończeniem podglądu i wylogowaniem).
+pub async fn impersonation_guard(
+    State(app_state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(session) = impersonation_from_headers(request.headers(), &app_state.jwt_secret) else {
+        return next.run(request).await;
+    };
+
+    // Sesję mogło zakończyć inne okno przeglądarki - ciasteczko jeszcze wtedy istnieje
+    let is_active = sqlx::query_scalar::<_, bool>(
+        "SELECT ended_at IS NULL AND expires_at > NOW() FROM impersonation_sessions WHERE id = $1",
+    )
+    .bind(session.sid)
+    .fetch_optional(&app_state.db_pool)
+    .await
+    .unwrap_or_else(|e| {
+        tracing::error!(
+            "Nie udało się sprawdzić sesji podglądu {}: {:?}",
+            session.sid,
+            e
+        );
+        None
+    })
+    .unwrap_or(false);
+    if !is_active {
+        return next.run(request).await;
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let allowed = is_read_only_request(&method, &path)
+        || path == STOP_IMPERSONATION_PATH
+        || path == "/api/auth/logout";
+
+    if !path.starts_with("/static") {
+        let pool = app_state.db_pool.clone();
+        let session_id = session.sid;
+        let method_name = method.to_string();
+        let audited_path = path.clone();
+        tokio::spawn(async move {
+            if let Err(e) = sqlx::query(
+                "INSERT INTO impersonation_events (session_id, method, path, blocked) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(session_id)
+            .bind(method_name)
+            .bind(audited_path)
+            .bind(!allowed)
+            .execute(&pool)
+            .await
+            {
+                tracing::error!("Nie udało się zapisać zdarzenia podglądu {}: {:?}", session_id, e);
+            }
+        });
+    }
+
+    if !allowed {
+        tracing::warn!(
+            "Zablokowano {} {} w podglądzie klienta {} (admin {})",
+            method,
+            path,
+            session.customer_id,
+            session.admin_id
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "HX-Trigger",
+            HeaderValue::from_static(
+                r#"{"showMessage": {"message": "Podglad konta klienta jest tylko do odczytu.", "type": "error"}}"#,
+            ),
+        );
+        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+        return (
+            StatusCode::FORBIDDEN,
+            headers,
+            Json(json!({ "error": "Podgląd konta klienta jest tylko do odczytu." })),
+        )
+            .into_response();
+    }
+
+    request
+        .extensions_mut()
+        .insert(ActiveImpersonation(session));
+    next.run(request).await
+}
@@ -0,0 +1,399 @@
+// src/inpost.rs
+
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::errors::AppError;
+use crate::models::Order;
+use crate::state::{InpostConfig, InpostReturnLabelConfig};
+
+/// Paczkomat w formie wyświetlanej w kasie
+#[derive(Debug, Clone, Serialize)]
+pub struct InpostPoint {
+    pub name: String,
+    pub address: String,
+    pub city: String,
+    pub postal_code: String,
+    pub description: Option<String>,
+    /// Odległość w metrach od wskazanego kodu pocztowego (tylko przy wyszukiwaniu najbliższych)
+    pub distance: Option<i64>,
+}
+
+impl InpostPoint {
+    /// Adres punktu w jednej linii, np. "Długa 5, 31-147 Kraków".
+    pub fn full_address(&self) -> String {
+        format!("{}, {} {}", self.address, self.postal_code, self.city)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ShipxPointsResponse {
+    #[serde(default)]
+    items: Vec<ShipxPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShipxPoint {
+    name: String,
+    address_details: Option<ShipxAddressDetails>,
+    location_description: Option<String>,
+    distance: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShipxAddressDetails {
+    city: Option<String>,
+    post_code: Option<String>,
+    street: Option<String>,
+    building_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShipxShipment {
+    id: i64,
+    tracking_number: Option<String>,
+}
+
+impl From<ShipxPoint> for InpostPoint {
+    fn from(point: ShipxPoint) -> Self {
+        let details = point.address_details;
+        let street = details
+            .as_ref()
+            .map(|d| {
+                format!(
+                    "{} {}",
+                    d.street.as_deref().unwrap_or(""),
+                    d.building_number.as_deref().unwrap_or("")
+                )
+                .trim()
+                .to_string()
+            })
+            .unwrap_or_default();
+        InpostPoint {
+            name: point.name,
+            address: street,
+            city: details
+                .as_ref()
+                .and_then(|d| d.city.clone())
+                .unwrap_or_default(),
+            postal_code: details.and_then(|d| d.post_code).unwrap_or_default(),
+            description: point.location_description,
+            distance: point.distance,
+        }
+    }
+}
+
+fn shipx_error(context: &str, e: reqwest::Error) -> AppError {
+    tracing::error!("Błąd sieci InPost ShipX ({}): {}", context, e);
+    AppError::InternalServerError("Błąd połączenia z InPost".to_string())
+}
+
+/// Dodaje token ShipX do żądań wymagających autoryzacji (przesyłki, etykiety).
+fn authorized(request: RequestBuilder, config: &InpostConfig) -> Result<RequestBuilder, AppError> {
+    let token = config.api_token.as_deref().ok_or_else(|| {
+        AppError::InternalServerError("Brak tokenu API InPost (INPOST_API_TOKEN).".to_string())
+    })?;
+    Ok(request.bearer_auth(token))
+}
+
+/// Wyszukuje Paczkomaty po kodzie pocztowym, nazwie Paczkomatu lub mieście.
+pub async fn search_points(
+    config: &InpostConfig,
+    query: &str,
+) -> Result<Vec<InpostPoint>, AppError> {
+    let query = query.trim();
+    let is_postal_code = query.len() == 6
+        && query.chars().enumerate().all(
+            |(i, c)| {
+                if i == 2 { c == '-' } else { c.is_ascii_digit() }
+            },
+        );
+    let is_point_name = query.len() >= 5
+        && query.chars().take(3).all(|c| c.is_ascii_alphabetic())
+        && query.chars().skip(3).any(|c| c.is_ascii_digit());
+
+    let search_param = if is_postal_code {
+        ("relative_post_code", query.to_string())
+    } else if is_point_name {
+        ("name", query.to_uppercase())
+    } else {
+        ("city", query.to_string())
+    };
+
+    let resp = Client::new()
+        .get(format!("{}/v1/points", config.api_base_url))
+        .query(&[
+            search_param,
+            ("type", "parcel_locker".to_string()),
+            ("status", "Operating".to_string()),
+            ("per_page", "10".to_string()),
+        ])
+        .send()
+        .await
+        .map_err(|e| shipx_error("wyszukiwanie punktów", e))?;
+
+    if !resp.status().is_success() {
+        tracing::warn!(
+            "InPost ShipX zwrócił status {} przy wyszukiwaniu '{}'",
+            resp.status(),
+            query
+        );
+        return Err(AppError::InternalServerError(
+            "Nie udało się pobrać listy Paczkomatów.".to_string(),
+        ));
+    }
+
+    let points = resp.json::<ShipxPointsResponse>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji punktów InPost: {}", e);
+        AppError::InternalServerError("Nie udało się pobrać listy Paczkomatów.".to_string())
+    })?;
+    Ok(points.items.into_iter().map(InpostPoint::from).collect())
+}
+
+/// Sprowadza polski kod pocztowy do postaci "NN-NNN" ("31147", " 31-147 " -> "31-147").
+pub fn normalize_postal_code(input: &str) -> Option<String> {
+    let digits: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if digits.len() != 5 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}-{}", &digits[..2], &digits[2..]))
+}
+
+/// Najbliższe działające Paczkomaty dla kodu pocztowego (format "NN-NNN"), posortowane wg odległości.
+pub async fn nearest_points(
+    config: &InpostConfig,
+    postal_code: &str,
+    limit: usize,
+) -> Result<Vec<InpostPoint>, AppError> {
+    let resp = Client::new()
+        .get(format!("{}/v1/points", config.api_base_url))
+        .query(&[
+            ("relative_post_code", postal_code.to_string()),
+            ("sort_by", "distance_to_relative_point".to_string()),
+            ("type", "parcel_locker".to_string()),
+            ("status", "Operating".to_string()),
+            ("per_page", limit.to_string()),
+        ])
+        .send()
+        .await
+        .map_err(|e| shipx_error("najbliższe punkty", e))?;
+
+    if !resp.status().is_success() {
+        tracing::warn!(
+            "InPost ShipX zwrócił status {} przy szukaniu punktów dla kodu {}",
+            resp.status(),
+            postal_code
+        );
+        return Err(AppError::InternalServerError(
+            "Nie udało się pobrać listy Paczkomatów.".to_string(),
+        ));
+    }
+
+    let points = resp.json::<ShipxPointsResponse>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji punktów InPost: {}", e);
+        AppError::InternalServerError("Nie udało się pobrać listy Paczkomatów.".to_string())
+    })?;
+    Ok(points
+        .items
+        .into_iter()
+        .take(limit)
+        .map(InpostPoint::from)
+        .collect())
+}
+
+/// Pobiera Paczkomat po kodzie (np. "KRA010M"). `None`, jeśli taki punkt nie istnieje.
+pub async fn fetch_point(
+    config: &InpostConfig,
+    code: &str,
+) -> Result<Option<InpostPoint>, AppError> {
+    let resp = Client::new()
+        .get(format!("{}/v1/points/{}", config.api_base_url, code))
+        .send()
+        .await
+        .map_err(|e| shipx_error("pobieranie punktu", e))?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(AppError::InternalServerError(format!(
+            "Nie udało się sprawdzić Paczkomatu (status: {})",
+            resp.status()
+        )));
+    }
+    let point = resp.json::<ShipxPoint>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji punktu InPost: {}", e);
+        AppError::InternalServerError("Nie udało się sprawdzić Paczkomatu.".to_string())
+    })?;
+    Ok(Some(point.into()))
+}
+
+/// Tworzy przesyłkę paczkomatową w ShipX i zwraca jej ID.
+pub async fn create_locker_shipment(
+    config: &InpostConfig,
+    order: &Order,
+    recipient_email: &str,
+) -> Result<i64, AppError> {
+    let locker_code = order.inpost_locker_code.as_deref().ok_or_else(|| {
+        AppError::BadRequest("Zamówienie nie ma wybranego Paczkomatu.".to_string())
+    })?;
+    let organization_id = config.organization_id.ok_or_else(|| {
+        AppError::InternalServerError(
+            "Brak ID organizacji InPost (INPOST_ORGANIZATION_ID).".to_string(),
+        )
+    })?;
+
+    let body = json!({
+        "receiver": {
+            "first_name": order.shipping_first_name,
+            "last_name": order.shipping_last_name,
+            "email": recipient_email,
+            "phone": order.shipping_phone,
+        },
+        "parcels": { "template": "small" },
+        "custom_attributes": {
+            "target_point": locker_code,
+            "sending_method": "dispatch_order",
+        },
+        "service": "inpost_locker_standard",
+        "reference": format!("Zamowienie {}", order.order_number),
+    });
+    post_shipment(config, organization_id, &body, order).await
+}
+
+/// Przesyłka zwrotna od klienta do Paczkomatu sklepu. Klient nadaje ją w dowolnym Paczkomacie.
+pub async fn create_return_shipment(
+    config: &InpostConfig,
+    return_config: &InpostReturnLabelConfig,
+    order: &Order,
+    sender_email: &str,
+    reference: &str,
+) -> Result<i64, AppError> {
+    let organization_id = config.organization_id.ok_or_else(|| {
+        AppError::InternalServerError(
+            "Brak ID organizacji InPost (INPOST_ORGANIZATION_ID).".to_string(),
+        )
+    })?;
+
+    let body = json!({
+        "sender": {
+            "first_name": order.shipping_first_name,
+            "last_name": order.shipping_last_name,
+            "email": sender_email,
+            "phone": order.shipping_phone,
+        },
+        "receiver": {
+            "company_name": return_config.receiver_name,
+            "email": return_config.receiver_email,
+            "phone": return_config.receiver_phone,
+        },
+        "parcels": { "template": "small" },
+        "custom_attributes": {
+            "target_point": return_config.target_point,
+            "sending_method": "parcel_locker",
+        },
+        "service": "inpost_locker_standard",
+        "reference": reference,
+    });
+    post_shipment(config, organization_id, &body, order).await
+}
+
+async fn post_shipment(
+    config: &InpostConfig,
+    organization_id: i64,
+    body: &serde_json::Value,
+    order: &Order,
+) -> Result<i64, AppError> {
+    let request = Client::new()
+        .post(format!(
+            "{}/v1/organizations/{}/shipments",
+            config.api_base_url, organization_id
+        ))
+        .json(body);
+    let resp = authorized(request, config)?
+        .send()
+        .await
+        .map_err(|e| shipx_error("tworzenie przesyłki", e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "InPost odrzucił przesyłkę dla zamówienia {}: Status={}, Treść={}",
+            order.id,
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "InPost odrzucił przesyłkę (status: {})",
+            status
+        )));
+    }
+
+    let shipment = resp.json::<ShipxShipment>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji przesyłki InPost: {}", e);
+        AppError::InternalServerError("Nie można przetworzyć odpowiedzi InPost".to_string())
+    })?;
+    Ok(shipment.id)
+}
+
+/// Numer przesyłki do śledzenia - nadawany przez InPost dopiero po przetworzeniu przesyłki.
+pub async fn fetch_tracking_number(
+    config: &InpostConfig,
+    shipment_id: i64,
+) -> Result<Option<String>, AppError> {
+    let request = Client::new().get(format!(
+        "{}/v1/shipments/{}",
+        config.api_base_url, shipment_id
+    ));
+    let resp = authorized(request, config)?
+        .send()
+        .await
+        .map_err(|e| shipx_error("pobieranie przesyłki", e))?;
+    if !resp.status().is_success() {
+        return Ok(None);
+    }
+    Ok(resp
+        .json::<ShipxShipment>()
+        .await
+        .ok()
+        .and_then(|s| s.tracking_number))
+}
+
+/// Pobiera etykietę przesyłki w PDF. Zwraca `None`, jeśli InPost jeszcze jej nie wygenerował.
+pub async fn fetch_label_pdf(
+    config: &InpostConfig,
+    shipment_id: i64,
+) -> Result<Option<Vec<u8>>, AppError> {
+    let request = Client::new()
+        .get(format!(
+            "{}/v1/shipments/{}/label",
+            config.api_base_url, shipment_id
+        ))
+        .query(&[("format", "pdf"), ("type", "normal")]);
+    let resp = authorized(request, config)?
+        .send()
+        .await
+        .map_err(|e| shipx_error("pobieranie etykiety", e))?;
+
+    if !resp.status().is_success() {
+        tracing::info!(
+            "Etykieta przesyłki InPost {} jeszcze niedostępna (status: {})",
+            shipment_id,
+            resp.status()
+        );
+        return Ok(None);
+    }
+    let bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| shipx_error("pobieranie etykiety", e))?;
+    Ok(Some(bytes.to_vec()))
+}
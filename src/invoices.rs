@@ -0,0 +1,357 @@
+// src/invoices.rs
+
+use std::process::Stdio;
+
+use chrono::{Datelike, Utc};
+use maud::{Markup, PreEscaped, html};
+use sqlx::{PgConnection, PgPool};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::date_format::{format_date, to_shop_time};
+use crate::errors::AppError;
+use crate::models::{Invoice, OrderDetailsResponse, OrderStatus};
+use crate::state::{AppState, InvoiceConfig};
+use crate::vat::{
+    OrderItemVat, OrderVatSummary, VatScheme, order_item_vat_lines, order_vat_summary,
+};
+
+/// Oznaczenie wymagane na fakturze przy sprzedaży towarów używanych w procedurze marży
+/// (art. 106e ust. 1 pkt 20 ustawy o VAT). Na takiej fakturze nie wykazuje się kwoty podatku.
+const MARGIN_SCHEME_ANNOTATION: &str = "procedura marży - towary używane";
+
+fn format_price(price: i64) -> String {
+    format!("{:.2}", (price as f64) / 100.0).replace('.', ",") + " zł"
+}
+
+/// Nazwa pliku PDF, np. "faktura-FM-2025-0007.pdf"
+pub fn invoice_filename(invoice: &Invoice) -> String {
+    format!("faktura-{}.pdf", invoice.number.replace('/', "-"))
+}
+
+/// Czy zamówienie w danym statusie jest opłacone (lub realizowane) i należy mu się faktura.
+pub fn status_requires_invoice(status: &OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::Processing | OrderStatus::Shipped | OrderStatus::Delivered
+    )
+}
+
+/// Wystawia fakturę zamówienia z kolejnym numerem w bieżącym roku (albo zwraca już wystawioną).
+/// Wywoływana w transakcji zmiany statusu, przy zablokowanym wierszu zamówienia, więc
+/// równoległe potwierdzenia płatności nie wystawią dwóch faktur ani nie zgubią numeru.
+pub async fn issue_invoice(conn: &mut PgConnection, order_id: Uuid) -> Result<Invoice, AppError> {
+    let existing = sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE order_id = $1")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if let Some(invoice) = existing {
+        return Ok(invoice);
+    }
+
+    let year = to_shop_time(&Utc::now()).year();
+    let sequence: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO invoice_counters (year, last_sequence) VALUES ($1, 1)
+        ON CONFLICT (year) DO UPDATE SET last_sequence = invoice_counters.last_sequence + 1
+        RETURNING last_sequence
+        "#,
+    )
+    .bind(year)
+    .fetch_one(&mut *conn)
+    .await?;
+    let number = format!("FM/{}/{:04}", year, sequence);
+
+    let invoice = sqlx::query_as::<_, Invoice>(
+        r#"
+        INSERT INTO invoices (order_id, year, sequence, number)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(order_id)
+    .bind(year)
+    .bind(sequence)
+    .bind(&number)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    tracing::info!("Wystawiono fakturę {} do zamówienia {}", number, order_id);
+    Ok(invoice)
+}
+
+pub async fn find_invoice_for_order(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Option<Invoice>, AppError> {
+    Ok(
+        sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE order_id = $1")
+            .bind(order_id)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+fn format_vat_rate(rate_bp: i32) -> String {
+    let fraction = format!("{:02}", rate_bp % 100);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        format!("{}%", rate_bp / 100)
+    } else {
+        format!("{},{}%", rate_bp / 100, fraction)
+    }
+}
+
+/// Dokument HTML faktury (wejście dla `wkhtmltopdf`) z rozbiciem VAT zapisanym przy zamówieniu.
+/// W procedurze marży ceny są cenami brutto, bez wyodrębnionego VAT - tak wymaga przepis;
+/// przy zwykłym VAT każda pozycja (już po rabacie) ma netto, stawkę i kwotę podatku.
+pub fn render_invoice_html(
+    invoice: &Invoice,
+    order_details: &OrderDetailsResponse,
+    vat_summary: &OrderVatSummary,
+    item_vat: &[OrderItemVat],
+    config: &InvoiceConfig,
+) -> Markup {
+    let order = &order_details.order;
+    let shipping_cost = vat_summary.shipping_cost;
+    let margin_scheme = vat_summary.vat_scheme == VatScheme::Margin;
+    let vat_for_item = |order_item_id: Uuid| {
+        item_vat
+            .iter()
+            .find(|line| line.order_item_id == order_item_id)
+    };
+    let payment_method_label = order
+        .payment_method
+        .as_ref()
+        .map(|method| method.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    html! {
+        (PreEscaped("<!DOCTYPE html>"))
+        html lang="pl" {
+            head {
+                meta charset="UTF-8";
+                title { "Faktura " (invoice.number) }
+                style {
+                    (PreEscaped(r#"
+                        body { font-family: "DejaVu Sans", Arial, sans-serif; font-size: 11px; color: #222; margin: 24px; }
+                        h1 { font-size: 20px; margin: 0 0 4px 0; }
+                        .meta { margin-bottom: 20px; }
+                        .parties { width: 100%; margin-bottom: 20px; }
+                        .parties td { width: 50%; vertical-align: top; padding-right: 16px; }
+                        .label { font-weight: bold; text-transform: uppercase; font-size: 9px; color: #666; }
+                        table.items { width: 100%; border-collapse: collapse; }
+                        table.items th, table.items td { border: 1px solid #ccc; padding: 6px; text-align: left; }
+                        table.items th { background: #f3f3f3; }
+                        .num { text-align: right !important; white-space: nowrap; }
+                        .total { font-size: 14px; font-weight: bold; text-align: right; margin-top: 12px; }
+                        .annotation { margin-top: 20px; padding: 8px; border: 1px solid #222; font-weight: bold; }
+                    "#))
+                }
+            }
+            body {
+                h1 {
+                    @if margin_scheme { "Faktura VAT marża nr " } @else { "Faktura VAT nr " }
+                    (invoice.number)
+                }
+                div class="meta" {
+                    "Data wystawienia: " (format_date(&invoice.issued_at)) br;
+                    "Data sprzedaży: " (format_date(&order.order_date)) br;
+                    "Zamówienie: " (order.payment_reference())
+                }
+                table class="parties" {
+                    tr {
+                        td {
+                            div class="label" { "Sprzedawca" }
+                            strong { (config.seller_name) } br;
+                            @if !config.seller_address.is_empty() { (config.seller_address) br; }
+                            @if let Some(tax_id) = &config.seller_tax_id { "NIP: " (tax_id) }
+                        }
+                        td {
+                            div class="label" { "Nabywca" }
+                            strong { (order.shipping_first_name) " " (order.shipping_last_name) } br;
+                            (order.shipping_address_line1) br;
+                            @if let Some(line2) = &order.shipping_address_line2 { (line2) br; }
+                            (order.shipping_postal_code) " " (order.shipping_city) br;
+                            (order.shipping_country)
+                        }
+                    }
+                }
+                @if margin_scheme {
+                    table class="items" {
+                        thead {
+                            tr {
+                                th { "Lp." }
+                                th { "Nazwa" }
+                                th class="num" { "Ilość" }
+                                th class="num" { "Cena brutto" }
+                                th class="num" { "Wartość brutto" }
+                            }
+                        }
+                        tbody {
+                            @for (index, item) in order_details.items.iter().enumerate() {
+                                tr {
+                                    td { (index + 1) }
+                                    td { (item.product.name) }
+                                    td class="num" { "1 szt." }
+                                    td class="num" { (format_price(item.price_at_purchase)) }
+                                    td class="num" { (format_price(item.price_at_purchase)) }
+                                }
+                            }
+                            @if order.discount_amount > 0 {
+                                tr {
+                                    td { (order_details.items.len() + 1) }
+                                    td { "Rabat" }
+                                    td class="num" { "1" }
+                                    td class="num" { "-" (format_price(order.discount_amount)) }
+                                    td class="num" { "-" (format_price(order.discount_amount)) }
+                                }
+                            }
+                            @if shipping_cost > 0 {
+                                tr {
+                                    td { (order_details.items.len() + 1 + usize::from(order.discount_amount > 0)) }
+                                    td { "Dostawa" @if let Some(name) = &order.shipping_method_name { " - " (name) } }
+                                    td class="num" { "1 usł." }
+                                    td class="num" { (format_price(shipping_cost)) }
+                                    td class="num" { (format_price(shipping_cost)) }
+                                }
+                            }
+                        }
+                    }
+                } @else {
+                    table class="items" {
+                        thead {
+                            tr {
+                                th { "Lp." }
+                                th { "Nazwa" }
+                                th class="num" { "Ilość" }
+                                th class="num" { "Stawka VAT" }
+                                th class="num" { "Wartość netto" }
+                                th class="num" { "Kwota VAT" }
+                                th class="num" { "Wartość brutto" }
+                            }
+                        }
+                        tbody {
+                            @for (index, item) in order_details.items.iter().enumerate() {
+                                @let line = vat_for_item(item.order_item_id);
+                                tr {
+                                    td { (index + 1) }
+                                    td {
+                                        (item.product.name)
+                                        @if line.is_some_and(|line| line.discount_share > 0) { " (po rabacie)" }
+                                    }
+                                    td class="num" { "1 szt." }
+                                    @if let Some(line) = line {
+                                        td class="num" { (format_vat_rate(line.vat_rate_bp)) }
+                                        td class="num" { (format_price(line.net_amount)) }
+                                        td class="num" { (format_price(line.vat_amount)) }
+                                        td class="num" { (format_price(line.gross_amount())) }
+                                    } @else {
+                                        td class="num" { "-" }
+                                        td class="num" { "-" }
+                                        td class="num" { "-" }
+                                        td class="num" { (format_price(item.price_at_purchase)) }
+                                    }
+                                }
+                            }
+                            @if shipping_cost > 0 {
+                                @let shipping = vat_summary.shipping();
+                                tr {
+                                    td { (order_details.items.len() + 1) }
+                                    td { "Dostawa" @if let Some(name) = &order.shipping_method_name { " - " (name) } }
+                                    td class="num" { "1 usł." }
+                                    td class="num" { (format_vat_rate(vat_summary.vat_rate_bp)) }
+                                    td class="num" { (format_price(shipping.net)) }
+                                    td class="num" { (format_price(shipping.vat)) }
+                                    td class="num" { (format_price(shipping_cost)) }
+                                }
+                            }
+                        }
+                    }
+                    p class="total" {
+                        "Razem netto: " (format_price(vat_summary.net_total))
+                        " · VAT: " (format_price(vat_summary.vat_total))
+                    }
+                }
+                @if order.gift_card_amount > 0 {
+                    p { "Zapłacono kartą podarunkową: " (format_price(order.gift_card_amount)) }
+                }
+                p class="total" { "Razem do zapłaty: " (format_price(order.total_price)) }
+                p { "Sposób płatności: " (payment_method_label) }
+                @if margin_scheme {
+                    div class="annotation" { (MARGIN_SCHEME_ANNOTATION) }
+                }
+            }
+        }
+    }
+}
+
+/// Konwertuje HTML do PDF przez `wkhtmltopdf` (HTML na stdin, PDF ze stdout).
+async fn html_to_pdf(config: &InvoiceConfig, html: String) -> Result<Vec<u8>, AppError> {
+    let mut child = Command::new(&config.pdf_command)
+        .args([
+            "--quiet",
+            "--encoding",
+            "utf-8",
+            "--page-size",
+            "A4",
+            "-",
+            "-",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            AppError::InternalServerError(format!(
+                "Nie udało się uruchomić {}: {}",
+                config.pdf_command, e
+            ))
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(html.as_bytes()).await.map_err(|e| {
+            AppError::InternalServerError(format!("Błąd przekazywania HTML do PDF: {}", e))
+        })?;
+    }
+    let output = child.wait_with_output().await.map_err(|e| {
+        AppError::InternalServerError(format!("Błąd generowania PDF faktury: {}", e))
+    })?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::InternalServerError(format!(
+            "{} zakończył się błędem ({}): {}",
+            config.pdf_command,
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+/// Wystawiona faktura zamówienia razem z plikiem PDF; `None`, dopóki zamówienie nie jest opłacone.
+pub async fn invoice_pdf_for_order(
+    app_state: &AppState,
+    order_details: &OrderDetailsResponse,
+) -> Result<Option<(Invoice, Vec<u8>)>, AppError> {
+    let Some(invoice) = find_invoice_for_order(&app_state.db_pool, order_details.order.id).await?
+    else {
+        return Ok(None);
+    };
+    let mut conn = app_state.db_pool.acquire().await?;
+    let vat_summary = order_vat_summary(&mut conn, order_details.order.id).await?;
+    let item_vat = order_item_vat_lines(&mut conn, order_details.order.id).await?;
+    drop(conn);
+    let html = render_invoice_html(
+        &invoice,
+        order_details,
+        &vat_summary,
+        &item_vat,
+        &app_state.invoice_config,
+    )
+    .into_string();
+    let pdf = html_to_pdf(&app_state.invoice_config, html).await?;
+    Ok(Some((invoice, pdf)))
+}
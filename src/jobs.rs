@@ -0,0 +1,301 @@
+// src/jobs.rs
+
+// Zadania cykliczne w tle zebrane w jednym rejestrze (`JOBS`), uruchamiane z `main.rs`.
+// Każdy przebieg działa we własnym spanie `job` (nazwa zadania trafia do każdego logu),
+// start jest przesunięty o losowe opóźnienie, żeby zadania o tym samym interwale nie ruszały
+// naraz, a wynik ostatniego przebiegu zapisujemy w `jobs_log` - widać go w panelu admina.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio::time::MissedTickBehavior;
+use tracing::Instrument;
+
+use crate::errors::AppError;
+use crate::state::AppState;
+use crate::{
+    backup, disposable_email, image_audit, image_hash, link_checker, product_views, reservations,
+    retention, rum, sales, sla,
+};
+
+/// Wynik przebiegu: krótki opis do `jobs_log` (pusty, gdy nie było nic do zrobienia)
+pub type JobResult = Result<String, AppError>;
+type JobFuture = Pin<Box<dyn Future<Output = JobResult> + Send>>;
+
+/// Maksymalne losowe opóźnienie przebiegu jako ułamek interwału...
+const JITTER_FRACTION: f64 = 0.1;
+/// ...ale nie więcej niż 5 minut, żeby zadania dobowe nie rozjeżdżały się o godziny
+const MAX_JITTER: Duration = Duration::from_secs(5 * 60);
+
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+pub struct PeriodicJob {
+    /// Klucz w `jobs_log` i w logach
+    pub name: &'static str,
+    /// Nazwa wyświetlana w panelu
+    pub label: &'static str,
+    pub interval: Duration,
+    /// Czy pierwszy przebieg ma ruszyć zaraz po starcie serwera (np. zaległe porządki),
+    /// czy dopiero po pierwszym interwale (ciężkie zadania, które nie powinny obciążać startu)
+    pub run_on_start: bool,
+    /// Zadania wymagające konfiguracji są pomijane, gdy jej brakuje
+    pub enabled: fn(&AppState) -> bool,
+    run: fn(Arc<AppState>) -> JobFuture,
+}
+
+impl PeriodicJob {
+    /// Interwał do panelu, np. "co 1 h"
+    pub fn interval_label(&self) -> String {
+        let secs = self.interval.as_secs();
+        if secs >= DAY.as_secs() && secs.is_multiple_of(DAY.as_secs()) {
+            format!("co {} d", secs / DAY.as_secs())
+        } else if secs >= HOUR.as_secs() && secs.is_multiple_of(HOUR.as_secs()) {
+            format!("co {} h", secs / HOUR.as_secs())
+        } else if secs >= 60 && secs.is_multiple_of(60) {
+            format!("co {} min", secs / 60)
+        } else {
+            format!("co {} s", secs)
+        }
+    }
+}
+
+fn always_enabled(_: &AppState) -> bool {
+    true
+}
+
+pub static JOBS: &[PeriodicJob] = &[
+    PeriodicJob {
+        name: "release_reservations",
+        label: "Zwalnianie wygasłych rezerwacji",
+        interval: Duration::from_secs(60),
+        run_on_start: true,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { reservations::release_expired_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "sale_boundaries",
+        label: "Start i koniec zaplanowanych promocji",
+        interval: Duration::from_secs(sales::SALE_BOUNDARY_CHECK_SECS),
+        run_on_start: true,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { sales::sale_boundaries_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "sla_reminders",
+        label: "Przypomnienia o terminach reklamacji i zwrotów",
+        interval: HOUR,
+        run_on_start: true,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { sla::sla_reminders_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "disposable_email_refresh",
+        label: "Odświeżanie listy jednorazowych domen e-mail",
+        interval: DAY,
+        run_on_start: true,
+        enabled: |state| state.disposable_email_blocklist.remote_list_url().is_some(),
+        run: |state| Box::pin(async move { disposable_email::refresh_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "image_hash_backfill",
+        label: "Uzupełnianie hashy zdjęć",
+        interval: DAY,
+        run_on_start: true,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { image_hash::backfill_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "image_audit",
+        label: "Audyt jakości zdjęć",
+        interval: DAY,
+        run_on_start: false,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { image_audit::image_audit_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "link_check",
+        label: "Sprawdzanie linków i zdjęć",
+        interval: DAY,
+        run_on_start: false,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { link_checker::link_check_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "database_backup",
+        label: "Kopia zapasowa bazy",
+        interval: DAY,
+        run_on_start: false,
+        enabled: |state| state.backup_config.is_some(),
+        run: |state| Box::pin(async move { backup::backup_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "data_retention",
+        label: "Retencja danych osobowych",
+        interval: DAY,
+        run_on_start: false,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { retention::retention_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "rum_purge",
+        label: "Usuwanie starych pomiarów wydajności",
+        interval: DAY,
+        run_on_start: false,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { rum::purge_rum_samples_job(&state).await }),
+    },
+    PeriodicJob {
+        name: "product_views_purge",
+        label: "Usuwanie starych wyświetleń produktów",
+        interval: DAY,
+        run_on_start: false,
+        enabled: always_enabled,
+        run: |state| Box::pin(async move { product_views::purge_product_views_job(&state).await }),
+    },
+];
+
+pub fn find_job(name: &str) -> Option<&'static PeriodicJob> {
+    JOBS.iter().find(|job| job.name == name)
+}
+
+fn jitter(interval: Duration) -> Duration {
+    let max = interval.mul_f64(JITTER_FRACTION).min(MAX_JITTER);
+    let max_ms = u64::try_from(max.as_millis()).unwrap_or(u64::MAX);
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::rng().random_range(0..=max_ms))
+}
+
+async fn record_start(pool: &PgPool, job: &PeriodicJob) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO jobs_log (job_name, last_started_at, last_outcome)
+        VALUES ($1, NOW(), 'running')
+        ON CONFLICT (job_name) DO UPDATE
+        SET last_started_at = NOW(), last_finished_at = NULL, last_duration_ms = NULL,
+            last_outcome = 'running', last_message = NULL
+        "#,
+    )
+    .bind(job.name)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn record_finish(
+    pool: &PgPool,
+    job: &PeriodicJob,
+    duration: Duration,
+    result: &JobResult,
+) -> Result<(), AppError> {
+    let (succeeded, message) = match result {
+        Ok(summary) => (true, summary.clone()),
+        Err(e) => (false, e.to_string()),
+    };
+    sqlx::query(
+        r#"
+        UPDATE jobs_log
+        SET last_finished_at = NOW(),
+            last_duration_ms = $2,
+            last_outcome = CASE WHEN $3 THEN 'success' ELSE 'failure' END,
+            last_message = NULLIF($4, ''),
+            last_success_at = CASE WHEN $3 THEN NOW() ELSE last_success_at END,
+            run_count = run_count + 1,
+            failure_count = failure_count + CASE WHEN $3 THEN 0 ELSE 1 END
+        WHERE job_name = $1
+        "#,
+    )
+    .bind(job.name)
+    .bind(i64::try_from(duration.as_millis()).unwrap_or(i64::MAX))
+    .bind(succeeded)
+    .bind(message)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Jeden przebieg zadania: span z nazwą, pomiar czasu i zapis wyniku w `jobs_log`.
+/// Błąd zapisu do dziennika nie przerywa samego zadania.
+pub async fn run_job(state: Arc<AppState>, job: &'static PeriodicJob) -> JobResult {
+    let span = tracing::info_span!("job", name = job.name);
+    async move {
+        if let Err(e) = record_start(&state.db_pool, job).await {
+            tracing::warn!("Nie udało się zapisać startu zadania w jobs_log: {:?}", e);
+        }
+        let started = Instant::now();
+        let result = (job.run)(state.clone()).await;
+        let duration = started.elapsed();
+        match &result {
+            Ok(summary) if summary.is_empty() => {
+                tracing::debug!("Zakończono w {} ms.", duration.as_millis())
+            }
+            Ok(summary) => tracing::info!("{} ({} ms)", summary, duration.as_millis()),
+            Err(e) => tracing::error!("Zadanie „{}” zakończyło się błędem: {:?}", job.label, e),
+        }
+        if let Err(e) = record_finish(&state.db_pool, job, duration, &result).await {
+            tracing::warn!("Nie udało się zapisać wyniku zadania w jobs_log: {:?}", e);
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+fn spawn_job(state: Arc<AppState>, job: &'static PeriodicJob) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(job.interval);
+        // Po długim przebiegu nie nadrabiamy zaległych ticków seriami
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        if !job.run_on_start {
+            // Pierwszy tick jest natychmiastowy - pomijamy go, żeby nie obciążać startu serwera.
+            interval.tick().await;
+        }
+        loop {
+            interval.tick().await;
+            tokio::time::sleep(jitter(job.interval)).await;
+            let _ = run_job(state.clone(), job).await;
+        }
+    });
+}
+
+/// Uruchamia wszystkie włączone zadania z rejestru
+pub fn spawn_periodic_jobs(state: Arc<AppState>) {
+    for job in JOBS {
+        if (job.enabled)(&state) {
+            spawn_job(state.clone(), job);
+        } else {
+            tracing::info!(
+                "[Zadania] Pominięto „{}” - brak wymaganej konfiguracji.",
+                job.label
+            );
+        }
+    }
+}
+
+/// Ostatni przebieg zadania z `jobs_log`
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct JobLogEntry {
+    pub job_name: String,
+    pub last_started_at: DateTime<Utc>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<i64>,
+    pub last_outcome: String,
+    pub last_message: Option<String>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub run_count: i64,
+    pub failure_count: i64,
+}
+
+pub async fn job_log_entries(pool: &PgPool) -> Result<Vec<JobLogEntry>, AppError> {
+    Ok(sqlx::query_as::<_, JobLogEntry>("SELECT * FROM jobs_log")
+        .fetch_all(pool)
+        .await?)
+}
@@ -0,0 +1,157 @@
+// src/link_checker.rs
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, StatusCode};
+
+use crate::errors::AppError;
+use crate::models::{AdminNotificationLevel, Product, ProductStatus};
+use crate::notifications::notify_admin;
+use crate::state::AppState;
+use crate::views::static_pages::{BUILT_IN_PAGES, static_page_content};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Ile adresów sprawdzamy równolegle - Cloudinary i własny serwer nie muszą dostać wszystkiego naraz
+const CONCURRENT_CHECKS: usize = 8;
+/// Ile problemów wypisujemy w treści powiadomienia (reszta tylko w logach)
+const MAX_LISTED_PROBLEMS: usize = 20;
+
+/// Niedziałający adres: gdzie go znaleziono, sam adres i powód
+#[derive(Debug)]
+pub struct LinkProblem {
+    pub source: String,
+    pub url: String,
+    pub reason: String,
+}
+
+/// Linki wewnętrzne (`href="/..."`) z kodu HTML, bez kotwic i parametrów zapytania.
+fn internal_links(html: &str) -> BTreeSet<String> {
+    html.split("href=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .filter(|href| href.starts_with('/') && !href.starts_with("//"))
+        .map(|href| href.split(['#', '?']).next().unwrap_or(href).to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Zwraca powód, jeśli adres nie działa. HEAD zamiast GET, żeby nie pobierać całych zdjęć;
+/// serwery bez obsługi HEAD (405) sprawdzamy zwykłym GET.
+async fn check_url(client: &Client, url: &str) -> Option<String> {
+    let response = match client.head(url).send().await {
+        Ok(response) if response.status() == StatusCode::METHOD_NOT_ALLOWED => {
+            client.get(url).send().await
+        }
+        other => other,
+    };
+    match response {
+        Ok(response) if response.status().is_success() => None,
+        Ok(response) => Some(format!("HTTP {}", response.status().as_u16())),
+        Err(e) if e.is_timeout() => Some("Przekroczony czas odpowiedzi".to_string()),
+        Err(e) => Some(format!("Błąd połączenia: {}", e)),
+    }
+}
+
+/// Sprawdza zdjęcia wszystkich niezarchiwizowanych produktów i linki wewnętrzne
+/// w stronach statycznych. Zwraca listę wykrytych problemów.
+pub async fn run_link_check(app_state: &AppState) -> Result<Vec<LinkProblem>, AppError> {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::limited(5))
+        .build()
+        .map_err(|e| AppError::InternalServerError(format!("Błąd klienta HTTP: {}", e)))?;
+
+    let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE status <> $1")
+        .bind(ProductStatus::Archived)
+        .fetch_all(&app_state.db_pool)
+        .await?;
+
+    // (źródło, adres) - zdjęcia produktów i linki stron statycznych sprawdzamy jedną kolejką
+    let mut targets: Vec<(String, String)> = products
+        .iter()
+        .flat_map(|product| {
+            product.images.iter().map(move |image_url| {
+                (
+                    format!("Produkt \"{}\" ({})", product.name, product.id),
+                    image_url.clone(),
+                )
+            })
+        })
+        .collect();
+    let base_url = app_state.public_base_url.trim_end_matches('/');
+    // Strony statyczne w wersji, którą widzą klienci (także treści z panelu)
+    for page in &BUILT_IN_PAGES {
+        let content = static_page_content(app_state, page).await?;
+        for path in internal_links(&content.into_string()) {
+            targets.push((
+                format!("Strona \"{}\"", page.name),
+                format!("{}{}", base_url, path),
+            ));
+        }
+    }
+    let checked = targets.len();
+
+    let problems: Vec<LinkProblem> = stream::iter(targets)
+        .map(|(source, url)| {
+            let client = &client;
+            async move {
+                check_url(client, &url).await.map(|reason| LinkProblem {
+                    source,
+                    url,
+                    reason,
+                })
+            }
+        })
+        .buffer_unordered(CONCURRENT_CHECKS)
+        .filter_map(|problem| async move { problem })
+        .collect()
+        .await;
+
+    tracing::info!(
+        "[Linki] Sprawdzono {} adresów, wykryto {} problemów.",
+        checked,
+        problems.len()
+    );
+    Ok(problems)
+}
+
+fn problems_notification_message(problems: &[LinkProblem]) -> String {
+    let mut lines: Vec<String> = problems
+        .iter()
+        .take(MAX_LISTED_PROBLEMS)
+        .map(|p| format!("{}: {} ({})", p.source, p.url, p.reason))
+        .collect();
+    if problems.len() > MAX_LISTED_PROBLEMS {
+        lines.push(format!(
+            "...i {} więcej - pełna lista w logach serwera.",
+            problems.len() - MAX_LISTED_PROBLEMS
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Zadanie cykliczne (raz na dobę): sprawdzenie linków, problemy trafiają do centrum powiadomień
+pub async fn link_check_job(state: &AppState) -> Result<String, AppError> {
+    let problems = run_link_check(state).await?;
+    if problems.is_empty() {
+        return Ok(String::new());
+    }
+    for problem in &problems {
+        tracing::warn!(
+            "[Linki] {}: {} ({})",
+            problem.source,
+            problem.url,
+            problem.reason
+        );
+    }
+    notify_admin(
+        &state.db_pool,
+        AdminNotificationLevel::Warning,
+        &format!("Niedziałające zdjęcia lub linki: {}", problems.len()),
+        &problems_notification_message(&problems),
+    )
+    .await;
+    Ok(format!("Wykryto {} problemów.", problems.len()))
+}
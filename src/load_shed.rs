@@ -0,0 +1,241 @@
+// src/load_shed.rs
+
+// Ochrona przed przeciążeniem: każda klasa ruchu (strony publiczne, koszyk i zamówienia, panel
+// admina) ma własny limit jednocześnie obsługiwanych żądań. Gdy limit jest wyczerpany, żądanie
+// czeka krótko w kolejce, a potem dostaje lekką, statyczną stronę 503 "sklep jest przeciążony"
+// (bez bazy i szablonów). Dzięki osobnym budżetom zalew wejść z Instagrama na listingi nie
+// blokuje finalizacji zamówień ani panelu.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use strum_macros::{AsRefStr, EnumIter};
+use tokio::sync::Semaphore;
+
+use crate::state::AppState;
+
+/// Po ilu sekundach klient może spróbować ponownie
+const RETRY_AFTER_SECS: u64 = 5;
+
+/// Ścieżki poza limitem: pliki statyczne są tanie, a metryki są potrzebne właśnie przy przeciążeniu
+const UNLIMITED_PATH_PREFIXES: [&str; 2] = ["/static/", "/internal/metrics"];
+
+const ADMIN_PATH_PREFIXES: [&str; 3] = ["/admin", "/htmx/admin", "/api/admin"];
+
+const CHECKOUT_PATH_PREFIXES: [&str; 9] = [
+    "/checkout",
+    "/htmx/checkout",
+    "/api/orders",
+    "/api/cart",
+    "/api/guest-cart",
+    "/htmx/cart",
+    "/api/payments",
+    "/zamowienie/",
+    "/htmx/zamowienie/",
+];
+
+/// Statyczna strona dla odrzuconych żądań - celowo bez zależności od bazy, cache'y i szablonów
+const OVERLOADED_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="pl">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta http-equiv="refresh" content="10">
+<title>Sklep jest przeciążony - mess - all that vintage</title>
+<style>
+body{margin:0;min-height:100vh;display:flex;align-items:center;justify-content:center;font-family:system-ui,sans-serif;background:#fdf2f8;color:#1f2937;text-align:center;padding:1rem}
+h1{font-size:1.75rem;margin:0 0 .75rem}
+p{margin:.25rem 0;color:#4b5563}
+a{display:inline-block;margin-top:1.5rem;padding:.75rem 1.5rem;background:#db2777;color:#fff;border-radius:.5rem;text-decoration:none;font-weight:600}
+</style>
+</head>
+<body>
+<main>
+<h1>Sklep jest przeciążony</h1>
+<p>Odwiedza nas teraz wyjątkowo dużo osób.</p>
+<p>Strona odświeży się sama za kilka sekund - Twój koszyk na Ciebie poczeka.</p>
+<a href="">Spróbuj ponownie</a>
+</main>
+</body>
+</html>
+"#;
+
+/// Klasa ruchu z osobnym budżetem jednoczesnych żądań
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum TrafficClass {
+    Public,
+    Checkout,
+    Admin,
+}
+
+impl TrafficClass {
+    /// Klasa żądania po ścieżce; `None` dla ścieżek poza limitem
+    fn for_path(path: &str) -> Option<Self> {
+        if UNLIMITED_PATH_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+        {
+            None
+        } else if ADMIN_PATH_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+        {
+            Some(TrafficClass::Admin)
+        } else if CHECKOUT_PATH_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+        {
+            Some(TrafficClass::Checkout)
+        } else {
+            Some(TrafficClass::Public)
+        }
+    }
+}
+
+/// Limity z konfiguracji (`MAX_CONCURRENT_*_REQUESTS`, `LOAD_SHED_QUEUE_MS`)
+#[derive(Debug, Clone)]
+pub struct LoadShedConfig {
+    pub public_limit: usize,
+    pub checkout_limit: usize,
+    pub admin_limit: usize,
+    /// Jak długo żądanie może czekać na wolne miejsce, zanim dostanie 503
+    pub queue_timeout: Duration,
+}
+
+struct ClassBudget {
+    limit: usize,
+    permits: Arc<Semaphore>,
+    shed: AtomicU64,
+}
+
+impl ClassBudget {
+    fn new(limit: usize) -> Self {
+        ClassBudget {
+            limit,
+            permits: Arc::new(Semaphore::new(limit)),
+            shed: AtomicU64::new(0),
+        }
+    }
+}
+
+pub struct LoadShedder {
+    public: ClassBudget,
+    checkout: ClassBudget,
+    admin: ClassBudget,
+    queue_timeout: Duration,
+}
+
+impl LoadShedder {
+    pub fn new(config: &LoadShedConfig) -> Self {
+        LoadShedder {
+            public: ClassBudget::new(config.public_limit),
+            checkout: ClassBudget::new(config.checkout_limit),
+            admin: ClassBudget::new(config.admin_limit),
+            queue_timeout: config.queue_timeout,
+        }
+    }
+
+    fn budget(&self, class: TrafficClass) -> &ClassBudget {
+        match class {
+            TrafficClass::Public => &self.public,
+            TrafficClass::Checkout => &self.checkout,
+            TrafficClass::Admin => &self.admin,
+        }
+    }
+
+    /// Żądania obsługiwane teraz w danej klasie - do `/internal/metrics`
+    pub fn in_flight(&self, class: TrafficClass) -> u64 {
+        let budget = self.budget(class);
+        budget
+            .limit
+            .saturating_sub(budget.permits.available_permits()) as u64
+    }
+
+    /// Żądania odrzucone od startu serwera - do `/internal/metrics`
+    pub fn shed_total(&self, class: TrafficClass) -> u64 {
+        self.budget(class).shed.load(Ordering::Relaxed)
+    }
+}
+
+/// Odpowiedź dla odrzuconego żądania: HTMX dostaje komunikat (bez podmiany treści),
+/// a zwykłe wejście - statyczną stronę
+fn overloaded_response(is_htmx: bool) -> Response {
+    let retry_after = HeaderValue::from(RETRY_AFTER_SECS);
+    if is_htmx {
+        let trigger = json!({
+            "showMessage": {
+                "message": "Sklep jest teraz przeciążony. Spróbuj ponownie za kilka sekund.",
+                "type": "warning"
+            }
+        });
+        let mut response = StatusCode::SERVICE_UNAVAILABLE.into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::RETRY_AFTER, retry_after);
+        headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+        if let Ok(value) = HeaderValue::from_str(&trigger.to_string()) {
+            headers.insert("HX-Trigger", value);
+        }
+        return response;
+    }
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [
+            (header::RETRY_AFTER, retry_after),
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/html; charset=utf-8"),
+            ),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+        ],
+        OVERLOADED_PAGE,
+    )
+        .into_response()
+}
+
+/// Middleware ograniczające liczbę jednocześnie obsługiwanych żądań w każdej klasie ruchu
+pub async fn shed_load(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(class) = TrafficClass::for_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let shedder = &app_state.load_shedder;
+    let budget = shedder.budget(class);
+
+    let permit = match budget.permits.clone().try_acquire_owned() {
+        Ok(permit) => Some(permit),
+        Err(_) => tokio::time::timeout(
+            shedder.queue_timeout,
+            budget.permits.clone().acquire_owned(),
+        )
+        .await
+        .ok()
+        .and_then(Result::ok),
+    };
+
+    let Some(_permit) = permit else {
+        budget.shed.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(
+            "Przeciążenie: odrzucono żądanie {} {} (klasa '{}', limit {})",
+            request.method(),
+            request.uri().path(),
+            class.as_ref(),
+            budget.limit
+        );
+        return overloaded_response(request.headers().contains_key("HX-Request"));
+    };
+
+    // Miejsce zwalniamy dopiero po zbudowaniu odpowiedzi (drop `_permit`)
+    next.run(request).await
+}
@@ -0,0 +1,197 @@
+// src/login_lockout.rs
+
+// Ochrona kont przed zgadywaniem haseł: każda próba logowania trafia do `login_attempts`,
+// a po serii nieudanych prób w krótkim czasie adres jest blokowany na kilkanaście minut.
+// Licznik działa po adresie e-mail, nie po koncie - nieistniejący adres blokuje się tak samo,
+// więc komunikat o blokadzie nie zdradza, czy konto istnieje. Admin może zdjąć blokadę ręcznie.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::email_service::send_account_locked_email;
+use crate::errors::AppError;
+use crate::state::AppState;
+
+/// Tyle nieudanych prób w oknie czasowym blokuje adres
+pub const MAX_FAILED_LOGIN_ATTEMPTS: i64 = 5;
+/// Okno czasowe liczenia nieudanych prób
+const FAILED_ATTEMPTS_WINDOW_MINUTES: i64 = 15;
+/// Czas trwania blokady
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AccountLockout {
+    pub id: Uuid,
+    pub email: String,
+    pub user_id: Option<Uuid>,
+    pub failed_attempts: i32,
+    pub locked_at: DateTime<Utc>,
+    pub locked_until: DateTime<Utc>,
+}
+
+impl AccountLockout {
+    /// Pełne minuty do końca blokady (co najmniej 1, dopóki blokada trwa)
+    pub fn remaining_minutes(&self) -> i64 {
+        let remaining_secs = (self.locked_until - Utc::now()).num_seconds().max(0);
+        ((remaining_secs + 59) / 60).max(1)
+    }
+}
+
+/// Adres w postaci, w jakiej liczymy próby i blokady
+pub fn normalize_login_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+pub async fn record_login_attempt(
+    pool: &PgPool,
+    email: &str,
+    user_id: Option<Uuid>,
+    succeeded: bool,
+    ip_address: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO login_attempts (email, user_id, succeeded, ip_address) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(email)
+    .bind(user_id)
+    .bind(succeeded)
+    .bind(ip_address)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Trwająca (niezdjęta i niewygasła) blokada adresu
+pub async fn active_lockout(
+    pool: &PgPool,
+    email: &str,
+) -> Result<Option<AccountLockout>, AppError> {
+    let lockout = sqlx::query_as::<_, AccountLockout>(
+        r#"
+            SELECT id, email, user_id, failed_attempts, locked_at, locked_until
+            FROM account_lockouts
+            WHERE email = $1 AND unlocked_at IS NULL AND locked_until > NOW()
+            ORDER BY locked_at DESC
+            LIMIT 1
+        "#,
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await?;
+    Ok(lockout)
+}
+
+/// Zapisuje nieudaną próbę i blokuje adres, jeśli to była ostatnia dozwolona.
+/// Liczymy tylko próby po ostatnim udanym logowaniu i po ostatniej blokadzie,
+/// więc po jej zdjęciu (lub wygaśnięciu) licznik startuje od zera.
+/// Zwraca nową blokadę, jeśli właśnie powstała.
+pub async fn register_failed_login(
+    pool: &PgPool,
+    email: &str,
+    user_id: Option<Uuid>,
+    ip_address: Option<&str>,
+) -> Result<Option<AccountLockout>, AppError> {
+    record_login_attempt(pool, email, user_id, false, ip_address).await?;
+
+    let window_start = Utc::now() - Duration::minutes(FAILED_ATTEMPTS_WINDOW_MINUTES);
+    let failed_attempts: i64 = sqlx::query_scalar(
+        r#"
+            SELECT COUNT(*)
+            FROM login_attempts
+            WHERE email = $1
+              AND NOT succeeded
+              AND attempted_at > GREATEST(
+                  $2,
+                  (SELECT MAX(attempted_at) FROM login_attempts WHERE email = $1 AND succeeded),
+                  (SELECT MAX(locked_at) FROM account_lockouts WHERE email = $1)
+              )
+        "#,
+    )
+    .bind(email)
+    .bind(window_start)
+    .fetch_one(pool)
+    .await?;
+
+    if failed_attempts < MAX_FAILED_LOGIN_ATTEMPTS {
+        return Ok(None);
+    }
+
+    let lockout = sqlx::query_as::<_, AccountLockout>(
+        r#"
+            INSERT INTO account_lockouts (email, user_id, failed_attempts, locked_until)
+            VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))
+            RETURNING id, email, user_id, failed_attempts, locked_at, locked_until
+        "#,
+    )
+    .bind(email)
+    .bind(user_id)
+    .bind(failed_attempts as i32)
+    .bind(LOCKOUT_MINUTES as i32)
+    .fetch_one(pool)
+    .await?;
+
+    tracing::warn!(
+        "Zablokowano logowanie na adres {} do {} po {} nieudanych próbach",
+        email,
+        lockout.locked_until,
+        failed_attempts
+    );
+    Ok(Some(lockout))
+}
+
+/// Powiadamia właściciela konta o blokadzie w tle - odpowiedź na logowanie nie czeka na Resend.
+/// Dla adresów bez konta nie wysyłamy nic.
+pub fn spawn_lockout_email(app_state: Arc<AppState>, lockout: AccountLockout) {
+    if lockout.user_id.is_none() {
+        return;
+    }
+    let background_jobs = app_state.background_jobs.clone();
+    background_jobs.spawn(async move {
+        if let Err(e) =
+            send_account_locked_email(&app_state, &lockout.email, &lockout.locked_until).await
+        {
+            tracing::error!(
+                "Nie udało się wysłać powiadomienia o blokadzie konta do {}: {:?}",
+                lockout.email,
+                e
+            );
+        }
+    });
+}
+
+/// Trwające blokady - do panelu admina
+pub async fn list_active_lockouts(pool: &PgPool) -> Result<Vec<AccountLockout>, AppError> {
+    let lockouts = sqlx::query_as::<_, AccountLockout>(
+        r#"
+            SELECT id, email, user_id, failed_attempts, locked_at, locked_until
+            FROM account_lockouts
+            WHERE unlocked_at IS NULL AND locked_until > NOW()
+            ORDER BY locked_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(lockouts)
+}
+
+/// Ręczne zdjęcie blokady przez admina
+pub async fn unlock_account(
+    pool: &PgPool,
+    lockout_id: Uuid,
+    admin_id: Uuid,
+) -> Result<(), AppError> {
+    let result = sqlx::query(
+        "UPDATE account_lockouts SET unlocked_at = NOW(), unlocked_by = $2 WHERE id = $1 AND unlocked_at IS NULL",
+    )
+    .bind(lockout_id)
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
@@ -1,72 +1,215 @@
 // src/main.rs
 
 use axum::Router;
-use axum::extract::{DefaultBodyLimit, State};
+use axum::extract::DefaultBodyLimit;
 use axum::response::Html;
 use axum::routing::{delete, get, post};
 use axum_server::tls_rustls::RustlsConfig;
 use dotenvy::dotenv;
-use maud::Markup;
 use models::{Product, ProductStatus};
 use moka::future::Cache;
 use reqwest::StatusCode;
 use sqlx::postgres::PgPoolOptions;
-use std::env;
 use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
-use tower_http::cors::{Any, CorsLayer};
+use tokio_util::task::TaskTracker;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::services::ServeDir;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 // Deklaracje modułów
+pub mod api_keys;
 pub mod auth;
 pub mod auth_models;
+pub mod backup;
+pub mod cache_stats;
+pub mod care_instructions;
 pub mod cart_utils;
+pub mod checkout;
+pub mod client_ip;
 pub mod cloudinary;
+pub mod cloudinary_maintenance;
+pub mod cms;
+pub mod complaints;
+pub mod config;
+pub mod coupon_campaigns;
+pub mod coupons;
+pub mod customer_profiles;
+pub mod customer_segments;
+pub mod date_format;
+pub mod description_assistant;
+pub mod disposable_email;
+pub mod duplicates;
 pub mod email_service;
+pub mod email_verification;
 pub mod errors;
+pub mod events;
 pub mod extractor;
+pub mod faq;
+pub mod feeds;
 pub mod filters;
+pub mod gift_cards;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 pub mod handlers;
-pub mod htmx_handlers;
+pub mod holds;
+pub mod homepage;
+pub mod image_audit;
+pub mod image_hash;
+pub mod image_tagging;
+pub mod impersonation;
+pub mod inpost;
+pub mod invoices;
+pub mod jobs;
+pub mod link_checker;
+pub mod load_shed;
+pub mod login_lockout;
+pub mod merchant_feed;
+pub mod meta_catalog;
+pub mod metrics;
 pub mod middleware;
 pub mod models;
+pub mod notifications;
+pub mod object_storage;
+pub mod order_numbers;
 pub mod pagination;
+pub mod payments;
+pub mod permissions;
+pub mod plural;
+pub mod price_history;
+pub mod product_views;
+pub mod public_api;
+pub mod rate_limit;
+pub mod redirects;
+pub mod repo;
+pub mod reservations;
 pub mod response;
+pub mod retention;
+pub mod return_labels;
+pub mod returns;
+pub mod reviews;
+pub mod risk;
+pub mod rum;
+pub mod sales;
+pub mod sales_register;
+pub mod search;
+pub mod security_headers;
 pub mod seo;
 pub mod services;
+pub mod shutdown;
 pub mod sitemap_generator;
+pub mod sizes;
+pub mod sla;
+pub mod slugs;
 pub mod state;
+pub mod thank_you_cards;
+pub mod two_factor;
+pub mod user_management;
+pub mod vat;
+pub mod views;
+pub mod wishlist;
 
 use crate::handlers::{
-    add_item_to_cart_handler, add_item_to_guest_cart, archivize_product_handler,
-    create_order_handler, create_product_handler, forgot_password_handler, get_cart_handler,
-    get_guest_cart, get_order_details_handler, get_product_details, init_guest_session_handler,
-    list_orders_handler, list_products, login_handler, logout_handler, merge_cart_handler,
+    add_customer_note_handler, add_customer_tag_handler, add_featured_product_handler,
+    add_item_to_cart_handler, add_item_to_guest_cart, approve_return_handler,
+    approve_review_handler, archivize_product_handler, bulk_products_handler,
+    cancel_product_hold_handler, clean_product_image_background_handler,
+    complete_order_refund_handler, confirm_two_factor_setup_handler, create_api_key_handler,
+    create_complaint_handler, create_coupon_campaign_handler, create_coupon_handler,
+    create_customer_flag_handler, create_faq_item_handler, create_order_handler,
+    create_product_handler, create_product_hold_handler, create_redirect_handler,
+    create_return_request_handler, create_review_handler, create_size_mapping_handler,
+    delete_care_instruction_handler, delete_coupon_handler, delete_customer_flag_handler,
+    delete_customer_note_handler, delete_faq_item_handler, delete_redirect_handler,
+    delete_size_mapping_handler, delete_static_page_handler, disable_two_factor_handler,
+    download_invoice_handler, draft_product_description_handler, export_coupon_campaign_handler,
+    export_customer_segment_handler, export_sales_register_handler, forgot_password_handler,
+    get_cart_handler, get_guest_cart, get_order_details_handler, get_product_details,
+    init_guest_session_handler, inpost_label_handler, inpost_points_handler,
+    invalidate_cache_handler, list_orders_handler, list_products, login_handler, logout_handler,
+    mark_admin_notifications_read_handler, mark_gift_card_paid_handler, merge_cart_handler,
+    move_faq_item_handler, move_featured_product_handler, move_homepage_section_handler,
     permanent_delete_order_handler, permanent_delete_product_handler, protected_route_handler,
-    register_handler, remove_item_from_cart_handler, remove_item_from_guest_cart,
-    reset_password_handler, update_order_status_handler, update_product_partial_handler,
-    upsert_user_shipping_details_handler,
+    przelewy24_webhook_handler, purchase_gift_card_handler, purge_all_caches_handler,
+    purge_cache_handler, regenerate_recovery_codes_handler, register_handler,
+    reject_return_handler, reject_review_handler, remove_customer_tag_handler,
+    remove_featured_product_handler, remove_item_from_cart_handler, remove_item_from_guest_cart,
+    remove_order_item_handler, resend_gift_card_handler, resend_verification_email_handler,
+    reset_password_handler, retry_gift_card_payment_handler, retry_przelewy24_payment_handler,
+    revoke_api_key_handler, run_cloudinary_folder_migration_handler,
+    run_cloudinary_orphan_cleanup_handler, run_database_backup_handler, run_image_audit_handler,
+    save_care_instruction_handler, save_homepage_featured_handler, save_homepage_hero_handler,
+    save_homepage_popular_handler, save_static_page_handler, send_customer_password_reset_handler,
+    set_customer_disabled_handler, set_user_disabled_handler, set_user_role_handler,
+    start_impersonation_handler, start_two_factor_setup_handler, stop_impersonation_handler,
+    suggest_product_attributes_handler, thank_you_card_handler, toggle_coupon_active_handler,
+    toggle_sold_archive_handler, unlock_account_handler, update_complaint_status_handler,
+    update_coupon_handler, update_faq_item_handler, update_order_status_handler,
+    update_product_partial_handler, upsert_user_shipping_details_handler, verify_email_handler,
+    verify_two_factor_login_handler,
 };
 
-use crate::htmx_handlers::{
-    about_us_page_handler, admin_dashboard_htmx_handler, admin_order_details_htmx_handler,
-    admin_orders_list_htmx_handler, admin_product_edit_form_htmx_handler,
-    admin_product_new_form_htmx_handler, admin_products_list_htmx_handler, checkout_page_handler,
-    contact_page_handler, dla_gender_handler, dla_gender_with_category_handler, faq_page_handler,
-    forgot_password_form_handler, get_cart_details_htmx_handler, get_product_detail_htmx_handler,
-    handler_404, home_page_handler, list_products_htmx_handler, live_search_handler,
-    login_page_htmx_handler, my_account_data_htmx_handler, my_account_page_handler,
-    my_order_details_htmx_handler, my_orders_htmx_handler, news_page_htmx_handler,
-    payment_finalization_page_handler, privacy_policy_page_handler, registration_page_htmx_handler,
-    remove_item_from_cart_htmx_handler, reset_password_form_handler, sale_page_htmx_handler,
-    search_page_handler, shipping_returns_page_handler, terms_of_service_page_handler,
-    toggle_cart_item_htmx_handler,
+use crate::cache_stats::{CacheName, CacheStats};
+use crate::config::AppConfig;
+use crate::disposable_email::DisposableEmailBlocklist;
+use crate::load_shed::LoadShedder;
+use crate::metrics::Metrics;
+use crate::public_api::{
+    category_tree_v1_handler, get_product_v1_handler, list_products_v1_handler,
+};
+use crate::state::AppState;
+use crate::views::{
+    account::{
+        complaint_form_htmx_handler, email_verification_page_handler, forgot_password_form_handler,
+        guest_order_lookup_handler, guest_order_lookup_page_handler,
+        impersonation_banner_htmx_handler, login_page_htmx_handler, my_account_data_htmx_handler,
+        my_account_page_handler, my_account_security_htmx_handler, my_order_details_htmx_handler,
+        my_orders_htmx_handler, registration_page_htmx_handler, reset_password_form_handler,
+        return_request_form_htmx_handler, review_form_htmx_handler,
+        two_factor_login_page_htmx_handler,
+    },
+    admin::{
+        admin_api_keys_htmx_handler, admin_cache_htmx_handler,
+        admin_care_instructions_htmx_handler, admin_complaints_htmx_handler,
+        admin_coupon_campaigns_htmx_handler, admin_coupons_htmx_handler,
+        admin_customer_details_htmx_handler, admin_customer_flags_htmx_handler,
+        admin_customers_htmx_handler, admin_dashboard_htmx_handler, admin_faq_htmx_handler,
+        admin_funnel_htmx_handler, admin_gift_cards_htmx_handler, admin_homepage_htmx_handler,
+        admin_image_audit_htmx_handler, admin_impersonation_htmx_handler,
+        admin_impersonation_session_htmx_handler, admin_jobs_htmx_handler,
+        admin_login_lockouts_htmx_handler, admin_notifications_htmx_handler,
+        admin_order_details_htmx_handler, admin_orders_list_htmx_handler,
+        admin_photo_search_htmx_handler, admin_photo_search_results_htmx_handler,
+        admin_product_edit_form_htmx_handler, admin_product_new_form_htmx_handler,
+        admin_products_list_htmx_handler, admin_redirects_htmx_handler, admin_returns_htmx_handler,
+        admin_reviews_htmx_handler, admin_rum_htmx_handler, admin_sales_htmx_handler,
+        admin_size_mappings_htmx_handler, admin_static_page_edit_htmx_handler,
+        admin_static_page_preview_htmx_handler, admin_static_pages_htmx_handler,
+        admin_users_htmx_handler,
+    },
+    cart::{
+        apply_coupon_htmx_handler, apply_gift_card_htmx_handler, checkout_page_handler,
+        checkout_step_htmx_handler, checkout_summary_htmx_handler, get_cart_details_htmx_handler,
+        gift_card_purchase_page_handler, gift_card_status_page_handler,
+        inpost_suggestions_htmx_handler, payment_finalization_page_handler,
+        remove_item_from_cart_htmx_handler, resend_order_confirmation_htmx_handler,
+        save_checkout_step_htmx_handler, toggle_cart_item_htmx_handler,
+    },
+    shop::{
+        dla_gender_handler, dla_gender_with_category_handler, get_product_detail_htmx_handler,
+        home_page_handler, list_products_htmx_handler, live_search_handler, news_page_htmx_handler,
+        photo_search_page_handler, photo_search_results_htmx_handler, product_hold_page_handler,
+        product_prefetch_htmx_handler, sale_page_htmx_handler, search_page_handler,
+        sold_archive_page_handler, toggle_wishlist_htmx_handler,
+    },
+    static_pages::{
+        about_us_page_handler, contact_page_handler, faq_page_handler, handler_404,
+        privacy_policy_page_handler, shipping_returns_page_handler, terms_of_service_page_handler,
+    },
 };
-use crate::state::{AppState, CloudinaryConfig};
 
 #[tokio::main]
 async fn main() {
@@ -92,11 +235,21 @@ async fn main() {
 
     tracing::info!("Inicjalizacja serwera...");
 
+    // --- Konfiguracja (wszystkie błędy naraz, zanim cokolwiek wystartuje) ---
+    let config = match AppConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("Nieprawidłowa konfiguracja środowiska:\n{}", e);
+            std::process::exit(1);
+        }
+    };
+    checkout::set_free_shipping_threshold(config.free_shipping_threshold);
+    date_format::set_shop_timezone(config.shop_timezone);
+
     // --- Połączenie z bazą danych ---
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let pool = match PgPoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
+        .max_connections(config.db_max_connections)
+        .connect(&config.database_url)
         .await
     {
         Ok(pool) => {
@@ -114,31 +267,40 @@ async fn main() {
         tracing::info!(
             "[Cache Warm-up] Rozpoczynanie rozgrzewania cache'u dla stron statycznych..."
         );
-        type StaticPageRenderer = fn() -> Markup;
-        use crate::htmx_handlers::{
-            render_about_us_content, render_contact_page, render_faq_page,
-            render_privacy_policy_content, render_shipping_returns_page, render_terms_of_service,
-        };
-
-        let pages_to_cache: Vec<(&str, StaticPageRenderer)> = vec![
-            ("about_us_cache_key", render_about_us_content),
-            ("privacy_policy_cache_key", render_privacy_policy_content),
-            ("terms_of_service_cache_key", render_terms_of_service),
-            ("contact_page_cache_key", render_contact_page),
-            ("faq_page_cache_key", render_faq_page),
-            ("shipping_returns_cache_key", render_shipping_returns_page),
-        ];
+        use crate::cms::static_page_cache_key;
+        use crate::views::static_pages::{BUILT_IN_PAGES, static_page_content};
 
         let mut count = 0;
-        for (key, renderer) in pages_to_cache {
-            let content_html = renderer();
-            let content_str = content_html.into_string();
+        for page in &BUILT_IN_PAGES {
+            // Wersja z panelu, jeśli jest opublikowana - inaczej wbudowana treść
+            let content_html = match static_page_content(&state, page).await {
+                Ok(content_html) => content_html,
+                Err(e) => {
+                    tracing::error!(
+                        "[Cache Warm-up] Błąd podczas generowania strony '{}': {:?}",
+                        page.slug,
+                        e
+                    );
+                    continue;
+                }
+            };
+            let key = static_page_cache_key(page.slug);
             state
                 .static_html_cache
-                .insert(key.to_string(), content_str)
+                .insert(key.clone(), content_html.into_string())
                 .await;
+            state.cache_stats.record_insert(CacheName::StaticHtml, key);
             count += 1;
         }
+
+        // Pierwsze strony listingów - pierwsi goście po restarcie nie czekają na zimny cache
+        match crate::views::shop::warm_listing_fragment_cache(state.clone()).await {
+            Ok(listing_count) => count += listing_count,
+            Err(e) => tracing::error!(
+                "[Cache Warm-up] Błąd podczas rozgrzewania listingów: {:?}",
+                e
+            ),
+        }
         count
     }
 
@@ -150,7 +312,7 @@ async fn main() {
                 SELECT *, ROW_NUMBER() OVER(PARTITION BY category ORDER BY created_at DESC) as rn
                 FROM products WHERE status = $1
             )
-            SELECT id, name, description, price, gender, condition, category, status, on_sale, images, created_at, updated_at
+            SELECT id, name, slug, description, price, gender, condition, category, status, on_sale, images, size, brand, color, material, meta_title, meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at, updated_at
             FROM RankedProducts WHERE rn <= 5 ORDER BY created_at DESC LIMIT 100;
         "#)
         .bind(ProductStatus::Available)
@@ -161,6 +323,9 @@ async fn main() {
             Ok(products) => {
                 let count = products.len() as u64;
                 for product in products {
+                    state
+                        .cache_stats
+                        .record_insert(CacheName::Products, product.id);
                     state.product_cache.insert(product.id, product).await;
                 }
                 count
@@ -175,55 +340,77 @@ async fn main() {
         }
     }
 
-    // --- Konfiguracja Cloudinary ---
-    let cloudinary_config = CloudinaryConfig {
-        cloud_name: env::var("CLOUDINARY_CLOUD_NAME").expect("CLOUDINARY_CLOUD_NAME must be set"),
-        api_key: env::var("CLOUDINARY_API_KEY").expect("CLOUDINARY_API_KEY must be set"),
-        api_secret: env::var("CLOUDINARY_API_SECRET").expect("CLOUDINARY_API_SECRET must be set"),
-    };
-
-    // --- Konfiguracja JWT ---
-    let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    let jwt_expiration_hours = env::var("JWT_EXPIRATION_HOURS")
-        .unwrap_or_else(|_| "1".to_string())
-        .parse::<i64>()
-        .expect("JWT_EXPIRATION_HOURS must be a valid number");
-
-    // --- Konfiguracja Resend ---
-    let resend_api_key = env::var("RESEND_API_KEY").expect("RESEND_API_KEY must be set");
+    let disposable_email_blocklist = Arc::new(DisposableEmailBlocklist::new(
+        &config.disposable_email_domains,
+        config.disposable_email_list_url.clone(),
+    ));
 
     let product_cache = Arc::new(
         Cache::builder()
             .max_capacity(1000)
-            .time_to_live(Duration::from_secs(3600))
+            .time_to_live(config.cache_ttl.products)
             .build(),
     );
 
     let static_html_cache = Arc::new(
         Cache::builder()
             .max_capacity(50) // Mało wpisów
-            .time_to_live(Duration::from_secs(3600 * 24)) // 24 godziny
+            .time_to_live(config.cache_ttl.static_html)
+            .build(),
+    );
+
+    // Krótki TTL - listingi zmieniają się przy każdej sprzedaży i rezerwacji
+    let listing_fragment_cache = Arc::new(
+        Cache::builder()
+            .max_capacity(100)
+            .time_to_live(config.cache_ttl.listing_fragments)
             .build(),
     );
 
     let category_list_cache = Arc::new(
         Cache::builder()
             .max_capacity(20)
-            .time_to_live(Duration::from_secs(3600))
+            .time_to_live(config.cache_ttl.category_list)
             .build(),
     );
 
+    let rate_limit_buckets = Arc::new(rate_limit::new_rate_limit_buckets());
+
     // Definicja AppState
     let app_state = Arc::new(AppState {
         db_pool: pool,
-        jwt_secret,
-        jwt_expiration_hours,
-        cloudinary_config,
-        resend_api_key,
+        jwt_secret: config.jwt_secret,
+        jwt_expiration_hours: config.jwt_expiration_hours,
+        public_base_url: config.public_base_url,
+        cloudinary_config: config.cloudinary,
+        resend_api_key: config.resend_api_key,
+        admin_email: config.admin_email,
         product_cache,
         static_html_cache,
+        listing_fragment_cache,
         category_list_cache,
+        cache_stats: Arc::new(CacheStats::default()),
+        rate_limit_buckets,
+        trusted_proxies: config.trusted_proxies,
+        disposable_email_blocklist,
+        description_assistant: config.description_assistant,
+        przelewy24_config: config.przelewy24,
+        google_oauth: config.google_oauth,
+        inpost_config: config.inpost,
+        payment_details: config.payment_details,
+        invoice_config: config.invoice,
+        backup_config: config.backup,
+        retention_config: config.retention,
+        order_number_config: config.order_number,
+        vat_config: config.vat,
+        indexnow_key: config.indexnow_key,
+        staging: config.staging,
+        metrics: Arc::new(Metrics::default()),
+        metrics_token: config.metrics_token,
+        load_shedder: Arc::new(LoadShedder::new(&config.load_shed)),
+        background_jobs: TaskTracker::new(),
     });
+    jobs::spawn_periodic_jobs(app_state.clone());
     // [ZMIANA] Nowa, poprawna sekcja rozgrzewania cache'u
     tracing::info!("Uruchamianie zadań rozgrzewania pamięci podręcznej...");
     let static_warmup_handle = tokio::spawn(warm_static_cache(app_state.clone()));
@@ -236,7 +423,7 @@ async fn main() {
     // Loguj wyniki po zakończeniu
     match static_count_result {
         Ok(count) => tracing::info!(
-            "[Cache Warm-up] Zakończono: Rozgrzano {} stron statycznych.",
+            "[Cache Warm-up] Zakończono: Rozgrzano {} stron statycznych i listingów.",
             count
         ),
         Err(e) => tracing::error!(
@@ -252,13 +439,82 @@ async fn main() {
         ),
     }
 
+    // Bez CORS_ALLOWED_ORIGINS dowolne źródło, jak dotąd
+    let cors_origin = if config.cors_allowed_origins.is_empty() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(config.cors_allowed_origins.clone())
+    };
     let cors = CorsLayer::new()
-        .allow_origin(Any)
+        .allow_origin(cors_origin)
         .allow_methods(Any)
         .allow_headers(Any);
 
+    // Trasy z limitem żądań na adres IP (rate_limit.rs)
+    let auth_routes = Router::new()
+        .route("/api/auth/register", post(register_handler))
+        .route("/api/auth/login", post(login_handler))
+        .route("/api/auth/forgot-password", post(forgot_password_handler))
+        .route("/api/auth/2fa", post(verify_two_factor_login_handler))
+        .route("/api/auth/google", get(auth::oauth::google_login_handler))
+        .route(
+            "/api/auth/google/callback",
+            get(auth::oauth::google_callback_handler),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::rate_limit_auth,
+        ));
+    let guest_cart_routes = Router::new()
+        .route("/api/session/guest/init", post(init_guest_session_handler))
+        .route("/api/guest-cart/items", post(add_item_to_guest_cart))
+        .route(
+            "/htmx/cart/toggle/{product_id}",
+            post(toggle_cart_item_htmx_handler),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::rate_limit_guest_cart,
+        ));
+    let guest_order_lookup_routes = Router::new()
+        .route("/api/zamowienie/status", post(guest_order_lookup_handler))
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::rate_limit_guest_order_lookup,
+        ));
+    let photo_search_routes = Router::new()
+        .route(
+            "/htmx/wyszukaj-podobne",
+            post(photo_search_results_htmx_handler),
+        )
+        // Publiczny formularz przyjmuje tylko jedno zdjęcie - bez globalnego limitu 100 MB
+        .layer(DefaultBodyLimit::max(config.photo_search_max_body_bytes))
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::rate_limit_photo_search,
+        ));
+    let rum_routes = Router::new()
+        .route("/api/rum", post(rum::rum_beacon_handler))
+        .route_layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::rate_limit_rum,
+        ));
+    // Opcjonalne API GraphQL katalogu (feature "graphql"); bez niego trasa nie istnieje
+    let graphql_routes: Router<Arc<AppState>> = Router::new();
+    #[cfg(feature = "graphql")]
+    let graphql_routes = graphql_routes.route(
+        "/api/graphql",
+        get(graphql::graphiql_handler).post(graphql::graphql_handler),
+    );
+
     // Definicja routingu aplikacji
     let app = Router::new()
+        .merge(auth_routes)
+        .merge(guest_cart_routes)
+        .merge(guest_order_lookup_routes)
+        .merge(photo_search_routes)
+        .merge(rum_routes)
+        .merge(graphql_routes)
         .route(
             "/api/products",
             get(list_products).post(create_product_handler),
@@ -273,8 +529,14 @@ async fn main() {
             "/api/products/{id}/permanent",
             delete(permanent_delete_product_handler),
         )
-        .route("/api/auth/register", post(register_handler))
-        .route("/api/auth/login", post(login_handler))
+        .route("/api/admin/products/bulk", post(bulk_products_handler))
+        // Publiczne API katalogu (klucz w nagłówku X-Api-Key)
+        .route("/api/v1/products", get(list_products_v1_handler))
+        .route(
+            "/api/v1/products/{product_ref}",
+            get(get_product_v1_handler),
+        )
+        .route("/api/v1/categories", get(category_tree_v1_handler))
         .route("/api/me", get(protected_route_handler))
         .route(
             "/api/orders",
@@ -288,6 +550,14 @@ async fn main() {
             "/api/orders/{order_id}/permanent",
             delete(permanent_delete_order_handler),
         )
+        .route(
+            "/api/orders/{order_id}/items/{order_item_id}/remove",
+            post(remove_order_item_handler),
+        )
+        .route(
+            "/api/orders/{order_id}/refunds/{refund_id}/complete",
+            post(complete_order_refund_handler),
+        )
         .route("/api/cart/items", post(add_item_to_cart_handler))
         .route("/api/cart", get(get_cart_handler))
         .route(
@@ -295,7 +565,6 @@ async fn main() {
             delete(remove_item_from_cart_handler),
         )
         .route("/api/guest-cart", get(get_guest_cart))
-        .route("/api/guest-cart/items", post(add_item_to_guest_cart))
         .route(
             "/api/guest-cart/items/{product_id}",
             delete(remove_item_from_guest_cart),
@@ -306,14 +575,38 @@ async fn main() {
             post(upsert_user_shipping_details_handler),
         )
         .route("/api/auth/logout", post(logout_handler))
-        .route("/api/session/guest/init", post(init_guest_session_handler))
         // Trasa główna i jej aliasy
         .route("/", get(home_page_handler))
+        .route("/feed.xml", get(feeds::atom_feed_handler))
+        .route(
+            "/feeds/google-merchant.xml",
+            get(merchant_feed::google_merchant_feed_handler),
+        )
+        .route(
+            "/feeds/meta-catalog.csv",
+            get(meta_catalog::meta_catalog_handler),
+        )
         .route(
             "/sitemap.xml",
-            get(|State(state): State<Arc<AppState>>| async move {
-                sitemap_generator::generate_sitemap_handler(&state).await
-            }),
+            get(sitemap_generator::sitemap_index_handler),
+        )
+        .route(
+            "/sitemaps/static.xml",
+            get(sitemap_generator::static_sitemap_handler),
+        )
+        .route(
+            "/sitemaps/kategorie.xml",
+            get(sitemap_generator::category_sitemap_handler),
+        )
+        .route(
+            "/sitemaps/produkty/{file_name}",
+            get(sitemap_generator::product_sitemap_handler),
+        )
+        .route("/robots.txt", get(sitemap_generator::robots_txt_handler))
+        .route("/internal/metrics", get(metrics::metrics_handler))
+        .route(
+            "/indexnow-key.txt",
+            get(sitemap_generator::indexnow_key_handler),
         )
         .route(
             "/{gender_slug}/{category}",
@@ -322,9 +615,12 @@ async fn main() {
         .route("/{gender_slug}", get(dla_gender_handler))
         .route("/kategoria", get(list_products_htmx_handler))
         .route("/nowosci", get(news_page_htmx_handler))
+        .route("/rezerwacja/{token}", get(product_hold_page_handler))
         .route("/okazje", get(sale_page_htmx_handler))
+        .route("/archiwum", get(sold_archive_page_handler))
+        .route("/htmx/archiwum", get(sold_archive_page_handler))
         .route(
-            "/produkty/{product_id}",
+            "/produkty/{product_ref}",
             get(get_product_detail_htmx_handler),
         )
         .route("/o-nas", get(about_us_page_handler))
@@ -336,13 +632,18 @@ async fn main() {
             "/moje-konto/zamowienia/{order_id}",
             get(my_order_details_htmx_handler),
         )
+        .route(
+            "/moje-konto/zamowienia/{order_id}/faktura",
+            get(download_invoice_handler),
+        )
         .route("/moje-konto/dane", get(my_account_data_htmx_handler))
-        .route("/checkout", get(checkout_page_handler))
-        .route("/wyszukiwanie", get(search_page_handler))
         .route(
-            "/htmx/cart/toggle/{product_id}",
-            post(toggle_cart_item_htmx_handler),
+            "/moje-konto/bezpieczenstwo",
+            get(my_account_security_htmx_handler),
         )
+        .route("/checkout", get(checkout_page_handler))
+        .route("/wyszukiwanie", get(search_page_handler))
+        .route("/wyszukaj-podobne", get(photo_search_page_handler))
         .route("/htmx/cart/details", get(get_cart_details_htmx_handler)) // TODO
         .route("/htmx/products", get(list_products_htmx_handler))
         .route(
@@ -350,9 +651,13 @@ async fn main() {
             post(remove_item_from_cart_htmx_handler),
         )
         .route(
-            "/htmx/produkt/{product_id}",
+            "/htmx/produkt/{product_ref}",
             get(get_product_detail_htmx_handler),
         )
+        .route(
+            "/htmx/produkt/{product_ref}/prefetch",
+            get(product_prefetch_htmx_handler),
+        )
         .route(
             "/htmx/page/polityka-prywatnosci",
             get(privacy_policy_page_handler),
@@ -370,12 +675,54 @@ async fn main() {
         .route("/wysylka-i-zwroty", get(shipping_returns_page_handler))
         .route("/htmx/logowanie", get(login_page_htmx_handler))
         .route("/logowanie", get(login_page_htmx_handler))
+        .route(
+            "/htmx/logowanie/weryfikacja",
+            get(two_factor_login_page_htmx_handler),
+        )
+        .route(
+            "/logowanie/weryfikacja",
+            get(two_factor_login_page_htmx_handler),
+        )
         .route("/htmx/rejestracja", get(registration_page_htmx_handler))
         .route("/rejestracja", get(registration_page_htmx_handler))
         .route("/htmx/my-account", get(my_account_page_handler))
         .route("/htmx/moje-konto/zamowienia", get(my_orders_htmx_handler))
         .route("/htmx/moje-konto/dane", get(my_account_data_htmx_handler))
+        .route(
+            "/htmx/moje-konto/bezpieczenstwo",
+            get(my_account_security_htmx_handler),
+        )
+        .route(
+            "/api/moje-konto/2fa/start",
+            post(start_two_factor_setup_handler),
+        )
+        .route(
+            "/api/moje-konto/2fa/potwierdz",
+            post(confirm_two_factor_setup_handler),
+        )
+        .route(
+            "/api/moje-konto/2fa/kody-zapasowe",
+            post(regenerate_recovery_codes_handler),
+        )
+        .route(
+            "/api/moje-konto/2fa/wylacz",
+            post(disable_two_factor_handler),
+        )
         .route("/htmx/checkout", get(checkout_page_handler))
+        .route(
+            "/htmx/checkout/krok/{step}",
+            get(checkout_step_htmx_handler).post(save_checkout_step_htmx_handler),
+        )
+        .route("/htmx/checkout/summary", get(checkout_summary_htmx_handler))
+        .route(
+            "/htmx/checkout/paczkomaty",
+            get(inpost_suggestions_htmx_handler),
+        )
+        .route("/htmx/checkout/coupon", post(apply_coupon_htmx_handler))
+        .route(
+            "/htmx/checkout/karta-podarunkowa",
+            post(apply_gift_card_htmx_handler),
+        )
         .route(
             "/htmx/moje-konto/zamowienie-szczegoly/{order_id}",
             get(my_order_details_htmx_handler),
@@ -384,6 +731,34 @@ async fn main() {
             "/moje-konto/zamowienie-szczegoly/{order_id}",
             get(my_order_details_htmx_handler),
         )
+        .route(
+            "/htmx/moje-konto/zamowienia/{order_id}/zwrot",
+            get(return_request_form_htmx_handler),
+        )
+        .route(
+            "/api/moje-konto/zamowienia/{order_id}/zwrot",
+            post(create_return_request_handler),
+        )
+        .route(
+            "/htmx/moje-konto/zamowienia/{order_id}/reklamacja",
+            get(complaint_form_htmx_handler),
+        )
+        .route(
+            "/api/moje-konto/zamowienia/{order_id}/reklamacja",
+            post(create_complaint_handler),
+        )
+        .route(
+            "/htmx/moje-konto/opinie/{product_id}",
+            get(review_form_htmx_handler),
+        )
+        .route(
+            "/htmx/ulubione/{product_id}",
+            post(toggle_wishlist_htmx_handler),
+        )
+        .route(
+            "/api/moje-konto/opinie/{product_id}",
+            post(create_review_handler),
+        )
         .route("/admin", get(admin_dashboard_htmx_handler))
         .route("/htmx/admin", get(admin_dashboard_htmx_handler))
         .route(
@@ -405,51 +780,424 @@ async fn main() {
             get(admin_order_details_htmx_handler),
         )
         .route("/htmx/admin/orders", get(admin_orders_list_htmx_handler))
+        .route("/htmx/admin/klienci", get(admin_customers_htmx_handler))
+        .route(
+            "/htmx/admin/klienci/{user_id}",
+            get(admin_customer_details_htmx_handler),
+        )
+        .route(
+            "/api/admin/klienci/{user_id}/reset-hasla",
+            post(send_customer_password_reset_handler),
+        )
+        .route(
+            "/api/admin/klienci/{user_id}/blokada",
+            post(set_customer_disabled_handler),
+        )
+        .route(
+            "/api/admin/klienci/{user_id}/notatki",
+            post(add_customer_note_handler),
+        )
+        .route(
+            "/api/admin/klienci/{user_id}/notatki/{note_id}",
+            delete(delete_customer_note_handler),
+        )
+        .route(
+            "/api/admin/klienci/eksport",
+            get(export_customer_segment_handler),
+        )
+        .route(
+            "/api/admin/klienci/{user_id}/tagi",
+            post(add_customer_tag_handler),
+        )
+        .route(
+            "/api/admin/klienci/{user_id}/tagi/{tag}",
+            delete(remove_customer_tag_handler),
+        )
+        .route(
+            "/htmx/admin/customer-flags",
+            get(admin_customer_flags_htmx_handler),
+        )
+        .route(
+            "/api/admin/customer-flags",
+            post(create_customer_flag_handler),
+        )
+        .route(
+            "/api/admin/customer-flags/{flag_id}",
+            delete(delete_customer_flag_handler),
+        )
+        .route(
+            "/htmx/admin/rozmiary",
+            get(admin_size_mappings_htmx_handler),
+        )
+        .route(
+            "/htmx/admin/pielegnacja",
+            get(admin_care_instructions_htmx_handler),
+        )
+        .route(
+            "/api/admin/pielegnacja",
+            post(save_care_instruction_handler),
+        )
+        .route(
+            "/api/admin/pielegnacja/{instruction_id}",
+            delete(delete_care_instruction_handler),
+        )
+        .route("/api/admin/rozmiary", post(create_size_mapping_handler))
+        .route(
+            "/api/admin/rozmiary/{mapping_id}",
+            delete(delete_size_mapping_handler),
+        )
+        .route("/htmx/admin/returns", get(admin_returns_htmx_handler))
+        .route(
+            "/api/admin/returns/{return_id}/approve",
+            post(approve_return_handler),
+        )
+        .route(
+            "/api/admin/returns/{return_id}/reject",
+            post(reject_return_handler),
+        )
+        .route("/htmx/admin/reklamacje", get(admin_complaints_htmx_handler))
+        .route(
+            "/api/admin/reklamacje/{complaint_id}/status",
+            post(update_complaint_status_handler),
+        )
+        .route("/htmx/admin/opinie", get(admin_reviews_htmx_handler))
+        .route(
+            "/api/admin/opinie/{review_id}/approve",
+            post(approve_review_handler),
+        )
+        .route(
+            "/api/admin/opinie/{review_id}/reject",
+            post(reject_review_handler),
+        )
+        .route(
+            "/htmx/admin/podobne",
+            get(admin_photo_search_htmx_handler).post(admin_photo_search_results_htmx_handler),
+        )
+        .route("/htmx/admin/coupons", get(admin_coupons_htmx_handler))
+        .route("/api/admin/coupons", post(create_coupon_handler))
+        .route(
+            "/htmx/admin/coupons/kampanie",
+            get(admin_coupon_campaigns_htmx_handler),
+        )
+        .route(
+            "/api/admin/coupons/kampanie",
+            post(create_coupon_campaign_handler),
+        )
+        .route(
+            "/api/admin/coupons/kampanie/{campaign_id}/eksport",
+            get(export_coupon_campaign_handler),
+        )
+        .route(
+            "/api/admin/coupons/{coupon_id}",
+            post(update_coupon_handler).delete(delete_coupon_handler),
+        )
+        .route(
+            "/api/admin/coupons/{coupon_id}/toggle-active",
+            post(toggle_coupon_active_handler),
+        )
+        .route(
+            "/htmx/admin/karty-podarunkowe",
+            get(admin_gift_cards_htmx_handler),
+        )
+        .route(
+            "/api/admin/karty-podarunkowe/{gift_card_id}/oplacona",
+            post(mark_gift_card_paid_handler),
+        )
+        .route(
+            "/api/admin/karty-podarunkowe/{gift_card_id}/wyslij",
+            post(resend_gift_card_handler),
+        )
+        .route(
+            "/htmx/admin/image-audit",
+            get(admin_image_audit_htmx_handler),
+        )
+        .route("/api/admin/image-audit/run", post(run_image_audit_handler))
+        .route("/htmx/admin/funnel", get(admin_funnel_htmx_handler))
+        .route("/htmx/admin/wydajnosc", get(admin_rum_htmx_handler))
+        .route(
+            "/htmx/admin/impersonation",
+            get(admin_impersonation_htmx_handler),
+        )
+        .route(
+            "/htmx/admin/impersonation/{session_id}",
+            get(admin_impersonation_session_htmx_handler),
+        )
+        .route(
+            "/api/admin/impersonation",
+            post(start_impersonation_handler),
+        )
+        .route("/api/impersonation/stop", post(stop_impersonation_handler))
+        .route(
+            "/htmx/admin/blokady-logowania",
+            get(admin_login_lockouts_htmx_handler),
+        )
+        .route(
+            "/api/admin/blokady-logowania/{lockout_id}/odblokuj",
+            post(unlock_account_handler),
+        )
+        .route("/htmx/admin/strony", get(admin_static_pages_htmx_handler))
+        .route(
+            "/htmx/admin/strony/podglad",
+            post(admin_static_page_preview_htmx_handler),
+        )
+        .route(
+            "/htmx/admin/strony/{slug}",
+            get(admin_static_page_edit_htmx_handler),
+        )
+        .route(
+            "/api/admin/strony/{slug}",
+            post(save_static_page_handler).delete(delete_static_page_handler),
+        )
+        .route(
+            "/htmx/admin/strona-glowna",
+            get(admin_homepage_htmx_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/baner",
+            post(save_homepage_hero_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/sekcje/{section}/przesun/{direction}",
+            post(move_homepage_section_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/wyroznione",
+            post(save_homepage_featured_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/popularne",
+            post(save_homepage_popular_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/wyroznione/produkty",
+            post(add_featured_product_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/wyroznione/produkty/{product_id}",
+            delete(remove_featured_product_handler),
+        )
+        .route(
+            "/api/admin/strona-glowna/wyroznione/produkty/{product_id}/przesun/{direction}",
+            post(move_featured_product_handler),
+        )
+        .route("/htmx/admin/faq", get(admin_faq_htmx_handler))
+        .route("/api/admin/faq", post(create_faq_item_handler))
+        .route(
+            "/api/admin/faq/{item_id}",
+            post(update_faq_item_handler).delete(delete_faq_item_handler),
+        )
+        .route(
+            "/api/admin/faq/{item_id}/przesun/{direction}",
+            post(move_faq_item_handler),
+        )
+        .route(
+            "/htmx/admin/przekierowania",
+            get(admin_redirects_htmx_handler),
+        )
+        .route("/api/admin/przekierowania", post(create_redirect_handler))
+        .route(
+            "/api/admin/przekierowania/{redirect_id}",
+            delete(delete_redirect_handler),
+        )
+        .route("/htmx/admin/users", get(admin_users_htmx_handler))
+        .route("/admin/uzytkownicy", get(admin_users_htmx_handler))
+        .route(
+            "/api/admin/users/{user_id}/rola",
+            post(set_user_role_handler),
+        )
+        .route(
+            "/api/admin/users/{user_id}/blokada",
+            post(set_user_disabled_handler),
+        )
+        .route(
+            "/htmx/impersonation/banner",
+            get(impersonation_banner_htmx_handler),
+        )
+        .route("/htmx/admin/sales", get(admin_sales_htmx_handler))
+        .route(
+            "/api/admin/raporty/rejestr-sprzedazy",
+            get(export_sales_register_handler),
+        )
+        .route("/htmx/admin/api-keys", get(admin_api_keys_htmx_handler))
+        .route("/api/admin/api-keys", post(create_api_key_handler))
+        .route(
+            "/api/admin/api-keys/{key_id}",
+            delete(revoke_api_key_handler),
+        )
+        .route("/htmx/admin/cache", get(admin_cache_htmx_handler))
+        .route("/htmx/admin/zadania", get(admin_jobs_htmx_handler))
+        .route("/api/admin/cache/purge", post(purge_all_caches_handler))
+        .route(
+            "/api/admin/cache/invalidate",
+            post(invalidate_cache_handler),
+        )
+        .route(
+            "/api/admin/cache/{cache_name}/purge",
+            post(purge_cache_handler),
+        )
+        .route(
+            "/htmx/admin/notifications",
+            get(admin_notifications_htmx_handler),
+        )
+        .route(
+            "/api/admin/notifications/read-all",
+            post(mark_admin_notifications_read_handler),
+        )
+        .route("/api/admin/backups/run", post(run_database_backup_handler))
+        .route(
+            "/api/admin/cloudinary/migrate-folders",
+            post(run_cloudinary_folder_migration_handler),
+        )
+        .route(
+            "/api/admin/cloudinary/cleanup-orphans",
+            post(run_cloudinary_orphan_cleanup_handler),
+        )
+        .route(
+            "/api/admin/products/description-draft",
+            post(draft_product_description_handler),
+        )
+        .route(
+            "/api/admin/products/suggest-attributes",
+            post(suggest_product_attributes_handler),
+        )
+        .route(
+            "/api/admin/products/{product_id}/images/clean-background",
+            post(clean_product_image_background_handler),
+        )
+        .route(
+            "/api/admin/products/{product_id}/hold",
+            post(create_product_hold_handler).delete(cancel_product_hold_handler),
+        )
+        .route(
+            "/api/admin/products/{product_id}/sold-archive",
+            post(toggle_sold_archive_handler),
+        )
+        .route("/api/shipping/inpost/points", get(inpost_points_handler))
+        .route(
+            "/api/admin/orders/{order_id}/inpost/label",
+            get(inpost_label_handler),
+        )
+        .route(
+            "/api/admin/orders/{order_id}/thank-you-card",
+            get(thank_you_card_handler),
+        )
+        .route(
+            "/api/payments/p24/webhook",
+            post(przelewy24_webhook_handler),
+        )
+        .route(
+            "/zamowienie/{order_id}/zaplac",
+            get(retry_przelewy24_payment_handler),
+        )
         .route(
             "/zamowienie/dziekujemy/{order_id}",
             get(payment_finalization_page_handler),
         )
+        .route("/karta-podarunkowa", get(gift_card_purchase_page_handler))
+        .route(
+            "/htmx/karta-podarunkowa",
+            get(gift_card_purchase_page_handler),
+        )
+        .route("/api/karta-podarunkowa", post(purchase_gift_card_handler))
+        .route(
+            "/karta-podarunkowa/{gift_card_id}",
+            get(gift_card_status_page_handler),
+        )
+        .route(
+            "/karta-podarunkowa/{gift_card_id}/zaplac",
+            get(retry_gift_card_payment_handler),
+        )
         .route(
             "/htmx/zamowienie/dziekujemy/{order_id}",
             get(payment_finalization_page_handler),
         )
-        .route("/api/auth/forgot-password", post(forgot_password_handler))
+        .route(
+            "/htmx/zamowienie/{order_id}/potwierdzenie",
+            post(resend_order_confirmation_htmx_handler),
+        )
         .route("/api/auth/reset-password", post(reset_password_handler))
+        .route("/zamowienie/status", get(guest_order_lookup_page_handler))
         .route("/zapomnialem-hasla", get(forgot_password_form_handler))
         .route("/htmx/zapomnialem-hasla", get(forgot_password_form_handler))
         .route("/resetuj-haslo", get(reset_password_form_handler))
+        .route("/api/auth/verify-email", get(verify_email_handler))
+        .route(
+            "/api/auth/resend-verification",
+            post(resend_verification_email_handler),
+        )
+        .route("/weryfikacja-email", get(email_verification_page_handler))
         .route("/htmx/live-search", get(live_search_handler))
         .nest_service("/static", ServeDir::new("static"))
         .fallback(handler_404)
+        // Stare adresy z tabeli `redirects` - sprawdzane tylko przy odpowiedzi 404
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            redirects::redirect_not_found,
+        ))
+        // Token CSRF dla żądań zmieniających stan (ciasteczko + nagłówek `X-CSRF-Token`)
+        .layer(axum::middleware::from_fn(crate::middleware::csrf_protect))
+        // Podgląd konta klienta przez admina: rozpoznanie sesji, dziennik i tryb tylko do odczytu
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            impersonation::impersonation_guard,
+        ))
+        // Osobne limity jednoczesnych żądań dla stron, zamówień i panelu - zob. `load_shed`
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            load_shed::shed_load,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            app_state.clone(),
+            metrics::track_http_metrics,
+        ))
+        // Kompresujemy HTML, JSON, CSS i JS; obrazy i PDF-y są już skompresowane.
+        // Warstwa dopisuje `Vary: Accept-Encoding` obok `Vary: HX-Request` z `build_response`.
+        .layer(
+            CompressionLayer::new()
+                .gzip(config.compression.gzip)
+                .br(config.compression.brotli)
+                .compress_when(
+                    SizeAbove::new(config.compression.min_size_bytes)
+                        .and(NotForContentType::IMAGES)
+                        .and(NotForContentType::const_new("application/pdf"))
+                        .and(NotForContentType::GRPC)
+                        .and(NotForContentType::SSE),
+                ),
+        )
+        // CSP, HSTS i pozostałe nagłówki bezpieczeństwa - zob. `security_headers`
+        .layer(axum::middleware::from_fn(
+            security_headers::security_headers,
+        ))
         .layer(TraceLayer::new_for_http())
-        .layer(DefaultBodyLimit::max(100 * 1024 * 1024))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
         .layer(cors)
         .with_state(app_state.clone());
 
     // Adres i port, na którym serwer będzie nasłuchiwał
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000)); // Nasłuchuj na wszystkich interfejsach na porcie 3000
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port)); // Nasłuchuj na wszystkich interfejsach
     tracing::info!("Serwer nasłuchuje na {}", addr);
 
     // Konfiguracja TLS
-    let config = match RustlsConfig::from_pem_file(
-        PathBuf::from("localhost+2.pem"),     // Ścieżka do pliku certyfikatu
-        PathBuf::from("localhost+2-key.pem"), // Ścieżka do pliku klucza
-    )
-    .await
-    {
-        Ok(config) => config,
-        Err(e) => {
-            tracing::error!("Błąd podczas ładowania certyfikatów TLS: {:?}", e);
-            std::process::exit(1);
-        }
-    };
+    let tls_config =
+        match RustlsConfig::from_pem_file(&config.tls_cert_path, &config.tls_key_path).await {
+            Ok(tls_config) => tls_config,
+            Err(e) => {
+                tracing::error!("Błąd podczas ładowania certyfikatów TLS: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+
+    let handle = axum_server::Handle::new();
+    shutdown::spawn_shutdown_listener(handle.clone());
 
-    if let Err(e) = axum_server::bind_rustls(addr, config)
-        .serve(app.into_make_service())
+    if let Err(e) = axum_server::bind_rustls(addr, tls_config)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
     {
         tracing::error!("Błąd serwera: {}", e);
     }
+    shutdown::drain_background_jobs(&app_state).await;
 }
 
 #[allow(dead_code)]
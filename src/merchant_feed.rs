@@ -0,0 +1,222 @@
+// src/merchant_feed.rs
+
+// Feed produktów dla Google Merchant Center (Zakupy Google) w formacie RSS 2.0 z przestrzenią
+// nazw `g:`. Zawiera wszystkie dostępne produkty; gotowy XML trzymamy w `static_html_cache`
+// i usuwamy go przy każdej zmianie produktów, więc Google przy następnym pobraniu dostaje
+// aktualną listę.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures::TryStreamExt;
+use quick_xml::se::to_string;
+use serde::Serialize;
+
+use crate::cache_stats::CacheName;
+use crate::errors::AppError;
+use crate::models::{Category, Product, ProductGender, ProductStatus};
+use crate::state::AppState;
+use crate::views::common::transform_cloudinary_url;
+
+pub const MERCHANT_FEED_CACHE_KEY: &str = "google_merchant_feed";
+/// Google przyjmuje główne zdjęcie i do 10 dodatkowych
+pub const MAX_ADDITIONAL_IMAGES: usize = 10;
+const MAX_TITLE_LEN: usize = 150;
+pub const FEED_IMAGE_TRANSFORMATION: &str = "w_1200,c_limit,f_jpg,q_auto";
+
+#[derive(Serialize)]
+#[serde(rename = "item")]
+struct FeedItem {
+    #[serde(rename = "g:id")]
+    id: String,
+    #[serde(rename = "g:title")]
+    title: String,
+    #[serde(rename = "g:description")]
+    description: String,
+    #[serde(rename = "g:link")]
+    link: String,
+    #[serde(rename = "g:image_link", skip_serializing_if = "Option::is_none")]
+    image_link: Option<String>,
+    #[serde(rename = "g:additional_image_link")]
+    additional_image_links: Vec<String>,
+    #[serde(rename = "g:availability")]
+    availability: &'static str,
+    #[serde(rename = "g:price")]
+    price: String,
+    #[serde(rename = "g:condition")]
+    condition: &'static str,
+    #[serde(rename = "g:brand", skip_serializing_if = "Option::is_none")]
+    brand: Option<String>,
+    /// Pojedyncze egzemplarze nie mają kodów EAN - bez tego Google odrzuca produkty
+    #[serde(rename = "g:identifier_exists")]
+    identifier_exists: &'static str,
+    #[serde(rename = "g:gender")]
+    gender: &'static str,
+    #[serde(rename = "g:age_group")]
+    age_group: &'static str,
+    #[serde(rename = "g:size", skip_serializing_if = "Option::is_none")]
+    size: Option<String>,
+    #[serde(rename = "g:color", skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(rename = "g:material", skip_serializing_if = "Option::is_none")]
+    material: Option<String>,
+    #[serde(rename = "g:google_product_category")]
+    google_product_category: u32,
+    #[serde(rename = "g:product_type")]
+    product_type: String,
+}
+
+/// Identyfikator kategorii z taksonomii Google (Apparel & Accessories > ...)
+pub fn google_product_category(category: Category) -> u32 {
+    match category {
+        // Shirts & Tops
+        Category::Koszule | Category::Swetry | Category::Bluzy => 212,
+        Category::Spodnie => 204,
+        Category::Sukienki => 2271,
+        Category::Spodnice => 1581,
+        // Outerwear > Coats & Jackets
+        Category::KurtkiPlaszcze => 5598,
+        // Suits > Suit Jackets
+        Category::MarynarkiZakiety => 5183,
+        Category::Obuwie => 187,
+        Category::Torebki => 3032,
+        // Clothing Accessories
+        Category::Akcesoria => 167,
+        // Underwear & Socks
+        Category::Bielizna => 213,
+        Category::StrojeKapielowe => 211,
+        // Clothing - kategoria ogólna
+        Category::Inne => 1604,
+    }
+}
+
+pub fn google_gender(gender: ProductGender) -> &'static str {
+    match gender {
+        ProductGender::Damskie => "female",
+        ProductGender::Meskie => "male",
+    }
+}
+
+/// Cena w groszach w formacie Google, np. "129.00 PLN"
+pub fn google_price(price: i64) -> String {
+    format!("{}.{:02} PLN", price / 100, price % 100)
+}
+
+/// Ścieżka kategorii w sklepie, np. "Damskie > Sukienki"
+pub fn shop_product_type(product: &Product) -> String {
+    let gender_label = match product.gender {
+        ProductGender::Damskie => "Damskie",
+        ProductGender::Meskie => "Męskie",
+    };
+    format!("{} > {}", gender_label, product.category)
+}
+
+fn feed_item(product: Product, base_url: &str) -> FeedItem {
+    let mut images = product
+        .images
+        .iter()
+        .map(|image| transform_cloudinary_url(image, FEED_IMAGE_TRANSFORMATION));
+    let image_link = images.next();
+    let additional_image_links = images.take(MAX_ADDITIONAL_IMAGES).collect();
+    let product_type = shop_product_type(&product);
+
+    FeedItem {
+        id: product.id.to_string(),
+        title: product.name.chars().take(MAX_TITLE_LEN).collect(),
+        link: format!("{}{}", base_url, product.public_path()),
+        image_link,
+        additional_image_links,
+        availability: "in_stock",
+        price: google_price(product.effective_price()),
+        condition: "used",
+        brand: product.brand,
+        identifier_exists: "no",
+        gender: google_gender(product.gender),
+        age_group: "adult",
+        size: product.size,
+        color: product.color,
+        material: product.material,
+        google_product_category: google_product_category(product.category),
+        product_type,
+        description: product.description,
+    }
+}
+
+/// Generuje cały feed; produkty czytamy strumieniem i od razu dopisujemy do XML-a
+pub async fn generate_merchant_feed(app_state: &AppState) -> Result<String, AppError> {
+    let base_url = &app_state.public_base_url;
+    let mut xml_output = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+         <rss version=\"2.0\" xmlns:g=\"http://base.google.com/ns/1.0\"><channel>\
+         <title>sklep mess - all that vintage</title><link>{}</link>\
+         <description>Odzież vintage i second hand</description>",
+        base_url
+    );
+
+    let mut products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE status = $1 ORDER BY created_at DESC",
+    )
+    .bind(ProductStatus::Available)
+    .fetch(&app_state.db_pool);
+    let mut count = 0;
+    while let Some(product) = products.try_next().await? {
+        let item = to_string(&feed_item(product, base_url)).map_err(|e| {
+            AppError::InternalServerError(format!("Błąd podczas generowania feedu XML: {}", e))
+        })?;
+        xml_output.push_str(&item);
+        count += 1;
+    }
+    xml_output.push_str("</channel></rss>");
+
+    tracing::info!("[Merchant feed] Wygenerowano feed z {} produktami.", count);
+    Ok(xml_output)
+}
+
+/// Usuwa feed z cache'u - wołać po każdej zmianie produktów widocznej w sklepie
+pub async fn invalidate_merchant_feed(app_state: &AppState) {
+    app_state
+        .static_html_cache
+        .invalidate(MERCHANT_FEED_CACHE_KEY)
+        .await;
+}
+
+/// GET /feeds/google-merchant.xml
+pub async fn google_merchant_feed_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let cached_feed = app_state
+        .static_html_cache
+        .get(MERCHANT_FEED_CACHE_KEY)
+        .await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::StaticHtml, cached_feed.is_some());
+
+    let feed = match cached_feed {
+        Some(feed) => feed,
+        None => {
+            let feed = generate_merchant_feed(&app_state).await?;
+            app_state
+                .static_html_cache
+                .insert(MERCHANT_FEED_CACHE_KEY.to_string(), feed.clone())
+                .await;
+            app_state
+                .cache_stats
+                .record_insert(CacheName::StaticHtml, MERCHANT_FEED_CACHE_KEY);
+            feed
+        }
+    };
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/xml; charset=utf-8"),
+        )],
+        feed,
+    )
+        .into_response())
+}
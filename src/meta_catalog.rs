@@ -0,0 +1,190 @@
+// src/meta_catalog.rs
+
+// Katalog produktów dla Meta (Facebook / Instagram) w formacie CSV, żeby na Instagramie sklepu
+// można było oznaczać produkty. Oprócz dostępnych produktów katalog zawiera zarezerwowane
+// i niedawno sprzedane jako "out of stock" - Meta oznacza wtedy otagowane posty jako wyprzedane,
+// zamiast zostawiać je z nieaktualnym produktem.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures::TryStreamExt;
+
+use crate::cache_stats::CacheName;
+use crate::errors::AppError;
+use crate::merchant_feed::{
+    FEED_IMAGE_TRANSFORMATION, MAX_ADDITIONAL_IMAGES, google_gender, google_price,
+    google_product_category, shop_product_type,
+};
+use crate::models::{Product, ProductStatus};
+use crate::state::AppState;
+use crate::views::common::transform_cloudinary_url;
+
+/// Katalog trzymamy w cache'u listingów - jego krótki TTL (10 min) wyznacza, jak szybko
+/// sprzedany produkt zostanie oznaczony jako niedostępny, nawet bez jawnego unieważnienia.
+pub const META_CATALOG_CACHE_KEY: &str = "meta_catalog_csv";
+/// Jak długo sprzedany produkt zostaje w katalogu jako "out of stock"
+const SOLD_VISIBLE_DAYS: i32 = 30;
+const MAX_TITLE_LEN: usize = 200;
+const DEFAULT_BRAND: &str = "mess - all that vintage";
+
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "description",
+    "availability",
+    "condition",
+    "price",
+    "link",
+    "image_link",
+    "additional_image_link",
+    "brand",
+    "google_product_category",
+    "product_type",
+    "gender",
+    "age_group",
+    "size",
+    "color",
+    "material",
+];
+
+/// Pole CSV wg RFC 4180: w cudzysłowie, gdy zawiera przecinek, cudzysłów lub nową linię
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_row(product: &Product, base_url: &str) -> String {
+    let images: Vec<String> = product
+        .images
+        .iter()
+        .map(|image| transform_cloudinary_url(image, FEED_IMAGE_TRANSFORMATION))
+        .collect();
+    let availability = if product.status == ProductStatus::Available {
+        "in stock"
+    } else {
+        "out of stock"
+    };
+    let title: String = product.name.chars().take(MAX_TITLE_LEN).collect();
+    // Meta odrzuca produkty bez opisu
+    let description = if product.description.trim().is_empty() {
+        title.clone()
+    } else {
+        product.description.clone()
+    };
+
+    let fields = [
+        product.id.to_string(),
+        title,
+        description,
+        availability.to_string(),
+        "used".to_string(),
+        google_price(product.effective_price()),
+        format!("{}{}", base_url, product.public_path()),
+        images.first().cloned().unwrap_or_default(),
+        images
+            .iter()
+            .skip(1)
+            .take(MAX_ADDITIONAL_IMAGES)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(","),
+        product
+            .brand
+            .clone()
+            .unwrap_or_else(|| DEFAULT_BRAND.to_string()),
+        google_product_category(product.category).to_string(),
+        shop_product_type(product),
+        google_gender(product.gender).to_string(),
+        "adult".to_string(),
+        product.size.clone().unwrap_or_default(),
+        product.color.clone().unwrap_or_default(),
+        product.material.clone().unwrap_or_default(),
+    ];
+    fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Generuje katalog: dostępne, zarezerwowane i sprzedane w ostatnich 30 dniach produkty
+pub async fn generate_meta_catalog(app_state: &AppState) -> Result<String, AppError> {
+    let base_url = &app_state.public_base_url;
+    let mut csv_output = CSV_COLUMNS.join(",");
+    csv_output.push('\n');
+
+    let mut products = sqlx::query_as::<_, Product>(
+        r#"
+        SELECT p.* FROM products p
+        WHERE p.status = ANY($1)
+           OR (p.status = $2 AND EXISTS (
+                SELECT 1 FROM order_items oi
+                JOIN orders o ON o.id = oi.order_id
+                WHERE oi.product_id = p.id
+                  AND o.order_date >= NOW() - make_interval(days => $3)
+           ))
+        ORDER BY p.created_at DESC
+        "#,
+    )
+    .bind(vec![ProductStatus::Available, ProductStatus::Reserved])
+    .bind(ProductStatus::Sold)
+    .bind(SOLD_VISIBLE_DAYS)
+    .fetch(&app_state.db_pool);
+    let mut count = 0;
+    while let Some(product) = products.try_next().await? {
+        csv_output.push_str(&csv_row(&product, base_url));
+        csv_output.push('\n');
+        count += 1;
+    }
+
+    tracing::info!(
+        "[Meta catalog] Wygenerowano katalog z {} produktami.",
+        count
+    );
+    Ok(csv_output)
+}
+
+/// GET /feeds/meta-catalog.csv
+pub async fn meta_catalog_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let cached_catalog = app_state
+        .listing_fragment_cache
+        .get(META_CATALOG_CACHE_KEY)
+        .await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::ListingFragments, cached_catalog.is_some());
+
+    let catalog = match cached_catalog {
+        Some(catalog) => catalog,
+        None => {
+            let catalog = generate_meta_catalog(&app_state).await?;
+            app_state
+                .listing_fragment_cache
+                .insert(META_CATALOG_CACHE_KEY.to_string(), catalog.clone())
+                .await;
+            app_state
+                .cache_stats
+                .record_insert(CacheName::ListingFragments, META_CATALOG_CACHE_KEY);
+            catalog
+        }
+    };
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/csv; charset=utf-8"),
+        )],
+        catalog,
+    )
+        .into_response())
+}
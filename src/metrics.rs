@@ -0,0 +1,288 @@
+// src/metrics.rs
+
+// Metryki w formacie tekstowym Prometheusa pod `/internal/metrics`: czasy odpowiedzi per trasa,
+// wykorzystanie puli połączeń z bazą, trafienia cache'y moka i liczniki biznesowe.
+// Endpoint wymaga tokenu `METRICS_TOKEN` (nagłówek `Authorization: Bearer ...`); bez niego
+// metryki nie są wystawiane.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, HeaderValue, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use strum::IntoEnumIterator;
+
+use crate::cache_stats::CacheName;
+use crate::errors::AppError;
+use crate::load_shed::TrafficClass;
+use crate::state::AppState;
+
+/// Górne granice kubełków histogramu czasu odpowiedzi (sekundy)
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+/// Etykieta żądań bez dopasowanej trasy (404, pliki statyczne) - bez pełnych ścieżek,
+/// żeby skanery nie mnożyły serii
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RequestLabels {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Default)]
+struct LatencyHistogram {
+    /// Liczniki per kubełek (nieskumulowane), ostatni to `+Inf`
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Liczniki zbierane od startu serwera
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<HashMap<RequestLabels, LatencyHistogram>>,
+    orders_created: AtomicU64,
+    carts_abandoned: AtomicU64,
+}
+
+impl Metrics {
+    fn observe_request(&self, labels: RequestLabels, seconds: f64) {
+        if let Ok(mut requests) = self.requests.lock() {
+            requests.entry(labels).or_default().observe(seconds);
+        }
+    }
+
+    pub fn record_order_created(&self) {
+        self.orders_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Koszyki gości usunięte przez retencję po okresie bezczynności
+    pub fn record_carts_abandoned(&self, count: u64) {
+        self.carts_abandoned.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn render_requests(&self, output: &mut String) {
+        output.push_str("# HELP http_request_duration_seconds Czas obsługi żądania HTTP.\n");
+        output.push_str("# TYPE http_request_duration_seconds histogram\n");
+        let Ok(requests) = self.requests.lock() else {
+            return;
+        };
+        for (labels, histogram) in requests.iter() {
+            let label_set = format!(
+                "method=\"{}\",route=\"{}\",status=\"{}\"",
+                labels.method,
+                escape_label(&labels.route),
+                labels.status
+            );
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    output,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    label_set, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                output,
+                "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                label_set, histogram.count
+            );
+            let _ = writeln!(
+                output,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                label_set, histogram.sum
+            );
+            let _ = writeln!(
+                output,
+                "http_request_duration_seconds_count{{{}}} {}",
+                label_set, histogram.count
+            );
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn write_metric(output: &mut String, name: &str, metric_type: &str, help: &str, value: u64) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, metric_type);
+    let _ = writeln!(output, "{} {}", name, value);
+}
+
+/// Pełny zrzut metryk w formacie tekstowym Prometheusa
+pub fn render_metrics(app_state: &AppState) -> String {
+    let mut output = String::new();
+    app_state.metrics.render_requests(&mut output);
+
+    let pool = &app_state.db_pool;
+    write_metric(
+        &mut output,
+        "db_pool_connections",
+        "gauge",
+        "Otwarte połączenia z bazą.",
+        u64::from(pool.size()),
+    );
+    write_metric(
+        &mut output,
+        "db_pool_idle_connections",
+        "gauge",
+        "Bezczynne połączenia z bazą.",
+        pool.num_idle() as u64,
+    );
+    write_metric(
+        &mut output,
+        "db_pool_max_connections",
+        "gauge",
+        "Limit połączeń puli.",
+        u64::from(pool.options().get_max_connections()),
+    );
+
+    output.push_str("# HELP cache_lookups_total Odczyty cache'y moka.\n");
+    output.push_str("# TYPE cache_lookups_total counter\n");
+    for cache in CacheName::iter() {
+        let (hits, misses) = app_state.cache_stats.lookups(cache);
+        let _ = writeln!(
+            output,
+            "cache_lookups_total{{cache=\"{}\",result=\"hit\"}} {}",
+            cache.as_ref(),
+            hits
+        );
+        let _ = writeln!(
+            output,
+            "cache_lookups_total{{cache=\"{}\",result=\"miss\"}} {}",
+            cache.as_ref(),
+            misses
+        );
+    }
+
+    let metrics = &app_state.metrics;
+    write_metric(
+        &mut output,
+        "shop_orders_created_total",
+        "counter",
+        "Złożone zamówienia.",
+        metrics.orders_created.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut output,
+        "shop_carts_abandoned_total",
+        "counter",
+        "Porzucone koszyki gości usunięte przez retencję.",
+        metrics.carts_abandoned.load(Ordering::Relaxed),
+    );
+
+    let shedder = &app_state.load_shedder;
+    output.push_str("# HELP http_requests_in_flight Żądania obsługiwane teraz, per klasa ruchu.\n");
+    output.push_str("# TYPE http_requests_in_flight gauge\n");
+    for class in TrafficClass::iter() {
+        let _ = writeln!(
+            output,
+            "http_requests_in_flight{{class=\"{}\"}} {}",
+            class.as_ref(),
+            shedder.in_flight(class)
+        );
+    }
+    output.push_str("# HELP http_requests_shed_total Żądania odrzucone z powodu przeciążenia.\n");
+    output.push_str("# TYPE http_requests_shed_total counter\n");
+    for class in TrafficClass::iter() {
+        let _ = writeln!(
+            output,
+            "http_requests_shed_total{{class=\"{}\"}} {}",
+            class.as_ref(),
+            shedder.shed_total(class)
+        );
+    }
+    output
+}
+
+/// Middleware mierzące czas obsługi żądania; trasa to wzorzec (`/api/orders/{order_id}`),
+/// a nie konkretna ścieżka
+pub async fn track_http_metrics(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE.to_string(), |path| {
+            path.as_str().to_string()
+        });
+
+    let response = next.run(request).await;
+
+    app_state.metrics.observe_request(
+        RequestLabels {
+            method,
+            route,
+            status: response.status().as_u16(),
+        },
+        started.elapsed().as_secs_f64(),
+    );
+    response
+}
+
+fn token_hash(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// GET /internal/metrics
+pub async fn metrics_handler(
+    State(app_state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    // Bez skonfigurowanego tokenu endpoint nie istnieje
+    let expected_token = app_state
+        .metrics_token
+        .as_deref()
+        .ok_or(AppError::NotFound)?;
+    let provided_token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Porównujemy skróty, żeby czas porównania nie zdradzał wspólnego prefiksu tokenu
+    if token_hash(provided_token) != token_hash(expected_token) {
+        return Err(AppError::InvalidToken(
+            "Nieprawidłowy token metryk.".to_string(),
+        ));
+    }
+
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        )],
+        render_metrics(&app_state),
+    )
+        .into_response())
+}
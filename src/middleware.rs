@@ -1,15 +1,27 @@
 use std::sync::Arc;
 
-use axum::extract::FromRef;
+use axum::Json;
+use axum::extract::{FromRef, Request};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
 use axum::{RequestPartsExt, extract::FromRequestParts, http::request::Parts};
 use axum_extra::TypedHeader;
-use axum_extra::extract::cookie::CookieJar;
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
 use axum_extra::headers::{Authorization, authorization::Bearer};
+use serde_json::json;
 use uuid::Uuid;
 
 use crate::handlers::XGuestCartId;
+use crate::impersonation::apply_impersonation;
+use crate::two_factor::require_staff_two_factor;
 use crate::{auth::verify_jwt, auth_models::TokenClaims, errors::AppError, state::AppState};
 
+/// Dane z poprawnego JWT po uwzględnieniu weryfikacji dwuetapowej personelu i trybu podglądu
+fn effective_claims(claims: TokenClaims, parts: &Parts) -> TokenClaims {
+    apply_impersonation(require_staff_two_factor(claims), parts)
+}
+
 impl FromRequestParts<AppState> for TokenClaims {
     type Rejection = AppError;
 
@@ -22,14 +34,14 @@ impl FromRequestParts<AppState> for TokenClaims {
             parts.extract::<TypedHeader<Authorization<Bearer>>>().await
         {
             let token_data = verify_jwt(bearer.token(), &state.jwt_secret)?;
-            return Ok(token_data.claims);
+            return Ok(effective_claims(token_data.claims, parts));
         }
 
         // Metoda 2: Jeśli nie ma nagłówka, spróbuj z ciasteczka (dla F5)
         let cookies = parts.extract::<CookieJar>().await.unwrap();
         if let Some(cookie) = cookies.get("token") {
             let token_data = verify_jwt(cookie.value(), &state.jwt_secret)?;
-            return Ok(token_data.claims);
+            return Ok(effective_claims(token_data.claims, parts));
         }
 
         // Jeśli obie metody zawiodą, sprawdź czy to żądanie HTML i przekieruj
@@ -72,7 +84,10 @@ impl FromRequestParts<AppState> for OptionalTokenClaims {
                 Ok(claims_data) => {
                     tracing::debug!("Znaleziono poprawny token w nagłówku Authorization.");
                     // Zwracamy poprawny typ: OptionalTokenClaims
-                    return Ok(OptionalTokenClaims(Some(claims_data.claims)));
+                    return Ok(OptionalTokenClaims(Some(effective_claims(
+                        claims_data.claims,
+                        parts,
+                    ))));
                 }
                 Err(e) => {
                     tracing::warn!(
@@ -91,7 +106,10 @@ impl FromRequestParts<AppState> for OptionalTokenClaims {
                 Ok(claims_data) => {
                     tracing::debug!("Znaleziono poprawny token w ciasteczku 'token'.");
                     // Zwracamy poprawny typ: OptionalTokenClaims
-                    return Ok(OptionalTokenClaims(Some(claims_data.claims)));
+                    return Ok(OptionalTokenClaims(Some(effective_claims(
+                        claims_data.claims,
+                        parts,
+                    ))));
                 }
                 Err(e) => {
                     tracing::warn!(
@@ -130,13 +148,13 @@ where
             parts.extract::<TypedHeader<Authorization<Bearer>>>().await
         {
             let token_data = verify_jwt(bearer.token(), &state.jwt_secret)?;
-            return Ok(token_data.claims);
+            return Ok(effective_claims(token_data.claims, parts));
         }
 
         let cookies = CookieJar::from_headers(&parts.headers);
         if let Some(cookie) = cookies.get("token") {
             let token_data = verify_jwt(cookie.value(), &state.jwt_secret)?;
-            return Ok(token_data.claims);
+            return Ok(effective_claims(token_data.claims, parts));
         }
 
         if let Some(accept_header) = parts.headers.get(axum::http::header::ACCEPT) {
@@ -169,14 +187,20 @@ where
             parts.extract::<TypedHeader<Authorization<Bearer>>>().await
         {
             if let Ok(claims_data) = verify_jwt(bearer.token(), &state.jwt_secret) {
-                return Ok(OptionalTokenClaims(Some(claims_data.claims)));
+                return Ok(OptionalTokenClaims(Some(effective_claims(
+                    claims_data.claims,
+                    parts,
+                ))));
             }
         }
 
         let cookies = CookieJar::from_headers(&parts.headers);
         if let Some(cookie) = cookies.get("token") {
             if let Ok(claims_data) = verify_jwt(cookie.value(), &state.jwt_secret) {
-                return Ok(OptionalTokenClaims(Some(claims_data.claims)));
+                return Ok(OptionalTokenClaims(Some(effective_claims(
+                    claims_data.claims,
+                    parts,
+                ))));
             }
         }
 
@@ -212,3 +236,140 @@ where
         Ok(OptionalGuestCartId(None))
     }
 }
+
+/// Adres e-mail zalogowanego klienta, który jeszcze nie potwierdził konta (`None` dla gości
+/// i kont potwierdzonych). Kasa i "Moje konto" pokazują na tej podstawie przypomnienie.
+#[derive(Debug, Clone)]
+pub struct UnverifiedEmail(pub Option<String>);
+
+impl<S> FromRequestParts<S> for UnverifiedEmail
+where
+    Arc<AppState>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let OptionalTokenClaims(claims_opt) =
+            OptionalTokenClaims::from_request_parts(parts, state).await?;
+        let Some(claims) = claims_opt else {
+            return Ok(UnverifiedEmail(None));
+        };
+
+        let app_state = Arc::<AppState>::from_ref(state);
+        let email: Option<String> = sqlx::query_scalar(
+            "SELECT email FROM users WHERE id = $1 AND email_verified_at IS NULL",
+        )
+        .bind(claims.sub)
+        .fetch_optional(&app_state.db_pool)
+        .await?;
+        Ok(UnverifiedEmail(email))
+    }
+}
+
+/// Ciasteczko z tokenem CSRF (jeden na sesję przeglądarki)
+pub const CSRF_COOKIE: &str = "csrf_token";
+/// Nagłówek, w którym HTMX i `fetch` odsyłają token (`hx-headers` na `<body>`, zob. `response.rs`)
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// Ścieżki bez sprawdzania tokenu: webhooki płatności, beacony RUM (`sendBeacon` nie ustawia
+/// nagłówków) i API autoryzowane kluczem, a nie ciasteczkiem
+const CSRF_EXEMPT_PATH_PREFIXES: [&str; 3] = ["/api/payments/", "/api/rum", "/api/graphql"];
+
+/// Token CSRF z ciasteczka żądania - `None`, jeśli go nie ma albo ma zły format
+pub fn csrf_token_from_headers(headers: &HeaderMap) -> Option<String> {
+    CookieJar::from_headers(headers)
+        .get(CSRF_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .filter(|token| token.len() == 64 && token.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Porównanie w czasie niezależnym od miejsca pierwszej różnicy
+fn csrf_tokens_match(expected: &str, provided: &str) -> bool {
+    expected.len() == provided.len()
+        && expected
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Token sprawdzamy tylko tam, gdzie przeglądarka mogła dołączyć ciasteczka sesji bez wiedzy
+/// klienta: żądania zmieniające stan, z ciasteczkami, poza wyjątkami z listy
+fn requires_csrf_check(method: &Method, path: &str, headers: &HeaderMap) -> bool {
+    let is_safe_method = matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    );
+    !is_safe_method
+        && headers.contains_key(header::COOKIE)
+        && !CSRF_EXEMPT_PATH_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+}
+
+fn csrf_rejection() -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "HX-Trigger",
+        HeaderValue::from_static(
+            r#"{"showMessage": {"message": "Sesja strony wygasla. Odswiez strone i sprobuj ponownie.", "type": "error"}}"#,
+        ),
+    );
+    headers.insert("HX-Reswap", HeaderValue::from_static("none"));
+    (
+        StatusCode::FORBIDDEN,
+        headers,
+        Json(json!({ "error": "Nieprawidłowy token CSRF. Odśwież stronę i spróbuj ponownie." })),
+    )
+        .into_response()
+}
+
+/// Ochrona przed CSRF (double submit): każda sesja przeglądarki dostaje losowy token
+/// w ciasteczku HttpOnly, strona wstawia go do `<meta>` i `hx-headers`, a żądania zmieniające
+/// stan muszą odesłać go w nagłówku `X-CSRF-Token`
+pub async fn csrf_protect(mut request: Request, next: Next) -> Response {
+    let cookie_token = csrf_token_from_headers(request.headers());
+
+    if requires_csrf_check(request.method(), request.uri().path(), request.headers()) {
+        let header_token = request
+            .headers()
+            .get(CSRF_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let is_valid = matches!(
+            (cookie_token.as_deref(), header_token),
+            (Some(expected), Some(provided)) if csrf_tokens_match(expected, provided)
+        );
+        if !is_valid {
+            tracing::warn!(
+                "Odrzucono {} {}: brak lub niezgodny token CSRF",
+                request.method(),
+                request.uri().path()
+            );
+            return csrf_rejection();
+        }
+    }
+
+    // Pliki statyczne nie potrzebują tokenu - nie wydajemy go równolegle do żądania strony
+    if cookie_token.is_some() || request.uri().path().starts_with("/static/") {
+        return next.run(request).await;
+    }
+
+    // Pierwsza wizyta: dopisujemy nowy token do ciasteczek żądania, żeby `build_response`
+    // wstawił go już do tej strony, i ustawiamy ciasteczko w odpowiedzi
+    let new_token = hex::encode(rand::random::<[u8; 32]>());
+    if let Ok(value) = HeaderValue::from_str(&format!("{}={}", CSRF_COOKIE, new_token)) {
+        request.headers_mut().append(header::COOKIE, value);
+    }
+    let mut response = next.run(request).await;
+    let cookie = Cookie::build((CSRF_COOKIE, new_token))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .build();
+    if let Ok(value) = HeaderValue::from_str(&cookie.to_string()) {
+        response.headers_mut().append(header::SET_COOKIE, value);
+    }
+    response
+}
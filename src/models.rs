@@ -99,6 +99,8 @@ pub enum ProductGender {
     Serialize,
     Deserialize,
     PartialEq,
+    Eq,
+    Hash,
     Type,
     EnumString,
     Display,
@@ -142,6 +144,8 @@ pub enum Category {
 pub struct Product {
     pub id: Uuid,
     pub name: String,
+    /// Fragment publicznego adresu `/produkty/{slug}`, nadawany przy dodaniu produktu
+    pub slug: String,
     pub description: String,
     pub price: i64,
     pub gender: ProductGender,
@@ -150,181 +154,1301 @@ pub struct Product {
     pub status: ProductStatus,
     pub images: Vec<String>,
     pub on_sale: bool,
+    /// Rozmiar w formie podanej przez sprzedawcę, np. "M", "38", "W32 L34"
+    pub size: Option<String>,
+    pub brand: Option<String>,
+    pub color: Option<String>,
+    pub material: Option<String>,
+    /// Własny tytuł strony produktu; `None` = nazwa produktu (zob. `seo::SocialMeta`)
+    pub meta_title: Option<String>,
+    pub meta_description: Option<String>,
+    /// Zdjęcie podglądu linku (OpenGraph); `None` = pierwsze zdjęcie produktu
+    pub og_image: Option<String>,
+    /// Cena zaplanowanej promocji (zob. `sales`); poza okresem promocji obowiązuje `price`
+    pub sale_price: Option<i64>,
+    /// Początek promocji; `None` = od razu
+    pub sale_starts_at: Option<DateTime<Utc>>,
+    /// Koniec promocji; `None` = do odwołania
+    pub sale_ends_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Product {
+    /// Czy zaplanowana promocja trwa w podanej chwili
+    pub fn scheduled_sale_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.sale_price.is_some()
+            && self.sale_starts_at.is_none_or(|starts_at| starts_at <= now)
+            && self.sale_ends_at.is_none_or(|ends_at| ends_at > now)
+    }
+
+    /// Cena, którą klient zapłaci teraz (z trwającą promocją albo zwykła)
+    pub fn effective_price(&self) -> i64 {
+        match self.sale_price {
+            Some(sale_price) if self.scheduled_sale_active_at(Utc::now()) => sale_price,
+            _ => self.price,
+        }
+    }
+
+    /// Produkt "na okazji": ręcznie oznaczony albo z trwającą promocją
+    pub fn is_discounted(&self) -> bool {
+        self.on_sale || self.scheduled_sale_active_at(Utc::now())
+    }
+
+    /// Publiczny adres strony produktu (bez domeny)
+    pub fn public_path(&self) -> String {
+        format!("/produkty/{}", self.slug)
+    }
+
+    /// Wypełnione atrybuty odzieży jako pary (etykieta, wartość) - do widoku produktu
+    pub fn attributes(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("Rozmiar", &self.size),
+            ("Marka", &self.brand),
+            ("Kolor", &self.color),
+            ("Materiał", &self.material),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| value.as_deref().map(|v| (label, v)))
+        .collect()
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Type, EnumString, EnumIter, AsRefStr,
+)]
+#[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Role {
+    Admin,
+    /// Redaktor katalogu - produkty i opinie (zob. `permissions`)
+    Editor,
+    /// Magazyn - zamówienia, zwroty i reklamacje
+    Fulfilment,
+    Customer,
+}
+
+impl Role {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Role::Admin => "Administrator",
+            Role::Editor => "Redaktor",
+            Role::Fulfilment => "Magazyn",
+            Role::Customer => "Klient",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct User {
+    pub id: Uuid,
+    #[sqlx(rename = "email")]
+    pub email: String,
+    #[sqlx(rename = "password_hash")]
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    #[sqlx(rename = "role")]
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserPublic {
+    pub id: Uuid,
+    pub email: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for UserPublic {
+    fn from(user: User) -> Self {
+        UserPublic {
+            id: user.id,
+            email: user.email,
+            role: user.role,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+/// Status zamówienia
+#[derive(
+    Debug, Clone, Serialize, Deserialize, PartialEq, Type, EnumString, Display, AsRefStr, EnumIter,
+)]
+#[sqlx(type_name = "order_status_enum")]
+#[sqlx(rename_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum OrderStatus {
+    #[strum(serialize = "Oczekujące")]
+    Pending,
+    #[strum(serialize = "W trakcie realizacji")]
+    Processing,
+    #[strum(serialize = "Wysłane")]
+    Shipped,
+    #[strum(serialize = "Dostarczone")]
+    Delivered,
+    #[strum(serialize = "Anulowane")]
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn to_form_value(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "Pending",
+            OrderStatus::Processing => "Processing",
+            OrderStatus::Shipped => "Shipped",
+            OrderStatus::Delivered => "Delivered",
+            OrderStatus::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Statusy, do których zamówienie może przejść z bieżącego.
+    /// Zamówienia dostarczone i anulowane są zamknięte - nie zmieniają już statusu.
+    pub fn allowed_transitions(&self) -> &'static [OrderStatus] {
+        match self {
+            OrderStatus::Pending => &[OrderStatus::Processing, OrderStatus::Cancelled],
+            OrderStatus::Processing => &[OrderStatus::Shipped, OrderStatus::Cancelled],
+            OrderStatus::Shipped => &[OrderStatus::Delivered],
+            OrderStatus::Delivered => &[],
+            OrderStatus::Cancelled => &[],
+        }
+    }
+
+    pub fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        self.allowed_transitions().contains(next)
+    }
+}
+
+/// Wpis w historii zmian statusu zamówienia
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct OrderStatusHistory {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    /// `None` dla pierwszego wpisu (złożenie zamówienia)
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    /// Admin, który zmienił status; `None` dla zmian automatycznych (np. płatność online)
+    pub changed_by: Option<Uuid>,
+    pub note: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Reprezentuje pojedyńczą pozycję w zamówieniu
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, Validate)]
+pub struct OrderItem {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub product_id: Uuid,
+    pub price_at_purchase: i64,
+}
+
+/// Reprezentuje zamówienie
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, Validate)]
+pub struct Order {
+    pub id: Uuid,
+    /// Czytelny numer zamówienia, np. "MESS-2025-00123" - zob. `order_numbers`
+    pub order_number: String,
+    pub user_id: Option<Uuid>,
+    pub order_date: DateTime<Utc>,
+    pub status: OrderStatus,
+    pub total_price: i64,
+
+    #[validate(length(min = 1, max = 100))]
+    pub shipping_first_name: String,
+    #[validate(length(min = 1, max = 100))]
+    pub shipping_last_name: String,
+
+    #[validate(length(min = 1, max = 255))]
+    pub shipping_address_line1: String,
+    #[validate(length(max = 255))]
+    pub shipping_address_line2: Option<String>,
+    #[validate(length(min = 1, max = 100))]
+    pub shipping_city: String,
+    #[validate(length(min = 1, max = 20))]
+    pub shipping_postal_code: String,
+    #[validate(length(min = 1, max = 100))]
+    pub shipping_country: String,
+    #[validate(length(min = 1, max = 30))]
+    pub shipping_phone: String,
+    pub payment_method: Option<PaymentMethod>,
+    pub shipping_method_name: Option<String>,
+    /// Kod Paczkomatu (np. "KRA010M") dla dostawy InPost
+    pub inpost_locker_code: Option<String>,
+    /// Adres Paczkomatu w chwili złożenia zamówienia (np. "Długa 5, 31-147 Kraków")
+    pub inpost_locker_address: Option<String>,
+    /// Kod rabatowy użyty przy zamówieniu i kwota rabatu od produktów (w groszach)
+    pub coupon_id: Option<Uuid>,
+    pub discount_amount: i64,
+    /// Część zamówienia opłacona kartą podarunkową; `total_price` to reszta do zapłaty
+    pub gift_card_amount: i64,
+
+    #[validate(email)]
+    pub guest_email: Option<String>,
+    pub guest_session_id: Option<Uuid>,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Order {
+    /// Tytuł przelewu, po którym dopasowujemy wpłatę do zamówienia - numer zamówienia.
+    /// Zamówienia sprzed wprowadzenia numerów miały tytuł "MESS-" + 8 znaków ID.
+    pub fn payment_reference(&self) -> String {
+        self.order_number.clone()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, sqlx::Type, Display, EnumString)]
+#[sqlx(type_name = "payment_method_enum", rename_all = "lowercase")] // Mapowanie na typ SQL i nazwy wariantów w DB
+#[strum(ascii_case_insensitive)]
+pub enum PaymentMethod {
+    #[strum(serialize = "BLIK")]
+    Blik,
+    #[strum(serialize = "Przelew tradycyjny", serialize = "transfer")]
+    Transfer,
+    #[strum(serialize = "Przelewy24", serialize = "przelewy24")]
+    Przelewy24,
+}
+
+// --- STRUKTURY PAYLOAD DLA HANDLERÓW ZAMÓWIEŃ ---
+
+/// Reprezentuje pojedyńczy produkt w payloadzie tworzenia zamówienia
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateOrderFromCartPayload {
+    #[validate(length(min = 1, max = 255, message = "Linia adresu wysyłki jest wymagana"))]
+    pub shipping_address_line1: String,
+
+    #[validate(length(max = 255))]
+    pub shipping_address_line2: Option<String>,
+
+    #[validate(length(min = 1, max = 100, message = "Miasto wysyłki jest wymagane"))]
+    pub shipping_city: String,
+
+    #[validate(length(min = 1, max = 20, message = "Kod pocztowy wysyłki jest wymagany"))]
+    pub shipping_postal_code: String,
+
+    #[validate(length(min = 1, max = 100, message = "Kraj wysyłki jest wymagany"))]
+    pub shipping_country: String,
+}
+
+/// Payload do aktualizacji statusu zamówienia
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct UpdateOrderStatusPayload {
+    pub status: OrderStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderItemDetailsPublic {
+    pub order_item_id: Uuid,
+    #[serde(flatten)]
+    pub product: Product,
+    pub price_at_purchase: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrderDetailsResponse {
+    #[serde(flatten)]
+    pub order: Order,
+    pub items: Vec<OrderItemDetailsPublic>,
+}
+
+/// Zwrot środków za pozycję usuniętą z zamówienia przed wysyłką albo przyjętą w zwrocie
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OrderRefund {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub product_id: Uuid,
+    pub amount: i64,
+    pub reason: String,
+    pub created_by: Option<Uuid>,
+    pub refunded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Payload do usunięcia pozycji z zamówienia (np. produkt uszkodzony)
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct RemoveOrderItemPayload {
+    #[validate(length(min = 1, max = 500, message = "Podaj powód usunięcia pozycji."))]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InpostPointsQuery {
+    pub q: String,
+}
+
+/// Kod pocztowy z kroku dostawy, dla którego podpowiadamy najbliższe Paczkomaty
+#[derive(Debug, Clone, Deserialize)]
+pub struct InpostSuggestionsQuery {
+    pub shipping_postal_code: Option<String>,
+}
+
+/// Payload akcji na pojedynczym zdjęciu produktu (np. usunięcie tła)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductImageActionPayload {
+    pub image_url: String,
+}
+
+/// Rodzaj danych klienta, które można oflagować jako problematyczne
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
+)]
+#[sqlx(type_name = "customer_flag_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum CustomerFlagType {
+    #[strum(to_string = "E-mail", serialize = "email")]
+    Email,
+    #[strum(to_string = "Telefon", serialize = "phone")]
+    Phone,
+    #[strum(to_string = "Adres", serialize = "address")]
+    Address,
+}
+
+impl CustomerFlagType {
+    pub fn to_form_value(&self) -> &'static str {
+        match self {
+            CustomerFlagType::Email => "email",
+            CustomerFlagType::Phone => "phone",
+            CustomerFlagType::Address => "address",
+        }
+    }
+
+    /// Sprowadza wartość do postaci porównywalnej niezależnie od zapisu
+    /// (wielkość liter, spacje, myślniki, prefiks +48 itp.).
+    pub fn normalize(&self, raw: &str) -> String {
+        match self {
+            CustomerFlagType::Email => raw.trim().to_lowercase(),
+            CustomerFlagType::Phone => {
+                let digits: String = raw.chars().filter(|c| c.is_ascii_digit()).collect();
+                match digits.strip_prefix("48") {
+                    Some(local) if local.len() == 9 => local.to_string(),
+                    _ => digits,
+                }
+            }
+            CustomerFlagType::Address => raw
+                .to_lowercase()
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Flaga ostrzegawcza dla e-maila, telefonu lub adresu klienta
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CustomerFlag {
+    pub id: Uuid,
+    pub flag_type: CustomerFlagType,
+    pub value: String,
+    pub reason: String,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateCustomerFlagPayload {
+    pub flag_type: CustomerFlagType,
+    #[validate(length(min = 1, max = 255, message = "Podaj oflagowaną wartość."))]
+    pub value: String,
+    #[validate(length(min = 1, max = 500, message = "Podaj powód oflagowania."))]
+    pub reason: String,
+}
+
+/// Tag klienta nadawany ręcznie w panelu
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
+)]
+#[sqlx(type_name = "customer_tag", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum CustomerTag {
+    #[strum(to_string = "VIP", serialize = "vip")]
+    Vip,
+    #[strum(to_string = "Hurt", serialize = "wholesale")]
+    Wholesale,
+    #[strum(to_string = "Problematyczny", serialize = "problematic")]
+    Problematic,
+}
+
+impl CustomerTag {
+    pub fn to_form_value(&self) -> &'static str {
+        match self {
+            CustomerTag::Vip => "vip",
+            CustomerTag::Wholesale => "wholesale",
+            CustomerTag::Problematic => "problematic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomerTagPayload {
+    pub tag: CustomerTag,
+}
+
+/// Formularz edycji strony informacyjnej (zob. `cms`); checkbox `published` jest tylko, gdy zaznaczony
+#[derive(Debug, Clone, Deserialize)]
+pub struct StaticPagePayload {
+    pub title: String,
+    pub body: String,
+    pub published: Option<String>,
+}
+
+/// Sekcja strony głównej nad listą nowości (zob. `homepage`)
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Type, EnumString, EnumIter, AsRefStr,
+)]
+#[sqlx(type_name = "homepage_section", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum HomepageSection {
+    /// Baner ze zdjęciem i tekstem
+    Hero,
+    /// Karuzela wybranych produktów
+    Featured,
+    /// Karuzela najczęściej oglądanych produktów (zob. `product_views`)
+    Popular,
+}
+
+impl HomepageSection {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HomepageSection::Hero => "Baner",
+            HomepageSection::Featured => "Wyróżnione produkty",
+            HomepageSection::Popular => "Najpopularniejsze produkty",
+        }
+    }
+}
+
+/// Formularz karuzeli (wyróżnione, najpopularniejsze); checkbox `enabled` jest tylko, gdy zaznaczony
+#[derive(Debug, Clone, Deserialize)]
+pub struct HomepageFeaturedPayload {
+    pub title: String,
+    pub enabled: Option<String>,
+}
+
+/// Produkt dodawany do karuzeli: adres strony produktu, slug albo ID
+#[derive(Debug, Clone, Deserialize)]
+pub struct HomepageFeaturedProductPayload {
+    pub product: String,
+}
+
+/// Kierunek przesunięcia elementu na uporządkowanej liście w panelu (`.../przesun/gora|dol`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MoveDirection {
+    #[serde(rename = "gora")]
+    Up,
+    #[serde(rename = "dol")]
+    Down,
+}
+
+/// Formularz pytania FAQ; checkbox `visible` jest tylko, gdy zaznaczony
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedirectPayload {
+    pub source_path: String,
+    pub target_path: String,
+    pub status_code: i16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FaqItemPayload {
+    pub question: String,
+    pub answer: String,
+    pub visible: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomerNotePayload {
+    pub content: String,
+}
+
+/// Klient na liście w panelu: suma i liczba opłaconych zamówień oraz tagi
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CustomerSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub orders_count: i64,
+    /// W groszach
+    pub total_spent: i64,
+    pub last_order_at: Option<DateTime<Utc>>,
+    pub tags: Vec<CustomerTag>,
+}
+
+/// Filtry segmentu z formularza listy klientów. Kwota w złotych; puste pola = bez ograniczeń.
+/// `q` to wyszukiwanie po e-mailu lub telefonie - nie jest częścią segmentu.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CustomerSegmentParams {
+    pub q: Option<String>,
+    pub tag: Option<String>,
+    pub min_spent: Option<String>,
+    pub min_orders: Option<String>,
+}
+
+/// Nowa rola z listy użytkowników (wartość jak w bazie, np. "editor")
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserRolePayload {
+    pub role: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserDisabledPayload {
+    pub disabled: bool,
+}
+
+/// Filtry listy użytkowników w panelu: fragment e-maila i rola
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdminUsersParams {
+    pub q: Option<String>,
+    pub role: Option<String>,
+}
+
+/// Przeliczenie rozmiaru z metki na współczesny rozmiar, np. FR "38" -> "M".
+/// Brak kategorii/płci oznacza mapowanie dla wszystkich.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SizeMapping {
+    pub id: Uuid,
+    pub category: Option<Category>,
+    pub gender: Option<ProductGender>,
+    pub size_system: String,
+    pub label_size: String,
+    pub modern_size: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateSizeMappingPayload {
+    /// Pusta wartość z formularza - wszystkie kategorie
+    #[serde(default)]
+    pub category: String,
+    /// Pusta wartość z formularza - obie płcie
+    #[serde(default)]
+    pub gender: String,
+    #[validate(length(min = 1, max = 20, message = "Podaj rozmiarówkę metki."))]
+    pub size_system: String,
+    #[validate(length(min = 1, max = 30, message = "Podaj rozmiar z metki."))]
+    pub label_size: String,
+    #[validate(length(min = 1, max = 30, message = "Podaj współczesny rozmiar."))]
+    pub modern_size: String,
+}
+
+/// Wskazówka pielęgnacji dla materiału, np. "Wełna" -> "pierz ręcznie...".
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CareInstruction {
+    pub id: Uuid,
+    pub material: String,
+    pub instructions: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CareInstructionPayload {
+    #[validate(length(min = 2, max = 40, message = "Podaj nazwę materiału."))]
+    pub material: String,
+    #[validate(length(
+        min = 5,
+        max = 500,
+        message = "Wskazówka musi mieć od 5 do 500 znaków."
+    ))]
+    pub instructions: String,
+}
+
+/// Waga powiadomienia w centrum powiadomień admina
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
+)]
+#[sqlx(type_name = "admin_notification_level", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum AdminNotificationLevel {
+    #[strum(to_string = "Informacja", serialize = "info")]
+    Info,
+    #[strum(to_string = "Ostrzeżenie", serialize = "warning")]
+    Warning,
+    #[strum(to_string = "Błąd", serialize = "error")]
+    Error,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
+)]
+#[sqlx(type_name = "return_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[strum(ascii_case_insensitive)]
+pub enum ReturnStatus {
+    #[strum(to_string = "Zgłoszony", serialize = "requested")]
+    Requested,
+    #[strum(to_string = "Przyjęty", serialize = "approved")]
+    Approved,
+    #[strum(to_string = "Odrzucony", serialize = "rejected")]
+    Rejected,
+}
+
+/// Zgłoszenie zwrotu (odstąpienie od umowy) dla wybranych pozycji zamówienia
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ReturnRequest {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub status: ReturnStatus,
+    pub reason: String,
+    /// Numer konta do zwrotu pieniędzy (IBAN, np. "PL61 1090 1014 0000 0712 1981 2874")
+    pub bank_account: String,
+    /// Uzasadnienie decyzji admina, pokazywane klientowi
+    pub admin_note: Option<String>,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Etykieta zwrotna InPost kupiona przez klienta; cena jest potrącana ze zwrotu
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ReturnLabel {
+    pub return_id: Uuid,
+    pub shipment_id: i64,
+    pub tracking_number: Option<String>,
+    pub price: i64,
+    pub emailed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pozycja zwrotu; kwota to cena zakupu pozycji
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ReturnItem {
+    pub return_id: Uuid,
+    pub order_item_id: Uuid,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnDecisionPayload {
+    pub admin_note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminReturnsQuery {
+    pub status: Option<String>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
+)]
+#[sqlx(type_name = "complaint_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[strum(ascii_case_insensitive)]
+pub enum ComplaintStatus {
+    #[strum(to_string = "Nowa", serialize = "open")]
+    Open,
+    #[strum(to_string = "Rozpatrywana", serialize = "in_review")]
+    InReview,
+    #[strum(to_string = "Uznana", serialize = "accepted")]
+    Accepted,
+    #[strum(to_string = "Odrzucona", serialize = "rejected")]
+    Rejected,
+}
+
+impl ComplaintStatus {
+    /// Reklamacja uznana albo odrzucona - sklep udzielił odpowiedzi
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, ComplaintStatus::Accepted | ComplaintStatus::Rejected)
+    }
+}
+
+/// Reklamacja jednej pozycji zamówienia (z nazwą produktu do wyświetlenia)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Complaint {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub order_item_id: Uuid,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub user_id: Option<Uuid>,
+    pub status: ComplaintStatus,
+    pub description: String,
+    /// Adresy zdjęć w Cloudinary
+    pub photos: Vec<String>,
+    /// Odpowiedź sklepu pokazywana klientowi
+    pub resolution: Option<String>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Type)]
-#[sqlx(type_name = "user_role", rename_all = "lowercase")]
-pub enum Role {
-    Admin,
-    Customer,
+#[derive(Debug, Deserialize)]
+pub struct ComplaintStatusPayload {
+    pub status: ComplaintStatus,
+    pub resolution: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminComplaintsQuery {
+    pub status: Option<String>,
+}
+
+/// Miesiąc rejestru sprzedaży w formacie "RRRR-MM"
+#[derive(Debug, Deserialize)]
+pub struct SalesRegisterQuery {
+    pub month: Option<String>,
+}
+
+/// Filtry raportu wydajności (RUM): okres w dniach i urządzenie ("mobile"/"desktop")
+#[derive(Debug, Deserialize)]
+pub struct RumReportQuery {
+    pub days: Option<i64>,
+    pub device: Option<String>,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
+)]
+#[sqlx(type_name = "review_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[strum(ascii_case_insensitive)]
+pub enum ReviewStatus {
+    #[strum(to_string = "Do moderacji", serialize = "pending")]
+    Pending,
+    #[strum(to_string = "Opublikowana", serialize = "approved")]
+    Approved,
+    #[strum(to_string = "Odrzucona", serialize = "rejected")]
+    Rejected,
+}
+
+/// Opinia klienta o produkcie (z nazwą produktu do wyświetlenia)
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProductReview {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub user_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    /// 1-5 gwiazdek
+    pub rating: i16,
+    pub content: String,
+    pub author_name: String,
+    pub verified_purchase: bool,
+    pub status: ReviewStatus,
+    pub moderated_by: Option<Uuid>,
+    pub moderated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductReviewPayload {
+    pub rating: i16,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminReviewsQuery {
+    pub status: Option<String>,
+}
+
+/// Ręczna rezerwacja produktu dla konkretnego klienta z osobistym linkiem zakupu
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ProductHold {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub token: Uuid,
+    pub customer_label: String,
+    pub note: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateProductHoldPayload {
+    #[validate(length(min = 1, max = 100, message = "Podaj, dla kogo jest rezerwacja."))]
+    pub customer_label: String,
+    #[validate(range(min = 1, max = 14, message = "Rezerwacja może trwać od 1 do 14 dni."))]
+    pub days: i64,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Token osobistego linku rezerwacji przekazywany przy dodawaniu produktu do koszyka
+#[derive(Debug, Default, Deserialize)]
+pub struct CartHoldQuery {
+    pub hold: Option<Uuid>,
+}
+
+/// Akcja masowa na zaznaczonych produktach w panelu admina (wartość pola `action`)
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, EnumIter, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum ProductBulkAction {
+    Archive,
+    MarkSold,
+    ToggleOnSale,
+    ChangePrice,
+}
+
+impl ProductBulkAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProductBulkAction::Archive => "Archiwizuj",
+            ProductBulkAction::MarkSold => "Oznacz jako sprzedane",
+            ProductBulkAction::ToggleOnSale => "Przełącz promocję",
+            ProductBulkAction::ChangePrice => "Zmień cenę o %",
+        }
+    }
+}
+
+/// Wynik akcji masowej dla jednego produktu; `error` to powód pominięcia produktu
+#[derive(Debug, Clone)]
+pub struct ProductBulkOutcome {
+    pub product_id: Uuid,
+    pub product_name: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Żądanie unieważnienia cache'u z zewnętrznych narzędzi (import CSV, synchronizacja
+/// z marketplace'ami). Bez `caches` dotyczy wszystkich cache'y; we wzorcach `*` to dowolny ciąg.
+#[derive(Debug, Deserialize)]
+pub struct CacheInvalidationPayload {
+    #[serde(default)]
+    pub caches: Vec<String>,
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheInvalidationResponse {
+    /// Liczba usuniętych wpisów we wszystkich wskazanych cache'ach
+    pub invalidated: usize,
+    pub caches: Vec<String>,
 }
 
+/// Klucz publicznego API katalogu; sam klucz nie jest przechowywany, tylko jego skrót
 #[derive(Debug, Clone, Serialize, sqlx::FromRow)]
-pub struct User {
+pub struct ApiKey {
     pub id: Uuid,
-    #[sqlx(rename = "email")]
-    pub email: String,
-    #[sqlx(rename = "password_hash")]
+    pub name: String,
+    pub key_prefix: String,
     #[serde(skip_serializing)]
-    pub password_hash: String,
-    #[sqlx(rename = "role")]
-    pub role: Role,
+    pub key_hash: String,
     pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct UserPublic {
+/// Formularz tworzenia klucza API w panelu admina
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKeyPayload {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Nazwa klucza musi mieć od 1 do 100 znaków."
+    ))]
+    pub name: String,
+}
+
+/// Faktura VAT marża wystawiona do zamówienia; numer ciągły w obrębie roku (np. "FM/2025/0007")
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Invoice {
     pub id: Uuid,
-    pub email: String,
-    pub role: Role,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub order_id: Uuid,
+    pub year: i32,
+    pub sequence: i32,
+    pub number: String,
+    pub issued_at: DateTime<Utc>,
 }
 
-impl From<User> for UserPublic {
-    fn from(user: User) -> Self {
-        UserPublic {
-            id: user.id,
-            email: user.email,
-            role: user.role,
-            created_at: user.created_at,
-            updated_at: user.updated_at,
-        }
-    }
+/// Powiadomienie dla admina (np. nieudana kopia zapasowa)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AdminNotification {
+    pub id: Uuid,
+    pub level: AdminNotificationLevel,
+    pub title: String,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
 }
 
-/// Status zamówienia
+/// Rodzaj rabatu udzielanego przez kod rabatowy
 #[derive(
-    Debug, Clone, Serialize, Deserialize, PartialEq, Type, EnumString, Display, AsRefStr, EnumIter,
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Type,
+    EnumString,
+    Display,
+    EnumIter,
+    AsRefStr,
 )]
-#[sqlx(type_name = "order_status_enum")]
-#[sqlx(rename_all = "lowercase")]
+#[sqlx(type_name = "coupon_discount_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 #[strum(ascii_case_insensitive)]
-pub enum OrderStatus {
-    #[strum(serialize = "Oczekujące")]
-    Pending,
-    #[strum(serialize = "W trakcie realizacji")]
-    Processing,
-    #[strum(serialize = "Wysłane")]
-    Shipped,
-    #[strum(serialize = "Dostarczone")]
-    Delivered,
-    #[strum(serialize = "Anulowane")]
-    Cancelled,
+pub enum CouponDiscountType {
+    #[strum(to_string = "Procentowy", serialize = "percentage")]
+    Percentage,
+    #[strum(to_string = "Kwotowy", serialize = "fixed")]
+    Fixed,
 }
 
-impl OrderStatus {
+impl CouponDiscountType {
     pub fn to_form_value(&self) -> &'static str {
         match self {
-            OrderStatus::Pending => "Pending",
-            OrderStatus::Processing => "Processing",
-            OrderStatus::Shipped => "Shipped",
-            OrderStatus::Delivered => "Delivered",
-            OrderStatus::Cancelled => "Cancelled",
+            CouponDiscountType::Percentage => "percentage",
+            CouponDiscountType::Fixed => "fixed",
         }
     }
 }
 
-/// Reprezentuje pojedyńczą pozycję w zamówieniu
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, Validate)]
-pub struct OrderItem {
+/// Kod rabatowy. `value` to procent (1-100) albo kwota w groszach, zależnie od `discount_type`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Coupon {
     pub id: Uuid,
-    pub order_id: Uuid,
-    pub product_id: Uuid,
-    pub price_at_purchase: i64,
+    pub code: String,
+    pub discount_type: CouponDiscountType,
+    pub value: i64,
+    pub min_order_value: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub single_use_per_user: bool,
+    pub is_active: bool,
+    pub usage_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Kryteria segmentu klientów - kod działa tylko dla zalogowanych klientów, którzy je spełniają
+    pub segment_tag: Option<CustomerTag>,
+    pub segment_min_spent: Option<i64>,
+    pub segment_min_orders: Option<i32>,
+    /// Kampania, z której wygenerowano kod (kody kampanii nie są pokazywane na liście kodów)
+    pub campaign_id: Option<Uuid>,
+    /// Łączny limit użyć kodu; `None` = bez limitu
+    pub max_uses: Option<i32>,
 }
 
-/// Reprezentuje zamówienie
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, Validate)]
-pub struct Order {
-    pub id: Uuid,
-    pub user_id: Option<Uuid>,
-    pub order_date: DateTime<Utc>,
-    pub status: OrderStatus,
-    pub total_price: i64,
+impl Coupon {
+    /// Normalizuje kod wpisany przez klienta lub admina (bez spacji, wielkie litery).
+    pub fn normalize_code(raw: &str) -> String {
+        raw.trim().to_uppercase()
+    }
 
-    #[validate(length(min = 1, max = 100))]
-    pub shipping_first_name: String,
-    #[validate(length(min = 1, max = 100))]
-    pub shipping_last_name: String,
+    /// Kwota rabatu (w groszach) dla podanej wartości produktów - nigdy większa niż ta wartość.
+    pub fn discount_for(&self, items_total: i64) -> i64 {
+        let discount = match self.discount_type {
+            CouponDiscountType::Percentage => items_total * self.value / 100,
+            CouponDiscountType::Fixed => self.value,
+        };
+        discount.clamp(0, items_total)
+    }
 
-    #[validate(length(min = 1, max = 255))]
-    pub shipping_address_line1: String,
-    #[validate(length(max = 255))]
-    pub shipping_address_line2: Option<String>,
-    #[validate(length(min = 1, max = 100))]
-    pub shipping_city: String,
-    #[validate(length(min = 1, max = 20))]
-    pub shipping_postal_code: String,
-    #[validate(length(min = 1, max = 100))]
-    pub shipping_country: String,
-    #[validate(length(min = 1, max = 30))]
-    pub shipping_phone: String,
-    pub payment_method: Option<PaymentMethod>,
-    pub shipping_method_name: Option<String>,
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+}
 
-    #[validate(email)]
-    pub guest_email: Option<String>,
-    pub guest_session_id: Option<Uuid>,
+/// Formularz tworzenia i edycji kodu rabatowego w panelu admina
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CouponPayload {
+    #[validate(length(min = 3, max = 40, message = "Kod musi mieć od 3 do 40 znaków."))]
+    pub code: String,
+    pub discount_type: CouponDiscountType,
+    #[validate(range(min = 1, message = "Wartość rabatu musi być większa od zera."))]
+    pub value: i64,
+    #[validate(range(min = 0, message = "Minimalna wartość zamówienia nie może być ujemna."))]
+    pub min_order_value: i64,
+    /// Data ważności w formacie RRRR-MM-DD (pole `input type="date"`), puste = bezterminowo
+    pub expires_at: Option<String>,
+    /// Checkbox - obecny w formularzu tylko, gdy zaznaczony
+    pub single_use_per_user: Option<String>,
+    /// Segment klientów: tag, min. wydana kwota (gr) i min. liczba zamówień; puste = bez ograniczeń
+    pub segment_tag: Option<String>,
+    pub segment_min_spent: Option<String>,
+    pub segment_min_orders: Option<String>,
+}
 
+/// Kampania jednorazowych kodów rabatowych wraz ze statystykami użycia
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct CouponCampaignSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub code_prefix: String,
+    pub discount_type: CouponDiscountType,
+    pub value: i64,
+    pub min_order_value: i64,
+    pub expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub codes_count: i64,
+    pub used_count: i64,
+    /// Suma wartości zamówień z kodami kampanii (w groszach)
+    pub orders_total: i64,
+    pub discount_total: i64,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, sqlx::Type, Display, EnumString)]
-#[sqlx(type_name = "payment_method_enum", rename_all = "lowercase")] // Mapowanie na typ SQL i nazwy wariantów w DB
-#[strum(ascii_case_insensitive)]
-pub enum PaymentMethod {
-    #[strum(serialize = "BLIK")]
-    Blik,
-    #[strum(serialize = "Przelew tradycyjny", serialize = "transfer")]
-    Transfer,
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CreateCouponCampaignPayload {
+    #[validate(length(
+        min = 3,
+        max = 100,
+        message = "Nazwa kampanii musi mieć od 3 do 100 znaków."
+    ))]
+    pub name: String,
+    #[validate(length(min = 2, max = 12, message = "Prefiks musi mieć od 2 do 12 znaków."))]
+    pub code_prefix: String,
+    #[validate(range(
+        min = 1,
+        max = 1000,
+        message = "Liczba kodów musi być z przedziału 1-1000."
+    ))]
+    pub count: i64,
+    pub discount_type: CouponDiscountType,
+    #[validate(range(min = 1, message = "Wartość rabatu musi być większa od zera."))]
+    pub value: i64,
+    #[validate(range(min = 0, message = "Minimalna wartość zamówienia nie może być ujemna."))]
+    pub min_order_value: i64,
+    /// Data ważności w formacie RRRR-MM-DD, puste = bezterminowo
+    pub expires_at: Option<String>,
 }
 
-// --- STRUKTURY PAYLOAD DLA HANDLERÓW ZAMÓWIEŃ ---
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplyCouponPayload {
+    pub coupon_code: String,
+}
 
-/// Reprezentuje pojedyńczy produkt w payloadzie tworzenia zamówienia
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplyGiftCardPayload {
+    pub gift_card_code: String,
+}
+
+/// Formularz zakupu karty podarunkowej (`/karta-podarunkowa`)
 #[derive(Debug, Clone, Deserialize, Validate)]
-pub struct CreateOrderFromCartPayload {
-    #[validate(length(min = 1, max = 255, message = "Linia adresu wysyłki jest wymagana"))]
-    pub shipping_address_line1: String,
+pub struct GiftCardPurchasePayload {
+    pub amount: i64,
+    #[validate(email(message = "Podaj prawidłowy adres e-mail osoby obdarowanej."))]
+    pub recipient_email: String,
+    #[validate(length(
+        max = 100,
+        message = "Imię obdarowanego może mieć najwyżej 100 znaków."
+    ))]
+    pub recipient_name: Option<String>,
+    #[validate(email(message = "Podaj prawidłowy adres e-mail kupującego."))]
+    pub purchaser_email: String,
+    pub message: Option<String>,
+}
 
-    #[validate(length(max = 255))]
+/// Szkic zamówienia - stan wieloetapowej kasy zapisany po stronie serwera
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct CheckoutDraft {
+    pub id: Uuid,
+    /// `None`, gdy koszyk został już usunięty (koszyk gościa po złożeniu zamówienia)
+    pub cart_id: Option<Uuid>,
+    pub completed_steps: i16,
+    pub guest_email: Option<String>,
+    pub shipping_first_name: Option<String>,
+    pub shipping_last_name: Option<String>,
+    pub shipping_phone: Option<String>,
+    pub shipping_address_line1: Option<String>,
     pub shipping_address_line2: Option<String>,
+    pub shipping_city: Option<String>,
+    pub shipping_postal_code: Option<String>,
+    pub shipping_country: Option<String>,
+    pub shipping_method_key: Option<String>,
+    pub inpost_locker_code: Option<String>,
+    pub inpost_locker_address: Option<String>,
+    pub payment_method: Option<String>,
+    pub coupon_code: Option<String>,
+    pub gift_card_code: Option<String>,
+    /// Zamówienie złożone z tego szkicu
+    pub order_id: Option<Uuid>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
 
-    #[validate(length(min = 1, max = 100, message = "Miasto wysyłki jest wymagane"))]
-    pub shipping_city: String,
+/// Pola wysyłane z jednego kroku kasy. Każdy krok wypełnia tylko swoją część.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CheckoutStepPayload {
+    pub guest_email: Option<String>,
+    pub shipping_first_name: Option<String>,
+    pub shipping_last_name: Option<String>,
+    pub shipping_phone: Option<String>,
+    pub shipping_address_line1: Option<String>,
+    pub shipping_address_line2: Option<String>,
+    pub shipping_city: Option<String>,
+    pub shipping_postal_code: Option<String>,
+    pub shipping_country: Option<String>,
+    pub shipping_method_key: Option<String>,
+    pub inpost_locker_code: Option<String>,
+    pub inpost_locker_address: Option<String>,
+    pub payment_method: Option<String>,
+}
 
-    #[validate(length(min = 1, max = 20, message = "Kod pocztowy wysyłki jest wymagany"))]
-    pub shipping_postal_code: String,
+/// Formularz rozpoczęcia podglądu konta klienta; `customer` to e-mail albo ID klienta
+#[derive(Debug, Deserialize)]
+pub struct StartImpersonationPayload {
+    pub customer: String,
+    pub reason: Option<String>,
+}
 
-    #[validate(length(min = 1, max = 100, message = "Kraj wysyłki jest wymagany"))]
-    pub shipping_country: String,
+/// Formularz czyszczenia cache'u z panelu admina; bez `key` czyszczony jest cały cache
+#[derive(Debug, Deserialize)]
+pub struct PurgeCachePayload {
+    pub key: Option<String>,
 }
 
-/// Payload do aktualizacji statusu zamówienia
-#[allow(dead_code)]
-#[derive(Debug, Clone, Deserialize, Validate)]
-pub struct UpdateOrderStatusPayload {
-    pub status: OrderStatus,
+/// Sesja podglądu konta klienta z licznikami żądań - dziennik w panelu admina
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ImpersonationSessionSummary {
+    pub id: Uuid,
+    pub admin_email: String,
+    pub customer_email: String,
+    pub reason: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub request_count: i64,
+    pub blocked_count: i64,
 }
 
-#[derive(Debug, Serialize)]
-pub struct OrderItemDetailsPublic {
-    pub order_item_id: Uuid,
-    #[serde(flatten)]
-    pub product: Product,
-    pub price_at_purchase: i64,
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ImpersonationEvent {
+    pub id: i64,
+    pub session_id: Uuid,
+    pub method: String,
+    pub path: String,
+    pub blocked: bool,
+    pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct OrderDetailsResponse {
-    #[serde(flatten)]
-    pub order: Order,
-    pub items: Vec<OrderItemDetailsPublic>,
+/// Wynik oceny ryzyka zamówienia wraz z sygnałami, które się na niego złożyły
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OrderRiskAssessment {
+    pub order_id: Uuid,
+    pub score: i16,
+    pub signals: Vec<String>,
+    pub client_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Próba płatności online powiązana z zamówieniem
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Payment {
+    pub id: Uuid,
+    /// Zamówienie albo karta podarunkowa - zawsze dokładnie jedno z nich
+    pub order_id: Option<Uuid>,
+    pub gift_card_id: Option<Uuid>,
+    pub provider: String,
+    pub session_id: String,
+    pub amount: i64,
+    pub status: String,
+    pub provider_order_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Powiadomienie o transakcji wysyłane przez Przelewy24 na `urlStatus`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Przelewy24Notification {
+    pub merchant_id: i64,
+    pub pos_id: i64,
+    pub session_id: String,
+    pub amount: i64,
+    pub origin_amount: i64,
+    pub currency: String,
+    pub order_id: i64,
+    pub method_id: i64,
+    pub statement: String,
+    pub sign: String,
+}
+
+/// Dane produktu przekazywane do asystenta opisów (etykiety w formie, w jakiej widzi je admin)
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct ProductDescriptionDraftPayload {
+    #[validate(length(min = 1, max = 255, message = "Podaj nazwę produktu."))]
+    pub name: String,
+    pub category: String,
+    pub gender: String,
+    pub condition: String,
+    #[validate(length(max = 100))]
+    pub brand: Option<String>,
+    #[validate(length(max = 500))]
+    pub measurements: Option<String>,
+}
+
+/// Problem ze zdjęciem produktu wykryty przez audyt jakości
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProductImageIssue {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub image_url: Option<String>,
+    pub issue: String,
+    pub detected_at: DateTime<Utc>,
 }
 
 // --- STRUKTURY DLA KOSZYKA ZAKUPÓW ---
@@ -393,6 +1517,7 @@ pub struct CartItemWithProduct {
 
     pub product_id: Uuid, // p.id AS product_id (aby odróżnić od ci.product_id jeśli byłby potrzebny)
     pub name: String,     // p.name
+    pub slug: String,     // p.slug
     pub description: String, // p.description
     pub price: i64,       // p.price
     pub gender: ProductGender, // p.gender
@@ -401,6 +1526,16 @@ pub struct CartItemWithProduct {
     pub on_sale: bool,
     pub status: ProductStatus, // p.status
     pub images: Vec<String>,   // p.images
+    pub size: Option<String>,
+    pub brand: Option<String>,
+    pub color: Option<String>,
+    pub material: Option<String>,
+    pub meta_title: Option<String>,
+    pub meta_description: Option<String>,
+    pub og_image: Option<String>,
+    pub sale_price: Option<i64>,
+    pub sale_starts_at: Option<DateTime<Utc>>,
+    pub sale_ends_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -513,6 +1648,13 @@ pub struct CheckoutFormPayload {
 
     #[validate(length(min = 1, message = "Metoda dostawy jest wymagana."))]
     pub shipping_method_key: String, // np. "inpost", "poczta"}
+    // Wymagany tylko przy dostawie do Paczkomatu
+    pub inpost_locker_code: Option<String>,
+    pub inpost_locker_address: Option<String>,
+    // Kod rabatowy zastosowany w podsumowaniu zamówienia (puste = brak)
+    pub coupon_code: Option<String>,
+    // Kod karty podarunkowej z podsumowania zamówienia (puste = brak)
+    pub gift_card_code: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone, Display, EnumIter)]
@@ -581,6 +1723,13 @@ pub struct ForgotPasswordPayload {
     pub email: String,
 }
 
+/// Formularz "Sprawdź status zamówienia" dla gości: numer zamówienia i e-mail z zamówienia
+#[derive(Deserialize)]
+pub struct GuestOrderLookupPayload {
+    pub order_ref: String,
+    pub email: String,
+}
+
 #[derive(Deserialize, Validate)]
 pub struct ResetPasswordPayload {
     pub token: String,
@@ -595,6 +1744,7 @@ pub struct ResetPasswordPayload {
 pub struct ProductWithTotalCount {
     pub id: Uuid,
     pub name: String,
+    pub slug: String,
     pub description: String,
     pub price: i64,
     pub gender: ProductGender,
@@ -603,13 +1753,100 @@ pub struct ProductWithTotalCount {
     pub status: ProductStatus,
     pub images: Vec<String>,
     pub on_sale: bool,
+    pub size: Option<String>,
+    pub brand: Option<String>,
+    pub color: Option<String>,
+    pub material: Option<String>,
+    pub meta_title: Option<String>,
+    pub meta_description: Option<String>,
+    pub og_image: Option<String>,
+    pub sale_price: Option<i64>,
+    pub sale_starts_at: Option<DateTime<Utc>>,
+    pub sale_ends_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub total_count: Option<i64>,
 }
 
-#[derive(Debug)]
-pub struct FaqItem {
-    pub question: String,
-    pub answer: String,
+/// Rodzaj zdarzenia domenowego zapisywanego w tabeli `events` (lejek konwersji).
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Type, Display, EnumIter,
+)]
+#[sqlx(type_name = "event_type_enum", rename_all = "snake_case")]
+pub enum EventType {
+    #[strum(serialize = "Wyświetlenie produktu")]
+    ProductViewed,
+    #[strum(serialize = "Dodanie do koszyka")]
+    AddedToCart,
+    #[strum(serialize = "Usunięcie z koszyka")]
+    RemovedFromCart,
+    #[strum(serialize = "Rozpoczęcie zamówienia")]
+    CheckoutStarted,
+    #[strum(serialize = "Złożenie zamówienia")]
+    OrderPlaced,
+}
+
+/// Pojedyncze zdarzenie domenowe (tabela tylko do dopisywania).
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Event {
+    pub id: i64,
+    pub event_type: EventType,
+    pub user_id: Option<Uuid>,
+    /// ID koszyka gościa - pozwala powiązać zdarzenia niezalogowanego klienta
+    pub guest_session_id: Option<Uuid>,
+    pub product_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub metadata: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Liczby unikalnych klientów na kolejnych etapach lejka konwersji w danym okresie.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct FunnelStats {
+    pub sessions: i64,
+    pub product_views: i64,
+    pub added_to_cart: i64,
+    pub checkout_started: i64,
+    pub orders_placed: i64,
+}
+
+/// Podsumowanie sprzedaży w zakresie dat panelu admina. Anulowane zamówienia nie są liczone.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct SalesSummary {
+    pub orders: i64,
+    pub revenue: i64,
+    pub items_sold: i64,
+    pub cancelled_orders: i64,
+}
+
+/// Liczba zamówień i przychód w jednym okresie (dzień lub tydzień) wykresu sprzedaży.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SalesPeriod {
+    pub period_start: chrono::NaiveDate,
+    pub orders: i64,
+    pub revenue: i64,
+}
+
+/// Sprzedaż w kategorii: liczba sprzedanych sztuk i przychód z nich.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CategorySales {
+    pub category: Category,
+    pub items_sold: i64,
+    pub revenue: i64,
+}
+
+/// Ile produktów zarezerwowanych (dodanych do koszyka) w okresie zostało sprzedanych.
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct ReservationConversion {
+    pub reserved_products: i64,
+    pub sold_products: i64,
+}
+
+/// Konwersja w obrębie kategorii: wyświetlenia, dodania do koszyka i sprzedane sztuki.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CategoryConversion {
+    pub category: Category,
+    pub views: i64,
+    pub adds: i64,
+    pub sold: i64,
 }
@@ -0,0 +1,30 @@
+// src/notifications.rs
+
+use sqlx::PgPool;
+
+use crate::models::AdminNotificationLevel;
+
+/// Dodaje powiadomienie do centrum powiadomień admina.
+/// Wywoływane z zadań w tle, więc błąd zapisu jest tylko logowany.
+pub async fn notify_admin(
+    pool: &PgPool,
+    level: AdminNotificationLevel,
+    title: &str,
+    message: &str,
+) {
+    let result =
+        sqlx::query("INSERT INTO admin_notifications (level, title, message) VALUES ($1, $2, $3)")
+            .bind(level)
+            .bind(title)
+            .bind(message)
+            .execute(pool)
+            .await;
+
+    if let Err(e) = result {
+        tracing::error!(
+            "Nie udało się zapisać powiadomienia admina '{}': {:?}",
+            title,
+            e
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// src/object_storage.rs
+
+// Minimalny klient magazynu zgodnego z S3 (AWS S3, Backblaze B2, MinIO).
+// Obsługuje tylko to, czego potrzebują kopie zapasowe: wysyłkę, listowanie i usuwanie obiektów.
+// Żądania są podpisywane AWS Signature V4 i adresowane w stylu `{endpoint}/{bucket}/{key}`.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::AppError;
+use crate::state::ObjectStorageConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Obiekt z listingu bucketu
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub key: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListBucketResult {
+    #[serde(default)]
+    contents: Vec<ListedObject>,
+    #[serde(default)]
+    is_truncated: bool,
+    next_continuation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListedObject {
+    key: String,
+    last_modified: DateTime<Utc>,
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC przyjmuje klucz dowolnej długości");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Koduje segment ścieżki lub parametr zapytania tak, jak wymaga SigV4 (wszystko poza A-Z a-z 0-9 - _ . ~).
+fn aws_encode(value: &str) -> String {
+    urlencoding::encode(value).into_owned()
+}
+
+/// Wysyła podpisane żądanie do magazynu. `query` musi być posortowane po nazwie parametru.
+async fn signed_request(
+    config: &ObjectStorageConfig,
+    method: Method,
+    key: Option<&str>,
+    query: &[(&str, String)],
+    body: Vec<u8>,
+) -> Result<reqwest::Response, AppError> {
+    let endpoint = config.endpoint.trim_end_matches('/');
+    let host = endpoint.split("://").nth(1).unwrap_or(endpoint).to_string();
+
+    let mut canonical_uri = format!("/{}", aws_encode(&config.bucket));
+    if let Some(key) = key {
+        for segment in key.split('/') {
+            canonical_uri.push('/');
+            canonical_uri.push_str(&aws_encode(segment));
+        }
+    }
+    let canonical_query = query
+        .iter()
+        .map(|(name, value)| format!("{}={}", aws_encode(name), aws_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let short_date = now.format("%Y%m%d").to_string();
+    let payload_hash = sha256_hex(&body);
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        method.as_str(),
+        canonical_uri,
+        canonical_query,
+        host,
+        payload_hash,
+        amz_date,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", short_date, config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let k_date = hmac_sha256(
+        format!("AWS4{}", config.secret_access_key).as_bytes(),
+        &short_date,
+    );
+    let k_region = hmac_sha256(&k_date, &config.region);
+    let k_service = hmac_sha256(&k_region, "s3");
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+        config.access_key_id, scope, signature
+    );
+
+    let mut url = format!("{}{}", endpoint, canonical_uri);
+    if !canonical_query.is_empty() {
+        url.push('?');
+        url.push_str(&canonical_query);
+    }
+
+    Client::new()
+        .request(method, &url)
+        .header("x-amz-date", &amz_date)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("Authorization", authorization)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!("Magazyn S3: błąd połączenia z {}: {}", url, e);
+            AppError::InternalServerError("Nie udało się połączyć z magazynem kopii.".to_string())
+        })
+}
+
+async fn ensure_success(
+    response: reqwest::Response,
+    operation: &str,
+) -> Result<reqwest::Response, AppError> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    tracing::error!("Magazyn S3: {} zwróciło {}: {}", operation, status, body);
+    Err(AppError::InternalServerError(format!(
+        "Magazyn kopii odrzucił operację {} (HTTP {}).",
+        operation, status
+    )))
+}
+
+pub async fn put_object(
+    config: &ObjectStorageConfig,
+    key: &str,
+    body: Vec<u8>,
+) -> Result<(), AppError> {
+    let response = signed_request(config, Method::PUT, Some(key), &[], body).await?;
+    ensure_success(response, "PUT").await?;
+    Ok(())
+}
+
+pub async fn delete_object(config: &ObjectStorageConfig, key: &str) -> Result<(), AppError> {
+    let response = signed_request(config, Method::DELETE, Some(key), &[], Vec::new()).await?;
+    ensure_success(response, "DELETE").await?;
+    Ok(())
+}
+
+/// Listuje wszystkie obiekty z danym prefiksem (przechodzi przez kolejne strony wyników).
+pub async fn list_objects(
+    config: &ObjectStorageConfig,
+    prefix: &str,
+) -> Result<Vec<StoredObject>, AppError> {
+    let mut objects = Vec::new();
+    let mut continuation_token: Option<String> = None;
+
+    loop {
+        let mut query = Vec::new();
+        if let Some(token) = &continuation_token {
+            query.push(("continuation-token", token.clone()));
+        }
+        query.push(("list-type", "2".to_string()));
+        query.push(("prefix", prefix.to_string()));
+
+        let response = signed_request(config, Method::GET, None, &query, Vec::new()).await?;
+        let xml = ensure_success(response, "LIST")
+            .await?
+            .text()
+            .await
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Nie udało się odczytać listy kopii: {}", e))
+            })?;
+        let page: ListBucketResult = quick_xml::de::from_str(&xml).map_err(|e| {
+            tracing::error!("Magazyn S3: nieprawidłowa odpowiedź listowania: {}", e);
+            AppError::InternalServerError("Nieprawidłowa odpowiedź magazynu kopii.".to_string())
+        })?;
+
+        objects.extend(page.contents.into_iter().map(|o| StoredObject {
+            key: o.key,
+            last_modified: o.last_modified,
+        }));
+
+        match (page.is_truncated, page.next_continuation_token) {
+            (true, Some(token)) => continuation_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(objects)
+}
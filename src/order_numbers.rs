@@ -0,0 +1,73 @@
+// src/order_numbers.rs
+
+// Czytelne numery zamówień, np. "MESS-2025-00123", do podania przez telefon i w tytule przelewu.
+// Format ustawia `ORDER_NUMBER_FORMAT` ({year} i {seq}), a liczba cyfr numeru kolejnego
+// `ORDER_NUMBER_DIGITS`. Z {year} w formacie numeracja zaczyna się od 1 w każdym roku.
+
+use chrono::{Datelike, Utc};
+use sqlx::PgConnection;
+
+use crate::date_format::to_shop_time;
+use crate::errors::AppError;
+use crate::state::OrderNumberConfig;
+
+pub const DEFAULT_ORDER_NUMBER_FORMAT: &str = "MESS-{year}-{seq}";
+pub const DEFAULT_ORDER_NUMBER_DIGITS: usize = 5;
+
+/// Klucz licznika dla formatów bez roku - jedna ciągła numeracja
+const SHARED_COUNTER_YEAR: i32 = 0;
+
+impl OrderNumberConfig {
+    /// Sprawdza format przy starcie - bez {seq} numery nie byłyby unikalne.
+    pub fn new(format: String, digits: usize) -> Result<Self, String> {
+        if !format.contains("{seq}") {
+            return Err(format!(
+                "ORDER_NUMBER_FORMAT musi zawierać {{seq}}, otrzymano '{}'",
+                format
+            ));
+        }
+        if !(1..=10).contains(&digits) {
+            return Err(format!(
+                "ORDER_NUMBER_DIGITS musi być z zakresu 1-10, otrzymano {}",
+                digits
+            ));
+        }
+        Ok(OrderNumberConfig { format, digits })
+    }
+
+    fn yearly(&self) -> bool {
+        self.format.contains("{year}")
+    }
+
+    pub fn format_number(&self, year: i32, seq: i64) -> String {
+        self.format
+            .replace("{year}", &year.to_string())
+            .replace("{seq}", &format!("{:0width$}", seq, width = self.digits))
+    }
+}
+
+/// Nadaje kolejny numer zamówienia. Licznik jest podbijany jednym UPSERT-em, który blokuje
+/// wiersz do końca transakcji, więc dwa równoległe zamówienia nie dostaną tego samego numeru,
+/// a wycofana transakcja nie zostawia dziury w numeracji.
+pub async fn next_order_number(
+    conn: &mut PgConnection,
+    config: &OrderNumberConfig,
+) -> Result<String, AppError> {
+    let year = to_shop_time(&Utc::now()).year();
+    let counter_year = if config.yearly() {
+        year
+    } else {
+        SHARED_COUNTER_YEAR
+    };
+    let seq: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO order_number_counters (year, last_value) VALUES ($1, 1)
+        ON CONFLICT (year) DO UPDATE SET last_value = order_number_counters.last_value + 1
+        RETURNING last_value
+        "#,
+    )
+    .bind(counter_year)
+    .fetch_one(conn)
+    .await?;
+    Ok(config.format_number(year, seq as i64))
+}
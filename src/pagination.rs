@@ -11,6 +11,15 @@ pub struct PaginatedProductsResponse {
     pub data: Vec<Product>,
 }
 
+/// Liczba stron listy; pusta lista ma zero stron
+pub fn total_pages(total_items: i64, per_page: i64) -> i64 {
+    if total_items == 0 {
+        0
+    } else {
+        (total_items as f64 / per_page as f64).ceil() as i64
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct PaginatedOrdersResponse<T> {
     pub total_items: i64,
@@ -0,0 +1,310 @@
+// src/payments.rs
+
+use chrono::Utc;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha384};
+use uuid::Uuid;
+
+use crate::email_service::resolve_order_recipient_email;
+use crate::errors::AppError;
+use crate::gift_cards::GiftCard;
+use crate::models::{Order, Przelewy24Notification};
+use crate::state::{AppState, Przelewy24Config};
+
+const P24_CURRENCY: &str = "PLN";
+
+// Pola podpisów muszą być serializowane dokładnie w tej kolejności (wymóg Przelewy24)
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RegisterSign<'a> {
+    session_id: &'a str,
+    merchant_id: i64,
+    amount: i64,
+    currency: &'a str,
+    crc: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationSign<'a> {
+    merchant_id: i64,
+    pos_id: i64,
+    session_id: &'a str,
+    amount: i64,
+    origin_amount: i64,
+    currency: &'a str,
+    order_id: i64,
+    method_id: i64,
+    statement: &'a str,
+    crc: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifySign<'a> {
+    session_id: &'a str,
+    order_id: i64,
+    amount: i64,
+    currency: &'a str,
+    crc: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisterResponse {
+    data: RegisterResponseData,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegisterResponseData {
+    token: String,
+}
+
+/// SHA-384 (hex) z JSON-a pól podpisu - format wymagany przez API Przelewy24.
+fn p24_sign<T: Serialize>(fields: &T) -> Result<String, AppError> {
+    let payload = serde_json::to_string(fields).map_err(|e| {
+        tracing::error!("Błąd serializacji podpisu Przelewy24: {}", e);
+        AppError::InternalServerError("Błąd przygotowania płatności".to_string())
+    })?;
+    let mut hasher = Sha384::new();
+    hasher.update(payload.as_bytes());
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn p24_request_error(context: &str, e: reqwest::Error) -> AppError {
+    tracing::error!("Błąd sieci Przelewy24 ({}): {}", context, e);
+    AppError::InternalServerError("Błąd połączenia z bramką płatności".to_string())
+}
+
+/// Za co jest płatność: zamówienie albo zakup karty podarunkowej (kolumny `payments`)
+enum PaymentTarget {
+    Order(Uuid),
+    GiftCard(Uuid),
+}
+
+/// Transakcja do zarejestrowania w Przelewy24
+struct TransactionRequest<'a> {
+    target: PaymentTarget,
+    amount: i64,
+    description: String,
+    email: &'a str,
+    client: String,
+    url_return: String,
+}
+
+/// Rejestruje transakcję w Przelewy24 dla zamówienia i zwraca adres strony płatności,
+/// na którą należy przekierować klienta.
+///
+/// Każda próba ma własny `sessionId`, dzięki czemu klient może ponowić płatność.
+pub async fn start_przelewy24_payment(
+    app_state: &AppState,
+    config: &Przelewy24Config,
+    order: &Order,
+) -> Result<String, AppError> {
+    let email = resolve_order_recipient_email(app_state, order).await?;
+    register_transaction(
+        app_state,
+        config,
+        TransactionRequest {
+            target: PaymentTarget::Order(order.id),
+            amount: order.total_price,
+            description: format!("Zamówienie {}", order.order_number),
+            email: &email,
+            client: format!("{} {}", order.shipping_first_name, order.shipping_last_name),
+            url_return: format!(
+                "{}/zamowienie/dziekujemy/{}",
+                config.public_base_url, order.id
+            ),
+        },
+    )
+    .await
+}
+
+/// Rejestruje transakcję za kartę podarunkową; po płatności klient wraca na stronę karty.
+pub async fn start_gift_card_payment(
+    app_state: &AppState,
+    config: &Przelewy24Config,
+    gift_card: &GiftCard,
+) -> Result<String, AppError> {
+    register_transaction(
+        app_state,
+        config,
+        TransactionRequest {
+            target: PaymentTarget::GiftCard(gift_card.id),
+            amount: gift_card.initial_amount,
+            description: format!("Karta podarunkowa {}", gift_card.payment_reference()),
+            email: &gift_card.purchaser_email,
+            client: gift_card.purchaser_email.clone(),
+            url_return: format!(
+                "{}/karta-podarunkowa/{}",
+                config.public_base_url, gift_card.id
+            ),
+        },
+    )
+    .await
+}
+
+async fn register_transaction(
+    app_state: &AppState,
+    config: &Przelewy24Config,
+    request: TransactionRequest<'_>,
+) -> Result<String, AppError> {
+    let (order_id, gift_card_id, target_id) = match request.target {
+        PaymentTarget::Order(id) => (Some(id), None, id),
+        PaymentTarget::GiftCard(id) => (None, Some(id), id),
+    };
+    let session_id = format!("{}-{}", target_id, Utc::now().timestamp());
+
+    let sign = p24_sign(&RegisterSign {
+        session_id: &session_id,
+        merchant_id: config.merchant_id,
+        amount: request.amount,
+        currency: P24_CURRENCY,
+        crc: &config.crc,
+    })?;
+
+    let request_body = json!({
+        "merchantId": config.merchant_id,
+        "posId": config.pos_id,
+        "sessionId": session_id,
+        "amount": request.amount,
+        "currency": P24_CURRENCY,
+        "description": request.description,
+        "email": request.email,
+        "client": request.client,
+        "country": "PL",
+        "language": "pl",
+        "urlReturn": request.url_return,
+        "urlStatus": format!("{}/api/payments/p24/webhook", config.public_base_url),
+        "sign": sign,
+    });
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/api/v1/transaction/register", config.base_url))
+        .basic_auth(config.pos_id.to_string(), Some(&config.api_key))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| p24_request_error("rejestracja", e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "Przelewy24 odrzuciło rejestrację transakcji '{}': Status={}, Treść={}",
+            request.description,
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Bramka płatności odrzuciła transakcję (status: {})",
+            status
+        )));
+    }
+
+    let register_response = resp.json::<RegisterResponse>().await.map_err(|e| {
+        tracing::error!("Błąd deserializacji odpowiedzi Przelewy24: {}", e);
+        AppError::InternalServerError(
+            "Nie można przetworzyć odpowiedzi bramki płatności".to_string(),
+        )
+    })?;
+
+    sqlx::query(
+        "INSERT INTO payments (order_id, gift_card_id, provider, session_id, amount) VALUES ($1, $2, 'przelewy24', $3, $4)",
+    )
+    .bind(order_id)
+    .bind(gift_card_id)
+    .bind(&session_id)
+    .bind(request.amount)
+    .execute(&app_state.db_pool)
+    .await?;
+
+    tracing::info!(
+        "Zarejestrowano transakcję Przelewy24 (sesja: {}): {}",
+        session_id,
+        request.description
+    );
+    Ok(format!(
+        "{}/trnRequest/{}",
+        config.base_url, register_response.data.token
+    ))
+}
+
+/// Sprawdza podpis powiadomienia z Przelewy24.
+pub fn verify_notification_sign(
+    config: &Przelewy24Config,
+    notification: &Przelewy24Notification,
+) -> Result<bool, AppError> {
+    let expected = p24_sign(&NotificationSign {
+        merchant_id: notification.merchant_id,
+        pos_id: notification.pos_id,
+        session_id: &notification.session_id,
+        amount: notification.amount,
+        origin_amount: notification.origin_amount,
+        currency: &notification.currency,
+        order_id: notification.order_id,
+        method_id: notification.method_id,
+        statement: &notification.statement,
+        crc: &config.crc,
+    })?;
+    Ok(expected.eq_ignore_ascii_case(&notification.sign))
+}
+
+/// Potwierdza transakcję w Przelewy24 (`transaction/verify`). Dopiero po tym kroku
+/// środki są zaksięgowane - bez weryfikacji bramka zwraca je klientowi.
+pub async fn verify_przelewy24_transaction(
+    config: &Przelewy24Config,
+    notification: &Przelewy24Notification,
+) -> Result<(), AppError> {
+    let sign = p24_sign(&VerifySign {
+        session_id: &notification.session_id,
+        order_id: notification.order_id,
+        amount: notification.amount,
+        currency: &notification.currency,
+        crc: &config.crc,
+    })?;
+
+    let request_body = json!({
+        "merchantId": config.merchant_id,
+        "posId": config.pos_id,
+        "sessionId": notification.session_id,
+        "amount": notification.amount,
+        "currency": notification.currency,
+        "orderId": notification.order_id,
+        "sign": sign,
+    });
+
+    let client = Client::new();
+    let resp = client
+        .put(format!("{}/api/v1/transaction/verify", config.base_url))
+        .basic_auth(config.pos_id.to_string(), Some(&config.api_key))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| p24_request_error("weryfikacja", e))?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let error_text = resp
+            .text()
+            .await
+            .unwrap_or_else(|_| "Brak treści błędu".to_string());
+        tracing::error!(
+            "Weryfikacja transakcji Przelewy24 (sesja: {}) nie powiodła się: Status={}, Treść={}",
+            notification.session_id,
+            status,
+            error_text
+        );
+        return Err(AppError::InternalServerError(format!(
+            "Weryfikacja płatności nie powiodła się (status: {})",
+            status
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+// src/permissions.rs
+
+// Role personelu i ich uprawnienia w panelu. Admin może wszystko, redaktor (Editor) prowadzi
+// katalog i treści stron, a magazyn (Fulfilment) obsługuje zamówienia, zwroty i reklamacje. Handlery panelu
+// nie porównują ról bezpośrednio - pytają o konkretne uprawnienie przez `require_permission`.
+
+use strum_macros::AsRefStr;
+
+use crate::auth_models::{Role, TokenClaims};
+use crate::errors::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Permission {
+    /// Wejście do panelu: pulpit i powiadomienia
+    AccessPanel,
+    /// Produkty, zdjęcia, rezerwacje, tabela rozmiarów, pielęgnacja
+    ManageProducts,
+    /// Zamówienia, etykiety, faktury, zwroty i reklamacje
+    ManageOrders,
+    ModerateReviews,
+    /// Klienci, flagi, tagi, podgląd jako klient, blokady logowania
+    ManageCustomers,
+    /// Kody rabatowe, kampanie i karty podarunkowe
+    ManageCoupons,
+    /// Treści stron informacyjnych (CMS)
+    ManageContent,
+    /// Sprzedaż, rejestr sprzedaży, lejek konwersji, RUM
+    ViewReports,
+    /// Cache, zadania w tle, kopie zapasowe, Cloudinary, klucze API
+    ManageSystem,
+    /// Konta personelu: role i blokowanie kont
+    ManageUsers,
+}
+
+impl Role {
+    /// Konto personelu - ma dostęp do panelu (i wymaga weryfikacji dwuetapowej)
+    pub fn is_staff(&self) -> bool {
+        *self != Role::Customer
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Editor => matches!(
+                permission,
+                Permission::AccessPanel
+                    | Permission::ManageProducts
+                    | Permission::ModerateReviews
+                    | Permission::ManageContent
+            ),
+            Role::Fulfilment => matches!(
+                permission,
+                Permission::AccessPanel | Permission::ManageOrders
+            ),
+            Role::Customer => false,
+        }
+    }
+}
+
+impl TokenClaims {
+    /// Odrzuca żądanie, jeśli rola z tokenu nie ma danego uprawnienia
+    pub fn require_permission(&self, permission: Permission) -> Result<(), AppError> {
+        if self.role.has_permission(permission) {
+            return Ok(());
+        }
+        tracing::warn!(
+            "Użytkownik {} ({}) bez uprawnienia '{}'",
+            self.sub,
+            self.role.label(),
+            permission.as_ref()
+        );
+        Err(AppError::UnauthorizedAccess(
+            "Brak uprawnień do tej części panelu.".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,80 @@
+// src/plural.rs
+
+/// Wybiera polską formę liczebnika dla podanej liczby.
+///
+/// Reguły:
+/// 1. `1` - forma pojedyncza ("przedmiot").
+/// 2. Końcówka 2-4, ale nie 12-14 - forma mnoga ("przedmioty").
+/// 3. Pozostałe (0, 5-21, 25-31, 112...) - dopełniacz liczby mnogiej ("przedmiotów").
+pub fn plural_form<'a>(count: i64, one: &'a str, few: &'a str, many: &'a str) -> &'a str {
+    let n = count.unsigned_abs();
+    if n == 1 {
+        return one;
+    }
+    let last_digit = n % 10;
+    let last_two_digits = n % 100;
+    if (2..=4).contains(&last_digit) && !(12..=14).contains(&last_two_digits) {
+        few
+    } else {
+        many
+    }
+}
+
+/// Zwraca liczbę razem z odmienionym rzeczownikiem, np. "3 przedmioty", "5 przedmiotów".
+pub fn pluralize(count: i64, one: &str, few: &str, many: &str) -> String {
+    format!("{} {}", count, plural_form(count, one, few, many))
+}
+
+/// Najczęściej używane odmiany w sklepie.
+pub fn items_count(count: i64) -> String {
+    pluralize(count, "przedmiot", "przedmioty", "przedmiotów")
+}
+
+pub fn products_count(count: i64) -> String {
+    pluralize(count, "produkt", "produkty", "produktów")
+}
+
+pub fn orders_count(count: i64) -> String {
+    pluralize(count, "zamówienie", "zamówienia", "zamówień")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(count: i64) -> &'static str {
+        plural_form(count, "przedmiot", "przedmioty", "przedmiotów")
+    }
+
+    #[test]
+    fn one_uses_singular() {
+        assert_eq!(form(1), "przedmiot");
+    }
+
+    #[test]
+    fn endings_two_to_four_use_few() {
+        for count in [2, 3, 4, 22, 24, 122] {
+            assert_eq!(form(count), "przedmioty", "count = {}", count);
+        }
+    }
+
+    #[test]
+    fn teens_and_other_endings_use_many() {
+        for count in [0, 5, 11, 12, 13, 14, 21, 25, 112] {
+            assert_eq!(form(count), "przedmiotów", "count = {}", count);
+        }
+    }
+
+    #[test]
+    fn negative_counts_follow_absolute_value() {
+        assert_eq!(form(-1), "przedmiot");
+        assert_eq!(form(-22), "przedmioty");
+    }
+
+    #[test]
+    fn pluralize_prefixes_count() {
+        assert_eq!(items_count(5), "5 przedmiotów");
+        assert_eq!(orders_count(1), "1 zamówienie");
+        assert_eq!(products_count(3), "3 produkty");
+    }
+}
@@ -0,0 +1,131 @@
+// src/price_history.rs
+
+// Historia zmian ceny i flagi `on_sale` produktów. Zapisujemy cenę efektywną (z trwającą
+// promocją), bo tyle płaci klient. Każda zmiana z panelu (formularz produktu, akcje masowe)
+// trafia do `product_price_history` w tej samej transakcji co UPDATE produktu, a start i koniec
+// zaplanowanej promocji zapisuje zadanie `sales::sale_boundaries_job`.
+// Z historii bierzemy "cena obniżona z X" na stronie produktu i najniższą cenę z 30 dni przed
+// obniżką, którą dyrektywa Omnibus każe pokazywać przy każdej promocji.
+
+use std::collections::HashMap;
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::Product;
+
+/// Zapisuje zmianę ceny efektywnej/okazji; bez zmiany obu nic nie robi
+pub async fn record_price_change(
+    conn: &mut PgConnection,
+    before: &Product,
+    after: &Product,
+    changed_by: Option<Uuid>,
+) -> Result<(), AppError> {
+    let (old_price, new_price) = (before.effective_price(), after.effective_price());
+    if old_price == new_price && before.on_sale == after.on_sale {
+        return Ok(());
+    }
+    sqlx::query(
+        r#"
+            INSERT INTO product_price_history (product_id, old_price, new_price, old_on_sale, new_on_sale, changed_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(before.id)
+    .bind(old_price)
+    .bind(new_price)
+    .bind(before.on_sale)
+    .bind(after.on_sale)
+    .bind(changed_by)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Cena sprzed obniżki do "cena obniżona z X": tylko gdy ostatnia zmiana ceny była obniżką
+/// do ceny, którą klient płaci teraz
+pub async fn price_before_drop(pool: &PgPool, product: &Product) -> Result<Option<i64>, AppError> {
+    let last_change: Option<(i64, i64)> = sqlx::query_as(
+        r#"
+            SELECT old_price, new_price
+            FROM product_price_history
+            WHERE product_id = $1 AND old_price <> new_price
+            ORDER BY changed_at DESC, id DESC
+            LIMIT 1
+        "#,
+    )
+    .bind(product.id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(last_change
+        .filter(|&(old_price, new_price)| {
+            new_price == product.effective_price() && old_price > new_price
+        })
+        .map(|(old_price, _)| old_price))
+}
+
+/// Najniższa cena z 30 dni przed obecną obniżką (Omnibus) dla podanych produktów. Gdy ostatnia
+/// zmiana ceny nie była obniżką (np. produkt tylko oznaczono jako okazję), liczymy 30 dni wstecz
+/// od dziś razem z obecną ceną.
+pub async fn lowest_prices_before_discount(
+    pool: &PgPool,
+    product_ids: &[Uuid],
+) -> Result<HashMap<Uuid, i64>, AppError> {
+    if product_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows: Vec<(Uuid, Option<i64>)> = sqlx::query_as(
+        r#"
+            SELECT p.id,
+                   (SELECT MIN(price) FROM (
+                        -- cena sprzed każdej zmiany w oknie obowiązywała w tym oknie...
+                        SELECT h.old_price AS price
+                        FROM product_price_history h
+                        WHERE h.product_id = p.id
+                          AND h.changed_at >= r.since - INTERVAL '30 days' AND h.changed_at <= r.since
+                        UNION ALL
+                        -- ...podobnie jak ceny ustawione w oknie przed samą obniżką
+                        SELECT h.new_price
+                        FROM product_price_history h
+                        WHERE h.product_id = p.id
+                          AND h.changed_at >= r.since - INTERVAL '30 days' AND h.changed_at < r.since
+                        UNION ALL
+                        SELECT p.price WHERE NOT r.is_drop
+                    ) prices) AS lowest_price
+            FROM products p
+            LEFT JOIN LATERAL (
+                SELECT h.changed_at, h.old_price > h.new_price AS is_drop
+                FROM product_price_history h
+                WHERE h.product_id = p.id AND h.old_price <> h.new_price
+                ORDER BY h.changed_at DESC, h.id DESC
+                LIMIT 1
+            ) last_change ON TRUE
+            CROSS JOIN LATERAL (
+                SELECT CASE WHEN last_change.is_drop THEN last_change.changed_at ELSE NOW() END AS since,
+                       COALESCE(last_change.is_drop, FALSE) AS is_drop
+            ) r
+            WHERE p.id = ANY($1)
+        "#,
+    )
+    .bind(product_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(product_id, lowest_price)| Some((product_id, lowest_price?)))
+        .collect())
+}
+
+/// Ceny Omnibus dla produktów na okazji (także z trwającą promocją) - do siatki listingu
+pub async fn lowest_prices_for_sale_products(
+    pool: &PgPool,
+    products: &[Product],
+) -> Result<HashMap<Uuid, i64>, AppError> {
+    let sale_ids: Vec<Uuid> = products
+        .iter()
+        .filter(|product| product.is_discounted())
+        .map(|product| product.id)
+        .collect();
+    lowest_prices_before_discount(pool, &sale_ids).await
+}
@@ -0,0 +1,108 @@
+// src/product_views.rs
+
+// Licznik wyświetleń produktów do sortowania `sort_by=popularity` i sekcji "Najpopularniejsze"
+// na stronie głównej. Wyświetlenie liczymy raz na odwiedzającego, produkt i dzień (klucz
+// główny `product_views`), więc odświeżanie strony i klikanie w galerii nie nabija licznika.
+// Pełna historia wejść jest w `events` - tu trzymamy tylko okno `POPULARITY_WINDOW_DAYS`.
+
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Product, ProductStatus};
+use crate::state::AppState;
+
+/// Z ilu ostatnich dni liczymy popularność
+pub const POPULARITY_WINDOW_DAYS: i32 = 30;
+/// Tyle produktów pokazuje sekcja "Najpopularniejsze"
+pub const POPULAR_PRODUCTS_LIMIT: i64 = 12;
+
+/// Kto ogląda produkt: zalogowany klient, sesja gościa albo (bez ciasteczka) adres IP
+pub fn visitor_key(
+    user_id: Option<Uuid>,
+    guest_session_id: Option<Uuid>,
+    client_ip: Option<String>,
+) -> Option<String> {
+    user_id
+        .map(|id| format!("u:{}", id))
+        .or_else(|| guest_session_id.map(|id| format!("g:{}", id)))
+        .or_else(|| client_ip.map(|ip| format!("ip:{}", ip)))
+}
+
+/// Zapisuje wyświetlenie w tle, tak jak `events::record_event` - licznik nie może spowolnić
+/// strony produktu. Kolejne wejścia tego samego dnia są pomijane przez `ON CONFLICT`.
+pub fn record_product_view(pool: &PgPool, product_id: Uuid, visitor: Option<String>) {
+    let Some(visitor) = visitor else {
+        return;
+    };
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        let result = sqlx::query(
+            r#"
+                INSERT INTO product_views (product_id, visitor)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(product_id)
+        .bind(visitor)
+        .execute(&pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!(
+                "Nie udało się zapisać wyświetlenia produktu {}: {:?}",
+                product_id,
+                e
+            );
+        }
+    });
+}
+
+/// Liczba wyświetleń produktu `products` z okna popularności - do ORDER BY listingu
+pub fn push_popularity_score(query_builder: &mut QueryBuilder<'_, Postgres>) {
+    query_builder
+        .push(
+            "(SELECT COUNT(*) FROM product_views v WHERE v.product_id = products.id AND v.viewed_on > CURRENT_DATE - ",
+        )
+        .push_bind(POPULARITY_WINDOW_DAYS)
+        .push(")");
+}
+
+/// Dostępne produkty z największą liczbą wyświetleń z ostatnich `POPULARITY_WINDOW_DAYS` dni
+pub async fn popular_products(pool: &PgPool, limit: i64) -> Result<Vec<Product>, AppError> {
+    let products = sqlx::query_as::<_, Product>(
+        r#"
+            SELECT p.*
+            FROM (
+                SELECT product_id, COUNT(*) AS views
+                FROM product_views
+                WHERE viewed_on > CURRENT_DATE - $1
+                GROUP BY product_id
+            ) v
+            JOIN products p ON p.id = v.product_id
+            WHERE p.status = $2
+            ORDER BY v.views DESC, p.created_at DESC
+            LIMIT $3
+        "#,
+    )
+    .bind(POPULARITY_WINDOW_DAYS)
+    .bind(ProductStatus::Available)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(products)
+}
+
+/// Zadanie cykliczne (raz na dobę): wyświetlenia spoza okna popularności nie są już potrzebne
+pub async fn purge_product_views_job(state: &AppState) -> Result<String, AppError> {
+    let purged = sqlx::query("DELETE FROM product_views WHERE viewed_on <= CURRENT_DATE - $1")
+        .bind(POPULARITY_WINDOW_DAYS)
+        .execute(&state.db_pool)
+        .await?
+        .rows_affected();
+    Ok(match purged {
+        0 => String::new(),
+        purged => format!("Usunięto {} starych wyświetleń produktów.", purged),
+    })
+}
@@ -0,0 +1,322 @@
+// src/public_api.rs
+
+// Publiczne API katalogu w wersji 1 (`/api/v1`) dla aplikacji mobilnej, kiosku itp.
+// Odpowiedzi to stabilne DTO niezależne od struktury tabel - zmiana kolumny w `products`
+// nie może zmienić kontraktu. Zmiany niezgodne wstecz idą do `/api/v2`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+use crate::api_keys::ApiClient;
+use crate::errors::AppError;
+use crate::filters::ListingParams;
+use crate::models::{Category, Product, ProductCondition, ProductGender, ProductStatus};
+use crate::state::AppState;
+use crate::views::{
+    common::{format_price_maud, transform_cloudinary_url},
+    shop::{PRODUCT_DETAIL_IMAGE_TRANSFORMATION, gender_listing_path},
+};
+
+const THUMBNAIL_IMAGE_TRANSFORMATION: &str = "w_150,h_150,c_fill,f_auto,q_auto:good";
+const CARD_IMAGE_TRANSFORMATION: &str = "w_400,h_400,c_fill,g_auto,f_auto,q_auto:best";
+/// Szerokość dopasowana do ekranów telefonów (2x przy ~375 px)
+const MOBILE_IMAGE_TRANSFORMATION: &str = "w_750,c_limit,f_auto,q_auto:good";
+
+/// Wartość słownikowa: stabilny identyfikator i polska etykieta do wyświetlenia
+#[derive(Debug, Serialize)]
+pub struct LabeledValueDto<T: Serialize> {
+    pub value: T,
+    pub label: String,
+}
+
+impl<T: Serialize + std::fmt::Display> LabeledValueDto<T> {
+    fn new(value: T) -> Self {
+        let label = value.to_string();
+        LabeledValueDto { value, label }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceDto {
+    /// Kwota w groszach
+    pub amount: i64,
+    pub currency: &'static str,
+    /// Np. "129,00 zł"
+    pub formatted: String,
+}
+
+impl PriceDto {
+    fn pln(amount: i64) -> Self {
+        PriceDto {
+            amount,
+            currency: "PLN",
+            formatted: format_price_maud(amount),
+        }
+    }
+}
+
+/// Jedno zdjęcie w kilku rozmiarach przygotowanych przez Cloudinary
+#[derive(Debug, Serialize)]
+pub struct ImageDto {
+    pub thumbnail: String,
+    pub card: String,
+    pub mobile: String,
+    pub large: String,
+    pub original: String,
+}
+
+impl ImageDto {
+    pub(crate) fn from_url(url: &str) -> Self {
+        ImageDto {
+            thumbnail: transform_cloudinary_url(url, THUMBNAIL_IMAGE_TRANSFORMATION),
+            card: transform_cloudinary_url(url, CARD_IMAGE_TRANSFORMATION),
+            mobile: transform_cloudinary_url(url, MOBILE_IMAGE_TRANSFORMATION),
+            large: transform_cloudinary_url(url, PRODUCT_DETAIL_IMAGE_TRANSFORMATION),
+            original: url.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductAttributesDto {
+    pub size: Option<String>,
+    pub brand: Option<String>,
+    pub color: Option<String>,
+    pub material: Option<String>,
+}
+
+/// Produkt na liście - bez opisu i z jednym zdjęciem
+#[derive(Debug, Serialize)]
+pub struct ProductSummaryDto {
+    pub id: Uuid,
+    pub slug: String,
+    pub url: String,
+    pub name: String,
+    pub price: PriceDto,
+    pub on_sale: bool,
+    pub available: bool,
+    pub gender: LabeledValueDto<ProductGender>,
+    pub category: LabeledValueDto<Category>,
+    pub condition: LabeledValueDto<ProductCondition>,
+    pub size: Option<String>,
+    pub image: Option<ImageDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductDto {
+    pub id: Uuid,
+    pub slug: String,
+    pub url: String,
+    pub name: String,
+    pub description: String,
+    pub price: PriceDto,
+    pub on_sale: bool,
+    /// `false`, gdy produkt jest zarezerwowany w czyimś koszyku
+    pub available: bool,
+    pub gender: LabeledValueDto<ProductGender>,
+    pub category: LabeledValueDto<Category>,
+    pub condition: LabeledValueDto<ProductCondition>,
+    pub attributes: ProductAttributesDto,
+    pub images: Vec<ImageDto>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductListDto {
+    pub total_items: i64,
+    pub total_pages: i64,
+    pub current_page: i64,
+    pub per_page: i64,
+    pub data: Vec<ProductSummaryDto>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CategoryNodeDto {
+    pub category: LabeledValueDto<Category>,
+    pub url: String,
+    pub product_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GenderNodeDto {
+    pub gender: LabeledValueDto<ProductGender>,
+    pub url: String,
+    pub product_count: i64,
+    pub categories: Vec<CategoryNodeDto>,
+}
+
+pub(crate) fn absolute_url(app_state: &AppState, path: &str) -> String {
+    format!("{}{}", app_state.public_base_url, path)
+}
+
+fn to_summary_dto(app_state: &AppState, product: Product) -> ProductSummaryDto {
+    // Cena liczona przed przeniesieniem pól produktu do DTO
+    let price = PriceDto::pln(product.effective_price());
+    ProductSummaryDto {
+        url: absolute_url(app_state, &product.public_path()),
+        image: product.images.first().map(|url| ImageDto::from_url(url)),
+        id: product.id,
+        slug: product.slug,
+        name: product.name,
+        price,
+        on_sale: product.on_sale,
+        available: product.status == ProductStatus::Available,
+        gender: LabeledValueDto::new(product.gender),
+        category: LabeledValueDto::new(product.category),
+        condition: LabeledValueDto::new(product.condition),
+        size: product.size,
+    }
+}
+
+fn to_product_dto(app_state: &AppState, product: Product) -> ProductDto {
+    // Cena liczona przed przeniesieniem pól produktu do DTO
+    let price = PriceDto::pln(product.effective_price());
+    ProductDto {
+        url: absolute_url(app_state, &product.public_path()),
+        images: product
+            .images
+            .iter()
+            .map(|url| ImageDto::from_url(url))
+            .collect(),
+        id: product.id,
+        slug: product.slug,
+        name: product.name,
+        description: product.description,
+        price,
+        on_sale: product.on_sale,
+        available: product.status == ProductStatus::Available,
+        gender: LabeledValueDto::new(product.gender),
+        category: LabeledValueDto::new(product.category),
+        condition: LabeledValueDto::new(product.condition),
+        attributes: ProductAttributesDto {
+            size: product.size,
+            brand: product.brand,
+            color: product.color,
+            material: product.material,
+        },
+        created_at: product.created_at,
+        updated_at: product.updated_at,
+    }
+}
+
+/// GET /api/v1/products - te same filtry co listing w sklepie (`gender`, `category`,
+/// `condition`, `size`, `brand`, `price_min`, `price_max`, `search`, `sort_by`, `order`,
+/// `limit`, `offset`...). Zawsze tylko produkty widoczne w sklepie (dostępne i zarezerwowane).
+pub async fn list_products_v1_handler(
+    State(app_state): State<Arc<AppState>>,
+    client: ApiClient,
+    Query(params): Query<ListingParams>,
+) -> Result<Json<ProductListDto>, AppError> {
+    tracing::debug!("API v1 ({}): lista produktów {:?}", client.name, params);
+    let params = ListingParams {
+        status: None,
+        ..params
+    };
+    let page = crate::handlers::list_products(State(app_state.clone()), Query(params))
+        .await?
+        .0;
+
+    Ok(Json(ProductListDto {
+        total_items: page.total_items,
+        total_pages: page.total_pages,
+        current_page: page.current_page,
+        per_page: page.per_page,
+        data: page
+            .data
+            .into_iter()
+            .map(|product| to_summary_dto(&app_state, product))
+            .collect(),
+    }))
+}
+
+/// GET /api/v1/products/{product_ref} - produkt po slugu albo ID
+pub async fn get_product_v1_handler(
+    State(app_state): State<Arc<AppState>>,
+    client: ApiClient,
+    Path(product_ref): Path<String>,
+) -> Result<Json<ProductDto>, AppError> {
+    tracing::debug!("API v1 ({}): produkt {}", client.name, product_ref);
+    let product =
+        match Uuid::parse_str(&product_ref) {
+            Ok(product_id) => {
+                sqlx::query_as::<_, Product>(
+                    "SELECT * FROM products WHERE id = $1 AND status IN ('Available', 'Reserved')",
+                )
+                .bind(product_id)
+                .fetch_optional(&app_state.db_pool)
+                .await?
+            }
+            Err(_) => sqlx::query_as::<_, Product>(
+                "SELECT * FROM products WHERE slug = $1 AND status IN ('Available', 'Reserved')",
+            )
+            .bind(&product_ref)
+            .fetch_optional(&app_state.db_pool)
+            .await?,
+        };
+    let product = product.ok_or(AppError::NotFound)?;
+    Ok(Json(to_product_dto(&app_state, product)))
+}
+
+/// GET /api/v1/categories - drzewo płeć → kategorie z liczbą dostępnych produktów.
+/// Kategorie bez produktów są pomijane, tak jak w menu sklepu.
+pub async fn category_tree_v1_handler(
+    State(app_state): State<Arc<AppState>>,
+    client: ApiClient,
+) -> Result<Json<Vec<GenderNodeDto>>, AppError> {
+    tracing::debug!("API v1 ({}): drzewo kategorii", client.name);
+    Ok(Json(category_tree(&app_state).await?))
+}
+
+/// Drzewo kategorii wspólne dla REST (`/api/v1/categories`) i GraphQL
+pub(crate) async fn category_tree(app_state: &AppState) -> Result<Vec<GenderNodeDto>, AppError> {
+    let rows: Vec<(ProductGender, Category, i64)> = sqlx::query_as(
+        r#"
+        SELECT gender, category, COUNT(*)
+        FROM products
+        WHERE status = $1
+        GROUP BY gender, category
+        "#,
+    )
+    .bind(ProductStatus::Available)
+    .fetch_all(&app_state.db_pool)
+    .await?;
+    let counts: HashMap<(ProductGender, Category), i64> = rows
+        .into_iter()
+        .map(|(gender, category, count)| ((gender, category), count))
+        .collect();
+
+    let tree = ProductGender::iter()
+        .map(|gender| {
+            let categories: Vec<CategoryNodeDto> = Category::iter()
+                .filter_map(|category| {
+                    let count = *counts.get(&(gender, category))?;
+                    Some(CategoryNodeDto {
+                        url: absolute_url(
+                            app_state,
+                            &gender_listing_path(&gender, Some(&category)),
+                        ),
+                        category: LabeledValueDto::new(category),
+                        product_count: count,
+                    })
+                })
+                .collect();
+            GenderNodeDto {
+                url: absolute_url(app_state, &gender_listing_path(&gender, None)),
+                product_count: categories.iter().map(|c| c.product_count).sum(),
+                gender: LabeledValueDto::new(gender),
+                categories,
+            }
+        })
+        .collect();
+    Ok(tree)
+}
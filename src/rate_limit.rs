@@ -0,0 +1,200 @@
+// src/rate_limit.rs
+
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use moka::future::Cache;
+
+use crate::client_ip::{peer_ip, resolve_client_ip};
+use crate::{errors::AppError, state::AppState};
+
+/// Limit żądań dla grupy tras: `capacity` żądań naraz, potem `refill_per_minute` na minutę.
+pub struct RateLimitPolicy {
+    name: &'static str,
+    capacity: f64,
+    refill_per_minute: f64,
+}
+
+/// Logowanie, rejestracja i reset hasła - ochrona przed zgadywaniem haseł i spamem e-maili
+pub const AUTH_POLICY: RateLimitPolicy = RateLimitPolicy {
+    name: "auth",
+    capacity: 10.0,
+    refill_per_minute: 5.0,
+};
+
+/// Zakładanie sesji i koszyków gości - ochrona przed zalewaniem bazy pustymi koszykami
+pub const GUEST_CART_POLICY: RateLimitPolicy = RateLimitPolicy {
+    name: "guest_cart",
+    capacity: 30.0,
+    refill_per_minute: 30.0,
+};
+
+/// Sprawdzanie zamówień gości po numerze i e-mailu - ochrona przed zgadywaniem numerów zamówień
+pub const GUEST_ORDER_LOOKUP_POLICY: RateLimitPolicy = RateLimitPolicy {
+    name: "guest_order_lookup",
+    capacity: 5.0,
+    refill_per_minute: 2.0,
+};
+
+/// Wyszukiwanie po zdjęciu - dekodowanie przesłanych zdjęć obciąża serwer
+pub const PHOTO_SEARCH_POLICY: RateLimitPolicy = RateLimitPolicy {
+    name: "photo_search",
+    capacity: 10.0,
+    refill_per_minute: 5.0,
+};
+
+/// Beacony wydajności (RUM) - jeden na wejście na stronę, limit chroni tabelę przed zalewaniem
+pub const RUM_POLICY: RateLimitPolicy = RateLimitPolicy {
+    name: "rum",
+    capacity: 30.0,
+    refill_per_minute: 20.0,
+};
+
+/// Kubełek żetonów jednego adresu IP dla jednej polityki.
+pub struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(policy: &RateLimitPolicy) -> Self {
+        TokenBucket {
+            tokens: policy.capacity,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// Zabiera jeden żeton. Przy pustym kubełku zwraca liczbę sekund do następnego żetonu.
+    fn try_take(&mut self, policy: &RateLimitPolicy) -> Result<(), u64> {
+        let now = Instant::now();
+        let elapsed_minutes = now.duration_since(self.updated_at).as_secs_f64() / 60.0;
+        self.tokens =
+            (self.tokens + elapsed_minutes * policy.refill_per_minute).min(policy.capacity);
+        self.updated_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing_minutes = (1.0 - self.tokens) / policy.refill_per_minute;
+            Err((missing_minutes * 60.0).ceil().max(1.0) as u64)
+        }
+    }
+}
+
+pub type RateLimitBuckets = Cache<String, Arc<Mutex<TokenBucket>>>;
+
+/// Kubełki wygasają po kwadransie bezczynności - wtedy i tak byłyby znowu pełne.
+pub fn new_rate_limit_buckets() -> RateLimitBuckets {
+    Cache::builder()
+        .max_capacity(100_000)
+        .time_to_idle(Duration::from_secs(15 * 60))
+        .build()
+}
+
+/// Adres klienta, od którego liczymy limit (zob. `client_ip::resolve_client_ip`)
+fn client_key(request: &Request, trusted_proxies: &[IpAddr]) -> String {
+    resolve_client_ip(
+        peer_ip(request.extensions()),
+        request.headers(),
+        trusted_proxies,
+    )
+    .map_or_else(|| "unknown".to_string(), |ip| ip.to_string())
+}
+
+/// Klient i trasa żądania jako własne `String`i. `Request` nie jest `Sync`, więc referencja
+/// do niego trzymana przez `.await` zrobiłaby z middleware'u future bez `Send`.
+fn request_identity(request: &Request, trusted_proxies: &[IpAddr]) -> (String, String) {
+    let route = format!("{} {}", request.method(), request.uri().path());
+    (client_key(request, trusted_proxies), route)
+}
+
+async fn check_rate_limit(
+    app_state: &AppState,
+    policy: &RateLimitPolicy,
+    client: String,
+    route: String,
+) -> Result<(), AppError> {
+    let bucket = app_state
+        .rate_limit_buckets
+        .get_with(format!("{}:{}", policy.name, client), async {
+            Arc::new(Mutex::new(TokenBucket::full(policy)))
+        })
+        .await;
+
+    let result = bucket
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .try_take(policy);
+
+    result.map_err(|retry_after_secs| {
+        tracing::warn!(
+            "Limit żądań '{}' przekroczony dla {} ({})",
+            policy.name,
+            client,
+            route
+        );
+        AppError::TooManyRequests(retry_after_secs)
+    })
+}
+
+/// Middleware dla tras logowania, rejestracji i resetu hasła.
+pub async fn rate_limit_auth(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (client, route) = request_identity(&request, &app_state.trusted_proxies);
+    check_rate_limit(&app_state, &AUTH_POLICY, client, route).await?;
+    Ok(next.run(request).await)
+}
+
+/// Middleware dla tras, które zakładają sesję lub koszyk gościa.
+pub async fn rate_limit_guest_cart(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (client, route) = request_identity(&request, &app_state.trusted_proxies);
+    check_rate_limit(&app_state, &GUEST_CART_POLICY, client, route).await?;
+    Ok(next.run(request).await)
+}
+
+/// Middleware dla wyszukiwania zamówienia gościa.
+pub async fn rate_limit_guest_order_lookup(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (client, route) = request_identity(&request, &app_state.trusted_proxies);
+    check_rate_limit(&app_state, &GUEST_ORDER_LOOKUP_POLICY, client, route).await?;
+    Ok(next.run(request).await)
+}
+
+/// Middleware dla publicznego wyszukiwania po zdjęciu.
+pub async fn rate_limit_photo_search(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (client, route) = request_identity(&request, &app_state.trusted_proxies);
+    check_rate_limit(&app_state, &PHOTO_SEARCH_POLICY, client, route).await?;
+    Ok(next.run(request).await)
+}
+
+/// Middleware dla beaconów wydajności.
+pub async fn rate_limit_rum(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let (client, route) = request_identity(&request, &app_state.trusted_proxies);
+    check_rate_limit(&app_state, &RUM_POLICY, client, route).await?;
+    Ok(next.run(request).await)
+}
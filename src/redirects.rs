@@ -0,0 +1,218 @@
+// src/redirects.rs
+
+// Przekierowania starych adresów z tabeli `redirects`. Zmiana sluga produktu dopisuje wpis sama
+// (`record_moved_path`), resztę zarządza się w panelu. Tabelę sprawdza `redirect_not_found`
+// dopiero wtedy, gdy żądanie skończyło się 404 - zwykłe wejścia nie kosztują dodatkowego zapytania.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::state::AppState;
+
+pub const MAX_REDIRECT_PATH_LEN: usize = 500;
+/// Kody do wyboru w panelu: stałe i tymczasowe przekierowanie
+pub const REDIRECT_STATUS_CODES: [i16; 2] = [301, 302];
+
+/// Ścieżki, których nie przekierowujemy - API, fragmenty HTMX i pliki statyczne
+const SKIPPED_PREFIXES: [&str; 3] = ["/api/", "/htmx/", "/static/"];
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Redirect {
+    pub id: Uuid,
+    pub source_path: String,
+    pub target_path: String,
+    pub status_code: i16,
+    pub hits: i64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+    pub created_by_email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Ścieżka w postaci zapisywanej w tabeli: bez parametrów, fragmentu i końcowego ukośnika
+pub fn normalize_redirect_path(path: &str) -> String {
+    let path = path.trim().split(['?', '#']).next().unwrap_or_default();
+    match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Źródło: ścieżka w sklepie (z pola panelu, więc może przyjść z domeną - tę odcinamy)
+pub fn is_valid_source_path(path: &str) -> bool {
+    path.starts_with('/')
+        && !path.starts_with("//")
+        && path != "/"
+        && !SKIPPED_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix) || path == prefix.trim_end_matches('/'))
+}
+
+/// Cel: ścieżka w sklepie albo adres https
+pub fn is_valid_target(target: &str) -> bool {
+    (target.starts_with('/') && !target.starts_with("//")) || target.starts_with("https://")
+}
+
+pub async fn list_redirects(pool: &PgPool) -> Result<Vec<Redirect>, AppError> {
+    let redirects = sqlx::query_as::<_, Redirect>(
+        r#"
+            SELECT r.id, r.source_path, r.target_path, r.status_code, r.hits, r.last_hit_at,
+                   u.email AS created_by_email, r.created_at
+            FROM redirects r
+            LEFT JOIN users u ON u.id = r.created_by
+            ORDER BY r.created_at DESC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(redirects)
+}
+
+/// Zwraca `false`, gdy dla tej ścieżki jest już przekierowanie
+pub async fn create_redirect(
+    pool: &PgPool,
+    source_path: &str,
+    target_path: &str,
+    status_code: i16,
+    admin_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+            INSERT INTO redirects (source_path, target_path, status_code, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (source_path) DO NOTHING
+        "#,
+    )
+    .bind(source_path)
+    .bind(target_path)
+    .bind(status_code)
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_redirect(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM redirects WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+/// Strona przeniesiona pod nowy adres (np. zmiana sluga produktu) - w transakcji zmiany.
+/// Przepina też wcześniejsze przekierowania na stary adres, żeby nie powstawały łańcuchy,
+/// i usuwa wpis dla nowego adresu, bo ten znowu działa.
+pub async fn record_moved_path(
+    conn: &mut PgConnection,
+    old_path: &str,
+    new_path: &str,
+    admin_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM redirects WHERE source_path = $1")
+        .bind(new_path)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE redirects SET target_path = $2 WHERE target_path = $1")
+        .bind(old_path)
+        .bind(new_path)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        r#"
+            INSERT INTO redirects (source_path, target_path, status_code, created_by)
+            VALUES ($1, $2, 301, $3)
+            ON CONFLICT (source_path)
+            DO UPDATE SET target_path = EXCLUDED.target_path, status_code = 301
+        "#,
+    )
+    .bind(old_path)
+    .bind(new_path)
+    .bind(admin_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Cel i kod przekierowania dla ścieżki; trafienie od razu podbija licznik
+async fn take_redirect(pool: &PgPool, path: &str) -> Result<Option<(String, i16)>, AppError> {
+    let redirect = sqlx::query_as::<_, (String, i16)>(
+        r#"
+            UPDATE redirects SET hits = hits + 1, last_hit_at = NOW()
+            WHERE source_path = $1
+            RETURNING target_path, status_code
+        "#,
+    )
+    .bind(path)
+    .fetch_optional(pool)
+    .await?;
+    Ok(redirect)
+}
+
+/// Middleware: odpowiedź 404 na GET zamienia na przekierowanie, jeśli ścieżka jest w tabeli.
+/// Działa także dla tras, które istnieją, ale nie znalazły zasobu (np. stary slug produktu).
+pub async fn redirect_not_found(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = normalize_redirect_path(request.uri().path());
+    let query = request.uri().query().map(str::to_string);
+    let is_htmx = request.headers().contains_key("HX-Request");
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::NOT_FOUND
+        || (method != Method::GET && method != Method::HEAD)
+        || !is_valid_source_path(&path)
+    {
+        return response;
+    }
+
+    let (target, status_code) = match take_redirect(&app_state.db_pool, &path).await {
+        Ok(Some(redirect)) => redirect,
+        Ok(None) => return response,
+        Err(e) => {
+            tracing::warn!("Nie sprawdzono przekierowania dla '{}': {:?}", path, e);
+            return response;
+        }
+    };
+    // Parametry (np. UTM) przechodzą na nowy adres, jeśli cel nie ma własnych
+    let location = match query {
+        Some(query) if !target.contains('?') => format!("{}?{}", target, query),
+        _ => target,
+    };
+    let Ok(location_value) = HeaderValue::from_str(&location) else {
+        tracing::warn!(
+            "Nieprawidłowy cel przekierowania '{}' -> '{}'",
+            path,
+            location
+        );
+        return response;
+    };
+    tracing::info!(
+        "Przekierowanie {} '{}' -> '{}'",
+        status_code,
+        path,
+        location
+    );
+
+    // HTMX sam podąża za 3xx i podmienia treść bez zmiany adresu - przeglądarka ma przejść sama
+    if is_htmx {
+        return (StatusCode::OK, [("HX-Redirect", location_value)]).into_response();
+    }
+    let status = StatusCode::from_u16(status_code as u16).unwrap_or(StatusCode::MOVED_PERMANENTLY);
+    (status, [(header::LOCATION, location_value)]).into_response()
+}
@@ -0,0 +1,270 @@
+// src/repo/carts.rs
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{
+    CartItem, CartItemWithProduct, Category, ProductCondition, ProductGender, ProductStatus,
+    ShoppingCart,
+};
+
+/// Właściciel koszyka: konto klienta albo sesja gościa (`guest_cart_id`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CartOwner {
+    User(Uuid),
+    Guest(Uuid),
+}
+
+pub async fn find_by_id<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<ShoppingCart>, AppError> {
+    Ok(sqlx::query_as!(
+        ShoppingCart,
+        "SELECT id, user_id, guest_session_id, created_at, updated_at FROM shopping_carts WHERE id = $1",
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn find_by_owner<'e>(
+    executor: impl PgExecutor<'e>,
+    owner: CartOwner,
+) -> Result<Option<ShoppingCart>, AppError> {
+    let cart = match owner {
+        CartOwner::User(user_id) => {
+            sqlx::query_as!(
+                ShoppingCart,
+                "SELECT id, user_id, guest_session_id, created_at, updated_at FROM shopping_carts WHERE user_id = $1",
+                user_id
+            )
+            .fetch_optional(executor)
+            .await?
+        }
+        CartOwner::Guest(guest_session_id) => {
+            sqlx::query_as!(
+                ShoppingCart,
+                "SELECT id, user_id, guest_session_id, created_at, updated_at FROM shopping_carts WHERE guest_session_id = $1",
+                guest_session_id
+            )
+            .fetch_optional(executor)
+            .await?
+        }
+    };
+    Ok(cart)
+}
+
+/// Koszyk zablokowany do końca transakcji (składanie zamówienia, zmiana zawartości)
+pub async fn find_by_owner_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    owner: CartOwner,
+) -> Result<Option<ShoppingCart>, AppError> {
+    let cart = match owner {
+        CartOwner::User(user_id) => {
+            sqlx::query_as!(
+                ShoppingCart,
+                "SELECT id, user_id, guest_session_id, created_at, updated_at FROM shopping_carts WHERE user_id = $1 FOR UPDATE",
+                user_id
+            )
+            .fetch_optional(executor)
+            .await?
+        }
+        CartOwner::Guest(guest_session_id) => {
+            sqlx::query_as!(
+                ShoppingCart,
+                "SELECT id, user_id, guest_session_id, created_at, updated_at FROM shopping_carts WHERE guest_session_id = $1 FOR UPDATE",
+                guest_session_id
+            )
+            .fetch_optional(executor)
+            .await?
+        }
+    };
+    Ok(cart)
+}
+
+/// Koszyk właściciela zablokowany do końca transakcji; gdy go jeszcze nie ma, zakłada nowy
+pub async fn cart_for_owner_for_update(
+    conn: &mut PgConnection,
+    owner: CartOwner,
+) -> Result<ShoppingCart, AppError> {
+    match find_by_owner_for_update(&mut *conn, owner).await? {
+        Some(cart) => Ok(cart),
+        None => create(&mut *conn, owner).await,
+    }
+}
+
+pub async fn create<'e>(
+    executor: impl PgExecutor<'e>,
+    owner: CartOwner,
+) -> Result<ShoppingCart, AppError> {
+    let (user_id, guest_session_id) = match owner {
+        CartOwner::User(user_id) => (Some(user_id), None),
+        CartOwner::Guest(guest_session_id) => (None, Some(guest_session_id)),
+    };
+    Ok(sqlx::query_as!(
+        ShoppingCart,
+        r#"
+        INSERT INTO shopping_carts (user_id, guest_session_id)
+        VALUES ($1, $2)
+        RETURNING id, user_id, guest_session_id, created_at, updated_at
+        "#,
+        user_id,
+        guest_session_id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Pozycje koszyka zablokowane do końca transakcji
+pub async fn items_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_id: Uuid,
+) -> Result<Vec<CartItem>, AppError> {
+    Ok(sqlx::query_as!(
+        CartItem,
+        "SELECT id, cart_id, product_id, added_at FROM cart_items WHERE cart_id = $1 FOR UPDATE",
+        cart_id
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+/// Pozycje koszyka razem z produktami, w kolejności dodania
+pub async fn items_with_products<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_id: Uuid,
+) -> Result<Vec<CartItemWithProduct>, AppError> {
+    Ok(sqlx::query_as!(
+        CartItemWithProduct,
+        r#"
+        SELECT ci.id AS cart_item_id, ci.cart_id, ci.added_at, p.id AS product_id, p.name, p.slug,
+               p.description AS "description!", p.price, p.gender AS "gender: ProductGender",
+               p.condition AS "condition: ProductCondition", p.category AS "category: Category",
+               p.on_sale, p.status AS "status: ProductStatus", p.images AS "images!", p.size,
+               p.brand, p.color, p.material, p.meta_title, p.meta_description, p.og_image,
+               p.sale_price, p.sale_starts_at, p.sale_ends_at,
+               p.created_at, p.updated_at
+        FROM cart_items ci
+        JOIN products p ON ci.product_id = p.id
+        WHERE ci.cart_id = $1
+        ORDER BY ci.added_at ASC
+        "#,
+        cart_id
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+/// Dodaje produkt do koszyka; `false`, gdy już w nim był
+pub async fn add_item<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_id: Uuid,
+    product_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO cart_items (cart_id, product_id) VALUES ($1, $2)
+        ON CONFLICT (cart_id, product_id) DO NOTHING
+        "#,
+        cart_id,
+        product_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Usuwa produkt z koszyka; `false`, gdy go w nim nie było
+pub async fn remove_item<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_id: Uuid,
+    product_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM cart_items WHERE cart_id = $1 AND product_id = $2",
+        cart_id,
+        product_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Usuwa pozycję po jej ID (np. produkt w międzyczasie sprzedany)
+pub async fn delete_item<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_item_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM cart_items WHERE id = $1", cart_item_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+pub async fn clear<'e>(executor: impl PgExecutor<'e>, cart_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM cart_items WHERE cart_id = $1", cart_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Odświeża `updated_at` koszyka (na nim opiera się sprzątanie porzuconych koszyków)
+pub async fn touch<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_id: Uuid,
+) -> Result<DateTime<Utc>, AppError> {
+    Ok(sqlx::query_scalar!(
+        "UPDATE shopping_carts SET updated_at = CURRENT_TIMESTAMP WHERE id = $1 RETURNING updated_at",
+        cart_id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn delete<'e>(executor: impl PgExecutor<'e>, cart_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM shopping_carts WHERE id = $1", cart_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Przenosi pozycje koszyka gościa do koszyka klienta, pomijając produkty, które klient już ma
+pub async fn move_items<'e>(
+    executor: impl PgExecutor<'e>,
+    from_cart_id: Uuid,
+    to_cart_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE cart_items
+        SET cart_id = $1
+        WHERE cart_id = $2 AND product_id NOT IN (
+            SELECT product_id FROM cart_items WHERE cart_id = $1
+        )
+        "#,
+        to_cart_id,
+        from_cart_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Odpina sesję gościa od koszyka, który należy już do klienta
+pub async fn detach_guest_session<'e>(
+    executor: impl PgExecutor<'e>,
+    cart_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE shopping_carts SET guest_session_id = NULL WHERE id = $1 AND user_id = $2",
+        cart_id,
+        user_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
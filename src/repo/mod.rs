@@ -0,0 +1,23 @@
+// src/repo/mod.rs
+
+// Warstwa dostępu do bazy dla produktów, koszyków, zamówień i kont klientów. Zapytania są pisane makrami
+// `sqlx::query!`/`query_as!`, więc kompilator sprawdza je ze schematem: zmiana kolumny
+// w migracji bez poprawki tutaj kończy się błędem kompilacji, a nie błędem na produkcji.
+//
+// Makra potrzebują przy kompilacji bazy (`DATABASE_URL`) albo metadanych offline z katalogu
+// `.sqlx` w repozytorium. Po zmianie zapytania lub migracji odświeżamy je na bazie
+// z aktualnymi migracjami:
+//
+//     cargo sqlx prepare -- --all-targets
+//
+// i commitujemy `.sqlx` razem ze zmianą. Bez `DATABASE_URL` (np. w CI) sqlx sam używa `.sqlx`.
+//
+// Kolumny wypisujemy jawnie zamiast `SELECT *`: `"kolumna!"` wymusza typ bez `Option`
+// (kolumny, które historycznie dopuszczają NULL, ale w praktyce zawsze mają wartość),
+// a `"kolumna: Typ"` wskazuje enum mapowany na typ Postgresa. Parametry z enumami
+// przekazujemy jako `wartość as _` - makro nie zna typów Rusta dla enumów Postgresa.
+
+pub mod carts;
+pub mod orders;
+pub mod products;
+pub mod users;
@@ -0,0 +1,408 @@
+// src/repo/orders.rs
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Order, OrderItem, OrderStatus, PaymentMethod};
+use crate::vat::{ItemVat, OrderVat, VatBreakdown};
+
+/// Dane nowego zamówienia wraz z rozbiciem VAT z chwili zakupu
+pub struct NewOrder<'a> {
+    pub id: Uuid,
+    pub order_number: &'a str,
+    pub user_id: Option<Uuid>,
+    pub guest_email: Option<&'a str>,
+    pub guest_session_id: Option<Uuid>,
+    pub status: OrderStatus,
+    pub total_price: i64,
+    pub shipping_first_name: &'a str,
+    pub shipping_last_name: &'a str,
+    pub shipping_address_line1: &'a str,
+    pub shipping_address_line2: Option<&'a str>,
+    pub shipping_city: &'a str,
+    pub shipping_postal_code: &'a str,
+    pub shipping_country: &'a str,
+    pub shipping_phone: &'a str,
+    pub payment_method: PaymentMethod,
+    pub shipping_method_name: Option<&'a str>,
+    pub inpost_locker_code: Option<&'a str>,
+    pub inpost_locker_address: Option<&'a str>,
+    pub coupon_id: Option<Uuid>,
+    pub discount_amount: i64,
+    pub gift_card_amount: i64,
+    pub vat: &'a OrderVat,
+}
+
+pub async fn find_by_id<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<Order>, AppError> {
+    Ok(sqlx::query_as!(
+        Order,
+        r#"
+        SELECT id, order_number, user_id, order_date, status AS "status: OrderStatus", total_price,
+               shipping_first_name, shipping_last_name, shipping_address_line1,
+               shipping_address_line2, shipping_city, shipping_postal_code, shipping_country,
+               shipping_phone, payment_method AS "payment_method: PaymentMethod",
+               shipping_method_name, inpost_locker_code, inpost_locker_address, coupon_id,
+               discount_amount, gift_card_amount, guest_email, guest_session_id, created_at,
+               updated_at
+        FROM orders
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Zamówienia klienta od najnowszych ("Moje zamówienia")
+pub async fn for_user<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+) -> Result<Vec<Order>, AppError> {
+    Ok(sqlx::query_as!(
+        Order,
+        r#"
+        SELECT id, order_number, user_id, order_date, status AS "status: OrderStatus", total_price,
+               shipping_first_name, shipping_last_name, shipping_address_line1,
+               shipping_address_line2, shipping_city, shipping_postal_code, shipping_country,
+               shipping_phone, payment_method AS "payment_method: PaymentMethod",
+               shipping_method_name, inpost_locker_code, inpost_locker_address, coupon_id,
+               discount_amount, gift_card_amount, guest_email, guest_session_id, created_at,
+               updated_at
+        FROM orders
+        WHERE user_id = $1
+        ORDER BY order_date DESC
+        "#,
+        user_id
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+/// Zamówienie gościa po e-mailu i numerze zamówienia (albo początku ID ze starszych maili).
+/// Bez `id_prefix` szukamy tylko po numerze zamówienia.
+pub async fn find_guest_order_id<'e>(
+    executor: impl PgExecutor<'e>,
+    email: &str,
+    order_number: &str,
+    id_prefix: Option<&str>,
+) -> Result<Option<Uuid>, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"
+        SELECT id FROM orders
+        WHERE user_id IS NULL
+          AND LOWER(guest_email) = LOWER($1)
+          AND (UPPER(order_number) = UPPER($2) OR REPLACE(id::text, '-', '') LIKE $3 || '%')
+        ORDER BY order_date DESC
+        LIMIT 1
+        "#,
+        email,
+        order_number,
+        id_prefix
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn exists<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> Result<bool, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM orders WHERE id = $1) AS "exists!""#,
+        id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Rezerwuje ponowną wysyłkę potwierdzenia; `false`, gdy zamówienia nie ma albo potwierdzenie
+/// wysłano w ciągu ostatnich `cooldown_minutes` minut
+pub async fn claim_confirmation_resend<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    cooldown_minutes: i32,
+) -> Result<bool, AppError> {
+    let claimed = sqlx::query_scalar!(
+        r#"
+        UPDATE orders SET confirmation_sent_at = NOW()
+        WHERE id = $1
+          AND (confirmation_sent_at IS NULL
+               OR confirmation_sent_at < NOW() - make_interval(mins => $2))
+        RETURNING id
+        "#,
+        id,
+        cooldown_minutes
+    )
+    .fetch_optional(executor)
+    .await?;
+    Ok(claimed.is_some())
+}
+
+/// Zamówienie zablokowane do końca transakcji (edycja pozycji, zmiana statusu)
+pub async fn find_by_id_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<Order>, AppError> {
+    Ok(sqlx::query_as!(
+        Order,
+        r#"
+        SELECT id, order_number, user_id, order_date, status AS "status: OrderStatus", total_price,
+               shipping_first_name, shipping_last_name, shipping_address_line1,
+               shipping_address_line2, shipping_city, shipping_postal_code, shipping_country,
+               shipping_phone, payment_method AS "payment_method: PaymentMethod",
+               shipping_method_name, inpost_locker_code, inpost_locker_address, coupon_id,
+               discount_amount, gift_card_amount, guest_email, guest_session_id, created_at,
+               updated_at
+        FROM orders
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Sam status zamówienia, z blokadą wiersza do końca transakcji
+pub async fn status_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<OrderStatus>, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT status AS "status: OrderStatus" FROM orders WHERE id = $1 FOR UPDATE"#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn insert<'e>(
+    executor: impl PgExecutor<'e>,
+    order: &NewOrder<'_>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO orders (
+            id, order_number, user_id, guest_email, guest_session_id, status, total_price,
+            shipping_first_name, shipping_last_name, shipping_address_line1, shipping_address_line2,
+            shipping_city, shipping_postal_code, shipping_country, shipping_phone,
+            payment_method, shipping_method_name, inpost_locker_code, inpost_locker_address,
+            coupon_id, discount_amount, gift_card_amount, vat_scheme, vat_rate_bp, shipping_cost,
+            net_total, vat_total
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18,
+                  $19, $20, $21, $22, $23, $24, $25, $26, $27)
+        "#,
+        order.id,
+        order.order_number,
+        order.user_id,
+        order.guest_email,
+        order.guest_session_id,
+        order.status as _,
+        order.total_price,
+        order.shipping_first_name,
+        order.shipping_last_name,
+        order.shipping_address_line1,
+        order.shipping_address_line2,
+        order.shipping_city,
+        order.shipping_postal_code,
+        order.shipping_country,
+        order.shipping_phone,
+        order.payment_method as _,
+        order.shipping_method_name,
+        order.inpost_locker_code,
+        order.inpost_locker_address,
+        order.coupon_id,
+        order.discount_amount,
+        order.gift_card_amount,
+        order.vat.scheme as _,
+        order.vat.rate_bp,
+        order.vat.shipping_cost,
+        order.vat.net_total,
+        order.vat.vat_total
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn insert_item<'e>(
+    executor: impl PgExecutor<'e>,
+    order_id: Uuid,
+    product_id: Uuid,
+    price_at_purchase: i64,
+    vat_rate_bp: i32,
+    item_vat: &ItemVat,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO order_items (
+            order_id, product_id, price_at_purchase, vat_rate_bp, discount_share, net_amount, vat_amount
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        order_id,
+        product_id,
+        price_at_purchase,
+        vat_rate_bp,
+        item_vat.discount_share,
+        item_vat.breakdown.net,
+        item_vat.breakdown.vat
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Pozycja zamówienia zablokowana do końca transakcji
+pub async fn find_item_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    order_id: Uuid,
+    order_item_id: Uuid,
+) -> Result<Option<OrderItem>, AppError> {
+    Ok(sqlx::query_as!(
+        OrderItem,
+        r#"
+        SELECT id, order_id, product_id, price_at_purchase
+        FROM order_items
+        WHERE id = $1 AND order_id = $2
+        FOR UPDATE
+        "#,
+        order_item_id,
+        order_id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn item_count<'e>(
+    executor: impl PgExecutor<'e>,
+    order_id: Uuid,
+) -> Result<i64, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM order_items WHERE order_id = $1"#,
+        order_id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn delete_item<'e>(
+    executor: impl PgExecutor<'e>,
+    order_item_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM order_items WHERE id = $1", order_item_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Udział pozycji w rabacie zamówienia, zapisany przy zakupie
+pub async fn item_discount_share<'e>(
+    executor: impl PgExecutor<'e>,
+    order_item_id: Uuid,
+) -> Result<i64, AppError> {
+    Ok(sqlx::query_scalar!(
+        "SELECT discount_share FROM order_items WHERE id = $1",
+        order_item_id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Pomniejsza sumy zamówienia o usuniętą pozycję (po rabacie): rabat o jej udział, część
+/// opłaconą kartą podarunkową o `gift_card_share`, a kwotę do zapłaty o resztę (`cash_share`).
+/// Suma dalej wynosi produkty - rabat + dostawa - karta.
+pub async fn reduce_totals<'e>(
+    executor: impl PgExecutor<'e>,
+    order_id: Uuid,
+    cash_share: i64,
+    gift_card_share: i64,
+    discount_share: i64,
+    breakdown: VatBreakdown,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE orders
+        SET total_price = GREATEST(total_price - $1, 0),
+            gift_card_amount = GREATEST(gift_card_amount - $2, 0),
+            discount_amount = discount_amount - $3,
+            net_total = net_total - $4, vat_total = vat_total - $5
+        WHERE id = $6
+        "#,
+        cash_share,
+        gift_card_share,
+        discount_share,
+        breakdown.net,
+        breakdown.vat,
+        order_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Zapisuje zwrot środków za pozycję usuniętą z zamówienia
+pub async fn insert_refund<'e>(
+    executor: impl PgExecutor<'e>,
+    item: &OrderItem,
+    amount: i64,
+    refund_vat: VatBreakdown,
+    reason: &str,
+    created_by: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO order_refunds (order_id, product_id, amount, net_amount, vat_amount, reason, created_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        item.order_id,
+        item.product_id,
+        amount,
+        refund_vat.net,
+        refund_vat.vat,
+        reason,
+        created_by
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn items<'e>(
+    executor: impl PgExecutor<'e>,
+    order_id: Uuid,
+) -> Result<Vec<OrderItem>, AppError> {
+    Ok(sqlx::query_as!(
+        OrderItem,
+        "SELECT id, order_id, product_id, price_at_purchase FROM order_items WHERE order_id = $1 ORDER BY id",
+        order_id
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+pub async fn product_ids<'e>(
+    executor: impl PgExecutor<'e>,
+    order_id: Uuid,
+) -> Result<Vec<Uuid>, AppError> {
+    Ok(sqlx::query_scalar!(
+        "SELECT product_id FROM order_items WHERE order_id = $1",
+        order_id
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+/// Trwale usuwa zamówienie razem z pozycjami; `false`, gdy zamówienie nie istniało
+pub async fn delete<'e>(executor: impl PgExecutor<'e>, order_id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        r#"
+        WITH removed_items AS (DELETE FROM order_items WHERE order_id = $1)
+        DELETE FROM orders WHERE id = $1
+        "#,
+        order_id
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,425 @@
+// src/repo/products.rs
+
+use chrono::{DateTime, Utc};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Category, Product, ProductCondition, ProductGender, ProductStatus};
+
+/// Dane nowego produktu - znaczniki czasu nadaje baza
+pub struct NewProduct<'a> {
+    pub id: Uuid,
+    pub name: &'a str,
+    pub slug: &'a str,
+    pub description: &'a str,
+    pub price: i64,
+    pub gender: ProductGender,
+    pub condition: ProductCondition,
+    pub category: Category,
+    pub status: ProductStatus,
+    pub images: &'a [String],
+    pub on_sale: bool,
+    pub size: Option<&'a str>,
+    pub brand: Option<&'a str>,
+    pub color: Option<&'a str>,
+    pub material: Option<&'a str>,
+    pub meta_title: Option<&'a str>,
+    pub meta_description: Option<&'a str>,
+    pub og_image: Option<&'a str>,
+    pub sale_price: Option<i64>,
+    pub sale_starts_at: Option<DateTime<Utc>>,
+    pub sale_ends_at: Option<DateTime<Utc>>,
+}
+
+pub async fn find_by_id<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT id, name, slug, description AS "description!", price,
+               gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+               category AS "category: Category", status AS "status: ProductStatus",
+               images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        FROM products
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn find_by_slug<'e>(
+    executor: impl PgExecutor<'e>,
+    slug: &str,
+) -> Result<Option<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT id, name, slug, description AS "description!", price,
+               gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+               category AS "category: Category", status AS "status: ProductStatus",
+               images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        FROM products
+        WHERE slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Produkt tylko wtedy, gdy ma podany status (np. zarezerwowany pod osobisty link)
+pub async fn find_by_id_with_status<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    status: ProductStatus,
+) -> Result<Option<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT id, name, slug, description AS "description!", price,
+               gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+               category AS "category: Category", status AS "status: ProductStatus",
+               images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        FROM products
+        WHERE id = $1 AND status = $2
+        "#,
+        id,
+        status as _
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Produkt zablokowany do końca transakcji (zmiana statusu, dodanie do koszyka, edycja)
+pub async fn find_by_id_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT id, name, slug, description AS "description!", price,
+               gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+               category AS "category: Category", status AS "status: ProductStatus",
+               images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        FROM products
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn find_by_ids<'e>(
+    executor: impl PgExecutor<'e>,
+    ids: &[Uuid],
+) -> Result<Vec<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT id, name, slug, description AS "description!", price,
+               gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+               category AS "category: Category", status AS "status: ProductStatus",
+               images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        FROM products
+        WHERE id = ANY($1)
+        "#,
+        ids
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+pub async fn find_by_ids_for_update<'e>(
+    executor: impl PgExecutor<'e>,
+    ids: &[Uuid],
+) -> Result<Vec<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT id, name, slug, description AS "description!", price,
+               gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+               category AS "category: Category", status AS "status: ProductStatus",
+               images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        FROM products
+        WHERE id = ANY($1)
+        FOR UPDATE
+        "#,
+        ids
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+/// Sprzedane produkty dodane przez admina do publicznego archiwum, od ostatnio dodanych
+pub async fn sold_archive<'e>(
+    executor: impl PgExecutor<'e>,
+    limit: i64,
+) -> Result<Vec<Product>, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        SELECT p.id, p.name, p.slug, p.description AS "description!", p.price,
+               p.gender AS "gender: ProductGender", p.condition AS "condition: ProductCondition",
+               p.category AS "category: Category", p.status AS "status: ProductStatus",
+               p.images AS "images!", p.on_sale, p.size, p.brand, p.color, p.material,
+               p.meta_title, p.meta_description, p.og_image, p.sale_price, p.sale_starts_at,
+               p.sale_ends_at, p.created_at, p.updated_at
+        FROM products p
+        JOIN sold_archive_products a ON a.product_id = p.id
+        WHERE p.status = $1
+        ORDER BY a.added_at DESC
+        LIMIT $2
+        "#,
+        ProductStatus::Sold as _,
+        limit
+    )
+    .fetch_all(executor)
+    .await?)
+}
+
+pub async fn status<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<ProductStatus>, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT status AS "status: ProductStatus" FROM products WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+pub async fn insert<'e>(
+    executor: impl PgExecutor<'e>,
+    product: &NewProduct<'_>,
+) -> Result<Product, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        INSERT INTO products (
+            id, name, slug, description, price, gender, condition, category, status, images,
+            on_sale, size, brand, color, material, meta_title, meta_description, og_image,
+            sale_price, sale_starts_at, sale_ends_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18,
+                $19, $20, $21)
+        RETURNING id, name, slug, description AS "description!", price,
+                  gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+                  category AS "category: Category", status AS "status: ProductStatus",
+                  images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        "#,
+        product.id,
+        product.name,
+        product.slug,
+        product.description,
+        product.price,
+        product.gender as _,
+        product.condition as _,
+        product.category as _,
+        product.status as _,
+        product.images,
+        product.on_sale,
+        product.size,
+        product.brand,
+        product.color,
+        product.material,
+        product.meta_title,
+        product.meta_description,
+        product.og_image,
+        product.sale_price,
+        product.sale_starts_at,
+        product.sale_ends_at
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Zapisuje edytowalne pola produktu (także sluga - stary adres przekierowuje handler)
+pub async fn update<'e>(
+    executor: impl PgExecutor<'e>,
+    product: &Product,
+) -> Result<Product, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        UPDATE products
+        SET name = $1, description = $2, price = $3, gender = $4, condition = $5, category = $6,
+            status = $7, images = $8, on_sale = $9, size = $10, brand = $11, color = $12,
+            material = $13, meta_title = $14, meta_description = $15, og_image = $16,
+            slug = $17, sale_price = $18, sale_starts_at = $19, sale_ends_at = $20,
+            updated_at = NOW()
+        WHERE id = $21
+        RETURNING id, name, slug, description AS "description!", price,
+                  gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+                  category AS "category: Category", status AS "status: ProductStatus",
+                  images AS "images!", on_sale, size, brand, color, material, meta_title,
+                  meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+                  updated_at
+        "#,
+        product.name,
+        product.description,
+        product.price,
+        product.gender as _,
+        product.condition as _,
+        product.category as _,
+        product.status as _,
+        product.images.as_slice(),
+        product.on_sale,
+        product.size,
+        product.brand,
+        product.color,
+        product.material,
+        product.meta_title,
+        product.meta_description,
+        product.og_image,
+        product.slug,
+        product.sale_price,
+        product.sale_starts_at,
+        product.sale_ends_at,
+        product.id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Zmienia status produktu; `false`, gdy produkt nie istnieje
+pub async fn set_status<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    status: ProductStatus,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE products SET status = $1, updated_at = NOW() WHERE id = $2",
+        status as _,
+        id
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Zmienia status produktu i zwraca go po zmianie
+pub async fn set_status_returning<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    status: ProductStatus,
+) -> Result<Product, AppError> {
+    Ok(sqlx::query_as!(
+        Product,
+        r#"
+        UPDATE products SET status = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, name, slug, description AS "description!", price,
+                  gender AS "gender: ProductGender", condition AS "condition: ProductCondition",
+                  category AS "category: Category", status AS "status: ProductStatus",
+                  images AS "images!", on_sale, size, brand, color, material, meta_title,
+               meta_description, og_image, sale_price, sale_starts_at, sale_ends_at, created_at,
+               updated_at
+        "#,
+        status as _,
+        id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn set_status_many<'e>(
+    executor: impl PgExecutor<'e>,
+    ids: &[Uuid],
+    status: ProductStatus,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "UPDATE products SET status = $1, updated_at = NOW() WHERE id = ANY($2)",
+        status as _,
+        ids
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn set_price<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    price: i64,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE products SET price = $1, updated_at = NOW() WHERE id = $2",
+        price,
+        id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn toggle_on_sale<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE products SET on_sale = NOT on_sale, updated_at = NOW() WHERE id = $1",
+        id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Podmienia adres jednego zdjęcia, zachowując kolejność pozostałych
+pub async fn replace_image<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    old_url: &str,
+    new_url: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE products SET images = array_replace(images, $1, $2), updated_at = NOW() WHERE id = $3",
+        old_url,
+        new_url,
+        id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Czy produkt występuje w jakimkolwiek zamówieniu (takiego nie można trwale usunąć)
+pub async fn is_in_any_order<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<bool, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM order_items WHERE product_id = $1) AS "exists!""#,
+        id
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+/// Trwale usuwa produkt; `false`, gdy produkt nie istniał
+pub async fn delete<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> Result<bool, AppError> {
+    let result = sqlx::query!("DELETE FROM products WHERE id = $1", id)
+        .execute(executor)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
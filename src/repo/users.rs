@@ -0,0 +1,193 @@
+// src/repo/users.rs
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Role, User, UserShippingDetails};
+
+/// Dane wysyłki zapisywane w "Moich danych" - puste pola trafiają do bazy jako NULL
+pub struct ShippingDetailsUpdate<'a> {
+    pub first_name: Option<&'a str>,
+    pub last_name: Option<&'a str>,
+    pub address_line1: Option<&'a str>,
+    pub address_line2: Option<&'a str>,
+    pub city: Option<&'a str>,
+    pub postal_code: Option<&'a str>,
+    pub country: Option<&'a str>,
+    pub phone: Option<&'a str>,
+}
+
+pub async fn find_by_id<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<User>, AppError> {
+    Ok(sqlx::query_as!(
+        User,
+        r#"
+        SELECT id, email, password_hash, role AS "role: Role", created_at, updated_at
+        FROM users
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Konto po adresie e-mail w dokładnie takim zapisie, jak przy rejestracji (logowanie, reset hasła)
+pub async fn find_by_email<'e>(
+    executor: impl PgExecutor<'e>,
+    email: &str,
+) -> Result<Option<User>, AppError> {
+    Ok(sqlx::query_as!(
+        User,
+        r#"
+        SELECT id, email, password_hash, role AS "role: Role", created_at, updated_at
+        FROM users
+        WHERE email = $1
+        "#,
+        email
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Konto po adresie e-mail bez rozróżniania wielkości liter (wyszukiwanie w panelu)
+pub async fn find_by_email_ignore_case<'e>(
+    executor: impl PgExecutor<'e>,
+    email: &str,
+) -> Result<Option<User>, AppError> {
+    Ok(sqlx::query_as!(
+        User,
+        r#"
+        SELECT id, email, password_hash, role AS "role: Role", created_at, updated_at
+        FROM users
+        WHERE LOWER(email) = LOWER($1)
+        "#,
+        email
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Czy na ten adres (bez rozróżniania wielkości liter) jest już konto - gość musi się wtedy zalogować
+pub async fn email_registered<'e>(
+    executor: impl PgExecutor<'e>,
+    email: &str,
+) -> Result<bool, AppError> {
+    Ok(sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM users WHERE LOWER(email) = LOWER($1)) AS "exists!""#,
+        email
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn email<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+) -> Result<Option<String>, AppError> {
+    Ok(
+        sqlx::query_scalar!("SELECT email FROM users WHERE id = $1", id)
+            .fetch_optional(executor)
+            .await?,
+    )
+}
+
+pub async fn insert<'e>(
+    executor: impl PgExecutor<'e>,
+    email: &str,
+    password_hash: &str,
+    role: Role,
+) -> Result<User, AppError> {
+    Ok(sqlx::query_as!(
+        User,
+        r#"
+        INSERT INTO users (email, password_hash, role)
+        VALUES ($1, $2, $3)
+        RETURNING id, email, password_hash, role AS "role: Role", created_at, updated_at
+        "#,
+        email,
+        password_hash,
+        role as _
+    )
+    .fetch_one(executor)
+    .await?)
+}
+
+pub async fn set_password_hash<'e>(
+    executor: impl PgExecutor<'e>,
+    id: Uuid,
+    password_hash: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE users SET password_hash = $1 WHERE id = $2",
+        password_hash,
+        id
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+pub async fn shipping_details<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+) -> Result<Option<UserShippingDetails>, AppError> {
+    Ok(sqlx::query_as!(
+        UserShippingDetails,
+        r#"
+        SELECT user_id, shipping_first_name, shipping_last_name, shipping_address_line1,
+               shipping_address_line2, shipping_city, shipping_postal_code, shipping_country,
+               shipping_phone, created_at, updated_at
+        FROM user_shipping_details
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?)
+}
+
+/// Zapisuje dane wysyłki klienta (pierwszy zapis tworzy wiersz, kolejne go nadpisują)
+pub async fn upsert_shipping_details<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    details: &ShippingDetailsUpdate<'_>,
+) -> Result<UserShippingDetails, AppError> {
+    Ok(sqlx::query_as!(
+        UserShippingDetails,
+        r#"
+        INSERT INTO user_shipping_details (
+            user_id, shipping_first_name, shipping_last_name, shipping_address_line1,
+            shipping_address_line2, shipping_city, shipping_postal_code, shipping_country,
+            shipping_phone
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (user_id) DO UPDATE SET
+            shipping_first_name = EXCLUDED.shipping_first_name,
+            shipping_last_name = EXCLUDED.shipping_last_name,
+            shipping_address_line1 = EXCLUDED.shipping_address_line1,
+            shipping_address_line2 = EXCLUDED.shipping_address_line2,
+            shipping_city = EXCLUDED.shipping_city,
+            shipping_postal_code = EXCLUDED.shipping_postal_code,
+            shipping_country = EXCLUDED.shipping_country,
+            shipping_phone = EXCLUDED.shipping_phone,
+            updated_at = NOW()
+        RETURNING user_id, shipping_first_name, shipping_last_name, shipping_address_line1,
+                  shipping_address_line2, shipping_city, shipping_postal_code, shipping_country,
+                  shipping_phone, created_at, updated_at
+        "#,
+        user_id,
+        details.first_name,
+        details.last_name,
+        details.address_line1,
+        details.address_line2,
+        details.city,
+        details.postal_code,
+        details.country,
+        details.phone
+    )
+    .fetch_one(executor)
+    .await?)
+}
@@ -0,0 +1,248 @@
+// src/reservations.rs
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::holds::release_expired_holds;
+use crate::merchant_feed::invalidate_merchant_feed;
+use crate::models::ProductStatus;
+use crate::state::AppState;
+
+/// Jak długo produkt dodany do koszyka jest zarezerwowany dla tego koszyka.
+/// Wejście do kasy przedłuża rezerwację o kolejny taki okres.
+pub const RESERVATION_TTL_MINUTES: i64 = 20;
+
+/// Wynik próby zarezerwowania produktu dla koszyka
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReservationOutcome {
+    /// Produkt zarezerwowany (lub rezerwacja tego koszyka przedłużona)
+    Reserved,
+    /// Produkt ma aktywną rezerwację innego koszyka
+    HeldByAnotherCart,
+    /// Produkt sprzedany, zarchiwizowany albo zarezerwowany ręcznie przez admina
+    Unavailable,
+}
+
+fn reservation_expiry() -> DateTime<Utc> {
+    Utc::now() + Duration::minutes(RESERVATION_TTL_MINUTES)
+}
+
+/// Rezerwuje produkt dla koszyka. Rezerwację, która wygasła, a nie została jeszcze zwolniona
+/// przez zadanie w tle, przejmuje nowy koszyk.
+///
+/// Wiersz produktu jest blokowany do końca transakcji, więc dwa koszyki nie zarezerwują
+/// tego samego produktu jednocześnie.
+pub async fn reserve_product_for_cart(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    cart_id: Uuid,
+) -> Result<ReservationOutcome, AppError> {
+    let row: Option<(ProductStatus, Option<Uuid>, Option<DateTime<Utc>>)> = sqlx::query_as(
+        r#"
+            SELECT p.status, r.cart_id, r.expires_at
+            FROM products p
+            LEFT JOIN product_reservations r ON r.product_id = p.id
+            WHERE p.id = $1
+            FOR UPDATE OF p
+        "#,
+    )
+    .bind(product_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some((status, reserved_by, expires_at)) = row else {
+        return Err(AppError::NotFound);
+    };
+
+    let can_reserve = match status {
+        ProductStatus::Available => true,
+        // Rezerwacja bez wpisu w product_reservations to rezerwacja ręczna admina - nie ruszamy jej
+        ProductStatus::Reserved => match (reserved_by, expires_at) {
+            (Some(holder), _) if holder == cart_id => true,
+            (Some(_), Some(expires_at)) if expires_at <= Utc::now() => true,
+            (Some(_), _) => return Ok(ReservationOutcome::HeldByAnotherCart),
+            (None, _) => false,
+        },
+        ProductStatus::Sold | ProductStatus::Archived => false,
+    };
+    if !can_reserve {
+        return Ok(ReservationOutcome::Unavailable);
+    }
+
+    sqlx::query(
+        r#"
+            INSERT INTO product_reservations (product_id, cart_id, expires_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (product_id) DO UPDATE
+            SET cart_id = EXCLUDED.cart_id,
+                -- Dłuższej rezerwacji tego samego koszyka (np. z linku rezerwacji ręcznej) nie skracamy
+                expires_at = CASE WHEN product_reservations.cart_id = EXCLUDED.cart_id
+                                  THEN GREATEST(product_reservations.expires_at, EXCLUDED.expires_at)
+                                  ELSE EXCLUDED.expires_at END,
+                created_at = NOW(),
+                checkout_draft_id = NULL
+        "#,
+    )
+    .bind(product_id)
+    .bind(cart_id)
+    .bind(reservation_expiry())
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE products SET status = $1 WHERE id = $2")
+        .bind(ProductStatus::Reserved)
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(ReservationOutcome::Reserved)
+}
+
+/// Zwalnia rezerwację produktu, jeśli należy do podanego koszyka (np. po usunięciu z koszyka).
+/// Produkt z aktywną rezerwacją ręczną zostaje zarezerwowany dla klienta z linku.
+pub async fn release_product_reservation(
+    conn: &mut PgConnection,
+    product_id: Uuid,
+    cart_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            WITH released AS (
+                DELETE FROM product_reservations
+                WHERE product_id = $1 AND cart_id = $2
+                RETURNING product_id
+            )
+            UPDATE products SET status = $3
+            WHERE id IN (SELECT product_id FROM released) AND status = $4
+              AND NOT EXISTS (
+                  SELECT 1 FROM product_holds h
+                  WHERE h.product_id = products.id AND h.expires_at > NOW()
+              )
+        "#,
+    )
+    .bind(product_id)
+    .bind(cart_id)
+    .bind(ProductStatus::Available)
+    .bind(ProductStatus::Reserved)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Przedłuża wszystkie rezerwacje koszyka i przypisuje je do szkicu zamówienia - klient w kasie
+/// nie powinien stracić produktów w trakcie wypełniania kolejnych kroków.
+pub async fn extend_cart_reservations(
+    conn: &mut PgConnection,
+    cart_id: Uuid,
+    checkout_draft_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE product_reservations SET expires_at = GREATEST(expires_at, $1), checkout_draft_id = $2 WHERE cart_id = $3",
+    )
+    .bind(reservation_expiry())
+    .bind(checkout_draft_id)
+    .bind(cart_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Przenosi rezerwacje z koszyka gościa do koszyka użytkownika po zalogowaniu.
+/// Szkic zamówienia gościa zostaje przy jego koszyku - kasa użytkownika ma własny szkic.
+pub async fn transfer_cart_reservations(
+    conn: &mut PgConnection,
+    from_cart_id: Uuid,
+    to_cart_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE product_reservations SET cart_id = $1, checkout_draft_id = NULL WHERE cart_id = $2",
+    )
+    .bind(to_cart_id)
+    .bind(from_cart_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Produkty z podanej listy, które są zarezerwowane dla tego koszyka.
+pub async fn reserved_product_ids_for_cart(
+    conn: &mut PgConnection,
+    cart_id: Uuid,
+    product_ids: &[Uuid],
+) -> Result<HashSet<Uuid>, AppError> {
+    let ids: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT product_id FROM product_reservations WHERE cart_id = $1 AND product_id = ANY($2)",
+    )
+    .bind(cart_id)
+    .bind(product_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(ids.into_iter().collect())
+}
+
+/// Usuwa wpisy rezerwacji sprzedanych produktów, także rezerwacje ręczne
+/// (status produktu ustawia składanie zamówienia).
+pub async fn clear_product_reservations(
+    conn: &mut PgConnection,
+    product_ids: &[Uuid],
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM product_reservations WHERE product_id = ANY($1)")
+        .bind(product_ids)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM product_holds WHERE product_id = ANY($1)")
+        .bind(product_ids)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Zwalnia wygasłe rezerwacje i przywraca produktom status "Dostępny"
+/// (poza produktami z aktywną rezerwacją ręczną).
+/// Zwraca ID zwolnionych produktów (do unieważnienia cache).
+pub async fn release_expired_reservations(pool: &PgPool) -> Result<Vec<Uuid>, AppError> {
+    let released: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+            WITH expired AS (
+                DELETE FROM product_reservations
+                WHERE expires_at <= NOW()
+                RETURNING product_id
+            )
+            UPDATE products SET status = $1
+            WHERE id IN (SELECT product_id FROM expired) AND status = $2
+              AND NOT EXISTS (
+                  SELECT 1 FROM product_holds h
+                  WHERE h.product_id = products.id AND h.expires_at > NOW()
+              )
+            RETURNING id
+        "#,
+    )
+    .bind(ProductStatus::Available)
+    .bind(ProductStatus::Reserved)
+    .fetch_all(pool)
+    .await?;
+    Ok(released)
+}
+
+/// Zadanie cykliczne: zwalnia wygasłe rezerwacje koszyków i rezerwacje ręczne,
+/// a zwolnione produkty wracają do cache i feedu jako dostępne
+pub async fn release_expired_job(state: &AppState) -> Result<String, AppError> {
+    let released = release_expired_reservations(&state.db_pool).await?;
+    let released_holds = release_expired_holds(&state.db_pool).await?;
+    for product_id in released.iter().chain(&released_holds) {
+        state.product_cache.invalidate(product_id).await;
+    }
+    if released.is_empty() && released_holds.is_empty() {
+        return Ok(String::new());
+    }
+    invalidate_merchant_feed(state).await;
+    Ok(format!(
+        "Zwolniono {} wygasłych rezerwacji koszyków i {} rezerwacji ręcznych.",
+        released.len(),
+        released_holds.len()
+    ))
+}
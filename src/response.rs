@@ -1,7 +1,7 @@
 use axum::body::{Body, Bytes};
 use axum::http::{HeaderMap, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
-use lol_html::{HtmlRewriter, Settings, element};
+use lol_html::{HtmlRewriter, RewriteStrSettings, Settings, element, rewrite_str};
 use maud::{Markup, html};
 use reqwest::header;
 use sha1::Digest;
@@ -9,6 +9,14 @@ use sha1::Sha1;
 use tokio::fs;
 
 use crate::errors::AppError;
+use crate::middleware::{CSRF_HEADER, csrf_token_from_headers};
+use crate::rum::RUM_SCRIPT;
+use crate::security_headers::CspNonce;
+use crate::seo::{PaginationLinks, SocialMeta};
+
+/// Strony HTML mogą zawierać dane klienta, więc nie trafiają do współdzielonych cache'y,
+/// a przeglądarka zawsze pyta o aktualność (ETag) zanim użyje swojej kopii
+const HTML_CACHE_CONTROL: &str = "private, no-cache";
 
 // pub enum AppResponse {
 //     Full(Html<String>),
@@ -45,10 +53,14 @@ pub async fn serve_full_page(page_builder: PageBuilder<'_>) -> Result<Vec<u8>, A
         main_content,
         head_scripts,
         body_scripts,
+        social_meta,
+        pagination_links,
+        csrf_token,
     } = page_builder;
 
     let content_string = main_content.into_string();
     let mut response_body = Vec::new();
+    let rum_script = format!("<script>{}</script>", RUM_SCRIPT);
 
     let mut element_handlers = vec![
         element!("#content", move |el| {
@@ -61,8 +73,49 @@ pub async fn serve_full_page(page_builder: PageBuilder<'_>) -> Result<Vec<u8>, A
             el.set_inner_content(title, lol_html::html_content::ContentType::Text);
             Ok(())
         }),
+        // Pomiar Core Web Vitals (rum.rs) - tylko przy pełnym załadowaniu strony
+        element!("body", move |el| {
+            el.append(&rum_script, lol_html::html_content::ContentType::Html);
+            Ok(())
+        }),
     ];
 
+    if let Some(social_meta) = social_meta {
+        let description = social_meta.description.clone();
+        let tags = social_meta.head_tags().into_string();
+        element_handlers.push(element!(r#"head > meta[name="description"]"#, move |el| {
+            el.set_attribute("content", &description)?;
+            Ok(())
+        }));
+        element_handlers.push(element!("head", move |el| {
+            el.append(&tags, lol_html::html_content::ContentType::Html);
+            Ok(())
+        }));
+    }
+
+    if let Some(pagination_links) = pagination_links {
+        let tags = pagination_links.head_tags().into_string();
+        element_handlers.push(element!("head", move |el| {
+            el.append(&tags, lol_html::html_content::ContentType::Html);
+            Ok(())
+        }));
+    }
+
+    // Token CSRF (middleware::csrf_protect): `<meta>` dla `fetch` w app.js, a `hx-headers`
+    // na `<body>` dziedziczą wszystkie żądania HTMX na stronie
+    if let Some(token) = csrf_token {
+        let meta_tag = format!(r#"<meta name="csrf-token" content="{}">"#, token);
+        let hx_headers = format!(r#"{{"{}": "{}"}}"#, CSRF_HEADER, token);
+        element_handlers.push(element!("head", move |el| {
+            el.append(&meta_tag, lol_html::html_content::ContentType::Html);
+            Ok(())
+        }));
+        element_handlers.push(element!("body", move |el| {
+            el.set_attribute("hx-headers", &hx_headers)?;
+            Ok(())
+        }));
+    }
+
     if let Some(scripts) = head_scripts {
         let scripts_string = scripts.into_string();
         element_handlers.push(element!("#head-scripts-placeholder", move |el| {
@@ -104,11 +157,34 @@ pub async fn serve_full_page(page_builder: PageBuilder<'_>) -> Result<Vec<u8>, A
     Ok(response_body)
 }
 
+/// Dopisuje nonce CSP żądania do wszystkich tagów `<script>` gotowej strony. Robimy to dopiero
+/// po policzeniu ETagu - nonce jest inny przy każdym żądaniu, a treść strony nie.
+fn with_script_nonce(page: Vec<u8>, nonce: &str) -> Result<Vec<u8>, AppError> {
+    let page = String::from_utf8(page).map_err(|e| {
+        tracing::error!("Strona nie jest poprawnym UTF-8: {}", e);
+        AppError::InternalServerError("Błąd renderowania strony".to_string())
+    })?;
+    let settings = RewriteStrSettings {
+        element_content_handlers: vec![element!("script", |el| {
+            el.set_attribute("nonce", nonce)?;
+            Ok(())
+        })],
+        ..RewriteStrSettings::default()
+    };
+    rewrite_str(&page, settings)
+        .map(String::into_bytes)
+        .map_err(|e| {
+            tracing::error!("Nie udało się dodać nonce do skryptów strony: {}", e);
+            AppError::InternalServerError("Błąd renderowania strony".to_string())
+        })
+}
+
 pub async fn build_response<'a>(
     headers: HeaderMap,
+    csp_nonce: &CspNonce,
     page_builder: PageBuilder<'a>,
 ) -> Result<Response, AppError> {
-    let body_bytes: Vec<u8>;
+    let mut body_bytes: Vec<u8>;
     let mut is_full_page_request = false;
 
     if headers.contains_key("HX-Request") {
@@ -122,33 +198,49 @@ pub async fn build_response<'a>(
         // <<< POPRAWKA 2: Konwertujemy markup do stringa, a potem na bajty
         body_bytes = final_markup.into_string().into_bytes();
     } else {
+        let mut page_builder = page_builder;
+        page_builder.csrf_token = csrf_token_from_headers(&headers);
         // <<< POPRAWKA 3: `serve_full_page` zwraca `Vec<u8>`, więc `body_bytes` jest poprawnego typu
         body_bytes = serve_full_page(page_builder).await?;
         is_full_page_request = true;
     }
 
-    // Obliczamy ETag
-    let mut hasher = Sha1::new(); // Teraz to działa, bo `Digest` jest w zasięgu
+    // ETag to skrót treści - ta sama strona daje ten sam nagłówek niezależnie od instancji serwera
+    let mut hasher = Sha1::new();
     hasher.update(&body_bytes);
     let etag = format!("\"{}\"", hex::encode(hasher.finalize()));
+    let etag_value = HeaderValue::from_str(&etag).map_err(|e| {
+        tracing::error!("Nieprawidłowa wartość ETag {}: {}", etag, e);
+        AppError::InternalServerError("Błąd budowania odpowiedzi".to_string())
+    })?;
 
-    // Sprawdzamy ETag z nagłówka
-    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
-        if if_none_match.to_str().unwrap_or_default() == etag {
-            tracing::info!("ETag match! Zwracam 304 Not Modified.");
-            return Ok(StatusCode::NOT_MODIFIED.into_response());
-        }
+    // Przeglądarka ma aktualną kopię - odsyłamy same nagłówki
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|if_none_match| etag_matches(if_none_match, &etag))
+    {
+        tracing::debug!("ETag {} bez zmian, zwracam 304 Not Modified", etag);
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        let response_headers = response.headers_mut();
+        response_headers.insert(header::ETAG, etag_value);
+        response_headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(HTML_CACHE_CONTROL),
+        );
+        response_headers.insert(header::VARY, HeaderValue::from_static("HX-Request"));
+        return Ok(response);
     }
 
-    // Budujemy pełną odpowiedź 200 OK
-    tracing::info!("ETag mismatch or first request. Zwracam 200 OK z nową treścią.");
     let mut response_builder = Response::builder()
         .status(StatusCode::OK)
-        .header(header::ETAG, HeaderValue::from_str(&etag).unwrap())
-        .header("Vary", HeaderValue::from_static("HX-Request, ETag"));
+        .header(header::ETAG, etag_value)
+        .header(header::CACHE_CONTROL, HTML_CACHE_CONTROL)
+        // Fragment HTMX i pełna strona pod tym samym adresem to różne treści
+        .header(header::VARY, HeaderValue::from_static("HX-Request"));
 
     if is_full_page_request {
         response_builder = response_builder.header("Content-Type", "text/html; charset=utf-8");
+        body_bytes = with_script_nonce(body_bytes, csp_nonce.as_str())?;
     }
 
     let response = response_builder.body(Body::from(body_bytes)).unwrap();
@@ -156,12 +248,30 @@ pub async fn build_response<'a>(
     Ok(response)
 }
 
+/// Porównanie `If-None-Match` z ETagiem strony (porównanie słabe, jak wymaga RFC 9110 dla GET):
+/// nagłówek może zawierać listę tagów, tagi z prefiksem `W/` albo `*`
+fn etag_matches(if_none_match: &HeaderValue, etag: &str) -> bool {
+    let Ok(value) = if_none_match.to_str() else {
+        return false;
+    };
+    value.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
 // Nowa struktura do budowania kompleksowych odpowiedzi
 pub struct PageBuilder<'a> {
     pub title: &'a str,
     pub main_content: Markup,
     pub head_scripts: Option<Markup>,
     pub body_scripts: Option<Markup>,
+    /// Podgląd linku i adres kanoniczny; zastępuje też domyślny `<meta name="description">`
+    /// szablonu (tylko pełna strona)
+    pub social_meta: Option<SocialMeta>,
+    /// Sąsiednie strony listy stronicowanej (tylko pełna strona)
+    pub pagination_links: Option<PaginationLinks>,
+    /// Uzupełniany przez `build_response` z ciasteczka żądania
+    pub csrf_token: Option<String>,
 }
 
 impl<'a> PageBuilder<'a> {
@@ -176,6 +286,19 @@ impl<'a> PageBuilder<'a> {
             main_content,
             head_scripts,
             body_scripts,
+            social_meta: None,
+            pagination_links: None,
+            csrf_token: None,
         }
     }
+
+    pub fn with_social_meta(mut self, social_meta: SocialMeta) -> Self {
+        self.social_meta = Some(social_meta);
+        self
+    }
+
+    pub fn with_pagination_links(mut self, pagination_links: PaginationLinks) -> Self {
+        self.pagination_links = Some(pagination_links);
+        self
+    }
 }
@@ -0,0 +1,172 @@
+// src/retention.rs
+
+// Egzekwowanie okresów przechowywania danych z polityki prywatności:
+// anonimizacja danych osobowych w starych zamówieniach gości, usuwanie wygasłych tokenów
+// (reset hasła, potwierdzenie e-mail) i porzuconych koszyków gości. Zadanie działa raz na dobę
+// (zob. rejestr w `jobs`).
+
+use chrono::{Duration, Utc};
+
+use crate::errors::AppError;
+use crate::models::{AdminNotificationLevel, OrderStatus, ProductStatus};
+use crate::notifications::notify_admin;
+use crate::state::{AppState, RetentionConfig};
+
+/// Co zostało usunięte lub zanonimizowane w jednym przebiegu
+#[derive(Debug, Default)]
+pub struct RetentionReport {
+    pub anonymized_orders: u64,
+    pub purged_expired_tokens: u64,
+    pub purged_guest_carts: u64,
+    pub purged_checkout_drafts: u64,
+}
+
+/// Zastępuje dane osobowe zamkniętych zamówień gości starszych niż okres retencji.
+/// Kwoty, produkty, kraj i metoda dostawy zostają - są potrzebne do rozliczeń i statystyk.
+/// Zamówienia kont klientów nie są ruszane: dane należą do konta, dopóki ono istnieje.
+async fn anonymize_guest_orders(
+    tx: &mut sqlx::PgConnection,
+    config: &RetentionConfig,
+) -> Result<u64, AppError> {
+    let cutoff = Utc::now() - Duration::days(config.guest_order_pii_days);
+
+    let anonymized_ids: Vec<uuid::Uuid> = sqlx::query_scalar(
+        r#"
+            UPDATE orders SET
+                shipping_first_name = 'Anonimizowane',
+                shipping_last_name = '-',
+                shipping_address_line1 = '-',
+                shipping_address_line2 = NULL,
+                shipping_city = '-',
+                shipping_postal_code = '-',
+                shipping_phone = '-',
+                -- guest_email musi pozostać niepusty (CHECK user_id OR guest_email)
+                guest_email = 'anonimizacja+' || id || '@invalid',
+                guest_session_id = NULL,
+                anonymized_at = NOW()
+            WHERE user_id IS NULL
+              AND anonymized_at IS NULL
+              AND order_date < $1
+              AND status IN ($2, $3)
+            RETURNING id
+        "#,
+    )
+    .bind(cutoff)
+    .bind(OrderStatus::Delivered)
+    .bind(OrderStatus::Cancelled)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if anonymized_ids.is_empty() {
+        return Ok(0);
+    }
+
+    // Adres IP z oceny ryzyka i e-mail przy użyciu kodu rabatowego to także dane osobowe
+    sqlx::query("UPDATE order_risk_assessments SET client_ip = NULL WHERE order_id = ANY($1)")
+        .bind(&anonymized_ids)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE coupon_redemptions SET guest_email = NULL WHERE order_id = ANY($1)")
+        .bind(&anonymized_ids)
+        .execute(&mut *tx)
+        .await?;
+
+    Ok(anonymized_ids.len() as u64)
+}
+
+/// Wykonuje jeden przebieg polityki retencji w transakcji.
+pub async fn run_retention_policy(state: &AppState) -> Result<RetentionReport, AppError> {
+    let config = &state.retention_config;
+    let mut tx = state.db_pool.begin().await?;
+
+    let anonymized_orders = anonymize_guest_orders(&mut tx, config).await?;
+
+    let purged_expired_tokens = sqlx::query("DELETE FROM password_resets WHERE expires_at < NOW()")
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+        + sqlx::query("DELETE FROM verification_tokens WHERE expires_at < NOW()")
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+    // Pozycje koszyka usuwa ON DELETE CASCADE. Porzucony koszyk zwykle nie ma już rezerwacji
+    // (wygasają po kilkudziesięciu minutach), ale gdyby miał, zwalniamy produkty tak jak zadanie rezerwacji.
+    let guest_cart_cutoff = Utc::now() - Duration::days(config.guest_cart_days);
+    sqlx::query(
+        r#"
+            WITH released AS (
+                DELETE FROM product_reservations
+                WHERE cart_id IN (
+                    SELECT id FROM shopping_carts WHERE user_id IS NULL AND updated_at < $1
+                )
+                RETURNING product_id
+            )
+            UPDATE products SET status = $2
+            WHERE id IN (SELECT product_id FROM released) AND status = $3
+        "#,
+    )
+    .bind(guest_cart_cutoff)
+    .bind(ProductStatus::Available)
+    .bind(ProductStatus::Reserved)
+    .execute(&mut *tx)
+    .await?;
+
+    let purged_guest_carts =
+        sqlx::query("DELETE FROM shopping_carts WHERE user_id IS NULL AND updated_at < $1")
+            .bind(guest_cart_cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+    // Szkice kasy powielają dane adresowe: złożone są potrzebne tylko do obsługi płatności,
+    // a otwarte bez koszyka (usuniętego powyżej) nie da się już dokończyć.
+    let purged_checkout_drafts = sqlx::query(
+        r#"
+            DELETE FROM checkout_drafts
+            WHERE (submitted_at IS NOT NULL AND submitted_at < $1)
+               OR (submitted_at IS NULL AND (cart_id IS NULL OR updated_at < $1))
+        "#,
+    )
+    .bind(guest_cart_cutoff)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    Ok(RetentionReport {
+        anonymized_orders,
+        purged_expired_tokens,
+        purged_guest_carts,
+        purged_checkout_drafts,
+    })
+}
+
+/// Zadanie cykliczne (raz na dobę); błąd trafia też do centrum powiadomień
+pub async fn retention_job(state: &AppState) -> Result<String, AppError> {
+    match run_retention_policy(state).await {
+        Ok(report) => {
+            state
+                .metrics
+                .record_carts_abandoned(report.purged_guest_carts);
+            Ok(format!(
+                "Zanonimizowano {} zamówień gości, usunięto {} wygasłych tokenów (reset hasła, potwierdzenie e-mail), {} koszyków gości i {} szkiców zamówień.",
+                report.anonymized_orders,
+                report.purged_expired_tokens,
+                report.purged_guest_carts,
+                report.purged_checkout_drafts
+            ))
+        }
+        Err(e) => {
+            notify_admin(
+                &state.db_pool,
+                AdminNotificationLevel::Error,
+                "Zadanie retencji danych nie powiodło się",
+                "Dane osobowe starych zamówień mogły nie zostać zanonimizowane. Szczegóły w logach serwera.",
+            )
+            .await;
+            Err(e)
+        }
+    }
+}
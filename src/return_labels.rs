@@ -0,0 +1,154 @@
+// src/return_labels.rs
+
+// Etykieta zwrotna InPost kupowana przez klienta przy zgłoszeniu zwrotu. Przesyłkę tworzymy
+// od razu, ale InPost generuje etykietę z opóźnieniem - PDF pobieramy w tle i wysyłamy
+// klientowi e-mailem. Cena etykiety jest potrącana z kwoty zwrotu przy jego przyjęciu.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::email_service::{resolve_order_recipient_email, send_return_label_email};
+use crate::errors::AppError;
+use crate::inpost::{create_return_shipment, fetch_label_pdf, fetch_tracking_number};
+use crate::models::{AdminNotificationLevel, Order, ReturnLabel};
+use crate::notifications::notify_admin;
+use crate::returns::{ReturnDetails, find_return, return_reference};
+use crate::state::{AppState, InpostConfig};
+
+const LABEL_FETCH_ATTEMPTS: u32 = 10;
+const LABEL_FETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Czy klient może kupić etykietę zwrotną - wymaga konfiguracji zwrotów i dostępu do ShipX
+pub fn return_labels_available(config: &InpostConfig) -> bool {
+    config.return_label.is_some() && config.api_token.is_some() && config.organization_id.is_some()
+}
+
+/// Tworzy przesyłkę zwrotną, zapisuje etykietę przy zwrocie i zleca jej wysyłkę e-mailem.
+pub async fn purchase_return_label(
+    app_state: &Arc<AppState>,
+    order: &Order,
+    details: &ReturnDetails,
+) -> Result<ReturnLabel, AppError> {
+    let return_config = app_state
+        .inpost_config
+        .return_label
+        .as_ref()
+        .filter(|_| return_labels_available(&app_state.inpost_config))
+        .ok_or_else(|| AppError::BadRequest("Etykiety zwrotne są niedostępne.".to_string()))?;
+    let sender_email = resolve_order_recipient_email(app_state, order).await?;
+    let reference = return_reference(&details.request);
+
+    let shipment_id = create_return_shipment(
+        &app_state.inpost_config,
+        return_config,
+        order,
+        &sender_email,
+        &format!("Zwrot {}", reference),
+    )
+    .await?;
+    let label = sqlx::query_as::<_, ReturnLabel>(
+        r#"
+        INSERT INTO return_labels (return_id, shipment_id, price)
+        VALUES ($1, $2, $3)
+        RETURNING *
+        "#,
+    )
+    .bind(details.request.id)
+    .bind(shipment_id)
+    .bind(return_config.price)
+    .fetch_one(&app_state.db_pool)
+    .await?;
+
+    tracing::info!(
+        "Utworzono przesyłkę zwrotną InPost {} dla zwrotu {}",
+        shipment_id,
+        reference
+    );
+    spawn_label_delivery(
+        app_state.clone(),
+        order.clone(),
+        details.request.id,
+        shipment_id,
+    );
+    Ok(label)
+}
+
+/// Czeka, aż InPost wygeneruje etykietę, i wysyła ją klientowi. Gdy się nie uda, admin
+/// dostaje powiadomienie z numerem przesyłki do pobrania etykiety ręcznie.
+fn spawn_label_delivery(app_state: Arc<AppState>, order: Order, return_id: Uuid, shipment_id: i64) {
+    let background_jobs = app_state.background_jobs.clone();
+    background_jobs.spawn(async move {
+        for _ in 0..LABEL_FETCH_ATTEMPTS {
+            tokio::time::sleep(LABEL_FETCH_INTERVAL).await;
+            let label_pdf = match fetch_label_pdf(&app_state.inpost_config, shipment_id).await {
+                Ok(Some(label_pdf)) => label_pdf,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!(
+                        "Nie udało się pobrać etykiety zwrotnej {}: {:?}",
+                        shipment_id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            match deliver_label(&app_state, &order, return_id, shipment_id, label_pdf).await {
+                Ok(()) => return,
+                Err(e) => {
+                    tracing::error!(
+                        "Nie udało się wysłać etykiety zwrotnej dla zwrotu {}: {:?}",
+                        return_id,
+                        e
+                    );
+                    break;
+                }
+            }
+        }
+        notify_admin(
+            &app_state.db_pool,
+            AdminNotificationLevel::Warning,
+            "Etykieta zwrotna nie została wysłana",
+            &format!(
+                "Klient nie dostał etykiety zwrotnej do zamówienia nr {} (przesyłka InPost {}). Pobierz ją z InPost Managera i wyślij ręcznie.",
+                order.order_number, shipment_id
+            ),
+        )
+        .await;
+    });
+}
+
+async fn deliver_label(
+    app_state: &AppState,
+    order: &Order,
+    return_id: Uuid,
+    shipment_id: i64,
+    label_pdf: Vec<u8>,
+) -> Result<(), AppError> {
+    let tracking_number = fetch_tracking_number(&app_state.inpost_config, shipment_id)
+        .await
+        .unwrap_or(None);
+    if let Some(tracking_number) = &tracking_number {
+        sqlx::query("UPDATE return_labels SET tracking_number = $1 WHERE return_id = $2")
+            .bind(tracking_number)
+            .bind(return_id)
+            .execute(&app_state.db_pool)
+            .await?;
+    }
+
+    let details = find_return(&app_state.db_pool, return_id).await?;
+    send_return_label_email(
+        app_state,
+        order,
+        &details,
+        label_pdf,
+        tracking_number.as_deref(),
+    )
+    .await?;
+    sqlx::query("UPDATE return_labels SET emailed_at = NOW() WHERE return_id = $1")
+        .bind(return_id)
+        .execute(&app_state.db_pool)
+        .await?;
+    Ok(())
+}
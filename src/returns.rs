@@ -0,0 +1,460 @@
+// src/returns.rs
+
+// Zwroty w ramach odstąpienia od umowy. Klient ma 14 dni od doręczenia, żeby zgłosić zwrot
+// wybranych pozycji; przyjęcie zwrotu rejestruje zwrot środków w `order_refunds` (realizowany
+// jak dotąd z widoku zamówienia w panelu) i przywraca produkty do sprzedaży.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::merchant_feed::invalidate_merchant_feed;
+use crate::models::{
+    Order, OrderItem, OrderStatus, ProductStatus, ReturnItem, ReturnLabel, ReturnRequest,
+    ReturnStatus,
+};
+use crate::state::AppState;
+use crate::vat::refund_vat_breakdown;
+
+/// Ustawowy termin na odstąpienie od umowy zawartej na odległość
+pub const RETURN_WINDOW_DAYS: i64 = 14;
+/// Ustawowy termin na zwrot pieniędzy, liczony od otrzymania oświadczenia o odstąpieniu
+pub const REFUND_DEADLINE_DAYS: i64 = 14;
+pub const MAX_RETURN_REASON_LEN: usize = 1000;
+
+/// Zgłoszenie zwrotu razem z pozycjami
+#[derive(Debug, Clone)]
+pub struct ReturnDetails {
+    pub request: ReturnRequest,
+    pub items: Vec<ReturnItem>,
+    /// Etykieta zwrotna InPost, jeśli klient ją kupił
+    pub label: Option<ReturnLabel>,
+    /// Kiedy przelano ostatnią kwotę zwrotu; `None`, dopóki pieniądze nie wyszły w całości
+    pub refunded_at: Option<DateTime<Utc>>,
+}
+
+impl ReturnDetails {
+    pub fn total_amount(&self) -> i64 {
+        self.items.iter().map(|item| item.amount).sum()
+    }
+
+    /// Kwota do przelania klientowi - wartość pozycji pomniejszona o cenę etykiety zwrotnej
+    pub fn refund_amount(&self) -> i64 {
+        let label_price = self.label.as_ref().map_or(0, |label| label.price);
+        (self.total_amount() - label_price).max(0)
+    }
+
+    /// Do kiedy klient musi dostać pieniądze
+    pub fn refund_deadline(&self) -> DateTime<Utc> {
+        self.request.created_at + Duration::days(REFUND_DEADLINE_DAYS)
+    }
+
+    /// Czy termin zwrotu pieniędzy jeszcze biegnie (zwrot nie odrzucony i nie rozliczony)
+    pub fn refund_pending(&self) -> bool {
+        self.request.status != ReturnStatus::Rejected && self.refunded_at.is_none()
+    }
+}
+
+/// Krótki numer zwrotu do e-maili i panelu, np. "ZW-1A2B3C4D"
+pub fn return_reference(request: &ReturnRequest) -> String {
+    format!("ZW-{}", request.id.simple().to_string()[..8].to_uppercase())
+}
+
+/// Sprawdza polski numer konta (IBAN, z "PL" lub bez) i zwraca go w postaci
+/// "PL61 1090 1014 0000 0712 1981 2874". `None`, gdy numer jest niepoprawny.
+pub fn normalize_bank_account(input: &str) -> Option<String> {
+    let compact: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    let digits = compact.strip_prefix("PL").unwrap_or(&compact);
+    if digits.len() != 26 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    // Suma kontrolna IBAN: BBAN + "PL" (P=25, L=21) + cyfry kontrolne, modulo 97 == 1
+    let rearranged = format!("{}2521{}", &digits[2..], &digits[..2]);
+    let remainder = rearranged
+        .chars()
+        .fold(0u32, |acc, c| (acc * 10 + c.to_digit(10).unwrap_or(0)) % 97);
+    if remainder != 1 {
+        return None;
+    }
+
+    let iban = format!("PL{}", digits);
+    let groups: Vec<&str> = iban
+        .as_bytes()
+        .chunks(4)
+        .filter_map(|chunk| std::str::from_utf8(chunk).ok())
+        .collect();
+    Some(groups.join(" "))
+}
+
+/// Moment doręczenia zamówienia (zmiana statusu na "Dostarczone"). `None`, gdy zamówienie
+/// nie zostało doręczone. Dla zamówień bez historii statusów liczymy od ostatniej zmiany.
+pub async fn delivered_at(pool: &PgPool, order: &Order) -> Result<Option<DateTime<Utc>>, AppError> {
+    if order.status != OrderStatus::Delivered {
+        return Ok(None);
+    }
+    let delivered_at: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT MAX(changed_at) FROM order_status_history WHERE order_id = $1 AND to_status = $2",
+    )
+    .bind(order.id)
+    .bind(OrderStatus::Delivered)
+    .fetch_one(pool)
+    .await?;
+    Ok(Some(delivered_at.unwrap_or(order.updated_at)))
+}
+
+/// Termin zwrotu zamówienia: 14 dni od doręczenia
+pub async fn return_deadline(
+    pool: &PgPool,
+    order: &Order,
+) -> Result<Option<DateTime<Utc>>, AppError> {
+    Ok(delivered_at(pool, order)
+        .await?
+        .map(|delivered_at| delivered_at + Duration::days(RETURN_WINDOW_DAYS)))
+}
+
+/// Pozycje zamówienia, których nie obejmuje jeszcze żaden zgłoszony ani przyjęty zwrot
+pub async fn returnable_order_item_ids(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Vec<Uuid>, AppError> {
+    Ok(sqlx::query_scalar(
+        r#"
+        SELECT oi.id FROM order_items oi
+        WHERE oi.order_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM return_items ri
+              JOIN returns r ON r.id = ri.return_id
+              WHERE ri.order_item_id = oi.id AND r.status <> $2
+          )
+        "#,
+    )
+    .bind(order_id)
+    .bind(ReturnStatus::Rejected)
+    .fetch_all(pool)
+    .await?)
+}
+
+async fn attach_items(
+    pool: &PgPool,
+    requests: Vec<ReturnRequest>,
+) -> Result<Vec<ReturnDetails>, AppError> {
+    let return_ids: Vec<Uuid> = requests.iter().map(|r| r.id).collect();
+    let items = sqlx::query_as::<_, ReturnItem>(
+        r#"
+        SELECT ri.return_id, ri.order_item_id, ri.product_id, p.name AS product_name, ri.amount
+        FROM return_items ri
+        JOIN products p ON p.id = ri.product_id
+        WHERE ri.return_id = ANY($1)
+        ORDER BY p.name
+        "#,
+    )
+    .bind(&return_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut labels_by_return: HashMap<Uuid, ReturnLabel> =
+        sqlx::query_as::<_, ReturnLabel>("SELECT * FROM return_labels WHERE return_id = ANY($1)")
+            .bind(&return_ids)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|label| (label.return_id, label))
+            .collect();
+
+    // Zwrot jest rozliczony, gdy wszystkie jego wpisy w order_refunds mają datę przelewu
+    let mut refunded_by_return: HashMap<Uuid, DateTime<Utc>> =
+        sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+            r#"
+            SELECT return_id, MAX(refunded_at) FROM order_refunds
+            WHERE return_id = ANY($1)
+            GROUP BY return_id
+            HAVING bool_and(refunded_at IS NOT NULL)
+            "#,
+        )
+        .bind(&return_ids)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut items_by_return: HashMap<Uuid, Vec<ReturnItem>> = HashMap::new();
+    for item in items {
+        items_by_return
+            .entry(item.return_id)
+            .or_default()
+            .push(item);
+    }
+    Ok(requests
+        .into_iter()
+        .map(|request| ReturnDetails {
+            items: items_by_return.remove(&request.id).unwrap_or_default(),
+            label: labels_by_return.remove(&request.id),
+            refunded_at: refunded_by_return.remove(&request.id),
+            request,
+        })
+        .collect())
+}
+
+pub async fn returns_for_order(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Vec<ReturnDetails>, AppError> {
+    let requests = sqlx::query_as::<_, ReturnRequest>(
+        "SELECT * FROM returns WHERE order_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?;
+    attach_items(pool, requests).await
+}
+
+/// Zwroty do panelu admina - najpierw najstarsze oczekujące na decyzję
+pub async fn list_returns(
+    pool: &PgPool,
+    status: Option<ReturnStatus>,
+) -> Result<Vec<ReturnDetails>, AppError> {
+    let requests = sqlx::query_as::<_, ReturnRequest>(
+        r#"
+        SELECT * FROM returns
+        WHERE ($1::return_status IS NULL OR status = $1)
+        ORDER BY status = 'requested' DESC,
+                 CASE WHEN status = 'requested' THEN created_at END ASC,
+                 created_at DESC
+        LIMIT 200
+        "#,
+    )
+    .bind(status)
+    .fetch_all(pool)
+    .await?;
+    attach_items(pool, requests).await
+}
+
+/// Zwroty, za które klient nie dostał jeszcze pieniędzy: czekające na decyzję albo przyjęte
+/// z nierozliczonym wpisem w order_refunds
+pub async fn pending_refund_returns(pool: &PgPool) -> Result<Vec<ReturnDetails>, AppError> {
+    let requests = sqlx::query_as::<_, ReturnRequest>(
+        r#"
+        SELECT r.* FROM returns r
+        WHERE r.status = $1
+           OR (r.status = $2 AND EXISTS (
+               SELECT 1 FROM order_refunds o WHERE o.return_id = r.id AND o.refunded_at IS NULL
+           ))
+        ORDER BY r.created_at ASC
+        "#,
+    )
+    .bind(ReturnStatus::Requested)
+    .bind(ReturnStatus::Approved)
+    .fetch_all(pool)
+    .await?;
+    attach_items(pool, requests).await
+}
+
+pub async fn find_return(pool: &PgPool, return_id: Uuid) -> Result<ReturnDetails, AppError> {
+    let request = sqlx::query_as::<_, ReturnRequest>("SELECT * FROM returns WHERE id = $1")
+        .bind(return_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    attach_items(pool, vec![request])
+        .await?
+        .pop()
+        .ok_or(AppError::NotFound)
+}
+
+/// Zapisuje zgłoszenie zwrotu. Zamówienie jest blokowane, żeby dwa równoległe zgłoszenia
+/// nie objęły tej samej pozycji. Sprawdzenie terminu należy do wywołującego.
+pub async fn create_return_request(
+    pool: &PgPool,
+    order_id: Uuid,
+    user_id: Uuid,
+    order_item_ids: &[Uuid],
+    reason: &str,
+    bank_account: &str,
+) -> Result<ReturnDetails, AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT id FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let items = sqlx::query_as::<_, OrderItem>(
+        r#"
+        SELECT oi.* FROM order_items oi
+        WHERE oi.order_id = $1 AND oi.id = ANY($2)
+          AND NOT EXISTS (
+              SELECT 1 FROM return_items ri
+              JOIN returns r ON r.id = ri.return_id
+              WHERE ri.order_item_id = oi.id AND r.status <> $3
+          )
+        "#,
+    )
+    .bind(order_id)
+    .bind(order_item_ids)
+    .bind(ReturnStatus::Rejected)
+    .fetch_all(&mut *tx)
+    .await?;
+    if items.is_empty() || items.len() != order_item_ids.len() {
+        return Err(AppError::Conflict(
+            "Część wybranych produktów jest już objęta zgłoszonym zwrotem.".to_string(),
+        ));
+    }
+
+    let request = sqlx::query_as::<_, ReturnRequest>(
+        r#"
+        INSERT INTO returns (order_id, user_id, reason, bank_account)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(order_id)
+    .bind(user_id)
+    .bind(reason)
+    .bind(bank_account)
+    .fetch_one(&mut *tx)
+    .await?;
+    for item in &items {
+        sqlx::query(
+            "INSERT INTO return_items (return_id, order_item_id, product_id, amount) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(request.id)
+        .bind(item.id)
+        .bind(item.product_id)
+        .bind(item.price_at_purchase)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!(
+        "Zgłoszono zwrot {} ({} pozycji) do zamówienia {}",
+        request.id,
+        items.len(),
+        order_id
+    );
+    find_return(pool, request.id).await
+}
+
+/// Przyjmuje albo odrzuca zgłoszony zwrot. Przyjęcie rejestruje zwrot środków za każdą
+/// pozycję (pomniejszony o cenę etykiety zwrotnej) i przywraca sprzedane produkty do sprzedaży.
+pub async fn decide_return(
+    app_state: &AppState,
+    return_id: Uuid,
+    admin_id: Uuid,
+    approve: bool,
+    admin_note: Option<&str>,
+) -> Result<ReturnDetails, AppError> {
+    let mut tx = app_state.db_pool.begin().await?;
+    let request =
+        sqlx::query_as::<_, ReturnRequest>("SELECT * FROM returns WHERE id = $1 FOR UPDATE")
+            .bind(return_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(AppError::NotFound)?;
+    if request.status != ReturnStatus::Requested {
+        return Err(AppError::Conflict(format!(
+            "Zwrot ma już status \"{}\".",
+            request.status
+        )));
+    }
+
+    let new_status = if approve {
+        ReturnStatus::Approved
+    } else {
+        ReturnStatus::Rejected
+    };
+    sqlx::query(
+        r#"
+        UPDATE returns
+        SET status = $1, admin_note = $2, decided_by = $3, decided_at = NOW()
+        WHERE id = $4
+        "#,
+    )
+    .bind(new_status)
+    .bind(admin_note)
+    .bind(admin_id)
+    .bind(return_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let mut relisted_ids: Vec<Uuid> = Vec::new();
+    if approve {
+        let items: Vec<(Uuid, Uuid, i64)> = sqlx::query_as(
+            "SELECT order_item_id, product_id, amount FROM return_items WHERE return_id = $1",
+        )
+        .bind(return_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let refund_reason = format!("Odstąpienie od umowy ({})", return_reference(&request));
+        // Cena etykiety zwrotnej pomniejsza kolejne pozycje, aż zostanie w całości potrącona
+        let mut label_charge: i64 =
+            sqlx::query_scalar("SELECT price FROM return_labels WHERE return_id = $1")
+                .bind(return_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .unwrap_or(0);
+        for (order_item_id, product_id, amount) in &items {
+            let deducted = label_charge.min(*amount);
+            label_charge -= deducted;
+            let refund_amount = amount - deducted;
+            let refund_vat = refund_vat_breakdown(&mut tx, *order_item_id, refund_amount).await?;
+            sqlx::query(
+                r#"
+                INSERT INTO order_refunds (order_id, return_id, product_id, amount, net_amount, vat_amount, reason, created_by)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                "#,
+            )
+            .bind(request.order_id)
+            .bind(return_id)
+            .bind(product_id)
+            .bind(refund_amount)
+            .bind(refund_vat.net)
+            .bind(refund_vat.vat)
+            .bind(&refund_reason)
+            .bind(admin_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let product_ids: Vec<Uuid> = items.iter().map(|(_, product_id, _)| *product_id).collect();
+        relisted_ids = sqlx::query_scalar(
+            r#"
+            UPDATE products SET status = $1, updated_at = NOW()
+            WHERE id = ANY($2) AND status = $3
+            RETURNING id
+            "#,
+        )
+        .bind(ProductStatus::Available)
+        .bind(&product_ids)
+        .bind(ProductStatus::Sold)
+        .fetch_all(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    for product_id in &relisted_ids {
+        app_state.product_cache.invalidate(product_id).await;
+    }
+    if !relisted_ids.is_empty() {
+        app_state.listing_fragment_cache.invalidate_all();
+        invalidate_merchant_feed(app_state).await;
+    }
+    tracing::info!(
+        "Admin {} {} zwrot {} (zamówienie {}), przywrócono do sprzedaży {} produktów",
+        admin_id,
+        if approve { "przyjął" } else { "odrzucił" },
+        return_id,
+        request.order_id,
+        relisted_ids.len()
+    );
+
+    find_return(&app_state.db_pool, return_id).await
+}
@@ -0,0 +1,216 @@
+// src/reviews.rs
+
+// Opinie o produktach. Wystawić ją może tylko klient, któremu produkt został doręczony
+// (zweryfikowany zakup); na stronie produktu widać wyłącznie opinie zatwierdzone w panelu.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Order, OrderStatus, ProductReview, ReviewStatus};
+
+pub const MAX_REVIEW_CONTENT_LEN: usize = 2000;
+
+const REVIEW_SELECT: &str = r#"
+    SELECT r.*, p.name AS product_name
+    FROM product_reviews r
+    JOIN products p ON p.id = r.product_id
+"#;
+
+/// Średnia ocena i liczba opublikowanych opinii produktu
+#[derive(Debug, Clone, Copy)]
+pub struct RatingSummary {
+    pub average: f64,
+    pub count: i64,
+}
+
+/// Podpis pod opinią: imię i inicjał nazwiska z danych dostawy, np. "Anna K."
+fn review_author_name(order: &Order) -> String {
+    let first_name = order.shipping_first_name.trim();
+    match order.shipping_last_name.trim().chars().next() {
+        Some(initial) => format!("{} {}.", first_name, initial.to_uppercase()),
+        None => first_name.to_string(),
+    }
+}
+
+/// Doręczone zamówienie klienta z tym produktem - warunek wystawienia opinii
+pub async fn delivered_order_with_product(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+) -> Result<Option<Order>, AppError> {
+    Ok(sqlx::query_as::<_, Order>(
+        r#"
+        SELECT o.* FROM orders o
+        WHERE o.user_id = $1 AND o.status = $2
+          AND EXISTS (SELECT 1 FROM order_items oi WHERE oi.order_id = o.id AND oi.product_id = $3)
+        ORDER BY o.order_date DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(user_id)
+    .bind(OrderStatus::Delivered)
+    .bind(product_id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn find_review(pool: &PgPool, review_id: Uuid) -> Result<ProductReview, AppError> {
+    sqlx::query_as::<_, ProductReview>(&format!("{} WHERE r.id = $1", REVIEW_SELECT))
+        .bind(review_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)
+}
+
+/// Opinie klienta o produktach z danego zamówienia (niezależnie od statusu moderacji)
+pub async fn reviews_for_order(
+    pool: &PgPool,
+    order_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<ProductReview>, AppError> {
+    Ok(sqlx::query_as::<_, ProductReview>(&format!(
+        r#"
+        {}
+        WHERE r.user_id = $1
+          AND r.product_id IN (SELECT product_id FROM order_items WHERE order_id = $2)
+        "#,
+        REVIEW_SELECT
+    ))
+    .bind(user_id)
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Opublikowane opinie produktu, od najnowszych
+pub async fn approved_reviews_for_product(
+    pool: &PgPool,
+    product_id: Uuid,
+) -> Result<Vec<ProductReview>, AppError> {
+    Ok(sqlx::query_as::<_, ProductReview>(&format!(
+        "{} WHERE r.product_id = $1 AND r.status = $2 ORDER BY r.created_at DESC",
+        REVIEW_SELECT
+    ))
+    .bind(product_id)
+    .bind(ReviewStatus::Approved)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Podsumowanie ocen z opublikowanych opinii; `None`, gdy produkt nie ma jeszcze żadnej
+pub fn rating_summary(reviews: &[ProductReview]) -> Option<RatingSummary> {
+    let approved: Vec<i16> = reviews
+        .iter()
+        .filter(|review| review.status == ReviewStatus::Approved)
+        .map(|review| review.rating)
+        .collect();
+    if approved.is_empty() {
+        return None;
+    }
+    Some(RatingSummary {
+        average: approved
+            .iter()
+            .map(|&rating| f64::from(rating))
+            .sum::<f64>()
+            / approved.len() as f64,
+        count: approved.len() as i64,
+    })
+}
+
+/// Kolejka moderacji: najpierw oczekujące, od najstarszych
+pub async fn list_reviews(
+    pool: &PgPool,
+    status: Option<ReviewStatus>,
+) -> Result<Vec<ProductReview>, AppError> {
+    Ok(sqlx::query_as::<_, ProductReview>(&format!(
+        r#"
+        {}
+        WHERE ($1::review_status IS NULL OR r.status = $1)
+        ORDER BY r.moderated_at IS NOT NULL, r.moderated_at DESC, r.created_at ASC
+        "#,
+        REVIEW_SELECT
+    ))
+    .bind(status)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Zapisuje opinię klienta (do moderacji). Klient musi mieć doręczone zamówienie
+/// z tym produktem; jeden produkt można ocenić raz.
+pub async fn create_review(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+    rating: i16,
+    content: &str,
+) -> Result<ProductReview, AppError> {
+    let order = delivered_order_with_product(pool, user_id, product_id)
+        .await?
+        .ok_or_else(|| {
+            AppError::UnauthorizedAccess(
+                "Opinię możesz wystawić dopiero po doręczeniu zamówienia z tym produktem."
+                    .to_string(),
+            )
+        })?;
+
+    let review_id: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        INSERT INTO product_reviews (product_id, user_id, order_id, rating, content, author_name, verified_purchase)
+        VALUES ($1, $2, $3, $4, $5, $6, TRUE)
+        ON CONFLICT (product_id, user_id) DO NOTHING
+        RETURNING id
+        "#,
+    )
+    .bind(product_id)
+    .bind(user_id)
+    .bind(order.id)
+    .bind(rating)
+    .bind(content)
+    .bind(review_author_name(&order))
+    .fetch_optional(pool)
+    .await?;
+    let review_id = review_id.ok_or_else(|| {
+        AppError::Conflict("Ten produkt został już przez Ciebie oceniony.".to_string())
+    })?;
+
+    tracing::info!(
+        "Nowa opinia {} o produkcie {} (ocena {}/5) czeka na moderację",
+        review_id,
+        product_id,
+        rating
+    );
+    find_review(pool, review_id).await
+}
+
+/// Decyzja moderatora. Opublikowaną opinię można później ukryć (odrzucić) i odwrotnie.
+pub async fn moderate_review(
+    pool: &PgPool,
+    review_id: Uuid,
+    admin_id: Uuid,
+    status: ReviewStatus,
+) -> Result<ProductReview, AppError> {
+    let updated = sqlx::query(
+        r#"
+        UPDATE product_reviews
+        SET status = $1, moderated_by = $2, moderated_at = NOW()
+        WHERE id = $3
+        "#,
+    )
+    .bind(status)
+    .bind(admin_id)
+    .bind(review_id)
+    .execute(pool)
+    .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!(
+        "Admin {} zmienił status opinii {} na '{}'",
+        admin_id,
+        review_id,
+        status
+    );
+    find_review(pool, review_id).await
+}
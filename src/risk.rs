@@ -0,0 +1,165 @@
+// src/risk.rs
+
+use crate::errors::AppError;
+use crate::models::{Order, OrderRiskAssessment};
+use crate::plural::pluralize;
+use crate::services::find_customer_flags_for_order;
+use crate::state::AppState;
+
+const SCORE_PHONE_PREFIX_MISMATCH: i16 = 30;
+const SCORE_DISPOSABLE_EMAIL: i16 = 40;
+const SCORE_PER_CANCELLED_ORDER_FROM_IP: i16 = 15;
+const MAX_SCORE_CANCELLED_ORDERS_FROM_IP: i16 = 45;
+const SCORE_FLAGGED_CUSTOMER: i16 = 50;
+
+/// Próg, od którego zamówienie traktujemy jako podejrzane.
+pub const HIGH_RISK_THRESHOLD: i16 = 60;
+
+/// Zwraca prefiks kierunkowy numeru telefonu, jeśli numer zapisano w formacie międzynarodowym.
+fn international_phone_prefix(phone: &str) -> Option<String> {
+    let compact: String = phone
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    let digits = compact
+        .strip_prefix('+')
+        .or_else(|| compact.strip_prefix("00"))?;
+    Some(digits.chars().take(2).collect())
+}
+
+fn is_poland(country: &str) -> bool {
+    matches!(
+        country.trim().to_lowercase().as_str(),
+        "polska" | "poland" | "pl"
+    )
+}
+
+/// Liczy prosty wynik ryzyka (0-100) dla świeżo złożonego zamówienia i zapisuje go w bazie.
+///
+/// Sygnały:
+/// 1. Prefiks telefonu niezgodny z krajem dostawy.
+/// 2. E-mail z domeny jednorazowej.
+/// 3. Anulowane (nieopłacone) zamówienia z tego samego IP w ostatnich 30 dniach.
+/// 4. Dane klienta pasujące do flag ostrzegawczych.
+pub async fn assess_and_store_order_risk(
+    app_state: &AppState,
+    order: &Order,
+    client_ip: Option<&str>,
+) -> Result<OrderRiskAssessment, AppError> {
+    let pool = &app_state.db_pool;
+    let mut score: i16 = 0;
+    let mut signals: Vec<String> = Vec::new();
+
+    // Krok 1: Kraj vs prefiks telefonu
+    match international_phone_prefix(&order.shipping_phone) {
+        Some(prefix) if is_poland(&order.shipping_country) && prefix != "48" => {
+            score += SCORE_PHONE_PREFIX_MISMATCH;
+            signals.push(format!(
+                "Zagraniczny numer telefonu (+{}) przy adresie w Polsce",
+                prefix
+            ));
+        }
+        Some(prefix) if !is_poland(&order.shipping_country) && prefix == "48" => {
+            score += SCORE_PHONE_PREFIX_MISMATCH;
+            signals.push(format!(
+                "Polski numer telefonu przy adresie w kraju: {}",
+                order.shipping_country
+            ));
+        }
+        _ => {}
+    }
+
+    // Krok 2: Jednorazowy e-mail
+    let customer_email: Option<String> = match (&order.guest_email, order.user_id) {
+        (Some(email), _) => Some(email.clone()),
+        (None, Some(user_id)) => {
+            sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+        }
+        (None, None) => None,
+    };
+    if let Some(email) = customer_email.as_deref()
+        && app_state.disposable_email_blocklist.is_disposable(email)
+    {
+        score += SCORE_DISPOSABLE_EMAIL;
+        signals.push(format!("Jednorazowy adres e-mail: {}", email));
+    }
+
+    // Krok 3: Anulowane zamówienia z tego samego IP
+    if let Some(ip) = client_ip {
+        let cancelled_from_ip: i64 = sqlx::query_scalar(
+            r#"
+                SELECT COUNT(*)
+                FROM order_risk_assessments ora
+                JOIN orders o ON o.id = ora.order_id
+                WHERE ora.client_ip = $1
+                  AND o.status = 'cancelled'
+                  AND o.order_date > NOW() - INTERVAL '30 days'
+            "#,
+        )
+        .bind(ip)
+        .fetch_one(pool)
+        .await?;
+
+        if cancelled_from_ip > 0 {
+            // Liczymy w i64 - rzutowanie dużej liczby na i16 przekręciłoby wynik
+            let ip_score = cancelled_from_ip
+                .saturating_mul(i64::from(SCORE_PER_CANCELLED_ORDER_FROM_IP))
+                .min(i64::from(MAX_SCORE_CANCELLED_ORDERS_FROM_IP));
+            score += ip_score as i16;
+            signals.push(format!(
+                "{} z IP {} w ostatnich 30 dniach",
+                pluralize(
+                    cancelled_from_ip,
+                    "anulowane zamówienie",
+                    "anulowane zamówienia",
+                    "anulowanych zamówień"
+                ),
+                ip
+            ));
+        }
+    }
+
+    // Krok 4: Flagi klienta
+    let flags = find_customer_flags_for_order(pool, order).await?;
+    if !flags.is_empty() {
+        score += SCORE_FLAGGED_CUSTOMER;
+        for flag in &flags {
+            signals.push(format!(
+                "Flaga klienta ({}): {}",
+                flag.flag_type, flag.reason
+            ));
+        }
+    }
+
+    let score = score.min(100);
+
+    let assessment = sqlx::query_as::<_, OrderRiskAssessment>(
+        r#"
+            INSERT INTO order_risk_assessments (order_id, score, signals, client_ip)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (order_id) DO UPDATE
+                SET score = EXCLUDED.score, signals = EXCLUDED.signals, client_ip = EXCLUDED.client_ip
+            RETURNING *
+        "#,
+    )
+    .bind(order.id)
+    .bind(score)
+    .bind(&signals)
+    .bind(client_ip)
+    .fetch_one(pool)
+    .await?;
+
+    if assessment.score >= HIGH_RISK_THRESHOLD {
+        tracing::warn!(
+            "Zamówienie {} ma wysoki wynik ryzyka: {} ({:?})",
+            order.id,
+            assessment.score,
+            assessment.signals
+        );
+    }
+
+    Ok(assessment)
+}
@@ -0,0 +1,299 @@
+// src/rum.rs
+
+// Pomiary od prawdziwych użytkowników (RUM): mały skrypt wstrzykiwany przez `serve_full_page`
+// zbiera LCP, CLS i INP wejścia na stronę i przy jej opuszczeniu wysyła beacon na `/api/rum`.
+// Przy LCP będącym zdjęciem zapisujemy też, czy miało preload i czy nie było ładowane leniwie -
+// po tym widać, czy zmiany w ładowaniu zdjęć z Cloudinary realnie pomagają.
+// Raport per trasa jest w panelu admina (Wydajność).
+
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::errors::AppError;
+use crate::state::AppState;
+
+/// Jak długo trzymamy surowe pomiary
+pub const RUM_RETENTION_DAYS: i64 = 90;
+
+/// Skrypt wstrzykiwany na koniec `<body>` każdej pełnej strony. Trasę bierzemy z chwili wejścia -
+/// późniejsze przejścia przez HTMX nie mają własnego LCP.
+pub const RUM_SCRIPT: &str = r#"(function () {
+  if (!('PerformanceObserver' in window) || !navigator.sendBeacon || location.pathname.indexOf('/admin') === 0) return;
+  var route = location.pathname, lcp = null, cls = 0, win = 0, winStart = 0, winLast = 0, inp = 0, sent = false;
+  function observe(type, cb, opts) {
+    try {
+      new PerformanceObserver(function (list) { list.getEntries().forEach(cb); })
+        .observe(Object.assign({ type: type, buffered: true }, opts || {}));
+    } catch (e) {}
+  }
+  observe('largest-contentful-paint', function (e) { lcp = e; });
+  observe('layout-shift', function (e) {
+    if (e.hadRecentInput) return;
+    if (win && e.startTime - winLast < 1000 && e.startTime - winStart < 5000) { win += e.value; } else { win = e.value; winStart = e.startTime; }
+    winLast = e.startTime;
+    cls = Math.max(cls, win);
+  });
+  observe('event', function (e) { if (e.interactionId) inp = Math.max(inp, e.duration); }, { durationThreshold: 40 });
+  function send() {
+    if (sent) return;
+    sent = true;
+    var img = !!(lcp && lcp.element && lcp.element.tagName === 'IMG');
+    var preloaded = img && Array.prototype.some.call(document.querySelectorAll('link[rel=preload][as=image]'), function (l) { return l.href === lcp.url; });
+    navigator.sendBeacon('/api/rum', new Blob([JSON.stringify({
+      route: route,
+      device: window.innerWidth < 768 ? 'mobile' : 'desktop',
+      lcp_ms: lcp ? Math.round(lcp.startTime) : null,
+      cls: Math.round(cls * 1000) / 1000,
+      inp_ms: inp ? Math.round(inp) : null,
+      lcp_is_image: lcp ? img : null,
+      lcp_preloaded: img ? preloaded : null,
+      lcp_lazy: img ? lcp.element.loading === 'lazy' : null,
+      lcp_load_ms: img && lcp.loadTime ? Math.round(lcp.loadTime) : null
+    })], { type: 'application/json' }));
+  }
+  addEventListener('visibilitychange', function () { if (document.visibilityState === 'hidden') send(); });
+  addEventListener('pagehide', send);
+})();"#;
+
+/// Strony o stałym adresie - pozostałe adresy grupujemy albo odrzucamy do "(inne)",
+/// żeby beacony z dowolnymi ścieżkami nie mnożyły tras w raporcie
+const STATIC_ROUTES: &[&str] = &[
+    "/",
+    "/kategoria",
+    "/nowosci",
+    "/okazje",
+    "/archiwum",
+    "/o-nas",
+    "/regulamin",
+    "/polityka-prywatnosci",
+    "/moje-konto",
+    "/moje-konto/zamowienia",
+    "/moje-konto/dane",
+    "/checkout",
+    "/wyszukiwanie",
+    "/wyszukaj-podobne",
+    "/kontakt",
+    "/faq",
+    "/wysylka-i-zwroty",
+    "/karta-podarunkowa",
+    "/logowanie",
+    "/rejestracja",
+    "/zamowienie/status",
+    "/zapomnialem-hasla",
+    "/resetuj-haslo",
+    "/weryfikacja-email",
+];
+const GENDER_SLUGS: &[&str] = &["dla-niej", "dla-niego"];
+const OTHER_ROUTE: &str = "(inne)";
+
+/// Grupuje ścieżkę w trasę raportu, np. "/produkty/kurtka-levis-abc" -> "/produkty/{produkt}"
+pub fn route_group(path: &str) -> String {
+    let path = path.trim_end_matches('/');
+    let path = if path.is_empty() { "/" } else { path };
+    if STATIC_ROUTES.contains(&path) {
+        return path.to_string();
+    }
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["produkty", _] => "/produkty/{produkt}".to_string(),
+        ["rezerwacja", _] => "/rezerwacja/{token}".to_string(),
+        ["karta-podarunkowa", _] => "/karta-podarunkowa/{id}".to_string(),
+        ["moje-konto", "zamowienia", _] => "/moje-konto/zamowienia/{id}".to_string(),
+        [gender] if GENDER_SLUGS.contains(gender) => format!("/{}", gender),
+        [gender, _] if GENDER_SLUGS.contains(gender) => format!("/{}/{{kategoria}}", gender),
+        _ => OTHER_ROUTE.to_string(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RumBeacon {
+    pub route: String,
+    pub device: String,
+    pub lcp_ms: Option<f64>,
+    pub cls: Option<f64>,
+    pub inp_ms: Option<f64>,
+    pub lcp_is_image: Option<bool>,
+    pub lcp_preloaded: Option<bool>,
+    pub lcp_lazy: Option<bool>,
+    pub lcp_load_ms: Option<f64>,
+}
+
+/// Odrzuca wartości spoza sensownego zakresu (zepsute przeglądarki, ręcznie wysłane beacony)
+fn bounded_ms(value: Option<f64>) -> Option<i32> {
+    value
+        .filter(|ms| ms.is_finite() && (0.0..=120_000.0).contains(ms))
+        .map(|ms| ms.round() as i32)
+}
+
+async fn record_rum_sample(pool: &PgPool, beacon: &RumBeacon) -> Result<(), AppError> {
+    let device = if beacon.device == "mobile" {
+        "mobile"
+    } else {
+        "desktop"
+    };
+    let cls = beacon
+        .cls
+        .filter(|cls| cls.is_finite() && (0.0..=10.0).contains(cls))
+        .map(|cls| cls as f32);
+    sqlx::query(
+        r#"
+        INSERT INTO rum_samples
+            (route, device, lcp_ms, cls, inp_ms, lcp_is_image, lcp_preloaded, lcp_lazy, lcp_load_ms)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+    )
+    .bind(route_group(&beacon.route))
+    .bind(device)
+    .bind(bounded_ms(beacon.lcp_ms))
+    .bind(cls)
+    .bind(bounded_ms(beacon.inp_ms))
+    .bind(beacon.lcp_is_image)
+    .bind(beacon.lcp_preloaded)
+    .bind(beacon.lcp_lazy)
+    .bind(bounded_ms(beacon.lcp_load_ms))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Beacon z przeglądarki. Odpowiedź nie jest czytana, więc zawsze 204.
+pub async fn rum_beacon_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(beacon): Json<RumBeacon>,
+) -> Result<StatusCode, AppError> {
+    if beacon.lcp_ms.is_none() && beacon.inp_ms.is_none() && beacon.cls.is_none() {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    record_rum_sample(&app_state.db_pool, &beacon).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Ocena metryki według progów Core Web Vitals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VitalRating {
+    Good,
+    NeedsImprovement,
+    Poor,
+}
+
+impl VitalRating {
+    fn from_thresholds(value: f64, good: f64, poor: f64) -> Self {
+        if value <= good {
+            VitalRating::Good
+        } else if value <= poor {
+            VitalRating::NeedsImprovement
+        } else {
+            VitalRating::Poor
+        }
+    }
+
+    pub fn lcp(ms: f64) -> Self {
+        Self::from_thresholds(ms, 2500.0, 4000.0)
+    }
+
+    pub fn cls(cls: f64) -> Self {
+        Self::from_thresholds(cls, 0.1, 0.25)
+    }
+
+    pub fn inp(ms: f64) -> Self {
+        Self::from_thresholds(ms, 200.0, 500.0)
+    }
+}
+
+/// 75. percentyle metryk i statystyki zdjęć LCP dla jednej trasy
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RumRouteStats {
+    pub route: String,
+    pub samples: i64,
+    pub lcp_p75: Option<f64>,
+    pub cls_p75: Option<f64>,
+    pub inp_p75: Option<f64>,
+    pub image_lcp_samples: i64,
+    pub preloaded_samples: i64,
+    pub lazy_samples: i64,
+    pub lcp_load_p75: Option<f64>,
+}
+
+pub async fn fetch_rum_route_stats(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    device: Option<&str>,
+) -> Result<Vec<RumRouteStats>, AppError> {
+    Ok(sqlx::query_as::<_, RumRouteStats>(
+        r#"
+        SELECT route,
+               COUNT(*) AS samples,
+               percentile_cont(0.75) WITHIN GROUP (ORDER BY lcp_ms) AS lcp_p75,
+               percentile_cont(0.75) WITHIN GROUP (ORDER BY cls) AS cls_p75,
+               percentile_cont(0.75) WITHIN GROUP (ORDER BY inp_ms) AS inp_p75,
+               COUNT(*) FILTER (WHERE lcp_is_image) AS image_lcp_samples,
+               COUNT(*) FILTER (WHERE lcp_is_image AND lcp_preloaded) AS preloaded_samples,
+               COUNT(*) FILTER (WHERE lcp_is_image AND lcp_lazy) AS lazy_samples,
+               percentile_cont(0.75) WITHIN GROUP (ORDER BY lcp_load_ms) AS lcp_load_p75
+        FROM rum_samples
+        WHERE recorded_at >= $1 AND ($2::TEXT IS NULL OR device = $2)
+        GROUP BY route
+        ORDER BY samples DESC
+        "#,
+    )
+    .bind(since)
+    .bind(device)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Tygodniowy 75. percentyl LCP całego sklepu - do porównania przed i po zmianach
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RumWeeklyTrend {
+    pub week_start: DateTime<Utc>,
+    pub samples: i64,
+    pub lcp_p75: Option<f64>,
+    pub image_lcp_samples: i64,
+    pub preloaded_samples: i64,
+}
+
+pub async fn fetch_rum_weekly_trend(
+    pool: &PgPool,
+    weeks: i32,
+    device: Option<&str>,
+) -> Result<Vec<RumWeeklyTrend>, AppError> {
+    Ok(sqlx::query_as::<_, RumWeeklyTrend>(
+        r#"
+        SELECT date_trunc('week', recorded_at) AS week_start,
+               COUNT(*) AS samples,
+               percentile_cont(0.75) WITHIN GROUP (ORDER BY lcp_ms) AS lcp_p75,
+               COUNT(*) FILTER (WHERE lcp_is_image) AS image_lcp_samples,
+               COUNT(*) FILTER (WHERE lcp_is_image AND lcp_preloaded) AS preloaded_samples
+        FROM rum_samples
+        WHERE recorded_at >= date_trunc('week', NOW()) - make_interval(weeks => $1)
+          AND ($2::TEXT IS NULL OR device = $2)
+        GROUP BY week_start
+        ORDER BY week_start DESC
+        "#,
+    )
+    .bind(weeks)
+    .bind(device)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Zadanie cykliczne (raz na dobę): usuwa pomiary starsze niż `RUM_RETENTION_DAYS`
+pub async fn purge_rum_samples_job(state: &AppState) -> Result<String, AppError> {
+    let purged = sqlx::query(
+        "DELETE FROM rum_samples WHERE recorded_at < NOW() - make_interval(days => $1)",
+    )
+    .bind(RUM_RETENTION_DAYS as i32)
+    .execute(&state.db_pool)
+    .await?
+    .rows_affected();
+    Ok(match purged {
+        0 => String::new(),
+        purged => format!("Usunięto {} starych pomiarów RUM.", purged),
+    })
+}
@@ -0,0 +1,127 @@
+// src/sales.rs
+
+// Zaplanowane promocje: `sale_price` obowiązuje między `sale_starts_at` a `sale_ends_at`.
+// Cenę efektywną liczymy przy każdym zapytaniu - w SQL (filtry, sortowanie, licznik cen)
+// przez `EFFECTIVE_PRICE_SQL`, w Rust przez `Product::effective_price`. Wyrenderowane listingi
+// siedzą w cache'u, więc zadanie `sale_boundaries_job` czyści go, gdy promocja rusza lub się kończy,
+// i zapisuje oba momenty w historii cen (najniższa cena z 30 dni musi je uwzględniać).
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::merchant_feed::invalidate_merchant_feed;
+use crate::models::Product;
+use crate::state::AppState;
+
+macro_rules! sale_active_sql {
+    () => {
+        "(sale_price IS NOT NULL AND (sale_starts_at IS NULL OR sale_starts_at <= NOW()) AND (sale_ends_at IS NULL OR sale_ends_at > NOW()))"
+    };
+}
+
+/// Cena, którą klient zapłaci teraz. Wyrażenia SQL z tego modułu wymagają `FROM products`
+/// bez aliasu.
+pub const EFFECTIVE_PRICE_SQL: &str = concat!(
+    "(CASE WHEN ",
+    sale_active_sql!(),
+    " THEN sale_price ELSE price END)"
+);
+
+/// Produkt "na okazji": ręcznie oznaczony albo z trwającą promocją
+pub const DISCOUNTED_SQL: &str = concat!("(on_sale OR ", sale_active_sql!(), ")");
+
+/// Jak często sprawdzamy granice promocji (interwał zadania w `jobs`)
+pub const SALE_BOUNDARY_CHECK_SECS: u64 = 60;
+
+/// Odliczanie na stronie "Okazje": kiedy kończy się promocja produktu
+#[derive(Debug, Serialize)]
+pub struct SaleCountdown {
+    pub product_id: Uuid,
+    pub ends_at: DateTime<Utc>,
+}
+
+/// Dane odliczania dla produktów z trwającą promocją z ustaloną datą końca
+pub fn sale_countdowns(products: &[Product]) -> Vec<SaleCountdown> {
+    let now = Utc::now();
+    products
+        .iter()
+        .filter(|product| product.scheduled_sale_active_at(now))
+        .filter_map(|product| {
+            Some(SaleCountdown {
+                product_id: product.id,
+                ends_at: product.sale_ends_at?,
+            })
+        })
+        .collect()
+}
+
+/// Zadanie cykliczne: gdy od ostatniego przebiegu któraś promocja ruszyła albo się skończyła,
+/// zapisujemy zmianę ceny w `product_price_history` i czyścimy wyrenderowane listingi i sekcje
+/// strony głównej (ceny w nich są już nieaktualne). Okno jest trochę dłuższe niż interwał, żeby
+/// opóźnienie przebiegu nie zgubiło granicy.
+pub async fn sale_boundaries_job(state: &AppState) -> Result<String, AppError> {
+    let window_secs = (SALE_BOUNDARY_CHECK_SECS * 2) as f64;
+    record_sale_boundaries(state, window_secs).await?;
+    let changed: i64 = sqlx::query_scalar(
+        r#"
+            SELECT COUNT(*)
+            FROM products
+            WHERE sale_price IS NOT NULL
+              AND ((sale_starts_at > NOW() - make_interval(secs => $1) AND sale_starts_at <= NOW())
+                OR (sale_ends_at > NOW() - make_interval(secs => $1) AND sale_ends_at <= NOW()))
+        "#,
+    )
+    .bind(window_secs)
+    .fetch_one(&state.db_pool)
+    .await?;
+    if changed == 0 {
+        return Ok(String::new());
+    }
+    state.listing_fragment_cache.invalidate_all();
+    invalidate_merchant_feed(state).await;
+    Ok(format!(
+        "Promocje zmieniły się dla {} produktów - wyczyszczono cache listingów.",
+        changed
+    ))
+}
+
+/// Zapisuje w historii cen starty i końce promocji z ostatnich `window_secs` sekund, z datą
+/// samej granicy. Okna kolejnych przebiegów nachodzą na siebie, więc granicę już zapisaną
+/// (ten sam produkt i moment, bez autora) pomijamy.
+async fn record_sale_boundaries(state: &AppState, window_secs: f64) -> Result<(), AppError> {
+    let recorded = sqlx::query(
+        r#"
+            INSERT INTO product_price_history (product_id, old_price, new_price, old_on_sale, new_on_sale, changed_by, changed_at)
+            SELECT b.product_id, b.old_price, b.new_price, b.on_sale, b.on_sale, NULL, b.changed_at
+            FROM (
+                SELECT id AS product_id, price AS old_price, sale_price AS new_price, on_sale,
+                       sale_starts_at AS changed_at
+                FROM products
+                WHERE sale_price IS NOT NULL
+                  AND sale_starts_at > NOW() - make_interval(secs => $1) AND sale_starts_at <= NOW()
+                UNION ALL
+                SELECT id, sale_price, price, on_sale, sale_ends_at
+                FROM products
+                WHERE sale_price IS NOT NULL
+                  AND sale_ends_at > NOW() - make_interval(secs => $1) AND sale_ends_at <= NOW()
+            ) b
+            WHERE NOT EXISTS (
+                SELECT 1
+                FROM product_price_history h
+                WHERE h.product_id = b.product_id
+                  AND h.changed_at = b.changed_at
+                  AND h.changed_by IS NULL
+            )
+        "#,
+    )
+    .bind(window_secs)
+    .execute(&state.db_pool)
+    .await?
+    .rows_affected();
+    if recorded > 0 {
+        tracing::info!("Zapisano {} granic promocji w historii cen", recorded);
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+// src/sales_register.rs
+
+// Miesięczny rejestr sprzedaży dla księgowej (CSV do importu w programie księgowym, układ
+// zgodny z ewidencją JPK_V7). Sprzedaż to zamówienia opłacone (bez oczekujących i anulowanych)
+// według daty złożenia, a zwroty pieniędzy zrealizowane w danym miesiącu idą jako korekty z minusem.
+// Netto, VAT i procedura pochodzą z rozbicia zapisanego przy zamówieniu i zwrocie (zob. `vat`), więc
+// zmiana ustawień podatkowych nie przepisuje wstecz już zaksięgowanych miesięcy. Przy procedurze
+// marży (MR_T) podatku nie wykazujemy - księgowa liczy go od marży.
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use sqlx::PgPool;
+
+use crate::date_format::{shop_local_to_utc, to_shop_time};
+use crate::errors::AppError;
+use crate::models::{OrderStatus, PaymentMethod};
+use crate::vat::VatScheme;
+
+/// Oznaczenie procedury marży dla towarów używanych w JPK_V7
+const MARGIN_SCHEME_PROCEDURE: &str = "MR_T";
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SalesRegisterEntry {
+    pub order_number: String,
+    pub invoice_number: Option<String>,
+    /// Data sprzedaży (złożenia zamówienia) albo data przelewu zwrotu dla korekty
+    pub entry_date: DateTime<Utc>,
+    pub payment_method: Option<PaymentMethod>,
+    pub customer_country: String,
+    /// Kwoty w groszach; ujemne dla korekt
+    pub net: i64,
+    pub vat: i64,
+    pub gross: i64,
+    pub vat_scheme: VatScheme,
+    pub is_correction: bool,
+}
+
+impl SalesRegisterEntry {
+    /// Oznaczenie procedury w JPK_V7; zwykła sprzedaż opodatkowana nie ma oznaczenia
+    pub fn procedure(&self) -> &'static str {
+        match self.vat_scheme {
+            VatScheme::Margin => MARGIN_SCHEME_PROCEDURE,
+            VatScheme::Standard => "",
+        }
+    }
+}
+
+/// Pierwszy dzień miesiąca z parametru "RRRR-MM"; domyślnie poprzedni miesiąc
+/// (eksport robi się zwykle po zamknięciu miesiąca)
+pub fn parse_register_month(month: Option<&str>) -> Result<NaiveDate, AppError> {
+    match month.filter(|month| !month.is_empty()) {
+        Some(month) => NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+            .map_err(|_| AppError::BadRequest("Miesiąc musi mieć format RRRR-MM.".to_string())),
+        None => {
+            let today = to_shop_time(&Utc::now()).date_naive();
+            let first_of_month = today.with_day(1).unwrap_or(today);
+            Ok(first_of_month
+                .pred_opt()
+                .and_then(|last_month_day| last_month_day.with_day(1))
+                .unwrap_or(first_of_month))
+        }
+    }
+}
+
+fn month_range_utc(month_start: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let next_month = month_start
+        .checked_add_months(chrono::Months::new(1))
+        .unwrap_or(month_start);
+    (
+        shop_local_to_utc(month_start.and_hms_opt(0, 0, 0).unwrap_or_default()),
+        shop_local_to_utc(next_month.and_hms_opt(0, 0, 0).unwrap_or_default()),
+    )
+}
+
+/// Sprzedaż i korekty z miesiąca, w kolejności dat
+pub async fn fetch_sales_register(
+    pool: &PgPool,
+    month_start: NaiveDate,
+) -> Result<Vec<SalesRegisterEntry>, AppError> {
+    let (from, to) = month_range_utc(month_start);
+    Ok(sqlx::query_as::<_, SalesRegisterEntry>(
+        r#"
+        SELECT o.order_number, i.number AS invoice_number, o.order_date AS entry_date,
+               o.payment_method, o.shipping_country AS customer_country,
+               o.net_total AS net, o.vat_total AS vat, o.total_price AS gross,
+               o.vat_scheme, FALSE AS is_correction
+        FROM orders o
+        LEFT JOIN invoices i ON i.order_id = o.id
+        WHERE o.order_date >= $1 AND o.order_date < $2
+          AND o.status NOT IN ($3, $4)
+
+        UNION ALL
+
+        SELECT o.order_number, i.number AS invoice_number, MAX(r.refunded_at) AS entry_date,
+               o.payment_method, o.shipping_country AS customer_country,
+               -SUM(r.net_amount)::BIGINT AS net, -SUM(r.vat_amount)::BIGINT AS vat,
+               -SUM(r.amount)::BIGINT AS gross, o.vat_scheme, TRUE AS is_correction
+        FROM order_refunds r
+        JOIN orders o ON o.id = r.order_id
+        LEFT JOIN invoices i ON i.order_id = o.id
+        WHERE r.refunded_at >= $1 AND r.refunded_at < $2
+        GROUP BY o.id, i.number, r.refunded_at::date
+
+        ORDER BY entry_date, order_number
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(OrderStatus::Pending)
+    .bind(OrderStatus::Cancelled)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Kwota z przecinkiem dziesiętnym, bez waluty ("1234,50")
+fn format_amount(amount: i64) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{}{},{:02}", sign, amount.abs() / 100, amount.abs() % 100)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([';', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSV rozdzielany średnikami, z BOM - tak, jak oczekują Excel i polskie programy księgowe
+pub fn sales_register_csv(entries: &[SalesRegisterEntry]) -> String {
+    let mut csv_output = String::from(
+        "\u{feff}Lp;Numer zamówienia;Numer faktury;Data;Rodzaj;Metoda płatności;Kraj nabywcy;Netto;VAT;Brutto;Procedura\n",
+    );
+    for (index, entry) in entries.iter().enumerate() {
+        let fields = [
+            (index + 1).to_string(),
+            entry.order_number.clone(),
+            entry.invoice_number.clone().unwrap_or_default(),
+            to_shop_time(&entry.entry_date)
+                .format("%Y-%m-%d")
+                .to_string(),
+            if entry.is_correction {
+                "Korekta (zwrot)".to_string()
+            } else {
+                "Sprzedaż".to_string()
+            },
+            entry
+                .payment_method
+                .as_ref()
+                .map(|method| method.to_string())
+                .unwrap_or_default(),
+            entry.customer_country.clone(),
+            format_amount(entry.net),
+            format_amount(entry.vat),
+            format_amount(entry.gross),
+            entry.procedure().to_string(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv_output.push_str(&line.join(";"));
+        csv_output.push('\n');
+    }
+    csv_output
+}
+
+/// Nazwa pliku, np. "rejestr-sprzedazy-2025-08.csv"
+pub fn sales_register_filename(month_start: NaiveDate) -> String {
+    format!("rejestr-sprzedazy-{}.csv", month_start.format("%Y-%m"))
+}
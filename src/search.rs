@@ -0,0 +1,177 @@
+// src/search.rs
+
+// Wyszukiwanie pełnotekstowe produktów (kolumna `products.search_vector`, konfiguracja `shop_search`).
+// Frazy są dopasowywane prefiksowo, więc "sukien" znajdzie "sukienka" już w trakcie pisania,
+// a literówki w nazwie łapie dodatkowo `word_similarity` z pg_trgm.
+
+use maud::{Markup, html};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Category, Product, ProductStatus};
+use crate::sales::EFFECTIVE_PRICE_SQL;
+
+/// Minimalne podobieństwo nazwy (0-1), przy którym produkt trafia do wyników mimo literówki.
+const TYPO_SIMILARITY_THRESHOLD: f32 = 0.5;
+
+// Znaczniki podświetlenia z ts_headline. Nie mogą być HTML-em, bo opis produktu nie jest
+// escapowany przez Postgresa - zamieniamy je na <mark> dopiero po escapowaniu w maud.
+const HIGHLIGHT_START: &str = "\u{1}";
+const HIGHLIGHT_STOP: &str = "\u{2}";
+
+/// Zamienia frazę użytkownika na zapytanie `to_tsquery`: każde słowo jako prefiks, wszystkie wymagane.
+/// Zwraca `None`, gdy fraza nie zawiera żadnego słowa (np. same znaki interpunkcyjne).
+pub fn prefix_tsquery(term: &str) -> Option<String> {
+    let words: Vec<String> = term
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("{}:*", w.to_lowercase()))
+        .collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" & "))
+    }
+}
+
+/// Dokłada warunek wyszukiwania (bez `WHERE`/`AND`) do zapytania o produkty.
+pub fn push_search_condition(builder: &mut QueryBuilder<'_, Postgres>, term: &str) {
+    builder.push("(");
+    if let Some(tsquery) = prefix_tsquery(term) {
+        builder
+            .push("search_vector @@ to_tsquery('shop_search', ")
+            .push_bind(tsquery)
+            .push(") OR ");
+    }
+    builder
+        .push("word_similarity(")
+        .push_bind(term.to_lowercase())
+        .push(", lower(name)) >= ")
+        .push_bind(TYPO_SIMILARITY_THRESHOLD)
+        .push(")");
+}
+
+/// Dokłada wyrażenie trafności wyniku (do `ORDER BY ... DESC`).
+pub fn push_search_rank(builder: &mut QueryBuilder<'_, Postgres>, term: &str) {
+    builder.push("(");
+    if let Some(tsquery) = prefix_tsquery(term) {
+        builder
+            .push("ts_rank(search_vector, to_tsquery('shop_search', ")
+            .push_bind(tsquery)
+            .push(")) + ");
+    }
+    builder
+        .push("word_similarity(")
+        .push_bind(term.to_lowercase())
+        .push(", lower(name)))");
+}
+
+/// Wynik podpowiedzi wyszukiwania z podświetlonymi fragmentami
+#[derive(Debug, sqlx::FromRow)]
+pub struct SearchHit {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub price: i64,
+    pub images: Vec<String>,
+    pub name_highlighted: String,
+    pub snippet: Option<String>,
+}
+
+/// Najtrafniejsze dostępne produkty dla frazy, z fragmentem opisu zawierającym szukane słowa.
+pub async fn search_products_with_snippets(
+    pool: &PgPool,
+    term: &str,
+    limit: i64,
+) -> Result<Vec<SearchHit>, AppError> {
+    let tsquery = prefix_tsquery(term).unwrap_or_default();
+    let headline_options = format!(
+        "StartSel={}, StopSel={}, HighlightAll=true",
+        HIGHLIGHT_START, HIGHLIGHT_STOP
+    );
+    let snippet_options = format!(
+        "StartSel={}, StopSel={}, MaxWords=14, MinWords=6, MaxFragments=1",
+        HIGHLIGHT_START, HIGHLIGHT_STOP
+    );
+
+    let mut builder: QueryBuilder<Postgres> = QueryBuilder::new("WITH q AS (SELECT CASE WHEN ");
+    builder
+        .push_bind(tsquery.clone())
+        .push(" = '' THEN NULL ELSE to_tsquery('shop_search', ")
+        .push_bind(tsquery)
+        .push(") END AS query) ")
+        .push(format!(
+            "SELECT id, name, slug, {} AS price, images, ",
+            EFFECTIVE_PRICE_SQL
+        ))
+        .push("COALESCE(ts_headline('shop_search', name, q.query, ")
+        .push_bind(headline_options)
+        .push("), name) AS name_highlighted, ")
+        .push("CASE WHEN q.query IS NOT NULL AND to_tsvector('shop_search', description) @@ q.query THEN ts_headline('shop_search', description, q.query, ")
+        .push_bind(snippet_options)
+        .push(") END AS snippet ")
+        .push("FROM products, q WHERE status IN ('Available', 'Reserved') AND ");
+    push_search_condition(&mut builder, term);
+    builder.push(" ORDER BY ");
+    push_search_rank(&mut builder, term);
+    builder.push(" DESC, id ASC LIMIT ").push_bind(limit);
+
+    let hits = builder
+        .build_query_as::<SearchHit>()
+        .fetch_all(pool)
+        .await?;
+    Ok(hits)
+}
+
+/// Propozycje dla nieistniejącego adresu (strona 404): dostępne produkty pasujące do
+/// któregokolwiek słowa (w odróżnieniu od wyszukiwarki, gdzie wymagane są wszystkie),
+/// a bez słów - najnowsze z kategorii albo całego sklepu.
+pub async fn suggest_products(
+    pool: &PgPool,
+    words: &[String],
+    category: Option<Category>,
+    limit: i64,
+) -> Result<Vec<Product>, AppError> {
+    let any_word_tsquery = words
+        .iter()
+        .filter_map(|word| prefix_tsquery(word))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    let mut builder: QueryBuilder<Postgres> =
+        QueryBuilder::new("SELECT * FROM products WHERE status = ");
+    builder.push_bind(ProductStatus::Available);
+    if let Some(category) = category {
+        builder.push(" AND category = ").push_bind(category);
+    }
+    if any_word_tsquery.is_empty() {
+        builder.push(" ORDER BY created_at DESC");
+    } else {
+        builder
+            .push(" AND search_vector @@ to_tsquery('shop_search', ")
+            .push_bind(any_word_tsquery.clone())
+            .push(") ORDER BY ts_rank(search_vector, to_tsquery('shop_search', ")
+            .push_bind(any_word_tsquery)
+            .push(")) DESC, created_at DESC");
+    }
+    builder.push(" LIMIT ").push_bind(limit);
+
+    let products = builder.build_query_as::<Product>().fetch_all(pool).await?;
+    Ok(products)
+}
+
+/// Renderuje tekst z ts_headline: treść jest escapowana, a dopasowania otoczone `<mark>`.
+pub fn render_highlighted(text: &str) -> Markup {
+    html! {
+        @for (i, part) in text.split(HIGHLIGHT_START).enumerate() {
+            @if i == 0 {
+                (part)
+            } @else {
+                @let (matched, rest) = part.split_once(HIGHLIGHT_STOP).unwrap_or((part, ""));
+                mark ."bg-pink-100 text-inherit rounded-sm" { (matched) }
+                (rest)
+            }
+        }
+    }
+}
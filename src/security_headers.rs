@@ -0,0 +1,97 @@
+// src/security_headers.rs
+
+// Nagłówki bezpieczeństwa dla wszystkich odpowiedzi: CSP, X-Frame-Options, Referrer-Policy,
+// X-Content-Type-Options i HSTS. Każde żądanie dostaje losowy nonce CSP (`CspNonce` w rozszerzeniach
+// żądania), którym `build_response` oznacza skrypty pełnej strony (RUM, JSON-LD, skrypty szablonu).
+
+use std::convert::Infallible;
+
+use axum::extract::{FromRequestParts, Request};
+use axum::http::request::Parts;
+use axum::http::{HeaderValue, header};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Skrypty z CDN faktycznie ładowane przez static/index.html (Tailwind, HTMX, Alpine)
+const SCRIPT_HOSTS: &str = "https://cdn.jsdelivr.net https://unpkg.com";
+/// Zdjęcia produktów i miniatury z transformacjami
+const IMAGE_HOSTS: &str = "https://res.cloudinary.com";
+
+const HSTS: &str = "max-age=31536000; includeSubDomains";
+
+/// Polityka CSP. `'unsafe-eval'` jest potrzebne standardowej wersji Alpine (wyrażenia w `x-data`,
+/// `@click` itd.), a `'unsafe-inline'` w stylach - przeglądarkowemu Tailwindowi i atrybutom `style`.
+pub fn content_security_policy(nonce: &str) -> String {
+    format!(
+        "default-src 'self'; \
+         script-src 'self' 'nonce-{}' 'unsafe-eval' {}; \
+         style-src 'self' 'unsafe-inline'; \
+         img-src 'self' data: blob: {}; \
+         font-src 'self' data:; \
+         connect-src 'self'; \
+         object-src 'none'; \
+         base-uri 'self'; \
+         form-action 'self'; \
+         frame-ancestors 'none'",
+        nonce, SCRIPT_HOSTS, IMAGE_HOSTS
+    )
+}
+
+/// Nonce CSP bieżącego żądania - losowany dla każdego żądania przez `security_headers`,
+/// więc podejrzany na jednej stronie nie pozwoli wstrzyknąć skryptu na następnej.
+#[derive(Debug, Clone)]
+pub struct CspNonce(String);
+
+impl CspNonce {
+    fn generate() -> Self {
+        CspNonce(hex::encode(rand::random::<[u8; 16]>()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Nonce z rozszerzeń żądania. Poza middleware (np. w testach) losujemy nowy -
+/// skrypty bez pasującego nagłówka CSP i tak się wykonają.
+impl<S: Send + Sync> FromRequestParts<S> for CspNonce {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<CspNonce>()
+            .cloned()
+            .unwrap_or_else(CspNonce::generate))
+    }
+}
+
+/// Middleware ustawiające nagłówki bezpieczeństwa. Losuje nonce CSP żądania i odkłada go
+/// w rozszerzeniach dla handlerów. Nie nadpisuje nagłówków ustawionych przez handler.
+pub async fn security_headers(mut request: Request, next: Next) -> Response {
+    let nonce = CspNonce::generate();
+    request.extensions_mut().insert(nonce.clone());
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+
+    headers
+        .entry(header::CONTENT_SECURITY_POLICY)
+        .or_insert_with(|| {
+            HeaderValue::from_str(&content_security_policy(nonce.as_str()))
+                .unwrap_or(HeaderValue::from_static("default-src 'self'"))
+        });
+    headers
+        .entry(header::X_FRAME_OPTIONS)
+        .or_insert(HeaderValue::from_static("DENY"));
+    headers
+        .entry(header::REFERRER_POLICY)
+        .or_insert(HeaderValue::from_static("strict-origin-when-cross-origin"));
+    headers
+        .entry(header::X_CONTENT_TYPE_OPTIONS)
+        .or_insert(HeaderValue::from_static("nosniff"));
+    headers
+        .entry(header::STRICT_TRANSPORT_SECURITY)
+        .or_insert(HeaderValue::from_static(HSTS));
+
+    response
+}
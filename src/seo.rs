@@ -1,7 +1,137 @@
 // src/seo.rs
 
+use maud::{Markup, html};
 use serde::Serialize;
 
+use crate::models::Product;
+use crate::views::common::transform_cloudinary_url;
+
+// --- Podgląd linku: meta description, OpenGraph, Twitter Card i adres kanoniczny ---
+
+/// Tyle znaków opisu produktu trafia do meta description, gdy nie ustawiono własnego
+const META_DESCRIPTION_FALLBACK_LEN: usize = 160;
+/// Podgląd linku: 1200x630 w JPG - nie każdy serwis obsługuje AVIF/WebP
+const OG_IMAGE_TRANSFORMATION: &str = "w_1200,h_630,c_fill,g_auto,f_jpg,q_auto:good";
+/// Obrazek podglądu stron bez własnego zdjęcia
+const DEFAULT_OG_IMAGE_PATH: &str = "/static/main-logo.png";
+
+/// Metadane podglądu linku (Facebook, Instagram, komunikatory) - przekazywane do `PageBuilder`
+pub struct SocialMeta {
+    pub title: String,
+    pub description: String,
+    pub image: String,
+    /// `og:type`: "website" albo "product"
+    pub og_type: &'static str,
+    pub canonical_url: String,
+}
+
+impl SocialMeta {
+    /// `path` to adres strony bez domeny i bez parametrów (filtry, paginacja)
+    pub fn new(
+        title: impl Into<String>,
+        description: impl Into<String>,
+        base_url: &str,
+        path: &str,
+    ) -> Self {
+        SocialMeta {
+            title: title.into(),
+            description: description.into(),
+            image: format!("{}{}", base_url, DEFAULT_OG_IMAGE_PATH),
+            og_type: "website",
+            canonical_url: format!("{}{}", base_url, path),
+        }
+    }
+
+    /// Własne pola SEO produktu albo wartości wyliczone z nazwy, opisu i pierwszego zdjęcia
+    pub fn for_product(product: &Product, base_url: &str) -> Self {
+        let title = product.meta_title.clone().unwrap_or_else(|| {
+            format!(
+                "{} - Szczegóły produktu - sklep mess - all that vintage",
+                product.name
+            )
+        });
+        let description = product
+            .meta_description
+            .clone()
+            .unwrap_or_else(|| truncate_description(&product.description));
+        let mut meta = SocialMeta::new(title, description, base_url, &product.public_path());
+        meta.og_type = "product";
+        if let Some(image) = product.og_image.clone().or_else(|| {
+            product
+                .images
+                .first()
+                .map(|url| transform_cloudinary_url(url, OG_IMAGE_TRANSFORMATION))
+        }) {
+            meta.image = image;
+        }
+        meta
+    }
+
+    /// Tagi do `<head>` - tylko przy pełnym wejściu na stronę, tak czytają je boty serwisów
+    pub fn head_tags(&self) -> Markup {
+        html! {
+            link rel="canonical" href=(self.canonical_url);
+            meta property="og:type" content=(self.og_type);
+            meta property="og:site_name" content="mess - all that vintage";
+            meta property="og:locale" content="pl_PL";
+            meta property="og:title" content=(self.title);
+            meta property="og:description" content=(self.description);
+            meta property="og:url" content=(self.canonical_url);
+            meta property="og:image" content=(self.image);
+            meta name="twitter:card" content="summary_large_image";
+            meta name="twitter:title" content=(self.title);
+            meta name="twitter:description" content=(self.description);
+            meta name="twitter:image" content=(self.image);
+        }
+    }
+}
+
+/// `rel="prev"`/`rel="next"` strony listy stronicowanej (pełne adresy)
+pub struct PaginationLinks {
+    pub prev_url: Option<String>,
+    pub next_url: Option<String>,
+}
+
+impl PaginationLinks {
+    /// `page_url` dostaje przesunięcie (`offset`) sąsiedniej strony
+    pub fn new(
+        current_page: i64,
+        total_pages: i64,
+        per_page: i64,
+        page_url: impl Fn(i64) -> String,
+    ) -> Self {
+        PaginationLinks {
+            prev_url: (current_page > 1).then(|| page_url((current_page - 2) * per_page)),
+            next_url: (current_page < total_pages).then(|| page_url(current_page * per_page)),
+        }
+    }
+
+    pub fn head_tags(&self) -> Markup {
+        html! {
+            @if let Some(prev_url) = &self.prev_url {
+                link rel="prev" href=(prev_url);
+            }
+            @if let Some(next_url) = &self.next_url {
+                link rel="next" href=(next_url);
+            }
+        }
+    }
+}
+
+/// Początek opisu w jednej linii, ucięty na granicy słowa
+fn truncate_description(description: &str) -> String {
+    let normalized = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if normalized.chars().count() <= META_DESCRIPTION_FALLBACK_LEN {
+        return normalized;
+    }
+    let cut: String = normalized
+        .chars()
+        .take(META_DESCRIPTION_FALLBACK_LEN)
+        .collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end_matches([',', '.', ';', ':', '-']))
+}
+
 // --- Struktury dla Schema.org -> Product ---
 
 #[derive(Serialize)]
@@ -22,6 +152,21 @@ pub struct SchemaOffer<'a> {
     pub item_condition: &'a str,
 }
 
+/// Średnia z opublikowanych opinii klientów
+#[derive(Serialize)]
+pub struct SchemaAggregateRating<'a> {
+    #[serde(rename = "@type")]
+    pub type_of: &'a str,
+    #[serde(rename = "ratingValue")]
+    pub rating_value: String,
+    #[serde(rename = "reviewCount")]
+    pub review_count: i64,
+    #[serde(rename = "bestRating")]
+    pub best_rating: u8,
+    #[serde(rename = "worstRating")]
+    pub worst_rating: u8,
+}
+
 #[derive(Serialize)]
 pub struct SchemaProduct<'a> {
     #[serde(rename = "@context")]
@@ -33,7 +178,15 @@ pub struct SchemaProduct<'a> {
     pub sku: String,
     pub image: &'a [String],
     pub brand: SchemaBrand<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub material: Option<&'a str>,
     pub offers: SchemaOffer<'a>,
+    #[serde(rename = "aggregateRating", skip_serializing_if = "Option::is_none")]
+    pub aggregate_rating: Option<SchemaAggregateRating<'a>>,
 }
 
 // --- Struktury dla Schema.org -> Organization (dla strony głównej) ---
@@ -74,6 +227,51 @@ pub struct SchemaListItem {
     pub item: Option<String>,
 }
 
+/// Element ścieżki nawigacji; `path` (bez domeny) jest `None` dla bieżącej strony
+pub struct Breadcrumb {
+    pub name: String,
+    pub path: Option<String>,
+}
+
+impl Breadcrumb {
+    pub fn link(name: impl Into<String>, path: impl Into<String>) -> Self {
+        Breadcrumb {
+            name: name.into(),
+            path: Some(path.into()),
+        }
+    }
+
+    pub fn current(name: impl Into<String>) -> Self {
+        Breadcrumb {
+            name: name.into(),
+            path: None,
+        }
+    }
+}
+
+impl SchemaBreadcrumbList<'_> {
+    /// Ta sama ścieżka co widoczne okruszki, z pełnymi adresami
+    pub fn from_breadcrumbs(breadcrumbs: &[Breadcrumb], base_url: &str) -> Self {
+        SchemaBreadcrumbList {
+            context: "https://schema.org",
+            type_of: "BreadcrumbList",
+            item_list: breadcrumbs
+                .iter()
+                .zip(1..)
+                .map(|(breadcrumb, position)| SchemaListItem {
+                    type_of: "ListItem",
+                    position,
+                    name: breadcrumb.name.clone(),
+                    item: breadcrumb
+                        .path
+                        .as_ref()
+                        .map(|path| format!("{}{}", base_url, path)),
+                })
+                .collect(),
+        }
+    }
+}
+
 // --- Struktury dla Schema.org -> FAQPage ---
 
 #[derive(Serialize)]
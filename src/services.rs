@@ -1,7 +1,22 @@
 // src/services.rs
 
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool, Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::cache_stats::CacheName;
+use crate::date_format::shop_timezone_name;
 use crate::errors::AppError;
-use crate::models::{Category, ProductGender, ProductStatus};
+use crate::filters::{Facet, FacetCounts, ListingParams, PRICE_BUCKETS, push_product_filters};
+use crate::gift_cards::restore_gift_card_redemptions;
+use crate::invoices::{issue_invoice, status_requires_invoice};
+use crate::models::{
+    Category, CategoryConversion, CategorySales, CustomerFlag, CustomerFlagType, FunnelStats,
+    Order, OrderStatus, OrderStatusHistory, ProductCondition, ProductGender, ProductStatus,
+    ReservationConversion, SalesPeriod, SalesSummary,
+};
+use crate::sales::EFFECTIVE_PRICE_SQL;
+use crate::sizes::NORMALIZED_SIZE_SQL;
 use crate::state::AppState;
 
 /// Pobiera listę unikalnych, dostępnych kategorii dla danej płci.
@@ -15,7 +30,11 @@ pub async fn get_available_categories_for_gender(
     gender: ProductGender,
 ) -> Result<Vec<Category>, AppError> {
     // Krok 1: Sprawdzenie cache'u
-    if let Some(cached_categories) = app_state.category_list_cache.get(&gender).await {
+    let cached = app_state.category_list_cache.get(&gender).await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::Categories, cached.is_some());
+    if let Some(cached_categories) = cached {
         tracing::info!("Cache HIT dla listy kategorii dla płci: {:?}", gender);
         return Ok(cached_categories);
     }
@@ -40,6 +59,9 @@ pub async fn get_available_categories_for_gender(
     .await?;
 
     // Krok 3: Zapisanie wyniku w cache'u
+    app_state
+        .cache_stats
+        .record_insert(CacheName::Categories, gender.as_ref());
     app_state
         .category_list_cache
         .insert(gender, available_categories.clone())
@@ -48,3 +70,448 @@ pub async fn get_available_categories_for_gender(
     // Krok 4: Zwrócenie wyniku
     Ok(available_categories)
 }
+
+/// Najwięcej rozmiarów pokazywanych w panelu filtrów (najczęstsze)
+const MAX_SIZE_FACETS: i64 = 12;
+
+/// Liczby produktów dla filtrów listingu: kategorie, stany, rozmiary i przedziały cenowe.
+///
+/// Uogólnienie `get_available_categories_for_gender` na bieżące filtry:
+/// 1. Każdy wymiar liczymy osobnym zapytaniem z filtrami listingu bez filtra tego wymiaru.
+/// 2. Przedziały cenowe liczymy jednym zapytaniem (`COUNT(*) FILTER (...)`).
+/// 3. Wartości bez produktów nie są zwracane (poza przedziałami cenowymi - tam jest 0).
+pub async fn get_facet_counts(
+    pool: &PgPool,
+    params: &ListingParams,
+) -> Result<FacetCounts, AppError> {
+    let mut categories_query: QueryBuilder<Postgres> =
+        QueryBuilder::new("SELECT category, COUNT(*) FROM products");
+    push_product_filters(&mut categories_query, params, Some(Facet::Category));
+    categories_query.push(" GROUP BY category ORDER BY category");
+    let categories = categories_query
+        .build_query_as::<(Category, i64)>()
+        .fetch_all(pool)
+        .await?;
+
+    let mut conditions_query: QueryBuilder<Postgres> =
+        QueryBuilder::new("SELECT condition, COUNT(*) FROM products");
+    push_product_filters(&mut conditions_query, params, Some(Facet::Condition));
+    conditions_query.push(" GROUP BY condition ORDER BY condition");
+    let conditions = conditions_query
+        .build_query_as::<(ProductCondition, i64)>()
+        .fetch_all(pool)
+        .await?;
+
+    // Rozmiary z metek liczymy razem z ich współczesnymi odpowiednikami (FR "38" jako "M")
+    let mut sizes_query: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+        "SELECT {} AS normalized_size, COUNT(*) FROM products",
+        NORMALIZED_SIZE_SQL
+    ));
+    push_product_filters(&mut sizes_query, params, Some(Facet::Size));
+    sizes_query
+        .push(" AND size IS NOT NULL GROUP BY 1 ORDER BY COUNT(*) DESC, 1 LIMIT ")
+        .push_bind(MAX_SIZE_FACETS);
+    let sizes = sizes_query
+        .build_query_as::<(String, i64)>()
+        .fetch_all(pool)
+        .await?;
+
+    let mut prices_query: QueryBuilder<Postgres> = QueryBuilder::new("SELECT ");
+    for (index, bucket) in PRICE_BUCKETS.iter().enumerate() {
+        if index > 0 {
+            prices_query.push(", ");
+        }
+        prices_query
+            .push(format!(
+                "COUNT(*) FILTER (WHERE {} >= ",
+                EFFECTIVE_PRICE_SQL
+            ))
+            .push_bind(bucket.min);
+        if let Some(max) = bucket.max {
+            prices_query
+                .push(format!(" AND {} < ", EFFECTIVE_PRICE_SQL))
+                .push_bind(max);
+        }
+        prices_query.push(")");
+    }
+    prices_query.push(" FROM products");
+    push_product_filters(&mut prices_query, params, Some(Facet::Price));
+    let prices_row = prices_query.build().fetch_one(pool).await?;
+    let price_buckets = (0..PRICE_BUCKETS.len())
+        .map(|index| prices_row.try_get::<i64, _>(index))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(FacetCounts {
+        categories,
+        conditions,
+        sizes,
+        price_buckets,
+    })
+}
+
+/// Zwraca flagi ostrzegawcze pasujące do danych klienta z zamówienia.
+///
+/// Porównanie odbywa się na wartościach znormalizowanych:
+/// 1. E-mail (gościa lub zalogowanego użytkownika) i telefon muszą być identyczne.
+/// 2. Adres flagi musi zawierać się w adresie dostawy (ulica, kod, miasto) jako całe słowa.
+pub async fn find_customer_flags_for_order(
+    pool: &PgPool,
+    order: &Order,
+) -> Result<Vec<CustomerFlag>, AppError> {
+    let customer_email: Option<String> = match (&order.guest_email, order.user_id) {
+        (Some(email), _) => Some(email.clone()),
+        (None, Some(user_id)) => {
+            sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await?
+        }
+        (None, None) => None,
+    };
+
+    let email = customer_email.map(|e| CustomerFlagType::Email.normalize(&e));
+    let phone = CustomerFlagType::Phone.normalize(&order.shipping_phone);
+    // Spacje na brzegach, aby "kwiatowa 5" nie pasowało do "kwiatowa 52"
+    let address = format!(
+        " {} ",
+        CustomerFlagType::Address.normalize(&format!(
+            "{} {} {} {}",
+            order.shipping_address_line1,
+            order.shipping_address_line2.as_deref().unwrap_or(""),
+            order.shipping_postal_code,
+            order.shipping_city
+        ))
+    );
+
+    let flags = sqlx::query_as::<_, CustomerFlag>("SELECT * FROM customer_flags")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(flags
+        .into_iter()
+        .filter(|flag| match flag.flag_type {
+            CustomerFlagType::Email => email.as_deref() == Some(flag.value.as_str()),
+            CustomerFlagType::Phone => !phone.is_empty() && phone == flag.value,
+            CustomerFlagType::Address => {
+                !flag.value.is_empty() && address.contains(&format!(" {} ", flag.value))
+            }
+        })
+        .collect())
+}
+
+/// Zapisuje wpis w historii statusów zamówienia. Wywoływane w tej samej transakcji,
+/// w której zmienia się status, żeby historia nie rozjechała się z `orders.status`.
+pub async fn record_order_status_change(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+    from_status: Option<OrderStatus>,
+    to_status: OrderStatus,
+    changed_by: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+            INSERT INTO order_status_history (order_id, from_status, to_status, changed_by, note)
+            VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(order_id)
+    .bind(from_status)
+    .bind(to_status)
+    .bind(changed_by)
+    .bind(note)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Zmienia status zamówienia zgodnie z dozwolonymi przejściami (`OrderStatus::allowed_transitions`)
+/// i zapisuje zmianę w historii.
+///
+/// 1. Blokuje wiersz zamówienia (`FOR UPDATE`), aby równoległe zmiany się nie nadpisały.
+/// 2. Ustawienie tego samego statusu nic nie robi i nie trafia do historii.
+/// 3. Niedozwolone przejście (np. `Cancelled` -> `Shipped`) kończy się `AppError::Conflict`.
+///
+/// Zwraca zamówienie oraz informację, czy status faktycznie się zmienił.
+pub async fn transition_order_status(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+    new_status: OrderStatus,
+    changed_by: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<(Order, bool), AppError> {
+    let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if order.status == new_status {
+        return Ok((order, false));
+    }
+    if !order.status.can_transition_to(&new_status) {
+        return Err(AppError::Conflict(format!(
+            "Nie można zmienić statusu zamówienia z '{}' na '{}'.",
+            order.status, new_status
+        )));
+    }
+
+    let updated_order = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET status = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2 RETURNING *",
+    )
+    .bind(new_status.clone())
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let cancelled = new_status == OrderStatus::Cancelled;
+    record_order_status_change(
+        conn,
+        order_id,
+        Some(order.status),
+        new_status,
+        changed_by,
+        note,
+    )
+    .await?;
+
+    // Kwota zapłacona kartą podarunkową wraca na jej saldo
+    if cancelled {
+        restore_gift_card_redemptions(conn, order_id).await?;
+    }
+    // Fakturę wystawiamy dopiero po opłaceniu zamówienia, w tej samej transakcji co zmiana statusu
+    if status_requires_invoice(&updated_order.status) {
+        issue_invoice(conn, order_id).await?;
+    }
+
+    Ok((updated_order, true))
+}
+
+/// Historia statusów zamówienia, od najstarszego wpisu.
+pub async fn fetch_order_status_history(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Vec<OrderStatusHistory>, AppError> {
+    let history = sqlx::query_as::<_, OrderStatusHistory>(
+        "SELECT * FROM order_status_history WHERE order_id = $1 ORDER BY changed_at ASC",
+    )
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(history)
+}
+
+/// Lejek konwersji w przedziale `[from, to)` na podstawie tabeli `events`.
+///
+/// Klienta identyfikujemy po `user_id` albo ID koszyka gościa. Anonimowe wyświetlenia
+/// (bez koszyka i logowania) liczymy jako osobne sesje, więc liczba sesji jest górnym szacunkiem.
+pub async fn fetch_funnel_stats(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<FunnelStats, AppError> {
+    let stats = sqlx::query_as::<_, FunnelStats>(
+        r#"
+            WITH scoped AS (
+                SELECT
+                    event_type,
+                    COALESCE(user_id::text, guest_session_id::text, 'anon-' || id::text) AS visitor
+                FROM events
+                WHERE created_at >= $1 AND created_at < $2
+            )
+            SELECT
+                COUNT(DISTINCT visitor) AS sessions,
+                COUNT(DISTINCT visitor) FILTER (WHERE event_type = 'product_viewed') AS product_views,
+                COUNT(DISTINCT visitor) FILTER (WHERE event_type = 'added_to_cart') AS added_to_cart,
+                COUNT(DISTINCT visitor) FILTER (WHERE event_type = 'checkout_started') AS checkout_started,
+                COUNT(DISTINCT visitor) FILTER (WHERE event_type = 'order_placed') AS orders_placed
+            FROM scoped
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_one(pool)
+    .await?;
+    Ok(stats)
+}
+
+/// Liczba zamówień, przychód i sprzedane sztuki w przedziale `[from, to)`.
+pub async fn fetch_sales_summary(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<SalesSummary, AppError> {
+    let summary = sqlx::query_as::<_, SalesSummary>(
+        r#"
+            SELECT
+                COUNT(*) FILTER (WHERE o.status <> $3) AS orders,
+                COALESCE(SUM(o.total_price) FILTER (WHERE o.status <> $3), 0)::BIGINT AS revenue,
+                COALESCE(SUM(
+                    (SELECT COUNT(*) FROM order_items oi WHERE oi.order_id = o.id)
+                ) FILTER (WHERE o.status <> $3), 0)::BIGINT AS items_sold,
+                COUNT(*) FILTER (WHERE o.status = $3) AS cancelled_orders
+            FROM orders o
+            WHERE o.order_date >= $1 AND o.order_date < $2
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(OrderStatus::Cancelled)
+    .fetch_one(pool)
+    .await?;
+    Ok(summary)
+}
+
+/// Zamówienia i przychód w kolejnych okresach (`bucket`: "day" albo "week") w przedziale `[from, to)`.
+/// Okresy bez sprzedaży też są zwracane (z zerami), a dni liczymy w strefie czasowej sklepu.
+pub async fn fetch_sales_timeline(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    bucket: &str,
+) -> Result<Vec<SalesPeriod>, AppError> {
+    let bucket = if bucket == "week" { "week" } else { "day" };
+    let rows = sqlx::query_as::<_, SalesPeriod>(
+        r#"
+            WITH periods AS (
+                SELECT generate_series(
+                    date_trunc($4, $1 AT TIME ZONE $3),
+                    date_trunc($4, ($2 - INTERVAL '1 second') AT TIME ZONE $3),
+                    ('1 ' || $4)::interval
+                ) AS period_start
+            ),
+            sales AS (
+                SELECT
+                    date_trunc($4, order_date AT TIME ZONE $3) AS period_start,
+                    COUNT(*) AS orders,
+                    SUM(total_price) AS revenue
+                FROM orders
+                WHERE order_date >= $1 AND order_date < $2 AND status <> $5
+                GROUP BY 1
+            )
+            SELECT
+                p.period_start::date AS period_start,
+                COALESCE(s.orders, 0) AS orders,
+                COALESCE(s.revenue, 0)::BIGINT AS revenue
+            FROM periods p
+            LEFT JOIN sales s ON s.period_start = p.period_start
+            ORDER BY p.period_start
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(shop_timezone_name())
+    .bind(bucket)
+    .bind(OrderStatus::Cancelled)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Kategorie z największym przychodem w przedziale `[from, to)`.
+pub async fn fetch_top_categories(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<CategorySales>, AppError> {
+    let rows = sqlx::query_as::<_, CategorySales>(
+        r#"
+            SELECT
+                p.category,
+                COUNT(*) AS items_sold,
+                SUM(oi.price_at_purchase)::BIGINT AS revenue
+            FROM order_items oi
+            JOIN orders o ON o.id = oi.order_id
+            JOIN products p ON p.id = oi.product_id
+            WHERE o.order_date >= $1 AND o.order_date < $2 AND o.status <> $3
+            GROUP BY p.category
+            ORDER BY revenue DESC, items_sold DESC
+            LIMIT $4
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(OrderStatus::Cancelled)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Konwersja rezerwacja → sprzedaż: produkty dodane do koszyka w przedziale `[from, to)`
+/// i ile z nich trafiło później do nieanulowanego zamówienia.
+pub async fn fetch_reservation_conversion(
+    pool: &PgPool,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<ReservationConversion, AppError> {
+    let conversion = sqlx::query_as::<_, ReservationConversion>(
+        r#"
+            WITH reserved AS (
+                SELECT DISTINCT product_id
+                FROM events
+                WHERE event_type = 'added_to_cart'
+                  AND product_id IS NOT NULL
+                  AND created_at >= $1 AND created_at < $2
+            )
+            SELECT
+                COUNT(*) AS reserved_products,
+                COUNT(*) FILTER (WHERE EXISTS (
+                    SELECT 1 FROM order_items oi
+                    JOIN orders o ON o.id = oi.order_id
+                    WHERE oi.product_id = r.product_id AND o.status <> $3
+                )) AS sold_products
+            FROM reserved r
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(OrderStatus::Cancelled)
+    .fetch_one(pool)
+    .await?;
+    Ok(conversion)
+}
+
+/// Wyświetlenia, dodania do koszyka i sprzedaż w podziale na kategorie od `since`.
+/// Anulowane zamówienia nie liczą się do sprzedaży.
+pub async fn fetch_category_conversion(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+) -> Result<Vec<CategoryConversion>, AppError> {
+    let rows = sqlx::query_as::<_, CategoryConversion>(
+        r#"
+            WITH activity AS (
+                SELECT
+                    p.category,
+                    COUNT(*) FILTER (WHERE e.event_type = 'product_viewed') AS views,
+                    COUNT(*) FILTER (WHERE e.event_type = 'added_to_cart') AS adds
+                FROM events e
+                JOIN products p ON p.id = e.product_id
+                WHERE e.created_at >= $1
+                GROUP BY p.category
+            ),
+            sales AS (
+                SELECT p.category, COUNT(*) AS sold
+                FROM order_items oi
+                JOIN orders o ON o.id = oi.order_id
+                JOIN products p ON p.id = oi.product_id
+                WHERE o.order_date >= $1 AND o.status <> $2
+                GROUP BY p.category
+            )
+            SELECT
+                COALESCE(a.category, s.category) AS category,
+                COALESCE(a.views, 0) AS views,
+                COALESCE(a.adds, 0) AS adds,
+                COALESCE(s.sold, 0) AS sold
+            FROM activity a
+            FULL OUTER JOIN sales s ON s.category = a.category
+            ORDER BY views DESC, sold DESC
+        "#,
+    )
+    .bind(since)
+    .bind(OrderStatus::Cancelled)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
@@ -0,0 +1,76 @@
+// src/shutdown.rs
+
+// Łagodne wyłączanie przy deployu: po SIGTERM/SIGINT serwer przestaje przyjmować połączenia,
+// kończy obsługę trwających żądań (np. składania zamówienia w transakcji), czeka na zadania
+// w tle zlecone z `AppState::background_jobs` (e-maile, etykiety, IndexNow) i zamyka pulę bazy.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum_server::Handle;
+
+use crate::state::AppState;
+
+/// Ile czekamy na dokończenie trwających żądań, zanim połączenia zostaną zerwane
+const REQUEST_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// Ile czekamy na zadania w tle po zamknięciu serwera
+const BACKGROUND_JOBS_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Czeka na Ctrl+C albo SIGTERM (tym sygnałem zatrzymuje kontener orkiestrator)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Nie udało się nasłuchiwać na Ctrl+C: {:?}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Nie udało się nasłuchiwać na SIGTERM: {:?}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Otrzymano SIGINT, zamykanie serwera..."),
+        _ = terminate => tracing::info!("Otrzymano SIGTERM, zamykanie serwera..."),
+    }
+}
+
+/// Po sygnale zamknięcia każe serwerowi odrzucać nowe połączenia i dokończyć trwające żądania
+pub fn spawn_shutdown_listener(handle: Handle) {
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        handle.graceful_shutdown(Some(REQUEST_DRAIN_TIMEOUT));
+    });
+}
+
+/// Wywoływane po zatrzymaniu serwera: dokańcza zadania w tle i zamyka pulę połączeń
+pub async fn drain_background_jobs(app_state: &Arc<AppState>) {
+    let jobs = &app_state.background_jobs;
+    jobs.close();
+    if !jobs.is_empty() {
+        tracing::info!("Oczekiwanie na {} zadań w tle...", jobs.len());
+    }
+    if tokio::time::timeout(BACKGROUND_JOBS_TIMEOUT, jobs.wait())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            "Nie dokończono {} zadań w tle przed wyłączeniem.",
+            jobs.len()
+        );
+    }
+
+    app_state.db_pool.close().await;
+    tracing::info!("Zamknięto pulę połączeń z bazą. Serwer zatrzymany.");
+}
@@ -1,9 +1,18 @@
 // src/sitemap_generator.rs
 
+// Mapa strony jako indeks (`/sitemap.xml`) z osobnymi mapami: stron statycznych, kategorii
+// i produktów. Mapa produktów jest dzielona na części po 50 000 adresów (limit protokołu)
+// i zawiera zdjęcia produktów (`<image:image>`), żeby trafiały do Grafiki Google.
+
+use std::sync::Arc;
+
 use crate::errors::AppError;
+use crate::merchant_feed::FEED_IMAGE_TRANSFORMATION;
 use crate::models::{Category, Product, ProductGender, ProductStatus};
 use crate::state::AppState;
+use crate::views::common::transform_cloudinary_url;
 use axum::{
+    extract::{Path, State},
     http::{HeaderValue, header},
     response::{IntoResponse, Response},
 };
@@ -11,14 +20,40 @@ use chrono::Utc;
 use quick_xml::se::to_string;
 use serde::Serialize;
 use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+/// Limit adresów w jednym pliku mapy wg sitemaps.org
+pub const MAX_URLS_PER_SITEMAP: i64 = 50_000;
+const SITEMAP_XMLNS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+const IMAGE_XMLNS: &str = "http://www.google.com/schemas/sitemap-image/1.1";
+const INDEXNOW_ENDPOINT: &str = "https://api.indexnow.org/indexnow";
 
 // --- Struktury danych odzwierciedlające format sitemap.xml ---
 
+#[derive(Serialize)]
+#[serde(rename = "sitemapindex")]
+pub struct SitemapIndex {
+    #[serde(rename = "@xmlns")]
+    xmlns: &'static str,
+    #[serde(rename = "sitemap")]
+    pub sitemaps: Vec<SitemapRef>,
+}
+
+#[derive(Serialize)]
+pub struct SitemapRef {
+    #[serde(rename = "loc")]
+    pub location: String,
+    #[serde(rename = "lastmod")]
+    pub last_modified: String,
+}
+
 #[derive(Serialize)]
 #[serde(rename = "urlset")]
 pub struct UrlSet {
     #[serde(rename = "@xmlns")]
-    xmlns: String,
+    xmlns: &'static str,
+    #[serde(rename = "@xmlns:image", skip_serializing_if = "Option::is_none")]
+    xmlns_image: Option<&'static str>,
     #[serde(rename = "url")]
     pub urls: Vec<UrlEntry>,
 }
@@ -33,6 +68,14 @@ pub struct UrlEntry {
     pub change_frequency: ChangeFreq,
     #[serde(rename = "priority")]
     pub priority: f32,
+    #[serde(rename = "image:image")]
+    pub images: Vec<ImageEntry>,
+}
+
+#[derive(Serialize)]
+pub struct ImageEntry {
+    #[serde(rename = "image:loc")]
+    pub location: String,
 }
 
 #[allow(dead_code)]
@@ -48,37 +91,116 @@ pub enum ChangeFreq {
     Never,
 }
 
-// --- Główny Handler ---
+fn xml_response<T: Serialize>(document: &T) -> Result<Response, AppError> {
+    // Serializacja do XML
+    let mut xml_output = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string();
+    xml_output.push_str(&to_string(document).map_err(|_| {
+        AppError::InternalServerError("Błąd podczas generowania XML mapy strony".to_string())
+    })?);
+
+    // Zwrócenie odpowiedzi z poprawnym typem zawartości
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/xml; charset=utf-8"),
+        )],
+        xml_output,
+    )
+        .into_response())
+}
+
+async fn product_sitemap_count(app_state: &AppState) -> Result<i64, AppError> {
+    let products_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM products WHERE status = $1")
+        .bind(ProductStatus::Available)
+        .fetch_one(&app_state.db_pool)
+        .await?;
+    // Pusta mapa produktów też jest poprawna - indeks zawsze wskazuje co najmniej jedną
+    Ok(((products_count + MAX_URLS_PER_SITEMAP - 1) / MAX_URLS_PER_SITEMAP).max(1))
+}
+
+// --- Handlery ---
 
-pub async fn generate_sitemap_handler(app_state: &AppState) -> Result<Response, AppError> {
-    let base_url = "https://messvintage.com"; // WAŻNE: Użyj swojego prawdziwego adresu URL
-    let mut urls = Vec::new();
+/// GET /sitemap.xml - indeks map strony
+pub async fn sitemap_index_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let base_url = &app_state.public_base_url;
+    let now = Utc::now().to_rfc3339();
+    let last_product_change: Option<chrono::DateTime<Utc>> =
+        sqlx::query_scalar("SELECT MAX(updated_at) FROM products WHERE status = $1")
+            .bind(ProductStatus::Available)
+            .fetch_one(&app_state.db_pool)
+            .await?;
+    let products_lastmod = last_product_change.map_or_else(|| now.clone(), |dt| dt.to_rfc3339());
+
+    let mut sitemaps = vec![
+        SitemapRef {
+            location: format!("{}/sitemaps/static.xml", base_url),
+            last_modified: now.clone(),
+        },
+        SitemapRef {
+            location: format!("{}/sitemaps/kategorie.xml", base_url),
+            last_modified: now,
+        },
+    ];
+    for page in 1..=product_sitemap_count(&app_state).await? {
+        sitemaps.push(SitemapRef {
+            location: format!("{}/sitemaps/produkty/{}.xml", base_url, page),
+            last_modified: products_lastmod.clone(),
+        });
+    }
+
+    xml_response(&SitemapIndex {
+        xmlns: SITEMAP_XMLNS,
+        sitemaps,
+    })
+}
 
-    // 1. Strony Statyczne (wysoki priorytet, rzadkie zmiany)
+/// GET /sitemaps/static.xml - strony statyczne (wysoki priorytet, rzadkie zmiany)
+pub async fn static_sitemap_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let base_url = &app_state.public_base_url;
     let static_pages = vec![
         ("", 1.0, ChangeFreq::Weekly), // Strona główna
         ("/dla-niej", 0.9, ChangeFreq::Daily),
         ("/dla-niego", 0.9, ChangeFreq::Daily),
         ("/nowosci", 0.9, ChangeFreq::Daily),
         ("/okazje", 0.9, ChangeFreq::Daily),
+        ("/archiwum", 0.4, ChangeFreq::Weekly),
         ("/o-nas", 0.5, ChangeFreq::Monthly),
         ("/kontakt", 0.5, ChangeFreq::Monthly),
         ("/regulamin", 0.3, ChangeFreq::Yearly),
         ("/polityka-prywatnosci", 0.3, ChangeFreq::Yearly),
         ("/faq", 0.5, ChangeFreq::Monthly),
         ("/wysylka-i-zwroty", 0.5, ChangeFreq::Monthly),
+        ("/karta-podarunkowa", 0.6, ChangeFreq::Monthly),
     ];
 
-    for (loc, prio, freq) in static_pages {
-        urls.push(UrlEntry {
+    let urls = static_pages
+        .into_iter()
+        .map(|(loc, prio, freq)| UrlEntry {
             location: format!("{}{}", base_url, loc),
             last_modified: Utc::now().to_rfc3339(), // Można by pobrać datę modyfikacji pliku
             change_frequency: freq,
             priority: prio,
-        });
-    }
+            images: Vec::new(),
+        })
+        .collect();
+
+    xml_response(&UrlSet {
+        xmlns: SITEMAP_XMLNS,
+        xmlns_image: None,
+        urls,
+    })
+}
 
-    // 2. Strony Kategorii (dla obu płci)
+/// GET /sitemaps/kategorie.xml - strony kategorii dla obu płci
+pub async fn category_sitemap_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let base_url = &app_state.public_base_url;
+    let mut urls = Vec::new();
     for gender in [ProductGender::Damskie, ProductGender::Meskie].iter() {
         let gender_slug = if *gender == ProductGender::Damskie {
             "dla-niej"
@@ -91,43 +213,162 @@ pub async fn generate_sitemap_handler(app_state: &AppState) -> Result<Response,
                 last_modified: Utc::now().to_rfc3339(),
                 change_frequency: ChangeFreq::Weekly,
                 priority: 0.8,
+                images: Vec::new(),
             });
         }
     }
 
-    // 3. Strony Produktów (dynamicznie z bazy danych)
-    let products = sqlx::query_as::<_, Product>("SELECT * FROM products WHERE status = $1")
-        .bind(ProductStatus::Available) // Tylko dostępne produkty
-        .fetch_all(&app_state.db_pool)
-        .await?;
+    xml_response(&UrlSet {
+        xmlns: SITEMAP_XMLNS,
+        xmlns_image: None,
+        urls,
+    })
+}
+
+/// GET /sitemaps/produkty/{page}.xml - dostępne produkty ze zdjęciami, po 50 000 na plik
+pub async fn product_sitemap_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(file_name): Path<String>,
+) -> Result<Response, AppError> {
+    let page = file_name
+        .strip_suffix(".xml")
+        .and_then(|page| page.parse::<i64>().ok())
+        .filter(|page| *page >= 1)
+        .ok_or(AppError::NotFound)?;
+    if page > product_sitemap_count(&app_state).await? {
+        return Err(AppError::NotFound);
+    }
 
-    for product in products {
-        urls.push(UrlEntry {
-            location: format!("{}/produkty/{}", base_url, product.id),
+    let base_url = &app_state.public_base_url;
+    let products = sqlx::query_as::<_, Product>(
+        "SELECT * FROM products WHERE status = $1 ORDER BY created_at, id LIMIT $2 OFFSET $3",
+    )
+    .bind(ProductStatus::Available) // Tylko dostępne produkty
+    .bind(MAX_URLS_PER_SITEMAP)
+    .bind((page - 1) * MAX_URLS_PER_SITEMAP)
+    .fetch_all(&app_state.db_pool)
+    .await?;
+
+    let urls = products
+        .iter()
+        .map(|product| UrlEntry {
+            location: format!("{}{}", base_url, product.public_path()),
             last_modified: product.updated_at.to_rfc3339(), // Używamy daty aktualizacji produktu
             change_frequency: ChangeFreq::Monthly, // Produkty się nie zmieniają, ale lista tak
             priority: 0.7,
-        });
-    }
+            images: product
+                .images
+                .iter()
+                .map(|image| ImageEntry {
+                    location: transform_cloudinary_url(image, FEED_IMAGE_TRANSFORMATION),
+                })
+                .collect(),
+        })
+        .collect();
 
-    let url_set = UrlSet {
-        xmlns: "http://www.sitemaps.org/schemas/sitemap/0.9".to_string(),
+    xml_response(&UrlSet {
+        xmlns: SITEMAP_XMLNS,
+        xmlns_image: Some(IMAGE_XMLNS),
         urls,
-    };
+    })
+}
 
-    // Serializacja do XML
-    let mut xml_output = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>".to_string();
-    xml_output.push_str(&to_string(&url_set).map_err(|_| {
-        AppError::InternalServerError("Błąd podczas generowania XML mapy strony".to_string())
-    })?);
+/// GET /indexnow-key.txt - plik z kluczem IndexNow, którym wyszukiwarki weryfikują zgłoszenia
+/// Ścieżki, których roboty nie powinny odwiedzać: panel, fragmenty HTMX i API
+const ROBOTS_DISALLOWED_PATHS: [&str; 3] = ["/admin", "/htmx/", "/api/"];
 
-    // Zwrócenie odpowiedzi z poprawnym typem zawartości
+/// Treść robots.txt; na stagingu blokuje całą stronę, żeby nie trafiła do wyników wyszukiwania
+pub fn robots_txt(base_url: &str, staging: bool) -> String {
+    if staging {
+        return "User-agent: *\nDisallow: /\n".to_string();
+    }
+    let mut robots = String::from("User-agent: *\n");
+    for path in ROBOTS_DISALLOWED_PATHS {
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+    robots.push_str(&format!("\nSitemap: {}/sitemap.xml\n", base_url));
+    robots
+}
+
+/// GET /robots.txt
+pub async fn robots_txt_handler(State(app_state): State<Arc<AppState>>) -> Response {
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        )],
+        robots_txt(&app_state.public_base_url, app_state.staging),
+    )
+        .into_response()
+}
+
+pub async fn indexnow_key_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let key = app_state.indexnow_key.clone().ok_or(AppError::NotFound)?;
     Ok((
         [(
             header::CONTENT_TYPE,
-            HeaderValue::from_static("application/xml; charset=utf-8"),
+            HeaderValue::from_static("text/plain; charset=utf-8"),
         )],
-        xml_output,
+        key,
     )
         .into_response())
 }
+
+/// Zgłasza w tle zmienione strony produktów (nowy produkt, sprzedaż) do IndexNow (Bing, Yandex,
+/// Seznam). Google nie obsługuje już pingowania map strony - czyta indeks z Search Console.
+/// Bez `INDEXNOW_KEY` nic nie robi; błędy są tylko logowane.
+pub fn notify_search_engines(app_state: Arc<AppState>, product_ids: Vec<Uuid>) {
+    let Some(key) = app_state.indexnow_key.clone() else {
+        return;
+    };
+    if product_ids.is_empty() {
+        return;
+    }
+
+    let background_jobs = app_state.background_jobs.clone();
+    background_jobs.spawn(async move {
+        let base_url = &app_state.public_base_url;
+        let products =
+            match sqlx::query_as::<_, Product>("SELECT * FROM products WHERE id = ANY($1)")
+                .bind(&product_ids)
+                .fetch_all(&app_state.db_pool)
+                .await
+            {
+                Ok(products) => products,
+                Err(e) => {
+                    tracing::warn!("[IndexNow] Nie udało się pobrać produktów: {:?}", e);
+                    return;
+                }
+            };
+        let Some(host) = reqwest::Url::parse(base_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        else {
+            tracing::warn!("[IndexNow] Nieprawidłowy adres sklepu: {}", base_url);
+            return;
+        };
+        let url_list: Vec<String> = products
+            .iter()
+            .map(|product| format!("{}{}", base_url, product.public_path()))
+            .collect();
+
+        let body = serde_json::json!({
+            "host": host,
+            "key": key,
+            "keyLocation": format!("{}/indexnow-key.txt", base_url),
+            "urlList": url_list,
+        });
+        match reqwest::Client::new()
+            .post(INDEXNOW_ENDPOINT)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => tracing::info!("[IndexNow] Zgłoszono {} adresów.", url_list.len()),
+            Err(e) => tracing::warn!("[IndexNow] Zgłoszenie nie powiodło się: {}", e),
+        }
+    });
+}
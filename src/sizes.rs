@@ -0,0 +1,87 @@
+// src/sizes.rs
+
+// Rozmiary z metek vintage zależą od kraju i dekady: francuska sukienka "38" to dzisiejsze "M",
+// a brytyjska "14" z lat 70. jest mniejsza niż dzisiejsza. Tabela `size_mappings` przelicza
+// rozmiar z metki na współczesny, a filtr rozmiaru w sklepie dopasowuje oba.
+
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{Category, ProductGender, SizeMapping};
+
+/// Rozmiarówki do wyboru w panelu
+pub const SIZE_SYSTEMS: &[&str] = &["EU", "FR", "IT", "UK", "US", "vintage"];
+
+/// Współczesny rozmiar produktu: najbardziej szczegółowe pasujące mapowanie (kategoria i płeć
+/// przed ogólnymi), a bez mapowania - rozmiar z metki. Wymaga `FROM products` bez aliasu.
+pub const NORMALIZED_SIZE_SQL: &str = r#"COALESCE((
+    SELECT m.modern_size FROM size_mappings m
+    WHERE lower(m.label_size) = lower(btrim(products.size))
+      AND (m.category IS NULL OR m.category = products.category)
+      AND (m.gender IS NULL OR m.gender = products.gender)
+    ORDER BY m.category IS NULL, m.gender IS NULL
+    LIMIT 1
+), products.size)"#;
+
+/// Warunek filtra rozmiaru: pasuje rozmiar z metki albo rozmiar po przeliczeniu,
+/// bez rozróżniania wielkości liter.
+pub fn push_size_filter(builder: &mut QueryBuilder<'_, Postgres>, size: &str) {
+    builder
+        .push(" AND (lower(size) = lower(")
+        .push_bind(size.to_string())
+        .push(format!(") OR lower({}) = lower(", NORMALIZED_SIZE_SQL))
+        .push_bind(size.to_string())
+        .push("))");
+}
+
+/// Mapowania pogrupowane jak w panelu: ogólne na końcu, w ramach grupy po rozmiarówce
+pub async fn list_size_mappings(pool: &PgPool) -> Result<Vec<SizeMapping>, AppError> {
+    Ok(sqlx::query_as::<_, SizeMapping>(
+        r#"
+        SELECT * FROM size_mappings
+        ORDER BY category IS NULL, category, gender IS NULL, gender, size_system, modern_size, label_size
+        "#,
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Dodaje mapowanie albo zmienia współczesny rozmiar istniejącego
+/// (ta sama kategoria, płeć i rozmiar z metki).
+pub async fn save_size_mapping(
+    pool: &PgPool,
+    category: Option<Category>,
+    gender: Option<ProductGender>,
+    size_system: &str,
+    label_size: &str,
+    modern_size: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO size_mappings (category, gender, size_system, label_size, modern_size)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (category, gender, lower(label_size))
+        DO UPDATE SET size_system = EXCLUDED.size_system, modern_size = EXCLUDED.modern_size
+        "#,
+    )
+    .bind(category)
+    .bind(gender)
+    .bind(size_system)
+    .bind(label_size)
+    .bind(modern_size)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_size_mapping(pool: &PgPool, mapping_id: Uuid) -> Result<(), AppError> {
+    let result = sqlx::query("DELETE FROM size_mappings WHERE id = $1")
+        .bind(mapping_id)
+        .execute(pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+// src/sla.rs
+
+// Ustawowe terminy w obsłudze klienta: 14 dni na odpowiedź na reklamację i 14 dni na zwrot
+// pieniędzy po odstąpieniu od umowy. Kolejki w panelu pokazują odliczanie, a zadanie w tle
+// wysyła do centrum powiadomień coraz pilniejsze przypomnienia (każdy etap raz, zob. `sla_reminders`).
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::complaints::{complaint_reference, response_deadline, unresolved_complaints};
+use crate::date_format::format_date;
+use crate::errors::AppError;
+use crate::models::AdminNotificationLevel;
+use crate::notifications::notify_admin;
+use crate::returns::{pending_refund_returns, return_reference};
+use crate::state::AppState;
+
+/// Od ilu dni przed terminem zaczynamy przypominać
+const DUE_SOON_DAYS: i64 = 3;
+
+/// Etap terminu - od niego zależy kolor odznaki i pilność przypomnienia
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SlaStage {
+    OnTrack,
+    DueSoon,
+    DueToday,
+    Overdue,
+}
+
+impl SlaStage {
+    pub fn for_deadline(deadline: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let remaining = deadline - now;
+        if remaining.num_seconds() <= 0 {
+            SlaStage::Overdue
+        } else if remaining.num_days() < 1 {
+            SlaStage::DueToday
+        } else if remaining.num_days() <= DUE_SOON_DAYS {
+            SlaStage::DueSoon
+        } else {
+            SlaStage::OnTrack
+        }
+    }
+
+    /// Klucz etapu w `sla_reminders`; `None` dla etapu bez przypomnienia
+    fn reminder_key(&self) -> Option<&'static str> {
+        match self {
+            SlaStage::OnTrack => None,
+            SlaStage::DueSoon => Some("due_soon"),
+            SlaStage::DueToday => Some("due_today"),
+            SlaStage::Overdue => Some("overdue"),
+        }
+    }
+
+    fn notification_level(&self) -> AdminNotificationLevel {
+        match self {
+            SlaStage::OnTrack | SlaStage::DueSoon => AdminNotificationLevel::Info,
+            SlaStage::DueToday => AdminNotificationLevel::Warning,
+            SlaStage::Overdue => AdminNotificationLevel::Error,
+        }
+    }
+}
+
+/// Zadanie objęte terminem: reklamacja albo zwrot
+struct SlaSubject {
+    kind: &'static str,
+    id: Uuid,
+    deadline: DateTime<Utc>,
+    description: String,
+}
+
+impl SlaSubject {
+    fn reminder_title(&self, stage: SlaStage) -> String {
+        match stage {
+            SlaStage::Overdue => format!("Minął termin: {}", self.description),
+            SlaStage::DueToday => format!("Termin mija dzisiaj: {}", self.description),
+            _ => format!("Zbliża się termin: {}", self.description),
+        }
+    }
+}
+
+/// Zapisuje etap przypomnienia; `true`, gdy ten etap nie był jeszcze wysłany
+async fn claim_reminder(
+    pool: &PgPool,
+    subject: &SlaSubject,
+    stage_key: &str,
+) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO sla_reminders (subject_kind, subject_id, stage)
+        VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(subject.kind)
+    .bind(subject.id)
+    .bind(stage_key)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Sprawdza terminy otwartych reklamacji i zwrotów; zwraca liczbę wysłanych przypomnień
+pub async fn send_sla_reminders(pool: &PgPool) -> Result<usize, AppError> {
+    let mut subjects: Vec<SlaSubject> = unresolved_complaints(pool)
+        .await?
+        .iter()
+        .map(|complaint| SlaSubject {
+            kind: "complaint",
+            id: complaint.id,
+            deadline: response_deadline(complaint),
+            description: format!(
+                "odpowiedź na reklamację {} ({})",
+                complaint_reference(complaint),
+                complaint.product_name
+            ),
+        })
+        .collect();
+    subjects.extend(
+        pending_refund_returns(pool)
+            .await?
+            .iter()
+            .map(|details| SlaSubject {
+                kind: "return",
+                id: details.request.id,
+                deadline: details.refund_deadline(),
+                description: format!("zwrot pieniędzy za {}", return_reference(&details.request)),
+            }),
+    );
+
+    let now = Utc::now();
+    let mut sent = 0;
+    for subject in &subjects {
+        let stage = SlaStage::for_deadline(subject.deadline, now);
+        let Some(stage_key) = stage.reminder_key() else {
+            continue;
+        };
+        if !claim_reminder(pool, subject, stage_key).await? {
+            continue;
+        }
+        notify_admin(
+            pool,
+            stage.notification_level(),
+            &subject.reminder_title(stage),
+            &format!(
+                "Ustawowy termin: {}. Sprawdź kolejkę w panelu.",
+                format_date(&subject.deadline)
+            ),
+        )
+        .await;
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+/// Zadanie cykliczne (co godzinę): przypomnienia o terminach reklamacji i zwrotów
+pub async fn sla_reminders_job(state: &AppState) -> Result<String, AppError> {
+    Ok(match send_sla_reminders(&state.db_pool).await? {
+        0 => String::new(),
+        sent => format!("Wysłano {} przypomnień o terminach.", sent),
+    })
+}
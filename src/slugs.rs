@@ -0,0 +1,78 @@
+// src/slugs.rs
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+/// Maksymalna długość sluga bez końcówki rozróżniającej
+const MAX_SLUG_LEN: usize = 80;
+
+/// Zamienia nazwę na fragment adresu: małe litery bez polskich znaków, słowa rozdzielone myślnikiem.
+/// Ta sama reguła co w migracji `add_products_slug` - oba miejsca trzeba zmieniać razem.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let c = match c {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ó' => 'o',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            other => other,
+        };
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let mut slug = slug.trim_matches('-').to_string();
+    if slug.len() > MAX_SLUG_LEN {
+        slug.truncate(MAX_SLUG_LEN);
+        slug = slug.trim_end_matches('-').to_string();
+    }
+    if slug.is_empty() {
+        "produkt".to_string()
+    } else {
+        slug
+    }
+}
+
+/// Czy slug należy już do innego produktu niż `product_id` (przy ręcznej zmianie w panelu)
+pub async fn is_slug_taken(
+    conn: &mut PgConnection,
+    slug: &str,
+    product_id: Uuid,
+) -> Result<bool, AppError> {
+    let taken =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products WHERE slug = $1 AND id <> $2)")
+            .bind(slug)
+            .bind(product_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    Ok(taken)
+}
+
+/// Slug dla nowego produktu. Jeśli nazwa jest już zajęta, dokłada początek UUID produktu.
+pub async fn unique_product_slug(
+    conn: &mut PgConnection,
+    name: &str,
+    product_id: Uuid,
+) -> Result<String, AppError> {
+    let base = slugify(name);
+    let taken: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM products WHERE slug = $1)")
+        .bind(&base)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    if taken {
+        Ok(format!("{}-{}", base, &product_id.to_string()[..8]))
+    } else {
+        Ok(base)
+    }
+}
@@ -2,20 +2,66 @@
 
 use moka::future::Cache;
 use sqlx::PgPool;
+use std::net::IpAddr;
 use std::sync::Arc;
+use tokio_util::task::TaskTracker;
 use uuid::Uuid;
 
+use crate::cache_stats::CacheStats;
+use crate::disposable_email::DisposableEmailBlocklist;
+use crate::load_shed::LoadShedder;
+use crate::metrics::Metrics;
 use crate::models::{Category, Product, ProductGender};
+use crate::rate_limit::RateLimitBuckets;
+use crate::vat::VatConfig;
 
 pub struct AppState {
     pub db_pool: PgPool,
     pub jwt_secret: String,
     pub jwt_expiration_hours: i64,
+    /// Publiczny adres sklepu (bez końcowego `/`) - do linków w e-mailach
+    pub public_base_url: String,
     pub cloudinary_config: CloudinaryConfig,
     pub resend_api_key: String,
+    /// Adres nadawcy e-maili sklepu (`ADMIN_EMAIL`)
+    pub admin_email: String,
     pub product_cache: Arc<Cache<Uuid, Product>>,
     pub static_html_cache: Arc<Cache<String, String>>,
+    /// Pierwsze strony listingów (strona główna, płeć, płeć + kategoria) dla gości z pustym
+    /// koszykiem; klucz "listing:...", rozgrzewane przy starcie
+    pub listing_fragment_cache: Arc<Cache<String, String>>,
     pub category_list_cache: Arc<Cache<ProductGender, Vec<Category>>>,
+    /// Trafienia i czasy wstawienia kluczy powyższych cache'y - do panelu "Cache" w adminie
+    pub cache_stats: Arc<CacheStats>,
+    /// Kubełki żetonów limitu żądań, klucz "polityka:ip"
+    pub rate_limit_buckets: Arc<RateLimitBuckets>,
+    /// Adresy naszych proxy (`TRUSTED_PROXIES`) - tylko od nich przyjmujemy `X-Forwarded-For`
+    pub trusted_proxies: Vec<IpAddr>,
+    pub disposable_email_blocklist: Arc<DisposableEmailBlocklist>,
+    pub description_assistant: Option<DescriptionAssistantConfig>,
+    pub przelewy24_config: Option<Przelewy24Config>,
+    /// Logowanie przez Google - bez konfiguracji przycisk nie jest pokazywany
+    pub google_oauth: Option<GoogleOAuthConfig>,
+    pub inpost_config: InpostConfig,
+    pub payment_details: PaymentDetailsConfig,
+    pub invoice_config: InvoiceConfig,
+    pub backup_config: Option<BackupConfig>,
+    pub retention_config: RetentionConfig,
+    pub order_number_config: OrderNumberConfig,
+    /// Procedura i stawka VAT dla nowych zamówień - zob. `vat`
+    pub vat_config: VatConfig,
+    /// Klucz IndexNow - bez niego nowe i sprzedane produkty nie są zgłaszane wyszukiwarkom
+    pub indexnow_key: Option<String>,
+    /// Środowisko testowe (`STAGING=true`) - robots.txt blokuje całą stronę przed indeksowaniem
+    pub staging: bool,
+    /// Liczniki dla `/internal/metrics`
+    pub metrics: Arc<Metrics>,
+    /// Token Bearer do `/internal/metrics` - bez niego metryki nie są wystawiane
+    pub metrics_token: Option<String>,
+    /// Limity jednoczesnych żądań per klasa ruchu (strony, zamówienia, panel)
+    pub load_shedder: Arc<LoadShedder>,
+    /// Jednorazowe zadania w tle (e-maile, etykiety), na które czekamy przy wyłączaniu serwera
+    pub background_jobs: TaskTracker,
 }
 
 #[derive(Clone)]
@@ -23,4 +69,120 @@ pub struct CloudinaryConfig {
     pub cloud_name: String,
     pub api_key: String,
     pub api_secret: String,
+    /// Dodatek Cloudinary do automatycznego tagowania zdjęć (np. `google_tagging`).
+    /// `None` wyłącza podpowiedzi kategorii/koloru/materiału w formularzu produktu.
+    pub tagging_addon: Option<String>,
+}
+
+/// Konfiguracja endpointu LLM (API zgodne z OpenAI chat completions) dla asystenta opisów.
+#[derive(Clone)]
+pub struct DescriptionAssistantConfig {
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    pub model: String,
+}
+
+/// Dane dostępowe do Przelewy24. `base_url` to sandbox albo produkcja,
+/// `public_base_url` to adres sklepu, na który bramka odsyła klienta i powiadomienia.
+#[derive(Clone)]
+pub struct Przelewy24Config {
+    pub merchant_id: i64,
+    pub pos_id: i64,
+    pub crc: String,
+    pub api_key: String,
+    pub base_url: String,
+    pub public_base_url: String,
+}
+
+/// Aplikacja OAuth w Google Cloud Console. `redirect_url` musi być dokładnie tym adresem,
+/// który wpisano tam jako "Authorized redirect URI".
+#[derive(Clone)]
+pub struct GoogleOAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+}
+
+/// Konfiguracja InPost ShipX. Wyszukiwanie Paczkomatów działa bez tokenu,
+/// tworzenie przesyłek i etykiet wymaga `api_token` i `organization_id`.
+#[derive(Clone)]
+pub struct InpostConfig {
+    pub api_base_url: String,
+    pub api_token: Option<String>,
+    pub organization_id: Option<i64>,
+    /// Etykiety zwrotne dla klientów - bez tej konfiguracji opcja nie jest pokazywana
+    pub return_label: Option<InpostReturnLabelConfig>,
+}
+
+/// Odbiorca zwrotów (sklep) i cena etykiety zwrotnej potrącana klientowi ze zwrotu
+#[derive(Clone)]
+pub struct InpostReturnLabelConfig {
+    /// Paczkomat sklepu, do którego trafiają zwroty
+    pub target_point: String,
+    /// W groszach - zwykle stawka umowna sklepu, niższa niż cennik dla klientów
+    pub price: i64,
+    pub receiver_name: String,
+    pub receiver_email: String,
+    pub receiver_phone: String,
+}
+
+/// Dane do płatności poza bramką (przelew tradycyjny, BLIK na telefon) - na stronie
+/// podziękowania i w e-mailu z potwierdzeniem zamówienia.
+#[derive(Clone)]
+pub struct PaymentDetailsConfig {
+    pub account_holder: String,
+    pub account_number: String,
+    pub blik_phone: String,
+}
+
+/// Dane sprzedawcy na fakturach i program konwertujący HTML faktury do PDF.
+#[derive(Clone)]
+pub struct InvoiceConfig {
+    pub seller_name: String,
+    /// Adres w jednej linii, np. "ul. Długa 5, 31-147 Kraków"
+    pub seller_address: String,
+    pub seller_tax_id: Option<String>,
+    /// Ścieżka do `wkhtmltopdf` (czyta HTML ze stdin, zapisuje PDF na stdout)
+    pub pdf_command: String,
+}
+
+/// Magazyn zgodny z S3 (AWS S3, Backblaze B2, MinIO). `endpoint` to adres bez nazwy bucketu,
+/// np. `https://s3.eu-central-003.backblazeb2.com`.
+#[derive(Clone)]
+pub struct ObjectStorageConfig {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+/// Konfiguracja codziennej kopii zapasowej bazy. Bez niej zadanie kopii nie jest uruchamiane.
+#[derive(Clone)]
+pub struct BackupConfig {
+    pub database_url: String,
+    pub storage: ObjectStorageConfig,
+    /// Hasło szyfrowania zrzutu (openssl AES-256, klucz z PBKDF2)
+    pub encryption_passphrase: String,
+    /// Prefiks kluczy w buckecie, np. `backups/`
+    pub prefix: String,
+    pub retention_days: i64,
+}
+
+/// Format czytelnych numerów zamówień - zob. `order_numbers`
+#[derive(Clone)]
+pub struct OrderNumberConfig {
+    /// Szablon z {year} i {seq}, np. "MESS-{year}-{seq}"
+    pub format: String,
+    /// Liczba cyfr numeru kolejnego (uzupełniana zerami)
+    pub digits: usize,
+}
+
+/// Okresy przechowywania danych z polityki prywatności, egzekwowane przez zadanie `retention`.
+#[derive(Clone)]
+pub struct RetentionConfig {
+    /// Po ilu dniach od złożenia zamknięte zamówienie gościa traci dane osobowe
+    pub guest_order_pii_days: i64,
+    /// Po ilu dniach bez zmian usuwany jest koszyk gościa
+    pub guest_cart_days: i64,
 }
@@ -0,0 +1,149 @@
+// src/thank_you_cards.rs
+
+// Kartka z podziękowaniem do wydrukowania i włożenia do paczki: imię klienta, osobisty
+// jednorazowy kod na kolejne zakupy i wskazówki pielęgnacji (`care_instructions`) dobrane do
+// materiałów kupionych rzeczy. Kod jest wydawany przy pierwszym otwarciu kartki i potem się nie zmienia.
+
+use chrono::{Duration, Utc};
+use maud::{Markup, PreEscaped, html};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::coupon_campaigns::issue_campaign_code;
+use crate::date_format::format_date;
+use crate::errors::AppError;
+use crate::models::{CareInstruction, Coupon, CouponDiscountType, OrderDetailsResponse};
+use crate::views::common::format_price_maud;
+
+/// Kampania systemowa, z której pochodzą kody kartek (zob. migrację `thank_you_cards`)
+const THANK_YOU_CAMPAIGN_KEY: &str = "thank_you_cards";
+const CODE_VALID_DAYS: i64 = 90;
+
+/// Gdy żaden materiał nie ma wskazówki w tabeli `care_instructions`
+const DEFAULT_CARE_TIP: &str =
+    "Rzeczy vintage pierz delikatnie i w niskiej temperaturze - posłużą kolejne lata.";
+
+/// Kod z kartki zamówienia; przy pierwszym wywołaniu wydaje nowy kod z kampanii kartek.
+pub async fn get_or_issue_thank_you_code(
+    pool: &PgPool,
+    order_id: Uuid,
+) -> Result<Coupon, AppError> {
+    let mut tx = pool.begin().await?;
+    // Blokada zamówienia - dwa równoległe wydruki nie wydadzą dwóch kodów
+    sqlx::query("SELECT id FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let existing = sqlx::query_as::<_, Coupon>(
+        r#"
+        SELECT c.* FROM thank_you_cards t
+        JOIN coupons c ON c.id = t.coupon_id
+        WHERE t.order_id = $1
+        "#,
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(coupon) = existing {
+        return Ok(coupon);
+    }
+
+    let campaign_id: Uuid =
+        sqlx::query_scalar("SELECT id FROM coupon_campaigns WHERE system_key = $1")
+            .bind(THANK_YOU_CAMPAIGN_KEY)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| {
+                AppError::InternalServerError("Brak kampanii kodów dla kartek.".to_string())
+            })?;
+    let coupon = issue_campaign_code(
+        &mut tx,
+        campaign_id,
+        Some(Utc::now() + Duration::days(CODE_VALID_DAYS)),
+    )
+    .await?;
+    sqlx::query("INSERT INTO thank_you_cards (order_id, coupon_id) VALUES ($1, $2)")
+        .bind(order_id)
+        .bind(coupon.id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Wydano kod '{}' na kartkę z podziękowaniem do zamówienia {}",
+        coupon.code,
+        order_id
+    );
+    Ok(coupon)
+}
+
+/// Kartka w formacie A6 gotowa do wydruku z przeglądarki
+pub fn render_thank_you_card(
+    order_details: &OrderDetailsResponse,
+    coupon: &Coupon,
+    care_instructions: &[CareInstruction],
+    shop_url: &str,
+    csp_nonce: &str,
+) -> Markup {
+    let order = &order_details.order;
+    let discount = match coupon.discount_type {
+        CouponDiscountType::Percentage => format!("-{}%", coupon.value),
+        CouponDiscountType::Fixed => format!("-{}", format_price_maud(coupon.value)),
+    };
+
+    html! {
+        (PreEscaped("<!DOCTYPE html>"))
+        html lang="pl" {
+            head {
+                meta charset="UTF-8";
+                title { "Kartka z podziękowaniem - " (order.payment_reference()) }
+                style {
+                    (PreEscaped(r#"
+                        @page { size: A6; margin: 8mm; }
+                        body { font-family: Georgia, "DejaVu Serif", serif; color: #222; margin: 0; }
+                        .card { max-width: 105mm; margin: 0 auto; text-align: center; }
+                        h1 { font-size: 20px; margin: 4px 0 8px 0; }
+                        p { font-size: 12px; line-height: 1.45; margin: 0 0 8px 0; }
+                        .code { border: 1px dashed #db2777; padding: 8px; margin: 10px 0; }
+                        .code strong { display: block; font-family: "DejaVu Sans Mono", monospace; font-size: 18px; letter-spacing: 2px; }
+                        .care { text-align: left; font-size: 11px; margin: 10px 0 0 0; padding-left: 16px; }
+                        .care li { margin-bottom: 4px; }
+                        .footer { font-size: 10px; color: #666; margin-top: 10px; }
+                        .print-button { margin: 12px auto; display: block; padding: 6px 14px; }
+                        @media print { .print-button { display: none; } }
+                    "#))
+                }
+            }
+            body {
+                div class="card" {
+                    h1 { "Dziękujemy, " (order.shipping_first_name) "!" }
+                    p { "Mamy nadzieję, że nowe-stare rzeczy będą Ci służyć długo. Dziękujemy, że dajesz ubraniom drugie życie." }
+                    div class="code" {
+                        p { (discount) " na kolejne zakupy z kodem:" }
+                        strong { (coupon.code) }
+                        p {
+                            @if coupon.min_order_value > 0 { "Przy zakupach od " (format_price_maud(coupon.min_order_value)) ". " }
+                            @if let Some(expires_at) = &coupon.expires_at { "Ważny do " (format_date(expires_at)) "." }
+                        }
+                    }
+                    p { strong { "Pielęgnacja" } }
+                    ul class="care" {
+                        @for instruction in care_instructions {
+                            li { (instruction.instructions) }
+                        }
+                        @if care_instructions.is_empty() {
+                            li { (DEFAULT_CARE_TIP) }
+                        }
+                    }
+                    p class="footer" { "mess - all that vintage · " (shop_url) }
+                }
+                button type="button" class="print-button" { "Drukuj" }
+                script nonce=(csp_nonce) {
+                    (PreEscaped("document.querySelector('.print-button').addEventListener('click', () => window.print());"))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,407 @@
+// src/two_factor.rs
+
+// Weryfikacja dwuetapowa (TOTP, RFC 6238) dla kont personelu. Pracownik konfiguruje ją
+// w "Moje konto" -> "Bezpieczeństwo" (sekret w aplikacji typu Google Authenticator + kody
+// zapasowe), a potem po haśle podaje jeszcze kod z aplikacji. Dopiero JWT wydany po drugim
+// kroku (`TokenClaims::two_factor`) daje dostęp do panelu - ekstraktory `TokenClaims`
+// traktują pozostałe tokeny personelu jak tokeny klienta (zob. `require_staff_two_factor`).
+
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::auth_models::{Role, TokenClaims};
+use crate::errors::AppError;
+use crate::models::User;
+
+type HmacSha1 = Hmac<Sha1>;
+type HmacSha256 = Hmac<Sha256>;
+
+const TOTP_STEP_SECS: i64 = 30;
+const TOTP_DIGITS: u32 = 6;
+/// Akceptujemy też kod z sąsiedniego okna - zegar telefonu bywa przesunięty
+const TOTP_ALLOWED_DRIFT_STEPS: i64 = 1;
+/// 160 bitów, jak zaleca RFC 4226
+const TOTP_SECRET_BYTES: usize = 20;
+/// Nazwa sklepu widoczna w aplikacji uwierzytelniającej
+const TOTP_ISSUER: &str = "mess - all that vintage";
+
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Ciasteczko łączące krok z hasłem z krokiem z kodem
+pub const CHALLENGE_COOKIE: &str = "totp_challenge";
+/// Tyle czasu jest na wpisanie kodu po podaniu hasła
+const CHALLENGE_MINUTES: i64 = 5;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Base32 bez dopełnienia - format sekretu w URI `otpauth://`
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|&letter| letter as char == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(decoded)
+}
+
+/// Kod HOTP (RFC 4226) dla danego licznika
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = HmacSha1::new_from_slice(key).expect("HMAC przyjmuje klucz dowolnej długości");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(TOTP_DIGITS)
+}
+
+/// Okno czasowe (licznik TOTP), w którym kod jest poprawny - `None` dla złego kodu
+fn matching_totp_step(secret: &str, code: &str, now: DateTime<Utc>) -> Option<i64> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != TOTP_DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let key = base32_decode(secret)?;
+    let current_step = now.timestamp() / TOTP_STEP_SECS;
+    (-TOTP_ALLOWED_DRIFT_STEPS..=TOTP_ALLOWED_DRIFT_STEPS)
+        .map(|drift| current_step + drift)
+        .find(|&step| step >= 0 && hotp(&key, step as u64) == code)
+}
+
+pub fn generate_totp_secret() -> String {
+    base32_encode(&rand::random::<[u8; TOTP_SECRET_BYTES]>())
+}
+
+/// Sekret w grupach po 4 znaki - do przepisania ręcznie
+pub fn format_totp_secret(secret: &str) -> String {
+    secret
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adres `otpauth://` - aplikacje na telefonie otwierają go bezpośrednio
+pub fn otpauth_uri(secret: &str, account_email: &str) -> String {
+    let issuer = urlencoding::encode(TOTP_ISSUER);
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        urlencoding::encode(account_email),
+        secret,
+        issuer,
+        TOTP_DIGITS,
+        TOTP_STEP_SECS
+    )
+}
+
+/// Kod zapasowy bez myślników i wielkich liter - w tej postaci liczymy skrót
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn recovery_code_hash(code: &str) -> String {
+    hex::encode(Sha256::digest(normalize_recovery_code(code).as_bytes()))
+}
+
+/// Losowy kod zapasowy w postaci "xxxxx-xxxxx"
+fn generate_recovery_code() -> String {
+    let code = hex::encode(rand::random::<[u8; 5]>());
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TwoFactorStatus {
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    pub totp_last_step: Option<i64>,
+}
+
+impl TwoFactorStatus {
+    pub fn is_enabled(&self) -> bool {
+        self.totp_enabled_at.is_some() && self.totp_secret.is_some()
+    }
+
+    /// Sekret rozpoczętej, ale jeszcze niepotwierdzonej konfiguracji
+    pub fn pending_secret(&self) -> Option<&str> {
+        if self.totp_enabled_at.is_some() {
+            None
+        } else {
+            self.totp_secret.as_deref()
+        }
+    }
+}
+
+pub async fn two_factor_status(pool: &PgPool, user_id: Uuid) -> Result<TwoFactorStatus, AppError> {
+    sqlx::query_as::<_, TwoFactorStatus>(
+        "SELECT totp_secret, totp_enabled_at, totp_last_step FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// Czy po haśle trzeba jeszcze podać kod - tylko personel z włączoną weryfikacją
+pub async fn requires_second_step(pool: &PgPool, user: &User) -> Result<bool, AppError> {
+    if !user.role.is_staff() {
+        return Ok(false);
+    }
+    Ok(two_factor_status(pool, user.id).await?.is_enabled())
+}
+
+/// Zapisuje nowy sekret (jeszcze nieaktywny) i go zwraca
+pub async fn begin_enrolment(pool: &PgPool, user_id: Uuid) -> Result<String, AppError> {
+    let secret = generate_totp_secret();
+    let result = sqlx::query(
+        "UPDATE users SET totp_secret = $2, totp_last_step = NULL WHERE id = $1 AND totp_enabled_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(&secret)
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::Conflict(
+            "Weryfikacja dwuetapowa jest już włączona.".to_string(),
+        ));
+    }
+    Ok(secret)
+}
+
+/// Kończy konfigurację pierwszym kodem z aplikacji. Zwraca kody zapasowe do pokazania
+/// (jedyny raz, gdy są widoczne) albo `None` dla złego kodu.
+pub async fn confirm_enrolment(
+    pool: &PgPool,
+    user_id: Uuid,
+    code: &str,
+) -> Result<Option<Vec<String>>, AppError> {
+    let status = two_factor_status(pool, user_id).await?;
+    let Some(secret) = status.pending_secret() else {
+        return Err(AppError::Conflict(
+            "Brak rozpoczętej konfiguracji weryfikacji dwuetapowej.".to_string(),
+        ));
+    };
+    let Some(step) = matching_totp_step(secret, code, Utc::now()) else {
+        return Ok(None);
+    };
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE users SET totp_enabled_at = NOW(), totp_last_step = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(step)
+        .execute(&mut *tx)
+        .await?;
+    let codes = replace_recovery_codes(&mut tx, user_id).await?;
+    tx.commit().await?;
+
+    tracing::info!("Użytkownik {} włączył weryfikację dwuetapową", user_id);
+    Ok(Some(codes))
+}
+
+/// Usuwa dotychczasowe kody zapasowe i tworzy nowe
+async fn replace_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Vec<String>, AppError> {
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    for code in &codes {
+        sqlx::query("INSERT INTO totp_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(recovery_code_hash(code))
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(codes)
+}
+
+pub async fn regenerate_recovery_codes(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<String>, AppError> {
+    let mut tx = pool.begin().await?;
+    let codes = replace_recovery_codes(&mut tx, user_id).await?;
+    tx.commit().await?;
+    tracing::info!("Użytkownik {} wygenerował nowe kody zapasowe", user_id);
+    Ok(codes)
+}
+
+pub async fn remaining_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM totp_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Sprawdza kod z aplikacji albo kod zapasowy (zużywając go). Kod TOTP można użyć tylko raz -
+/// zapamiętujemy ostatnie wykorzystane okno czasowe.
+pub async fn verify_second_factor(
+    pool: &PgPool,
+    user_id: Uuid,
+    code: &str,
+) -> Result<bool, AppError> {
+    let status = two_factor_status(pool, user_id).await?;
+    let Some(secret) = status
+        .totp_secret
+        .as_deref()
+        .filter(|_| status.is_enabled())
+    else {
+        return Ok(false);
+    };
+
+    if let Some(step) = matching_totp_step(secret, code, Utc::now()) {
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_step = $2 WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)",
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(pool)
+        .await?;
+        return Ok(result.rows_affected() == 1);
+    }
+
+    let result = sqlx::query(
+        "UPDATE totp_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .bind(recovery_code_hash(code))
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 1 {
+        tracing::warn!("Użytkownik {} zalogował się kodem zapasowym", user_id);
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+pub async fn disable_two_factor(pool: &PgPool, user_id: Uuid) -> Result<(), AppError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = $1",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    tracing::warn!("Użytkownik {} wyłączył weryfikację dwuetapową", user_id);
+    Ok(())
+}
+
+fn challenge_signature(jwt_secret: &str, user_id: Uuid, expires_at: i64) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(jwt_secret.as_bytes())
+        .expect("HMAC przyjmuje klucz dowolnej długości");
+    mac.update(format!("totp-challenge:{}:{}", user_id, expires_at).as_bytes());
+    mac
+}
+
+/// Podpisany znacznik "hasło już sprawdzone" dla konta, ważny `CHALLENGE_MINUTES`
+pub fn create_challenge(jwt_secret: &str, user_id: Uuid) -> String {
+    let expires_at = (Utc::now() + Duration::minutes(CHALLENGE_MINUTES)).timestamp();
+    let signature = challenge_signature(jwt_secret, user_id, expires_at)
+        .finalize()
+        .into_bytes();
+    format!("{}.{}.{}", user_id, expires_at, hex::encode(signature))
+}
+
+/// Konto z ważnego znacznika z `create_challenge`
+pub fn verify_challenge(jwt_secret: &str, challenge: &str) -> Option<Uuid> {
+    let mut parts = challenge.splitn(3, '.');
+    let user_id = Uuid::parse_str(parts.next()?).ok()?;
+    let expires_at: i64 = parts.next()?.parse().ok()?;
+    let signature = hex::decode(parts.next()?).ok()?;
+    if expires_at < Utc::now().timestamp() {
+        return None;
+    }
+    challenge_signature(jwt_secret, user_id, expires_at)
+        .verify_slice(&signature)
+        .ok()
+        .map(|_| user_id)
+}
+
+/// Ciasteczko drugiego kroku; `None` tworzy ciasteczko usuwające.
+pub fn challenge_cookie(challenge: Option<String>) -> Cookie<'static> {
+    let max_age = if challenge.is_some() {
+        time::Duration::minutes(CHALLENGE_MINUTES)
+    } else {
+        time::Duration::ZERO
+    };
+    Cookie::build((CHALLENGE_COOKIE, challenge.unwrap_or_default()))
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .max_age(max_age)
+        .build()
+}
+
+/// Token personelu bez drugiego kroku daje tylko uprawnienia klienta - można robić zakupy
+/// i skonfigurować weryfikację w "Moim koncie", ale panel jest zamknięty.
+/// Wywoływane przez ekstraktory `TokenClaims`/`OptionalTokenClaims`.
+pub fn require_staff_two_factor(claims: TokenClaims) -> TokenClaims {
+    if claims.role.is_staff() && !claims.two_factor {
+        tracing::debug!(
+            "Token personelu {} bez weryfikacji dwuetapowej - uprawnienia klienta",
+            claims.sub
+        );
+        return TokenClaims {
+            role: Role::Customer,
+            ..claims
+        };
+    }
+    claims
+}
@@ -0,0 +1,170 @@
+// src/user_management.rs
+
+// Zarządzanie kontami w panelu: lista użytkowników, nadawanie ról personelu (zob. `permissions`)
+// i blokowanie kont. Zablokowane konto nie może się zalogować (hasło, Google, drugi krok);
+// wydane wcześniej tokeny wygasają normalnie. Sklep zawsze musi mieć aktywnego admina -
+// pilnujemy tego w samych zapytaniach, żeby dwa równoległe żądania nie zdjęły ostatniego.
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::{AdminUsersParams, Role};
+
+/// Limit wierszy listy - szukanie po e-mailu zawęża resztę
+const USERS_LIST_LIMIT: i64 = 200;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ManagedUser {
+    pub id: Uuid,
+    pub email: String,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    pub last_login_at: Option<DateTime<Utc>>,
+}
+
+/// Filtry listy użytkowników z formularza panelu; nieznaną rolę pomijamy
+#[derive(Debug, Clone, Default)]
+pub struct UserListFilter {
+    pub search: Option<String>,
+    pub role: Option<Role>,
+}
+
+impl UserListFilter {
+    pub fn from_params(params: &AdminUsersParams) -> Self {
+        UserListFilter {
+            search: params
+                .q
+                .as_deref()
+                .map(str::trim)
+                .filter(|q| !q.is_empty())
+                .map(str::to_string),
+            role: params.role.as_deref().and_then(|role| role.parse().ok()),
+        }
+    }
+
+    /// Parametry zapytania odpowiadające filtrom (do przeładowania listy)
+    pub fn query_string(&self) -> String {
+        format!(
+            "q={}&role={}",
+            urlencoding::encode(self.search.as_deref().unwrap_or_default()),
+            self.role.as_ref().map_or("", |role| role.as_ref())
+        )
+    }
+}
+
+/// Użytkownicy wg filtrów: najpierw personel, potem najnowsze konta
+pub async fn list_users(
+    pool: &PgPool,
+    filter: &UserListFilter,
+) -> Result<Vec<ManagedUser>, AppError> {
+    let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+        r#"
+        SELECT u.id, u.email, u.role, u.created_at, u.disabled_at, u.totp_enabled_at,
+               (SELECT MAX(a.attempted_at) FROM login_attempts a
+                WHERE a.user_id = u.id AND a.succeeded) AS last_login_at
+        FROM users u
+        WHERE TRUE"#,
+    );
+    if let Some(search) = &filter.search {
+        builder
+            .push(" AND u.email ILIKE ")
+            .push_bind(format!("%{}%", search));
+    }
+    if let Some(role) = filter.role {
+        builder.push(" AND u.role = ").push_bind(role);
+    }
+    builder
+        .push(" ORDER BY (u.role = 'customer'), u.created_at DESC LIMIT ")
+        .push_bind(USERS_LIST_LIMIT);
+
+    Ok(builder
+        .build_query_as::<ManagedUser>()
+        .fetch_all(pool)
+        .await?)
+}
+
+pub async fn find_managed_user(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<ManagedUser>, AppError> {
+    let user = sqlx::query_as::<_, ManagedUser>(
+        r#"
+            SELECT u.id, u.email, u.role, u.created_at, u.disabled_at, u.totp_enabled_at,
+                   (SELECT MAX(a.attempted_at) FROM login_attempts a
+                    WHERE a.user_id = u.id AND a.succeeded) AS last_login_at
+            FROM users u
+            WHERE u.id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(user)
+}
+
+/// Zmienia rolę. Zwraca `false`, jeśli zmiana odebrałaby sklepowi ostatniego aktywnego admina.
+pub async fn set_user_role(pool: &PgPool, user_id: Uuid, role: Role) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        r#"
+            UPDATE users SET role = $2, updated_at = NOW()
+            WHERE id = $1
+              AND ($2 = 'admin' OR EXISTS (
+                  SELECT 1 FROM users
+                  WHERE role = 'admin' AND disabled_at IS NULL AND id <> $1
+              ))
+        "#,
+    )
+    .bind(user_id)
+    .bind(role)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Blokuje lub odblokowuje konto. Zwraca `false`, jeśli blokada odebrałaby sklepowi
+/// ostatniego aktywnego admina.
+pub async fn set_account_disabled(
+    pool: &PgPool,
+    user_id: Uuid,
+    disabled: bool,
+    admin_id: Uuid,
+) -> Result<bool, AppError> {
+    let result = if disabled {
+        sqlx::query(
+            r#"
+                UPDATE users SET disabled_at = NOW(), disabled_by = $2, updated_at = NOW()
+                WHERE id = $1 AND disabled_at IS NULL
+                  AND (role <> 'admin' OR EXISTS (
+                      SELECT 1 FROM users
+                      WHERE role = 'admin' AND disabled_at IS NULL AND id <> $1
+                  ))
+            "#,
+        )
+        .bind(user_id)
+        .bind(admin_id)
+        .execute(pool)
+        .await?
+    } else {
+        sqlx::query(
+            "UPDATE users SET disabled_at = NULL, disabled_by = NULL, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?
+    };
+    Ok(!disabled || result.rows_affected() > 0)
+}
+
+/// Sprawdzane przy każdym sposobie logowania
+pub async fn is_account_disabled(pool: &PgPool, user_id: Uuid) -> Result<bool, AppError> {
+    let disabled: Option<bool> =
+        sqlx::query_scalar("SELECT disabled_at IS NOT NULL FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(disabled.unwrap_or(false))
+}
@@ -0,0 +1,281 @@
+// src/vat.rs
+
+// Rozbicie VAT zamówień zapisywane w chwili zakupu (zob. migrację `add_vat_breakdown`).
+// Wszystkie kwoty to grosze w i64; VAT wyliczamy z ceny brutto z zaokrągleniem połówek w górę,
+// a netto to zawsze brutto minus VAT, więc suma netto + VAT zgadza się co do grosza.
+// Sklep sprzedaje w procedurze marży - wtedy VAT nie jest wykazywany (księgowa liczy go od marży),
+// ale stawka i tak trafia do zamówienia, żeby późniejsza zmiana ustawień nie zmieniła historii.
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use strum_macros::{Display, EnumString};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, Display, EnumString,
+)]
+#[sqlx(type_name = "vat_scheme", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[strum(ascii_case_insensitive)]
+pub enum VatScheme {
+    /// Procedura marży dla towarów używanych - VAT od marży, niewykazywany na fakturze
+    #[strum(to_string = "VAT marża", serialize = "margin")]
+    Margin,
+    #[strum(to_string = "VAT", serialize = "standard")]
+    Standard,
+}
+
+/// Bieżące ustawienia podatkowe (`VAT_SCHEME`, `VAT_RATE_PERCENT`), stosowane do nowych zamówień
+#[derive(Debug, Clone, Copy)]
+pub struct VatConfig {
+    pub scheme: VatScheme,
+    /// Stawka w punktach bazowych: 2300 = 23%
+    pub rate_bp: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VatBreakdown {
+    pub net: i64,
+    pub vat: i64,
+}
+
+/// Dzielenie z zaokrągleniem połówek od zera (0,5 gr -> 1 gr)
+fn div_round_half_away(numerator: i128, denominator: i128) -> i64 {
+    let quotient = (numerator.abs() * 2 + denominator) / (denominator * 2);
+    (quotient * numerator.signum()) as i64
+}
+
+/// Netto i VAT kwoty brutto. W procedurze marży VAT nie jest wykazywany.
+pub fn vat_breakdown(gross: i64, scheme: VatScheme, rate_bp: i32) -> VatBreakdown {
+    let vat = match scheme {
+        VatScheme::Margin => 0,
+        VatScheme::Standard => div_round_half_away(
+            i128::from(gross) * i128::from(rate_bp),
+            10_000 + i128::from(rate_bp),
+        ),
+    };
+    VatBreakdown {
+        net: gross - vat,
+        vat,
+    }
+}
+
+/// Dzieli rabat na pozycje proporcjonalnie do cen; reszta z zaokrągleń trafia do najdroższej
+/// pozycji, więc udziały sumują się dokładnie do rabatu.
+pub fn allocate_discount(prices: &[i64], discount: i64) -> Vec<i64> {
+    let total: i128 = prices.iter().map(|price| i128::from(*price)).sum();
+    if total <= 0 || discount == 0 {
+        return vec![0; prices.len()];
+    }
+    let mut shares: Vec<i64> = prices
+        .iter()
+        .map(|price| (i128::from(discount) * i128::from(*price) / total) as i64)
+        .collect();
+    let remainder = discount - shares.iter().sum::<i64>();
+    if let Some(largest) = prices
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, price)| **price)
+        .map(|(index, _)| index)
+    {
+        shares[largest] += remainder;
+    }
+    shares
+}
+
+/// O ile usunięcie pozycji zmniejsza zamówienie: cena minus udział pozycji w rabacie
+/// (`allocate_discount`). Tyle zwracamy klientowi - rabatu na tę pozycję nie zapłacił.
+pub fn removed_item_gross(price_at_purchase: i64, discount_share: i64) -> i64 {
+    (price_at_purchase - discount_share).max(0)
+}
+
+/// Dzieli wartość usuniętej pozycji na część opłaconą kartą podarunkową i gotówką -
+/// proporcjonalnie do tego, jak zapłacono za całe zamówienie. Zwraca `(karta, gotówka)`.
+pub fn split_removed_item_gross(
+    item_gross: i64,
+    gift_card_amount: i64,
+    total_price: i64,
+) -> (i64, i64) {
+    let order_value = gift_card_amount + total_price;
+    if order_value <= 0 || gift_card_amount <= 0 {
+        return (0, item_gross);
+    }
+    let card = ((i128::from(item_gross) * i128::from(gift_card_amount)
+        + i128::from(order_value) / 2)
+        / i128::from(order_value)) as i64;
+    let card = card.clamp(0, item_gross.min(gift_card_amount));
+    (card, item_gross - card)
+}
+
+/// Rozbicie jednej pozycji zamówienia
+#[derive(Debug, Clone, Copy)]
+pub struct ItemVat {
+    pub discount_share: i64,
+    pub breakdown: VatBreakdown,
+}
+
+/// Rozbicie całego zamówienia: pozycje (w kolejności cen), dostawa i sumy
+#[derive(Debug, Clone)]
+pub struct OrderVat {
+    pub scheme: VatScheme,
+    pub rate_bp: i32,
+    pub items: Vec<ItemVat>,
+    pub shipping_cost: i64,
+    pub net_total: i64,
+    pub vat_total: i64,
+}
+
+impl OrderVat {
+    pub fn compute(config: VatConfig, prices: &[i64], discount: i64, shipping_cost: i64) -> Self {
+        let items: Vec<ItemVat> = prices
+            .iter()
+            .zip(allocate_discount(prices, discount))
+            .map(|(price, discount_share)| ItemVat {
+                discount_share,
+                breakdown: vat_breakdown(price - discount_share, config.scheme, config.rate_bp),
+            })
+            .collect();
+        let shipping = vat_breakdown(shipping_cost, config.scheme, config.rate_bp);
+        OrderVat {
+            scheme: config.scheme,
+            rate_bp: config.rate_bp,
+            net_total: items.iter().map(|item| item.breakdown.net).sum::<i64>() + shipping.net,
+            vat_total: items.iter().map(|item| item.breakdown.vat).sum::<i64>() + shipping.vat,
+            items,
+            shipping_cost,
+        }
+    }
+}
+
+/// Rozbicie VAT zapisane przy zamówieniu - do faktury
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OrderVatSummary {
+    pub vat_scheme: VatScheme,
+    pub vat_rate_bp: i32,
+    pub shipping_cost: i64,
+    pub net_total: i64,
+    pub vat_total: i64,
+}
+
+impl OrderVatSummary {
+    pub fn shipping(&self) -> VatBreakdown {
+        vat_breakdown(self.shipping_cost, self.vat_scheme, self.vat_rate_bp)
+    }
+}
+
+/// Rozbicie VAT zapisane przy pozycji zamówienia
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OrderItemVat {
+    pub order_item_id: Uuid,
+    pub vat_rate_bp: i32,
+    pub discount_share: i64,
+    pub net_amount: i64,
+    pub vat_amount: i64,
+}
+
+impl OrderItemVat {
+    pub fn gross_amount(&self) -> i64 {
+        self.net_amount + self.vat_amount
+    }
+}
+
+pub async fn order_vat_summary(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+) -> Result<OrderVatSummary, AppError> {
+    sqlx::query_as::<_, OrderVatSummary>(
+        "SELECT vat_scheme, vat_rate_bp, shipping_cost, net_total, vat_total FROM orders WHERE id = $1",
+    )
+    .bind(order_id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+pub async fn order_item_vat_lines(
+    conn: &mut PgConnection,
+    order_id: Uuid,
+) -> Result<Vec<OrderItemVat>, AppError> {
+    Ok(sqlx::query_as::<_, OrderItemVat>(
+        r#"
+        SELECT id AS order_item_id, vat_rate_bp, discount_share, net_amount, vat_amount
+        FROM order_items WHERE order_id = $1
+        "#,
+    )
+    .bind(order_id)
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Rozbicie zwrotu środków za pozycję - według stawki i procedury z chwili zakupu
+pub async fn refund_vat_breakdown(
+    conn: &mut PgConnection,
+    order_item_id: Uuid,
+    amount: i64,
+) -> Result<VatBreakdown, AppError> {
+    let (scheme, rate_bp): (VatScheme, i32) = sqlx::query_as(
+        r#"
+        SELECT o.vat_scheme, oi.vat_rate_bp
+        FROM order_items oi
+        JOIN orders o ON o.id = oi.order_id
+        WHERE oi.id = $1
+        "#,
+    )
+    .bind(order_item_id)
+    .fetch_optional(conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    Ok(vat_breakdown(amount, scheme, rate_bp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARGIN: VatConfig = VatConfig {
+        scheme: VatScheme::Margin,
+        rate_bp: 2300,
+    };
+
+    #[test]
+    fn removing_item_from_discounted_order_refunds_only_its_discounted_price() {
+        // 100 zł + 50 zł, rabat 15 zł, dostawa 10 zł -> do zapłaty 145 zł
+        let prices = [10_000, 5_000];
+        let discount = 1_500;
+        let shipping = 1_000;
+        let order_vat = OrderVat::compute(MARGIN, &prices, discount, shipping);
+        let total = prices.iter().sum::<i64>() - discount + shipping;
+        assert_eq!(total, 14_500);
+
+        let share = order_vat.items[1].discount_share;
+        assert_eq!(share, 500);
+        let refund = removed_item_gross(prices[1], share);
+        assert_eq!(refund, 4_500);
+
+        // Po usunięciu: 100 zł - pozostały rabat 10 zł + dostawa 10 zł
+        let remaining_total = prices[0] - (discount - share) + shipping;
+        assert_eq!(total - refund, remaining_total);
+        assert!(total - refund >= 0);
+    }
+
+    #[test]
+    fn discount_shares_add_up_to_discount() {
+        let shares = allocate_discount(&[3_333, 3_333, 3_334], 1_000);
+        assert_eq!(shares.iter().sum::<i64>(), 1_000);
+        assert_eq!(removed_item_gross(1_000, 1_500), 0);
+    }
+
+    #[test]
+    fn removed_item_is_split_between_gift_card_and_cash() {
+        // 145 zł: 45 zł z karty, 100 zł gotówką; pozycja za 45 zł
+        assert_eq!(
+            split_removed_item_gross(4_500, 4_500, 10_000),
+            (1_397, 3_103)
+        );
+        // Całość opłacona kartą - nic nie zwracamy gotówką
+        assert_eq!(split_removed_item_gross(4_500, 14_500, 0), (4_500, 0));
+        assert_eq!(split_removed_item_gross(4_500, 0, 14_500), (0, 4_500));
+    }
+}
@@ -0,0 +1,1814 @@
+// src/views/account.rs
+
+// Konto klienta: logowanie, rejestracja, reset hasła, dane do wysyłki, zamówienia z posprzedażą (zwroty, reklamacje, opinie) i podgląd zamówienia gościa.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::Form;
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use axum_extra::extract::cookie::CookieJar;
+use chrono::{DateTime, Utc};
+use maud::{Markup, html};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::auth_models::TokenClaims;
+use crate::complaints::{
+    MAX_COMPLAINT_DESCRIPTION_LEN, MAX_COMPLAINT_PHOTOS, complaint_reference,
+    complaint_window_open, complaintable_order_item_ids, complaints_for_order, response_deadline,
+};
+use crate::date_format::{format_date, format_datetime, format_datetime_long, to_shop_time};
+use crate::errors::{AppError, ValidationErrors};
+use crate::invoices::find_invoice_for_order;
+use crate::login_lockout::{active_lockout, normalize_login_email};
+use crate::middleware::{OptionalTokenClaims, UnverifiedEmail};
+use crate::models::{
+    Complaint, GuestOrderLookupPayload, Order, OrderItemDetailsPublic, OrderStatus,
+    OrderStatusHistory, PasswordResetToken, Product, ProductReview, ReturnStatus,
+    UserShippingDetails,
+};
+use crate::permissions::Permission;
+use crate::plural::pluralize;
+use crate::repo;
+use crate::response::{PageBuilder, build_response};
+use crate::return_labels::return_labels_available;
+use crate::returns::{
+    MAX_RETURN_REASON_LEN, ReturnDetails, return_deadline, return_reference,
+    returnable_order_item_ids, returns_for_order,
+};
+use crate::reviews::{MAX_REVIEW_CONTENT_LEN, delivered_order_with_product, reviews_for_order};
+use crate::security_headers::CspNonce;
+use crate::services::fetch_order_status_history;
+use crate::state::AppState;
+use crate::two_factor::{
+    CHALLENGE_COOKIE, TwoFactorStatus, format_totp_secret, remaining_recovery_codes,
+    two_factor_status, verify_challenge,
+};
+
+use super::common::{
+    complaint_photos_maud, complaint_status_badge_classes, format_price_maud, rating_stars_maud,
+    render_order_status_timeline, return_status_badge_classes,
+};
+
+pub async fn my_account_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    claims: TokenClaims,
+    UnverifiedEmail(unverified_email): UnverifiedEmail,
+) -> Result<Response, AppError> {
+    tracing::info!(
+        "MAUD: Użytkownik ID {} wszedł na stronę Moje Konto",
+        claims.sub
+    );
+
+    let sidebar_links = vec![
+        (
+            "Moje Zamówienia",
+            "/htmx/moje-konto/zamowienia",
+            "/moje-konto/zamowienia",
+        ),
+        ("Moje Dane", "/htmx/moje-konto/dane", "/moje-konto/dane"),
+        (
+            "Bezpieczeństwo",
+            "/htmx/moje-konto/bezpieczenstwo",
+            "/moje-konto/bezpieczenstwo",
+        ),
+    ];
+    let default_section_url = "/htmx/moje-konto/zamowienia";
+
+    let page_content = html! {
+        div ."max-w-7xl mx-auto px-2 sm:px-4 lg:px-8 py-8 sm:py-10" {
+            h1 ."text-3xl sm:text-4xl font-bold tracking-tight text-gray-900 mb-8 text-center md:text-left" { "Moje Konto" }
+            @if let Some(email) = &unverified_email {
+                (render_email_verification_banner_maud(email))
+            }
+            div ."flex flex-col md:flex-row gap-6 lg:gap-8" {
+                aside ."w-full md:w-1/4 lg:w-1/5 bg-white p-4 sm:p-6 rounded-lg shadow-md md:sticky md:top-20 md:self-start" {
+                    nav {
+                        ul ."space-y-2" {
+                            @for (label, hx_get_url, push_url) in sidebar_links {
+                                li {
+                                    a href=(push_url)
+                                       hx-get=(hx_get_url)
+                                       hx-target="#my-account-content"
+                                       hx-swap="innerHTML"
+                                       hx-push-url=(push_url)
+                                       class="block px-3 py-2 rounded-md text-gray-700 hover:bg-[var(--color-secondary)] hover:text-[var(--text-color-primary)] transition-colors duration-150 ease-in-out focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)] text-center" {
+                                        (label)
+                                    }
+                                }
+                            }
+                            li ."pt-4 mt-4 border-t border-gray-200" {
+                                // ZMIANA: Uproszczony i poprawiony link wylogowania
+                                button type="button"
+                                    hx-post="/api/auth/logout"
+                                    hx-swap="none"
+                                   "@click"="clientSideLogout()" // Wywołuje funkcję z Alpine.js
+                                   class="w-full text-center block px-3 py-2 rounded-md text-[var(--text-color-primary)] hover:bg-[var(--color-secondary)] hover:text-[var(--text-color-primary-hover)] font-medium transition-colors duration-150 ease-in-out focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)]" {
+                                    "Wyloguj"
+                                }
+                            }
+                        }
+                    }
+                }
+                main #my-account-content ."w-full md:w-3/4 lg:w-4/5 bg-white p-4 sm:p-6 rounded-lg shadow-md min-h-[300px]"
+                     hx-get=(default_section_url)
+                     hx-trigger="load"
+                     hx-swap="innerHTML"
+                     hx-push-url="true" {
+                    div #my-account-content-spinner .flex.justify-center.items-center.h-40 {
+                        svg class="animate-spin h-8 w-8 text-[var(--text-color-primary)]" xmlns="http://www.w3.org/2000/svg" fill="none" "viewBox"="0 0 24 24" {
+                            circle class="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" "stroke-width"="4";
+                            path class="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4zm2 5.291A7.962 7.962 0 014 12H0c0 3.042 1.135 5.824 3 7.938l3-2.647z";
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Moje konto - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Sekcja "Bezpieczeństwo" w "Moim koncie" - weryfikacja dwuetapowa (zob. `two_factor`)
+pub async fn my_account_security_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    // Rola z bazy, nie z tokenu - token admina bez drugiego kroku ma uprawnienia klienta
+    let user = repo::users::find_by_id(&app_state.db_pool, claims.sub)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let status = two_factor_status(&app_state.db_pool, user.id).await?;
+    let remaining_codes = remaining_recovery_codes(&app_state.db_pool, user.id).await?;
+
+    let page_content = html! {
+        div {
+            h2 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-6" { "Bezpieczeństwo" }
+            (render_two_factor_section_maud(user.role.is_staff(), &status, remaining_codes))
+        }
+    };
+
+    let title = "Bezpieczeństwo - Moje konto - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Stan weryfikacji dwuetapowej z przyciskami włączenia / wyłączenia
+pub fn render_two_factor_section_maud(
+    is_staff: bool,
+    status: &TwoFactorStatus,
+    remaining_codes: i64,
+) -> Markup {
+    html! {
+        div #two-factor-section ."bg-white p-6 rounded-lg shadow space-y-4" {
+            h3 ."text-lg font-semibold text-gray-800" { "Weryfikacja dwuetapowa" }
+            @if !is_staff {
+                p ."text-sm text-gray-600" {
+                    "Weryfikacja dwuetapowa jest dostępna dla kont personelu sklepu."
+                }
+            } @else if let (true, Some(enabled_at)) = (status.is_enabled(), status.totp_enabled_at) {
+                p ."text-sm text-green-700" {
+                    "Włączona od " (format_datetime(&enabled_at)) ". Po haśle logowanie wymaga kodu z aplikacji."
+                }
+                p ."text-sm text-gray-600" {
+                    "Niewykorzystane kody zapasowe: " span ."font-semibold" { (remaining_codes) }
+                }
+                (two_factor_code_form_maud(
+                    "/api/moje-konto/2fa/kody-zapasowe",
+                    "Wygeneruj nowe kody zapasowe",
+                    "Dotychczasowe kody zapasowe przestaną działać.",
+                ))
+                (two_factor_code_form_maud(
+                    "/api/moje-konto/2fa/wylacz",
+                    "Wyłącz weryfikację dwuetapową",
+                    "Bez weryfikacji dwuetapowej panel sklepu będzie niedostępny.",
+                ))
+            } @else {
+                p ."text-sm text-gray-600" {
+                    "Panel sklepu wymaga weryfikacji dwuetapowej. Po włączeniu przy logowaniu podasz oprócz hasła 6-cyfrowy kod z aplikacji uwierzytelniającej (np. Google Authenticator, Aegis, 1Password)."
+                }
+                button type="button"
+                       hx-post="/api/moje-konto/2fa/start"
+                       hx-target="#two-factor-section"
+                       hx-swap="outerHTML"
+                       class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                    "Włącz weryfikację dwuetapową"
+                }
+            }
+        }
+    }
+}
+
+/// Formularz akcji potwierdzanej aktualnym kodem (z aplikacji albo zapasowym)
+fn two_factor_code_form_maud(endpoint: &str, submit_label: &str, hint: &str) -> Markup {
+    html! {
+        form hx-post=(endpoint)
+             hx-target="#two-factor-section"
+             hx-swap="outerHTML"
+             class="pt-4 border-t border-gray-200 space-y-2" {
+            p ."text-xs text-gray-500" { (hint) }
+            div ."flex flex-col sm:flex-row gap-2" {
+                input name="code" type="text" required autocomplete="one-time-code" inputmode="numeric"
+                      maxlength="20" placeholder="Aktualny kod"
+                      class="px-3 py-2 border border-gray-300 rounded-md shadow-sm font-mono sm:w-48 focus:outline-none focus:ring-2 focus:ring-pink-500";
+                button type="submit"
+                       class="px-4 py-2 text-sm font-medium text-gray-700 bg-white border border-gray-300 hover:bg-gray-50 rounded-md" {
+                    (submit_label)
+                }
+            }
+        }
+    }
+}
+
+/// Drugi krok konfiguracji: sekret do aplikacji i pole na pierwszy kod
+pub fn render_two_factor_setup_maud(secret: &str, otpauth_uri: &str) -> Markup {
+    html! {
+        div #two-factor-section ."bg-white p-6 rounded-lg shadow space-y-4" {
+            h3 ."text-lg font-semibold text-gray-800" { "Włączanie weryfikacji dwuetapowej" }
+            ol ."list-decimal list-inside text-sm text-gray-700 space-y-2" {
+                li {
+                    "Na telefonie "
+                    a href=(otpauth_uri) class="text-pink-600 hover:underline" { "otwórz ten link" }
+                    " albo dodaj konto w aplikacji ręcznie, wpisując klucz:"
+                    div ."mt-2 p-3 bg-gray-50 border border-gray-200 rounded-md font-mono text-base tracking-wider select-all break-all" {
+                        (format_totp_secret(secret))
+                    }
+                }
+                li { "Wpisz 6-cyfrowy kod, który pokazała aplikacja." }
+            }
+            form hx-post="/api/moje-konto/2fa/potwierdz"
+                 hx-target="#two-factor-section"
+                 hx-swap="outerHTML"
+                 class="flex flex-col sm:flex-row gap-2" {
+                input name="code" type="text" required autofocus autocomplete="one-time-code" inputmode="numeric"
+                      maxlength="6" placeholder="123456"
+                      class="px-3 py-2 border border-gray-300 rounded-md shadow-sm font-mono tracking-widest sm:w-40 focus:outline-none focus:ring-2 focus:ring-pink-500";
+                button type="submit"
+                       class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                    "Potwierdź i włącz"
+                }
+            }
+        }
+    }
+}
+
+/// Kody zapasowe - pokazywane tylko raz, zaraz po wygenerowaniu
+pub fn render_recovery_codes_maud(codes: &[String]) -> Markup {
+    html! {
+        div #two-factor-section ."bg-white p-6 rounded-lg shadow space-y-4" {
+            h3 ."text-lg font-semibold text-gray-800" { "Kody zapasowe" }
+            p ."text-sm text-gray-700" {
+                "Zapisz je w bezpiecznym miejscu - każdy działa raz zamiast kodu z aplikacji, np. po zgubieniu telefonu. "
+                span ."font-semibold" { "Nie pokażemy ich ponownie." }
+            }
+            ul ."grid grid-cols-2 gap-2 p-4 bg-gray-50 border border-gray-200 rounded-md font-mono text-sm select-all" {
+                @for code in codes {
+                    li { (code) }
+                }
+            }
+            a href="/moje-konto/bezpieczenstwo"
+              hx-get="/htmx/moje-konto/bezpieczenstwo"
+              hx-select="#two-factor-section"
+              hx-target="#two-factor-section"
+              hx-swap="outerHTML"
+              class="inline-block px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                "Kody zapisane - gotowe"
+            }
+        }
+    }
+}
+
+/// `?email=` ustawia logowanie po odrzuceniu próby z powodu blokady (zob. `login_lockout`)
+#[derive(Deserialize)]
+pub struct LoginPageQuery {
+    pub email: Option<String>,
+    /// `blad` po nieudanym powrocie z logowania przez Google
+    pub google: Option<String>,
+}
+
+pub async fn login_page_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<LoginPageQuery>,
+) -> Result<Response, AppError> {
+    tracing::info!("MAUD: Żądanie strony logowania HTMX");
+
+    let prefilled_email = query
+        .email
+        .as_deref()
+        .map(str::trim)
+        .filter(|email| !email.is_empty());
+    let lockout = match prefilled_email {
+        Some(email) => active_lockout(&app_state.db_pool, &normalize_login_email(email)).await?,
+        None => None,
+    };
+
+    let google_login_failed = query.google.as_deref() == Some("blad");
+    let google_login_enabled = app_state.google_oauth.is_some();
+
+    let page_title = "Logowanie";
+    let form_id = "login-form";
+    let messages_id = "login-messages";
+    let api_login_endpoint = "/api/auth/login";
+    let registration_htmx_endpoint = "/htmx/rejestracja"; // Bez /page/
+    let registration_url = "/rejestracja";
+
+    let page_content = html! {
+            div ."min-h-[calc(100vh-var(--header-height,10rem))] w-full flex items-center justify-center p-4 bg-gradient-to-br from-pink-50 via-purple-50 to-indigo-100" {
+                div ."w-full max-w-md" { // Ten div centruje kartę
+                    div ."bg-white/80 backdrop-blur-md py-8 px-6 sm:px-10 shadow-2xl rounded-xl border border-gray-200" {
+                        div ."mb-8 text-center" {
+                            h2 ."text-3xl font-bold text-gray-900" { (page_title) }
+                        }
+
+                        @if let Some(lockout) = &lockout {
+                            div ."mb-4 p-3 rounded-lg bg-red-50 border border-red-200 text-sm text-red-700" role="alert" {
+                                p ."font-semibold" { "Logowanie na ten adres jest chwilowo zablokowane." }
+                                p {
+                                    "Po kilku nieudanych próbach wstrzymaliśmy logowanie. Spróbuj ponownie za "
+                                    (pluralize(lockout.remaining_minutes(), "minutę", "minuty", "minut"))
+                                    " albo ustaw nowe hasło."
+                                }
+                            }
+                        }
+                        @if google_login_failed {
+                            div ."mb-4 p-3 rounded-lg bg-red-50 border border-red-200 text-sm text-red-700" role="alert" {
+                                "Nie udało się zalogować przez Google. Spróbuj ponownie albo zaloguj się hasłem."
+                            }
+                        }
+                        div #(messages_id) ."mb-4 text-sm min-h-[1.25em]"; // min-h-[1.25em] aby uniknąć skoku layoutu
+
+                        form #(form_id)
+                            hx-post=(api_login_endpoint)
+                            hx-ext="json-enc"
+                            hx-target=(format!("#{}", messages_id))
+                            hx-swap="innerHTML"
+                            class="space-y-6" {
+
+                            div {
+                                label for="email" ."block text-sm font-medium text-gray-700" { "Adres e-mail" }
+                                div ."mt-1" {
+                                    input #email name="email" type="email" autocomplete="email" required value=[prefilled_email]
+                                       class="appearance-none block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm placeholder-gray-400 focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)] focus:border-[var(--color-primary)] transition duration-150 ease-in-out sm:text-sm";
+                                }
+                            }
+
+                            div {
+                                label for="password" ."block text-sm font-medium text-gray-700" { "Hasło" }
+                                div ."mt-1" {
+                                    input #password name="password" type="password" autocomplete="current-password" required
+                                           class="appearance-none block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm placeholder-gray-400
+                                              focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)] focus:border-[var(--color-primary)] transition duration-150 ease-in-out sm:text-sm";
+                                }
+                            }
+
+                            div {
+                                button type="submit"
+                                       class="w-full flex justify-center py-3 px-4 border border-transparent rounded-lg shadow-sm text-sm font-medium text-white
+                                          bg-[var(--color-primary)] hover:bg-[var(--color-primary-hover)] focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-[var(--color-primary)] 
+                                          transition-all duration-150 ease-in-out transform hover:scale-105" {
+                                    "Zaloguj się"
+                                }
+                            }
+                        }
+
+                        @if google_login_enabled {
+                            div ."mt-4" {
+                                // Zwykły link, nie hx-get - logowanie wymaga pełnego przekierowania do Google
+                                a href="/api/auth/google"
+                                   class="w-full flex justify-center items-center gap-2 py-3 px-4 border border-gray-300 rounded-lg shadow-sm text-sm font-medium text-gray-700 bg-white hover:bg-gray-50 transition-all duration-150 ease-in-out" {
+                                    "Zaloguj się przez Google"
+                                }
+                            }
+                        }
+
+                        div ."mt-6 pt-6 border-t border-gray-200" {
+                            div ."text-center" {
+                                p ."text-sm text-gray-600" {
+                                    "Nie masz jeszcze konta? "
+                                    a href=(registration_url)
+                                       hx-get=(registration_htmx_endpoint)
+                                       hx-target="#content"
+                                       hx-swap="innerHTML"
+                                       hx-push-url=(registration_url)
+                                       class="font-medium text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline" {
+                                        "Zarejestruj się"
+                                    }
+                                }
+                            }
+                        div ."text-center" {
+                            a href="/zapomnialem-hasla"
+                               hx-get="/zapomnialem-hasla"
+                               hx-target="#content"
+                               hx-swap="innerHTML"
+                               hx-push-url="/zapomnialem-hasla"
+                               class="text-xs text-gray-500 hover:text-pink-600 hover:underline" {
+                                "Zapomniałeś hasła?"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Logowanie - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Drugi krok logowania admina - kod z aplikacji uwierzytelniającej albo kod zapasowy
+pub async fn two_factor_login_page_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let challenge_valid = CookieJar::from_headers(&headers)
+        .get(CHALLENGE_COOKIE)
+        .and_then(|cookie| verify_challenge(&app_state.jwt_secret, cookie.value()))
+        .is_some();
+
+    let page_content = html! {
+        div ."min-h-[calc(100vh-var(--header-height,10rem))] w-full flex items-center justify-center p-4 bg-gradient-to-br from-pink-50 via-purple-50 to-indigo-100" {
+            div ."w-full max-w-md" {
+                div ."bg-white/80 backdrop-blur-md py-8 px-6 sm:px-10 shadow-2xl rounded-xl border border-gray-200" {
+                    div ."mb-6 text-center" {
+                        h2 ."text-3xl font-bold text-gray-900" { "Weryfikacja dwuetapowa" }
+                    }
+                    @if challenge_valid {
+                        p ."mb-6 text-sm text-gray-600 text-center" {
+                            "Wpisz 6-cyfrowy kod z aplikacji uwierzytelniającej albo jeden z kodów zapasowych."
+                        }
+                        form hx-post="/api/auth/2fa"
+                             hx-swap="none"
+                             class="space-y-6" {
+                            div {
+                                label for="two_factor_code" ."block text-sm font-medium text-gray-700" { "Kod" }
+                                input #two_factor_code name="code" type="text" required autofocus
+                                      autocomplete="one-time-code" inputmode="numeric" maxlength="20"
+                                      class="mt-1 appearance-none block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm text-center tracking-widest font-mono focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)] focus:border-[var(--color-primary)] sm:text-sm";
+                            }
+                            button type="submit"
+                                   class="w-full flex justify-center py-3 px-4 border border-transparent rounded-lg shadow-sm text-sm font-medium text-white bg-[var(--color-primary)] hover:bg-[var(--color-primary-hover)] focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-[var(--color-primary)] transition-all duration-150 ease-in-out" {
+                                "Potwierdź"
+                            }
+                        }
+                    } @else {
+                        p ."mb-6 text-sm text-gray-600 text-center" {
+                            "Czas na podanie kodu minął. Zaloguj się ponownie."
+                        }
+                    }
+                    div ."mt-6 text-center" {
+                        a href="/logowanie"
+                           hx-get="/htmx/logowanie"
+                           hx-target="#content"
+                           hx-swap="innerHTML"
+                           hx-push-url="/logowanie"
+                           class="text-xs text-gray-500 hover:text-pink-600 hover:underline" {
+                            "Wróć do logowania"
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Weryfikacja dwuetapowa - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn registration_page_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+) -> Result<Response, AppError> {
+    tracing::info!("MAUD: Żądanie strony rejestracji HTMX");
+
+    let page_title = "Załóż konto";
+    let form_id = "registration-form";
+    let messages_id = "registration-messages";
+    let api_register_endpoint = "/api/auth/register";
+    let login_htmx_endpoint = "/htmx/logowanie"; // Bez /page/
+    let login_url = "/logowanie";
+    // Puste miejsca na komunikaty przy polach, wypełniane przez odpowiedź 422 (`hx-swap-oob`)
+    let no_errors = ValidationErrors::new();
+
+    let page_content = html! {
+        div ."min-h-[calc(100vh-var(--header-height,10rem))] w-full flex items-center justify-center p-4 bg-gradient-to-br from-teal-50 via-cyan-50 to-sky-100" {
+            div ."w-full max-w-md" {
+                div ."bg-white/80 backdrop-blur-md py-8 px-6 sm:px-10 shadow-2xl rounded-xl border border-gray-200" {
+                    div ."mb-8 text-center" {
+                        h2 ."text-3xl font-bold text-gray-900" { (page_title) }
+                    }
+
+                    div #(messages_id) ."mb-4 text-sm min-h-[1.25em]"; // Na komunikaty (sukces/błąd)
+
+                    form #(form_id)
+                        hx-post=(api_register_endpoint)
+                        hx-ext="json-enc"
+                        hx-target=(format!("#{}", messages_id))
+                        hx-swap="innerHTML"
+                        class="space-y-6" {
+
+                        div {
+                            label for="reg-email" ."block text-sm font-medium text-gray-700" { "Adres e-mail" }
+                            div ."mt-1" {
+                                input #reg-email name="email" type="email" autocomplete="email" required
+                                       class="appearance-none block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm placeholder-gray-400 focus:outline-none focus:ring-2 focus:ring-teal-500 focus:border-teal-500 transition duration-150 ease-in-out sm:text-sm";
+                                (no_errors.field_error_maud("email"))
+                            }
+                        }
+
+                        div {
+                            label for="reg-password" ."block text-sm font-medium text-gray-700" { "Hasło" }
+                            div ."mt-1" {
+                                input #reg-password name="password" type="password" autocomplete="new-password" required minlength="8"
+                                       class="appearance-none block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm placeholder-gray-400 focus:outline-none focus:ring-2 focus:ring-teal-500 focus:border-teal-500 transition duration-150 ease-in-out sm:text-sm";
+                                p ."mt-1 text-xs text-gray-500" { "Minimum 8 znaków." }
+                                (no_errors.field_error_maud("password"))
+                            }
+                        }
+
+                        div {
+                            label for="confirm_password" ."block text-sm font-medium text-gray-700" { "Potwierdź hasło" }
+                            div ."mt-1" {
+                                input #confirm_password name="confirm_password" type="password" autocomplete="new-password" required minlength="8"
+                                       class="appearance-none block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm ...";
+                            }
+                        }
+
+                        // TODO: Dodaj checkboxy ze zgodami (Regulamin, Polityka Prywatności) - są one prawnie wymagane.
+                        // div ."pt-2 space-y-2" {
+                        //    ... przykładowy checkbox ...
+                        // }
+
+                        div {
+                            button type="submit"
+                                   class="w-full flex justify-center py-3 px-4 border border-transparent rounded-lg shadow-sm text-sm font-medium text-white
+                                          bg-teal-600 hover:bg-teal-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-teal-500 
+                                          transition-all duration-150 ease-in-out transform hover:scale-105" {
+                                "Zarejestruj się"
+                            }
+                        }
+                    }
+
+                    div ."mt-6 pt-6 border-t border-gray-200" {
+                        div ."text-center" {
+                            p ."text-sm text-gray-600" {
+                                "Masz już konto? "
+                                a href=(login_url)
+                                   hx-get=(login_htmx_endpoint)
+                                   hx-target="#content"
+                                   hx-swap="innerHTML"
+                                   hx-push-url=(login_url)
+                                   class="font-medium text-teal-600 hover:text-teal-500 hover:underline" {
+                                    "Zaloguj się"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Rejestracja - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn my_orders_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims, // Wymagane zalogowanie
+) -> Result<Response, AppError> {
+    let user_id = claims.sub;
+    tracing::info!("MAUD: Użytkownik ID {} żąda listy swoich zamówień", user_id);
+
+    // 1. Pobierz zamówienia użytkownika z bazy danych
+    let orders = repo::orders::for_user(&app_state.db_pool, user_id).await?;
+
+    let page_content = html! {
+        div { // Główny kontener dla tej sekcji, może mieć ID jeśli jest potrzebne dla hx-target z innego miejsca
+            h2 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-6" { "Moje Zamówienia" }
+            @if orders.is_empty() {
+                p ."text-gray-600 py-4" { "Nie złożyłeś/aś jeszcze żadnych zamówień." }
+            } @else {
+                div ."space-y-6" {
+                    @for order_item in &orders {
+                        // Przygotowanie wartości do wyświetlenia
+                        @let order_date_display = format_datetime(&order_item.order_date);
+                        @let order_status_display = order_item.status.to_string(); // Zakłada, że OrderStatus implementuje Display
+                        @let order_total_display = format_price_maud(order_item.total_price); // Użyj swojej funkcji formatującej
+
+                        @let status_classes = match order_item.status {
+                            OrderStatus::Pending => "bg-yellow-100 text-yellow-800",
+                            OrderStatus::Processing => "bg-blue-100 text-blue-800",
+                            OrderStatus::Shipped => "bg-green-100 text-green-800",
+                            OrderStatus::Delivered => "bg-emerald-100 text-emerald-800",
+                            OrderStatus::Cancelled => "bg-red-100 text-red-800",
+                            // Dodaj inne statusy, jeśli masz
+                        };
+
+                        div ."border border-gray-200 rounded-lg p-4 sm:p-6 hover:shadow-lg transition-shadow duration-200 ease-in-out bg-white" {
+                            div ."flex flex-col sm:flex-row justify-between sm:items-center mb-3 pb-3 border-b border-gray-100" {
+                                div {
+                                    h3 ."text-lg font-semibold text-[var(--text-color-primary)]" {
+                                        "Zamówienie " (order_item.order_number)
+                                    }
+                                    p ."text-sm text-gray-500" { "Data złożenia: " (order_date_display) }
+                                }
+                                div ."mt-2 sm:mt-0 text-left sm:text-right" {
+                                    span class=(format!("px-3 py-1 text-xs font-semibold rounded-full {}", status_classes)) {
+                                        (order_status_display)
+                                    }
+                                }
+                            }
+                            div ."flex flex-col sm:flex-row justify-between items-start sm:items-center" {
+                                div ."text-sm text-gray-700" {
+                                    // Możesz tutaj dodać więcej informacji, np. kto zamawiał, jeśli to potrzebne
+                                    // np. jeśli order_item.shipping_first_name.is_some() ...
+                                    p { "Suma: " strong { (order_total_display) } }
+                                }
+                                div ."mt-3 sm:mt-0" {
+                                    // Link do szczegółów zamówienia - bez zmian, ale handler docelowy
+                                    // /htmx/moje-konto/zamowienie-szczegoly/{order_id}
+                                    // będzie musiał być świadomy pełnej struktury Order.
+                                    a href=(format!("/moje-konto/zamowienia/{}", order_item.id))
+                                       hx-get=(format!("/htmx/moje-konto/zamowienie-szczegoly/{}", order_item.id))
+                                       hx-target="#my-account-content" // Celuje w główny obszar treści "Moje Konto"
+                                       hx-swap="innerHTML"
+                                       hx-push-url=(format!("/moje-konto/zamowienia/{}", order_item.id))
+                                       class="text-sm text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline font-medium py-2 px-3 rounded-md hover:bg-[var(--color-secondary)] transition-colors" {
+                                        "Zobacz szczegóły"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Moje zamówienia - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn my_account_data_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    let user_id = claims.sub;
+    tracing::info!("MAUD: Użytkownik ID {} żąda sekcji 'Moje dane'", user_id);
+
+    let shipping_details_option =
+        repo::users::shipping_details(&app_state.db_pool, user_id).await?;
+
+    let details = shipping_details_option.unwrap_or_else(|| UserShippingDetails {
+        user_id,
+        ..Default::default()
+    });
+
+    let countries = vec![
+        "Polska",
+        "Niemcy",
+        "Czechy",
+        "Słowacja",
+        "Wielka Brytania",
+        "Francja",
+        "Hiszpania",
+        "Holandia",
+        "Włochy",
+    ];
+
+    let page_content = html! {
+        div #my-data-section { // Kontener dla tej sekcji
+            h2 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-6" { "Moje dane do wysyłki" }
+
+            // Miejsce na komunikaty (sukces/błąd) z HX-Trigger
+            div #my-data-messages ."mb-4 text-sm min-h-[1.25em]" {}
+
+            form id="user-shipping-details-form"
+                hx-post="/api/user/shipping-details"
+                hx-target="#my-data-messages"
+                hx-swap="none" // Lub "none" jeśli polegasz tylko na globalnym showMessage
+                class="space-y-6 bg-white p-6 rounded-lg shadow" {
+
+                // --- Imię ---
+                div {
+                    label for="shipping_first_name" ."block text-sm font-medium text-gray-700 mb-1" { "Imię" }
+                    input type="text" name="shipping_first_name" id="shipping_first_name"
+                           value=[details.shipping_first_name.as_deref()]
+                           maxlength="100"
+                           class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                }
+
+                // --- Nazwisko ---
+                div {
+                    label for="shipping_last_name" ."block text-sm font-medium text-gray-700 mb-1" { "Nazwisko" }
+                    input type="text" name="shipping_last_name" id="shipping_last_name"
+                           value=[details.shipping_last_name.as_deref()]
+                           maxlength="100"
+                           class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                }
+
+                // --- Adres linia 1 ---
+                div {
+                    label for="shipping_address_line1" ."block text-sm font-medium text-gray-700 mb-1" { "Adres (ulica i numer)" }
+                    input type="text" name="shipping_address_line1" id="shipping_address_line1"
+                           value=[details.shipping_address_line1.as_deref()]
+                           maxlength="255"
+                           class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                }
+
+                // --- Adres linia 2 (opcjonalnie) ---
+                div {
+                    label for="shipping_address_line2" ."block text-sm font-medium text-gray-700 mb-1" { "Adres cd. (opcjonalnie)" }
+                    input type="text" name="shipping_address_line2" id="shipping_address_line2"
+                           value=[details.shipping_address_line2.as_deref()]
+                           maxlength="255"
+                           class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                }
+
+                // --- Miasto i Kod pocztowy (w jednym rzędzie na większych ekranach) ---
+                div ."grid grid-cols-1 sm:grid-cols-2 gap-x-4 gap-y-6" {
+                    div {
+                        label for="shipping_city" ."block text-sm font-medium text-gray-700 mb-1" { "Miasto" }
+                        input type="text" name="shipping_city" id="shipping_city"
+                               value=[details.shipping_city.as_deref()]
+                               maxlength="100"
+                               class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                    }
+                    div {
+                        label for="shipping_postal_code" ."block text-sm font-medium text-gray-700 mb-1" { "Kod pocztowy" }
+                        input type="text" name="shipping_postal_code" id="shipping_postal_code"
+                               value=[details.shipping_postal_code.as_deref()]
+                               maxlength="20"
+                               class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                    }
+                }
+
+                // --- Kraj ---
+                div {
+                    label for="shipping_country" ."block text-sm font-medium text-gray-700 mb-1" { "Kraj" }
+                    select name="shipping_country" id="shipping_country"
+                           class="mt-1 block w-full px-3 py-2 border border-gray-300 bg-white rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm" {
+                        // Dla opcji "Wybierz kraj", chcemy ją wybrać i zablokować, jeśli żaden kraj nie jest jeszcze zapisany.
+                        option value=""
+                               disabled[details.shipping_country.is_none()]
+                               selected[details.shipping_country.is_none()] { "Wybierz kraj..." }
+
+                        @for country_name_str_slice in &countries { // country_name_str_slice jest &str (z Vec<&str>)
+                            option value=(country_name_str_slice)
+                                   selected[details.shipping_country.as_deref() == Some(country_name_str_slice)] {
+                                (country_name_str_slice)
+                            }
+                        }
+                        // Obsługa kraju, który jest zapisany w bazie, ale nie ma go na liście `countries`
+                        @if let Some(ref saved_country_string) = details.shipping_country { // saved_country_string jest &String
+                            @let saved_country_str = saved_country_string.as_str(); // Konwersja na &str
+                            @if !countries.iter().any(|&c| c == saved_country_str) { // Sprawdzenie czy &str jest w Vec<&str>
+                                option value=(saved_country_str) selected { (saved_country_str) " (inny)" }
+                            }
+                        }
+                    }
+                }
+
+                // --- Telefon ---
+                div {
+                    label for="shipping_phone" ."block text-sm font-medium text-gray-700 mb-1" { "Telefon" }
+                    input type="tel" name="shipping_phone" id="shipping_phone"
+                           value=[details.shipping_phone.as_deref()]
+                           maxlength="30"
+                           class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-md shadow-sm focus:outline-none focus:ring-pink-500 focus:border-pink-500 sm:text-sm";
+                }
+
+                // --- Przycisk Zapisz ---
+                div ."pt-4" {
+                    button type="submit"
+
+                           class="w-full sm:w-auto inline-flex justify-center items-center px-6 py-2 border border-transparent text-base font-medium rounded-md shadow-sm text-white bg-pink-600 hover:bg-pink-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500 transition-colors" {
+                        span { "Zapisz zmiany" }
+                        // Opcjonalny spinner dla przycisku (jeśli chcesz)
+                        // span class="htmx-indicator ml-2" { /* SVG spinnera */ }
+                    }
+                }
+            }
+        }
+    };
+    let title = "Moje konto - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn my_order_details_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let user_id = claims.sub;
+    let user_role = claims.role;
+
+    tracing::info!(
+        "MAUD: Użytkownik ID {} żąda szczegółów zamówienia ID {}",
+        user_id,
+        order_id
+    );
+
+    // 1. Pobierz zamówienie z bazy danych
+    let order_opt = repo::orders::find_by_id(&app_state.db_pool, order_id).await?;
+
+    let order = match order_opt {
+        Some(o) => o,
+        None => {
+            tracing::warn!(
+                "Nie znaleziono zamówienia o ID: {} (żąądane przez user_id: {})",
+                order_id,
+                user_id
+            );
+            return Err(AppError::NotFound);
+        }
+    };
+
+    // 2. Autoryzacja: Sprawdź, czy zalogowany użytkownik jest właścicielem zamówienia
+    if !user_role.has_permission(Permission::ManageOrders) && order.user_id != Some(user_id) {
+        // <--- POPRAWNA LOGIKA DLA ADMINA
+        tracing::warn!(
+            "Nieautoryzowany dostęp do zamówienia: order_id={}, user_id={}, user_role={:?}",
+            order_id,
+            user_id,
+            user_role
+        );
+        return Err(AppError::UnauthorizedAccess(
+            "Nie masz uprawnień do tego zamówienia".to_string(),
+        ));
+    }
+
+    // 3. Pobierz pozycje zamówienia (order_items)
+    let order_items_db = repo::orders::items(&app_state.db_pool, order_id).await?;
+
+    // 4. Przygotuj OrderItemDetailsPublic (pobierz produkty dla pozycji)
+    let mut items_details_public: Vec<OrderItemDetailsPublic> =
+        Vec::with_capacity(order_items_db.len());
+
+    if !order_items_db.is_empty() {
+        let product_ids: Vec<Uuid> = order_items_db.iter().map(|item| item.product_id).collect();
+
+        let products_db = repo::products::find_by_ids(&app_state.db_pool, &product_ids).await?;
+
+        let products_map: HashMap<Uuid, Product> =
+            products_db.into_iter().map(|p| (p.id, p)).collect();
+
+        for item_db in order_items_db {
+            if let Some(product) = products_map.get(&item_db.product_id) {
+                items_details_public.push(OrderItemDetailsPublic {
+                    order_item_id: item_db.id,
+                    product: product.clone(), // Klonujemy produkt
+                    price_at_purchase: item_db.price_at_purchase,
+                });
+            } else {
+                // Ta sytuacja nie powinna mieć miejsca, jeśli dane są spójne (produkt istnieje)
+                tracing::error!(
+                    "Krytyczny błąd: Produkt (ID: {}) dla pozycji zamówienia (ID: {}) nie został znaleziony. OrderID: {}.",
+                    item_db.product_id,
+                    item_db.id,
+                    order_id
+                );
+                // Można zwrócić błąd lub pominąć tę pozycję
+            }
+        }
+    }
+
+    let status_history = fetch_order_status_history(&app_state.db_pool, order_id).await?;
+    let is_owner = order.user_id == Some(user_id);
+    // Przycisk zwrotu tylko dla właściciela, w terminie i gdy zostało coś do zwrotu
+    let return_available_until = match return_deadline(&app_state.db_pool, &order).await? {
+        Some(deadline)
+            if deadline > Utc::now()
+                && is_owner
+                && !returnable_order_item_ids(&app_state.db_pool, order_id)
+                    .await?
+                    .is_empty() =>
+        {
+            Some(deadline)
+        }
+        _ => None,
+    };
+    let complaint_available = is_owner
+        && complaint_window_open(&app_state.db_pool, &order).await?
+        && !complaintable_order_item_ids(&app_state.db_pool, order_id)
+            .await?
+            .is_empty();
+    let reviews = match order.user_id {
+        Some(owner_id) => reviews_for_order(&app_state.db_pool, order_id, owner_id).await?,
+        None => Vec::new(),
+    };
+    let after_sales = OrderAfterSales {
+        returns: returns_for_order(&app_state.db_pool, order_id).await?,
+        return_available_until,
+        complaints: complaints_for_order(&app_state.db_pool, order_id).await?,
+        complaint_available,
+        reviews,
+        reviews_available: is_owner && order.status == OrderStatus::Delivered,
+    };
+    let has_invoice = find_invoice_for_order(&app_state.db_pool, order_id)
+        .await?
+        .is_some();
+    let page_content = render_customer_order_details_maud(
+        &order,
+        &items_details_public,
+        &status_history,
+        &after_sales,
+        has_invoice,
+        OrderDetailsViewer::Account,
+    );
+
+    let title = format!(
+        "Szczegóły zamówienia: {} sklep mess - all that vintage",
+        order.order_number
+    );
+    let page_builder = PageBuilder::new(&title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Kto ogląda szczegóły zamówienia - od tego zależą linki (konto klienta albo strona dla gości)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OrderDetailsViewer {
+    Account,
+    Guest,
+}
+
+/// Zwroty i reklamacje zamówienia, pokazywane pod historią statusów
+struct OrderAfterSales {
+    returns: Vec<ReturnDetails>,
+    /// Termin zgłoszenia zwrotu - tylko gdy klient może go jeszcze zgłosić
+    return_available_until: Option<DateTime<Utc>>,
+    complaints: Vec<Complaint>,
+    complaint_available: bool,
+    /// Opinie klienta o produktach z zamówienia
+    reviews: Vec<ProductReview>,
+    /// Czy klient może ocenić produkty (zamówienie doręczone, oglądane z konta)
+    reviews_available: bool,
+}
+
+/// Szczegóły zamówienia widziane przez klienta: w "Moje konto" i na stronie statusu dla gości.
+fn render_customer_order_details_maud(
+    order: &Order,
+    items_details_public: &[OrderItemDetailsPublic],
+    status_history: &[OrderStatusHistory],
+    after_sales: &OrderAfterSales,
+    has_invoice: bool,
+    viewer: OrderDetailsViewer,
+) -> Markup {
+    // Dane do wyświetlenia
+    let order_date_display = format_datetime_long(&order.order_date);
+    let order_status_display = order.status.to_string();
+    let order_total_display = format_price_maud(order.total_price);
+
+    let status_classes = match order.status {
+        OrderStatus::Pending => "bg-yellow-100 text-yellow-800",
+        OrderStatus::Processing => "bg-blue-100 text-blue-800",
+        OrderStatus::Shipped => "bg-green-100 text-green-800",
+        OrderStatus::Delivered => "bg-emerald-100 text-emerald-800",
+        OrderStatus::Cancelled => "bg-red-100 text-red-800",
+    };
+
+    html! {
+        div #order-details-section {
+            div ."flex justify-between items-center mb-6 pb-4 border-b border-gray-200" {
+                h2 ."text-2xl sm:text-3xl font-semibold text-gray-800" {
+                    "Szczegóły zamówienia " (order.order_number)
+                }
+                @if viewer == OrderDetailsViewer::Account {
+                    @if has_invoice {
+                        a href=(format!("/moje-konto/zamowienia/{}/faktura", order.id))
+                           target="_blank" rel="noopener"
+                           class="text-sm text-gray-700 hover:text-[var(--text-color-primary)] hover:underline" {
+                            "Pobierz fakturę (PDF)"
+                        }
+                    }
+                    a href="/moje-konto/zamowienia"
+                       hx-get="/htmx/moje-konto/zamowienia"
+                       hx-target="#my-account-content"
+                       hx-swap="innerHTML"
+                       hx-push-url="/moje-konto/zamowienia"
+                       class="text-sm text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline" {
+                        "← Wróć do listy zamówień"
+                    }
+                } @else {
+                    a href="/zamowienie/status"
+                       hx-get="/zamowienie/status"
+                       hx-target="#content"
+                       hx-swap="innerHTML"
+                       hx-push-url="/zamowienie/status"
+                       class="text-sm text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline" {
+                        "← Sprawdź inne zamówienie"
+                    }
+                }
+            }
+
+            // Podstawowe informacje o zamówieniu
+            div ."grid grid-cols-1 md:grid-cols-2 gap-6 mb-6" {
+                div ."space-y-2" {
+                    p ."text-sm text-gray-600" { "Data złożenia:" strong ."text-gray-900 ml-1" { (order_date_display) } }
+                    p ."text-sm text-gray-600" { "Status:"
+                        span class=(format!("ml-1 px-2 py-0.5 text-xs font-semibold rounded-full {}", status_classes)) {
+                            (order_status_display)
+                        }
+                    }
+                    p ."text-sm text-gray-600" { "Suma zamówienia:" strong ."text-[var(--text-color-primary)] font-semibold ml-1" { (order_total_display) } }
+                    @if order.discount_amount > 0 {
+                        p ."text-sm text-gray-600" { "Rabat:" strong ."text-green-700 ml-1" { "-" (format_price_maud(order.discount_amount)) } }
+                    }
+                    @if order.gift_card_amount > 0 {
+                        p ."text-sm text-gray-600" { "Karta podarunkowa:" strong ."text-green-700 ml-1" { "-" (format_price_maud(order.gift_card_amount)) } }
+                    }
+                    p ."text-sm text-gray-600" { "Forma płatności:"
+                        strong ."text-gray-900 ml-1" {
+                            @if let Some(pm) = &order.payment_method {
+                                (pm.to_string()) // Użyje implementacji Display z Strum (np. "BLIK", "Przelew tradycyjny")
+                            } @else {
+                                "Nie określono"
+                            }
+                        }
+                    }
+                    @if let Some(shipping_name) = &order.shipping_method_name {
+                            p ."text-sm text-gray-600" { "Metoda dostawy:"
+                                strong ."text-gray-900 ml-1" { (shipping_name) }
+                            }
+                        }
+                    @if let Some(locker_code) = &order.inpost_locker_code {
+                        p ."text-sm text-gray-600" { "Paczkomat:"
+                            strong ."text-gray-900 ml-1 font-mono" { (locker_code) }
+                        }
+                        @if let Some(locker_address) = &order.inpost_locker_address {
+                            p ."text-xs text-gray-500" { (locker_address) }
+                        }
+                    }
+                    }
+
+                // Adres dostawy
+                div {
+                    h3 ."text-md font-semibold text-gray-700 mb-1" { "Adres dostawy:" }
+                    p ."text-sm text-gray-800" {
+                        (order.shipping_first_name) " " (order.shipping_last_name) br;
+                        (order.shipping_address_line1) br;
+                        @if let Some(line2) = &order.shipping_address_line2 {
+                            (line2) br;
+                        }
+                        (order.shipping_postal_code) " " (order.shipping_city) br;
+                        (order.shipping_country) br;
+                        "Tel: " (order.shipping_phone)
+
+                    }
+                }
+            }
+
+            // Historia statusów zamówienia
+            h3 ."text-xl font-semibold text-gray-700 mb-3 mt-8 pt-4 border-t border-gray-200" { "Historia zamówienia:" }
+            (render_order_status_timeline(status_history, false))
+
+            // Zwroty (odstąpienie od umowy)
+            @if !after_sales.returns.is_empty() || after_sales.return_available_until.is_some() {
+                h3 ."text-xl font-semibold text-gray-700 mb-3 mt-8 pt-4 border-t border-gray-200" { "Zwroty:" }
+                div #return-request-section ."mb-4" {
+                    @if let Some(deadline) = after_sales.return_available_until {
+                        div ."flex flex-col sm:flex-row sm:items-center sm:justify-between gap-3 p-4 bg-gray-50 rounded-lg border border-gray-200" {
+                            p ."text-sm text-gray-700" {
+                                "Możesz odstąpić od umowy i zwrócić produkty do " strong { (format_date(&deadline)) } "."
+                            }
+                            button type="button"
+                                   hx-get=(format!("/htmx/moje-konto/zamowienia/{}/zwrot", order.id))
+                                   hx-target="#return-request-section"
+                                   hx-swap="innerHTML"
+                                   class="shrink-0 px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                                "Zgłoś zwrot"
+                            }
+                        }
+                    }
+                }
+                div ."space-y-3" {
+                    @for return_details in &after_sales.returns {
+                        (render_customer_return_maud(return_details))
+                    }
+                }
+            }
+
+            // Reklamacje
+            @if !after_sales.complaints.is_empty() || after_sales.complaint_available {
+                h3 ."text-xl font-semibold text-gray-700 mb-3 mt-8 pt-4 border-t border-gray-200" { "Reklamacje:" }
+                div #complaint-section ."mb-4" {
+                    @if after_sales.complaint_available {
+                        div ."flex flex-col sm:flex-row sm:items-center sm:justify-between gap-3 p-4 bg-gray-50 rounded-lg border border-gray-200" {
+                            p ."text-sm text-gray-700" {
+                                "Produkt ma wadę, której nie było w opisie? Złóż reklamację - odpowiemy w ciągu 14 dni."
+                            }
+                            button type="button"
+                                   hx-get=(format!("/htmx/moje-konto/zamowienia/{}/reklamacja", order.id))
+                                   hx-target="#complaint-section"
+                                   hx-swap="innerHTML"
+                                   class="shrink-0 px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                                "Złóż reklamację"
+                            }
+                        }
+                    }
+                }
+                div ."space-y-3" {
+                    @for complaint in &after_sales.complaints {
+                        (render_customer_complaint_maud(complaint))
+                    }
+                }
+            }
+
+            // Lista produktów w zamówieniu
+            h3 ."text-xl font-semibold text-gray-700 mb-3 mt-8 pt-4 border-t border-gray-200" { "Zamówione produkty:" }
+            @if items_details_public.is_empty() {
+                p ."text-gray-500" { "Brak produktów w tym zamówieniu (to nie powinno się zdarzyć, jeśli zamówienie istnieje)." }
+            } @else {
+                ul role="list" ."divide-y divide-gray-200 border-b border-gray-200" {
+                    @for item_detail in items_details_public.iter() {
+                        // Na koncie klienta link powrotny wraca do szczegółów zamówienia, tak jak w panelu admina
+                        @let (product_hx_get, product_target) = match viewer {
+                            OrderDetailsViewer::Account => {
+                                let return_url_unencoded = format!("/htmx/moje-konto/zamowienie-szczegoly/{}", order.id);
+                                (
+                                    format!("/htmx/produkt/{}?return_url={}&return_text={}&return_target={}",
+                                        item_detail.product.slug,
+                                        urlencoding::encode(&return_url_unencoded),
+                                        urlencoding::encode("Wróć do szczegółów zamówienia"),
+                                        urlencoding::encode("#my-account-content")),
+                                    "#my-account-content",
+                                )
+                            }
+                            OrderDetailsViewer::Guest => (format!("/htmx/produkt/{}", item_detail.product.slug), "#content"),
+                        };
+
+                        li ."py-4 flex items-center" {
+                            // KROK 1: Opakowujemy obrazek w klikalny link
+                            a href=(item_detail.product.public_path())
+                               hx-get=(product_hx_get)
+                               hx-target=(product_target)
+                               hx-swap="innerHTML"
+                               hx-push-url=(item_detail.product.public_path())
+                               class="block group" {
+                                @if !item_detail.product.images.is_empty() {
+                                    img src=(item_detail.product.images[0]) alt=(item_detail.product.name)
+                                         class="h-16 w-16 sm:h-20 sm:w-20 flex-shrink-0 rounded-md border border-gray-200 object-cover mr-4 group-hover:opacity-85 transition-opacity";
+                                } @else {
+                                    div class="h-16 w-16 sm:h-20 sm:w-20 flex-shrink-0 rounded-md border border-gray-200 bg-gray-100 flex items-center justify-center text-xs text-gray-400 mr-4 group-hover:opacity-85 transition-opacity" {
+                                        "Brak zdjęcia"
+                                    }
+                                }
+                            }
+
+                            div ."flex-grow min-w-0" {
+                                // KROK 2: Opakowujemy nazwę produktu w klikalny link
+                                a href=(item_detail.product.public_path())
+                                   hx-get=(product_hx_get)
+                                   hx-target=(product_target)
+                                   hx-swap="innerHTML"
+                                   hx-push-url=(item_detail.product.public_path())
+                                   class="text-sm font-medium text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline block truncate" {
+                                    (item_detail.product.name)
+                                }
+                                p ."text-xs text-gray-500" { "Kategoria: " (item_detail.product.category.to_string()) }
+                                p ."text-xs text-gray-500" { "Stan: " (item_detail.product.condition.to_string()) }
+                                @let review = after_sales.reviews.iter().find(|review| review.product_id == item_detail.product.id);
+                                @if review.is_some() || after_sales.reviews_available {
+                                    div id=(format!("review-slot-{}", item_detail.product.id)) ."mt-2" {
+                                        @if let Some(review) = review {
+                                            (render_customer_review_maud(review))
+                                        } @else {
+                                            button type="button"
+                                                   hx-get=(format!("/htmx/moje-konto/opinie/{}", item_detail.product.id))
+                                                   hx-target=(format!("#review-slot-{}", item_detail.product.id))
+                                                   hx-swap="innerHTML"
+                                                   class="text-xs font-medium text-pink-700 hover:underline" {
+                                                "Oceń produkt"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            div ."ml-4 text-right" {
+                                p ."text-sm text-gray-700" { "Cena (zakup): " strong{ (format_price_maud(item_detail.price_at_purchase)) } }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Zgłoszony zwrot widziany przez klienta (szczegóły zamówienia, odpowiedź na zgłoszenie)
+pub fn render_customer_return_maud(details: &ReturnDetails) -> Markup {
+    let request = &details.request;
+    html! {
+        div ."p-4 rounded-lg border border-gray-200 text-sm" {
+            div ."flex justify-between items-center mb-2" {
+                span ."font-semibold text-gray-800" { "Zwrot " (return_reference(request)) }
+                span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", return_status_badge_classes(request.status))) {
+                    (request.status.to_string())
+                }
+            }
+            ul ."text-gray-700 mb-2" {
+                @for item in &details.items {
+                    li { (item.product_name) " - " (format_price_maud(item.amount)) }
+                }
+            }
+            p ."text-gray-600" { "Zgłoszono: " (format_date(&request.created_at)) }
+            @match request.status {
+                ReturnStatus::Requested => {
+                    @if let Some(label) = &details.label {
+                        p ."text-gray-600 mt-1" {
+                            @if label.emailed_at.is_some() {
+                                "Etykietę zwrotną InPost wysłaliśmy e-mailem - nadaj paczkę w dowolnym Paczkomacie."
+                            } @else {
+                                "Etykieta zwrotna InPost jest przygotowywana - wyślemy ją e-mailem."
+                            }
+                        }
+                    } @else {
+                        p ."text-gray-600 mt-1" { "Odeślij produkty na adres sklepu - po ich otrzymaniu damy znać o decyzji." }
+                    }
+                }
+                ReturnStatus::Approved => {
+                    p ."text-gray-600 mt-1" {
+                        "Kwota " strong { (format_price_maud(details.refund_amount())) } " zostanie przelana na konto " (request.bank_account) "."
+                    }
+                }
+                ReturnStatus::Rejected => {}
+            }
+            @if let Some(note) = &request.admin_note {
+                p ."text-gray-600 mt-1" { "Uwagi sklepu: " (note) }
+            }
+        }
+    }
+}
+
+/// GET /htmx/moje-konto/zamowienia/{order_id}/zwrot - formularz zgłoszenia zwrotu
+pub async fn return_request_form_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+) -> Result<Markup, AppError> {
+    let order_details =
+        crate::handlers::fetch_order_details_service(&app_state.db_pool, order_id).await?;
+    if order_details.order.user_id != Some(claims.sub) {
+        return Err(AppError::UnauthorizedAccess(
+            "Nie masz uprawnień do tego zamówienia".to_string(),
+        ));
+    }
+    match return_deadline(&app_state.db_pool, &order_details.order).await? {
+        Some(deadline) if deadline > Utc::now() => {}
+        _ => {
+            return Err(AppError::Conflict(
+                "Termin na odstąpienie od umowy minął albo zamówienie nie zostało jeszcze doręczone."
+                    .to_string(),
+            ));
+        }
+    }
+    let returnable_ids = returnable_order_item_ids(&app_state.db_pool, order_id).await?;
+
+    Ok(html! {
+        form hx-post=(format!("/api/moje-konto/zamowienia/{}/zwrot", order_id))
+             hx-target="#return-request-section"
+             hx-swap="innerHTML"
+             class="space-y-4 p-4 bg-gray-50 rounded-lg border border-gray-200" {
+            fieldset {
+                legend ."text-sm font-medium text-gray-700 mb-2" { "Które produkty zwracasz?" }
+                @for item in order_details.items.iter().filter(|item| returnable_ids.contains(&item.order_item_id)) {
+                    label ."flex items-center gap-2 text-sm text-gray-800 py-1" {
+                        input type="checkbox" name="order_item_ids" value=(item.order_item_id)
+                              class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                        (item.product.name) " - " (format_price_maud(item.price_at_purchase))
+                    }
+                }
+            }
+            div {
+                label for="return_reason" ."block text-sm font-medium text-gray-700" { "Powód zwrotu (opcjonalnie)" }
+                textarea #return_reason name="reason" rows="3" maxlength=(MAX_RETURN_REASON_LEN)
+                         class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500" {}
+            }
+            div {
+                label for="return_bank_account" ."block text-sm font-medium text-gray-700" { "Numer konta do zwrotu pieniędzy" }
+                input #return_bank_account name="bank_account" type="text" required inputmode="numeric"
+                       placeholder="PL00 0000 0000 0000 0000 0000 0000"
+                       class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500 font-mono";
+            }
+            @if let Some(return_label) = app_state.inpost_config.return_label.as_ref().filter(|_| return_labels_available(&app_state.inpost_config)) {
+                label ."flex items-start gap-2 text-sm text-gray-800" {
+                    input type="checkbox" name="return_label" value="on"
+                          class="mt-0.5 h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                    span {
+                        "Kup etykietę zwrotną InPost za " strong { (format_price_maud(return_label.price)) }
+                        " - wyślemy ją e-mailem, a koszt potrącimy z kwoty zwrotu. Paczkę nadasz w dowolnym Paczkomacie."
+                    }
+                }
+            }
+            p ."text-xs text-gray-500" {
+                "Po zgłoszeniu odeślij produkty na adres sklepu w ciągu 14 dni. Pieniądze zwrócimy po otrzymaniu i sprawdzeniu przesyłki."
+            }
+            button type="submit"
+                   class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                "Wyślij zgłoszenie zwrotu"
+            }
+        }
+    })
+}
+
+/// Reklamacja widziana przez klienta (szczegóły zamówienia, odpowiedź na zgłoszenie)
+pub fn render_customer_complaint_maud(complaint: &Complaint) -> Markup {
+    html! {
+        div ."p-4 rounded-lg border border-gray-200 text-sm" {
+            div ."flex justify-between items-center mb-2" {
+                span ."font-semibold text-gray-800" { "Reklamacja " (complaint_reference(complaint)) }
+                span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", complaint_status_badge_classes(complaint.status))) {
+                    (complaint.status.to_string())
+                }
+            }
+            p ."text-gray-800 font-medium" { (complaint.product_name) }
+            p ."text-gray-700 mt-1 whitespace-pre-line" { (complaint.description) }
+            (complaint_photos_maud(complaint))
+            p ."text-gray-600 mt-2" { "Złożono: " (format_date(&complaint.created_at)) }
+            @if !complaint.status.is_resolved() {
+                p ."text-gray-600" { "Odpowiemy najpóźniej do " (format_date(&response_deadline(complaint))) "." }
+            }
+            @if let Some(resolution) = &complaint.resolution {
+                p ."text-gray-600 mt-1" { "Odpowiedź sklepu: " (resolution) }
+            }
+        }
+    }
+}
+
+/// GET /htmx/moje-konto/zamowienia/{order_id}/reklamacja - formularz reklamacji
+pub async fn complaint_form_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+) -> Result<Markup, AppError> {
+    let order_details =
+        crate::handlers::fetch_order_details_service(&app_state.db_pool, order_id).await?;
+    if order_details.order.user_id != Some(claims.sub) {
+        return Err(AppError::UnauthorizedAccess(
+            "Nie masz uprawnień do tego zamówienia".to_string(),
+        ));
+    }
+    if !complaint_window_open(&app_state.db_pool, &order_details.order).await? {
+        return Err(AppError::Conflict(
+            "Do tego zamówienia nie można już złożyć reklamacji.".to_string(),
+        ));
+    }
+    let complaintable_ids = complaintable_order_item_ids(&app_state.db_pool, order_id).await?;
+
+    Ok(html! {
+        form hx-post=(format!("/api/moje-konto/zamowienia/{}/reklamacja", order_id))
+             hx-encoding="multipart/form-data"
+             hx-target="#complaint-section"
+             hx-swap="innerHTML"
+             hx-disabled-elt="find button[type='submit']"
+             class="space-y-4 p-4 bg-gray-50 rounded-lg border border-gray-200" {
+            fieldset {
+                legend ."text-sm font-medium text-gray-700 mb-2" { "Którego produktu dotyczy reklamacja?" }
+                @for item in order_details.items.iter().filter(|item| complaintable_ids.contains(&item.order_item_id)) {
+                    label ."flex items-center gap-2 text-sm text-gray-800 py-1" {
+                        input type="radio" name="order_item_id" value=(item.order_item_id) required
+                              class="h-4 w-4 border-gray-300 text-pink-600 focus:ring-pink-500";
+                        (item.product.name)
+                    }
+                }
+            }
+            div {
+                label for="complaint_description" ."block text-sm font-medium text-gray-700" { "Opis wady" }
+                textarea #complaint_description name="description" rows="4" required maxlength=(MAX_COMPLAINT_DESCRIPTION_LEN)
+                         placeholder="Opisz, co jest nie tak z produktem i kiedy to zauważono."
+                         class="mt-1 block w-full px-3 py-2 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500" {}
+            }
+            div {
+                label for="complaint_photos" ."block text-sm font-medium text-gray-700" {
+                    "Zdjęcia (opcjonalnie, maks. " (MAX_COMPLAINT_PHOTOS) ")"
+                }
+                input #complaint_photos name="photos" type="file" accept="image/*" multiple
+                       class="mt-1 block w-full text-sm text-gray-700 file:mr-3 file:py-2 file:px-4 file:rounded-md file:border-0 file:bg-pink-50 file:text-pink-700 hover:file:bg-pink-100";
+            }
+            button type="submit"
+                   class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md disabled:opacity-50" {
+                "Wyślij reklamację"
+            }
+        }
+    })
+}
+
+/// Opinia klienta widziana w szczegółach jego zamówienia
+pub fn render_customer_review_maud(review: &ProductReview) -> Markup {
+    html! {
+        div ."flex flex-wrap items-center gap-2 text-xs text-gray-600" {
+            (rating_stars_maud(f64::from(review.rating)))
+            span { "Twoja opinia: " (review.status.to_string().to_lowercase()) }
+        }
+    }
+}
+
+/// GET /htmx/moje-konto/opinie/{product_id} - formularz opinii o produkcie z doręczonego zamówienia
+pub async fn review_form_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(product_id): Path<Uuid>,
+) -> Result<Markup, AppError> {
+    if delivered_order_with_product(&app_state.db_pool, claims.sub, product_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::UnauthorizedAccess(
+            "Opinię możesz wystawić dopiero po doręczeniu zamówienia z tym produktem.".to_string(),
+        ));
+    }
+
+    Ok(html! {
+        form hx-post=(format!("/api/moje-konto/opinie/{}", product_id))
+             hx-target=(format!("#review-slot-{}", product_id))
+             hx-swap="innerHTML"
+             hx-disabled-elt="find button[type='submit']"
+             class="space-y-3 p-3 bg-gray-50 rounded-lg border border-gray-200" {
+            div {
+                label for=(format!("review-rating-{}", product_id)) ."block text-xs font-medium text-gray-700" { "Ocena" }
+                select name="rating" id=(format!("review-rating-{}", product_id)) required
+                       class="mt-1 block w-full px-3 py-2 text-sm border border-gray-300 rounded-lg focus:outline-none focus:ring-2 focus:ring-pink-500" {
+                    @for rating in (1..=5).rev() {
+                        option value=(rating) { ("★".repeat(rating)) " (" (rating) ")" }
+                    }
+                }
+            }
+            div {
+                label for=(format!("review-content-{}", product_id)) ."block text-xs font-medium text-gray-700" { "Opinia" }
+                textarea name="content" id=(format!("review-content-{}", product_id)) rows="3" required maxlength=(MAX_REVIEW_CONTENT_LEN)
+                         placeholder="Jak produkt sprawdza się w praktyce? Czy zgadza się z opisem?"
+                         class="mt-1 block w-full px-3 py-2 text-sm border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500" {}
+            }
+            p ."text-xs text-gray-500" { "Opinia pojawi się na stronie produktu po zatwierdzeniu przez sklep." }
+            button type="submit"
+                   class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md disabled:opacity-50" {
+                "Wyślij opinię"
+            }
+        }
+    })
+}
+
+/// Odwołanie do zamówienia sprzed wprowadzenia numerów: pełne ID, dawny tytuł przelewu
+/// ("MESS-1A2B3C4D") albo skrócony numer z e-maili (pierwsze 8 znaków ID).
+/// Zwraca prefiks ID bez myślników.
+fn normalize_guest_order_ref(order_ref: &str) -> Option<String> {
+    let order_ref = order_ref.trim();
+    let order_ref = order_ref
+        .strip_prefix("MESS-")
+        .or_else(|| order_ref.strip_prefix("mess-"))
+        .unwrap_or(order_ref)
+        .trim_start_matches('#');
+    if let Ok(id) = Uuid::parse_str(order_ref) {
+        return Some(id.simple().to_string());
+    }
+    let prefix = order_ref.to_lowercase();
+    (prefix.len() == 8 && prefix.chars().all(|c| c.is_ascii_hexdigit())).then_some(prefix)
+}
+
+fn render_guest_order_lookup_error_maud() -> Markup {
+    html! {
+        p ."p-3 bg-red-50 border border-red-200 rounded-lg text-sm text-red-700" {
+            "Nie znaleźliśmy zamówienia o tym numerze złożonego z podanego adresu e-mail. "
+            "Sprawdź numer w e-mailu z potwierdzeniem zamówienia."
+        }
+    }
+}
+
+/// GET /zamowienie/status - formularz sprawdzania zamówienia bez konta
+pub async fn guest_order_lookup_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+) -> Result<Response, AppError> {
+    let page_content = html! {
+        div ."max-w-3xl mx-auto px-4 py-10" {
+            div ."max-w-md mx-auto bg-white p-8 rounded-xl shadow-lg border border-gray-200 mb-8" {
+                div ."text-center mb-6" {
+                    h2 ."text-2xl font-bold text-gray-800" { "Sprawdź status zamówienia" }
+                    p ."text-sm text-gray-500 mt-2" {
+                        "Zamówienie bez zakładania konta? Podaj numer zamówienia z e-maila z potwierdzeniem i adres e-mail użyty przy zakupie."
+                    }
+                }
+                form hx-post="/api/zamowienie/status"
+                     hx-target="#guest-order-result"
+                     hx-swap="innerHTML"
+                     class="space-y-5" {
+                    div {
+                        label for="order_ref" ."block text-sm font-medium text-gray-700" { "Numer zamówienia" }
+                        input #order_ref name="order_ref" type="text" required autocomplete="off"
+                               placeholder="np. MESS-2025-00123"
+                               class="mt-1 block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500";
+                    }
+                    div {
+                        label for="guest_order_email" ."block text-sm font-medium text-gray-700" { "Adres e-mail" }
+                        input #guest_order_email name="email" type="email" required autocomplete="email"
+                               class="mt-1 block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500";
+                    }
+                    button type="submit"
+                           class="w-full flex justify-center py-3 px-4 border rounded-lg text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500" {
+                        "Sprawdź zamówienie"
+                    }
+                }
+            }
+            div #guest-order-result {}
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Status zamówienia - sklep mess - all that vintage",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// POST /api/zamowienie/status - szczegóły zamówienia gościa, jeśli numer i e-mail pasują.
+/// Przy braku dopasowania zawsze ten sam komunikat, bez zdradzania, czy zamówienie istnieje.
+pub async fn guest_order_lookup_handler(
+    State(app_state): State<Arc<AppState>>,
+    Form(payload): Form<GuestOrderLookupPayload>,
+) -> Result<Markup, AppError> {
+    let email = payload.email.trim();
+    let order_number = payload.order_ref.trim().trim_start_matches('#');
+    // Numer zamówienia albo - dla starszych zamówień - prefiks ID
+    let id_prefix = normalize_guest_order_ref(&payload.order_ref);
+    if email.is_empty() || order_number.is_empty() {
+        return Ok(render_guest_order_lookup_error_maud());
+    }
+
+    let order_id = repo::orders::find_guest_order_id(
+        &app_state.db_pool,
+        email,
+        order_number,
+        id_prefix.as_deref(),
+    )
+    .await?;
+    let Some(order_id) = order_id else {
+        tracing::info!(
+            "Nie znaleziono zamówienia gościa dla numeru {}",
+            order_number
+        );
+        return Ok(render_guest_order_lookup_error_maud());
+    };
+
+    let order_details =
+        crate::handlers::fetch_order_details_service(&app_state.db_pool, order_id).await?;
+    let status_history = fetch_order_status_history(&app_state.db_pool, order_id).await?;
+    // Zwroty i reklamacje zgłasza się z konta, gość widzi tylko ich status
+    let after_sales = OrderAfterSales {
+        returns: returns_for_order(&app_state.db_pool, order_id).await?,
+        return_available_until: None,
+        complaints: complaints_for_order(&app_state.db_pool, order_id).await?,
+        complaint_available: false,
+        reviews: Vec::new(),
+        reviews_available: false,
+    };
+    tracing::info!("Gość sprawdził status zamówienia {}", order_id);
+
+    Ok(html! {
+        div ."bg-white p-6 rounded-xl shadow-lg border border-gray-200" {
+            (render_customer_order_details_maud(
+                &order_details.order,
+                &order_details.items,
+                &status_history,
+                &after_sales,
+                false,
+                OrderDetailsViewer::Guest,
+            ))
+        }
+    })
+}
+
+/// Lejek konwersji (sesje → wyświetlenia → koszyk → kasa → zakup) z porównaniem tydzień do tygodnia
+/// oraz konwersja w podziale na kategorie z ostatnich 30 dni.
+/// Pasek trybu podglądu konta klienta (wczytywany przez szablon strony). Pusty poza podglądem.
+pub async fn impersonation_banner_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+) -> Result<Markup, AppError> {
+    let Some(claims) = user_claims_opt.filter(|claims| claims.impersonated_by.is_some()) else {
+        return Ok(html! {});
+    };
+    let customer_email = repo::users::email(&app_state.db_pool, claims.sub)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let expires_at = chrono::DateTime::from_timestamp(claims.exp, 0).unwrap_or_else(Utc::now);
+
+    Ok(html! {
+        div #impersonation-banner role="status"
+            class="sticky top-0 z-50 bg-red-600 text-white text-sm" {
+            div ."max-w-7xl mx-auto px-4 py-2 flex flex-wrap items-center justify-between gap-2" {
+                p {
+                    "Podgląd konta klienta " strong { (customer_email) }
+                    " - tylko do odczytu, wszystkie odsłony są zapisywane. Wygasa o "
+                    (to_shop_time(&expires_at).format("%H:%M")) "."
+                }
+                button type="button" hx-post="/api/impersonation/stop"
+                       class="px-3 py-1 bg-white text-red-700 font-semibold rounded hover:bg-red-50" {
+                    "Zakończ podgląd"
+                }
+            }
+        }
+    })
+}
+
+pub async fn forgot_password_form_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+) -> Result<Response, AppError> {
+    tracing::info!("MAUD: Żądanie strony 'Zapomniałem hasła'");
+    let page_content = html! {
+        div ."min-h-[60vh] flex items-center justify-center p-4 bg-gray-100" {
+            div ."w-full max-w-md bg-white p-8 rounded-xl shadow-lg border border-gray-200" {
+                div ."text-center mb-6" {
+                    h2 ."text-2xl font-bold text-gray-800" { "Resetowanie hasła" }
+                    p ."text-sm text-gray-500 mt-2" { "Podaj adres e-mail powiązany z Twoim kontem, a wyślemy Ci link do zresetowania hasła." }
+                }
+
+                div #forgot-password-messages ."mb-4 text-sm min-h-[1.25em]";
+
+                form #forgot-password-form
+                    hx-post="/api/auth/forgot-password"
+                    hx-target="#forgot-password-messages"
+                    hx-swap="innerHTML"
+                    class="space-y-6" {
+
+                    div {
+                        label for="email" ."block text-sm font-medium text-gray-700" { "Adres e-mail" }
+                        input #email name="email" type="email" autocomplete="email" required
+                               class="mt-1 block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500";
+                    }
+                    div {
+                        button type="submit"
+                               class="w-full flex justify-center py-3 px-4 border rounded-lg text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500 transition-transform hover:scale-105" {
+                            "Wyślij link do resetu hasła"
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Zapomniałem hasła - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Przypomnienie o niepotwierdzonym adresie e-mail (kasa, "Moje konto").
+pub fn render_email_verification_banner_maud(email: &str) -> Markup {
+    html! {
+        div ."mb-6 p-4 bg-yellow-50 border border-yellow-300 rounded-lg text-sm flex flex-col sm:flex-row sm:items-center sm:justify-between gap-3" role="status" {
+            p ."text-gray-800" {
+                "Potwierdź swój adres e-mail "
+                span ."font-semibold" { (email) }
+                " - link wysłaliśmy po rejestracji. Dzięki temu na pewno dotrą do Ciebie informacje o zamówieniach."
+            }
+            button type="button"
+                   hx-post="/api/auth/resend-verification"
+                   hx-swap="none"
+                   class="shrink-0 px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                "Wyślij link ponownie"
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct EmailVerificationResultQuery {
+    pub status: Option<String>,
+}
+
+/// Strona z wynikiem kliknięcia w link potwierdzający adres e-mail.
+pub async fn email_verification_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    Query(query): Query<EmailVerificationResultQuery>,
+) -> Result<Response, AppError> {
+    let (heading, message, success) = match query.status.as_deref() {
+        Some("potwierdzony") => (
+            "Adres e-mail potwierdzony",
+            "Dziękujemy! Twój adres e-mail został potwierdzony.",
+            true,
+        ),
+        Some("wygasl") => (
+            "Link wygasł",
+            "Link potwierdzający jest już nieważny. Zaloguj się i wyślij nowy z zakładki „Moje konto”.",
+            false,
+        ),
+        _ => (
+            "Nieprawidłowy link",
+            "Ten link jest nieprawidłowy albo został już użyty. Zaloguj się i wyślij nowy z zakładki „Moje konto”.",
+            false,
+        ),
+    };
+
+    let page_content = html! {
+        div ."min-h-[60vh] flex items-center justify-center p-4" {
+            div ."w-full max-w-md bg-white p-8 rounded-xl shadow-lg text-center" {
+                h2 ."text-2xl font-bold mb-4" .(if success { "text-green-700" } else { "text-gray-900" }) { (heading) }
+                p ."text-gray-600 mb-6" { (message) }
+                a href="/moje-konto" hx-get="/htmx/moje-konto" hx-target="#content" hx-swap="innerHTML" hx-push-url="/moje-konto"
+                   class="inline-block bg-pink-600 hover:bg-pink-700 text-white font-medium py-2 px-6 rounded-lg" {
+                    "Przejdź do konta"
+                }
+            }
+        }
+    };
+
+    let title = "Potwierdzenie adresu e-mail - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+#[derive(Deserialize)]
+pub struct ResetTokenQuery {
+    pub token: String,
+}
+
+pub async fn reset_password_form_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<ResetTokenQuery>,
+) -> Result<Response, AppError> {
+    let token_uuid = match Uuid::from_str(&query.token) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return Err(AppError::InvalidToken(
+                "Format tokenu jest nieprawidłowy".into(),
+            ));
+        }
+    };
+
+    let title = "Resetowanie hasła - sklep mess - all that vitnage";
+
+    // Walidacja tokenu
+    match sqlx::query_as::<_, PasswordResetToken>("SELECT * FROM password_resets WHERE token = $1")
+        .bind(token_uuid)
+        .fetch_optional(&app_state.db_pool)
+        .await?
+    {
+        Some(token_data) if token_data.expires_at > Utc::now() => {
+            // Token jest poprawny i nie wygasł - renderuj formularz
+            let page_content = html! {
+                div ."min-h-[60vh] flex items-center justify-center p-4 bg-gray-100" {
+                    div ."w-full max-w-md bg-white p-8 rounded-xl shadow-lg" {
+                        h2 ."text-2xl font-bold text-center mb-6" { "Ustaw nowe hasło" }
+                        div #reset-password-messages ."mb-4 text-sm min-h-[1.25em]";
+                        form #reset-password-form
+                            hx-post="/api/auth/reset-password"
+                            hx-target="#reset-password-messages"
+                            hx-swap="innerHTML" {
+                            input type="hidden" name="token" value=(query.token);
+                            div class="space-y-4" {
+                                div {
+                                    label for="new_password" ."block text-sm font-medium" { "Nowe hasło" }
+                                    input #new_password name="new_password" type="password" required minlength="8" class="mt-1 block w-full px-3 py-2 border rounded-md";
+                                }
+                                div {
+                                    label for="confirm_password" ."block text-sm font-medium" { "Potwierdź nowe hasło" }
+                                    input #confirm_password name="confirm_password" type="password" required class="mt-1 block w-full px-3 py-2 border rounded-md";
+                                }
+                                button type="submit" class="w-full py-3 px-4 border rounded-lg text-white bg-pink-600 hover:bg-pink-700" { "Zmień hasło" }
+                            }
+                        }
+                    }
+                }
+            };
+
+            let page_builder = PageBuilder::new(title, page_content, None, None);
+            build_response(headers, &csp_nonce, page_builder).await
+        }
+        _ => {
+            // Token nie istnieje lub wygasł
+            let error_content = html! {
+                p class="text-red-600 text-center" { "Ten link do resetowania hasła jest nieprawidłowy lub wygasł. Poproś o nowy." }
+            };
+            let page_builder = PageBuilder::new(title, error_content, None, None);
+            build_response(headers, &csp_nonce, page_builder).await
+        }
+    }
+}
@@ -0,0 +1,5580 @@
+// src/views/admin.rs
+
+// Panel administratora: produkty, zamówienia, klienci, posprzedaż, kupony, raporty i narzędzia serwisowe.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::Form;
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use maud::{Markup, PreEscaped, html};
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+use crate::api_keys::list_api_keys;
+use crate::auth_models::TokenClaims;
+use crate::cache_stats::{CacheOverview, cache_overview};
+use crate::care_instructions::list_care_instructions;
+use crate::cms::{
+    MAX_STATIC_PAGE_BODY_LEN, MAX_STATIC_PAGE_TITLE_LEN, StaticPage, find_static_page,
+    list_static_pages, published_static_page,
+};
+use crate::complaints::{complaint_reference, list_complaints, response_deadline};
+use crate::coupon_campaigns::list_campaigns;
+use crate::customer_profiles::{
+    MAX_CUSTOMER_NOTE_LEN, fetch_customer_profile, list_customer_notes,
+};
+use crate::customer_segments::{CustomerSegment, customer_search, customer_tags, list_customers};
+use crate::date_format::{
+    format_date, format_datetime, format_datetime_admin, format_datetime_input, format_local_date,
+    format_local_day_month, to_shop_time,
+};
+use crate::duplicates::DuplicateCandidate;
+use crate::errors::{AppError, ValidationErrors};
+use crate::faq::{
+    FREE_SHIPPING_PLACEHOLDER, FaqItem, MAX_FAQ_ANSWER_LEN, MAX_FAQ_QUESTION_LEN, list_faq_items,
+};
+use crate::filters::{ListingParams, OrderListingParams, SalesDashboardParams};
+use crate::gift_cards::{GiftCard, list_gift_cards};
+use crate::holds::{find_hold_for_product, hold_link};
+use crate::homepage::{
+    HomepageSectionConfig, MAX_FEATURED_PRODUCTS, MAX_HERO_TEXT_LEN, featured_products,
+    list_homepage_sections,
+};
+use crate::image_hash::find_similar_products;
+use crate::impersonation::IMPERSONATION_MINUTES;
+use crate::jobs::{JOBS, job_log_entries};
+use crate::login_lockout::{LOCKOUT_MINUTES, MAX_FAILED_LOGIN_ATTEMPTS, list_active_lockouts};
+use crate::models::{
+    AdminComplaintsQuery, AdminNotification, AdminNotificationLevel, AdminReturnsQuery,
+    AdminReviewsQuery, AdminUsersParams, ApiKey, Category, Complaint, ComplaintStatus, Coupon,
+    CouponDiscountType, CustomerFlag, CustomerFlagType, CustomerSegmentParams, CustomerTag,
+    HomepageSection, ImpersonationEvent, ImpersonationSessionSummary, OrderDetailsResponse,
+    OrderRefund, OrderRiskAssessment, OrderStatus, OrderWithCustomerInfo, PaginationItem, Product,
+    ProductBulkAction, ProductBulkOutcome, ProductCondition, ProductGender, ProductHold,
+    ProductImageIssue, ProductReview, ProductStatus, ReturnStatus, ReviewStatus, Role,
+    RumReportQuery, StaticPagePayload,
+};
+use crate::pagination::{PaginatedOrdersResponse, PaginatedProductsResponse};
+use crate::permissions::Permission;
+use crate::plural::{orders_count, pluralize, products_count};
+use crate::product_views::{POPULAR_PRODUCTS_LIMIT, POPULARITY_WINDOW_DAYS};
+use crate::redirects::{MAX_REDIRECT_PATH_LEN, REDIRECT_STATUS_CODES, list_redirects};
+use crate::repo;
+use crate::response::{PageBuilder, build_response};
+use crate::returns::{ReturnDetails, list_returns, return_reference};
+use crate::reviews::list_reviews;
+use crate::risk::HIGH_RISK_THRESHOLD;
+use crate::rum::{RUM_RETENTION_DAYS, VitalRating, fetch_rum_route_stats, fetch_rum_weekly_trend};
+use crate::sales_register::parse_register_month;
+use crate::security_headers::CspNonce;
+use crate::services::{
+    fetch_category_conversion, fetch_funnel_stats, fetch_order_status_history,
+    fetch_reservation_conversion, fetch_sales_summary, fetch_sales_timeline, fetch_top_categories,
+    find_customer_flags_for_order,
+};
+use crate::sizes::{SIZE_SYSTEMS, list_size_mappings};
+use crate::sla::SlaStage;
+use crate::state::AppState;
+use crate::user_management::{UserListFilter, list_users};
+
+use super::common::{
+    build_full_query_string_from_params, complaint_photos_maud, complaint_status_badge_classes,
+    format_price_maud, generate_pagination_items, get_order_status_badge_classes,
+    rating_stars_maud, render_order_status_timeline, return_status_badge_classes,
+    transform_cloudinary_url,
+};
+use super::shop::{
+    PHOTO_SEARCH_ADMIN_LIMIT, PHOTO_SEARCH_THUMBNAIL_TRANSFORMATION, image_similarity_percent,
+    photo_search_form_maud, photo_search_hash, photo_search_message_maud,
+};
+use super::static_pages::{BUILT_IN_PAGES, built_in_page, render_cms_page};
+
+const DUPLICATE_THUMBNAIL_TRANSFORMATION: &str = "w_96,h_96,c_fill,f_auto,q_auto:good";
+
+/// Ostrzeżenie nad formularzem nowego produktu: podobne dostępne produkty i przycisk,
+/// który wysyła formularz ponownie z potwierdzeniem (`confirm_duplicate`).
+pub fn render_duplicate_warning_maud(candidates: &[DuplicateCandidate]) -> Markup {
+    html! {
+        div role="alert" class="p-4 bg-yellow-50 border border-yellow-300 rounded-md text-sm text-yellow-800" {
+            p ."font-semibold" { "Ten produkt może być już wystawiony:" }
+            ul class="mt-3 space-y-2" {
+                @for candidate in candidates {
+                    li class="flex items-center gap-3" {
+                        @if let Some(image_url) = &candidate.image {
+                            img src=(transform_cloudinary_url(image_url, DUPLICATE_THUMBNAIL_TRANSFORMATION))
+                                alt=(candidate.name) class="w-12 h-12 object-cover rounded border border-yellow-200" loading="lazy";
+                        }
+                        div {
+                            a href=(format!("/htmx/admin/products/{}/edit", candidate.product_id))
+                              target="_blank" rel="noopener"
+                              class="font-medium text-yellow-900 underline hover:text-yellow-700" {
+                                (candidate.name)
+                            }
+                            p class="text-xs text-yellow-700" {
+                                @if let Some(distance) = candidate.image_distance {
+                                    @if distance == 0 { "Identyczne zdjęcie" } @else { "Bardzo podobne zdjęcie" }
+                                }
+                                @if candidate.image_distance.is_some() && candidate.name_similarity.is_some() { " · " }
+                                @if let Some(similarity) = candidate.name_similarity {
+                                    "Podobna nazwa (" (format!("{:.0}", similarity * 100.0)) "%)"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div class="mt-4 flex flex-wrap items-center gap-3" {
+                button type="button" x-data
+                       "@click"="const form = document.querySelector('#admin-product-form-container form'); form.querySelector('[name=confirm_duplicate]').value = 'true'; form.requestSubmit()"
+                       class="px-4 py-2 text-sm font-medium text-white bg-yellow-600 rounded-md hover:bg-yellow-700" {
+                    "To inny produkt - zapisz mimo to"
+                }
+                span class="text-xs text-yellow-700" { "Albo popraw formularz, jeśli to pomyłka." }
+            }
+        }
+    }
+}
+
+// REFAKTORYZACJA: Nowa, reużywalna funkcja do renderowania formularza produktu
+fn render_product_form_maud(
+    product_opt: Option<&Product>,
+    app_state: &AppState,
+) -> Result<Markup, AppError> {
+    let is_new = product_opt.is_none();
+    let description_assistant_enabled = app_state.description_assistant.is_some();
+    let attribute_suggestions_enabled = app_state.cloudinary_config.tagging_addon.is_some();
+    let default_product = Product {
+        id: Uuid::new_v4(),
+        name: "".to_string(),
+        slug: "".to_string(),
+        description: "".to_string(),
+        price: 0,
+        gender: ProductGender::Damskie,
+        condition: ProductCondition::VeryGood,
+        category: Category::Inne,
+        status: ProductStatus::Available,
+        images: vec![],
+        on_sale: false,
+        size: None,
+        brand: None,
+        color: None,
+        material: None,
+        meta_title: None,
+        meta_description: None,
+        og_image: None,
+        sale_price: None,
+        sale_starts_at: None,
+        sale_ends_at: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    let product = product_opt.unwrap_or(&default_product);
+
+    let form_title = if is_new {
+        "Dodaj Nowy Produkt"
+    } else {
+        "Edytuj Produkt"
+    };
+    let form_action = if is_new {
+        "/api/products".to_string()
+    } else {
+        format!("/api/products/{}", product.id)
+    };
+
+    let initial_images_json =
+        serde_json::to_string(&product.images).unwrap_or_else(|_| "[]".to_string());
+    let current_status_str = product.status.as_ref().to_string();
+    // Puste miejsca na komunikaty przy polach, wypełniane przez odpowiedź 422 (`hx-swap-oob`)
+    let no_errors = ValidationErrors::new();
+
+    let form_body = html! {
+        // Wszystkie pola formularza idą tutaj
+        input type="hidden" name="urls_to_delete" id="urls_to_delete_hidden_input";
+        @if is_new {
+            // Ustawiane przez ostrzeżenie o duplikacie ("zapisz mimo to")
+            input type="hidden" name="confirm_duplicate" value="";
+        }
+        section {
+            h3 ."text-xl font-semibold text-gray-700 mb-4 pb-2 border-b border-gray-200" { "Dane Podstawowe" }
+            div ."space-y-5" {
+                div {
+                    label for="name" ."block text-sm font-medium text-gray-700 mb-1" { "Nazwa produktu *" }
+                    input type="text" name="name" id="name" required value=(product.name) class="admin-filter-input";
+                    (no_errors.field_error_maud("name"))
+                }
+                div {
+                    label for="description" ."block text-sm font-medium text-gray-700 mb-1" { "Opis produktu *" }
+                    textarea name="description" id="description" rows="6" required class="admin-filter-input" { (product.description) }
+                    (no_errors.field_error_maud("description"))
+                    @if description_assistant_enabled {
+                        // Asystent opisów: szkic trafia tylko do pola tekstowego, zapis wymaga kliknięcia "Zapisz"
+                        div ."mt-2 p-3 bg-gray-50 rounded-lg border border-gray-200" {
+                            div ."grid grid-cols-1 sm:grid-cols-2 gap-3 mb-2" {
+                                input type="text" id="draft_brand" placeholder="Marka (opcjonalnie, tylko dla asystenta)" class="admin-filter-input";
+                                input type="text" id="draft_measurements" placeholder="Wymiary, np. pacha-pacha 52 cm, długość 70 cm" class="admin-filter-input";
+                            }
+                            div ."flex items-center gap-3" {
+                                button type="button"
+                                       "@click.prevent"="draftDescription()"
+                                       "x-bind:disabled"="isDrafting"
+                                       class="px-4 py-2 text-sm font-medium text-pink-700 bg-white border border-pink-300 rounded-lg hover:bg-pink-50 disabled:opacity-50 disabled:cursor-wait" {
+                                    span x-show="!isDrafting" { "Zaproponuj opis" }
+                                    span x-show="isDrafting" { "Generowanie..." }
+                                }
+                                p ."text-xs text-gray-500" { "Szkic zastąpi treść pola. Przejrzyj go przed zapisaniem produktu." }
+                            }
+                        }
+                    }
+                }
+                div {
+                    label for="price" ."block text-sm font-medium text-gray-700 mb-1" { "Cena (w groszach) *" }
+                    input type="number" name="price" id="price" required min="0" step="1" value=(product.price) class="admin-filter-input";
+                    (no_errors.field_error_maud("price"))
+                }
+            }
+        }
+
+        section {
+            h3 ."text-xl font-semibold text-gray-700 mb-4 pb-2 border-b border-gray-200" { "Atrybuty" }
+            div ."grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-x-6 gap-y-5" {
+                @for (field, label, placeholder, value) in [
+                    ("size", "Rozmiar", "np. M, 38, W32 L34", &product.size),
+                    ("brand", "Marka", "np. Levi's", &product.brand),
+                    ("color", "Kolor", "np. granatowy", &product.color),
+                    ("material", "Materiał", "np. 100% wełna", &product.material),
+                ] {
+                    div {
+                        label for=(field) ."block text-sm font-medium text-gray-700 mb-1" { (label) }
+                        input type="text" name=(field) id=(field) maxlength="100" placeholder=(placeholder)
+                               value=[value.as_deref()] class="admin-filter-input";
+                        (no_errors.field_error_maud(field))
+                    }
+                }
+            }
+        }
+
+        section {
+            h3 ."text-xl font-semibold text-gray-700 mb-4 pb-2 border-b border-gray-200" { "SEO i udostępnianie" }
+            p ."text-xs text-gray-500 mb-4" {
+                "Pola opcjonalne. Puste oznaczają: nazwę produktu w tytule, początek opisu w wynikach wyszukiwania i pierwsze zdjęcie w podglądzie linku (Facebook, Instagram, Messenger)."
+            }
+            div ."space-y-5" {
+                @if !is_new {
+                    div {
+                        label for="slug" ."block text-sm font-medium text-gray-700 mb-1" { "Adres strony produktu" }
+                        div ."flex items-center gap-1" {
+                            span ."text-sm text-gray-500" { "/produkty/" }
+                            input type="text" name="slug" id="slug" maxlength="100" required
+                                   value=(product.slug) class="admin-filter-input";
+                        }
+                        p ."text-xs text-gray-500 mt-1" { "Po zmianie stary adres przekierowuje na nowy (zob. Przekierowania)." }
+                        (no_errors.field_error_maud("slug"))
+                    }
+                }
+                div {
+                    label for="meta_title" ."block text-sm font-medium text-gray-700 mb-1" { "Tytuł strony (meta title)" }
+                    input type="text" name="meta_title" id="meta_title" maxlength="120"
+                           placeholder=(format!("{} - sklep mess - all that vintage", if product.name.is_empty() { "Nazwa produktu" } else { product.name.as_str() }))
+                           value=[product.meta_title.as_deref()] class="admin-filter-input";
+                    (no_errors.field_error_maud("meta_title"))
+                }
+                div {
+                    label for="meta_description" ."block text-sm font-medium text-gray-700 mb-1" { "Opis w wynikach wyszukiwania (meta description)" }
+                    textarea name="meta_description" id="meta_description" rows="2" maxlength="320"
+                             placeholder="Najlepiej 120-160 znaków" class="admin-filter-input" {
+                        (product.meta_description.as_deref().unwrap_or_default())
+                    }
+                    (no_errors.field_error_maud("meta_description"))
+                }
+                div {
+                    label for="og_image" ."block text-sm font-medium text-gray-700 mb-1" { "Zdjęcie podglądu linku (adres https)" }
+                    input type="url" name="og_image" id="og_image" maxlength="500" placeholder="https://res.cloudinary.com/..."
+                           value=[product.og_image.as_deref()] class="admin-filter-input";
+                    (no_errors.field_error_maud("og_image"))
+                }
+            }
+        }
+
+        section {
+            h3 ."text-xl font-semibold text-gray-700 mb-4 pb-2 border-b border-gray-200" { "Klasyfikacja i Status" }
+            @if attribute_suggestions_enabled {
+                // Podpowiedzi ze zdjęcia głównego - nigdy nie są stosowane automatycznie
+                div x-show="attributeSuggestionsLoading || attributeSuggestions" x-cloak
+                    class="mb-5 p-3 rounded-lg border border-dashed border-amber-400 bg-amber-50 text-sm" {
+                    p ."font-medium text-amber-800 mb-2" { "Sugestie ze zdjęcia (do potwierdzenia)" }
+                    p x-show="attributeSuggestionsLoading" ."text-amber-700 text-xs" { "Analizuję zdjęcie..." }
+                    template x-if="attributeSuggestions" {
+                        div ."space-y-2" {
+                            template x-if="attributeSuggestions.category" {
+                                div ."flex items-center gap-2" {
+                                    span ."text-gray-700" { "Kategoria: " strong x-text="attributeSuggestions.category" {} }
+                                    button type="button" "@click.prevent"="applySuggestedCategory()"
+                                           class="px-2 py-0.5 text-xs bg-white border border-amber-400 rounded hover:bg-amber-100" { "Zastosuj" }
+                                }
+                            }
+                            template x-if="attributeSuggestions.colours.length || attributeSuggestions.materials.length" {
+                                div ."flex flex-wrap items-center gap-2" {
+                                    span ."text-gray-700" { "Kolor / materiał:" }
+                                    template x-for="value in attributeSuggestions.colours.concat(attributeSuggestions.materials)" {
+                                        button type="button" "@click.prevent"="appendSuggestionToDescription(value)"
+                                               title="Dopisz do opisu"
+                                               class="px-2 py-0.5 text-xs bg-white border border-amber-400 rounded hover:bg-amber-100" {
+                                            "+ " span x-text="value" {}
+                                        }
+                                    }
+                                }
+                            }
+                            template x-if="!attributeSuggestions.category && !attributeSuggestions.colours.length && !attributeSuggestions.materials.length" {
+                                p ."text-xs text-gray-600" { "Brak rozpoznanych atrybutów." }
+                            }
+                            button type="button" "@click.prevent"="attributeSuggestions = null"
+                                   class="text-xs text-gray-500 hover:underline" { "Odrzuć sugestie" }
+                        }
+                    }
+                }
+            }
+            div ."grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-x-6 gap-y-5" {
+                div {
+                    label for="gender" ."block text-sm font-medium text-gray-700 mb-1" { "Płeć *" }
+                    select name="gender" id="gender" required class="admin-filter-select" {
+                        @for v in ProductGender::iter() { option value=(v.as_ref()) selected[product.gender == v] { (v.to_string()) } }
+                    }
+                    (no_errors.field_error_maud("gender"))
+                }
+                div {
+                    label for="condition" ."block text-sm font-medium text-gray-700 mb-1" { "Stan *" }
+                    select name="condition" id="condition" required class="admin-filter-select" {
+                        @for v in ProductCondition::iter() { option value=(v.as_ref()) selected[product.condition == v] { (v.to_string()) } }
+                    }
+                    (no_errors.field_error_maud("condition"))
+                }
+                div {
+                    label for="category" ."block text-sm font-medium text-gray-700 mb-1" { "Kategoria *" }
+                    select name="category" id="category" required class="admin-filter-select" {
+                        @for v in Category::iter() { option value=(v.as_ref()) selected[product.category == v] { (v.to_string()) } }
+                    }
+                    (no_errors.field_error_maud("category"))
+                }
+                div {
+                    label for="status" ."block text-sm font-medium text-gray-700 mb-1" { "Status *" }
+                    select name="status" id="status" required x-model="productStatus" class="admin-filter-select" {
+                        @for v in ProductStatus::iter() { option value=(v.as_ref()) { (v.to_string()) } }
+                    }
+                    (no_errors.field_error_maud("status"))
+                }
+            }
+        }
+
+        section ."mt-6 pt-6 border-t border-gray-200" {
+             h3 ."text-xl font-semibold text-gray-700 mb-4 pb-2 border-b border-gray-200" { "Opcje Sprzedaży" }
+            div class="relative flex items-start" {
+                div class="flex h-6 items-center" {
+                    input id="on_sale" name="on_sale" type="checkbox" checked[product.on_sale] class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                }
+                div class="ml-3 text-sm leading-6" {
+                    label for="on_sale" class="font-medium text-gray-700" { "Okazja" }
+                    p class="text-xs text-gray-500" { "Zaznacz, jeśli produkt ma być częścią okazji." }
+                }
+            }
+            div ."mt-5" {
+                h4 ."text-sm font-semibold text-gray-700 mb-1" { "Zaplanowana promocja" }
+                p ."text-xs text-gray-500 mb-3" {
+                    "W podanym okresie produkt kosztuje cenę promocyjną i pokazuje się w Okazjach. Puste daty: od razu / do odwołania. Pusta cena wyłącza promocję."
+                }
+                div ."grid grid-cols-1 sm:grid-cols-3 gap-4" {
+                    div {
+                        label for="sale_price" ."block text-sm font-medium text-gray-700 mb-1" { "Cena promocyjna (w groszach)" }
+                        input type="number" name="sale_price" id="sale_price" min="1" step="1"
+                               value=[product.sale_price] class="admin-filter-input w-full";
+                        (no_errors.field_error_maud("sale_price"))
+                    }
+                    div {
+                        label for="sale_starts_at" ."block text-sm font-medium text-gray-700 mb-1" { "Początek" }
+                        input type="datetime-local" name="sale_starts_at" id="sale_starts_at"
+                               value=[product.sale_starts_at.as_ref().map(format_datetime_input)]
+                               class="admin-filter-input w-full";
+                        (no_errors.field_error_maud("sale_starts_at"))
+                    }
+                    div {
+                        label for="sale_ends_at" ."block text-sm font-medium text-gray-700 mb-1" { "Koniec" }
+                        input type="datetime-local" name="sale_ends_at" id="sale_ends_at"
+                               value=[product.sale_ends_at.as_ref().map(format_datetime_input)]
+                               class="admin-filter-input w-full";
+                        (no_errors.field_error_maud("sale_ends_at"))
+                    }
+                }
+            }
+        }
+
+        // Sekcja: Zdjęcia Produktu (TA SAMA LOGIKA HTML CO W EDYCJI)
+        section {
+            // input type="hidden" name="urls_to_delete" id="urls_to_delete_hidden_input_new_form"; // Już dodane na początku formularza
+            h3 ."text-xl font-semibold text-gray-700 mb-2 pb-2 border-b border-gray-200" { "Zdjęcia Produktu" }
+            p ."text-xs text-gray-500 mb-4" { "Dodaj od 1 do 10 zdjęć. Pierwsze zdjęcie będzie zdjęciem głównym." }
+            (no_errors.field_error_maud("images"))
+            div ."grid grid-cols-2 sm:grid-cols-3 md:grid-cols-4 lg:grid-cols-5 gap-4" {
+                @for i in 0..10 {
+                    @let slot_input_id = format!("product_image_file_slot_{}", i);
+                    @let input_name = format!("image_file_{}", i + 1);
+
+                    div class="relative aspect-square border-2 border-dashed border-gray-300 rounded-lg flex flex-col items-center justify-center text-gray-400 hover:border-pink-400 transition-colors group"
+                        x-bind:class="{
+                            '!border-solid !border-pink-500 shadow-lg': isSlotFilled(@(i)),
+                            '!border-red-400 !border-solid bg-red-50': isMarkedForDeletion(@(i))
+                        }" {
+
+                        // --- 1. Widok, gdy obrazek JEST OZNACZONY DO USUNIĘCIA ---
+                        template "x-if"=(format!("isMarkedForDeletion({})", i)) {
+                            div class="absolute inset-0 w-full h-full flex flex-col items-center justify-center text-center p-2 bg-red-50/80 z-10" {
+                                img "x-bind:src"=(format!("getSlotImageSrc({})", i))
+                                     alt="Oznaczono do usunięcia"
+                                     class="w-full h-full object-cover rounded-md opacity-30";
+                                div class="absolute inset-0 w-full h-full flex flex-col items-center justify-center" {
+                                    p class="text-xs font-bold text-red-700 uppercase" { "Oznaczono" }
+                                    p class="text-xs font-semibold text-red-700 uppercase mb-2" { "do usunięcia" }
+                                    button type="button"
+                                           "@click.prevent"=(format!("cancelDeletion({})", i))
+                                           class="px-3 py-1 text-xs font-medium text-gray-700 bg-white border border-gray-400 rounded-md hover:bg-gray-100 focus:outline-none focus:ring-2 focus:ring-offset-1 focus:ring-gray-500" {
+                                        "Anuluj"
+                                    }
+                                }
+                            }
+                        }
+
+                        // --- 2. Widok, gdy obrazek JEST WYPEŁNIONY (ale nie oznaczony do usunięcia) ---
+                        template "x-if"=(format!("isSlotFilled({}) && !isMarkedForDeletion({})", i, i)) {
+                            div class="absolute inset-0 w-full h-full z-10" {
+                                img "x-bind:src"=(format!("getSlotImageSrc({})", i))
+                                     alt=(format!("Podgląd zdjęcia {}", i + 1))
+                                     class="w-full h-full object-cover rounded-md";
+
+                                button type="button"
+                                       "@click.prevent"=(format!("removeImage({}, '{}')", i, slot_input_id))
+                                       class="absolute top-1 right-1 p-0.5 bg-red-600 text-white rounded-full opacity-0 group-hover:opacity-100 hover:bg-red-700 transition-all text-xs w-5 h-5 flex items-center justify-center shadow-md z-10"
+                                       title="Oznacz do usunięcia lub usuń podgląd" {
+                                    // Ikona "X"
+                                    svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-3 h-3" {
+                                        path "d"="M6.28 5.22a.75.75 0 00-1.06 1.06L8.94 10l-3.72 3.72a.75.75 0 101.06 1.06L10 11.06l3.72 3.72a.75.75 0 101.06-1.06L11.06 10l3.72-3.72a.75.75 0 00-1.06-1.06L10 8.94 6.28 5.22z" {}
+                                    }
+                                }
+
+                                @if !is_new {
+                                    // Tylko dla zdjęć już zapisanych w Cloudinary (nie dla nowych podglądów)
+                                    button type="button"
+                                           x-show=(format!("isSavedImage({})", i))
+                                           "@click.prevent"=(format!("cleanImageBackground({}, '{}')", i, product.id))
+                                           class="absolute bottom-1 left-1 right-1 px-1 py-0.5 bg-white/90 text-gray-700 text-[10px] font-medium rounded opacity-0 group-hover:opacity-100 hover:bg-white transition-all shadow z-10"
+                                           title="Usuń tło i wyrównaj kadr" {
+                                        "wyczyść tło"
+                                    }
+                                }
+                            }
+                        }
+
+                        // --- 3. Widok, gdy slot JEST PUSTY ---
+                                template "x-if"=(format!("!isSlotFilled({}) && !isMarkedForDeletion({})", i, i)) {                            label for=(slot_input_id) class="cursor-pointer p-2 text-center w-full h-full flex flex-col items-center justify-center hover:bg-pink-50/50 transition-colors rounded-md" {
+                                svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-8 h-8 text-gray-400 group-hover:text-pink-500 transition-colors" {
+                                    path d="M9.25 13.25a.75.75 0 001.5 0V4.793l2.97 2.97a.75.75 0 001.06-1.06l-4.25-4.25a.75.75 0 00-1.06 0L5.22 6.704a.75.75 0 001.06 1.06L9.25 4.793v8.457z" {}
+                                    path d="M3.5 12.75a.75.75 0 00-1.5 0v2.5A2.75 2.75 0 004.75 18h10.5A2.75 2.75 0 0018 15.25v-2.5a.75.75 0 00-1.5 0v2.5c0 .69-.56 1.25-1.25 1.25H4.75c-.69 0-1.25-.56-1.25-1.25v-2.5z" {}
+                                }
+                                div ."text-xs mt-1 text-gray-500 group-hover:text-pink-600 transition-colors" {
+                                     @if i == 0 { "Dodaj główne *" } @else { "Dodaj zdjęcie" }
+                                }
+                            }
+                        }
+
+                        // Input pliku jest zawsze obecny, ale niewidoczny
+                        input type="file" name=(input_name) id=(slot_input_id)
+                               accept="image/jpeg,image/png,image/webp"
+                               "@change"=(format!("handleFileChange($event, {})", i))
+                               class="opacity-0 absolute inset-0 w-full h-full cursor-pointer z-0"
+                               required[is_new && i == 0];
+                        }
+                    }
+                }
+            }
+
+        // Przyciski Akcji
+        section ."pt-8 border-t border-gray-200 mt-8" {
+            div ."flex flex-col sm:flex-row justify-end items-center gap-3" {
+                a href="/htmx/admin/products"
+                   hx-get="/htmx/admin/products" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                   class="px-6 py-2.5 text-sm font-medium text-gray-700 bg-white border border-gray-300 rounded-lg hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-gray-400 transition-all w-full sm:w-auto text-center" {
+                    "Anuluj"
+                }
+                button type="submit"
+                       class="w-full sm:w-auto inline-flex justify-center items-center px-8 py-2.5 border border-transparent text-sm font-medium rounded-lg shadow-sm text-white bg-pink-600 hover:bg-pink-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500 transition-transform transform hover:scale-105" {
+                    span { "Zapisz" }
+                }
+            }
+        }
+    };
+
+    Ok(html! {
+        div #admin-product-form-container ."p-4 sm:p-6 lg:p-8 bg-gray-50 min-h-screen" {
+            div ."max-w-4xl mx-auto" {
+                div ."flex justify-between items-center mb-6 pb-3 border-b border-gray-300" {
+                    h2 ."text-2xl sm:text-3xl font-semibold text-gray-800" { (form_title)
+                        @if !is_new { ": " span."text-pink-600"{(product.name)} }
+                    }
+                    a href="/htmx/admin/products" hx-get="/htmx/admin/products" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                       class="text-sm text-pink-600 hover:text-pink-700 hover:underline font-medium transition-colors" {
+                        "← Wróć do listy"
+                    }
+                }
+                div #product-form-messages ."mb-4 min-h-[2em]" {}
+
+                // KROK 2: Użyj @if do wyrenderowania całego, kompletnego tagu <form>
+                // z odpowiednim atrybutem, a wewnątrz wstaw zdefiniowany wcześniej `form_body`.
+                @if is_new {
+                    form hx-encoding="multipart/form-data" hx-post=(form_action)
+                         hx-target="#product-form-messages"
+                         class="space-y-8 bg-white p-6 sm:p-8 rounded-xl shadow-xl border border-gray-200"
+                         x-data="adminProductEditForm()"
+                         "data-attribute-suggestions"=(attribute_suggestions_enabled)
+                         "data-initial-images"=(initial_images_json)
+                         "data-current-status"=(current_status_str)
+                         x-init="initAlpineComponent($el.dataset.initialImages, $el.dataset.currentStatus)" {
+
+                        (form_body)
+                    }
+                } @else {
+                    form hx-encoding="multipart/form-data" hx-patch=(form_action)
+                         hx-target="#product-form-messages"
+                         class="space-y-8 bg-white p-6 sm:p-8 rounded-xl shadow-xl border border-gray-200"
+                         x-data="adminProductEditForm()"
+                         "data-attribute-suggestions"=(attribute_suggestions_enabled)
+                         "data-initial-images"=(initial_images_json)
+                         "data-current-status"=(current_status_str)
+                         x-init="initAlpineComponent($el.dataset.initialImages, $el.dataset.currentStatus)" {
+
+                        (form_body)
+                    }
+                }
+            }
+        }
+    })
+}
+
+pub async fn admin_product_new_form_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    tracing::info!(
+        "Admin ID {} żąda formularza dodawania nowego produktu",
+        claims.sub
+    );
+    let page_content = render_product_form_maud(None, &app_state)?;
+
+    let title = "Admin - dodawanie produktu - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Sekcja rezerwacji ręcznej w edycji produktu: aktywna rezerwacja z linkiem zakupu
+/// albo formularz zarezerwowania produktu dla klienta.
+fn render_product_hold_section_maud(
+    product_id: Uuid,
+    hold: Option<&ProductHold>,
+    base_url: &str,
+) -> Markup {
+    html! {
+        div #product-hold-section ."max-w-4xl mx-auto mt-6 bg-white shadow-md rounded-lg p-6" {
+            h3 ."text-md font-semibold text-gray-800 mb-1" { "Rezerwacja dla klienta" }
+            @if let Some(hold) = hold {
+                @let link = hold_link(base_url, hold);
+                p ."text-sm text-gray-600 mb-3" {
+                    "Zarezerwowany dla " span ."font-semibold text-gray-800" { (hold.customer_label) }
+                    " do " (format_datetime_admin(&hold.expires_at)) "."
+                    @if let Some(note) = &hold.note {
+                        " " span ."italic" { (note) }
+                    }
+                }
+                div ."flex flex-col sm:flex-row gap-2 sm:items-center" {
+                    input type="text" readonly value=(link)
+                           class="admin-filter-input font-mono text-xs flex-1" "@focus"="$el.select()";
+                    button type="button" x-data
+                           "@click"=(format!("navigator.clipboard.writeText('{}')", link))
+                           class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Kopiuj link" }
+                    button type="button"
+                           hx-delete=(format!("/api/admin/products/{}/hold", product_id))
+                           hx-confirm="Anulować rezerwację? Produkt wróci do sprzedaży."
+                           hx-swap="none"
+                           class="admin-filter-button bg-red-600 hover:bg-red-700 text-white" { "Anuluj rezerwację" }
+                }
+            } @else {
+                p ."text-sm text-gray-600 mb-3" {
+                    "Produkt dostanie status „Zarezerwowany”, a klient osobisty link, przez który może go kupić. Po terminie rezerwacja wygasa sama."
+                }
+                form hx-post=(format!("/api/admin/products/{}/hold", product_id)) hx-swap="none"
+                     class="grid grid-cols-1 sm:grid-cols-4 gap-3 items-end" {
+                    div ."sm:col-span-1" {
+                        label for="hold_customer_label" ."block text-xs font-medium text-gray-600 mb-1" { "Dla kogo:" }
+                        input type="text" name="customer_label" id="hold_customer_label" required maxlength="100"
+                              placeholder="np. @anna.vintage" class="admin-filter-input";
+                    }
+                    div {
+                        label for="hold_days" ."block text-xs font-medium text-gray-600 mb-1" { "Na ile dni:" }
+                        input type="number" name="days" id="hold_days" min="1" max="14" value="3" required class="admin-filter-input";
+                    }
+                    div {
+                        label for="hold_note" ."block text-xs font-medium text-gray-600 mb-1" { "Notatka (opcjonalnie):" }
+                        input type="text" name="note" id="hold_note" maxlength="500" placeholder="np. cena 120 zł" class="admin-filter-input";
+                    }
+                    div {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white w-full" { "Zarezerwuj" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn admin_product_edit_form_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(product_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    tracing::info!(
+        "Admin ID {} żąda formularza edycji dla produktu ID {}",
+        claims.sub,
+        product_id
+    );
+
+    let product_to_edit = repo::products::find_by_id(&app_state.db_pool, product_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let product_form = render_product_form_maud(Some(&product_to_edit), &app_state)?;
+    let in_sold_archive = if product_to_edit.status == ProductStatus::Sold {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM sold_archive_products WHERE product_id = $1)",
+        )
+        .bind(product_id)
+        .fetch_one(&app_state.db_pool)
+        .await?
+    } else {
+        false
+    };
+    let hold = find_hold_for_product(&app_state.db_pool, product_id).await?;
+    let page_content = html! {
+        (product_form)
+        @if matches!(product_to_edit.status, ProductStatus::Available | ProductStatus::Reserved) {
+            (render_product_hold_section_maud(product_id, hold.as_ref(), &app_state.public_base_url))
+        }
+        @if product_to_edit.status == ProductStatus::Sold {
+            div ."max-w-4xl mx-auto mt-6 bg-white shadow-md rounded-lg p-6 flex flex-col sm:flex-row sm:items-center justify-between gap-4" {
+                div {
+                    h3 ."text-md font-semibold text-gray-800" { "Archiwum sprzedanych" }
+                    p ."text-sm text-gray-600" {
+                        @if in_sold_archive {
+                            "Produkt jest widoczny w publicznym archiwum (zdjęcia ze znakiem wodnym „SPRZEDANE”)."
+                        } @else {
+                            "Produkt nie jest pokazywany w publicznym archiwum."
+                        }
+                    }
+                }
+                button type="button"
+                       hx-post=(format!("/api/admin/products/{}/sold-archive", product_id))
+                       hx-swap="none"
+                       class="px-4 py-2 text-sm font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" {
+                    @if in_sold_archive { "Ukryj w archiwum" } @else { "Pokaż w archiwum" }
+                }
+            }
+        }
+    };
+    let title = "Admin - edycja produktu - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Linki menu panelu (etykieta, adres, wymagane uprawnienie) - każda rola widzi tylko swoje sekcje
+const ADMIN_NAV_LINKS: [(&str, &str, Permission); 25] = [
+    (
+        "Zarządzaj produktami",
+        "/htmx/admin/products?status=all&limit=25",
+        Permission::ManageProducts,
+    ),
+    (
+        "Zarządzaj zamówieniami",
+        "/htmx/admin/orders",
+        Permission::ManageOrders,
+    ),
+    ("Zwroty", "/htmx/admin/returns", Permission::ManageOrders),
+    (
+        "Reklamacje",
+        "/htmx/admin/reklamacje",
+        Permission::ManageOrders,
+    ),
+    ("Opinie", "/htmx/admin/opinie", Permission::ModerateReviews),
+    (
+        "Wyszukaj podobne",
+        "/htmx/admin/podobne",
+        Permission::ManageProducts,
+    ),
+    (
+        "Tabela rozmiarów",
+        "/htmx/admin/rozmiary",
+        Permission::ManageProducts,
+    ),
+    (
+        "Pielęgnacja",
+        "/htmx/admin/pielegnacja",
+        Permission::ManageProducts,
+    ),
+    (
+        "Strona główna",
+        "/htmx/admin/strona-glowna",
+        Permission::ManageContent,
+    ),
+    (
+        "Strony informacyjne",
+        "/htmx/admin/strony",
+        Permission::ManageContent,
+    ),
+    ("FAQ", "/htmx/admin/faq", Permission::ManageContent),
+    (
+        "Przekierowania",
+        "/htmx/admin/przekierowania",
+        Permission::ManageContent,
+    ),
+    (
+        "Klienci",
+        "/htmx/admin/klienci",
+        Permission::ManageCustomers,
+    ),
+    (
+        "Flagi klientów",
+        "/htmx/admin/customer-flags",
+        Permission::ManageCustomers,
+    ),
+    (
+        "Kody rabatowe",
+        "/htmx/admin/coupons",
+        Permission::ManageCoupons,
+    ),
+    (
+        "Karty podarunkowe",
+        "/htmx/admin/karty-podarunkowe",
+        Permission::ManageCoupons,
+    ),
+    (
+        "Zdjęcia do poprawy",
+        "/htmx/admin/image-audit",
+        Permission::ManageProducts,
+    ),
+    (
+        "Lejek konwersji",
+        "/htmx/admin/funnel",
+        Permission::ViewReports,
+    ),
+    (
+        "Wydajność (RUM)",
+        "/htmx/admin/wydajnosc",
+        Permission::ViewReports,
+    ),
+    (
+        "Podgląd jako klient",
+        "/htmx/admin/impersonation",
+        Permission::ManageCustomers,
+    ),
+    (
+        "Blokady logowania",
+        "/htmx/admin/blokady-logowania",
+        Permission::ManageCustomers,
+    ),
+    ("Użytkownicy", "/htmx/admin/users", Permission::ManageUsers),
+    (
+        "Klucze API",
+        "/htmx/admin/api-keys",
+        Permission::ManageSystem,
+    ),
+    ("Cache", "/htmx/admin/cache", Permission::ManageSystem),
+    (
+        "Zadania w tle",
+        "/htmx/admin/zadania",
+        Permission::ManageSystem,
+    ),
+];
+
+pub async fn admin_dashboard_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<SalesDashboardParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::AccessPanel)?;
+    tracing::info!("Admin ID {} wszedł na dashboard admina", claims.sub);
+
+    let unread_notifications: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM admin_notifications WHERE read_at IS NULL")
+            .fetch_one(&app_state.db_pool)
+            .await?;
+
+    let page_content = html! {
+        div ."flex flex-col md:flex-row min-h-screen" {
+            // Sidebar nawigacyjny admina
+            nav ."w-full md:w-64 bg-gray-800 text-white p-4 space-y-2" {
+                h2 ."text-xl font-semibold mb-4" { "Panel Admina" }
+                @if claims.role.has_permission(Permission::ViewReports) {
+                    a href="/admin" hx-get="/htmx/admin/sales" hx-target="#admin-content" hx-swap="innerHTML"
+                       class="block py-2 px-3 rounded hover:bg-gray-700" { "Sprzedaż" }
+                }
+                @for (label, url, permission) in ADMIN_NAV_LINKS {
+                    @if claims.role.has_permission(permission) {
+                        a href=(url) hx-get=(url) hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                           class="block py-2 px-3 rounded hover:bg-gray-700" { (label) }
+                    }
+                }
+                a href="/htmx/admin/notifications" hx-get="/htmx/admin/notifications" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                   class="flex justify-between items-center py-2 px-3 rounded hover:bg-gray-700" {
+                    span { "Powiadomienia" }
+                    @if unread_notifications > 0 {
+                        span ."text-xs font-semibold bg-red-600 text-white px-2 py-0.5 rounded-full" { (unread_notifications) }
+                    }
+                }
+
+                hr ."my-4 border-gray-700";
+                a href="/" target="_blank" class="block py-2 px-3 rounded hover:bg-gray-700" { "Przejdź do sklepu" }
+
+                 // Link wylogowania dla admina
+                a href="#"
+                    "@click.prevent"="clientSideLogout()"
+                    class="block py-2 px-3 rounded hover:bg-red-700 text-red-300 hover:text-white mt-auto" {
+                    "Wyloguj"
+                }
+            }
+            // Główny kontener na treść panelu admina
+            main #admin-content ."flex-1 p-6 bg-gray-100 relative" {
+                // === DEFINICJA SPINNERA ===
+                div id="page-wide-spinner"
+                    class="fixed inset-0 bg-gray-800 bg-opacity-50 flex justify-center items-center z-[9999]"
+                    style="display: none;" {
+                    svg class="animate-spin h-12 w-12 text-white" xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" {
+                        circle class="opacity-25" cx="12" cy="12" r="10" stroke="currentColor" stroke-width="4" {}
+                        path class="opacity-75" fill="currentColor" d="M4 12a8 8 0 018-8V0C5.373 0 0 5.373 0 12h4zm2 5.291A7.962 7.962 0 014 12H0c0 3.042 1.135 5.824 3 7.938l3-2.647z" {}
+                    }
+                }
+                // === KONIEC DEFINICJI SPINNERA ===
+                // Statystyki sprzedaży doładowujemy osobno, żeby agregacje nie opóźniały otwarcia panelu
+                @if claims.role.has_permission(Permission::ViewReports) {
+                    div hx-get=(format!("/htmx/admin/sales?{}", params.to_query_string()))
+                        hx-trigger="load"
+                        hx-swap="outerHTML" {
+                        p ."text-gray-500" { "Wczytywanie statystyk sprzedaży..." }
+                    }
+                } @else {
+                    h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Panel sklepu" }
+                    p ."text-gray-600" {
+                        "Zalogowano jako: " (claims.role.label()) ". Wybierz sekcję z menu."
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn admin_products_list_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(mut params): Query<ListingParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    tracing::info!(
+        "Admin ID {} żąda listy produktów (admin view) z parametrami: {:?}",
+        claims.sub,
+        params
+    );
+
+    if params.limit.is_none() {
+        params.limit = Some(25);
+    }
+    let current_limit = params.limit();
+
+    let current_query_string = build_full_query_string_from_params(&params);
+
+    let paginated_response_json =
+        crate::handlers::list_products(State(app_state.clone()), Query(params.clone())).await?;
+    let paginated_response: PaginatedProductsResponse = paginated_response_json.0;
+
+    let _params_for_edit_links = params.to_query_string_with_skips(&["offset"]);
+
+    let page_content = html! {
+        div #admin-product-list-container ."p-1"
+            hx-get=(format!("/htmx/admin/products?{}", current_query_string))
+            hx-trigger="reloadAdminProductList from:body"  // Nasłuchuje na zdarzenie z elementu body
+            hx-swap="outerHTML"                             // Podmienia cały ten kontener
+            hx-push-url="true"
+
+        {
+            // Nagłówek i przycisk dodawania (bez zmian)
+            div ."flex flex-col sm:flex-row justify-between items-center mb-6 gap-4" {
+                h3 ."text-2xl font-semibold text-gray-800" { "Zarządzanie produktami" }
+                a href="/htmx/admin/products/new-form"
+                   hx-get="/htmx/admin/products/new-form"
+                   hx-target="#admin-content"
+                   hx-swap="innerHTML"
+                   hx-push-url="true"
+                   class="bg-pink-600 hover:bg-pink-700 text-white font-semibold py-2 px-5 rounded-lg shadow-md hover:shadow-lg transition-all duration-150 ease-in-out text-sm inline-flex items-center" {
+                    svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-5 h-5 mr-2"{
+                        path d="M10.75 4.75a.75.75 0 00-1.5 0v4.5h-4.5a.75.75 0 000 1.5h4.5v4.5a.75.75 0 001.5 0v-4.5h4.5a.75.75 0 000-1.5h-4.5v-4.5z" {}
+                    }
+                    "Dodaj Nowy Produkt"
+                }
+            }
+
+            // Formularz filtrów (bez zmian)
+            form hx-get="/htmx/admin/products"
+                 hx-target="#admin-product-list-container"
+                 hx-swap="outerHTML"
+                 hx-push-url="true"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-2 md:grid-cols-3 lg:grid-cols-4 gap-4 items-end" {
+                    div {
+                        label for="filter_category_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Kategoria:" }
+                        select name="category" id="filter_category_admin" class="admin-filter-select" {
+                            option value="" selected[params.category.is_none()] { "Wszystkie" }
+                            @for cat_variant in Category::iter() {
+                                option value=(cat_variant.as_ref()) selected[params.category.as_ref() == Some(&cat_variant)] { (cat_variant.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="filter_status_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Status:" }
+                        select name="status" id="filter_status_admin" class="admin-filter-select" {
+                                    // === ZMIANA W LOGICE ZAZNACZANIA ===
+                                    // Opcja "Wszystkie" teraz wysyła value="all"
+                                    option value="all" selected[params.status.as_deref() == Some("all") || params.status.is_none()] { "Wszystkie" }
+
+                                    @for status_variant in ProductStatus::iter() {
+                                        @let status_str = status_variant.as_ref();
+                                        option value=(status_str) selected[params.status.as_deref() == Some(status_str)] { (status_variant.to_string()) }
+                                    }
+                                }
+                            }
+                    div {
+                        label for="filter_brand_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Marka:" }
+                        input type="text" name="brand" id="filter_brand_admin" value=[params.brand()]
+                               placeholder="np. Levi's" class="admin-filter-input";
+                    }
+                    div {
+                        label for="filter_size_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Rozmiar:" }
+                        input type="text" name="size" id="filter_size_admin" value=[params.size()]
+                               placeholder="np. M, 38" class="admin-filter-input";
+                    }
+                    div ."lg:col-span-1" {
+                        label for="search_query_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Szukaj:" }
+                        input type="search" name="search" id="search_query_admin" value=[params.search.as_deref()]
+                               placeholder="Nazwa, opis..." class="admin-filter-input";
+                    }
+                    div ."flex flex-col sm:flex-row space-y-2 sm:space-y-0 sm:space-x-2 items-end lg:col-start-4" {
+                        button type="submit" class="admin-filter-button bg-gray-700 hover:bg-gray-800 text-white w-full sm:w-auto" { "Filtruj" }
+                        a href="/htmx/admin/products" hx-get="/htmx/admin/products" hx-target="#admin-product-list-container" hx-swap="outerHTML" hx-push-url="true"
+                           class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-700 w-full sm:w-auto text-center" { "Resetuj" }
+                    }
+                }
+                @if let Some(sort_val) = &params.sort_by { input type="hidden" name="sort-by" value=(sort_val); }
+                @if let Some(order_val) = &params.order { input type="hidden" name="order" value=(order_val); }
+                input type="hidden" name="limit" value=(current_limit);
+            }
+
+            // Akcje masowe na zaznaczonych wierszach (checkboksy w tabeli wskazują ten formularz)
+            form #admin-bulk-form
+                 hx-post=(format!("/api/admin/products/bulk?{}", current_query_string))
+                 hx-target="#admin-bulk-result"
+                 hx-swap="innerHTML"
+                 hx-confirm="Wykonać wybraną akcję na zaznaczonych produktach?"
+                 x-data="{ action: 'archive' }"
+                 class="mb-4 p-3 bg-gray-50 rounded-lg border border-gray-200 flex flex-wrap items-end gap-3" {
+                div {
+                    label for="bulk_action_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Zaznaczone produkty:" }
+                    select name="action" id="bulk_action_admin" x-model="action" class="admin-filter-select" {
+                        @for bulk_action in ProductBulkAction::iter() {
+                            option value=(bulk_action.as_ref()) { (bulk_action.label()) }
+                        }
+                    }
+                }
+                div x-show="action === 'change_price'" x-cloak {
+                    label for="bulk_percent_admin" ."block text-sm font-medium text-gray-700 mb-1" { "Zmiana ceny (%):" }
+                    input type="number" name="percent" id="bulk_percent_admin" min="-90" max="200" step="1"
+                          placeholder="np. -20" class="admin-filter-input w-28"
+                          ":disabled"="action !== 'change_price'";
+                }
+                button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Wykonaj" }
+            }
+            div #admin-bulk-result ."mb-4" {}
+
+            // Tabela produktów (bez zmian w tej części)
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th w-8" {
+                                input type="checkbox" aria-label="Zaznacz wszystkie"
+                                      class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500"
+                                      "x-on:change"="document.querySelectorAll('input[name=product_ids]').forEach(cb => cb.checked = $el.checked)";
+                            }
+                            th scope="col" class="admin-th" { "Zdjęcie" }
+                            th scope="col" class="admin-th" { (sort_link("/htmx/admin/products", &params, "name", "Nazwa")) }
+                            th scope="col" class="admin-th" { (sort_link("/htmx/admin/products", &params, "price", "Cena")) }
+                            th scope="col" class="admin-th" { "Status" }
+                            th scope="col" class="admin-th" { "Kategoria" }
+                            th scope="col" class="admin-th" { (sort_link("/htmx/admin/products", &params, "created_at", "Dodano")) }
+                            th scope="col" class="admin-th text-right" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if paginated_response.data.is_empty() {
+                            tr { td colspan="8" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Nie znaleziono produktów." } }
+                        }
+                        @for product in &paginated_response.data {
+                            tr ."hover:bg-pink-50/30 transition-colors duration-150 ease-in-out" {
+                                (render_admin_product_list_row_maud(product, &params))
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Paginacja - Z NOWĄ LOGIKĄ RENDEROWANIA
+            @if paginated_response.total_pages > 1 {
+                nav class="mt-6 flex flex-col sm:flex-row justify-between items-center text-sm" aria-label="Paginacja produktów" {
+
+                    div {
+                        label for="limit_select" ."block text-sm font-medium text-gray-700 mb-1" { "Pokaż na stronie:" }
+                        select name="limit" id="limit_select" class="admin-filter-select"
+                               hx-trigger="change"
+                               hx-get="/htmx/admin/products"
+                               hx-target="#admin-product-list-container"
+                               hx-swap="outerHTML"
+                               hx-push-url="true"
+                        {
+                            // Opcje do wyboru. Sprawdzamy, która jest aktualnie wybrana.
+                            option value="10" selected[current_limit == 10] { "10" }
+                            option value="25" selected[current_limit == 25] { "25" }
+                            option value="50" selected[current_limit == 50] { "50" }
+                        }
+                    }
+                    div class="text-gray-600 mb-2 sm:mb-0" {
+                        "Strona " strong { (paginated_response.current_page) }
+                        " z " strong { (paginated_response.total_pages) }
+                        " (Łącznie: " strong { (products_count(paginated_response.total_items)) } ")"
+                    }
+                    div class="flex space-x-1" {
+                        @let base_pagination_url = format!("/htmx/admin/products?{}&limit={}", params.to_query_string_with_skips(&["offset", "limit"]), current_limit);
+                        @let current_p = paginated_response.current_page;
+                        @let total_p = paginated_response.total_pages;
+                        @let side_window = 1; // Ile stron pokazać obok bieżącej, pierwszej i ostatniej
+
+                        // Przycisk "Pierwsza"
+                        @if current_p > 1 {
+                            { a href=(format!("{}&offset=0", base_pagination_url)) hx-get=(format!("{}&offset=0", base_pagination_url))
+                               hx-target="#admin-product-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { "«" } }
+                        } @else {
+                            { span class="admin-pagination-button-disabled" { "«" } }
+                        }
+                        // Przycisk "Poprzednia"
+                        @if current_p > 1 {
+                            { a href=(format!("{}&offset={}", base_pagination_url, (current_p - 2) * current_limit))
+                               hx-get=(format!("{}&offset={}", base_pagination_url, (current_p - 2) * current_limit))
+                               hx-target="#admin-product-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { "‹" } }
+                        } @else {
+                            { span class="admin-pagination-button-disabled" { "‹" } }
+                        }
+
+                        // Numery stron - generowane przez funkcję pomocniczą
+                        @let pagination_items_vec = generate_pagination_items(current_p, total_p, side_window);
+                        @for item in pagination_items_vec {
+                            @match item {
+                                PaginationItem::Page(page_num_val) => {
+                                    @if page_num_val == current_p {
+                                        { span class="admin-pagination-button-active" { (page_num_val) } }
+                                    } @else {
+                                        { a href=(format!("{}&offset={}", base_pagination_url, (page_num_val - 1) * current_limit))
+                                           hx-get=(format!("{}&offset={}", base_pagination_url, (page_num_val - 1) * current_limit))
+                                           hx-target="#admin-product-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { (page_num_val) } }
+                                    }
+                                }
+                                PaginationItem::Dots => {
+                                    { span class="admin-pagination-dots" { "..." } }
+                                }
+                            }
+                        }
+
+                        // Przycisk "Następna"
+                        @if current_p < total_p {
+                            { a href=(format!("{}&offset={}", base_pagination_url, current_p * current_limit))
+                               hx-get=(format!("{}&offset={}", base_pagination_url, current_p * current_limit))
+                               hx-target="#admin-product-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { "›" } }
+                        } @else {
+                            { span class="admin-pagination-button-disabled" { "›" } }
+                        }
+                        // Przycisk "Ostatnia"
+                        @if current_p < total_p {
+                            { a href=(format!("{}&offset={}", base_pagination_url, (total_p - 1) * current_limit))
+                               hx-get=(format!("{}&offset={}", base_pagination_url, (total_p - 1) * current_limit))
+                               hx-target="#admin-product-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { "»" } }
+                        } @else {
+                            { span class="admin-pagination-button-disabled" { "»" } }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Lista produktów - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+// Pomocnicza funkcja do generowania linków sortowania
+fn sort_link(
+    base_url: &str,
+    current_params: &ListingParams,
+    sort_field: &str,
+    display_name: &str,
+) -> Markup {
+    let mut next_order = "asc";
+    let mut icon = "↕"; // Domyślna ikona dla nieaktywnego sortowania
+
+    if current_params.sort_by() == sort_field {
+        if current_params.order() == "asc" {
+            next_order = "desc";
+            icon = "↑"; // Strzałka w górę dla ASC:
+        } else {
+            // next_order pozostaje "asc" (domyślnie, aby przełączać)
+            icon = "↓"; // Strzałka w dół dla DESC
+        }
+    }
+
+    // Skopiuj istniejące parametry, aby nie stracić filtrów
+    let mut query_params = Vec::new();
+    if let Some(s) = &current_params.status {
+        query_params.push(format!("status={}", s));
+    }
+    if let Some(c) = &current_params.category {
+        query_params.push(format!("category={}", c.as_ref()));
+    }
+    if let Some(search) = &current_params.search {
+        query_params.push(format!("search={}", urlencoding::encode(search)));
+    }
+    if let Some(limit) = current_params.limit {
+        query_params.push(format!("limit={}", limit));
+    }
+    // Offset nie jest potrzebny w linku sortowania, bo sortowanie powinno resetować do pierwszej strony
+    // query_params.push(format!("offset=0")); // lub pominąć, backend powinien obsłużyć
+
+    query_params.push(format!("sort-by={}", sort_field));
+    query_params.push(format!("order={}", next_order));
+
+    let query_string = query_params.join("&");
+    let hx_get_url = format!("{}?{}", base_url, query_string);
+
+    html! {
+        a href="#" // href nie jest potrzebny, HTMX go nadpisze
+           hx-get=(hx_get_url)
+           hx-target="#admin-product-list-container" // Odświeża cały kontener listy
+           hx-swap="outerHTML" // Zastępuje kontener nową zawartością
+           class="flex items-center space-x-1 hover:text-pink-600" {
+            span { (display_name) }
+            span class="text-xs" { (PreEscaped(icon)) } // Używamy PreEscaped dla strzałek
+        }
+    }
+}
+
+/// Pomocnicza funkcja do klas dla statusu (możesz ją umieścić gdzieś indziej lub inline)
+fn get_status_badge_classes(status: ProductStatus) -> &'static str {
+    match status {
+        ProductStatus::Available => {
+            "px-2 inline-flex text-xs leading-5 font-semibold rounded-full bg-green-100 text-green-800"
+        }
+        ProductStatus::Reserved => {
+            "px-2 inline-flex text-xs leading-5 font-semibold rounded-full bg-yellow-100 text-yellow-800"
+        }
+        ProductStatus::Sold => {
+            "px-2 inline-flex text-xs leading-5 font-semibold rounded-full bg-red-100 text-red-800"
+        }
+        ProductStatus::Archived => {
+            "px-2 inline-flex text-xs leading-5 font-semibold rounded-full bg-gray-200 text-gray-800"
+        }
+    }
+}
+
+// Funkcja pomocnicza do generowania linków sortowania dla zamówień
+fn order_sort_link(
+    base_url: &str,
+    current_params: &OrderListingParams,
+    sort_field: &str,
+    display_name: &str,
+) -> Markup {
+    let mut next_order_dir = "asc";
+    let mut icon = "↕";
+
+    if current_params.sort_by() == sort_field {
+        if current_params.order() == "asc" {
+            next_order_dir = "desc";
+            icon = "↑";
+        } else {
+            icon = "↓";
+        }
+    }
+
+    // Zachowaj istniejące filtry i paginację (offset zostanie zresetowany przez sortowanie)
+    let mut query_params_vec = Vec::new();
+    if let Some(s) = &current_params.status {
+        query_params_vec.push(format!("status={}", s.as_ref()));
+    }
+    if let Some(df) = &current_params.date_from {
+        query_params_vec.push(format!("date-from={}", df));
+    }
+    if let Some(dt) = &current_params.date_to {
+        query_params_vec.push(format!("date-to={}", dt));
+    }
+    if let Some(sr) = &current_params.search {
+        query_params_vec.push(format!("search={}", urlencoding::encode(sr)));
+    }
+    if let Some(l) = current_params.limit {
+        query_params_vec.push(format!("limit={}", l));
+    }
+    // Offset jest resetowany przy sortowaniu
+    // query_params_vec.push("offset=0".to_string());
+
+    query_params_vec.push(format!("sort-by={}", sort_field));
+    query_params_vec.push(format!("order={}", next_order_dir));
+
+    let query_string = query_params_vec.join("&");
+    let hx_get_url = format!("{}?{}", base_url, query_string);
+
+    html! {
+        a href="#" // href nie jest potrzebny, HTMX go nadpisze
+           hx-get=(hx_get_url)
+           hx-target="#admin-orders-list-container" // Celuje w kontener listy zamówień
+           hx-swap="outerHTML"
+           hx-push-url="true"
+           class="flex items-center space-x-1 hover:text-pink-600" {
+            span { (display_name) }
+            span class="text-xs" { (PreEscaped(icon)) }
+        }
+    }
+}
+
+pub async fn admin_orders_list_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<OrderListingParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+
+    // Wywołaj zmodyfikowany list_orders_handler (API)
+    let paginated_response_axum_json = crate::handlers::list_orders_handler(
+        State(app_state.clone()), // Klonujemy, bo app_state jest używane dalej
+        claims.clone(),           // Klonujemy claims
+        Query(params.clone()),
+    )
+    .await?;
+    let paginated_orders: PaginatedOrdersResponse<OrderWithCustomerInfo> =
+        paginated_response_axum_json.0;
+
+    let current_limit = params.limit(); // Używamy metody z OrderListingParams
+
+    // Przygotuj query string dla linków paginacji, zachowując filtry i sortowanie
+    let mut pagination_query_params = Vec::new();
+    if let Some(s) = &params.status {
+        pagination_query_params.push(format!("status={}", s.as_ref()));
+    }
+    if let Some(df) = &params.date_from {
+        pagination_query_params.push(format!("date-from={}", df));
+    }
+    if let Some(dt) = &params.date_to {
+        pagination_query_params.push(format!("date-to={}", dt));
+    }
+    if let Some(srch) = &params.search {
+        pagination_query_params.push(format!("search={}", urlencoding::encode(srch)));
+    }
+    pagination_query_params.push(format!("sort-by={}", params.sort_by()));
+    pagination_query_params.push(format!("order={}", params.order()));
+    pagination_query_params.push(format!("limit={}", current_limit));
+    let base_pagination_query_string_for_links = pagination_query_params.join("&");
+
+    let page_content = html! {
+        div #admin-orders-list-container ."p-1"
+            hx-get=(format!("/htmx/admin/orders?{}", params.to_query_string()))
+            hx-trigger="reloadAdminOrderList from:body"
+            hx-swap="outerHTML"
+            hx-push-url="true"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Zarządzanie zamówieniami" }
+            }
+
+            // --- Formularz Filtrów ---
+            form hx-get="/htmx/admin/orders"
+                 hx-target="#admin-orders-list-container" // Odświeża ten sam kontener
+                 hx-swap="outerHTML" // Zastępuje cały kontener nową, przefiltrowaną listą
+                 hx-push-url="true"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+
+                // Ukryte pola do zachowania sortowania i limitu przy filtrowaniu
+                input type="hidden" name="limit" value=(current_limit);
+                @if let Some(sort_val) = &params.sort_by { input type="hidden" name="sort-by" value=(sort_val); }
+                @if let Some(order_val) = &params.order { input type="hidden" name="order" value=(order_val); }
+
+
+                div ."grid grid-cols-1 sm:grid-cols-2 md:grid-cols-3 lg:grid-cols-4 xl:grid-cols-5 gap-4 items-end" {
+                    div {
+                        label for="filter_status_order" ."block text-sm font-medium text-gray-700 mb-1" { "Status:" }
+                        select name="status" id="filter_status_order" class="admin-filter-select" {
+                            option value="" selected[params.status.is_none()] { "Wszystkie" }
+                            @for status_opt in OrderStatus::iter() {
+                                option value=(status_opt.as_ref()) selected[params.status.as_ref() == Some(&status_opt)] { (status_opt.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="filter_date_from" ."block text-sm font-medium text-gray-700 mb-1" { "Data od:" }
+                        input type="date" name="date_from" id="filter_date_from" value=[params.date_from.as_deref()] class="admin-filter-input";
+                    }
+                    div {
+                        label for="filter_date_to" ."block text-sm font-medium text-gray-700 mb-1" { "Data do:" }
+                        input type="date" name="date_to" id="filter_date_to" value=[params.date_to.as_deref()] class="admin-filter-input";
+                    }
+                    div {
+                        label for="search_order" ."block text-sm font-medium text-gray-700 mb-1" { "Szukaj:" }
+                        input type="search" name="search" id="search_order" value=[params.search.as_deref()] placeholder="Numer, ID, nazwisko, e-mail..." class="admin-filter-input";
+                    }
+                    div ."flex flex-col sm:flex-row space-y-2 sm:space-y-0 sm:space-x-2 items-end pt-2 sm:pt-0" {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white w-full sm:w-auto" { "Filtruj" }
+                        a href="/htmx/admin/orders" // Link do resetowania filtrów (ładuje stronę z domyślnymi parametrami)
+                           hx-get="/htmx/admin/orders" // Upewnij się, że ten GET nie przekazuje starych params, jeśli to reset
+                           hx-target="#admin-orders-list-container" hx-swap="outerHTML" hx-push-url="true"
+                           class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-700 w-full sm:w-auto text-center" {
+                            "Resetuj"
+                        }
+                    }
+                }
+            }
+
+            // --- Tabela Zamówień ---
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "ID Zam." }
+                            th scope="col" class="admin-th" { "Klient" }
+                            th scope="col" class="admin-th" { (order_sort_link("/htmx/admin/orders", &params, "order_date", "Data Zam.")) }
+                            th scope="col" class="admin-th" { (order_sort_link("/htmx/admin/orders", &params, "status", "Status")) }
+                            th scope="col" class="admin-th text-right" { (order_sort_link("/htmx/admin/orders", &params, "total_price", "Suma")) }
+                            th scope="col" class="admin-th" { "Płatność" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if paginated_orders.data.is_empty() {
+                            tr { td colspan="7" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Nie znaleziono zamówień." } }
+                        }
+                        @for order_info in &paginated_orders.data {
+                            @let list_query_string = params.to_query_string();
+                            @let order = &order_info.order;
+                                tr id=(format!("order-row-{}", order.id)) ."hover:bg-pink-50/30 transition-colors duration-150 ease-in-out" {
+
+                                    td class="admin-td font-mono text-xs text-gray-500" {
+                                        a href=({
+                                                    // Dodaj '?' tylko jeśli list_query_string nie jest pusty
+                                                    if list_query_string.is_empty() {
+                                                        format!("/htmx/admin/order-details/{}", order.id)
+                                                    } else {
+                                                        format!("/htmx/admin/order-details/{}?{}", order.id, list_query_string)
+                                                    }
+                                                })
+                                               hx-get=({ // Ta sama logika dla hx-get
+                                                    if list_query_string.is_empty() {
+                                                        format!("/htmx/admin/order-details/{}", order.id)
+                                                    } else {
+                                                        format!("/htmx/admin/order-details/{}?{}", order.id, list_query_string)
+                                                    }
+                                                })
+                                               hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                               class="hover:text-pink-600 hover:underline" { (order.order_number) }
+                                    }
+                                    td class="admin-td" {
+
+                                    @if let Some(email) = &order_info.customer_email {
+                                        span class="text-gray-800" { (email) }
+                                    } @else if order.user_id.is_some() {
+                                        span class="text-gray-500 italic" { "Użytkownik ID: " (order.user_id.unwrap().to_string().chars().take(8).collect::<String>()) "..." }
+                                    } @else {
+                                        span class="text-gray-500 italic" { "Gość" }
+                                    }
+                                    br;
+                                    small class="text-gray-500" { (order.shipping_first_name) " " (order.shipping_last_name) }
+                                }
+                                td class="admin-td text-gray-600 text-xs" { (format_datetime_admin(&order.order_date)) }
+                                td class="admin-td" {
+                                    // --- Dropdown do zmiany statusu ---
+                                    div class="inline-block relative" {
+                                        select name="status"
+                                            hx-patch=(format!("/api/orders/{}", order.id))
+                                            hx-trigger="change"
+                                            class="block w-full pl-3 pr-8 py-1.5 text-xs border-gray-300 focus:outline-none focus:ring-pink-500 focus:border-pink-500 rounded-md shadow-sm appearance-none"
+                                            aria-label="Zmień status zamówienia" {
+                                            @for status_option in OrderStatus::iter() {
+                                                option value=(status_option.to_form_value())
+                                                    selected[order.status == status_option]
+                                                    disabled[order.status != status_option && !order.status.can_transition_to(&status_option)] { (status_option.to_string()) }
+                                            }
+                                        }
+                                    }
+                                }
+                                td class="admin-td text-right font-medium text-gray-800" { (format_price_maud(order.total_price)) }
+                                td class="admin-td text-xs text-gray-600" {
+                                    @if let Some(pm) = &order.payment_method {
+                                        (pm.to_string())
+                                    } @else {
+                                        "Brak info"
+                                    }
+                                }
+
+                                td class="admin-td text-center whitespace-nowrap" {
+                                    a href=({
+                                                if list_query_string.is_empty() {
+                                                    format!("/htmx/admin/order-details/{}", order.id)
+                                                } else {
+                                                    format!("/htmx/admin/order-details/{}?{}", order.id, list_query_string)
+                                                }
+                                            })
+                                           hx-get=({
+                                                if list_query_string.is_empty() {
+                                                    format!("/htmx/admin/order-details/{}", order.id)
+                                                } else {
+                                                    format!("/htmx/admin/order-details/{}?{}", order.id, list_query_string)
+                                                }
+                                            })
+                                           hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true" {                                        svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-5 h-5 inline-block" {
+                                            path d="M10 12.5a2.5 2.5 0 100-5 2.5 2.5 0 000 5z" {}
+                                            path "fill-rule"="evenodd" d="M.664 10.59a1.651 1.651 0 010-1.186A10.004 10.004 0 0110 3c4.257 0 7.893 2.66 9.336 6.41.147.381.146.804 0 1.186A10.004 10.004 0 0110 17c-4.257 0-7.893-2.66-9.336-6.41zM14 10a4 4 0 11-8 0 4 4 0 018 0z" "clip-rule"="evenodd" {}
+                                        }
+                                    }
+                                    // POCZĄTEK NOWEGO KODU - Przycisk usuwania
+                                    button
+                                        class="admin-action-button text-red-600 hover:text-red-800 ml-2" // ml-2 dla odstępu
+                                        title="Usuń zamówienie trwale"
+                                        hx-delete=(format!("/api/orders/{}/permanent", order.id))
+                                        hx-confirm="UWAGA! Czy na pewno chcesz TRWALE usunąć to zamówienie? Produkty z tego zamówienia wrócą do sprzedaży. Tej operacji nie można cofnąć!"
+                                        hx-target="closest tr"
+                                        hx-swap="outerHTML"
+                                    {
+                                        // Ikona kosza na śmieci
+                                        svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-5 h-5" {
+                                            path "fill-rule"="evenodd" d="M8.75 1A2.75 2.75 0 006 3.75v.443c-.795.077-1.584.176-2.365.298a.75.75 0 10.23 1.482l.149-.022.841 10.518A2.75 2.75 0 007.596 19h4.807a2.75 2.75 0 002.742-2.53l.841-10.52.149.023a.75.75 0 00.23-1.482A41.03 41.03 0 0014 4.193v-.443A2.75 2.75 0 0011.25 1h-2.5zM10 4c.84 0 1.673.025 2.5.075V3.75c0-.69-.56-1.25-1.25-1.25h-2.5c-.69 0-1.25.56-1.25 1.25v.325C8.327 4.025 9.16 4 10 4zM8.58 7.72a.75.75 0 00-1.5.06l.3 7.5a.75.75 0 101.5-.06l-.3-7.5zm4.34.06a.75.75 0 10-1.5-.06l-.3 7.5a.75.75 0 101.5.06l.3-7.5z" "clip-rule"="evenodd" {}
+                                        }
+                                    }
+                                    // KONIEC NOWEGO KODU
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // --- Paginacja ---
+            @if paginated_orders.total_pages > 1 {
+                nav class="mt-6 flex flex-col sm:flex-row justify-between items-center text-sm" aria-label="Paginacja zamówień" {
+                    div class="text-gray-600 mb-2 sm:mb-0" {
+                        "Strona " strong { (paginated_orders.current_page) }
+                        " z " strong { (paginated_orders.total_pages) }
+                        " (Łącznie: " strong { (orders_count(paginated_orders.total_items)) } ")"
+                    }
+                    div class="flex space-x-1" {
+                        @let current_p_orders = paginated_orders.current_page;
+                        @let total_p_orders = paginated_orders.total_pages;
+                        @let side_window_orders = 1;
+
+                        // Przycisk "Pierwsza"
+                        @if current_p_orders > 1 {
+                            { a href=(format!("/htmx/admin/orders?{}&offset=0", base_pagination_query_string_for_links))
+                               hx-get=(format!("/htmx/admin/orders?{}&offset=0", base_pagination_query_string_for_links))
+                               hx-target="#admin-orders-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { "«" } }
+                        } @else { { span class="admin-pagination-button-disabled" { "«" } } }
+                        // Przycisk "Poprzednia"
+                        @if current_p_orders > 1 {
+                            { a href=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, (current_p_orders - 2) * current_limit))
+                               hx-get=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, (current_p_orders - 2) * current_limit))
+                               hx-target="#admin-orders-list-container" hx-swap="outerHTML" hx-push-url="true"  hx-scroll="window:top" class="admin-pagination-button" { "‹" } }
+                        } @else { { span class="admin-pagination-button-disabled" { "‹" } } }
+
+                        @let pagination_items_vec_orders = generate_pagination_items(current_p_orders, total_p_orders, side_window_orders);
+                        @for item_order in pagination_items_vec_orders {
+                            @match item_order {
+                                PaginationItem::Page(page_num_val_order) => {
+                                    @if page_num_val_order == current_p_orders {
+                                        { span class="admin-pagination-button-active" { (page_num_val_order) } }
+                                    } @else {
+                                        { a href=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, (page_num_val_order - 1) * current_limit))
+                                           hx-get=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, (page_num_val_order - 1) * current_limit))
+                                           hx-target="#admin-orders-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top" class="admin-pagination-button" { (page_num_val_order) } }
+                                    }
+                                }
+                                PaginationItem::Dots => { { span class="admin-pagination-dots" { "..." } } }
+                            }
+                        }
+
+                        // Przycisk "Następna"
+                        @if current_p_orders < total_p_orders {
+                            { a href=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, current_p_orders * current_limit))
+                               hx-get=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, current_p_orders * current_limit))
+                               hx-target="#admin-orders-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top"  class="admin-pagination-button" { "›" } }
+                        } @else { { span class="admin-pagination-button-disabled" { "›" } } }
+                        // Przycisk "Ostatnia"
+                        @if current_p_orders < total_p_orders {
+                            { a href=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, (total_p_orders - 1) * current_limit))
+                               hx-get=(format!("/htmx/admin/orders?{}&offset={}", base_pagination_query_string_for_links, (total_p_orders - 1) * current_limit))
+                               hx-target="#admin-orders-list-container" hx-swap="outerHTML" hx-push-url="true" hx-scroll="window:top"  class="admin-pagination-button" { "»" } }
+                        } @else { { span class="admin-pagination-button-disabled" { "»" } } }
+                    }
+                }
+            }
+        }
+    };
+    let title = "Admin Panel - Lista zamówień - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn admin_order_details_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(order_id): Path<Uuid>,
+    Query(list_params): Query<OrderListingParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+
+    tracing::info!(
+        "Admin ID {} żąda szczegółów zamówienia ID {}",
+        claims.sub,
+        order_id
+    );
+
+    // Wywołaj istniejący handler API do pobrania szczegółów zamówienia
+    // get_order_details_handler już sprawdza uprawnienia admina
+    let order_details_response_json = crate::handlers::get_order_details_handler(
+        State(app_state.clone()),
+        claims.clone(), // Przekaż claims
+        Path(order_id),
+    )
+    .await?;
+    let order_details: OrderDetailsResponse = order_details_response_json.0;
+    let order = &order_details.order; // Skrót do danych zamówienia
+
+    let order_date_display = format_datetime(&order.order_date);
+
+    // Zwroty za pozycje usunięte z zamówienia (np. uszkodzone przed wysyłką)
+    let refunds = sqlx::query_as::<_, OrderRefund>(
+        "SELECT * FROM order_refunds WHERE order_id = $1 ORDER BY created_at ASC",
+    )
+    .bind(order_id)
+    .fetch_all(&app_state.db_pool)
+    .await?;
+    let is_order_editable = matches!(order.status, OrderStatus::Pending | OrderStatus::Processing)
+        && order_details.items.len() > 1;
+    let customer_flags = find_customer_flags_for_order(&app_state.db_pool, order).await?;
+    let risk_assessment = sqlx::query_as::<_, OrderRiskAssessment>(
+        "SELECT * FROM order_risk_assessments WHERE order_id = $1",
+    )
+    .bind(order_id)
+    .fetch_optional(&app_state.db_pool)
+    .await?;
+
+    let coupon_code: Option<String> = match order.coupon_id {
+        Some(coupon_id) => {
+            sqlx::query_scalar("SELECT code FROM coupons WHERE id = $1")
+                .bind(coupon_id)
+                .fetch_optional(&app_state.db_pool)
+                .await?
+        }
+        None => None,
+    };
+
+    let status_history = fetch_order_status_history(&app_state.db_pool, order_id).await?;
+
+    // Przygotuj query string dla linku powrotnego do listy zamówień, zachowując filtry
+    let back_to_list_query_string = list_params.to_query_string();
+
+    let page_content = html! {
+        // Kontener dla strony szczegółów, który będzie nasłuchiwał na odświeżenie
+        // po zmianie statusu na tej stronie.
+        div id=(format!("order-details-page-container-{}", order.id)) // Unikalne ID kontenera
+            hx-get=(format!("/htmx/admin/order-details/{}?{}", order.id, back_to_list_query_string)) // URL do przeładowania tej strony z parametrami listy
+            hx-trigger="reloadAdminOrderList from:body" // Nasłuchuje na ten sam globalny trigger
+                                                        // Można też zdefiniować bardziej specyficzny trigger np. refreshOrderDetails-{order.id}
+                                                        // i zmodyfikować update_order_status_handler, aby go wysyłał,
+                                                        // jeśli zmiana statusu pochodzi z tej strony (np. przez dodatkowy parametr w PATCH).
+                                                        // Na razie użyjemy globalnego.
+            hx-swap="innerHTML" // Podmienia zawartość tego diva
+            hx-push-url="true"
+        {
+            div ."flex justify-between items-center mb-6 pb-4 border-b border-gray-200" {
+                h1 ."text-2xl sm:text-3xl font-semibold text-gray-800" {
+                    "Szczegóły Zamówienia " (order.order_number)
+                }
+                a href=(format!("/htmx/admin/orders?{}", back_to_list_query_string))
+                   hx-get=(format!("/htmx/admin/orders?{}", back_to_list_query_string))
+                   hx-target="#admin-content" // Celuje w główny kontener panelu admina
+                   hx-swap="innerHTML"
+                   hx-push-url="true"
+                   // hx-push-url=(format!("/admin/zamowienia?{}", back_to_list_query_string)) // Opcjonalnie
+                   class="text-sm text-pink-600 hover:text-pink-700 hover:underline" {
+                    "← Wróć do listy zamówień"
+                }
+            }
+
+            // --- Ostrzeżenie o oflagowanym kliencie ---
+            @if !customer_flags.is_empty() {
+                div ."bg-red-50 border border-red-300 text-red-800 rounded-lg p-4 mb-6" role="alert" {
+                    p ."font-semibold mb-1" { "Uwaga: dane klienta pasują do flag ostrzegawczych" }
+                    ul ."list-disc list-inside text-sm" {
+                        @for flag in &customer_flags {
+                            li { (flag.flag_type.to_string()) " " strong { (flag.value) } " - " (flag.reason) }
+                        }
+                    }
+                }
+            }
+
+            // --- Ocena ryzyka ---
+            @if let Some(risk) = &risk_assessment {
+                div ."bg-white shadow-md rounded-lg p-6 mb-6" {
+                    div ."flex items-center justify-between mb-2" {
+                        h2 ."text-xl font-semibold text-gray-800" { "Ocena ryzyka" }
+                        span class=(format!("px-3 py-1 text-xs font-semibold rounded-full {}", get_risk_badge_classes(risk.score))) {
+                            (risk.score) " / 100"
+                        }
+                    }
+                    @if risk.signals.is_empty() {
+                        p ."text-sm text-gray-500" { "Brak sygnałów ostrzegawczych." }
+                    } @else {
+                        ul ."list-disc list-inside text-sm text-gray-700" {
+                            @for signal in &risk.signals { li { (signal) } }
+                        }
+                    }
+                    @if let Some(ip) = &risk.client_ip {
+                        p ."text-xs text-gray-500 mt-2" { "IP klienta: " (ip) }
+                    }
+                }
+            }
+
+            // --- Podsumowanie Zamówienia i Edycja Statusu ---
+            div ."bg-white shadow-md rounded-lg p-6 mb-6" {
+                h2 ."text-xl font-semibold text-gray-800 mb-4" { "Podsumowanie" }
+                div ."grid grid-cols-1 md:grid-cols-2 gap-4 text-sm" {
+                    div {
+                        p ."text-gray-600" { "ID Zamówienia: " strong ."text-gray-900" { (order.id) } }
+                        p ."text-gray-600" { "Data złożenia: " strong ."text-gray-900" { (order_date_display) } }
+                        p ."text-gray-600" { "Suma zamówienia: " strong ."text-pink-600 font-semibold" { (format_price_maud(order.total_price)) } }
+                        @if order.discount_amount > 0 {
+                            p ."text-gray-600" { "Rabat: "
+                                strong ."text-green-700" { "-" (format_price_maud(order.discount_amount)) }
+                                @if let Some(code) = &coupon_code {
+                                    " (kod " span ."font-mono" { (code) } ")"
+                                }
+                            }
+                        }
+                        @if order.gift_card_amount > 0 {
+                            p ."text-gray-600" { "Zapłacono kartą podarunkową: "
+                                strong ."text-green-700" { (format_price_maud(order.gift_card_amount)) }
+                            }
+                        }
+                        p ."text-gray-600" { "Metoda płatności: "
+                            strong ."text-gray-900" {
+                                @if let Some(pm) = &order.payment_method { (pm.to_string()) } @else { "Nieokreślona" }
+                            }
+                        }
+                        @if let Some(shipping_name) = &order.shipping_method_name {
+                            p ."text-gray-600" { "Metoda dostawy: " strong ."text-gray-900" { (shipping_name) } }
+                        }
+                        @if let Some(locker_code) = &order.inpost_locker_code {
+                            p ."text-gray-600" { "Paczkomat: " strong ."text-gray-900 font-mono" { (locker_code) } }
+                            @if let Some(locker_address) = &order.inpost_locker_address {
+                                p ."text-xs text-gray-500" { (locker_address) }
+                            }
+                            a href=(format!("/api/admin/orders/{}/inpost/label", order.id)) target="_blank"
+                              class="inline-block mt-2 px-3 py-1.5 text-xs font-medium text-white bg-yellow-500 hover:bg-yellow-600 rounded-md" {
+                                "Etykieta InPost (PDF)"
+                            }
+                        }
+                        a href=(format!("/api/admin/orders/{}/thank-you-card", order.id)) target="_blank"
+                          class="inline-block mt-2 px-3 py-1.5 text-xs font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                            "Kartka z podziękowaniem"
+                        }
+                    }
+                    div {
+                        div ."flex items-center space-x-3 mb-2" {
+                            label for="order_status_details" ."text-gray-600 font-medium whitespace-nowrap" { "Status zamówienia:" }
+                            select name="status" id="order_status_details"
+                                   hx-patch=(format!("/api/orders/{}", order.id))
+                                   hx-trigger="change"
+                                   class="block w-full max-w-[200px] pl-3 pr-8 py-1.5 text-xs border-gray-300 focus:outline-none focus:ring-pink-500 focus:border-pink-500 rounded-md shadow-sm appearance-none" {
+                                @for status_opt in OrderStatus::iter() {
+                                    option value=(status_opt.to_form_value())
+                                        selected[order.status == status_opt]
+                                        disabled[order.status != status_opt && !order.status.can_transition_to(&status_opt)] { (status_opt.to_string()) }
+                                }
+                            }
+                        }
+                        // Wyświetlenie aktualnego statusu jako badge (opcjonalne, bo select go pokazuje)
+                        // span class=(format!("px-3 py-1 text-xs font-semibold rounded-full {}", get_order_status_badge_classes(order.status.clone()))) {
+                        //     (order.status.to_string())
+                        // }
+                    }
+                }
+            }
+
+            // --- Historia statusów ---
+            div ."bg-white shadow-md rounded-lg p-6 mb-6" {
+                h2 ."text-xl font-semibold text-gray-800 mb-4" { "Historia statusów" }
+                (render_order_status_timeline(&status_history, true))
+            }
+
+            // --- Dane Klienta i Wysyłki ---
+            div ."bg-white shadow-md rounded-lg p-6 mb-6" {
+                h2 ."text-xl font-semibold text-gray-800 mb-4" { "Dane Klienta i Dostawy" }
+                div ."grid grid-cols-1 md:grid-cols-2 gap-6 text-sm" {
+                    div {
+                        h3 ."text-md font-semibold text-gray-700 mb-1" { "Klient:" }
+                        @if let Some(user_id_val) = order.user_id {
+                            p ."text-gray-800" { "ID Użytkownika: " (user_id_val) }
+                            div ."mt-2" { (render_start_impersonation_form_maud(Some(&user_id_val.to_string()))) }
+                            // Tutaj można by pobrać i wyświetlić email użytkownika, jeśli OrderDetailsResponse go nie zawiera
+                            // Na razie zakładamy, że get_order_details_handler może dołączyć email
+                            // lub użyjemy order.guest_email jeśli user_id jest None
+                        }
+                        @if let Some(guest_email_val) = &order.guest_email {
+                             p ."text-gray-800" { "Email (Gość): " (guest_email_val) }
+                        }
+                    }
+                    div {
+                        h3 ."text-md font-semibold text-gray-700 mb-1" { "Adres dostawy:" }
+                        p ."text-gray-800" {
+                            (order.shipping_first_name) " " (order.shipping_last_name) br;
+                            (order.shipping_address_line1) br;
+                            @if let Some(line2) = &order.shipping_address_line2 { (line2) br; }
+                            (order.shipping_postal_code) " " (order.shipping_city) br;
+                            (order.shipping_country) br;
+                            "Tel: " (order.shipping_phone)
+                        }
+                    }
+                }
+            }
+
+            // --- Lista Produktów w Zamówieniu ---
+            div ."bg-white shadow-md rounded-lg p-6" {
+                h2 ."text-xl font-semibold text-gray-800 mb-4" { "Zamówione Produkty (" (order_details.items.len()) ")" }
+                @if order_details.items.is_empty() {
+                    p ."text-gray-500" { "Brak produktów w tym zamówieniu." }
+                } @else {
+                    ul role="list" ."divide-y divide-gray-200" {
+                        @let list_query_string = list_params.to_query_string();
+                        @for item_detail in &order_details.items {
+                            @let return_url_unencoded = format!("/htmx/admin/order-details/{}?{}", order_id, list_query_string);
+                            @let return_url_encoded = urlencoding::encode(&return_url_unencoded);
+                            @let return_text_encoded = urlencoding::encode("Wróć do szczegółów zamówienia");
+
+                            li ."py-4 flex flex-col sm:flex-row sm:items-center" {
+                                @if let Some(image_url) = item_detail.product.images.get(0) {
+                                    img src=(image_url) alt=(item_detail.product.name)
+                                         class="h-20 w-20 sm:h-24 sm:w-24 flex-shrink-0 rounded-md border border-gray-200 object-cover mb-3 sm:mb-0 sm:mr-4";
+                                } @else {
+                                    div class="h-20 w-20 sm:h-24 sm:w-24 flex-shrink-0 rounded-md border border-gray-200 bg-gray-100 flex items-center justify-center text-xs text-gray-400 mb-3 sm:mb-0 sm:mr-4" {
+                                        "Brak zdjęcia"
+                                    }
+                                }
+                                div ."flex-grow min-w-0" {
+                                    a href=(item_detail.product.public_path())
+                                       hx-get=(format!("/htmx/produkt/{}?return_url={}&return_text={}&return_target=%23admin-content", item_detail.product.slug, return_url_encoded, return_text_encoded))
+                                       hx-target="#admin-content"
+                                       hx-swap="innerHTML"
+                                       hx-push-url=(item_detail.product.public_path())
+                                       class="text-sm font-medium text-pink-600 hover:text-pink-700 hover:underline block truncate" {
+                                        (item_detail.product.name)
+                                    }
+                                    p ."text-xs text-gray-500 mt-1" { "Kategoria: " (item_detail.product.category.to_string()) }
+                                    p ."text-xs text-gray-500" { "Stan: " (item_detail.product.condition.to_string()) }
+                                }
+                                div ."ml-0 sm:ml-4 mt-2 sm:mt-0 text-left sm:text-right flex-shrink-0" {
+                                    p ."text-sm text-gray-700" { "Cena (zakup): " strong{ (format_price_maud(item_detail.price_at_purchase)) } }
+                                    // Jeśli masz ilość (quantity) w OrderItemDetailsPublic:
+                                    // p ."text-xs text-gray-500" { "Ilość: " (item_detail.quantity) }
+                                    @if is_order_editable {
+                                        form hx-post=(format!("/api/orders/{}/items/{}/remove", order.id, item_detail.order_item_id))
+                                             hx-confirm="Usunąć tę pozycję z zamówienia? Klient otrzyma e-mail, a kwota zostanie zarejestrowana do zwrotu."
+                                             hx-swap="none"
+                                             class="mt-2 flex flex-col sm:items-end gap-1" {
+                                            input type="text" name="reason" required placeholder="Powód (np. uszkodzenie)"
+                                                  class="text-xs border-gray-300 rounded-md shadow-sm focus:ring-pink-500 focus:border-pink-500";
+                                            button type="submit" class="text-xs text-red-600 hover:text-red-800 hover:underline" {
+                                                "Usuń pozycję i zwróć kwotę"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // --- Zwroty za usunięte pozycje ---
+            @if !refunds.is_empty() {
+                div ."bg-white shadow-md rounded-lg p-6 mt-6" {
+                    h2 ."text-xl font-semibold text-gray-800 mb-4" { "Zwroty (" (refunds.len()) ")" }
+                    ul role="list" ."divide-y divide-gray-200 text-sm" {
+                        @for refund in &refunds {
+                            li ."py-3 flex flex-col sm:flex-row sm:items-center sm:justify-between gap-2" {
+                                div {
+                                    p ."text-gray-800" {
+                                        "Produkt: "
+                                        a href=(format!("/produkty/{}", refund.product_id)) class="text-pink-600 hover:underline" { (refund.product_id.to_string().chars().take(8).collect::<String>()) }
+                                        " - kwota: " strong { (format_price_maud(refund.amount)) }
+                                    }
+                                    p ."text-xs text-gray-500" { "Powód: " (refund.reason) " (" (format_datetime(&refund.created_at)) ")" }
+                                }
+                                @if let Some(refunded_at) = refund.refunded_at {
+                                    span ."px-3 py-1 text-xs font-semibold rounded-full bg-green-100 text-green-800" {
+                                        "Zwrócono " (format_date(&refunded_at))
+                                    }
+                                } @else {
+                                    button hx-post=(format!("/api/orders/{}/refunds/{}/complete", order.id, refund.id))
+                                           hx-confirm="Potwierdzasz, że pieniądze zostały odesłane klientowi?"
+                                           hx-swap="none"
+                                           class="px-3 py-1 text-xs font-semibold rounded-full bg-yellow-100 text-yellow-800 hover:bg-yellow-200" {
+                                        "Do zwrotu - oznacz jako zwrócone"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        } // Koniec #order-details-page-container
+    };
+
+    let title = format!(
+        "Admin Panel - Szczegóły zamówienia: {} sklep mess - all that vintage",
+        order.order_number
+    );
+    let page_builder = PageBuilder::new(&title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+fn get_risk_badge_classes(score: i16) -> &'static str {
+    if score >= HIGH_RISK_THRESHOLD {
+        "bg-red-100 text-red-800"
+    } else if score >= 30 {
+        "bg-yellow-100 text-yellow-800"
+    } else {
+        "bg-green-100 text-green-800"
+    }
+}
+
+/// Lista flag ostrzegawczych klientów (czarna lista) z formularzem dodawania.
+pub async fn admin_customer_flags_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let flags =
+        sqlx::query_as::<_, CustomerFlag>("SELECT * FROM customer_flags ORDER BY created_at DESC")
+            .fetch_all(&app_state.db_pool)
+            .await?;
+
+    let page_content = html! {
+        div id="admin-customer-flags-container"
+            hx-get="/htmx/admin/customer-flags"
+            hx-trigger="reloadCustomerFlags from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Flagi klientów" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Zamówienia, których e-mail, telefon lub adres pasuje do flagi, są oznaczane ostrzeżeniem w szczegółach zamówienia."
+            }
+
+            form hx-post="/api/admin/customer-flags" hx-swap="none"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-2 lg:grid-cols-4 gap-4 items-end" {
+                    div {
+                        label for="flag_type" ."block text-sm font-medium text-gray-700 mb-1" { "Rodzaj:" }
+                        select name="flag_type" id="flag_type" class="admin-filter-select" {
+                            @for flag_type in CustomerFlagType::iter() {
+                                option value=(flag_type.to_form_value()) { (flag_type.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="flag_value" ."block text-sm font-medium text-gray-700 mb-1" { "Wartość:" }
+                        input type="text" name="value" id="flag_value" required placeholder="np. jan@example.com, 600 100 200" class="admin-filter-input";
+                    }
+                    div {
+                        label for="flag_reason" ."block text-sm font-medium text-gray-700 mb-1" { "Powód:" }
+                        input type="text" name="reason" id="flag_reason" required placeholder="np. 2x nieopłacone zamówienie" class="admin-filter-input";
+                    }
+                    div {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white w-full sm:w-auto" { "Dodaj flagę" }
+                    }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Rodzaj" }
+                            th scope="col" class="admin-th" { "Wartość" }
+                            th scope="col" class="admin-th" { "Powód" }
+                            th scope="col" class="admin-th" { "Dodano" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if flags.is_empty() {
+                            tr { td colspan="5" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak oflagowanych klientów." } }
+                        }
+                        @for flag in &flags {
+                            tr {
+                                td class="admin-td text-xs text-gray-600" { (flag.flag_type.to_string()) }
+                                td class="admin-td font-mono text-xs text-gray-800" { (flag.value) }
+                                td class="admin-td text-sm text-gray-700" { (flag.reason) }
+                                td class="admin-td text-xs text-gray-600" { (format_datetime_admin(&flag.created_at)) }
+                                td class="admin-td text-center" {
+                                    button hx-delete=(format!("/api/admin/customer-flags/{}", flag.id))
+                                           hx-confirm="Usunąć tę flagę?"
+                                           hx-swap="none"
+                                           class="text-xs text-red-600 hover:text-red-800 hover:underline" { "Usuń" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Flagi klientów - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Lista klientów z tagami i filtrem segmentu; segment można wyeksportować do newslettera.
+pub async fn admin_customers_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<CustomerSegmentParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let segment = CustomerSegment::from_params(&params);
+    let search = customer_search(&params);
+    let customers = list_customers(&app_state.db_pool, &segment, search.as_deref()).await?;
+    let query_string = format!(
+        "{}&q={}",
+        segment.query_string(),
+        urlencoding::encode(search.as_deref().unwrap_or_default())
+    );
+
+    let page_content = html! {
+        div id="admin-customers-container"
+            hx-get=(format!("/htmx/admin/klienci?{}", query_string))
+            hx-trigger="reloadCustomers from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Klienci" }
+                a href=(format!("/api/admin/klienci/eksport?{}", query_string))
+                  class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Eksportuj e-maile (CSV)" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Wydana kwota i liczba zamówień obejmują opłacone, nieanulowane zamówienia. "
+                "Segment można też ustawić na kodzie rabatowym. Eksportowane adresy wysyłaj tylko klientom, którzy zgodzili się na newsletter."
+            }
+
+            form hx-get="/htmx/admin/klienci" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-2 lg:grid-cols-5 gap-4 items-end" {
+                    div {
+                        label for="customer_search" ."block text-sm font-medium text-gray-700 mb-1" { "E-mail lub telefon:" }
+                        input type="search" name="q" id="customer_search" placeholder="np. anna@ lub 600100200"
+                               value=[search.as_deref()]
+                               class="admin-filter-input";
+                    }
+                    div {
+                        label for="segment_tag" ."block text-sm font-medium text-gray-700 mb-1" { "Tag:" }
+                        select name="tag" id="segment_tag" class="admin-filter-select" {
+                            option value="" { "Wszyscy" }
+                            @for tag in CustomerTag::iter() {
+                                option value=(tag.to_form_value()) selected[segment.tag == Some(tag)] { (tag.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="segment_min_spent" ."block text-sm font-medium text-gray-700 mb-1" { "Wydali min. (zł):" }
+                        input type="number" name="min_spent" id="segment_min_spent" min="1" placeholder="np. 1000"
+                               value=[segment.min_spent.map(|gr| gr / 100)]
+                               class="admin-filter-input";
+                    }
+                    div {
+                        label for="segment_min_orders" ."block text-sm font-medium text-gray-700 mb-1" { "Min. liczba zamówień:" }
+                        input type="number" name="min_orders" id="segment_min_orders" min="1" placeholder="np. 3"
+                               value=[segment.min_orders]
+                               class="admin-filter-input";
+                    }
+                    div ."flex gap-3" {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Filtruj" }
+                        a href="/htmx/admin/klienci" hx-get="/htmx/admin/klienci" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                          class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-800" { "Wyczyść" }
+                    }
+                }
+            }
+
+            p ."text-sm text-gray-700 mb-2" {
+                "Klientów: " (customers.len())
+                @if !segment.is_empty() { " (segment: " (segment.describe()) ")" }
+                @if let Some(search) = &search { " (wyszukiwanie: „" (search) "”)" }
+            }
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "E-mail" }
+                            th scope="col" class="admin-th" { "Zamówienia" }
+                            th scope="col" class="admin-th" { "Wydano" }
+                            th scope="col" class="admin-th" { "Ostatnie zamówienie" }
+                            th scope="col" class="admin-th" { "Tagi" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if customers.is_empty() {
+                            tr { td colspan="5" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak klientów spełniających kryteria." } }
+                        }
+                        @for customer in &customers {
+                            tr {
+                                td class="admin-td text-sm text-gray-800" {
+                                    a href=(format!("/htmx/admin/klienci/{}", customer.id))
+                                      hx-get=(format!("/htmx/admin/klienci/{}", customer.id))
+                                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                      class="hover:text-pink-600 hover:underline" { (customer.email) }
+                                }
+                                td class="admin-td text-sm text-gray-700" { (customer.orders_count) }
+                                td class="admin-td text-sm text-gray-700" { (format_price_maud(customer.total_spent)) }
+                                td class="admin-td text-xs text-gray-600" {
+                                    @if let Some(last_order_at) = &customer.last_order_at { (format_date(last_order_at)) } @else { "–" }
+                                }
+                                td class="admin-td whitespace-nowrap space-x-1" {
+                                    (customer_tag_buttons_maud(customer.id, &customer.tags))
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Klienci - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Przełączniki tagów klienta - nadany tag klika się, żeby go usunąć
+fn customer_tag_buttons_maud(user_id: Uuid, tags: &[CustomerTag]) -> Markup {
+    html! {
+        @for tag in CustomerTag::iter() {
+            @if tags.contains(&tag) {
+                button hx-delete=(format!("/api/admin/klienci/{}/tagi/{}", user_id, tag.to_form_value()))
+                       hx-swap="none" title="Kliknij, aby usunąć tag"
+                       class="text-xs px-2 py-0.5 rounded-full bg-pink-600 text-white hover:bg-pink-700" { (tag.to_string()) }
+            } @else {
+                button hx-post=(format!("/api/admin/klienci/{}/tagi", user_id))
+                       hx-vals=(serde_json::json!({ "tag": tag.to_form_value() }).to_string())
+                       hx-swap="none" title="Kliknij, aby nadać tag"
+                       class="text-xs px-2 py-0.5 rounded-full border border-gray-300 text-gray-500 hover:bg-gray-100" { (tag.to_string()) }
+            }
+        }
+    }
+}
+
+/// Karta klienta: wartość klienta, dane konta i wysyłki, zamówienia, tagi, notatki i szybkie akcje
+pub async fn admin_customer_details_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(user_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let profile = fetch_customer_profile(&app_state.db_pool, user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let (shipping, orders, tags, notes) = tokio::try_join!(
+        repo::users::shipping_details(&app_state.db_pool, user_id),
+        repo::orders::for_user(&app_state.db_pool, user_id),
+        customer_tags(&app_state.db_pool, user_id),
+        list_customer_notes(&app_state.db_pool, user_id),
+    )?;
+
+    let page_content = html! {
+        div id="admin-customer-details-container"
+            hx-get=(format!("/htmx/admin/klienci/{}", user_id))
+            hx-trigger="reloadCustomers from:body, reloadCustomerProfile from:body"
+            hx-swap="outerHTML"
+        {
+            a href="/htmx/admin/klienci" hx-get="/htmx/admin/klienci" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+              class="text-sm text-pink-600 hover:underline" { "← Wróć do listy klientów" }
+            div ."flex flex-wrap items-center gap-3 mt-2 mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 break-all" { (profile.email) }
+                @if profile.disabled_at.is_some() {
+                    span ."px-2 py-0.5 text-xs font-semibold rounded-full bg-red-100 text-red-800" { "Konto zablokowane" }
+                }
+            }
+
+            div ."grid grid-cols-2 lg:grid-cols-4 gap-4 mb-6" {
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                    p ."text-xs text-gray-500" { "Wartość klienta" }
+                    p ."text-xl font-semibold text-gray-800" { (format_price_maud(profile.total_spent)) }
+                }
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                    p ."text-xs text-gray-500" { "Opłacone zamówienia" }
+                    p ."text-xl font-semibold text-gray-800" {
+                        (profile.orders_count)
+                        span ."text-sm font-normal text-gray-500" { " / " (profile.all_orders_count) " wszystkich" }
+                    }
+                }
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                    p ."text-xs text-gray-500" { "Średnie zamówienie" }
+                    p ."text-xl font-semibold text-gray-800" {
+                        @if let Some(average) = profile.average_order_value() { (format_price_maud(average)) } @else { "–" }
+                    }
+                }
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                    p ."text-xs text-gray-500" { "Pierwsze / ostatnie zamówienie" }
+                    p ."text-sm font-semibold text-gray-800 mt-1" {
+                        @if let (Some(first), Some(last)) = (&profile.first_order_at, &profile.last_order_at) {
+                            (format_date(first)) " / " (format_date(last))
+                        } @else { "Brak zamówień" }
+                    }
+                }
+            }
+
+            div ."grid grid-cols-1 lg:grid-cols-3 gap-6 mb-6" {
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4 text-sm space-y-1" {
+                    h4 ."font-semibold text-gray-800 mb-2" { "Konto" }
+                    p { "Założone: " (format_date(&profile.created_at)) }
+                    p {
+                        "E-mail potwierdzony: "
+                        @if let Some(verified_at) = &profile.email_verified_at { (format_date(verified_at)) } @else { "nie" }
+                    }
+                    p {
+                        "Ostatnie logowanie: "
+                        @if let Some(last_login_at) = &profile.last_login_at { (format_datetime_admin(last_login_at)) } @else { "–" }
+                    }
+                    @if let Some(disabled_at) = &profile.disabled_at {
+                        p ."text-red-700" { "Zablokowane: " (format_datetime_admin(disabled_at)) }
+                    }
+                    div ."pt-2 space-x-1" { (customer_tag_buttons_maud(user_id, &tags)) }
+                }
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4 text-sm space-y-1" {
+                    h4 ."font-semibold text-gray-800 mb-2" { "Dane wysyłki" }
+                    @if let Some(shipping) = &shipping {
+                        p {
+                            (shipping.shipping_first_name.as_deref().unwrap_or_default()) " "
+                            (shipping.shipping_last_name.as_deref().unwrap_or_default())
+                        }
+                        p { (shipping.shipping_address_line1.as_deref().unwrap_or_default()) }
+                        @if let Some(line2) = shipping.shipping_address_line2.as_deref().filter(|line| !line.is_empty()) {
+                            p { (line2) }
+                        }
+                        p {
+                            (shipping.shipping_postal_code.as_deref().unwrap_or_default()) " "
+                            (shipping.shipping_city.as_deref().unwrap_or_default())
+                        }
+                        p { (shipping.shipping_country.as_deref().unwrap_or_default()) }
+                        @if let Some(phone) = shipping.shipping_phone.as_deref().filter(|phone| !phone.is_empty()) {
+                            p { "Tel.: " a href=(format!("tel:{}", phone)) class="text-pink-600 hover:underline" { (phone) } }
+                        }
+                    } @else {
+                        p ."text-gray-500" { "Klient nie zapisał danych wysyłki." }
+                    }
+                }
+                div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4 text-sm" {
+                    h4 ."font-semibold text-gray-800 mb-2" { "Szybkie akcje" }
+                    div ."flex flex-col gap-2" {
+                        button type="button"
+                               hx-post=(format!("/api/admin/klienci/{}/reset-hasla", user_id))
+                               hx-swap="none"
+                               hx-confirm=(format!("Wysłać do {} link do ustawienia nowego hasła?", profile.email))
+                               class="px-3 py-2 text-xs font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" {
+                            "Wyślij e-mail z resetem hasła"
+                        }
+                        @if profile.disabled_at.is_some() {
+                            button type="button"
+                                   hx-post=(format!("/api/admin/klienci/{}/blokada", user_id))
+                                   hx-vals=r#"{"disabled": "false"}"#
+                                   hx-swap="none"
+                                   class="px-3 py-2 text-xs font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" {
+                                "Odblokuj konto"
+                            }
+                        } @else {
+                            button type="button"
+                                   hx-post=(format!("/api/admin/klienci/{}/blokada", user_id))
+                                   hx-vals=r#"{"disabled": "true"}"#
+                                   hx-swap="none"
+                                   hx-confirm=(format!("Zablokować konto {}? Klient nie będzie mógł się zalogować.", profile.email))
+                                   class="px-3 py-2 text-xs font-medium rounded-md border border-red-300 text-red-700 hover:bg-red-50" {
+                                "Zablokuj konto"
+                            }
+                        }
+                    }
+                }
+            }
+
+            div ."bg-white rounded-lg shadow-sm border border-gray-200 mb-6 overflow-x-auto" {
+                h4 ."font-semibold text-gray-800 p-4 pb-2" { "Zamówienia" }
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Numer" }
+                            th ."admin-th" { "Data" }
+                            th ."admin-th" { "Status" }
+                            th ."admin-th text-right" { "Kwota" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if orders.is_empty() {
+                            tr { td colspan="4" ."admin-td text-center text-gray-500" { "Klient nie złożył jeszcze zamówienia." } }
+                        }
+                        @for order in &orders {
+                            tr {
+                                td ."admin-td font-mono text-xs" {
+                                    a href=(format!("/htmx/admin/order-details/{}", order.id))
+                                      hx-get=(format!("/htmx/admin/order-details/{}", order.id))
+                                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                      class="hover:text-pink-600 hover:underline" { (order.order_number) }
+                                }
+                                td ."admin-td text-xs text-gray-600" { (format_datetime_admin(&order.order_date)) }
+                                td ."admin-td" {
+                                    span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", get_order_status_badge_classes(order.status.clone()))) {
+                                        (order.status.to_string())
+                                    }
+                                }
+                                td ."admin-td text-right" { (format_price_maud(order.total_price)) }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                h4 ."font-semibold text-gray-800 mb-2" { "Notatki" }
+                p ."text-xs text-gray-500 mb-3" { "Widoczne tylko dla personelu sklepu." }
+                form hx-post=(format!("/api/admin/klienci/{}/notatki", user_id)) hx-swap="none"
+                     class="mb-4" {
+                    textarea name="content" rows="3" required maxlength=(MAX_CUSTOMER_NOTE_LEN)
+                             placeholder="np. prosi o wysyłkę tylko do Paczkomatu"
+                             class="w-full p-2 border border-gray-300 rounded-md text-sm focus:ring-pink-500 focus:border-pink-500" {}
+                    button type="submit" class="mt-2 admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Dodaj notatkę" }
+                }
+                @if notes.is_empty() {
+                    p ."text-sm text-gray-500" { "Brak notatek." }
+                }
+                ul ."divide-y divide-gray-200" {
+                    @for note in &notes {
+                        li ."py-3 flex justify-between gap-4" {
+                            div {
+                                p ."text-sm text-gray-800 whitespace-pre-line" { (note.content) }
+                                p ."text-xs text-gray-500 mt-1" {
+                                    (format_datetime_admin(&note.created_at))
+                                    @if let Some(author) = &note.created_by_email { " · " (author) }
+                                }
+                            }
+                            button type="button"
+                                   hx-delete=(format!("/api/admin/klienci/{}/notatki/{}", user_id, note.id))
+                                   hx-swap="none"
+                                   hx-confirm="Usunąć notatkę?"
+                                   class="text-xs text-red-600 hover:underline shrink-0" { "Usuń" }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = format!(
+        "Admin Panel - Klient {} - sklep mess - all that vintage",
+        profile.email
+    );
+    let page_builder = PageBuilder::new(&title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Tabela przeliczania rozmiarów z metek na współczesne, używana przez filtr rozmiaru
+pub async fn admin_size_mappings_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    let mappings = list_size_mappings(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-size-mappings-container"
+            hx-get="/htmx/admin/rozmiary"
+            hx-trigger="reloadSizeMappings from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Tabela rozmiarów" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Rozmiar z metki (np. francuskie \"38\" albo \"UK 10\") jest przeliczany na współczesny, więc produkt pojawia się też przy filtrze \"M\". "
+                "Puste pola kategorii i płci oznaczają mapowanie dla wszystkich; mapowanie dla konkretnej kategorii ma pierwszeństwo."
+            }
+
+            form hx-post="/api/admin/rozmiary" hx-swap="none"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-3 lg:grid-cols-6 gap-4 items-end" {
+                    div {
+                        label for="size_mapping_category" ."block text-sm font-medium text-gray-700 mb-1" { "Kategoria:" }
+                        select name="category" id="size_mapping_category" class="admin-filter-select" {
+                            option value="" { "Wszystkie" }
+                            @for category in Category::iter() {
+                                option value=(category.as_ref()) { (category.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="size_mapping_gender" ."block text-sm font-medium text-gray-700 mb-1" { "Płeć:" }
+                        select name="gender" id="size_mapping_gender" class="admin-filter-select" {
+                            option value="" { "Obie" }
+                            @for gender in ProductGender::iter() {
+                                option value=(gender.as_ref()) { (gender.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="size_mapping_system" ."block text-sm font-medium text-gray-700 mb-1" { "Rozmiarówka:" }
+                        select name="size_system" id="size_mapping_system" class="admin-filter-select" {
+                            @for system in SIZE_SYSTEMS {
+                                option value=(system) { (system) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="size_mapping_label" ."block text-sm font-medium text-gray-700 mb-1" { "Rozmiar z metki:" }
+                        input type="text" name="label_size" id="size_mapping_label" required maxlength="30" placeholder="np. FR 38" class="admin-filter-input";
+                    }
+                    div {
+                        label for="size_mapping_modern" ."block text-sm font-medium text-gray-700 mb-1" { "Współczesny:" }
+                        input type="text" name="modern_size" id="size_mapping_modern" required maxlength="30" placeholder="np. M" class="admin-filter-input";
+                    }
+                    div {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white w-full sm:w-auto" { "Zapisz" }
+                    }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Kategoria" }
+                            th scope="col" class="admin-th" { "Płeć" }
+                            th scope="col" class="admin-th" { "Rozmiarówka" }
+                            th scope="col" class="admin-th" { "Z metki" }
+                            th scope="col" class="admin-th" { "Współczesny" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if mappings.is_empty() {
+                            tr { td colspan="6" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak mapowań rozmiarów." } }
+                        }
+                        @for mapping in &mappings {
+                            tr {
+                                td class="admin-td text-xs text-gray-600" {
+                                    (mapping.category.map_or("Wszystkie".to_string(), |category| category.to_string()))
+                                }
+                                td class="admin-td text-xs text-gray-600" {
+                                    (mapping.gender.map_or("Obie".to_string(), |gender| gender.to_string()))
+                                }
+                                td class="admin-td text-xs text-gray-600" { (mapping.size_system) }
+                                td class="admin-td font-mono text-sm text-gray-800" { (mapping.label_size) }
+                                td class="admin-td font-semibold text-sm text-gray-800" { (mapping.modern_size) }
+                                td class="admin-td text-center" {
+                                    button hx-delete=(format!("/api/admin/rozmiary/{}", mapping.id))
+                                           hx-confirm="Usunąć to mapowanie rozmiaru?"
+                                           hx-swap="none"
+                                           class="text-xs text-red-600 hover:text-red-800 hover:underline" { "Usuń" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Tabela rozmiarów - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Wskazówki pielęgnacji według materiału - strona produktu, e-mail o wysyłce i kartka do paczki
+pub async fn admin_care_instructions_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    let instructions = list_care_instructions(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-care-instructions-container"
+            hx-get="/htmx/admin/pielegnacja"
+            hx-trigger="reloadCareInstructions from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Pielęgnacja" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Wskazówka pojawia się przy produktach, których materiał zawiera tę nazwę - także w odmianie (\"Wełna\" pasuje do \"100% wełna\" i \"wełniany\"). "
+                "Zapisanie istniejącego materiału podmienia jego wskazówkę."
+            }
+
+            form hx-post="/api/admin/pielegnacja" hx-swap="none"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-4 gap-4 items-end" {
+                    div {
+                        label for="care_material" ."block text-sm font-medium text-gray-700 mb-1" { "Materiał:" }
+                        input type="text" name="material" id="care_material" required maxlength="40" placeholder="np. Wełna" class="admin-filter-input";
+                    }
+                    div ."sm:col-span-2" {
+                        label for="care_instructions_text" ."block text-sm font-medium text-gray-700 mb-1" { "Wskazówka:" }
+                        input type="text" name="instructions" id="care_instructions_text" required maxlength="500" placeholder="np. Pierz ręcznie w letniej wodzie" class="admin-filter-input";
+                    }
+                    div {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white w-full sm:w-auto" { "Zapisz" }
+                    }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Materiał" }
+                            th scope="col" class="admin-th" { "Wskazówka" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if instructions.is_empty() {
+                            tr { td colspan="3" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak wskazówek pielęgnacji." } }
+                        }
+                        @for instruction in &instructions {
+                            tr {
+                                td class="admin-td font-semibold text-sm text-gray-800" { (instruction.material) }
+                                td class="admin-td text-sm text-gray-600 whitespace-normal" { (instruction.instructions) }
+                                td class="admin-td text-center" {
+                                    button hx-delete=(format!("/api/admin/pielegnacja/{}", instruction.id))
+                                           hx-confirm="Usunąć tę wskazówkę pielęgnacji?"
+                                           hx-swap="none"
+                                           class="text-xs text-red-600 hover:text-red-800 hover:underline" { "Usuń" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Pielęgnacja - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Zwrot na liście w panelu admina; po decyzji podmieniany w miejscu (outerHTML).
+pub fn render_admin_return_card_maud(details: &ReturnDetails) -> Markup {
+    let request = &details.request;
+    let order_id_short = request
+        .order_id
+        .to_string()
+        .chars()
+        .take(8)
+        .collect::<String>();
+    html! {
+        div id=(format!("admin-return-{}", request.id)) ."bg-white rounded-lg shadow-sm border border-gray-200 p-4 text-sm" {
+            div ."flex flex-wrap justify-between items-center gap-2 mb-3" {
+                div {
+                    span ."font-semibold text-gray-800" { (return_reference(request)) }
+                    " do zamówienia "
+                    a href=(format!("/htmx/admin/order-details/{}", request.order_id))
+                      hx-get=(format!("/htmx/admin/order-details/{}", request.order_id))
+                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                      class="text-pink-700 hover:underline font-mono" { "#" (order_id_short) }
+                }
+                div ."flex items-center gap-2" {
+                    (return_sla_maud(details))
+                    span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", return_status_badge_classes(request.status))) {
+                        (request.status.to_string())
+                    }
+                }
+            }
+            div ."grid grid-cols-1 md:grid-cols-2 gap-4" {
+                div {
+                    ul ."text-gray-700 mb-2" {
+                        @for item in &details.items {
+                            li { (item.product_name) " - " (format_price_maud(item.amount)) }
+                        }
+                    }
+                    @if let Some(label) = &details.label {
+                        p ."text-gray-600" {
+                            "Etykieta zwrotna InPost: -" (format_price_maud(label.price))
+                            @if let Some(tracking_number) = &label.tracking_number {
+                                " (" span ."font-mono" { (tracking_number) } ")"
+                            }
+                            @if label.emailed_at.is_none() { " - jeszcze nie wysłana klientowi" }
+                        }
+                    }
+                    p ."font-medium text-gray-800" { "Razem do zwrotu: " (format_price_maud(details.refund_amount())) }
+                }
+                div ."space-y-1 text-gray-600" {
+                    p { "Zgłoszono: " (format_datetime_admin(&request.created_at)) }
+                    p { "Konto: " span ."font-mono text-gray-800" { (request.bank_account) } }
+                    p { "Powód: " @if request.reason.is_empty() { "–" } @else { (request.reason) } }
+                    @if let Some(decided_at) = &request.decided_at {
+                        p { "Decyzja: " (format_datetime_admin(decided_at)) }
+                    }
+                    @if let Some(note) = &request.admin_note {
+                        p { "Uwagi: " (note) }
+                    }
+                }
+            }
+            @if request.status == ReturnStatus::Requested {
+                form ."mt-4 flex flex-col sm:flex-row gap-2 sm:items-end" {
+                    div ."flex-1" {
+                        label for=(format!("return-note-{}", request.id)) ."block text-xs font-medium text-gray-600 mb-1" { "Uwagi dla klienta (opcjonalnie):" }
+                        input type="text" name="admin_note" id=(format!("return-note-{}", request.id)) maxlength="500" class="admin-filter-input";
+                    }
+                    button type="button"
+                           hx-post=(format!("/api/admin/returns/{}/approve", request.id))
+                           hx-target=(format!("#admin-return-{}", request.id)) hx-swap="outerHTML"
+                           hx-confirm="Przyjąć zwrot? Produkty wrócą do sprzedaży, a zwrot środków zostanie zarejestrowany w zamówieniu."
+                           class="admin-filter-button bg-green-600 hover:bg-green-700 text-white" { "Przyjmij" }
+                    button type="button"
+                           hx-post=(format!("/api/admin/returns/{}/reject", request.id))
+                           hx-target=(format!("#admin-return-{}", request.id)) hx-swap="outerHTML"
+                           hx-confirm="Odrzucić zwrot?"
+                           class="admin-filter-button bg-red-600 hover:bg-red-700 text-white" { "Odrzuć" }
+                }
+            }
+        }
+    }
+}
+
+/// GET /htmx/admin/returns - zgłoszone zwroty, najpierw oczekujące na decyzję
+pub async fn admin_returns_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(query): Query<AdminReturnsQuery>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+    let status_filter = query
+        .status
+        .as_deref()
+        .and_then(|status| ReturnStatus::from_str(status).ok());
+    let returns = list_returns(&app_state.db_pool, status_filter).await?;
+
+    let page_content = html! {
+        div #admin-returns-container {
+            div ."flex flex-col sm:flex-row justify-between sm:items-center gap-3 mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Zwroty" }
+                form hx-get="/htmx/admin/returns" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                     hx-trigger="change" {
+                    select name="status" class="admin-filter-select" {
+                        option value="" selected[status_filter.is_none()] { "Wszystkie" }
+                        @for status in ReturnStatus::iter() {
+                            option value=(status.as_ref()) selected[status_filter == Some(status)] { (status.to_string()) }
+                        }
+                    }
+                }
+            }
+            @if returns.is_empty() {
+                p ."px-4 py-10 text-center text-gray-500 italic bg-white rounded-lg border border-gray-200" { "Brak zwrotów." }
+            } @else {
+                div ."space-y-4" {
+                    @for return_details in &returns {
+                        (render_admin_return_card_maud(return_details))
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Zwroty - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Odliczanie do ustawowego terminu (`deadline_label` opisuje termin w podpowiedzi)
+fn sla_badge_maud(deadline: DateTime<Utc>, deadline_label: &str) -> Markup {
+    let now = Utc::now();
+    let remaining = deadline - now;
+    let days_left = remaining.num_days();
+    let (classes, label) = match SlaStage::for_deadline(deadline, now) {
+        SlaStage::Overdue => {
+            let overdue_days = (-remaining).num_days();
+            let label = if overdue_days == 0 {
+                "Termin minął dzisiaj".to_string()
+            } else {
+                format!(
+                    "Termin minął {} temu",
+                    pluralize(overdue_days, "dzień", "dni", "dni")
+                )
+            };
+            ("bg-red-100 text-red-800", label)
+        }
+        SlaStage::DueToday => (
+            "bg-red-100 text-red-800",
+            format!("Zostało {} h", remaining.num_hours().max(1)),
+        ),
+        SlaStage::DueSoon => (
+            "bg-orange-100 text-orange-800",
+            format!(
+                "Do terminu: {}",
+                pluralize(days_left, "dzień", "dni", "dni")
+            ),
+        ),
+        SlaStage::OnTrack => (
+            "bg-gray-100 text-gray-700",
+            format!(
+                "Do terminu: {}",
+                pluralize(days_left, "dzień", "dni", "dni")
+            ),
+        ),
+    };
+    html! {
+        span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", classes))
+             title=(format!("{}: {}", deadline_label, format_datetime_admin(&deadline))) {
+            (label)
+        }
+    }
+}
+
+/// Odliczanie do ustawowego terminu odpowiedzi na reklamację
+fn complaint_sla_maud(complaint: &Complaint) -> Markup {
+    if let Some(resolved_at) = &complaint.resolved_at {
+        return html! {
+            span ."text-xs text-gray-500" { "Odpowiedź: " (format_datetime_admin(resolved_at)) }
+        };
+    }
+    sla_badge_maud(response_deadline(complaint), "Termin odpowiedzi")
+}
+
+/// Odliczanie do ustawowego terminu zwrotu pieniędzy
+fn return_sla_maud(details: &ReturnDetails) -> Markup {
+    if let Some(refunded_at) = &details.refunded_at {
+        return html! {
+            span ."text-xs text-gray-500" { "Zwrócono: " (format_datetime_admin(refunded_at)) }
+        };
+    }
+    if !details.refund_pending() {
+        return html! {};
+    }
+    sla_badge_maud(details.refund_deadline(), "Termin zwrotu pieniędzy")
+}
+
+/// Reklamacja w kolejce w panelu admina; po zmianie statusu podmieniana w miejscu (outerHTML).
+pub fn render_admin_complaint_card_maud(complaint: &Complaint) -> Markup {
+    let order_id_short = complaint
+        .order_id
+        .to_string()
+        .chars()
+        .take(8)
+        .collect::<String>();
+    html! {
+        div id=(format!("admin-complaint-{}", complaint.id)) ."bg-white rounded-lg shadow-sm border border-gray-200 p-4 text-sm" {
+            div ."flex flex-wrap justify-between items-center gap-2 mb-3" {
+                div {
+                    span ."font-semibold text-gray-800" { (complaint_reference(complaint)) }
+                    " do zamówienia "
+                    a href=(format!("/htmx/admin/order-details/{}", complaint.order_id))
+                      hx-get=(format!("/htmx/admin/order-details/{}", complaint.order_id))
+                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                      class="text-pink-700 hover:underline font-mono" { "#" (order_id_short) }
+                }
+                div ."flex items-center gap-2" {
+                    (complaint_sla_maud(complaint))
+                    span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", complaint_status_badge_classes(complaint.status))) {
+                        (complaint.status.to_string())
+                    }
+                }
+            }
+            p ."font-medium text-gray-800" { (complaint.product_name) }
+            p ."text-gray-700 mt-1 whitespace-pre-line" { (complaint.description) }
+            (complaint_photos_maud(complaint))
+            p ."text-xs text-gray-500 mt-2" { "Złożono: " (format_datetime_admin(&complaint.created_at)) }
+            @if let Some(resolution) = &complaint.resolution {
+                p ."text-gray-600 mt-1" { "Odpowiedź: " (resolution) }
+            }
+            @if !complaint.status.is_resolved() {
+                form hx-post=(format!("/api/admin/reklamacje/{}/status", complaint.id))
+                     hx-target=(format!("#admin-complaint-{}", complaint.id)) hx-swap="outerHTML"
+                     class="mt-4 flex flex-col sm:flex-row gap-2 sm:items-end" {
+                    div {
+                        label for=(format!("complaint-status-{}", complaint.id)) ."block text-xs font-medium text-gray-600 mb-1" { "Nowy status:" }
+                        select name="status" id=(format!("complaint-status-{}", complaint.id)) class="admin-filter-select" {
+                            @for status in ComplaintStatus::iter().filter(|status| *status != ComplaintStatus::Open) {
+                                option value=(status.as_ref()) selected[status == complaint.status] { (status.to_string()) }
+                            }
+                        }
+                    }
+                    div ."flex-1" {
+                        label for=(format!("complaint-resolution-{}", complaint.id)) ."block text-xs font-medium text-gray-600 mb-1" {
+                            "Odpowiedź dla klienta (wymagana przy uznaniu i odrzuceniu):"
+                        }
+                        input type="text" name="resolution" id=(format!("complaint-resolution-{}", complaint.id))
+                              maxlength="1000" class="admin-filter-input";
+                    }
+                    button type="submit" class="admin-filter-button" { "Zapisz i powiadom klienta" }
+                }
+            }
+        }
+    }
+}
+
+/// GET /htmx/admin/reklamacje - kolejka reklamacji z odliczaniem 14-dniowego terminu
+pub async fn admin_complaints_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(query): Query<AdminComplaintsQuery>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageOrders)?;
+    let status_filter = query
+        .status
+        .as_deref()
+        .and_then(|status| ComplaintStatus::from_str(status).ok());
+    let complaints = list_complaints(&app_state.db_pool, status_filter).await?;
+
+    let page_content = html! {
+        div #admin-complaints-container {
+            div ."flex flex-col sm:flex-row justify-between sm:items-center gap-3 mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Reklamacje" }
+                form hx-get="/htmx/admin/reklamacje" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                     hx-trigger="change" {
+                    select name="status" class="admin-filter-select" {
+                        option value="" selected[status_filter.is_none()] { "Wszystkie" }
+                        @for status in ComplaintStatus::iter() {
+                            option value=(status.as_ref()) selected[status_filter == Some(status)] { (status.to_string()) }
+                        }
+                    }
+                }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Na reklamację trzeba odpowiedzieć w ciągu 14 dni od jej złożenia - brak odpowiedzi w terminie oznacza jej uznanie."
+            }
+            @if complaints.is_empty() {
+                p ."px-4 py-10 text-center text-gray-500 italic bg-white rounded-lg border border-gray-200" { "Brak reklamacji." }
+            } @else {
+                div ."space-y-4" {
+                    @for complaint in &complaints {
+                        (render_admin_complaint_card_maud(complaint))
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Reklamacje - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+fn review_status_badge_classes(status: ReviewStatus) -> &'static str {
+    match status {
+        ReviewStatus::Pending => "bg-yellow-100 text-yellow-800",
+        ReviewStatus::Approved => "bg-green-100 text-green-800",
+        ReviewStatus::Rejected => "bg-red-100 text-red-800",
+    }
+}
+
+pub fn render_admin_review_card_maud(review: &ProductReview) -> Markup {
+    html! {
+        div id=(format!("admin-review-{}", review.id)) ."bg-white rounded-lg shadow-sm border border-gray-200 p-4 text-sm" {
+            div ."flex flex-wrap justify-between items-center gap-2 mb-2" {
+                div ."flex flex-wrap items-center gap-2" {
+                    a href=(format!("/htmx/admin/products/{}/edit", review.product_id))
+                      hx-get=(format!("/htmx/admin/products/{}/edit", review.product_id))
+                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                      class="font-semibold text-pink-700 hover:underline" { (review.product_name) }
+                    (rating_stars_maud(f64::from(review.rating)))
+                }
+                span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", review_status_badge_classes(review.status))) {
+                    (review.status.to_string())
+                }
+            }
+            p ."text-gray-700 whitespace-pre-line" { (review.content) }
+            p ."text-xs text-gray-500 mt-2" {
+                (review.author_name)
+                @if review.verified_purchase { " · zweryfikowany zakup" }
+                " · dodano " (format_datetime_admin(&review.created_at))
+                @if let Some(moderated_at) = review.moderated_at {
+                    " · moderacja " (format_datetime_admin(&moderated_at))
+                }
+            }
+            div ."mt-3 flex gap-2" {
+                @if review.status != ReviewStatus::Approved {
+                    button type="button"
+                           hx-post=(format!("/api/admin/opinie/{}/approve", review.id))
+                           hx-target=(format!("#admin-review-{}", review.id)) hx-swap="outerHTML"
+                           class="admin-filter-button bg-green-600 hover:bg-green-700 text-white" { "Opublikuj" }
+                }
+                @if review.status != ReviewStatus::Rejected {
+                    button type="button"
+                           hx-post=(format!("/api/admin/opinie/{}/reject", review.id))
+                           hx-target=(format!("#admin-review-{}", review.id)) hx-swap="outerHTML"
+                           hx-confirm="Odrzucić opinię? Nie będzie widoczna na stronie produktu."
+                           class="admin-filter-button bg-red-600 hover:bg-red-700 text-white" { "Odrzuć" }
+                }
+            }
+        }
+    }
+}
+
+/// GET /htmx/admin/opinie - moderacja opinii, najpierw oczekujące
+pub async fn admin_reviews_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(query): Query<AdminReviewsQuery>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ModerateReviews)?;
+    let status_filter = query
+        .status
+        .as_deref()
+        .and_then(|status| ReviewStatus::from_str(status).ok());
+    let reviews = list_reviews(&app_state.db_pool, status_filter).await?;
+
+    let page_content = html! {
+        div #admin-reviews-container {
+            div ."flex flex-col sm:flex-row justify-between sm:items-center gap-3 mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Opinie" }
+                form hx-get="/htmx/admin/opinie" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                     hx-trigger="change" {
+                    select name="status" class="admin-filter-select" {
+                        option value="" selected[status_filter.is_none()] { "Wszystkie" }
+                        @for status in ReviewStatus::iter() {
+                            option value=(status.as_ref()) selected[status_filter == Some(status)] { (status.to_string()) }
+                        }
+                    }
+                }
+            }
+            @if reviews.is_empty() {
+                p ."px-4 py-10 text-center text-gray-500 italic bg-white rounded-lg border border-gray-200" { "Brak opinii." }
+            } @else {
+                div ."space-y-4" {
+                    @for review in &reviews {
+                        (render_admin_review_card_maud(review))
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Opinie - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// GET /htmx/admin/podobne - wyszukiwanie produktów po zdjęciu, np. ze zrzutu z Instagrama
+pub async fn admin_photo_search_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    let page_content = html! {
+        div #admin-photo-search-container {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Wyszukaj podobne" }
+            p ."text-sm text-gray-600 mb-6" {
+                "Wgraj zdjęcie (np. zrzut ekranu z Instagrama), a pokażemy produkty z podobnymi zdjęciami - także sprzedane."
+            }
+            (photo_search_form_maud("/htmx/admin/podobne"))
+            div #photo-search-results ."mt-6" {}
+        }
+    };
+
+    let title = "Admin Panel - Wyszukaj podobne - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// POST /htmx/admin/podobne - wyniki wyszukiwania po zdjęciu dla admina (wszystkie statusy poza archiwum)
+pub async fn admin_photo_search_results_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    mut multipart: Multipart,
+) -> Result<Markup, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+    let hash = match photo_search_hash(&mut multipart).await? {
+        Ok(hash) => hash,
+        Err(message) => return Ok(photo_search_message_maud(message)),
+    };
+    let statuses = [
+        ProductStatus::Available,
+        ProductStatus::Reserved,
+        ProductStatus::Sold,
+    ];
+    let results = find_similar_products(
+        &app_state.db_pool,
+        hash,
+        &statuses,
+        PHOTO_SEARCH_ADMIN_LIMIT,
+    )
+    .await?;
+    tracing::info!(
+        "Admin {}: wyszukiwanie po zdjęciu, {} wyników",
+        claims.sub,
+        results.len()
+    );
+
+    Ok(html! {
+        @if results.is_empty() {
+            (photo_search_message_maud("Nie znaleziono produktów z podobnym zdjęciem."))
+        } @else {
+            div ."grid grid-cols-2 sm:grid-cols-3 lg:grid-cols-4 gap-4" {
+                @for (product, distance) in &results {
+                    a href=(format!("/htmx/admin/products/{}/edit", product.id))
+                      hx-get=(format!("/htmx/admin/products/{}/edit", product.id))
+                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                      class="block bg-white rounded-lg border border-gray-200 overflow-hidden hover:shadow-md transition-shadow" {
+                        @if let Some(image_url) = product.images.first() {
+                            img src=(transform_cloudinary_url(image_url, PHOTO_SEARCH_THUMBNAIL_TRANSFORMATION))
+                                alt=(product.name) class="w-full aspect-square object-cover" loading="lazy";
+                        }
+                        div ."p-3 text-sm" {
+                            p ."font-medium text-gray-800 truncate" { (product.name) }
+                            p ."text-gray-600" { (format_price_maud(product.price)) " · " (product.status.to_string()) }
+                            p ."text-xs text-gray-500" { "Podobieństwo: " (image_similarity_percent(*distance)) "%" }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CouponAdminParams {
+    /// Kod wczytany do formularza edycji
+    pub edit: Option<Uuid>,
+}
+
+/// Lista kodów rabatowych z formularzem dodawania i edycji.
+pub async fn admin_coupons_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<CouponAdminParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    // Kody z kampanii mają osobną stronę - tu byłoby ich zbyt wiele
+    let coupons = sqlx::query_as::<_, Coupon>(
+        "SELECT * FROM coupons WHERE campaign_id IS NULL ORDER BY created_at DESC",
+    )
+    .fetch_all(&app_state.db_pool)
+    .await?;
+    let editing = params
+        .edit
+        .and_then(|id| coupons.iter().find(|coupon| coupon.id == id));
+
+    let form_action = match editing {
+        Some(coupon) => format!("/api/admin/coupons/{}", coupon.id),
+        None => "/api/admin/coupons".to_string(),
+    };
+    let selected_type = editing.map_or(CouponDiscountType::Percentage, |c| c.discount_type);
+    let expires_value = editing
+        .and_then(|c| c.expires_at)
+        .map(|dt| to_shop_time(&dt).format("%Y-%m-%d").to_string());
+
+    let page_content = html! {
+        div id="admin-coupons-container"
+            hx-get="/htmx/admin/coupons"
+            hx-trigger="reloadCoupons from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Kody rabatowe" }
+                a href="/htmx/admin/coupons/kampanie" hx-get="/htmx/admin/coupons/kampanie" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                  class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Kampanie kodów" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Rabat liczony jest od wartości produktów (bez dostawy). Kwoty podajemy w groszach, np. 2000 = 20,00 zł."
+            }
+
+            form hx-post=(form_action) hx-swap="none"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                @if let Some(coupon) = editing {
+                    p ."text-sm font-medium text-gray-800 mb-3" {
+                        "Edycja kodu " span ."font-mono" { (coupon.code) }
+                    }
+                }
+                div ."grid grid-cols-1 sm:grid-cols-2 lg:grid-cols-3 gap-4 items-end" {
+                    div {
+                        label for="coupon_code" ."block text-sm font-medium text-gray-700 mb-1" { "Kod:" }
+                        input type="text" name="code" id="coupon_code" required placeholder="np. LATO20"
+                               value=[editing.map(|c| c.code.as_str())]
+                               class="admin-filter-input uppercase";
+                    }
+                    div {
+                        label for="coupon_discount_type" ."block text-sm font-medium text-gray-700 mb-1" { "Rodzaj rabatu:" }
+                        select name="discount_type" id="coupon_discount_type" class="admin-filter-select" {
+                            @for discount_type in CouponDiscountType::iter() {
+                                option value=(discount_type.to_form_value()) selected[discount_type == selected_type] {
+                                    (discount_type.to_string())
+                                }
+                            }
+                        }
+                    }
+                    div {
+                        label for="coupon_value" ."block text-sm font-medium text-gray-700 mb-1" { "Wartość (% lub gr):" }
+                        input type="number" name="value" id="coupon_value" required min="1"
+                               value=[editing.map(|c| c.value)]
+                               class="admin-filter-input";
+                    }
+                    div {
+                        label for="coupon_min_order_value" ."block text-sm font-medium text-gray-700 mb-1" { "Min. wartość produktów (gr):" }
+                        input type="number" name="min_order_value" id="coupon_min_order_value" required min="0"
+                               value=(editing.map_or(0, |c| c.min_order_value))
+                               class="admin-filter-input";
+                    }
+                    div {
+                        label for="coupon_expires_at" ."block text-sm font-medium text-gray-700 mb-1" { "Ważny do (włącznie):" }
+                        input type="date" name="expires_at" id="coupon_expires_at"
+                               value=[expires_value.as_deref()]
+                               class="admin-filter-input";
+                    }
+                    div ."flex items-center h-10" {
+                        input type="checkbox" name="single_use_per_user" id="coupon_single_use" value="true"
+                               checked[editing.is_some_and(|c| c.single_use_per_user)]
+                               class="h-4 w-4 text-pink-600 border-gray-300 rounded focus:ring-pink-500";
+                        label for="coupon_single_use" ."ml-2 text-sm text-gray-700" { "Jednorazowy dla klienta" }
+                    }
+                }
+                p ."mt-4 mb-2 text-sm font-medium text-gray-700" { "Tylko dla segmentu klientów (puste = dla wszystkich):" }
+                div ."grid grid-cols-1 sm:grid-cols-2 lg:grid-cols-3 gap-4 items-end" {
+                    div {
+                        label for="coupon_segment_tag" ."block text-sm font-medium text-gray-700 mb-1" { "Tag klienta:" }
+                        select name="segment_tag" id="coupon_segment_tag" class="admin-filter-select" {
+                            option value="" { "Dowolny" }
+                            @for tag in CustomerTag::iter() {
+                                option value=(tag.to_form_value()) selected[editing.is_some_and(|c| c.segment_tag == Some(tag))] {
+                                    (tag.to_string())
+                                }
+                            }
+                        }
+                    }
+                    div {
+                        label for="coupon_segment_min_spent" ."block text-sm font-medium text-gray-700 mb-1" { "Wydali min. (gr):" }
+                        input type="number" name="segment_min_spent" id="coupon_segment_min_spent" min="1" placeholder="np. 100000"
+                               value=[editing.and_then(|c| c.segment_min_spent)]
+                               class="admin-filter-input";
+                    }
+                    div {
+                        label for="coupon_segment_min_orders" ."block text-sm font-medium text-gray-700 mb-1" { "Min. liczba zamówień:" }
+                        input type="number" name="segment_min_orders" id="coupon_segment_min_orders" min="1" placeholder="np. 3"
+                               value=[editing.and_then(|c| c.segment_min_orders)]
+                               class="admin-filter-input";
+                    }
+                }
+                div ."mt-4 flex gap-3" {
+                    button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" {
+                        @if editing.is_some() { "Zapisz zmiany" } @else { "Dodaj kod" }
+                    }
+                    @if editing.is_some() {
+                        a href="/htmx/admin/coupons" hx-get="/htmx/admin/coupons" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                          class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-800" { "Anuluj" }
+                    }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Kod" }
+                            th scope="col" class="admin-th" { "Rabat" }
+                            th scope="col" class="admin-th" { "Min. wartość" }
+                            th scope="col" class="admin-th" { "Ważny do" }
+                            th scope="col" class="admin-th" { "Użycia" }
+                            th scope="col" class="admin-th" { "Status" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if coupons.is_empty() {
+                            tr { td colspan="7" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak kodów rabatowych." } }
+                        }
+                        @for coupon in &coupons {
+                            tr {
+                                td class="admin-td font-mono text-sm text-gray-800" {
+                                    (coupon.code)
+                                    @if coupon.single_use_per_user {
+                                        span class="block text-xs text-gray-500 font-sans" { "jednorazowy" }
+                                    }
+                                    @let segment = CustomerSegment::for_coupon(coupon);
+                                    @if !segment.is_empty() {
+                                        span class="block text-xs text-gray-500 font-sans" { "segment: " (segment.describe()) }
+                                    }
+                                }
+                                td class="admin-td text-sm text-gray-700" {
+                                    @match coupon.discount_type {
+                                        CouponDiscountType::Percentage => { (coupon.value) "%" }
+                                        CouponDiscountType::Fixed => { (format_price_maud(coupon.value)) }
+                                    }
+                                }
+                                td class="admin-td text-sm text-gray-700" {
+                                    @if coupon.min_order_value > 0 { (format_price_maud(coupon.min_order_value)) } @else { "–" }
+                                }
+                                td class="admin-td text-xs text-gray-600" {
+                                    @if let Some(expires_at) = &coupon.expires_at { (format_date(expires_at)) } @else { "bezterminowo" }
+                                }
+                                td class="admin-td text-sm text-gray-700" { (coupon.usage_count) }
+                                td class="admin-td text-xs" {
+                                    @if !coupon.is_active {
+                                        span class="text-gray-500" { "Wyłączony" }
+                                    } @else if coupon.is_expired() {
+                                        span class="text-orange-600" { "Wygasł" }
+                                    } @else {
+                                        span class="text-green-700" { "Aktywny" }
+                                    }
+                                }
+                                td class="admin-td text-center whitespace-nowrap space-x-2" {
+                                    a href=(format!("/htmx/admin/coupons?edit={}", coupon.id))
+                                      hx-get=(format!("/htmx/admin/coupons?edit={}", coupon.id))
+                                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                      class="text-xs text-pink-600 hover:text-pink-800 hover:underline" { "Edytuj" }
+                                    button hx-post=(format!("/api/admin/coupons/{}/toggle-active", coupon.id))
+                                           hx-swap="none"
+                                           class="text-xs text-gray-600 hover:text-gray-900 hover:underline" {
+                                        @if coupon.is_active { "Wyłącz" } @else { "Włącz" }
+                                    }
+                                    @if coupon.usage_count == 0 {
+                                        button hx-delete=(format!("/api/admin/coupons/{}", coupon.id))
+                                               hx-confirm="Usunąć ten kod rabatowy?"
+                                               hx-swap="none"
+                                               class="text-xs text-red-600 hover:text-red-800 hover:underline" { "Usuń" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Kody rabatowe - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Wydane karty podarunkowe z saldami. Karty opłacane przelewem potwierdzamy tutaj ręcznie.
+pub async fn admin_gift_cards_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    let gift_cards = list_gift_cards(&app_state.db_pool).await?;
+    let paid_cards: Vec<&GiftCard> = gift_cards
+        .iter()
+        .filter(|gift_card| gift_card.paid_at.is_some())
+        .collect();
+    let issued_total: i64 = paid_cards
+        .iter()
+        .map(|gift_card| gift_card.initial_amount)
+        .sum();
+    let redeemed_total: i64 = paid_cards
+        .iter()
+        .map(|gift_card| gift_card.initial_amount - gift_card.balance)
+        .sum();
+    let outstanding_total: i64 = paid_cards
+        .iter()
+        .filter(|gift_card| !gift_card.is_expired())
+        .map(|gift_card| gift_card.balance)
+        .sum();
+
+    let page_content = html! {
+        div id="admin-gift-cards-container"
+            hx-get="/htmx/admin/karty-podarunkowe"
+            hx-trigger="reloadGiftCards from:body"
+            hx-swap="outerHTML"
+        {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-6" { "Karty podarunkowe" }
+
+            div ."grid grid-cols-1 sm:grid-cols-3 gap-4 mb-6" {
+                div ."p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                    p ."text-sm text-gray-500" { "Sprzedane karty (" (paid_cards.len()) ")" }
+                    p ."text-2xl font-semibold text-gray-800" { (format_price_maud(issued_total)) }
+                }
+                div ."p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                    p ."text-sm text-gray-500" { "Wykorzystano w zamówieniach" }
+                    p ."text-2xl font-semibold text-gray-800" { (format_price_maud(redeemed_total)) }
+                }
+                div ."p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                    p ."text-sm text-gray-500" { "Pozostałe saldo ważnych kart" }
+                    p ."text-2xl font-semibold text-pink-600" { (format_price_maud(outstanding_total)) }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Kod" }
+                            th scope="col" class="admin-th" { "Wartość" }
+                            th scope="col" class="admin-th" { "Saldo" }
+                            th scope="col" class="admin-th" { "Obdarowany" }
+                            th scope="col" class="admin-th" { "Kupujący" }
+                            th scope="col" class="admin-th" { "Ważna do" }
+                            th scope="col" class="admin-th" { "Status" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if gift_cards.is_empty() {
+                            tr { td colspan="8" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak kart podarunkowych." } }
+                        }
+                        @for gift_card in &gift_cards {
+                            tr {
+                                td class="admin-td font-mono text-sm text-gray-800" {
+                                    (gift_card.code)
+                                    span class="block text-xs text-gray-500 font-sans" { "przelew: " (gift_card.payment_reference()) }
+                                }
+                                td class="admin-td text-sm text-gray-700" { (format_price_maud(gift_card.initial_amount)) }
+                                td class="admin-td text-sm font-semibold text-gray-800" { (format_price_maud(gift_card.balance)) }
+                                td class="admin-td text-xs text-gray-700" {
+                                    (gift_card.recipient_email)
+                                    @if let Some(name) = &gift_card.recipient_name {
+                                        span class="block text-gray-500" { (name) }
+                                    }
+                                }
+                                td class="admin-td text-xs text-gray-700" {
+                                    (gift_card.purchaser_email)
+                                    span class="block text-gray-500" { (format_date(&gift_card.created_at)) }
+                                }
+                                td class="admin-td text-xs text-gray-600" {
+                                    @if let Some(expires_at) = &gift_card.expires_at { (format_date(expires_at)) } @else { "–" }
+                                }
+                                td class="admin-td text-xs" {
+                                    @if gift_card.paid_at.is_none() {
+                                        span class="text-orange-600" { "Czeka na płatność" }
+                                    } @else if gift_card.balance == 0 {
+                                        span class="text-gray-500" { "Wykorzystana" }
+                                    } @else if gift_card.is_expired() {
+                                        span class="text-gray-500" { "Wygasła" }
+                                    } @else {
+                                        span class="text-green-700" { "Aktywna" }
+                                    }
+                                    @if gift_card.paid_at.is_some() && gift_card.sent_at.is_none() {
+                                        span class="block text-red-600" { "kod niewysłany" }
+                                    }
+                                }
+                                td class="admin-td text-center whitespace-nowrap space-x-2" {
+                                    @if gift_card.paid_at.is_none() {
+                                        button hx-post=(format!("/api/admin/karty-podarunkowe/{}/oplacona", gift_card.id))
+                                               hx-confirm="Potwierdzić wpłatę? Kod karty zostanie wysłany do obdarowanego."
+                                               hx-swap="none"
+                                               class="text-xs text-pink-600 hover:text-pink-800 hover:underline" { "Oznacz jako opłaconą" }
+                                    } @else {
+                                        button hx-post=(format!("/api/admin/karty-podarunkowe/{}/wyslij", gift_card.id))
+                                               hx-swap="none"
+                                               class="text-xs text-gray-600 hover:text-gray-900 hover:underline" { "Wyślij kod ponownie" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Karty podarunkowe - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Kampanie jednorazowych kodów: formularz generowania i statystyki wykorzystania.
+pub async fn admin_coupon_campaigns_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCoupons)?;
+
+    let campaigns = list_campaigns(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-coupon-campaigns-container"
+            hx-get="/htmx/admin/coupons/kampanie"
+            hx-trigger="reloadCouponCampaigns from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Kampanie kodów" }
+                a href="/htmx/admin/coupons" hx-get="/htmx/admin/coupons" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                  class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-800" { "Wróć do kodów" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Kampania generuje serię unikalnych kodów - każdy działa tylko raz (np. kody na kartkach dołączanych do paczek). "
+                "Kwoty podajemy w groszach, np. 2000 = 20,00 zł."
+            }
+
+            form hx-post="/api/admin/coupons/kampanie" hx-swap="none"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-2 lg:grid-cols-4 gap-4 items-end" {
+                    div {
+                        label for="campaign_name" ."block text-sm font-medium text-gray-700 mb-1" { "Nazwa kampanii:" }
+                        input type="text" name="name" id="campaign_name" required maxlength="100" placeholder="np. Kartki - wrzesień" class="admin-filter-input";
+                    }
+                    div {
+                        label for="campaign_prefix" ."block text-sm font-medium text-gray-700 mb-1" { "Prefiks kodów:" }
+                        input type="text" name="code_prefix" id="campaign_prefix" required maxlength="12" placeholder="np. DZIEKI" class="admin-filter-input uppercase";
+                    }
+                    div {
+                        label for="campaign_count" ."block text-sm font-medium text-gray-700 mb-1" { "Liczba kodów:" }
+                        input type="number" name="count" id="campaign_count" required min="1" max="1000" value="50" class="admin-filter-input";
+                    }
+                    div {
+                        label for="campaign_discount_type" ."block text-sm font-medium text-gray-700 mb-1" { "Rodzaj rabatu:" }
+                        select name="discount_type" id="campaign_discount_type" class="admin-filter-select" {
+                            @for discount_type in CouponDiscountType::iter() {
+                                option value=(discount_type.to_form_value()) { (discount_type.to_string()) }
+                            }
+                        }
+                    }
+                    div {
+                        label for="campaign_value" ."block text-sm font-medium text-gray-700 mb-1" { "Wartość (% lub gr):" }
+                        input type="number" name="value" id="campaign_value" required min="1" class="admin-filter-input";
+                    }
+                    div {
+                        label for="campaign_min_order_value" ."block text-sm font-medium text-gray-700 mb-1" { "Min. wartość produktów (gr):" }
+                        input type="number" name="min_order_value" id="campaign_min_order_value" required min="0" value="0" class="admin-filter-input";
+                    }
+                    div {
+                        label for="campaign_expires_at" ."block text-sm font-medium text-gray-700 mb-1" { "Ważne do (włącznie):" }
+                        input type="date" name="expires_at" id="campaign_expires_at" class="admin-filter-input";
+                    }
+                    div {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white w-full sm:w-auto" { "Generuj kody" }
+                    }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Kampania" }
+                            th scope="col" class="admin-th" { "Rabat" }
+                            th scope="col" class="admin-th" { "Wykorzystane" }
+                            th scope="col" class="admin-th" { "Wartość zamówień" }
+                            th scope="col" class="admin-th" { "Udzielony rabat" }
+                            th scope="col" class="admin-th" { "Ważne do" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if campaigns.is_empty() {
+                            tr { td colspan="7" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak kampanii." } }
+                        }
+                        @for campaign in &campaigns {
+                            tr {
+                                td class="admin-td text-sm text-gray-800" {
+                                    (campaign.name)
+                                    span class="block text-xs text-gray-500 font-mono" { (campaign.code_prefix) "-…" }
+                                }
+                                td class="admin-td text-sm text-gray-700" {
+                                    @match campaign.discount_type {
+                                        CouponDiscountType::Percentage => { (campaign.value) "%" }
+                                        CouponDiscountType::Fixed => { (format_price_maud(campaign.value)) }
+                                    }
+                                    @if campaign.min_order_value > 0 {
+                                        span class="block text-xs text-gray-500" { "od " (format_price_maud(campaign.min_order_value)) }
+                                    }
+                                }
+                                td class="admin-td text-sm text-gray-700" {
+                                    (campaign.used_count) " / " (campaign.codes_count)
+                                    @if campaign.codes_count > 0 {
+                                        span class="block text-xs text-gray-500" {
+                                            (format!("{:.1}%", campaign.used_count as f64 * 100.0 / campaign.codes_count as f64))
+                                        }
+                                    }
+                                }
+                                td class="admin-td text-sm text-gray-700" { (format_price_maud(campaign.orders_total)) }
+                                td class="admin-td text-sm text-gray-700" { (format_price_maud(campaign.discount_total)) }
+                                td class="admin-td text-xs text-gray-600" {
+                                    @if let Some(expires_at) = &campaign.expires_at { (format_date(expires_at)) } @else { "bezterminowo" }
+                                }
+                                td class="admin-td text-center" {
+                                    a href=(format!("/api/admin/coupons/kampanie/{}/eksport", campaign.id))
+                                      class="text-xs text-pink-600 hover:text-pink-800 hover:underline" { "Pobierz kody (CSV)" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Kampanie kodów - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Raport "do poprawy": produkty, których zdjęcia oznaczył audyt jakości (`image_audit`).
+pub async fn admin_image_audit_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageProducts)?;
+
+    let issues = sqlx::query_as::<_, ProductImageIssue>(
+        "SELECT * FROM product_image_issues ORDER BY product_id, detected_at",
+    )
+    .fetch_all(&app_state.db_pool)
+    .await?;
+
+    let product_ids: Vec<Uuid> = issues.iter().map(|i| i.product_id).collect();
+    let product_names: HashMap<Uuid, String> =
+        repo::products::find_by_ids(&app_state.db_pool, &product_ids)
+            .await?
+            .into_iter()
+            .map(|product| (product.id, product.name))
+            .collect();
+
+    // Grupujemy problemy po produkcie (zapytanie jest posortowane po product_id)
+    let mut grouped: Vec<(Uuid, Vec<&ProductImageIssue>)> = Vec::new();
+    for issue in &issues {
+        match grouped.last_mut() {
+            Some((product_id, list)) if *product_id == issue.product_id => list.push(issue),
+            _ => grouped.push((issue.product_id, vec![issue])),
+        }
+    }
+    let last_run = issues.iter().map(|i| i.detected_at).max();
+
+    let page_content = html! {
+        div id="admin-image-audit-container"
+            hx-get="/htmx/admin/image-audit"
+            hx-trigger="reloadImageAudit from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Zdjęcia do poprawy" }
+                div ."flex flex-wrap gap-2" {
+                    button hx-post="/api/admin/cloudinary/migrate-folders" hx-swap="none"
+                           hx-confirm="Przenieść zdjęcia wszystkich produktów do folderów products/{id}? Adresy zdjęć w bazie zostaną zaktualizowane."
+                           class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Uporządkuj foldery" }
+                    button hx-post="/api/admin/cloudinary/cleanup-orphans" hx-swap="none"
+                           hx-confirm="Usunąć z Cloudinary zdjęcia, które nie należą do żadnego produktu? Operacji nie można cofnąć."
+                           class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Usuń osierocone zdjęcia" }
+                    button hx-post="/api/admin/image-audit/run" hx-swap="none"
+                           class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Uruchom audyt teraz" }
+                }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Audyt sprawdza raz na dobę zdjęcia dostępnych produktów: rozdzielczość, rozmiar pliku, ostrość oraz brak drugiego zdjęcia."
+                @if let Some(last_run) = last_run {
+                    " Ostatnie wyniki: " (format_datetime_admin(&last_run)) "."
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200" {
+                    thead ."bg-gray-100" {
+                        tr {
+                            th scope="col" class="admin-th" { "Produkt" }
+                            th scope="col" class="admin-th" { "Problemy" }
+                            th scope="col" class="admin-th text-center" { "Akcje" }
+                        }
+                    }
+                    tbody ."bg-white divide-y divide-gray-200" {
+                        @if grouped.is_empty() {
+                            tr { td colspan="3" class="px-4 py-10 text-center text-gray-500 italic text-lg" { "Brak zdjęć do poprawy." } }
+                        }
+                        @for (product_id, product_issues) in &grouped {
+                            tr {
+                                td class="admin-td text-sm font-medium text-gray-800" {
+                                    (product_names.get(product_id).map(String::as_str).unwrap_or("(usunięty produkt)"))
+                                }
+                                td class="admin-td text-sm text-gray-700" {
+                                    ul ."list-disc list-inside space-y-1" {
+                                        @for issue in product_issues {
+                                            li {
+                                                (issue.issue)
+                                                @if let Some(url) = &issue.image_url {
+                                                    " "
+                                                    a href=(url) target="_blank" class="text-xs text-pink-600 hover:underline" { "(podgląd)" }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                td class="admin-td text-center" {
+                                    a href=(format!("/htmx/admin/products/{}/edit", product_id))
+                                      hx-get=(format!("/htmx/admin/products/{}/edit", product_id))
+                                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                      class="text-xs text-pink-600 hover:text-pink-800 hover:underline" { "Edytuj" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Zdjęcia do poprawy - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Procent `part` z `whole`, sformatowany do wyświetlenia ("–" gdy brak danych).
+fn format_rate(part: i64, whole: i64) -> String {
+    if whole <= 0 {
+        "–".to_string()
+    } else {
+        format!("{:.1}%", part as f64 * 100.0 / whole as f64).replace('.', ",")
+    }
+}
+
+/// Panel kluczy publicznego API. `created` to świeżo utworzony klucz z pełną wartością -
+/// pokazywaną tylko w odpowiedzi na utworzenie, bo w bazie jest wyłącznie skrót.
+pub fn render_api_keys_panel_maud(keys: &[ApiKey], created: Option<(&ApiKey, &str)>) -> Markup {
+    html! {
+        div id="admin-api-keys-container"
+            hx-get="/htmx/admin/api-keys"
+            hx-trigger="reloadApiKeys from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Klucze API" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Klucze dają dostęp tylko do odczytu katalogu pod " span ."font-mono" { "/api/v1" }
+                " (produkty, kategorie). Klient przesyła klucz w nagłówku " span ."font-mono" { "X-Api-Key" } "."
+            }
+
+            @if let Some((api_key, full_key)) = created {
+                div ."mb-6 p-4 bg-green-50 border border-green-300 rounded-lg" {
+                    p ."text-sm font-semibold text-green-800 mb-2" {
+                        "Utworzono klucz \"" (api_key.name) "\". Skopiuj go teraz - później nie będzie można go wyświetlić."
+                    }
+                    div ."flex gap-2 items-center" {
+                        input type="text" readonly value=(full_key)
+                               class="admin-filter-input font-mono flex-1" "@focus"="$el.select()";
+                        button type="button" x-data
+                               "@click"=(format!("navigator.clipboard.writeText('{}')", full_key))
+                               class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Kopiuj" }
+                    }
+                }
+            }
+
+            form hx-post="/api/admin/api-keys" hx-target="#admin-api-keys-container" hx-swap="outerHTML"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200 flex flex-col sm:flex-row gap-3 sm:items-end" {
+                div ."flex-1" {
+                    label for="api_key_name" ."block text-sm font-medium text-gray-700 mb-1" { "Nazwa (np. aplikacja mobilna):" }
+                    input type="text" name="name" id="api_key_name" required maxlength="100" class="admin-filter-input";
+                }
+                button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Utwórz klucz" }
+            }
+
+            div ."bg-white rounded-lg shadow-md border border-gray-200 overflow-x-auto" {
+                @if keys.is_empty() {
+                    p ."px-4 py-10 text-center text-gray-500 italic" { "Brak kluczy API." }
+                } @else {
+                    table ."min-w-full text-sm" {
+                        thead ."bg-gray-50 text-left text-xs uppercase text-gray-500" {
+                            tr {
+                                th ."px-4 py-2" { "Nazwa" }
+                                th ."px-4 py-2" { "Klucz" }
+                                th ."px-4 py-2" { "Utworzony" }
+                                th ."px-4 py-2" { "Ostatnio użyty" }
+                                th ."px-4 py-2" {}
+                            }
+                        }
+                        tbody ."divide-y divide-gray-100" {
+                            @for api_key in keys {
+                                tr class=(if api_key.revoked_at.is_some() { "opacity-60" } else { "" }) {
+                                    td ."px-4 py-2 font-medium text-gray-800" { (api_key.name) }
+                                    td ."px-4 py-2 font-mono text-xs" { (api_key.key_prefix) "…" }
+                                    td ."px-4 py-2 whitespace-nowrap" { (format_datetime_admin(&api_key.created_at)) }
+                                    td ."px-4 py-2 whitespace-nowrap" {
+                                        @match &api_key.last_used_at {
+                                            Some(last_used_at) => { (format_datetime_admin(last_used_at)) }
+                                            None => { "–" }
+                                        }
+                                    }
+                                    td ."px-4 py-2 text-right whitespace-nowrap" {
+                                        @if let Some(revoked_at) = &api_key.revoked_at {
+                                            span ."text-xs text-gray-500" { "Unieważniony " (format_datetime_admin(revoked_at)) }
+                                        } @else {
+                                            button hx-delete=(format!("/api/admin/api-keys/{}", api_key.id)) hx-swap="none"
+                                                   hx-confirm=(format!("Unieważnić klucz \"{}\"? Klienci, którzy go używają, stracą dostęp.", api_key.name))
+                                                   class="text-red-600 hover:text-red-800 text-xs font-semibold" { "Unieważnij" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn admin_api_keys_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    let keys = list_api_keys(&app_state.db_pool).await?;
+    let page_content = render_api_keys_panel_maud(&keys, None);
+
+    let title = "Admin Panel - Klucze API - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Trwające blokady logowania (seria nieudanych prób) z możliwością ręcznego zdjęcia.
+pub async fn admin_login_lockouts_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let lockouts = list_active_lockouts(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-login-lockouts-container"
+            hx-get="/htmx/admin/blokady-logowania"
+            hx-trigger="reloadLoginLockouts from:body"
+            hx-swap="outerHTML"
+        {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Blokady logowania" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Po " (MAX_FAILED_LOGIN_ATTEMPTS) " nieudanych próbach logowania adres jest blokowany na "
+                (LOCKOUT_MINUTES) " minut, a właściciel konta dostaje e-mail. Blokadę można zdjąć wcześniej."
+            }
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Adres e-mail" }
+                            th ."admin-th" { "Konto" }
+                            th ."admin-th text-right" { "Nieudane próby" }
+                            th ."admin-th" { "Zablokowano" }
+                            th ."admin-th" { "Do" }
+                            th ."admin-th" {}
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if lockouts.is_empty() {
+                            tr { td colspan="6" ."admin-td text-center text-gray-500" { "Brak aktywnych blokad." } }
+                        }
+                        @for lockout in &lockouts {
+                            tr {
+                                td ."admin-td" { (lockout.email) }
+                                td ."admin-td text-xs text-gray-600" {
+                                    @if lockout.user_id.is_some() { "Tak" } @else { "Brak konta" }
+                                }
+                                td ."admin-td text-right" { (lockout.failed_attempts) }
+                                td ."admin-td text-xs text-gray-600" { (format_datetime_admin(&lockout.locked_at)) }
+                                td ."admin-td text-xs text-gray-600" { (format_datetime_admin(&lockout.locked_until)) }
+                                td ."admin-td text-right" {
+                                    button type="button"
+                                           hx-post=(format!("/api/admin/blokady-logowania/{}/odblokuj", lockout.id))
+                                           hx-swap="none"
+                                           hx-confirm=(format!("Zdjąć blokadę logowania dla {}?", lockout.email))
+                                           class="px-3 py-1 text-xs font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" {
+                                        "Odblokuj"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Blokady logowania - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Konta użytkowników: role personelu i blokowanie kont
+pub async fn admin_users_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<AdminUsersParams>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageUsers)?;
+
+    let filter = UserListFilter::from_params(&params);
+    let users = list_users(&app_state.db_pool, &filter).await?;
+
+    let page_content = html! {
+        div id="admin-users-container"
+            hx-get=(format!("/htmx/admin/users?{}", filter.query_string()))
+            hx-trigger="reloadAdminUsers from:body"
+            hx-swap="outerHTML"
+        {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Użytkownicy" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Redaktor zarządza produktami, opiniami i treścią stron, magazyn - zamówieniami, zwrotami i reklamacjami. "
+                "Personel musi włączyć weryfikację dwuetapową, zanim zobaczy panel. "
+                "Zablokowane konto nie może się zalogować; wcześniejsze sesje wygasają same."
+            }
+
+            form hx-get="/htmx/admin/users" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                 class="mb-6 p-4 bg-white rounded-lg shadow-sm border border-gray-200" {
+                div ."grid grid-cols-1 sm:grid-cols-3 gap-4 items-end" {
+                    div {
+                        label for="users_q" ."block text-sm font-medium text-gray-700 mb-1" { "E-mail:" }
+                        input type="search" name="q" id="users_q" placeholder="np. anna@"
+                               value=[filter.search.as_deref()]
+                               class="admin-filter-input";
+                    }
+                    div {
+                        label for="users_role" ."block text-sm font-medium text-gray-700 mb-1" { "Rola:" }
+                        select name="role" id="users_role" class="admin-filter-select" {
+                            option value="" { "Wszystkie" }
+                            @for role in Role::iter() {
+                                option value=(role.as_ref()) selected[filter.role == Some(role)] { (role.label()) }
+                            }
+                        }
+                    }
+                    div ."flex gap-3" {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Filtruj" }
+                        a href="/htmx/admin/users" hx-get="/htmx/admin/users" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                          class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-800" { "Wyczyść" }
+                    }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "E-mail" }
+                            th ."admin-th" { "Rola" }
+                            th ."admin-th" { "2FA" }
+                            th ."admin-th" { "Ostatnie logowanie" }
+                            th ."admin-th" { "Konto od" }
+                            th ."admin-th" {}
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if users.is_empty() {
+                            tr { td colspan="6" ."admin-td text-center text-gray-500" { "Brak użytkowników spełniających kryteria." } }
+                        }
+                        @for user in &users {
+                            @let is_self = user.id == claims.sub;
+                            tr class=[user.disabled_at.is_some().then_some("bg-gray-50")] {
+                                td ."admin-td" {
+                                    (user.email)
+                                    @if is_self { span ."ml-1 text-xs text-gray-500" { "(Ty)" } }
+                                    @if let Some(disabled_at) = user.disabled_at {
+                                        span ."block text-xs text-red-600" { "Zablokowane " (format_datetime_admin(&disabled_at)) }
+                                    }
+                                }
+                                td ."admin-td" {
+                                    select name="role"
+                                           hx-post=(format!("/api/admin/users/{}/rola", user.id))
+                                           hx-trigger="change"
+                                           hx-swap="none"
+                                           disabled[is_self]
+                                           class="admin-filter-select text-xs" {
+                                        @for role in Role::iter() {
+                                            option value=(role.as_ref()) selected[user.role == role] { (role.label()) }
+                                        }
+                                    }
+                                }
+                                td ."admin-td text-xs text-gray-600" {
+                                    @if user.totp_enabled_at.is_some() { "Włączona" }
+                                    @else if user.role.is_staff() { span ."text-amber-700" { "Brak - panel zablokowany" } }
+                                    @else { "-" }
+                                }
+                                td ."admin-td text-xs text-gray-600" {
+                                    @if let Some(last_login_at) = user.last_login_at { (format_datetime_admin(&last_login_at)) }
+                                    @else { "-" }
+                                }
+                                td ."admin-td text-xs text-gray-600" { (format_date(&user.created_at)) }
+                                td ."admin-td text-right" {
+                                    @if !is_self {
+                                        @if user.disabled_at.is_some() {
+                                            button type="button"
+                                                   hx-post=(format!("/api/admin/users/{}/blokada", user.id))
+                                                   hx-vals=r#"{"disabled": "false"}"#
+                                                   hx-swap="none"
+                                                   class="px-3 py-1 text-xs font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" {
+                                                "Odblokuj"
+                                            }
+                                        } @else {
+                                            button type="button"
+                                                   hx-post=(format!("/api/admin/users/{}/blokada", user.id))
+                                                   hx-vals=r#"{"disabled": "true"}"#
+                                                   hx-swap="none"
+                                                   hx-confirm=(format!("Zablokować konto {}? Nie będzie można się na nie zalogować.", user.email))
+                                                   class="px-3 py-1 text-xs font-medium rounded-md border border-red-300 text-red-700 hover:bg-red-50" {
+                                                "Zablokuj"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Użytkownicy - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Strony informacyjne: która ma wersję z panelu, a która wbudowaną treść
+pub async fn admin_static_pages_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let saved_pages = list_static_pages(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-static-pages-container" {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Strony informacyjne" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Dopóki strona nie ma opublikowanej wersji z panelu, klienci widzą treść wbudowaną w sklep. "
+                "Treść pisze się w Markdownie (nagłówki ##, listy, linki, tabele)."
+            }
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Strona" }
+                            th ."admin-th" { "Adres" }
+                            th ."admin-th" { "Treść" }
+                            th ."admin-th" { "Ostatnia zmiana" }
+                            th ."admin-th" {}
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @for page in &BUILT_IN_PAGES {
+                            @let saved = saved_pages.iter().find(|saved| saved.slug == page.slug);
+                            tr {
+                                td ."admin-td font-medium text-gray-800" { (page.name) }
+                                td ."admin-td text-xs" {
+                                    a href=(format!("/{}", page.slug)) target="_blank" class="text-pink-600 hover:underline" { "/" (page.slug) }
+                                }
+                                td ."admin-td text-xs" {
+                                    @match saved {
+                                        Some(saved) if saved.published => {
+                                            span ."px-2 py-0.5 font-semibold rounded-full bg-green-100 text-green-800" { "Z panelu" }
+                                        }
+                                        Some(_) => {
+                                            span ."px-2 py-0.5 font-semibold rounded-full bg-yellow-100 text-yellow-800" { "Szkic (widoczna wbudowana)" }
+                                        }
+                                        None => {
+                                            span ."px-2 py-0.5 font-semibold rounded-full bg-gray-100 text-gray-700" { "Wbudowana" }
+                                        }
+                                    }
+                                }
+                                td ."admin-td text-xs text-gray-600" {
+                                    @if let Some(saved) = saved {
+                                        (format_datetime_admin(&saved.updated_at))
+                                        @if let Some(author) = &saved.updated_by_email { " · " (author) }
+                                    } @else { "–" }
+                                }
+                                td ."admin-td text-right" {
+                                    a href=(format!("/htmx/admin/strony/{}", page.slug))
+                                      hx-get=(format!("/htmx/admin/strony/{}", page.slug))
+                                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                      class="px-3 py-1 text-xs font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" { "Edytuj" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Strony informacyjne - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Edycja strony informacyjnej z podglądem Markdownu na żywo
+pub async fn admin_static_page_edit_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(slug): Path<String>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let page = built_in_page(&slug).ok_or(AppError::NotFound)?;
+    let saved = find_static_page(&app_state.db_pool, page.slug).await?;
+    let title_value = saved
+        .as_ref()
+        .map_or(page.name, |saved| saved.title.as_str());
+    let body_value = saved.as_ref().map_or("", |saved| saved.body.as_str());
+
+    let page_content = html! {
+        div id="admin-static-page-edit" {
+            a href="/htmx/admin/strony" hx-get="/htmx/admin/strony" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+              class="text-sm text-pink-600 hover:underline" { "← Wróć do listy stron" }
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mt-2 mb-2" { (page.name) }
+            @if saved.is_none() {
+                p ."mb-4 p-3 rounded-md bg-gray-50 border border-gray-200 text-sm text-gray-700" {
+                    "Strona używa treści wbudowanej w sklep. Po zapisaniu i opublikowaniu wersji z panelu klienci zobaczą ją zamiast wbudowanej."
+                }
+            }
+            div ."grid grid-cols-1 xl:grid-cols-2 gap-6" {
+                form hx-post=(format!("/api/admin/strony/{}", page.slug)) hx-swap="none"
+                     class="bg-white rounded-lg shadow-sm border border-gray-200 p-4 space-y-4" {
+                    div {
+                        label for="static_page_title" ."block text-sm font-medium text-gray-700 mb-1" { "Tytuł (nagłówek strony):" }
+                        input type="text" name="title" id="static_page_title" required maxlength=(MAX_STATIC_PAGE_TITLE_LEN)
+                               value=(title_value) class="admin-filter-input w-full";
+                    }
+                    div {
+                        label for="static_page_body" ."block text-sm font-medium text-gray-700 mb-1" { "Treść (Markdown):" }
+                        textarea name="body" id="static_page_body" rows="24" required maxlength=(MAX_STATIC_PAGE_BODY_LEN)
+                                 hx-post="/htmx/admin/strony/podglad"
+                                 hx-trigger="input changed delay:500ms"
+                                 hx-target="#static-page-preview"
+                                 hx-swap="innerHTML"
+                                 hx-include="#static_page_title"
+                                 class="w-full p-2 border border-gray-300 rounded-md font-mono text-sm focus:ring-pink-500 focus:border-pink-500" {
+                            (body_value)
+                        }
+                    }
+                    label ."flex items-center gap-2 text-sm text-gray-700" {
+                        input type="checkbox" name="published" value="true"
+                              checked[saved.as_ref().is_some_and(|saved| saved.published)]
+                              class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                        "Opublikowana (widoczna dla klientów)"
+                    }
+                    div ."flex flex-wrap gap-3" {
+                        button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Zapisz" }
+                        @if saved.is_some() {
+                            button type="button"
+                                   hx-delete=(format!("/api/admin/strony/{}", page.slug))
+                                   hx-swap="none"
+                                   hx-confirm="Usunąć wersję z panelu i przywrócić treść wbudowaną?"
+                                   class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-800" { "Przywróć wbudowaną treść" }
+                        }
+                    }
+                }
+                div {
+                    p ."text-sm font-medium text-gray-700 mb-1" { "Podgląd" }
+                    div id="static-page-preview" ."bg-white rounded-lg shadow-sm border border-gray-200 overflow-hidden" {
+                        @if let Some(saved) = &saved {
+                            (render_cms_page(saved))
+                        } @else {
+                            p ."p-4 text-sm text-gray-500" { "Podgląd pojawi się podczas pisania." }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = format!(
+        "Admin Panel - {} - sklep mess - all that vintage",
+        page.name
+    );
+    let page_builder = PageBuilder::new(&title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Podgląd Markdownu w formularzu edycji strony (bez zapisu)
+pub async fn admin_static_page_preview_htmx_handler(
+    claims: TokenClaims,
+    Form(payload): Form<StaticPagePayload>,
+) -> Result<Markup, AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let preview = StaticPage {
+        slug: String::new(),
+        title: payload.title,
+        body: payload.body,
+        published: false,
+        updated_by_email: None,
+        updated_at: Utc::now(),
+    };
+    Ok(render_cms_page(&preview))
+}
+
+/// Pytania FAQ: dodawanie, edycja, kolejność i widoczność na /faq
+pub async fn admin_faq_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let items = list_faq_items(&app_state.db_pool, false).await?;
+    let cms_override = published_static_page(&app_state.db_pool, "faq")
+        .await?
+        .is_some();
+
+    let page_content = html! {
+        div id="admin-faq-container"
+            hx-get="/htmx/admin/faq"
+            hx-trigger="reloadFaqItems from:body"
+            hx-swap="outerHTML"
+        {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "FAQ" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Widoczne pytania trafiają na stronę " a href="/faq" target="_blank" class="text-pink-600 hover:underline" { "/faq" }
+                " i do danych strukturalnych dla Google, w tej kolejności. "
+                "Znacznik " code ."px-1 bg-gray-100 rounded" { (FREE_SHIPPING_PLACEHOLDER) }
+                " w odpowiedzi zamienia się na aktualny próg darmowej dostawy."
+            }
+            @if cms_override {
+                p ."mb-4 p-3 rounded-md bg-yellow-50 border border-yellow-300 text-sm text-yellow-800" {
+                    "Strona FAQ ma opublikowaną wersję w „Stronach informacyjnych” - dopóki jej nie usuniesz, klienci nie widzą poniższych pytań."
+                }
+            }
+
+            form hx-post="/api/admin/faq" hx-swap="none"
+                 class="bg-white rounded-lg shadow-sm border border-gray-200 p-4 space-y-3 mb-6" {
+                h4 ."font-semibold text-gray-800" { "Nowe pytanie" }
+                (faq_item_fields_maud(None))
+                button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Dodaj pytanie" }
+            }
+
+            @if items.is_empty() {
+                p ."text-sm text-gray-500" { "Brak pytań - strona FAQ jest pusta." }
+            }
+            ol ."space-y-3" {
+                @for (index, item) in items.iter().enumerate() {
+                    li ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" x-data="{ editing: false }" {
+                        div ."flex flex-col sm:flex-row sm:items-start sm:justify-between gap-3" {
+                            div {
+                                p ."font-semibold text-gray-800" {
+                                    (index + 1) ". " (item.question)
+                                    @if !item.visible {
+                                        span ."ml-2 px-2 py-0.5 text-xs font-semibold rounded-full bg-gray-100 text-gray-700" { "Ukryte" }
+                                    }
+                                }
+                                p ."text-sm text-gray-600 mt-1 whitespace-pre-line" x-show="!editing" { (item.answer) }
+                                p ."text-xs text-gray-400 mt-1" { "Zmiana: " (format_datetime_admin(&item.updated_at)) }
+                            }
+                            div ."flex flex-wrap gap-2 shrink-0" {
+                                button type="button" disabled[index == 0]
+                                       hx-post=(format!("/api/admin/faq/{}/przesun/gora", item.id)) hx-swap="none"
+                                       title="Przesuń wyżej"
+                                       class="px-2 py-1 text-xs rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50 disabled:opacity-40" { "↑" }
+                                button type="button" disabled[index + 1 == items.len()]
+                                       hx-post=(format!("/api/admin/faq/{}/przesun/dol", item.id)) hx-swap="none"
+                                       title="Przesuń niżej"
+                                       class="px-2 py-1 text-xs rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50 disabled:opacity-40" { "↓" }
+                                button type="button" "@click"="editing = !editing"
+                                       class="px-3 py-1 text-xs font-medium rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50" { "Edytuj" }
+                                button type="button"
+                                       hx-delete=(format!("/api/admin/faq/{}", item.id)) hx-swap="none"
+                                       hx-confirm="Usunąć to pytanie z FAQ?"
+                                       class="px-3 py-1 text-xs font-medium rounded-md text-red-600 hover:underline" { "Usuń" }
+                            }
+                        }
+                        form hx-post=(format!("/api/admin/faq/{}", item.id)) hx-swap="none"
+                             x-show="editing" x-cloak
+                             class="mt-4 space-y-3 border-t border-gray-200 pt-4" {
+                            (faq_item_fields_maud(Some(item)))
+                            button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Zapisz" }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - FAQ - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Przekierowania starych adresów: ręczne i dodane przy zmianie adresu produktu
+pub async fn admin_redirects_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let redirects = list_redirects(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-redirects-container"
+            hx-get="/htmx/admin/przekierowania"
+            hx-trigger="reloadRedirects from:body"
+            hx-swap="outerHTML"
+        {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Przekierowania" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Gdy ktoś wejdzie pod stary adres, którego już nie ma w sklepie, trafi na nowy. "
+                "Zmiana adresu produktu dodaje przekierowanie sama. Adresy /api, /htmx i /static są pomijane."
+            }
+
+            form hx-post="/api/admin/przekierowania" hx-swap="none"
+                 class="bg-white rounded-lg shadow-sm border border-gray-200 p-4 mb-6 grid grid-cols-1 md:grid-cols-[1fr_1fr_auto_auto] gap-3 items-end" {
+                div {
+                    label for="redirect_source" ."block text-sm font-medium text-gray-700 mb-1" { "Stary adres" }
+                    input type="text" name="source_path" id="redirect_source" required maxlength=(MAX_REDIRECT_PATH_LEN)
+                           placeholder="/produkty/stary-adres" class="admin-filter-input";
+                }
+                div {
+                    label for="redirect_target" ."block text-sm font-medium text-gray-700 mb-1" { "Nowy adres" }
+                    input type="text" name="target_path" id="redirect_target" required maxlength=(MAX_REDIRECT_PATH_LEN)
+                           placeholder="/dla-niej/sukienki albo https://..." class="admin-filter-input";
+                }
+                div {
+                    label for="redirect_status" ."block text-sm font-medium text-gray-700 mb-1" { "Rodzaj" }
+                    select name="status_code" id="redirect_status" class="admin-filter-input" {
+                        @for code in REDIRECT_STATUS_CODES {
+                            option value=(code) { (redirect_status_label(code)) }
+                        }
+                    }
+                }
+                button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Dodaj" }
+            }
+
+            @if redirects.is_empty() {
+                p ."text-sm text-gray-500" { "Brak przekierowań." }
+            } @else {
+                div ."bg-white shadow-md rounded-lg overflow-x-auto" {
+                    table ."min-w-full divide-y divide-gray-200 text-sm" {
+                        thead ."bg-gray-50" {
+                            tr {
+                                th ."px-4 py-3 text-left font-medium text-gray-500" { "Stary adres" }
+                                th ."px-4 py-3 text-left font-medium text-gray-500" { "Nowy adres" }
+                                th ."px-4 py-3 text-left font-medium text-gray-500" { "Rodzaj" }
+                                th ."px-4 py-3 text-right font-medium text-gray-500" { "Wejścia" }
+                                th ."px-4 py-3 text-left font-medium text-gray-500" { "Dodane" }
+                                th ."px-4 py-3" {}
+                            }
+                        }
+                        tbody ."divide-y divide-gray-200" {
+                            @for redirect in &redirects {
+                                tr {
+                                    td ."px-4 py-3 font-mono text-gray-800 break-all" { (redirect.source_path) }
+                                    td ."px-4 py-3 font-mono break-all" {
+                                        a href=(redirect.target_path) target="_blank" class="text-pink-600 hover:underline" { (redirect.target_path) }
+                                    }
+                                    td ."px-4 py-3 text-gray-600 whitespace-nowrap" { (redirect_status_label(redirect.status_code)) }
+                                    td ."px-4 py-3 text-right text-gray-600" {
+                                        (redirect.hits)
+                                        @if let Some(last_hit_at) = &redirect.last_hit_at {
+                                            span ."block text-xs text-gray-400" { (format_datetime_admin(last_hit_at)) }
+                                        }
+                                    }
+                                    td ."px-4 py-3 text-gray-600 whitespace-nowrap" {
+                                        (format_datetime_admin(&redirect.created_at))
+                                        @if let Some(email) = &redirect.created_by_email {
+                                            span ."block text-xs text-gray-400" { (email) }
+                                        }
+                                    }
+                                    td ."px-4 py-3 text-right" {
+                                        button type="button"
+                                               hx-delete=(format!("/api/admin/przekierowania/{}", redirect.id)) hx-swap="none"
+                                               hx-confirm="Usunąć to przekierowanie? Stary adres zacznie zwracać błąd 404."
+                                               class="text-xs font-medium text-red-600 hover:underline" { "Usuń" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Przekierowania - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+fn redirect_status_label(status_code: i16) -> &'static str {
+    match status_code {
+        302 => "302 - tymczasowe",
+        _ => "301 - stałe",
+    }
+}
+
+/// Strona główna: baner, karuzela wyróżnionych produktów i kolejność sekcji
+pub async fn admin_homepage_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageContent)?;
+
+    let sections = list_homepage_sections(&app_state.db_pool).await?;
+    let products = featured_products(&app_state.db_pool, false).await?;
+    let button_class = "px-2 py-1 text-xs rounded-md border border-gray-300 text-gray-700 hover:bg-gray-50 disabled:opacity-40";
+
+    let page_content = html! {
+        div id="admin-homepage-container"
+            hx-get="/htmx/admin/strona-glowna"
+            hx-trigger="reloadHomepageSettings from:body"
+            hx-swap="outerHTML"
+        {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Strona główna" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Sekcje pokazują się nad listą nowości w poniższej kolejności. Zmiany widać w sklepie od razu po zapisaniu."
+            }
+            div ."space-y-6" {
+                @for (index, section) in sections.iter().enumerate() {
+                    div ."bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                        div ."flex items-start justify-between gap-3 mb-4" {
+                            div {
+                                h4 ."text-lg font-semibold text-gray-800" {
+                                    (index + 1) ". " (section.section.label())
+                                    @if !section.enabled {
+                                        span ."ml-2 px-2 py-0.5 text-xs font-semibold rounded-full bg-gray-100 text-gray-700" { "Ukryta" }
+                                    }
+                                }
+                                p ."text-xs text-gray-400" {
+                                    "Zmiana: " (format_datetime_admin(&section.updated_at))
+                                    @if let Some(author) = &section.updated_by_email { " · " (author) }
+                                }
+                            }
+                            div ."flex gap-2 shrink-0" {
+                                button type="button" disabled[index == 0] title="Przesuń wyżej"
+                                       hx-post=(format!("/api/admin/strona-glowna/sekcje/{}/przesun/gora", section.section.as_ref()))
+                                       hx-swap="none" class=(button_class) { "↑" }
+                                button type="button" disabled[index + 1 == sections.len()] title="Przesuń niżej"
+                                       hx-post=(format!("/api/admin/strona-glowna/sekcje/{}/przesun/dol", section.section.as_ref()))
+                                       hx-swap="none" class=(button_class) { "↓" }
+                            }
+                        }
+                        @match section.section {
+                            HomepageSection::Hero => { (admin_homepage_hero_form_maud(section)) }
+                            HomepageSection::Featured => { (admin_homepage_featured_maud(section, &products, button_class)) }
+                            HomepageSection::Popular => { (admin_homepage_popular_maud(section)) }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Strona główna - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+fn admin_homepage_hero_form_maud(section: &HomepageSectionConfig) -> Markup {
+    html! {
+        form hx-post="/api/admin/strona-glowna/baner" hx-encoding="multipart/form-data" hx-swap="none"
+             class="grid grid-cols-1 lg:grid-cols-2 gap-4" {
+            div ."space-y-3" {
+                div {
+                    label ."block text-sm font-medium text-gray-700 mb-1" { "Nagłówek (opcjonalny):" }
+                    input type="text" name="title" maxlength=(MAX_HERO_TEXT_LEN)
+                           value=[section.title.as_deref()] class="admin-filter-input w-full";
+                }
+                div {
+                    label ."block text-sm font-medium text-gray-700 mb-1" { "Podtytuł (opcjonalny):" }
+                    input type="text" name="subtitle" maxlength=(MAX_HERO_TEXT_LEN)
+                           value=[section.subtitle.as_deref()] class="admin-filter-input w-full";
+                }
+                div {
+                    label ."block text-sm font-medium text-gray-700 mb-1" { "Link po kliknięciu (opcjonalny):" }
+                    input type="text" name="link_url" maxlength=(MAX_HERO_TEXT_LEN) placeholder="np. /damskie/sukienki"
+                           value=[section.link_url.as_deref()] class="admin-filter-input w-full";
+                }
+                div {
+                    label ."block text-sm font-medium text-gray-700 mb-1" { "Nowe zdjęcie (proporcje 3:1):" }
+                    input type="file" name="image" accept="image/*" class="text-sm";
+                }
+                label ."flex items-center gap-2 text-sm text-gray-700" {
+                    input type="checkbox" name="enabled" value="true" checked[section.enabled]
+                          class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                    "Widoczny na stronie głównej"
+                }
+                button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Zapisz baner" }
+            }
+            div {
+                @if let Some(image_url) = &section.image_url {
+                    img src=(image_url) alt="Obecny baner" class="w-full aspect-[3/1] object-cover rounded-lg border border-gray-200";
+                } @else {
+                    p ."text-sm text-gray-500" { "Brak zdjęcia - baner nie jest pokazywany." }
+                }
+            }
+        }
+    }
+}
+
+fn admin_homepage_featured_maud(
+    section: &HomepageSectionConfig,
+    products: &[Product],
+    button_class: &str,
+) -> Markup {
+    html! {
+        form hx-post="/api/admin/strona-glowna/wyroznione" hx-swap="none" class="flex flex-wrap items-end gap-3 mb-4" {
+            div ."flex-1 min-w-[12rem]" {
+                label ."block text-sm font-medium text-gray-700 mb-1" { "Tytuł karuzeli:" }
+                input type="text" name="title" maxlength=(MAX_HERO_TEXT_LEN)
+                       value=[section.title.as_deref()] class="admin-filter-input w-full";
+            }
+            label ."flex items-center gap-2 text-sm text-gray-700 mb-2" {
+                input type="checkbox" name="enabled" value="true" checked[section.enabled]
+                      class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                "Widoczna"
+            }
+            button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Zapisz" }
+        }
+        form hx-post="/api/admin/strona-glowna/wyroznione/produkty" hx-swap="none" class="flex flex-wrap items-end gap-3 mb-4" {
+            div ."flex-1 min-w-[12rem]" {
+                label ."block text-sm font-medium text-gray-700 mb-1" { "Dodaj produkt (adres strony produktu, slug albo ID):" }
+                input type="text" name="product" required class="admin-filter-input w-full";
+            }
+            button type="submit" class="admin-filter-button bg-gray-200 hover:bg-gray-300 text-gray-800" { "Dodaj" }
+        }
+        p ."text-xs text-gray-500 mb-3" {
+            "Maksymalnie " (MAX_FEATURED_PRODUCTS) " produktów. Klienci widzą tylko dostępne - sprzedane i zarezerwowane są pomijane."
+        }
+        @if products.is_empty() {
+            p ."text-sm text-gray-500" { "Karuzela jest pusta i nie jest pokazywana." }
+        }
+        ol ."divide-y divide-gray-200" {
+            @for (index, product) in products.iter().enumerate() {
+                li ."py-2 flex items-center gap-3" {
+                    @if let Some(image_url) = product.images.first() {
+                        img src=(transform_cloudinary_url(image_url, "w_96,h_96,c_fill,f_auto,q_auto:good"))
+                            alt=(product.name) class="w-12 h-12 object-cover rounded border border-gray-200" loading="lazy";
+                    }
+                    div ."flex-1 min-w-0" {
+                        a href=(format!("/htmx/admin/products/{}/edit", product.id))
+                          hx-get=(format!("/htmx/admin/products/{}/edit", product.id))
+                          hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                          class="text-sm font-medium text-gray-800 hover:text-pink-600 truncate block" { (product.name) }
+                        p ."text-xs text-gray-500" {
+                            (format_price_maud(product.price)) " · " (product.status.to_string())
+                        }
+                    }
+                    div ."flex gap-2 shrink-0" {
+                        button type="button" disabled[index == 0] title="Przesuń wcześniej"
+                               hx-post=(format!("/api/admin/strona-glowna/wyroznione/produkty/{}/przesun/gora", product.id))
+                               hx-swap="none" class=(button_class) { "↑" }
+                        button type="button" disabled[index + 1 == products.len()] title="Przesuń dalej"
+                               hx-post=(format!("/api/admin/strona-glowna/wyroznione/produkty/{}/przesun/dol", product.id))
+                               hx-swap="none" class=(button_class) { "↓" }
+                        button type="button"
+                               hx-delete=(format!("/api/admin/strona-glowna/wyroznione/produkty/{}", product.id))
+                               hx-swap="none"
+                               class="px-2 py-1 text-xs text-red-600 hover:underline" { "Usuń" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Karuzela najpopularniejszych: produkty dobierają się same z wyświetleń, w panelu tylko tytuł
+fn admin_homepage_popular_maud(section: &HomepageSectionConfig) -> Markup {
+    html! {
+        form hx-post="/api/admin/strona-glowna/popularne" hx-swap="none" class="flex flex-wrap items-end gap-3 mb-2" {
+            div ."flex-1 min-w-[12rem]" {
+                label ."block text-sm font-medium text-gray-700 mb-1" { "Tytuł karuzeli:" }
+                input type="text" name="title" maxlength=(MAX_HERO_TEXT_LEN)
+                       value=[section.title.as_deref()] class="admin-filter-input w-full";
+            }
+            label ."flex items-center gap-2 text-sm text-gray-700 mb-2" {
+                input type="checkbox" name="enabled" value="true" checked[section.enabled]
+                      class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+                "Widoczna"
+            }
+            button type="submit" class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Zapisz" }
+        }
+        p ."text-xs text-gray-500" {
+            (POPULAR_PRODUCTS_LIMIT) " dostępnych produktów najczęściej oglądanych w ostatnich "
+            (POPULARITY_WINDOW_DAYS) " dniach. Lista odświeża się razem z cache'em strony głównej."
+        }
+    }
+}
+
+/// Pola formularza pytania FAQ (nowe albo edycja istniejącego)
+fn faq_item_fields_maud(item: Option<&FaqItem>) -> Markup {
+    html! {
+        div {
+            label ."block text-sm font-medium text-gray-700 mb-1" { "Pytanie:" }
+            input type="text" name="question" required maxlength=(MAX_FAQ_QUESTION_LEN)
+                   value=[item.map(|item| item.question.as_str())] class="admin-filter-input w-full";
+        }
+        div {
+            label ."block text-sm font-medium text-gray-700 mb-1" { "Odpowiedź:" }
+            textarea name="answer" rows="4" required maxlength=(MAX_FAQ_ANSWER_LEN)
+                     class="w-full p-2 border border-gray-300 rounded-md text-sm focus:ring-pink-500 focus:border-pink-500" {
+                @if let Some(item) = item { (item.answer) }
+            }
+        }
+        label ."flex items-center gap-2 text-sm text-gray-700" {
+            input type="checkbox" name="visible" value="true"
+                  checked[item.is_none_or(|item| item.visible)]
+                  class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500";
+            "Widoczne na stronie FAQ"
+        }
+    }
+}
+
+/// Czas do wygaśnięcia wpisu w panelu cache'u, np. "23 h 5 min", "4 min 10 s"
+fn format_cache_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{} h {} min", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{} min {} s", secs / 60, secs % 60)
+    } else {
+        format!("{} s", secs)
+    }
+}
+
+fn render_cache_overview_maud(overview: &CacheOverview) -> Markup {
+    let cache_slug = overview.name.as_ref();
+    html! {
+        div ."bg-white rounded-lg shadow-md border border-gray-200 p-4" {
+            div ."flex flex-col sm:flex-row sm:items-start sm:justify-between gap-3 mb-3" {
+                div {
+                    h4 ."text-lg font-semibold text-gray-800" { (overview.name.label()) }
+                    p ."text-sm text-gray-600" {
+                        "Wpisy: " span ."font-semibold" { (overview.entry_count) }
+                        @if let Some(capacity) = overview.max_capacity { " / " (capacity) }
+                        @if let Some(ttl) = overview.time_to_live { " · TTL: " (format_cache_duration(ttl)) }
+                    }
+                    p ."text-sm text-gray-600" {
+                        "Trafienia: " (overview.hits) " · chybienia: " (overview.misses) " · skuteczność: "
+                        span ."font-semibold" {
+                            @match overview.hit_rate() {
+                                Some(rate) => { (format!("{:.1}%", rate).replace('.', ",")) }
+                                None => { "–" }
+                            }
+                        }
+                    }
+                }
+                button hx-post=(format!("/api/admin/cache/{}/purge", cache_slug)) hx-swap="none"
+                       hx-confirm=(format!("Wyczyścić cały cache \"{}\"?", overview.name.label()))
+                       disabled[overview.entry_count == 0]
+                       class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white disabled:opacity-50" { "Wyczyść ten cache" }
+            }
+            @if overview.entries.is_empty() {
+                p ."text-sm text-gray-500 italic" { "Cache jest pusty." }
+            } @else {
+                details {
+                    summary ."cursor-pointer text-sm text-gray-700 select-none" {
+                        "Klucze"
+                        @if (overview.entries.len() as u64) < overview.entry_count {
+                            " (pierwsze " (overview.entries.len()) " według czasu wygaśnięcia)"
+                        }
+                    }
+                    div ."overflow-x-auto mt-2" {
+                        table ."min-w-full text-sm" {
+                            thead ."bg-gray-50 text-left text-xs uppercase text-gray-500" {
+                                tr {
+                                    th ."px-3 py-2" { "Klucz" }
+                                    th ."px-3 py-2" { "Wartość" }
+                                    th ."px-3 py-2" { "Wygasa za" }
+                                    th ."px-3 py-2" {}
+                                }
+                            }
+                            tbody ."divide-y divide-gray-100" {
+                                @for entry in &overview.entries {
+                                    tr {
+                                        td ."px-3 py-2 font-mono text-xs break-all" { (entry.key) }
+                                        td ."px-3 py-2 text-gray-700" { (entry.description) }
+                                        td ."px-3 py-2 whitespace-nowrap text-gray-700" {
+                                            @match entry.expires_in {
+                                                Some(expires_in) => { (format_cache_duration(expires_in)) }
+                                                None => { "–" }
+                                            }
+                                        }
+                                        td ."px-3 py-2 text-right" {
+                                            button hx-post=(format!("/api/admin/cache/{}/purge", cache_slug))
+                                                   hx-vals=(serde_json::json!({ "key": entry.key }).to_string())
+                                                   hx-swap="none"
+                                                   class="text-red-600 hover:text-red-800 text-xs font-semibold" { "Usuń" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Panel cache'y moka: liczba wpisów, skuteczność trafień, czas do wygaśnięcia kluczy
+/// i przyciski czyszczenia pojedynczych kluczy, całego cache'u albo wszystkiego.
+pub async fn admin_cache_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    let overviews = cache_overview(&app_state).await;
+
+    let page_content = html! {
+        div id="admin-cache-container"
+            hx-get="/htmx/admin/cache"
+            hx-trigger="reloadAdminCache from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Cache" }
+                button hx-post="/api/admin/cache/purge" hx-swap="none"
+                       hx-confirm="Wyczyścić wszystkie cache? Strony i listingi zostaną wygenerowane od nowa przy kolejnych wejściach."
+                       class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Wyczyść wszystko" }
+            }
+            p ."text-sm text-gray-600 mb-4" {
+                "Liczniki trafień są liczone od ostatniego uruchomienia serwera. "
+                "Wyczyszczone strony i listingi generują się ponownie przy pierwszym wejściu."
+            }
+            div ."space-y-4" {
+                @for overview in &overviews {
+                    (render_cache_overview_maud(overview))
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Cache - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Zadania cykliczne z rejestru `jobs` z wynikiem ostatniego przebiegu (`jobs_log`).
+pub async fn admin_jobs_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageSystem)?;
+
+    let log_entries = job_log_entries(&app_state.db_pool).await?;
+
+    let page_content = html! {
+        div id="admin-jobs-container" {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Zadania w tle" }
+                button hx-get="/htmx/admin/zadania" hx-target="#admin-content" hx-swap="innerHTML"
+                       class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Odśwież" }
+            }
+            div ."bg-white rounded-lg shadow-md border border-gray-200 overflow-x-auto" {
+                table ."min-w-full text-sm" {
+                    thead ."bg-gray-50 text-left text-gray-600" {
+                        tr {
+                            th ."px-4 py-3 font-medium" { "Zadanie" }
+                            th ."px-4 py-3 font-medium" { "Interwał" }
+                            th ."px-4 py-3 font-medium" { "Ostatni przebieg" }
+                            th ."px-4 py-3 font-medium" { "Wynik" }
+                            th ."px-4 py-3 font-medium text-right" { "Przebiegi / błędy" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @for job in JOBS {
+                            @let entry = log_entries.iter().find(|entry| entry.job_name == job.name);
+                            tr {
+                                td ."px-4 py-3 align-top" {
+                                    p ."font-medium text-gray-800" { (job.label) }
+                                    p ."text-xs text-gray-500 font-mono" { (job.name) }
+                                }
+                                td ."px-4 py-3 align-top whitespace-nowrap text-gray-700" {
+                                    @if (job.enabled)(&app_state) {
+                                        (job.interval_label())
+                                    } @else {
+                                        span ."text-gray-400 italic" { "wyłączone" }
+                                    }
+                                }
+                                @if let Some(entry) = entry {
+                                    td ."px-4 py-3 align-top whitespace-nowrap text-gray-700" {
+                                        (format_datetime_admin(&entry.last_started_at))
+                                        @if let Some(duration_ms) = entry.last_duration_ms {
+                                            span ."block text-xs text-gray-500" { (duration_ms) " ms" }
+                                        }
+                                    }
+                                    td ."px-4 py-3 align-top" {
+                                        @match entry.last_outcome.as_str() {
+                                            "success" => { span ."text-xs font-semibold bg-green-100 text-green-700 px-2 py-0.5 rounded-full" { "OK" } }
+                                            "failure" => { span ."text-xs font-semibold bg-red-100 text-red-700 px-2 py-0.5 rounded-full" { "Błąd" } }
+                                            _ => { span ."text-xs font-semibold bg-blue-100 text-blue-700 px-2 py-0.5 rounded-full" { "W toku" } }
+                                        }
+                                        @if let Some(message) = &entry.last_message {
+                                            p ."text-xs text-gray-600 mt-1 break-words" { (message) }
+                                        }
+                                        @if entry.last_outcome == "failure" {
+                                            @if let Some(last_success_at) = &entry.last_success_at {
+                                                p ."text-xs text-gray-500 mt-1" { "Ostatni sukces: " (format_datetime_admin(last_success_at)) }
+                                            }
+                                        }
+                                    }
+                                    td ."px-4 py-3 align-top text-right whitespace-nowrap text-gray-700" {
+                                        (entry.run_count) " / " (entry.failure_count)
+                                    }
+                                } @else {
+                                    td ."px-4 py-3 align-top text-gray-400 italic" colspan="3" { "Jeszcze nie uruchomione" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Zadania w tle - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Centrum powiadomień admina: zdarzenia wymagające uwagi (np. nieudane kopie zapasowe).
+pub async fn admin_notifications_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::AccessPanel)?;
+
+    let notifications = sqlx::query_as::<_, AdminNotification>(
+        "SELECT * FROM admin_notifications ORDER BY created_at DESC LIMIT 100",
+    )
+    .fetch_all(&app_state.db_pool)
+    .await?;
+    let unread_count = notifications.iter().filter(|n| n.read_at.is_none()).count();
+
+    let page_content = html! {
+        div id="admin-notifications-container"
+            hx-get="/htmx/admin/notifications"
+            hx-trigger="reloadAdminNotifications from:body"
+            hx-swap="outerHTML"
+        {
+            div ."flex justify-between items-center mb-6" {
+                h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Powiadomienia" }
+                @if unread_count > 0 {
+                    button hx-post="/api/admin/notifications/read-all" hx-swap="none"
+                           class="admin-filter-button bg-gray-600 hover:bg-gray-700 text-white" { "Oznacz wszystkie jako przeczytane" }
+                }
+            }
+
+            div ."bg-white rounded-lg shadow-md border border-gray-200 p-4 mb-6 flex flex-col sm:flex-row sm:items-center sm:justify-between gap-3" {
+                div {
+                    h4 ."text-lg font-semibold text-gray-800" { "Kopie zapasowe bazy" }
+                    @if let Some(config) = &app_state.backup_config {
+                        p ."text-sm text-gray-600" {
+                            "Codzienna zaszyfrowana kopia do bucketu "
+                            span ."font-mono" { (config.storage.bucket) "/" (config.prefix) }
+                            ", przechowywana przez " (config.retention_days) " dni."
+                        }
+                    } @else {
+                        p ."text-sm text-red-600" {
+                            "Kopie zapasowe są wyłączone - ustaw zmienne BACKUP_S3_* i BACKUP_ENCRYPTION_PASSPHRASE."
+                        }
+                    }
+                }
+                @if app_state.backup_config.is_some() {
+                    button hx-post="/api/admin/backups/run" hx-swap="none"
+                           hx-confirm="Wykonać kopię zapasową bazy teraz?"
+                           class="admin-filter-button bg-pink-600 hover:bg-pink-700 text-white" { "Wykonaj kopię teraz" }
+                }
+            }
+
+            div ."space-y-3" {
+                @if notifications.is_empty() {
+                    p ."px-4 py-10 text-center text-gray-500 italic text-lg bg-white rounded-lg shadow-md border border-gray-200" { "Brak powiadomień." }
+                }
+                @for notification in &notifications {
+                    @let level_class = match notification.level {
+                        AdminNotificationLevel::Info => "border-blue-400",
+                        AdminNotificationLevel::Warning => "border-yellow-400",
+                        AdminNotificationLevel::Error => "border-red-500",
+                    };
+                    div class=(format!(
+                            "rounded-lg shadow-sm border border-gray-200 border-l-4 {} p-4 {}",
+                            level_class,
+                            if notification.read_at.is_none() { "bg-white" } else { "bg-gray-50 opacity-75" }
+                        )) {
+                        div ."flex justify-between items-start gap-4" {
+                            p ."font-semibold text-gray-800" {
+                                (notification.title)
+                                @if notification.read_at.is_none() {
+                                    span ."ml-2 text-xs font-medium bg-pink-100 text-pink-700 px-2 py-0.5 rounded-full" { "nowe" }
+                                }
+                            }
+                            span ."text-xs text-gray-500 whitespace-nowrap" {
+                                (notification.level) " · " (format_datetime_admin(&notification.created_at))
+                            }
+                        }
+                        p ."text-sm text-gray-700 mt-1 whitespace-pre-line break-words" { (notification.message) }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Powiadomienia - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Formularz rozpoczęcia podglądu konta klienta (e-mail lub ID klienta i powód).
+fn render_start_impersonation_form_maud(customer: Option<&str>) -> Markup {
+    html! {
+        form hx-post="/api/admin/impersonation" hx-swap="none"
+             class="flex flex-wrap items-end gap-2" {
+            @if let Some(customer) = customer {
+                input type="hidden" name="customer" value=(customer);
+            } @else {
+                div {
+                    label for="impersonation_customer" class="block text-xs font-medium text-gray-600 mb-1" { "E-mail lub ID klienta" }
+                    input type="text" id="impersonation_customer" name="customer" required
+                          class="px-3 py-1.5 border border-gray-300 rounded-md text-sm";
+                }
+            }
+            div {
+                label for="impersonation_reason" class="block text-xs font-medium text-gray-600 mb-1" { "Powód (np. treść zgłoszenia)" }
+                input type="text" id="impersonation_reason" name="reason" required maxlength="200"
+                      class="px-3 py-1.5 border border-gray-300 rounded-md text-sm";
+            }
+            button type="submit"
+                   class="px-3 py-1.5 text-xs font-medium text-white bg-gray-700 hover:bg-gray-800 rounded-md" {
+                "Zobacz jako klient"
+            }
+        }
+    }
+}
+
+/// Dziennik podglądów kont klientów i formularz rozpoczęcia nowego podglądu.
+pub async fn admin_impersonation_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let sessions = sqlx::query_as::<_, ImpersonationSessionSummary>(
+        r#"
+            SELECT s.id, a.email AS admin_email, c.email AS customer_email, s.reason,
+                   s.started_at, s.expires_at, s.ended_at,
+                   COUNT(e.id) AS request_count,
+                   COUNT(e.id) FILTER (WHERE e.blocked) AS blocked_count
+            FROM impersonation_sessions s
+            JOIN users a ON a.id = s.admin_id
+            JOIN users c ON c.id = s.customer_id
+            LEFT JOIN impersonation_events e ON e.session_id = s.id
+            GROUP BY s.id, a.email, c.email
+            ORDER BY s.started_at DESC
+            LIMIT 100
+        "#,
+    )
+    .fetch_all(&app_state.db_pool)
+    .await?;
+
+    let page_content = html! {
+        div id="admin-impersonation-container" {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Podgląd jako klient" }
+            p ."text-sm text-gray-600 mb-4" {
+                "Pozwala zobaczyć sklep i konto klienta jego oczami (np. przy zgłoszeniu \"nie widzę mojego zamówienia\"). "
+                "Podgląd jest tylko do odczytu, wygasa po " (IMPERSONATION_MINUTES) " minutach, a każda odsłona trafia do dziennika."
+            }
+            div ."bg-white rounded-lg shadow-md border border-gray-200 p-4 mb-8" {
+                (render_start_impersonation_form_maud(None))
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Rozpoczęto" }
+                            th ."admin-th" { "Admin" }
+                            th ."admin-th" { "Klient" }
+                            th ."admin-th" { "Powód" }
+                            th ."admin-th" { "Status" }
+                            th ."admin-th text-right" { "Żądania" }
+                            th ."admin-th text-right" { "Zablokowane" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if sessions.is_empty() {
+                            tr { td colspan="7" ."admin-td text-center text-gray-500" { "Brak podglądów." } }
+                        }
+                        @for session in &sessions {
+                            tr {
+                                td ."admin-td text-xs text-gray-600" {
+                                    a href=(format!("/htmx/admin/impersonation/{}", session.id))
+                                      hx-get=(format!("/htmx/admin/impersonation/{}", session.id))
+                                      hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                                      class="text-pink-600 hover:underline" {
+                                        (format_datetime_admin(&session.started_at))
+                                    }
+                                }
+                                td ."admin-td" { (session.admin_email) }
+                                td ."admin-td" { (session.customer_email) }
+                                td ."admin-td text-gray-600" { (session.reason.as_deref().unwrap_or("-")) }
+                                td ."admin-td text-xs" {
+                                    @if let Some(ended_at) = &session.ended_at {
+                                        "Zakończony " (format_datetime_admin(ended_at))
+                                    } @else if session.expires_at < Utc::now() {
+                                        "Wygasł"
+                                    } @else {
+                                        span ."text-red-600 font-semibold" { "Aktywny" }
+                                    }
+                                }
+                                td ."admin-td text-right" { (session.request_count) }
+                                td ."admin-td text-right" {
+                                    @if session.blocked_count > 0 {
+                                        span ."text-red-600 font-semibold" { (session.blocked_count) }
+                                    } @else { "0" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Podgląd jako klient - panel admina",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Pełny dziennik żądań jednej sesji podglądu.
+pub async fn admin_impersonation_session_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Path(session_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ManageCustomers)?;
+
+    let events = sqlx::query_as::<_, ImpersonationEvent>(
+        "SELECT * FROM impersonation_events WHERE session_id = $1 ORDER BY created_at",
+    )
+    .bind(session_id)
+    .fetch_all(&app_state.db_pool)
+    .await?;
+
+    let page_content = html! {
+        div {
+            a href="/htmx/admin/impersonation" hx-get="/htmx/admin/impersonation"
+              hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+              class="text-sm text-pink-600 hover:underline" { "← Wszystkie podglądy" }
+            h3 ."text-2xl font-semibold text-gray-800 mt-2 mb-4" { "Dziennik podglądu" }
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Czas" }
+                            th ."admin-th" { "Metoda" }
+                            th ."admin-th" { "Ścieżka" }
+                            th ."admin-th" { "" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if events.is_empty() {
+                            tr { td colspan="4" ."admin-td text-center text-gray-500" { "Brak zapisanych żądań." } }
+                        }
+                        @for event in &events {
+                            tr {
+                                td ."admin-td text-xs text-gray-600" { (format_datetime_admin(&event.created_at)) }
+                                td ."admin-td font-mono text-xs" { (event.method) }
+                                td ."admin-td font-mono text-xs" { (event.path) }
+                                td ."admin-td text-xs" {
+                                    @if event.blocked { span ."text-red-600 font-semibold" { "Zablokowane" } }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let page_builder =
+        PageBuilder::new("Dziennik podglądu - panel admina", page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn admin_funnel_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ViewReports)?;
+
+    let now = Utc::now();
+    let week_ago = now - chrono::Duration::days(7);
+    let two_weeks_ago = now - chrono::Duration::days(14);
+    let this_week = fetch_funnel_stats(&app_state.db_pool, week_ago, now).await?;
+    let previous_week = fetch_funnel_stats(&app_state.db_pool, two_weeks_ago, week_ago).await?;
+    let categories =
+        fetch_category_conversion(&app_state.db_pool, now - chrono::Duration::days(30)).await?;
+
+    let steps: [(&str, i64, i64); 5] = [
+        ("Sesje", this_week.sessions, previous_week.sessions),
+        (
+            "Wyświetlenia produktów",
+            this_week.product_views,
+            previous_week.product_views,
+        ),
+        (
+            "Dodanie do koszyka",
+            this_week.added_to_cart,
+            previous_week.added_to_cart,
+        ),
+        (
+            "Rozpoczęcie zamówienia",
+            this_week.checkout_started,
+            previous_week.checkout_started,
+        ),
+        (
+            "Zakup",
+            this_week.orders_placed,
+            previous_week.orders_placed,
+        ),
+    ];
+
+    let page_content = html! {
+        div id="admin-funnel-container" {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Lejek konwersji" }
+            p ."text-sm text-gray-600 mb-6" {
+                "Ostatnie 7 dni w porównaniu z poprzednim tygodniem. Liczymy unikalnych klientów (konto lub koszyk gościa)."
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200 mb-8" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Etap" }
+                            th ."admin-th text-right" { "Ten tydzień" }
+                            th ."admin-th text-right" { "Z poprz. etapu" }
+                            th ."admin-th text-right" { "Poprzedni tydzień" }
+                            th ."admin-th text-right" { "Z poprz. etapu" }
+                            th ."admin-th w-1/3" { "" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @for (index, (label, current, previous)) in steps.iter().enumerate() {
+                            @let prev_step_current = if index == 0 { *current } else { steps[index - 1].1 };
+                            @let prev_step_previous = if index == 0 { *previous } else { steps[index - 1].2 };
+                            @let bar_width = if this_week.sessions > 0 { (*current as f64 * 100.0 / this_week.sessions as f64).round() as i64 } else { 0 };
+                            tr {
+                                td ."admin-td font-medium text-gray-800" { (label) }
+                                td ."admin-td text-right" { (current) }
+                                td ."admin-td text-right text-gray-600" { @if index > 0 { (format_rate(*current, prev_step_current)) } }
+                                td ."admin-td text-right text-gray-500" { (previous) }
+                                td ."admin-td text-right text-gray-500" { @if index > 0 { (format_rate(*previous, prev_step_previous)) } }
+                                td ."admin-td" {
+                                    div ."h-2 bg-gray-100 rounded" {
+                                        div ."h-2 bg-pink-500 rounded" style=(format!("width: {}%", bar_width)) {}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            h4 ."text-xl font-semibold text-gray-800 mb-2" { "Konwersja w kategoriach (30 dni)" }
+            p ."text-sm text-gray-600 mb-4" { "Kategorie z wysoką konwersją warto częściej uzupełniać." }
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Kategoria" }
+                            th ."admin-th text-right" { "Wyświetlenia" }
+                            th ."admin-th text-right" { "Do koszyka" }
+                            th ."admin-th text-right" { "Sprzedane" }
+                            th ."admin-th text-right" { "Konwersja" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if categories.is_empty() {
+                            tr { td colspan="5" ."admin-td text-center text-gray-500" { "Brak danych z ostatnich 30 dni." } }
+                        }
+                        @for row in &categories {
+                            tr {
+                                td ."admin-td font-medium text-gray-800" { (row.category.to_string()) }
+                                td ."admin-td text-right" { (row.views) }
+                                td ."admin-td text-right" { (row.adds) }
+                                td ."admin-td text-right" { (row.sold) }
+                                td ."admin-td text-right font-semibold" { (format_rate(row.sold, row.views)) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Lejek konwersji - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+fn vital_rating_class(rating: VitalRating) -> &'static str {
+    match rating {
+        VitalRating::Good => "text-green-700",
+        VitalRating::NeedsImprovement => "text-yellow-700",
+        VitalRating::Poor => "text-red-700 font-semibold",
+    }
+}
+
+fn vital_ms_maud(value: Option<f64>, rating: fn(f64) -> VitalRating) -> Markup {
+    html! {
+        @match value {
+            Some(ms) => { span class=(vital_rating_class(rating(ms))) { (format!("{:.0} ms", ms)) } }
+            None => { span ."text-gray-400" { "-" } }
+        }
+    }
+}
+
+/// Raport wydajności z pomiarów prawdziwych użytkowników (RUM): 75. percentyle LCP, CLS i INP
+/// per trasa oraz tygodniowy trend LCP - do oceny zmian w ładowaniu zdjęć i preloadach.
+pub async fn admin_rum_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<RumReportQuery>,
+) -> Result<Response, AppError> {
+    claims.require_permission(Permission::ViewReports)?;
+
+    let days = params.days.unwrap_or(7).clamp(1, RUM_RETENTION_DAYS);
+    let device = params
+        .device
+        .as_deref()
+        .filter(|device| matches!(*device, "mobile" | "desktop"));
+    let since = Utc::now() - chrono::Duration::days(days);
+    let (routes, trend) = tokio::try_join!(
+        fetch_rum_route_stats(&app_state.db_pool, since, device),
+        fetch_rum_weekly_trend(&app_state.db_pool, 8, device),
+    )?;
+    let share = |part: i64, total: i64| {
+        if total > 0 {
+            format!("{:.0}%", part as f64 * 100.0 / total as f64)
+        } else {
+            "-".to_string()
+        }
+    };
+
+    let page_content = html! {
+        div id="admin-rum-container" {
+            h3 ."text-2xl sm:text-3xl font-semibold text-gray-800 mb-2" { "Wydajność (RUM)" }
+            p ."text-sm text-gray-600 mb-4" {
+                "75. percentyl Core Web Vitals z wejść prawdziwych użytkowników. Progi Google: LCP ≤ 2500 ms, CLS ≤ 0,1, INP ≤ 200 ms."
+            }
+            form hx-get="/htmx/admin/wydajnosc" hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                 hx-trigger="change"
+                 class="flex flex-wrap gap-3 mb-6" {
+                select name="days" class="admin-filter-select" {
+                    @for (value, label) in [(1, "Ostatnia doba"), (7, "Ostatnie 7 dni"), (30, "Ostatnie 30 dni"), (90, "Ostatnie 90 dni")] {
+                        option value=(value) selected[days == value] { (label) }
+                    }
+                }
+                select name="device" class="admin-filter-select" {
+                    option value="" selected[device.is_none()] { "Wszystkie urządzenia" }
+                    option value="mobile" selected[device == Some("mobile")] { "Telefony" }
+                    option value="desktop" selected[device == Some("desktop")] { "Komputery" }
+                }
+            }
+
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200 mb-8" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Trasa" }
+                            th ."admin-th text-right" { "Wejścia" }
+                            th ."admin-th text-right" { "LCP" }
+                            th ."admin-th text-right" { "CLS" }
+                            th ."admin-th text-right" { "INP" }
+                            th ."admin-th text-right" { "LCP = zdjęcie" }
+                            th ."admin-th text-right" { "z preloadem" }
+                            th ."admin-th text-right" { "leniwe (lazy)" }
+                            th ."admin-th text-right" { "Pobranie zdjęcia LCP" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if routes.is_empty() {
+                            tr { td colspan="9" ."admin-td text-center text-gray-500" { "Brak pomiarów w wybranym okresie." } }
+                        }
+                        @for row in &routes {
+                            tr {
+                                td ."admin-td font-mono text-gray-800" { (row.route) }
+                                td ."admin-td text-right" { (row.samples) }
+                                td ."admin-td text-right" { (vital_ms_maud(row.lcp_p75, VitalRating::lcp)) }
+                                td ."admin-td text-right" {
+                                    @match row.cls_p75 {
+                                        Some(cls) => { span class=(vital_rating_class(VitalRating::cls(cls))) { (format!("{:.3}", cls)) } }
+                                        None => { span ."text-gray-400" { "-" } }
+                                    }
+                                }
+                                td ."admin-td text-right" { (vital_ms_maud(row.inp_p75, VitalRating::inp)) }
+                                td ."admin-td text-right" { (share(row.image_lcp_samples, row.samples)) }
+                                td ."admin-td text-right" { (share(row.preloaded_samples, row.image_lcp_samples)) }
+                                td ."admin-td text-right" {
+                                    @if row.lazy_samples > 0 {
+                                        span ."text-red-700 font-semibold" { (share(row.lazy_samples, row.image_lcp_samples)) }
+                                    } @else {
+                                        (share(row.lazy_samples, row.image_lcp_samples))
+                                    }
+                                }
+                                td ."admin-td text-right" {
+                                    @if let Some(load_ms) = row.lcp_load_p75 { (format!("{:.0} ms", load_ms)) } @else { "-" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            h4 ."text-xl font-semibold text-gray-800 mb-2" { "LCP tydzień po tygodniu" }
+            p ."text-sm text-gray-600 mb-4" { "Cały sklep, ostatnie 8 tygodni - po wdrożeniu zmian w zdjęciach porównaj kolejne tygodnie." }
+            div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                table ."min-w-full divide-y divide-gray-200 text-sm" {
+                    thead ."bg-gray-50" {
+                        tr {
+                            th ."admin-th" { "Tydzień od" }
+                            th ."admin-th text-right" { "Wejścia" }
+                            th ."admin-th text-right" { "LCP (p75)" }
+                            th ."admin-th text-right" { "Zdjęcia LCP z preloadem" }
+                        }
+                    }
+                    tbody ."divide-y divide-gray-200" {
+                        @if trend.is_empty() {
+                            tr { td colspan="4" ."admin-td text-center text-gray-500" { "Brak pomiarów." } }
+                        }
+                        @for week in &trend {
+                            tr {
+                                td ."admin-td" { (format_date(&week.week_start)) }
+                                td ."admin-td text-right" { (week.samples) }
+                                td ."admin-td text-right" { (vital_ms_maud(week.lcp_p75, VitalRating::lcp)) }
+                                td ."admin-td text-right" { (share(week.preloaded_samples, week.image_lcp_samples)) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Admin Panel - Wydajność - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Statystyki sprzedaży na dashboardzie admina: podsumowanie, zamówienia dziennie, przychód
+/// tygodniowo, najlepsze kategorie i konwersja rezerwacji w sprzedaż dla wybranego zakresu dat.
+pub async fn admin_sales_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    claims: TokenClaims,
+    Query(params): Query<SalesDashboardParams>,
+) -> Result<Markup, AppError> {
+    claims.require_permission(Permission::ViewReports)?;
+
+    let (from, to) = params.range_utc();
+    let pool = &app_state.db_pool;
+    let (summary, daily, weekly, top_categories, conversion) = tokio::try_join!(
+        fetch_sales_summary(pool, from, to),
+        fetch_sales_timeline(pool, from, to, "day"),
+        fetch_sales_timeline(pool, from, to, "week"),
+        fetch_top_categories(pool, from, to, 8),
+        fetch_reservation_conversion(pool, from, to),
+    )?;
+
+    let register_month = parse_register_month(None)?;
+    let max_daily_orders = daily.iter().map(|d| d.orders).max().unwrap_or(0);
+    let max_weekly_revenue = weekly.iter().map(|w| w.revenue).max().unwrap_or(0);
+    let max_category_revenue = top_categories.iter().map(|c| c.revenue).max().unwrap_or(0);
+    let average_order = if summary.orders > 0 {
+        summary.revenue / summary.orders
+    } else {
+        0
+    };
+    let bar_percent = |value: i64, max: i64| {
+        if max > 0 {
+            (value as f64 * 100.0 / max as f64).round() as i64
+        } else {
+            0
+        }
+    };
+
+    Ok(html! {
+        div #admin-sales {
+            div ."flex flex-col sm:flex-row sm:items-end sm:justify-between gap-4 mb-6" {
+                div {
+                    h3 ."text-2xl sm:text-3xl font-semibold text-gray-800" { "Sprzedaż" }
+                    p ."text-sm text-gray-600" {
+                        (format_local_date(&params.date_from())) " – " (format_local_date(&params.date_to()))
+                        ". Anulowane zamówienia nie są wliczane."
+                    }
+                }
+                form hx-get="/htmx/admin/sales" hx-target="#admin-sales" hx-swap="outerHTML"
+                     class="flex flex-wrap items-end gap-2" {
+                    div {
+                        label for="sales-date-from" class="block text-xs font-medium text-gray-600 mb-1" { "Od" }
+                        input type="date" id="sales-date-from" name="date-from"
+                               value=(params.date_from().format("%Y-%m-%d"))
+                               class="px-3 py-1.5 border border-gray-300 rounded-md text-sm";
+                    }
+                    div {
+                        label for="sales-date-to" class="block text-xs font-medium text-gray-600 mb-1" { "Do" }
+                        input type="date" id="sales-date-to" name="date-to"
+                               value=(params.date_to().format("%Y-%m-%d"))
+                               class="px-3 py-1.5 border border-gray-300 rounded-md text-sm";
+                    }
+                    button type="submit" class="px-4 py-1.5 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                        "Pokaż"
+                    }
+                }
+            }
+
+            div ."grid grid-cols-2 lg:grid-cols-4 gap-4 mb-8" {
+                @for (label, value) in [
+                    ("Zamówienia", summary.orders.to_string()),
+                    ("Przychód", format_price_maud(summary.revenue)),
+                    ("Średnia wartość zamówienia", format_price_maud(average_order)),
+                    ("Rezerwacje zakończone sprzedażą", format_rate(conversion.sold_products, conversion.reserved_products)),
+                ] {
+                    div ."bg-white p-4 rounded-lg shadow-md border border-gray-200" {
+                        p ."text-xs uppercase tracking-wide text-gray-500" { (label) }
+                        p ."text-2xl font-semibold text-gray-900 mt-1" { (value) }
+                    }
+                }
+            }
+            p ."text-xs text-gray-500 -mt-6 mb-8" {
+                "Sprzedane sztuki: " (summary.items_sold)
+                " · anulowane zamówienia: " (summary.cancelled_orders)
+                " · zarezerwowane produkty: " (conversion.reserved_products)
+                ", z czego sprzedane: " (conversion.sold_products)
+            }
+
+            h4 ."text-xl font-semibold text-gray-800 mb-2" { "Zamówienia dziennie" }
+            div ."bg-white p-4 rounded-lg shadow-md border border-gray-200 mb-8" {
+                @if max_daily_orders == 0 {
+                    p ."text-sm text-gray-500 text-center py-8" { "Brak zamówień w wybranym okresie." }
+                } @else {
+                    div ."flex items-end gap-px h-40" {
+                        @for day in &daily {
+                            div ."flex-1 h-full flex items-end"
+                                title=(format!("{}: {} · {}", format_local_date(&day.period_start), orders_count(day.orders), format_price_maud(day.revenue))) {
+                                div ."w-full bg-pink-500 rounded-t" style=(format!("height: {}%", bar_percent(day.orders, max_daily_orders))) {}
+                            }
+                        }
+                    }
+                    div ."flex justify-between text-xs text-gray-500 mt-2" {
+                        @if let Some(first) = daily.first() { span { (format_local_day_month(&first.period_start)) } }
+                        span { "maks. " (max_daily_orders) " dziennie" }
+                        @if let Some(last) = daily.last() { span { (format_local_day_month(&last.period_start)) } }
+                    }
+                }
+            }
+
+            div ."grid grid-cols-1 xl:grid-cols-2 gap-8" {
+                div {
+                    h4 ."text-xl font-semibold text-gray-800 mb-2" { "Przychód tygodniowo" }
+                    div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                        table ."min-w-full divide-y divide-gray-200 text-sm" {
+                            thead ."bg-gray-50" {
+                                tr {
+                                    th ."admin-th" { "Tydzień od" }
+                                    th ."admin-th text-right" { "Zamówienia" }
+                                    th ."admin-th text-right" { "Przychód" }
+                                    th ."admin-th w-1/3" { "" }
+                                }
+                            }
+                            tbody ."divide-y divide-gray-200" {
+                                @for week in &weekly {
+                                    tr {
+                                        td ."admin-td font-medium text-gray-800" { (format_local_date(&week.period_start)) }
+                                        td ."admin-td text-right" { (week.orders) }
+                                        td ."admin-td text-right" { (format_price_maud(week.revenue)) }
+                                        td ."admin-td" {
+                                            div ."h-2 bg-gray-100 rounded" {
+                                                div ."h-2 bg-pink-500 rounded" style=(format!("width: {}%", bar_percent(week.revenue, max_weekly_revenue))) {}
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    h4 ."text-xl font-semibold text-gray-800 mb-2" { "Najlepsze kategorie" }
+                    div ."overflow-x-auto bg-white rounded-lg shadow-md border border-gray-200" {
+                        table ."min-w-full divide-y divide-gray-200 text-sm" {
+                            thead ."bg-gray-50" {
+                                tr {
+                                    th ."admin-th" { "Kategoria" }
+                                    th ."admin-th text-right" { "Sprzedane" }
+                                    th ."admin-th text-right" { "Przychód" }
+                                    th ."admin-th w-1/3" { "" }
+                                }
+                            }
+                            tbody ."divide-y divide-gray-200" {
+                                @if top_categories.is_empty() {
+                                    tr { td colspan="4" ."admin-td text-center text-gray-500" { "Brak sprzedaży w wybranym okresie." } }
+                                }
+                                @for row in &top_categories {
+                                    tr {
+                                        td ."admin-td font-medium text-gray-800" { (row.category.to_string()) }
+                                        td ."admin-td text-right" { (row.items_sold) }
+                                        td ."admin-td text-right" { (format_price_maud(row.revenue)) }
+                                        td ."admin-td" {
+                                            div ."h-2 bg-gray-100 rounded" {
+                                                div ."h-2 bg-pink-500 rounded" style=(format!("width: {}%", bar_percent(row.revenue, max_category_revenue))) {}
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div ."mt-8 bg-white p-4 rounded-lg shadow-md border border-gray-200" {
+                h4 ."text-xl font-semibold text-gray-800 mb-1" { "Rejestr sprzedaży dla księgowości" }
+                p ."text-sm text-gray-600 mb-3" {
+                    "Plik CSV (średniki, układ JPK_V7) z opłaconymi zamówieniami i korektami za zwroty w wybranym miesiącu."
+                }
+                form action="/api/admin/raporty/rejestr-sprzedazy" method="get" class="flex flex-wrap items-end gap-2" {
+                    div {
+                        label for="sales-register-month" class="block text-xs font-medium text-gray-600 mb-1" { "Miesiąc" }
+                        input type="month" id="sales-register-month" name="month"
+                               value=(register_month.format("%Y-%m"))
+                               class="px-3 py-1.5 border border-gray-300 rounded-md text-sm";
+                    }
+                    button type="submit" class="px-4 py-1.5 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" {
+                        "Pobierz CSV"
+                    }
+                }
+            }
+        }
+    })
+}
+
+pub fn render_admin_product_list_row_maud(
+    product: &Product,
+    params: &ListingParams, // Potrzebne do zbudowania poprawnych linków edycji
+) -> Markup {
+    admin_product_list_row_maud(product, params, false)
+}
+
+/// Podsumowanie akcji masowej: liczba zmienionych i lista pominiętych produktów z powodem.
+/// Wiersze zmienionych produktów są podmieniane w tabeli przez OOB.
+pub fn render_product_bulk_result_maud(
+    action: ProductBulkAction,
+    outcomes: &[ProductBulkOutcome],
+    updated_products: &[Product],
+    params: &ListingParams,
+) -> Markup {
+    let failures: Vec<&ProductBulkOutcome> =
+        outcomes.iter().filter(|o| o.error.is_some()).collect();
+    let success_count = outcomes.len() - failures.len();
+    html! {
+        div class="p-4 rounded-lg border border-gray-200 bg-white shadow-sm text-sm" {
+            p class="font-medium text-gray-800" {
+                (action.label()) ": "
+                span class="text-green-700" { "zmieniono " (success_count) }
+                @if !failures.is_empty() {
+                    ", " span class="text-red-700" { "pominięto " (failures.len()) }
+                }
+                " z " (outcomes.len()) "."
+            }
+            @if !failures.is_empty() {
+                ul class="mt-2 list-disc list-inside text-gray-600 space-y-1" {
+                    @for failure in &failures {
+                        li {
+                            span class="font-medium" {
+                                (failure.product_name.clone().unwrap_or_else(|| failure.product_id.to_string()))
+                            }
+                            ": " (failure.error.as_deref().unwrap_or_default())
+                        }
+                    }
+                }
+            }
+        }
+        @for product in updated_products {
+            (render_admin_product_list_row_oob_maud(product, params))
+        }
+    }
+}
+
+/// Wiersz podmieniany poza głównym celem (OOB), np. po akcji masowej. `template` jest
+/// potrzebny, bo `<tr>` poza tabelą zostałby odrzucony przez parser HTML.
+fn render_admin_product_list_row_oob_maud(product: &Product, params: &ListingParams) -> Markup {
+    html! {
+        template { (admin_product_list_row_maud(product, params, true)) }
+    }
+}
+
+fn admin_product_list_row_maud(product: &Product, params: &ListingParams, oob: bool) -> Markup {
+    let params_for_edit_links = params.to_query_string_with_skips(&["offset"]);
+    html! {
+        // Cały kod dla `<tr>` jest teraz tutaj
+        tr id=(format!("product-row-{}", product.id)) ."hover:bg-pink-50/30 transition-colors duration-150 ease-in-out"
+           hx-swap-oob=[oob.then_some("true")] {
+            td class="admin-td w-8" {
+                input type="checkbox" name="product_ids" value=(product.id) form="admin-bulk-form"
+                      class="h-4 w-4 rounded border-gray-300 text-pink-600 focus:ring-pink-500"
+                      aria-label=(format!("Zaznacz {}", product.name));
+            }
+            td class="admin-td-image" {
+                a href=(format!("/htmx/admin/products/{}/edit?{}", product.id, params_for_edit_links))
+                   hx-get=(format!("/htmx/admin/products/{}/edit?{}", product.id, params_for_edit_links))
+                   hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                   title="Edytuj produkt" class="block w-12 h-12" {
+                    @if let Some(image_url) = product.images.get(0) {
+                        @let transformed_url = transform_cloudinary_url(image_url, "w_100,h_100,c_fill,f_auto,q_auto");
+                        img src=(transformed_url) alt=(product.name) class="h-full w-full rounded-md object-cover shadow-sm hover:shadow-md transition-shadow";
+                    } @else {
+                        div class="h-full w-full rounded-md bg-gray-200 flex items-center justify-center text-xs text-gray-400" { "N/A" }
+                    }
+                }
+            }
+            td class="admin-td font-medium text-gray-900" {
+                a href=(format!("/htmx/admin/products/{}/edit?{}", product.id, params_for_edit_links))
+                   hx-get=(format!("/htmx/admin/products/{}/edit?{}", product.id, params_for_edit_links))
+                   hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                   class="hover:text-pink-700 hover:underline" {
+                    (product.name)
+                }
+            }
+            td class="admin-td text-gray-700" { (format_price_maud(product.price)) }
+            td class="admin-td" {
+                span class=(get_status_badge_classes(product.status.clone())) { (product.status.to_string()) }
+            }
+            td class="admin-td text-gray-600" { (product.category.to_string()) }
+            td class="admin-td text-gray-500 text-xs" { (format_datetime_admin(&product.created_at)) }
+            td class="admin-td text-right space-x-2 whitespace-nowrap" {
+                @if product.status != ProductStatus::Archived {
+                    a href=(format!("/htmx/admin/products/{}/edit?{}", product.id, params_for_edit_links))
+                        hx-get=(format!("/htmx/admin/products/{}/edit?{}", product.id, params_for_edit_links))
+                        hx-target="#admin-content" hx-swap="innerHTML" hx-push-url="true"
+                        class="admin-action-button text-indigo-600 hover:text-indigo-800" title="Edytuj" {
+                            svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-5 h-5" { path d="M2.695 14.763l-1.262 3.154a.5.5 0 00.65.65l3.155-1.262a4 4 0 001.343-.885L17.5 5.5a2.121 2.121 0 00-3-3L3.58 13.42a4 4 0 00-.885 1.343z"; }
+                    }
+                    button hx-delete=(format!("/api/products/{}", product.id))
+                           hx-confirm="Czy na pewno chcesz zarchiwizować ten produkt? Zniknie on ze sklepu, ale pozostanie w systemie."
+                           hx-target="closest tr" hx-swap="outerHTML"
+                           class="admin-action-button text-gray-500 hover:text-gray-800" title="Archiwizuj" {
+                        svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-5 h-5" { path d="M3.5 3.75a.75.75 0 00-1.5 0v1.5c0 .414.336.75.75.75h13.5a.75.75 0 00.75-.75v-1.5a.75.75 0 00-1.5 0V5H4V3.75z"; path fill-rule="evenodd" d="M5.5 6.4v1.528A2.249 2.249 0 007.75 10h4.5A2.25 2.25 0 0014.5 7.928V6.4H5.5zm1.25 1.528a.75.75 0 01.75-.75h4.5a.75.75 0 01.75.75v5.322a.75.75 0 01-.75.75h-4.5a.75.75 0 01-.75-.75V7.928z" clip-rule="evenodd"; }
+                    }
+                } @else {
+                    button hx-delete=(format!("/api/products/{}/permanent", product.id))
+                           hx-confirm="UWAGA! Czy na pewno chcesz TRWALE usunąć ten produkt? Operacji nie można cofnąć."
+                           hx-target="closest tr" hx-swap="outerHTML"
+                           class="admin-action-button text-red-600 hover:text-red-800" title="Usuń trwale" {
+                        svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20" fill="currentColor" class="w-5 h-5" { path fill-rule="evenodd" d="M8.75 1A2.75 2.75 0 006 3.75v.443c-.795.077-1.584.176-2.365.298a.75.75 0 10.23 1.482l.149-.022.841 10.518A2.75 2.75 0 007.596 19h4.807a2.75 2.75 0 002.742-2.53l.841-10.52.149.023a.75.75 0 00.23-1.482A41.03 41.03 0 0014 4.193v-.443A2.75 2.75 0 0011.25 1h-2.5zM10 4c.84 0 1.673.025 2.5.075V3.75c0-.69-.56-1.25-1.25-1.25h-2.5c-.69 0-1.25.56-1.25 1.25v.325C8.327 4.025 9.16 4 10 4zM8.58 7.72a.75.75 0 00-1.5.06l.3 7.5a.75.75 0 101.5-.06l-.3-7.5zm4.34.06a.75.75 0 10-1.5-.06l-.3 7.5a.75.75 0 101.5.06l.3-7.5z" clip-rule="evenodd"; }
+                    }
+                }
+            }
+        }
+    }
+}
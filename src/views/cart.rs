@@ -0,0 +1,2395 @@
+// src/views/cart.rs
+
+// Koszyk, kroki checkoutu, finalizacja płatności i strona podziękowania.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::Form;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::Response;
+use axum_extra::TypedHeader;
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use maud::{Markup, html};
+use uuid::Uuid;
+
+use crate::auth_models::TokenClaims;
+use crate::cart_utils;
+use crate::checkout::{
+    CheckoutStep, available_shipping_options, find_shipping_option, free_shipping_threshold,
+    get_or_create_checkout_draft, open_draft_progress, payload_from_draft, payment_method_options,
+    save_checkout_step, validate_step,
+};
+use crate::coupons::find_applicable_coupon;
+use crate::email_service::send_order_confirmation_email;
+use crate::errors::{AppError, ValidationErrors};
+use crate::events::{NewEvent, record_event};
+use crate::filters::ListingParams;
+use crate::gift_cards::{
+    GIFT_CARD_AMOUNTS, GIFT_CARD_VALIDITY_MONTHS, GiftCard, MAX_GIFT_CARD_MESSAGE_LEN,
+    find_gift_card, find_usable_gift_card,
+};
+use crate::handlers::XGuestCartId;
+use crate::holds::reserve_held_product_for_cart;
+use crate::inpost::{nearest_points, normalize_postal_code};
+use crate::middleware::{OptionalGuestCartId, OptionalTokenClaims, UnverifiedEmail};
+use crate::models::{
+    ApplyCouponPayload, ApplyGiftCardPayload, CartDetailsResponse, CartHoldQuery, CheckoutDraft,
+    CheckoutStepPayload, Coupon, EventType, InpostSuggestionsQuery, Order, OrderItemDetailsPublic,
+    OrderStatus, PaymentMethod, Product, ShoppingCart,
+};
+use crate::plural::items_count;
+use crate::repo;
+use crate::repo::carts::CartOwner;
+use crate::reservations::{
+    ReservationOutcome, extend_cart_reservations, release_product_reservation,
+    reserve_product_for_cart,
+};
+use crate::response::{PageBuilder, build_response};
+use crate::security_headers::CspNonce;
+use crate::state::{AppState, PaymentDetailsConfig};
+
+use super::account::render_email_verification_banner_maud;
+use super::common::{
+    build_full_query_string_from_params, format_price_maud, product_price_maud,
+    transform_cloudinary_url,
+};
+
+pub async fn get_cart_details_htmx_handler(
+    headers: HeaderMap,
+    State(app_state): State<Arc<AppState>>,
+    user_claims_result: Result<TokenClaims, AppError>, // Wynik ekstrakcji JWT (może być błąd, jeśli brak tokenu)
+    guest_cart_id_header: Option<TypedHeader<XGuestCartId>>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    // Zwracamy teraz krotkę (nagłówki, Markup)
+    tracing::info!("MAUD: /htmx/cart/details - żądanie zawartości koszyka");
+
+    let mut conn = app_state.db_pool.acquire().await.map_err(|e| {
+        tracing::error!("MAUD Cart: Nie można uzyskać połączenia z puli: {}", e);
+        AppError::InternalServerError("Błąd serwera przy ładowaniu koszyka".to_string())
+    })?;
+
+    let mut cart_details_response: Option<CartDetailsResponse> = None;
+    let mut final_guest_cart_id_for_trigger: Option<Uuid> = None;
+
+    if let Ok(claims) = user_claims_result {
+        // Użytkownik jest zalogowany
+        let user_id = claims.sub;
+        if let Some(cart) = repo::carts::find_by_owner(&mut *conn, CartOwner::User(user_id)).await?
+        {
+            cart_details_response =
+                Some(cart_utils::build_cart_details_response(&cart, &mut conn).await?);
+        }
+    } else if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
+        // Użytkownik-gość z istniejącym ID koszyka
+        final_guest_cart_id_for_trigger = Some(guest_id);
+        if let Some(cart) =
+            repo::carts::find_by_owner(&mut *conn, CartOwner::Guest(guest_id)).await?
+        {
+            cart_details_response =
+                Some(cart_utils::build_cart_details_response(&cart, &mut conn).await?);
+        }
+    }
+
+    // --- NOWA LOGIKA: Budowanie kontekstu powrotu na podstawie nagłówka ---
+    let mut return_params_qs = String::new();
+    if let Some(current_url_header) = headers.get(axum::http::header::HeaderName::from_static(
+        "hx-current-url",
+    )) {
+        if let Ok(current_url_str) = current_url_header.to_str() {
+            // Parsujemy pełny URL, który podesłał HTMX
+            if let Ok(current_url) = url::Url::parse(current_url_str) {
+                let path = current_url.path();
+                let query = current_url.query().unwrap_or("");
+
+                // Tworzymy parametry na podstawie query stringa z bieżącego URL
+                let mut back_params: ListingParams = serde_qs::from_str(query).unwrap_or_default();
+
+                // Uzupełniamy `source` na podstawie ścieżki
+                if path.starts_with("/nowosci") {
+                    back_params.source = Some("nowosci".to_string());
+                } else if path.starts_with("/okazje") {
+                    back_params.source = Some("okazje".to_string());
+                } else if path.starts_with("/wyszukiwanie") {
+                    back_params.source = Some("search".to_string());
+                } else if path == "/" {
+                    back_params.source = Some("home".to_string());
+                }
+
+                // Budujemy kompletny string `return_params`
+                return_params_qs = build_full_query_string_from_params(&back_params);
+            }
+        }
+    }
+    let encoded_return_params = urlencoding::encode(&return_params_qs);
+    // --- KONIEC NOWEJ LOGIKI ---
+
+    // Rozpoczęta, niedokończona kasa - proponujemy powrót do miejsca, w którym klient przerwał
+    let resume_step = match &cart_details_response {
+        Some(cdr) if !cdr.items.is_empty() => open_draft_progress(&mut conn, cdr.cart_id)
+            .await?
+            .filter(|completed_steps| *completed_steps > 0)
+            .map(CheckoutStep::first_incomplete),
+        _ => None,
+    };
+
+    let items = cart_details_response
+        .as_ref()
+        .map_or_else(Vec::new, |cdr| cdr.items.clone()); // Klonujemy, bo cdr jest potrzebny niżej
+    let total_items = cart_details_response
+        .as_ref()
+        .map_or(0, |cdr| cdr.total_items);
+    let total_price = cart_details_response
+        .as_ref()
+        .map_or(0, |cdr| cdr.total_price);
+
+    // Przygotuj nagłówek HX-Trigger
+    let mut headers = HeaderMap::new();
+    let trigger_payload = serde_json::json!({
+        "updateCartCount": {
+            "newCount": total_items,
+            "newCartTotalPrice": total_price, // Przekazujemy sumę do aktualizacji w Alpine.js
+            "newGuestCartId": final_guest_cart_id_for_trigger // Przekaż, jeśli jest (dla Alpine)
+        }
+    });
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    } else {
+        tracing::error!("MAUD Cart: Nie można utworzyć nagłówka HX-Trigger dla koszyka");
+    }
+
+    let markup = html! {
+        @if items.is_empty() {
+            p ."text-gray-600 py-6 text-center" { "Twój koszyk jest pusty." }
+        } @else {
+            p ."text-sm text-gray-500" { "Masz " (items_count(total_items as i64)) " w koszyku." }
+            @if let Some(step) = resume_step {
+                div ."mt-4 p-3 bg-pink-50 border border-pink-200 rounded-md text-sm" {
+                    p ."text-gray-700" {
+                        "Masz niedokończone zamówienie. Następny krok: „" (step.label()) "”."
+                    }
+                    a href="/checkout" hx-get="/htmx/checkout" hx-target="#content" hx-swap="innerHTML" hx-push-url="/checkout"
+                       "@click"="if(typeof cartOpen !== 'undefined') cartOpen = false"
+                       class="inline-block mt-1 font-medium text-[var(--text-color-primary)] hover:underline" {
+                        "Dokończ zamówienie →"
+                    }
+                }
+            }
+
+    ul role="list" ."my-6 divide-y divide-gray-200 border-t border-b" {
+        @for item in &items { // lub &items, zależnie od nazwy zmiennej
+            li ."flex py-4 px-4 sm:px-0" {
+                // --- Obrazek jako link ---
+                a href=(item.product.public_path()) // Fallback URL
+                   hx-get=(format!("/htmx/produkt/{}?return_params={}", item.product.slug, encoded_return_params))
+                   hx-target="#content"                                 // Cel podmiany
+                   hx-swap="innerHTML"
+                   hx-push-url=(item.product.public_path()) // Aktualizacja URL w przeglądarce
+                   "@click"="if(typeof cartOpen !== 'undefined') cartOpen = false" // Zamknij koszyk (Alpine.js)
+                   class="h-20 w-20 flex-shrink-0 overflow-hidden rounded-md border border-gray-200 block group"
+                   aria-label={"Zobacz szczegóły produktu " (item.product.name)} {
+                    @if !item.product.images.is_empty() {
+                        @let transformed_url = transform_cloudinary_url(&item.product.images[0], "w_100,h_100,c_fill,f_auto,q_auto");
+                        img src=(transformed_url) alt=(item.product.name)
+                            class="h-full w-full object-cover object-center group-hover:opacity-85 transition-opacity" loading="lazy" width="80" height="80";                    } @else {
+                        div ."h-full w-full bg-gray-100 flex items-center justify-center text-xs text-gray-400 group-hover:opacity-85 transition-opacity" { "Brak foto" }
+                    }
+                }
+
+                div ."ml-4 flex flex-1 flex-col" {
+                    div {
+                        div ."flex justify-between text-sm font-medium text-gray-800" {
+                            h3 ."group" {
+                                a href=(item.product.public_path()) // Fallback URL
+                                   hx-get=(format!("/htmx/produkt/{}?return_params={}", item.product.slug, encoded_return_params))
+                                   hx-target="#content"
+                                   hx-swap="innerHTML"
+                                   hx-push-url=(item.product.public_path())
+                                   "@click"="if(typeof cartOpen !== 'undefined') cartOpen = false" // Zamknij koszyk (Alpine.js)
+                                  class="hover:text-pink-600 transition-colors group-hover:underline" {
+                                    (item.product.name)
+                                }
+                            }
+                            p ."ml-4 whitespace-nowrap" { (product_price_maud(&item.product)) }
+                        }
+                        p ."mt-1 text-xs text-gray-500" { (item.product.category.to_string()) }
+                    }
+                    div ."flex flex-1 items-end justify-between text-xs mt-2" { // Dodano mt-2 dla odstępu
+                        div ."flex" {
+                            button type="button"
+                                hx-post=(format!("/htmx/cart/remove/{}", item.product.id))
+                                hx-target="#cart-content-target"
+                                hx-swap="innerHTML"
+                                class="text-sm font-medium text-[var(--text-color-primary)] px-3 py-1 rounded-md hover:bg-[var(--color-secondary)] hover:text-[var(--text-color-primary-hover)]" {
+                                "Usuń"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+        }
+    };
+    Ok((headers, markup))
+}
+
+pub async fn add_item_to_cart_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    user_claims_result: Result<TokenClaims, AppError>, // Rezultat ekstrakcji JWT
+    guest_cart_id_header: Option<TypedHeader<XGuestCartId>>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    tracing::info!(
+        "MAUD HTMX: /htmx/cart/add/{} - próba dodania produktu",
+        product_id
+    );
+
+    let mut tx = app_state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("MAUD AddToCart: Błąd rozpoczynania transakcji: {}", e);
+        AppError::InternalServerError("Błąd serwera przy dodawaniu do koszyka".to_string())
+    })?;
+
+    let mut headers = HeaderMap::new();
+    let cart: ShoppingCart;
+    let mut new_guest_cart_id_to_set: Option<Uuid> = None;
+
+    if let Ok(claims) = user_claims_result {
+        // --- SCENARIUSZ 1: Użytkownik zalogowany ---
+        cart = repo::carts::cart_for_owner_for_update(&mut tx, CartOwner::User(claims.sub)).await?;
+    } else {
+        // --- SCENARIUSZ 2: Użytkownik jest gościem ---
+        if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
+            // Gość ma już ID sesji - jeśli koszyk zniknął (rzadkie), powstaje nowy z tym samym ID
+            cart =
+                repo::carts::cart_for_owner_for_update(&mut tx, CartOwner::Guest(guest_id)).await?;
+            new_guest_cart_id_to_set = Some(guest_id);
+        } else {
+            // Nowy gość, tworzymy mu ID sesji i koszyk
+            let new_id = Uuid::new_v4();
+            new_guest_cart_id_to_set = Some(new_id);
+            cart = repo::carts::create(&mut *tx, CartOwner::Guest(new_id)).await?;
+
+            // <<< KLUCZOWA POPRAWKA: Ustawiamy ciasteczko dla nowego gościa >>>
+            let guest_cookie = Cookie::build(("guest_cart_id", new_id.to_string()))
+                .path("/")
+                .http_only(true)
+                .secure(true)
+                .same_site(SameSite::Lax) // Użyj Lax zamiast None dla lepszego bezpieczeństwa
+                .max_age(time::Duration::days(365))
+                .build();
+            headers.insert(
+                axum::http::header::SET_COOKIE,
+                guest_cookie.to_string().parse().unwrap(),
+            );
+            tracing::info!("Ustawiono ciasteczko guest_cart_id dla nowego gościa.");
+        }
+    }
+
+    // 2. Sprawdź produkt i dodaj do koszyka
+    let product_opt = repo::products::find_by_id_for_update(&mut *tx, product_id).await?;
+
+    match product_opt {
+        Some(product) => {
+            let reservation = reserve_product_for_cart(&mut tx, product_id, cart.id).await?;
+            if reservation != ReservationOutcome::Reserved {
+                tracing::warn!(
+                    "MAUD AddToCart: Produkt {} (ID: {}) niedostępny. Status: {:?}, rezerwacja: {:?}",
+                    product.name,
+                    product_id,
+                    product.status,
+                    reservation
+                );
+                tx.rollback().await?; // Ważne: wycofaj transakcję, bo nic nie dodajemy
+
+                let message = if reservation == ReservationOutcome::HeldByAnotherCart {
+                    format!(
+                        "Produkt '{}' jest zarezerwowany przez innego klienta.",
+                        product.name
+                    )
+                } else {
+                    format!("Produkt '{}' jest obecnie niedostepny.", product.name)
+                };
+                let trigger_payload = serde_json::json!({
+                    "showMessage": { "type": "warning", "message": message }
+                });
+                if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+                    headers.insert("HX-Trigger", val);
+                }
+                return Ok((headers, html!())); // Zwracamy OK, ale z wiadomością o błędzie
+            }
+
+            // Dodaj produkt do cart_items (lub zignoruj, jeśli już istnieje)
+            repo::carts::add_item(&mut *tx, cart.id, product_id).await?;
+            tracing::info!(
+                "MAUD AddToCart: Produkt ID {} dodany/istniał w koszyku ID {}",
+                product_id,
+                cart.id
+            );
+        }
+        None => {
+            tracing::warn!(
+                "MAUD AddToCart: Produkt o ID {} nie znaleziony.",
+                product_id
+            );
+            tx.rollback().await?;
+
+            let trigger_payload = serde_json::json!({
+                "showMessage": { "type": "error", "message": "Wybrany produkt nie został znaleziony." }
+            });
+            if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+                headers.insert("HX-Trigger", val);
+            }
+            return Ok((headers, html!())); // Można też OK z triggerem błędu
+        }
+    }
+
+    // 3. Pobierz zaktualizowane szczegóły koszyka dla HX-Trigger
+    // Funkcja build_cart_details_response aktualizuje też `updated_at` w tabeli `shopping_carts`
+    let cart_details: CartDetailsResponse =
+        cart_utils::build_cart_details_response(&cart, &mut tx).await?;
+
+    // 4. Zatwierdź transakcję
+    tx.commit().await.map_err(|e| {
+        tracing::error!("MAUD AddToCart: Błąd przy zatwierdzaniu transakcji: {}", e);
+        AppError::InternalServerError("Błąd serwera przy zapisie koszyka".to_string())
+    })?;
+    record_event(
+        &app_state.db_pool,
+        EventType::AddedToCart,
+        NewEvent::for_cart(&cart, product_id),
+    );
+
+    // 5. Przygotuj nagłówek HX-Trigger
+    let trigger_payload = serde_json::json!({
+        "product-added": { "productId": product_id },
+        "updateCartCount": {
+            "newCount": cart_details.total_items,
+            "newCartTotalPrice": cart_details.total_price,
+            "newGuestCartId": new_guest_cart_id_to_set // Przekaż nowe lub istniejące ID gościa
+        },
+        "showMessage": {
+            "type": "success",
+            "message": "Dodano produkt do koszyka!"
+        }
+    });
+
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    } else {
+        tracing::error!("MAUD AddToCart: Nie można utworzyć nagłówka HX-Trigger.");
+    }
+
+    if let Some(cookie) = new_guest_cart_id_to_set {
+        // Bezpiecznie parsujemy string ciasteczka na HeaderValue
+        if let Ok(cookie_value) = cookie.to_string().parse() {
+            headers.insert(axum::http::header::SET_COOKIE, cookie_value);
+            tracing::info!("Ustawiono ciasteczko guest_cart_id dla nowego gościa.");
+        }
+    }
+
+    Ok((headers, render_added_to_cart_button(product_id)))
+}
+
+pub async fn remove_item_from_cart_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id_to_remove): Path<Uuid>,
+    user_claims_result: Result<TokenClaims, AppError>,
+    guest_cart_id_header: Option<TypedHeader<XGuestCartId>>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    tracing::info!(
+        "MAUD HTMX: /htmx/cart/remove/{} - próba usunięcia produktu",
+        product_id_to_remove
+    );
+
+    let mut tx = app_state.db_pool.begin().await.map_err(|e| {
+        tracing::error!("MAUD RemoveFromCart: Błąd rozpoczynania transakcji: {}", e);
+        AppError::InternalServerError("Błąd serwera przy usuwaniu z koszyka".to_string())
+    })?;
+    let mut cart_for_response: Option<ShoppingCart> = None;
+    let mut guest_cart_id_for_trigger: Option<Uuid> = None;
+    let mut item_removed = false;
+
+    // 1. Znajdź koszyk użytkownika lub gościa
+    if let Ok(claims) = user_claims_result {
+        // Użytkownik zalogowany
+        cart_for_response =
+            repo::carts::find_by_owner_for_update(&mut *tx, CartOwner::User(claims.sub)).await?;
+    } else if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
+        // Gość
+        if let Some(cart) =
+            repo::carts::find_by_owner_for_update(&mut *tx, CartOwner::Guest(guest_id)).await?
+        {
+            cart_for_response = Some(cart);
+            guest_cart_id_for_trigger = Some(guest_id);
+        }
+    }
+
+    // 2. Usuń produkt z koszyka, jeśli koszyk istnieje
+    if let Some(ref cart) = cart_for_response {
+        if repo::carts::remove_item(&mut *tx, cart.id, product_id_to_remove).await? {
+            item_removed = true;
+            release_product_reservation(&mut tx, product_id_to_remove, cart.id).await?;
+            tracing::info!(
+                "MAUD RemoveFromCart: Produkt ID {} usunięty z koszyka ID {}",
+                product_id_to_remove,
+                cart.id
+            );
+        } else {
+            tracing::warn!(
+                "MAUD RemoveFromCart: Produkt ID {} nie znaleziony w koszyku ID {} do usunięcia.",
+                product_id_to_remove,
+                cart.id
+            );
+        }
+        // Nawet jeśli produkt nie został znaleziony do usunięcia (może już go nie było),
+        // nadal chcemy zbudować i zwrócić aktualny stan koszyka.
+    } else {
+        // Koszyk nie istnieje, więc nie ma z czego usuwać.
+        // Zwrócimy pusty stan koszyka, trigger z zerowymi wartościami.
+        tracing::warn!("MAUD RemoveFromCart: Próba usunięcia produktu, ale koszyk nie istnieje.");
+    }
+
+    // 3. Pobierz zaktualizowane szczegóły koszyka (lub domyślne, jeśli koszyk nie istniał)
+    //    build_cart_details_response aktualizuje też updated_at koszyka.
+    let cart_details: CartDetailsResponse = if let Some(ref cart_ref) = cart_for_response {
+        // Musimy odświeżyć stan koszyka, ponieważ build_cart_details_response może go zaktualizować
+        // Używamy tx, bo updated_at jest modyfikowane w build_cart_details_response
+        let refreshed_cart = repo::carts::find_by_id(&mut *tx, cart_ref.id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        cart_utils::build_cart_details_response(&refreshed_cart, &mut tx).await?
+    } else {
+        // Jeśli koszyk nie istniał, zwracamy "pustą" odpowiedź.
+        // Można by tu zwrócić błąd, ale dla HTMX chcemy zazwyczaj zwrócić fragment HTML.
+        // W tym przypadku, jeśli nie ma koszyka, `items` będzie puste.
+        CartDetailsResponse {
+            cart_id: Uuid::nil(), // Lub inne sensowne domyślne ID
+            user_id: None,
+            items: vec![],
+            total_items: 0,
+            total_price: 0,
+            updated_at: chrono::Utc::now(),
+        }
+    };
+
+    // 4. Zatwierdź transakcję
+    tx.commit().await.map_err(|e| {
+        tracing::error!(
+            "MAUD RemoveFromCart: Błąd przy zatwierdzaniu transakcji: {}",
+            e
+        );
+        AppError::InternalServerError("Błąd serwera przy aktualizacji koszyka".to_string())
+    })?;
+    if let (true, Some(cart)) = (item_removed, &cart_for_response) {
+        record_event(
+            &app_state.db_pool,
+            EventType::RemovedFromCart,
+            NewEvent::for_cart(cart, product_id_to_remove),
+        );
+    }
+
+    // 5. Przygotuj nagłówek HX-Trigger
+    let mut headers = HeaderMap::new();
+    let trigger_payload = serde_json::json!({
+        "product-removed": { "productId": product_id_to_remove },
+        "updateCartCount": {
+            "newCount": cart_details.total_items,
+            "newCartTotalPrice": cart_details.total_price,
+            "newGuestCartId": guest_cart_id_for_trigger // Przekazujemy ID gościa, jeśli było
+        },
+        "showMessage": {
+            "type": "info",
+            "message": "Produkt usuniety z koszyka."
+        }
+    });
+
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", trigger_value);
+    } else {
+        tracing::error!("MAUD RemoveFromCart: Nie można utworzyć nagłówka HX-Trigger.");
+    }
+
+    // 6. Wyrenderuj HTML dla listy przedmiotów w koszyku (podobnie jak w get_cart_details_htmx_handler)
+    let markup = html! {
+            // === CZĘŚĆ 1: GŁÓWNA TREŚĆ DLA PANELU KOSZYKA ===
+            // Ten kod trafi do elementu, który był celem (hx-target) przycisku "Usuń" w koszyku.
+            // Jest to zaktualizowany widok bocznego panelu.
+            @if cart_details.items.is_empty() {
+                p ."text-gray-600 py-6 text-center" { "Twój koszyk jest pusty." }
+            } @else {
+                ul role="list" ."my-6 divide-y divide-gray-200 border-t border-b" {
+                    @for item in &cart_details.items {
+                        li ."flex py-4 px-4 sm:px-0" {
+                            a href=(item.product.public_path())
+                               hx-get=(format!("/htmx/produkt/{}", item.product.slug))
+                               hx-target="#content"
+                               hx-swap="innerHTML"
+                               hx-push-url=(item.product.public_path())
+                               "@click"="if(typeof cartOpen !== 'undefined') cartOpen = false"
+                               class="h-20 w-20 flex-shrink-0 overflow-hidden rounded-md border border-gray-200 block group"
+                               aria-label={"Zobacz szczegóły produktu " (item.product.name)} {
+                                @if !item.product.images.is_empty() {
+                                    img src=(item.product.images[0]) alt=(item.product.name) class="h-full w-full object-cover object-center group-hover:opacity-85 transition-opacity" loading="lazy";
+                                } @else {
+                                    div ."h-full w-full bg-gray-100 flex items-center justify-center text-xs text-gray-400 group-hover:opacity-85 transition-opacity" { "Brak foto" }
+                                }
+                            }
+
+                            div ."ml-4 flex flex-1 flex-col" {
+                                div {
+                                    div ."flex justify-between text-sm font-medium text-gray-800" {
+                                        h3 ."group" {
+                                            a href=(item.product.public_path())
+                                               hx-get=(format!("/htmx/produkt/{}", item.product.slug))
+                                               hx-target="#content"
+                                               hx-swap="innerHTML"
+                                               hx-push-url=(item.product.public_path())
+                                               "@click"="if(typeof cartOpen !== 'undefined') cartOpen = false"
+                                               class="hover:text-[var(--text-color-primary)] transition-colors group-hover:underline" {
+                                                (item.product.name)
+                                            }
+                                        }
+                                        p ."ml-4 whitespace-nowrap" { (product_price_maud(&item.product)) }
+                                    }
+                                }
+                                div ."flex flex-1 items-end justify-between text-xs mt-2" {
+                                    div ."flex" {
+                                        button type="button"
+                                            hx-post=(format!("/htmx/cart/remove/{}", item.product.id))
+                                            hx-target="#cart-content-target"
+                                            hx-swap="innerHTML"
+                                            class="text-sm font-medium text-[var(--text-color-primary)] px-3 py-1 rounded-md hover:bg-[var(--color-secondary)] hover:text-[var(--text-color-primary-hover)] focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)] focus:ring-opacity-50 transition-all duration-150 ease-in-out" {
+                                            "Usuń"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+        // === POPRAWIONA SKŁADNIA MAUD DLA OOB SWAP ===
+        // Krok 1: Tworzymy pełny selektor jako zmienną Rust.
+        @let oob_selector = format!("outerHTML:#product-cart-button-{}", product_id_to_remove);
+        // Krok 2: Używamy tej zmiennej w atrybucie hx-swap-oob.
+        div hx-swap-oob=(oob_selector) {
+            (render_add_to_cart_button(product_id_to_remove))
+        }
+    };
+
+    Ok((headers, markup))
+}
+
+/// Koszyk i szkic zamówienia bieżącego klienta. `None`, gdy koszyk nie istnieje lub jest pusty.
+async fn load_checkout_state(
+    conn: &mut sqlx::PgConnection,
+    user_claims_opt: Option<TokenClaims>,
+    guest_cart_id_opt: Option<Uuid>,
+) -> Result<Option<(CartDetailsResponse, CheckoutDraft)>, AppError> {
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+    let Some(cart_details) =
+        cart_utils::get_cart_details(&mut *conn, user_claims_opt, guest_cart_id_opt).await?
+    else {
+        return Ok(None);
+    };
+    if cart_details.items.is_empty() {
+        return Ok(None);
+    }
+    let draft = get_or_create_checkout_draft(&mut *conn, cart_details.cart_id, user_id).await?;
+    Ok(Some((cart_details, draft)))
+}
+
+/// Rabat z kodu zapisanego w szkicu, przeliczony od aktualnej wartości koszyka.
+/// Kod, który przestał działać (wygasł, koszyk poniżej minimum), po prostu nie daje rabatu.
+async fn checkout_discount(
+    conn: &mut sqlx::PgConnection,
+    draft: &CheckoutDraft,
+    items_total: i64,
+    user_id: Option<Uuid>,
+) -> Result<i64, AppError> {
+    let Some(code) = draft.coupon_code.as_deref() else {
+        return Ok(0);
+    };
+    let discount = match find_applicable_coupon(
+        conn,
+        code,
+        items_total,
+        user_id,
+        draft.guest_email.as_deref(),
+    )
+    .await?
+    {
+        Ok(coupon) => coupon.discount_for(items_total),
+        Err(_) => 0,
+    };
+    Ok(discount)
+}
+
+/// Saldo karty podarunkowej zapisanej w szkicu. Karta, której nie można już użyć, daje 0.
+async fn checkout_gift_card_balance(
+    conn: &mut sqlx::PgConnection,
+    draft: &CheckoutDraft,
+) -> Result<i64, AppError> {
+    let Some(code) = draft.gift_card_code.as_deref() else {
+        return Ok(0);
+    };
+    Ok(find_usable_gift_card(conn, code)
+        .await?
+        .map_or(0, |gift_card| gift_card.balance))
+}
+
+fn render_empty_checkout_maud() -> Markup {
+    html! {
+        div ."max-w-4xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16 text-center" {
+            div ."bg-white p-8 rounded-lg shadow-lg border border-gray-200 inline-block" {
+                h2 ."text-2xl font-bold text-gray-800 mb-4" { "Twój koszyk jest pusty" }
+                p ."text-gray-600 mb-6" { "Nie możesz przejść do kasy z pustym koszykiem." }
+                a href="/"
+                   hx-get="/htmx/products?limit=8"
+                   hx-target="#content"
+                   hx-swap="innerHTML"
+                   hx-push-url="/"
+                   class="inline-block bg-pink-600 hover:bg-pink-700 text-white font-medium py-2 px-6 rounded-lg transition-colors duration-200" {
+                    "Wróć do sklepu"
+                }
+            }
+        }
+    }
+}
+
+/// Podsumowanie koszyka w kasie. Przeładowuje się po każdej zmianie kroku, kodu rabatowego
+/// lub karty podarunkowej.
+fn render_checkout_summary_maud(
+    cart_details: &CartDetailsResponse,
+    draft: &CheckoutDraft,
+    discount: i64,
+    gift_card_balance: i64,
+) -> Markup {
+    let items_total = cart_details.total_price;
+    let shipping_option = draft
+        .shipping_method_key
+        .as_deref()
+        .and_then(|key| find_shipping_option(key, items_total));
+    let order_total = (items_total - discount).max(0) + shipping_option.map_or(0, |o| o.cost);
+    let gift_card_amount = gift_card_balance.min(order_total);
+    let grand_total = order_total - gift_card_amount;
+
+    html! {
+        div #checkout-summary
+            hx-get="/htmx/checkout/summary"
+            hx-trigger="checkoutSummaryChanged from:body"
+            hx-swap="outerHTML"
+        {
+            h2 ."text-xl font-semibold text-gray-800 mb-4" { "Twoje zamówienie" }
+
+            div ."border-b border-gray-200 pb-4 mb-4" {
+                ul role="list" class="divide-y divide-gray-200 max-h-60 overflow-y-auto" {
+                    @for item_summary in &cart_details.items {
+                        li class="py-3 flex justify-between items-center" {
+                            div class="flex items-center min-w-0" {
+                                @if let Some(image) = item_summary.product.images.first() {
+                                    img src=(image) alt=(item_summary.product.name)
+                                         class="h-12 w-12 sm:h-16 sm:w-16 flex-shrink-0 rounded-md border border-gray-200 object-cover";
+                                } @else {
+                                    div class="h-12 w-12 sm:h-16 sm:w-16 flex-shrink-0 rounded-md border border-gray-200 bg-gray-100 flex items-center justify-center" {
+                                        span class="text-xs text-gray-500" { "Brak foto" }
+                                    }
+                                }
+                                div class="ml-3 sm:ml-4 min-w-0 flex-1" {
+                                    h3 class="text-sm font-medium text-gray-900 truncate" { (item_summary.product.name) }
+                                    p class="text-xs text-gray-500 mt-1" { (item_summary.product.category.to_string()) }
+                                }
+                            }
+                            p class="text-sm font-medium text-gray-900 ml-2 whitespace-nowrap" {
+                                (product_price_maud(&item_summary.product))
+                            }
+                        }
+                    }
+                }
+            }
+
+            div class="space-y-3" {
+                div class="flex justify-between" {
+                    span class="text-sm text-gray-600" { "Suma częściowa" }
+                    span class="text-sm font-medium text-gray-900" { (format_price_maud(items_total)) }
+                }
+                @if discount > 0 {
+                    div class="flex justify-between" {
+                        span class="text-sm text-gray-600" {
+                            "Rabat "
+                            @if let Some(code) = &draft.coupon_code { span class="font-mono" { (code) } }
+                        }
+                        span class="text-sm font-medium text-green-700" { "-" (format_price_maud(discount)) }
+                    }
+                }
+                div class="flex justify-between" {
+                    span class="text-sm text-gray-600" { "Dostawa" }
+                    span class="text-sm font-medium text-gray-900" id="checkout-shipping-cost" {
+                        @if let Some(option) = shipping_option {
+                            (format_price_maud(option.cost))
+                        } @else {
+                            "Wybierz w kroku „Dostawa”"
+                        }
+                    }
+                }
+                @if items_total < free_shipping_threshold() {
+                    p class="text-xs text-gray-500" {
+                        "Darmowa dostawa od " (format_price_maud(free_shipping_threshold())) "."
+                    }
+                }
+                @if gift_card_amount > 0 {
+                    div class="flex justify-between" {
+                        span class="text-sm text-gray-600" {
+                            "Karta podarunkowa "
+                            @if let Some(code) = &draft.gift_card_code { span class="font-mono" { (code) } }
+                        }
+                        span class="text-sm font-medium text-green-700" { "-" (format_price_maud(gift_card_amount)) }
+                    }
+                }
+                div class="flex justify-between border-t border-gray-200 pt-3" {
+                    span class="text-base font-semibold text-gray-900" { "Do zapłaty" }
+                    span class="text-base font-semibold text-[var(--text-color-primary)]" id="checkout-grand-total" {
+                        (format_price_maud(grand_total))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pole tekstowe kroku kasy z komunikatem błędu pod spodem.
+fn checkout_input_maud(
+    name: &str,
+    label: &str,
+    input_type: &str,
+    value: Option<&str>,
+    errors: &ValidationErrors,
+    required: bool,
+) -> Markup {
+    html! {
+        div {
+            label for=(name) class="block text-sm font-medium text-gray-700 mb-1" {
+                (label) @if required { " *" }
+            }
+            input type=(input_type) id=(name) name=(name) value=[value] required[required]
+                   aria-invalid=[errors.contains(name).then_some("true")]
+                   class=(format!("w-full px-4 py-2 border {} rounded-md shadow-sm focus:ring-pink-500 focus:border-pink-500", errors.border_class(name)));
+            (errors.field_error_maud(name))
+        }
+    }
+}
+
+fn checkout_field_error_maud(errors: &ValidationErrors, name: &str) -> Markup {
+    errors.field_error_maud(name)
+}
+
+/// Pasek kroków kasy. Do zatwierdzonych kroków można wrócić kliknięciem.
+fn render_checkout_steps_nav_maud(current: CheckoutStep, completed_steps: i16) -> Markup {
+    html! {
+        nav aria-label="Kroki zamówienia" class="mb-6" {
+            ol class="flex items-center gap-2 text-xs sm:text-sm" {
+                @for step in CheckoutStep::ALL {
+                    li class="flex items-center gap-2" {
+                        @if step.index() > 0 { span class="text-gray-300" { "→" } }
+                        @if step == current {
+                            span class="font-semibold text-pink-600" aria-current="step" {
+                                (step.index() + 1) ". " (step.label())
+                            }
+                        } @else if step.is_reachable(completed_steps) {
+                            a href="/checkout"
+                              hx-get=(format!("/htmx/checkout/krok/{}", step.slug()))
+                              hx-target="#checkout-step"
+                              hx-swap="outerHTML"
+                              class="text-gray-700 hover:text-pink-600 hover:underline" {
+                                (step.index() + 1) ". " (step.label())
+                            }
+                        } @else {
+                            span class="text-gray-400" { (step.index() + 1) ". " (step.label()) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct CheckoutStepContext<'a> {
+    draft: &'a CheckoutDraft,
+    items_total: i64,
+    discount: i64,
+    is_guest: bool,
+    przelewy24_enabled: bool,
+}
+
+/// Renderuje krok kasy wraz z paskiem kroków. `values` to dane do wypełnienia formularza
+/// (ze szkicu albo z odrzuconego zgłoszenia), `errors` - błędy walidacji pól.
+fn render_checkout_step_maud(
+    step: CheckoutStep,
+    ctx: &CheckoutStepContext,
+    values: &CheckoutStepPayload,
+    errors: &ValidationErrors,
+) -> Markup {
+    let countries = [
+        "Polska",
+        "Niemcy",
+        "Czechy",
+        "Słowacja",
+        "Wielka Brytania",
+        "Francja",
+        "Hiszpania",
+        "Holandia",
+        "Włochy",
+    ];
+    let form_attrs = |step: CheckoutStep| format!("/htmx/checkout/krok/{}", step.slug());
+    let previous = CheckoutStep::ALL
+        .into_iter()
+        .find(|s| s.index() + 1 == step.index());
+
+    let back_button = html! {
+        @if let Some(previous) = previous {
+            button type="button"
+                   hx-get=(format!("/htmx/checkout/krok/{}", previous.slug()))
+                   hx-target="#checkout-step"
+                   hx-swap="outerHTML"
+                   class="w-full sm:w-auto px-6 py-3 border border-gray-300 rounded-md shadow-sm text-base font-medium text-gray-700 bg-white hover:bg-gray-50" {
+                "Wstecz"
+            }
+        }
+    };
+    let next_button = html! {
+        button type="submit"
+               class="w-full sm:w-auto px-6 py-3 border border-transparent rounded-md shadow-sm text-base font-medium text-white bg-[var(--color-primary)] hover:bg-[var(--color-primary-hover)]" {
+            "Dalej"
+        }
+    };
+
+    html! {
+        div #checkout-step {
+            (render_checkout_steps_nav_maud(step, ctx.draft.completed_steps))
+
+            @match step {
+                CheckoutStep::Dane => {
+                    form hx-post=(form_attrs(step)) hx-target="#checkout-step" hx-swap="outerHTML" class="space-y-6" {
+                        fieldset ."bg-white p-6 rounded-lg shadow-sm border border-gray-200 space-y-4" {
+                            legend ."text-lg font-semibold text-gray-800 px-2" { "Dane kontaktowe" }
+                            @if ctx.is_guest {
+                                (checkout_input_maud("guest_email", "Twój adres email", "email", values.guest_email.as_deref(), errors, true))
+                                p ."-mt-2 text-xs text-gray-500" { "Potrzebny do potwierdzenia zamówienia, jeśli kupujesz jako gość." }
+                            }
+                            div ."grid grid-cols-1 sm:grid-cols-2 gap-4" {
+                                (checkout_input_maud("shipping_first_name", "Imię", "text", values.shipping_first_name.as_deref(), errors, true))
+                                (checkout_input_maud("shipping_last_name", "Nazwisko", "text", values.shipping_last_name.as_deref(), errors, true))
+                            }
+                            (checkout_input_maud("shipping_phone", "Telefon", "tel", values.shipping_phone.as_deref(), errors, true))
+                        }
+                        div ."flex flex-col sm:flex-row-reverse justify-between gap-4" { (next_button) }
+                    }
+                }
+                CheckoutStep::Dostawa => {
+                    @let selected_key = values.shipping_method_key.clone().unwrap_or_default();
+                    form hx-post=(form_attrs(step)) hx-target="#checkout-step" hx-swap="outerHTML" class="space-y-6" {
+                        fieldset ."bg-white p-6 rounded-lg shadow-sm border border-gray-200" {
+                            legend ."text-lg font-semibold text-gray-800 px-2" { "Metoda dostawy" }
+                            // Komponent Paczkomatu odczytuje wybraną metodę z tego pola przy inicjalizacji
+                            input type="hidden" id="selected_shipping_method_key_input" value=(selected_key);
+                            div ."space-y-2 mt-4" {
+                                @for option in available_shipping_options(ctx.items_total) {
+                                    div ."flex items-center" {
+                                        input type="radio" id=(format!("{}_shipping_option", option.key))
+                                               name="shipping_method_key" value=(option.key)
+                                               checked[selected_key == option.key]
+                                               "x-on:change"=(format!("$dispatch('shipping-method-changed', '{}')", option.key))
+                                               class="h-4 w-4 text-pink-600 border-gray-300 focus:ring-pink-500";
+                                        label for=(format!("{}_shipping_option", option.key)) class="ml-3 block text-sm text-gray-700 hover:cursor-pointer" {
+                                            (option.name) " - " span ."font-medium" { (format_price_maud(option.cost)) }
+                                        }
+                                    }
+                                }
+                            }
+                            (checkout_field_error_maud(errors, "shipping_method_key"))
+                        }
+
+                        // Wybór Paczkomatu - widoczny tylko przy dostawie InPost
+                        fieldset x-data="inpostLockerPicker()" x-show="visible" x-cloak
+                                 x-init=(format!(
+                                     "selectedCode = {}; selectedLabel = {}",
+                                     serde_json::to_string(values.inpost_locker_code.as_deref().unwrap_or("")).unwrap_or_else(|_| "''".to_string()),
+                                     serde_json::to_string(values.inpost_locker_address.as_deref().unwrap_or("")).unwrap_or_else(|_| "''".to_string()),
+                                 ))
+                                 "@shipping-method-changed.window"="visible = ($event.detail === 'inpost')"
+                                 class="bg-white p-6 rounded-lg shadow-sm border border-gray-200" {
+                            legend ."text-lg font-semibold text-gray-800 px-2" { "Paczkomat InPost *" }
+                            input type="hidden" name="inpost_locker_code" x-model="selectedCode";
+                            input type="hidden" name="inpost_locker_address" x-model="selectedLabel";
+                            // Najbliższe Paczkomaty dla wpisanego kodu pocztowego
+                            div #inpost-suggestions
+                                hx-get="/htmx/checkout/paczkomaty"
+                                hx-trigger="load, input changed delay:600ms from:#shipping_postal_code"
+                                hx-include="#shipping_postal_code"
+                                hx-sync="this:replace" {}
+                            template x-if="selectedCode" {
+                                div ."mt-4 flex items-center justify-between p-3 bg-yellow-50 border border-yellow-300 rounded-md" {
+                                    div {
+                                        p ."font-mono font-semibold text-gray-900" x-text="selectedCode" {}
+                                        p ."text-xs text-gray-600" x-text="selectedLabel" {}
+                                    }
+                                    button type="button" "@click.prevent"="selectedCode = ''"
+                                           class="text-xs text-pink-600 hover:underline" { "Zmień" }
+                                }
+                            }
+                            div x-show="!selectedCode" ."mt-4" {
+                                input type="text" x-model="query" "@input.debounce.400ms"="search()"
+                                       placeholder="Wpisz miasto, kod pocztowy (np. 00-001) lub kod Paczkomatu"
+                                       class="w-full px-4 py-2 border border-gray-300 rounded-md shadow-sm focus:ring-pink-500 focus:border-pink-500";
+                                p x-show="loading" ."mt-2 text-xs text-gray-500" { "Szukam Paczkomatów..." }
+                                p x-show="error" x-text="error" ."mt-2 text-xs text-red-600" {}
+                                ul ."mt-2 divide-y divide-gray-100 max-h-64 overflow-y-auto" {
+                                    template x-for="point in points" x-bind:key="point.name" {
+                                        li {
+                                            button type="button" "@click.prevent"="select(point)"
+                                                   class="w-full text-left px-3 py-2 hover:bg-pink-50 rounded" {
+                                                span ."font-mono font-semibold text-sm text-gray-900" x-text="point.name" {}
+                                                span ."block text-xs text-gray-600" x-text="point.address + ', ' + point.postal_code + ' ' + point.city" {}
+                                                span ."block text-xs text-gray-400" x-show="point.description" x-text="point.description" {}
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            (checkout_field_error_maud(errors, "inpost_locker_code"))
+                        }
+
+                        fieldset ."bg-white p-6 rounded-lg shadow-sm border border-gray-200 space-y-4" {
+                            legend ."text-lg font-semibold text-gray-800 px-2" { "Adres dostawy" }
+                            (checkout_input_maud("shipping_address_line1", "Adres (ulica i numer)", "text", values.shipping_address_line1.as_deref(), errors, true))
+                            (checkout_input_maud("shipping_address_line2", "Adres cd. (opcjonalnie)", "text", values.shipping_address_line2.as_deref(), errors, false))
+                            div ."grid grid-cols-1 sm:grid-cols-3 gap-4" {
+                                (checkout_input_maud("shipping_city", "Miasto", "text", values.shipping_city.as_deref(), errors, true))
+                                (checkout_input_maud("shipping_postal_code", "Kod pocztowy", "text", values.shipping_postal_code.as_deref(), errors, true))
+                                div {
+                                    label for="shipping_country" class="block text-sm font-medium text-gray-700 mb-1" { "Kraj *" }
+                                    select id="shipping_country" name="shipping_country" required
+                                            class="w-full px-4 py-2 border border-gray-300 bg-white rounded-md shadow-sm focus:ring-pink-500 focus:border-pink-500" {
+                                        option value="" disabled selected[values.shipping_country.is_none()] { "Wybierz kraj..." }
+                                        @for country in &countries {
+                                            option value=(country) selected[values.shipping_country.as_deref() == Some(*country)] { (country) }
+                                        }
+                                        @if let Some(saved_country) = values.shipping_country.as_deref() {
+                                            @if !countries.contains(&saved_country) {
+                                                option value=(saved_country) selected { (saved_country) " (inny)" }
+                                            }
+                                        }
+                                    }
+                                    (checkout_field_error_maud(errors, "shipping_country"))
+                                }
+                            }
+                        }
+                        div ."flex flex-col sm:flex-row-reverse justify-between gap-4" { (next_button) (back_button) }
+                    }
+                }
+                CheckoutStep::Platnosc => {
+                    @let selected = values.payment_method.clone().unwrap_or_else(|| "blik".to_string());
+                    form hx-post=(form_attrs(step)) hx-target="#checkout-step" hx-swap="outerHTML" class="space-y-6" {
+                        fieldset ."bg-white p-6 rounded-lg shadow-sm border border-gray-200" {
+                            legend ."text-lg font-semibold text-gray-800 px-2" { "Metoda płatności" }
+                            div ."space-y-4 mt-4" {
+                                @for (value, label, hint) in payment_method_options(ctx.przelewy24_enabled) {
+                                    div ."flex items-center" {
+                                        input type="radio" id=(format!("payment_{}", value)) name="payment_method" value=(value)
+                                               checked[selected == value]
+                                               class="h-4 w-4 text-pink-600 focus:ring-pink-500 border-gray-300";
+                                        label for=(format!("payment_{}", value)) class="ml-3 block text-sm font-medium text-gray-700" {
+                                            (label)
+                                            @if let Some(hint) = hint { span class="text-xs text-gray-500 ml-1" { (hint) } }
+                                        }
+                                    }
+                                }
+                            }
+                            (checkout_field_error_maud(errors, "payment_method"))
+                        }
+                        div ."flex flex-col sm:flex-row-reverse justify-between gap-4" { (next_button) (back_button) }
+                    }
+                }
+                CheckoutStep::Podsumowanie => {
+                    @let draft = ctx.draft;
+                    @let shipping_option = draft.shipping_method_key.as_deref().and_then(|key| find_shipping_option(key, ctx.items_total));
+                    @let payment_label = payment_method_options(ctx.przelewy24_enabled)
+                        .into_iter()
+                        .find(|(value, _, _)| Some(*value) == draft.payment_method.as_deref())
+                        .map(|(_, label, _)| label);
+                    div ."space-y-4" {
+                        @for (section_step, title) in [(CheckoutStep::Dane, "Dane kontaktowe"), (CheckoutStep::Dostawa, "Dostawa"), (CheckoutStep::Platnosc, "Płatność")] {
+                            div ."bg-white p-6 rounded-lg shadow-sm border border-gray-200" {
+                                div ."flex justify-between items-center mb-2" {
+                                    h3 ."text-lg font-semibold text-gray-800" { (title) }
+                                    button type="button"
+                                           hx-get=(format!("/htmx/checkout/krok/{}", section_step.slug()))
+                                           hx-target="#checkout-step" hx-swap="outerHTML"
+                                           class="text-sm text-pink-600 hover:underline" { "Zmień" }
+                                }
+                                div ."text-sm text-gray-700 space-y-1" {
+                                    @match section_step {
+                                        CheckoutStep::Dane => {
+                                            p { (draft.shipping_first_name.as_deref().unwrap_or("")) " " (draft.shipping_last_name.as_deref().unwrap_or("")) }
+                                            p { (draft.shipping_phone.as_deref().unwrap_or("")) }
+                                            @if let Some(email) = &draft.guest_email { p { (email) } }
+                                        }
+                                        CheckoutStep::Dostawa => {
+                                            p ."font-medium" { (shipping_option.map_or("Nie wybrano metody dostawy", |o| o.name)) }
+                                            @if let Some(locker) = &draft.inpost_locker_code {
+                                                p { "Paczkomat: " span ."font-mono" { (locker) } }
+                                                @if let Some(address) = &draft.inpost_locker_address { p ."text-xs text-gray-500" { (address) } }
+                                            }
+                                            p { (draft.shipping_address_line1.as_deref().unwrap_or("")) }
+                                            @if let Some(line2) = &draft.shipping_address_line2 { p { (line2) } }
+                                            p { (draft.shipping_postal_code.as_deref().unwrap_or("")) " " (draft.shipping_city.as_deref().unwrap_or("")) ", " (draft.shipping_country.as_deref().unwrap_or("")) }
+                                        }
+                                        _ => {
+                                            p { (payment_label.unwrap_or("Nie wybrano metody płatności")) }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
+                        // Zamówienie składa istniejący endpoint - dane pochodzą ze szkicu
+                        form #checkout-form
+                             hx-post="/api/orders"
+                             hx-target="#content"
+                             hx-swap="innerHTML"
+                             hx-push-url="true" {
+                            div #checkout-messages {}
+                            @if let Some(email) = &draft.guest_email { input type="hidden" name="guest_checkout_email" value=(email); }
+                            input type="hidden" name="shipping_first_name" value=[draft.shipping_first_name.as_deref()];
+                            input type="hidden" name="shipping_last_name" value=[draft.shipping_last_name.as_deref()];
+                            input type="hidden" name="shipping_phone" value=[draft.shipping_phone.as_deref()];
+                            input type="hidden" name="shipping_address_line1" value=[draft.shipping_address_line1.as_deref()];
+                            input type="hidden" name="shipping_address_line2" value=[draft.shipping_address_line2.as_deref()];
+                            input type="hidden" name="shipping_city" value=[draft.shipping_city.as_deref()];
+                            input type="hidden" name="shipping_postal_code" value=[draft.shipping_postal_code.as_deref()];
+                            input type="hidden" name="shipping_country" value=[draft.shipping_country.as_deref()];
+                            input type="hidden" name="shipping_method_key" value=[draft.shipping_method_key.as_deref()];
+                            input type="hidden" name="inpost_locker_code" value=[draft.inpost_locker_code.as_deref()];
+                            input type="hidden" name="inpost_locker_address" value=[draft.inpost_locker_address.as_deref()];
+                            input type="hidden" name="payment_method" value=[draft.payment_method.as_deref()];
+                            @if ctx.discount > 0 {
+                                input type="hidden" name="coupon_code" value=[draft.coupon_code.as_deref()];
+                            }
+                            input type="hidden" name="gift_card_code" value=[draft.gift_card_code.as_deref()];
+
+                            p class="text-xs text-gray-500 mb-4" {
+                                "Klikając „Złóż zamówienie i zapłać”, akceptujesz "
+                                a href="/regulamin" hx-get="/htmx/page/regulamin" hx-target="#content" hx-swap="innerHTML" hx-push-url="/regulamin"
+                                   class="text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline" { "Regulamin sklepu" }
+                                " oraz "
+                                a href="/polityka-prywatnosci" hx-get="/htmx/page/polityka-prywatnosci" hx-target="#content" hx-swap="innerHTML" hx-push-url="/polityka-prywatnosci"
+                                   class="text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline" { "Politykę prywatności" }
+                                "."
+                            }
+                            div ."flex flex-col sm:flex-row-reverse justify-between gap-4" {
+                                button type="submit"
+                                       class="w-full sm:w-auto px-6 py-3 border border-transparent rounded-md shadow-sm text-base font-medium text-white bg-[var(--color-primary)] hover:bg-[var(--color-primary-hover)] focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-[var(--color-primary)] transition-all duration-200 transform hover:scale-105" {
+                                    "Złóż zamówienie i zapłać"
+                                }
+                                (back_button)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Strona kasy: pasek kroków, bieżący krok (pierwszy niezatwierdzony - klient, który przerwał
+/// zakupy, wraca tam, gdzie skończył) i podsumowanie koszyka.
+pub async fn checkout_page_handler(
+    request_headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+    UnverifiedEmail(unverified_email): UnverifiedEmail,
+) -> Result<(HeaderMap, Response), AppError> {
+    tracing::info!("MAUD: /htmx/checkout - żądanie strony kasy");
+
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+    let is_guest = user_claims_opt.is_none();
+    let mut conn = app_state.db_pool.acquire().await?;
+    let state = load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+
+    let (cart_count, cart_total) = state.as_ref().map_or((0, 0), |(details, _)| {
+        (details.total_items, details.total_price)
+    });
+    let mut response_headers = HeaderMap::new();
+    let trigger_payload = serde_json::json!({
+        "updateCartCount": {
+            "newCount": cart_count,
+            "newCartTotalPrice": cart_total,
+            "newGuestCartId": if is_guest { guest_cart_id_opt } else { None }
+        }
+    });
+    if let Ok(trigger_value) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        response_headers.insert("HX-Trigger", trigger_value);
+    }
+
+    let page_content = match &state {
+        None => render_empty_checkout_maud(),
+        Some((cart_details, draft)) => {
+            extend_cart_reservations(&mut conn, cart_details.cart_id, draft.id).await?;
+            record_event(
+                &app_state.db_pool,
+                EventType::CheckoutStarted,
+                NewEvent {
+                    user_id,
+                    guest_session_id: if is_guest { guest_cart_id_opt } else { None },
+                    metadata: Some(serde_json::json!({
+                        "items": cart_details.total_items,
+                        "cart_value": cart_details.total_price
+                    })),
+                    ..Default::default()
+                },
+            );
+
+            let discount =
+                checkout_discount(&mut conn, draft, cart_details.total_price, user_id).await?;
+            let gift_card_balance = checkout_gift_card_balance(&mut conn, draft).await?;
+            let ctx = CheckoutStepContext {
+                draft,
+                items_total: cart_details.total_price,
+                discount,
+                is_guest,
+                przelewy24_enabled: app_state.przelewy24_config.is_some(),
+            };
+            let step = CheckoutStep::first_incomplete(draft.completed_steps);
+
+            html! {
+                div ."max-w-7xl mx-auto px-4 sm:px-6 lg:px-8 py-8 sm:py-12" {
+                    div ."flex flex-col lg:flex-row gap-8" {
+                        // Podsumowanie (na mobilnych nad formularzem)
+                        div ."lg:w-1/3 lg:order-2" {
+                            div ."bg-white p-6 rounded-lg shadow-md border border-gray-200 sticky top-20 md:top-40" {
+                                (render_checkout_summary_maud(cart_details, draft, discount, gift_card_balance))
+
+                                // Kod rabatowy - rabat liczy serwer i zapisuje kod w szkicu zamówienia
+                                div class="mt-4 pt-4 border-t border-gray-200" {
+                                    label for="coupon_code_input" class="block text-sm font-medium text-gray-900 mb-2" { "Kod rabatowy:" }
+                                    div class="flex gap-2" {
+                                        input type="text" id="coupon_code_input" name="coupon_code"
+                                               placeholder="Wpisz kod"
+                                               value=[draft.coupon_code.as_deref()]
+                                               class="flex-1 min-w-0 px-3 py-2 border border-gray-300 rounded-md shadow-sm text-sm uppercase focus:ring-pink-500 focus:border-pink-500";
+                                        button type="button"
+                                               hx-post="/htmx/checkout/coupon"
+                                               hx-include="#coupon_code_input"
+                                               hx-target="#coupon-feedback"
+                                               hx-swap="innerHTML"
+                                               class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" { "Zastosuj" }
+                                    }
+                                    div #coupon-feedback ."mt-2" {}
+                                }
+
+                                // Karta podarunkowa - pokrywa część albo całość kwoty do zapłaty
+                                div class="mt-4 pt-4 border-t border-gray-200" {
+                                    label for="gift_card_code_input" class="block text-sm font-medium text-gray-900 mb-2" { "Karta podarunkowa:" }
+                                    div class="flex gap-2" {
+                                        input type="text" id="gift_card_code_input" name="gift_card_code"
+                                               placeholder="np. PREZENT-XXXXX-XXXXX"
+                                               value=[draft.gift_card_code.as_deref()]
+                                               class="flex-1 min-w-0 px-3 py-2 border border-gray-300 rounded-md shadow-sm text-sm uppercase focus:ring-pink-500 focus:border-pink-500";
+                                        button type="button"
+                                               hx-post="/htmx/checkout/karta-podarunkowa"
+                                               hx-include="#gift_card_code_input"
+                                               hx-target="#gift-card-feedback"
+                                               hx-swap="innerHTML"
+                                               class="px-4 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md" { "Zastosuj" }
+                                    }
+                                    div #gift-card-feedback ."mt-2" {}
+                                }
+                            }
+                        }
+
+                        div ."lg:w-2/3 lg:order-1" {
+                            h1 ."text-2xl sm:text-3xl font-bold text-gray-900 mb-4" { "Składanie zamówienia" }
+                            @if let Some(email) = &unverified_email {
+                                (render_email_verification_banner_maud(email))
+                            }
+                            (render_checkout_step_maud(step, &ctx, &payload_from_draft(draft), &ValidationErrors::new()))
+                            div ."mt-8" {
+                                a href="/" hx-get="/htmx/products?limit=8" hx-target="#content" hx-swap="innerHTML" hx-push-url="/"
+                                   class="text-sm text-gray-600 hover:text-pink-600 hover:underline" {
+                                    "← Wróć do sklepu"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Składanie zamówienia - sklep mess - all that vintage";
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    let app_response = build_response(request_headers, &csp_nonce, page_builder).await?;
+    Ok((response_headers, app_response))
+}
+
+/// Wyświetla krok kasy (powrót do wcześniejszego kroku albo przejście z podsumowania).
+/// Kroku, do którego klient jeszcze nie doszedł, nie da się otworzyć - dostaje pierwszy niezatwierdzony.
+pub async fn checkout_step_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(step_slug): Path<String>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Markup, AppError> {
+    let requested_step = CheckoutStep::from_slug(&step_slug).ok_or(AppError::NotFound)?;
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+    let is_guest = user_claims_opt.is_none();
+
+    let mut conn = app_state.db_pool.acquire().await?;
+    let Some((cart_details, draft)) =
+        load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?
+    else {
+        return Ok(html! { div #checkout-step { (render_empty_checkout_maud()) } });
+    };
+
+    let step = if requested_step.is_reachable(draft.completed_steps) {
+        requested_step
+    } else {
+        CheckoutStep::first_incomplete(draft.completed_steps)
+    };
+    let discount = checkout_discount(&mut conn, &draft, cart_details.total_price, user_id).await?;
+    let ctx = CheckoutStepContext {
+        draft: &draft,
+        items_total: cart_details.total_price,
+        discount,
+        is_guest,
+        przelewy24_enabled: app_state.przelewy24_config.is_some(),
+    };
+
+    Ok(render_checkout_step_maud(
+        step,
+        &ctx,
+        &payload_from_draft(&draft),
+        &ValidationErrors::new(),
+    ))
+}
+
+/// Zatwierdza krok kasy: waliduje pola, zapisuje je w szkicu i zwraca kolejny krok.
+/// Przy błędach zwraca ten sam krok z komunikatami przy polach i wpisanymi wartościami.
+pub async fn save_checkout_step_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(step_slug): Path<String>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+    Form(payload): Form<CheckoutStepPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let step = CheckoutStep::from_slug(&step_slug).ok_or(AppError::NotFound)?;
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+    let is_guest = user_claims_opt.is_none();
+    let przelewy24_enabled = app_state.przelewy24_config.is_some();
+
+    let mut conn = app_state.db_pool.acquire().await?;
+    let Some((cart_details, draft)) =
+        load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?
+    else {
+        return Ok((
+            HeaderMap::new(),
+            html! { div #checkout-step { (render_empty_checkout_maud()) } },
+        ));
+    };
+    let items_total = cart_details.total_price;
+
+    if !step.is_reachable(draft.completed_steps) || step == CheckoutStep::Podsumowanie {
+        let discount = checkout_discount(&mut conn, &draft, items_total, user_id).await?;
+        let fallback = CheckoutStep::first_incomplete(draft.completed_steps);
+        let ctx = CheckoutStepContext {
+            draft: &draft,
+            items_total,
+            discount,
+            is_guest,
+            przelewy24_enabled,
+        };
+        return Ok((
+            HeaderMap::new(),
+            render_checkout_step_maud(
+                fallback,
+                &ctx,
+                &payload_from_draft(&draft),
+                &ValidationErrors::new(),
+            ),
+        ));
+    }
+
+    let mut errors = validate_step(step, &payload, is_guest, items_total, przelewy24_enabled);
+
+    // E-mail gościa sprawdzamy od razu, a nie dopiero przy składaniu zamówienia
+    if step == CheckoutStep::Dane && is_guest && !errors.contains("guest_email") {
+        if let Some(email) = payload.guest_email.as_deref().map(str::trim) {
+            if app_state.disposable_email_blocklist.is_disposable(email) {
+                errors.insert(
+                    "guest_email",
+                    "Nie przyjmujemy adresów z tymczasowych skrzynek e-mail. Podaj swój stały adres."
+                        .to_string(),
+                );
+            } else {
+                if repo::users::email_registered(&mut *conn, email).await? {
+                    errors.insert(
+                        "guest_email",
+                        "Ten adres e-mail jest już zarejestrowany. Zaloguj się, aby kontynuować."
+                            .to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        let discount = checkout_discount(&mut conn, &draft, items_total, user_id).await?;
+        let ctx = CheckoutStepContext {
+            draft: &draft,
+            items_total,
+            discount,
+            is_guest,
+            przelewy24_enabled,
+        };
+        return Ok((
+            HeaderMap::new(),
+            render_checkout_step_maud(step, &ctx, &payload, &errors),
+        ));
+    }
+
+    let draft = save_checkout_step(&mut conn, draft.id, step, &payload).await?;
+    // Każdy zatwierdzony krok odnawia rezerwacje - klient wypełniający kasę nie traci produktów
+    extend_cart_reservations(&mut conn, cart_details.cart_id, draft.id).await?;
+    let discount = checkout_discount(&mut conn, &draft, items_total, user_id).await?;
+    let ctx = CheckoutStepContext {
+        draft: &draft,
+        items_total,
+        discount,
+        is_guest,
+        przelewy24_enabled,
+    };
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "HX-Trigger",
+        HeaderValue::from_static("checkoutSummaryChanged"),
+    );
+    Ok((
+        headers,
+        render_checkout_step_maud(
+            step.next(),
+            &ctx,
+            &payload_from_draft(&draft),
+            &ValidationErrors::new(),
+        ),
+    ))
+}
+
+/// Błąd walidacji przy składaniu zamówienia: krok kasy z komunikatami przy polach i wartościami
+/// wpisanymi przez klienta. Odpowiedź 422 podmienia tylko `#checkout-step` - reszta strony zostaje.
+pub async fn checkout_step_errors(
+    app_state: &AppState,
+    user_claims_opt: Option<TokenClaims>,
+    guest_cart_id_opt: Option<Uuid>,
+    step: CheckoutStep,
+    values: &CheckoutStepPayload,
+    errors: &ValidationErrors,
+) -> AppError {
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+    let is_guest = user_claims_opt.is_none();
+    let render = async {
+        let mut conn = app_state.db_pool.acquire().await?;
+        let Some((cart_details, draft)) =
+            load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?
+        else {
+            return Err(AppError::UnprocessableEntity(
+                "Twój koszyk jest pusty.".to_string(),
+            ));
+        };
+        let discount =
+            checkout_discount(&mut conn, &draft, cart_details.total_price, user_id).await?;
+        let ctx = CheckoutStepContext {
+            draft: &draft,
+            items_total: cart_details.total_price,
+            discount,
+            is_guest,
+            przelewy24_enabled: app_state.przelewy24_config.is_some(),
+        };
+        Ok(render_checkout_step_maud(step, &ctx, values, errors))
+    };
+
+    match render.await {
+        Ok(markup) => errors.clone().into_fragment(markup, "#checkout-step"),
+        Err(e) => e,
+    }
+}
+
+/// Podsumowanie koszyka w kasie (przeładowywane zdarzeniem `checkoutSummaryChanged`).
+pub async fn checkout_summary_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Markup, AppError> {
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+    let mut conn = app_state.db_pool.acquire().await?;
+    let Some((cart_details, draft)) =
+        load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?
+    else {
+        return Ok(html! { div #checkout-summary { p ."text-gray-500" { "Koszyk jest pusty." } } });
+    };
+    let discount = checkout_discount(&mut conn, &draft, cart_details.total_price, user_id).await?;
+    let gift_card_balance = checkout_gift_card_balance(&mut conn, &draft).await?;
+    Ok(render_checkout_summary_maud(
+        &cart_details,
+        &draft,
+        discount,
+        gift_card_balance,
+    ))
+}
+
+/// Liczba Paczkomatów podpowiadanych w kroku dostawy
+const INPOST_SUGGESTIONS_LIMIT: usize = 5;
+
+/// Podpowiedzi najbliższych Paczkomatów dla kodu pocztowego wpisanego w kroku dostawy.
+/// Radio ustawia `selectedCode`/`selectedLabel` komponentu `inpostLockerPicker()`,
+/// więc wybór trafia do tych samych ukrytych pól co wyszukiwarka.
+pub async fn inpost_suggestions_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Query(query): Query<InpostSuggestionsQuery>,
+) -> Result<Markup, AppError> {
+    let Some(postal_code) = query
+        .shipping_postal_code
+        .as_deref()
+        .and_then(normalize_postal_code)
+    else {
+        return Ok(html! {});
+    };
+
+    let points = match nearest_points(
+        &app_state.inpost_config,
+        &postal_code,
+        INPOST_SUGGESTIONS_LIMIT,
+    )
+    .await
+    {
+        Ok(points) => points,
+        Err(e) => {
+            tracing::warn!(
+                "Nie udało się pobrać Paczkomatów dla kodu {}: {:?}",
+                postal_code,
+                e
+            );
+            return Ok(html! {
+                p ."mt-4 text-xs text-gray-500" { "Nie udało się wczytać Paczkomatów w pobliżu. Skorzystaj z wyszukiwarki poniżej." }
+            });
+        }
+    };
+
+    Ok(html! {
+        @if points.is_empty() {
+            p ."mt-4 text-xs text-gray-500" { "Brak Paczkomatów w pobliżu kodu " (postal_code) "." }
+        } @else {
+            div ."mt-4" {
+                p ."text-sm font-medium text-gray-700 mb-2" { "Najbliższe Paczkomaty dla kodu " (postal_code) }
+                ul ."space-y-2" {
+                    @for point in &points {
+                        @let input_id = format!("inpost_suggestion_{}", point.name);
+                        @let label_json = serde_json::to_string(&point.full_address()).unwrap_or_else(|_| "''".to_string());
+                        li {
+                            label for=(input_id) class="flex items-start gap-3 p-3 border border-gray-200 rounded-md hover:bg-pink-50 hover:cursor-pointer" {
+                                input type="radio" id=(input_id) value=(point.name)
+                                       x-model="selectedCode"
+                                       "x-on:change"=(format!("selectedLabel = {}", label_json))
+                                       class="mt-1 h-4 w-4 text-pink-600 border-gray-300 focus:ring-pink-500";
+                                span {
+                                    span ."font-mono font-semibold text-sm text-gray-900" { (point.name) }
+                                    @if let Some(distance) = point.distance {
+                                        span ."ml-2 text-xs text-gray-500" {
+                                            @if distance < 1000 { (distance) " m" } @else { (format!("{:.1} km", distance as f64 / 1000.0)) }
+                                        }
+                                    }
+                                    span ."block text-xs text-gray-600" { (point.full_address()) }
+                                    @if let Some(description) = &point.description {
+                                        span ."block text-xs text-gray-400" { (description) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Sprawdza kod rabatowy wpisany w podsumowaniu zamówienia i przelicza rabat od aktualnej
+/// zawartości koszyka. Kod jest zapisywany w szkicu zamówienia, podsumowanie przeładowuje się
+/// zdarzeniem `checkoutSummaryChanged`, a przy składaniu zamówienia kod jest weryfikowany ponownie.
+pub async fn apply_coupon_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+    Form(payload): Form<ApplyCouponPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let code = Coupon::normalize_code(&payload.coupon_code);
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+
+    let mut conn = app_state.db_pool.acquire().await?;
+    let checkout_state = load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+    let items_total = checkout_state
+        .as_ref()
+        .map_or(0, |(details, _)| details.total_price);
+
+    let result = match &checkout_state {
+        _ if code.is_empty() => None,
+        None => Some(Err("Twój koszyk jest pusty.".to_string())),
+        // E-mail gościa jest znany dopiero po kroku "Dane" - wcześniej jednorazowość sprawdzamy przy składaniu zamówienia
+        Some((_, draft)) => Some(
+            find_applicable_coupon(
+                &mut conn,
+                &code,
+                items_total,
+                user_id,
+                draft.guest_email.as_deref(),
+            )
+            .await?
+            .map_err(|rejection| rejection.message()),
+        ),
+    };
+
+    let discount = match &result {
+        Some(Ok(coupon)) => coupon.discount_for(items_total),
+        _ => 0,
+    };
+
+    // Kod trafia do szkicu zamówienia - podsumowanie i ostatni krok kasy biorą go stamtąd
+    let mut headers = HeaderMap::new();
+    if let Some((_, draft)) = &checkout_state {
+        let applied_code = match &result {
+            Some(Ok(coupon)) => Some(coupon.code.clone()),
+            _ => None,
+        };
+        sqlx::query(
+            "UPDATE checkout_drafts SET coupon_code = $1, updated_at = NOW() WHERE id = $2",
+        )
+        .bind(applied_code)
+        .bind(draft.id)
+        .execute(&mut *conn)
+        .await?;
+        headers.insert(
+            "HX-Trigger",
+            HeaderValue::from_static("checkoutSummaryChanged"),
+        );
+    }
+
+    let feedback = html! {
+        @match &result {
+            Some(Ok(coupon)) => {
+                p ."text-xs text-green-700" {
+                    "Kod " span ."font-mono font-semibold" { (coupon.code) }
+                    " zastosowany - rabat " (format_price_maud(discount)) "."
+                }
+            }
+            Some(Err(message)) => {
+                p ."text-xs text-red-600" { (message) }
+            }
+            None => {}
+        }
+    };
+
+    Ok((headers, feedback))
+}
+
+/// Sprawdza kartę podarunkową wpisaną w podsumowaniu zamówienia i zapisuje ją w szkicu.
+/// Kwotę do pokrycia liczy podsumowanie (saldo karty, najwyżej cała kwota zamówienia),
+/// a przy składaniu zamówienia karta jest sprawdzana ponownie.
+pub async fn apply_gift_card_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+    Form(payload): Form<ApplyGiftCardPayload>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    let code = GiftCard::normalize_code(&payload.gift_card_code);
+
+    let mut conn = app_state.db_pool.acquire().await?;
+    let checkout_state = load_checkout_state(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+
+    let result = match &checkout_state {
+        _ if code.is_empty() => None,
+        None => Some(Err("Twój koszyk jest pusty.")),
+        Some(_) => Some(
+            find_usable_gift_card(&mut conn, &code)
+                .await?
+                .map_err(|rejection| rejection.message()),
+        ),
+    };
+
+    let mut headers = HeaderMap::new();
+    if let Some((_, draft)) = &checkout_state {
+        let applied_code = match &result {
+            Some(Ok(gift_card)) => Some(gift_card.code.clone()),
+            _ => None,
+        };
+        sqlx::query(
+            "UPDATE checkout_drafts SET gift_card_code = $1, updated_at = NOW() WHERE id = $2",
+        )
+        .bind(applied_code)
+        .bind(draft.id)
+        .execute(&mut *conn)
+        .await?;
+        headers.insert(
+            "HX-Trigger",
+            HeaderValue::from_static("checkoutSummaryChanged"),
+        );
+    }
+
+    let feedback = html! {
+        @match &result {
+            Some(Ok(gift_card)) => {
+                p ."text-xs text-green-700" {
+                    "Karta " span ."font-mono font-semibold" { (gift_card.code) }
+                    " zastosowana - dostępne saldo " (format_price_maud(gift_card.balance)) "."
+                }
+            }
+            Some(Err(message)) => {
+                p ."text-xs text-red-600" { (message) }
+            }
+            None => {}
+        }
+    };
+
+    Ok((headers, feedback))
+}
+
+pub async fn payment_finalization_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    tracing::info!(
+        "MAUD: Wyświetlanie strony podsumowania płatności dla zamówienia ID {}",
+        order_id
+    );
+
+    // Pobierz szczegóły zamówienia, aby je wyświetlić.
+    // Użyjemy logiki podobnej do get_order_details_handler, ale bez sprawdzania uprawnień,
+    // ponieważ dostęp do tej strony jest "publiczny" dla osoby, która zna link.
+    // W bardziej zaawansowanym systemie można by użyć podpisanego tokenu w URL.
+    let order = repo::orders::find_by_id(&app_state.db_pool, order_id)
+        .await?
+        .ok_or(AppError::NotFound)?; // Jeśli zamówienie nie istnieje, zwróć 404
+
+    let order_items_db = repo::orders::items(&app_state.db_pool, order_id).await?;
+
+    let mut items_details: Vec<OrderItemDetailsPublic> = Vec::new();
+    if !order_items_db.is_empty() {
+        let product_ids: Vec<Uuid> = order_items_db.iter().map(|item| item.product_id).collect();
+        let products = repo::products::find_by_ids(&app_state.db_pool, &product_ids).await?;
+        let products_map: HashMap<Uuid, Product> =
+            products.into_iter().map(|p| (p.id, p)).collect();
+
+        for item_db in order_items_db {
+            if let Some(product) = products_map.get(&item_db.product_id) {
+                items_details.push(OrderItemDetailsPublic {
+                    order_item_id: item_db.id,
+                    product: product.clone(),
+                    price_at_purchase: item_db.price_at_purchase,
+                });
+            }
+        }
+    }
+
+    let payment_details = &app_state.payment_details;
+    let page_content = html! {
+        div class="max-w-4xl mx-auto px-4 sm:px-6 lg:px-8 py-12" {
+            div class="bg-white p-6 sm:p-8 rounded-xl shadow-2xl border border-gray-200" {
+                // Nagłówek
+                div class="text-center border-b-2 border-pink-100 pb-6 mb-6" {
+                    div class="w-16 h-16 bg-green-100 rounded-full flex items-center justify-center mx-auto mb-4" {
+                        // Ikona "check"
+                        svg class="w-10 h-10 text-green-600" fill="none" stroke="currentColor" viewBox="0 0 24 24" {
+                            path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M5 13l4 4L19 7";
+                        }
+                    }
+                    h1 class="text-3xl sm:text-4xl font-bold text-gray-900" { "Dziękujemy za zamówienie!" }
+                    p class="mt-2 text-md text-gray-600" {
+                        "Twoje zamówienie nr " strong { (order.order_number) } " zostało przyjęte do realizacji."
+                    }
+                    (render_confirmation_resend_maud(order.id, None))
+                }
+
+                @if order.total_price > 0 {
+                    (render_payment_instructions_maud(&order, payment_details))
+                }
+
+                // Sekcja: Podsumowanie Zamówienia
+                div {
+                    h3 class="text-lg font-semibold text-gray-700 mb-3" { "Podsumowanie zamówienia" }
+                    ul class="divide-y divide-gray-200 border rounded-lg" {
+                        @for item in &items_details {
+                            li class="p-3 flex items-center space-x-4" {
+                                @if let Some(img) = item.product.images.get(0) {
+                                    img src=(img) class="w-16 h-16 rounded-md object-cover border";
+                                }
+                                div class="flex-grow" {
+                                    p class="text-sm font-medium text-gray-800" { (item.product.name) }
+                                    p class="text-xs text-gray-500" { "Cena: " (format_price_maud(item.price_at_purchase)) }
+                                }
+                                p class="text-sm font-semibold text-gray-900" { (format_price_maud(item.price_at_purchase)) }
+                            }
+                        }
+                    }
+                    // Sumy
+                    div class="mt-4 space-y-2 text-sm text-right" {
+                        @if let Some(shipping_name) = &order.shipping_method_name {
+                             @let shipping_cost = order.total_price + order.discount_amount + order.gift_card_amount - items_details.iter().map(|i| i.price_at_purchase).sum::<i64>();
+                             p { "Produkty: " span class="font-medium w-24 inline-block" { (format_price_maud(items_details.iter().map(|i| i.price_at_purchase).sum())) } }
+                             @if order.discount_amount > 0 {
+                                 p { "Rabat: " span class="font-medium text-green-700 w-24 inline-block" { "-" (format_price_maud(order.discount_amount)) } }
+                             }
+                             p { "Dostawa (" (shipping_name) "): " span class="font-medium w-24 inline-block" { (format_price_maud(shipping_cost)) } }
+                        }
+                        @if order.gift_card_amount > 0 {
+                            p { "Karta podarunkowa: " span class="font-medium text-green-700 w-24 inline-block" { "-" (format_price_maud(order.gift_card_amount)) } }
+                        }
+                         p class="text-lg border-t pt-2 mt-2" { "Suma: " span class="font-bold text-pink-600 w-24 inline-block" { (format_price_maud(order.total_price)) } }
+                    }
+                }
+
+                // Sekcja: Adres dostawy
+                div class="mt-6 pt-6 border-t" {
+                     h3 class="text-lg font-semibold text-gray-700 mb-2" { "Dane do wysyłki" }
+                     address class="not-italic text-sm text-gray-600 leading-relaxed" {
+                        (order.shipping_first_name) " " (order.shipping_last_name) br;
+                        (order.shipping_address_line1) br;
+                        @if let Some(line2) = &order.shipping_address_line2 { (line2) br; }
+                        (order.shipping_postal_code) " " (order.shipping_city) br;
+                        (order.shipping_country) br;
+                        "tel: " (order.shipping_phone)
+                     }
+                }
+
+                // Stopka z linkiem do strony głównej
+                div class="text-center mt-8 pt-6 border-t" {
+                    a href="/"
+                       hx-get="/htmx/products?limit=8"
+                       hx-target="#content"
+                       hx-swap="innerHTML"
+                       hx-push-url="/"
+                       class="inline-block bg-pink-600 hover:bg-pink-700 text-white font-medium py-3 px-8 rounded-lg shadow-md hover:shadow-lg transition-all duration-200" {
+                        "Wróć do sklepu"
+                    }
+
+                }
+            }
+        }
+    };
+
+    let title = format!(
+        "Finalizacja płatności zamówienia: {} sklep mess - all that vintage",
+        order_id
+    );
+    let page_builder = PageBuilder::new(&title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Instrukcje płatności na stronie podziękowania: dane do przelewu/BLIK z tytułem płatności
+/// albo ponowienie płatności Przelewy24.
+fn render_payment_instructions_maud(
+    order: &Order,
+    payment_details: &PaymentDetailsConfig,
+) -> Markup {
+    html! {
+        div class="bg-yellow-50 border-l-4 border-yellow-400 p-4 rounded-md mb-6" {
+            h2 class="text-xl font-semibold text-yellow-800 mb-2" { "Prosimy o dokonanie płatności" }
+            div class="text-yellow-700 space-y-2" {
+                @if let Some(payment_method) = &order.payment_method {
+                    @match payment_method {
+                        PaymentMethod::Blik => {
+                            p { "Wybrana metoda: " strong { "BLIK" } }
+                            p { "Prosimy o dokonanie płatności na numer telefonu:" }
+                            p class="text-2xl font-mono bg-white p-3 rounded text-center my-2" { (payment_details.blik_phone) }
+                        }
+                        PaymentMethod::Transfer => {
+                            p { "Wybrana metoda: " strong { "Przelew tradycyjny" } }
+                            p { "Odbiorca: " strong { (payment_details.account_holder) } }
+                            p { "Prosimy o dokonanie przelewu na poniższy numer konta:" }
+                            p class="text-xl font-mono bg-white p-3 rounded text-center my-2" { (payment_details.account_number) }
+                        }
+                        PaymentMethod::Przelewy24 => {
+                            p { "Wybrana metoda: " strong { "Przelewy24" } }
+                            @if order.status == OrderStatus::Pending {
+                                p { "Nie otrzymaliśmy jeszcze potwierdzenia płatności. Jeśli płatność została przerwana, możesz ją ponowić:" }
+                                a href=(format!("/zamowienie/{}/zaplac", order.id))
+                                  class="inline-block mt-2 px-5 py-2 bg-pink-600 text-white rounded-lg hover:bg-pink-700" { "Zapłać teraz" }
+                            } @else {
+                                p { "Płatność została zaksięgowana. Dziękujemy!" }
+                            }
+                        }
+                    }
+                } @else {
+                    p { "Nie wybrano metody płatności. Skontaktuj się z nami." }
+                }
+                @if order.payment_method != Some(PaymentMethod::Przelewy24) {
+                    p { "Kwota: " strong { (format_price_maud(order.total_price)) } }
+                    p { "W tytule przelewu prosimy wpisać: " strong ."font-mono" { (order.payment_reference()) } }
+                    p { "Zamówienie zostanie wysłane po zaksięgowaniu wpłaty." }
+                }
+            }
+        }
+    }
+}
+
+/// Minimalny odstęp między wysyłkami potwierdzenia zamówienia (w minutach)
+const CONFIRMATION_RESEND_COOLDOWN_MINUTES: i32 = 2;
+
+/// Informacja o wysłanym potwierdzeniu z przyciskiem ponownej wysyłki.
+/// `message` to wynik ostatniej próby ponownej wysyłki.
+fn render_confirmation_resend_maud(order_id: Uuid, message: Option<&str>) -> Markup {
+    html! {
+        div #order-confirmation-resend class="text-sm text-gray-500 mt-1" {
+            p { "Potwierdzenie zostało wysłane na Twój adres e-mail." }
+            @if let Some(message) = message {
+                p class="mt-1 text-gray-700" { (message) }
+            } @else {
+                button type="button"
+                       hx-post=(format!("/htmx/zamowienie/{}/potwierdzenie", order_id))
+                       hx-target="#order-confirmation-resend"
+                       hx-swap="outerHTML"
+                       class="mt-1 text-pink-600 hover:underline" {
+                    "Nie dotarło? Wyślij ponownie"
+                }
+            }
+        }
+    }
+}
+
+/// Ponowna wysyłka potwierdzenia zamówienia (z danymi do przelewu) ze strony podziękowania.
+/// E-mail trafia zawsze na adres zapisany w zamówieniu, najwyżej raz na kilka minut.
+pub async fn resend_order_confirmation_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(order_id): Path<Uuid>,
+) -> Result<Markup, AppError> {
+    let claimed = repo::orders::claim_confirmation_resend(
+        &app_state.db_pool,
+        order_id,
+        CONFIRMATION_RESEND_COOLDOWN_MINUTES,
+    )
+    .await?;
+
+    if !claimed {
+        // Zamówienie nie istnieje albo potwierdzenie wysłano przed chwilą
+        if !repo::orders::exists(&app_state.db_pool, order_id).await? {
+            return Err(AppError::NotFound);
+        }
+        return Ok(render_confirmation_resend_maud(
+            order_id,
+            Some(
+                "Potwierdzenie wysłaliśmy przed chwilą. Sprawdź też folder spam lub spróbuj ponownie za kilka minut.",
+            ),
+        ));
+    }
+
+    let order_details =
+        crate::handlers::fetch_order_details_service(&app_state.db_pool, order_id).await?;
+    let message = match send_order_confirmation_email(&app_state, &order_details).await {
+        Ok(()) => "Wysłaliśmy potwierdzenie ponownie. Sprawdź skrzynkę (także folder spam).",
+        Err(e) => {
+            tracing::error!(
+                "Nie udało się ponownie wysłać potwierdzenia zamówienia {}: {:?}",
+                order_id,
+                e
+            );
+            "Nie udało się wysłać potwierdzenia. Spróbuj ponownie za chwilę."
+        }
+    };
+    Ok(render_confirmation_resend_maud(order_id, Some(message)))
+}
+
+/// Renderuje stronę błędu, gdy produkt w koszyku jest niedostępny.
+pub fn render_checkout_error_page_maud(product_name: &str) -> Markup {
+    html! {
+        div class="max-w-2xl mx-auto px-4 sm:px-6 lg:px-8 py-16 text-center" {
+            div class="bg-white p-8 rounded-xl shadow-lg border border-red-200" {
+                // Ikona błędu
+                div class="w-16 h-16 bg-red-100 rounded-full flex items-center justify-center mx-auto mb-5" {
+                    svg class="w-10 h-10 text-red-600" fill="none" stroke="currentColor" viewBox="0 0 24 24" {
+                        path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M12 8v4m0 4h.01M21 12a9 9 0 11-18 0 9 9 0 0118 0z";
+                    }
+                }
+
+                h1 class="text-2xl sm:text-3xl font-bold text-gray-900 mb-3" { "Wystąpił problem z zamówieniem" }
+
+                // Wyświetlenie konkretnego komunikatu
+                p class="text-md text-gray-700" {
+                    "Produkt '" strong class="text-red-600" { (product_name) } "' w Twoim koszyku jest już niedostępny."
+                }
+                p class="text-md text-gray-600 mt-2" {
+                    "Prawdopodobnie ktoś inny właśnie go zakupił. Prosimy o usunięcie go z koszyka, aby kontynuować."
+                }
+
+                // Przyciski akcji
+                div class="mt-8 flex flex-col sm:flex-row justify-center items-center gap-4" {
+                    // Przycisk "Wróć do koszyka" - otwiera panel boczny
+                    button type="button"
+                           "@click"="if(typeof cartOpen !== 'undefined') cartOpen = true"
+                           class="w-full sm:w-auto px-6 py-3 border border-transparent rounded-md shadow-sm text-base font-medium text-white bg-pink-600 hover:bg-pink-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500 transition-colors" {
+                        "Pokaż koszyk, aby usunąć produkt"
+                    }
+
+                    // Przycisk "Wróć do sklepu"
+                    a href="/"
+                       hx-get="/htmx/products?limit=8"
+                       hx-target="#content"
+                       hx-swap="innerHTML"
+                       hx-push-url="/"
+                       class="w-full sm:w-auto px-6 py-3 border border-gray-300 rounded-md shadow-sm text-base font-medium text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500" {
+                        "Wróć do sklepu"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Renderuje WIDOK (Markup) dla strony z podziękowaniem za zamówienie.
+/// Jest to reużywalna funkcja, która nie wykonuje zapytań do bazy - przyjmuje gotowe dane.
+pub fn render_thank_you_page_maud(
+    order: &Order,
+    items_details: &[OrderItemDetailsPublic],
+    payment_details: &PaymentDetailsConfig,
+) -> Markup {
+    html! {
+        div class="max-w-4xl mx-auto px-4 sm:px-6 lg:px-8 py-12" {
+            div class="bg-white p-6 sm:p-8 rounded-xl shadow-2xl border border-gray-200" {
+                // Nagłówek
+                div class="text-center border-b-2 border-pink-100 pb-6 mb-6" {
+                    div class="w-16 h-16 bg-green-100 rounded-full flex items-center justify-center mx-auto mb-4" {
+                        // Ikona "check"
+                        svg class="w-10 h-10 text-green-600" fill="none" stroke="currentColor" viewBox="0 0 24 24" {
+                            path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M5 13l4 4L19 7";
+                        }
+                    }
+                    h1 class="text-3xl sm:text-4xl font-bold text-gray-900" { "Dziękujemy za zamówienie!" }
+                    p class="mt-2 text-md text-gray-600" {
+                        "Twoje zamówienie nr " strong { (order.order_number) } " zostało przyjęte do realizacji."
+                    }
+                    (render_confirmation_resend_maud(order.id, None))
+                }
+
+                @if order.total_price > 0 {
+                    (render_payment_instructions_maud(order, payment_details))
+                }
+
+                // Sekcja: Podsumowanie Zamówienia
+                div {
+                    h3 class="text-lg font-semibold text-gray-700 mb-3" { "Podsumowanie zamówienia" }
+                    ul class="divide-y divide-gray-200 border rounded-lg" {
+                        @for item in items_details {
+                            li class="p-3 flex items-center space-x-4" {
+                                @if let Some(img) = item.product.images.get(0) {
+                                    img src=(img) class="w-16 h-16 rounded-md object-cover border width="64" height="64"";
+                                }
+                                div class="flex-grow" {
+                                    p class="text-sm font-medium text-gray-800" { (item.product.name) }
+                                    p class="text-xs text-gray-500" { "Cena: " (format_price_maud(item.price_at_purchase)) }
+                                }
+                                p class="text-sm font-semibold text-gray-900" { (format_price_maud(item.price_at_purchase)) }
+                            }
+                        }
+                    }
+                    // Sumy
+                    div class="mt-4 space-y-2 text-sm text-right" {
+                        @if let Some(shipping_name) = &order.shipping_method_name {
+                             @let shipping_cost = order.total_price + order.discount_amount + order.gift_card_amount - items_details.iter().map(|i| i.price_at_purchase).sum::<i64>();
+                             p { "Produkty: " span class="font-medium w-24 inline-block" { (format_price_maud(items_details.iter().map(|i| i.price_at_purchase).sum())) } }
+                             @if order.discount_amount > 0 {
+                                 p { "Rabat: " span class="font-medium text-green-700 w-24 inline-block" { "-" (format_price_maud(order.discount_amount)) } }
+                             }
+                             p { "Dostawa (" (shipping_name) "): " span class="font-medium w-24 inline-block" { (format_price_maud(shipping_cost)) } }
+                        }
+                        @if order.gift_card_amount > 0 {
+                            p { "Karta podarunkowa: " span class="font-medium text-green-700 w-24 inline-block" { "-" (format_price_maud(order.gift_card_amount)) } }
+                        }
+                         p class="text-lg border-t pt-2 mt-2" { "Suma: " span class="font-bold text-pink-600 w-24 inline-block" { (format_price_maud(order.total_price)) } }
+                    }
+                }
+
+                // Sekcja: Adres dostawy
+                div class="mt-6 pt-6 border-t" {
+                     h3 class="text-lg font-semibold text-gray-700 mb-2" { "Dane do wysyłki" }
+                     address class="not-italic text-sm text-gray-600 leading-relaxed" {
+                        (order.shipping_first_name) " " (order.shipping_last_name) br;
+                        (order.shipping_address_line1) br;
+                        @if let Some(line2) = &order.shipping_address_line2 { (line2) br; }
+                        (order.shipping_postal_code) " " (order.shipping_city) br;
+                        (order.shipping_country) br;
+                        "tel: " (order.shipping_phone)
+                     }
+                }
+
+                // Stopka z linkiem do strony głównej
+                div class="text-center mt-8 pt-6 border-t" {
+                    a href="/"
+                       hx-get="/htmx/products?limit=8"
+                       hx-target="#content"
+                       hx-swap="innerHTML"
+                       hx-push-url="/"
+                       class="inline-block bg-pink-600 hover:bg-pink-700 text-white font-medium py-3 px-8 rounded-lg shadow-md hover:shadow-lg transition-all duration-200" {
+                        "Wróć do sklepu"
+                    }
+
+                }
+            }
+        }
+    }
+}
+
+/// Renderuje włączony przycisk "Dodaj do koszyka".
+pub fn render_add_to_cart_button(product_id: Uuid) -> Markup {
+    html! {
+        button id=(format!("product-cart-button-{}", product_id))
+               type="button"
+               hx-post=(format!("/htmx/cart/toggle/{}", product_id)) // ZMIANA: Nowy endpoint
+               hx-target=(format!("#product-cart-button-{}", product_id))
+               hx-swap="outerHTML"
+               class="w-full text-[var(--color-primary-text)] font-medium py-2 px-4 rounded-lg transition-all duration-200 ease-in-out inline-flex items-center justify-center bg-[var(--color-primary)] hover:bg-[var(--color-primary-hover)]"
+        {
+            div class="flex items-center" {
+                svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="2" stroke="currentColor" class="w-5 h-5 mr-2" {
+                    path stroke-linecap="round" stroke-linejoin="round" d="M12 9v6m3-3H9m12 0a9 9 0 1 1-18 0 9 9 0 0 1 18 0Z";
+                }
+                span { "Dodaj do koszyka" }
+            }
+        }
+    }
+}
+
+/// Renderuje nieaktywny przycisk dla produktu zarezerwowanego w koszyku innego klienta.
+pub fn render_reserved_by_other_button(product_id: Uuid) -> Markup {
+    html! {
+        div id=(format!("product-cart-button-{}", product_id))
+            class="w-full text-center py-2 px-4 rounded-lg bg-gray-100 text-gray-500 text-sm font-medium cursor-not-allowed" {
+            "Zarezerwowany przez innego klienta"
+        }
+    }
+}
+
+/// Renderuje klikalny przycisk "Dodano!".
+pub fn render_added_to_cart_button(product_id: Uuid) -> Markup {
+    html! {
+        // Przycisk "Dodano!" - już nie jest wyłączony i pozwala na usunięcie produktu
+        button id=(format!("product-cart-button-{}", product_id))
+               type="button"
+               hx-post=(format!("/htmx/cart/toggle/{}", product_id)) // ZMIANA: Ten sam endpoint co wyżej
+               hx-target=(format!("#product-cart-button-{}", product_id))
+               hx-swap="outerHTML"
+                class="w-full text-white font-semibold py-2 px-4 rounded-lg transition-all inline-flex items-center justify-center bg-green-600 hover:bg-green-700 cursor-pointer"
+               title="Kliknij, aby usunąć z koszyka"
+        {
+            div class="flex items-center" {
+                svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="2.5" stroke="currentColor" class="w-5 h-5 mr-2" {
+                    path stroke-linecap="round" stroke-linejoin="round" d="m4.5 12.75 6 6 9-13.5";
+                }
+                span { "Dodano!" }
+            }
+        }
+    }
+}
+
+pub async fn toggle_cart_item_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    Query(hold_query): Query<CartHoldQuery>,
+    user_claims_result: Result<TokenClaims, AppError>,
+    guest_cart_id_header: Option<TypedHeader<XGuestCartId>>,
+) -> Result<(HeaderMap, Markup), AppError> {
+    tracing::info!(
+        "[ToggleCart] Próba przełączenia statusu produktu {}",
+        product_id
+    );
+
+    let mut tx = app_state.db_pool.begin().await?;
+    let mut headers = HeaderMap::new();
+    let mut new_guest_cart_id_to_set: Option<Uuid> = None;
+    let mut cart_opt: Option<ShoppingCart> = None;
+
+    if let Ok(claims) = user_claims_result {
+        // Użytkownik zalogowany
+        cart_opt = Some(
+            repo::carts::cart_for_owner_for_update(&mut tx, CartOwner::User(claims.sub)).await?,
+        );
+    } else {
+        // Gość
+        if let Some(TypedHeader(XGuestCartId(guest_id))) = guest_cart_id_header {
+            new_guest_cart_id_to_set = Some(guest_id);
+            cart_opt =
+                repo::carts::find_by_owner_for_update(&mut *tx, CartOwner::Guest(guest_id)).await?;
+        }
+
+        if cart_opt.is_none() {
+            // Ta logika uruchomi się, jeśli gość nie ma ID lub jego koszyk nie został znaleziony
+            let new_id = if let Some(id) = new_guest_cart_id_to_set {
+                id
+            } else {
+                Uuid::new_v4()
+            };
+            new_guest_cart_id_to_set = Some(new_id);
+
+            // Ustaw ciasteczko, jeśli tworzymy zupełnie nową sesję
+            if guest_cart_id_header.is_none() {
+                let guest_cookie = Cookie::build(("guest_cart_id", new_id.to_string()))
+                    .path("/")
+                    .http_only(true)
+                    .secure(true)
+                    .same_site(SameSite::Lax)
+                    .max_age(time::Duration::days(365))
+                    .build();
+                headers.insert(
+                    axum::http::header::SET_COOKIE,
+                    guest_cookie.to_string().parse().unwrap(),
+                );
+            }
+
+            cart_opt = Some(repo::carts::create(&mut *tx, CartOwner::Guest(new_id)).await?);
+        }
+    };
+
+    let cart: ShoppingCart = cart_opt
+        .ok_or_else(|| AppError::InternalServerError("Nie udało się uzyskać koszyka.".into()))?;
+
+    // --- Krok 2: Jeśli produkt JEST w koszyku -> USUŃ GO ---
+    let final_markup;
+    let toast_message;
+
+    let removed = repo::carts::remove_item(&mut *tx, cart.id, product_id).await?;
+    if removed {
+        tracing::info!(
+            "[ToggleCart] Produkt {} był w koszyku. Usunięto.",
+            product_id
+        );
+        release_product_reservation(&mut tx, product_id, cart.id).await?;
+
+        final_markup = render_add_to_cart_button(product_id);
+        toast_message = serde_json::json!({
+            "showMessage": { "type": "info", "message": "Produkt usunięty z koszyka." }
+        });
+    } else {
+        // --- Jeśli NIE MA go w koszyku -> DODAJ GO ---
+        tracing::info!(
+            "[ToggleCart] Produktu {} nie ma w koszyku. Dodawanie.",
+            product_id
+        );
+        // Z osobistego linku rezerwacji ręcznej produkt trafia do koszyka mimo statusu "Zarezerwowany"
+        let reservation = match hold_query.hold {
+            Some(token) => {
+                reserve_held_product_for_cart(&mut tx, product_id, cart.id, token).await?
+            }
+            None => reserve_product_for_cart(&mut tx, product_id, cart.id).await?,
+        };
+        match reservation {
+            ReservationOutcome::Reserved => {}
+            ReservationOutcome::HeldByAnotherCart => {
+                // Transakcja zostanie wycofana - przycisk pokazuje, że produkt trzyma inny klient
+                let trigger_payload = serde_json::json!({
+                    "toast": {
+                        "showMessage": { "type": "warning", "message": "Produkt jest zarezerwowany przez innego klienta." }
+                    }
+                });
+                if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+                    headers.insert("HX-Trigger", val);
+                }
+                return Ok((headers, render_reserved_by_other_button(product_id)));
+            }
+            ReservationOutcome::Unavailable => {
+                return Err(AppError::Conflict("Produkt jest już niedostępny.".into()));
+            }
+        }
+
+        repo::carts::add_item(&mut *tx, cart.id, product_id).await?;
+
+        final_markup = render_added_to_cart_button(product_id);
+        toast_message = serde_json::json!({
+            "showMessage": { "type": "success", "message": "Dodano do koszyka!" }
+        });
+    }
+
+    // --- Krok 3: Pobierz aktualne dane koszyka i wyślij trigger ---
+    let cart_details = cart_utils::build_cart_details_response(&cart, &mut tx).await?;
+    tx.commit().await?;
+    record_event(
+        &app_state.db_pool,
+        if removed {
+            EventType::RemovedFromCart
+        } else {
+            EventType::AddedToCart
+        },
+        NewEvent::for_cart(&cart, product_id),
+    );
+
+    let trigger_payload = serde_json::json!({
+        "updateCartCount": {
+            "newCount": cart_details.total_items,
+            "newCartTotalPrice": cart_details.total_price,
+            "newGuestCartId": new_guest_cart_id_to_set
+        },
+        "toast": toast_message // Używamy ogólnego klucza na toast
+    });
+
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+
+    Ok((headers, final_markup))
+}
+
+/// GET /karta-podarunkowa - formularz zakupu karty podarunkowej
+pub async fn gift_card_purchase_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+) -> Result<Response, AppError> {
+    let purchaser_email = match &user_claims_opt {
+        Some(claims) => repo::users::email(&app_state.db_pool, claims.sub).await?,
+        None => None,
+    };
+    let errors = ValidationErrors::new();
+    let input_classes = "mt-1 block w-full px-4 py-3 border border-gray-300 rounded-lg shadow-sm focus:outline-none focus:ring-2 focus:ring-pink-500";
+
+    let page_content = html! {
+        div ."max-w-3xl mx-auto px-4 py-10" {
+            div ."max-w-xl mx-auto bg-white p-8 rounded-xl shadow-lg border border-gray-200" {
+                div ."text-center mb-6" {
+                    h1 ."text-2xl font-bold text-gray-800" { "Karta podarunkowa" }
+                    p ."text-sm text-gray-500 mt-2" {
+                        "Podaruj zakupy w mess - all that vintage. Po zaksięgowaniu płatności wyślemy kod e-mailem "
+                        "do osoby obdarowanej. Karta jest ważna " (GIFT_CARD_VALIDITY_MONTHS) " miesięcy i można nią płacić w częściach."
+                    }
+                }
+                div #gift-card-messages ."mb-4" {}
+                form hx-post="/api/karta-podarunkowa"
+                     hx-target="#gift-card-messages"
+                     hx-swap="innerHTML"
+                     hx-disabled-elt="find button[type='submit']"
+                     class="space-y-5" {
+                    fieldset {
+                        legend ."block text-sm font-medium text-gray-700 mb-2" { "Wartość karty" }
+                        div ."grid grid-cols-3 sm:grid-cols-5 gap-2" {
+                            @for (index, amount) in GIFT_CARD_AMOUNTS.iter().enumerate() {
+                                label ."cursor-pointer" {
+                                    input type="radio" name="amount" value=(amount) checked[index == 1] class="peer sr-only";
+                                    span ."block text-center py-2 px-3 border border-gray-300 rounded-lg text-sm font-medium text-gray-700 peer-checked:border-pink-600 peer-checked:bg-pink-50 peer-checked:text-pink-700" {
+                                        (format_price_maud(*amount))
+                                    }
+                                }
+                            }
+                        }
+                        (errors.field_error_maud("amount"))
+                    }
+                    div {
+                        label for="gift_card_recipient_email" ."block text-sm font-medium text-gray-700" { "E-mail osoby obdarowanej" }
+                        input #gift_card_recipient_email name="recipient_email" type="email" required class=(input_classes);
+                        (errors.field_error_maud("recipient_email"))
+                    }
+                    div {
+                        label for="gift_card_recipient_name" ."block text-sm font-medium text-gray-700" { "Imię osoby obdarowanej (opcjonalnie)" }
+                        input #gift_card_recipient_name name="recipient_name" type="text" maxlength="100" class=(input_classes);
+                        (errors.field_error_maud("recipient_name"))
+                    }
+                    div {
+                        label for="gift_card_message" ."block text-sm font-medium text-gray-700" { "Życzenia (opcjonalnie)" }
+                        textarea #gift_card_message name="message" rows="3" maxlength=(MAX_GIFT_CARD_MESSAGE_LEN) class=(input_classes) {}
+                        (errors.field_error_maud("message"))
+                    }
+                    div {
+                        label for="gift_card_purchaser_email" ."block text-sm font-medium text-gray-700" { "Twój e-mail" }
+                        input #gift_card_purchaser_email name="purchaser_email" type="email" required autocomplete="email"
+                               value=[purchaser_email.as_deref()] class=(input_classes);
+                        (errors.field_error_maud("purchaser_email"))
+                    }
+                    button type="submit"
+                           class="w-full flex justify-center py-3 px-4 border rounded-lg text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-pink-500 disabled:opacity-50" {
+                        @if app_state.przelewy24_config.is_some() { "Przejdź do płatności" } @else { "Zamawiam kartę" }
+                    }
+                }
+            }
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Karta podarunkowa - sklep mess - all that vintage",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// GET /karta-podarunkowa/{id} - stan zakupionej karty: dane do przelewu albo potwierdzenie
+/// wysyłki. Kodu karty tu nie pokazujemy - dostaje go tylko obdarowany.
+pub async fn gift_card_status_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Path(gift_card_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let gift_card = find_gift_card(&app_state.db_pool, gift_card_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let payment_details = &app_state.payment_details;
+
+    let page_content = html! {
+        div ."max-w-3xl mx-auto px-4 py-10" {
+            div ."max-w-xl mx-auto bg-white p-8 rounded-xl shadow-lg border border-gray-200" {
+                h1 ."text-2xl font-bold text-gray-800 text-center mb-2" { "Karta podarunkowa" }
+                p ."text-center text-gray-600 mb-6" {
+                    "Wartość " strong { (format_price_maud(gift_card.initial_amount)) }
+                    " dla " strong { (gift_card.recipient_email) }
+                }
+                @if gift_card.paid_at.is_some() {
+                    div class="bg-green-50 border-l-4 border-green-400 p-4 rounded-md text-green-800" {
+                        p ."font-semibold" { "Płatność zaksięgowana - dziękujemy!" }
+                        p { "Kod karty wysłaliśmy e-mailem do osoby obdarowanej." }
+                    }
+                } @else if app_state.przelewy24_config.is_some() {
+                    div class="bg-yellow-50 border-l-4 border-yellow-400 p-4 rounded-md text-yellow-800 space-y-2" {
+                        p { "Czekamy na potwierdzenie płatności. Jeśli płatność została przerwana, możesz ją ponowić:" }
+                        a href=(format!("/karta-podarunkowa/{}/zaplac", gift_card.id))
+                          class="inline-block mt-2 px-5 py-2 bg-pink-600 text-white rounded-lg hover:bg-pink-700" { "Zapłać teraz" }
+                    }
+                } @else {
+                    div class="bg-yellow-50 border-l-4 border-yellow-400 p-4 rounded-md text-yellow-700 space-y-2" {
+                        h2 class="text-xl font-semibold text-yellow-800" { "Prosimy o dokonanie płatności" }
+                        p { "Odbiorca: " strong { (payment_details.account_holder) } }
+                        p { "Prosimy o dokonanie przelewu na poniższy numer konta:" }
+                        p class="text-xl font-mono bg-white p-3 rounded text-center my-2" { (payment_details.account_number) }
+                        p { "Kwota: " strong { (format_price_maud(gift_card.initial_amount)) } }
+                        p { "W tytule przelewu prosimy wpisać: " strong ."font-mono" { (gift_card.payment_reference()) } }
+                        p ."text-sm" { "Kod wyślemy do osoby obdarowanej, gdy tylko wpłata do nas dotrze." }
+                    }
+                }
+                div class="text-center mt-8" {
+                    a href="/" hx-get="/htmx/products?limit=8" hx-target="#content" hx-swap="innerHTML" hx-push-url="/"
+                      class="inline-block bg-pink-600 hover:bg-pink-700 text-white font-medium py-3 px-8 rounded-lg" {
+                        "Wróć do sklepu"
+                    }
+                }
+            }
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Karta podarunkowa - sklep mess - all that vintage",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
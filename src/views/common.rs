@@ -0,0 +1,328 @@
+// src/views/common.rs
+
+// Wspólne elementy widoków: formatowanie cen, adresy obrazków z Cloudinary, budowanie query stringów listingu, paginacja i odznaki statusów.
+
+use maud::{Markup, html};
+
+use crate::date_format::format_datetime;
+use crate::filters::ListingParams;
+use crate::models::{
+    Complaint, ComplaintStatus, OrderStatus, OrderStatusHistory, PaginationItem, Product,
+    ReturnStatus,
+};
+
+pub fn build_full_query_string_from_params(params: &ListingParams) -> String {
+    let mut query_parts = Vec::new();
+    query_parts.push(format!("limit={}", params.limit()));
+    query_parts.push(format!("offset={}", params.offset()));
+
+    if let Some(g) = params.gender() {
+        query_parts.push(format!("gender={}", g.to_string()));
+    }
+    if let Some(c) = params.category() {
+        query_parts.push(format!("category={}", c.as_ref()));
+    }
+    if let Some(cond) = params.condition() {
+        query_parts.push(format!("condition={}", cond.to_string()));
+    }
+    if let Some(stat) = params.status() {
+        query_parts.push(format!("status={}", stat.to_string()));
+    }
+    if let Some(p_min) = params.price_min() {
+        query_parts.push(format!("price_min={}", p_min));
+    }
+    if let Some(p_max) = params.price_max() {
+        query_parts.push(format!("price_max={}", p_max));
+    }
+    for (key, value) in params.attribute_filters() {
+        query_parts.push(format!("{}={}", key, urlencoding::encode(value)));
+    }
+    if let Some(s) = params.search() {
+        if !s.is_empty() {
+            query_parts.push(format!("search={}", urlencoding::encode(&s)));
+        }
+    }
+    if let Some(source) = &params.source {
+        query_parts.push(format!("source={}", source));
+    }
+    query_parts.push(format!("sort_by={}", params.sort_by()));
+    query_parts.push(format!("order={}", params.order()));
+    query_parts.join("&")
+}
+
+pub fn build_filter_only_query_string(params: &ListingParams) -> String {
+    let mut filter_parts = Vec::new();
+    if let Some(g) = params.gender() {
+        filter_parts.push(format!("gender={}", g.to_string()));
+    }
+    if let Some(c) = params.category() {
+        filter_parts.push(format!("category={}", c.to_string()));
+    }
+    if let Some(cond) = params.condition() {
+        filter_parts.push(format!("condition={}", cond.to_string()));
+    }
+    if let Some(stat) = params.status() {
+        filter_parts.push(format!("status={}", stat.to_string()));
+    }
+    if let Some(p_min) = params.price_min() {
+        filter_parts.push(format!("price_min={}", p_min));
+    }
+    if let Some(p_max) = params.price_max() {
+        filter_parts.push(format!("price_max={}", p_max));
+    }
+    for (key, value) in params.attribute_filters() {
+        filter_parts.push(format!("{}={}", key, urlencoding::encode(value)));
+    }
+    if let Some(s) = params.search() {
+        if !s.is_empty() {
+            filter_parts.push(format!("search={}", urlencoding::encode(&s)));
+        }
+    }
+    if let Some(source) = &params.source {
+        filter_parts.push(format!("source={}", source));
+    }
+    filter_parts.push(format!("sort_by={}", params.sort_by()));
+    filter_parts.push(format!("order={}", params.order()));
+
+    if filter_parts.is_empty() {
+        String::new()
+    } else {
+        format!("&{}", filter_parts.join("&")) // Zaczyna się od &
+    }
+}
+
+pub fn format_price_maud(price: i64) -> String {
+    format!("{:.2}", (price as f64) / 100.0).replace('.', ",") + " zł"
+}
+
+/// Cena dla klienta: w trakcie zaplanowanej promocji cena promocyjna i obok przekreślona zwykła
+pub fn product_price_maud(product: &Product) -> Markup {
+    let effective_price = product.effective_price();
+    html! {
+        (format_price_maud(effective_price))
+        @if effective_price < product.price {
+            " "
+            span ."text-[0.85em] font-normal text-gray-500 line-through" { (format_price_maud(product.price)) }
+        }
+    }
+}
+
+/// Gwiazdki oceny (zaokrąglonej do pełnych), np. średniej z opinii
+pub fn rating_stars_maud(rating: f64) -> Markup {
+    html! {
+        span ."inline-flex text-base leading-none" title=(format!("Ocena {:.1} na 5", rating)) {
+            @for star in 1..=5 {
+                @if f64::from(star) <= rating.round() {
+                    span ."text-yellow-400" { "★" }
+                } @else {
+                    span ."text-gray-300" { "★" }
+                }
+            }
+        }
+    }
+}
+
+pub fn return_status_badge_classes(status: ReturnStatus) -> &'static str {
+    match status {
+        ReturnStatus::Requested => "bg-yellow-100 text-yellow-800",
+        ReturnStatus::Approved => "bg-green-100 text-green-800",
+        ReturnStatus::Rejected => "bg-red-100 text-red-800",
+    }
+}
+
+const COMPLAINT_PHOTO_THUMBNAIL_TRANSFORMATION: &str = "w_160,h_160,c_fill,f_auto,q_auto:good";
+
+pub fn complaint_status_badge_classes(status: ComplaintStatus) -> &'static str {
+    match status {
+        ComplaintStatus::Open => "bg-yellow-100 text-yellow-800",
+        ComplaintStatus::InReview => "bg-blue-100 text-blue-800",
+        ComplaintStatus::Accepted => "bg-green-100 text-green-800",
+        ComplaintStatus::Rejected => "bg-red-100 text-red-800",
+    }
+}
+
+pub fn complaint_photos_maud(complaint: &Complaint) -> Markup {
+    html! {
+        @if !complaint.photos.is_empty() {
+            div ."flex flex-wrap gap-2 mt-2" {
+                @for photo_url in &complaint.photos {
+                    a href=(photo_url) target="_blank" rel="noopener" {
+                        img src=(transform_cloudinary_url(photo_url, COMPLAINT_PHOTO_THUMBNAIL_TRANSFORMATION))
+                            alt="Zdjęcie do reklamacji" loading="lazy"
+                            class="w-20 h-20 object-cover rounded-md border border-gray-200";
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn generate_pagination_items(
+    current_page: i64,
+    total_pages: i64,
+    window_size: i64,
+) -> Vec<PaginationItem> {
+    if total_pages <= 0 {
+        return Vec::new();
+    }
+
+    let mut items = Vec::new();
+    let mut last_added_page = 0;
+
+    for page_num in 1..=total_pages {
+        // Warunki, kiedy numer strony powinien być wyświetlony:
+        // 1. Pierwsza strona
+        // 2. Ostatnia strona
+        // 3. Strony w "oknie" wokół bieżącej strony
+        let should_display_page = page_num == 1
+            || page_num == total_pages
+            || (page_num >= current_page - window_size && page_num <= current_page + window_size);
+
+        if should_display_page {
+            // Jeśli jest przerwa od ostatnio dodanej strony, wstaw kropki
+            if last_added_page > 0 && page_num > last_added_page + 1 {
+                // Upewnij się, że nie dodajesz kropek tuż po stronie 1, jeśli okno zaczyna się od 3
+                // lub tuż przed ostatnią stroną, jeśli okno kończy się na total_pages - 2
+                if items.last() != Some(&PaginationItem::Dots) {
+                    // Unikaj podwójnych kropek
+                    items.push(PaginationItem::Dots);
+                }
+            }
+            items.push(PaginationItem::Page(page_num));
+            last_added_page = page_num;
+        }
+    }
+    // Czasami ostatnia pętla może nie dodać kropek przed ostatnią stroną, jeśli warunek przerwy nie został spełniony
+    // np. current=1, total=10, window=1 -> [1, Dots, 9, 10] zamiast [1, Dots, 10]
+    // Ta dodatkowa weryfikacja może pomóc, ale logika powyżej powinna być już dość solidna.
+    // Jeśli ostatnim elementem nie jest strona total_pages, a przedostatnim nie są kropki, i jest luka...
+    if total_pages > 1
+        && last_added_page < total_pages
+        && items.last() != Some(&PaginationItem::Dots)
+    {
+        // Ten warunek może być zbyt agresywny, powyższa pętla powinna sobie radzić.
+        // Jeśli jest problem z ostatnimi kropkami, można tu dodać logikę.
+    }
+
+    // Prostsze podejście do kropek może być takie:
+    // Zawsze dodaj 1.
+    // Jeśli current_page - window > 2, dodaj kropki.
+    // Dodaj strony od max(2, current_page - window) do min(total_pages - 1, current_page + window).
+    // Jeśli current_page + window < total_pages - 1, dodaj kropki.
+    // Zawsze dodaj total_pages (jeśli > 1).
+    // To jest klasyczny algorytm paginacji.
+
+    // Użyjemy bardziej bezpośredniej logiki budowania listy `items`, jak poniżej,
+    // która jest często spotykana i bardziej przewidywalna.
+
+    if total_pages <= 1 {
+        // Jeśli jest 0 lub 1 strona, nie ma co pokazywać z kropkami
+        if total_pages == 1 {
+            return vec![PaginationItem::Page(1)];
+        }
+        return Vec::new();
+    }
+
+    let mut pages_to_render = std::collections::HashSet::new();
+    pages_to_render.insert(1); // Zawsze pierwsza
+    pages_to_render.insert(total_pages); // Zawsze ostatnia
+
+    for i in -window_size..=window_size {
+        let page_in_window = current_page + i;
+        if page_in_window > 0 && page_in_window <= total_pages {
+            pages_to_render.insert(page_in_window);
+        }
+    }
+
+    let mut sorted_pages: Vec<i64> = pages_to_render.into_iter().collect();
+    sorted_pages.sort_unstable();
+
+    let mut final_items = Vec::new();
+    let mut last_page_num = 0;
+
+    for page_num in sorted_pages {
+        if last_page_num > 0 && page_num > last_page_num + 1 {
+            final_items.push(PaginationItem::Dots);
+        }
+        final_items.push(PaginationItem::Page(page_num));
+        last_page_num = page_num;
+    }
+
+    final_items
+}
+
+// Funkcja pomocnicza do klas badge dla statusu zamówienia (możesz ją przenieść)
+pub fn get_order_status_badge_classes(status: OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "bg-yellow-100 text-yellow-800",
+        OrderStatus::Processing => "bg-blue-100 text-blue-800",
+        OrderStatus::Shipped => "bg-teal-100 text-teal-800", // Zmieniono na teal dla lepszego kontrastu
+        OrderStatus::Delivered => "bg-green-100 text-green-800",
+        OrderStatus::Cancelled => "bg-red-100 text-red-800",
+    }
+}
+
+/// Oś czasu zmian statusu zamówienia. `show_actor` pokazuje, czy zmiany dokonał admin,
+/// czy system (widoczne tylko w panelu admina).
+pub fn render_order_status_timeline(history: &[OrderStatusHistory], show_actor: bool) -> Markup {
+    html! {
+        @if history.is_empty() {
+            p ."text-sm text-gray-500" { "Brak zapisanych zmian statusu." }
+        } @else {
+            ol ."relative border-l border-gray-200 ml-2" {
+                @for entry in history {
+                    li ."mb-4 ml-4 last:mb-0" {
+                        div ."absolute w-3 h-3 bg-gray-300 rounded-full -left-1.5 mt-1.5 border border-white" {}
+                        time ."block text-xs text-gray-500" { (format_datetime(&entry.changed_at)) }
+                        p ."text-sm text-gray-800" {
+                            @if let Some(from_status) = &entry.from_status {
+                                (from_status.to_string()) " → "
+                            }
+                            span class=(format!("px-2 py-0.5 text-xs font-semibold rounded-full {}", get_order_status_badge_classes(entry.to_status.clone()))) {
+                                (entry.to_status.to_string())
+                            }
+                        }
+                        @if let Some(note) = &entry.note {
+                            p ."text-xs text-gray-600" { (note) }
+                        }
+                        @if show_actor {
+                            p ."text-xs text-gray-400" {
+                                @if entry.changed_by.is_some() { "Zmienione przez administratora" } @else { "Zmiana automatyczna" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Transformuje URL z Cloudinary, dodając podane parametry we właściwym miejscu.
+pub fn transform_cloudinary_url(original_url: &str, transformations: &str) -> String {
+    // Definiujemy stały "marker", którego szukamy w URL-u.
+    const UPLOAD_MARKER: &str = "/upload/";
+
+    if let Some(upload_index) = original_url.find(UPLOAD_MARKER) {
+        // Dzielimy URL na dwie części względem markera "/upload/":
+
+        // 1. Część bazowa (wszystko przed "/upload/")
+        // np. "https://res.cloudinary.com/dvndapjpc/image"
+        let base_part = &original_url[..upload_index];
+
+        // 2. Reszta ścieżki (wszystko po "/upload/")
+        // np. "v1747674210/zeyli8kvgdvvipvgt6y2.jpg"
+        let path_part = &original_url[upload_index + UPLOAD_MARKER.len()..];
+
+        // Składamy nowy, poprawny URL
+        format!(
+            "{}{}{}/{}",
+            base_part,       // Część 1: https://.../image
+            UPLOAD_MARKER,   // /upload/
+            transformations, // w_400,h_400,c_fill...
+            path_part        // Część 2: v174.../image.jpg
+        )
+    } else {
+        // Jeśli URL nie ma standardowej struktury (fallback), zwracamy go bez zmian.
+        original_url.to_string()
+    }
+}
@@ -0,0 +1,11 @@
+// src/views/mod.rs
+
+// Widoki HTML (maud + HTMX) podzielone według obszarów sklepu. Nazwy handlerów się nie zmieniły,
+// więc routing w main.rs tylko importuje je z właściwego modułu.
+
+pub mod account;
+pub mod admin;
+pub mod cart;
+pub mod common;
+pub mod shop;
+pub mod static_pages;
@@ -0,0 +1,2521 @@
+// src/views/shop.rs
+
+// Widoki sklepu: strona główna, listingi i filtry, karta produktu, wyszukiwarka (także po zdjęciu), archiwum sprzedanych i rezerwacje pod link.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::{Multipart, Path, Query, RawQuery, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use maud::{Markup, PreEscaped, html};
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+use crate::auth_models::TokenClaims;
+use crate::cache_stats::CacheName;
+use crate::care_instructions::care_instructions_for_materials;
+use crate::checkout::free_shipping_threshold;
+use crate::client_ip::ClientIp;
+use crate::date_format::{format_date, format_datetime_long};
+use crate::errors::AppError;
+use crate::events::{NewEvent, record_event};
+use crate::filters::{FacetCounts, ListingParams, PRICE_BUCKETS};
+use crate::holds::find_active_hold_by_token;
+use crate::homepage::{
+    HOMEPAGE_SECTIONS_CACHE_KEY, HomepageSectionConfig, featured_products, list_homepage_sections,
+};
+use crate::image_hash::{find_similar_products, perceptual_hash};
+use crate::middleware::{OptionalGuestCartId, OptionalTokenClaims};
+use crate::models::{
+    Category, EventType, HomepageSection, PaginationItem, Product, ProductCondition, ProductGender,
+    ProductReview, ProductStatus,
+};
+use crate::pagination::{PaginatedProductsResponse, total_pages};
+use crate::plural::pluralize;
+use crate::price_history::{
+    lowest_prices_before_discount, lowest_prices_for_sale_products, price_before_drop,
+};
+use crate::product_views::{
+    POPULAR_PRODUCTS_LIMIT, popular_products, record_product_view, visitor_key,
+};
+use crate::repo;
+use crate::response::{PageBuilder, build_response};
+use crate::reviews::{RatingSummary, approved_reviews_for_product, rating_summary};
+use crate::sales::sale_countdowns;
+use crate::search::{render_highlighted, search_products_with_snippets};
+use crate::security_headers::CspNonce;
+use crate::seo::{
+    Breadcrumb, PaginationLinks, SchemaAddress, SchemaAggregateRating, SchemaBrand,
+    SchemaBreadcrumbList, SchemaOffer, SchemaOrganization, SchemaProduct, SchemaSearchAction,
+    SchemaWebSite, SocialMeta,
+};
+use crate::services::{get_available_categories_for_gender, get_facet_counts};
+use crate::state::AppState;
+use crate::wishlist::{is_on_wishlist, toggle_wishlist_item};
+
+use super::cart::{
+    render_add_to_cart_button, render_added_to_cart_button, render_reserved_by_other_button,
+};
+use super::common::{
+    build_filter_only_query_string, build_full_query_string_from_params, format_price_maud,
+    generate_pagination_items, product_price_maud, rating_stars_maud, transform_cloudinary_url,
+};
+
+#[derive(Deserialize, Debug)]
+pub struct DetailViewParams {
+    #[serde(default)]
+    pub return_params: Option<String>,
+    #[serde(default)]
+    pub return_url: Option<String>,
+    #[serde(default)]
+    pub return_text: Option<String>,
+    #[serde(default)]
+    pub return_target: Option<String>,
+}
+
+/// Transformacja dużych zdjęć w widoku produktu - ta sama w prefetchu, żeby trafić w cache przeglądarki
+pub const PRODUCT_DETAIL_IMAGE_TRANSFORMATION: &str = "w_1000,f_auto,q_auto:best";
+/// Ile pierwszych zdjęć produktu wstępnie pobieramy po najechaniu na kartę w siatce
+const PRODUCT_PREFETCH_IMAGES: usize = 2;
+
+/// Wskazówki `<link rel="preload">` dla dużych zdjęć produktu. Karta w siatce pobiera je
+/// raz, po najechaniu (`mouseenter once`) - bez renderowania całego widoku produktu.
+pub async fn product_prefetch_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let product = crate::handlers::get_product_details(State(app_state), Path(product_id))
+        .await?
+        .0;
+    let markup = html! {
+        @for url in product.images.iter().take(PRODUCT_PREFETCH_IMAGES) {
+            link rel="preload" as="image" fetchpriority="low"
+                 href=(transform_cloudinary_url(url, PRODUCT_DETAIL_IMAGE_TRANSFORMATION));
+        }
+    };
+    Ok((
+        [(axum::http::header::CACHE_CONTROL, "public, max-age=300")],
+        markup,
+    )
+        .into_response())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn get_product_detail_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Path(product_ref): Path<String>,
+    RawQuery(raw_query): RawQuery,
+    Query(query_params): Query<DetailViewParams>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+    ClientIp(client_ip): ClientIp,
+) -> Result<Response, AppError> {
+    tracing::info!(
+        "MAUD: /htmx/product/{} z parametrami: {:?}",
+        product_ref,
+        query_params
+    );
+
+    let user_id = user_claims_opt.as_ref().map(|claims| claims.sub);
+
+    // Stare adresy z UUID nadal działają - pełne wejście dostaje 301 na adres ze slugiem
+    let legacy_product_id = Uuid::parse_str(&product_ref).ok();
+    let product = match legacy_product_id {
+        Some(product_id) => repo::products::find_by_id(&app_state.db_pool, product_id).await?,
+        None => repo::products::find_by_slug(&app_state.db_pool, &product_ref).await?,
+    };
+    let Some(product) = product else {
+        tracing::warn!("MAUD: Nie znaleziono produktu: {}", product_ref);
+        return Err(AppError::NotFound);
+    };
+
+    if legacy_product_id.is_some() && !headers.contains_key("HX-Request") {
+        let location = match raw_query {
+            Some(query) if !query.is_empty() => format!("{}?{}", product.public_path(), query),
+            _ => product.public_path(),
+        };
+        return Ok((
+            StatusCode::MOVED_PERMANENTLY,
+            [(axum::http::header::LOCATION, location)],
+        )
+            .into_response());
+    }
+
+    // --- NOWA LOGIKA: Pobranie koszyka i sprawdzenie, czy produkt w nim jest ---
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+
+    record_event(
+        &app_state.db_pool,
+        EventType::ProductViewed,
+        NewEvent {
+            user_id,
+            guest_session_id: guest_cart_id_opt,
+            product_id: Some(product.id),
+            ..Default::default()
+        },
+    );
+    record_product_view(
+        &app_state.db_pool,
+        product.id,
+        visitor_key(user_id, guest_cart_id_opt, client_ip),
+    );
+
+    // Przygotowujemy JSON dla wyspy danych, tak jak na liście produktów
+    let cart_product_ids_json =
+        serde_json::to_string(&product_ids_in_cart).unwrap_or_else(|_| "[]".to_string());
+
+    let is_in_cart = product_ids_in_cart.contains(&product.id);
+    let formatted_price = product_price_maud(&product);
+    let price_dropped_from = price_before_drop(&app_state.db_pool, &product).await?;
+    let on_wishlist = match user_id {
+        Some(user_id) => is_on_wishlist(&app_state.db_pool, user_id, product.id).await?,
+        None => false,
+    };
+    // Każda zapowiedź obniżki (okazja albo "cena obniżona z") musi mieć cenę z 30 dni
+    let lowest_price = if product.is_discounted() || price_dropped_from.is_some() {
+        lowest_prices_before_discount(&app_state.db_pool, &[product.id])
+            .await?
+            .remove(&product.id)
+    } else {
+        None
+    };
+
+    // --- NOWY BLOK: TWORZENIE DANYCH STRUKTURALNYCH (JSON-LD) ---
+    // 1. Mapujemy statusy i stany z naszej aplikacji na standard Schema.org
+    let schema_availability = match product.status {
+        ProductStatus::Available | ProductStatus::Reserved => "https://schema.org/InStock",
+        ProductStatus::Sold | ProductStatus::Archived => "https://schema.org/OutOfStock",
+    };
+
+    let schema_condition = match product.condition {
+        ProductCondition::New => "https://schema.org/NewCondition",
+        _ => "https://schema.org/UsedCondition", // Wszystkie inne traktujemy jako używane
+    };
+
+    // 2. Tworzymy obiekt "Offer"
+    let schema_offer = SchemaOffer {
+        type_of: "Offer",
+        url: format!("https://messvintage.com{}", product.public_path()),
+        price_currency: "PLN",
+        price: format!("{:.2}", product.effective_price() as f64 / 100.0),
+        availability: schema_availability,
+        item_condition: schema_condition,
+    };
+
+    // Opublikowane opinie - na stronę i jako aggregateRating w JSON-LD
+    let reviews = approved_reviews_for_product(&app_state.db_pool, product.id).await?;
+    let rating = rating_summary(&reviews);
+    let care_instructions =
+        care_instructions_for_materials(&app_state.db_pool, product.material.as_deref()).await?;
+
+    // 3. Tworzymy główny obiekt "Product"
+    let schema_product = SchemaProduct {
+        context: "https://schema.org",
+        type_of: "Product",
+        name: &product.name,
+        description: &product.description,
+        sku: product.id.to_string(),
+        image: &product.images,
+        // Marka produktu, a gdy jej nie znamy - marka sklepu
+        brand: SchemaBrand {
+            type_of: "Brand",
+            name: product
+                .brand
+                .as_deref()
+                .unwrap_or("mess - all that vintage"),
+        },
+        size: product.size.as_deref(),
+        color: product.color.as_deref(),
+        material: product.material.as_deref(),
+        offers: schema_offer,
+        aggregate_rating: rating.map(|rating| SchemaAggregateRating {
+            type_of: "AggregateRating",
+            rating_value: format!("{:.1}", rating.average),
+            review_count: rating.count,
+            best_rating: 5,
+            worst_rating: 1,
+        }),
+    };
+
+    // 4. Serializujemy całą strukturę do stringa JSON
+    let json_ld_string = serde_json::to_string(&schema_product).unwrap_or_else(|e| {
+        tracing::error!("Błąd serializacji JSON-LD: {}", e);
+        "{}".to_string()
+    });
+
+    let breadcrumbs = gender_breadcrumbs(
+        &product.gender,
+        Some(&product.category),
+        Some(&product.name),
+    );
+    let head_scripts = html! {
+        script type="application/ld+json" {
+            (PreEscaped(json_ld_string))
+        }
+        (breadcrumbs_json_ld(&breadcrumbs, &app_state.public_base_url))
+    };
+
+    let body_scripts = html! {
+        script #cart_state-data type="application/json" {
+            (PreEscaped(cart_product_ids_json))
+        }
+    };
+
+    // --- KROK 1: PRZYGOTOWANIE ZOPTymalizowanych URL-i ---
+
+    // Zestaw DUŻYCH obrazków (dla widoku głównego i modala)
+    let large_image_urls: Vec<String> = product
+        .images
+        .iter()
+        .map(|url| transform_cloudinary_url(url, PRODUCT_DETAIL_IMAGE_TRANSFORMATION))
+        .collect();
+
+    // Zestaw MAŁYCH miniaturek (do klikania)
+    let thumbnail_urls: Vec<String> = product
+        .images
+        .iter()
+        .map(|url| transform_cloudinary_url(url, "w_150,h_150,c_fill,f_auto,q_auto:good"))
+        .collect();
+
+    // Początkowy główny obrazek (pierwszy z listy dużych)
+    let initial_main_image_url = large_image_urls
+        .get(0)
+        .cloned()
+        .unwrap_or_else(|| "/static/placeholder.png".to_string());
+
+    // === KROK 2: STWORZENIE TAGÓW PRELOAD ===
+    // Wstępnie ładujemy wszystkie duże obrazki, aby klikanie było natychmiastowe
+    let preload_links_markup = html! {
+        @for url in &large_image_urls {
+            link rel="preload" as="image" href=(url);
+        }
+    };
+
+    let social_meta = SocialMeta::for_product(&product, &app_state.public_base_url);
+    let combined_head_content = html! {
+        (head_scripts)
+        (preload_links_markup)
+    };
+
+    // --- KROK 3: PRZYGOTOWANIE DANYCH DLA ALPINE.JS ---
+    let large_images_json = serde_json::to_string(&large_image_urls).unwrap();
+    let thumbnails_json = serde_json::to_string(&thumbnail_urls).unwrap();
+
+    let page_content = html! {
+    (render_breadcrumbs_maud(&breadcrumbs))
+    div #product-detail-view
+        "data-initial-image"=(initial_main_image_url)
+        "data-large-images"=(large_images_json)
+        "data-thumbnails"=(thumbnails_json)
+        x-data="{
+            currentMainImage: '',
+            allLargeImages: [],
+            allThumbnails: []
+        }"
+        x-init="
+            currentMainImage = $el.dataset.initialImage;
+            allLargeImages = JSON.parse($el.dataset.largeImages);
+            allThumbnails = JSON.parse($el.dataset.thumbnails);
+        "
+
+        class="bg-white p-4 sm:p-6 lg:p-8 rounded-lg shadow-xl" {
+        div ."grid grid-cols-1 md:grid-cols-2 gap-8 lg:gap-12" {
+            // --- Kolumna z obrazkami ---
+            div ."space-y-4" {
+                @if !product.images.is_empty() {
+                    div ."aspect-w-4 aspect-h-3 sm:aspect-w-1 sm:aspect-h-1 rounded-lg overflow-hidden border border-gray-200 shadow-sm bg-gray-50 flex items-center justify-center" {
+
+                        img src=(initial_main_image_url)
+                            "x-bind:src"="currentMainImage"
+                            alt={"Zdjęcie główne: " (product.name)}
+                            loading="lazy"
+                            "@click"="$dispatch('open-alpine-modal', { src: currentMainImage, imagesArray: allLargeImages })"
+                            class="w-full h-full object-contain cursor-pointer hover:opacity-90 transition-opacity duration-200";
+                        }
+
+
+                    @if product.images.len() > 1 {
+                        div .grid.grid-cols-3.sm:grid-cols-4.md:grid-cols-3.lg:grid-cols-5.gap-2.sm:gap-3 {
+                            template x-for="(thumbnailUrl, index) in allThumbnails" x-bind:key="index" {
+                                button type="button"
+                                    "@click"="currentMainImage = allLargeImages[index]; $nextTick(() => window.scrollTo({ top: 0, behavior: 'auto' }))"
+                                    "x-bind:class"="currentMainImage === allLargeImages[index] ? 'border-[var(--color-primary)] ring-2 ring-[var(--color-primary)]' : 'border-gray-200 hover:border-[var(--color-primary)]'"
+                                    class="aspect-square block border-2 rounded-md overflow-hidden focus:outline-none focus:border-pink-500 transition-all duration-150 bg-gray-50" {
+                                    img "x-bind:src"="thumbnailUrl"
+                                        x-bind:alt="'Miniaturka ' + (index + 1)"
+                                        class="w-full h-full object-cover object-center"
+                                        loading="lazy"
+                                        width="150"
+                                        height="150";
+                                }
+
+                            }
+                        }
+                    }
+                } @else {
+                    div ."aspect-w-4 aspect-h-3 sm:aspect-w-1 sm:aspect-h-1 w-full bg-gray-100 rounded-lg flex items-center justify-center border border-gray-200" {
+                        img src="/static/placeholder.png" alt={"Brak zdjęcia produktu " (product.name)} class="max-w-full max-h-full object-contain opacity-50";
+                    }
+                }
+            }
+                // --- Kolumna z informacjami o produkcie ---
+                div ."flex flex-col" {
+                    h1 ."text-2xl sm:text-3xl lg:text-4xl font-bold tracking-tight text-gray-900 mb-2" { (product.name) }
+                        p ."text-3xl font-semibold text-[var(--text-color-primary)] mb-5" {
+                            (formatted_price)
+                            @if let Some(old_price) = price_dropped_from {
+                                span ."block text-sm font-normal text-gray-500 mt-1" {
+                                    "cena obniżona z " span ."line-through" { (format_price_maud(old_price)) }
+                                }
+                            }
+                            @if let Some(lowest_price) = lowest_price {
+                                span ."block text-xs font-normal text-gray-500 mt-1" { (lowest_price_note_maud(lowest_price)) }
+                            }
+                        }
+
+                    div ."space-y-2 text-sm text-gray-700 mb-5" {
+                        p { strong ."font-medium text-gray-900" { "Rodzaj:" } " " (product.gender.to_string()) }
+                        p { strong ."font-medium text-gray-900" { "Kategoria:" } " " (product.category.to_string()) }
+                        p { strong ."font-medium text-gray-900" { "Stan:" } " " (product.condition.to_string()) }
+                        @for (label, value) in product.attributes() {
+                            p { strong ."font-medium text-gray-900" { (label) ":" } " " (value) }
+                        }
+                        p {
+                            strong ."font-medium text-gray-900" { "Status:" } " "
+                            @let status_str = product.status.to_string();
+                            @if status_str == "Dostępny" {
+                                span ."px-2 py-0.5 inline-flex text-xs leading-5 font-semibold rounded-full bg-[#b0d4ac] text-green-900" { "Dostępny" }
+                            } @else if status_str == "Zarezerwowany" {
+                                span ."px-2 py-0.5 inline-flex text-xs leading-5 font-semibold rounded-full bg-yellow-100 text-yellow-800" { "Zarezerwowany" }
+                            } @else if status_str == "Sprzedany" {
+                                span ."px-2 py-0.5 inline-flex text-xs leading-5 font-semibold rounded-full bg-red-100 text-red-800" { "Sprzedany" }
+                            } @else {
+                                span { (status_str) }
+                            }
+                        }
+                    }
+
+                    div ."prose prose-sm max-w-none text-gray-600 mb-6" {
+                        h2 ."text-md font-semibold text-gray-800 mb-1 not-prose" { "Opis produktu:" }
+                        @for line in product.description.lines() {
+                            (line) br;
+                        }
+                    }
+
+                    @if !care_instructions.is_empty() {
+                        div "x-data"="{ open: false }" ."border border-gray-200 rounded-lg mb-6" {
+                            button type="button" "@click"="open = !open" class="w-full flex justify-between items-center px-4 py-3 text-left text-md font-semibold text-gray-800 hover:bg-gray-50 focus:outline-none" {
+                                "Pielęgnacja"
+                                svg ."w-5 h-5 text-gray-500 transform transition-transform duration-200" "x-bind:class"="open ? 'rotate-180' : ''" fill="none" stroke="currentColor" "viewBox"="0 0 24 24" "xmlns"="http://www.w3.org/2000/svg" {
+                                    path "stroke-linecap"="round" "stroke-linejoin"="round" "stroke-width"="2" d="M19 9l-7 7-7-7";
+                                }
+                            }
+                            ul ."px-4 pb-4 space-y-2 text-sm text-gray-600" "x-show"="open" "x-cloak" {
+                                @for instruction in &care_instructions {
+                                    li { strong ."text-gray-800" { (instruction.material) ": " } (instruction.instructions) }
+                                }
+                            }
+                        }
+                    }
+
+                    div ."mt-auto pt-6" {
+                        @if is_in_cart {
+                            (render_added_to_cart_button(product.id))
+                        } @else if product.status == ProductStatus::Available {
+                            (render_add_to_cart_button(product.id))
+                        } @else if product.status == ProductStatus::Reserved {
+                            (render_reserved_by_other_button(product.id))
+                        } @else {
+                                div ."w-full text-center py-3 px-6 rounded-lg bg-gray-100 text-gray-500 font-semibold" {
+                                "Produkt obecnie niedostępny"
+                            }
+                        }
+                        @if product.status == ProductStatus::Available {
+                            (render_wishlist_button(product.id, user_id.is_some(), on_wishlist))
+                        }
+
+                        // --- Logika linku powrotnego (WERSJA OSTATECZNA) ---
+                        div ."mt-4 text-center" {
+                            @if let (Some(url), Some(text)) = (&query_params.return_url, &query_params.return_text) {
+                                // Ta część obsługuje specyficzne powroty, np. ze szczegółów zamówienia
+                                a href=(url.replace("/htmx", ""))
+                                   hx-get=(url)
+                                   hx-target=(query_params.return_target.as_deref().unwrap_or("#content"))
+                                   hx-swap="innerHTML"
+                                   hx-push-url=(url.replace("/htmx", ""))
+                                   class="js-back-to-list-link inline-flex items-center px-4 py-2 border border-[var(--color-secondary)] rounded-md shadow-sm text-sm font-medium text-pink-700 bg-pink-100 hover:bg-pink-200 hover:border-pink-300 transition-colors focus:outline-none focus:ring-2 focus:ring-pink-500 focus:ring-offset-2" {
+                                   svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5 mr-2" {
+                                       path stroke-linecap="round" stroke-linejoin="round" d="M9 15 3 9m0 0 6-6M3 9h12a6 6 0 0 1 0 12h-3";
+                                   }
+                                   span { (text) }
+                                }
+                            } @else {
+                                // Ta część obsługuje powroty z list produktów
+                                @if let Some(return_params_str) = query_params.return_params.as_deref().filter(|s| !s.is_empty()) {
+                                    @let back_params: ListingParams = serde_qs::from_str(return_params_str).unwrap_or_default();
+
+                                    @let (return_url, return_text) = {
+                                        if let Some(source) = &back_params.source {
+                                            match source.as_str() {
+                                                "home" => (format!("/?{}", return_params_str), "Wróć na stronę główną".to_string()),
+                                                "nowosci" => (format!("/nowosci?{}", return_params_str), "Wróć do Nowości".to_string()),                                              "okazje" => (format!("/okazje?{}", return_params_str), "Wróć do Okazji".to_string()),
+                                                "search" => (format!("/wyszukiwanie?{}", return_params_str), "Wróć do wyników wyszukiwania".to_string()),
+                                                _ => (String::new(), String::new())
+                                            }
+                                        } else {
+                                            // Logika dla kategorii (jeśli brak `source`)
+                                            let gender_slug = if back_params.gender == Some(ProductGender::Meskie) { "dla-niego" } else { "dla-niej" };
+                                            if let Some(category) = back_params.category {
+                                                (format!("/{}/{}?{}", gender_slug, category.as_ref(), return_params_str), "Wróć do listy".to_string())
+                                            } else {
+                                                (format!("/{}?{}", gender_slug, return_params_str), "Wróć do listy".to_string())
+                                            }
+                                        }
+                                    };
+
+                                    @if !return_url.is_empty() {
+                                        a href=(return_url)
+                                           hx-get=(return_url)
+                                           hx-target="#content"
+                                           hx-swap="innerHTML"
+                                           hx-push-url="true"
+                                           class="js-back-to-list-link inline-flex items-center px-4 py-2 border border-pink-200 rounded-md shadow-sm text-sm font-medium text-pink-700 bg-pink-100 hover:bg-pink-200 hover:border-pink-300 transition-colors focus:outline-none focus:ring-2 focus:ring-pink-500 focus:ring-offset-2" {
+                                            svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5 mr-2" {
+                                                path stroke-linecap="round" stroke-linejoin="round" d="M9 15 3 9m0 0 6-6M3 9h12a6 6 0 0 1 0 12h-3";
+                                            }
+                                            span { (return_text) }
+                                        }
+                                    }
+                                } @else {
+                                    // Domyślny przycisk powrotu, jeśli nie ma żadnych parametrów
+                                    @let (return_path, return_text) = if product.gender == crate::models::ProductGender::Damskie {
+                                        ("/dla-niej", "Damskie")
+                                    } else {
+                                        ("/dla-niego", "Męskie")
+                                    };
+                                    a href=(return_path) hx-get=(format!("/htmx{}", return_path)) hx-target="#content" hx-swap="innerHTML" hx-push-url=(return_path)
+                                       class="js-back-to-list-link inline-flex items-center px-4 py-2 border border-pink-200 rounded-md shadow-sm text-sm font-medium text-pink-700 bg-pink-100 hover:bg-pink-200 hover:border-pink-300 transition-colors focus:outline-none focus:ring-2 focus:ring-pink-500 focus:ring-offset-2" {
+                                        svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5 mr-2" {
+                                            path stroke-linecap="round" stroke-linejoin="round" d="M9 15 3 9m0 0 6-6M3 9h12a6 6 0 0 1 0 12h-3";
+                                        }
+                                        span { "Wróć do " (return_text) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            (render_product_reviews_maud(&reviews, rating))
+        }
+    };
+
+    let title = social_meta.title.clone();
+    let page_builder = PageBuilder::new(
+        &title,
+        page_content,
+        Some(combined_head_content),
+        Some(body_scripts),
+    )
+    .with_social_meta(social_meta);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Przycisk "Dodaj do ulubionych" pod koszykiem. Gość dostaje link do logowania - ulubione
+/// są przypięte do konta, bo na nie wysyłamy powiadomienia o obniżkach.
+pub fn render_wishlist_button(product_id: Uuid, logged_in: bool, on_wishlist: bool) -> Markup {
+    let button_id = format!("product-wishlist-button-{}", product_id);
+    let label = if on_wishlist {
+        "W ulubionych"
+    } else {
+        "Dodaj do ulubionych"
+    };
+    html! {
+        @if logged_in {
+            button id=(button_id)
+                   type="button"
+                   hx-post=(format!("/htmx/ulubione/{}", product_id))
+                   hx-target=(format!("#product-wishlist-button-{}", product_id))
+                   hx-swap="outerHTML"
+                   class="mt-3 w-full font-medium py-2 px-4 rounded-lg border border-[var(--color-secondary)] text-pink-700 bg-white hover:bg-pink-50 transition-colors inline-flex items-center justify-center"
+            {
+                svg xmlns="http://www.w3.org/2000/svg" fill=(if on_wishlist { "currentColor" } else { "none" }) viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="w-5 h-5 mr-2" {
+                    path stroke-linecap="round" stroke-linejoin="round" d="M21 8.25c0-2.485-2.099-4.5-4.688-4.5-1.935 0-3.597 1.126-4.312 2.733-.715-1.607-2.377-2.733-4.313-2.733C5.1 3.75 3 5.765 3 8.25c0 7.22 9 12 9 12s9-4.78 9-12Z";
+                }
+                span { (label) }
+            }
+        } @else {
+            a id=(button_id)
+              href="/logowanie"
+              hx-get="/htmx/logowanie"
+              hx-target="#content"
+              hx-swap="innerHTML"
+              hx-push-url="/logowanie"
+              class="mt-3 block text-center text-xs text-gray-500 hover:text-pink-600 hover:underline" {
+                "Zaloguj się, aby dodać do ulubionych i dostać e-mail o obniżce"
+            }
+        }
+    }
+}
+
+/// POST /htmx/ulubione/{product_id} - dodaje produkt do ulubionych albo go z nich usuwa
+pub async fn toggle_wishlist_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(product_id): Path<Uuid>,
+    claims: TokenClaims,
+) -> Result<(HeaderMap, Markup), AppError> {
+    if repo::products::find_by_id(&app_state.db_pool, product_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound);
+    }
+    let added = toggle_wishlist_item(&app_state.db_pool, claims.sub, product_id).await?;
+
+    let mut headers = HeaderMap::new();
+    let trigger_payload = serde_json::json!({
+        "showMessage": {
+            "type": if added { "success" } else { "info" },
+            "message": if added {
+                "Dodano do ulubionych - damy znać o obniżce."
+            } else {
+                "Usunięto z ulubionych."
+            }
+        }
+    });
+    if let Ok(val) = HeaderValue::from_str(&trigger_payload.to_string()) {
+        headers.insert("HX-Trigger", val);
+    }
+    Ok((headers, render_wishlist_button(product_id, true, added)))
+}
+
+/// Opinie klientów pod szczegółami produktu - tylko opublikowane
+fn render_product_reviews_maud(
+    reviews: &[ProductReview],
+    summary: Option<RatingSummary>,
+) -> Markup {
+    html! {
+        @if let Some(summary) = summary {
+            section #product-reviews ."mt-10 pt-6 border-t border-gray-200" {
+                div ."flex flex-wrap items-center gap-3 mb-4" {
+                    h2 ."text-lg font-semibold text-gray-800" { "Opinie klientów" }
+                    (rating_stars_maud(summary.average))
+                    span ."text-sm text-gray-600" {
+                        (format!("{:.1}", summary.average).replace('.', ",")) " / 5 · "
+                        (pluralize(summary.count, "opinia", "opinie", "opinii"))
+                    }
+                }
+                ul ."space-y-4" {
+                    @for review in reviews {
+                        li ."p-4 rounded-lg bg-gray-50 border border-gray-200" {
+                            div ."flex flex-wrap items-center gap-2 mb-1" {
+                                (rating_stars_maud(f64::from(review.rating)))
+                                span ."text-sm font-medium text-gray-800" { (review.author_name) }
+                                @if review.verified_purchase {
+                                    span ."px-2 py-0.5 text-xs rounded-full bg-green-100 text-green-800" { "Zweryfikowany zakup" }
+                                }
+                                span ."text-xs text-gray-500" { (format_date(&review.created_at)) }
+                            }
+                            p ."text-sm text-gray-700 whitespace-pre-line" { (review.content) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn render_product_grid_maud(
+    products: &[Product],
+    paginated_response: &PaginatedProductsResponse,
+    params: &ListingParams,
+    product_ids_in_cart: &[Uuid],
+    facets: &FacetCounts,
+    lowest_prices: &HashMap<Uuid, i64>,
+) -> Markup {
+    let current_page = paginated_response.current_page;
+    let total_pages = paginated_response.total_pages;
+    let per_page = paginated_response.per_page;
+    let filter_query_string = build_filter_only_query_string(params);
+    let current_listing_params_qs = build_full_query_string_from_params(params);
+
+    let base_path = listing_base_path(params);
+    // Na "Okazjach" produkty z kończącą się promocją dostają odliczanie (zob. `startSaleCountdowns` w app.js)
+    let sale_countdowns = if params.on_sale == Some(true) {
+        sale_countdowns(products)
+    } else {
+        Vec::new()
+    };
+
+    html! {
+
+        div #products-grid-container {
+            @if !sale_countdowns.is_empty() {
+                script #sale-countdowns type="application/json" {
+                    (PreEscaped(serde_json::to_string(&sale_countdowns).unwrap_or_else(|_| "[]".to_string())))
+                }
+            }
+            div ."flex flex-col xl:flex-row gap-6" {
+                (render_facet_sidebar_maud(params, facets, per_page))
+                div ."flex-1 min-w-0" {
+                    div #products-container .grid.grid-cols-1.sm:grid-cols-2.lg:grid-cols-3.xl:grid-cols-4.gap-6 {
+                        @if products.is_empty() {
+                            p ."col-span-full text-center text-gray-500 py-8" {
+                                "Brak produktów spełniających wybrane kryteria."
+                            }
+                        } @else {
+                            @for (index, product) in products.iter().enumerate() { // Iterujemy po plasterku
+                                @let initial_image_raw = product.images.get(0).cloned().unwrap_or_default();
+                                @let hover_image_raw = product.images.get(1).cloned().unwrap_or_default();
+
+                                @let initial_image_transformed = transform_cloudinary_url(
+                                    &initial_image_raw, "w_400,h_400,c_fill,g_auto,f_auto,q_auto:best"
+                                );
+                                @let hover_image_transformed = if !hover_image_raw.is_empty() {
+                                    transform_cloudinary_url(&hover_image_raw, "w_400,h_400,c_fill,g_auto,f_auto,q_auto:best")
+                                } else {
+                                    String::new()
+                                };
+                                @let has_hover_image = !hover_image_transformed.is_empty();
+                                    @let class_binding_initial = format!(
+                                    "{{ 'opacity-0': isHovering && {} }}",
+                                    has_hover_image
+                                );
+                                @let class_binding_hover = "{ 'opacity-100': isHovering }";
+
+                                div class="product-card border border-gray-200 rounded-lg p-4 flex flex-col bg-white transition-all duration-200 ease-in-out hover:border-gray-300 hover:-translate-y-1"
+                                    x-data="{ isHovering: false }"
+                                    "@mouseenter"="isHovering = true"
+                                    "@mouseleave"="isHovering = false" {
+                                    // Po pierwszym najechaniu pobieramy tylko wskazówki preload dużych zdjęć,
+                                    // żeby otwarcie produktu z siatki nie czekało na obrazy
+                                    span ."hidden"
+                                         hx-get=(format!("/htmx/produkt/{}/prefetch", product.id))
+                                         hx-trigger="mouseenter once from:closest .product-card, touchstart once from:closest .product-card"
+                                         hx-swap="innerHTML" {}
+                                    a  href=(product.public_path())
+                                        hx-get=(format!("/produkty/{}?return_params={}", product.slug, urlencoding::encode(&current_listing_params_qs)))
+                                        hx-target="#content"
+                                        hx-swap="innerHTML"
+                                        hx-push-url="true"
+                                        class="block mb-2 group aspect-square relative" {
+
+                                        @if !product.images.is_empty() {
+                                            img
+                                                src=(initial_image_transformed)
+                                                alt=(product.name)
+                                                class="absolute inset-0 w-full h-full object-cover rounded-md transition-opacity duration-300 ease-in-out"
+                                                x-bind:class=(class_binding_initial)
+                                                loading="lazy"
+                                                width="400"
+                                                height="400"
+                                                fetchpriority=[if index == 0 { Some("high") } else { None }]
+                                                ;
+                                            // Obrazek PO NAJECHANIU (tylko jeśli istnieje)
+                                            @if has_hover_image {
+                                                img src=(hover_image_transformed)
+                                                    alt=(product.name)
+                                                    class="absolute inset-0 w-full h-full object-cover rounded-md transition-opacity duration-300 ease-in-out opacity-0"
+                                                    x-bind:class=(class_binding_hover)
+                                                    x-cloak;
+                                            }
+                                        } @else {
+                                            div ."w-full h-full bg-gray-200 rounded-md flex items-center justify-center group-hover:opacity-85 transition-opacity duration-200" {
+                                                span ."text-gray-500 text-sm" { "Brak zdjęcia" }
+                                            }
+                                        }
+                                    }
+                                    div ."flex-grow" {
+                                        h2 ."text-lg font-semibold mb-1 text-gray-800 group-hover:text-pink-600 transition-colors duration-200" {
+                                            a href=(product.public_path())
+                                               hx-get=(format!("/htmx/produkt/{}?return_params={}", product.slug, urlencoding::encode(&current_listing_params_qs)))
+                                               hx-target="#content" hx-swap="innerHTML"
+                                               hx-push-url=(product.public_path()) {
+                                                (product.name)
+                                            }
+                                        }
+                                        p ."text-gray-700 mb-1" { (product_price_maud(product)) }
+                                        @if sale_countdowns.iter().any(|countdown| countdown.product_id == product.id) {
+                                            p ."text-xs font-semibold text-pink-600 mb-1" data-sale-countdown=(product.id) {}
+                                        }
+                                        @if let Some(lowest_price) = lowest_prices.get(&product.id) {
+                                            p ."text-xs text-gray-500 mb-1" { (lowest_price_note_maud(*lowest_price)) }
+                                        }
+                                        p ."text-xs text-gray-500 mb-1" { "Stan: " (product.condition.to_string()) }
+                                        @if let Some(size) = &product.size {
+                                            p ."text-xs text-gray-500 mb-1" { "Rozmiar: " (size) }
+                                        }
+                                        p ."text-xs text-gray-500 mb-2" { "Kategoria: " (product.category.to_string()) }
+                                    }
+
+                                    div ."mt-auto" {
+                                        @let is_in_cart = product_ids_in_cart.contains(&product.id);
+                                        @if is_in_cart {
+                                            (render_added_to_cart_button(product.id))
+                                        } @else if product.status == ProductStatus::Reserved {
+                                            (render_reserved_by_other_button(product.id))
+                                        } @else {
+                                            (render_add_to_cart_button(product.id))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // === SEKCJA PAGINACJI (W PEŁNI POPRAWIONA) ===
+                    @if total_pages > 1 {
+                        nav class="mt-8 flex items-center justify-center" aria-label="Paginacja" {
+                            div class="flex items-center space-x-1 sm:space-x-2" {
+
+                                // --- Przycisk "Poprzednia strona" ---
+                                @if current_page > 1 {
+                                    @let prev_offset = (current_page - 2) * per_page;
+                                    @let get_url = format!("/htmx/products?offset={}&limit={}{}", prev_offset, per_page, filter_query_string);
+                                    @let push_url = format!("{}?offset={}&limit={}{}", base_path, prev_offset, per_page, filter_query_string);
+
+                                    button type="button"
+                                           hx-get=(get_url)
+                                           hx-push-url=(push_url)
+                                           hx-target="#products-grid-container"
+                                           hx-swap="outerHTML"
+                                           hx-scroll="window:top"
+                                           class="px-3 sm:px-4 py-2 border rounded-md text-sm font-medium text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-pink-500" {
+                                        "Poprzednia"
+                                    }
+                                } @else {
+                                    // --- Wyłączony przycisk "Poprzednia strona" ---
+                                    span class="px-3 sm:px-4 py-2 border rounded-md text-sm font-medium text-gray-400 bg-gray-50 cursor-not-allowed" {
+                                        "Poprzednia"
+                                    }
+                                }
+
+                                // --- Numery stron wygenerowane PRZED makrem ---
+                                @let pagination_items = generate_pagination_items(current_page, total_pages, 2); // 2 to "okno" po bokach
+                                @for item in pagination_items {
+                                    @match item {
+                                        PaginationItem::Page(page_num) => {
+                                            @if page_num == current_page {
+                                                // --- Aktywna (bieżąca) strona ---
+                                                span class="z-10 px-3 sm:px-4 py-2 border border-[var(--color-primary)] rounded-md text-sm font-medium text-[var(--color-primary-text)] bg-[var(--color-primary)]"
+                                                aria-current="page" {
+                                                    (page_num)
+                                                }
+                                            } @else {
+                                                // --- Klikalny numer strony ---
+                                                @let offset = (page_num - 1) * per_page;
+                                                @let get_url = format!("/htmx/products?offset={}&limit={}{}", offset, per_page, filter_query_string);
+                                                @let push_url = format!("{}?offset={}&limit={}{}", base_path, offset, per_page, filter_query_string);
+
+                                                button type="button"
+                                                       hx-get=(get_url)
+                                                       hx-push-url=(push_url)
+                                                       hx-target="#products-grid-container"
+                                                       hx-swap="outerHTML"
+                                                       hx-scroll="window:top"
+                                                       class="px-3 sm:px-4 py-2 border rounded-md text-sm font-medium text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-pink-500" {
+                                                    (page_num)
+                                                }
+                                            }
+                                        },
+                                        PaginationItem::Dots => {
+                                            // --- Kropki jako separator ---
+                                            span class="px-1 sm:px-2 py-2 text-sm text-gray-500" { "..." }
+                                        }
+                                    }
+                                }
+
+                                // --- Przycisk "Następna strona" ---
+                                @if current_page < total_pages {
+                                    @let next_offset = current_page * per_page;
+                                    @let get_url = format!("/htmx/products?offset={}&limit={}{}", next_offset, per_page, filter_query_string);
+                                    @let push_url = format!("{}?offset={}&limit={}{}", base_path, next_offset, per_page, filter_query_string);
+
+                                    button type="button"
+                                           hx-get=(get_url)
+                                           hx-push-url=(push_url)
+                                           hx-target="#products-grid-container"
+                                           hx-swap="outerHTML"
+                                           hx-scroll="window:top"
+                                           class="px-3 sm:px-4 py-2 border rounded-md text-sm font-medium text-gray-700 bg-white hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-pink-500" {
+                                        "Następna"
+                                    }
+                                } @else {
+                                    // --- Wyłączony przycisk "Następna strona" ---
+                                    span class="px-3 sm:px-4 py-2 border rounded-md text-sm font-medium text-gray-400 bg-gray-50 cursor-not-allowed" {
+                                        "Następna"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Ścieżka publicznego adresu listingu (do `hx-push-url`), zależna od źródła, płci i kategorii
+fn listing_base_path(params: &ListingParams) -> String {
+    match params.source.as_deref() {
+        Some("nowosci") => "/nowosci".to_string(),
+        Some("okazje") => "/okazje".to_string(),
+        _ => gender_listing_path(
+            params.gender.as_ref().unwrap_or(&ProductGender::Damskie),
+            params.category.as_ref(),
+        ),
+    }
+}
+
+/// Publiczny adres listingu płci albo płci i kategorii, np. "/dla-niego/koszule"
+pub fn gender_listing_path(gender: &ProductGender, category: Option<&Category>) -> String {
+    match category {
+        Some(category) => format!("/{}/{}", gender_listing_slug(gender), category.as_ref()),
+        None => format!("/{}", gender_listing_slug(gender)),
+    }
+}
+
+/// Pozycja panelu filtrów: przełącza wartość filtra i przeładowuje siatkę.
+/// Wartości bez produktów są nieaktywne (chyba że są właśnie wybrane - wtedy można je odznaczyć).
+fn facet_option_maud(new_params: ListingParams, label: &str, count: i64, active: bool) -> Markup {
+    let new_params = ListingParams {
+        offset: None,
+        ..new_params
+    };
+    let get_url = format!(
+        "/htmx/products?{}",
+        build_full_query_string_from_params(&new_params)
+    );
+    let push_url = format!(
+        "{}?limit={}{}",
+        listing_base_path(&new_params),
+        new_params.limit(),
+        build_filter_only_query_string(&new_params)
+    );
+    html! {
+        li {
+            @if count == 0 && !active {
+                span aria-disabled="true"
+                     class="flex justify-between items-center px-2 py-1 rounded text-sm text-gray-400 cursor-not-allowed" {
+                    span { (label) }
+                    span ."text-xs" { "0" }
+                }
+            } @else {
+                button type="button"
+                       hx-get=(get_url)
+                       hx-push-url=(push_url)
+                       hx-target="#products-grid-container"
+                       hx-swap="outerHTML"
+                       aria-pressed=(if active { "true" } else { "false" })
+                       class=(format!("w-full flex justify-between items-center px-2 py-1 rounded text-sm transition-colors {}",
+                           if active { "bg-[var(--color-secondary)] text-[var(--text-color-primary)] font-semibold" } else { "text-gray-700 hover:bg-gray-100" })) {
+                    span { @if active { "✓ " } (label) }
+                    span ."text-xs text-gray-500" { (count) }
+                }
+            }
+        }
+    }
+}
+
+/// Zwijany panel filtrów z liczbą produktów przy każdej wartości (kategoria, stan, rozmiar, cena).
+fn render_facet_sidebar_maud(
+    params: &ListingParams,
+    facets: &FacetCounts,
+    per_page: i64,
+) -> Markup {
+    let base_params = ListingParams {
+        limit: Some(per_page),
+        ..params.clone()
+    };
+    let active_filters = [
+        params.category.is_some(),
+        params.condition.is_some(),
+        params.size().is_some(),
+        params.price_min.is_some() || params.price_max.is_some(),
+    ]
+    .into_iter()
+    .filter(|active| *active)
+    .count();
+    let selected_size = params.size().map(str::to_lowercase);
+    let mut sizes: Vec<(String, i64)> = facets.sizes.clone();
+    // Wybrany rozmiar zostaje na liście, nawet jeśli przy innych filtrach nie ma produktów
+    if let Some(size) = params.size() {
+        if !sizes
+            .iter()
+            .any(|(s, _)| s.to_lowercase() == size.to_lowercase())
+        {
+            sizes.push((size.to_string(), 0));
+        }
+    }
+    let clear_params = ListingParams {
+        category: None,
+        condition: None,
+        size: None,
+        price_min: None,
+        price_max: None,
+        ..base_params.clone()
+    };
+
+    html! {
+        aside #facet-sidebar ."xl:w-56 shrink-0" {
+            details class="bg-white border border-gray-200 rounded-lg"
+                    open[active_filters > 0]
+                    x-data x-init="if (window.innerWidth >= 1280) $el.open = true" {
+                summary ."px-4 py-3 cursor-pointer font-semibold text-gray-800 select-none" {
+                    "Filtry"
+                    @if active_filters > 0 {
+                        span ."ml-2 text-xs font-medium text-[var(--text-color-primary)]" { "(" (active_filters) ")" }
+                    }
+                }
+                div ."px-4 pb-4 space-y-4" {
+                    section {
+                        h3 ."text-xs font-semibold uppercase tracking-wide text-gray-500 mb-1" { "Kategoria" }
+                        ul ."space-y-0.5" {
+                            @for category in Category::iter() {
+                                @let active = params.category.as_ref() == Some(&category);
+                                (facet_option_maud(
+                                    ListingParams { category: if active { None } else { Some(category.clone()) }, ..base_params.clone() },
+                                    &category.to_string(),
+                                    facets.category_count(&category),
+                                    active,
+                                ))
+                            }
+                        }
+                    }
+                    section {
+                        h3 ."text-xs font-semibold uppercase tracking-wide text-gray-500 mb-1" { "Stan" }
+                        ul ."space-y-0.5" {
+                            @for condition in ProductCondition::iter() {
+                                @let active = params.condition.as_ref() == Some(&condition);
+                                (facet_option_maud(
+                                    ListingParams { condition: if active { None } else { Some(condition.clone()) }, ..base_params.clone() },
+                                    &condition.to_string(),
+                                    facets.condition_count(&condition),
+                                    active,
+                                ))
+                            }
+                        }
+                    }
+                    @if !sizes.is_empty() {
+                        section {
+                            h3 ."text-xs font-semibold uppercase tracking-wide text-gray-500 mb-1" { "Rozmiar" }
+                            ul ."space-y-0.5" {
+                                @for (size, count) in &sizes {
+                                    @let active = selected_size.as_deref() == Some(size.to_lowercase().as_str());
+                                    (facet_option_maud(
+                                        ListingParams { size: if active { None } else { Some(size.clone()) }, ..base_params.clone() },
+                                        size,
+                                        *count,
+                                        active,
+                                    ))
+                                }
+                            }
+                        }
+                    }
+                    section {
+                        h3 ."text-xs font-semibold uppercase tracking-wide text-gray-500 mb-1" { "Cena" }
+                        ul ."space-y-0.5" {
+                            @for (index, bucket) in PRICE_BUCKETS.iter().enumerate() {
+                                @let (price_min, price_max) = bucket.price_range();
+                                @let active = params.price_min == price_min && params.price_max == price_max;
+                                (facet_option_maud(
+                                    ListingParams {
+                                        price_min: if active { None } else { price_min },
+                                        price_max: if active { None } else { price_max },
+                                        ..base_params.clone()
+                                    },
+                                    bucket.label,
+                                    facets.price_buckets.get(index).copied().unwrap_or(0),
+                                    active,
+                                ))
+                            }
+                        }
+                    }
+                    @if active_filters > 0 {
+                        ul {
+                            (facet_option_maud(clear_params, "Wyczyść filtry", 1, false))
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn list_products_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(params): Query<ListingParams>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+
+    // --- NOWA LOGIKA TWORZENIA NAGŁÓWKÓW I TYTUŁU ---
+    let mut seo_header_markup = html! {};
+    let mut title_parts: Vec<String> = Vec::new();
+
+    // Część tytułu dla płci
+    if let Some(gender) = params.gender() {
+        let gender_display = if gender == ProductGender::Meskie {
+            "niego"
+        } else {
+            "niej"
+        };
+        title_parts.push(format!("Produkty dla {}", gender_display));
+    }
+
+    if let Some(category) = &params.category {
+        let (h1_text, h2_text) = get_seo_headers_for_category(category);
+        seo_header_markup = render_seo_header_maud(h1_text, h2_text);
+        title_parts.push(category.to_string());
+    }
+
+    // Dodaj część tytułu dla kategorii
+    if let Some(category) = params.category() {
+        title_parts.push(category.to_string());
+    }
+
+    // Dodaj czesc tytułu dla kategorii
+    if let Some(search_term) = params.search() {
+        if !search_term.is_empty() {
+            title_parts.push(format!("Szukaj: '{}'", search_term));
+        }
+    }
+
+    let dynamic_part = if title_parts.is_empty() {
+        "Wszystkie produkty".to_string()
+    } else {
+        title_parts.join(": ")
+    };
+    // Lista z płcią wskazuje jako kanoniczną stronę płci/kategorii - ta sama treść pod dwoma adresami
+    let canonical_path = match params.gender() {
+        Some(gender) => gender_listing_path(&gender, params.category().as_ref()),
+        None => "/kategoria".to_string(),
+    };
+    let description =
+        listing_meta_description(params.gender().as_ref(), params.category().as_ref());
+    let title = format!("{} - sklep mess - all that vintage", dynamic_part);
+    let social_meta = SocialMeta::new(
+        title.clone(),
+        description,
+        &app_state.public_base_url,
+        &canonical_path,
+    );
+    let (product_grid_markup, pagination_links) =
+        render_product_listing_page(app_state, params, product_ids_in_cart).await?;
+
+    let page_content = html! {
+        (seo_header_markup)
+        (product_grid_markup)
+    };
+    let page_builder = PageBuilder::new(&title, page_content, None, None)
+        .with_social_meta(social_meta)
+        .with_pagination_links(pagination_links);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Maksymalny rozmiar zdjęcia w wyszukiwaniu po zdjęciu
+const PHOTO_SEARCH_MAX_BYTES: usize = 10 * 1024 * 1024;
+pub const PHOTO_SEARCH_ADMIN_LIMIT: usize = 24;
+const PHOTO_SEARCH_PUBLIC_LIMIT: usize = 12;
+pub const PHOTO_SEARCH_THUMBNAIL_TRANSFORMATION: &str =
+    "w_400,h_400,c_fill,g_auto,f_auto,q_auto:good";
+
+/// Formularz wyszukiwania po zdjęciu - ten sam w panelu i w sklepie, różni się adresem wysyłki
+pub fn photo_search_form_maud(action: &str) -> Markup {
+    html! {
+        form hx-post=(action)
+             hx-encoding="multipart/form-data"
+             hx-target="#photo-search-results"
+             hx-swap="innerHTML"
+             hx-disabled-elt="find button[type='submit']"
+             class="flex flex-col sm:flex-row gap-3 sm:items-end p-4 bg-white rounded-lg border border-gray-200" {
+            div ."flex-1" {
+                label for="photo_search_file" ."block text-sm font-medium text-gray-700 mb-1" { "Zdjęcie (JPG, PNG lub WEBP, maks. 10 MB)" }
+                input #photo_search_file name="photo" type="file" accept="image/jpeg,image/png,image/webp" required
+                      class="block w-full text-sm text-gray-700 file:mr-3 file:py-2 file:px-4 file:rounded-md file:border-0 file:bg-pink-50 file:text-pink-700 hover:file:bg-pink-100";
+            }
+            button type="submit"
+                   class="px-5 py-2 text-sm font-medium text-white bg-pink-600 hover:bg-pink-700 rounded-md disabled:opacity-50" {
+                "Szukaj"
+            }
+        }
+    }
+}
+
+/// Hash zdjęcia z formularza albo komunikat dla użytkownika, gdy zdjęcia brak lub nie da się go odczytać
+pub async fn photo_search_hash(
+    multipart: &mut Multipart,
+) -> Result<Result<i64, &'static str>, AppError> {
+    let mut photo: Option<Vec<u8>> = None;
+    while let Some(field) = multipart.next_field().await? {
+        if field.name() == Some("photo") {
+            let bytes = field.bytes().await?;
+            if bytes.len() > PHOTO_SEARCH_MAX_BYTES {
+                return Ok(Err("Zdjęcie jest za duże - maksymalnie 10 MB."));
+            }
+            if !bytes.is_empty() {
+                photo = Some(bytes.to_vec());
+            }
+        }
+    }
+    let Some(photo) = photo else {
+        return Ok(Err("Wybierz zdjęcie."));
+    };
+    let hash = tokio::task::spawn_blocking(move || perceptual_hash(&photo))
+        .await
+        .map_err(|e| {
+            AppError::InternalServerError(format!("Błąd liczenia hasha zdjęcia: {}", e))
+        })?;
+    Ok(hash.ok_or("Nie udało się odczytać zdjęcia. Spróbuj pliku JPG lub PNG."))
+}
+
+pub fn photo_search_message_maud(message: &str) -> Markup {
+    html! {
+        p ."px-4 py-8 text-center text-gray-500 italic bg-white rounded-lg border border-gray-200" { (message) }
+    }
+}
+
+/// Podobieństwo zdjęć w procentach (64 bity pHash)
+pub fn image_similarity_percent(distance: u32) -> u32 {
+    (64 - distance.min(64)) * 100 / 64
+}
+
+/// Funkcja, która renderuje stronę 'Nowości'
+/// Znak wodny nakładany na zdjęcia w archiwum sprzedanych. Oryginałów nie pokazujemy,
+/// żeby zrzutów ekranu nie dało się wykorzystać w fałszywych ogłoszeniach.
+const SOLD_ARCHIVE_IMAGE_TRANSFORMATION: &str = "c_fill,w_600,h_750,f_auto,q_auto/l_text:Arial_110_bold:SPRZEDANE,co_white,o_60,a_-35/fl_layer_apply,fl_tiled";
+
+/// Publiczne archiwum sprzedanych produktów (tylko te, które admin do niego dodał).
+pub async fn sold_archive_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    let products = repo::products::sold_archive(&app_state.db_pool, 120).await?;
+
+    let page_content = html! {
+        div ."max-w-6xl mx-auto px-4 py-8" {
+            h1 ."text-3xl font-bold text-gray-900 mb-2" { "Archiwum sprzedanych" }
+            p ."text-gray-600 mb-8" { "Rzeczy, które znalazły już nowy dom. Zajrzyj do nowości, żeby nie przegapić kolejnych perełek." }
+            @if products.is_empty() {
+                p ."text-gray-500" { "Archiwum jest jeszcze puste." }
+            } @else {
+                div ."grid grid-cols-2 sm:grid-cols-3 lg:grid-cols-4 gap-4" {
+                    @for product in &products {
+                        div ."bg-white rounded-lg shadow-sm overflow-hidden" {
+                            @if let Some(image_url) = product.images.get(0) {
+                                img src=(transform_cloudinary_url(image_url, SOLD_ARCHIVE_IMAGE_TRANSFORMATION))
+                                    alt=(product.name) loading="lazy"
+                                    class="w-full aspect-[4/5] object-cover select-none" draggable="false";
+                            } @else {
+                                div class="w-full aspect-[4/5] bg-gray-100 flex items-center justify-center text-xs text-gray-400" { "Brak zdjęcia" }
+                            }
+                            div ."p-3" {
+                                p ."text-sm font-medium text-gray-800 truncate" { (product.name) }
+                                p ."text-xs text-gray-500" { (product.category.to_string()) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Archiwum sprzedanych - sklep mess - all that vintage",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn news_page_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(params): Query<ListingParams>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    tracing::info!("MAUD: Obsługa publicznego URL /nowosci");
+
+    // Definiujemy teksty dla tej konkretnej strony
+    let title = "Nowości - sklep mess - all that vintage";
+    let h1_text = "Nowości w mess - all that vintage – świeże perełki czekają";
+    let h2_text =
+        "Sprawdź najnowsze dodatki i ubrania vintage, które właśnie trafiły do naszej kolekcji";
+    let seo_header_markup = render_seo_header_maud(h1_text, h2_text);
+
+    // ZMIANA 2: Pobieramy zawartość koszyka przed renderowaniem widoku
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+
+    // Łączymy parametry z URL z tymi wymaganymi dla "Nowości"
+    let final_params = ListingParams {
+        sort_by: params.sort_by.or_else(|| Some("created_at".to_string())),
+        order: params.order.or_else(|| Some("desc".to_string())),
+        limit: params.limit.or(Some(8)),
+        offset: params.offset,
+        source: Some("nowosci".to_string()), // Ustawiamy źródło
+        ..params                             // Klonujemy resztę parametrów z URL
+    };
+
+    let (product_grid_markup, pagination_links) =
+        render_product_listing_page(app_state.clone(), final_params, product_ids_in_cart).await?;
+    let page_content = html! {
+        (seo_header_markup)
+        (product_grid_markup)
+    };
+    let social_meta = SocialMeta::new(title, h2_text, &app_state.public_base_url, "/nowosci");
+    let page_builder = PageBuilder::new(title, page_content.clone(), None, None)
+        .with_social_meta(social_meta)
+        .with_pagination_links(pagination_links);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn sale_page_htmx_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(params): Query<ListingParams>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    tracing::info!("MAUD: Obsługa publicznego URL /okazje");
+    let final_params = ListingParams {
+        on_sale: Some(true),
+        status: Some(ProductStatus::Available.as_ref().to_string()),
+        limit: params.limit.or(Some(8)),
+        offset: params.offset,
+        source: Some("okazje".to_string()), // Ustawiamy źródło
+        ..params                            // Klonujemy resztę
+    };
+    // Definiujemy teksty dla tej strony
+    let h1_text = "Wyjątkowe okazje – moda vintage w najlepszych cenach";
+    let h2_text = "Upoluj stylowe ubrania i dodatki pre-owned w jeszcze lepszych cenach";
+    let seo_header_markup = render_seo_header_maud(h1_text, h2_text);
+
+    // --- NOWA LOGIKA POBIERANIA KOSZYKA ---
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+    // --- KONIEC NOWEJ LOGIKI ---
+
+    let (product_grid_markup, pagination_links) =
+        render_product_listing_page(app_state.clone(), final_params, product_ids_in_cart).await?;
+    let page_content = html! {
+        (seo_header_markup)
+        (product_grid_markup)
+    };
+    let page_content_str = page_content.into_string();
+
+    let title = "Okazje - sklep mess - all that vintage";
+    let social_meta = SocialMeta::new(title, h2_text, &app_state.public_base_url, "/okazje");
+    let page_builder = PageBuilder::new(
+        title,
+        html! { (maud::PreEscaped(page_content_str)) },
+        None,
+        None,
+    )
+    .with_social_meta(social_meta)
+    .with_pagination_links(pagination_links);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn render_product_listing_view(
+    app_state: Arc<AppState>,
+    params: ListingParams,
+    product_ids_in_cart: Vec<Uuid>,
+) -> Result<Markup, AppError> {
+    render_product_listing_page(app_state, params, product_ids_in_cart)
+        .await
+        .map(|(markup, _)| markup)
+}
+
+/// Siatka listingu razem z linkami `rel="prev"/"next"` dla pełnej strony
+async fn render_product_listing_page(
+    app_state: Arc<AppState>,
+    params: ListingParams,
+    product_ids_in_cart: Vec<Uuid>,
+) -> Result<(Markup, PaginationLinks), AppError> {
+    tracing::info!("MAUD: /htmx/products z parametrami: {:?}", params);
+
+    // Konwersja ID produktów w koszyku na JSON dla Alpine.js (bez zmian)
+    let cart_product_ids_json =
+        serde_json::to_string(&product_ids_in_cart).unwrap_or_else(|_| "[]".to_string());
+    let (paginated_response_axum_json, facets) = tokio::try_join!(
+        crate::handlers::list_products(State(app_state.clone()), Query(params.clone())),
+        get_facet_counts(&app_state.db_pool, &params),
+    )?;
+    let paginated_response = paginated_response_axum_json.0;
+    let lowest_prices =
+        lowest_prices_for_sale_products(&app_state.db_pool, &paginated_response.data).await?;
+    let pagination_links = listing_pagination_links(
+        &app_state,
+        &params,
+        paginated_response.current_page,
+        paginated_response.total_pages,
+    );
+
+    // Renderowanie widoku (bez zmian)
+    let markup = html!(
+        // Przekazujemy stan koszyka do Alpine.js
+        script #cart-state-data type="application/json" {
+            (PreEscaped(cart_product_ids_json))
+        }
+        // Renderujemy siatkę produktów na podstawie danych (z cache'u lub z bazy)
+        (render_product_grid_maud(
+            &paginated_response.data,
+            &paginated_response,
+            &params,
+            &product_ids_in_cart,
+            &facets,
+            &lowest_prices,
+        ))
+    );
+    Ok((markup, pagination_links))
+}
+
+/// Linki `rel="prev"/"next"` w formacie adresów przycisków paginacji siatki
+fn listing_pagination_links(
+    app_state: &AppState,
+    params: &ListingParams,
+    current_page: i64,
+    total_pages: i64,
+) -> PaginationLinks {
+    let per_page = params.limit();
+    let base_url = format!("{}{}", app_state.public_base_url, listing_base_path(params));
+    let filter_query_string = build_filter_only_query_string(params);
+    PaginationLinks::new(current_page, total_pages, per_page, |offset| {
+        format!(
+            "{}?offset={}&limit={}{}",
+            base_url, offset, per_page, filter_query_string
+        )
+    })
+}
+
+/// GET /rezerwacja/{token} - osobisty link zakupu produktu zarezerwowanego dla klienta
+pub async fn product_hold_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Path(token): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let hold = find_active_hold_by_token(&app_state.db_pool, token).await?;
+    let product = match &hold {
+        Some(hold) => {
+            repo::products::find_by_id_with_status(
+                &app_state.db_pool,
+                hold.product_id,
+                ProductStatus::Reserved,
+            )
+            .await?
+        }
+        None => None,
+    };
+
+    let page_content = html! {
+        div ."max-w-3xl mx-auto px-4 py-10" {
+            @if let (Some(hold), Some(product)) = (&hold, &product) {
+                h1 ."text-2xl sm:text-3xl font-bold text-gray-800 mb-2 text-center" { "Zarezerwowane dla Ciebie" }
+                p ."text-sm text-gray-500 mb-8 text-center" {
+                    "Ten produkt czeka na Ciebie do " (format_datetime_long(&hold.expires_at)) ". Dodaj go do koszyka i złóż zamówienie jak zwykle."
+                }
+                div ."flex flex-col sm:flex-row gap-6 bg-white rounded-lg shadow-sm border border-gray-200 p-4" {
+                    @if let Some(image) = product.images.first() {
+                        img src=(transform_cloudinary_url(image, "w_400,h_400,c_fill,f_auto,q_auto"))
+                            alt=(product.name)
+                            class="w-full sm:w-48 h-48 object-cover rounded-md";
+                    }
+                    div ."flex flex-col justify-between flex-1 gap-4" {
+                        div {
+                            a href=(product.public_path()) class="text-lg font-semibold text-gray-800 hover:underline" { (product.name) }
+                            p ."text-xl font-bold text-gray-900 mt-1" { (product_price_maud(product)) }
+                            @if let Some(size) = &product.size {
+                                p ."text-sm text-gray-500 mt-1" { "Rozmiar: " (size) }
+                            }
+                        }
+                        button id=(format!("product-cart-button-{}", product.id))
+                               type="button"
+                               hx-post=(format!("/htmx/cart/toggle/{}?hold={}", product.id, hold.token))
+                               hx-target=(format!("#product-cart-button-{}", product.id))
+                               hx-swap="outerHTML"
+                               class="w-full text-[var(--color-primary-text)] font-medium py-2 px-4 rounded-lg transition-all duration-200 ease-in-out bg-[var(--color-primary)] hover:bg-[var(--color-primary-hover)]" {
+                            "Dodaj do koszyka"
+                        }
+                    }
+                }
+            } @else {
+                div ."text-center" {
+                    h1 ."text-2xl font-bold text-gray-800 mb-2" { "Rezerwacja nieaktywna" }
+                    p ."text-gray-600 mb-6" {
+                        "Link wygasł albo produkt nie jest już zarezerwowany. Napisz do nas, jeśli nadal go szukasz."
+                    }
+                    a href="/kontakt" class="text-pink-700 hover:underline" { "Kontakt" }
+                }
+            }
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Twoja rezerwacja - sklep mess - all that vintage",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+pub async fn live_search_handler(
+    State(app_state): State<Arc<AppState>>,
+    Query(params): Query<ListingParams>,
+) -> Result<Markup, AppError> {
+    // Sprawdź, czy zapytanie nie jest puste. Jeśli jest, zwróć pusty HTML.
+    let search_query = match params.search {
+        Some(q) if !q.trim().is_empty() => q,
+        _ => return Ok(html! {}),
+    };
+
+    tracing::info!("LIVE SEARCH: Szukanie dla '{}'", search_query);
+
+    let search_params = ListingParams {
+        search: Some(search_query.clone()),
+        limit: Some(5),
+        source: Some("search".to_string()),
+        ..Default::default()
+    };
+    let hits = search_products_with_snippets(&app_state.db_pool, search_query.trim(), 5).await?;
+
+    // Przygotowujemy parametry powrotu DLA WSZYSTKICH linków w tej odpowiedzi
+    let return_params_qs = build_full_query_string_from_params(&search_params);
+    let encoded_return_params = urlencoding::encode(&return_params_qs);
+
+    Ok(html! {
+        @if hits.is_empty() {
+            // Komunikat, gdy nic nie znaleziono
+            div class="p-4 text-sm text-gray-500 text-center" {
+                "Brak wyników."
+            }
+        } @else {
+            // Lista znalezionych produktów
+            ul class="divide-y divide-gray-100" {
+                @for hit in &hits {
+                    li {
+                        a href=(format!("/produkty/{}", hit.slug))
+                           hx-get=(format!("/htmx/produkt/{}?return_params={}", hit.slug, encoded_return_params))
+                           hx-target="#content"
+                           hx-swap="innerHTML"
+                           hx-push-url=(format!("/produkty/{}", hit.slug))
+                           class="flex items-center p-3 hover:bg-gray-50 transition-colors"
+                           "@click"="hasResults = false; hasMobileResults = false; isMobileMenuOpen = false"
+
+                        {
+                            // Miniaturka obrazka
+                            @if let Some(image_url) = hit.images.first() {
+                                img src=(image_url) alt=(hit.name) class="h-12 w-12 rounded-md object-cover flex-shrink-0";
+                            } @else {
+                                div class="h-12 w-12 rounded-md bg-gray-200 flex-shrink-0" {}
+                            }
+                            // Nazwa, fragment opisu z dopasowaniem i cena
+                            div class="ml-4 flex-1 overflow-hidden" {
+                                p class="text-sm font-medium text-gray-900 truncate" { (render_highlighted(&hit.name_highlighted)) }
+                                @if let Some(snippet) = &hit.snippet {
+                                    p class="text-xs text-gray-500 truncate" { "…" (render_highlighted(snippet)) "…" }
+                                }
+                                p class="text-sm text-gray-500" { (format_price_maud(hit.price)) }
+                            }
+                        }
+                    }
+                }
+                li {
+                    a href=(format!("/wyszukiwanie?search={}", urlencoding::encode(search_query.trim())))
+                       class="block p-3 text-center text-sm font-medium text-pink-600 hover:bg-gray-50"
+                       "@click"="hasResults = false; hasMobileResults = false; isMobileMenuOpen = false"
+                    { "Pokaż wszystkie wyniki" }
+                }
+            }
+        }
+    })
+}
+
+pub async fn search_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(mut params): Query<ListingParams>, // Pobiera parametry z URL, np. ?search=Biała
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    let search_term = params.search().unwrap_or_default();
+    tracing::info!(
+        "MAUD: Obsługa strony wyszukiwania dla frazy: '{}'",
+        search_term
+    );
+    // KLUCZOWA ZMIANA: Ustawiamy źródło na "search"
+    params.source = Some("search".to_string());
+
+    // Pobieramy stan koszyka, aby przyciski "Dodaj do koszyka" miały poprawny stan
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+
+    // Wywołujemy naszą reużywalną funkcję do renderowania siatki produktów,
+    // przekazując jej parametry wyszukiwania i stan koszyka.
+    let page_content = html! {
+        div ."mb-8" {
+            h1 ."text-2xl sm:text-3xl font-bold text-gray-800" {
+                "Wyniki wyszukiwania dla: "
+                span ."text-pink-600" { (search_term) }
+            }
+            a href="/wyszukaj-podobne" hx-get="/wyszukaj-podobne" hx-target="#content" hx-swap="innerHTML" hx-push-url="true"
+              class="inline-block mt-2 text-sm text-pink-700 hover:underline" {
+                "Masz zdjęcie? Wyszukaj podobne produkty"
+            }
+        }
+        // render_product_listing_view zwróci nam gotową siatkę produktów z paginacją
+        (render_product_listing_view(
+            app_state.clone(),
+            params.clone(),
+            product_ids_in_cart,
+        ).await?)
+    };
+
+    let title = format!(
+        "Wyniki dla: {} - sklep mess - all that vintage",
+        search_term
+    );
+    let page_builder = PageBuilder::new(&title, page_content, None, None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// GET /wyszukaj-podobne - publiczne wyszukiwanie po zdjęciu ("widziałam to na Instagramie")
+pub async fn photo_search_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+) -> Result<Response, AppError> {
+    let page_content = html! {
+        div ."max-w-5xl mx-auto px-4 py-10" {
+            div ."text-center mb-6" {
+                h1 ."text-2xl sm:text-3xl font-bold text-gray-800" { "Wyszukaj podobne" }
+                p ."text-sm text-gray-500 mt-2" {
+                    "Widzisz coś na naszym Instagramie albo masz zdjęcie rzeczy, której szukasz? Wgraj je, a pokażemy podobne produkty dostępne w sklepie."
+                }
+            }
+            (photo_search_form_maud("/htmx/wyszukaj-podobne"))
+            div #photo-search-results ."mt-8" {}
+        }
+    };
+
+    let page_builder = PageBuilder::new(
+        "Wyszukaj podobne - sklep mess - all that vintage",
+        page_content,
+        None,
+        None,
+    );
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// POST /htmx/wyszukaj-podobne - produkty widoczne w sklepie z podobnym zdjęciem
+pub async fn photo_search_results_htmx_handler(
+    State(app_state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Result<Markup, AppError> {
+    let hash = match photo_search_hash(&mut multipart).await? {
+        Ok(hash) => hash,
+        Err(message) => return Ok(photo_search_message_maud(message)),
+    };
+    let statuses = [ProductStatus::Available, ProductStatus::Reserved];
+    let results = find_similar_products(
+        &app_state.db_pool,
+        hash,
+        &statuses,
+        PHOTO_SEARCH_PUBLIC_LIMIT,
+    )
+    .await?;
+    tracing::info!("Wyszukiwanie po zdjęciu: {} wyników", results.len());
+
+    Ok(html! {
+        @if results.is_empty() {
+            (photo_search_message_maud("Nie mamy teraz nic podobnego - zajrzyj do nowości, asortyment zmienia się codziennie."))
+        } @else {
+            div ."grid grid-cols-2 sm:grid-cols-3 lg:grid-cols-4 gap-6" {
+                @for (product, _) in &results {
+                    a href=(product.public_path())
+                      hx-get=(format!("/htmx/produkt/{}", product.slug))
+                      hx-target="#content" hx-swap="innerHTML" hx-push-url=(product.public_path())
+                      class="group block bg-white rounded-lg shadow-sm border border-gray-200 overflow-hidden hover:shadow-md transition-shadow" {
+                        @if let Some(image_url) = product.images.first() {
+                            img src=(transform_cloudinary_url(image_url, PHOTO_SEARCH_THUMBNAIL_TRANSFORMATION))
+                                alt=(product.name) class="w-full aspect-square object-cover group-hover:opacity-90" loading="lazy";
+                        }
+                        div ."p-3" {
+                            p ."text-sm font-medium text-gray-800 truncate" { (product.name) }
+                            p ."text-sm font-semibold text-[var(--text-color-primary)]" { (product_price_maud(product)) }
+                            @if product.status == ProductStatus::Reserved {
+                                p ."text-xs text-gray-500" { "Zarezerwowany" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Obsługuje pełne załadowanie strony głównej ("/").
+/// Pobiera stan koszyka, aby poprawnie wyrenderować przyciski "Dodaj do koszyka",
+/// renderuje sekcje ustawione w panelu (baner, wyróżnione) oraz początkową listę produktów.
+pub async fn home_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Query(params): Query<ListingParams>,
+    OptionalTokenClaims(user_claims_opt): OptionalTokenClaims,
+    OptionalGuestCartId(guest_cart_id_opt): OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    let title = "mess - all that vintage - Sklep Vintage Online";
+    let cache_key = is_default_listing_request(&params).then(|| HOME_LISTING_CACHE_KEY.to_string());
+    let final_params = home_listing_params(params);
+
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt, guest_cart_id_opt).await?;
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+
+    // Generowanie danych strukturalnych dla organizacji
+    let org_schema = SchemaOrganization {
+        context: "https://schema.org",
+        type_of: "Organization",
+        name: "mess - all that vintage",
+        url: "https://messvintage.com",
+        logo: "https://messvintage.com/static/main-logo.avif",
+        address: SchemaAddress {
+            type_of: "PostalAddress",
+            street_address: "Piotrkowska 104",
+            address_locality: "Łódź",
+            postal_code: "90-001",
+            address_country: "PL",
+        },
+        email: "contact@messvintage.com",
+        telephone: "+48603117793",
+    };
+
+    let website_schema = SchemaWebSite {
+        context: "https://schema.org",
+        type_of: "WebSite",
+        url: "https://messvintage.com",
+        potential_action: SchemaSearchAction {
+            type_of: "SearchAction",
+            target: "https://messvintage.com/wyszukiwanie?search={search_term_string}".to_string(),
+            query_input: "required name=search_term_string",
+        },
+    };
+
+    let org_json_ld = serde_json::to_string(&org_schema).unwrap_or_default();
+    let website_json_ld = serde_json::to_string(&website_schema).unwrap_or_default();
+
+    let json_ld_org = serde_json::to_string(&org_schema).unwrap_or_default();
+    let head_content = html! {
+        script type="application/ld+json" { (PreEscaped(org_json_ld)) }
+        script type="application/ld+json" { (PreEscaped(website_json_ld)) }
+        script type="application/ld+json" { (PreEscaped(json_ld_org)) }
+    };
+
+    // Renderowanie siatki produktów (pierwsza strona z pustym koszykiem idzie z cache'u)
+    let cache_key = cache_key.filter(|_| product_ids_in_cart.is_empty());
+    let product_listing_view = with_listing_fragment_cache(
+        &app_state,
+        cache_key,
+        render_product_listing_view(app_state.clone(), final_params, product_ids_in_cart),
+    )
+    .await?;
+
+    let homepage_sections = with_listing_fragment_cache(
+        &app_state,
+        Some(HOMEPAGE_SECTIONS_CACHE_KEY.to_string()),
+        render_homepage_sections(&app_state),
+    )
+    .await?;
+
+    let page_content = html! {
+        (homepage_sections)
+        (product_listing_view)
+    };
+
+    let page_builder = PageBuilder::new(title, page_content, Some(head_content), None);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Sekcje strony głównej nad listą nowości, w kolejności i z treścią ustawioną w panelu
+async fn render_homepage_sections(app_state: &AppState) -> Result<Markup, AppError> {
+    let sections = list_homepage_sections(&app_state.db_pool).await?;
+    let mut markup = String::new();
+    for section in sections.iter().filter(|section| section.enabled) {
+        let rendered = match section.section {
+            HomepageSection::Hero => render_home_page_hero(section),
+            HomepageSection::Featured => {
+                let products = featured_products(&app_state.db_pool, true).await?;
+                render_featured_products_carousel(section, &products)
+            }
+            HomepageSection::Popular => {
+                let products = popular_products(&app_state.db_pool, POPULAR_PRODUCTS_LIMIT).await?;
+                render_featured_products_carousel(section, &products)
+            }
+        };
+        markup.push_str(&rendered.into_string());
+    }
+    Ok(PreEscaped(markup))
+}
+
+/// Baner strony głównej: zdjęcie z opcjonalnym nagłówkiem H1, podtytułem i linkiem
+fn render_home_page_hero(section: &HomepageSectionConfig) -> Markup {
+    let Some(image_url) = &section.image_url else {
+        return html! {};
+    };
+    let alt = section
+        .title
+        .as_deref()
+        .unwrap_or("mess - all that vintage");
+    let banner = html! {
+        // 1. Kontener definiuje kształt, proporcje i zaokrąglenie
+        div class="relative aspect-[3/1] rounded-2xl overflow-hidden mb-8" {
+            // 2. Obrazek wypełnia ten kontener
+            img src=(image_url) alt=(alt) class="absolute w-full h-full object-cover" fetchpriority="high";
+            @if section.title.is_some() || section.subtitle.is_some() {
+                div class="absolute inset-0 flex flex-col items-center justify-center text-center px-4 bg-black/25" {
+                    @if let Some(title) = &section.title {
+                        h1 ."text-2xl sm:text-4xl lg:text-5xl font-bold text-white drop-shadow" { (title) }
+                    }
+                    @if let Some(subtitle) = &section.subtitle {
+                        p ."mt-2 text-sm sm:text-lg text-white drop-shadow" { (subtitle) }
+                    }
+                }
+            }
+        }
+    };
+    html! {
+        @if let Some(link) = &section.link_url {
+            a href=(link) class="block" { (banner) }
+        } @else {
+            (banner)
+        }
+    }
+}
+
+/// Karuzela produktów (wyróżnione, najpopularniejsze) przewijana poziomo; bez dostępnych
+/// produktów jej nie ma
+fn render_featured_products_carousel(
+    section: &HomepageSectionConfig,
+    products: &[Product],
+) -> Markup {
+    html! {
+        @if !products.is_empty() {
+            section ."mb-10" {
+                @if let Some(title) = &section.title {
+                    h2 ."text-xl sm:text-2xl font-semibold text-gray-800 mb-4" { (title) }
+                }
+                div ."flex gap-4 overflow-x-auto snap-x snap-mandatory pb-2" {
+                    @for product in products {
+                        (product_tile_maud(product, "snap-start shrink-0 w-40 sm:w-52"))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Informacja o najniższej cenie z 30 dni przed obniżką (dyrektywa Omnibus)
+fn lowest_price_note_maud(lowest_price: i64) -> Markup {
+    html! { "najniższa cena z 30 dni: " (format_price_maud(lowest_price)) }
+}
+
+/// Mały kafelek produktu (zdjęcie, nazwa, cena) - karuzela strony głównej i propozycje na 404
+pub fn product_tile_maud(product: &Product, extra_classes: &str) -> Markup {
+    html! {
+        a href=(product.public_path())
+          hx-get=(format!("/htmx/produkt/{}", product.slug))
+          hx-target="#content" hx-swap="innerHTML" hx-push-url=(product.public_path())
+          class={"group bg-white rounded-lg border border-gray-200 overflow-hidden hover:border-gray-300 " (extra_classes)} {
+            @if let Some(image_url) = product.images.first() {
+                img src=(transform_cloudinary_url(image_url, "w_400,h_400,c_fill,g_auto,f_auto,q_auto:best"))
+                    alt=(product.name) class="w-full aspect-square object-cover group-hover:opacity-90"
+                    loading="lazy" width="400" height="400";
+            } @else {
+                div ."w-full aspect-square bg-gray-200 flex items-center justify-center" {
+                    span ."text-gray-500 text-sm" { "Brak zdjęcia" }
+                }
+            }
+            div ."p-3" {
+                p ."text-sm font-medium text-gray-800 truncate group-hover:text-pink-600" { (product.name) }
+                p ."text-sm text-gray-700" { (product_price_maud(product)) }
+            }
+        }
+    }
+}
+
+/// Zwraca krotkę z tekstami (H1, H2) dla danej kategorii.
+fn get_seo_headers_for_category(category: &Category) -> (&'static str, &'static str) {
+    match category {
+        Category::Koszule => (
+            "Stylowe koszule  – moda z klasą",
+            "Odkryj wyjątkowe koszule vintage i casual w atrakcyjnych cenach",
+        ),
+        Category::Spodnie => (
+            "Spodnie od mess – komfort i styl każdego dnia",
+            "Wybierz modne spodnie damskie i męskie w duchu slow fashion",
+        ),
+        Category::Sukienki => (
+            "Sukienki vintage – ponadczasowa elegancja",
+            "Znajdź swoją wymarzoną sukienkę vintage i nadaj jej nowe życie",
+        ),
+        Category::Spodnice => (
+            "Spódnice vintage – kobiecość w stylu retro",
+            "Przeglądaj klasyczne i nowoczesne fasony spódnic",
+        ),
+        Category::Swetry => (
+            "Ciepłe swetry – styl i wygoda",
+            "Znajdź sweter idealny na chłodne dni – od oversize po klasykę",
+        ),
+        Category::Bluzy => (
+            "Bluzy mess – wygoda w dobrym stylu",
+            "Odkryj modne bluzy vintage i streetwear w świetnym stanie",
+        ),
+        Category::KurtkiPlaszcze => (
+            "Kurtki i płaszcze vintage – klasyka na każdą porę roku",
+            "Postaw na modę z przeszłości – klasyczne płaszcze i modne kurtki",
+        ),
+        Category::MarynarkiZakiety => (
+            "Marynarki i żakiety – szyk i elegancja",
+            "Wybierz unikalne marynarki vintage i żakiety na każdą okazję",
+        ),
+        Category::Obuwie => (
+            "Obuwie od mess – stylowe buty z charakterem",
+            "Znajdź wyjątkowe obuwie w doskonałym stanie i dobrej cenie",
+        ),
+        Category::Torebki => (
+            "Torebki vintage – wyjątkowe dodatki z historią",
+            "Odkryj eleganckie i praktyczne torebki vintage do każdej stylizacji",
+        ),
+        Category::Akcesoria => (
+            "Akcesoria typu vintage – detale, które tworzą styl",
+            "Dodaj charakteru swoim stylizacjom dzięki wyjątkowym dodatkom",
+        ),
+        Category::Bielizna => (
+            "Bielizna vintage – subtelność i wygoda na co dzień",
+            "Oferujemy starannie wyselekcjonowaną bieliznę w idealnym stanie",
+        ),
+        Category::StrojeKapielowe => (
+            "Stroje kąpielowe vintage – plażowy szyk w duchu slow fashion",
+            "Odkryj unikalne fasony idealne na lato",
+        ),
+        Category::Inne => (
+            "Rzeczy unikatowe od mess - sklep, który zaskakuje",
+            "Znajdź perełki spoza schematów – nietypowe i rzadkie ubrania oraz dodatki",
+        ),
+    }
+}
+
+/// Renderuje ostylowany blok z nagłówkami H1 i H2.
+fn render_seo_header_maud(h1_text: &str, h2_text: &str) -> Markup {
+    // Spróbuj znaleźć słowa kluczowe do podświetlenia
+    let keyword_to_highlight = match true {
+        _ if h1_text.contains("mess") => "mess",
+        _ if h1_text.contains("secondhand") => "secondhand",
+        _ if h1_text.contains("vintage") => "vintage",
+        _ if h1_text.contains("pre-owned") => "pre-owned",
+        _ if h1_text.contains("slow fashion") => "slow fashion",
+        _ if h1_text.contains("moda z klasą") => "moda z klasą",
+        _ if h1_text.contains("z drugiego obiegu") => "z drugiego obiegu",
+        _ if h1_text.contains("styl") => "styl",
+        _ => "z drugiej ręki",
+    };
+    html! {
+        div class="text-center mb-6 md:mb-12" {
+            h1 class="text-2xl sm:text-3xl font-bold tracking-tight text-gray-900" {
+                (highlight_keyword(h1_text, keyword_to_highlight))
+            }
+            h2 class="mt-3 text-lg text-gray-600 max-w-2xl mx-auto" { (h2_text) }
+        }
+    }
+}
+
+/// Funkcja pomocnicza do podświetlania słowa kluczowego w tekście.
+fn highlight_keyword(text: &str, keyword: &str) -> Markup {
+    if let Some(index) = text.to_lowercase().find(&keyword.to_lowercase()) {
+        let start = &text[..index];
+        let highlighted = &text[index..index + keyword.len()];
+        let end = &text[index + keyword.len()..];
+        html! {
+            (start)
+            span class="text-[var(--text-color-primary)]" { (highlighted) }
+            (end)
+        }
+    } else {
+        // Jeśli słowo kluczowe nie zostanie znalezione, zwróć oryginalny tekst.
+        html! { (text) }
+    }
+}
+
+/// Renderuje ostylowany baner "Darmowa dostawa".
+fn render_free_shipping_banner_maud() -> Markup {
+    html! {
+        div class="p-3 sm:p-4 bg-[var(--color-secondary)] border border-[var(--color-primary)] rounded-xl text-[var(--text-color-primary-hover)] shadow-sm text-pink-800 flex items-center justify-center gap-x-3 sm:gap-x-4 h-full lg:max-w-2xl mx-auto" {
+            div class="flex-shrink-0" {
+                svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="2" stroke="currentColor" class="w-6 h-6 sm:w-7 sm:h-7" {
+                  path stroke-linecap="round" stroke-linejoin="round" d="M8.25 18.75a1.5 1.5 0 0 1-3 0m3 0a1.5 1.5 0 0 0-3 0m3 0h6m-9 0H3.375a1.125 1.125 0 0 1-1.125-1.125V14.25m17.25 4.5a1.5 1.5 0 0 1-3 0m3 0a1.5 1.5 0 0 0-3 0m3 0h1.125c.621 0 1.129-.504 1.09-1.124a17.902 17.902 0 0 0-3.213-9.193 2.056 2.056 0 0 0-1.58-.86H14.25M16.5 18.75h-2.25m0-11.177v-.958c0-.568-.422-1.048-.987-1.106a48.554 48.554 0 0 0-10.026 0 1.106 1.106 0 0 0-.987 1.106v7.635m12-6.677v6.677m0 4.5v-4.5m0 0h-12";
+                }
+            }
+            // Tekst - dodajemy większą wagę czcionki
+            div {
+                p class="font-semibold whitespace-nowrap text-base sm:text-lg" {
+                    "Darmowa dostawa od " (format_price_maud(free_shipping_threshold())) "!"
+                }
+            }
+        }
+    }
+}
+
+/// Renderuje w pełni funkcjonalny, responsywny i reużywalny panel boczny z kategoriami.
+fn render_category_sidebar_maud(
+    gender_slug: &str,
+    current_category: Option<&Category>, // Przyjmuje opcjonalną referencję do aktywnej kategorii
+    available_categories: &[Category],
+) -> Markup {
+    html! {
+        // --- GŁÓWNY KONTENER PANELU ---
+        // Jest widoczny jako blok na desktopie (`md:block`) i lepki (`md:sticky`)
+        aside #category-sidebar
+              class="w-full md:w-1/4 lg:w-1/5 bg-white md:bg-gray-50 md:p-4 md:border md:border-gray-200 md:rounded-lg md:shadow-sm md:sticky md:top-20 md:self-start" {
+
+            // --- PRZYCISK WIDOCZNY TYLKO NA MOBILE ---
+            // Służy do rozwijania/zwijania listy kategorii.
+            div ."md:hidden p-4 border-b border-gray-200 bg-gray-50" {
+                button type="button"
+                       "@click"="isCategorySidebarOpen = !isCategorySidebarOpen"
+                       class="w-full flex justify-center items-center px-3 py-2 rounded-md text-gray-700 hover:bg-gray-100 focus:outline-none font-semibold" {
+                    span { "Kategorie " }
+                    svg class="w-5 h-5 ml-2 transform transition-transform duration-250"
+                        x-bind:class="{ 'rotate-180': isCategorySidebarOpen }"
+                        fill="none" stroke="currentColor" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg" {
+                        path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M19 9l-7 7-7-7";
+                    }
+                }
+            }
+
+            // --- WEWNĘTRZNY KONTENER Z ANIMACJĄ I TREŚCIĄ ---
+            // Ten kontener jest widoczny na desktopie LUB gdy jest rozwinięty na mobile.
+            // Posiada dyrektywy `x-transition` dla płynnej animacji akordeonu.
+            div class="p-4 md:p-0"
+                x-show="isCategorySidebarOpen || window.innerWidth >= 768" x-cloak
+                x-transition:enter="transition ease-out duration-250"
+                x-transition:enter-start="opacity-0 max-h-0"
+                x-transition:enter-end="opacity-100 max-h-[1000px]" // Duża wartość, aby zmieścić wszystkie kategorie
+                x-transition:leave="transition ease-in duration-200"
+                x-transition:leave-start="opacity-100 max-h-[1000px]"
+                x-transition:leave-end="opacity-0 max-h-0"
+                style="overflow: hidden;" {
+
+                // Tytuł widoczny tylko na desktopie
+                h2 ."text-xl font-semibold mb-4 text-gray-800 hidden md:block text-center" { "Kategorie" }
+
+                nav {
+                    ul ."space-y-1" {
+                        // Definicje klas dla linków dla czystszego kodu
+                        @let active_class = "flex items-center justify-center px-3 py-2 rounded-md transition-colors bg-[var(--color-secondary)] text-[var(--text-color-primary)] font-bold";
+                        @let inactive_class = "flex items-center justify-center px-3 py-2 rounded-md text-gray-700 hover:bg-[var(--color-secondary)] hover:text-[var(--text-color-primary)] transition-colors";
+
+                        // --- Link "Wszystkie" ---
+                        li {
+                            @let all_classes = if current_category.is_none() { active_class } else { inactive_class };
+                            a href=(format!("/{}", gender_slug))
+                                hx-get=(format!("/{}", gender_slug))
+                                hx-target="#content"
+                                hx-swap="innerHTML"
+                                hx-push-url="true"
+                                class=(all_classes)
+                                "@click"="isCategorySidebarOpen = false" {
+                                { "Wszystkie" }
+                            }
+                        }
+
+                        // --- Pętla po wszystkich kategoriach ---
+                        @for category in available_categories.iter() {
+                            li {
+                                // ZMIANA: Porównujemy bezpośrednio z `category`
+                                @let category_classes = if current_category == Some(category) { active_class } else { inactive_class };
+                                @let category_param = category.as_ref();
+                                a href=(format!("/{}/{}", gender_slug, category_param))
+                                    hx-get=(format!("/{}/{}", gender_slug, category_param))
+                                    hx-target="#content"
+                                    hx-swap="innerHTML"
+                                    hx-push-url="true"
+                                    class=(category_classes)
+                                    "@click"="isCategorySidebarOpen = false" {
+                                    { span {(category.to_string()) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Implementuje cachowanie tylko dla pierwszej strony każdej kategorii.
+/// Handler, który obsługuje wszystkie strony kategorii:
+/// - /dla-niej
+/// - /dla-niego
+/// - /dla-niej/koszule
+/// - /dla-niego/spodnie
+/// Implementuje cachowanie tylko dla pierwszej strony każdej kategorii.
+/// "Silnik" do renderowania stron kategorii, z logiką cachowania.
+/// Ta funkcja nie jest handlerem, jest wywoływana przez handlery.
+#[allow(clippy::too_many_arguments)]
+async fn render_gender_page(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    app_state: Arc<AppState>,
+    params: ListingParams,
+    user_claims_opt: OptionalTokenClaims,
+    guest_cart_id_opt: OptionalGuestCartId,
+    current_gender: ProductGender,
+    current_category_opt: Option<Category>,
+) -> Result<Response, AppError> {
+    let gender_slug = gender_listing_slug(&current_gender);
+
+    // --- POPRAWIONA LOGIKA GENEROWANIA TYTUŁU ---
+    // 1. Mapujemy enum `ProductGender` na przyjazną nazwę.
+    let gender_display_name = match current_gender {
+        ProductGender::Damskie => "niej",
+        ProductGender::Meskie => "niego",
+    };
+
+    // 2. Mapujemy opcjonalną kategorię na przyjazną nazwę.
+    let category_display_name = current_category_opt
+        .as_ref()
+        .map_or("Wszystkie".to_string(), |c| c.to_string());
+    let title = format!(
+        "Produkty dla {}: {} - sklep mess - all that vintage",
+        gender_display_name, category_display_name
+    );
+
+    // --- Pobieranie Danych (jeśli nie ma w cache'u) ---
+    let mut conn = app_state.db_pool.acquire().await?;
+    let cart_details_opt =
+        crate::cart_utils::get_cart_details(&mut conn, user_claims_opt.0, guest_cart_id_opt.0)
+            .await?;
+    let product_ids_in_cart: Vec<Uuid> = cart_details_opt
+        .map(|details| details.items.iter().map(|item| item.product.id).collect())
+        .unwrap_or_else(Vec::new);
+
+    let cache_key = (is_default_listing_request(&params) && product_ids_in_cart.is_empty())
+        .then(|| gender_listing_cache_key(&current_gender, current_category_opt.as_ref()));
+    let final_params = ListingParams {
+        gender: Some(current_gender.clone()),
+        category: current_category_opt.clone(),
+        ..params
+    };
+    let count_params = final_params.clone();
+    let mut pagination_links = None;
+    let product_grid_markup = with_listing_fragment_cache(&app_state, cache_key, async {
+        let (markup, links) =
+            render_gender_grid(app_state.clone(), final_params, product_ids_in_cart).await?;
+        pagination_links = Some(links);
+        Ok(markup)
+    })
+    .await?;
+    // Siatka z cache'u to zawsze pierwsza strona - brakuje tylko liczby stron
+    let pagination_links = match pagination_links {
+        Some(links) => links,
+        None => {
+            let total_items = crate::handlers::count_products(&app_state, &count_params).await?;
+            listing_pagination_links(
+                &app_state,
+                &count_params,
+                1,
+                total_pages(total_items, count_params.limit()),
+            )
+        }
+    };
+
+    let seo_header_markup = if let Some(category) = &current_category_opt {
+        let (h1, h2) = get_seo_headers_for_category(category);
+        render_seo_header_maud(h1, h2)
+    } else {
+        html! {}
+    };
+
+    // --- NOWA CZĘŚĆ: POBIERANIE DANYCH DLA PASKA BOCZNEGO ---
+    // ZMIANA: Wywołujemy nową funkcję i zmieniamy nazwę zmiennej
+    let available_categories = get_available_categories_for_gender(&app_state, current_gender)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Nie udało się pobrać listy kategorii: {}", e);
+            vec![] // W razie błędu zwróć pusty wektor.
+        });
+
+    // --- Renderowanie Treści ---
+    let breadcrumbs = gender_breadcrumbs(&current_gender, current_category_opt.as_ref(), None);
+    let page_content = html! {
+        div class="mb-6 md:mb-12" {
+            (render_free_shipping_banner_maud())
+        }
+        (render_breadcrumbs_maud(&breadcrumbs))
+        (seo_header_markup)
+        div ."flex flex-col md:flex-row gap-6" {
+            (render_category_sidebar_maud(gender_slug, current_category_opt.as_ref(), &available_categories))
+            section #product-listing-area ."w-full md:w-3/4 lg:w-4/5" {
+                (product_grid_markup)
+            }
+        }
+    };
+
+    let social_meta = SocialMeta::new(
+        title.clone(),
+        listing_meta_description(Some(&current_gender), current_category_opt.as_ref()),
+        &app_state.public_base_url,
+        &gender_listing_path(&current_gender, current_category_opt.as_ref()),
+    );
+    let head_scripts = breadcrumbs_json_ld(&breadcrumbs, &app_state.public_base_url);
+    let page_builder = PageBuilder::new(&title, page_content, Some(head_scripts), None)
+        .with_social_meta(social_meta)
+        .with_pagination_links(pagination_links);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+fn gender_listing_slug(gender: &ProductGender) -> &'static str {
+    match gender {
+        ProductGender::Damskie => "dla-niej",
+        ProductGender::Meskie => "dla-niego",
+    }
+}
+
+fn gender_display_name(gender: &ProductGender) -> &'static str {
+    match gender {
+        ProductGender::Damskie => "Dla niej",
+        ProductGender::Meskie => "Dla niego",
+    }
+}
+
+/// Ścieżka "Strona główna → Dla niej → Sukienki [→ produkt]" z tych samych adresów co
+/// pasek kategorii; ostatni element to bieżąca strona (bez linku)
+fn gender_breadcrumbs(
+    gender: &ProductGender,
+    category: Option<&Category>,
+    product_name: Option<&str>,
+) -> Vec<Breadcrumb> {
+    let mut breadcrumbs = vec![
+        Breadcrumb::link("Strona główna", "/"),
+        Breadcrumb::link(
+            gender_display_name(gender),
+            gender_listing_path(gender, None),
+        ),
+    ];
+    if let Some(category) = category {
+        breadcrumbs.push(Breadcrumb::link(
+            category.to_string(),
+            gender_listing_path(gender, Some(category)),
+        ));
+    }
+    if let Some(name) = product_name {
+        breadcrumbs.push(Breadcrumb::current(name));
+    } else if let Some(last) = breadcrumbs.last_mut() {
+        last.path = None;
+    }
+    breadcrumbs
+}
+
+fn render_breadcrumbs_maud(breadcrumbs: &[Breadcrumb]) -> Markup {
+    html! {
+        nav aria-label="Ścieżka nawigacji" class="mb-4 text-sm text-gray-500" {
+            ol class="flex flex-wrap items-center gap-x-2 gap-y-1" {
+                @for (index, breadcrumb) in breadcrumbs.iter().enumerate() {
+                    li class="flex items-center gap-x-2" {
+                        @if index > 0 {
+                            span aria-hidden="true" { "→" }
+                        }
+                        @if let Some(path) = &breadcrumb.path {
+                            a href=(path)
+                                hx-get=(path)
+                                hx-target="#content"
+                                hx-swap="innerHTML"
+                                hx-push-url="true"
+                                class="hover:text-gray-800 hover:underline" {
+                                (breadcrumb.name)
+                            }
+                        } @else {
+                            span aria-current="page" class="font-medium text-gray-800" { (breadcrumb.name) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Dane strukturalne BreadcrumbList do `<head>` (przez `PageBuilder`)
+fn breadcrumbs_json_ld(breadcrumbs: &[Breadcrumb], base_url: &str) -> Markup {
+    let schema = SchemaBreadcrumbList::from_breadcrumbs(breadcrumbs, base_url);
+    let json_ld_string = serde_json::to_string(&schema).unwrap_or_else(|e| {
+        tracing::error!("Błąd serializacji JSON-LD okruszków: {}", e);
+        "{}".to_string()
+    });
+    html! {
+        script type="application/ld+json" {
+            (PreEscaped(json_ld_string))
+        }
+    }
+}
+
+/// Opis listy do podglądu linku: podtytuł SEO kategorii albo ogólny opis sklepu
+fn listing_meta_description(
+    gender: Option<&ProductGender>,
+    category: Option<&Category>,
+) -> &'static str {
+    match (gender, category) {
+        (_, Some(category)) => get_seo_headers_for_category(category).1,
+        (Some(ProductGender::Damskie), None) => {
+            "Odzież vintage dla niej - unikatowe perełki z drugiej ręki w mess - all that vintage."
+        }
+        (Some(ProductGender::Meskie), None) => {
+            "Odzież vintage dla niego - unikatowe perełki z drugiej ręki w mess - all that vintage."
+        }
+        (None, None) => {
+            "Odkryj unikalną odzież vintage w mess - all that vintage. Najlepsze perełki z drugiej ręki dla niej i dla niego."
+        }
+    }
+}
+
+/// Siatka produktów strony płci/kategorii (bez paska bocznego i nagłówków SEO).
+async fn render_gender_grid(
+    app_state: Arc<AppState>,
+    final_params: ListingParams,
+    product_ids_in_cart: Vec<Uuid>,
+) -> Result<(Markup, PaginationLinks), AppError> {
+    let (paginated_response, facets) = tokio::try_join!(
+        crate::handlers::list_products(State(app_state.clone()), Query(final_params.clone())),
+        get_facet_counts(&app_state.db_pool, &final_params),
+    )?;
+    let lowest_prices =
+        lowest_prices_for_sale_products(&app_state.db_pool, &paginated_response.0.data).await?;
+    let pagination_links = listing_pagination_links(
+        &app_state,
+        &final_params,
+        paginated_response.0.current_page,
+        paginated_response.0.total_pages,
+    );
+    let markup = render_product_grid_maud(
+        &paginated_response.0.data,
+        &paginated_response.0,
+        &final_params,
+        &product_ids_in_cart,
+        &facets,
+        &lowest_prices,
+    );
+    Ok((markup, pagination_links))
+}
+
+const HOME_LISTING_CACHE_KEY: &str = "listing:home";
+
+fn gender_listing_cache_key(gender: &ProductGender, category: Option<&Category>) -> String {
+    format!(
+        "listing:{}:{}",
+        gender.as_ref(),
+        category.map_or("all", |c| c.as_ref())
+    )
+}
+
+/// Parametry listingu strony głównej
+fn home_listing_params(params: ListingParams) -> ListingParams {
+    ListingParams {
+        limit: params.limit.or(Some(8)),
+        offset: params.offset,
+        source: Some("home".to_string()),
+        ..params
+    }
+}
+
+/// Czy żądanie dotyczy pierwszej strony listingu bez filtrów, wyszukiwania i własnego sortowania -
+/// tylko taki widok trafia do `listing_fragment_cache`.
+fn is_default_listing_request(params: &ListingParams) -> bool {
+    params.offset.unwrap_or(0) == 0
+        && params.limit.is_none()
+        && params.condition.is_none()
+        && params.status.is_none()
+        && params.price_min.is_none()
+        && params.price_max.is_none()
+        && params.on_sale.is_none()
+        && params.attribute_filters().is_empty()
+        && params.sort_by.is_none()
+        && params.order.is_none()
+        && params.search().filter(|s| !s.trim().is_empty()).is_none()
+}
+
+/// Zwraca fragment z cache'u albo renderuje go i zapisuje. Bez klucza (`None`) zawsze renderuje -
+/// np. gdy klient ma coś w koszyku, bo przyciski "W koszyku" zależą od koszyka.
+async fn with_listing_fragment_cache<F>(
+    app_state: &AppState,
+    cache_key: Option<String>,
+    render: F,
+) -> Result<Markup, AppError>
+where
+    F: std::future::Future<Output = Result<Markup, AppError>>,
+{
+    let Some(key) = cache_key else {
+        return render.await;
+    };
+    let cached_fragment = app_state.listing_fragment_cache.get(&key).await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::ListingFragments, cached_fragment.is_some());
+    if let Some(cached_html) = cached_fragment {
+        return Ok(PreEscaped(cached_html));
+    }
+    let markup = render.await?;
+    app_state
+        .cache_stats
+        .record_insert(CacheName::ListingFragments, &key);
+    app_state
+        .listing_fragment_cache
+        .insert(key, markup.clone().into_string())
+        .await;
+    Ok(markup)
+}
+
+/// Prerenderuje pierwsze strony listingów (strona główna, każda płeć i każda kategoria)
+/// do `listing_fragment_cache`. Wywoływane przy starcie serwera. Zwraca liczbę fragmentów.
+pub async fn warm_listing_fragment_cache(app_state: Arc<AppState>) -> Result<u64, AppError> {
+    let home_markup = render_product_listing_view(
+        app_state.clone(),
+        home_listing_params(ListingParams::default()),
+        Vec::new(),
+    )
+    .await?;
+    app_state
+        .listing_fragment_cache
+        .insert(
+            HOME_LISTING_CACHE_KEY.to_string(),
+            home_markup.into_string(),
+        )
+        .await;
+    app_state
+        .cache_stats
+        .record_insert(CacheName::ListingFragments, HOME_LISTING_CACHE_KEY);
+    let mut count = 1;
+
+    for gender in ProductGender::iter() {
+        let categories = std::iter::once(None).chain(Category::iter().map(Some));
+        for category in categories {
+            let final_params = ListingParams {
+                gender: Some(gender.clone()),
+                category: category.clone(),
+                ..Default::default()
+            };
+            let (markup, _) =
+                render_gender_grid(app_state.clone(), final_params, Vec::new()).await?;
+            let key = gender_listing_cache_key(&gender, category.as_ref());
+            app_state
+                .cache_stats
+                .record_insert(CacheName::ListingFragments, &key);
+            app_state
+                .listing_fragment_cache
+                .insert(key, markup.into_string())
+                .await;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Handler dla tras BEZ kategorii, np. "/dla-niej"
+pub async fn dla_gender_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Path(gender_slug): Path<String>, // Pobiera 'dla-niej' lub 'dla-niego'
+    Query(params): Query<ListingParams>,
+    user_claims_opt: OptionalTokenClaims,
+    guest_cart_id_opt: OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    let gender = match gender_slug.as_str() {
+        "dla-niej" => ProductGender::Damskie,
+        "dla-niego" => ProductGender::Meskie,
+        _ => return Err(AppError::NotFound),
+    };
+    // Wywołujemy silnik, przekazując `None` jako kategorię
+    render_gender_page(
+        headers,
+        csp_nonce,
+        app_state,
+        params,
+        user_claims_opt,
+        guest_cart_id_opt,
+        gender,
+        None,
+    )
+    .await
+}
+
+/// Handler dla tras Z KATEGORIĄ, np. "/dla-niej/koszule"
+pub async fn dla_gender_with_category_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+    Path((gender_slug, category_slug)): Path<(String, String)>, // Pobiera oba segmenty
+    Query(params): Query<ListingParams>,
+    user_claims_opt: OptionalTokenClaims,
+    guest_cart_id_opt: OptionalGuestCartId,
+) -> Result<Response, AppError> {
+    let gender = match gender_slug.as_str() {
+        "dla-niej" => ProductGender::Damskie,
+        "dla-niego" => ProductGender::Meskie,
+        _ => return Err(AppError::NotFound),
+    };
+    let category = Category::from_str(&category_slug).map_err(|_| AppError::NotFound)?;
+    // Wywołujemy silnik, przekazując `Some(category)`
+    render_gender_page(
+        headers,
+        csp_nonce,
+        app_state,
+        params,
+        user_claims_opt,
+        guest_cart_id_opt,
+        gender,
+        Some(category),
+    )
+    .await
+}
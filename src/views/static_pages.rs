@@ -0,0 +1,1308 @@
+// src/views/static_pages.rs
+
+// Strony informacyjne (o nas, regulamin, polityka prywatności, kontakt, FAQ, wysyłka i zwroty) oraz strona 404.
+// Treść każdej z nich można podmienić w panelu (zob. `cms`); poniższe renderery są treścią wbudowaną.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use maud::{Markup, PreEscaped, html};
+
+use crate::cache_stats::CacheName;
+use crate::checkout::free_shipping_threshold;
+use crate::cms::{StaticPage, published_static_page, render_markdown, static_page_cache_key};
+use crate::errors::AppError;
+use crate::faq::{FaqItem, list_faq_items};
+use crate::models::{Category, Product};
+use crate::response::{PageBuilder, build_response};
+use crate::search::suggest_products;
+use crate::security_headers::CspNonce;
+use crate::seo::{SchemaAcceptedAnswer, SchemaFAQPage, SchemaQuestion, SocialMeta};
+use crate::state::AppState;
+
+use super::common::format_price_maud;
+use super::shop::product_tile_maud;
+
+/// Wbudowana treść strony informacyjnej
+pub enum BuiltInContent {
+    Static(fn() -> Markup),
+    /// Pytania z tabeli `faq_items` (zob. `faq`)
+    Faq,
+}
+
+/// Strona informacyjna: adres (slug), nazwa w panelu, tytuł karty przeglądarki, opis do
+/// podglądu linku i wbudowana treść
+pub struct BuiltInPage {
+    pub slug: &'static str,
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub content: BuiltInContent,
+}
+
+/// Strony, których treść można edytować w panelu
+pub const BUILT_IN_PAGES: [BuiltInPage; 6] = [
+    BuiltInPage {
+        slug: "o-nas",
+        name: "O nas",
+        title: "O nas - sklep mess - all that vintage",
+        description: "Poznaj mess - all that vintage: sklep z ręcznie wybieraną odzieżą vintage i z drugiej ręki.",
+        content: BuiltInContent::Static(render_about_us_content),
+    },
+    BuiltInPage {
+        slug: "polityka-prywatnosci",
+        name: "Polityka prywatności",
+        title: "Polityka prywatności - sklep mess - all that vintage",
+        description: "Jak sklep mess - all that vintage przetwarza i chroni dane osobowe klientów.",
+        content: BuiltInContent::Static(render_privacy_policy_content),
+    },
+    BuiltInPage {
+        slug: "regulamin",
+        name: "Regulamin",
+        title: "Regulamin sklepu - sklep mess - all that vintage",
+        description: "Regulamin sklepu internetowego mess - all that vintage: zamówienia, płatności, dostawa i zwroty.",
+        content: BuiltInContent::Static(render_terms_of_service),
+    },
+    BuiltInPage {
+        slug: "kontakt",
+        name: "Kontakt",
+        title: "Kontakt - sklep mess - all that vintage",
+        description: "Skontaktuj się ze sklepem mess - all that vintage - odpowiadamy na pytania o produkty i zamówienia.",
+        content: BuiltInContent::Static(render_contact_page),
+    },
+    BuiltInPage {
+        slug: "faq",
+        name: "FAQ",
+        title: "FAQ - Najczęściej zadawane pytania - sklep mess - all that vintage",
+        description: "Odpowiedzi na najczęstsze pytania o zamówienia, wysyłkę, płatności i zwroty w mess - all that vintage.",
+        content: BuiltInContent::Faq,
+    },
+    BuiltInPage {
+        slug: "wysylka-i-zwroty",
+        name: "Wysyłka i zwroty",
+        title: "Wysyłki i zwroty - sklep mess - all that vintage",
+        description: "Koszty i czas wysyłki oraz zasady zwrotów w sklepie mess - all that vintage.",
+        content: BuiltInContent::Static(render_shipping_returns_page),
+    },
+];
+
+pub fn built_in_page(slug: &str) -> Option<&'static BuiltInPage> {
+    BUILT_IN_PAGES.iter().find(|page| page.slug == slug)
+}
+
+/// Strona z panelu: tytuł i treść Markdown w tym samym układzie co strony wbudowane
+pub fn render_cms_page(page: &StaticPage) -> Markup {
+    html! {
+        div ."max-w-4xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+            h1 ."text-4xl sm:text-5xl font-bold tracking-tight text-gray-900 text-center mb-12" { (page.title) }
+            div ."cms-content text-gray-700" { (PreEscaped(render_markdown(&page.body))) }
+        }
+    }
+}
+
+/// Treść strony: opublikowana wersja z panelu albo wbudowana
+pub async fn static_page_content(
+    app_state: &AppState,
+    page: &BuiltInPage,
+) -> Result<Markup, AppError> {
+    if let Some(cms_page) = published_static_page(&app_state.db_pool, page.slug).await? {
+        return Ok(render_cms_page(&cms_page));
+    }
+    Ok(match page.content {
+        BuiltInContent::Static(render) => render(),
+        BuiltInContent::Faq => {
+            render_faq_with_schema(&list_faq_items(&app_state.db_pool, true).await?)
+        }
+    })
+}
+
+/// Renderuje samą treść (Markup) dla strony "O nas".
+/// Ta funkcja nie zajmuje się cachowaniem ani budowaniem odpowiedzi HTTP.
+pub fn render_about_us_content() -> Markup {
+    html! {
+        div ."max-w-4xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+            // Baner lub główny nagłówek strony
+            div ."text-center mb-12" {
+                h1 ."text-4xl sm:text-5xl font-bold tracking-tight text-gray-900" { "Nasza Pasja, Twój Styl" }
+                p ."mt-4 text-xl text-gray-600" { "Poznaj historię i filozofię mess - all that vintage." }
+            }
+
+            // Sekcja wprowadzająca
+            div ."prose prose-lg lg:prose-xl max-w-none text-gray-700 leading-relaxed space-y-6" {
+
+                p ."text-xl font-semibold text-[var(--text-color-primary)]" { // Lekkie wyróżnienie pierwszego zdania
+                    "Witaj w świecie mess - all that vintage!"
+                }
+                p {
+                    "Jesteśmy grupą prawdziwych entuzjastów mody, dla których ubrania to coś znacznie więcej niż tylko okrycie. To forma sztuki, sposób na wyrażenie siebie i opowieść, którą każde z nas pisze na nowo każdego dnia."
+                }
+
+                // Możemy dodać zdjęcie zespołu lub inspirujące zdjęcie modowe tutaj, jeśli chcesz
+                // Dla przykładu, placeholder na zdjęcie:
+                /*
+                div ."my-8 rounded-lg shadow-xl overflow-hidden aspect-w-16 aspect-h-9" {
+                    img src="/static/images/team_placeholder.jpg" alt="Zespół mess - all that vintage lub inspiracja modowa" class="object-cover w-full h-full";
+                }
+                */
+
+                h2 ."text-2xl sm:text-3xl font-semibold text-gray-800 mt-10 mb-4 border-b-2 border-[var(--color-primary)] pb-2" {
+                    "Miłość do Vintage i Zrównoważonego Stylu"
+                }
+                p {
+                    "Naszą największą inspiracją jest moda z duszą – starannie wyszukane perełki vintage i odzież z drugiej ręki, która niesie ze sobą niepowtarzalne historie i ponadczasową jakość. Wierzymy, że moda powinna być zrównoważona, a dawanie ubraniom drugiego życia to najpiękniejszy sposób na dbanie o naszą planetę i podkreślanie własnej indywidualności. Przeszukujemy niezliczone miejsca, aby znaleźć te wyjątkowe egzemplarze, które wniosą do Twojej szafy niepowtarzalny charakter."
+                }
+
+                h2 ."text-2xl sm:text-3xl font-semibold text-gray-800 mt-10 mb-4 border-b-2 border-[var(--color-primary)] pb-2" {
+                    "Misja mess - all that vintage"
+                }
+                p {
+                    "mess - all that vintage narodziło się z pragnienia dzielenia się tymi odkryciami. Chcemy stworzyć miejsce, gdzie każda i każdy z Was znajdzie coś wyjątkowego – ubrania, które nie tylko świetnie wyglądają, ale też mają charakter i pozwalają wyróżnić się z tłumu. Selekcjonujemy nasze kolekcje z największą starannością, dbając o jakość, unikalność i autentyczny styl."
+                }
+
+                // Sekcja z wyróżnionym cytatem lub wartościami
+                div ."my-10 p-6 bg-[var(--color-secondary)] rounded-xl border-l-4 border-[var(--color-primary)]" {
+                        p ."text-lg italic text-[var(--text-color-primary-hover)] leading-relaxed" {
+                        "„Moda przemija, styl pozostaje. W mess - all that vintage celebrujemy ten ponadczasowy styl, dając drugie życie wyjątkowym ubraniom.”"
+                    }
+                }
+
+                h2 ."text-2xl sm:text-3xl font-semibold text-gray-800 mt-10 mb-4 border-b-2 border-[var(--color-primary)] pb-2" {
+                    "Co u nas znajdziesz?"
+                }
+                p {
+                    "W naszych kolekcjach dla Niej i dla Niego znajdziesz ubrania, które opowiadają historie, dodatki z duszą i klasyki, które nigdy nie wychodzą z mody. Dbamy o to, by każdy produkt był dokładnie sprawdzony i opisany, gotowy na nowy rozdział w Twojej garderobie."
+                }
+
+                // Zaproszenie
+                div ."mt-12 text-center" {
+                    p ."text-xl text-gray-700 mb-4" {
+                        "Dziękujemy, że jesteś z nami! Rozejrzyj się, zainspiruj i znajdź coś, co idealnie odda Twój styl."
+                    }
+                    a href="/" hx-get="/" hx-target="#content" hx-swap="innerHTML" hx-push-url="/"
+                       class="inline-block bg-[var(--color-primary)] text-[var(--color-primary-text)] font-semibold py-3 px-8 rounded-lg shadow-md hover:bg-[var(--color-primary-hover)] transition-all duration-200 ease-in-out text-lg" {
+                        "Odkrywaj nasze kolekcje"
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn about_us_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    handle_static_page(headers, csp_nonce, app_state, "o-nas").await
+}
+
+pub fn render_privacy_policy_content() -> Markup {
+    let effective_date = "25 maja 2025";
+    let shop_name = "mess - all that vintage";
+    let shop_url = "www.messvintage.com";
+    let company_full_name = "mess - all that vintage Jan Kowalski";
+    let company_address = "ul. Modna 1, 00-001 Warszawa";
+    let company_nip = "123-456-78-90";
+    let company_regon = "123456789";
+    let contact_email_privacy = "contact@messvintage.com";
+    let link_do_polityki_cookies = "/polityka-cookies";
+
+    // Definicje tekstów jako zmienne Rusta
+    let heading_main_text = format!("Polityka Prywatności {}", shop_name);
+    let last_update_text = format!("Ostatnia aktualizacja: {}", effective_date);
+
+    let intro_heading_text = "1. Wprowadzenie";
+    let intro_paragraph_text = format!(
+        "Witamy w {} (dalej jako \"Sklep\", \"my\", \"nas\"). Cenimy Twoją prywatność i zobowiązujemy się \
+        do ochrony Twoich danych osobowych. Niniejsza Polityka Prywatności wyjaśnia, jakie dane osobowe \
+        zbieramy, w jaki sposób je wykorzystujemy, udostępniamy i chronimy w związku z korzystaniem \
+        z naszego sklepu internetowego dostępnego pod adresem {}.",
+        shop_name, shop_url
+    );
+
+    let admin_heading_text = "2. Administrator Danych Osobowych";
+    let admin_details_text = format!(
+        "Administratorem Twoich danych osobowych jest {}, z siedzibą w {}, NIP: {}, REGON: {}.",
+        company_full_name, company_address, company_nip, company_regon
+    );
+    let admin_contact_text = format!(
+        "W sprawach dotyczących przetwarzania danych osobowych możesz skontaktować się z nami pod adresem e-mail: {}.",
+        contact_email_privacy
+    );
+
+    let data_collected_heading_text = "3. Jakie dane zbieramy?";
+    let data_collected_intro_text =
+        "Podczas korzystania z naszego Sklepu możemy zbierać następujące rodzaje danych:";
+    let data_voluntary_text = format!(
+        "{} imię i nazwisko, adres e-mail, adres dostawy, numer telefonu, dane do faktury, dane logowania do konta użytkownika, treści wiadomości przesyłanych przez formularz kontaktowy.",
+        "Dane podawane dobrowolnie przez Ciebie:"
+    );
+    let data_automatic_text = format!(
+        "{} adres IP, typ i wersja przeglądarki, system operacyjny, odwołujący URL, strony odwiedzane w naszym Sklepie, czas spędzony na stronie, informacje zbierane za pomocą plików cookies i podobnych technologii.",
+        "Dane zbierane automatycznie:"
+    );
+
+    let purpose_heading_text = "4. W jakim celu przetwarzamy Twoje dane?";
+    let purpose_intro_text = "Twoje dane osobowe przetwarzamy w następujących celach:";
+    let purposes_list_items = [
+        "Realizacji i obsługi zamówień (podstawa prawna: art. 6 ust. 1 lit. b RODO - wykonanie umowy).",
+        "Założenia i prowadzenia konta użytkownika w Sklepie (podstawa prawna: art. 6 ust. 1 lit. b RODO).",
+        "Komunikacji z Tobą, w tym odpowiedzi na zapytania (podstawa prawna: art. 6 ust. 1 lit. f RODO - nasz prawnie uzasadniony interes).",
+        "Rozpatrywania reklamacji i roszczeń (podstawa prawna: art. 6 ust. 1 lit. b, c, f RODO).",
+        "Marketingu bezpośredniego naszych produktów i usług, w tym wysyłki newslettera, wyłącznie za Twoją zgodą (podstawa prawna: art. 6 ust. 1 lit. a RODO).",
+        "Analizy statystycznej i ulepszania działania Sklepu (podstawa prawna: art. 6 ust. 1 lit. f RODO - nasz prawnie uzasadniony interes).",
+        "Wypełnienia obowiązków prawnych ciążących na nas, np. podatkowych (podstawa prawna: art. 6 ust. 1 lit. c RODO).",
+    ];
+
+    let sharing_heading_text = "5. Komu udostępniamy Twoje dane?";
+    let sharing_intro_text =
+        "Twoje dane osobowe mogą być udostępniane następującym kategoriom odbiorców:";
+    let shared_with_list_items = [
+        "Dostawcom usług płatniczych w celu realizacji płatności.",
+        "Firmom kurierskim i pocztowym w celu dostarczenia zamówień.",
+        "Dostawcom usług IT (np. hosting, systemy mailingowe), którzy przetwarzają dane w naszym imieniu.",
+        "Organom państwowym, jeśli wynika to z obowiązujących przepisów prawa.",
+    ];
+    let sharing_assurance_text = "Zapewniamy, że wszyscy nasi partnerzy przetwarzają Twoje dane zgodnie z obowiązującymi przepisami o ochronie danych i na podstawie odpowiednich umów powierzenia przetwarzania.";
+
+    let storage_duration_heading_text = "6. Jak długo przechowujemy Twoje dane?";
+    let storage_duration_text = "Twoje dane osobowe będą przechowywane przez okres niezbędny do realizacji celów, dla których zostały zebrane, a po tym czasie przez okres wymagany przepisami prawa (np. dla celów podatkowych, przedawnienia roszczeń) lub do momentu wycofania przez Ciebie zgody (jeśli przetwarzanie odbywało się na jej podstawie).";
+
+    let user_rights_heading_text = "7. Twoje prawa";
+    let user_rights_intro_text =
+        "W związku z przetwarzaniem Twoich danych osobowych przysługują Ci następujące prawa:";
+    let user_rights_list_items = [
+        "Prawo dostępu do swoich danych.",
+        "Prawo do sprostowania (poprawiania) swoich danych.",
+        "Prawo do usunięcia danych (tzw. \"prawo do bycia zapomnianym\").", // Użyto standardowych cudzysłowów ASCII
+        "Prawo do ograniczenia przetwarzania danych.",
+        "Prawo do przenoszenia danych.",
+        "Prawo do wniesienia sprzeciwu wobec przetwarzania danych (w szczególności wobec marketingu bezpośredniego).",
+        "Prawo do cofnięcia zgody w dowolnym momencie, jeśli przetwarzanie odbywa się na podstawie zgody (cofnięcie zgody nie wpływa na zgodność z prawem przetwarzania, którego dokonano na podstawie zgody przed jej wycofaniem).",
+        "Prawo do wniesienia skargi do organu nadzorczego, tj. Prezesa Urzędu Ochrony Danych Osobowych (ul. Stawki 2, 00-193 Warszawa).",
+    ];
+    let user_rights_contact_text = format!(
+        "Aby skorzystać ze swoich praw, skontaktuj się z nami pod adresem e-mail podanym w punkcie 2 ({}) lub listownie.",
+        contact_email_privacy
+    );
+
+    let cookies_heading_text = "8. Pliki Cookies";
+    let cookies_paragraph1_text = "
+        Nasz Sklep wykorzystuje pliki cookies (ciasteczka). Są to małe pliki tekstowe przechowywane na Twoim urządzeniu \
+        końcowym. Używamy ich m.in. do zapewnienia prawidłowego działania Sklepu, zapamiętywania Twoich preferencji, \
+        analizy ruchu oraz w celach marketingowych. Szczegółowe informacje na temat plików cookies oraz możliwości \
+        zarządzania nimi znajdziesz w naszej [LINK DO POLITYKI COOKIES - TODO].";
+
+    let cookies_paragraph2_text =
+        "Możesz zarządzać ustawieniami cookies z poziomu swojej przeglądarki internetowej.";
+
+    let security_heading_text = "9. Bezpieczeństwo danych";
+    let security_text = "Przykładamy dużą wagę do bezpieczeństwa Twoich danych osobowych. Stosujemy odpowiednie środki techniczne i organizacyjne, aby chronić Twoje dane przed nieuprawnionym dostępem, utratą, zniszczeniem czy modyfikacją.";
+
+    let changes_heading_text = "10. Zmiany w Polityce Prywatności";
+    let changes_text = "Zastrzegamy sobie prawo do wprowadzania zmian w niniejszej Polityce Prywatności. Wszelkie zmiany będą publikowane na tej stronie i wchodzą w życie z dniem publikacji. Zachęcamy do regularnego zapoznawania się z treścią Polityki Prywatności.";
+
+    let contact_heading_text = "11. Kontakt";
+    let contact_text_final_paragraph = format!(
+        // Poprawiono problematyczny string
+        "W przypadku pytań dotyczących niniejszej Polityki Prywatności lub przetwarzania Twoich danych osobowych, {} \
+        prosimy o kontakt pod adresem e-mail: {}",
+        "", // Pusty string, jeśli nie ma nic do dodania na początku, lub dodaj jakiś tekst.
+        contact_email_privacy
+    );
+
+    html! {
+        div ."max-w-3xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+            div ."text-center mb-10" {
+                h1 ."text-3xl sm:text-4xl font-bold tracking-tight text-gray-900" { (heading_main_text) }
+                p ."mt-2 text-sm text-gray-500" { (last_update_text) }
+            }
+
+            article ."prose prose-lg lg:prose-xl max-w-none text-gray-700 leading-relaxed space-y-6" {
+
+                h2 { (intro_heading_text) }
+                p { (intro_paragraph_text) }
+
+                h2 { (admin_heading_text) }
+                p { (admin_details_text) }
+                p { (admin_contact_text) }
+
+
+                h2 { (data_collected_heading_text) }
+                p { (data_collected_intro_text) }
+                ul {
+                    li { (PreEscaped(data_voluntary_text.replace("Dane podawane dobrowolnie przez Ciebie:", "<strong>Dane podawane dobrowolnie przez Ciebie:</strong>"))) }
+                    li { (PreEscaped(data_automatic_text.replace("Dane zbierane automatycznie:", "<strong>Dane zbierane automatycznie:</strong>"))) }
+                }
+
+                h2 { (purpose_heading_text) }
+                p { (purpose_intro_text) }
+                ul {
+                    @for purpose_item in &purposes_list_items {
+                        // Zamieniono półpauzy na myślniki
+                        li { (purpose_item.replace(" – ", " - ")) }
+                    }
+                }
+
+                h2 { (sharing_heading_text) }
+                p { (sharing_intro_text) }
+                ul {
+                    @for shared_item in &shared_with_list_items {
+                        li { (shared_item) }
+                    }
+                }
+                p { (sharing_assurance_text) }
+
+                h2 { (storage_duration_heading_text) }
+                p { (storage_duration_text) }
+
+                h2 { (user_rights_heading_text) }
+                p { (user_rights_intro_text) }
+                ul {
+                    @for right_item in &user_rights_list_items {
+                        // Zamieniono cudzysłowy typograficzne
+                        li { (right_item.replace("„", "\"").replace("”", "\"")) }
+                    }
+                }
+                p { (user_rights_contact_text) }
+
+                h2 { (cookies_heading_text) }
+                p { (cookies_paragraph1_text) }
+                p { (cookies_paragraph2_text) }
+
+                h2 { (security_heading_text) }
+                p { (security_text) }
+
+                h2 { (changes_heading_text) }
+                p { (changes_text) }
+
+                h2 { (contact_heading_text) }
+                p { (contact_text_final_paragraph) } // Użycie poprawionego stringa
+            }
+        }
+    }
+}
+
+pub async fn privacy_policy_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    handle_static_page(headers, csp_nonce, app_state, "polityka-prywatnosci").await
+}
+
+pub fn render_terms_of_service() -> Markup {
+    let effective_date = "25 maja 2025";
+    let shop_name = "mess - all that vintage";
+    let shop_url = "www.messvintage.com";
+    let company_full_name = "mess - all that vintage Sp. z o.o.";
+    let company_address = "ul. Przykładowa 1, 00-001 Miasto";
+    let company_nip = "123-456-78-90";
+    let company_regon = "123456789";
+    let contact_email = "contact@messvintage.com";
+    let complaint_address = "ul. Przykładowa 1, 00-001 Miasto (Dział Reklamacji)";
+    let bank_account_for_returns = "[NUMER KONTA BANKOWEGO DO ZWROTÓW]";
+
+    // --- Definicje tekstów jako zmienne Rusta ---
+
+    let heading_main_text = format!("Regulamin Sklepu Internetowego {}", shop_name);
+    let last_update_text = format!("Obowiązuje od: {}", effective_date);
+
+    // §1 Postanowienia ogólne
+    let s1_title = "§1 Postanowienia ogólne";
+    let s1_p1 = format!(
+        "Sklep internetowy działający pod adresem {} (zwany dalej \"Sklepem\") prowadzony jest przez {}, \
+        z siedzibą w {}, NIP: {}, REGON: {} (zwany dalej \"Sprzedawcą\").",
+        shop_url, company_full_name, company_address, company_nip, company_regon
+    );
+    let s1_p2 = "Niniejszy regulamin (zwany dalej \"Regulaminem\") określa zasady i warunki korzystania ze Sklepu, \
+        składania zamówień na produkty dostępne w Sklepie, dostarczania zamówionych produktów Klientowi, \
+        uiszczania przez Klienta ceny sprzedaży produktów, uprawnienia Klienta do odstąpienia od umowy \
+        oraz zasady składania i rozpatrywania reklamacji.";
+    let s1_p3_intro = "Do korzystania ze Sklepu, w tym przeglądania asortymentu Sklepu oraz składania zamówień na Produkty, niezbędne jest:";
+    let s1_p3_reqs = [
+        "Urządzenie końcowe (np. komputer, tablet, smartfon) z dostępem do sieci Internet i przeglądarką internetową typu np. Chrome, Firefox, Safari, Edge.",
+        "Aktywne konto poczty elektronicznej (e-mail).",
+        "Włączona obsługa plików cookies oraz JavaScript w przeglądarce internetowej.",
+    ];
+    let s1_p4 = "Klient zobowiązany jest do korzystania ze Sklepu w sposób zgodny z prawem i dobrymi obyczajami, \
+        mając na uwadze poszanowanie dóbr osobistych oraz praw autorskich i własności intelektualnej Sprzedawcy \
+        oraz osób trzecich.";
+    let s1_p5 = "Klienta obowiązuje zakaz dostarczania treści o charakterze bezprawnym.";
+    let s1_p6_intro = "Definicje użyte w Regulaminie:";
+    let s1_p6_defs = [
+        ("Sprzedawca", "podmiot wskazany w ust. 1."),
+        (
+            "Klient",
+            "osoba fizyczna posiadająca pełną zdolność do czynności prawnych, osoba prawna lub jednostka organizacyjna nieposiadająca osobowości prawnej, której ustawa przyznaje zdolność prawną, dokonująca Zamówienia w Sklepie.",
+        ),
+        (
+            "Konsument",
+            "Klient będący osobą fizyczną dokonujący ze Sprzedawcą czynności prawnej niezwiązanej bezpośrednio z jej działalnością gospodarczą lub zawodową.",
+        ),
+        (
+            "Produkt",
+            "dostępna w Sklepie rzecz ruchoma będąca przedmiotem Umowy Sprzedaży. Produkty w Sklepie są towarami używanymi (vintage), chyba że wyraźnie wskazano inaczej. Ich stan jest opisany na karcie produktu.",
+        ),
+        (
+            "Umowa Sprzedaży",
+            "umowa sprzedaży Produktu zawierana albo zawarta między Klientem a Sprzedawcą za pośrednictwem Sklepu internetowego.",
+        ),
+        (
+            "Zamówienie",
+            "oświadczenie woli Klienta składane za pomocą Formularza Zamówienia i zmierzające bezpośrednio do zawarcia Umowy Sprzedaży Produktu ze Sprzedawcą.",
+        ),
+    ];
+
+    // §2 Składanie Zamówień
+    let s2_title = "§2 Składanie Zamówień";
+    let s2_p1 = "Informacje o Produktach podane na stronach internetowych Sklepu, w szczególności ich opisy, \
+        parametry techniczne i użytkowe oraz ceny, stanowią zaproszenie do zawarcia umowy, w rozumieniu art. 71 Kodeksu Cywilnego.";
+    let s2_p2 = "Wszystkie Produkty dostępne w Sklepie są używane, pochodzą z \"drugiej ręki\" (są towarem typu vintage), \
+        chyba że w opisie Produktu wyraźnie zaznaczono inaczej. Sprzedawca dokłada wszelkich starań, aby stan Produktów \
+        był dokładnie opisany i sfotografowany. Klient akceptuje, że Produkty mogą nosić ślady normalnego użytkowania, \
+        które nie stanowią wady produktu, jeśli są zgodne z opisem.";
+    let s2_p3_intro =
+        "W celu złożenia Zamówienia Klient powinien wykonać co najmniej następujące czynności:";
+    let s2_p3_steps = [
+        "Dodać wybrany Produkt (lub Produkty) do koszyka.",
+        "Podać dane niezbędne do realizacji Zamówienia, takie jak: imię i nazwisko, adres dostawy, adres e-mail, numer telefonu, a w przypadku firm dodatkowo NIP i nazwę firmy.",
+        "Wybrać jeden z dostępnych sposobów dostawy.",
+        "Wybrać jeden z dostępnych sposobów płatności.",
+        "Zapoznać się z Regulaminem i zaakceptować jego postanowienia.",
+        "Kliknąć przycisk \"Zamawiam i płacę\" lub inny równoznaczny.",
+    ];
+    let s2_p4 = "Złożenie Zamówienia stanowi złożenie Sprzedawcy przez Klienta oferty zawarcia Umowy Sprzedaży Produktów będących przedmiotem Zamówienia.";
+    let s2_p5 = "Po złożeniu Zamówienia, Klient otrzymuje wiadomość e-mail zawierającą ostateczne potwierdzenie wszystkich \
+        istotnych elementów Zamówienia. Z chwilą otrzymania przez Klienta powyższej wiadomości e-mail zostaje zawarta \
+        Umowa Sprzedaży między Klientem a Sprzedawcą.";
+
+    // §3 Ceny i Metody Płatności
+    let s3_title = "§3 Ceny i Metody Płatności";
+    let s3_p1 = "Ceny Produktów podawane są w polskich złotych (PLN) i są cenami brutto (zawierają podatek VAT, jeśli dotyczy).";
+    let s3_p2 = "Ceny Produktów nie zawierają kosztów dostawy. Koszty dostawy są wskazywane w trakcie składania Zamówienia \
+        i są doliczane do całkowitej wartości Zamówienia.";
+    let s3_p3_intro =
+        "Klient może wybrać następujące metody płatności: [LISTA METOD PŁATNOŚCI, np.:]";
+    let s3_p3_methods = [
+        "Przelew tradycyjny na konto bankowe Sprzedawcy.",
+        "Płatność za pośrednictwem systemu płatności online [NAZWA SYSTEMU PŁATNOŚCI np. Przelewy24, PayU, Stripe].",
+        "[Inne dostępne metody].",
+    ];
+    let s3_p4 = "Klient zobowiązany jest do dokonania płatności w terminie [np. 7] dni kalendarzowych od dnia zawarcia \
+        Umowy Sprzedaży. W przypadku braku płatności we wskazanym terminie, Zamówienie może zostać anulowane.";
+
+    // §4 Dostawa
+    let s4_title = "§4 Dostawa";
+    let s4_p1 = "Zamówione Produkty są dostarczane na terytorium Rzeczypospolitej Polskiej. W przypadku chęci zamówienia \
+        dostawy poza terytorium Polski, prosimy o indywidualny kontakt.";
+    let s4_p2_intro = "Dostawa Produktów odbywa się za pośrednictwem [LISTA DOSTAWCÓW, np.:]";
+    let s4_p2_methods = ["Firmy kurierskiej [Nazwa firmy].", "Paczkomatów InPost."];
+    let s4_p3 = "Termin realizacji Zamówienia (przygotowanie do wysyłki) wynosi zazwyczaj [np. 1-3] dni robocze od dnia \
+        zaksięgowania wpłaty na koncie Sprzedawcy lub od dnia potwierdzenia Zamówienia w przypadku wyboru płatności \
+        za pobraniem (jeśli dostępna).";
+    let s4_p4 = "Czas dostawy przez przewoźnika zależy od wybranej metody dostawy i wynosi zazwyczaj [np. 1-2] dni robocze.";
+
+    // §5 Prawo odstąpienia od umowy
+    let s5_title = "§5 Prawo odstąpienia od umowy (dotyczy Konsumentów)";
+    let s5_p1 = "Konsument, który zawarł umowę na odległość, może w terminie 14 dni odstąpić od niej bez podawania \
+        przyczyny i bez ponoszenia kosztów, z wyjątkiem kosztów określonych w ustawie o prawach konsumenta.";
+    let s5_p2 = "Bieg terminu do odstąpienia od umowy rozpoczyna się od objęcia Produktu w posiadanie przez Konsumenta \
+        lub wskazaną przez niego osobę trzecią inną niż przewoźnik.";
+    let s5_p3_text = format!(
+        "Konsument może odstąpić od umowy, składając Sprzedawcy oświadczenie o odstąpieniu od umowy. Oświadczenie można \
+        złożyć na formularzu, którego wzór stanowi załącznik nr 2 do Ustawy o Prawach Konsumenta, lub w innej formie \
+        pisemnej, bądź drogą elektroniczną na adres e-mail: {}.",
+        contact_email
+    );
+    let s5_p3_form_intro = "Przykładowy wzór formularza odstąpienia od umowy (nieobowiązkowy):";
+    let s5_p3_form_content = format!(
+        "Miejscowość, data\n\n\
+        Imię i nazwisko konsumenta\n\
+        Adres konsumenta\n\n\
+        {}\n\
+        {}\n\n\
+        OŚWIADCZENIE O ODSTĄPIENIU OD UMOWY ZAWARTEJ NA ODLEGŁOŚĆ\n\n\
+        Oświadczam, że zgodnie z art. 27 ustawy z dnia 30 maja 2014 r. o prawach konsumenta (Dz. U. 2014 poz. 827 ze zm.) \
+        odstępuję od umowy sprzedaży następujących rzeczy: [nazwa produktu/produktów], numer zamówienia [numer zamówienia], \
+        zawartej dnia [data zawarcia umowy], odebranej dnia [data odbioru produktu].\n\n\
+        Proszę o zwrot kwoty [kwota] zł na rachunek bankowy numer: [numer rachunku bankowego, np. {}].\n\n\
+        Podpis konsumenta (tylko jeżeli formularz jest przesyłany w wersji papierowej)",
+        company_full_name, company_address, bank_account_for_returns
+    );
+    let s5_p4 = "Konsument ma obowiązek zwrócić Produkt Sprzedawcy lub przekazać go osobie upoważnionej przez Sprzedawcę \
+        do odbioru niezwłocznie, jednak nie później niż 14 dni od dnia, w którym odstąpił od umowy. Do zachowania \
+        terminu wystarczy odesłanie Produktu przed jego upływem. Konsument ponosi bezpośrednie koszty zwrotu Produktu.";
+    let s5_p5 = format!(
+        "Produkt należy zwrócić na adres: {} (lub adres siedziby, jeśli taki sam).",
+        complaint_address
+    );
+    let s5_p6 = "Sprzedawca ma obowiązek niezwłocznie, nie później niż w terminie 14 dni od dnia otrzymania oświadczenia \
+        Konsumenta o odstąpieniu od umowy, zwrócić Konsumentowi wszystkie dokonane przez niego płatności, w tym koszty \
+        dostarczenia Produktu (z wyjątkiem dodatkowych kosztów wynikających z wybranego przez Konsumenta sposobu \
+        dostarczenia innego niż najtańszy zwykły sposób dostarczenia oferowany przez Sprzedawcę).";
+    let s5_p7 = "Sprzedawca dokonuje zwrotu płatności przy użyciu takiego samego sposobu płatności, jakiego użył Konsument, \
+        chyba że Konsument wyraźnie zgodził się na inny sposób zwrotu, który nie wiąże się dla niego z żadnymi kosztami. \
+        Sprzedawca może wstrzymać się ze zwrotem płatności otrzymanych od Konsumenta do chwili otrzymania Produktu z \
+        powrotem lub dostarczenia przez Konsumenta dowodu jego odesłania, w zależności od tego, które zdarzenie nastąpi wcześniej.";
+    let s5_p8 = "Konsument ponosi odpowiedzialność za zmniejszenie wartości Produktu będące wynikiem korzystania z niego \
+        w sposób wykraczający poza konieczny do stwierdzenia charakteru, cech i funkcjonowania Produktu.";
+
+    // §6 Reklamacje
+    let s6_title = "§6 Reklamacje";
+    let s6_p1 = "Sprzedawca jest zobowiązany dostarczyć Klientowi Produkt wolny od wad fizycznych i prawnych (rękojmia), \
+        z uwzględnieniem, że oferowane Produkty są towarami używanymi, a ich stan (w tym ewentualne ślady użytkowania \
+        niebędące wadami) jest opisany indywidualnie dla każdego Produktu.";
+    let s6_p2 = format!(
+        "Reklamację można złożyć pisemnie na adres: {} lub drogą elektroniczną na adres e-mail: {}.",
+        complaint_address, contact_email
+    );
+    let s6_p3 = "Zaleca się, aby zgłoszenie reklamacyjne zawierało co najmniej: imię i nazwisko Klienta, adres do korespondencji, \
+        adres e-mail, datę nabycia towaru, rodzaj reklamowanego towaru, dokładny opis wady oraz datę jej stwierdzenia, \
+        żądanie Klienta, a także preferowany przez Klienta sposób poinformowania o sposobie rozpatrzenia reklamacji. \
+        Dołączenie dowodu zakupu może przyspieszyć proces.";
+    let s6_p4 = "Sprzedawca rozpatrzy reklamację w terminie 14 dni od dnia jej otrzymania i poinformuje Klienta o sposobie jej załatwienia.";
+    let s6_p5 = "W przypadku uznania reklamacji, Produkt wadliwy zostanie naprawiony lub wymieniony na inny, wolny od wad. \
+        Jeśli naprawa lub wymiana okażą się niemożliwe lub wymagałyby nadmiernych kosztów, Klient może żądać stosownego \
+        obniżenia ceny albo odstąpić od umowy (o ile wada jest istotna). Zwrot środków nastąpi na wskazany przez Klienta \
+        numer konta bankowego.";
+
+    // §7 Ochrona Danych Osobowych
+    let s7_title = "§7 Ochrona Danych Osobowych";
+    let s7_p1 = format!(
+        // Dodaj link do Polityki Prywatności
+        "Administratorem danych osobowych Klientów zbieranych za pośrednictwem Sklepu internetowego jest Sprzedawca. \
+        Szczegółowe informacje dotyczące przetwarzania danych osobowych oraz praw przysługujących Klientom znajdują się \
+        w Polityce Prywatności dostępnej na stronie Sklepu pod adresem: {}/htmx/page/polityka-prywatnosci.", // Użyj dynamicznego linku lub stałego
+        shop_url // Lub bezpośrednio "/htmx/page/polityka-prywatnosci", jeśli URL jest względny
+    );
+
+    // §8 Postanowienia końcowe
+    let s8_title = "§8 Postanowienia końcowe";
+    let s8_p1 = "W sprawach nieuregulowanych w niniejszym Regulaminie mają zastosowanie powszechnie obowiązujące przepisy \
+        prawa polskiego, w szczególności Kodeksu cywilnego oraz ustawy o prawach konsumenta.";
+    let s8_p2 = "Sprzedawca zastrzega sobie prawo do dokonywania zmian Regulaminu z ważnych przyczyn, np. zmiany przepisów prawa, \
+        zmiany sposobów płatności i dostaw - w zakresie, w jakim te zmiany wpływają na realizację postanowień niniejszego Regulaminu. \
+        O każdej zmianie Sprzedawca poinformuje Klienta z co najmniej 7-dniowym wyprzedzeniem, publikując zmieniony Regulamin \
+        na stronie Sklepu. Zamówienia złożone przed datą wejścia w życie zmian Regulaminu są realizowane na podstawie \
+        zapisów obowiązujących w dniu złożenia zamówienia.";
+    let s8_p3 = "Ewentualne spory powstałe pomiędzy Sprzedawcą a Klientem będącym Konsumentem zostają poddane sądom \
+        właściwym zgodnie z postanowieniami właściwych przepisów Kodeksu postępowania cywilnego.";
+    let s8_p4 = "Konsument ma możliwość skorzystania z pozasądowych sposobów rozpatrywania reklamacji i dochodzenia roszczeń. \
+        Szczegółowe informacje dotyczące możliwości skorzystania przez Konsumenta z pozasądowych sposobów rozpatrywania \
+        reklamacji i dochodzenia roszczeń oraz zasady dostępu do tych procedur dostępne są w siedzibach oraz na stronach \
+        internetowych powiatowych (miejskich) rzeczników konsumentów, organizacji społecznych, do których zadań statutowych \
+        należy ochrona konsumentów, Wojewódzkich Inspektoratów Inspekcji Handlowej oraz pod następującymi adresami \
+        internetowymi Urzędu Ochrony Konkurencji i Konsumentów: [wstaw odpowiednie linki do UOKiK, platformy ODR itp.].";
+    let s8_p5 = format!("Regulamin wchodzi w życie z dniem {}.", effective_date);
+
+    html! {
+        div ."max-w-3xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+            div ."text-center mb-10" {
+                h1 ."text-3xl sm:text-4xl font-bold tracking-tight text-gray-900" { (heading_main_text) }
+                p ."mt-2 text-sm text-gray-500" { (last_update_text) }
+            }
+
+            article ."prose prose-lg lg:prose-xl max-w-none text-gray-700 leading-relaxed space-y-6" {
+
+                h2 { (s1_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s1_p1) }
+                    li { (s1_p2) }
+                    li { (s1_p3_intro)
+                        ul ."list-disc list-inside pl-6 space-y-1 mt-1" {
+                            @for req_item in &s1_p3_reqs {
+                                li { (req_item) }
+                            }
+                        }
+                    }
+                    li { (s1_p4) }
+                    li { (s1_p5) }
+                    li { (s1_p6_intro)
+                        ul ."list-disc list-inside pl-6 space-y-1 mt-1" {
+                            @for (term, def) in &s1_p6_defs {
+                                li { strong {(term)} " - " (def) }
+                            }
+                        }
+                    }
+                }
+
+                h2 { (s2_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s2_p1) }
+                    li { (s2_p2) }
+                    li { (s2_p3_intro)
+                        ul ."list-disc list-inside pl-6 space-y-1 mt-1" {
+                            @for step_item in &s2_p3_steps {
+                                li { (step_item) }
+                            }
+                        }
+                    }
+                    li { (s2_p4) }
+                    li { (s2_p5) }
+                }
+
+                h2 { (s3_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s3_p1) }
+                    li { (s3_p2) }
+                    li { (s3_p3_intro)
+                        ul ."list-disc list-inside pl-6 space-y-1 mt-1" {
+                            @for method_item in &s3_p3_methods {
+                                li { (method_item) }
+                            }
+                        }
+                    }
+                    li { (s3_p4) }
+                }
+
+                h2 { (s4_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s4_p1) }
+                    li { (s4_p2_intro)
+                        ul ."list-disc list-inside pl-6 space-y-1 mt-1" {
+                            @for method_item in &s4_p2_methods {
+                                li { (method_item) }
+                            }
+                        }
+                    }
+                    li { (s4_p3) }
+                    li { (s4_p4) }
+                }
+
+                h2 { (s5_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s5_p1) }
+                    li { (s5_p2) }
+                    li {
+                        (s5_p3_text)
+                        br;
+                        (s5_p3_form_intro)
+                        pre ."bg-gray-100 p-3 rounded text-sm whitespace-pre-wrap mt-2" {
+                            (s5_p3_form_content)
+                        }
+                    }
+                    li { (s5_p4) }
+                    li { (s5_p5) }
+                    li { (s5_p6) }
+                    li { (s5_p7) }
+                    li { (s5_p8) }
+                }
+
+                h2 { (s6_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s6_p1) }
+                    li { (s6_p2) }
+                    li { (s6_p3) }
+                    li { (s6_p4) }
+                    li { (s6_p5) }
+                }
+
+                h2 { (s7_title) }
+                p {
+                    (PreEscaped(s7_p1.replace("[LINK DO POLITYKI PRYWATNOŚCI]", &format!("<a href=\"/htmx/page/polityka-prywatnosci\" class=\"text-pink-600 hover:underline\">{}</a>", "Polityce Prywatności"))))
+                }
+
+                h2 { (s8_title) }
+                ol ."list-decimal list-inside space-y-2" {
+                    li { (s8_p1) }
+                    li { (s8_p2) }
+                    li { (s8_p3) }
+                    li { (s8_p4) } // Pamiętaj o uzupełnieniu linków w tej zmiennej
+                    li { (s8_p5) }
+                }
+            }
+        }
+    }
+}
+
+pub async fn terms_of_service_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    handle_static_page(headers, csp_nonce, app_state, "regulamin").await
+}
+
+pub fn render_contact_page() -> Markup {
+    // Dane kontaktowe - UZUPEŁNIJ WŁASNYMI DANYMI!
+    let shop_name = "mess - all that vintage";
+    let contact_email = "contact@messvintage.com";
+    let contact_phone = Some("+48 603 117 793");
+    // let company_full_name = "mess - all that vintage";
+    // let company_address_line1 = "ul. Piotrkowska 104";
+    // let company_address_line2 = "90-001 Łódź";
+    // Możesz dodać linki do mediów społecznościowych
+    let social_facebook_url = Some("https://www.facebook.com/megjoni");
+    let social_instagram_url = Some("https://www.instagram.com/meg.joni");
+
+    // --- Definicje tekstów jako zmienne Rusta ---
+    let heading_main_text = "Skontaktuj się z nami";
+    let intro_text = format!(
+        "Masz pytania dotyczące naszych produktów, zamówienia, a może chcesz po prostu porozmawiać o modzie vintage? \
+        Jesteśmy tutaj, aby Ci pomóc! W {} cenimy każdego klienta i staramy się odpowiadać na wszystkie wiadomości \
+        tak szybko, jak to tylko możliwe.",
+        shop_name
+    );
+
+    let email_heading_text = "Napisz do nas";
+    let email_description_text = format!(
+        "Najlepszym i najszybszym sposobem na kontakt jest wysłanie wiadomości przez Whatsapp, ale można się z nami skontaktować również mailowo:"
+    );
+
+    let phone_heading_text = "Zadzwoń do nas";
+    let phone_description_text = if contact_phone.is_some() {
+        "Jeśli wolisz rozmowę telefoniczną, jesteśmy dostępni pod numerem:"
+    } else {
+        "" // Pusty, jeśli nie ma telefonu
+    };
+    let phone_hours_text = "Poniedziałek - Sobota w godzinach 10:00 - 23:00"; // Przykładowe godziny
+
+    // let address_heading_text = "Adres korespondencyjny";
+    // // let address_note_text = "(Uwaga: nie prowadzimy sprzedaży stacjonarnej pod tym adresem)"; // Jeśli dotyczy
+
+    let social_media_heading_text = "Znajdź nas w sieci";
+
+    let response_time_text =
+        "Staramy się odpowiadać na wszystkie zapytania w ciągu 24 godzin w dni robocze.";
+
+    html! {
+        div ."max-w-3xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+            div ."text-center mb-12" {
+                h1 ."text-4xl sm:text-5xl font-bold tracking-tight text-gray-900" { (heading_main_text) }
+                p ."mt-4 text-lg text-gray-600" { (intro_text) }
+            }
+
+            div ."space-y-10" {
+                // Sekcja Email
+                section ."p-6 bg-white rounded-lg border border-gray-200" {
+                    h2 ."text-2xl font-semibold text-[var(--text-color-primary)] mb-3" { (email_heading_text) }
+                    p ."text-gray-700 mb-2" { (email_description_text) }
+                    a href=(format!("mailto:{}", contact_email)) class="text-lg text-gray-900 font-medium hover:underline break-all" { (contact_email) }
+                }
+
+                // Sekcja Telefon (opcjonalna)
+                @if let Some(phone) = contact_phone {
+                    section ."p-6 bg-white rounded-lg border border-gray-200" {
+                        h2 ."text-2xl font-semibold text-[var(--text-color-primary)] mb-3" { (phone_heading_text) }
+                        @if !phone_description_text.is_empty() {
+                            p ."text-gray-700 mb-2" { (phone_description_text) }
+                        }
+                        a href=(format!("tel:{}", phone.replace(" ", ""))) class="text-lg text-gray-900 font-medium hover:underline" { (phone) }
+                        p ."text-sm text-gray-500 mt-1" { (phone_hours_text) }
+                    }
+                }
+
+                // Sekcja Media Społecznościowe (opcjonalna)
+                @if social_facebook_url.is_some() || social_instagram_url.is_some() {
+                    section ."p-6 bg-white rounded-lg border border-gray-200" {
+                        h2 ."text-2xl font-semibold text-[var(--text-color-primary)] mb-4" { (social_media_heading_text) }
+                        div ."flex space-x-6" {
+                            @if let Some(fb_url) = social_facebook_url {
+                                a href=(fb_url) target="_blank" rel="noopener noreferrer" class="text-gray-600 hover:text-blue-600 transition-colors" {
+                                    // Prosty tekst lub SVG ikona
+                                    span class="text-lg font-medium" {"Facebook"}
+                                    // Dla SVG np.:
+                                    // svg."w-8 h-8" fill="currentColor" viewBox="0 0 24 24" { path d="..." /}
+                                }
+                            }
+                            @if let Some(ig_url) = social_instagram_url {
+                                a href=(ig_url) target="_blank" rel="noopener noreferrer" class="text-gray-600 hover:text-pink-500 transition-colors" {
+                                    span class="text-lg font-medium" {"Instagram"}
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Czas odpowiedzi
+                div ."text-center mt-10 pt-6 border-t border-gray-200" {
+                    p ."text-md text-gray-600" { (response_time_text) }
+                }
+            }
+        }
+    }
+}
+pub async fn contact_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    handle_static_page(headers, csp_nonce, app_state, "kontakt").await
+}
+
+/// Akordeon FAQ (Alpine) z pytań z bazy
+pub fn render_faq_page(faq_items: &[FaqItem]) -> Markup {
+    html! {
+        div ."max-w-3xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+            div ."text-center mb-12" {
+                h1 ."text-4xl sm:text-5xl font-bold tracking-tight text-gray-900" { "Najczęściej Zadawane Pytania (FAQ)" }
+                p ."mt-3 text-lg text-gray-600" { "Masz pytanie? Sprawdź, czy nie ma tutaj odpowiedzi!" }
+            }
+
+            div ."space-y-6" { // Kontener na wszystkie pytania i odpowiedzi
+                @for (index, item) in faq_items.iter().enumerate() {
+                    div ."bg-white rounded-lg shadow-md border border-gray-200 overflow-hidden"
+                        "x-data"=(format!("{{ open: {} }}", if index == 0 { "true" } else { "false" })) // Pierwsze pytanie domyślnie otwarte
+                        {
+                        // Pytanie - klikalny nagłówek
+                        h3 ."cursor-pointer p-5 sm:p-6 border-b border-gray-200 hover:bg-gray-50 transition-colors duration-150"
+                           "@click"="open = !open"
+                           class="flex justify-between items-center w-full" {
+                            span ."text-lg font-semibold text-gray-800" { (item.question) }
+                            span ."text-pink-500" { // Kontener na ikonkę
+                                svg ."w-6 h-6 transform transition-transform duration-200 ease-in-out"
+                                    "x-bind:class"="open ? 'rotate-180' : ''" // Obrót ikonki
+                                    fill="none" stroke="currentColor" "viewBox"="0 0 24 24" "xmlns"="http://www.w3.org/2000/svg" {
+                                    path "stroke-linecap"="round" "stroke-linejoin"="round" "stroke-width"="2" d="M19 9l-7 7-7-7";
+                                }
+                            }
+                        }
+                        // Odpowiedź - rozwijana sekcja
+                        div ."p-5 sm:p-6 text-gray-700 leading-relaxed prose max-w-none" // prose dla formatowania tekstu
+                            "x-show"="open"
+                            "x-cloak"
+                            "x-transition:enter"="transition ease-out duration-300"
+                            "x-transition:enter-start"="opacity-0 max-h-0"
+                            "x-transition:enter-end"="opacity-100 max-h-screen"
+                            "x-transition:leave"="transition ease-in duration-200"
+                            "x-transition:leave-start"="opacity-100 max-h-screen"
+                            "x-transition:leave-end"="opacity-0 max-h-0"
+                            style="overflow: hidden;" {
+
+                            @for line in item.rendered_answer().lines() {
+                                (line) br;
+                            }
+                        }
+                    }
+                }
+        }
+            }
+    }
+}
+
+pub async fn faq_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    handle_static_page(headers, csp_nonce, app_state, "faq").await
+}
+
+/// FAQ razem z danymi strukturalnymi (FAQPage). Dane są w treści, a nie w <head>,
+/// żeby trafiały do cache'u razem z pytaniami i znikały, gdy FAQ zastąpi wersja z panelu.
+pub fn render_faq_with_schema(faq_items: &[FaqItem]) -> Markup {
+    // Generowanie danych strukturalnych
+    let answers: Vec<String> = faq_items.iter().map(FaqItem::rendered_answer).collect();
+    let questions: Vec<SchemaQuestion> = faq_items
+        .iter()
+        .zip(&answers)
+        .map(|(item, answer)| SchemaQuestion {
+            type_of: "Question",
+            name: &item.question,
+            accepted_answer: SchemaAcceptedAnswer {
+                type_of: "AcceptedAnswer",
+                text: answer,
+            },
+        })
+        .collect();
+
+    let faq_schema = SchemaFAQPage {
+        context: "https://schema.org",
+        type_of: "FAQPage",
+        main_entity: questions,
+    };
+
+    let json_ld_string = serde_json::to_string(&faq_schema).unwrap_or_default();
+    html! {
+        script type="application/ld+json" { (PreEscaped(json_ld_string)) }
+        (render_faq_page(faq_items))
+    }
+}
+
+pub fn render_shipping_returns_page() -> Markup {
+    let shop_name = "mess - all that vintage";
+    let processing_time = "1-2 dni robocze";
+    let delivery_time = "1-2 dni robocze";
+    let free_shipping_threshold = format_price_maud(free_shipping_threshold());
+    let contact_email_returns = "contact@messvintage.com";
+    let return_address_line1 = "mess - all that vintage - Zwroty";
+    let return_address_line2 = "ul. Magazynowa 5";
+    let return_address_line3 = "00-002 Miasto";
+    let link_to_terms = "/htmx/page/regulamin";
+
+    let page_title = "Wysyłka i Zwroty";
+    let page_subtitle = format!(
+        "Wszystko, co musisz wiedzieć o dostawie i zwrotach w {}",
+        shop_name
+    );
+
+    let shipping_section_title = "Informacje o Wysyłce";
+    let shipping_area = "Realizujemy wysyłki na terenie całej Polski.".to_string();
+    let shipping_carriers_intro = "Korzystamy z usług zaufanych partnerów logistycznych, aby Twoje zamówienie dotarło bezpiecznie i na czas. Dostępne opcje to:".to_string();
+    let shipping_carriers_list = [
+        "Paczkomaty InPost 24/7".to_string(),
+        "Poczta Polska".to_string(),
+    ];
+    let shipping_costs_text = format!(
+        "Koszty wysyłki są obliczane automatycznie podczas składania zamówienia i zależą od wybranej metody dostawy \
+        oraz wagi/gabarytów paczki. Dokładny koszt zobaczysz przed finalizacją zakupu. \
+        Pamiętaj, że dla wszystkich zamówień powyżej {} dostawa jest całkowicie darmowa!",
+        free_shipping_threshold
+    );
+    let processing_time_text = format!(
+        "Staramy się, aby każde zamówienie zostało przygotowane i wysłane jak najszybciej. \
+        Standardowy czas realizacji (przygotowanie paczki do nadania) wynosi {}.",
+        processing_time
+    );
+    let delivery_time_text = format!(
+        "Po nadaniu przesyłki, przewidywany czas dostawy przez naszych partnerów logistycznych to zwykle {}.",
+        delivery_time
+    );
+    let tracking_text =
+        "Gdy tylko Twoje zamówienie zostanie wysłane, otrzymasz od nas wiadomość e-mail, bądź poinformujemy Cie na komunikatorze WhatsApp/Messenger/Instagram".to_string();
+    let packaging_text = "Każde vintage cudo pakujemy z najwyższą starannością, używając (tam gdzie to możliwe) \
+        materiałów przyjaznych środowisku, aby Twoje nowe nabytki dotarły do Ciebie w nienaruszonym stanie.".to_string();
+
+    let returns_section_title = "Zwroty i Odstąpienie od Umowy";
+    let right_to_return_text = format!(
+        "Rozumiemy, że czasem coś może nie pasować idealnie. Zgodnie z obowiązującym prawem, jako Konsument masz \
+        14 dni kalendarzowych na odstąpienie od umowy sprzedaży (zwrot towaru) bez podawania przyczyny, licząc od dnia, \
+        w którym otrzymałeś/aś przesyłkę. Pełne informacje na ten temat znajdziesz w naszym Regulaminie Sklepu (link poniżej)."
+    );
+    let return_conditions_heading = "Warunki Zwrotu:";
+    let return_conditions_list = [
+        "Produkt nie może nosić żadnych nowych śladów użytkowania poza tymi, które wynikały z jego charakteru vintage i były jasno opisane na stronie produktu.".to_string(),
+        "Produkt powinien posiadać wszystkie oryginalne metki i oznaczenia (jeśli były dołączone).".to_string(),
+        "Produkt musi być kompletny i zwrócony w stanie umożliwiającym jego dalszą odsprzedaż.".to_string(),
+        "Prosimy o staranne zapakowanie zwracanego towaru, aby nie uległ uszkodzeniu podczas transportu.".to_string()
+    ];
+    let return_procedure_heading = "Procedura Zwrotu - krok po kroku:";
+    let return_procedure_steps = [
+        format!("1. Poinformuj nas: Skontaktuj się z nami mailowo na adres {} w ciągu 14 dni od otrzymania towaru, informując o chęci dokonania zwrotu. Podaj numer zamówienia i zwracane produkty. Możesz skorzystać ze wzoru formularza odstąpienia od umowy dostępnego w Regulaminie Sklepu, ale nie jest to obowiązkowe.", contact_email_returns),
+        "2. Przygotuj paczkę: Starannie zapakuj zwracane produkty wraz z dowodem zakupu lub jego kopią oraz (opcjonalnie) wypełnionym formularzem zwrotu.".to_string(),
+        format!("3. Odeślij produkt: Wyślij paczkę na adres: {}, {}, {}. Pamiętaj, że bezpośredni koszt odesłania produktu ponosi Klient. Nie przyjmujemy przesyłek za pobraniem.", return_address_line1, return_address_line2, return_address_line3),
+        "4. Oczekuj na zwrot środków: Po otrzymaniu i pozytywnym zweryfikowaniu przesyłki zwrotnej, niezwłocznie (nie później niż w ciągu 14 dni) zwrócimy Ci należność za produkty oraz pierwotne koszty najtańszej oferowanej przez nas formy dostawy. Zwrot nastąpi tą samą metodą płatności, jakiej użyłeś/aś przy zakupie, chyba że wspólnie ustalimy inaczej.".to_string()
+    ];
+    let non_returnable_heading = "Produkty niepodlegające zwrotowi:";
+    let non_returnable_text = "Ze względu na charakter naszych produktów (odzież używana/vintage), większość z nich podlega standardowej procedurze zwrotu. Wyjątki mogą dotyczyć np. bielizny ze względów higienicznych, jeśli została rozpakowana z zapieczętowanego opakowania – o takich sytuacjach zawsze informujemy w opisie produktu.".to_string();
+
+    let complaints_section_title = "Reklamacje";
+    let complaints_text_part1 = "W mess - all that vintage przykładamy ogromną wagę do jakości i dokładności opisów naszych unikatowych produktów. \
+        Jeśli jednak zdarzy się, że otrzymany towar posiada wadę, która nie została ujawniona w opisie, lub jest \
+        niezgodny z zamówieniem, masz pełne prawo do złożenia reklamacji. Szczegółowe informacje dotyczące procedury \
+        reklamacyjnej, Twoich praw oraz naszych obowiązków znajdziesz w §6 naszego Regulaminu Sklepu, dostępnego tutaj: ";
+    let complaints_text_part2 = ".";
+
+    html! {
+            div ."max-w-4xl mx-auto px-4 sm:px-6 lg:px-8 py-12 sm:py-16" {
+                div ."text-center mb-12" {
+                    h1 ."text-4xl sm:text-5xl font-bold tracking-tight text-gray-900" { (page_title) }
+                    p ."mt-3 text-lg text-gray-600" { (page_subtitle) }
+                }
+
+                div ."space-y-8" {
+                    // Sekcja Wysyłka
+                    div "x-data"="{ open: true }" ."bg-white rounded-xl border border-gray-200 overflow-hidden" {
+                        button type="button" "@click"="open = !open" class="w-full flex justify-between items-center p-5 sm:p-6 text-left hover:bg-gray-50 focus:outline-none" {
+                            h2 ."text-2xl sm:text-3xl font-semibold text-[var(--text-color-primary)]" { (shipping_section_title) }
+                            svg ."w-6 h-6 text-[var(--text-color-primary)] transform transition-transform duration-200" "x-bind:class"="open ? 'rotate-180' : ''" fill="none" stroke="currentColor" "viewBox"="0 0 24 24" "xmlns"="http://www.w3.org/2000/svg" {
+                                path "stroke-linecap"="round" "stroke-linejoin"="round" "stroke-width"="2" d="M19 9l-7 7-7-7";
+                            }
+                        }
+                        div ."px-5 sm:px-6 pb-6 pt-3 prose prose-lg max-w-none text-gray-700 leading-relaxed"
+                            "x-show"="open" "x-cloak"
+                            "x-transition:enter"="transition ease-out duration-300" "x-transition:enter-start"="opacity-0 max-h-0" "x-transition:enter-end"="opacity-100 max-h-[1000px]"
+                            "x-transition:leave"="transition ease-in duration-200" "x-transition:leave-start"="opacity-100 max-h-[1000px]" "x-transition:leave-end"="opacity-0 max-h-0"
+                            style="overflow: hidden;" {
+
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { "Obszar dostawy" }
+                            p { (shipping_area) }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { "Dostępni przewoźnicy" }
+                            p { (shipping_carriers_intro) }
+                            ul ."list-disc pl-5 space-y-1" {
+                                @for carrier in &shipping_carriers_list {
+                                    li { (carrier) }
+                                }
+                            }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { "Koszty wysyłki" }
+                            p { (shipping_costs_text) }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { "Czas realizacji i dostawy" }
+                            p { (processing_time_text) }
+                            p { (delivery_time_text) }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { "Śledzenie przesyłki" }
+                            p { (tracking_text) }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { "Pakowanie" }
+                            p { (packaging_text) }
+                        }
+                    }
+
+                    // Sekcja Zwroty
+                    div "x-data"="{ open: false }" ."bg-white rounded-xl border border-gray-200 overflow-hidden" {
+                        button type="button" "@click"="open = !open" class="w-full flex justify-between items-center p-5 sm:p-6 text-left hover:bg-gray-50 focus:outline-none" {
+                            h2 ."text-2xl sm:text-3xl font-semibold text-[var(--text-color-primary)]" { (returns_section_title) }
+                            svg ."w-6 h-6 text-[var(--text-color-primary)] transform transition-transform duration-200" "x-bind:class"="open ? 'rotate-180' : ''" fill="none" stroke="currentColor" "viewBox"="0 0 24 24" "xmlns"="http://www.w3.org/2000/svg" {
+                                path "stroke-linecap"="round" "stroke-linejoin"="round" "stroke-width"="2" d="M19 9l-7 7-7-7";
+                            }
+                        }
+                        div ."px-5 sm:px-6 pb-6 pt-3 prose prose-lg max-w-none text-gray-700 leading-relaxed"
+                            "x-show"="open" "x-cloak"
+                            "x-transition:enter"="transition ease-out duration-300" "x-transition:enter-start"="opacity-0 max-h-0" "x-transition:enter-end"="opacity-100 max-h-[1500px]"
+                            "x-transition:leave"="transition ease-in duration-200" "x-transition:leave-start"="opacity-100 max-h-[1500px]" "x-transition:leave-end"="opacity-0 max-h-0"
+                            style="overflow: hidden;" {
+
+                            p { (right_to_return_text) }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { (return_conditions_heading) }
+                            ul ."list-disc pl-5 space-y-1" {
+                                @for condition in &return_conditions_list {
+                                    li { (condition) }
+                                }
+                            }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { (return_procedure_heading) }
+                            ol ."list-decimal pl-5 space-y-2" {
+                                @for step in &return_procedure_steps {
+                                    li { (step) }
+                                }
+                            }
+                            h3 ."text-xl font-semibold text-gray-800 mt-4 mb-2" { (non_returnable_heading) }
+                            p { (non_returnable_text) }
+                        }
+                    }
+
+                    // Sekcja Reklamacje
+                    div ."p-6 bg-white rounded-lg border border-gray-200" {
+                        h2 ."text-2xl sm:text-3xl font-semibold text-[var(--text-color-primary)] mb-3" { (complaints_section_title) }
+
+                        // ZMIANA: Budujemy paragraf i link bezpośrednio w maud
+                        p ."text-gray-700 leading-relaxed" {
+                            (complaints_text_part1)
+                            a href=(link_to_terms)
+                               class="text-[var(--text-color-primary)] hover:text-[var(--text-color-primary-hover)] hover:underline"
+                               hx-get=(link_to_terms)
+                               hx-target="#content"
+                               hx-swap="innerHTML"
+                               hx-push-url=(link_to_terms) {
+                                "Regulamin Sklepu"
+                            }
+                            (complaints_text_part2)
+                    }
+                }
+           }
+       }
+    }
+}
+pub async fn shipping_returns_page_handler(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    State(app_state): State<Arc<AppState>>,
+) -> Result<Response, AppError> {
+    handle_static_page(headers, csp_nonce, app_state, "wysylka-i-zwroty").await
+}
+
+/// Generyczna funkcja do obsługi stron statycznych z cachowaniem.
+///
+/// # Argumenty
+/// * `app_state` - Stan aplikacji z dostępem do cache'u.
+/// * `slug` - Adres strony z `BUILT_IN_PAGES`; wyznacza klucz w cache'u, tytuł i wbudowaną treść.
+///   Treść generuje `static_page_content` - wersja z panelu albo wbudowany renderer.
+async fn handle_static_page(
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+    app_state: Arc<AppState>,
+    slug: &'static str,
+) -> Result<Response, AppError> {
+    let page = built_in_page(slug).ok_or(AppError::NotFound)?;
+    let cache_key = static_page_cache_key(slug);
+    let social_meta = SocialMeta::new(
+        page.title,
+        page.description,
+        &app_state.public_base_url,
+        &format!("/{}", slug),
+    );
+
+    // 1. Sprawdź, czy wersja strony istnieje w cache'u.
+    let cached_page = app_state.static_html_cache.get(&cache_key).await;
+    app_state
+        .cache_stats
+        .record_lookup(CacheName::StaticHtml, cached_page.is_some());
+    if let Some(cached_html) = cached_page {
+        tracing::info!("Zwracam stronę '{}' z cache'u.", cache_key);
+        // Jeśli tak, zbuduj odpowiedź na podstawie danych z cache'u i natychmiast ją zwróć.
+        let page_builder = PageBuilder::new(
+            page.title,
+            html! { (maud::PreEscaped(cached_html)) },
+            None,
+            None,
+        )
+        .with_social_meta(social_meta);
+        return build_response(headers, &csp_nonce, page_builder).await;
+    }
+
+    // 2. Jeśli strona nie istnieje w cache'u, wygeneruj ją.
+    tracing::info!("Generuję stronę '{}' (brak w cache'u).", cache_key);
+
+    let page_content = static_page_content(&app_state, page).await?;
+    let page_content_str = page_content.into_string();
+
+    // 3. Zapisz nowo wygenerowaną treść w cache'u na przyszłość.
+    app_state
+        .static_html_cache
+        .insert(cache_key.clone(), page_content_str.clone())
+        .await;
+    app_state
+        .cache_stats
+        .record_insert(CacheName::StaticHtml, &cache_key);
+
+    // 4. Zbuduj i zwróć odpowiedź.
+    let page_builder = PageBuilder::new(
+        page.title,
+        html! { (maud::PreEscaped(page_content_str)) },
+        None,
+        None,
+    )
+    .with_social_meta(social_meta);
+    build_response(headers, &csp_nonce, page_builder).await
+}
+
+/// Tyle propozycji produktów pokazuje strona 404
+const NOT_FOUND_SUGGESTIONS: i64 = 4;
+
+/// Słowa z adresu, które nic nie mówią o szukanym produkcie
+const NOT_FOUND_IGNORED_WORDS: [&str; 8] = [
+    "produkty",
+    "produkt",
+    "htmx",
+    "kategoria",
+    "dla",
+    "niej",
+    "niego",
+    "html",
+];
+
+/// Kategoria i słowa do wyszukania z brakującego adresu, np. "/dla-niej/sukienki/czerwona-midi"
+/// daje kategorię Sukienki i słowa "czerwona", "midi". Słowa z cyframi (końcówki slugów z UUID)
+/// pomijamy.
+fn suggestion_query_from_path(path: &str) -> (Option<Category>, Vec<String>) {
+    let mut category = None;
+    let mut words = Vec::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        let segment = urlencoding::decode(segment)
+            .map(|decoded| decoded.into_owned())
+            .unwrap_or_else(|_| segment.to_string());
+        if let Ok(segment_category) = Category::from_str(&segment) {
+            category.get_or_insert(segment_category);
+            continue;
+        }
+        words.extend(
+            segment
+                .split(|c: char| !c.is_alphanumeric())
+                .map(str::to_lowercase)
+                .filter(|word| word.chars().count() >= 3)
+                .filter(|word| !word.chars().any(|c| c.is_ascii_digit()))
+                .filter(|word| !NOT_FOUND_IGNORED_WORDS.contains(&word.as_str())),
+        );
+    }
+    words.truncate(6);
+    (category, words)
+}
+
+/// Propozycje do strony 404: najpierw po słowach z adresu, potem najnowsze z kategorii
+async fn not_found_suggestions(app_state: &AppState, path: &str) -> Vec<Product> {
+    let (category, words) = suggestion_query_from_path(path);
+    let pool = &app_state.db_pool;
+    let suggestions = match suggest_products(pool, &words, category, NOT_FOUND_SUGGESTIONS).await {
+        Ok(products) if products.is_empty() && !words.is_empty() => {
+            suggest_products(pool, &[], category, NOT_FOUND_SUGGESTIONS).await
+        }
+        result => result,
+    };
+    suggestions.unwrap_or_else(|e| {
+        tracing::warn!(
+            "Nie udało się pobrać propozycji dla 404 '{}': {:?}",
+            path,
+            e
+        );
+        Vec::new()
+    })
+}
+
+/// Handler, który renderuje stronę błędu 404 z propozycjami produktów i wyszukiwarką.
+pub async fn handler_404(
+    State(app_state): State<Arc<AppState>>,
+    uri: Uri,
+    headers: HeaderMap,
+    csp_nonce: CspNonce,
+) -> impl IntoResponse {
+    let suggestions = not_found_suggestions(&app_state, uri.path()).await;
+
+    let page_content = html! {
+        div ."min-h-[60vh] flex flex-col items-center justify-center text-center p-4" {
+            div ."w-full max-w-4xl" {
+                // Duży, stylizowany napis "404"
+                p ."text-8xl sm:text-9xl font-black text-pink-200" { "404" }
+
+                // Główny komunikat
+                h1 ."mt-2 text-3xl sm:text-4xl font-bold text-gray-800 tracking-tight" {
+                    "Strona nie została znaleziona"
+                }
+
+                // Dodatkowy opis
+                p ."mt-4 text-base text-gray-600" {
+                    "Przepraszamy, nie mogliśmy znaleźć strony, której szukasz. Może ten produkt został już sprzedany - poszukaj podobnego:"
+                }
+
+                // Wyszukiwarka z podpowiedziami (ta sama co w nagłówku strony)
+                div ."mt-6 mx-auto max-w-md relative text-left"
+                    x-data="{ hasResults: false }"
+                    "@htmx:after-request"="if ($event.target.id === 'not-found-search-input') hasResults = $event.detail.xhr.responseText.trim() !== ''"
+                    "@click.away"="hasResults = false" {
+                    form hx-get="/htmx/products" hx-target="#content" hx-swap="innerHTML" hx-push-url="true"
+                         class="flex w-full" "@submit"="hasResults = false" {
+                        input type="search" name="search" id="not-found-search-input"
+                              placeholder="Szukaj perełek..." aria-label="Wyszukaj produkty" autocomplete="off"
+                              hx-get="/htmx/live-search" hx-trigger="keyup changed delay:300ms, search"
+                              hx-target="#not-found-search-results" hx-swap="innerHTML"
+                              "@focus"="hasResults = true"
+                              class="w-full px-4 py-2 border border-gray-300 rounded-l-full focus:outline-none focus:ring-2 focus:ring-[var(--color-primary)] focus:border-transparent text-sm";
+                        button type="submit"
+                               class="px-4 py-2 bg-black text-white rounded-r-full hover:bg-gray-700 text-sm" { "Szukaj" }
+                    }
+                    div #not-found-search-results x-show="hasResults" x-transition x-cloak
+                        class="absolute mt-1 w-full bg-white rounded-md shadow-lg z-20 overflow-hidden border border-gray-200" {}
+                }
+
+                @if !suggestions.is_empty() {
+                    h2 ."mt-10 mb-4 text-xl font-semibold text-gray-800" { "Może zainteresuje Cię" }
+                    div ."grid grid-cols-2 md:grid-cols-4 gap-4 text-left" {
+                        @for product in &suggestions {
+                            (product_tile_maud(product, "block"))
+                        }
+                    }
+                }
+
+                // Przycisk powrotu na stronę główną
+                div ."mt-8" {
+                    a href="/"
+                       hx-get="/htmx/products?limit=8" // Ładuje domyślne produkty
+                       hx-target="#content"
+                       hx-swap="innerHTML"
+                       hx-push-url="/"
+                       class="inline-block px-6 py-3 bg-pink-600 text-white font-semibold rounded-lg shadow-md hover:bg-pink-700 transition-all duration-200 ease-in-out" {
+                        "Powrót na stronę główną"
+                    }
+                }
+            }
+        }
+    };
+
+    let title = "Bład 404 - sklep mess - all that vintage";
+    // Zbuduj odpowiedź (pełną stronę lub fragment) i ustaw status na 404 NOT FOUND
+    let page_builder = PageBuilder::new(title, page_content, None, None);
+    let response = build_response(headers, &csp_nonce, page_builder)
+        .await
+        .unwrap_or_else(|err| err.into_response());
+    (StatusCode::NOT_FOUND, response)
+}
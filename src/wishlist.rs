@@ -0,0 +1,118 @@
+// src/wishlist.rs
+
+// Ulubione produkty klientów i powiadomienia o obniżkach. Gdy admin obniży cenę albo oznaczy
+// produkt jako okazję, każdy klient z tym produktem w ulubionych dostaje e-mail. Wysyłka idzie
+// w tle po zatwierdzeniu transakcji, żeby wycofana zmiana nie wysłała maila.
+
+use std::sync::Arc;
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::email_service::send_price_drop_email;
+use crate::errors::AppError;
+use crate::models::{Product, ProductStatus};
+use crate::state::AppState;
+
+/// Czy produkt jest w ulubionych klienta
+pub async fn is_on_wishlist(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+) -> Result<bool, AppError> {
+    let on_wishlist: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM wishlist_items WHERE user_id = $1 AND product_id = $2)",
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(on_wishlist)
+}
+
+/// Dodaje produkt do ulubionych albo go z nich usuwa. Zwraca `true`, gdy produkt został dodany.
+pub async fn toggle_wishlist_item(
+    pool: &PgPool,
+    user_id: Uuid,
+    product_id: Uuid,
+) -> Result<bool, AppError> {
+    let removed = sqlx::query("DELETE FROM wishlist_items WHERE user_id = $1 AND product_id = $2")
+        .bind(user_id)
+        .bind(product_id)
+        .execute(pool)
+        .await?
+        .rows_affected()
+        > 0;
+    if removed {
+        return Ok(false);
+    }
+    sqlx::query(
+        r#"
+            INSERT INTO wishlist_items (user_id, product_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+        "#,
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .execute(pool)
+    .await?;
+    Ok(true)
+}
+
+/// Obniżka to niższa cena (z trwającą promocją) albo nowo włączona flaga "okazja"
+pub fn is_price_drop(before: &Product, after: &Product) -> bool {
+    after.effective_price() < before.effective_price() || (after.on_sale && !before.on_sale)
+}
+
+/// Po zatwierdzonej zmianie ceny/okazji wysyła w tle e-mail do klientów, którzy mają produkt
+/// w ulubionych. Sprzedanych, zarezerwowanych i zarchiwizowanych produktów nie ogłaszamy.
+pub fn notify_price_drop(app_state: &Arc<AppState>, before: &Product, after: &Product) {
+    if after.status != ProductStatus::Available || !is_price_drop(before, after) {
+        return;
+    }
+    let app_state = app_state.clone();
+    let product = after.clone();
+    let old_price = before.effective_price();
+    tokio::spawn(async move {
+        let recipients: Vec<String> = match sqlx::query_scalar(
+            r#"
+                SELECT u.email
+                FROM wishlist_items w
+                JOIN users u ON u.id = w.user_id
+                WHERE w.product_id = $1
+            "#,
+        )
+        .bind(product.id)
+        .fetch_all(&app_state.db_pool)
+        .await
+        {
+            Ok(recipients) => recipients,
+            Err(e) => {
+                tracing::error!(
+                    "Nie udało się pobrać ulubionych dla produktu {}: {:?}",
+                    product.id,
+                    e
+                );
+                return;
+            }
+        };
+        for email in &recipients {
+            if let Err(e) = send_price_drop_email(&app_state, email, &product, old_price).await {
+                tracing::error!(
+                    "Nie udało się wysłać powiadomienia o obniżce produktu {} do {}: {:?}",
+                    product.id,
+                    email,
+                    e
+                );
+            }
+        }
+        if !recipients.is_empty() {
+            tracing::info!(
+                "Wysłano {} powiadomień o obniżce produktu {}",
+                recipients.len(),
+                product.id
+            );
+        }
+    });
+}
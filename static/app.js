@@ -161,6 +161,11 @@ document.addEventListener("DOMContentLoaded", function () {
   })();
 });
 
+/** Token CSRF wstawiany przez serwer do `<meta name="csrf-token">` (dla żądań `fetch`). */
+function csrfToken() {
+  return document.querySelector('meta[name="csrf-token"]')?.content || "";
+}
+
 initEventListeners();
 function initEventListeners() {
   document.body.addEventListener("htmx:configRequest", (event) => {
@@ -209,7 +214,10 @@ function initEventListeners() {
       console.warn(
         `Wygasła sesja (401) dla ścieżki: ${requestPath}. Usuwam token i przeładowuję stronę.`,
       );
-      fetch("/api/auth/logout", { method: "POST" }).catch((err) =>
+      fetch("/api/auth/logout", {
+        method: "POST",
+        headers: { "X-CSRF-Token": csrfToken() },
+      }).catch((err) =>
         console.error("Błąd podczas serwerowego wylogowania:", err),
       );
       localStorage.removeItem("jwtToken");
@@ -238,6 +246,30 @@ function initEventListeners() {
     }
   });
 
+  /**
+   * Odpowiedź 422 z nagłówkiem HX-Retarget to fragment formularza z błędami przy polach
+   * (np. krok kasy) - podmieniamy tylko wskazany fragment, a wpisane dane zostają.
+   */
+  document.body.addEventListener("htmx:beforeSwap", (event) => {
+    const { xhr } = event.detail;
+    if (xhr?.status === 422 && xhr.getResponseHeader("HX-Retarget")) {
+      event.detail.shouldSwap = true;
+      event.detail.isError = false;
+    }
+  });
+
+  /**
+   * Przed ponownym wysłaniem formularza czyścimy komunikaty przy polach (`.field-error`)
+   * z poprzedniej odpowiedzi 422 - serwer odeśle tylko te, które nadal są aktualne.
+   */
+  document.body.addEventListener("htmx:beforeRequest", (event) => {
+    const form = event.detail.elt?.closest?.("form");
+    if (!form) return;
+    form.querySelectorAll(".field-error").forEach((el) => {
+      el.textContent = "";
+    });
+  });
+
   /**
    * Przechwytuje odpowiedź z udanej aktualizacji produktu (PATCH)
    * aby wyświetlić komunikat i przeładować listę, zamiast wstawiać JSON na stronę.
@@ -490,6 +522,7 @@ function adminProductEditForm() {
       }
 
       this.imageFiles[index] = selectedFile;
+      if (index === 0) this.suggestAttributesFromImage(selectedFile);
       const reader = new FileReader();
       reader.onload = (e) => {
         this.$nextTick(() => {
@@ -536,9 +569,260 @@ function adminProductEditForm() {
       const originalUrl = this.getOriginalUrlForSlot(index);
       return originalUrl && this.imagesToDelete.includes(originalUrl);
     },
+
+    /** Czy slot pokazuje zdjęcie zapisane już w produkcie (a nie nowy, niewysłany plik). */
+    isSavedImage(index) {
+      return (
+        !!this.getOriginalUrlForSlot(index) &&
+        !this.imageFiles[index] &&
+        !this.isMarkedForDeletion(index)
+      );
+    },
+
+    cleanImageBackground(index, productId) {
+      const imageUrl = this.getOriginalUrlForSlot(index);
+      if (!imageUrl) return;
+      if (
+        !confirm(
+          "Usunąć tło tego zdjęcia? Formularz zostanie przeładowany, niezapisane zmiany przepadną.",
+        )
+      ) {
+        return;
+      }
+      htmx.ajax(
+        "POST",
+        `/api/admin/products/${productId}/images/clean-background`,
+        { values: { image_url: imageUrl }, swap: "none" },
+      );
+    },
+
+    attributeSuggestions: null,
+    attributeSuggestionsLoading: false,
+
+    /**
+     * Wysyła zdjęcie główne do analizy i pokazuje podpowiedzi kategorii/koloru/materiału.
+     * Podpowiedzi nie zmieniają formularza, dopóki admin ich nie zastosuje.
+     */
+    async suggestAttributesFromImage(file) {
+      if (this.$root.dataset.attributeSuggestions !== "true") return;
+
+      const formData = new FormData();
+      formData.append("image", file);
+      const headers = { "X-CSRF-Token": csrfToken() };
+      const jwtToken = localStorage.getItem("jwtToken");
+      if (jwtToken) headers["Authorization"] = `Bearer ${jwtToken}`;
+
+      this.attributeSuggestionsLoading = true;
+      this.attributeSuggestions = null;
+      try {
+        const response = await fetch("/api/admin/products/suggest-attributes", {
+          method: "POST",
+          headers,
+          body: formData,
+        });
+        if (!response.ok) throw new Error(`Status ${response.status}`);
+        this.attributeSuggestions = await response.json();
+      } catch (e) {
+        console.error("Błąd podpowiedzi atrybutów:", e);
+      } finally {
+        this.attributeSuggestionsLoading = false;
+      }
+    },
+
+    applySuggestedCategory() {
+      const select = this.$root.querySelector("#category");
+      if (select && this.attributeSuggestions?.category) {
+        select.value = this.attributeSuggestions.category;
+      }
+    },
+
+    appendSuggestionToDescription(value) {
+      const textarea = this.$root.querySelector("#description");
+      if (!textarea) return;
+      const separator = textarea.value.trim() ? "\n" : "";
+      textarea.value = `${textarea.value.trimEnd()}${separator}${value}`;
+    },
+
+    isDrafting: false,
+
+    /**
+     * Pobiera strumieniowo szkic opisu z asystenta i wpisuje go do pola opisu.
+     * Nic nie jest zapisywane - admin musi przejrzeć tekst i kliknąć "Zapisz".
+     */
+    async draftDescription() {
+      const form = this.$el.closest("form") || this.$root;
+      const textarea = form.querySelector("#description");
+      const nameInput = form.querySelector("#name");
+      if (!textarea || !nameInput || !nameInput.value.trim()) {
+        alert("Najpierw podaj nazwę produktu.");
+        return;
+      }
+      if (
+        textarea.value.trim() &&
+        !confirm("Zastąpić obecny opis szkicem z asystenta?")
+      ) {
+        return;
+      }
+
+      const selectedLabel = (id) => {
+        const select = form.querySelector(`#${id}`);
+        return select ? select.selectedOptions[0]?.text || "" : "";
+      };
+      const inputValue = (id) => form.querySelector(`#${id}`)?.value || null;
+
+      const headers = {
+        "Content-Type": "application/json",
+        "X-CSRF-Token": csrfToken(),
+      };
+      const jwtToken = localStorage.getItem("jwtToken");
+      if (jwtToken) headers["Authorization"] = `Bearer ${jwtToken}`;
+
+      this.isDrafting = true;
+      try {
+        const response = await fetch("/api/admin/products/description-draft", {
+          method: "POST",
+          headers,
+          body: JSON.stringify({
+            name: nameInput.value,
+            category: selectedLabel("category"),
+            gender: selectedLabel("gender"),
+            condition: selectedLabel("condition"),
+            brand: inputValue("draft_brand"),
+            measurements: inputValue("draft_measurements"),
+          }),
+        });
+        if (!response.ok || !response.body) {
+          throw new Error(`Status ${response.status}`);
+        }
+
+        textarea.value = "";
+        const reader = response.body.getReader();
+        const decoder = new TextDecoder();
+        while (true) {
+          const { done, value } = await reader.read();
+          if (done) break;
+          textarea.value += decoder.decode(value, { stream: true });
+          textarea.scrollTop = textarea.scrollHeight;
+        }
+      } catch (e) {
+        console.error("Błąd asystenta opisów:", e);
+        alert("Nie udało się wygenerować opisu. Spróbuj ponownie.");
+      } finally {
+        this.isDrafting = false;
+      }
+    },
   };
 }
 
+/**
+ * Komponent Alpine.js do wyboru Paczkomatu w kasie.
+ * Wyniki pochodzą z /api/shipping/inpost/points (proxy do InPost ShipX).
+ */
+function inpostLockerPicker() {
+  return {
+    visible: false,
+    query: "",
+    points: [],
+    loading: false,
+    error: "",
+    selectedCode: "",
+    selectedLabel: "",
+
+    init() {
+      // Metoda dostawy mogła zostać wybrana, zanim komponent się zainicjalizował
+      this.$nextTick(() => {
+        const keyInput = document.getElementById(
+          "selected_shipping_method_key_input",
+        );
+        this.visible = !!keyInput && keyInput.value === "inpost";
+      });
+    },
+
+    async search() {
+      const q = this.query.trim();
+      this.error = "";
+      if (q.length < 3) {
+        this.points = [];
+        return;
+      }
+      this.loading = true;
+      try {
+        const response = await fetch(
+          `/api/shipping/inpost/points?q=${encodeURIComponent(q)}`,
+        );
+        if (!response.ok) throw new Error(`Status ${response.status}`);
+        this.points = await response.json();
+        if (this.points.length === 0) {
+          this.error = "Nie znaleziono Paczkomatów dla podanej frazy.";
+        }
+      } catch (e) {
+        console.error("Błąd wyszukiwania Paczkomatów:", e);
+        this.error = "Nie udało się pobrać listy Paczkomatów. Spróbuj ponownie.";
+      } finally {
+        this.loading = false;
+      }
+    },
+
+    select(point) {
+      this.selectedCode = point.name;
+      this.selectedLabel = `${point.address}, ${point.postal_code} ${point.city}`;
+      this.points = [];
+    },
+  };
+}
+
+/**
+ * Odliczanie do końca promocji na stronie "Okazje". Serwer wstawia dane (`product_id`,
+ * `ends_at`) do `<script id="sale-countdowns" type="application/json">` razem z siatką,
+ * a karty produktów mają pusty element `[data-sale-countdown="<id>"]`.
+ */
+let saleCountdownTimer = null;
+
+function startSaleCountdowns() {
+  clearInterval(saleCountdownTimer);
+  const payload = document.getElementById("sale-countdowns");
+  if (!payload) return;
+
+  let countdowns;
+  try {
+    countdowns = JSON.parse(payload.textContent);
+  } catch (e) {
+    console.error("Błąd odczytu danych odliczania promocji:", e);
+    return;
+  }
+
+  const render = () => {
+    const now = Date.now();
+    for (const { product_id, ends_at } of countdowns) {
+      const el = document.querySelector(
+        `[data-sale-countdown="${product_id}"]`,
+      );
+      if (!el) continue;
+      const left = new Date(ends_at).getTime() - now;
+      el.textContent =
+        left > 0
+          ? `Promocja kończy się za ${formatCountdown(left)}`
+          : "Promocja zakończona";
+    }
+  };
+  render();
+  saleCountdownTimer = setInterval(render, 1000);
+}
+
+/** "2 d 03:15:09" albo "03:15:09" */
+function formatCountdown(ms) {
+  const total = Math.floor(ms / 1000);
+  const pad = (n) => String(n).padStart(2, "0");
+  const days = Math.floor(total / 86400);
+  const clock = `${pad(Math.floor((total % 86400) / 3600))}:${pad(
+    Math.floor((total % 3600) / 60),
+  )}:${pad(total % 60)}`;
+  return days > 0 ? `${days} d ${clock}` : clock;
+}
+
+document.addEventListener("DOMContentLoaded", startSaleCountdowns);
+document.body.addEventListener("htmx:afterSettle", startSaleCountdowns);
+
 /**
  * Parsuje token JWT, aby uzyskać dostęp do jego zawartości (payload).
  * @param {string} token - Token JWT.
@@ -0,0 +1,149 @@
+// tests/e2e/checkout_flow.rs
+
+//! Krytyczna ścieżka sklepu: listing -> produkt -> koszyk -> checkout gościa -> obsługa
+//! zamówienia w panelu admina (opłacone -> wysłane).
+
+use fantoccini::{Client, Locator};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::support::{self, Seed};
+
+#[tokio::test]
+#[ignore = "wymaga działającego serwera, WebDrivera i bazy - zob. tests/e2e/main.rs"]
+async fn guest_checkout_and_admin_fulfilment() {
+    let db = support::database().await;
+    let seed = support::seed(&db).await;
+    let client = support::browser().await;
+
+    // Sprzątamy niezależnie od wyniku - panika w kroku nie może zostawić śmieci w bazie
+    let result = tokio::spawn({
+        let client = client.clone();
+        let db = db.clone();
+        let seed = seed.clone();
+        async move { run_flow(&client, &db, &seed).await }
+    })
+    .await;
+
+    client.close().await.ok();
+    support::cleanup(&db, &seed).await;
+
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic.into_panic());
+    }
+}
+
+async fn run_flow(client: &Client, db: &PgPool, seed: &Seed) {
+    // 1. Strona główna renderuje listing produktów
+    client
+        .goto(&support::url("/"))
+        .await
+        .expect("Nie udało się otworzyć strony głównej");
+    support::wait_for(client, Locator::Css(".product-card")).await;
+
+    // 2. Strona produktu i dodanie do koszyka (przycisk podmieniany przez HTMX + licznik OOB)
+    client
+        .goto(&support::url(&format!("/produkty/{}", seed.product_slug)))
+        .await
+        .expect("Nie udało się otworzyć strony produktu");
+    support::click(client, &format!("#product-cart-button-{}", seed.product_id)).await;
+    support::wait_for_text(client, "Dodano!").await;
+    let bubble = support::wait_for(client, Locator::Id("cart-count-bubble")).await;
+    assert_eq!(
+        bubble.text().await.unwrap_or_default().trim(),
+        "1",
+        "Licznik koszyka nie został zaktualizowany swapem OOB"
+    );
+
+    // 3. Checkout jako gość - każdy krok podmienia tylko #checkout-step
+    client
+        .goto(&support::url("/checkout"))
+        .await
+        .expect("Nie udało się otworzyć checkoutu");
+
+    support::fill(client, "#guest_email", "e2e-gosc@example.com").await;
+    support::fill(client, "#shipping_first_name", "Anna").await;
+    support::fill(client, "#shipping_last_name", "Testowa").await;
+    support::fill(client, "#shipping_phone", "500600700").await;
+    next_step(client, "#poczta_shipping_option").await;
+
+    support::click(client, "#poczta_shipping_option").await;
+    support::fill(client, "#shipping_address_line1", "ul. Testowa 1").await;
+    support::fill(client, "#shipping_city", "Warszawa").await;
+    support::fill(client, "#shipping_postal_code", "00-001").await;
+    support::wait_for(client, Locator::Id("shipping_country"))
+        .await
+        .select_by_value("Polska")
+        .await
+        .expect("Nie udało się wybrać kraju");
+    next_step(client, "#payment_blik").await;
+
+    support::click(client, "#payment_transfer").await;
+    next_step(client, "#checkout-form").await;
+
+    // 4. Złożenie zamówienia i strona podziękowania
+    support::click(client, "#checkout-form button[type=submit]").await;
+    support::wait_for_text(client, "Dziękujemy za zamówienie!").await;
+    let order_number = support::wait_for(client, Locator::Css("h1 + p strong"))
+        .await
+        .text()
+        .await
+        .expect("Brak numeru zamówienia na stronie podziękowania");
+
+    let (order_id, status): (Uuid, String) =
+        sqlx::query_as("SELECT id, status::TEXT FROM orders WHERE order_number = $1")
+            .bind(order_number.trim())
+            .fetch_one(db)
+            .await
+            .expect("Zamówienie ze strony podziękowania nie istnieje w bazie");
+    assert_eq!(status, "pending");
+
+    // 5. Logowanie admina
+    client
+        .goto(&support::url("/logowanie"))
+        .await
+        .expect("Nie udało się otworzyć logowania");
+    support::fill(client, "#login-form #email", &seed.admin_email).await;
+    support::fill(client, "#login-form #password", support::ADMIN_PASSWORD).await;
+    support::click(client, "#login-form button[type=submit]").await;
+    wait_for_cookie(client, "token").await;
+
+    // 6. Lista zamówień -> szczegóły (HTMX) -> zmiana statusu
+    client
+        .goto(&support::url("/admin/zamowienia"))
+        .await
+        .expect("Nie udało się otworzyć listy zamówień");
+    support::click(client, &format!("#order-row-{order_id} a")).await;
+
+    for (form_value, db_value) in [("Processing", "processing"), ("Shipped", "shipped")] {
+        support::wait_for(client, Locator::Id("order_status_details"))
+            .await
+            .select_by_value(form_value)
+            .await
+            .unwrap_or_else(|e| panic!("Nie udało się ustawić statusu {form_value}: {e}"));
+        support::wait_for_db(db_value.to_string(), || async {
+            sqlx::query_scalar::<_, String>("SELECT status::TEXT FROM orders WHERE id = $1")
+                .bind(order_id)
+                .fetch_one(db)
+                .await
+                .unwrap_or_default()
+        })
+        .await;
+    }
+}
+
+/// Wysyła bieżący krok checkoutu i czeka na element z następnego kroku
+async fn next_step(client: &Client, next_step_marker: &str) {
+    support::click(client, "#checkout-step form button[type=submit]").await;
+    support::wait_for(client, Locator::Css(next_step_marker)).await;
+}
+
+async fn wait_for_cookie(client: &Client, name: &str) {
+    let deadline = tokio::time::Instant::now() + support::WAIT_TIMEOUT;
+    while client.get_named_cookie(name).await.is_err() {
+        if tokio::time::Instant::now() >= deadline {
+            panic!("Logowanie nie ustawiło ciasteczka '{name}'");
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+}
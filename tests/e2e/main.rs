@@ -0,0 +1,27 @@
+// tests/e2e/main.rs
+
+//! Testy end-to-end w przeglądarce (headless) dla krytycznych ścieżek sklepu.
+//!
+//! Testy nie uruchamiają serwera same - chodzą po działającej instancji z zaseedowaną bazą,
+//! tak jak klient. Dzięki temu łapią regresje szablonów i HTMX (zły `hx-target`, brakujący
+//! OOB swap, zepsuty krok checkoutu), których nie widać w testach samych handlerów.
+//!
+//! Uruchomienie lokalnie:
+//!
+//! ```text
+//! cargo run                                   # serwer deweloperski (https://localhost:3000)
+//! chromedriver --port=4444                    # albo: geckodriver --port 4444
+//! cargo test --test e2e -- --ignored --test-threads=1
+//! ```
+//!
+//! Zmienne środowiskowe:
+//! - `E2E_BASE_URL` - adres sklepu (domyślnie `https://localhost:3000`),
+//! - `E2E_WEBDRIVER_URL` - adres WebDrivera (domyślnie `http://localhost:4444`),
+//! - `E2E_DATABASE_URL` - baza serwera do seedowania i sprawdzania stanu
+//!   (domyślnie `DATABASE_URL`),
+//! - `E2E_HEADFUL=1` - pokazuje okno przeglądarki (przydatne przy debugowaniu).
+//!
+//! Testy są oznaczone `#[ignore]`, żeby zwykłe `cargo test` nie wymagało przeglądarki.
+
+mod checkout_flow;
+mod support;
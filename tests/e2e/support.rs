@@ -0,0 +1,219 @@
+// tests/e2e/support.rs
+
+use std::time::Duration;
+
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
+};
+use fantoccini::{Client, ClientBuilder, Locator, elements::Element};
+use serde_json::json;
+use sqlx::{PgPool, postgres::PgPoolOptions};
+use uuid::Uuid;
+
+/// Ile czekamy na odpowiedź HTMX / przeładowanie strony
+pub const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const ADMIN_PASSWORD: &str = "e2e-Haslo-123!";
+
+pub fn base_url() -> String {
+    std::env::var("E2E_BASE_URL")
+        .unwrap_or_else(|_| "https://localhost:3000".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub fn url(path: &str) -> String {
+    format!("{}{}", base_url(), path)
+}
+
+/// Klient WebDrivera. Certyfikat serwera deweloperskiego jest self-signed, więc go akceptujemy.
+pub async fn browser() -> Client {
+    let webdriver_url =
+        std::env::var("E2E_WEBDRIVER_URL").unwrap_or_else(|_| "http://localhost:4444".to_string());
+    let headful = std::env::var("E2E_HEADFUL").is_ok_and(|v| v == "1");
+
+    let mut chrome_args = vec!["--window-size=1280,1024", "--no-sandbox"];
+    let mut firefox_args = vec![];
+    if !headful {
+        chrome_args.push("--headless=new");
+        firefox_args.push("-headless");
+    }
+    let capabilities = json!({
+        "acceptInsecureCerts": true,
+        "goog:chromeOptions": { "args": chrome_args },
+        "moz:firefoxOptions": { "args": firefox_args },
+    });
+    let serde_json::Value::Object(capabilities) = capabilities else {
+        unreachable!("capabilities to zawsze obiekt JSON");
+    };
+
+    ClientBuilder::rustls()
+        .expect("Nie udało się zainicjalizować TLS dla WebDrivera")
+        .capabilities(capabilities)
+        .connect(&webdriver_url)
+        .await
+        .unwrap_or_else(|e| panic!("Brak połączenia z WebDriverem pod {webdriver_url}: {e}"))
+}
+
+pub async fn database() -> PgPool {
+    let database_url = std::env::var("E2E_DATABASE_URL")
+        .or_else(|_| std::env::var("DATABASE_URL"))
+        .expect("Ustaw E2E_DATABASE_URL albo DATABASE_URL");
+    PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .expect("Brak połączenia z bazą testów e2e")
+}
+
+/// Dane zaseedowane na potrzeby jednego przebiegu - unikalne, żeby testy nie kolidowały
+/// z danymi deweloperskimi ani z równoległym przebiegiem
+#[derive(Clone)]
+pub struct Seed {
+    pub admin_id: Uuid,
+    pub admin_email: String,
+    pub product_id: Uuid,
+    pub product_slug: String,
+}
+
+pub async fn seed(db: &PgPool) -> Seed {
+    let run_id = Uuid::new_v4().simple().to_string();
+    let run_id = &run_id[..8];
+
+    let admin_id = Uuid::new_v4();
+    let admin_email = format!("e2e-admin-{run_id}@example.com");
+    let password_hash = Argon2::default()
+        .hash_password(ADMIN_PASSWORD.as_bytes(), &SaltString::generate(&mut OsRng))
+        .expect("Hashowanie hasła admina nie powiodło się")
+        .to_string();
+    sqlx::query(
+        "INSERT INTO users (id, email, password_hash, role, email_verified_at)
+         VALUES ($1, $2, $3, 'admin', NOW())",
+    )
+    .bind(admin_id)
+    .bind(&admin_email)
+    .bind(password_hash)
+    .execute(db)
+    .await
+    .expect("Nie udało się dodać admina e2e");
+
+    let product_id = Uuid::new_v4();
+    let product_slug = format!("e2e-koszula-{run_id}");
+    sqlx::query(
+        "INSERT INTO products (id, name, slug, description, price, gender, condition, category,
+                               status, images, on_sale)
+         VALUES ($1, $2, $3, 'Produkt testów e2e', 4900, 'Damskie', 'VeryGood', 'Koszule',
+                 'Available', $4, false)",
+    )
+    .bind(product_id)
+    .bind(format!("Koszula e2e {run_id}"))
+    .bind(&product_slug)
+    .bind(vec![
+        "https://res.cloudinary.com/demo/image/upload/sample.jpg".to_string(),
+    ])
+    .execute(db)
+    .await
+    .expect("Nie udało się dodać produktu e2e");
+
+    Seed {
+        admin_id,
+        admin_email,
+        product_id,
+        product_slug,
+    }
+}
+
+/// Sprząta po przebiegu. Błędy tylko logujemy - nieudane sprzątanie nie może zasłonić
+/// właściwego wyniku testu.
+pub async fn cleanup(db: &PgPool, seed: &Seed) {
+    let statements = [
+        (
+            "WITH seeded_orders AS (
+                 DELETE FROM order_items WHERE product_id = $1 RETURNING order_id
+             )
+             DELETE FROM orders WHERE id IN (SELECT order_id FROM seeded_orders)",
+            seed.product_id,
+        ),
+        (
+            "DELETE FROM cart_items WHERE product_id = $1",
+            seed.product_id,
+        ),
+        ("DELETE FROM products WHERE id = $1", seed.product_id),
+        ("DELETE FROM users WHERE id = $1", seed.admin_id),
+    ];
+
+    for (sql, id) in statements {
+        if let Err(e) = sqlx::query(sql).bind(id).execute(db).await {
+            eprintln!("e2e: sprzątanie '{sql}' dla {id} nie powiodło się: {e}");
+        }
+    }
+}
+
+pub async fn wait_for(client: &Client, locator: Locator<'_>) -> Element {
+    client
+        .wait()
+        .at_most(WAIT_TIMEOUT)
+        .for_element(locator)
+        .await
+        .unwrap_or_else(|e| panic!("Nie znaleziono elementu {locator:?}: {e}"))
+}
+
+/// Czeka, aż tekst strony będzie zawierał `needle` - po podmianie HTMX lub przeładowaniu
+pub async fn wait_for_text(client: &Client, needle: &str) {
+    let deadline = tokio::time::Instant::now() + WAIT_TIMEOUT;
+    loop {
+        let body = client
+            .find(Locator::Css("body"))
+            .await
+            .expect("Strona bez <body>")
+            .text()
+            .await
+            .unwrap_or_default();
+        if body.contains(needle) {
+            return;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            panic!("Na stronie nie pojawił się tekst '{needle}'");
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+pub async fn fill(client: &Client, css: &str, value: &str) {
+    let input = wait_for(client, Locator::Css(css)).await;
+    input.clear().await.ok();
+    input
+        .send_keys(value)
+        .await
+        .unwrap_or_else(|e| panic!("Nie udało się wpisać wartości w {css}: {e}"));
+}
+
+pub async fn click(client: &Client, css: &str) {
+    wait_for(client, Locator::Css(css))
+        .await
+        .click()
+        .await
+        .unwrap_or_else(|e| panic!("Nie udało się kliknąć {css}: {e}"));
+}
+
+/// Czeka, aż zapytanie do bazy zwróci oczekiwaną wartość - zmiany z HTMX zapisują się asynchronicznie
+/// względem tego, co widzi przeglądarka
+pub async fn wait_for_db<T, F, Fut>(expected: T, mut query: F)
+where
+    T: PartialEq + std::fmt::Debug,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    let deadline = tokio::time::Instant::now() + WAIT_TIMEOUT;
+    loop {
+        let current = query().await;
+        if current == expected {
+            return;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            panic!("Oczekiwano w bazie {expected:?}, jest {current:?}");
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}